@@ -19,6 +19,12 @@ typedef struct bevy_app bevy_app;
 // App Lifecycle
 // ============================================================================
 
+/// Keep the viewer state in files under a directory. Call before create_bevy_app;
+/// without it the state lives in memory.
+/// @param dir Null-terminated UTF-8 path of the directory, created if missing
+/// @return false if the directory can't be used or the app already exists
+bool set_state_dir(const char* dir);
+
 /// Create a new Bevy app attached to a native Metal view
 /// @param view_ptr Pointer to the UIView (iOS) or NSView (macOS) with CAMetalLayer backing
 /// @param max_fps Maximum frames per second (use 60 or 120)
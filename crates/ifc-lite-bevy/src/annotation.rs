@@ -0,0 +1,205 @@
+//! Annotations - text notes anchored to entities or 3D points
+//!
+//! The UI owns the annotations and their text. Anchors come here from
+//! storage: a point in IFC coordinates, or an entity whose centre carries
+//! the note. In [`CameraMode::Annotate`] a click on the model queues the hit
+//! point and entity for the UI to attach new text to. Anchors are drawn as
+//! gizmo markers; the labels are HTML rendered by the UI at the screen
+//! positions published here, so they always face the camera.
+
+use crate::camera::{CameraController, CameraMode, MainCamera};
+use crate::mesh::{EntityBounds, IfcEntity, TriangleEntityMapping};
+use crate::picking::{pick_entity, PickableBatch};
+use crate::storage::{AnnotationLabelStorage, AnnotationPickStorage, AnnotationStorage};
+use crate::IfcSceneData;
+use bevy::prelude::*;
+use rustc_hash::FxHashMap;
+
+/// Radius of anchor markers in pixels
+const MARKER_RADIUS_PX: f32 = 3.0;
+
+/// Annotation plugin
+pub struct AnnotationPlugin;
+
+impl Plugin for AnnotationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AnnotationState>().add_systems(
+            Update,
+            (
+                poll_annotations_system,
+                resolve_anchors_system,
+                annotate_pick_system,
+                draw_annotations_system,
+                update_annotation_labels_system,
+            )
+                .chain()
+                .after(crate::camera::CameraInputSet),
+        );
+    }
+}
+
+/// Annotations shown in the viewport
+#[derive(Resource, Default)]
+pub struct AnnotationState {
+    /// Annotations from the UI
+    pub annotations: Vec<AnnotationStorage>,
+    /// World-space anchor of each annotation, with the entity carrying it
+    anchors: Vec<(u32, Vec3, Option<Entity>)>,
+    /// Labels last published to storage
+    labels: Vec<AnnotationLabelStorage>,
+}
+
+/// Poll annotations from the UI
+fn poll_annotations_system(mut state: ResMut<AnnotationState>, mut frame: Local<u32>) {
+    *frame = frame.wrapping_add(1);
+    if !frame.is_multiple_of(10) {
+        return;
+    }
+    let annotations = crate::storage::load_annotations().unwrap_or_default();
+    if annotations != state.annotations {
+        state.annotations = annotations;
+    }
+}
+
+/// Place annotations in the world when they or the scene entities change
+fn resolve_anchors_system(
+    mut state: ResMut<AnnotationState>,
+    scene_data: Res<IfcSceneData>,
+    entities: Query<(Entity, &IfcEntity, &EntityBounds)>,
+    added: Query<(), Added<IfcEntity>>,
+) {
+    if !state.is_changed() && !scene_data.is_changed() && added.is_empty() {
+        return;
+    }
+    let wanted: rustc_hash::FxHashSet<u64> = state
+        .annotations
+        .iter()
+        .filter_map(|a| a.entity_id)
+        .collect();
+    let by_id: FxHashMap<u64, (Entity, Vec3)> = if wanted.is_empty() {
+        FxHashMap::default()
+    } else {
+        entities
+            .iter()
+            .filter(|(_, entity, _)| wanted.contains(&entity.id))
+            .map(|(e, entity, bounds)| (entity.id, (e, bounds.center())))
+            .collect()
+    };
+
+    let anchors: Vec<_> = state
+        .annotations
+        .iter()
+        .filter_map(|a| {
+            let carrier = a.entity_id.and_then(|id| by_id.get(&id));
+            let point = match (a.point, carrier) {
+                (Some(point), _) => scene_data.file_to_world(point),
+                (None, Some((_, center))) => *center,
+                (None, None) => return None,
+            };
+            Some((a.id, point, carrier.map(|(e, _)| *e)))
+        })
+        .collect();
+    // Bypass change detection, or this would run again next frame
+    state.bypass_change_detection().anchors = anchors;
+}
+
+/// Queue the clicked point and entity for a new annotation
+fn annotate_pick_system(
+    cameras: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    batched_meshes: Query<PickableBatch>,
+    triangle_mapping: Res<TriangleEntityMapping>,
+    meshes: Res<Assets<Mesh>>,
+    scene_data: Res<IfcSceneData>,
+    mut controller: ResMut<CameraController>,
+) {
+    if controller.mode != CameraMode::Annotate || !controller.just_clicked {
+        return;
+    }
+    controller.just_clicked = false;
+    let Ok((camera, camera_transform)) = cameras.single() else {
+        return;
+    };
+    let Ok(ray) = camera.viewport_to_world(camera_transform, controller.drag_start_pos) else {
+        return;
+    };
+    if let Some(hit) = pick_entity(&ray, &batched_meshes, &triangle_mapping, &meshes) {
+        crate::log(&format!("[Bevy] Annotation point on #{}", hit.entity_id));
+        crate::storage::push_annotation_pick(AnnotationPickStorage {
+            point: scene_data.world_to_file(hit.point),
+            entity_id: hit.entity_id,
+        });
+    }
+}
+
+/// Whether the entity carrying an annotation is shown
+fn is_anchor_visible(carrier: Option<Entity>, visibility: &Query<&Visibility>) -> bool {
+    carrier.is_none_or(|e| visibility.get(e).is_ok_and(|v| *v != Visibility::Hidden))
+}
+
+/// Draw a marker at each visible anchor
+fn draw_annotations_system(
+    state: Res<AnnotationState>,
+    cameras: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    controller: Res<CameraController>,
+    visibility: Query<&Visibility>,
+    mut gizmos: Gizmos,
+) {
+    let Ok((camera, camera_transform)) = cameras.single() else {
+        return;
+    };
+    let viewport_height = camera.logical_viewport_size().map_or(1.0, |size| size.y);
+    let units_per_pixel = 2.0 * (controller.fov.to_radians() / 2.0).tan() / viewport_height;
+    let eye = camera_transform.translation();
+    let color = Color::srgb(0.95, 0.55, 0.15);
+
+    for (_, point, carrier) in &state.anchors {
+        if !is_anchor_visible(*carrier, &visibility) {
+            continue;
+        }
+        let per_pixel = if controller.orthographic {
+            controller.world_units_per_pixel(viewport_height)
+        } else {
+            eye.distance(*point) * units_per_pixel
+        };
+        gizmos.sphere(
+            Isometry3d::from_translation(*point),
+            per_pixel * MARKER_RADIUS_PX,
+            color,
+        );
+    }
+}
+
+/// Publish where the UI should put annotation labels
+fn update_annotation_labels_system(
+    mut state: ResMut<AnnotationState>,
+    cameras: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    visibility: Query<&Visibility>,
+) {
+    let Ok((camera, camera_transform)) = cameras.single() else {
+        return;
+    };
+
+    let labels: Vec<AnnotationLabelStorage> = state
+        .anchors
+        .iter()
+        .filter(|(_, _, carrier)| is_anchor_visible(*carrier, &visibility))
+        .filter_map(|(id, point, _)| {
+            let anchor = camera.world_to_viewport(camera_transform, *point).ok()?;
+            Some(AnnotationLabelStorage {
+                id: *id,
+                x: anchor.x,
+                y: anchor.y,
+            })
+        })
+        .collect();
+
+    // Only touch storage when a label visibly moves
+    let moved = labels.len() != state.labels.len()
+        || labels.iter().zip(&state.labels).any(|(new, old)| {
+            new.id != old.id || (new.x - old.x).abs() > 0.5 || (new.y - old.y).abs() > 0.5
+        });
+    if moved {
+        crate::storage::save_annotation_labels(&labels);
+        state.bypass_change_detection().labels = labels;
+    }
+}
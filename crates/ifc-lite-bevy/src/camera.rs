@@ -1,35 +1,61 @@
-//! Camera system with orbit, pan, and zoom controls
+//! Camera system with orbit, pan, and zoom controls for mouse and touch
 //!
 //! Provides a flexible camera controller similar to the TypeScript version.
 
+use crate::mesh::TriangleEntityMapping;
+use crate::picking::{pick_entity, PickableBatch};
 #[cfg(target_arch = "wasm32")]
 use crate::storage::save_camera;
 use crate::storage::CameraStorage;
+use crate::GraphicsQuality;
+use bevy::camera::ScalingMode;
+use bevy::core_pipeline::prepass::{DepthPrepass, NormalPrepass};
 use bevy::ecs::message::MessageReader;
 use bevy::input::mouse::{MouseMotion, MouseWheel};
+use bevy::input::touch::Touches;
+use bevy::light::{CascadeShadowConfig, CascadeShadowConfigBuilder, DirectionalLightShadowMap};
+use bevy::pbr::{ScreenSpaceAmbientOcclusion, ScreenSpaceAmbientOcclusionQualityLevel};
 use bevy::prelude::*;
+use bevy::render::renderer::RenderDevice;
 
 /// System set for camera input (for ordering)
 #[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct CameraInputSet;
 
+/// System set for overlays drawn in the viewport that take pointer input,
+/// like the mini-map and the view cube
+///
+/// Runs before [`CameraInputSet`]. Each overlay sets
+/// [`CameraController::pointer_over_overlay`] while the cursor is over it.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OverlayInputSet;
+
 /// Camera controller plugin
 pub struct CameraPlugin;
 
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<CameraController>()
+            .configure_sets(Update, OverlayInputSet.before(CameraInputSet))
             .add_systems(Startup, setup_camera)
+            .add_systems(First, clear_pointer_over_overlay_system)
             .add_systems(
                 Update,
                 (
                     poll_camera_commands_system,
+                    poll_navigation_settings_system,
                     camera_input_system,
+                    camera_touch_system,
                     camera_update_system,
+                    sync_projection_system,
                     camera_keyboard_system,
                 )
                     .chain()
                     .in_set(CameraInputSet),
+            )
+            .add_systems(
+                Update,
+                (poll_render_settings_system, apply_render_settings_system).chain(),
             );
     }
 }
@@ -47,7 +73,77 @@ pub enum CameraMode {
     #[default]
     Orbit,
     Pan,
+    /// First person; clicks teleport to the floor, see [`crate::walk`]
     Walk,
+    /// Left drag draws a selection rectangle and leaves the camera alone
+    BoxSelect,
+    /// Orbits like [`CameraMode::Orbit`]; clicks place measurement points
+    Measure,
+    /// Orbits like [`CameraMode::Orbit`]; clicks place annotations
+    Annotate,
+}
+
+/// Standard views the camera snaps to
+///
+/// Named after the side of the model the camera looks at; Front looks
+/// along IFC +Y (world -Z).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StandardView {
+    Top,
+    Bottom,
+    Front,
+    Back,
+    Left,
+    Right,
+    /// The home view, from above the front right corner
+    Iso,
+}
+
+impl StandardView {
+    /// All views, in the order the native hosts number them
+    pub const ALL: [StandardView; 7] = [
+        StandardView::Top,
+        StandardView::Bottom,
+        StandardView::Front,
+        StandardView::Back,
+        StandardView::Left,
+        StandardView::Right,
+        StandardView::Iso,
+    ];
+
+    /// Azimuth and elevation of the view, in radians
+    ///
+    /// Top and Bottom stop just short of the poles, where the look-at
+    /// rotation is undefined.
+    pub fn angles(self) -> (f32, f32) {
+        use std::f32::consts::{FRAC_PI_2, PI};
+        match self {
+            StandardView::Top => (0.0, FRAC_PI_2 - 0.001),
+            StandardView::Bottom => (0.0, -FRAC_PI_2 + 0.001),
+            StandardView::Front => (0.0, 0.0),
+            StandardView::Back => (PI, 0.0),
+            StandardView::Left => (-FRAC_PI_2, 0.0),
+            StandardView::Right => (FRAC_PI_2, 0.0),
+            StandardView::Iso => (0.785, 0.615), // 45°, 35.264°
+        }
+    }
+
+    /// Name used by the UI and the camera commands
+    pub fn as_str(self) -> &'static str {
+        match self {
+            StandardView::Top => "top",
+            StandardView::Bottom => "bottom",
+            StandardView::Front => "front",
+            StandardView::Back => "back",
+            StandardView::Left => "left",
+            StandardView::Right => "right",
+            StandardView::Iso => "iso",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|view| view.as_str() == name)
+    }
 }
 
 /// Camera controller resource
@@ -69,6 +165,9 @@ pub struct CameraController {
     pub velocity: Vec3,
     /// Angular velocity for orbit inertia
     pub angular_velocity: Vec2,
+    /// Point orbits turn about when it is not the target, picked under the
+    /// cursor as a drag starts
+    pub pivot: Option<Vec3>,
     /// Whether camera is currently animating
     pub is_animating: bool,
     /// Animation target (for preset views)
@@ -79,6 +178,8 @@ pub struct CameraController {
     pub near: f32,
     /// Far clipping plane
     pub far: f32,
+    /// Parallel projection, framing what the perspective view shows at the target
+    pub orthographic: bool,
     /// Walk mode speed
     pub walk_speed: f32,
     /// Orbit sensitivity
@@ -97,6 +198,9 @@ pub struct CameraController {
     pub did_drag: bool,
     /// Was this a click (released without dragging)?
     pub just_clicked: bool,
+    /// Pointer is over an overlay drawn by the viewer (the mini-map or the
+    /// view cube), which handles clicks itself
+    pub pointer_over_overlay: bool,
 }
 
 impl Default for CameraController {
@@ -110,11 +214,13 @@ impl Default for CameraController {
             damping: 0.92,
             velocity: Vec3::ZERO,
             angular_velocity: Vec2::ZERO,
+            pivot: None,
             is_animating: false,
             animation_target: None,
             fov: 45.0,
-            near: 1.0,         // 1mm near plane for IFC-scale models
-            far: 1000000.0,    // 1km far plane for large IFC models
+            near: 1.0,      // 1mm near plane for IFC-scale models
+            far: 1000000.0, // 1km far plane for large IFC models
+            orthographic: false,
             walk_speed: 500.0, // 0.5m per frame for walking in mm-scale
             orbit_sensitivity: 0.005,
             pan_sensitivity: 0.01,
@@ -124,6 +230,7 @@ impl Default for CameraController {
             drag_start_pos: Vec2::ZERO,
             did_drag: false,
             just_clicked: false,
+            pointer_over_overlay: false,
         }
     }
 }
@@ -137,6 +244,52 @@ impl CameraController {
         self.target + Vec3::new(x, y, z)
     }
 
+    /// Orbit by a change of azimuth and elevation, in radians, about
+    /// [`Self::pivot`] or else the target
+    ///
+    /// Turning about a pivot moves the target along with the camera, so the
+    /// view direction changes exactly as in a plain orbit.
+    pub fn orbit(&mut self, delta: Vec2) {
+        let azimuth = self.azimuth - delta.x;
+        // Clamp elevation to avoid gimbal lock
+        let elevation = (self.elevation - delta.y).clamp(-1.5, 1.5);
+        if let Some(pivot) = self.pivot {
+            let turned = Vec3::new(azimuth.sin(), 0.0, azimuth.cos());
+            let yaw = Quat::from_rotation_y(azimuth - self.azimuth);
+            let pitch = Quat::from_rotation_arc(
+                turned * self.elevation.cos() + Vec3::Y * self.elevation.sin(),
+                turned * elevation.cos() + Vec3::Y * elevation.sin(),
+            );
+            self.target = pivot + pitch * yaw * (self.target - pivot);
+        }
+        self.azimuth = azimuth;
+        self.elevation = elevation;
+    }
+
+    /// Zoom by `factor` (below 1 moves closer) keeping `point` where it is
+    /// on screen
+    pub fn zoom_toward(&mut self, factor: f32, point: Vec3) {
+        let distance = (self.distance * factor).clamp(1.0, 500000.0);
+        let scale = distance / self.distance;
+        self.target = point + (self.target - point) * scale;
+        self.distance = distance;
+    }
+
+    /// Whether the camera looks (nearly) straight down, as in the Top preset
+    pub fn is_plan_view(&self) -> bool {
+        self.elevation > 1.4
+    }
+
+    /// Height of the view at the target, in world units
+    pub fn view_height(&self) -> f32 {
+        2.0 * self.distance * (self.fov.to_radians() / 2.0).tan()
+    }
+
+    /// World units covered by one pixel at the target, for a viewport of the given height
+    pub fn world_units_per_pixel(&self, viewport_height: f32) -> f32 {
+        self.view_height() / viewport_height.max(1.0)
+    }
+
     /// Set preset view
     pub fn set_preset_view(&mut self, azimuth: f32, elevation: f32) {
         self.animation_target = Some(CameraAnimationTarget {
@@ -150,9 +303,31 @@ impl CameraController {
         self.is_animating = true;
     }
 
+    /// Glide to the given angles, keeping target and distance
+    ///
+    /// The azimuth takes the short way round, so going from Left to Back
+    /// turns a quarter instead of three.
+    pub fn turn_to(&mut self, azimuth: f32, elevation: f32) {
+        let turn = (azimuth - self.azimuth + std::f32::consts::PI)
+            .rem_euclid(std::f32::consts::TAU)
+            - std::f32::consts::PI;
+        self.set_preset_view(self.azimuth + turn, elevation);
+    }
+
+    /// Glide to one of the standard views
+    pub fn set_standard_view(&mut self, view: StandardView) {
+        let (azimuth, elevation) = view.angles();
+        self.turn_to(azimuth, elevation);
+    }
+
+    /// Switch between perspective and orthographic projection
+    pub fn set_projection(&mut self, orthographic: bool) {
+        self.orthographic = orthographic;
+    }
+
     /// Set home/isometric view
     pub fn home(&mut self) {
-        self.set_preset_view(0.785, 0.615); // 45°, 35.264°
+        self.set_standard_view(StandardView::Iso);
     }
 
     /// Fit all - zoom to show entire scene
@@ -197,16 +372,32 @@ impl CameraController {
             azimuth: self.azimuth,
             elevation: self.elevation,
             distance: self.distance,
-            target: [self.target.x, self.target.y, self.target.z],
+            target: self.target.to_array().map(f64::from),
+            orthographic: self.orthographic,
         }
     }
 
+    /// Glide to a stored camera, e.g. of a saved viewpoint
+    pub fn animate_to(&mut self, storage: &CameraStorage) {
+        self.animation_target = Some(CameraAnimationTarget {
+            azimuth: storage.azimuth,
+            elevation: storage.elevation,
+            distance: storage.distance,
+            target: Vec3::from_array(storage.target.map(|v| v as f32)),
+            duration: 0.5,
+            elapsed: 0.0,
+        });
+        self.is_animating = true;
+        self.orthographic = storage.orthographic;
+    }
+
     /// Load from storage format
     pub fn from_storage(&mut self, storage: &CameraStorage) {
         self.azimuth = storage.azimuth;
         self.elevation = storage.elevation;
         self.distance = storage.distance;
-        self.target = Vec3::new(storage.target[0], storage.target[1], storage.target[2]);
+        self.target = Vec3::from_array(storage.target.map(|v| v as f32));
+        self.orthographic = storage.orthographic;
     }
 }
 
@@ -245,11 +436,29 @@ fn poll_camera_commands_system(
                         controller.fit_bounds(bounds.min, bounds.max);
                     }
                 }
+                "set_view" => {
+                    if let Some(view) = cmd.view {
+                        controller.animate_to(&scene_data.camera_to_world(&view));
+                    }
+                }
+                "set_projection" => {
+                    if let Some(mode) = cmd.mode {
+                        controller.set_projection(mode == "orthographic");
+                    }
+                }
+                "set_standard_view" => {
+                    if let Some(view) = cmd.mode.as_deref().and_then(StandardView::parse) {
+                        controller.set_standard_view(view);
+                    }
+                }
                 "set_mode" => {
                     if let Some(mode) = cmd.mode {
                         controller.mode = match mode.as_str() {
                             "pan" => CameraMode::Pan,
                             "walk" => CameraMode::Walk,
+                            "box_select" => CameraMode::BoxSelect,
+                            "measure" => CameraMode::Measure,
+                            "annotate" => CameraMode::Annotate,
                             _ => CameraMode::Orbit,
                         };
                     }
@@ -260,10 +469,33 @@ fn poll_camera_commands_system(
     }
 }
 
-/// Setup the 3D camera
-fn setup_camera(mut commands: Commands, controller: Res<CameraController>) {
+/// Forget last frame's overlay hover before the overlays check the cursor
+fn clear_pointer_over_overlay_system(mut controller: ResMut<CameraController>) {
+    if controller.pointer_over_overlay {
+        controller.pointer_over_overlay = false;
+    }
+}
+
+/// Main light, the only one that casts shadows
+#[derive(Component)]
+pub struct KeyLight;
+
+fn msaa_for_samples(samples: u32) -> bevy::render::view::Msaa {
     use bevy::render::view::Msaa;
+    match samples {
+        0 | 1 => Msaa::Off,
+        2 => Msaa::Sample2,
+        8 => Msaa::Sample8,
+        _ => Msaa::Sample4,
+    }
+}
 
+/// Setup the 3D camera
+fn setup_camera(
+    mut commands: Commands,
+    controller: Res<CameraController>,
+    settings: Res<crate::ViewerSettings>,
+) {
     let position = controller.get_position();
 
     commands.spawn((
@@ -276,8 +508,8 @@ fn setup_camera(mut commands: Commands, controller: Res<CameraController>) {
             ..default()
         }),
         MainCamera,
-        // Enable 4x MSAA for smoother edges
-        Msaa::Sample4,
+        // 4x MSAA unless the device profile turned it down
+        msaa_for_samples(settings.msaa_samples),
     ));
 
     // Ambient light - lower for more contrast (like original viewer)
@@ -292,11 +524,12 @@ fn setup_camera(mut commands: Commands, controller: Res<CameraController>) {
         DirectionalLight {
             color: Color::srgb(1.0, 0.99, 0.97), // Slightly warm
             illuminance: 25000.0,                // Strong key light
-            shadows_enabled: false,
+            shadows_enabled: settings.shadows.is_enabled(),
             affects_lightmapped_mesh_diffuse: true,
             ..default()
         },
         Transform::from_xyz(0.5, 1.0, 0.3).looking_at(Vec3::ZERO, Vec3::Y),
+        KeyLight,
     ));
 
     // Fill light from opposite side - subtle
@@ -324,14 +557,234 @@ fn setup_camera(mut commands: Commands, controller: Res<CameraController>) {
     ));
 }
 
+/// Poll the renderer defaults the UI picked for the device
+#[allow(unused_mut, unused_variables)]
+fn poll_render_settings_system(
+    mut settings: ResMut<crate::ViewerSettings>,
+    mut lod: ResMut<crate::LodSettings>,
+    mut frame: Local<u32>,
+) {
+    #[cfg(target_arch = "wasm32")]
+    {
+        *frame = frame.wrapping_add(1);
+        if !frame.is_multiple_of(60) {
+            return;
+        }
+        let Some(render) = crate::storage::load_render_settings() else {
+            return;
+        };
+        if settings.msaa_samples != render.msaa_samples
+            || settings.shadows != render.shadows
+            || settings.ambient_occlusion != render.ambient_occlusion
+            || settings.chunk_size != render.chunk_size
+            || settings.smooth_normals != render.smooth_normals
+            || settings.crease_angle != render.crease_angle
+            || settings.optimize_meshes != render.optimize_meshes
+        {
+            crate::log(&format!(
+                "[Bevy] Render settings: MSAA {}, shadows {:?}, ambient occlusion {:?}, chunk size {}",
+                render.msaa_samples, render.shadows, render.ambient_occlusion, render.chunk_size
+            ));
+            settings.msaa_samples = render.msaa_samples;
+            settings.shadows = render.shadows;
+            settings.ambient_occlusion = render.ambient_occlusion;
+            settings.chunk_size = render.chunk_size;
+            settings.smooth_normals = render.smooth_normals;
+            settings.crease_angle = render.crease_angle;
+            settings.optimize_meshes = render.optimize_meshes;
+        }
+        if lod.max_error_px != render.lod_error_px {
+            lod.max_error_px = render.lod_error_px;
+        }
+    }
+}
+
+/// Poll the mouse navigation preferences from the UI
+#[allow(unused_mut, unused_variables)]
+fn poll_navigation_settings_system(
+    mut settings: ResMut<crate::ViewerSettings>,
+    mut frame: Local<u32>,
+) {
+    #[cfg(target_arch = "wasm32")]
+    {
+        *frame = frame.wrapping_add(1);
+        if !frame.is_multiple_of(30) {
+            return;
+        }
+        let Some(navigation) = crate::storage::load_navigation() else {
+            return;
+        };
+        if settings.orbit_around_cursor != navigation.orbit_around_cursor
+            || settings.zoom_to_cursor != navigation.zoom_to_cursor
+        {
+            settings.orbit_around_cursor = navigation.orbit_around_cursor;
+            settings.zoom_to_cursor = navigation.zoom_to_cursor;
+        }
+    }
+}
+
+/// Whether the GPU can run Bevy's SSAO
+///
+/// Its compute passes need five storage textures per stage, which WebGL2
+/// lacks, and Bevy does not support it on WebGPU yet.
+fn ssao_supported(device: Option<&RenderDevice>) -> bool {
+    !cfg!(target_arch = "wasm32")
+        && device.is_some_and(|d| d.limits().max_storage_textures_per_shader_stage >= 5)
+}
+
+/// Shadow cascades of the key light that cover the whole scene
+///
+/// `reach` is the distance from the camera shadows have to reach. Low
+/// quality spends two cascades on it, high quality four; WebGL2 only
+/// supports one.
+fn shadow_cascades(quality: GraphicsQuality, reach: f32) -> CascadeShadowConfig {
+    let num_cascades = if cfg!(feature = "webgl2") {
+        1
+    } else if quality == GraphicsQuality::High {
+        4
+    } else {
+        2
+    };
+    CascadeShadowConfigBuilder {
+        num_cascades,
+        maximum_distance: reach,
+        first_cascade_far_bound: reach / (num_cascades * num_cascades) as f32,
+        ..default()
+    }
+    .build()
+}
+
+/// Edge length of the shadow map of each cascade
+fn shadow_map_size(quality: GraphicsQuality) -> usize {
+    match quality {
+        GraphicsQuality::High => 4096,
+        _ => 1024,
+    }
+}
+
+/// Apply MSAA, ambient occlusion and shadow settings to the camera and key
+/// light
+///
+/// SSAO only works without MSAA, so MSAA is off while it runs. `reach` is
+/// the shadow distance the cascades were last built for; it follows the
+/// size of the scene.
+#[allow(clippy::too_many_arguments)]
+fn apply_render_settings_system(
+    mut commands: Commands,
+    settings: Res<crate::ViewerSettings>,
+    scene_data: Res<crate::IfcSceneData>,
+    render_device: Option<Res<RenderDevice>>,
+    mut shadow_map: ResMut<DirectionalLightShadowMap>,
+    cameras: Query<(Entity, Has<ScreenSpaceAmbientOcclusion>), With<MainCamera>>,
+    mut key_lights: Query<(&mut DirectionalLight, &mut CascadeShadowConfig), With<KeyLight>>,
+    mut reach: Local<f32>,
+) {
+    let next_reach = scene_data
+        .bounds
+        .as_ref()
+        .map_or(150.0, |bounds| bounds.diagonal() * 2.0)
+        .max(20.0);
+    if !settings.is_changed() && *reach == next_reach {
+        return;
+    }
+    *reach = next_reach;
+
+    let ssao = settings.ambient_occlusion.is_enabled() && ssao_supported(render_device.as_deref());
+    for (camera, has_ssao) in cameras.iter() {
+        let mut camera = commands.entity(camera);
+        if ssao {
+            camera.insert((
+                ScreenSpaceAmbientOcclusion {
+                    quality_level: match settings.ambient_occlusion {
+                        GraphicsQuality::High => ScreenSpaceAmbientOcclusionQualityLevel::High,
+                        _ => ScreenSpaceAmbientOcclusionQualityLevel::Low,
+                    },
+                    ..default()
+                },
+                bevy::render::view::Msaa::Off,
+            ));
+        } else {
+            if has_ssao {
+                camera.remove::<ScreenSpaceAmbientOcclusion>();
+                // Occlusion culling keeps its prepass
+                if !settings.occlusion_culling {
+                    camera.remove::<(DepthPrepass, NormalPrepass)>();
+                }
+            }
+            camera.insert(msaa_for_samples(settings.msaa_samples));
+        }
+    }
+
+    let size = shadow_map_size(settings.shadows);
+    if shadow_map.size != size {
+        shadow_map.size = size;
+    }
+    for (mut light, mut cascades) in key_lights.iter_mut() {
+        if light.shadows_enabled != settings.shadows.is_enabled() {
+            light.shadows_enabled = settings.shadows.is_enabled();
+        }
+        if settings.shadows.is_enabled() {
+            *cascades = shadow_cascades(settings.shadows, *reach);
+        }
+    }
+}
+
+/// Ray through the cursor, with the model point it hits first
+fn point_under_cursor(
+    cursor: Vec2,
+    cameras: &Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    batched_meshes: &Query<PickableBatch>,
+    triangle_mapping: &TriangleEntityMapping,
+    meshes: &Assets<Mesh>,
+) -> Option<(Ray3d, Option<Vec3>)> {
+    let (camera, camera_transform) = cameras.single().ok()?;
+    let ray = camera.viewport_to_world(camera_transform, cursor).ok()?;
+    let hit = pick_entity(&ray, batched_meshes, triangle_mapping, meshes);
+    Some((ray, hit.map(|hit| hit.point)))
+}
+
+/// Zoom by `factor` toward a screen position, or the view centre without one
+///
+/// Zooms toward the model under the position, or else toward where its ray
+/// crosses the view plane through the target.
+fn zoom_at(
+    controller: &mut CameraController,
+    factor: f32,
+    screen_pos: Option<Vec2>,
+    cameras: &Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    batched_meshes: &Query<PickableBatch>,
+    triangle_mapping: &TriangleEntityMapping,
+    meshes: &Assets<Mesh>,
+) {
+    let toward = screen_pos
+        .and_then(|pos| point_under_cursor(pos, cameras, batched_meshes, triangle_mapping, meshes))
+        .and_then(|(ray, point)| {
+            point.or_else(|| {
+                let normal = Dir3::new(controller.get_position() - controller.target).ok()?;
+                let distance =
+                    ray.intersect_plane(controller.target, InfinitePlane3d::new(normal))?;
+                Some(ray.get_point(distance))
+            })
+        });
+    match toward {
+        Some(point) => controller.zoom_toward(factor, point),
+        None => controller.distance = (controller.distance * factor).clamp(1.0, 500000.0),
+    }
+}
+
 /// Handle mouse input for camera control
-#[allow(unused_variables)]
+#[allow(unused_variables, clippy::too_many_arguments)]
 fn camera_input_system(
     mouse_button: Res<ButtonInput<MouseButton>>,
     mut mouse_motion: MessageReader<MouseMotion>,
     mut mouse_wheel: MessageReader<MouseWheel>,
     mut controller: ResMut<CameraController>,
+    settings: Res<crate::ViewerSettings>,
     windows: Query<&Window>,
+    cameras: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    batched_meshes: Query<PickableBatch>,
+    triangle_mapping: Res<TriangleEntityMapping>,
+    meshes: Res<Assets<Mesh>>,
     // Check if mouse is over any UI element with Interaction (only when bevy-ui feature is enabled)
     #[cfg(feature = "bevy-ui")] ui_interactions: Query<&Interaction, With<Node>>,
 ) {
@@ -344,20 +797,31 @@ fn camera_input_system(
         .any(|interaction| matches!(interaction, Interaction::Hovered | Interaction::Pressed));
     #[cfg(not(feature = "bevy-ui"))]
     let mouse_over_ui = false;
+    let mouse_over_ui = mouse_over_ui || controller.pointer_over_overlay;
 
     // Handle mouse button state - only start drag if not over UI
     if mouse_button.just_pressed(MouseButton::Left) && !mouse_over_ui {
         controller.is_dragging = true;
         controller.did_drag = false;
         controller.just_clicked = false; // Reset on press
+        controller.pivot = None;
         if let Some(pos) = window.cursor_position() {
             controller.last_mouse_pos = pos;
             controller.drag_start_pos = pos;
+            let orbits = matches!(
+                controller.mode,
+                CameraMode::Orbit | CameraMode::Measure | CameraMode::Annotate
+            );
+            if orbits && settings.orbit_around_cursor {
+                controller.pivot =
+                    point_under_cursor(pos, &cameras, &batched_meshes, &triangle_mapping, &meshes)
+                        .and_then(|(_, point)| point);
+            }
         }
     }
     if mouse_button.just_released(MouseButton::Left) {
-        // Check if this was a click (no significant drag)
-        if !controller.did_drag {
+        // Check if this was a click (no significant drag) that started here
+        if controller.is_dragging && !controller.did_drag {
             controller.just_clicked = true;
         }
         controller.is_dragging = false;
@@ -372,11 +836,9 @@ fn camera_input_system(
             }
 
             match controller.mode {
-                CameraMode::Orbit => {
-                    controller.azimuth -= ev.delta.x * controller.orbit_sensitivity;
-                    controller.elevation -= ev.delta.y * controller.orbit_sensitivity;
-                    // Clamp elevation to avoid gimbal lock
-                    controller.elevation = controller.elevation.clamp(-1.5, 1.5);
+                CameraMode::Orbit | CameraMode::Measure | CameraMode::Annotate => {
+                    let delta = ev.delta * controller.orbit_sensitivity;
+                    controller.orbit(delta);
                     // Store angular velocity for inertia
                     controller.angular_velocity = ev.delta * controller.orbit_sensitivity;
                 }
@@ -398,6 +860,7 @@ fn camera_input_system(
                     controller.elevation -= ev.delta.y * controller.orbit_sensitivity * 0.5;
                     controller.elevation = controller.elevation.clamp(-1.5, 1.5);
                 }
+                CameraMode::BoxSelect => {}
             }
         }
     } else {
@@ -405,19 +868,212 @@ fn camera_input_system(
         let damping = controller.damping;
         controller.angular_velocity *= damping;
         if controller.angular_velocity.length() > 0.0001 {
-            controller.azimuth -= controller.angular_velocity.x;
-            controller.elevation -= controller.angular_velocity.y;
-            controller.elevation = controller.elevation.clamp(-1.5, 1.5);
+            let delta = controller.angular_velocity;
+            controller.orbit(delta);
         }
     }
 
     // Handle mouse wheel for zoom - only when NOT over UI
     if !mouse_over_ui {
-        for ev in mouse_wheel.read() {
-            let zoom_delta = ev.y * controller.zoom_sensitivity;
-            controller.distance = (controller.distance * (1.0 - zoom_delta)).clamp(1.0, 500000.0);
+        let zoom_delta: f32 = mouse_wheel
+            .read()
+            .map(|ev| ev.y * controller.zoom_sensitivity)
+            .sum();
+        if zoom_delta != 0.0 {
+            zoom_at(
+                &mut controller,
+                (1.0 - zoom_delta).max(0.1),
+                window.cursor_position().filter(|_| settings.zoom_to_cursor),
+                &cameras,
+                &batched_meshes,
+                &triangle_mapping,
+                &meshes,
+            );
+        }
+    }
+}
+
+/// Fingers may move this far, in pixels, and still tap
+const TAP_SLOP_PX: f32 = 10.0;
+
+/// Longest touch that still taps, in seconds
+const TAP_SECS: f64 = 0.3;
+
+/// Longest gap between the taps of a double tap, in seconds
+const DOUBLE_TAP_SECS: f64 = 0.35;
+
+/// Farthest apart the taps of a double tap may land, in pixels
+const DOUBLE_TAP_SLOP_PX: f32 = 30.0;
+
+/// Touch gesture in progress
+#[derive(Default)]
+struct TouchGesture {
+    /// Most fingers down since the gesture started; a pinch that ends with
+    /// one finger lifted after the other does not turn into an orbit
+    fingers: usize,
+    /// Where and when the gesture started
+    start: Vec2,
+    start_secs: f64,
+    /// The finger left the tap slop
+    moved: bool,
+    /// Last one-finger orbit step, kept for inertia once the finger lifts
+    orbit: Vec2,
+    /// Where and when the last tap landed, for double taps
+    last_tap: Option<(Vec2, f64)>,
+}
+
+/// Handle touch input for camera control
+///
+/// One finger drags like the left mouse button in the current mode, two
+/// fingers pan and pinch to zoom. A tap clicks; a double tap also frames
+/// the tapped entity, or the whole model when it misses.
+#[allow(clippy::too_many_arguments)]
+fn camera_touch_system(
+    touches: Res<Touches>,
+    time: Res<Time>,
+    mut controller: ResMut<CameraController>,
+    settings: Res<crate::ViewerSettings>,
+    scene_data: Res<crate::IfcSceneData>,
+    mut pending_focus: ResMut<crate::mesh::PendingFocus>,
+    cameras: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    batched_meshes: Query<PickableBatch>,
+    triangle_mapping: Res<TriangleEntityMapping>,
+    meshes: Res<Assets<Mesh>>,
+    mut gesture: Local<TouchGesture>,
+) {
+    let now = time.elapsed_secs_f64();
+    let active: Vec<_> = touches.iter().collect();
+
+    if touches.any_just_pressed() {
+        if gesture.fingers == 0 {
+            if let [touch] = active.as_slice() {
+                gesture.start = touch.position();
+                gesture.start_secs = now;
+                gesture.moved = false;
+                gesture.orbit = Vec2::ZERO;
+                controller.angular_velocity = Vec2::ZERO;
+                controller.pivot = None;
+                let orbits = matches!(
+                    controller.mode,
+                    CameraMode::Orbit | CameraMode::Measure | CameraMode::Annotate
+                );
+                if orbits && settings.orbit_around_cursor {
+                    controller.pivot = point_under_cursor(
+                        touch.position(),
+                        &cameras,
+                        &batched_meshes,
+                        &triangle_mapping,
+                        &meshes,
+                    )
+                    .and_then(|(_, point)| point);
+                }
+            }
+        }
+        gesture.fingers = gesture.fingers.max(active.len());
+    }
+
+    match active.as_slice() {
+        [touch] if gesture.fingers == 1 => {
+            if touch.position().distance(gesture.start) > TAP_SLOP_PX {
+                gesture.moved = true;
+            }
+            let delta = touch.delta();
+            if gesture.moved && delta != Vec2::ZERO {
+                match controller.mode {
+                    CameraMode::Orbit | CameraMode::Measure | CameraMode::Annotate => {
+                        let delta = delta * controller.orbit_sensitivity;
+                        controller.orbit(delta);
+                        gesture.orbit = delta;
+                    }
+                    CameraMode::Pan => pan_by_pixels(&mut controller, &cameras, delta),
+                    CameraMode::Walk => {
+                        let delta = delta * controller.orbit_sensitivity * 0.5;
+                        controller.azimuth -= delta.x;
+                        controller.elevation = (controller.elevation - delta.y).clamp(-1.5, 1.5);
+                    }
+                    CameraMode::BoxSelect => {}
+                }
+            }
+        }
+        [a, b] => {
+            gesture.moved = true;
+            let middle = (a.position() + b.position()) * 0.5;
+            let previous_middle = (a.previous_position() + b.previous_position()) * 0.5;
+            pan_by_pixels(&mut controller, &cameras, middle - previous_middle);
+
+            let spread = a.position().distance(b.position());
+            let previous_spread = a.previous_position().distance(b.previous_position());
+            if spread > 1.0 && previous_spread > 1.0 && spread != previous_spread {
+                zoom_at(
+                    &mut controller,
+                    previous_spread / spread,
+                    Some(middle).filter(|_| settings.zoom_to_cursor),
+                    &cameras,
+                    &batched_meshes,
+                    &triangle_mapping,
+                    &meshes,
+                );
+            }
+        }
+        _ => {}
+    }
+
+    for touch in touches.iter_just_released() {
+        if gesture.fingers != 1 {
+            continue;
+        }
+        if gesture.moved {
+            // Let the orbit coast like after a mouse drag
+            controller.angular_velocity = gesture.orbit;
+            continue;
+        }
+        if now - gesture.start_secs > TAP_SECS {
+            continue;
+        }
+        let position = touch.position();
+        let double_tap = gesture.last_tap.is_some_and(|(at, secs)| {
+            now - secs < DOUBLE_TAP_SECS && at.distance(position) < DOUBLE_TAP_SLOP_PX
+        });
+        if double_tap {
+            gesture.last_tap = None;
+            let hit = cameras
+                .single()
+                .ok()
+                .and_then(|(camera, transform)| camera.viewport_to_world(transform, position).ok())
+                .and_then(|ray| pick_entity(&ray, &batched_meshes, &triangle_mapping, &meshes));
+            match hit {
+                Some(hit) => pending_focus.entity_id = Some(hit.entity_id),
+                None => {
+                    if let Some(bounds) = &scene_data.bounds {
+                        controller.fit_bounds(bounds.min, bounds.max);
+                    }
+                }
+            }
+        } else {
+            gesture.last_tap = Some((position, now));
+            controller.drag_start_pos = position;
+            controller.just_clicked = true;
         }
     }
+
+    if active.is_empty() {
+        gesture.fingers = 0;
+    }
+}
+
+/// Move the view with content following a drag of `delta` pixels
+fn pan_by_pixels(
+    controller: &mut CameraController,
+    cameras: &Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    delta: Vec2,
+) {
+    let Ok((camera, camera_transform)) = cameras.single() else {
+        return;
+    };
+    let viewport_height = camera.logical_viewport_size().map_or(1.0, |size| size.y);
+    let per_pixel = controller.world_units_per_pixel(viewport_height);
+    controller.target +=
+        (*camera_transform.up() * delta.y - *camera_transform.right() * delta.x) * per_pixel;
 }
 
 /// Handle keyboard input for camera control
@@ -466,33 +1122,32 @@ fn camera_keyboard_system(
     }
 
     // Preset views (number keys)
-    if keyboard.just_pressed(KeyCode::Digit1) {
-        controller.set_preset_view(0.0, 0.0); // Front
-    }
-    if keyboard.just_pressed(KeyCode::Digit2) {
-        controller.set_preset_view(std::f32::consts::PI, 0.0); // Back
-    }
-    if keyboard.just_pressed(KeyCode::Digit3) {
-        controller.set_preset_view(-std::f32::consts::FRAC_PI_2, 0.0); // Left
-    }
-    if keyboard.just_pressed(KeyCode::Digit4) {
-        controller.set_preset_view(std::f32::consts::FRAC_PI_2, 0.0); // Right
-    }
-    if keyboard.just_pressed(KeyCode::Digit5) {
-        controller.set_preset_view(0.0, std::f32::consts::FRAC_PI_2 - 0.001); // Top
-    }
-    if keyboard.just_pressed(KeyCode::Digit6) {
-        controller.set_preset_view(0.0, -std::f32::consts::FRAC_PI_2 + 0.001); // Bottom
+    let presets = [
+        (KeyCode::Digit1, StandardView::Front),
+        (KeyCode::Digit2, StandardView::Back),
+        (KeyCode::Digit3, StandardView::Left),
+        (KeyCode::Digit4, StandardView::Right),
+        (KeyCode::Digit5, StandardView::Top),
+        (KeyCode::Digit6, StandardView::Bottom),
+        (KeyCode::KeyH, StandardView::Iso),
+    ];
+    for (key, view) in presets {
+        if keyboard.just_pressed(key) {
+            controller.set_standard_view(view);
+        }
     }
-    if keyboard.just_pressed(KeyCode::KeyH) {
-        controller.home(); // Isometric
+    if keyboard.just_pressed(KeyCode::KeyO) {
+        let orthographic = !controller.orthographic;
+        controller.set_projection(orthographic);
     }
 }
 
 /// Update camera transform
+#[allow(unused_variables)]
 fn camera_update_system(
     mut controller: ResMut<CameraController>,
     mut camera: Query<&mut Transform, With<MainCamera>>,
+    scene_data: Res<crate::IfcSceneData>,
     time: Res<Time>,
 ) {
     let dt = time.delta_secs();
@@ -550,13 +1205,197 @@ fn camera_update_system(
         unsafe {
             SAVE_COUNTER += 1;
             if SAVE_COUNTER % 30 == 0 {
-                save_camera(&controller.to_storage());
+                save_camera(&scene_data.camera_to_file(&controller.to_storage()));
             }
         }
     }
 }
 
+/// Switch the main camera between perspective and orthographic projection
+fn sync_projection_system(
+    controller: Res<CameraController>,
+    mut camera: Query<&mut Projection, With<MainCamera>>,
+) {
+    let Ok(mut projection) = camera.single_mut() else {
+        return;
+    };
+    if !projection_matches(&projection, &controller) {
+        *projection = projection_of(&controller);
+    }
+}
+
+/// Projection of a camera driven by `controller`
+///
+/// The orthographic view is as high as the perspective one at the target, so
+/// zooming keeps working through [`CameraController::distance`]. Its depth
+/// range follows the distance too; a fixed one as deep as
+/// [`CameraController::far`] would leave too little depth precision.
+pub fn projection_of(controller: &CameraController) -> Projection {
+    if controller.orthographic {
+        Projection::Orthographic(OrthographicProjection {
+            near: -controller.distance,
+            far: controller.distance * 10.0,
+            scaling_mode: ScalingMode::FixedVertical {
+                viewport_height: controller.view_height(),
+            },
+            ..OrthographicProjection::default_3d()
+        })
+    } else {
+        Projection::Perspective(PerspectiveProjection {
+            fov: controller.fov.to_radians(),
+            near: controller.near,
+            far: controller.far,
+            ..default()
+        })
+    }
+}
+
+/// Whether `projection` is already what [`projection_of`] would make
+pub fn projection_matches(projection: &Projection, controller: &CameraController) -> bool {
+    match (projection, controller.orthographic) {
+        (Projection::Orthographic(ortho), true) => matches!(ortho.scaling_mode,
+            ScalingMode::FixedVertical { viewport_height } if viewport_height == controller.view_height()),
+        (Projection::Perspective(_), false) => true,
+        _ => false,
+    }
+}
+
 /// Linear interpolation
 fn lerp(a: f32, b: f32, t: f32) -> f32 {
     a + (b - a) * t
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::BatchMaterials;
+    use crate::section::SectionPlanePlugin;
+    use crate::{IfcSceneData, ViewerSettings};
+    use bevy::camera::RenderTarget;
+    use bevy::render::gpu_readback::{Readback, ReadbackComplete};
+    use bevy::render::render_resource::{TextureFormat, TextureUsages};
+    use bevy::render::RenderPlugin;
+    use bevy::window::ExitCondition;
+
+    /// Width and height of the test render, one 256-byte row per line
+    const SIZE: u32 = 64;
+    /// Frames rendered before the image is read
+    const FRAMES: usize = 20;
+
+    #[derive(Resource, Default)]
+    struct Captured(Option<Vec<u8>>);
+
+    /// Render an inside corner (floor and two walls) with the batch
+    /// material and the ambient light alone, returning RGBA pixels
+    fn render_corner(ambient_occlusion: GraphicsQuality) -> Vec<u8> {
+        let mut app = App::new();
+        app.add_plugins((
+            DefaultPlugins
+                .set(WindowPlugin {
+                    primary_window: None,
+                    exit_condition: ExitCondition::DontExit,
+                    ..default()
+                })
+                .set(RenderPlugin {
+                    synchronous_pipeline_compilation: true,
+                    ..default()
+                })
+                .disable::<bevy::winit::WinitPlugin>(),
+            SectionPlanePlugin,
+        ))
+        .init_resource::<IfcSceneData>()
+        .init_resource::<Captured>()
+        .insert_resource(ViewerSettings {
+            ambient_occlusion,
+            msaa_samples: 1,
+            ..default()
+        })
+        .add_systems(Update, apply_render_settings_system);
+        app.finish();
+        app.cleanup();
+        app.init_resource::<BatchMaterials>();
+
+        let world = app.world_mut();
+        let mut image = Image::new_target_texture(SIZE, SIZE, TextureFormat::Rgba8UnormSrgb, None);
+        image.data = None;
+        image.texture_descriptor.usage |= TextureUsages::COPY_SRC;
+        let image = world.resource_mut::<Assets<Image>>().add(image);
+        let material = world.resource::<BatchMaterials>().opaque.clone();
+
+        let quads = [
+            (Vec3::Y, Vec3::new(1.0, 0.0, 1.0)),
+            (Vec3::X, Vec3::new(0.0, 1.0, 1.0)),
+            (Vec3::Z, Vec3::new(1.0, 1.0, 0.0)),
+        ];
+        for (normal, center) in quads {
+            let mesh = world
+                .resource_mut::<Assets<Mesh>>()
+                .add(Plane3d::new(normal, Vec2::ONE));
+            world.spawn((
+                Mesh3d(mesh),
+                MeshMaterial3d(material.clone()),
+                Transform::from_translation(center),
+            ));
+        }
+
+        world.spawn((
+            Camera3d::default(),
+            Camera {
+                clear_color: ClearColorConfig::Custom(Color::BLACK),
+                ..default()
+            },
+            RenderTarget::Image(image.clone().into()),
+            Transform::from_xyz(3.0, 2.5, 3.0).looking_at(Vec3::ZERO, Vec3::Y),
+            AmbientLight {
+                brightness: 2000.0,
+                ..default()
+            },
+            MainCamera,
+        ));
+        world.spawn(Readback::texture(image)).observe(
+            |event: On<ReadbackComplete>, mut captured: ResMut<Captured>| {
+                captured.0 = Some(event.data.clone());
+            },
+        );
+
+        for _ in 0..FRAMES {
+            app.update();
+        }
+        app.world_mut()
+            .resource_mut::<Captured>()
+            .0
+            .take()
+            .expect("no image read back")
+    }
+
+    /// Mean brightness of the pixels around the image centre, where the
+    /// corner is
+    fn corner_brightness(rgba: &[u8]) -> f32 {
+        let (from, to) = (SIZE / 2 - 4, SIZE / 2 + 4);
+        let mut sum = 0.0;
+        for y in from..to {
+            for x in from..to {
+                let i = ((y * SIZE + x) * 4) as usize;
+                sum += rgba[i..i + 3].iter().map(|&c| c as f32).sum::<f32>();
+            }
+        }
+        sum / ((to - from) * (to - from) * 3) as f32
+    }
+
+    #[test]
+    #[ignore = "renders offscreen, needs a GPU adapter"]
+    fn test_ssao_darkens_batched_corner() {
+        let plain = corner_brightness(&render_corner(GraphicsQuality::Off));
+        assert!(plain > 10.0, "corner not drawn: {}", plain);
+        for quality in [GraphicsQuality::Low, GraphicsQuality::High] {
+            let occluded = corner_brightness(&render_corner(quality));
+            assert!(
+                occluded < plain * 0.95,
+                "{:?}: {} is not darker than {}",
+                quality,
+                occluded,
+                plain
+            );
+        }
+    }
+}
@@ -0,0 +1,235 @@
+//! Camera paths - keyframed fly-throughs for presentations
+//!
+//! A path is a list of recorded cameras, each with the time it takes to get
+//! there. Playback runs a Catmull-Rom spline through the keyframes, so the
+//! camera keeps moving through them instead of stopping at each one, and
+//! eases in and out over the whole path. Orbit angles, distance and target
+//! are interpolated rather than the eye point, which keeps the camera
+//! looking where the keyframes look. Distance goes through its logarithm so
+//! long zooms keep an even pace.
+//!
+//! Dragging the view or a preset view stops playback.
+
+use crate::camera::{CameraController, CameraInputSet};
+#[cfg(target_arch = "wasm32")]
+use crate::storage::load_camera_path;
+use crate::storage::{CameraKeyframeStorage, CameraStorage};
+use bevy::prelude::*;
+use std::f32::consts::{PI, TAU};
+
+/// Camera path plugin
+pub struct CameraPathPlugin;
+
+impl Plugin for CameraPathPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraPathPlayer>().add_systems(
+            Update,
+            (poll_camera_path_system, play_camera_path_system)
+                .chain()
+                .before(CameraInputSet),
+        );
+    }
+}
+
+/// Path being played, if any
+#[derive(Resource, Default)]
+pub struct CameraPathPlayer {
+    keyframes: Vec<CameraKeyframeStorage>,
+    looping: bool,
+    /// Seconds into the path; `None` while stopped
+    elapsed: Option<f32>,
+    /// Camera the first keyframe is approached from, taken on the first frame
+    start: Option<CameraStorage>,
+}
+
+impl CameraPathPlayer {
+    /// Play `keyframes` from the current camera; a `looping` path starts
+    /// over from its end
+    pub fn play(&mut self, keyframes: Vec<CameraKeyframeStorage>, looping: bool) {
+        self.keyframes = keyframes;
+        self.looping = looping;
+        self.elapsed = (!self.keyframes.is_empty()).then_some(0.0);
+        self.start = None;
+    }
+
+    pub fn stop(&mut self) {
+        self.elapsed = None;
+        self.start = None;
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.elapsed.is_some()
+    }
+}
+
+/// Length of a path in seconds
+pub fn path_duration(keyframes: &[CameraKeyframeStorage]) -> f32 {
+    keyframes.iter().map(|k| k.duration.max(0.0)).sum()
+}
+
+/// Camera `time` seconds into a path that starts at `start`
+///
+/// Times past the end give the last keyframe.
+pub fn sample_path(
+    start: &CameraStorage,
+    keyframes: &[CameraKeyframeStorage],
+    time: f32,
+) -> CameraStorage {
+    let Some(last) = keyframes.last() else {
+        return start.clone();
+    };
+    let total = path_duration(keyframes);
+    if total <= 0.0 || time >= total {
+        return last.camera.clone();
+    }
+    let eased = smoothstep((time / total).max(0.0)) * total;
+
+    let cameras: Vec<&CameraStorage> = std::iter::once(start)
+        .chain(keyframes.iter().map(|k| &k.camera))
+        .collect();
+    let poses = unwrapped_poses(&cameras);
+    // Segment `i` runs from camera `i` to camera `i + 1`
+    let mut from = 0.0;
+    let mut segment = keyframes.len() - 1;
+    for (i, keyframe) in keyframes.iter().enumerate() {
+        let duration = keyframe.duration.max(0.0);
+        if eased < from + duration {
+            segment = i;
+            break;
+        }
+        from += duration;
+    }
+    let duration = keyframes[segment].duration.max(0.0);
+    let t = if duration > 0.0 {
+        ((eased - from) / duration).clamp(0.0, 1.0)
+    } else {
+        1.0
+    };
+    let at = |i: isize| poses[i.clamp(0, poses.len() as isize - 1) as usize];
+    let i = segment as isize;
+    let pose = catmull_rom(at(i - 1), at(i), at(i + 1), at(i + 2), t);
+
+    CameraStorage {
+        azimuth: pose[0].rem_euclid(TAU),
+        elevation: pose[1].clamp(-1.5, 1.5),
+        distance: pose[2].exp(),
+        target: [pose[3], pose[4], pose[5]].map(f64::from),
+        orthographic: cameras[segment + 1].orthographic,
+    }
+}
+
+/// Orbit cameras as points to interpolate between: azimuth, elevation,
+/// log distance and target
+///
+/// Each azimuth is moved by whole turns to within half a turn of the one
+/// before, so the camera never goes the long way round.
+fn unwrapped_poses(cameras: &[&CameraStorage]) -> Vec<[f32; 6]> {
+    let mut poses: Vec<[f32; 6]> = Vec::with_capacity(cameras.len());
+    for camera in cameras {
+        let mut azimuth = camera.azimuth;
+        if let Some(previous) = poses.last() {
+            azimuth = previous[0] + (azimuth - previous[0] + PI).rem_euclid(TAU) - PI;
+        }
+        let [x, y, z] = camera.target.map(|v| v as f32);
+        poses.push([
+            azimuth,
+            camera.elevation,
+            camera.distance.max(0.01).ln(),
+            x,
+            y,
+            z,
+        ]);
+    }
+    poses
+}
+
+/// Uniform Catmull-Rom spline between `p1` and `p2`
+fn catmull_rom(p0: [f32; 6], p1: [f32; 6], p2: [f32; 6], p3: [f32; 6], t: f32) -> [f32; 6] {
+    let (t2, t3) = (t * t, t * t * t);
+    std::array::from_fn(|i| {
+        0.5 * (2.0 * p1[i]
+            + (p2[i] - p0[i]) * t
+            + (2.0 * p0[i] - 5.0 * p1[i] + 4.0 * p2[i] - p3[i]) * t2
+            + (3.0 * p1[i] - p0[i] - 3.0 * p2[i] + p3[i]) * t3)
+    })
+}
+
+/// Ease in and out
+fn smoothstep(t: f32) -> f32 {
+    let t = t.clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Poll the camera path and play/stop requests from localStorage
+///
+/// `last_run` is the run that was started last, see
+/// [`crate::storage::CameraPathStorage`].
+#[allow(unused_mut, unused_variables)]
+fn poll_camera_path_system(
+    mut player: ResMut<CameraPathPlayer>,
+    scene_data: Res<crate::IfcSceneData>,
+    mut frame: Local<u32>,
+    mut last_run: Local<u32>,
+) {
+    #[cfg(target_arch = "wasm32")]
+    {
+        *frame = frame.wrapping_add(1);
+        if !frame.is_multiple_of(30) {
+            return;
+        }
+        let Some(path) = load_camera_path() else {
+            return;
+        };
+        if path.playing && path.run != *last_run {
+            *last_run = path.run;
+            // Keyframes target file coordinates; play them in the world
+            let keyframes = path
+                .keyframes
+                .into_iter()
+                .map(|k| CameraKeyframeStorage {
+                    camera: scene_data.camera_to_world(&k.camera),
+                    ..k
+                })
+                .collect();
+            player.play(keyframes, path.looping);
+        } else if !path.playing && player.is_playing() {
+            player.stop();
+        }
+    }
+}
+
+/// Move the camera along the path being played
+fn play_camera_path_system(
+    time: Res<Time>,
+    mut player: ResMut<CameraPathPlayer>,
+    mut controller: ResMut<CameraController>,
+) {
+    let Some(elapsed) = player.elapsed else {
+        return;
+    };
+    // The user took over the camera
+    if controller.is_dragging || (player.start.is_some() && controller.animation_target.is_some()) {
+        player.stop();
+        return;
+    }
+    let start = player
+        .start
+        .get_or_insert_with(|| controller.to_storage())
+        .clone();
+
+    let elapsed = elapsed + time.delta_secs();
+    let camera = sample_path(&start, &player.keyframes, elapsed);
+    controller.animation_target = None;
+    controller.is_animating = false;
+    controller.from_storage(&camera);
+
+    if elapsed < path_duration(&player.keyframes) {
+        player.elapsed = Some(elapsed);
+    } else if player.looping {
+        // Back to the first keyframe from the last
+        player.elapsed = Some(0.0);
+        player.start = Some(camera);
+    } else {
+        player.stop();
+    }
+}
@@ -0,0 +1,247 @@
+//! Geometry deduplication across entities
+//!
+//! Files that don't use IfcMappedItem still often place the same window or
+//! fixture hundreds of times, each with its own copy of identical geometry in
+//! world coordinates. [`dedup_meshes`] finds meshes that only differ by a
+//! translation, points them at one shared `Arc<MeshGeometry>` and moves the
+//! offset into the per-instance transform. Batching, picking and plan
+//! sections already apply that transform, so nothing downstream changes.
+
+use crate::mesh::{IfcMesh, MeshGeometry};
+use rustc_hash::FxHashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+/// Grid (in model units) positions are snapped to before hashing
+const HASH_GRID: f32 = 1e-4;
+
+/// Largest per-coordinate difference for two meshes to count as identical
+const MATCH_TOLERANCE: f32 = 1e-4;
+
+/// Outcome of a [`dedup_meshes`] pass
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DedupStats {
+    /// Meshes examined
+    pub meshes: usize,
+    /// Distinct geometries left afterwards
+    pub unique_geometries: usize,
+    /// Meshes now sharing another mesh's geometry
+    pub shared_instances: usize,
+    /// Vertex and index bytes no longer held
+    pub saved_bytes: usize,
+}
+
+impl std::fmt::Display for DedupStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} meshes, {} unique geometries, {} shared instances, {:.1} MB saved",
+            self.meshes,
+            self.unique_geometries,
+            self.shared_instances,
+            self.saved_bytes as f64 / (1024.0 * 1024.0)
+        )
+    }
+}
+
+/// Share geometry between meshes that are translated copies of each other
+///
+/// Only meshes with an identity transform are considered; normals, indices
+/// and per-vertex colors must match exactly, positions within
+/// [`MATCH_TOLERANCE`] after removing the offset of the first vertex.
+pub fn dedup_meshes(meshes: &mut [IfcMesh]) -> DedupStats {
+    let mut stats = DedupStats {
+        meshes: meshes.len(),
+        ..Default::default()
+    };
+
+    // Hash -> indices of meshes holding a distinct geometry with that hash
+    let mut buckets: FxHashMap<u64, Vec<usize>> = FxHashMap::default();
+    let mut unique = 0;
+
+    for i in 0..meshes.len() {
+        let geometry = &meshes[i].geometry;
+        if geometry.is_empty() || meshes[i].transform != IDENTITY {
+            unique += 1;
+            continue;
+        }
+
+        let hash = shape_hash(geometry);
+        let candidates = buckets.entry(hash).or_default();
+        let original = candidates.iter().copied().find(|&j| {
+            Arc::ptr_eq(&meshes[j].geometry, geometry) || same_shape(&meshes[j].geometry, geometry)
+        });
+
+        match original {
+            Some(j) if !Arc::ptr_eq(&meshes[j].geometry, geometry) => {
+                let from = origin(&meshes[j].geometry);
+                let to = origin(geometry);
+                stats.saved_bytes += geometry_bytes(geometry);
+                stats.shared_instances += 1;
+                meshes[i].geometry = Arc::clone(&meshes[j].geometry);
+                meshes[i].transform =
+                    translation([to[0] - from[0], to[1] - from[1], to[2] - from[2]]);
+            }
+            // Already shared (e.g. a MappedItem instance)
+            Some(_) => {}
+            None => {
+                candidates.push(i);
+                unique += 1;
+            }
+        }
+    }
+
+    stats.unique_geometries = unique;
+    stats
+}
+
+const IDENTITY: [f32; 16] = [
+    1.0, 0.0, 0.0, 0.0, //
+    0.0, 1.0, 0.0, 0.0, //
+    0.0, 0.0, 1.0, 0.0, //
+    0.0, 0.0, 0.0, 1.0,
+];
+
+/// First vertex, used as the reference point of a shape
+fn origin(geometry: &MeshGeometry) -> [f32; 3] {
+    [
+        geometry.positions[0],
+        geometry.positions[1],
+        geometry.positions[2],
+    ]
+}
+
+/// Hash of a geometry independent of where it is placed
+fn shape_hash(geometry: &MeshGeometry) -> u64 {
+    let mut hasher = rustc_hash::FxHasher::default();
+    let o = origin(geometry);
+    geometry.positions.len().hash(&mut hasher);
+    for p in geometry.positions.chunks_exact(3) {
+        for axis in 0..3 {
+            (((p[axis] - o[axis]) / HASH_GRID).round() as i64).hash(&mut hasher);
+        }
+    }
+    geometry.indices.hash(&mut hasher);
+    for n in &geometry.normals {
+        n.to_bits().hash(&mut hasher);
+    }
+    for c in &geometry.colors {
+        c.to_bits().hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Whether two geometries are translated copies of each other
+fn same_shape(a: &MeshGeometry, b: &MeshGeometry) -> bool {
+    if a.positions.len() != b.positions.len()
+        || a.indices != b.indices
+        || a.normals != b.normals
+        || a.colors != b.colors
+    {
+        return false;
+    }
+    let (oa, ob) = (origin(a), origin(b));
+    a.positions
+        .chunks_exact(3)
+        .zip(b.positions.chunks_exact(3))
+        .all(|(pa, pb)| {
+            (0..3).all(|i| ((pa[i] - oa[i]) - (pb[i] - ob[i])).abs() <= MATCH_TOLERANCE)
+        })
+}
+
+/// Bevy transform for an offset given in IFC (Z-up) coordinates
+fn translation(offset: [f32; 3]) -> [f32; 16] {
+    let mut transform = IDENTITY;
+    transform[12] = offset[0];
+    transform[13] = offset[2];
+    transform[14] = -offset[1];
+    transform
+}
+
+fn geometry_bytes(geometry: &MeshGeometry) -> usize {
+    (geometry.positions.len() + geometry.normals.len() + geometry.colors.len())
+        * std::mem::size_of::<f32>()
+        + geometry.indices.len() * std::mem::size_of::<u32>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::math::{Mat4, Vec3};
+
+    /// Triangle with its first vertex at `at`
+    fn triangle(entity_id: u64, at: [f32; 3]) -> IfcMesh {
+        let [x, y, z] = at;
+        let geometry = MeshGeometry::new(
+            vec![x, y, z, x + 1.0, y, z, x, y + 2.0, z + 3.0],
+            vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0],
+            vec![0, 1, 2],
+        );
+        IfcMesh::new(
+            entity_id,
+            Arc::new(geometry),
+            [0.8, 0.8, 0.8, 1.0],
+            IDENTITY,
+            "IFCWINDOW".to_string(),
+            None,
+        )
+    }
+
+    /// Vertices as rendered: swizzled to Y-up, then transformed
+    fn world_positions(mesh: &IfcMesh) -> Vec<Vec3> {
+        let transform = Mat4::from_cols_array(&mesh.transform);
+        mesh.geometry
+            .positions
+            .chunks_exact(3)
+            .map(|p| transform.transform_point3(Vec3::new(p[0], p[2], -p[1])))
+            .collect()
+    }
+
+    #[test]
+    fn test_translated_copy_is_shared() {
+        let mut meshes = vec![triangle(1, [1.0, 2.0, 3.0]), triangle(2, [11.0, -4.0, 7.5])];
+        let before: Vec<_> = meshes.iter().map(world_positions).collect();
+
+        let stats = dedup_meshes(&mut meshes);
+        assert_eq!(stats.unique_geometries, 1);
+        assert_eq!(stats.shared_instances, 1);
+        assert_eq!(stats.saved_bytes, geometry_bytes(&meshes[0].geometry));
+        assert!(Arc::ptr_eq(&meshes[0].geometry, &meshes[1].geometry));
+        assert_eq!(meshes[0].transform, IDENTITY);
+
+        for (mesh, expected) in meshes.iter().zip(&before) {
+            for (p, e) in world_positions(mesh).iter().zip(expected) {
+                assert!(p.abs_diff_eq(*e, 1e-5), "{p} != {e}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_transformed_mesh_is_left_alone() {
+        let mut placed = triangle(2, [5.0, 0.0, 0.0]);
+        placed.transform = translation([0.0, 0.0, 1.0]);
+        let original = Arc::clone(&placed.geometry);
+        let mut meshes = vec![triangle(1, [0.0, 0.0, 0.0]), placed];
+
+        let stats = dedup_meshes(&mut meshes);
+        assert_eq!(stats.unique_geometries, 2);
+        assert_eq!(stats.shared_instances, 0);
+        assert!(Arc::ptr_eq(&meshes[1].geometry, &original));
+        assert_eq!(meshes[1].transform, translation([0.0, 0.0, 1.0]));
+    }
+
+    #[test]
+    fn test_near_miss_is_not_merged() {
+        let mut near = triangle(2, [10.0, 0.0, 0.0]);
+        let mut geometry = (*near.geometry).clone();
+        geometry.positions[8] += MATCH_TOLERANCE * 10.0;
+        near.geometry = Arc::new(geometry);
+        let mut meshes = vec![triangle(1, [0.0, 0.0, 0.0]), near];
+
+        let stats = dedup_meshes(&mut meshes);
+        assert_eq!(stats.unique_geometries, 2);
+        assert_eq!(stats.shared_instances, 0);
+        assert!(!Arc::ptr_eq(&meshes[0].geometry, &meshes[1].geometry));
+        assert_eq!(meshes[1].transform, IDENTITY);
+    }
+}
@@ -0,0 +1,381 @@
+//! Exploded view - storeys lifted apart, or elements pushed out from the centre
+//!
+//! Every entity gets an offset at factor 1: storeys rise by their rank times
+//! the average storey height, elements move away from the model centre by
+//! their distance to it. The applied factor eases toward the requested one,
+//! and batches are moved in place by rewriting the vertex positions of each
+//! entity's vertex range. The assembled positions are stashed the first time
+//! a batch moves and put back once the factor is zero again. Entity bounds
+//! follow the geometry, so focus and labels land on the moved elements. The
+//! picking BVHs are rebuilt once the animation settles.
+
+use crate::mesh::{BatchBvh, BatchVisibility, EntityBounds, IfcEntity, MaskableMesh, XrayGhost};
+use crate::IfcSceneData;
+use bevy::camera::primitives::Aabb;
+use bevy::mesh::VertexAttributeValues;
+use bevy::prelude::*;
+use rustc_hash::FxHashMap;
+
+/// Rate the applied factor approaches the requested one, per second
+const EASE_RATE: f32 = 8.0;
+
+/// Distance to the requested factor at which the animation snaps to it
+const SETTLE_EPSILON: f32 = 1e-3;
+
+/// Exploded view plugin
+pub struct ExplodePlugin;
+
+impl Plugin for ExplodePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ExplodeState>().add_systems(
+            Update,
+            (
+                poll_explode_system,
+                animate_explode_system,
+                apply_explode_system,
+            )
+                .chain(),
+        );
+    }
+}
+
+/// How the exploded view pulls the model apart
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExplodeMode {
+    /// Storeys lifted apart, each keeping its elements together
+    #[default]
+    Storeys,
+    /// Every element pushed out from the model centre
+    Elements,
+}
+
+/// Geometry of a batch before it was moved
+struct Assembled {
+    /// Vertex positions, one list per mesh in [`BatchVisibility::meshes`]
+    positions: Vec<Vec<[f32; 3]>>,
+    bvh: BatchBvh,
+}
+
+/// Requested exploded view and the animation toward it
+#[derive(Resource, Default)]
+pub struct ExplodeState {
+    pub mode: ExplodeMode,
+    /// Spread of the explosion; 0 assembles the model
+    pub factor: f32,
+    /// Mode the geometry is moved in; a new mode waits until the model is
+    /// assembled again
+    applied_mode: ExplodeMode,
+    /// Factor currently applied to the geometry
+    current: f32,
+    /// Offset of each entity at factor 1
+    offsets: FxHashMap<u64, Vec3>,
+    /// Mode `offsets` were computed in; `None` once the entities changed
+    offsets_mode: Option<ExplodeMode>,
+    /// Assembled geometry of moved batches
+    batches: FxHashMap<Entity, Assembled>,
+    /// Assembled bounds of moved entities
+    bounds: FxHashMap<Entity, EntityBounds>,
+}
+
+impl ExplodeState {
+    /// Factor currently applied to the geometry
+    pub fn current(&self) -> f32 {
+        self.current
+    }
+
+    /// Factor the animation heads for
+    fn goal(&self) -> f32 {
+        if self.mode == self.applied_mode {
+            self.factor
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Poll the exploded view from localStorage
+#[allow(unused_mut, unused_variables)]
+fn poll_explode_system(mut state: ResMut<ExplodeState>, mut frame: Local<u32>) {
+    #[cfg(target_arch = "wasm32")]
+    {
+        *frame = frame.wrapping_add(1);
+        if !frame.is_multiple_of(30) {
+            return;
+        }
+        let Some(storage) = crate::storage::load_explode() else {
+            return;
+        };
+        // Off keeps the mode, so the model collapses the way it came apart
+        let (mode, factor) = match storage.mode.as_str() {
+            "storeys" => (ExplodeMode::Storeys, storage.factor.max(0.0)),
+            "elements" => (ExplodeMode::Elements, storage.factor.max(0.0)),
+            _ => (state.mode, 0.0),
+        };
+        if state.mode != mode || state.factor != factor {
+            state.mode = mode;
+            state.factor = factor;
+        }
+    }
+}
+
+/// Ease the applied factor toward the requested one
+fn animate_explode_system(time: Res<Time>, mut state: ResMut<ExplodeState>) {
+    let goal = state.goal();
+    if state.current == goal {
+        if state.current == 0.0 && state.applied_mode != state.mode {
+            state.applied_mode = state.mode;
+        }
+        return;
+    }
+    let t = 1.0 - (-EASE_RATE * time.delta_secs()).exp();
+    let current = state.current + (goal - state.current) * t;
+    state.current = if (goal - current).abs() < SETTLE_EPSILON {
+        goal
+    } else {
+        current
+    };
+}
+
+/// Offset of each entity at factor 1, from the assembled bounds
+fn compute_offsets(
+    mode: ExplodeMode,
+    scene_data: &IfcSceneData,
+    bounds: &FxHashMap<u64, EntityBounds>,
+) -> FxHashMap<u64, Vec3> {
+    let mut min = Vec3::splat(f32::INFINITY);
+    let mut max = Vec3::splat(f32::NEG_INFINITY);
+    for b in bounds.values() {
+        min = min.min(b.min);
+        max = max.max(b.max);
+    }
+    if !min.is_finite() || !max.is_finite() {
+        return FxHashMap::default();
+    }
+
+    match mode {
+        ExplodeMode::Storeys => {
+            // Storeys by elevation, so the ground floor stays put
+            let mut elevations: FxHashMap<&str, f32> = FxHashMap::default();
+            for entity in &scene_data.entities {
+                if let Some(storey) = &entity.storey {
+                    elevations
+                        .entry(&**storey)
+                        .or_insert(entity.storey_elevation.unwrap_or(0.0));
+                }
+            }
+            let mut storeys: Vec<(&str, f32)> = elevations.into_iter().collect();
+            storeys.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(b.0)));
+            let rank: FxHashMap<&str, usize> = storeys
+                .iter()
+                .enumerate()
+                .map(|(i, (storey, _))| (*storey, i))
+                .collect();
+            let storey_height = (max.y - min.y) / storeys.len().max(1) as f32;
+
+            scene_data
+                .entities
+                .iter()
+                .filter_map(|e| {
+                    let rank = *rank.get(&**e.storey.as_ref()?)?;
+                    (rank > 0).then(|| (e.id, Vec3::Y * rank as f32 * storey_height))
+                })
+                .collect()
+        }
+        ExplodeMode::Elements => {
+            let center = (min + max) * 0.5;
+            bounds
+                .iter()
+                .map(|(id, b)| (*id, b.center() - center))
+                .collect()
+        }
+    }
+}
+
+/// Vertex positions of a mesh
+fn vertex_positions(meshes: &Assets<Mesh>, handle: &Handle<Mesh>) -> Vec<[f32; 3]> {
+    match meshes
+        .get(handle)
+        .and_then(|mesh| mesh.attribute(Mesh::ATTRIBUTE_POSITION))
+    {
+        Some(VertexAttributeValues::Float32x3(positions)) => positions.clone(),
+        _ => Vec::new(),
+    }
+}
+
+/// Upload assembled positions moved by each entity's offset, returning the
+/// BVH over them if `rebuild_bvh`
+fn move_mesh(
+    meshes: &mut Assets<Mesh>,
+    maskable: &MaskableMesh,
+    assembled: &[[f32; 3]],
+    offset: impl Fn(u64) -> Vec3,
+    rebuild_bvh: bool,
+) -> Option<BatchBvh> {
+    let mut positions = assembled.to_vec();
+    for (entity_id, range) in maskable.vertex_ranges() {
+        let offset = offset(entity_id);
+        if offset == Vec3::ZERO {
+            continue;
+        }
+        if let Some(vertices) = positions.get_mut(range) {
+            for vertex in vertices {
+                *vertex = (Vec3::from(*vertex) + offset).to_array();
+            }
+        }
+    }
+    let bvh = rebuild_bvh
+        .then(|| maskable.build_bvh(meshes, &positions))
+        .flatten();
+    if let Some(mesh) = meshes.get_mut(&maskable.handle) {
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    }
+    bvh
+}
+
+/// System to move batches and entity bounds to the applied factor
+///
+/// Runs when the factor changes, when the offsets are recomputed and for
+/// batches and entities that appear while the model is apart.
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+fn apply_explode_system(
+    mut commands: Commands,
+    mut state: ResMut<ExplodeState>,
+    scene_data: Res<IfcSceneData>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut entities: Query<(Entity, Ref<IfcEntity>, &mut EntityBounds)>,
+    mut batches: Query<(Entity, &mut BatchVisibility, &mut BatchBvh), Without<XrayGhost>>,
+    mut ghosts: Query<(Entity, Ref<XrayGhost>, &mut BatchBvh)>,
+    mut last_factor: Local<f32>,
+) {
+    let state = &mut *state;
+    let entities_added = entities.iter().any(|(_, entity, _)| entity.is_added());
+    if entities_added {
+        state.offsets_mode = None;
+    }
+    if state.current == 0.0 && *last_factor == 0.0 {
+        return;
+    }
+    let moved = state.current != *last_factor;
+    *last_factor = state.current;
+    let current = state.current;
+    let settled = current == state.goal();
+
+    // Offsets follow the assembled bounds, stashed before anything moves
+    if entities_added {
+        state.bounds.retain(|e, _| entities.contains(*e));
+    }
+    for (e, _, bounds) in entities.iter() {
+        state.bounds.entry(e).or_insert_with(|| bounds.clone());
+    }
+    let recompute = state.offsets_mode != Some(state.applied_mode);
+    if recompute {
+        let mut assembled: FxHashMap<u64, EntityBounds> = FxHashMap::default();
+        for (e, entity, _) in entities.iter() {
+            let Some(bounds) = state.bounds.get(&e) else {
+                continue;
+            };
+            if !bounds.min.is_finite() || !bounds.max.is_finite() {
+                continue;
+            }
+            assembled
+                .entry(entity.id)
+                .and_modify(|b| {
+                    b.min = b.min.min(bounds.min);
+                    b.max = b.max.max(bounds.max);
+                })
+                .or_insert_with(|| bounds.clone());
+        }
+        state.offsets = compute_offsets(state.applied_mode, &scene_data, &assembled);
+        state.offsets_mode = Some(state.applied_mode);
+    }
+    let offsets = &state.offsets;
+    let offset = |id: u64| offsets.get(&id).map_or(Vec3::ZERO, |o| *o * current);
+
+    for (e, entity, mut bounds) in entities.iter_mut() {
+        if !(moved || recompute || entity.is_added()) {
+            continue;
+        }
+        if let Some(assembled) = state.bounds.get(&e) {
+            let offset = offset(entity.id);
+            bounds.min = assembled.min + offset;
+            bounds.max = assembled.max + offset;
+        }
+    }
+
+    let mut moved_batches = 0;
+    let mut batches_added = false;
+    for (e, mut batch, mut bvh) in batches.iter_mut() {
+        batches_added |= batch.is_added();
+        if !(moved || recompute || batch.is_added()) {
+            continue;
+        }
+        if current == 0.0 && !state.batches.contains_key(&e) {
+            continue;
+        }
+        let assembled = state.batches.entry(e).or_insert_with(|| Assembled {
+            positions: batch
+                .meshes
+                .iter()
+                .map(|maskable| vertex_positions(&meshes, &maskable.handle))
+                .collect(),
+            bvh: bvh.clone(),
+        });
+        for (level, (maskable, positions)) in
+            batch.meshes.iter().zip(&assembled.positions).enumerate()
+        {
+            // Picking only uses the full-detail level
+            let rebuild_bvh = level == 0 && settled && current > 0.0;
+            if let Some(rebuilt) = move_mesh(&mut meshes, maskable, positions, offset, rebuild_bvh)
+            {
+                *bvh = rebuilt;
+            }
+        }
+        if current == 0.0 {
+            *bvh = assembled.bvh.clone();
+        }
+        // Frustum culling needs bounds around the moved vertices
+        commands.entity(e).remove::<Aabb>();
+        batch.set_changed();
+        moved_batches += 1;
+    }
+
+    // Forget batches a rebuild despawned
+    if batches_added {
+        state.batches.retain(|e, _| batches.contains(*e));
+    }
+
+    // Ghosts copy their source batch, moved or not
+    for (e, ghost, mut bvh) in ghosts.iter_mut() {
+        if !(moved || recompute || ghost.is_added()) {
+            continue;
+        }
+        let Some(assembled) = state.batches.get(&ghost.source) else {
+            continue;
+        };
+        let Some(positions) = assembled.positions.first() else {
+            continue;
+        };
+        let rebuild_bvh = settled && current > 0.0;
+        if let Some(rebuilt) = move_mesh(&mut meshes, &ghost.mesh, positions, offset, rebuild_bvh) {
+            *bvh = rebuilt;
+        }
+        if current == 0.0 {
+            *bvh = assembled.bvh.clone();
+        }
+        commands.entity(e).remove::<Aabb>();
+    }
+
+    if current == 0.0 {
+        state.batches.clear();
+        state.bounds.clear();
+        crate::log(&format!(
+            "[Bevy] Model assembled ({} batches)",
+            moved_batches
+        ));
+    } else if settled && moved_batches > 0 {
+        crate::log(&format!(
+            "[Bevy] Exploded view at {:.2} ({} batches)",
+            current, moved_batches
+        ));
+    }
+}
@@ -15,6 +15,24 @@ pub struct BevyApp {
     app: App,
 }
 
+/// Keep the viewer state in files under `dir`
+///
+/// Call before [`create_bevy_app`]; without it the state lives in memory.
+/// Returns false if the directory can't be used or the app already exists.
+///
+/// # Safety
+/// - `dir` must be a valid null-terminated UTF-8 path
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn set_state_dir(dir: *const std::ffi::c_char) -> bool {
+    if dir.is_null() {
+        return false;
+    }
+    match std::ffi::CStr::from_ptr(dir).to_str() {
+        Ok(dir) => crate::storage::use_state_dir(dir),
+        Err(_) => false,
+    }
+}
+
 /// Create a new Bevy app attached to a native view
 ///
 /// # Safety
@@ -157,6 +175,42 @@ pub unsafe extern "C" fn load_geometry(
     }
 }
 
+/// Load IFC geometry in the binary transfer format
+///
+/// Much faster than [`load_geometry`] for large models: the data is copied
+/// straight into vertex buffers instead of being parsed as JSON. See
+/// [`ifc_lite_state::geometry`] for the layout.
+///
+/// # Safety
+/// - `bevy_app` must be a valid pointer returned by `create_bevy_app`
+/// - `data` must point to `len` readable bytes
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn load_geometry_binary(
+    bevy_app: *mut BevyApp,
+    data: *const u8,
+    len: usize,
+) -> bool {
+    if bevy_app.is_null() || data.is_null() {
+        return false;
+    }
+
+    let bytes = std::slice::from_raw_parts(data, len);
+    let Some(meshes) = crate::storage::decode_geometry(bytes) else {
+        eprintln!("Failed to decode binary geometry ({} bytes)", len);
+        return false;
+    };
+
+    let app = &mut (*bevy_app).app;
+
+    if let Some(mut scene_data) = app.world_mut().get_resource_mut::<IfcSceneData>() {
+        scene_data.meshes = meshes;
+        scene_data.dirty = true;
+        true
+    } else {
+        false
+    }
+}
+
 /// Load entity metadata
 ///
 /// # Safety
@@ -176,7 +230,7 @@ pub unsafe extern "C" fn load_entities(
         Err(_) => return false,
     };
 
-    let entities: Vec<EntityInfo> = match serde_json::from_str(json_str) {
+    let mut entities: Vec<EntityInfo> = match serde_json::from_str(json_str) {
         Ok(e) => e,
         Err(e) => {
             eprintln!("Failed to parse entities JSON: {}", e);
@@ -184,6 +238,8 @@ pub unsafe extern "C" fn load_entities(
         }
     };
 
+    crate::intern_entity_strings(&mut entities);
+
     let app = &mut (*bevy_app).app;
 
     if let Some(mut scene_data) = app.world_mut().get_resource_mut::<IfcSceneData>() {
@@ -212,6 +268,62 @@ pub unsafe extern "C" fn select_entity(bevy_app: *mut BevyApp, entity_id: u64) {
     }
 }
 
+/// Copy the selected entity IDs into `out_ids`
+///
+/// Returns the total number of selected entities, which may exceed
+/// `capacity`; call again with a larger buffer in that case.
+///
+/// # Safety
+/// - `bevy_app` must be a valid pointer returned by `create_bevy_app`
+/// - `out_ids` must be null or point to `capacity` writable u64 values
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn get_selection(
+    bevy_app: *mut BevyApp,
+    out_ids: *mut u64,
+    capacity: usize,
+) -> usize {
+    if bevy_app.is_null() {
+        return 0;
+    }
+
+    let app = &(*bevy_app).app;
+
+    let Some(selection) = app.world().get_resource::<crate::SelectionState>() else {
+        return 0;
+    };
+    if !out_ids.is_null() {
+        let out = std::slice::from_raw_parts_mut(out_ids, capacity);
+        for (slot, id) in out.iter_mut().zip(&selection.selected) {
+            *slot = *id;
+        }
+    }
+    selection.selected.len()
+}
+
+/// Switch left drag between camera navigation and box selection
+///
+/// # Safety
+/// - `bevy_app` must be a valid pointer returned by `create_bevy_app`
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn set_box_select(bevy_app: *mut BevyApp, enabled: bool) {
+    if bevy_app.is_null() {
+        return;
+    }
+
+    let app = &mut (*bevy_app).app;
+
+    if let Some(mut controller) = app
+        .world_mut()
+        .get_resource_mut::<crate::CameraController>()
+    {
+        controller.mode = if enabled {
+            crate::CameraMode::BoxSelect
+        } else {
+            crate::CameraMode::Orbit
+        };
+    }
+}
+
 /// Clear selection
 ///
 /// # Safety
@@ -305,6 +417,159 @@ pub unsafe extern "C" fn isolate_entities(
     }
 }
 
+/// Set x-ray mode
+///
+/// With `enabled`, everything except the focus set is drawn translucent. The
+/// focus set is every entity of `ifc_type`, or the selection if it is null.
+///
+/// # Safety
+/// - `bevy_app` must be a valid pointer returned by `create_bevy_app`
+/// - `ifc_type` must be null or a valid null-terminated string
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn set_xray(
+    bevy_app: *mut BevyApp,
+    enabled: bool,
+    ifc_type: *const std::ffi::c_char,
+) {
+    if bevy_app.is_null() {
+        return;
+    }
+
+    let focus = if !enabled {
+        None
+    } else if ifc_type.is_null() {
+        Some(crate::XrayFocus::Selection)
+    } else {
+        match std::ffi::CStr::from_ptr(ifc_type).to_str() {
+            Ok(ifc_type) => Some(crate::XrayFocus::Type(ifc_type.to_string())),
+            Err(_) => return,
+        }
+    };
+
+    let app = &mut (*bevy_app).app;
+
+    if let Some(mut settings) = app.world_mut().get_resource_mut::<ViewerSettings>() {
+        settings.xray = focus;
+    }
+}
+
+/// Set space mode
+///
+/// With `show`, IfcSpace volumes are drawn translucent; otherwise they are
+/// hidden.
+///
+/// # Safety
+/// - `bevy_app` must be a valid pointer returned by `create_bevy_app`
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn show_spaces(bevy_app: *mut BevyApp, show: bool) {
+    if bevy_app.is_null() {
+        return;
+    }
+
+    let app = &mut (*bevy_app).app;
+
+    if let Some(mut settings) = app.world_mut().get_resource_mut::<ViewerSettings>() {
+        settings.show_spaces = show;
+    }
+}
+
+/// Set the exploded view
+///
+/// With `elements`, every element moves out from the model centre;
+/// otherwise storeys are lifted apart. The model animates to `factor`; 0
+/// assembles it again.
+///
+/// # Safety
+/// - `bevy_app` must be a valid pointer returned by `create_bevy_app`
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn set_explode(bevy_app: *mut BevyApp, elements: bool, factor: f32) {
+    if bevy_app.is_null() {
+        return;
+    }
+
+    let app = &mut (*bevy_app).app;
+
+    if let Some(mut state) = app.world_mut().get_resource_mut::<crate::ExplodeState>() {
+        state.mode = if elements {
+            crate::ExplodeMode::Elements
+        } else {
+            crate::ExplodeMode::Storeys
+        };
+        state.factor = factor.max(0.0);
+    }
+}
+
+/// Set whether orbits turn about the point under the cursor and the wheel
+/// zooms toward it
+///
+/// # Safety
+/// - `bevy_app` must be a valid pointer returned by `create_bevy_app`
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn set_cursor_navigation(
+    bevy_app: *mut BevyApp,
+    orbit_around_cursor: bool,
+    zoom_to_cursor: bool,
+) {
+    if bevy_app.is_null() {
+        return;
+    }
+
+    let app = &mut (*bevy_app).app;
+
+    if let Some(mut settings) = app.world_mut().get_resource_mut::<crate::ViewerSettings>() {
+        settings.orbit_around_cursor = orbit_around_cursor;
+        settings.zoom_to_cursor = zoom_to_cursor;
+    }
+}
+
+/// Set section planes and section box
+///
+/// `planes` holds `plane_count` planes of six floats each: origin x, y, z
+/// then normal x, y, z, in IFC coordinates. The side the normal points to is
+/// cut away; planes past [`crate::section::MAX_SECTION_PLANES`] are ignored.
+/// `box_min` and `box_max` are IFC points of three floats each, or null for
+/// no section box.
+///
+/// # Safety
+/// - `bevy_app` must be a valid pointer returned by `create_bevy_app`
+/// - `planes` must be null or point to `plane_count * 6` floats
+/// - `box_min` and `box_max` must be null or point to 3 floats each
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn set_section(
+    bevy_app: *mut BevyApp,
+    planes: *const f32,
+    plane_count: usize,
+    box_min: *const f32,
+    box_max: *const f32,
+) {
+    use crate::section::{box_planes, world_plane, SectionClipping, MAX_SECTION_PLANES};
+
+    if bevy_app.is_null() {
+        return;
+    }
+
+    let mut world_planes = Vec::new();
+    if !planes.is_null() {
+        let values = std::slice::from_raw_parts(planes, plane_count.min(MAX_SECTION_PLANES) * 6);
+        world_planes.extend(
+            values
+                .chunks_exact(6)
+                .map(|p| world_plane(Vec3::new(p[3], p[4], p[5]), Vec3::new(p[0], p[1], p[2]))),
+        );
+    }
+    if !box_min.is_null() && !box_max.is_null() {
+        let min = Vec3::from_slice(std::slice::from_raw_parts(box_min, 3));
+        let max = Vec3::from_slice(std::slice::from_raw_parts(box_max, 3));
+        world_planes.extend(box_planes(min.min(max), min.max(max)));
+    }
+
+    let app = &mut (*bevy_app).app;
+
+    if let Some(mut clipping) = app.world_mut().get_resource_mut::<SectionClipping>() {
+        clipping.world_planes = world_planes;
+    }
+}
+
 /// Set camera home view
 ///
 /// # Safety
@@ -350,6 +615,112 @@ pub unsafe extern "C" fn camera_fit_all(bevy_app: *mut BevyApp) {
     }
 }
 
+/// Switch the camera between perspective and orthographic projection
+///
+/// # Safety
+/// - `bevy_app` must be a valid pointer returned by `create_bevy_app`
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn camera_set_projection(bevy_app: *mut BevyApp, orthographic: bool) {
+    if bevy_app.is_null() {
+        return;
+    }
+
+    let app = &mut (*bevy_app).app;
+
+    if let Some(mut controller) = app
+        .world_mut()
+        .get_resource_mut::<crate::CameraController>()
+    {
+        controller.set_projection(orthographic);
+    }
+}
+
+/// Turn the camera to a standard view
+///
+/// `view` indexes [`crate::StandardView::ALL`]: 0 top, 1 bottom, 2 front,
+/// 3 back, 4 left, 5 right, 6 iso. Other values are ignored.
+///
+/// # Safety
+/// - `bevy_app` must be a valid pointer returned by `create_bevy_app`
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn camera_set_standard_view(bevy_app: *mut BevyApp, view: u32) {
+    if bevy_app.is_null() {
+        return;
+    }
+    let Some(&view) = crate::StandardView::ALL.get(view as usize) else {
+        return;
+    };
+
+    let app = &mut (*bevy_app).app;
+
+    if let Some(mut controller) = app
+        .world_mut()
+        .get_resource_mut::<crate::CameraController>()
+    {
+        controller.set_standard_view(view);
+    }
+}
+
+/// Play a camera path
+///
+/// `path_json` is a [`crate::CameraPathStorage`], as exported by the scene.
+/// Returns false if it does not parse.
+///
+/// # Safety
+/// - `bevy_app` must be a valid pointer returned by `create_bevy_app`
+/// - `path_json` must be a valid null-terminated C string
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn camera_play_path(
+    bevy_app: *mut BevyApp,
+    path_json: *const std::ffi::c_char,
+    looping: bool,
+) -> bool {
+    if bevy_app.is_null() || path_json.is_null() {
+        return false;
+    }
+
+    let Ok(json_str) = std::ffi::CStr::from_ptr(path_json).to_str() else {
+        return false;
+    };
+    let path: crate::CameraPathStorage = match serde_json::from_str(json_str) {
+        Ok(path) => path,
+        Err(e) => {
+            eprintln!("Failed to parse camera path JSON: {}", e);
+            return false;
+        }
+    };
+
+    let app = &mut (*bevy_app).app;
+
+    if let Some(mut player) = app
+        .world_mut()
+        .get_resource_mut::<crate::CameraPathPlayer>()
+    {
+        player.play(path.keyframes, looping);
+    }
+    true
+}
+
+/// Stop the camera path being played
+///
+/// # Safety
+/// - `bevy_app` must be a valid pointer returned by `create_bevy_app`
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn camera_stop_path(bevy_app: *mut BevyApp) {
+    if bevy_app.is_null() {
+        return;
+    }
+
+    let app = &mut (*bevy_app).app;
+
+    if let Some(mut player) = app
+        .world_mut()
+        .get_resource_mut::<crate::CameraPathPlayer>()
+    {
+        player.stop();
+    }
+}
+
 /// Focus camera on a specific entity
 ///
 /// # Safety
@@ -458,5 +829,29 @@ pub unsafe extern "C" fn set_theme(bevy_app: *mut BevyApp, dark: bool) {
     }
 }
 
+/// Set the shadow and ambient occlusion presets: 0 off, 1 low, 2 high
+///
+/// Ambient occlusion stays off on GPUs that can't run it.
+///
+/// # Safety
+/// - `bevy_app` must be a valid pointer returned by `create_bevy_app`
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn set_graphics_quality(
+    bevy_app: *mut BevyApp,
+    shadows: u32,
+    ambient_occlusion: u32,
+) {
+    if bevy_app.is_null() {
+        return;
+    }
+
+    let app = &mut (*bevy_app).app;
+
+    if let Some(mut settings) = app.world_mut().get_resource_mut::<ViewerSettings>() {
+        settings.shadows = crate::GraphicsQuality::from_index(shadows);
+        settings.ambient_occlusion = crate::GraphicsQuality::from_index(ambient_occlusion);
+    }
+}
+
 use bevy::ecs::message::Messages;
 use bevy::input::touch::{TouchInput, TouchPhase};
@@ -0,0 +1,379 @@
+//! Selection highlight - outlines around selected and hovered entities
+//!
+//! Highlights leave the model colours alone, so they work with every
+//! colouring mode and on translucent batches alike. The triangles of the
+//! selected entities, and of the hovered one, are copied out of the batches
+//! into small mask meshes on a render layer of their own. A mask camera
+//! follows the main camera and renders them into an offscreen image: red
+//! for the selection, green for the hover. A full-screen pass at the end of
+//! the main view then draws an outline wherever a pixel lies just outside a
+//! masked shape, so highlights show through whatever stands in front.
+
+use crate::camera::{CameraInputSet, MainCamera};
+use crate::mesh::{BatchMaterials, BatchVisibility};
+use crate::picking::SelectionState;
+use crate::section::ClippedMaterial;
+use crate::{IfcSceneData, ViewerSettings};
+use bevy::camera::visibility::{NoFrustumCulling, RenderLayers};
+use bevy::camera::{RenderTarget, Viewport};
+use bevy::core_pipeline::tonemapping::{DebandDither, Tonemapping};
+use bevy::mesh::MeshVertexBufferLayoutRef;
+use bevy::pbr::{MaterialPipeline, MaterialPipelineKey};
+use bevy::prelude::*;
+use bevy::render::render_resource::{
+    AsBindGroup, CompareFunction, Extent3d, RenderPipelineDescriptor, SpecializedMeshPipelineError,
+    TextureFormat,
+};
+use bevy::render::view::Msaa;
+use bevy::shader::ShaderRef;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+/// Render layer of the mask meshes, seen only by the mask camera
+pub const HIGHLIGHT_MASK_LAYER: usize = 3;
+/// Render layer of the outline pass, seen only by the main camera
+pub const HIGHLIGHT_LAYER: usize = 4;
+/// Mask colour of selected entities
+pub const SELECTED_MASK_COLOR: Color = Color::srgb(1.0, 0.0, 0.0);
+/// Mask colour of the hovered entity
+pub const HOVERED_MASK_COLOR: Color = Color::srgb(0.0, 1.0, 0.0);
+/// Outline width in physical pixels
+const OUTLINE_WIDTH_PX: f32 = 2.0;
+/// Opacity of the tint over selected entities
+const SELECTED_TINT_ALPHA: f32 = 0.12;
+
+const SHADER_PATH: &str = "embedded://ifc_lite_bevy/highlight.wgsl";
+
+/// Selection highlight plugin
+pub struct HighlightPlugin;
+
+impl Plugin for HighlightPlugin {
+    fn build(&self, app: &mut App) {
+        bevy::asset::embedded_asset!(app, "highlight.wgsl");
+        app.add_plugins(MaterialPlugin::<OutlineMaterial>::default())
+            .add_systems(Startup, setup_highlight)
+            .add_systems(
+                Update,
+                (
+                    show_outline_layer_system,
+                    update_highlight_masks_system,
+                    sync_mask_camera_system,
+                )
+                    .chain()
+                    .after(CameraInputSet),
+            );
+    }
+}
+
+/// Material drawing a mask mesh in one flat colour
+///
+/// Clipped like the batches, so cut-away parts of a selection are not
+/// outlined.
+pub fn mask_material(color: Color) -> StandardMaterial {
+    StandardMaterial {
+        base_color: color,
+        unlit: true,
+        double_sided: true,
+        cull_mode: None,
+        ..default()
+    }
+}
+
+/// What a mask mesh marks
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HighlightKind {
+    Selected,
+    Hovered,
+}
+
+/// Highlighted entities of one batch, as drawn into the mask
+#[derive(Component)]
+pub struct HighlightMask {
+    pub kind: HighlightKind,
+}
+
+/// Marker component for the camera rendering the highlight mask
+#[derive(Component)]
+pub struct HighlightMaskCamera;
+
+/// Marker component for the full-screen outline quad
+#[derive(Component)]
+struct OutlineQuad;
+
+/// Target of the mask camera
+#[derive(Resource)]
+struct HighlightMaskImage(Handle<Image>);
+
+/// Outline pass over the main view
+#[derive(Asset, TypePath, AsBindGroup, Clone)]
+pub struct OutlineMaterial {
+    #[texture(0)]
+    pub mask: Handle<Image>,
+    #[uniform(1)]
+    pub selected_color: Vec4,
+    #[uniform(1)]
+    pub hovered_color: Vec4,
+    /// x: outline width in pixels, y: tint alpha inside the selection
+    #[uniform(1)]
+    pub params: Vec4,
+}
+
+impl Material for OutlineMaterial {
+    fn vertex_shader() -> ShaderRef {
+        SHADER_PATH.into()
+    }
+
+    fn fragment_shader() -> ShaderRef {
+        SHADER_PATH.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        AlphaMode::Blend
+    }
+
+    /// Sorts the quad after every other translucent mesh
+    fn depth_bias(&self) -> f32 {
+        1e9
+    }
+
+    fn enable_prepass() -> bool {
+        false
+    }
+
+    fn enable_shadows() -> bool {
+        false
+    }
+
+    /// Positions only, and no depth test: the outline lies over everything
+    fn specialize(
+        _pipeline: &MaterialPipeline,
+        descriptor: &mut RenderPipelineDescriptor,
+        layout: &MeshVertexBufferLayoutRef,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        descriptor.vertex.buffers = vec![layout
+            .0
+            .get_layout(&[Mesh::ATTRIBUTE_POSITION.at_shader_location(0)])?];
+        descriptor.primitive.cull_mode = None;
+        if let Some(depth_stencil) = descriptor.depth_stencil.as_mut() {
+            depth_stencil.depth_compare = CompareFunction::Always;
+            depth_stencil.depth_write_enabled = false;
+        }
+        Ok(())
+    }
+}
+
+fn setup_highlight(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<OutlineMaterial>>,
+) {
+    let mut image = Image::new_target_texture(1, 1, TextureFormat::Rgba8Unorm, None);
+    // Rendered on the GPU only
+    image.data = None;
+    let mask = images.add(image);
+    commands.insert_resource(HighlightMaskImage(mask.clone()));
+
+    commands.spawn((
+        Camera3d::default(),
+        Camera {
+            // Before the main view reads the mask
+            order: -1,
+            is_active: false,
+            clear_color: ClearColorConfig::Custom(Color::NONE),
+            ..default()
+        },
+        RenderTarget::Image(mask.clone().into()),
+        Msaa::Off,
+        Tonemapping::None,
+        DebandDither::Disabled,
+        RenderLayers::layer(HIGHLIGHT_MASK_LAYER),
+        HighlightMaskCamera,
+    ));
+
+    let color = |color: Color| LinearRgba::from(color).to_vec4();
+    commands.spawn((
+        Mesh3d(meshes.add(Rectangle::new(2.0, 2.0))),
+        MeshMaterial3d(materials.add(OutlineMaterial {
+            mask,
+            selected_color: color(Color::srgb(0.2, 0.6, 1.0)),
+            hovered_color: color(Color::srgba(0.75, 0.9, 1.0, 0.8)),
+            params: Vec4::new(OUTLINE_WIDTH_PX, SELECTED_TINT_ALPHA, 0.0, 0.0),
+        })),
+        Transform::default(),
+        Visibility::Hidden,
+        NoFrustumCulling,
+        RenderLayers::layer(HIGHLIGHT_LAYER),
+        OutlineQuad,
+    ));
+}
+
+/// Let the main camera see the outline pass on top of the model
+fn show_outline_layer_system(
+    mut commands: Commands,
+    cameras: Query<Entity, (With<MainCamera>, Without<RenderLayers>)>,
+) {
+    for camera in cameras.iter() {
+        commands
+            .entity(camera)
+            .insert(RenderLayers::from_layers(&[0, HIGHLIGHT_LAYER]));
+    }
+}
+
+/// Rebuild the mask meshes when the selection, the hover, the visibility
+/// filters or the batches change (appear, or move in an exploded view)
+#[allow(clippy::too_many_arguments)]
+fn update_highlight_masks_system(
+    mut commands: Commands,
+    selection: Res<SelectionState>,
+    settings: Res<ViewerSettings>,
+    scene_data: Res<IfcSceneData>,
+    batch_materials: Res<BatchMaterials>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    batches: Query<&BatchVisibility>,
+    changed_batches: Query<(), Changed<BatchVisibility>>,
+    masks: Query<Entity, With<HighlightMask>>,
+) {
+    if !selection.is_changed()
+        && !settings.is_changed()
+        && !scene_data.is_changed()
+        && changed_batches.is_empty()
+    {
+        return;
+    }
+    for mask in masks.iter() {
+        commands.entity(mask).despawn();
+    }
+
+    // Storeys are only needed while filtering by them
+    let storeys: FxHashMap<u64, Vec<&str>> = if settings.filters_by_storey() {
+        scene_data
+            .entities
+            .iter()
+            .map(|e| {
+                let names = e
+                    .storey
+                    .iter()
+                    .chain(&e.referenced_storeys)
+                    .map(|s| &**s)
+                    .collect();
+                (e.id, names)
+            })
+            .collect()
+    } else {
+        FxHashMap::default()
+    };
+    let is_visible =
+        |id: u64| settings.is_entity_visible(id, storeys.get(&id).map_or(&[][..], Vec::as_slice));
+
+    // A hovered entity that is also selected keeps the selection outline
+    let hovered: FxHashSet<u64> = selection
+        .hovered
+        .filter(|id| !selection.selected.contains(id))
+        .into_iter()
+        .collect();
+    let kinds = [
+        (
+            HighlightKind::Selected,
+            &selection.selected,
+            &batch_materials.selected_mask,
+        ),
+        (
+            HighlightKind::Hovered,
+            &hovered,
+            &batch_materials.hovered_mask,
+        ),
+    ];
+    for (kind, ids, material) in kinds {
+        if ids.is_empty() {
+            continue;
+        }
+        for batch in batches.iter() {
+            // The full-detail level
+            let Some(full) = batch.meshes.first() else {
+                continue;
+            };
+            let Some(mesh) = full.extract(&meshes, |id| ids.contains(&id) && is_visible(id)) else {
+                continue;
+            };
+            commands.spawn((
+                Mesh3d(meshes.add(mesh)),
+                MeshMaterial3d::<ClippedMaterial>(material.clone()),
+                Transform::default(),
+                RenderLayers::layer(HIGHLIGHT_MASK_LAYER),
+                HighlightMask { kind },
+            ));
+        }
+    }
+}
+
+/// Keep the mask camera on the main camera's view and target size, and run
+/// it and the outline pass only while something is highlighted
+#[allow(clippy::type_complexity)]
+fn sync_mask_camera_system(
+    selection: Res<SelectionState>,
+    mask_image: Res<HighlightMaskImage>,
+    mut images: ResMut<Assets<Image>>,
+    main_camera: Query<
+        (&Camera, &Transform, Ref<Projection>),
+        (With<MainCamera>, Without<HighlightMaskCamera>),
+    >,
+    mut mask_camera: Query<
+        (&mut Camera, &mut Transform, &mut Projection),
+        With<HighlightMaskCamera>,
+    >,
+    mut quad: Query<&mut Visibility, With<OutlineQuad>>,
+) {
+    let (
+        Ok((main, main_transform, main_projection)),
+        Ok((mut camera, mut transform, mut projection)),
+    ) = (main_camera.single(), mask_camera.single_mut())
+    else {
+        return;
+    };
+    let size = main
+        .physical_target_size()
+        .filter(|size| size.x > 0 && size.y > 0);
+    let active = size.is_some() && (!selection.selected.is_empty() || selection.hovered.is_some());
+    let activated = active && !camera.is_active;
+    if camera.is_active != active {
+        camera.is_active = active;
+    }
+    if let Ok(mut visibility) = quad.single_mut() {
+        visibility.set_if_neq(if active {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        });
+    }
+    let Some(size) = size.filter(|_| active) else {
+        return;
+    };
+
+    // One mask pixel per pixel of the main view
+    let extent = Extent3d {
+        width: size.x,
+        height: size.y,
+        depth_or_array_layers: 1,
+    };
+    if images
+        .get(&mask_image.0)
+        .is_some_and(|image| image.texture_descriptor.size != extent)
+    {
+        if let Some(image) = images.get_mut(&mask_image.0) {
+            image.resize(extent);
+        }
+    }
+    // Mask pixels line up with the main view's, also when it is one half
+    // of a split view
+    let placement = |v: &Viewport| (v.physical_position, v.physical_size);
+    if camera.viewport.as_ref().map(placement) != main.viewport.as_ref().map(placement) {
+        camera.viewport = main.viewport.clone();
+    }
+    if *transform != *main_transform {
+        *transform = *main_transform;
+    }
+    if main_projection.is_changed() || activated {
+        *projection = (*main_projection).clone();
+    }
+}
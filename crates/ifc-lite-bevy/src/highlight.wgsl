@@ -0,0 +1,66 @@
+// Selection outline over the main view
+//
+// A full-screen quad drawn last in the main pass. The highlight mask holds
+// the selected entities in red and the hovered one in green, rendered from
+// the main camera's point of view; pixels just outside a masked shape get
+// the outline colour and pixels inside a light tint of it.
+
+struct Outline {
+    selected_color: vec4<f32>,
+    hovered_color: vec4<f32>,
+    // x: outline width in pixels, y: tint alpha inside the selection
+    params: vec4<f32>,
+}
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(0) var mask: texture_2d<f32>;
+@group(#{MATERIAL_BIND_GROUP}) @binding(1) var<uniform> outline: Outline;
+
+struct Vertex {
+    @location(0) position: vec3<f32>,
+}
+
+struct VertexOutput {
+    @builtin(position) position: vec4<f32>,
+}
+
+@vertex
+fn vertex(vertex: Vertex) -> VertexOutput {
+    // The quad spans -1..1, straight to clip space
+    var out: VertexOutput;
+    out.position = vec4(vertex.position.xy, 0.0, 1.0);
+    return out;
+}
+
+fn mask_at(pixel: vec2<i32>) -> vec2<f32> {
+    let size = vec2<i32>(textureDimensions(mask));
+    return textureLoad(mask, clamp(pixel, vec2(0), size - 1), 0).rg;
+}
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    let pixel = vec2<i32>(in.position.xy);
+    let center = mask_at(pixel);
+    let radius = i32(outline.params.x);
+
+    // Strongest mask value in a disc around the pixel
+    var around = vec2(0.0);
+    for (var dy = -radius; dy <= radius; dy++) {
+        for (var dx = -radius; dx <= radius; dx++) {
+            if dx * dx + dy * dy <= radius * radius {
+                around = max(around, mask_at(pixel + vec2(dx, dy)));
+            }
+        }
+    }
+
+    if around.x > 0.5 && center.x < 0.5 {
+        return outline.selected_color;
+    }
+    if around.y > 0.5 && center.y < 0.5 {
+        return outline.hovered_color;
+    }
+    if center.x > 0.5 {
+        return vec4(outline.selected_color.rgb, outline.params.y);
+    }
+    discard;
+    return vec4(0.0);
+}
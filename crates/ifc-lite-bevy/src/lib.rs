@@ -8,12 +8,29 @@
 // Allow unexpected_cfgs from objc crate's msg_send! macro used in native_view
 #![allow(unexpected_cfgs)]
 
+pub mod annotation;
 pub mod camera;
+pub mod camera_path;
+pub mod dedup;
+pub mod explode;
+pub mod highlight;
 pub mod loader;
+pub mod lod;
+pub mod measure;
 pub mod mesh;
+pub mod minimap;
+pub mod overlay;
+pub mod palette;
 pub mod picking;
+pub mod plan;
 pub mod section;
+pub mod space;
+pub mod split;
+pub mod stats;
 pub mod storage;
+pub mod thumbnail;
+pub mod view_cube;
+pub mod walk;
 
 #[cfg(feature = "bevy-ui")]
 pub mod ui;
@@ -25,9 +42,11 @@ pub mod native_view;
 pub mod ffi;
 
 use bevy::prelude::*;
+use ifc_lite_core::StringInterner;
 use rustc_hash::FxHashSet;
 use serde::{Deserialize, Serialize};
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 
 /// Global debug mode flag (set from URL parameter ?debug=1)
 static DEBUG_MODE: AtomicBool = AtomicBool::new(false);
@@ -61,12 +80,34 @@ fn init_debug_from_url() {
 }
 
 // Re-exports
-pub use camera::{CameraController, CameraMode, CameraPlugin};
+pub use annotation::{AnnotationPlugin, AnnotationState};
+pub use camera::{CameraController, CameraMode, CameraPlugin, StandardView};
+pub use camera_path::{CameraPathPlayer, CameraPathPlugin};
+pub use dedup::{dedup_meshes, DedupStats};
+pub use explode::{ExplodeMode, ExplodePlugin, ExplodeState};
+pub use highlight::{HighlightMaskCamera, HighlightPlugin};
 pub use loader::{LoadIfcFileEvent, LoaderPlugin, OpenFileDialogRequest};
-pub use mesh::{AutoFitState, IfcEntity, IfcMesh, IfcMeshSerialized, MeshGeometry, MeshPlugin};
-pub use picking::{PickingPlugin, SelectionState};
-pub use section::{SectionPlane, SectionPlanePlugin};
+pub use lod::{LodPlugin, LodSettings};
+pub use measure::MeasurementPlugin;
+pub use mesh::{
+    AutoFitState, IfcEntity, IfcMesh, IfcMeshSerialized, MeshGeometry, MeshPlugin, XrayGhost,
+};
+pub use minimap::{MinimapCamera, MinimapPlugin};
+pub use overlay::OverlayPlugin;
+pub use palette::{ColorBy, EntityColorMapping, LegendEntry, PalettePlugin};
+pub use picking::{BoxSelectMode, BoxSelection, PickingPlugin, SelectionState};
+pub use plan::{PlanOutlines, PlanView, PlanViewPlugin};
+pub use section::{
+    ClippedMaterial, SectionAxis, SectionBox, SectionClip, SectionClipping, SectionPlane,
+    SectionPlanePlugin,
+};
+pub use space::SpacePlugin;
+pub use split::{SplitCamera, SplitView, SplitViewPlugin};
+pub use stats::StatsPlugin;
 pub use storage::*;
+pub use thumbnail::{ThumbnailCamera, ThumbnailPlugin, ThumbnailQueue};
+pub use view_cube::{ViewCubeCamera, ViewCubePlugin};
+pub use walk::{WalkPlugin, WalkSettings};
 
 #[cfg(feature = "bevy-ui")]
 pub use ui::{IfcUiPlugin, UiState};
@@ -84,10 +125,29 @@ impl Plugin for IfcViewerPlugin {
             .init_resource::<IfcTimestamp>()
             .add_plugins((
                 CameraPlugin,
+                CameraPathPlugin,
+                // Registers the batch material MeshPlugin uses
+                SectionPlanePlugin,
                 MeshPlugin,
+                HighlightPlugin,
                 PickingPlugin,
-                SectionPlanePlugin,
                 LoaderPlugin,
+                OverlayPlugin,
+                MinimapPlugin,
+                ViewCubePlugin,
+                LodPlugin,
+                MeasurementPlugin,
+                PalettePlugin,
+                PlanViewPlugin,
+            ))
+            .add_plugins((
+                SpacePlugin,
+                SplitViewPlugin,
+                AnnotationPlugin,
+                ExplodePlugin,
+                WalkPlugin,
+                StatsPlugin,
+                ThumbnailPlugin,
             ))
             .add_systems(Update, poll_scene_changes);
 
@@ -106,20 +166,122 @@ pub struct IfcSceneData {
     pub entities: Vec<EntityInfo>,
     /// Scene bounds (AABB)
     pub bounds: Option<SceneBounds>,
+    /// True north angle (radians, counter-clockwise from model +Y)
+    pub true_north: Option<f32>,
+    /// IfcGrid axes, in IFC file coordinates
+    pub grid_axes: Vec<GridAxisStorage>,
+    /// Crossings of the grid axes, in IFC file coordinates
+    pub grid_intersections: Vec<GridIntersectionStorage>,
+    /// Offset (metres, IFC Z-up) taken off the geometry of models placed far
+    /// from the file origin; points exchanged with the UI are in file
+    /// coordinates and converted with it
+    pub origin_shift: [f64; 3],
     /// Data timestamp for change detection
     pub timestamp: u64,
     /// Whether scene needs rebuild
     pub dirty: bool,
+    /// First mesh appended by a streamed chunk since the last batching;
+    /// only `meshes[from..]` need new batches
+    pub appended_from: Option<usize>,
+    /// Geometry stream the meshes were appended from
+    pub stream_id: Option<String>,
+}
+
+impl IfcSceneData {
+    /// Append a streamed chunk without invalidating existing batches
+    pub fn append_meshes(&mut self, meshes: Vec<IfcMesh>) {
+        if meshes.is_empty() {
+            return;
+        }
+        if !self.dirty && self.appended_from.is_none() {
+            self.appended_from = Some(self.meshes.len());
+        }
+        self.meshes.extend(meshes);
+    }
+
+    /// IFC point in file coordinates to the shifted world
+    pub fn file_to_world(&self, point: [f64; 3]) -> Vec3 {
+        let [sx, sy, sz] = self.origin_shift;
+        measure::ifc_to_world([
+            (point[0] - sx) as f32,
+            (point[1] - sy) as f32,
+            (point[2] - sz) as f32,
+        ])
+    }
+
+    /// World position to IFC file coordinates
+    pub fn world_to_file(&self, point: Vec3) -> [f64; 3] {
+        let [x, y, z] = measure::world_to_ifc(point);
+        let [sx, sy, sz] = self.origin_shift;
+        [x as f64 + sx, y as f64 + sy, z as f64 + sz]
+    }
+
+    /// Elevation (IFC Z) in file coordinates to the shifted world
+    pub fn world_elevation(&self, elevation: f32) -> f32 {
+        (elevation as f64 - self.origin_shift[2]) as f32
+    }
+
+    /// Shift in world (Y-up) axes
+    fn world_shift(&self) -> [f64; 3] {
+        let [x, y, z] = self.origin_shift;
+        [x, z, -y]
+    }
+
+    /// Camera from the UI, targeting a point in file coordinates, in the
+    /// shifted world
+    pub fn camera_to_world(&self, camera: &CameraStorage) -> CameraStorage {
+        let shift = self.world_shift();
+        CameraStorage {
+            target: std::array::from_fn(|i| camera.target[i] - shift[i]),
+            ..camera.clone()
+        }
+    }
+
+    /// Camera in the shifted world, targeting a point in file coordinates
+    pub fn camera_to_file(&self, camera: &CameraStorage) -> CameraStorage {
+        let shift = self.world_shift();
+        CameraStorage {
+            target: std::array::from_fn(|i| camera.target[i] + shift[i]),
+            ..camera.clone()
+        }
+    }
 }
 
 /// Entity metadata
+///
+/// `entity_type` and `storey` repeat across many entities and are shared
+/// via [`StringInterner`] handles.
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct EntityInfo {
     pub id: u64,
-    pub entity_type: String,
+    pub entity_type: Arc<str>,
     pub name: Option<String>,
-    pub storey: Option<String>,
+    pub storey: Option<Arc<str>>,
     pub storey_elevation: Option<f32>,
+    /// Further storeys that reference the entity without containing it
+    #[serde(default)]
+    pub referenced_storeys: Vec<Arc<str>>,
+    /// PredefinedType enum (e.g. "ROOF" for a roof slab)
+    #[serde(default)]
+    pub predefined_type: Option<String>,
+    /// ObjectType string
+    #[serde(default)]
+    pub object_type: Option<String>,
+}
+
+/// Re-point entity type and storey names at shared interned strings
+/// Deserialized entities each own their strings; call this after loading.
+pub fn intern_entity_strings(entities: &mut [EntityInfo]) {
+    let mut interner = StringInterner::new();
+    for entity in entities.iter_mut() {
+        entity.entity_type = interner.intern(&entity.entity_type);
+        if let Some(storey) = entity.storey.as_mut() {
+            *storey = interner.intern(storey);
+        }
+        for storey in entity.referenced_storeys.iter_mut() {
+            *storey = interner.intern(storey);
+        }
+    }
 }
 
 /// Axis-aligned bounding box for scene
@@ -152,12 +314,114 @@ pub struct ViewerSettings {
     pub show_grid: bool,
     /// Show axes helper
     pub show_axes: bool,
+    /// Show true north arrow
+    pub show_north_arrow: bool,
+    /// Publish a scale bar in plan view
+    pub show_scale_bar: bool,
+    /// Top-down overview in the corner of the viewport
+    pub show_minimap: bool,
+    /// Orientation cube in the corner of the viewport
+    pub show_view_cube: bool,
+    /// IfcGrid axes with their tags
+    pub show_grid_axes: bool,
+    /// Publish frame statistics for the debug overlay
+    pub show_stats: bool,
     /// Hidden entity IDs
     pub hidden_entities: FxHashSet<u64>,
     /// Isolated entity IDs (if Some, only show these)
     pub isolated_entities: Option<FxHashSet<u64>>,
     /// Active storey filter
     pub storey_filter: Option<String>,
+    /// Storeys hidden above a storey section
+    pub hidden_storeys: Vec<String>,
+    /// Space mode: IfcSpace volumes are hidden unless it is on
+    pub show_spaces: bool,
+    /// Storeys whose spaces stay hidden in space mode
+    pub hidden_space_storeys: Vec<String>,
+    /// Spaces hidden by the space mode, kept up to date by
+    /// [`space::SpacePlugin`]
+    pub hidden_spaces: FxHashSet<u64>,
+    /// X-ray mode: everything outside the focus set is drawn translucent
+    pub xray: Option<XrayFocus>,
+    /// Edge length of the spatial chunks batches are split into, in world
+    /// units (0 = one batch per scene, no frustum culling)
+    pub chunk_size: f32,
+    /// GPU occlusion culling of chunks hidden behind others (experimental,
+    /// needs WebGPU or a native backend)
+    pub occlusion_culling: bool,
+    /// MSAA sample count of the main camera (1 = off)
+    pub msaa_samples: u32,
+    /// Shadows cast by the key light
+    pub shadows: GraphicsQuality,
+    /// Screen-space ambient occlusion, where the GPU supports it
+    pub ambient_occlusion: GraphicsQuality,
+    /// Smooth-shade meshes that come without normals (faceted breps, pipes)
+    pub smooth_normals: bool,
+    /// Edges sharper than this stay hard when smoothing, in degrees
+    pub crease_angle: f32,
+    /// Weld vertices and reorder indices for the GPU while batching
+    pub optimize_meshes: bool,
+    /// Vertices closer than this are welded, in metres
+    pub weld_tolerance: f32,
+    /// Orbit about the model point under the cursor where one is hit
+    pub orbit_around_cursor: bool,
+    /// Zoom toward the cursor rather than the view centre
+    pub zoom_to_cursor: bool,
+}
+
+impl ViewerSettings {
+    /// Whether an entity passes the hide, isolate and storey filters
+    ///
+    /// `storeys` lists every storey containing or referencing the entity.
+    /// It is hidden above a storey section only when all of them are.
+    pub fn is_entity_visible(&self, id: u64, storeys: &[&str]) -> bool {
+        if self.hidden_entities.contains(&id) || self.hidden_spaces.contains(&id) {
+            return false;
+        }
+        if let Some(isolated) = &self.isolated_entities {
+            if !isolated.contains(&id) {
+                return false;
+            }
+        }
+        if !storeys.is_empty()
+            && storeys
+                .iter()
+                .all(|storey| self.hidden_storeys.iter().any(|hidden| hidden == storey))
+        {
+            return false;
+        }
+        match &self.storey_filter {
+            Some(filter) => storeys.contains(&filter.as_str()),
+            None => true,
+        }
+    }
+
+    /// Whether [`Self::is_entity_visible`] needs the storeys of entities
+    pub fn filters_by_storey(&self) -> bool {
+        self.storey_filter.is_some() || !self.hidden_storeys.is_empty()
+    }
+
+    /// Crease angle in radians if normals get smoothed
+    pub fn smoothing_crease_angle(&self) -> Option<f32> {
+        self.smooth_normals.then(|| self.crease_angle.to_radians())
+    }
+
+    /// Mesh optimization options if meshes get optimized
+    pub fn mesh_optimize_options(&self) -> Option<ifc_lite_geometry::OptimizeOptions> {
+        self.optimize_meshes
+            .then(|| ifc_lite_geometry::OptimizeOptions {
+                weld_tolerance: Some(self.weld_tolerance as f64),
+                ..Default::default()
+            })
+    }
+
+    /// Whether any filter is active
+    pub fn has_visibility_filter(&self) -> bool {
+        !self.hidden_entities.is_empty()
+            || !self.hidden_spaces.is_empty()
+            || self.isolated_entities.is_some()
+            || self.filters_by_storey()
+    }
 }
 
 impl Default for ViewerSettings {
@@ -166,9 +430,79 @@ impl Default for ViewerSettings {
             theme: Theme::Dark,
             show_grid: true,
             show_axes: true,
+            show_north_arrow: true,
+            show_scale_bar: true,
+            show_minimap: true,
+            show_view_cube: true,
+            show_grid_axes: true,
+            show_stats: false,
             hidden_entities: FxHashSet::default(),
             isolated_entities: None,
             storey_filter: None,
+            hidden_storeys: Vec::new(),
+            show_spaces: false,
+            hidden_space_storeys: Vec::new(),
+            hidden_spaces: FxHashSet::default(),
+            xray: None,
+            chunk_size: 16.0,
+            occlusion_culling: false,
+            msaa_samples: 4,
+            shadows: GraphicsQuality::Off,
+            ambient_occlusion: GraphicsQuality::Off,
+            smooth_normals: true,
+            crease_angle: 30.0,
+            optimize_meshes: true,
+            weld_tolerance: ifc_lite_geometry::DEFAULT_WELD_TOLERANCE as f32,
+            orbit_around_cursor: true,
+            zoom_to_cursor: true,
+        }
+    }
+}
+
+/// Entities that stay opaque in x-ray mode
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum XrayFocus {
+    /// The current selection
+    Selection,
+    /// Every entity of this IFC type (e.g. "IfcWall")
+    Type(String),
+}
+
+impl XrayFocus {
+    /// Focus set for the current scene and selection
+    pub fn entities(&self, entities: &[EntityInfo], selection: &SelectionState) -> FxHashSet<u64> {
+        match self {
+            XrayFocus::Selection => selection.selected.clone(),
+            XrayFocus::Type(ifc_type) => entities
+                .iter()
+                .filter(|e| e.entity_type.eq_ignore_ascii_case(ifc_type))
+                .map(|e| e.id)
+                .collect(),
+        }
+    }
+}
+
+/// Quality preset of an optional rendering effect
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphicsQuality {
+    #[default]
+    Off,
+    Low,
+    High,
+}
+
+impl GraphicsQuality {
+    pub fn is_enabled(&self) -> bool {
+        *self != GraphicsQuality::Off
+    }
+
+    /// Preset from the number used over FFI: 0 off, 1 low, 2 high
+    pub fn from_index(index: u32) -> Self {
+        match index {
+            0 => GraphicsQuality::Off,
+            1 => GraphicsQuality::Low,
+            _ => GraphicsQuality::High,
         }
     }
 }
@@ -195,6 +529,22 @@ impl Theme {
             Theme::Dark => Color::srgba(0.4, 0.4, 0.4, 0.3),
         }
     }
+
+    /// Background of the mini-map, set off from the main view
+    pub fn minimap_color(&self) -> Color {
+        match self {
+            Theme::Light => Color::srgb(0.85, 0.85, 0.85),
+            Theme::Dark => Color::srgb(0.2, 0.2, 0.2),
+        }
+    }
+
+    /// Cut outlines of the storey plan
+    pub fn plan_line_color(&self) -> Color {
+        match self {
+            Theme::Light => Color::srgb(0.05, 0.05, 0.05),
+            Theme::Dark => Color::srgb(0.95, 0.95, 0.95),
+        }
+    }
 }
 
 /// Timestamp for detecting localStorage changes (WASM)
@@ -211,6 +561,25 @@ pub fn poll_scene_changes(
 ) {
     #[cfg(target_arch = "wasm32")]
     {
+        // Streamed chunks: a new stream clears the scene, then each chunk
+        // is appended while parsing continues
+        let stream_id = storage::get_geometry_stream_id();
+        if stream_id.is_some() && stream_id != scene_data.stream_id {
+            log(&format!("[Bevy] Geometry stream started: {:?}", stream_id));
+            scene_data.meshes.clear();
+            scene_data.bounds = None;
+            scene_data.dirty = true;
+            scene_data.appended_from = None;
+            scene_data.stream_id = stream_id.clone();
+            auto_fit.has_fit = false;
+            auto_fit.streaming = true;
+        }
+        let chunk = storage::take_geometry_chunks();
+        if !chunk.is_empty() {
+            log(&format!("[Bevy] Appending {} streamed meshes", chunk.len()));
+            scene_data.append_meshes(chunk);
+        }
+
         if let Some(new_timestamp) = storage::get_timestamp() {
             if new_timestamp != last_timestamp.0 {
                 log(&format!(
@@ -218,11 +587,22 @@ pub fn poll_scene_changes(
                     last_timestamp.0, new_timestamp
                 ));
 
+                // The final geometry of a stream is already on screen
+                let streamed = stream_id.is_some() && stream_id == scene_data.stream_id;
+                auto_fit.streaming = false;
+
                 // Load geometry from storage
-                if let Some(geometry) = storage::load_geometry() {
+                if streamed {
+                    log(&format!(
+                        "[Bevy] Stream complete: {} meshes",
+                        scene_data.meshes.len()
+                    ));
+                } else if let Some(mut geometry) = storage::load_geometry() {
                     log(&format!("[Bevy] Loaded {} meshes", geometry.len()));
+                    log(&format!("[Bevy] Dedup: {}", dedup_meshes(&mut geometry)));
                     scene_data.meshes = geometry;
                     scene_data.dirty = true;
+                    scene_data.stream_id = None;
                     // Reset auto-fit state to trigger camera fit for new scene
                     auto_fit.has_fit = false;
                 }
@@ -233,6 +613,13 @@ pub fn poll_scene_changes(
                     scene_data.entities = entities;
                 }
 
+                if let Some(info) = storage::load_scene_info() {
+                    scene_data.true_north = info.true_north;
+                    scene_data.grid_axes = info.grid_axes;
+                    scene_data.grid_intersections = info.grid_intersections;
+                    scene_data.origin_shift = info.origin_shift;
+                }
+
                 // Load selection state
                 if let Some(selection) = storage::load_selection() {
                     // Selection is handled by PickingPlugin
@@ -243,6 +630,7 @@ pub fn poll_scene_changes(
                     settings.hidden_entities = visibility.hidden.into_iter().collect();
                     settings.isolated_entities =
                         visibility.isolated.map(|v| v.into_iter().collect());
+                    settings.storey_filter = visibility.storey;
                 }
 
                 last_timestamp.0 = new_timestamp;
@@ -285,9 +673,16 @@ pub fn run_on_canvas(canvas_selector: &str) {
     init_debug_from_url();
     log(&format!("[Bevy] Starting on canvas: {}", canvas_selector));
 
-    // Load initial data from localStorage
-    let meshes = storage::load_geometry().unwrap_or_default();
+    // Load initial data from localStorage. A finished stream is included in
+    // the stored geometry; a stream still in progress continues via chunks.
+    let stored = storage::load_geometry();
+    let stream_id = storage::get_geometry_stream_id();
+    if stored.is_some() {
+        storage::take_geometry_chunks();
+    }
+    let meshes = stored.unwrap_or_default();
     let entities = storage::load_entities().unwrap_or_default();
+    let scene_info = storage::load_scene_info().unwrap_or_default();
 
     log(&format!(
         "[Bevy] Initial load - {} meshes, {} entities",
@@ -299,8 +694,14 @@ pub fn run_on_canvas(canvas_selector: &str) {
         meshes,
         entities,
         bounds: None,
+        true_north: scene_info.true_north,
+        grid_axes: scene_info.grid_axes,
+        grid_intersections: scene_info.grid_intersections,
+        origin_shift: scene_info.origin_shift,
         timestamp: 0,
         dirty: true,
+        appended_from: None,
+        stream_id,
     };
 
     let mut app = App::new();
@@ -333,8 +734,16 @@ pub fn run_on_canvas(_canvas_selector: &str) {
 }
 
 /// Run native desktop viewer
+///
+/// State is kept in files under `IFC_LITE_STATE_DIR`, or a directory in the
+/// system temp directory.
 #[cfg(not(target_arch = "wasm32"))]
 pub fn run_native() {
+    let state_dir = std::env::var_os("IFC_LITE_STATE_DIR")
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|| std::env::temp_dir().join("ifc-lite-state"));
+    storage::use_state_dir(state_dir);
+
     App::new()
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
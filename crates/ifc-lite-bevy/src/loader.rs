@@ -1,6 +1,7 @@
 //! IFC file loading - handles file dialog and drag-and-drop
 
-use crate::mesh::IfcMesh;
+use crate::mesh::{IfcMesh, MeshGeometry};
+use crate::storage::{GridAxisStorage, GridIntersectionStorage, SceneInfoStorage};
 use crate::{EntityInfo, IfcSceneData};
 use bevy::prelude::*;
 #[cfg(all(
@@ -10,9 +11,11 @@ use bevy::prelude::*;
 ))]
 use bevy::tasks::IoTaskPool;
 use bevy::tasks::Task;
-use ifc_lite_core::{EntityDecoder, EntityScanner};
-use ifc_lite_geometry::GeometryRouter;
+use ifc_lite_engine::{ElementInfo, SceneBuilder, SceneSink};
+use ifc_lite_geometry::{grid_intersections, ElementGeometry, Point3};
+use rustc_hash::FxHashMap;
 use std::path::PathBuf;
+use std::sync::Arc;
 
 /// Plugin for file loading functionality
 pub struct LoaderPlugin;
@@ -133,7 +136,7 @@ fn handle_load_file_event(
         crate::log_info(&format!("[Loader] Loading file: {:?}", event.path));
 
         match load_ifc_file(&event.path) {
-            Ok((meshes, entities)) => {
+            Ok((meshes, entities, info)) => {
                 let mesh_count = meshes.len();
                 let entity_count = entities.len();
 
@@ -147,6 +150,10 @@ fn handle_load_file_event(
                 scene_data.entities = entities;
                 scene_data.dirty = true;
                 scene_data.bounds = None;
+                scene_data.true_north = info.true_north;
+                scene_data.grid_axes = info.grid_axes;
+                scene_data.grid_intersections = info.grid_intersections;
+                scene_data.origin_shift = info.origin_shift;
 
                 // Reset auto-fit to trigger camera adjustment
                 auto_fit.has_fit = false;
@@ -184,88 +191,125 @@ fn handle_file_drop(
     }
 }
 
-/// Load an IFC file and convert to viewer format
-fn load_ifc_file(
-    path: &std::path::Path,
-) -> Result<(Vec<IfcMesh>, Vec<EntityInfo>), Box<dyn std::error::Error>> {
-    // Read file content
-    let content = std::fs::read_to_string(path)?;
+/// Meshes, entity info and model-wide info of a loaded file
+type LoadedIfc = (Vec<IfcMesh>, Vec<EntityInfo>, SceneInfoStorage);
 
-    // Create decoder and router
-    let mut decoder = EntityDecoder::new(&content);
-    let router = GeometryRouter::with_units(&content, &mut decoder);
-
-    // Collect building elements and their info
-    let mut meshes = Vec::new();
-    let mut entities = Vec::new();
-    let mut scanner = EntityScanner::new(&content);
-
-    // First pass: collect all elements with potential geometry
-    // Use the same comprehensive check as the Yew viewer
-    let mut element_ids: Vec<(u32, String)> = Vec::new();
-
-    while let Some((id, type_name, _, _)) = scanner.next_entity() {
-        // Use ifc_lite_core's comprehensive geometry check
-        if ifc_lite_core::has_geometry_by_name(type_name) {
-            // Skip Unknown types - we can't properly process them
-            let ifc_type = ifc_lite_core::IfcType::from_str(type_name);
-            if !matches!(ifc_type, ifc_lite_core::IfcType::Unknown(_)) {
-                element_ids.push((id, type_name.to_string()));
-            }
-        }
+/// Collects the meshes of a scene build in viewer format
+#[derive(Default)]
+struct MeshCollector {
+    meshes: Vec<IfcMesh>,
+    /// Geometry of the mapped items, shared by their instances
+    shared: FxHashMap<u32, Arc<MeshGeometry>>,
+}
+
+/// The engine's color, except that spaces get a translucent color each,
+/// like in the web viewer
+fn viewer_color(element: &ElementInfo, color: [f32; 4]) -> [f32; 4] {
+    if crate::space::is_space(&element.entity_type) {
+        crate::space::space_color(element.id as u64)
+    } else {
+        color
     }
+}
 
-    crate::log_info(&format!(
-        "[Loader] Found {} building elements",
-        element_ids.len()
-    ));
+impl SceneSink for MeshCollector {
+    fn mesh(&mut self, element: &ElementInfo, mesh: ifc_lite_geometry::Mesh, color: [f32; 4]) {
+        // Takes ownership of the mesh, no cloning
+        self.meshes.push(IfcMesh::from_geometry_mesh(
+            element.id as u64,
+            mesh,
+            viewer_color(element, color),
+            element.entity_type.to_string(),
+            element.name.clone(),
+        ));
+    }
 
-    // Process each element
-    for (id, type_name) in element_ids {
-        let entity = match decoder.decode_by_id(id) {
-            Ok(e) => e,
-            Err(_) => continue,
-        };
+    fn wants_instances(&mut self) -> bool {
+        true
+    }
 
-        // Get entity name (attribute 2 for most building elements)
-        let name = entity.get_string(2).map(|s| s.to_string());
+    fn instanced(&mut self, element: &ElementInfo, geometry: ElementGeometry, color: [f32; 4]) {
+        if !geometry.mesh.is_empty() {
+            self.mesh(element, geometry.mesh, color);
+        }
+        let color = viewer_color(element, color);
+        for instance in geometry.instances {
+            let shared = self.shared.entry(instance.source_id).or_insert_with(|| {
+                Arc::new(MeshGeometry::from_geometry_mesh((*instance.mesh).clone()))
+            });
+            self.meshes.push(IfcMesh {
+                entity_id: element.id as u64,
+                geometry: Arc::clone(shared),
+                color,
+                transform: instance.y_up_transform(),
+                entity_type: element.entity_type.to_string(),
+                name: element.name.clone(),
+            });
+        }
+    }
+}
 
-        // Process geometry
-        let mesh = match router.process_element(&entity, &mut decoder) {
-            Ok(m) => m,
-            Err(e) => {
-                crate::log(&format!(
-                    "[Loader] Failed to process #{} ({}): {}",
-                    id, type_name, e
-                ));
-                continue;
-            }
-        };
+/// Load an IFC file and convert to viewer format
+fn load_ifc_file(path: &std::path::Path) -> Result<LoadedIfc, Box<dyn std::error::Error>> {
+    // Read file content
+    let content = std::fs::read_to_string(path)?;
 
-        if mesh.is_empty() {
-            continue;
-        }
+    // Models placed in map coordinates are shifted so they don't jitter in f32
+    let mut collector = MeshCollector::default();
+    let mut scene = SceneBuilder::new(&content).build(&mut collector);
+    let mut meshes = collector.meshes;
+    crate::log_info(&format!(
+        "[Loader] Found {} building elements, {} problems",
+        scene.elements.len(),
+        scene.diagnostics.len()
+    ));
 
-        // Convert to IfcMesh format - takes ownership of mesh, no cloning!
-        let color = crate::mesh::get_default_color(&type_name);
-        let ifc_mesh = IfcMesh::from_geometry_mesh(
-            id as u64,
-            mesh, // Move, not clone
-            color,
-            type_name.clone(),
-            name.clone(),
-        );
-        meshes.push(ifc_mesh);
-
-        // Add entity info
-        entities.push(EntityInfo {
-            id: id as u64,
-            entity_type: type_name,
-            name,
-            storey: None, // TODO: extract from spatial structure
-            storey_elevation: None,
-        });
-    }
+    // Type and storey names are interned by the scene builder
+    let entities = scene
+        .elements
+        .iter()
+        .filter(|e| e.has_geometry)
+        .map(|e| EntityInfo {
+            id: e.id as u64,
+            entity_type: Arc::clone(&e.entity_type),
+            name: e.name.clone(),
+            storey: e.storey.clone(),
+            storey_elevation: e.storey_elevation,
+            referenced_storeys: e.referenced_storeys.clone(),
+            predefined_type: e.predefined_type.clone(),
+            object_type: e.object_type.clone(),
+        })
+        .collect();
+
+    crate::log_info(&format!(
+        "[Loader] Dedup: {}",
+        crate::dedup::dedup_meshes(&mut meshes)
+    ));
 
-    Ok((meshes, entities))
+    // The router places the axes in the shifted scene; storage holds file
+    // coordinates
+    let shift = scene.origin_shift;
+    let to_file = |p: &Point3<f64>| [p.x + shift[0], p.y + shift[1], p.z + shift[2]];
+    let axes = scene.router.grid_axes(&content, &mut scene.decoder);
+    let info = SceneInfoStorage {
+        true_north: scene.structure.true_north,
+        grid_axes: axes
+            .iter()
+            .map(|axis| GridAxisStorage {
+                tag: axis.tag.clone(),
+                start: to_file(&axis.start),
+                end: to_file(&axis.end),
+            })
+            .collect(),
+        grid_intersections: grid_intersections(&axes)
+            .into_iter()
+            .map(|i| GridIntersectionStorage {
+                label: format!("{}/{}", i.tags.0, i.tags.1),
+                point: to_file(&i.point),
+            })
+            .collect(),
+        origin_shift: shift,
+    };
+
+    Ok((meshes, entities, info))
 }
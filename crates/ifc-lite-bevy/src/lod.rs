@@ -0,0 +1,161 @@
+//! Level of detail - decimated batches switched by camera distance
+//!
+//! Opaque chunk batches with many triangles get coarser copies built with
+//! [`ifc_lite_geometry::generate_lods`], decimated per entity so vertex
+//! colours stay intact. Each frame the coarsest level whose grid cell still
+//! projects below [`LodSettings::max_error_px`] is swapped into `Mesh3d`.
+//! Picking always tests the full-detail mesh.
+
+use crate::camera::{CameraController, MainCamera};
+use crate::mesh::{IfcMesh, MeshGeometry};
+use bevy::prelude::*;
+use std::sync::Arc;
+
+/// Grid cell size of each coarser level, as a fraction of the batch diagonal
+const LOD_CELL_FRACTIONS: [f32; 2] = [1.0 / 256.0, 1.0 / 64.0];
+
+/// LOD plugin
+pub struct LodPlugin;
+
+impl Plugin for LodPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LodSettings>().add_systems(
+            Update,
+            update_lod_system.after(crate::camera::CameraInputSet),
+        );
+    }
+}
+
+/// LOD configuration
+#[derive(Resource, Clone, Debug)]
+pub struct LodSettings {
+    /// Generate and switch LOD levels
+    pub enabled: bool,
+    /// Batches with fewer triangles are always drawn at full detail
+    pub min_batch_triangles: usize,
+    /// Largest on-screen size of a decimation cell, in pixels
+    pub max_error_px: f32,
+}
+
+impl Default for LodSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            min_batch_triangles: 20_000,
+            max_error_px: 2.0,
+        }
+    }
+}
+
+/// Detail levels of a batch; level 0 is the full mesh
+#[derive(Component)]
+pub struct BatchLod {
+    pub levels: Vec<Handle<Mesh>>,
+    /// Decimation cell size per level (0 for full detail)
+    pub cell_sizes: Vec<f32>,
+    /// World-space bounds of the batch
+    pub min: Vec3,
+    pub max: Vec3,
+    /// Level currently shown
+    pub current: usize,
+}
+
+impl BatchLod {
+    /// Full-detail mesh, used for picking
+    pub fn full(&self) -> &Handle<Mesh> {
+        &self.levels[0]
+    }
+}
+
+/// Decimated versions of a batch: `(cell_size, meshes)` per coarser level
+///
+/// `cell_size` is derived from the batch bounds. Each entry lists the
+/// geometry to draw for every input mesh at that level, in input order;
+/// meshes too simple to decimate reuse their previous level. Empty if the
+/// batch is too small to benefit.
+pub fn decimate_batch(
+    ifc_meshes: &[&IfcMesh],
+    min: Vec3,
+    max: Vec3,
+    settings: &LodSettings,
+) -> Vec<(f32, Vec<Arc<MeshGeometry>>)> {
+    let triangle_count: usize = ifc_meshes.iter().map(|m| m.geometry.triangle_count()).sum();
+    if !settings.enabled || triangle_count < settings.min_batch_triangles {
+        return Vec::new();
+    }
+
+    let diagonal = (max - min).length();
+    let cell_sizes: Vec<f32> = LOD_CELL_FRACTIONS.iter().map(|f| diagonal * f).collect();
+    let mut levels: Vec<(f32, Vec<Arc<MeshGeometry>>)> = cell_sizes
+        .iter()
+        .map(|&cell_size| (cell_size, Vec::with_capacity(ifc_meshes.len())))
+        .collect();
+
+    for ifc_mesh in ifc_meshes {
+        let geometry = &ifc_mesh.geometry;
+        let source = ifc_lite_geometry::Mesh {
+            positions: geometry.positions.clone(),
+            normals: geometry.normals.clone(),
+            indices: geometry.indices.clone(),
+            colors: geometry.colors.clone(),
+        };
+        let mut decimated = ifc_lite_geometry::generate_lods(&source, &cell_sizes).into_iter();
+        let mut previous = Arc::clone(geometry);
+        for (_, meshes) in levels.iter_mut() {
+            if let Some(mesh) = decimated.next() {
+                previous = Arc::new(MeshGeometry::from_geometry_mesh(mesh));
+            }
+            meshes.push(Arc::clone(&previous));
+        }
+    }
+
+    // Drop levels that barely save anything over their predecessor
+    let mut previous_triangles = triangle_count;
+    let mut kept = 0;
+    for (_, meshes) in &levels {
+        let triangles: usize = meshes.iter().map(|m| m.triangle_count()).sum();
+        if triangles * 10 > previous_triangles * 9 {
+            break;
+        }
+        previous_triangles = triangles;
+        kept += 1;
+    }
+    levels.truncate(kept);
+    levels
+}
+
+/// Pick the coarsest acceptable level for each batch
+fn update_lod_system(
+    settings: Res<LodSettings>,
+    controller: Res<CameraController>,
+    cameras: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut batches: Query<(&mut BatchLod, &mut Mesh3d)>,
+) {
+    let Ok((camera, camera_transform)) = cameras.single() else {
+        return;
+    };
+    let viewport_height = camera
+        .logical_viewport_size()
+        .map(|size| size.y)
+        .unwrap_or(1080.0);
+    // Pixels per world unit at distance 1
+    let focal_px = viewport_height / (2.0 * (controller.fov.to_radians() / 2.0).tan());
+    let eye = camera_transform.translation();
+
+    for (mut lod, mut mesh) in batches.iter_mut() {
+        let level = if settings.enabled {
+            let distance = (eye.clamp(lod.min, lod.max) - eye).length();
+            lod.cell_sizes
+                .iter()
+                .rposition(|&cell| cell * focal_px <= settings.max_error_px * distance)
+                .unwrap_or(0)
+        } else {
+            0
+        };
+
+        if level != lod.current {
+            mesh.0 = lod.levels[level].clone();
+            lod.current = level;
+        }
+    }
+}
@@ -0,0 +1,330 @@
+//! Measurement tool - snapped point picking and measurement display
+//!
+//! In [`CameraMode::Measure`] a click picks a point on the model, snapped to
+//! a corner or edge of the hit triangle or to a grid axis intersection when
+//! one lies within a few pixels of the cursor. Points are queued in storage
+//! in IFC file coordinates (Z-up, metres, without the scene's origin shift)
+//! and the UI pairs them into measurements, which come back to be drawn with
+//! gizmos. Distance labels need text, so only their screen positions are
+//! published and the UI renders them.
+
+use crate::camera::{CameraController, CameraMode, MainCamera};
+use crate::mesh::TriangleEntityMapping;
+use crate::picking::{pick_entity, PickHit, PickableBatch};
+use crate::storage::{
+    GridIntersectionStorage, MeasureLabelStorage, MeasurePointStorage, MeasurementsStorage,
+};
+use crate::{IfcSceneData, ViewerSettings};
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+/// Snap distance around the cursor in pixels
+const SNAP_RADIUS_PX: f32 = 10.0;
+/// Radius of point markers in pixels
+const MARKER_RADIUS_PX: f32 = 4.0;
+
+/// Measurement plugin
+pub struct MeasurementPlugin;
+
+impl Plugin for MeasurementPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MeasureState>().add_systems(
+            Update,
+            (
+                poll_measurements_system,
+                measure_pick_system,
+                draw_measurements_system,
+                update_measure_labels_system,
+            )
+                .chain()
+                .after(crate::camera::CameraInputSet),
+        );
+    }
+}
+
+/// What a measurement point snapped to
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SnapKind {
+    Vertex,
+    Edge,
+    Face,
+    /// Crossing of two IfcGrid axes
+    Grid,
+}
+
+impl SnapKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SnapKind::Vertex => "vertex",
+            SnapKind::Edge => "edge",
+            SnapKind::Face => "face",
+            SnapKind::Grid => "grid",
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            SnapKind::Vertex => Color::srgb(1.0, 0.3, 0.8),
+            SnapKind::Edge => Color::srgb(0.2, 0.8, 1.0),
+            SnapKind::Face => Color::WHITE,
+            SnapKind::Grid => Color::srgb(0.3, 0.55, 0.85),
+        }
+    }
+}
+
+/// Measurements shown in the viewport
+#[derive(Resource, Default)]
+pub struct MeasureState {
+    /// Measurements from the UI, in IFC coordinates
+    pub measurements: MeasurementsStorage,
+    /// Snapped point under the cursor, in world space
+    pub preview: Option<(Vec3, SnapKind)>,
+    /// Labels last published to the UI
+    labels: Vec<MeasureLabelStorage>,
+}
+
+/// IFC (Z-up) to Bevy (Y-up) coordinates
+pub fn ifc_to_world(point: [f32; 3]) -> Vec3 {
+    Vec3::new(point[0], point[2], -point[1])
+}
+
+/// Bevy (Y-up) to IFC (Z-up) coordinates
+pub fn world_to_ifc(point: Vec3) -> [f32; 3] {
+    [point.x, -point.z, point.y]
+}
+
+/// Snap a hit to the nearest triangle corner, else the nearest triangle edge,
+/// within `radius` pixels of `cursor`
+///
+/// `project` maps world positions to viewport pixels.
+pub fn snap_point(
+    hit: &PickHit,
+    cursor: Vec2,
+    radius: f32,
+    project: impl Fn(Vec3) -> Option<Vec2>,
+) -> (Vec3, SnapKind) {
+    let nearest = |candidates: &mut dyn Iterator<Item = Vec3>| {
+        candidates
+            .filter_map(|p| {
+                let d = project(p)?.distance(cursor);
+                (d <= radius).then_some((p, d))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(p, _)| p)
+    };
+
+    let [a, b, c] = hit.triangle;
+    if let Some(corner) = nearest(&mut [a, b, c].into_iter()) {
+        return (corner, SnapKind::Vertex);
+    }
+    let mut edge_points = [(a, b), (b, c), (c, a)]
+        .into_iter()
+        .map(|(start, end)| closest_on_segment(start, end, hit.point));
+    if let Some(on_edge) = nearest(&mut edge_points) {
+        return (on_edge, SnapKind::Edge);
+    }
+    (hit.point, SnapKind::Face)
+}
+
+/// Nearest grid intersection within `radius` pixels of `cursor`, in world space
+pub fn snap_to_grid(
+    scene: &IfcSceneData,
+    intersections: &[GridIntersectionStorage],
+    cursor: Vec2,
+    radius: f32,
+    project: impl Fn(Vec3) -> Option<Vec2>,
+) -> Option<Vec3> {
+    intersections
+        .iter()
+        .filter_map(|intersection| {
+            let p = scene.file_to_world(intersection.point);
+            let d = project(p)?.distance(cursor);
+            (d <= radius).then_some((p, d))
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(p, _)| p)
+}
+
+fn closest_on_segment(start: Vec3, end: Vec3, point: Vec3) -> Vec3 {
+    let segment = end - start;
+    let length_sq = segment.length_squared();
+    if length_sq <= f32::EPSILON {
+        return start;
+    }
+    let t = ((point - start).dot(segment) / length_sq).clamp(0.0, 1.0);
+    start + segment * t
+}
+
+/// Poll measurements from the UI
+fn poll_measurements_system(mut state: ResMut<MeasureState>, mut frame: Local<u32>) {
+    *frame = frame.wrapping_add(1);
+    if !frame.is_multiple_of(10) {
+        return;
+    }
+    let measurements = crate::storage::load_measurements().unwrap_or_default();
+    if measurements != state.measurements {
+        state.measurements = measurements;
+    }
+}
+
+/// Track the snap point under the cursor and queue it on click
+#[allow(clippy::too_many_arguments)]
+fn measure_pick_system(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    batched_meshes: Query<PickableBatch>,
+    triangle_mapping: Res<TriangleEntityMapping>,
+    meshes: Res<Assets<Mesh>>,
+    scene_data: Res<IfcSceneData>,
+    settings: Res<ViewerSettings>,
+    mut controller: ResMut<CameraController>,
+    mut state: ResMut<MeasureState>,
+) {
+    if controller.mode != CameraMode::Measure {
+        if state.preview.is_some() {
+            state.preview = None;
+        }
+        return;
+    }
+    let Ok((camera, camera_transform)) = cameras.single() else {
+        return;
+    };
+
+    let cursor = if controller.just_clicked {
+        Some(controller.drag_start_pos)
+    } else {
+        windows.single().ok().and_then(Window::cursor_position)
+    };
+    let project = |p: Vec3| camera.world_to_viewport(camera_transform, p).ok();
+    let grid: &[GridIntersectionStorage] = if settings.show_grid_axes {
+        &scene_data.grid_intersections
+    } else {
+        &[]
+    };
+    let snapped = cursor.and_then(|cursor| {
+        let ray = camera.viewport_to_world(camera_transform, cursor).ok()?;
+        let hit = pick_entity(&ray, &batched_meshes, &triangle_mapping, &meshes)
+            .map(|hit| snap_point(&hit, cursor, SNAP_RADIUS_PX, project));
+        // Model corners win; grid intersections also snap off the model
+        match hit {
+            Some((_, SnapKind::Vertex)) => hit,
+            _ => snap_to_grid(&scene_data, grid, cursor, SNAP_RADIUS_PX, project)
+                .map(|p| (p, SnapKind::Grid))
+                .or(hit),
+        }
+    });
+    state.preview = snapped;
+
+    if controller.just_clicked {
+        controller.just_clicked = false;
+        if let Some((point, snap)) = snapped {
+            let [x, y, z] = scene_data.world_to_file(point);
+            crate::log(&format!(
+                "[Bevy] Measure point ({:.3}, {:.3}, {:.3}) on {}",
+                x,
+                y,
+                z,
+                snap.as_str()
+            ));
+            crate::storage::push_measure_point(MeasurePointStorage {
+                x,
+                y,
+                z,
+                snap: snap.as_str().to_string(),
+            });
+        }
+    }
+}
+
+/// Draw measurement lines, their end points and the snap preview
+fn draw_measurements_system(
+    state: Res<MeasureState>,
+    scene_data: Res<IfcSceneData>,
+    cameras: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    controller: Res<CameraController>,
+    mut gizmos: Gizmos,
+) {
+    let Ok((camera, camera_transform)) = cameras.single() else {
+        return;
+    };
+    let viewport_height = camera.logical_viewport_size().map_or(1.0, |size| size.y);
+    let units_per_pixel = 2.0 * (controller.fov.to_radians() / 2.0).tan() / viewport_height;
+    let eye = camera_transform.translation();
+    let marker = |gizmos: &mut Gizmos, point: Vec3, color: Color| {
+        let per_pixel = if controller.orthographic {
+            controller.world_units_per_pixel(viewport_height)
+        } else {
+            eye.distance(point) * units_per_pixel
+        };
+        let radius = per_pixel * MARKER_RADIUS_PX;
+        gizmos.sphere(Isometry3d::from_translation(point), radius, color);
+    };
+
+    let line_color = Color::srgb(1.0, 0.8, 0.1);
+    for measurement in &state.measurements.measurements {
+        let start = scene_data.file_to_world(measurement.start);
+        let end = scene_data.file_to_world(measurement.end);
+        marker(&mut gizmos, start, line_color);
+        marker(&mut gizmos, end, line_color);
+        gizmos.line(start, end, line_color);
+    }
+
+    let pending = state
+        .measurements
+        .pending
+        .map(|p| scene_data.file_to_world(p));
+    if let Some(pending) = pending {
+        marker(&mut gizmos, pending, line_color);
+    }
+    if let Some((point, snap)) = state.preview {
+        marker(&mut gizmos, point, snap.color());
+        if let Some(pending) = pending {
+            gizmos.line(pending, point, line_color.with_alpha(0.5));
+        }
+    }
+}
+
+/// Publish where the UI should put distance labels
+fn update_measure_labels_system(
+    mut state: ResMut<MeasureState>,
+    scene_data: Res<IfcSceneData>,
+    cameras: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+) {
+    let Ok((camera, camera_transform)) = cameras.single() else {
+        return;
+    };
+
+    let labels: Vec<MeasureLabelStorage> = state
+        .measurements
+        .measurements
+        .iter()
+        .filter_map(|m| {
+            let (start, end) = (
+                scene_data.file_to_world(m.start),
+                scene_data.file_to_world(m.end),
+            );
+            let anchor = camera
+                .world_to_viewport(camera_transform, (start + end) * 0.5)
+                .ok()?;
+            Some(MeasureLabelStorage {
+                id: m.id,
+                x: anchor.x,
+                y: anchor.y,
+                distance: start.distance(end),
+            })
+        })
+        .collect();
+
+    // Only touch storage when a label visibly moves
+    let moved = labels.len() != state.labels.len()
+        || labels.iter().zip(&state.labels).any(|(new, old)| {
+            new.id != old.id
+                || new.distance != old.distance
+                || (new.x - old.x).abs() > 0.5
+                || (new.y - old.y).abs() > 0.5
+        });
+    if moved {
+        crate::storage::save_measure_labels(&labels);
+        state.labels = labels;
+    }
+}
@@ -11,17 +11,34 @@
 //!
 //! This reduces draw calls from N to 2-3, dramatically improving orbit/pan performance.
 //!
+//! Hiding entities does not rebuild batches: every batch mesh keeps the index
+//! range of each entity, and hidden ranges are collapsed to degenerate
+//! triangles in the index buffer (see [`BatchVisibility`]). Triangle order is
+//! unchanged, so picking offsets stay valid. X-ray mode uses the same masks:
+//! entities outside the focus set move to a translucent [`XrayGhost`] copy.
+//! Selection highlights copy the triangles of the highlighted entities out
+//! of the batches instead (see [`crate::highlight`]).
+//!
 //! ## Memory Optimization: Arc-based Geometry Sharing
 //!
 //! Geometry data (positions, normals, indices) is stored in `Arc<MeshGeometry>` to avoid
 //! expensive cloning. This saves ~1.7GB RAM on a 200MB IFC file by sharing geometry
 //! between the parser output and our mesh structures.
 
+use crate::lod::{BatchLod, LodSettings};
+use crate::section::{ClippedMaterial, SectionClip};
+use crate::storage::{BatchTimingsStorage, SceneBoundsStorage};
 use crate::{log, IfcSceneData, SceneBounds, ViewerSettings};
 use bevy::asset::RenderAssetUsages;
+use bevy::core_pipeline::prepass::{DepthPrepass, NormalPrepass};
 use bevy::mesh::{Indices, PrimitiveTopology};
+use bevy::pbr::ScreenSpaceAmbientOcclusion;
+use bevy::platform::time::Instant;
 use bevy::prelude::*;
+use bevy::render::experimental::occlusion_culling::OcclusionCulling;
+use rustc_hash::FxHashMap;
 use serde::{Deserialize, Serialize};
+use std::ops::Range;
 use std::sync::Arc;
 
 /// Mesh plugin
@@ -30,22 +47,47 @@ pub struct MeshPlugin;
 impl Plugin for MeshPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<AutoFitState>()
+            .init_resource::<BatchMaterials>()
             .init_resource::<PendingFocus>()
             .init_resource::<TriangleEntityMapping>()
+            .init_resource::<BatchTimings>()
+            .add_systems(First, finish_upload_timing)
             .add_systems(
                 Update,
                 (
                     spawn_meshes_system,
                     auto_fit_camera_system,
+                    poll_xray_system,
                     update_mesh_visibility_system,
-                    update_mesh_selection_system,
                     poll_focus_command_system,
+                    update_occlusion_culling_system,
                 )
                     .chain(),
             );
     }
 }
 
+/// Time spent batching the current geometry and uploading the batches
+#[derive(Resource, Default)]
+pub struct BatchTimings {
+    pub timings: BatchTimingsStorage,
+    /// When the last batches were spawned; the next frame uploads them
+    upload_start: Option<Instant>,
+}
+
+/// Count the frame after batching as upload time and publish the timings
+///
+/// Bevy prepares GPU buffers for new meshes while rendering that frame, so
+/// the time from spawning the batches to the start of the next frame
+/// covers the upload.
+fn finish_upload_timing(mut batch_timings: ResMut<BatchTimings>) {
+    let Some(start) = batch_timings.upload_start.take() else {
+        return;
+    };
+    batch_timings.timings.upload_ms += start.elapsed().as_secs_f64() * 1000.0;
+    crate::storage::save_batch_timings(&batch_timings.timings);
+}
+
 /// Resource for pending focus command
 #[derive(Resource, Default)]
 pub struct PendingFocus {
@@ -57,6 +99,8 @@ pub struct PendingFocus {
 pub struct AutoFitState {
     /// Whether we've already auto-fit for this scene
     pub has_fit: bool,
+    /// Refit whenever a streamed chunk grows the scene bounds
+    pub streaming: bool,
 }
 
 /// Shared geometry data - uses Arc to avoid expensive cloning
@@ -72,6 +116,9 @@ pub struct MeshGeometry {
     pub normals: Vec<f32>,
     /// Triangle indices
     pub indices: Vec<u32>,
+    /// Per-vertex RGBA (flattened), empty unless faces carry their own colors.
+    /// A negative alpha means the vertex uses the element color.
+    pub colors: Vec<f32>,
 }
 
 impl MeshGeometry {
@@ -81,15 +128,32 @@ impl MeshGeometry {
             positions,
             normals,
             indices,
+            colors: Vec::new(),
         }
     }
 
+    /// Attach per-vertex colors
+    pub fn with_colors(mut self, colors: Vec<f32>) -> Self {
+        self.colors = colors;
+        self
+    }
+
     /// Create from ifc_lite_geometry::Mesh (takes ownership via conversion)
     pub fn from_geometry_mesh(mesh: ifc_lite_geometry::Mesh) -> Self {
         Self {
             positions: mesh.positions,
             normals: mesh.normals,
             indices: mesh.indices,
+            colors: mesh.colors,
+        }
+    }
+
+    /// Color of a vertex, falling back to the element color
+    #[inline]
+    pub fn vertex_color(&self, index: usize, element_color: [f32; 4]) -> [f32; 4] {
+        match self.colors.get(index * 4..index * 4 + 4) {
+            Some(c) if c[3] >= 0.0 => [c[0], c[1], c[2], c[3]],
+            _ => element_color,
         }
     }
 
@@ -142,6 +206,9 @@ pub struct IfcMeshSerialized {
     pub normals: Vec<f32>,
     /// Triangle indices
     pub indices: Vec<u32>,
+    /// Per-vertex colors (flattened RGBA), empty if none
+    #[serde(default)]
+    pub colors: Vec<f32>,
     /// Base color [r, g, b, a]
     pub color: [f32; 4],
     /// Transform matrix (column-major 4x4)
@@ -156,7 +223,9 @@ impl From<IfcMeshSerialized> for IfcMesh {
     fn from(s: IfcMeshSerialized) -> Self {
         Self {
             entity_id: s.entity_id,
-            geometry: Arc::new(MeshGeometry::new(s.positions, s.normals, s.indices)),
+            geometry: Arc::new(
+                MeshGeometry::new(s.positions, s.normals, s.indices).with_colors(s.colors),
+            ),
             color: s.color,
             transform: s.transform,
             entity_type: s.entity_type,
@@ -172,6 +241,7 @@ impl From<&IfcMesh> for IfcMeshSerialized {
             positions: m.geometry.positions.clone(),
             normals: m.geometry.normals.clone(),
             indices: m.geometry.indices.clone(),
+            colors: m.geometry.colors.clone(),
             color: m.color,
             transform: m.transform,
             entity_type: m.entity_type.clone(),
@@ -285,6 +355,16 @@ impl IfcMesh {
     pub fn get_color(&self) -> Color {
         Color::srgba(self.color[0], self.color[1], self.color[2], self.color[3])
     }
+
+    /// Whether the element or any of its colored faces is see-through
+    pub fn is_transparent(&self) -> bool {
+        self.color[3] < 1.0
+            || self
+                .geometry
+                .colors
+                .chunks_exact(4)
+                .any(|c| (0.0..1.0).contains(&c[3]))
+    }
 }
 
 /// Marker component for IFC entities
@@ -321,9 +401,18 @@ pub struct NeedsMaterialUpdate;
 pub struct BatchedMesh {
     /// Whether this batch is transparent
     pub is_transparent: bool,
+    /// Index of the batch's first triangle in [`TriangleEntityMapping`]
+    pub triangle_offset: usize,
 }
 
+/// Ray-cast acceleration structure over a batch's full-detail triangles
+#[derive(Component, Clone)]
+pub struct BatchBvh(pub Arc<ifc_lite_geometry::TriangleBvh>);
+
 /// Resource mapping triangle indices to entity IDs for picking
+///
+/// Streamed scenes have several batches per kind; their mappings are
+/// concatenated and each batch records its offset.
 #[derive(Resource, Default)]
 pub struct TriangleEntityMapping {
     /// Maps triangle index -> entity ID for opaque batch
@@ -344,6 +433,243 @@ impl TriangleEntityMapping {
     }
 }
 
+/// Batch mesh with the index range of every entity in it
+///
+/// The index buffer lives in the mesh asset only. Masking an entity out
+/// overwrites its range there with a degenerate triangle and keeps the
+/// original indices of that range until it is shown again.
+pub struct MaskableMesh {
+    pub handle: Handle<Mesh>,
+    /// Index range per entity, in batch order
+    ranges: Vec<(u64, Range<usize>)>,
+    /// Vertex range of each entry in `ranges`
+    vertices: Vec<Range<usize>>,
+    /// Original indices of the masked-out entries in `ranges`
+    stashed: FxHashMap<usize, Vec<u32>>,
+}
+
+impl MaskableMesh {
+    /// Collapse the entities failing `is_visible` in the mesh asset and
+    /// restore the others, returning whether any are masked out
+    fn apply_mask(&mut self, meshes: &mut Assets<Mesh>, is_visible: impl Fn(u64) -> bool) -> bool {
+        let changes: Vec<(usize, bool)> = self
+            .ranges
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, range))| !range.is_empty())
+            .map(|(i, (entity_id, _))| (i, is_visible(*entity_id)))
+            .filter(|(i, visible)| *visible == self.stashed.contains_key(i))
+            .collect();
+        if changes.is_empty() {
+            return !self.stashed.is_empty();
+        }
+        let Some(Indices::U32(indices)) = meshes
+            .get_mut(&self.handle)
+            .and_then(|mesh| mesh.indices_mut())
+        else {
+            return !self.stashed.is_empty();
+        };
+        for (i, visible) in changes {
+            let range = self.ranges[i].1.clone();
+            if visible {
+                if let Some(original) = self.stashed.remove(&i) {
+                    indices[range].copy_from_slice(&original);
+                }
+            } else {
+                self.stashed.insert(i, indices[range.clone()].to_vec());
+                let anchor = indices[range.start];
+                indices[range].fill(anchor);
+            }
+        }
+        !self.stashed.is_empty()
+    }
+
+    /// Index buffer of the asset with every entity visible
+    fn full_indices(&self, meshes: &Assets<Mesh>) -> Option<Vec<u32>> {
+        let Some(Indices::U32(indices)) = meshes.get(&self.handle)?.indices() else {
+            return None;
+        };
+        let mut indices = indices.clone();
+        for (i, original) in &self.stashed {
+            indices[self.ranges[*i].1.clone()].copy_from_slice(original);
+        }
+        Some(indices)
+    }
+
+    /// Unmasked copy in a new mesh asset, e.g. to mask differently
+    fn duplicate(&self, meshes: &mut Assets<Mesh>) -> Option<Self> {
+        let indices = self.full_indices(meshes)?;
+        let mut copy = meshes.get(&self.handle)?.clone();
+        copy.insert_indices(Indices::U32(indices));
+        Some(Self {
+            handle: meshes.add(copy),
+            ranges: self.ranges.clone(),
+            vertices: self.vertices.clone(),
+            stashed: FxHashMap::default(),
+        })
+    }
+
+    /// The entities passing `keep` as a mesh of their own, positions and
+    /// normals only; `None` if none of them has triangles here
+    pub(crate) fn extract(
+        &self,
+        meshes: &Assets<Mesh>,
+        keep: impl Fn(u64) -> bool,
+    ) -> Option<Mesh> {
+        self.extract_attributes(meshes, keep, false)
+    }
+
+    /// Like [`Self::extract`], with the vertex colours of the batch
+    pub(crate) fn extract_colored(
+        &self,
+        meshes: &Assets<Mesh>,
+        keep: impl Fn(u64) -> bool,
+    ) -> Option<Mesh> {
+        self.extract_attributes(meshes, keep, true)
+    }
+
+    fn extract_attributes(
+        &self,
+        meshes: &Assets<Mesh>,
+        keep: impl Fn(u64) -> bool,
+        with_colors: bool,
+    ) -> Option<Mesh> {
+        use bevy::mesh::VertexAttributeValues;
+
+        let mesh = meshes.get(&self.handle)?;
+        let Some(VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            return None;
+        };
+        let normals = match mesh.attribute(Mesh::ATTRIBUTE_NORMAL) {
+            Some(VertexAttributeValues::Float32x3(normals)) => Some(normals),
+            _ => None,
+        };
+        let colors = match mesh.attribute(Mesh::ATTRIBUTE_COLOR) {
+            Some(VertexAttributeValues::Float32x4(colors)) if with_colors => Some(colors),
+            _ => None,
+        };
+        let Some(Indices::U32(batch_indices)) = mesh.indices() else {
+            return None;
+        };
+
+        let mut out_positions = Vec::new();
+        let mut out_normals = Vec::new();
+        let mut out_colors = Vec::new();
+        let mut out_indices = Vec::new();
+        for (i, ((entity_id, range), vertices)) in
+            self.ranges.iter().zip(&self.vertices).enumerate()
+        {
+            if range.is_empty() || !keep(*entity_id) {
+                continue;
+            }
+            let indices = self
+                .stashed
+                .get(&i)
+                .map_or(&batch_indices[range.clone()], Vec::as_slice);
+            let first = vertices.start as u32;
+            let base = out_positions.len() as u32;
+            out_positions.extend_from_slice(&positions[vertices.clone()]);
+            if let Some(normals) = normals {
+                out_normals.extend_from_slice(&normals[vertices.clone()]);
+            }
+            if let Some(colors) = colors {
+                out_colors.extend_from_slice(&colors[vertices.clone()]);
+            }
+            out_indices.extend(indices.iter().map(|i| i - first + base));
+        }
+        if out_indices.is_empty() {
+            return None;
+        }
+
+        let mut extracted = Mesh::new(
+            PrimitiveTopology::TriangleList,
+            RenderAssetUsages::RENDER_WORLD,
+        );
+        extracted.insert_attribute(Mesh::ATTRIBUTE_POSITION, out_positions);
+        if !out_normals.is_empty() {
+            extracted.insert_attribute(Mesh::ATTRIBUTE_NORMAL, out_normals);
+        }
+        if !out_colors.is_empty() {
+            extracted.insert_attribute(Mesh::ATTRIBUTE_COLOR, out_colors);
+        }
+        extracted.insert_indices(Indices::U32(out_indices));
+        Some(extracted)
+    }
+
+    /// BVH over the full index buffer with moved vertex positions
+    pub(crate) fn build_bvh(
+        &self,
+        meshes: &Assets<Mesh>,
+        positions: &[[f32; 3]],
+    ) -> Option<BatchBvh> {
+        let indices = self.full_indices(meshes)?;
+        Some(BatchBvh(Arc::new(ifc_lite_geometry::TriangleBvh::build(
+            positions, &indices,
+        ))))
+    }
+
+    /// Vertex range of every entity; each entity's vertices are contiguous
+    pub fn vertex_ranges(&self) -> impl Iterator<Item = (u64, Range<usize>)> + '_ {
+        self.ranges
+            .iter()
+            .zip(&self.vertices)
+            .filter(|(_, vertices)| !vertices.is_empty())
+            .map(|((entity_id, _), vertices)| (*entity_id, vertices.clone()))
+    }
+}
+
+/// Vertex range spanned by each index range
+fn vertex_ranges(indices: &[u32], ranges: &[(u64, Range<usize>)]) -> Vec<Range<usize>> {
+    ranges
+        .iter()
+        .map(|(_, range)| {
+            let indices = &indices[range.clone()];
+            match (indices.iter().min(), indices.iter().max()) {
+                (Some(&first), Some(&last)) => first as usize..last as usize + 1,
+                _ => 0..0,
+            }
+        })
+        .collect()
+}
+
+/// Per-entity visibility of a batch: full-detail mesh plus any LOD levels
+#[derive(Component)]
+pub struct BatchVisibility {
+    pub meshes: Vec<MaskableMesh>,
+    /// Whether an index buffer currently has entities masked out
+    masked: bool,
+}
+
+impl BatchVisibility {
+    fn new(meshes: Vec<MaskableMesh>) -> Self {
+        Self {
+            meshes,
+            masked: false,
+        }
+    }
+}
+
+/// Per-mesh processing applied while batching
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MeshPrep {
+    /// Crease angle (radians) for smoothing meshes without normals
+    pub crease_angle: Option<f32>,
+    /// Vertex welding and index reordering for the GPU
+    pub optimize: Option<ifc_lite_geometry::OptimizeOptions>,
+}
+
+impl MeshPrep {
+    /// Processing selected by the viewer settings
+    pub fn from_settings(settings: &ViewerSettings) -> Self {
+        Self {
+            crease_angle: settings.smoothing_crease_angle(),
+            optimize: settings.mesh_optimize_options(),
+        }
+    }
+}
+
 /// Batched geometry builder - combines multiple meshes into one
 struct BatchBuilder {
     positions: Vec<[f32; 3]>,
@@ -352,6 +678,10 @@ struct BatchBuilder {
     indices: Vec<u32>,
     /// Maps triangle index -> entity_id (for picking)
     triangle_to_entity: Vec<u64>,
+    /// Index range of each added mesh (for visibility masking)
+    entity_ranges: Vec<(u64, Range<usize>)>,
+    /// Smoothing and optimization of added meshes
+    prep: MeshPrep,
 }
 
 impl BatchBuilder {
@@ -362,17 +692,60 @@ impl BatchBuilder {
             colors: Vec::with_capacity(vertex_hint),
             indices: Vec::with_capacity(index_hint),
             triangle_to_entity: Vec::with_capacity(index_hint / 3),
+            entity_ranges: Vec::new(),
+            prep: MeshPrep::default(),
         }
     }
 
+    /// Smooth and optimize added meshes
+    fn prepared(mut self, prep: MeshPrep) -> Self {
+        self.prep = prep;
+        self
+    }
+
+    /// Smoothed and optimized copy of a geometry, None if nothing applies
+    fn prepare(&self, geometry: &MeshGeometry) -> Option<MeshGeometry> {
+        let smooth = self
+            .prep
+            .crease_angle
+            .filter(|_| geometry.normals.len() != geometry.positions.len());
+        if smooth.is_none() && self.prep.optimize.is_none() {
+            return None;
+        }
+
+        let mut mesh = ifc_lite_geometry::Mesh {
+            positions: geometry.positions.clone(),
+            normals: geometry.normals.clone(),
+            indices: geometry.indices.clone(),
+            colors: geometry.colors.clone(),
+        };
+        if let Some(crease_angle) = smooth {
+            mesh.normals.clear();
+            ifc_lite_geometry::smooth_normals(&mut mesh, crease_angle as f64);
+        }
+        if let Some(options) = &self.prep.optimize {
+            ifc_lite_geometry::optimize_mesh(&mut mesh, options);
+        }
+        Some(MeshGeometry::from_geometry_mesh(mesh))
+    }
+
     /// Add a mesh to the batch, transforming vertices to world space
     fn add_mesh(&mut self, ifc_mesh: &IfcMesh) {
-        let geometry = &ifc_mesh.geometry;
-        let vertex_count = geometry.vertex_count();
-        if vertex_count == 0 {
+        self.add_geometry(ifc_mesh, &ifc_mesh.geometry);
+    }
+
+    /// Add replacement geometry (e.g. a decimated level) for a mesh
+    fn add_geometry(&mut self, ifc_mesh: &IfcMesh, geometry: &MeshGeometry) {
+        if geometry.vertex_count() == 0 {
             return;
         }
 
+        // Smoothing and optimization keep the triangle count, so picking
+        // offsets stay valid
+        let prepared = self.prepare(geometry);
+        let geometry = prepared.as_ref().unwrap_or(geometry);
+        let vertex_count = geometry.vertex_count();
+
         let start_vertex = self.positions.len();
         let transform = ifc_mesh.get_transform();
         let color = [
@@ -408,15 +781,18 @@ impl BatchBuilder {
                 self.normals.push([0.0, 1.0, 0.0]); // Default up
             }
 
-            self.colors.push(color);
+            self.colors.push(geometry.vertex_color(i, color));
         }
 
         // Add indices with offset and track triangle-to-entity mapping
         let index_offset = start_vertex as u32;
         let num_triangles = geometry.triangle_count();
+        let index_start = self.indices.len();
         for &idx in &geometry.indices {
             self.indices.push(idx + index_offset);
         }
+        self.entity_ranges
+            .push((ifc_mesh.entity_id, index_start..self.indices.len()));
 
         // Map each triangle to its entity ID (for picking)
         for _ in 0..num_triangles {
@@ -451,6 +827,26 @@ impl BatchBuilder {
         mesh
     }
 
+    /// BVH over the batch triangles, for picking
+    fn build_bvh(&self) -> BatchBvh {
+        BatchBvh(Arc::new(ifc_lite_geometry::TriangleBvh::build(
+            &self.positions,
+            &self.indices,
+        )))
+    }
+
+    /// Build the mesh into `meshes`, keeping what visibility masking needs
+    fn build_maskable(mut self, meshes: &mut Assets<Mesh>) -> MaskableMesh {
+        let ranges = std::mem::take(&mut self.entity_ranges);
+        let vertices = vertex_ranges(&self.indices, &ranges);
+        MaskableMesh {
+            handle: meshes.add(self.build()),
+            ranges,
+            vertices,
+            stashed: FxHashMap::default(),
+        }
+    }
+
     fn is_empty(&self) -> bool {
         self.positions.is_empty()
     }
@@ -465,48 +861,228 @@ impl BatchBuilder {
 }
 
 /// System to spawn batched meshes when scene data changes
+///
+/// A dirty scene is rebuilt from scratch. Meshes appended by a geometry
+/// stream only get batches of their own, so earlier chunks stay on the GPU.
+#[allow(clippy::too_many_arguments)]
 fn spawn_meshes_system(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
+    batch_materials: Res<BatchMaterials>,
     mut scene_data: ResMut<IfcSceneData>,
     mut triangle_mapping: ResMut<TriangleEntityMapping>,
+    mut auto_fit: ResMut<AutoFitState>,
+    lod_settings: Res<LodSettings>,
+    settings: Res<ViewerSettings>,
     existing_entities: Query<Entity, With<IfcEntity>>,
     existing_batches: Query<Entity, With<BatchedMesh>>,
+    mut batch_timings: ResMut<BatchTimings>,
+    mut last_chunk_size: Local<f32>,
+    mut last_prep: Local<Option<MeshPrep>>,
 ) {
-    if !scene_data.dirty {
+    // Re-chunk the scene when the chunk size changes
+    if settings.chunk_size != *last_chunk_size {
+        *last_chunk_size = settings.chunk_size;
+        scene_data.dirty |= !scene_data.meshes.is_empty();
+    }
+
+    // Rebuild batches when smoothing or optimization is toggled
+    let prep = MeshPrep::from_settings(&settings);
+    if last_prep.replace(prep) != Some(prep) {
+        scene_data.dirty |= !scene_data.meshes.is_empty();
+    }
+
+    let start = if scene_data.dirty {
+        log(&format!(
+            "[Bevy] Batching {} meshes for GPU",
+            scene_data.meshes.len()
+        ));
+
+        // Clear previous triangle mapping
+        triangle_mapping.opaque.clear();
+        triangle_mapping.transparent.clear();
+
+        // Despawn existing entities and batches
+        for entity in existing_entities.iter() {
+            commands.entity(entity).despawn();
+        }
+        for entity in existing_batches.iter() {
+            commands.entity(entity).despawn();
+        }
+
+        scene_data.bounds = None;
+        batch_timings.timings = BatchTimingsStorage::default();
+        0
+    } else if let Some(start) = scene_data.appended_from {
+        log(&format!(
+            "[Bevy] Batching {} streamed meshes",
+            scene_data.meshes.len() - start
+        ));
+        start
+    } else {
         return;
+    };
+
+    let batching_start = Instant::now();
+    let chunk_bounds = spawn_batches(
+        &mut commands,
+        &mut meshes,
+        &batch_materials,
+        &mut triangle_mapping,
+        &scene_data.meshes[start..],
+        settings.chunk_size,
+        prep,
+        &lod_settings,
+    );
+
+    // Update scene bounds
+    if let Some(chunk_bounds) = chunk_bounds {
+        let bounds = match scene_data.bounds.take() {
+            Some(bounds) => SceneBounds {
+                min: bounds.min.min(chunk_bounds.min),
+                max: bounds.max.max(chunk_bounds.max),
+            },
+            None => chunk_bounds,
+        };
+        log(&format!(
+            "[Bevy] Scene bounds: {:?} to {:?}",
+            bounds.min, bounds.max
+        ));
+        let (min, max) = crate::section::ifc_bounds(&bounds);
+        let shift = scene_data.origin_shift;
+        let to_file = |p: Vec3| std::array::from_fn(|i| p[i] as f64 + shift[i]);
+        crate::storage::save_scene_bounds(&SceneBoundsStorage {
+            min: to_file(min),
+            max: to_file(max),
+        });
+        scene_data.bounds = Some(bounds);
+
+        // Keep the whole model in view while a stream is still growing it
+        if auto_fit.streaming {
+            auto_fit.has_fit = false;
+        }
     }
 
-    let mesh_count = scene_data.meshes.len();
-    log(&format!("[Bevy] Batching {} meshes for GPU", mesh_count));
+    log(&format!(
+        "[Bevy] Batching complete: {} meshes",
+        scene_data.meshes.len() - start
+    ));
+    batch_timings.timings.batching_ms += batching_start.elapsed().as_secs_f64() * 1000.0;
+    batch_timings.timings.meshes = scene_data.meshes.len();
+    batch_timings.upload_start = Some(Instant::now());
+
+    scene_data.dirty = false;
+    scene_data.appended_from = None;
+}
 
-    // Clear previous triangle mapping
-    triangle_mapping.opaque.clear();
-    triangle_mapping.transparent.clear();
+/// Materials shared by all batches, so chunks do not break render batching
+///
+/// All of them clip against the section planes.
+#[derive(Resource)]
+pub struct BatchMaterials {
+    pub opaque: Handle<ClippedMaterial>,
+    pub transparent: Handle<ClippedMaterial>,
+    /// Translucent copy of out-of-focus geometry in x-ray mode
+    pub ghost: Handle<ClippedMaterial>,
+    /// Selected entities in the highlight mask, see [`crate::highlight`]
+    pub selected_mask: Handle<ClippedMaterial>,
+    /// The hovered entity in the highlight mask
+    pub hovered_mask: Handle<ClippedMaterial>,
+}
 
-    // Despawn existing entities and batches
-    for entity in existing_entities.iter() {
-        commands.entity(entity).despawn();
+impl FromWorld for BatchMaterials {
+    fn from_world(world: &mut World) -> Self {
+        let mut materials = world.resource_mut::<Assets<ClippedMaterial>>();
+        let mut add = |base| {
+            materials.add(ClippedMaterial {
+                base,
+                extension: SectionClip::default(),
+            })
+        };
+        Self {
+            opaque: add(StandardMaterial {
+                base_color: Color::WHITE,
+                metallic: 0.0,
+                perceptual_roughness: 0.6,
+                reflectance: 0.3,
+                double_sided: true,
+                cull_mode: None,
+                // Use vertex colors
+                ..default()
+            }),
+            transparent: add(StandardMaterial {
+                base_color: Color::WHITE,
+                metallic: 0.0,
+                perceptual_roughness: 0.1,
+                reflectance: 0.5,
+                double_sided: true,
+                cull_mode: None,
+                alpha_mode: AlphaMode::Blend,
+                ..default()
+            }),
+            ghost: add(StandardMaterial {
+                base_color: Color::srgba(1.0, 1.0, 1.0, 0.12),
+                unlit: true,
+                double_sided: true,
+                cull_mode: None,
+                alpha_mode: AlphaMode::Blend,
+                ..default()
+            }),
+            selected_mask: add(crate::highlight::mask_material(
+                crate::highlight::SELECTED_MASK_COLOR,
+            )),
+            hovered_mask: add(crate::highlight::mask_material(
+                crate::highlight::HOVERED_MASK_COLOR,
+            )),
+        }
     }
-    for entity in existing_batches.iter() {
-        commands.entity(entity).despawn();
+}
+
+impl BatchMaterials {
+    /// Each material with whether it draws caps on cut solids; ghosts stay
+    /// see-through and highlight masks only cover the surfaces
+    pub fn with_caps(&self) -> [(&Handle<ClippedMaterial>, bool); 5] {
+        [
+            (&self.opaque, true),
+            (&self.transparent, true),
+            (&self.ghost, false),
+            (&self.selected_mask, false),
+            (&self.hovered_mask, false),
+        ]
     }
+}
 
-    // Estimate capacity (rough: 100 verts per mesh average)
-    let vertex_hint = mesh_count * 100;
-    let index_hint = mesh_count * 300;
+/// Meshes whose centres fall into one grid cell
+struct SpatialChunk<'a> {
+    members: Vec<&'a IfcMesh>,
+    min: Vec3,
+    max: Vec3,
+}
 
-    let mut opaque_batch = BatchBuilder::with_capacity(vertex_hint, index_hint);
-    let mut transparent_batch = BatchBuilder::with_capacity(vertex_hint / 10, index_hint / 10);
+/// Spawn metadata entities for `ifc_meshes` and batch them per spatial
+/// chunk, returning their bounds
+///
+/// Each chunk gets its own opaque and transparent batch with a tight AABB,
+/// so Bevy's frustum culling skips chunks outside the view. A `chunk_size`
+/// of zero puts everything into a single chunk.
+#[allow(clippy::too_many_arguments)]
+fn spawn_batches(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &BatchMaterials,
+    triangle_mapping: &mut TriangleEntityMapping,
+    ifc_meshes: &[IfcMesh],
+    chunk_size: f32,
+    prep: MeshPrep,
+    lod_settings: &LodSettings,
+) -> Option<SceneBounds> {
+    let mut chunks: FxHashMap<IVec3, SpatialChunk> = FxHashMap::default();
 
     // Track bounds
     let mut scene_min = Vec3::splat(f32::INFINITY);
     let mut scene_max = Vec3::splat(f32::NEG_INFINITY);
 
-    // Process all meshes - group by transparency
-    for ifc_mesh in &scene_data.meshes {
-        let is_transparent = ifc_mesh.color[3] < 1.0;
+    for ifc_mesh in ifc_meshes {
         let transform = ifc_mesh.get_transform();
         let geometry = &ifc_mesh.geometry;
 
@@ -522,15 +1098,6 @@ fn spawn_meshes_system(
             let world_pos = transform.transform_point(pos);
             entity_min = entity_min.min(world_pos);
             entity_max = entity_max.max(world_pos);
-            scene_min = scene_min.min(world_pos);
-            scene_max = scene_max.max(world_pos);
-        }
-
-        // Add to appropriate batch
-        if is_transparent {
-            transparent_batch.add_mesh(ifc_mesh);
-        } else {
-            opaque_batch.add_mesh(ifc_mesh);
         }
 
         // Spawn lightweight entity for selection/visibility (no mesh, just metadata)
@@ -547,6 +1114,86 @@ fn spawn_meshes_system(
             Transform::default(),
             Visibility::default(),
         ));
+
+        if !entity_min.is_finite() || !entity_max.is_finite() {
+            continue;
+        }
+        scene_min = scene_min.min(entity_min);
+        scene_max = scene_max.max(entity_max);
+
+        let key = if chunk_size > 0.0 {
+            ((entity_min + entity_max) * 0.5 / chunk_size)
+                .floor()
+                .as_ivec3()
+        } else {
+            IVec3::ZERO
+        };
+        let chunk = chunks.entry(key).or_insert_with(|| SpatialChunk {
+            members: Vec::new(),
+            min: Vec3::splat(f32::INFINITY),
+            max: Vec3::splat(f32::NEG_INFINITY),
+        });
+        chunk.members.push(ifc_mesh);
+        chunk.min = chunk.min.min(entity_min);
+        chunk.max = chunk.max.max(entity_max);
+    }
+
+    // Stable order keeps triangle offsets reproducible between rebuilds
+    let mut chunks: Vec<(IVec3, SpatialChunk)> = chunks.into_iter().collect();
+    chunks.sort_by_key(|(key, _)| (key.x, key.y, key.z));
+    log(&format!(
+        "[Bevy] {} meshes in {} spatial chunks",
+        ifc_meshes.len(),
+        chunks.len()
+    ));
+
+    for (_, chunk) in &chunks {
+        spawn_chunk_batches(
+            commands,
+            meshes,
+            materials,
+            triangle_mapping,
+            chunk,
+            prep,
+            lod_settings,
+        );
+    }
+
+    (scene_min.x.is_finite() && scene_max.x.is_finite()).then_some(SceneBounds {
+        min: scene_min,
+        max: scene_max,
+    })
+}
+
+/// Spawn the opaque and transparent batch of one chunk
+fn spawn_chunk_batches(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &BatchMaterials,
+    triangle_mapping: &mut TriangleEntityMapping,
+    chunk: &SpatialChunk,
+    prep: MeshPrep,
+    lod_settings: &LodSettings,
+) {
+    let mesh_count = chunk.members.len();
+
+    // Estimate capacity (rough: 100 verts per mesh average)
+    let vertex_hint = mesh_count * 100;
+    let index_hint = mesh_count * 300;
+
+    let mut opaque_batch = BatchBuilder::with_capacity(vertex_hint, index_hint).prepared(prep);
+    let mut transparent_batch =
+        BatchBuilder::with_capacity(vertex_hint / 10, index_hint / 10).prepared(prep);
+    let mut opaque_members = Vec::with_capacity(mesh_count);
+
+    // Group by transparency
+    for &ifc_mesh in &chunk.members {
+        if ifc_mesh.is_transparent() {
+            transparent_batch.add_mesh(ifc_mesh);
+        } else {
+            opaque_batch.add_mesh(ifc_mesh);
+            opaque_members.push(ifc_mesh);
+        }
     }
 
     // Spawn opaque batch
@@ -558,28 +1205,57 @@ fn spawn_meshes_system(
         ));
 
         // Store triangle-to-entity mapping for picking
-        triangle_mapping.opaque = opaque_batch.take_triangle_mapping();
-
-        let mesh = opaque_batch.build();
-        let material = StandardMaterial {
-            base_color: Color::WHITE,
-            metallic: 0.0,
-            perceptual_roughness: 0.6,
-            reflectance: 0.3,
-            double_sided: true,
-            cull_mode: None,
-            // Use vertex colors
-            ..default()
-        };
-
-        commands.spawn((
-            Mesh3d(meshes.add(mesh)),
-            MeshMaterial3d(materials.add(material)),
+        let triangle_offset = triangle_mapping.opaque.len();
+        triangle_mapping
+            .opaque
+            .extend(opaque_batch.take_triangle_mapping());
+
+        // Coarser copies for distant views
+        let lod_levels =
+            crate::lod::decimate_batch(&opaque_members, chunk.min, chunk.max, lod_settings);
+
+        let bvh = opaque_batch.build_bvh();
+        let full = opaque_batch.build_maskable(meshes);
+        let mesh = full.handle.clone();
+        let mut maskable = vec![full];
+        let mut batch = commands.spawn((
+            Mesh3d(mesh.clone()),
+            MeshMaterial3d(materials.opaque.clone()),
             Transform::default(),
             BatchedMesh {
                 is_transparent: false,
+                triangle_offset,
             },
+            bvh,
         ));
+
+        if !lod_levels.is_empty() {
+            let mut levels = vec![mesh];
+            let mut cell_sizes = vec![0.0];
+            for (cell_size, geometries) in lod_levels {
+                let mut builder = BatchBuilder::with_capacity(0, 0).prepared(prep);
+                for (ifc_mesh, geometry) in opaque_members.iter().zip(&geometries) {
+                    builder.add_geometry(ifc_mesh, geometry);
+                }
+                log(&format!(
+                    "[Bevy] LOD level {}: {} triangles",
+                    levels.len(),
+                    builder.triangle_count()
+                ));
+                let level = builder.build_maskable(meshes);
+                levels.push(level.handle.clone());
+                maskable.push(level);
+                cell_sizes.push(cell_size);
+            }
+            batch.insert(BatchLod {
+                levels,
+                cell_sizes,
+                min: chunk.min,
+                max: chunk.max,
+                current: 0,
+            });
+        }
+        batch.insert(BatchVisibility::new(maskable));
     }
 
     // Spawn transparent batch
@@ -591,48 +1267,64 @@ fn spawn_meshes_system(
         ));
 
         // Store triangle-to-entity mapping for picking
-        triangle_mapping.transparent = transparent_batch.take_triangle_mapping();
-
-        let mesh = transparent_batch.build();
-        let material = StandardMaterial {
-            base_color: Color::WHITE,
-            metallic: 0.0,
-            perceptual_roughness: 0.1,
-            reflectance: 0.5,
-            double_sided: true,
-            cull_mode: None,
-            alpha_mode: AlphaMode::Blend,
-            ..default()
-        };
+        let triangle_offset = triangle_mapping.transparent.len();
+        triangle_mapping
+            .transparent
+            .extend(transparent_batch.take_triangle_mapping());
 
+        let bvh = transparent_batch.build_bvh();
+        let full = transparent_batch.build_maskable(meshes);
         commands.spawn((
-            Mesh3d(meshes.add(mesh)),
-            MeshMaterial3d(materials.add(material)),
+            Mesh3d(full.handle.clone()),
+            MeshMaterial3d(materials.transparent.clone()),
             Transform::default(),
             BatchedMesh {
                 is_transparent: true,
+                triangle_offset,
             },
+            BatchVisibility::new(vec![full]),
+            bvh,
         ));
     }
+}
 
-    // Update scene bounds
-    if scene_min.x.is_finite() && scene_max.x.is_finite() {
-        scene_data.bounds = Some(SceneBounds {
-            min: scene_min,
-            max: scene_max,
-        });
-        log(&format!(
-            "[Bevy] Scene bounds: {:?} to {:?}",
-            scene_min, scene_max
-        ));
+/// Toggle GPU occlusion culling on the main camera
+///
+/// Bevy's occlusion culling needs a depth prepass; both are only added
+/// while the setting is on, unless SSAO keeps the prepass. The normal
+/// prepass comes along so the prepass runs [`ClippedMaterial`]'s clip
+/// discard, see [`crate::section::SectionClip`].
+fn update_occlusion_culling_system(
+    mut commands: Commands,
+    settings: Res<ViewerSettings>,
+    cameras: Query<
+        (
+            Entity,
+            Has<OcclusionCulling>,
+            Has<ScreenSpaceAmbientOcclusion>,
+        ),
+        With<crate::camera::MainCamera>,
+    >,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    for (camera, enabled, ssao) in cameras.iter() {
+        if settings.occlusion_culling && !enabled {
+            log("[Bevy] Occlusion culling enabled");
+            commands
+                .entity(camera)
+                .insert((DepthPrepass, NormalPrepass, OcclusionCulling));
+        } else if !settings.occlusion_culling && enabled {
+            log("[Bevy] Occlusion culling disabled");
+            let mut camera = commands.entity(camera);
+            camera.remove::<OcclusionCulling>();
+            // Ambient occlusion needs the prepass too
+            if !ssao {
+                camera.remove::<(DepthPrepass, NormalPrepass)>();
+            }
+        }
     }
-
-    log(&format!(
-        "[Bevy] Batching complete: {} meshes -> 2 draw calls",
-        mesh_count
-    ));
-
-    scene_data.dirty = false;
 }
 
 /// System to auto-fit camera to scene bounds when first loaded
@@ -675,38 +1367,193 @@ fn auto_fit_camera_system(
     }
 }
 
-/// System to update mesh visibility based on settings
-/// Note: With batched rendering, per-entity visibility requires rebuilding the batch.
-/// For now, this is a no-op - visibility changes require scene reload.
-/// TODO: Implement dynamic visibility via vertex color alpha or shader.
+/// Filters last applied to the batches
+#[derive(Default, PartialEq)]
+struct AppliedVisibility {
+    hidden: rustc_hash::FxHashSet<u64>,
+    isolated: Option<rustc_hash::FxHashSet<u64>>,
+    storey: Option<String>,
+    hidden_storeys: Vec<String>,
+    hidden_spaces: rustc_hash::FxHashSet<u64>,
+    /// Entities kept opaque in x-ray mode
+    xray: Option<rustc_hash::FxHashSet<u64>>,
+}
+
+/// Translucent copy of a batch drawing the entities x-ray mode fades out
+///
+/// The copy masks the complement of its source batch, so each entity is
+/// drawn by exactly one of the two. It shares the source's triangle order,
+/// so it stays pickable through the same [`TriangleEntityMapping`] offset.
+#[derive(Component)]
+pub struct XrayGhost {
+    pub(crate) source: Entity,
+    pub(crate) mesh: MaskableMesh,
+}
+
+/// System to apply hide, isolate and storey filters and x-ray mode to
+/// batched meshes
+///
+/// Index buffers are re-masked when the filters or the entity list change;
+/// new batches (streamed chunks, rebuilt scenes) are masked as they appear.
+/// In x-ray mode every batch also gets an [`XrayGhost`] while the focus set
+/// stays in the opaque batch.
+#[allow(clippy::too_many_arguments)]
 fn update_mesh_visibility_system(
+    mut commands: Commands,
     settings: Res<ViewerSettings>,
-    _query: Query<(&IfcEntity, &mut Visibility)>,
+    scene_data: Res<IfcSceneData>,
+    selection: Res<crate::picking::SelectionState>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    batch_materials: Res<BatchMaterials>,
+    mut batches: Query<(Entity, &mut BatchVisibility, &BatchedMesh, &BatchBvh)>,
+    mut ghosts: Query<(Entity, &mut XrayGhost)>,
+    mut entities: Query<(&IfcEntity, &mut Visibility)>,
+    mut applied: Local<AppliedVisibility>,
 ) {
-    if !settings.is_changed() {
-        // With batched meshes, individual entity visibility would require:
-        // 1. Rebuilding the batch (expensive), or
-        // 2. Custom shader with visibility buffer, or
-        // 3. Setting vertex alpha to 0 (requires mesh mutation)
-        // For now, visibility is handled at scene load time only.
+    let xray_changed = (settings.is_changed() || scene_data.is_changed() || selection.is_changed())
+        && {
+            let xray = settings
+                .xray
+                .as_ref()
+                .map(|focus| focus.entities(&scene_data.entities, &selection));
+            let changed = xray != applied.xray;
+            applied.xray = xray;
+            changed
+        };
+    let filters_changed = settings.is_changed()
+        && (settings.hidden_entities != applied.hidden
+            || settings.isolated_entities != applied.isolated
+            || settings.storey_filter != applied.storey
+            || settings.hidden_storeys != applied.hidden_storeys
+            || settings.hidden_spaces != applied.hidden_spaces);
+    let refresh_all = filters_changed || xray_changed || scene_data.is_changed();
+    if filters_changed {
+        applied.hidden = settings.hidden_entities.clone();
+        applied.isolated = settings.isolated_entities.clone();
+        applied.storey = settings.storey_filter.clone();
+        applied.hidden_storeys = settings.hidden_storeys.clone();
+        applied.hidden_spaces = settings.hidden_spaces.clone();
+    }
+
+    // Storeys are only needed while filtering by them
+    let storeys: FxHashMap<u64, Vec<&str>> = if settings.filters_by_storey() {
+        scene_data
+            .entities
+            .iter()
+            .map(|e| {
+                let names = e
+                    .storey
+                    .iter()
+                    .chain(&e.referenced_storeys)
+                    .map(|s| &**s)
+                    .collect();
+                (e.id, names)
+            })
+            .collect()
+    } else {
+        FxHashMap::default()
+    };
+    let is_visible =
+        |id: u64| settings.is_entity_visible(id, storeys.get(&id).map_or(&[][..], Vec::as_slice));
+    let focus = applied.xray.as_ref();
+    let in_focus = |id: u64| focus.is_none_or(|focus| focus.contains(&id));
+    let filtering = settings.has_visibility_filter() || focus.is_some();
+
+    let mut updated = 0;
+    for (_, mut batch, _, _) in batches.iter_mut() {
+        if !(refresh_all || batch.is_added()) || (!filtering && !batch.masked) {
+            continue;
+        }
+        let mut masked = false;
+        for maskable in &mut batch.meshes {
+            masked |= maskable.apply_mask(&mut meshes, |id| is_visible(id) && in_focus(id));
+        }
+        batch.masked = masked;
+        updated += 1;
+    }
+
+    // Ghosts draw what the focus set leaves out
+    if let Some(focus) = focus {
+        let mut with_ghost = rustc_hash::FxHashSet::default();
+        for (_, mut ghost) in ghosts.iter_mut() {
+            with_ghost.insert(ghost.source);
+            if refresh_all {
+                ghost
+                    .mesh
+                    .apply_mask(&mut meshes, |id| is_visible(id) && !focus.contains(&id));
+            }
+        }
+
+        let material = batch_materials.ghost.clone();
+        let mut spawned = 0;
+        for (entity, batch, batched_mesh, bvh) in batches.iter() {
+            if with_ghost.contains(&entity) {
+                continue;
+            }
+            let Some(full) = batch.meshes.first() else {
+                continue;
+            };
+            let Some(mut mesh) = full.duplicate(&mut meshes) else {
+                continue;
+            };
+            mesh.apply_mask(&mut meshes, |id| is_visible(id) && !focus.contains(&id));
+            commands.spawn((
+                Mesh3d(mesh.handle.clone()),
+                MeshMaterial3d(material.clone()),
+                Transform::default(),
+                BatchedMesh {
+                    is_transparent: batched_mesh.is_transparent,
+                    triangle_offset: batched_mesh.triangle_offset,
+                },
+                bvh.clone(),
+                XrayGhost {
+                    source: entity,
+                    mesh,
+                },
+            ));
+            spawned += 1;
+        }
+        if spawned > 0 {
+            log(&format!(
+                "[Bevy] X-ray ghosts spawned for {} batches",
+                spawned
+            ));
+        }
+    } else if xray_changed {
+        for (entity, _) in ghosts.iter() {
+            commands.entity(entity).despawn();
+        }
+    }
+
+    if refresh_all {
+        for (entity, mut visibility) in entities.iter_mut() {
+            let target = if is_visible(entity.id) {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            };
+            visibility.set_if_neq(target);
+        }
+    }
+    if updated > 0 {
+        log(&format!("[Bevy] Visibility applied to {} batches", updated));
     }
 }
 
-/// System to update mesh selection highlighting
-/// Note: With batched rendering, per-entity selection requires custom shaders.
-/// TODO: Implement selection via outline post-process or stencil buffer.
-fn update_mesh_selection_system(
-    selection: Res<crate::picking::SelectionState>,
-    _materials: ResMut<Assets<StandardMaterial>>,
-    _query: Query<(&IfcEntity, &MeshMaterial3d<StandardMaterial>)>,
-) {
-    if !selection.is_changed() {
-        // With batched meshes, per-entity selection highlighting would require:
-        // 1. Custom shader with entity ID buffer, or
-        // 2. Outline post-processing effect, or
-        // 3. Separate unbatched mesh for selected entities
-        // For now, selection state is tracked but not visually shown.
-        // The Yew UI still shows selection in the hierarchy panel.
+/// System to poll x-ray mode from Yew
+///
+/// Hide, isolate and storey filters come with the scene timestamp in
+/// [`crate::poll_scene_changes`].
+fn poll_xray_system(mut settings: ResMut<ViewerSettings>, mut frame: Local<u32>) {
+    *frame = frame.wrapping_add(1);
+    if !frame.is_multiple_of(10) {
+        return;
+    }
+    if let Some(xray) = crate::storage::load_xray() {
+        let focus = xray.focus();
+        if focus != settings.xray {
+            settings.xray = focus;
+        }
     }
 }
 
@@ -747,77 +1594,6 @@ fn poll_focus_command_system(
     }
 }
 
-/// Get default color for IFC entity type
-pub fn get_default_color(entity_type: &str) -> [f32; 4] {
-    // Convert to uppercase for case-insensitive matching
-    let upper = entity_type.to_uppercase();
-
-    if upper.contains("WALL") {
-        // Walls - warm beige/cream
-        [0.92, 0.85, 0.75, 1.0]
-    } else if upper.contains("SLAB") {
-        // Slabs/floors - concrete gray
-        [0.75, 0.73, 0.70, 1.0]
-    } else if upper.contains("ROOF") {
-        // Roofs - terracotta/reddish
-        [0.72, 0.55, 0.45, 1.0]
-    } else if upper.contains("BEAM") || upper.contains("COLUMN") || upper.contains("MEMBER") {
-        // Structural elements - steel blue-gray
-        [0.60, 0.65, 0.72, 1.0]
-    } else if upper.contains("DOOR") {
-        // Doors - rich wood brown
-        [0.55, 0.35, 0.20, 1.0]
-    } else if upper.contains("WINDOW") || upper.contains("CURTAINWALL") {
-        // Windows/curtain walls - blue glass (semi-transparent)
-        [0.5, 0.7, 0.85, 0.35]
-    } else if upper.contains("STAIR") || upper.contains("RAMP") {
-        // Stairs/ramps - warm gray
-        [0.65, 0.62, 0.58, 1.0]
-    } else if upper.contains("RAILING") {
-        // Railings - dark metallic
-        [0.35, 0.35, 0.38, 1.0]
-    } else if upper.contains("FURNITURE") || upper.contains("FURNISHING") {
-        // Furniture - warm wood
-        [0.65, 0.45, 0.28, 1.0]
-    } else if upper.contains("SPACE") {
-        // Space - very light blue, semi-transparent
-        [0.8, 0.85, 0.95, 0.12]
-    } else if upper.contains("PLATE") {
-        // Plates - steel
-        [0.68, 0.70, 0.75, 1.0]
-    } else if upper.contains("COVERING") {
-        // Coverings - light warm gray
-        [0.82, 0.80, 0.76, 1.0]
-    } else if upper.contains("FOOTING") || upper.contains("PILE") {
-        // Foundations - dark concrete
-        [0.55, 0.53, 0.50, 1.0]
-    } else if upper.contains("PROXY") {
-        // Building element proxies - purple tint
-        [0.70, 0.65, 0.75, 1.0]
-    } else if upper.contains("FLOW") || upper.contains("DUCT") || upper.contains("PIPE") {
-        // MEP flow elements - green tint
-        [0.55, 0.70, 0.58, 1.0]
-    } else if upper.contains("ELECTRIC") || upper.contains("ENERGY") {
-        // Electrical/energy elements - yellow tint
-        [0.75, 0.72, 0.45, 1.0]
-    } else if upper.contains("SANITARY") || upper.contains("FIRE") {
-        // Plumbing fixtures - white ceramic
-        [0.92, 0.92, 0.95, 1.0]
-    } else if upper.contains("SHADING") {
-        // Shading devices - dark blue-gray
-        [0.45, 0.48, 0.55, 0.8]
-    } else if upper.contains("TRANSPORT") {
-        // Transport elements (elevators, etc) - dark gray
-        [0.40, 0.40, 0.42, 1.0]
-    } else if upper.contains("GEOGRAPHIC") || upper.contains("VIRTUAL") {
-        // Geographic/virtual - light green, semi-transparent
-        [0.75, 0.85, 0.75, 0.25]
-    } else {
-        // Default - neutral warm gray
-        [0.75, 0.72, 0.70, 1.0]
-    }
-}
-
 /// Compute flat normals from triangle positions and indices
 fn compute_flat_normals(positions: &[[f32; 3]], indices: &[u32]) -> Vec<[f32; 3]> {
     let mut normals = vec![[0.0f32, 0.0, 0.0]; positions.len()];
@@ -0,0 +1,297 @@
+//! Mini-map - top-down overview in a corner of the viewport
+//!
+//! A second, orthographic camera looks straight down on the whole model and
+//! draws into a square viewport in the bottom-right corner, over the main
+//! view. It renders into the window itself rather than into a texture: the
+//! web and native hosts bring their own UI, and without bevy_ui there would
+//! be nothing to show a texture with. The footprint of the main view is
+//! drawn on a render layer only the mini-map camera sees. That camera must
+//! not clear, or it would wipe the main view, so a backdrop plane on the
+//! same layer stands in for its background.
+//!
+//! Clicking the mini-map moves the camera target to the clicked point.
+
+use crate::camera::{CameraController, CameraInputSet, MainCamera, OverlayInputSet};
+use crate::{IfcSceneData, ViewerSettings};
+use bevy::camera::visibility::RenderLayers;
+use bevy::camera::{ScalingMode, Viewport};
+use bevy::prelude::*;
+use bevy::render::view::Msaa;
+
+/// Render layer of the mini-map backdrop and view marker
+pub const MINIMAP_LAYER: usize = 1;
+/// Edge length of the mini-map, in logical pixels
+const MINIMAP_SIZE_PX: f32 = 180.0;
+/// Gap between the mini-map and the viewport edges, in logical pixels
+const MINIMAP_MARGIN_PX: f32 = 12.0;
+/// Room around the model footprint
+const MINIMAP_PADDING: f32 = 1.1;
+
+/// Mini-map plugin
+pub struct MinimapPlugin;
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_gizmo_group::<MinimapGizmos>()
+            .add_systems(Startup, setup_minimap)
+            .add_systems(Update, minimap_click_system.in_set(OverlayInputSet))
+            .add_systems(
+                Update,
+                (update_minimap_camera_system, draw_minimap_marker_system)
+                    .chain()
+                    .after(CameraInputSet),
+            );
+    }
+}
+
+/// Gizmos drawn only in the mini-map
+#[derive(Default, Reflect, GizmoConfigGroup)]
+pub struct MinimapGizmos;
+
+/// Marker component for the mini-map camera
+#[derive(Component)]
+pub struct MinimapCamera;
+
+/// Plane behind the model in the mini-map
+#[derive(Component)]
+struct MinimapBackdrop;
+
+fn setup_minimap(
+    mut commands: Commands,
+    mut gizmo_config: ResMut<GizmoConfigStore>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    settings: Res<ViewerSettings>,
+) {
+    let (config, _) = gizmo_config.config_mut::<MinimapGizmos>();
+    config.render_layers = RenderLayers::layer(MINIMAP_LAYER);
+    // Stay on top of the roofs
+    config.depth_bias = -1.0;
+    config.line.width = 2.0;
+
+    commands.spawn((
+        Camera3d::default(),
+        Camera {
+            order: 1,
+            is_active: false,
+            clear_color: ClearColorConfig::None,
+            ..default()
+        },
+        Projection::Orthographic(OrthographicProjection::default_3d()),
+        RenderLayers::from_layers(&[0, MINIMAP_LAYER]),
+        MinimapCamera,
+    ));
+
+    commands.spawn((
+        Mesh3d(meshes.add(Plane3d::default().mesh().size(1.0, 1.0))),
+        MeshMaterial3d(materials.add(StandardMaterial {
+            base_color: settings.theme.minimap_color(),
+            unlit: true,
+            ..default()
+        })),
+        Transform::default(),
+        Visibility::Hidden,
+        RenderLayers::layer(MINIMAP_LAYER),
+        MinimapBackdrop,
+    ));
+}
+
+/// Fit the mini-map camera and backdrop to the model and place its viewport
+/// in the corner of the main view
+///
+/// `fitted` holds the scene bounds the camera was last fitted to.
+#[allow(clippy::type_complexity)]
+fn update_minimap_camera_system(
+    settings: Res<ViewerSettings>,
+    scene_data: Res<IfcSceneData>,
+    main_camera: Query<(&Camera, &Msaa), (With<MainCamera>, Without<MinimapCamera>)>,
+    mut minimap: Query<
+        (&mut Camera, &mut Msaa, &mut Projection, &mut Transform),
+        (With<MinimapCamera>, Without<MinimapBackdrop>),
+    >,
+    mut backdrop: Query<
+        (
+            &mut Transform,
+            &mut Visibility,
+            &MeshMaterial3d<StandardMaterial>,
+        ),
+        With<MinimapBackdrop>,
+    >,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut fitted: Local<Option<(Vec3, Vec3)>>,
+) {
+    let (Ok((main, main_msaa)), Ok((mut camera, mut msaa, mut projection, mut transform))) =
+        (main_camera.single(), minimap.single_mut())
+    else {
+        return;
+    };
+    let Ok((mut backdrop_transform, mut backdrop_visibility, material)) = backdrop.single_mut()
+    else {
+        return;
+    };
+
+    // The viewport sits inside the main camera's, in physical pixels
+    let viewport = main
+        .physical_viewport_rect()
+        .zip(main.target_scaling_factor())
+        .and_then(|(main_rect, scale)| {
+            let size = (MINIMAP_SIZE_PX * scale) as u32;
+            let margin = (MINIMAP_MARGIN_PX * scale) as u32;
+            let view = main_rect.size();
+            // Leave most of a small view to the model
+            (view.x >= size * 3 && view.y >= size * 2).then(|| Viewport {
+                physical_position: main_rect.max - UVec2::splat(size + margin),
+                physical_size: UVec2::splat(size),
+                ..default()
+            })
+        });
+    let active = settings.show_minimap && scene_data.bounds.is_some() && viewport.is_some();
+    if camera.is_active != active {
+        camera.is_active = active;
+        *backdrop_visibility = if active {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+    if !active {
+        return;
+    }
+    let placement = |v: &Viewport| (v.physical_position, v.physical_size);
+    if camera.viewport.as_ref().map(placement) != viewport.as_ref().map(placement) {
+        camera.viewport = viewport;
+    }
+    // Cameras sharing a target have to agree on MSAA
+    if *msaa != *main_msaa {
+        *msaa = *main_msaa;
+    }
+    if settings.is_changed() {
+        if let Some(material) = materials.get_mut(&material.0) {
+            material.base_color = settings.theme.minimap_color();
+        }
+    }
+
+    let Some(bounds) = &scene_data.bounds else {
+        return;
+    };
+    if *fitted == Some((bounds.min, bounds.max)) {
+        return;
+    }
+    *fitted = Some((bounds.min, bounds.max));
+    let center = (bounds.min + bounds.max) * 0.5;
+    let size = bounds.max - bounds.min;
+    let extent = size.x.max(size.z).max(1.0) * MINIMAP_PADDING;
+
+    // Looking down with model +Y (world -Z) up
+    *transform = Transform::from_xyz(center.x, bounds.max.y + 1.0, center.z)
+        .looking_at(Vec3::new(center.x, bounds.min.y, center.z), Vec3::NEG_Z);
+    *projection = Projection::Orthographic(OrthographicProjection {
+        near: 0.0,
+        far: size.y + 3.0,
+        scaling_mode: ScalingMode::Fixed {
+            width: extent,
+            height: extent,
+        },
+        ..OrthographicProjection::default_3d()
+    });
+    *backdrop_transform = Transform::from_xyz(center.x, bounds.min.y - 1.0, center.z)
+        .with_scale(Vec3::new(extent, 1.0, extent));
+}
+
+/// Outline where the main view meets the ground at the camera target, and
+/// the camera's position looking at it
+fn draw_minimap_marker_system(
+    controller: Res<CameraController>,
+    main_camera: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    minimap: Query<&Camera, With<MinimapCamera>>,
+    mut gizmos: Gizmos<MinimapGizmos>,
+) {
+    if !minimap.single().is_ok_and(|camera| camera.is_active) {
+        return;
+    }
+    let Ok((camera, camera_transform)) = main_camera.single() else {
+        return;
+    };
+    let Some(rect) = camera.logical_viewport_rect() else {
+        return;
+    };
+    let ground = controller.target.y;
+    // Rays reaching the horizon are cut off a few orbit distances out
+    let reach = controller.distance * 4.0;
+    let on_ground = |corner: Vec2| {
+        let ray = camera.viewport_to_world(camera_transform, corner).ok()?;
+        let t = (ground - ray.origin.y) / ray.direction.y;
+        let t = if t > 0.0 && t.is_finite() {
+            t.min(reach)
+        } else {
+            reach
+        };
+        let point = ray.get_point(t);
+        Some(Vec3::new(point.x, ground, point.z))
+    };
+    let corners = [
+        rect.min,
+        Vec2::new(rect.max.x, rect.min.y),
+        rect.max,
+        Vec2::new(rect.min.x, rect.max.y),
+    ];
+    let Some(footprint) = corners
+        .map(on_ground)
+        .into_iter()
+        .collect::<Option<Vec<_>>>()
+    else {
+        return;
+    };
+
+    let color = Color::srgb(1.0, 0.8, 0.1);
+    gizmos.linestrip(footprint.iter().copied().chain([footprint[0]]), color);
+    let eye = camera_transform.translation().with_y(ground);
+    let target = controller.target;
+    gizmos.line(eye, target, color.with_alpha(0.6));
+    gizmos.circle(
+        Isometry3d::new(eye, Quat::from_rotation_x(std::f32::consts::FRAC_PI_2)),
+        controller.distance * 0.03,
+        color,
+    );
+}
+
+/// Move the camera target to a point clicked on the mini-map
+///
+/// Runs before the camera input, which leaves the pointer alone while it
+/// is over the mini-map.
+fn minimap_click_system(
+    mouse_button: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window>,
+    minimap: Query<(&Camera, &GlobalTransform), With<MinimapCamera>>,
+    mut controller: ResMut<CameraController>,
+) {
+    let cursor = windows.single().ok().and_then(Window::cursor_position);
+    let hit = minimap.single().ok().and_then(|(camera, transform)| {
+        let cursor = cursor?;
+        let over = camera.is_active
+            && camera
+                .logical_viewport_rect()
+                .is_some_and(|rect| rect.contains(cursor));
+        over.then_some((camera, transform, cursor))
+    });
+    let over = hit.is_some() && !controller.is_dragging;
+    if over {
+        controller.pointer_over_overlay = true;
+    }
+    if !over || !mouse_button.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let Some((camera, transform, cursor)) = hit else {
+        return;
+    };
+    let Ok(ray) = camera.viewport_to_world(transform, cursor) else {
+        return;
+    };
+    let Some(t) = ray.intersect_plane(controller.target, InfinitePlane3d::new(Vec3::Y)) else {
+        return;
+    };
+    let point = ray.get_point(t);
+    let mut view = controller.to_storage();
+    view.target = [point.x, controller.target.y, point.z].map(f64::from);
+    controller.animate_to(&view);
+}
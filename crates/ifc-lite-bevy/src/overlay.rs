@@ -0,0 +1,241 @@
+//! Orientation overlay - origin axes, true north arrow, plan scale bar and
+//! IfcGrid axes
+//!
+//! Axes, the north arrow and grid lines are drawn with gizmos. The scale bar
+//! and the grid axis tags need text, so the bar length and the tag positions
+//! are published to storage and rendered by the UI.
+
+use crate::camera::{CameraController, MainCamera};
+use crate::storage::{GridLabelStorage, ScaleBarStorage};
+use crate::{IfcSceneData, ViewerSettings};
+use bevy::prelude::*;
+
+/// Target on-screen width of the scale bar in pixels
+const SCALE_BAR_MAX_WIDTH: f32 = 120.0;
+
+/// Overlay plugin
+pub struct OverlayPlugin;
+
+impl Plugin for OverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ScaleBarState>()
+            .init_resource::<GridLabelState>()
+            .add_systems(
+                Update,
+                (
+                    poll_overlay_settings,
+                    draw_origin_axes,
+                    draw_north_arrow,
+                    draw_grid_axes,
+                    update_scale_bar,
+                    update_grid_labels,
+                )
+                    .chain()
+                    .after(crate::camera::CameraInputSet),
+            );
+    }
+}
+
+/// Last scale bar published to storage
+#[derive(Resource, Default)]
+pub struct ScaleBarState(pub Option<ScaleBarStorage>);
+
+/// Grid axis tags last published to storage
+#[derive(Resource, Default)]
+pub struct GridLabelState(pub Vec<GridLabelStorage>);
+
+/// Poll overlay toggles from localStorage
+#[allow(unused_mut, unused_variables)]
+fn poll_overlay_settings(mut settings: ResMut<ViewerSettings>, mut frame: Local<u32>) {
+    #[cfg(target_arch = "wasm32")]
+    {
+        *frame = frame.wrapping_add(1);
+        if !frame.is_multiple_of(30) {
+            return;
+        }
+        if let Some(overlay) = crate::storage::load_overlay() {
+            if settings.show_axes != overlay.origin_axes
+                || settings.show_north_arrow != overlay.north_arrow
+                || settings.show_scale_bar != overlay.scale_bar
+                || settings.show_minimap != overlay.minimap
+                || settings.show_view_cube != overlay.view_cube
+                || settings.show_grid_axes != overlay.grid_axes
+                || settings.show_stats != overlay.stats
+            {
+                settings.show_axes = overlay.origin_axes;
+                settings.show_north_arrow = overlay.north_arrow;
+                settings.show_scale_bar = overlay.scale_bar;
+                settings.show_minimap = overlay.minimap;
+                settings.show_view_cube = overlay.view_cube;
+                settings.show_grid_axes = overlay.grid_axes;
+                settings.show_stats = overlay.stats;
+            }
+        }
+    }
+}
+
+/// Length of overlay gizmos in world units, so they stay readable at any zoom
+fn gizmo_length(controller: &CameraController) -> f32 {
+    controller.distance * 0.08
+}
+
+/// Draw IFC X (red), Y (green) and Z (blue) axes at the model origin
+fn draw_origin_axes(
+    mut gizmos: Gizmos,
+    settings: Res<ViewerSettings>,
+    controller: Res<CameraController>,
+) {
+    if !settings.show_axes {
+        return;
+    }
+    let length = gizmo_length(&controller);
+    // IFC is Z-up; Bevy is Y-up with IFC +Y along -Z
+    gizmos.arrow(Vec3::ZERO, Vec3::X * length, Color::srgb(0.9, 0.2, 0.2));
+    gizmos.arrow(Vec3::ZERO, Vec3::NEG_Z * length, Color::srgb(0.2, 0.8, 0.2));
+    gizmos.arrow(Vec3::ZERO, Vec3::Y * length, Color::srgb(0.2, 0.4, 0.9));
+}
+
+/// Draw an arrow pointing to true north on the ground plane, with an "N" at its tip
+fn draw_north_arrow(
+    mut gizmos: Gizmos,
+    settings: Res<ViewerSettings>,
+    scene_data: Res<IfcSceneData>,
+    controller: Res<CameraController>,
+) {
+    if !settings.show_north_arrow {
+        return;
+    }
+    let Some(angle) = scene_data.true_north else {
+        return;
+    };
+
+    let length = gizmo_length(&controller) * 1.5;
+    // Rotate model +Y counter-clockwise by the true north angle
+    let north = Vec3::new(-angle.sin(), 0.0, -angle.cos());
+    let east = Vec3::new(-north.z, 0.0, north.x);
+    let color = Color::srgb(0.95, 0.75, 0.1);
+
+    gizmos.arrow(Vec3::ZERO, north * length, color);
+
+    // Letter "N" just beyond the tip, upright when viewed with north up
+    let size = length * 0.15;
+    let base = north * (length + size * 0.5);
+    let corner = |x: f32, y: f32| base + east * (x * size) + north * (y * size);
+    gizmos.linestrip(
+        [
+            corner(-0.5, 0.0),
+            corner(-0.5, 1.0),
+            corner(0.5, 0.0),
+            corner(0.5, 1.0),
+        ],
+        color,
+    );
+}
+
+/// Draw IfcGrid axes as thin lines at their grid's elevation
+fn draw_grid_axes(
+    mut gizmos: Gizmos,
+    settings: Res<ViewerSettings>,
+    scene_data: Res<IfcSceneData>,
+) {
+    if !settings.show_grid_axes {
+        return;
+    }
+    let color = Color::srgba(0.3, 0.55, 0.85, 0.8);
+    for axis in &scene_data.grid_axes {
+        gizmos.line(
+            scene_data.file_to_world(axis.start),
+            scene_data.file_to_world(axis.end),
+            color,
+        );
+    }
+}
+
+/// Publish where the UI should put grid axis tags, one at each axis end
+fn update_grid_labels(
+    settings: Res<ViewerSettings>,
+    scene_data: Res<IfcSceneData>,
+    cameras: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut state: ResMut<GridLabelState>,
+) {
+    let labels: Vec<GridLabelStorage> = match cameras.single() {
+        Ok((camera, camera_transform)) if settings.show_grid_axes => scene_data
+            .grid_axes
+            .iter()
+            .flat_map(|axis| [(axis, axis.start), (axis, axis.end)])
+            .filter_map(|(axis, point)| {
+                let anchor = camera
+                    .world_to_viewport(camera_transform, scene_data.file_to_world(point))
+                    .ok()?;
+                Some(GridLabelStorage {
+                    tag: axis.tag.clone(),
+                    x: anchor.x,
+                    y: anchor.y,
+                })
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    // Only touch storage when a tag visibly moves
+    let moved = labels.len() != state.0.len()
+        || labels.iter().zip(&state.0).any(|(new, old)| {
+            new.tag != old.tag || (new.x - old.x).abs() > 0.5 || (new.y - old.y).abs() > 0.5
+        });
+    if moved {
+        crate::storage::save_grid_labels(&labels);
+        state.0 = labels;
+    }
+}
+
+/// Publish a scale bar while the camera looks down in plan view
+fn update_scale_bar(
+    settings: Res<ViewerSettings>,
+    controller: Res<CameraController>,
+    camera: Query<&Camera, With<MainCamera>>,
+    mut state: ResMut<ScaleBarState>,
+) {
+    let viewport_height = camera
+        .single()
+        .ok()
+        .and_then(|c| c.logical_viewport_size())
+        .map(|size| size.y);
+
+    let scale_bar = match viewport_height {
+        Some(height) if settings.show_scale_bar && controller.is_plan_view() => {
+            let per_pixel = controller.world_units_per_pixel(height);
+            let length = nice_length(per_pixel * SCALE_BAR_MAX_WIDTH);
+            Some(ScaleBarStorage {
+                length,
+                width_px: length / per_pixel,
+            })
+        }
+        _ => None,
+    };
+
+    // Only touch storage when the bar visibly changes
+    let changed = match (&state.0, &scale_bar) {
+        (Some(old), Some(new)) => {
+            old.length != new.length || (old.width_px - new.width_px).abs() > 0.5
+        }
+        (None, None) => false,
+        _ => true,
+    };
+    if changed {
+        crate::storage::save_scale_bar(scale_bar.as_ref());
+        state.0 = scale_bar;
+    }
+}
+
+/// Largest 1, 2 or 5 × 10ⁿ that does not exceed `max`
+fn nice_length(max: f32) -> f32 {
+    if max <= 0.0 || !max.is_finite() {
+        return 0.0;
+    }
+    let magnitude = 10f32.powf(max.log10().floor());
+    let step = [5.0, 2.0, 1.0]
+        .into_iter()
+        .find(|step| step * magnitude <= max)
+        .unwrap_or(1.0);
+    step * magnitude
+}
@@ -0,0 +1,225 @@
+//! Palette - per-entity color overrides on batched meshes
+//!
+//! The UI groups entities (by type object, property value, ...) and gives
+//! every group a color; [`EntityColorMapping`] holds the result along with
+//! its legend. Batches are recolored in place by rewriting the vertex colors
+//! of each entity's vertex range. The model colors are stashed on the batch
+//! the first time it is recolored and put back once the mapping is empty.
+//! Alpha is left alone, so glazing stays translucent in any palette.
+
+use crate::mesh::BatchVisibility;
+use crate::storage::{PaletteGroupStorage, PaletteStorage};
+use crate::EntityInfo;
+use bevy::mesh::VertexAttributeValues;
+use bevy::prelude::*;
+use rustc_hash::FxHashMap;
+use std::collections::BTreeMap;
+
+/// Hue step between consecutive groups, in degrees
+const GOLDEN_ANGLE: f32 = 137.507_77;
+
+/// Palette plugin
+pub struct PalettePlugin;
+
+impl Plugin for PalettePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EntityColorMapping>()
+            .add_systems(Update, (poll_palette_system, apply_palette_system).chain());
+    }
+}
+
+/// What the native UI colors entities by
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorBy {
+    /// Colors from the model
+    #[default]
+    Model,
+    EntityType,
+    Storey,
+}
+
+impl ColorBy {
+    /// Next mode of the toolbar cycle
+    pub fn next(self) -> Self {
+        match self {
+            Self::Model => Self::EntityType,
+            Self::EntityType => Self::Storey,
+            Self::Storey => Self::Model,
+        }
+    }
+}
+
+/// Legend entry of a color mapping
+#[derive(Clone, Debug, PartialEq)]
+pub struct LegendEntry {
+    pub label: String,
+    pub color: [f32; 3],
+    /// Number of entities drawn in this color
+    pub count: usize,
+}
+
+/// Color override per entity and the legend explaining them; empty shows
+/// the model colors
+#[derive(Resource, Default)]
+pub struct EntityColorMapping {
+    pub colors: FxHashMap<u64, [f32; 3]>,
+    pub legend: Vec<LegendEntry>,
+}
+
+impl EntityColorMapping {
+    /// Mapping of palette groups, in legend order
+    pub fn from_groups(groups: &[PaletteGroupStorage]) -> Self {
+        Self {
+            colors: groups
+                .iter()
+                .flat_map(|group| group.entities.iter().map(|&id| (id, group.color)))
+                .collect(),
+            legend: groups
+                .iter()
+                .map(|group| LegendEntry {
+                    label: group.label.clone(),
+                    color: group.color,
+                    count: group.entities.len(),
+                })
+                .collect(),
+        }
+    }
+
+    /// One palette color per distinct label, groups sorted by label
+    pub fn by_label<'a>(
+        entities: &'a [EntityInfo],
+        label: impl Fn(&'a EntityInfo) -> Option<&'a str>,
+    ) -> Self {
+        let mut groups: BTreeMap<&str, Vec<u64>> = BTreeMap::new();
+        for entity in entities {
+            if let Some(label) = label(entity) {
+                groups.entry(label).or_default().push(entity.id);
+            }
+        }
+        let groups: Vec<PaletteGroupStorage> = groups
+            .into_iter()
+            .enumerate()
+            .map(|(i, (label, entities))| PaletteGroupStorage {
+                label: label.to_string(),
+                color: group_color(i),
+                entities,
+            })
+            .collect();
+        Self::from_groups(&groups)
+    }
+
+    /// Mapping for a native color mode
+    pub fn for_mode(entities: &[EntityInfo], mode: ColorBy) -> Self {
+        match mode {
+            ColorBy::Model => Self::default(),
+            ColorBy::EntityType => Self::by_label(entities, |e| Some(&*e.entity_type)),
+            ColorBy::Storey => Self::by_label(entities, |e| e.storey.as_deref()),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.colors.is_empty()
+    }
+}
+
+/// Color of the `index`-th palette group, matching the web UI's palette
+pub fn group_color(index: usize) -> [f32; 3] {
+    let color = Color::hsl((index as f32 * GOLDEN_ANGLE) % 360.0, 0.6, 0.55).to_srgba();
+    [color.red, color.green, color.blue]
+}
+
+/// Vertex colors of a batch before recoloring, one list per mesh in
+/// [`BatchVisibility::meshes`]
+#[derive(Component)]
+struct ModelColors(Vec<Vec<[f32; 4]>>);
+
+/// Poll the palette from localStorage
+#[allow(unused_mut, unused_variables)]
+fn poll_palette_system(
+    mut mapping: ResMut<EntityColorMapping>,
+    mut applied: Local<Option<PaletteStorage>>,
+    mut frame: Local<u32>,
+) {
+    #[cfg(target_arch = "wasm32")]
+    {
+        *frame = frame.wrapping_add(1);
+        if !frame.is_multiple_of(30) {
+            return;
+        }
+        let Some(storage) = crate::storage::load_palette() else {
+            return;
+        };
+        if applied.as_ref() == Some(&storage) {
+            return;
+        }
+        *mapping = EntityColorMapping::from_groups(&storage.groups);
+        *applied = Some(storage);
+    }
+}
+
+/// System to recolor batches when the mapping changes or batches appear
+fn apply_palette_system(
+    mut commands: Commands,
+    mapping: Res<EntityColorMapping>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    batches: Query<(Entity, Ref<BatchVisibility>, Option<&ModelColors>)>,
+) {
+    for (entity, batch, model_colors) in batches.iter() {
+        if !(mapping.is_changed() || batch.is_added()) {
+            continue;
+        }
+
+        if mapping.is_empty() {
+            if let Some(ModelColors(model)) = model_colors {
+                for (maskable, colors) in batch.meshes.iter().zip(model) {
+                    if let Some(mesh) = meshes.get_mut(&maskable.handle) {
+                        mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, colors.clone());
+                    }
+                }
+                commands.entity(entity).remove::<ModelColors>();
+            }
+            continue;
+        }
+
+        let stashed = model_colors.is_none().then(|| {
+            batch
+                .meshes
+                .iter()
+                .map(|maskable| vertex_colors(&meshes, &maskable.handle))
+                .collect::<Vec<_>>()
+        });
+        let model = model_colors.map_or_else(|| stashed.as_ref().unwrap(), |m| &m.0);
+
+        for (maskable, colors) in batch.meshes.iter().zip(model) {
+            let mut recolored = colors.clone();
+            for (entity_id, range) in maskable.vertex_ranges() {
+                let (Some(color), Some(vertices)) =
+                    (mapping.colors.get(&entity_id), recolored.get_mut(range))
+                else {
+                    continue;
+                };
+                for vertex in vertices {
+                    vertex[..3].copy_from_slice(color);
+                }
+            }
+            if let Some(mesh) = meshes.get_mut(&maskable.handle) {
+                mesh.insert_attribute(Mesh::ATTRIBUTE_COLOR, recolored);
+            }
+        }
+
+        if let Some(stashed) = stashed {
+            commands.entity(entity).insert(ModelColors(stashed));
+        }
+    }
+}
+
+/// Current vertex colors of a mesh
+fn vertex_colors(meshes: &Assets<Mesh>, handle: &Handle<Mesh>) -> Vec<[f32; 4]> {
+    match meshes
+        .get(handle)
+        .and_then(|mesh| mesh.attribute(Mesh::ATTRIBUTE_COLOR))
+    {
+        Some(VertexAttributeValues::Float32x4(colors)) => colors.clone(),
+        _ => Vec::new(),
+    }
+}
@@ -1,15 +1,39 @@
 //! Picking and selection system
 //!
 //! Handles raycasting for object selection and hover detection.
-
-use crate::camera::MainCamera;
-use crate::mesh::{BatchedMesh, TriangleEntityMapping};
-use crate::storage::{save_selection, SelectionStorage};
+//!
+//! Batches carry a [`BatchBvh`], so a ray only tests the few triangles
+//! whose boxes it crosses; batches without one fall back to testing every
+//! triangle. Rays read the live index buffer, where hidden entities are
+//! collapsed, so hidden geometry cannot be picked.
+//!
+//! In [`CameraMode::BoxSelect`] a left drag selects by rectangle instead:
+//! entity bounds are projected to screen space and matched in window or
+//! crossing mode depending on the drag direction.
+//!
+//! Once the cursor rests on an entity for [`HOVER_TOOLTIP_DELAY`], the
+//! hovered id and cursor position are published for the UI's tooltip. The
+//! model point under the cursor is published with every hover pick for the
+//! status bar's coordinate readout.
+//! A right click without dragging publishes the entity under the cursor for
+//! the UI's context menu.
+
+use crate::camera::{CameraController, CameraMode, MainCamera};
+use crate::lod::BatchLod;
+use crate::mesh::{BatchBvh, BatchedMesh, EntityBounds, IfcEntity, TriangleEntityMapping};
+use crate::storage::{
+    save_selection, ContextMenuStorage, CursorStorage, HoverStorage, SelectionStorage,
+};
+use crate::IfcSceneData;
 use bevy::math::Affine3A;
+use bevy::mesh::Indices;
 use bevy::prelude::*;
 use bevy::window::PrimaryWindow;
 use rustc_hash::FxHashSet;
 
+/// Seconds the hovered entity must stay the same before its tooltip shows
+pub const HOVER_TOOLTIP_DELAY: f32 = 0.4;
+
 /// Picking plugin
 pub struct PickingPlugin;
 
@@ -17,10 +41,20 @@ impl Plugin for PickingPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<SelectionState>()
             .init_resource::<PickingSettings>()
+            .init_resource::<BoxSelection>()
+            .init_resource::<HoverTooltip>()
             // Run picking after camera input so we can see just_clicked flag
             .add_systems(
                 Update,
-                (picking_system, hover_system)
+                (
+                    box_select_system,
+                    draw_box_selection_system,
+                    picking_system,
+                    hover_system,
+                    hover_tooltip_system,
+                    context_menu_system,
+                )
+                    .chain()
                     .after(crate::camera::CameraPlugin::input_system_set()),
             );
     }
@@ -70,6 +104,15 @@ impl SelectionState {
         self.save();
     }
 
+    /// Select several entities, replacing the selection unless `additive`
+    pub fn select_many(&mut self, ids: impl IntoIterator<Item = u64>, additive: bool) {
+        if !additive {
+            self.selected.clear();
+        }
+        self.selected.extend(ids);
+        self.save();
+    }
+
     /// Clear all selection
     pub fn clear(&mut self) {
         self.selected.clear();
@@ -104,12 +147,200 @@ impl Default for PickingSettings {
     }
 }
 
+/// How a selection rectangle matches entities
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BoxSelectMode {
+    /// Entities lying entirely inside the rectangle
+    Window,
+    /// Entities inside or touching the rectangle
+    Crossing,
+}
+
+impl BoxSelectMode {
+    /// CAD convention: dragging to the right selects by window, dragging to
+    /// the left by crossing
+    pub fn from_drag(start: Vec2, end: Vec2) -> Self {
+        if end.x >= start.x {
+            BoxSelectMode::Window
+        } else {
+            BoxSelectMode::Crossing
+        }
+    }
+}
+
+/// Selection rectangle being dragged, in viewport pixels
+#[derive(Resource, Default)]
+pub struct BoxSelection {
+    /// Drag start, while the button is held
+    pub start: Option<Vec2>,
+    /// Current cursor position
+    pub end: Vec2,
+}
+
+impl BoxSelection {
+    /// Rectangles smaller than this (in pixels) are treated as clicks
+    const MIN_SIZE: f32 = 4.0;
+
+    /// The dragged rectangle, once it is large enough to count
+    pub fn rect(&self) -> Option<Rect> {
+        let rect = Rect::from_corners(self.start?, self.end);
+        (rect.width() >= Self::MIN_SIZE || rect.height() >= Self::MIN_SIZE).then_some(rect)
+    }
+}
+
+/// Box select system - tracks the rectangle and selects on release
+#[allow(clippy::too_many_arguments)]
+fn box_select_system(
+    mouse_button: Res<ButtonInput<MouseButton>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    entities: Query<(&IfcEntity, &EntityBounds, &Visibility)>,
+    mut box_selection: ResMut<BoxSelection>,
+    mut selection: ResMut<SelectionState>,
+    mut camera_controller: ResMut<CameraController>,
+) {
+    if camera_controller.mode != CameraMode::BoxSelect {
+        box_selection.start = None;
+        return;
+    }
+    let Ok(window) = windows.single() else { return };
+    if let Some(cursor_pos) = window.cursor_position() {
+        if mouse_button.just_pressed(MouseButton::Left) {
+            box_selection.start = Some(cursor_pos);
+        }
+        box_selection.end = cursor_pos;
+    }
+    if !mouse_button.just_released(MouseButton::Left) {
+        return;
+    }
+
+    let rect = box_selection.rect();
+    let start = box_selection.start.take();
+    let (Some(rect), Some(start)) = (rect, start) else {
+        // Too small for a rectangle - let the click pick
+        return;
+    };
+    camera_controller.just_clicked = false;
+    let Ok((camera, camera_transform)) = cameras.single() else {
+        return;
+    };
+
+    let mode = BoxSelectMode::from_drag(start, box_selection.end);
+    let ids: Vec<u64> = entities
+        .iter()
+        .filter(|(_, _, visibility)| **visibility != Visibility::Hidden)
+        .filter(|(_, bounds, _)| {
+            screen_bounds(camera, camera_transform, bounds).is_some_and(|screen| match mode {
+                BoxSelectMode::Window => rect.contains(screen.min) && rect.contains(screen.max),
+                BoxSelectMode::Crossing => !rect.intersect(screen).is_empty(),
+            })
+        })
+        .map(|(entity, _, _)| entity.id)
+        .collect();
+
+    let additive = keyboard.any_pressed([
+        KeyCode::ControlLeft,
+        KeyCode::ControlRight,
+        KeyCode::SuperLeft,
+        KeyCode::SuperRight,
+        KeyCode::ShiftLeft,
+        KeyCode::ShiftRight,
+    ]);
+    crate::log(&format!(
+        "[Bevy] Box select ({:?}): {} entities",
+        mode,
+        ids.len()
+    ));
+    selection.select_many(ids, additive);
+}
+
+/// Screen-space rectangle covering an entity's bounding box
+///
+/// `None` if part of the box is behind the camera. Crossing mode compares
+/// against this rectangle, so it can pick up entities whose projected box
+/// touches the selection even where their geometry does not.
+fn screen_bounds(
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+    bounds: &EntityBounds,
+) -> Option<Rect> {
+    if !bounds.min.is_finite() || !bounds.max.is_finite() {
+        return None;
+    }
+    let mut screen = Rect {
+        min: Vec2::splat(f32::INFINITY),
+        max: Vec2::splat(f32::NEG_INFINITY),
+    };
+    for i in 0..8 {
+        let corner = Vec3::new(
+            if i & 1 == 0 {
+                bounds.min.x
+            } else {
+                bounds.max.x
+            },
+            if i & 2 == 0 {
+                bounds.min.y
+            } else {
+                bounds.max.y
+            },
+            if i & 4 == 0 {
+                bounds.min.z
+            } else {
+                bounds.max.z
+            },
+        );
+        let point = camera.world_to_viewport(camera_transform, corner).ok()?;
+        screen = screen.union_point(point);
+    }
+    Some(screen)
+}
+
+/// Draw the selection rectangle just beyond the near plane
+///
+/// Blue for window mode, green for crossing, as in most CAD tools.
+fn draw_box_selection_system(
+    box_selection: Res<BoxSelection>,
+    cameras: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    camera_controller: Res<CameraController>,
+    mut gizmos: Gizmos,
+) {
+    let (Some(rect), Some(start)) = (box_selection.rect(), box_selection.start) else {
+        return;
+    };
+    let Ok((camera, camera_transform)) = cameras.single() else {
+        return;
+    };
+
+    let depth = camera_controller.near * 2.0;
+    let corners = [
+        rect.min,
+        Vec2::new(rect.max.x, rect.min.y),
+        rect.max,
+        Vec2::new(rect.min.x, rect.max.y),
+        rect.min,
+    ];
+    let mut points = Vec::with_capacity(corners.len());
+    for corner in corners {
+        let Ok(ray) = camera.viewport_to_world(camera_transform, corner) else {
+            return;
+        };
+        points.push(ray.get_point(depth));
+    }
+
+    let color = match BoxSelectMode::from_drag(start, box_selection.end) {
+        BoxSelectMode::Window => Color::srgb(0.2, 0.5, 1.0),
+        BoxSelectMode::Crossing => Color::srgb(0.2, 0.85, 0.4),
+    };
+    gizmos.linestrip(points, color);
+}
+
 /// Picking system - handles click selection on batched meshes
 #[allow(clippy::too_many_arguments)]
 fn picking_system(
     keyboard: Res<ButtonInput<KeyCode>>,
     cameras: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
-    batched_meshes: Query<(&BatchedMesh, &GlobalTransform, &Mesh3d)>,
+    batched_meshes: Query<PickableBatch>,
     triangle_mapping: Res<TriangleEntityMapping>,
     meshes: Res<Assets<Mesh>>,
     mut selection: ResMut<SelectionState>,
@@ -120,8 +351,14 @@ fn picking_system(
         return;
     }
 
-    // Use camera controller's click detection (click = press+release without drag)
-    if !camera_controller.just_clicked {
+    // Use camera controller's click detection (click = press+release without drag);
+    // measuring, annotating and walking (teleport) consume clicks themselves
+    if !camera_controller.just_clicked
+        || matches!(
+            camera_controller.mode,
+            CameraMode::Measure | CameraMode::Annotate | CameraMode::Walk
+        )
+    {
         return;
     }
 
@@ -141,27 +378,10 @@ fn picking_system(
     };
 
     // Find closest intersection in batched meshes
-    let mut closest: Option<(u64, f32)> = None;
-
-    for (batched_mesh, transform, mesh_handle) in batched_meshes.iter() {
-        if let Some(mesh) = meshes.get(&mesh_handle.0) {
-            if let Some((distance, triangle_index)) =
-                ray_mesh_intersection_with_triangle(&ray, mesh, transform)
-            {
-                // Look up which entity this triangle belongs to
-                if let Some(entity_id) =
-                    triangle_mapping.get_entity(batched_mesh.is_transparent, triangle_index)
-                {
-                    if closest.map(|(_, d)| distance < d).unwrap_or(true) {
-                        closest = Some((entity_id, distance));
-                    }
-                }
-            }
-        }
-    }
+    let closest = pick_entity(&ray, &batched_meshes, &triangle_mapping, &meshes);
 
     // Update selection based on result
-    if let Some((entity_id, _)) = closest {
+    if let Some(PickHit { entity_id, .. }) = closest {
         let ctrl_pressed = keyboard.pressed(KeyCode::ControlLeft)
             || keyboard.pressed(KeyCode::ControlRight)
             || keyboard.pressed(KeyCode::SuperLeft)
@@ -185,12 +405,15 @@ fn picking_system(
 fn hover_system(
     windows: Query<&Window, With<PrimaryWindow>>,
     cameras: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
-    batched_meshes: Query<(&BatchedMesh, &GlobalTransform, &Mesh3d)>,
+    batched_meshes: Query<PickableBatch>,
     triangle_mapping: Res<TriangleEntityMapping>,
     meshes: Res<Assets<Mesh>>,
     mut selection: ResMut<SelectionState>,
     settings: Res<PickingSettings>,
+    camera_controller: Res<CameraController>,
+    scene_data: Res<IfcSceneData>,
     mut frame_counter: Local<u32>,
+    mut cursor: Local<Option<CursorStorage>>,
 ) {
     if !settings.enabled {
         return;
@@ -203,10 +426,14 @@ fn hover_system(
     }
 
     let Ok(window) = windows.single() else { return };
-    let Some(cursor_pos) = window.cursor_position() else {
+    let cursor_pos = window
+        .cursor_position()
+        .filter(|_| !camera_controller.pointer_over_overlay);
+    let Some(cursor_pos) = cursor_pos else {
         if selection.hovered.is_some() {
             selection.hovered = None;
         }
+        publish_cursor(&mut cursor, None);
         return;
     };
     let Ok((camera, camera_transform)) = cameras.single() else {
@@ -219,32 +446,252 @@ fn hover_system(
     };
 
     // Find closest intersection in batched meshes
-    let mut closest: Option<(u64, f32)> = None;
-
-    for (batched_mesh, transform, mesh_handle) in batched_meshes.iter() {
-        if let Some(mesh) = meshes.get(&mesh_handle.0) {
-            if let Some((distance, triangle_index)) =
-                ray_mesh_intersection_with_triangle(&ray, mesh, transform)
-            {
-                // Look up which entity this triangle belongs to
-                if let Some(entity_id) =
-                    triangle_mapping.get_entity(batched_mesh.is_transparent, triangle_index)
-                {
-                    if closest.map(|(_, d)| distance < d).unwrap_or(true) {
-                        closest = Some((entity_id, distance));
-                    }
-                }
-            }
-        }
-    }
+    let closest = pick_entity(&ray, &batched_meshes, &triangle_mapping, &meshes);
+    publish_cursor(
+        &mut cursor,
+        closest.as_ref().map(|hit| CursorStorage {
+            id: hit.entity_id,
+            point: scene_data.world_to_file(hit.point),
+        }),
+    );
 
     // Update hover state
-    let new_hovered = closest.map(|(id, _)| id);
+    let new_hovered = closest.map(|hit| hit.entity_id);
     if selection.hovered != new_hovered {
         selection.hovered = new_hovered;
     }
 }
 
+/// Publish the point under the cursor when it moved by more than a millimetre
+fn publish_cursor(published: &mut Option<CursorStorage>, next: Option<CursorStorage>) {
+    let changed = match (published.as_ref(), next.as_ref()) {
+        (Some(old), Some(new)) => {
+            old.id != new.id || Vec3::from(old.point).distance_squared(Vec3::from(new.point)) > 1e-6
+        }
+        (None, None) => false,
+        _ => true,
+    };
+    if changed {
+        crate::storage::save_cursor(next.as_ref());
+        *published = next;
+    }
+}
+
+/// Debounce state of the hover tooltip
+#[derive(Resource, Default)]
+pub struct HoverTooltip {
+    /// Entity the cursor rests on, and the time it first did
+    pending: Option<(u64, f32)>,
+    /// Tooltip last published to storage
+    published: Option<HoverStorage>,
+}
+
+/// Publish the hovered entity for the UI tooltip once hover settles
+fn hover_tooltip_system(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    selection: Res<SelectionState>,
+    camera_controller: Res<CameraController>,
+    time: Res<Time>,
+    mut tooltip: ResMut<HoverTooltip>,
+) {
+    let now = time.elapsed_secs();
+    let cursor = windows.single().ok().and_then(|w| w.cursor_position());
+
+    // Dragging the camera or leaving the model hides the tooltip at once
+    let hovered = selection.hovered.filter(|_| !camera_controller.is_dragging);
+    match (hovered, tooltip.pending) {
+        (Some(id), Some((pending, _))) if id == pending => {}
+        (Some(id), _) => tooltip.pending = Some((id, now)),
+        (None, _) => tooltip.pending = None,
+    }
+
+    let next = match (tooltip.pending, cursor) {
+        (Some((id, since)), Some(cursor)) if now - since >= HOVER_TOOLTIP_DELAY => {
+            Some(HoverStorage {
+                id,
+                x: cursor.x,
+                y: cursor.y,
+            })
+        }
+        _ => None,
+    };
+
+    // Only touch storage when the tooltip visibly changes
+    let changed = match (&tooltip.published, &next) {
+        (Some(old), Some(new)) => {
+            old.id != new.id || (old.x - new.x).abs() > 0.5 || (old.y - new.y).abs() > 0.5
+        }
+        (None, None) => false,
+        _ => true,
+    };
+    if changed {
+        crate::storage::save_hover(next.as_ref());
+        tooltip.published = next;
+    }
+}
+
+/// Open the UI context menu for the entity under a right click
+#[allow(clippy::too_many_arguments)]
+fn context_menu_system(
+    mouse_button: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    cameras: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    batched_meshes: Query<PickableBatch>,
+    triangle_mapping: Res<TriangleEntityMapping>,
+    meshes: Res<Assets<Mesh>>,
+    settings: Res<PickingSettings>,
+    camera_controller: Res<CameraController>,
+    mut press_pos: Local<Option<Vec2>>,
+) {
+    if !settings.enabled {
+        return;
+    }
+    let Ok(window) = windows.single() else { return };
+    let cursor = window
+        .cursor_position()
+        .filter(|_| !camera_controller.pointer_over_overlay);
+
+    if mouse_button.just_pressed(MouseButton::Right) {
+        *press_pos = cursor;
+    }
+    if !mouse_button.just_released(MouseButton::Right) {
+        return;
+    }
+    // Same threshold as the camera's click detection
+    let (Some(start), Some(cursor)) = (press_pos.take(), cursor) else {
+        return;
+    };
+    if start.distance(cursor) > 3.0 {
+        return;
+    }
+
+    let Ok((camera, camera_transform)) = cameras.single() else {
+        return;
+    };
+    let Ok(ray) = camera.viewport_to_world(camera_transform, cursor) else {
+        return;
+    };
+    if let Some(PickHit { entity_id, .. }) =
+        pick_entity(&ray, &batched_meshes, &triangle_mapping, &meshes)
+    {
+        crate::storage::save_context_menu(&ContextMenuStorage {
+            id: entity_id,
+            x: cursor.x,
+            y: cursor.y,
+        });
+    }
+}
+
+/// Batch components needed to pick against it
+pub(crate) type PickableBatch = (
+    &'static BatchedMesh,
+    &'static GlobalTransform,
+    &'static Mesh3d,
+    Option<&'static BatchLod>,
+    Option<&'static BatchBvh>,
+);
+
+/// Closest hit of a ray on the batched meshes
+#[derive(Clone, Copy, Debug)]
+pub struct PickHit {
+    pub entity_id: u64,
+    /// Distance along the ray in world units
+    pub distance: f32,
+    /// Hit position in world space
+    pub point: Vec3,
+    /// World-space corners of the hit triangle
+    pub triangle: [Vec3; 3],
+}
+
+/// Closest entity hit by a ray
+pub(crate) fn pick_entity(
+    ray: &Ray3d,
+    batched_meshes: &Query<PickableBatch>,
+    triangle_mapping: &TriangleEntityMapping,
+    meshes: &Assets<Mesh>,
+) -> Option<PickHit> {
+    let mut closest: Option<(u64, f32, &Mesh, &GlobalTransform, usize)> = None;
+
+    for (batched_mesh, transform, mesh_handle, lod, bvh) in batched_meshes.iter() {
+        // Triangle indices refer to the full-detail mesh
+        let handle = lod.map_or(&mesh_handle.0, BatchLod::full);
+        let Some(mesh) = meshes.get(handle) else {
+            continue;
+        };
+        let hit = match bvh {
+            Some(bvh) => ray_bvh_intersection(ray, mesh, transform, bvh),
+            None => ray_mesh_intersection_with_triangle(ray, mesh, transform),
+        };
+        let Some((distance, triangle_index)) = hit else {
+            continue;
+        };
+
+        // Look up which entity this triangle belongs to
+        if let Some(entity_id) = triangle_mapping.get_entity(
+            batched_mesh.is_transparent,
+            batched_mesh.triangle_offset + triangle_index,
+        ) {
+            if closest.is_none_or(|(_, d, ..)| distance < d) {
+                closest = Some((entity_id, distance, mesh, transform, triangle_index));
+            }
+        }
+    }
+
+    let (entity_id, distance, mesh, transform, triangle_index) = closest?;
+    Some(PickHit {
+        entity_id,
+        distance,
+        point: ray.get_point(distance),
+        triangle: triangle_corners(mesh, transform, triangle_index)?,
+    })
+}
+
+/// World-space corners of triangle `index` in the mesh's current index buffer
+fn triangle_corners(mesh: &Mesh, transform: &GlobalTransform, index: usize) -> Option<[Vec3; 3]> {
+    let positions = mesh.attribute(Mesh::ATTRIBUTE_POSITION)?.as_float3()?;
+    let corner = |i: usize| -> Option<Vec3> {
+        let vertex = match mesh.indices()? {
+            Indices::U32(indices) => *indices.get(index * 3 + i)? as usize,
+            Indices::U16(indices) => *indices.get(index * 3 + i)? as usize,
+        };
+        Some(transform.transform_point(Vec3::from(*positions.get(vertex)?)))
+    };
+    Some([corner(0)?, corner(1)?, corner(2)?])
+}
+
+/// Ray-BVH intersection in the batch's local space
+/// Returns (world distance, triangle_index) of the closest hit
+fn ray_bvh_intersection(
+    ray: &Ray3d,
+    mesh: &Mesh,
+    transform: &GlobalTransform,
+    bvh: &BatchBvh,
+) -> Option<(f32, usize)> {
+    let positions = mesh.attribute(Mesh::ATTRIBUTE_POSITION)?.as_float3()?;
+    let Some(Indices::U32(indices)) = mesh.indices() else {
+        return None;
+    };
+
+    // Distances along the local direction equal world distances as long as
+    // the transform does not scale, which holds for batches
+    let to_local = transform.affine().inverse();
+    let origin = to_local.transform_point3(ray.origin);
+    let direction = to_local.transform_vector3(*ray.direction);
+    let scale = direction.length();
+    if scale <= f32::EPSILON {
+        return None;
+    }
+
+    bvh.0
+        .raycast(
+            positions,
+            indices,
+            origin.to_array(),
+            (direction / scale).to_array(),
+        )
+        .map(|(t, triangle)| (t / scale, triangle))
+}
+
 /// Ray-mesh intersection with triangle index for batched mesh picking
 /// Returns (distance, triangle_index) of the closest hit
 fn ray_mesh_intersection_with_triangle(
@@ -0,0 +1,270 @@
+//! Storey plan view
+//!
+//! A 2D floor plan of one storey. The model is cut at the storey elevation
+//! plus a cut height: everything above goes through the section clipping,
+//! and the outlines of the cut are drawn on top. The camera looks straight
+//! down with an orthographic projection, so the plan reads like a drawing.
+//! The UI picks the storey; the camera from before comes back when the plan
+//! closes.
+
+use crate::camera::CameraController;
+use crate::measure::{ifc_to_world, world_to_ifc};
+use crate::section::SectionClipping;
+#[cfg(target_arch = "wasm32")]
+use crate::storage::load_plan;
+use crate::storage::CameraStorage;
+use crate::{IfcSceneData, ViewerSettings};
+use bevy::prelude::*;
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::borrow::Cow;
+
+/// Cut height above the storey elevation when the UI gives none, in metres
+pub const DEFAULT_CUT_HEIGHT: f32 = 1.2;
+
+/// Storey plan plugin
+pub struct PlanViewPlugin;
+
+impl Plugin for PlanViewPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlanView>()
+            .init_resource::<PlanOutlines>()
+            .add_systems(
+                Update,
+                (
+                    poll_plan_system,
+                    apply_plan_system,
+                    update_plan_outlines_system,
+                    draw_plan_outlines_system,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Storey shown as a plan; no storey is the normal 3D view
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct PlanView {
+    pub storey: Option<String>,
+    /// Height of the cut above the storey elevation, in metres
+    pub cut_height: f32,
+}
+
+impl Default for PlanView {
+    fn default() -> Self {
+        Self {
+            storey: None,
+            cut_height: DEFAULT_CUT_HEIGHT,
+        }
+    }
+}
+
+impl PlanView {
+    pub fn is_active(&self) -> bool {
+        self.storey.is_some()
+    }
+
+    /// Elevation (IFC Z) of the cut in the shifted world, `None` while no
+    /// plan is shown
+    ///
+    /// Storeys without an elevation are cut as if they stood at zero.
+    pub fn cut_elevation(&self, scene: &IfcSceneData) -> Option<f32> {
+        let storey = self.storey.as_deref()?;
+        let elevation = scene
+            .entities
+            .iter()
+            .filter(|e| e.storey.as_deref() == Some(storey))
+            .find_map(|e| e.storey_elevation)
+            .unwrap_or(0.0);
+        Some(scene.world_elevation(elevation + self.cut_height))
+    }
+}
+
+/// Outline of one entity in the plan, in world space
+#[derive(Clone, Debug)]
+pub struct PlanOutline {
+    pub entity_id: u64,
+    pub points: Vec<Vec3>,
+    pub closed: bool,
+}
+
+/// Cut outlines of the current plan
+#[derive(Resource, Default)]
+pub struct PlanOutlines {
+    pub outlines: Vec<PlanOutline>,
+}
+
+/// Poll the plan storey from localStorage
+#[allow(unused_mut, unused_variables)]
+fn poll_plan_system(mut plan: ResMut<PlanView>, mut frame: Local<u32>) {
+    #[cfg(target_arch = "wasm32")]
+    {
+        *frame = frame.wrapping_add(1);
+        if !frame.is_multiple_of(30) {
+            return;
+        }
+        let storage = load_plan().unwrap_or_default();
+        let next = PlanView {
+            storey: storage.storey,
+            cut_height: if storage.cut_height > 0.0 {
+                storage.cut_height
+            } else {
+                DEFAULT_CUT_HEIGHT
+            },
+        };
+        if next != *plan {
+            *plan = next;
+        }
+    }
+}
+
+/// Move the cut plane and switch the camera in and out of the top view
+///
+/// `saved` holds the camera to restore while a plan is open.
+fn apply_plan_system(
+    plan: Res<PlanView>,
+    scene_data: Res<IfcSceneData>,
+    mut clipping: ResMut<SectionClipping>,
+    mut controller: ResMut<CameraController>,
+    mut saved: Local<Option<CameraStorage>>,
+) {
+    if !plan.is_changed() && !scene_data.is_changed() {
+        return;
+    }
+    let cut = plan.cut_elevation(&scene_data);
+    if clipping.plan_cut != cut {
+        clipping.plan_cut = cut;
+    }
+
+    match (cut, saved.is_some()) {
+        (Some(cut), false) => {
+            *saved = Some(controller.to_storage());
+            controller.orthographic = true;
+            let mut top = controller.to_storage();
+            top.azimuth = 0.0;
+            top.elevation = std::f32::consts::FRAC_PI_2 - 0.001;
+            top.target[1] = cut as f64;
+            controller.animate_to(&top);
+        }
+        (None, true) => {
+            // Restores the projection from before too
+            if let Some(camera) = saved.take() {
+                controller.animate_to(&camera);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Inputs the outlines were last computed from
+#[derive(Default, PartialEq)]
+struct OutlineInputs {
+    cut: Option<f32>,
+    hidden: FxHashSet<u64>,
+    isolated: Option<FxHashSet<u64>>,
+    storey: Option<String>,
+    hidden_storeys: Vec<String>,
+    hidden_spaces: FxHashSet<u64>,
+}
+
+/// Slice the visible meshes at the cut elevation
+fn update_plan_outlines_system(
+    plan: Res<PlanView>,
+    scene_data: Res<IfcSceneData>,
+    settings: Res<ViewerSettings>,
+    mut outlines: ResMut<PlanOutlines>,
+    mut applied: Local<OutlineInputs>,
+) {
+    if !plan.is_changed() && !scene_data.is_changed() && !settings.is_changed() {
+        return;
+    }
+    let inputs = OutlineInputs {
+        cut: plan.cut_elevation(&scene_data),
+        hidden: settings.hidden_entities.clone(),
+        isolated: settings.isolated_entities.clone(),
+        storey: settings.storey_filter.clone(),
+        hidden_storeys: settings.hidden_storeys.clone(),
+        hidden_spaces: settings.hidden_spaces.clone(),
+    };
+    if inputs == *applied && !scene_data.is_changed() {
+        return;
+    }
+    *applied = inputs;
+    let Some(cut) = applied.cut else {
+        if !outlines.outlines.is_empty() {
+            outlines.outlines.clear();
+        }
+        return;
+    };
+
+    let storeys: FxHashMap<u64, Vec<&str>> = if settings.filters_by_storey() {
+        scene_data
+            .entities
+            .iter()
+            .map(|e| {
+                let names = e
+                    .storey
+                    .iter()
+                    .chain(&e.referenced_storeys)
+                    .map(|s| &**s)
+                    .collect();
+                (e.id, names)
+            })
+            .collect()
+    } else {
+        FxHashMap::default()
+    };
+    let identity = Mat4::IDENTITY.to_cols_array();
+
+    let mut computed = Vec::new();
+    for mesh in &scene_data.meshes {
+        let visible = settings.is_entity_visible(
+            mesh.entity_id,
+            storeys.get(&mesh.entity_id).map_or(&[][..], Vec::as_slice),
+        );
+        if !visible {
+            continue;
+        }
+        let geometry = &mesh.geometry;
+        // Geometry is in IFC coordinates, the transform applies after the
+        // switch to Y-up
+        let positions: Cow<[f32]> = if mesh.transform == identity {
+            Cow::Borrowed(&geometry.positions)
+        } else {
+            let transform = mesh.get_transform();
+            Cow::Owned(
+                geometry
+                    .positions
+                    .chunks_exact(3)
+                    .flat_map(|p| {
+                        world_to_ifc(transform.transform_point(ifc_to_world([p[0], p[1], p[2]])))
+                    })
+                    .collect(),
+            )
+        };
+        for polyline in ifc_lite_geometry::section_polylines(&positions, &geometry.indices, cut) {
+            computed.push(PlanOutline {
+                entity_id: mesh.entity_id,
+                points: polyline
+                    .points
+                    .iter()
+                    .map(|&[x, y]| ifc_to_world([x, y, cut]))
+                    .collect(),
+                closed: polyline.closed,
+            });
+        }
+    }
+    outlines.outlines = computed;
+}
+
+/// Draw the cut outlines over the clipped model
+fn draw_plan_outlines_system(
+    outlines: Res<PlanOutlines>,
+    settings: Res<ViewerSettings>,
+    mut gizmos: Gizmos,
+) {
+    let color = settings.theme.plan_line_color();
+    for outline in &outlines.outlines {
+        let closing = outline.closed.then(|| outline.points[0]);
+        gizmos.linestrip(outline.points.iter().copied().chain(closing), color);
+    }
+}
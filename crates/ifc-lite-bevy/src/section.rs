@@ -1,44 +1,78 @@
-//! Section plane system
+//! Section planes and section box
 //!
-//! Provides clipping plane functionality for viewing building cross-sections.
+//! Up to [`MAX_SECTION_PLANES`] axis-aligned planes and an optional section
+//! box cut the model. Batches are drawn with [`ClippedMaterial`], a
+//! StandardMaterial extension whose fragment shader discards everything in
+//! front of a clip plane and fills the inside of cut solids with a hatched
+//! cap colour; its prepass discards the same fragments. Planes and box come from the UI relative to the scene bounds,
+//! along IFC axes (Z up); native hosts can also set world-space planes.
+//! A storey section cuts just above a storey's elevation and hides the
+//! storeys above it, for a quick look into one floor.
 
+use crate::measure::ifc_to_world;
 #[cfg(target_arch = "wasm32")]
-#[allow(unused_imports)]
 use crate::storage::load_section;
-use crate::storage::SectionStorage;
+use crate::storage::{
+    KeyWatch, SectionBoxStorage, SectionPlaneStorage, SectionStorage, StoreySectionStorage,
+    SECTION_KEY,
+};
+use crate::{IfcSceneData, SceneBounds, ViewerSettings};
+use bevy::pbr::{ExtendedMaterial, MaterialExtension};
 use bevy::prelude::*;
+use bevy::render::render_resource::AsBindGroup;
+use bevy::shader::ShaderRef;
+
+/// Most planes the UI can place, besides the section box
+pub const MAX_SECTION_PLANES: usize = 6;
+/// Planes the shader tests: the section planes plus the six box faces
+pub const MAX_CLIP_PLANES: usize = MAX_SECTION_PLANES + 6;
+
+const SHADER_PATH: &str = "embedded://ifc_lite_bevy/section_clip.wgsl";
+const PREPASS_SHADER_PATH: &str = "embedded://ifc_lite_bevy/section_clip_prepass.wgsl";
+
+/// Material of all model batches
+pub type ClippedMaterial = ExtendedMaterial<StandardMaterial, SectionClip>;
 
 /// Section plane plugin
+///
+/// Registers [`ClippedMaterial`], so it has to be added before
+/// [`crate::MeshPlugin`] creates the batch materials.
 pub struct SectionPlanePlugin;
 
 impl Plugin for SectionPlanePlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<SectionPlane>()
-            .add_systems(Update, poll_section_settings);
+        bevy::asset::embedded_asset!(app, "section_clip.wgsl");
+        bevy::asset::embedded_asset!(app, "section_clip_prepass.wgsl");
+        app.add_plugins(MaterialPlugin::<ClippedMaterial>::default())
+            .init_resource::<SectionClipping>()
+            .insert_resource(SectionWatch(KeyWatch::new(SECTION_KEY)))
+            .add_systems(
+                Update,
+                (
+                    poll_section_settings,
+                    hide_storeys_above_system,
+                    update_clip_materials_system,
+                )
+                    .chain(),
+            );
     }
 }
 
-/// Section plane axis
+/// Section plane axis, in IFC coordinates
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
 pub enum SectionAxis {
     X,
-    #[default]
     Y,
+    #[default]
     Z,
 }
 
 impl SectionAxis {
-    /// Get plane normal vector
-    pub fn normal(&self, flipped: bool) -> Vec3 {
-        let base = match self {
-            SectionAxis::X => Vec3::X,
-            SectionAxis::Y => Vec3::Y,
-            SectionAxis::Z => Vec3::Z,
-        };
-        if flipped {
-            -base
-        } else {
-            base
+    fn index(&self) -> usize {
+        match self {
+            SectionAxis::X => 0,
+            SectionAxis::Y => 1,
+            SectionAxis::Z => 2,
         }
     }
 
@@ -61,151 +95,360 @@ impl SectionAxis {
     }
 }
 
-/// Section plane state
-#[derive(Resource)]
+/// World-space plane (normal.xyz, distance) through an IFC point
+pub fn world_plane(ifc_normal: Vec3, ifc_point: Vec3) -> Vec4 {
+    let normal = ifc_to_world(ifc_normal.normalize_or_zero().to_array());
+    let point = ifc_to_world(ifc_point.to_array());
+    normal.extend(normal.dot(point))
+}
+
+/// World-space planes of the faces of an IFC box, facing outwards
+pub fn box_planes(min: Vec3, max: Vec3) -> [Vec4; 6] {
+    [
+        world_plane(Vec3::X, max),
+        world_plane(Vec3::NEG_X, min),
+        world_plane(Vec3::Y, max),
+        world_plane(Vec3::NEG_Y, min),
+        world_plane(Vec3::Z, max),
+        world_plane(Vec3::NEG_Z, min),
+    ]
+}
+
+/// Scene bounds in IFC coordinates
+pub(crate) fn ifc_bounds(bounds: &SceneBounds) -> (Vec3, Vec3) {
+    let (min, max) = (bounds.min, bounds.max);
+    (
+        Vec3::new(min.x, -max.z, min.y),
+        Vec3::new(max.x, -min.z, max.y),
+    )
+}
+
+/// Axis-aligned section plane
+#[derive(Clone, Debug, PartialEq)]
 pub struct SectionPlane {
-    /// Whether section plane is enabled
-    pub enabled: bool,
-    /// Section axis
     pub axis: SectionAxis,
-    /// Position along axis (0.0 to 1.0 of scene bounds)
+    /// Position along the axis (0.0 to 1.0 of the scene bounds)
     pub position: f32,
-    /// Whether plane normal is flipped
+    /// Cut away the part below the plane instead of above it
     pub flipped: bool,
-    /// Cached plane equation (normal.xyz, distance)
-    pub plane: Vec4,
 }
 
 impl Default for SectionPlane {
     fn default() -> Self {
         Self {
-            enabled: false,
-            axis: SectionAxis::Y,
+            axis: SectionAxis::Z,
             position: 0.5,
             flipped: false,
-            plane: Vec4::new(0.0, 1.0, 0.0, 0.0),
         }
     }
 }
 
 impl SectionPlane {
-    /// Set axis
-    pub fn set_axis(&mut self, axis: SectionAxis) {
-        self.axis = axis;
-        self.update_plane();
+    /// World-space clip plane within the scene bounds
+    pub fn clip_plane(&self, bounds: &SceneBounds) -> Vec4 {
+        let (min, max) = ifc_bounds(bounds);
+        let axis = self.axis.index();
+        let mut point = min;
+        point[axis] = min[axis] + (max[axis] - min[axis]) * self.position.clamp(0.0, 1.0);
+        let mut normal = Vec3::ZERO;
+        normal[axis] = if self.flipped { -1.0 } else { 1.0 };
+        world_plane(normal, point)
     }
 
-    /// Set position (0.0 to 1.0)
-    pub fn set_position(&mut self, position: f32) {
-        self.position = position.clamp(0.0, 1.0);
-        self.update_plane();
+    pub fn from_storage(storage: &SectionPlaneStorage) -> Self {
+        Self {
+            axis: SectionAxis::parse(&storage.axis),
+            position: storage.position,
+            flipped: storage.flipped,
+        }
     }
 
-    /// Toggle flip
-    pub fn toggle_flip(&mut self) {
-        self.flipped = !self.flipped;
-        self.update_plane();
+    pub fn to_storage(&self) -> SectionPlaneStorage {
+        SectionPlaneStorage {
+            axis: self.axis.as_str().to_string(),
+            position: self.position,
+            flipped: self.flipped,
+        }
     }
+}
 
-    /// Toggle enabled
-    pub fn toggle(&mut self) {
-        self.enabled = !self.enabled;
+/// Section box, relative to the scene bounds per IFC axis (0.0 to 1.0)
+#[derive(Clone, Debug, PartialEq)]
+pub struct SectionBox {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Default for SectionBox {
+    fn default() -> Self {
+        Self {
+            min: Vec3::ZERO,
+            max: Vec3::ONE,
+        }
     }
+}
 
-    /// Update plane equation from current settings
-    pub fn update_plane(&mut self) {
-        let normal = self.axis.normal(self.flipped);
-        // Distance is calculated based on position - will be updated with scene bounds
-        self.plane = Vec4::new(normal.x, normal.y, normal.z, 0.0);
+impl SectionBox {
+    /// World-space planes of the six box faces, facing outwards
+    pub fn clip_planes(&self, bounds: &SceneBounds) -> [Vec4; 6] {
+        let (min, max) = ifc_bounds(bounds);
+        let size = max - min;
+        box_planes(
+            min + size * self.min.clamp(Vec3::ZERO, Vec3::ONE),
+            min + size * self.max.clamp(Vec3::ZERO, Vec3::ONE),
+        )
     }
+}
 
-    /// Update plane with scene bounds
-    pub fn update_with_bounds(&mut self, min: Vec3, max: Vec3) {
-        let normal = self.axis.normal(self.flipped);
-        let axis_min = match self.axis {
-            SectionAxis::X => min.x,
-            SectionAxis::Y => min.y,
-            SectionAxis::Z => min.z,
-        };
-        let axis_max = match self.axis {
-            SectionAxis::X => max.x,
-            SectionAxis::Y => max.y,
-            SectionAxis::Z => max.z,
-        };
-        let distance = axis_min + (axis_max - axis_min) * self.position;
-        self.plane = Vec4::new(normal.x, normal.y, normal.z, distance);
+/// Horizontal cut above a storey's elevation
+#[derive(Clone, Debug, PartialEq)]
+pub struct StoreySection {
+    pub storey: String,
+    /// Storey elevation (IFC Z), in metres
+    pub elevation: f32,
+    /// Height of the cut above the elevation, in metres
+    pub cut_height: f32,
+}
+
+impl StoreySection {
+    /// Elevation (IFC Z) of the cut
+    pub fn cut_elevation(&self) -> f32 {
+        self.elevation + self.cut_height
+    }
+
+    /// Storeys standing higher than this one, sorted by name
+    pub fn storeys_above(&self, scene: &IfcSceneData) -> Vec<String> {
+        let mut above: Vec<String> = scene
+            .entities
+            .iter()
+            .filter_map(|e| match (&e.storey, e.storey_elevation) {
+                (Some(storey), Some(elevation)) if elevation > self.elevation + 1e-3 => {
+                    Some(storey.to_string())
+                }
+                _ => None,
+            })
+            .collect();
+        above.sort_unstable();
+        above.dedup();
+        above
+    }
+}
+
+/// Active section planes and box
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct SectionClipping {
+    /// Planes placed relative to the scene bounds
+    pub planes: Vec<SectionPlane>,
+    pub section_box: Option<SectionBox>,
+    /// World-space planes (normal.xyz, distance), e.g. from a native host
+    pub world_planes: Vec<Vec4>,
+    /// Elevation (IFC Z) of the storey plan cut in the shifted world,
+    /// everything above is removed
+    pub plan_cut: Option<f32>,
+    /// Storey section from the UI
+    pub storey: Option<StoreySection>,
+    /// Colour of cut surfaces
+    pub cap_color: Color,
+}
+
+impl Default for SectionClipping {
+    fn default() -> Self {
+        Self {
+            planes: Vec::new(),
+            section_box: None,
+            world_planes: Vec::new(),
+            plan_cut: None,
+            storey: None,
+            cap_color: Color::srgb(0.8, 0.3, 0.25),
+        }
+    }
+}
+
+impl SectionClipping {
+    pub fn is_active(&self) -> bool {
+        !self.planes.is_empty()
+            || self.section_box.is_some()
+            || !self.world_planes.is_empty()
+            || self.plan_cut.is_some()
+            || self.storey.is_some()
+    }
+
+    /// All clip planes in world space, at most [`MAX_CLIP_PLANES`]
+    ///
+    /// Relative planes and the box need the scene bounds and are left out
+    /// until a model is loaded.
+    pub fn clip_planes(&self, scene: &IfcSceneData) -> Vec<Vec4> {
+        let storey_cut = self
+            .storey
+            .as_ref()
+            .map(|s| scene.world_elevation(s.cut_elevation()));
+        let mut planes: Vec<Vec4> = self
+            .plan_cut
+            .into_iter()
+            .chain(storey_cut)
+            .map(|z| world_plane(Vec3::Z, Vec3::new(0.0, 0.0, z)))
+            .collect();
+        planes.extend_from_slice(&self.world_planes);
+        if let Some(bounds) = scene.bounds.as_ref() {
+            planes.extend(
+                self.planes
+                    .iter()
+                    .take(MAX_SECTION_PLANES)
+                    .map(|p| p.clip_plane(bounds)),
+            );
+            if let Some(section_box) = &self.section_box {
+                planes.extend(section_box.clip_planes(bounds));
+            }
+        }
+        planes.truncate(MAX_CLIP_PLANES);
+        planes
     }
 
-    /// Load from storage
-    pub fn from_storage(&mut self, storage: &SectionStorage) {
-        self.enabled = storage.enabled;
-        self.axis = SectionAxis::parse(&storage.axis);
-        self.position = storage.position;
-        self.flipped = storage.flipped;
-        self.update_plane();
+    /// Replace planes, box and cap colour with the UI's
+    pub fn apply_storage(&mut self, storage: &SectionStorage) {
+        self.planes = storage
+            .planes
+            .iter()
+            .take(MAX_SECTION_PLANES)
+            .map(SectionPlane::from_storage)
+            .collect();
+        self.section_box = storage.section_box.as_ref().map(|b| SectionBox {
+            min: Vec3::from_array(b.min),
+            max: Vec3::from_array(b.max),
+        });
+        self.storey = storage.storey.as_ref().map(|s| StoreySection {
+            storey: s.storey.clone(),
+            elevation: s.elevation,
+            cut_height: s.cut_height,
+        });
+        if let Some(color) = storage
+            .cap_color
+            .as_deref()
+            .and_then(|hex| Srgba::hex(hex).ok())
+        {
+            self.cap_color = color.into();
+        }
     }
 
-    /// Convert to storage
     pub fn to_storage(&self) -> SectionStorage {
         SectionStorage {
-            enabled: self.enabled,
-            axis: self.axis.as_str().to_string(),
-            position: self.position,
-            flipped: self.flipped,
+            planes: self.planes.iter().map(SectionPlane::to_storage).collect(),
+            section_box: self.section_box.as_ref().map(|b| SectionBoxStorage {
+                min: b.min.to_array(),
+                max: b.max.to_array(),
+            }),
+            cap_color: Some(self.cap_color.to_srgba().to_hex()),
+            storey: self.storey.as_ref().map(|s| StoreySectionStorage {
+                storey: s.storey.clone(),
+                elevation: s.elevation,
+                cut_height: s.cut_height,
+            }),
         }
     }
 }
 
-/// Poll section settings from localStorage
-#[allow(unused_mut)]
-fn poll_section_settings(mut section: ResMut<SectionPlane>) {
+/// Clip uniforms of [`ClippedMaterial`]
+#[derive(Asset, AsBindGroup, Reflect, Debug, Clone, Default)]
+pub struct SectionClip {
+    /// normal.xyz, distance; points with dot(normal, p) > distance are cut
+    #[uniform(100)]
+    pub planes: [Vec4; MAX_CLIP_PLANES],
+    /// Colour of cut surfaces (linear)
+    #[uniform(100)]
+    pub cap_color: Vec4,
+    /// x: number of active planes, y: draw caps (0 or 1)
+    #[uniform(100)]
+    pub flags: UVec4,
+}
+
+impl SectionClip {
+    pub fn new(planes: &[Vec4], cap_color: Color, caps: bool) -> Self {
+        let count = planes.len().min(MAX_CLIP_PLANES);
+        let mut clip = Self {
+            cap_color: LinearRgba::from(cap_color).to_vec4(),
+            flags: UVec4::new(count as u32, caps as u32, 0, 0),
+            ..default()
+        };
+        clip.planes[..count].copy_from_slice(&planes[..count]);
+        clip
+    }
+}
+
+impl MaterialExtension for SectionClip {
+    fn fragment_shader() -> ShaderRef {
+        SHADER_PATH.into()
+    }
+
+    /// Discards clipped fragments before they write depth or normals
+    ///
+    /// Bevy skips the prepass fragment stage when the prepass only writes
+    /// depth, so cameras with a prepass also request normals.
+    fn prepass_fragment_shader() -> ShaderRef {
+        PREPASS_SHADER_PATH.into()
+    }
+}
+
+/// Changes of the UI's section settings
+#[derive(Resource)]
+struct SectionWatch(KeyWatch);
+
+/// Apply section settings from the UI when they change
+#[allow(unused_mut, unused_variables)]
+fn poll_section_settings(mut clipping: ResMut<SectionClipping>, mut watch: ResMut<SectionWatch>) {
     #[cfg(target_arch = "wasm32")]
     {
-        // Only poll occasionally
-        static mut POLL_COUNTER: u32 = 0;
-        unsafe {
-            POLL_COUNTER += 1;
-            if POLL_COUNTER % 30 == 0 {
-                if let Some(storage) = load_section() {
-                    if storage.enabled != section.enabled
-                        || storage.axis != section.axis.as_str()
-                        || storage.position != section.position
-                        || storage.flipped != section.flipped
-                    {
-                        section.from_storage(&storage);
-                    }
-                }
-            }
+        if !watch.0.changed() {
+            return;
+        }
+        let storage = load_section().unwrap_or_default();
+        let mut next = clipping.clone();
+        next.apply_storage(&storage);
+        // World planes are never set from the web UI, the plan cut has its
+        // own storage
+        if next != *clipping {
+            *clipping = next;
         }
     }
+}
 
-    // Suppress unused warning for native builds
-    let _ = &section;
+/// Hide the storeys above a storey section
+fn hide_storeys_above_system(
+    clipping: Res<SectionClipping>,
+    scene_data: Res<IfcSceneData>,
+    mut settings: ResMut<ViewerSettings>,
+) {
+    if !clipping.is_changed() && !scene_data.is_changed() {
+        return;
+    }
+    let above = clipping
+        .storey
+        .as_ref()
+        .map(|section| section.storeys_above(&scene_data))
+        .unwrap_or_default();
+    if settings.hidden_storeys != above {
+        settings.hidden_storeys = above;
+    }
 }
 
-// Note: Actual clipping would require custom shaders.
-// For a simpler approach, we can use Bevy's built-in clipping planes
-// or implement material-based clipping in a custom shader.
-//
-// For now, this module provides the data structures and settings.
-// The actual clipping can be implemented using:
-// 1. Custom material with clip plane uniform
-// 2. Bevy's ClipPlane component (if available)
-// 3. Post-processing effect
+/// Push the clip planes into the batch materials when they move
+fn update_clip_materials_system(
+    clipping: Res<SectionClipping>,
+    scene_data: Res<IfcSceneData>,
+    batch_materials: Res<crate::mesh::BatchMaterials>,
+    mut materials: ResMut<Assets<ClippedMaterial>>,
+    mut applied: Local<Option<(Vec<Vec4>, Color)>>,
+) {
+    let planes = clipping.clip_planes(&scene_data);
+    let current = Some((planes, clipping.cap_color));
+    if *applied == current {
+        return;
+    }
+    *applied = current;
+    let (planes, cap_color) = applied.as_ref().unwrap();
 
-/// Custom material with section plane support (placeholder)
-/// To implement actual clipping, create a custom material:
-///
-/// ```glsl
-/// // In fragment shader:
-/// if (section_enabled) {
-///     float d = dot(world_position.xyz, section_plane.xyz) - section_plane.w;
-///     if (d > 0.0) discard;
-/// }
-/// ```
-#[derive(Clone, Debug)]
-pub struct SectionPlaneMaterial {
-    pub base_color: Color,
-    pub section_plane: Vec4,
-    pub section_enabled: bool,
+    for (handle, caps) in batch_materials.with_caps() {
+        if let Some(material) = materials.get_mut(handle) {
+            material.extension = SectionClip::new(planes, *cap_color, caps);
+        }
+    }
 }
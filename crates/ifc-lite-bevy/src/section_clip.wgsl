@@ -0,0 +1,74 @@
+// Section clipping for batched IFC geometry
+//
+// StandardMaterial shading, with fragments in front of any clip plane
+// discarded. Back faces seen through a cut are the inside of a solid and
+// are drawn as a hatched cap instead of being lit.
+
+#import bevy_pbr::{
+    pbr_functions::alpha_discard,
+    pbr_fragment::pbr_input_from_standard_material,
+    pbr_types::STANDARD_MATERIAL_FLAGS_UNLIT_BIT,
+}
+
+#ifdef PREPASS_PIPELINE
+#import bevy_pbr::{
+    prepass_io::{VertexOutput, FragmentOutput},
+    pbr_deferred_functions::deferred_output,
+}
+#else
+#import bevy_pbr::{
+    forward_io::{VertexOutput, FragmentOutput},
+    pbr_functions::{apply_pbr_lighting, main_pass_post_lighting_processing},
+}
+#endif
+
+struct SectionClip {
+    // normal.xyz, distance; points with dot(normal, p) > distance are cut
+    planes: array<vec4<f32>, 12>,
+    cap_color: vec4<f32>,
+    // x: active planes, y: draw caps
+    flags: vec4<u32>,
+}
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(100)
+var<uniform> section_clip: SectionClip;
+
+// Hatch stripe period and width, in pixels
+const HATCH_PERIOD: f32 = 8.0;
+const HATCH_WIDTH: f32 = 2.0;
+
+@fragment
+fn fragment(
+    in: VertexOutput,
+    @builtin(front_facing) is_front: bool,
+) -> FragmentOutput {
+    let plane_count = section_clip.flags.x;
+    for (var i = 0u; i < plane_count; i++) {
+        let plane = section_clip.planes[i];
+        if dot(plane.xyz, in.world_position.xyz) > plane.w {
+            discard;
+        }
+    }
+
+    var pbr_input = pbr_input_from_standard_material(in, is_front);
+    pbr_input.material.base_color = alpha_discard(pbr_input.material, pbr_input.material.base_color);
+
+#ifdef PREPASS_PIPELINE
+    let out = deferred_output(in, pbr_input);
+#else
+    var out: FragmentOutput;
+    if plane_count > 0u && section_clip.flags.y != 0u && !is_front {
+        // Diagonal stripes in screen space, darker than the cap colour
+        let stripe = (in.position.x + in.position.y) % HATCH_PERIOD < HATCH_WIDTH;
+        let cap = section_clip.cap_color;
+        out.color = select(cap, vec4(cap.rgb * 0.5, cap.a), stripe);
+    } else if (pbr_input.material.flags & STANDARD_MATERIAL_FLAGS_UNLIT_BIT) == 0u {
+        out.color = apply_pbr_lighting(pbr_input);
+        out.color = main_pass_post_lighting_processing(pbr_input, out.color);
+    } else {
+        out.color = main_pass_post_lighting_processing(pbr_input, pbr_input.material.base_color);
+    }
+#endif
+
+    return out;
+}
@@ -0,0 +1,70 @@
+// Section clipping in the depth and normal prepass
+//
+// StandardMaterial's prepass with the clip planes of section_clip.wgsl, so
+// cut-away fragments write neither depth nor normals: they must not occlude
+// what lies behind the cut, for the main pass, occlusion culling or SSAO.
+
+#import bevy_pbr::{
+    pbr_bindings::material,
+    pbr_functions,
+    pbr_prepass_functions,
+    pbr_types,
+    prepass_io,
+}
+
+// Same layout as in section_clip.wgsl
+struct SectionClip {
+    planes: array<vec4<f32>, 12>,
+    cap_color: vec4<f32>,
+    flags: vec4<u32>,
+}
+
+@group(#{MATERIAL_BIND_GROUP}) @binding(100)
+var<uniform> section_clip: SectionClip;
+
+fn clip(world_position: vec3<f32>) {
+    for (var i = 0u; i < section_clip.flags.x; i++) {
+        let plane = section_clip.planes[i];
+        if dot(plane.xyz, world_position) > plane.w {
+            discard;
+        }
+    }
+}
+
+#ifdef PREPASS_FRAGMENT
+@fragment
+fn fragment(
+    in: prepass_io::VertexOutput,
+    @builtin(front_facing) is_front: bool,
+) -> prepass_io::FragmentOutput {
+    clip(in.world_position.xyz);
+    pbr_prepass_functions::prepass_alpha_discard(in);
+
+    var out: prepass_io::FragmentOutput;
+
+#ifdef UNCLIPPED_DEPTH_ORTHO_EMULATION
+    out.frag_depth = in.unclipped_depth;
+#endif
+
+#ifdef NORMAL_PREPASS
+    let double_sided = (material.flags & pbr_types::STANDARD_MATERIAL_FLAGS_DOUBLE_SIDED_BIT) != 0u;
+    let world_normal = pbr_functions::prepare_world_normal(in.world_normal, double_sided, is_front);
+    out.normal = vec4(world_normal * 0.5 + vec3(0.5), 1.0);
+#endif
+
+#ifdef MOTION_VECTOR_PREPASS
+    out.motion_vector = pbr_prepass_functions::calculate_motion_vector(
+        in.world_position,
+        in.previous_world_position,
+    );
+#endif
+
+    return out;
+}
+#else
+@fragment
+fn fragment(in: prepass_io::VertexOutput) {
+    clip(in.world_position.xyz);
+    pbr_prepass_functions::prepass_alpha_discard(in);
+}
+#endif
@@ -0,0 +1,130 @@
+//! Space mode - IfcSpace room volumes for facility management
+//!
+//! Spaces are loaded like any other element but hidden until space mode is
+//! turned on. They are drawn as translucent volumes, one palette color per
+//! space, and can be hidden storey by storey. Their names need text, so the
+//! label positions at the centre of each visible space are published to
+//! storage and rendered by the UI.
+
+use crate::camera::MainCamera;
+use crate::mesh::{EntityBounds, IfcEntity};
+use crate::storage::SpaceLabelStorage;
+use crate::{IfcSceneData, ViewerSettings};
+use bevy::prelude::*;
+use rustc_hash::FxHashSet;
+
+/// Opacity of space volumes, low enough to see the rooms' contents
+const SPACE_ALPHA: f32 = 0.25;
+
+/// Space mode plugin
+pub struct SpacePlugin;
+
+impl Plugin for SpacePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SpaceLabelState>().add_systems(
+            Update,
+            (poll_space_settings, hide_spaces_system, update_space_labels)
+                .chain()
+                .after(crate::camera::CameraInputSet),
+        );
+    }
+}
+
+/// Space labels last published to storage
+#[derive(Resource, Default)]
+pub struct SpaceLabelState(pub Vec<SpaceLabelStorage>);
+
+/// Whether an IFC type name is IfcSpace
+pub fn is_space(entity_type: &str) -> bool {
+    entity_type.eq_ignore_ascii_case("IFCSPACE")
+}
+
+/// Translucent color of a space volume, stable per space
+pub fn space_color(id: u64) -> [f32; 4] {
+    let [r, g, b] = crate::palette::group_color(id as usize);
+    [r, g, b, SPACE_ALPHA]
+}
+
+/// Poll the space mode from localStorage
+#[allow(unused_mut, unused_variables)]
+fn poll_space_settings(mut settings: ResMut<ViewerSettings>, mut frame: Local<u32>) {
+    #[cfg(target_arch = "wasm32")]
+    {
+        *frame = frame.wrapping_add(1);
+        if !frame.is_multiple_of(30) {
+            return;
+        }
+        let spaces = crate::storage::load_spaces().unwrap_or_default();
+        if settings.show_spaces != spaces.visible
+            || settings.hidden_space_storeys != spaces.hidden_storeys
+        {
+            settings.show_spaces = spaces.visible;
+            settings.hidden_space_storeys = spaces.hidden_storeys;
+        }
+    }
+}
+
+/// Collect the spaces the space mode hides
+fn hide_spaces_system(scene_data: Res<IfcSceneData>, mut settings: ResMut<ViewerSettings>) {
+    if !settings.is_changed() && !scene_data.is_changed() {
+        return;
+    }
+    let hidden: FxHashSet<u64> = scene_data
+        .entities
+        .iter()
+        .filter(|e| is_space(&e.entity_type))
+        .filter(|e| {
+            !settings.show_spaces
+                || e.storey.as_ref().is_some_and(|storey| {
+                    settings
+                        .hidden_space_storeys
+                        .iter()
+                        .any(|hidden| **hidden == **storey)
+                })
+        })
+        .map(|e| e.id)
+        .collect();
+    if settings.hidden_spaces != hidden {
+        settings.hidden_spaces = hidden;
+    }
+}
+
+/// Publish the screen position of each visible space's name
+fn update_space_labels(
+    settings: Res<ViewerSettings>,
+    spaces: Query<(&IfcEntity, &EntityBounds, &Visibility)>,
+    cameras: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    mut state: ResMut<SpaceLabelState>,
+) {
+    let labels: Vec<SpaceLabelStorage> = match cameras.single() {
+        Ok((camera, camera_transform)) if settings.show_spaces => spaces
+            .iter()
+            .filter(|(entity, _, visibility)| {
+                is_space(&entity.entity_type) && **visibility != Visibility::Hidden
+            })
+            .filter_map(|(entity, bounds, _)| {
+                let name = entity.name.clone()?;
+                let anchor = camera
+                    .world_to_viewport(camera_transform, bounds.center())
+                    .ok()?;
+                Some(SpaceLabelStorage {
+                    id: entity.id,
+                    name,
+                    x: anchor.x,
+                    y: anchor.y,
+                })
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    // Only touch storage when a label visibly moves
+    let moved = labels.len() != state.0.len()
+        || labels.iter().zip(&state.0).any(|(new, old)| {
+            new.id != old.id || (new.x - old.x).abs() > 0.5 || (new.y - old.y).abs() > 0.5
+        });
+    if moved {
+        crate::storage::save_space_labels(&labels);
+        state.0 = labels;
+    }
+}
@@ -0,0 +1,276 @@
+//! Split view - two viewports side by side
+//!
+//! A second camera draws the same scene into the other half of the window,
+//! so a plan and a 3D view, or two angles on a detail, can be seen at once.
+//! Both halves share the batches, the selection and the visibility; each has
+//! a camera of its own.
+//!
+//! The camera controller drives the view under the cursor. That view is
+//! always the main camera's, so picking, highlights, measurements and the
+//! corner overlays work wherever the user points. When the cursor moves to
+//! the other half, the two cameras trade places: the main camera takes over
+//! the transform and projection shown there, the controller takes over that
+//! view's camera, and nothing jumps on screen.
+//!
+//! Synchronized views pan and zoom together. The second view follows the
+//! target and distance of the first and keeps its own direction and
+//! projection, e.g. a top view following a perspective one.
+
+use crate::camera::{
+    projection_matches, projection_of, CameraController, CameraInputSet, MainCamera,
+    OverlayInputSet,
+};
+#[cfg(target_arch = "wasm32")]
+use crate::storage::load_split_view;
+use crate::storage::CameraStorage;
+use bevy::camera::Viewport;
+use bevy::input::touch::Touches;
+use bevy::prelude::*;
+use bevy::render::view::Msaa;
+
+/// Split view plugin
+pub struct SplitViewPlugin;
+
+impl Plugin for SplitViewPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SplitView>()
+            .add_systems(Startup, setup_split_camera)
+            .add_systems(
+                Update,
+                (poll_split_view_system, split_focus_system)
+                    .chain()
+                    .before(OverlayInputSet),
+            )
+            .add_systems(Update, update_split_cameras_system.after(CameraInputSet));
+    }
+}
+
+/// Half of the window a view takes
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum SplitSide {
+    #[default]
+    Left,
+    Right,
+}
+
+impl SplitSide {
+    pub fn opposite(self) -> Self {
+        match self {
+            SplitSide::Left => SplitSide::Right,
+            SplitSide::Right => SplitSide::Left,
+        }
+    }
+}
+
+/// Split view state
+#[derive(Resource, Default)]
+pub struct SplitView {
+    pub enabled: bool,
+    /// Both views pan and zoom together, each keeping its view direction
+    pub synchronized: bool,
+    /// Half the main camera, and the controller, work in
+    pub main_side: SplitSide,
+    /// Camera of the other view; it starts as a copy of the main one
+    pub other: Option<CameraStorage>,
+}
+
+/// Marker component for the camera of the second view
+#[derive(Component)]
+pub struct SplitCamera;
+
+fn setup_split_camera(mut commands: Commands) {
+    commands.spawn((
+        Camera3d::default(),
+        Camera {
+            order: 3,
+            is_active: false,
+            // The main camera clears the whole window
+            clear_color: ClearColorConfig::None,
+            ..default()
+        },
+        SplitCamera,
+    ));
+}
+
+/// Poll the split view toggles from localStorage
+#[allow(unused_mut, unused_variables)]
+fn poll_split_view_system(mut split: ResMut<SplitView>, mut frame: Local<u32>) {
+    #[cfg(target_arch = "wasm32")]
+    {
+        *frame = frame.wrapping_add(1);
+        if !frame.is_multiple_of(30) {
+            return;
+        }
+        let storage = load_split_view().unwrap_or_default();
+        if split.enabled != storage.enabled || split.synchronized != storage.synchronized {
+            split.enabled = storage.enabled;
+            split.synchronized = storage.synchronized;
+        }
+    }
+}
+
+/// Camera the controller is heading for, so a glide cut short by a swap
+/// still ends where it was going
+fn settled(controller: &CameraController) -> CameraStorage {
+    let mut storage = controller.to_storage();
+    if let Some(animation) = &controller.animation_target {
+        storage.azimuth = animation.azimuth;
+        storage.elevation = animation.elevation;
+        storage.distance = animation.distance;
+        storage.target = animation.target.to_array().map(f64::from);
+    }
+    storage
+}
+
+/// Hand the controller to the view under the cursor, or under a new touch
+///
+/// Runs before the overlays and the camera input, and not during a drag.
+/// The cameras' cached matrices only catch up after the swap, so for that
+/// frame the pointer counts as over an overlay.
+#[allow(clippy::type_complexity)]
+fn split_focus_system(
+    mut split: ResMut<SplitView>,
+    mut controller: ResMut<CameraController>,
+    windows: Query<&Window>,
+    touches: Res<Touches>,
+    mut main_camera: Query<
+        (&mut Transform, &mut GlobalTransform, &mut Projection),
+        (With<MainCamera>, Without<SplitCamera>),
+    >,
+    mut split_camera: Query<
+        (
+            &Camera,
+            &mut Transform,
+            &mut GlobalTransform,
+            &mut Projection,
+        ),
+        (With<SplitCamera>, Without<MainCamera>),
+    >,
+) {
+    if !split.enabled || controller.is_dragging {
+        return;
+    }
+    let (
+        Ok((mut main_transform, mut main_global, mut main_projection)),
+        Ok((camera, mut transform, mut global, mut projection)),
+    ) = (main_camera.single_mut(), split_camera.single_mut())
+    else {
+        return;
+    };
+    let Some(rect) = camera.logical_viewport_rect().filter(|_| camera.is_active) else {
+        return;
+    };
+    let hovered = windows
+        .single()
+        .ok()
+        .and_then(Window::cursor_position)
+        .is_some_and(|cursor| rect.contains(cursor));
+    let touched = touches
+        .iter_just_pressed()
+        .any(|touch| rect.contains(touch.position()));
+    if !hovered && !touched {
+        return;
+    }
+
+    std::mem::swap(&mut *main_transform, &mut *transform);
+    std::mem::swap(&mut *main_global, &mut *global);
+    std::mem::swap(&mut *main_projection, &mut *projection);
+    let focused = settled(&controller);
+    if let Some(other) = split.other.replace(focused) {
+        controller.from_storage(&other);
+    }
+    controller.animation_target = None;
+    controller.is_animating = false;
+    controller.velocity = Vec3::ZERO;
+    controller.angular_velocity = Vec2::ZERO;
+    controller.pivot = None;
+    controller.pointer_over_overlay = true;
+    split.main_side = split.main_side.opposite();
+}
+
+/// Place both views and move the second camera to its stored view, or take
+/// the whole window back for the main camera when the split closes
+#[allow(clippy::type_complexity)]
+fn update_split_cameras_system(
+    mut split: ResMut<SplitView>,
+    controller: Res<CameraController>,
+    mut main_camera: Query<(&mut Camera, &Msaa), (With<MainCamera>, Without<SplitCamera>)>,
+    mut split_camera: Query<
+        (&mut Camera, &mut Msaa, &mut Transform, &mut Projection),
+        (With<SplitCamera>, Without<MainCamera>),
+    >,
+) {
+    let (Ok((mut main, main_msaa)), Ok((mut camera, mut msaa, mut transform, mut projection))) =
+        (main_camera.single_mut(), split_camera.single_mut())
+    else {
+        return;
+    };
+    let placement = |v: &Viewport| (v.physical_position, v.physical_size);
+
+    let size = main
+        .physical_target_size()
+        .filter(|size| size.x >= 2 && size.y > 0);
+    let Some(size) = size.filter(|_| split.enabled) else {
+        if camera.is_active {
+            camera.is_active = false;
+        }
+        if main.viewport.is_some() {
+            main.viewport = None;
+        }
+        if !split.enabled && split.other.is_some() {
+            split.other = None;
+            split.main_side = SplitSide::Left;
+        }
+        return;
+    };
+
+    let half = size.x / 2;
+    let left = Viewport {
+        physical_position: UVec2::ZERO,
+        physical_size: UVec2::new(half, size.y),
+        ..default()
+    };
+    let right = Viewport {
+        physical_position: UVec2::new(half, 0),
+        physical_size: UVec2::new(size.x - half, size.y),
+        ..default()
+    };
+    let (main_viewport, split_viewport) = match split.main_side {
+        SplitSide::Left => (left, right),
+        SplitSide::Right => (right, left),
+    };
+    if main.viewport.as_ref().map(placement) != Some(placement(&main_viewport)) {
+        main.viewport = Some(main_viewport);
+    }
+    if camera.viewport.as_ref().map(placement) != Some(placement(&split_viewport)) {
+        camera.viewport = Some(split_viewport);
+    }
+    if !camera.is_active {
+        camera.is_active = true;
+    }
+    // Cameras sharing a target have to agree on MSAA
+    if *msaa != *main_msaa {
+        *msaa = *main_msaa;
+    }
+
+    let synchronized = split.synchronized;
+    let other = split.other.get_or_insert_with(|| controller.to_storage());
+    if synchronized {
+        other.target = controller.target.to_array().map(f64::from);
+        other.distance = controller.distance;
+    }
+    let mut view = CameraController {
+        fov: controller.fov,
+        near: controller.near,
+        far: controller.far,
+        ..default()
+    };
+    view.from_storage(other);
+    let next = Transform::from_translation(view.get_position()).looking_at(view.target, Vec3::Y);
+    if *transform != next {
+        *transform = next;
+    }
+    if !projection_matches(&projection, &view) {
+        *projection = projection_of(&view);
+    }
+}
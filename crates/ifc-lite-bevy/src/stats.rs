@@ -0,0 +1,107 @@
+//! Frame statistics for the debug overlay
+//!
+//! While the `stats` overlay is on, frame times are collected and every
+//! [`STATS_WINDOW`] seconds the frame rate, the triangles and meshes drawn
+//! after frustum culling, the batch count and the wasm heap size are
+//! published to storage for the UI.
+
+use crate::mesh::BatchedMesh;
+use crate::storage::FrameStatsStorage;
+use crate::ViewerSettings;
+use bevy::prelude::*;
+
+/// Seconds of frames averaged into one published sample
+const STATS_WINDOW: f32 = 0.5;
+
+/// Stats plugin
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FrameStats>()
+            .add_systems(Last, update_frame_stats);
+    }
+}
+
+/// Frames of the current window and the last published sample
+#[derive(Resource, Default)]
+pub struct FrameStats {
+    frames: u32,
+    elapsed: f32,
+    slowest: f32,
+    pub published: Option<FrameStatsStorage>,
+}
+
+fn update_frame_stats(
+    settings: Res<ViewerSettings>,
+    time: Res<Time<Real>>,
+    meshes: Res<Assets<Mesh>>,
+    drawn: Query<(&Mesh3d, &ViewVisibility)>,
+    batches: Query<(), With<BatchedMesh>>,
+    mut stats: ResMut<FrameStats>,
+) {
+    if !settings.show_stats {
+        if stats.published.take().is_some() {
+            crate::storage::save_frame_stats(None);
+        }
+        stats.frames = 0;
+        stats.elapsed = 0.0;
+        stats.slowest = 0.0;
+        return;
+    }
+
+    let delta = time.delta_secs();
+    stats.frames += 1;
+    stats.elapsed += delta;
+    stats.slowest = stats.slowest.max(delta);
+    if stats.elapsed < STATS_WINDOW {
+        return;
+    }
+
+    let mut triangles = 0;
+    let mut draws = 0;
+    for (mesh, visibility) in &drawn {
+        if !visibility.get() {
+            continue;
+        }
+        let Some(mesh) = meshes.get(&mesh.0) else {
+            continue;
+        };
+        draws += 1;
+        triangles += match mesh.indices() {
+            Some(indices) => indices.len() / 3,
+            None => mesh.count_vertices() / 3,
+        };
+    }
+
+    let sample = FrameStatsStorage {
+        fps: stats.frames as f32 / stats.elapsed,
+        frame_ms: 1000.0 * stats.elapsed / stats.frames as f32,
+        frame_ms_max: 1000.0 * stats.slowest,
+        triangles,
+        draws,
+        batches: batches.iter().count(),
+        heap_bytes: heap_bytes(),
+    };
+    crate::storage::save_frame_stats(Some(&sample));
+    *stats = FrameStats {
+        published: Some(sample),
+        ..default()
+    };
+}
+
+/// Size of this wasm instance's linear memory
+#[cfg(target_arch = "wasm32")]
+fn heap_bytes() -> Option<u64> {
+    use wasm_bindgen::JsCast;
+    let memory = wasm_bindgen::memory()
+        .dyn_into::<js_sys::WebAssembly::Memory>()
+        .ok()?;
+    let buffer = memory.buffer().dyn_into::<js_sys::ArrayBuffer>().ok()?;
+    Some(buffer.byte_length() as u64)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn heap_bytes() -> Option<u64> {
+    None
+}
@@ -1,22 +1,20 @@
-//! localStorage bridge for Bevy-Yew communication
+//! State bridge for Bevy-Yew communication
 //!
-//! This module handles data transfer between Yew UI and Bevy renderer
-//! using localStorage as an intermediary (proven pattern from gldf-rs).
-//! Geometry data uses binary format for efficiency.
+//! This module handles data transfer between Yew UI and Bevy renderer.
+//! Small state (selection, camera, section, ...) goes through the
+//! [`ifc_lite_state`] store: localStorage in the browser, in-memory in the
+//! unified build, files for native hosts (see [`use_state_dir`]).
+//! Geometry and entities use the JS bridge, geometry in the binary format of
+//! [`ifc_lite_state::geometry`] so that large models transfer without JSON
+//! overhead.
 
-use crate::{EntityInfo, IfcMesh};
-use serde::{Deserialize, Serialize};
-
-#[cfg(target_arch = "wasm32")]
 use crate::mesh::MeshGeometry;
-#[cfg(target_arch = "wasm32")]
+use crate::{EntityInfo, GraphicsQuality, IfcMesh};
+use ifc_lite_state::{get_json, set_json, StateStore, SubscriptionId};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
-/// Binary format header magic number
-#[allow(dead_code)]
-const BINARY_MAGIC: u32 = 0x49464342; // "IFCB" in ASCII
-
-/// Storage keys for localStorage
 pub const GEOMETRY_KEY: &str = "ifc_lite_geometry";
 pub const ENTITIES_KEY: &str = "ifc_lite_entities";
 pub const SELECTION_KEY: &str = "ifc_lite_selection";
@@ -27,6 +25,34 @@ pub const TIMESTAMP_KEY: &str = "ifc_lite_timestamp";
 pub const SECTION_KEY: &str = "ifc_lite_section";
 pub const FOCUS_KEY: &str = "ifc_lite_focus";
 pub const CAMERA_CMD_KEY: &str = "ifc_lite_camera_cmd";
+pub const SCENE_INFO_KEY: &str = "ifc_lite_scene_info";
+pub const OVERLAY_KEY: &str = "ifc_lite_overlay";
+pub const RENDER_SETTINGS_KEY: &str = "ifc_lite_render_settings";
+pub const SCALE_BAR_KEY: &str = "ifc_lite_scale_bar";
+pub const SCENE_BOUNDS_KEY: &str = "ifc_lite_scene_bounds";
+pub const XRAY_KEY: &str = "ifc_lite_xray";
+pub const MEASURE_POINTS_KEY: &str = "ifc_lite_measure_points";
+pub const MEASUREMENTS_KEY: &str = "ifc_lite_measurements";
+pub const MEASURE_LABELS_KEY: &str = "ifc_lite_measure_labels";
+pub const GRID_LABELS_KEY: &str = "ifc_lite_grid_labels";
+pub const ANNOTATIONS_KEY: &str = "ifc_lite_annotations";
+pub const ANNOTATION_PICKS_KEY: &str = "ifc_lite_annotation_picks";
+pub const ANNOTATION_LABELS_KEY: &str = "ifc_lite_annotation_labels";
+pub const SPACES_KEY: &str = "ifc_lite_spaces";
+pub const SPACE_LABELS_KEY: &str = "ifc_lite_space_labels";
+pub const EXPLODE_KEY: &str = "ifc_lite_explode";
+pub const NAVIGATION_KEY: &str = "ifc_lite_navigation";
+pub const PALETTE_KEY: &str = "ifc_lite_palette";
+pub const PLAN_KEY: &str = "ifc_lite_plan";
+pub const CAMERA_PATH_KEY: &str = "ifc_lite_camera_path";
+pub const HOVER_KEY: &str = "ifc_lite_hover";
+pub const CONTEXT_MENU_KEY: &str = "ifc_lite_context_menu";
+pub const BATCH_TIMINGS_KEY: &str = "ifc_lite_batch_timings";
+pub const FRAME_STATS_KEY: &str = "ifc_lite_frame_stats";
+pub const CURSOR_KEY: &str = "ifc_lite_cursor";
+pub const SPLIT_VIEW_KEY: &str = "ifc_lite_split_view";
+pub const THUMBNAIL_REQUESTS_KEY: &str = "ifc_lite_thumbnail_requests";
+pub const THUMBNAILS_KEY: &str = "ifc_lite_thumbnails";
 
 /// Selection state for storage
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
@@ -40,6 +66,230 @@ pub struct SelectionStorage {
 pub struct VisibilityStorage {
     pub hidden: Vec<u64>,
     pub isolated: Option<Vec<u64>>,
+    /// Only show entities on this storey
+    #[serde(default)]
+    pub storey: Option<String>,
+}
+
+/// X-ray mode from UI
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct XrayStorage {
+    pub enabled: bool,
+    /// Keep this IFC type opaque instead of the selection
+    #[serde(default)]
+    pub ifc_type: Option<String>,
+}
+
+impl XrayStorage {
+    pub fn focus(&self) -> Option<crate::XrayFocus> {
+        self.enabled.then(|| match &self.ifc_type {
+            Some(ifc_type) => crate::XrayFocus::Type(ifc_type.clone()),
+            None => crate::XrayFocus::Selection,
+        })
+    }
+}
+
+/// Entities sharing one palette color
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PaletteGroupStorage {
+    /// Legend label, e.g. the type object name
+    pub label: String,
+    /// RGB, applied like the model's vertex colors
+    pub color: [f32; 3],
+    pub entities: Vec<u64>,
+}
+
+/// Color overrides from UI; no groups restores the model colors
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PaletteStorage {
+    pub groups: Vec<PaletteGroupStorage>,
+}
+
+/// Storey plan view from UI; no storey closes the plan
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlanStorage {
+    pub storey: Option<String>,
+    /// Height of the cut above the storey elevation, in metres
+    pub cut_height: f32,
+}
+
+/// Split view from UI: a second viewport next to the main one
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SplitViewStorage {
+    pub enabled: bool,
+    /// Both views pan and zoom together, each keeping its view direction
+    #[serde(default)]
+    pub synchronized: bool,
+}
+
+/// Thumbnail requested by the UI
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ThumbnailRequestStorage {
+    /// Name the UI gives the thumbnail, e.g. "storey:Level 1"
+    pub key: String,
+    /// Entities drawn in it
+    pub entities: Vec<u64>,
+}
+
+/// Rendered thumbnail for UI
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ThumbnailStorage {
+    pub key: String,
+    /// Base64 PNG; `None` if none of the entities has geometry
+    pub png: Option<String>,
+}
+
+/// One stop of a camera path
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CameraKeyframeStorage {
+    pub camera: CameraStorage,
+    /// Seconds to get here from the previous keyframe, or from wherever the
+    /// camera is for the first one
+    pub duration: f32,
+}
+
+/// Camera path from UI, also the JSON format paths are exported in
+///
+/// A path plays once `playing` is set with a `run` it has not played yet,
+/// so pressing play again restarts it.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CameraPathStorage {
+    pub keyframes: Vec<CameraKeyframeStorage>,
+    #[serde(default)]
+    pub looping: bool,
+    #[serde(default)]
+    pub playing: bool,
+    #[serde(default)]
+    pub run: u32,
+}
+
+/// Measurement point picked in the viewport, in IFC file coordinates (Z-up,
+/// metres)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MeasurePointStorage {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    /// What the point snapped to: "vertex", "edge" or "face"
+    pub snap: String,
+}
+
+/// Measurement from UI, in IFC file coordinates
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MeasurementStorage {
+    pub id: u32,
+    pub start: [f64; 3],
+    pub end: [f64; 3],
+}
+
+/// Measurements to draw, plus the first point of one in progress
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct MeasurementsStorage {
+    pub measurements: Vec<MeasurementStorage>,
+    pub pending: Option<[f64; 3]>,
+}
+
+/// Measurement label position published to UI
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MeasureLabelStorage {
+    pub id: u32,
+    /// Label anchor in CSS pixels from the top left of the viewport
+    pub x: f32,
+    pub y: f32,
+    /// Length in metres
+    pub distance: f32,
+}
+
+/// Annotation from UI, anchored to a point (IFC file coordinates) or to the
+/// centre of an entity
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AnnotationStorage {
+    pub id: u32,
+    pub entity_id: Option<u64>,
+    pub point: Option<[f64; 3]>,
+}
+
+/// Point clicked in annotate mode, waiting for the UI to add its text
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AnnotationPickStorage {
+    /// Clicked point on the model, in IFC file coordinates
+    pub point: [f64; 3],
+    pub entity_id: u64,
+}
+
+/// Annotation label position published to UI
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AnnotationLabelStorage {
+    pub id: u32,
+    /// Label anchor in CSS pixels from the top left of the viewport
+    pub x: f32,
+    pub y: f32,
+}
+
+/// Grid axis bubble position published to UI
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GridLabelStorage {
+    /// Axis tag, e.g. "A" or "1"
+    pub tag: String,
+    /// Label anchor in CSS pixels from the top left of the viewport
+    pub x: f32,
+    pub y: f32,
+}
+
+/// Space mode from UI: IfcSpace volumes are hidden unless it is on
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SpaceStorage {
+    pub visible: bool,
+    /// Storeys whose spaces stay hidden in space mode
+    #[serde(default)]
+    pub hidden_storeys: Vec<String>,
+}
+
+/// Exploded view from UI
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ExplodeStorage {
+    /// "off", "storeys" or "elements"
+    pub mode: String,
+    /// Spread of the explosion; 1 lifts storeys by a storey height
+    pub factor: f32,
+}
+
+/// Space name label position published to UI
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpaceLabelStorage {
+    pub id: u64,
+    /// Space name, usually the room number
+    pub name: String,
+    /// Label anchor in CSS pixels from the top left of the viewport
+    pub x: f32,
+    pub y: f32,
+}
+
+/// Hovered entity published to UI once the cursor rests on it
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HoverStorage {
+    pub id: u64,
+    /// Cursor in CSS pixels from the top left of the viewport
+    pub x: f32,
+    pub y: f32,
+}
+
+/// Model point under the cursor, published to UI for the status bar
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CursorStorage {
+    /// Entity the point lies on
+    pub id: u64,
+    /// Point in IFC file coordinates (Z up), metres
+    pub point: [f64; 3],
+}
+
+/// Entity right-clicked in the viewport, for the UI's context menu
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ContextMenuStorage {
+    pub id: u64,
+    /// Click in CSS pixels from the top left of the viewport
+    pub x: f32,
+    pub y: f32,
 }
 
 /// Camera state for storage
@@ -48,7 +298,11 @@ pub struct CameraStorage {
     pub azimuth: f32,
     pub elevation: f32,
     pub distance: f32,
-    pub target: [f32; 3],
+    /// Orbit centre (Y up); in file coordinates in the UI's storage
+    pub target: [f64; 3],
+    /// Parallel projection; cameras saved before it existed are perspective
+    #[serde(default)]
+    pub orthographic: bool,
 }
 
 impl Default for CameraStorage {
@@ -58,17 +312,47 @@ impl Default for CameraStorage {
             elevation: 0.615, // ~35 degrees (isometric)
             distance: 10.0,
             target: [0.0, 0.0, 0.0],
+            orthographic: false,
         }
     }
 }
 
-/// Section plane state for storage
+/// One axis-aligned section plane for storage
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SectionPlaneStorage {
+    pub axis: String,  // IFC axis: "x", "y", or "z"
+    pub position: f32, // 0.0 to 1.0 of the scene bounds
+    pub flipped: bool,
+}
+
+/// Section box for storage, relative to the scene bounds per IFC axis
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SectionBoxStorage {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
+/// Horizontal cut above a storey, with the storeys above it hidden
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StoreySectionStorage {
+    pub storey: String,
+    /// Storey elevation (IFC Z), in metres
+    pub elevation: f32,
+    /// Height of the cut above the elevation, in metres
+    pub cut_height: f32,
+}
+
+/// Section planes and box for storage; no planes and no box means no cut
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct SectionStorage {
-    pub enabled: bool,
-    pub axis: String,  // "x", "y", or "z"
-    pub position: f32, // 0.0 to 1.0
-    pub flipped: bool,
+    pub planes: Vec<SectionPlaneStorage>,
+    #[serde(default)]
+    pub section_box: Option<SectionBoxStorage>,
+    /// Colour of cut surfaces, "#rrggbb"
+    #[serde(default)]
+    pub cap_color: Option<String>,
+    #[serde(default)]
+    pub storey: Option<StoreySectionStorage>,
 }
 
 /// Focus command for zooming to entity
@@ -81,11 +365,213 @@ pub struct FocusStorage {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CameraCommandStorage {
     pub cmd: String,
+    /// Argument of "set_mode", "set_projection" ("orthographic" or
+    /// "perspective") and "set_standard_view" (a [`crate::StandardView`] name)
     pub mode: Option<String>,
+    /// Camera to move to for "set_view"
+    #[serde(default)]
+    pub view: Option<CameraStorage>,
+}
+
+/// IfcGrid axis in IFC file coordinates (Z-up, metres)
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GridAxisStorage {
+    /// Axis tag, e.g. "A" or "1"
+    pub tag: String,
+    pub start: [f64; 3],
+    pub end: [f64; 3],
+}
+
+/// Crossing of two grid axes in IFC file coordinates (Z-up, metres)
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GridIntersectionStorage {
+    /// Tags of both axes, e.g. "A/1"
+    pub label: String,
+    pub point: [f64; 3],
+}
+
+/// Model-wide information that is not tied to a single entity
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SceneInfoStorage {
+    /// Angle in radians from model +Y to true north, counter-clockwise
+    pub true_north: Option<f32>,
+    #[serde(default)]
+    pub grid_axes: Vec<GridAxisStorage>,
+    #[serde(default)]
+    pub grid_intersections: Vec<GridIntersectionStorage>,
+    /// Offset (metres, IFC Z-up) taken off the geometry, see
+    /// [`crate::IfcSceneData::origin_shift`]
+    #[serde(default)]
+    pub origin_shift: [f64; 3],
+}
+
+/// Overlay toggles from UI
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OverlayStorage {
+    pub origin_axes: bool,
+    pub north_arrow: bool,
+    pub scale_bar: bool,
+    #[serde(default)]
+    pub minimap: bool,
+    #[serde(default)]
+    pub view_cube: bool,
+    #[serde(default)]
+    pub grid_axes: bool,
+    /// Publish frame statistics for the debug overlay
+    #[serde(default)]
+    pub stats: bool,
+}
+
+impl Default for OverlayStorage {
+    fn default() -> Self {
+        Self {
+            origin_axes: true,
+            north_arrow: true,
+            scale_bar: true,
+            minimap: true,
+            view_cube: true,
+            grid_axes: true,
+            stats: false,
+        }
+    }
+}
+
+/// Renderer defaults the UI picked for the device, with the user's graphics
+/// presets applied
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RenderSettingsStorage {
+    /// MSAA sample count (1 = off)
+    pub msaa_samples: u32,
+    pub shadows: GraphicsQuality,
+    #[serde(default)]
+    pub ambient_occlusion: GraphicsQuality,
+    /// Edge length of spatial batching chunks, in world units
+    pub chunk_size: f32,
+    /// Largest on-screen LOD error, in pixels
+    pub lod_error_px: f32,
+    /// Smooth-shade meshes without normals
+    #[serde(default = "default_smooth_normals")]
+    pub smooth_normals: bool,
+    /// Crease angle for smoothing, in degrees
+    #[serde(default = "default_crease_angle")]
+    pub crease_angle: f32,
+    /// Weld vertices and reorder indices for the GPU while batching
+    #[serde(default)]
+    pub optimize_meshes: bool,
+}
+
+/// Mouse navigation preferences
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NavigationStorage {
+    /// Orbit about the point under the cursor instead of the view centre
+    pub orbit_around_cursor: bool,
+    /// Zoom toward the cursor instead of the view centre
+    pub zoom_to_cursor: bool,
+}
+
+impl Default for NavigationStorage {
+    fn default() -> Self {
+        Self {
+            orbit_around_cursor: true,
+            zoom_to_cursor: true,
+        }
+    }
+}
+
+fn default_smooth_normals() -> bool {
+    true
+}
+
+fn default_crease_angle() -> f32 {
+    30.0
+}
+
+/// Scale bar published to UI while in plan view
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScaleBarStorage {
+    /// Bar length in metres
+    pub length: f32,
+    /// Bar width in CSS pixels
+    pub width_px: f32,
+}
+
+/// Time spent turning the loaded geometry into GPU batches, published to UI
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct BatchTimingsStorage {
+    /// Combining meshes into spatial chunk batches, in milliseconds
+    pub batching_ms: f64,
+    /// Frames that uploaded the new batches, in milliseconds
+    pub upload_ms: f64,
+    /// Meshes in the batches
+    pub meshes: usize,
+}
+
+/// Rendering statistics for the debug overlay, averaged over a short window
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct FrameStatsStorage {
+    pub fps: f32,
+    /// Mean frame time in milliseconds
+    pub frame_ms: f32,
+    /// Slowest frame of the window in milliseconds
+    pub frame_ms_max: f32,
+    /// Triangles in the meshes drawn after frustum culling
+    pub triangles: usize,
+    /// Meshes drawn after frustum culling, roughly the draw calls
+    pub draws: usize,
+    /// Batch meshes of the model, one opaque and one transparent per chunk
+    pub batches: usize,
+    /// Linear memory of the viewer's wasm instance in bytes
+    pub heap_bytes: Option<u64>,
+}
+
+/// Bounds of the loaded geometry in IFC file coordinates (Z up), published to
+/// UI
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SceneBoundsStorage {
+    pub min: [f64; 3],
+    pub max: [f64; 3],
+}
+
+// ============================================================================
+// Binary Geometry Format
+// ============================================================================
+
+/// Parse meshes from the binary transfer format of
+/// [`ifc_lite_state::geometry`]
+///
+/// Returns `None` on a wrong magic, an unknown version or truncated data.
+pub fn decode_geometry(data: &[u8]) -> Option<Vec<IfcMesh>> {
+    let Some(meshes) = ifc_lite_state::decode_geometry(data) else {
+        crate::log(&format!(
+            "[Bevy] Invalid or truncated geometry binary ({} bytes)",
+            data.len()
+        ));
+        return None;
+    };
+    crate::log(&format!(
+        "[Bevy] Parsed {} meshes from binary",
+        meshes.len()
+    ));
+    Some(
+        meshes
+            .into_iter()
+            .map(|mesh| IfcMesh {
+                entity_id: mesh.entity_id,
+                geometry: Arc::new(
+                    MeshGeometry::new(mesh.positions, mesh.normals, mesh.indices)
+                        .with_colors(mesh.colors),
+                ),
+                color: mesh.color,
+                transform: mesh.transform,
+                entity_type: mesh.entity_type,
+                name: mesh.name,
+            })
+            .collect(),
+    )
 }
 
 // ============================================================================
-// WASM Storage Functions
+// WASM JS Bridge Functions
 // ============================================================================
 
 #[cfg(target_arch = "wasm32")]
@@ -104,10 +590,12 @@ mod wasm_storage {
 
         #[wasm_bindgen(js_name = getIfcTimestamp)]
         fn get_ifc_timestamp() -> String;
-    }
 
-    fn get_storage() -> Option<web_sys::Storage> {
-        web_sys::window()?.local_storage().ok()?
+        #[wasm_bindgen(js_name = getIfcGeometryStreamId)]
+        fn get_ifc_geometry_stream_id() -> String;
+
+        #[wasm_bindgen(js_name = takeIfcGeometryChunks)]
+        fn take_ifc_geometry_chunks() -> js_sys::Array;
     }
 
     pub fn get_timestamp() -> Option<String> {
@@ -119,103 +607,6 @@ mod wasm_storage {
         }
     }
 
-    /// Deserialize geometry from binary format
-    fn deserialize_geometry_binary(data: &[u8]) -> Option<Vec<IfcMesh>> {
-        let mut cursor = 0;
-
-        // Helper to read bytes
-        macro_rules! read_bytes {
-            ($n:expr) => {{
-                if cursor + $n > data.len() {
-                    crate::log("[Bevy] Binary data truncated");
-                    return None;
-                }
-                let slice = &data[cursor..cursor + $n];
-                cursor += $n;
-                slice
-            }};
-        }
-
-        // Read header
-        let magic = u32::from_le_bytes(read_bytes!(4).try_into().ok()?);
-        if magic != BINARY_MAGIC {
-            crate::log(&format!("[Bevy] Invalid magic: {:08x}", magic));
-            return None;
-        }
-
-        let version = u32::from_le_bytes(read_bytes!(4).try_into().ok()?);
-        if version != 1 {
-            crate::log(&format!("[Bevy] Unsupported version: {}", version));
-            return None;
-        }
-
-        let mesh_count = u32::from_le_bytes(read_bytes!(4).try_into().ok()?) as usize;
-        crate::log(&format!("[Bevy] Parsing {} meshes from binary", mesh_count));
-
-        let mut meshes = Vec::with_capacity(mesh_count);
-
-        for _ in 0..mesh_count {
-            // entity_id
-            let entity_id = u64::from_le_bytes(read_bytes!(8).try_into().ok()?);
-
-            // positions
-            let positions_len = u32::from_le_bytes(read_bytes!(4).try_into().ok()?) as usize;
-            let mut positions = Vec::with_capacity(positions_len);
-            for _ in 0..positions_len {
-                positions.push(f32::from_le_bytes(read_bytes!(4).try_into().ok()?));
-            }
-
-            // normals
-            let normals_len = u32::from_le_bytes(read_bytes!(4).try_into().ok()?) as usize;
-            let mut normals = Vec::with_capacity(normals_len);
-            for _ in 0..normals_len {
-                normals.push(f32::from_le_bytes(read_bytes!(4).try_into().ok()?));
-            }
-
-            // indices
-            let indices_len = u32::from_le_bytes(read_bytes!(4).try_into().ok()?) as usize;
-            let mut indices = Vec::with_capacity(indices_len);
-            for _ in 0..indices_len {
-                indices.push(u32::from_le_bytes(read_bytes!(4).try_into().ok()?));
-            }
-
-            // color
-            let mut color = [0.0f32; 4];
-            for c in &mut color {
-                *c = f32::from_le_bytes(read_bytes!(4).try_into().ok()?);
-            }
-
-            // transform
-            let mut transform = [0.0f32; 16];
-            for t in &mut transform {
-                *t = f32::from_le_bytes(read_bytes!(4).try_into().ok()?);
-            }
-
-            // entity_type
-            let type_len = read_bytes!(1)[0] as usize;
-            let entity_type = String::from_utf8_lossy(read_bytes!(type_len)).to_string();
-
-            // name
-            let name_len = read_bytes!(1)[0] as usize;
-            let name = if name_len > 0 {
-                Some(String::from_utf8_lossy(read_bytes!(name_len)).to_string())
-            } else {
-                None
-            };
-
-            meshes.push(IfcMesh {
-                entity_id,
-                geometry: Arc::new(MeshGeometry::new(positions, normals, indices)),
-                color,
-                transform,
-                entity_type,
-                name,
-            });
-        }
-
-        Some(meshes)
-    }
-
     pub fn load_geometry() -> Option<Vec<IfcMesh>> {
         let array = match get_ifc_geometry_binary() {
             Some(a) if a.length() > 0 => a,
@@ -232,86 +623,36 @@ mod wasm_storage {
 
         // Copy to Vec<u8>
         let data = array.to_vec();
-        deserialize_geometry_binary(&data)
-    }
-
-    pub fn load_entities() -> Option<Vec<EntityInfo>> {
-        let json = get_ifc_entities()?;
-        serde_json::from_str(&json).ok()
-    }
-
-    pub fn load_selection() -> Option<SelectionStorage> {
-        let storage = get_storage()?;
-        let json = storage.get_item(SELECTION_KEY).ok()??;
-        serde_json::from_str(&json).ok()
+        decode_geometry(&data)
     }
 
-    pub fn save_selection(selection: &SelectionStorage) {
-        if let Some(storage) = get_storage() {
-            if let Ok(json) = serde_json::to_string(selection) {
-                let _ = storage.set_item(SELECTION_KEY, &json);
-                let _ = storage.set_item(SELECTION_SOURCE_KEY, "bevy");
-                update_timestamp();
-            }
+    /// Id of the geometry stream Yew is filling, if any
+    pub fn get_geometry_stream_id() -> Option<String> {
+        let id = get_ifc_geometry_stream_id();
+        if id.is_empty() {
+            None
+        } else {
+            Some(id)
         }
     }
 
-    pub fn load_visibility() -> Option<VisibilityStorage> {
-        let storage = get_storage()?;
-        let json = storage.get_item(VISIBILITY_KEY).ok()??;
-        serde_json::from_str(&json).ok()
-    }
-
-    pub fn load_camera() -> Option<CameraStorage> {
-        let storage = get_storage()?;
-        let json = storage.get_item(CAMERA_KEY).ok()??;
-        serde_json::from_str(&json).ok()
-    }
-
-    pub fn save_camera(camera: &CameraStorage) {
-        if let Some(storage) = get_storage() {
-            if let Ok(json) = serde_json::to_string(camera) {
-                let _ = storage.set_item(CAMERA_KEY, &json);
-                // Don't update timestamp for camera - too frequent
+    /// Take the geometry chunks queued since the last call
+    pub fn take_geometry_chunks() -> Vec<IfcMesh> {
+        let mut meshes = Vec::new();
+        for chunk in take_ifc_geometry_chunks().iter() {
+            let data = Uint8Array::new(&chunk).to_vec();
+            if let Some(chunk_meshes) = decode_geometry(&data) {
+                meshes.extend(chunk_meshes);
             }
         }
+        meshes
     }
 
-    pub fn load_section() -> Option<SectionStorage> {
-        let storage = get_storage()?;
-        let json = storage.get_item(SECTION_KEY).ok()??;
-        serde_json::from_str(&json).ok()
-    }
-
-    pub fn load_focus() -> Option<FocusStorage> {
-        let storage = get_storage()?;
-        let json = storage.get_item(FOCUS_KEY).ok()??;
-        serde_json::from_str(&json).ok()
-    }
-
-    pub fn clear_focus() {
-        if let Some(storage) = get_storage() {
-            let _ = storage.remove_item(FOCUS_KEY);
-        }
-    }
-
-    pub fn load_camera_cmd() -> Option<CameraCommandStorage> {
-        let storage = get_storage()?;
-        let json = storage.get_item(CAMERA_CMD_KEY).ok()??;
-        serde_json::from_str(&json).ok()
-    }
-
-    pub fn clear_camera_cmd() {
-        if let Some(storage) = get_storage() {
-            let _ = storage.remove_item(CAMERA_CMD_KEY);
-        }
-    }
-
-    fn update_timestamp() {
-        if let Some(storage) = get_storage() {
-            let ts = js_sys::Date::now().to_string();
-            let _ = storage.set_item(TIMESTAMP_KEY, &ts);
-        }
+    pub fn load_entities() -> Option<Vec<EntityInfo>> {
+        let json = get_ifc_entities()?;
+        let mut entities: Vec<EntityInfo> = serde_json::from_str(&json).ok()?;
+        crate::intern_entity_strings(&mut entities);
+        Some(entities)
     }
 }
 
@@ -319,7 +660,7 @@ mod wasm_storage {
 pub use wasm_storage::*;
 
 // ============================================================================
-// Native (no-op) Storage Functions
+// Native JS Bridge Stand-ins (no-op)
 // ============================================================================
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -334,42 +675,298 @@ mod native_storage {
         None
     }
 
-    pub fn load_entities() -> Option<Vec<EntityInfo>> {
+    pub fn get_geometry_stream_id() -> Option<String> {
         None
     }
 
-    pub fn load_selection() -> Option<SelectionStorage> {
+    pub fn take_geometry_chunks() -> Vec<IfcMesh> {
+        Vec::new()
+    }
+
+    pub fn load_entities() -> Option<Vec<EntityInfo>> {
         None
     }
+}
 
-    pub fn save_selection(_selection: &SelectionStorage) {}
+#[cfg(not(target_arch = "wasm32"))]
+pub use native_storage::*;
 
-    pub fn load_visibility() -> Option<VisibilityStorage> {
-        None
+// ============================================================================
+// State Store Functions
+// ============================================================================
+
+fn store() -> &'static dyn StateStore {
+    ifc_lite_state::global()
+}
+
+/// Keep the shared state in files under `dir`, for native hosts
+///
+/// Has to be called before the viewer first touches the store; returns
+/// false if that already happened or the directory can't be created.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn use_state_dir(dir: impl Into<std::path::PathBuf>) -> bool {
+    match ifc_lite_state::FileStore::open(dir) {
+        Ok(file_store) => ifc_lite_state::set_global(Arc::new(file_store)).is_ok(),
+        Err(e) => {
+            crate::log(&format!("[Bevy] State directory unavailable: {}", e));
+            false
+        }
     }
+}
 
-    pub fn load_camera() -> Option<CameraStorage> {
-        None
+/// Frames between two reads of a watched key the store can't notify about
+const POLL_FRAMES: u32 = 30;
+
+/// Tells when a key of the shared store may have changed
+///
+/// Writes through the same store instance, as in the unified build, are
+/// seen through a subscription. A store that other instances write to as
+/// well (localStorage, shared with the UI's wasm module) is re-read every
+/// [`POLL_FRAMES`] frames instead.
+pub struct KeyWatch {
+    changed: Arc<AtomicBool>,
+    subscription: SubscriptionId,
+    poll: bool,
+    frame: u32,
+}
+
+impl KeyWatch {
+    pub fn new(key: &str) -> Self {
+        // Read once at startup
+        let changed = Arc::new(AtomicBool::new(true));
+        let flag = Arc::clone(&changed);
+        let subscription = store().subscribe(
+            key,
+            Arc::new(move |_, _| flag.store(true, Ordering::Relaxed)),
+        );
+        Self {
+            changed,
+            subscription,
+            poll: !store().observes_all_writes(),
+            frame: 0,
+        }
     }
 
-    pub fn save_camera(_camera: &CameraStorage) {}
+    /// Whether to read the key this frame
+    pub fn changed(&mut self) -> bool {
+        self.frame = self.frame.wrapping_add(1);
+        let due = self.poll && self.frame.is_multiple_of(POLL_FRAMES);
+        self.changed.swap(false, Ordering::Relaxed) || due
+    }
+}
 
-    pub fn load_section() -> Option<SectionStorage> {
-        None
+impl Drop for KeyWatch {
+    fn drop(&mut self) {
+        store().unsubscribe(self.subscription);
     }
+}
 
-    pub fn load_focus() -> Option<FocusStorage> {
-        None
+/// Milliseconds since the Unix epoch, as the UI writes its timestamps
+fn now_ms() -> String {
+    #[cfg(target_arch = "wasm32")]
+    {
+        js_sys::Date::now().to_string()
     }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis().to_string())
+            .unwrap_or_default()
+    }
+}
 
-    pub fn clear_focus() {}
+pub fn load_selection() -> Option<SelectionStorage> {
+    get_json(store(), SELECTION_KEY)
+}
 
-    pub fn load_camera_cmd() -> Option<CameraCommandStorage> {
-        None
+pub fn save_selection(selection: &SelectionStorage) {
+    if set_json(store(), SELECTION_KEY, selection).is_ok() {
+        let _ = store().set(SELECTION_SOURCE_KEY, "bevy");
+        update_timestamp();
     }
+}
 
-    pub fn clear_camera_cmd() {}
+pub fn load_visibility() -> Option<VisibilityStorage> {
+    get_json(store(), VISIBILITY_KEY)
 }
 
-#[cfg(not(target_arch = "wasm32"))]
-pub use native_storage::*;
+pub fn load_xray() -> Option<XrayStorage> {
+    get_json(store(), XRAY_KEY)
+}
+
+pub fn load_palette() -> Option<PaletteStorage> {
+    get_json(store(), PALETTE_KEY)
+}
+
+pub fn load_plan() -> Option<PlanStorage> {
+    get_json(store(), PLAN_KEY)
+}
+
+pub fn load_split_view() -> Option<SplitViewStorage> {
+    get_json(store(), SPLIT_VIEW_KEY)
+}
+
+/// Thumbnails the UI asked for since the last call
+pub fn take_thumbnail_requests() -> Vec<ThumbnailRequestStorage> {
+    let Some(requests) = get_json(store(), THUMBNAIL_REQUESTS_KEY) else {
+        return Vec::new();
+    };
+    let _ = store().remove(THUMBNAIL_REQUESTS_KEY);
+    requests
+}
+
+/// Queue a rendered thumbnail until the UI collects it
+pub fn push_thumbnail(thumbnail: ThumbnailStorage) {
+    let mut thumbnails: Vec<ThumbnailStorage> =
+        get_json(store(), THUMBNAILS_KEY).unwrap_or_default();
+    thumbnails.push(thumbnail);
+    let _ = set_json(store(), THUMBNAILS_KEY, &thumbnails);
+}
+
+pub fn load_camera_path() -> Option<CameraPathStorage> {
+    get_json(store(), CAMERA_PATH_KEY)
+}
+
+/// Queue a picked point until the UI collects it
+pub fn push_measure_point(point: MeasurePointStorage) {
+    let mut points: Vec<MeasurePointStorage> =
+        get_json(store(), MEASURE_POINTS_KEY).unwrap_or_default();
+    points.push(point);
+    let _ = set_json(store(), MEASURE_POINTS_KEY, &points);
+}
+
+pub fn load_measurements() -> Option<MeasurementsStorage> {
+    get_json(store(), MEASUREMENTS_KEY)
+}
+
+pub fn save_measure_labels(labels: &[MeasureLabelStorage]) {
+    let _ = set_json(store(), MEASURE_LABELS_KEY, labels);
+}
+
+pub fn save_grid_labels(labels: &[GridLabelStorage]) {
+    let _ = set_json(store(), GRID_LABELS_KEY, labels);
+}
+
+pub fn load_annotations() -> Option<Vec<AnnotationStorage>> {
+    get_json(store(), ANNOTATIONS_KEY)
+}
+
+/// Queue a clicked annotation point until the UI collects it
+pub fn push_annotation_pick(pick: AnnotationPickStorage) {
+    let mut picks: Vec<AnnotationPickStorage> =
+        get_json(store(), ANNOTATION_PICKS_KEY).unwrap_or_default();
+    picks.push(pick);
+    let _ = set_json(store(), ANNOTATION_PICKS_KEY, &picks);
+}
+
+pub fn save_annotation_labels(labels: &[AnnotationLabelStorage]) {
+    let _ = set_json(store(), ANNOTATION_LABELS_KEY, labels);
+}
+
+pub fn load_camera() -> Option<CameraStorage> {
+    get_json(store(), CAMERA_KEY)
+}
+
+pub fn save_camera(camera: &CameraStorage) {
+    // Don't update timestamp for camera - too frequent
+    let _ = set_json(store(), CAMERA_KEY, camera);
+}
+
+pub fn load_section() -> Option<SectionStorage> {
+    get_json(store(), SECTION_KEY)
+}
+
+pub fn load_focus() -> Option<FocusStorage> {
+    get_json(store(), FOCUS_KEY)
+}
+
+pub fn clear_focus() {
+    let _ = store().remove(FOCUS_KEY);
+}
+
+pub fn load_camera_cmd() -> Option<CameraCommandStorage> {
+    get_json(store(), CAMERA_CMD_KEY)
+}
+
+pub fn clear_camera_cmd() {
+    let _ = store().remove(CAMERA_CMD_KEY);
+}
+
+pub fn load_scene_info() -> Option<SceneInfoStorage> {
+    get_json(store(), SCENE_INFO_KEY)
+}
+
+pub fn load_overlay() -> Option<OverlayStorage> {
+    get_json(store(), OVERLAY_KEY)
+}
+
+pub fn load_spaces() -> Option<SpaceStorage> {
+    get_json(store(), SPACES_KEY)
+}
+
+pub fn load_explode() -> Option<ExplodeStorage> {
+    get_json(store(), EXPLODE_KEY)
+}
+
+pub fn save_space_labels(labels: &[SpaceLabelStorage]) {
+    let _ = set_json(store(), SPACE_LABELS_KEY, labels);
+}
+
+pub fn load_render_settings() -> Option<RenderSettingsStorage> {
+    get_json(store(), RENDER_SETTINGS_KEY)
+}
+
+pub fn load_navigation() -> Option<NavigationStorage> {
+    get_json(store(), NAVIGATION_KEY)
+}
+
+pub fn save_scale_bar(scale_bar: Option<&ScaleBarStorage>) {
+    // Polled by UI, no timestamp update
+    let _ = match scale_bar {
+        Some(scale_bar) => set_json(store(), SCALE_BAR_KEY, scale_bar),
+        None => store().remove(SCALE_BAR_KEY),
+    };
+}
+
+pub fn save_frame_stats(stats: Option<&FrameStatsStorage>) {
+    // Polled by UI, no timestamp update
+    let _ = match stats {
+        Some(stats) => set_json(store(), FRAME_STATS_KEY, stats),
+        None => store().remove(FRAME_STATS_KEY),
+    };
+}
+
+pub fn save_hover(hover: Option<&HoverStorage>) {
+    // Polled by UI, no timestamp update
+    let _ = match hover {
+        Some(hover) => set_json(store(), HOVER_KEY, hover),
+        None => store().remove(HOVER_KEY),
+    };
+}
+
+pub fn save_cursor(cursor: Option<&CursorStorage>) {
+    // Polled by UI, no timestamp update
+    let _ = match cursor {
+        Some(cursor) => set_json(store(), CURSOR_KEY, cursor),
+        None => store().remove(CURSOR_KEY),
+    };
+}
+
+/// Leave a context menu request until the UI collects it
+pub fn save_context_menu(menu: &ContextMenuStorage) {
+    let _ = set_json(store(), CONTEXT_MENU_KEY, menu);
+}
+
+pub fn save_scene_bounds(bounds: &SceneBoundsStorage) {
+    let _ = set_json(store(), SCENE_BOUNDS_KEY, bounds);
+}
+
+pub fn save_batch_timings(timings: &BatchTimingsStorage) {
+    // Polled by UI, no timestamp update
+    let _ = set_json(store(), BATCH_TIMINGS_KEY, timings);
+}
+
+fn update_timestamp() {
+    let _ = store().set(TIMESTAMP_KEY, &now_ms());
+}
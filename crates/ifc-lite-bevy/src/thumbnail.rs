@@ -0,0 +1,342 @@
+//! Thumbnails - small isometric renders of parts of the model
+//!
+//! The UI asks for a thumbnail by key with the entities to draw, e.g. the
+//! elements of a storey. Requests are rendered one at a time: the entities'
+//! triangles are copied out of the batches onto a render layer of their
+//! own, an orthographic camera looking down the home view's diagonal
+//! renders them into an offscreen image, and the image is read back from the
+//! GPU and handed to the UI as PNG. Thumbnails show the entities whether or
+//! not they are hidden in the main view.
+
+use crate::camera::StandardView;
+use crate::log;
+use crate::mesh::{BatchVisibility, BatchedMesh};
+#[cfg(target_arch = "wasm32")]
+use crate::storage::take_thumbnail_requests;
+use crate::storage::{push_thumbnail, ThumbnailStorage};
+use base64::Engine;
+use bevy::camera::visibility::RenderLayers;
+use bevy::camera::{RenderTarget, ScalingMode};
+use bevy::light::{NotShadowCaster, NotShadowReceiver};
+use bevy::mesh::VertexAttributeValues;
+use bevy::prelude::*;
+use bevy::render::gpu_readback::{Readback, ReadbackComplete};
+use bevy::render::render_resource::{TextureFormat, TextureUsages};
+use rustc_hash::FxHashSet;
+use std::collections::VecDeque;
+
+/// Render layer of the thumbnail meshes, seen only by the thumbnail camera
+pub const THUMBNAIL_LAYER: usize = 5;
+/// Width and height of a thumbnail in pixels
+pub const THUMBNAIL_SIZE: u32 = 128;
+/// Frames between spawning a thumbnail's meshes and reading the image back,
+/// so the meshes are uploaded and drawn
+const RENDER_FRAMES: u32 = 2;
+/// Fraction of the image left empty on each side
+const MARGIN: f32 = 0.06;
+
+/// Offscreen thumbnail plugin
+pub struct ThumbnailPlugin;
+
+impl Plugin for ThumbnailPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ThumbnailQueue>()
+            .add_systems(Startup, setup_thumbnails)
+            .add_systems(
+                Update,
+                (poll_thumbnail_requests_system, render_thumbnail_system).chain(),
+            );
+    }
+}
+
+/// Thumbnail to render
+#[derive(Clone, Debug)]
+pub struct ThumbnailRequest {
+    pub key: String,
+    pub entities: Vec<u64>,
+}
+
+/// Thumbnails waiting to be rendered, and the one being rendered
+#[derive(Resource, Default)]
+pub struct ThumbnailQueue {
+    pub pending: VecDeque<ThumbnailRequest>,
+    active: Option<ActiveThumbnail>,
+}
+
+impl ThumbnailQueue {
+    /// Queue a thumbnail of `entities`, published under `key` when done
+    pub fn request(&mut self, key: impl Into<String>, entities: Vec<u64>) {
+        self.pending.push_back(ThumbnailRequest {
+            key: key.into(),
+            entities,
+        });
+    }
+}
+
+/// Thumbnail on the render layer
+struct ActiveThumbnail {
+    key: String,
+    meshes: Vec<Entity>,
+    frames: u32,
+    readback: Option<Entity>,
+}
+
+/// Marker component for the camera rendering the thumbnails
+#[derive(Component)]
+pub struct ThumbnailCamera;
+
+/// Target of the thumbnail camera and the material of the meshes
+#[derive(Resource)]
+struct ThumbnailTarget {
+    image: Handle<Image>,
+    material: Handle<StandardMaterial>,
+}
+
+fn setup_thumbnails(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let mut image = Image::new_target_texture(
+        THUMBNAIL_SIZE,
+        THUMBNAIL_SIZE,
+        TextureFormat::Rgba8UnormSrgb,
+        None,
+    );
+    // Rendered on the GPU, then copied back
+    image.data = None;
+    image.texture_descriptor.usage |= TextureUsages::COPY_SRC;
+    let image = images.add(image);
+
+    commands.spawn((
+        Camera3d::default(),
+        Camera {
+            // Before every on-screen view
+            order: -2,
+            is_active: false,
+            clear_color: ClearColorConfig::Custom(Color::NONE),
+            ..default()
+        },
+        RenderTarget::Image(image.clone().into()),
+        Projection::Orthographic(OrthographicProjection::default_3d()),
+        RenderLayers::layer(THUMBNAIL_LAYER),
+        ThumbnailCamera,
+    ));
+
+    // White, so the vertex colours come through; lit by the scene lights
+    let material = materials.add(StandardMaterial {
+        base_color: Color::WHITE,
+        double_sided: true,
+        cull_mode: None,
+        ..default()
+    });
+    commands.insert_resource(ThumbnailTarget { image, material });
+}
+
+/// Poll the thumbnail requests from localStorage
+#[allow(unused_mut, unused_variables)]
+fn poll_thumbnail_requests_system(mut queue: ResMut<ThumbnailQueue>, mut frame: Local<u32>) {
+    #[cfg(target_arch = "wasm32")]
+    {
+        *frame = frame.wrapping_add(1);
+        if !frame.is_multiple_of(30) {
+            return;
+        }
+        for request in take_thumbnail_requests() {
+            queue.request(request.key, request.entities);
+        }
+    }
+}
+
+/// Bounds of the vertices of `mesh`
+fn mesh_bounds(mesh: &Mesh) -> Option<(Vec3, Vec3)> {
+    let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+    else {
+        return None;
+    };
+    let mut points = positions.iter().map(|p| Vec3::from_array(*p));
+    let first = points.next()?;
+    Some(points.fold((first, first), |(min, max), p| (min.min(p), max.max(p))))
+}
+
+/// Camera transform and projection framing the box from the home view's
+/// direction
+fn fit_view(min: Vec3, max: Vec3) -> (Transform, Projection) {
+    let (azimuth, elevation) = StandardView::Iso.angles();
+    let direction = Vec3::new(
+        elevation.cos() * azimuth.sin(),
+        elevation.sin(),
+        elevation.cos() * azimuth.cos(),
+    );
+    let center = (min + max) / 2.0;
+    let radius = ((max - min).length() / 2.0).max(0.01);
+    let transform =
+        Transform::from_translation(center + direction * radius * 2.0).looking_at(center, Vec3::Y);
+
+    // Extent of the box corners across the view
+    let to_view = transform.rotation.inverse();
+    let extent = (0..8)
+        .map(|i| {
+            let corner = Vec3::new(
+                if i & 1 == 0 { min.x } else { max.x },
+                if i & 2 == 0 { min.y } else { max.y },
+                if i & 4 == 0 { min.z } else { max.z },
+            );
+            let local = to_view * (corner - center);
+            local.x.abs().max(local.y.abs())
+        })
+        .fold(0.01_f32, f32::max);
+
+    let projection = Projection::Orthographic(OrthographicProjection {
+        near: 0.0,
+        far: radius * 4.0,
+        scaling_mode: ScalingMode::FixedVertical {
+            viewport_height: extent * 2.0 / (1.0 - 2.0 * MARGIN),
+        },
+        ..OrthographicProjection::default_3d()
+    });
+    (transform, projection)
+}
+
+/// Put the next requested thumbnail on the render layer, and read the
+/// image back once it has been drawn
+fn render_thumbnail_system(
+    mut commands: Commands,
+    mut queue: ResMut<ThumbnailQueue>,
+    target: Res<ThumbnailTarget>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    batches: Query<(&BatchedMesh, &BatchVisibility)>,
+    mut camera: Query<(&mut Camera, &mut Transform, &mut Projection), With<ThumbnailCamera>>,
+) {
+    let Ok((mut camera, mut transform, mut projection)) = camera.single_mut() else {
+        return;
+    };
+
+    if let Some(active) = queue.active.as_mut() {
+        if active.readback.is_none() {
+            active.frames += 1;
+            if active.frames >= RENDER_FRAMES {
+                let readback = commands
+                    .spawn(Readback::texture(target.image.clone()))
+                    .observe(thumbnail_read_back)
+                    .id();
+                active.readback = Some(readback);
+            }
+        }
+        return;
+    }
+    // Wait for a model
+    if batches.is_empty() {
+        return;
+    }
+    let Some(request) = queue.pending.pop_front() else {
+        return;
+    };
+
+    // Translucent batches hold spaces and glazing, which would veil the
+    // rest; they are drawn only when nothing else is, e.g. a lone window
+    let ids: FxHashSet<u64> = request.entities.iter().copied().collect();
+    let extract = |transparent: bool| -> Vec<Mesh> {
+        batches
+            .iter()
+            .filter(|(batch, _)| batch.is_transparent == transparent)
+            .filter_map(|(_, visibility)| visibility.meshes.first())
+            .filter_map(|full| full.extract_colored(&meshes, |id| ids.contains(&id)))
+            .collect()
+    };
+    let mut extracted = extract(false);
+    if extracted.is_empty() {
+        extracted = extract(true);
+    }
+    let bounds = extracted
+        .iter()
+        .filter_map(mesh_bounds)
+        .reduce(|(a_min, a_max), (b_min, b_max)| (a_min.min(b_min), a_max.max(b_max)));
+    let Some((min, max)) = bounds else {
+        push_thumbnail(ThumbnailStorage {
+            key: request.key,
+            png: None,
+        });
+        return;
+    };
+
+    let spawned = extracted
+        .into_iter()
+        .map(|mesh| {
+            commands
+                .spawn((
+                    Mesh3d(meshes.add(mesh)),
+                    MeshMaterial3d(target.material.clone()),
+                    Transform::default(),
+                    RenderLayers::layer(THUMBNAIL_LAYER),
+                    NotShadowCaster,
+                    NotShadowReceiver,
+                ))
+                .id()
+        })
+        .collect();
+    (*transform, *projection) = fit_view(min, max);
+    camera.is_active = true;
+    queue.active = Some(ActiveThumbnail {
+        key: request.key,
+        meshes: spawned,
+        frames: 0,
+        readback: None,
+    });
+}
+
+/// Image rows without the padding GPU copies align them to
+fn unpadded_rows(data: &[u8], width: u32, height: u32) -> Vec<u8> {
+    let row = width as usize * 4;
+    let padded = (data.len() / height.max(1) as usize).max(row);
+    data.chunks(padded)
+        .take(height as usize)
+        .filter_map(|line| line.get(..row))
+        .flatten()
+        .copied()
+        .collect()
+}
+
+fn encode_png(rgba: &[u8], width: u32, height: u32) -> Option<Vec<u8>> {
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().ok()?;
+    writer.write_image_data(rgba).ok()?;
+    writer.finish().ok()?;
+    Some(png)
+}
+
+/// Publish the rendered thumbnail and clear the render layer
+fn thumbnail_read_back(
+    event: On<ReadbackComplete>,
+    mut commands: Commands,
+    mut queue: ResMut<ThumbnailQueue>,
+    mut camera: Query<&mut Camera, With<ThumbnailCamera>>,
+) {
+    // The readback runs every frame until it is despawned
+    let Some(active) = queue.active.take_if(|active| active.readback.is_some()) else {
+        return;
+    };
+    let rgba = unpadded_rows(&event.data, THUMBNAIL_SIZE, THUMBNAIL_SIZE);
+    let png = encode_png(&rgba, THUMBNAIL_SIZE, THUMBNAIL_SIZE);
+    if png.is_none() {
+        log(&format!(
+            "[Bevy] Thumbnail {} could not be encoded",
+            active.key
+        ));
+    }
+    push_thumbnail(ThumbnailStorage {
+        key: active.key,
+        png: png.map(|png| base64::engine::general_purpose::STANDARD.encode(png)),
+    });
+
+    for entity in active.meshes.into_iter().chain(active.readback) {
+        commands.entity(entity).despawn();
+    }
+    if let Ok(mut camera) = camera.single_mut() {
+        camera.is_active = false;
+    }
+}
@@ -0,0 +1,144 @@
+//! Color legend - explains the active entity color mapping
+//!
+//! Floats over the bottom left of the viewport while entities are colored
+//! by something other than the model colors.
+
+use super::layout::ViewportArea;
+use super::styles::{UiColors, UiSizes};
+use crate::EntityColorMapping;
+use bevy::ecs::hierarchy::ChildSpawnerCommands;
+use bevy::prelude::*;
+use bevy::ui::{
+    AlignItems, BackgroundColor, BorderRadius, FlexDirection, Node, Overflow, PositionType, UiRect,
+    Val,
+};
+
+/// Rows shown before the rest are summarized
+const MAX_ROWS: usize = 24;
+
+pub struct LegendPlugin;
+
+impl Plugin for LegendPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_legend.after(super::layout::setup_layout))
+            .add_systems(Update, update_legend);
+    }
+}
+
+/// Marker for the legend container
+#[derive(Component)]
+pub struct LegendPanel;
+
+/// Marker for legend rows
+#[derive(Component)]
+pub struct LegendRow;
+
+fn setup_legend(mut commands: Commands, viewport_query: Query<Entity, With<ViewportArea>>) {
+    let Ok(viewport) = viewport_query.single() else {
+        return;
+    };
+
+    commands.entity(viewport).with_children(|viewport| {
+        viewport.spawn((
+            LegendPanel,
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(UiSizes::PADDING),
+                bottom: Val::Px(UiSizes::PADDING),
+                max_height: Val::Percent(60.0),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(UiSizes::PADDING)),
+                overflow: Overflow::scroll_y(),
+                border_radius: BorderRadius::all(Val::Px(UiSizes::BORDER_RADIUS)),
+                ..default()
+            },
+            BackgroundColor(UiColors::PANEL_BG),
+            Visibility::Hidden,
+        ));
+    });
+}
+
+fn update_legend(
+    mut commands: Commands,
+    mapping: Res<EntityColorMapping>,
+    mut panel_query: Query<(Entity, &mut Visibility), With<LegendPanel>>,
+    existing_rows: Query<Entity, With<LegendRow>>,
+) {
+    if !mapping.is_changed() {
+        return;
+    }
+    let Ok((panel, mut visibility)) = panel_query.single_mut() else {
+        return;
+    };
+
+    for entity in existing_rows.iter() {
+        commands.entity(entity).despawn();
+    }
+    *visibility = if mapping.legend.is_empty() {
+        Visibility::Hidden
+    } else {
+        Visibility::Inherited
+    };
+
+    commands.entity(panel).with_children(|panel| {
+        for entry in mapping.legend.iter().take(MAX_ROWS) {
+            spawn_legend_row(
+                panel,
+                Some(Color::srgb(entry.color[0], entry.color[1], entry.color[2])),
+                &entry.label,
+                entry.count,
+            );
+        }
+        let rest = &mapping.legend[mapping.legend.len().min(MAX_ROWS)..];
+        if !rest.is_empty() {
+            let count = rest.iter().map(|entry| entry.count).sum();
+            spawn_legend_row(panel, None, &format!("{} more", rest.len()), count);
+        }
+    });
+}
+
+fn spawn_legend_row(
+    parent: &mut ChildSpawnerCommands,
+    swatch: Option<Color>,
+    label: &str,
+    count: usize,
+) {
+    parent
+        .spawn((
+            LegendRow,
+            Node {
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(UiSizes::PADDING),
+                padding: UiRect::vertical(Val::Px(2.0)),
+                ..default()
+            },
+        ))
+        .with_children(|row: &mut ChildSpawnerCommands| {
+            row.spawn((
+                Node {
+                    width: Val::Px(12.0),
+                    height: Val::Px(12.0),
+                    border_radius: BorderRadius::all(Val::Px(2.0)),
+                    ..default()
+                },
+                BackgroundColor(swatch.unwrap_or(Color::NONE)),
+            ));
+            row.spawn((
+                Text::new(label),
+                TextFont {
+                    font_size: UiSizes::FONT_SIZE_SM,
+                    ..default()
+                },
+                TextColor(UiColors::TEXT_PRIMARY),
+            ));
+            row.spawn((
+                Text::new(count.to_string()),
+                TextFont {
+                    font_size: UiSizes::FONT_SIZE_SM,
+                    ..default()
+                },
+                TextColor(UiColors::TEXT_SECONDARY),
+            ));
+        });
+}
@@ -4,13 +4,17 @@
 
 mod hierarchy;
 mod layout;
+mod legend;
 mod properties;
+mod start_screen;
 mod styles;
 mod toolbar;
 
 pub use hierarchy::*;
 pub use layout::*;
+pub use legend::*;
 pub use properties::*;
+pub use start_screen::*;
 pub use styles::*;
 pub use toolbar::{ButtonAction, ToolbarButton, ToolbarPlugin};
 
@@ -29,6 +33,8 @@ impl Plugin for IfcUiPlugin {
                 ToolbarPlugin,
                 HierarchyPlugin,
                 PropertiesPlugin,
+                LegendPlugin,
+                StartScreenPlugin,
             ))
             .add_systems(Update, ui_scroll_system);
     }
@@ -113,6 +119,8 @@ pub struct UiState {
     pub search_filter: String,
     /// Selected storey filter
     pub storey_filter: Option<String>,
+    /// What entities are colored by
+    pub color_by: crate::ColorBy,
 }
 
 impl Default for UiState {
@@ -122,6 +130,7 @@ impl Default for UiState {
             show_properties: true,
             search_filter: String::new(),
             storey_filter: None,
+            color_by: crate::ColorBy::Model,
         }
     }
 }
@@ -92,6 +92,14 @@ fn update_properties(
                 // Entity type
                 spawn_property_row(content, "Type", &entity_info.entity_type);
 
+                // Predefined type / object type
+                if let Some(ref predefined) = entity_info.predefined_type {
+                    spawn_property_row(content, "Predefined", predefined);
+                }
+                if let Some(ref object_type) = entity_info.object_type {
+                    spawn_property_row(content, "Object Type", object_type);
+                }
+
                 // Name
                 if let Some(ref name) = entity_info.name {
                     spawn_property_row(content, "Name", name);
@@ -0,0 +1,224 @@
+//! Start screen - sample model gallery shown while no model is loaded
+//!
+//! Samples come from the same `samples.json` manifest the web viewer serves,
+//! read from the directory in `IFC_LITE_SAMPLES` (default `samples`). Entries
+//! are opened from that directory; entries with a URL are skipped, as the
+//! native viewer cannot download. On the web the gallery stays empty.
+
+use super::layout::ViewportArea;
+use super::styles::{UiColors, UiSizes};
+use crate::loader::LoadIfcFileEvent;
+use crate::IfcSceneData;
+use bevy::ecs::hierarchy::ChildSpawnerCommands;
+use bevy::prelude::*;
+use bevy::ui::{
+    widget::Button, AlignItems, BackgroundColor, BorderRadius, FlexDirection, Interaction,
+    JustifyContent, Node, PositionType, UiRect, Val,
+};
+use serde::Deserialize;
+use std::path::PathBuf;
+
+pub struct StartScreenPlugin;
+
+impl Plugin for StartScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SampleGallery::load())
+            .add_systems(
+                Startup,
+                setup_start_screen.after(super::layout::setup_layout),
+            )
+            .add_systems(Update, (sample_button_interaction, update_start_screen));
+    }
+}
+
+/// Sample model manifest, as in the web viewer's `samples.json`
+#[derive(Deserialize, Default)]
+struct SampleManifest {
+    samples: Vec<SampleModel>,
+}
+
+#[derive(Deserialize, Clone)]
+struct SampleModel {
+    name: String,
+    #[serde(default)]
+    description: String,
+    file: String,
+}
+
+/// Samples that can be opened from disk
+#[derive(Resource, Default)]
+pub struct SampleGallery {
+    /// Display name, description and file path of each sample
+    pub samples: Vec<(String, String, PathBuf)>,
+}
+
+impl SampleGallery {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn load() -> Self {
+        let dir = PathBuf::from(
+            std::env::var("IFC_LITE_SAMPLES").unwrap_or_else(|_| "samples".to_string()),
+        );
+        let Ok(json) = std::fs::read_to_string(dir.join("samples.json")) else {
+            return Self::default();
+        };
+        let manifest: SampleManifest = match serde_json::from_str(&json) {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                crate::log(&format!("[UI] Invalid sample manifest: {}", e));
+                return Self::default();
+            }
+        };
+        let samples = manifest
+            .samples
+            .into_iter()
+            .filter(|sample| !sample.file.contains("://"))
+            .map(|sample| (sample.name, sample.description, dir.join(sample.file)))
+            .filter(|(_, _, path)| path.is_file())
+            .collect();
+        Self { samples }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn load() -> Self {
+        Self::default()
+    }
+}
+
+/// Marker for the start screen container
+#[derive(Component)]
+pub struct StartScreen;
+
+/// Gallery button opening one sample
+#[derive(Component)]
+pub struct SampleButton(pub PathBuf);
+
+fn setup_start_screen(
+    mut commands: Commands,
+    gallery: Res<SampleGallery>,
+    viewport_query: Query<Entity, With<ViewportArea>>,
+) {
+    let Ok(viewport) = viewport_query.single() else {
+        return;
+    };
+
+    commands.entity(viewport).with_children(|viewport| {
+        viewport
+            .spawn((
+                StartScreen,
+                Node {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(UiSizes::PADDING),
+                    ..default()
+                },
+            ))
+            .with_children(|screen| {
+                screen.spawn((
+                    Text::new("Open an IFC file to view it in 3D"),
+                    TextFont {
+                        font_size: UiSizes::FONT_SIZE_LG,
+                        ..default()
+                    },
+                    TextColor(UiColors::TEXT_PRIMARY),
+                ));
+                if gallery.samples.is_empty() {
+                    return;
+                }
+                screen.spawn((
+                    Text::new("or try a sample model"),
+                    TextFont {
+                        font_size: UiSizes::FONT_SIZE_SM,
+                        ..default()
+                    },
+                    TextColor(UiColors::TEXT_SECONDARY),
+                ));
+                for (name, description, path) in &gallery.samples {
+                    spawn_sample_button(screen, name, description, path.clone());
+                }
+            });
+    });
+}
+
+fn spawn_sample_button(
+    parent: &mut ChildSpawnerCommands,
+    name: &str,
+    description: &str,
+    path: PathBuf,
+) {
+    parent
+        .spawn((
+            SampleButton(path),
+            Button,
+            Node {
+                width: Val::Px(UiSizes::PANEL_WIDTH),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(UiSizes::PADDING)),
+                border_radius: BorderRadius::all(Val::Px(UiSizes::BORDER_RADIUS)),
+                ..default()
+            },
+            BackgroundColor(UiColors::BUTTON_BG),
+        ))
+        .with_children(|btn: &mut ChildSpawnerCommands| {
+            btn.spawn((
+                Text::new(name),
+                TextFont {
+                    font_size: UiSizes::FONT_SIZE,
+                    ..default()
+                },
+                TextColor(UiColors::TEXT_PRIMARY),
+            ));
+            btn.spawn((
+                Text::new(description),
+                TextFont {
+                    font_size: UiSizes::FONT_SIZE_SM,
+                    ..default()
+                },
+                TextColor(UiColors::TEXT_SECONDARY),
+            ));
+        });
+}
+
+fn sample_button_interaction(
+    mut query: Query<(&Interaction, &mut BackgroundColor, &SampleButton), Changed<Interaction>>,
+    mut load_events: MessageWriter<LoadIfcFileEvent>,
+) {
+    for (interaction, mut bg_color, button) in query.iter_mut() {
+        match *interaction {
+            Interaction::Pressed => {
+                *bg_color = BackgroundColor(UiColors::BUTTON_ACTIVE);
+                crate::log_info(&format!("[UI] Opening sample {:?}", button.0));
+                load_events.write(LoadIfcFileEvent {
+                    path: button.0.clone(),
+                });
+            }
+            Interaction::Hovered => {
+                *bg_color = BackgroundColor(UiColors::BUTTON_HOVER);
+            }
+            Interaction::None => {
+                *bg_color = BackgroundColor(UiColors::BUTTON_BG);
+            }
+        }
+    }
+}
+
+/// Hide the start screen once a model has geometry
+fn update_start_screen(
+    scene_data: Res<IfcSceneData>,
+    mut screen_query: Query<&mut Visibility, With<StartScreen>>,
+) {
+    if !scene_data.is_changed() {
+        return;
+    }
+    let Ok(mut visibility) = screen_query.single_mut() else {
+        return;
+    };
+    *visibility = if scene_data.meshes.is_empty() {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+}
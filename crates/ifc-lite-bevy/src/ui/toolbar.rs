@@ -15,7 +15,7 @@ pub struct ToolbarPlugin;
 impl Plugin for ToolbarPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, setup_toolbar.after(super::layout::setup_layout))
-            .add_systems(Update, button_interaction);
+            .add_systems(Update, (button_interaction, refresh_color_mapping));
     }
 }
 
@@ -33,6 +33,12 @@ pub enum ButtonAction {
     ToggleHierarchy,
     ToggleProperties,
     ToggleSection,
+    /// Cycle the entity color mode
+    ColorBy,
+    /// Toggle space mode
+    ToggleSpaces,
+    /// Pull the storeys apart or put them back
+    ToggleExplode,
 }
 
 fn setup_toolbar(mut commands: Commands, toolbar_query: Query<Entity, With<ToolbarContainer>>) {
@@ -57,6 +63,9 @@ fn setup_toolbar(mut commands: Commands, toolbar_query: Query<Entity, With<Toolb
 
         // Tools
         spawn_button(toolbar, "Section", ButtonAction::ToggleSection);
+        spawn_button(toolbar, "Color", ButtonAction::ColorBy);
+        spawn_button(toolbar, "Spaces", ButtonAction::ToggleSpaces);
+        spawn_button(toolbar, "Explode", ButtonAction::ToggleExplode);
 
         // Spacer
         toolbar.spawn(Node {
@@ -116,6 +125,7 @@ fn spawn_separator(parent: &mut ChildSpawnerCommands) {
     ));
 }
 
+#[allow(clippy::too_many_arguments)]
 fn button_interaction(
     mut query: Query<(&Interaction, &mut BackgroundColor, &ToolbarButton), Changed<Interaction>>,
     mut ui_state: ResMut<super::UiState>,
@@ -136,6 +146,9 @@ fn button_interaction(
     mut open_dialog_events: MessageWriter<crate::loader::OpenFileDialogRequest>,
     mut camera_controller: ResMut<crate::camera::CameraController>,
     scene_data: Res<crate::IfcSceneData>,
+    mut color_mapping: ResMut<crate::EntityColorMapping>,
+    mut settings: ResMut<crate::ViewerSettings>,
+    mut explode: ResMut<crate::ExplodeState>,
 ) {
     for (interaction, mut bg_color, button) in query.iter_mut() {
         match *interaction {
@@ -184,6 +197,23 @@ fn button_interaction(
                     ButtonAction::ToggleSection => {
                         crate::log("[UI] Toggle section requested");
                     }
+                    ButtonAction::ColorBy => {
+                        ui_state.color_by = ui_state.color_by.next();
+                        crate::log(&format!("[UI] Color by {:?}", ui_state.color_by));
+                        *color_mapping = crate::EntityColorMapping::for_mode(
+                            &scene_data.entities,
+                            ui_state.color_by,
+                        );
+                    }
+                    ButtonAction::ToggleSpaces => {
+                        settings.show_spaces = !settings.show_spaces;
+                        crate::log(&format!("[UI] Spaces shown: {}", settings.show_spaces));
+                    }
+                    ButtonAction::ToggleExplode => {
+                        explode.mode = crate::ExplodeMode::Storeys;
+                        explode.factor = if explode.factor > 0.0 { 0.0 } else { 1.0 };
+                        crate::log(&format!("[UI] Explode factor: {}", explode.factor));
+                    }
                 }
             }
             Interaction::Hovered => {
@@ -195,3 +225,15 @@ fn button_interaction(
         }
     }
 }
+
+/// Recompute the color mapping of the active mode for a new scene
+fn refresh_color_mapping(
+    ui_state: Res<super::UiState>,
+    scene_data: Res<crate::IfcSceneData>,
+    mut color_mapping: ResMut<crate::EntityColorMapping>,
+) {
+    if scene_data.is_changed() && ui_state.color_by != crate::ColorBy::Model {
+        *color_mapping =
+            crate::EntityColorMapping::for_mode(&scene_data.entities, ui_state.color_by);
+    }
+}
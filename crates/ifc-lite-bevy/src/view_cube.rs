@@ -0,0 +1,276 @@
+//! View cube - orientation gizmo in a corner of the viewport
+//!
+//! A small cube turns with the main camera in the top-right corner. Its
+//! faces carry the axis colours of the origin axes: red for X, green for
+//! IFC Y, blue for IFC Z, darker on the negative side. Clicking a face snaps
+//! the camera to that standard view; clicking near an edge or a corner looks
+//! at the model from that edge or corner, the front right top one being the
+//! home view.
+//!
+//! Like the mini-map it renders into the window through a camera of its own
+//! that sees only its render layer.
+
+use crate::camera::{CameraController, CameraInputSet, MainCamera, OverlayInputSet, StandardView};
+use crate::ViewerSettings;
+use bevy::camera::visibility::RenderLayers;
+use bevy::camera::{ScalingMode, Viewport};
+use bevy::math::bounding::{Aabb3d, RayCast3d};
+use bevy::prelude::*;
+use bevy::render::view::Msaa;
+
+/// Render layer of the cube and its outline
+pub const VIEW_CUBE_LAYER: usize = 2;
+/// Edge length of the view cube viewport, in logical pixels
+const VIEW_CUBE_SIZE_PX: f32 = 96.0;
+/// Gap between the view cube and the viewport edges, in logical pixels
+const VIEW_CUBE_MARGIN_PX: f32 = 12.0;
+/// Band along the cube edges that picks an edge or corner view instead of
+/// the face, in cube units (the cube is one unit wide)
+const EDGE_BAND: f32 = 0.2;
+
+/// View cube plugin
+pub struct ViewCubePlugin;
+
+impl Plugin for ViewCubePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_gizmo_group::<ViewCubeGizmos>()
+            .init_resource::<ViewCubeHover>()
+            .add_systems(Startup, setup_view_cube)
+            .add_systems(Update, view_cube_click_system.in_set(OverlayInputSet))
+            .add_systems(
+                Update,
+                (update_view_cube_camera_system, draw_view_cube_system)
+                    .chain()
+                    .after(CameraInputSet),
+            );
+    }
+}
+
+/// Gizmos drawn only on the view cube
+#[derive(Default, Reflect, GizmoConfigGroup)]
+pub struct ViewCubeGizmos;
+
+/// Marker component for the view cube camera
+#[derive(Component)]
+pub struct ViewCubeCamera;
+
+/// Part of the cube under the cursor, as the direction from the cube centre:
+/// one non-zero component for a face, two for an edge, three for a corner
+#[derive(Resource, Default)]
+struct ViewCubeHover(Option<IVec3>);
+
+fn setup_view_cube(
+    mut commands: Commands,
+    mut gizmo_config: ResMut<GizmoConfigStore>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let (config, _) = gizmo_config.config_mut::<ViewCubeGizmos>();
+    config.render_layers = RenderLayers::layer(VIEW_CUBE_LAYER);
+    // Edges lie on the faces, keep them in front
+    config.depth_bias = -0.1;
+    config.line.width = 1.5;
+
+    commands.spawn((
+        Camera3d::default(),
+        Camera {
+            order: 2,
+            is_active: false,
+            clear_color: ClearColorConfig::None,
+            ..default()
+        },
+        Projection::Orthographic(OrthographicProjection {
+            near: 0.0,
+            far: 8.0,
+            // Room for the cube seen across its diagonal
+            scaling_mode: ScalingMode::Fixed {
+                width: 1.9,
+                height: 1.9,
+            },
+            ..OrthographicProjection::default_3d()
+        }),
+        RenderLayers::layer(VIEW_CUBE_LAYER),
+        ViewCubeCamera,
+    ));
+
+    // World +Z is IFC -Y, so the front face is the dark green one
+    let faces = [
+        (Vec3::X, Color::srgb(0.85, 0.3, 0.3)),
+        (Vec3::NEG_X, Color::srgb(0.5, 0.18, 0.18)),
+        (Vec3::NEG_Z, Color::srgb(0.3, 0.75, 0.3)),
+        (Vec3::Z, Color::srgb(0.18, 0.45, 0.18)),
+        (Vec3::Y, Color::srgb(0.3, 0.45, 0.85)),
+        (Vec3::NEG_Y, Color::srgb(0.18, 0.27, 0.5)),
+    ];
+    for (normal, color) in faces {
+        commands.spawn((
+            Mesh3d(meshes.add(Plane3d::new(normal, Vec2::splat(0.5)).mesh())),
+            MeshMaterial3d(materials.add(StandardMaterial {
+                base_color: color,
+                unlit: true,
+                ..default()
+            })),
+            Transform::from_translation(normal * 0.5),
+            RenderLayers::layer(VIEW_CUBE_LAYER),
+        ));
+    }
+}
+
+/// Viewport of the view cube in the top-right corner of the main view, if
+/// it has room
+fn view_cube_viewport(main: &Camera) -> Option<Viewport> {
+    let (main_rect, scale) = main
+        .physical_viewport_rect()
+        .zip(main.target_scaling_factor())?;
+    let size = (VIEW_CUBE_SIZE_PX * scale) as u32;
+    let margin = (VIEW_CUBE_MARGIN_PX * scale) as u32;
+    let view = main_rect.size();
+    (view.x >= size * 4 && view.y >= size * 3).then(|| Viewport {
+        physical_position: UVec2::new(main_rect.max.x - size - margin, main_rect.min.y + margin),
+        physical_size: UVec2::splat(size),
+        ..default()
+    })
+}
+
+/// Place the view cube viewport and turn its camera with the main one
+#[allow(clippy::type_complexity)]
+fn update_view_cube_camera_system(
+    settings: Res<ViewerSettings>,
+    main_camera: Query<(&Camera, &Msaa, &Transform), (With<MainCamera>, Without<ViewCubeCamera>)>,
+    mut view_cube: Query<(&mut Camera, &mut Msaa, &mut Transform), With<ViewCubeCamera>>,
+) {
+    let (Ok((main, main_msaa, main_transform)), Ok((mut camera, mut msaa, mut transform))) =
+        (main_camera.single(), view_cube.single_mut())
+    else {
+        return;
+    };
+    let viewport = view_cube_viewport(main);
+    let active = settings.show_view_cube && viewport.is_some();
+    if camera.is_active != active {
+        camera.is_active = active;
+    }
+    if !active {
+        return;
+    }
+    let placement = |v: &Viewport| (v.physical_position, v.physical_size);
+    if camera.viewport.as_ref().map(placement) != viewport.as_ref().map(placement) {
+        camera.viewport = viewport;
+    }
+    if *msaa != *main_msaa {
+        *msaa = *main_msaa;
+    }
+
+    let rotation = main_transform.rotation;
+    let next = Transform::from_translation(rotation * Vec3::Z * 4.0).with_rotation(rotation);
+    if *transform != next {
+        *transform = next;
+    }
+}
+
+/// Outline the cube and the part of it under the cursor
+fn draw_view_cube_system(
+    hover: Res<ViewCubeHover>,
+    view_cube: Query<&Camera, With<ViewCubeCamera>>,
+    mut gizmos: Gizmos<ViewCubeGizmos>,
+) {
+    if !view_cube.single().is_ok_and(|camera| camera.is_active) {
+        return;
+    }
+    gizmos.cube(Transform::default(), Color::srgb(0.1, 0.1, 0.1));
+
+    let Some(region) = hover.0 else {
+        return;
+    };
+    // Box covering the hovered face, edge or corner on the cube surface
+    let (mut min, mut max) = (Vec3::ZERO, Vec3::ZERO);
+    for axis in 0..3 {
+        (min[axis], max[axis]) = match region[axis] {
+            1 => (0.5 - EDGE_BAND, 0.5),
+            -1 => (-0.5, -0.5 + EDGE_BAND),
+            _ => (-0.5 + EDGE_BAND, 0.5 - EDGE_BAND),
+        };
+    }
+    gizmos.cube(
+        Transform::from_translation((min + max) * 0.5).with_scale(max - min),
+        Color::srgb(1.0, 0.8, 0.1),
+    );
+}
+
+/// Part of the cube a ray hits, see [`ViewCubeHover`]
+fn cube_region(ray: Ray3d) -> Option<IVec3> {
+    let cube = Aabb3d::new(Vec3::ZERO, Vec3::splat(0.5));
+    let t = RayCast3d::from_ray(ray, f32::MAX).aabb_intersection_at(&cube)?;
+    let point = ray.get_point(t);
+    let side = |v: f32| {
+        if v > 0.5 - EDGE_BAND {
+            1
+        } else if v < -0.5 + EDGE_BAND {
+            -1
+        } else {
+            0
+        }
+    };
+    let region = IVec3::new(side(point.x), side(point.y), side(point.z));
+    (region != IVec3::ZERO).then_some(region)
+}
+
+/// Standard view looking at a face of the cube
+fn face_view(region: IVec3) -> Option<StandardView> {
+    match region.to_array() {
+        [1, 0, 0] => Some(StandardView::Right),
+        [-1, 0, 0] => Some(StandardView::Left),
+        [0, 1, 0] => Some(StandardView::Top),
+        [0, -1, 0] => Some(StandardView::Bottom),
+        [0, 0, 1] => Some(StandardView::Front),
+        [0, 0, -1] => Some(StandardView::Back),
+        _ => None,
+    }
+}
+
+/// Track the part of the cube under the cursor and turn the camera to it
+/// on click
+///
+/// Runs before the camera input, like the mini-map.
+fn view_cube_click_system(
+    mouse_button: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window>,
+    view_cube: Query<(&Camera, &GlobalTransform), With<ViewCubeCamera>>,
+    mut controller: ResMut<CameraController>,
+    mut hover: ResMut<ViewCubeHover>,
+) {
+    let cursor = windows.single().ok().and_then(Window::cursor_position);
+    let over = view_cube.single().ok().and_then(|(camera, transform)| {
+        let cursor = cursor?;
+        let inside = camera.is_active
+            && camera
+                .logical_viewport_rect()
+                .is_some_and(|rect| rect.contains(cursor));
+        (inside && !controller.is_dragging).then_some((camera, transform, cursor))
+    });
+    let region = over.and_then(|(camera, transform, cursor)| {
+        camera
+            .viewport_to_world(transform, cursor)
+            .ok()
+            .and_then(cube_region)
+    });
+    if hover.0 != region {
+        hover.0 = region;
+    }
+    if over.is_none() {
+        return;
+    }
+    controller.pointer_over_overlay = true;
+    let Some(region) = region else {
+        return;
+    };
+    if !mouse_button.just_pressed(MouseButton::Left) {
+        return;
+    }
+    match face_view(region) {
+        Some(view) => controller.set_standard_view(view),
+        None => {
+            let direction = region.as_vec3().normalize();
+            controller.turn_to(direction.x.atan2(direction.z), direction.y.asin());
+        }
+    }
+}
@@ -0,0 +1,170 @@
+//! Walk aids - click to teleport and standing on the floor
+//!
+//! In [`CameraMode::Walk`] a click moves the eye to the clicked floor, at eye
+//! height: a click on a wall or door lands on the floor in front of it.
+//! Between clicks the eye stays at eye height above whatever lies below it,
+//! so walking over a slab edge steps down and a stair tread steps up. Where
+//! no geometry is below, the nearest storey elevation is the floor. Holding
+//! Q or E flies up or down as before; the eye lands once they are released.
+
+use crate::camera::{CameraAnimationTarget, CameraController, CameraMode, MainCamera};
+use crate::mesh::TriangleEntityMapping;
+use crate::picking::{pick_entity, PickableBatch};
+use crate::IfcSceneData;
+use bevy::prelude::*;
+
+/// Walk aids plugin
+pub struct WalkPlugin;
+
+impl Plugin for WalkPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WalkSettings>().add_systems(
+            Update,
+            (teleport_system, stand_on_floor_system)
+                .chain()
+                .after(crate::camera::CameraInputSet),
+        );
+    }
+}
+
+/// How the eye keeps to the floor in walk mode, in world units (metres)
+#[derive(Resource, Clone, Debug)]
+pub struct WalkSettings {
+    /// Keep the eye at eye height above the floor
+    pub snap_to_floor: bool,
+    pub eye_height: f32,
+    /// Highest floor step the eye climbs; higher obstacles are walked into
+    pub max_step: f32,
+}
+
+impl Default for WalkSettings {
+    fn default() -> Self {
+        Self {
+            snap_to_floor: true,
+            eye_height: 1.7,
+            max_step: 0.5,
+        }
+    }
+}
+
+/// Smallest upward component of a surface normal that still counts as floor
+const FLOOR_NORMAL_Y: f32 = 0.7;
+
+/// Distance a click on a wall lands in front of it
+const WALL_CLEARANCE: f32 = 0.4;
+
+/// Rate the eye settles onto a new floor height, per second
+const SETTLE_RATE: f32 = 10.0;
+
+/// Height of the first floor below `point`, if any geometry is there
+fn floor_below(
+    point: Vec3,
+    batched_meshes: &Query<PickableBatch>,
+    triangle_mapping: &TriangleEntityMapping,
+    meshes: &Assets<Mesh>,
+) -> Option<f32> {
+    let ray = Ray3d::new(point, Dir3::NEG_Y);
+    pick_entity(&ray, batched_meshes, triangle_mapping, meshes).map(|hit| hit.point.y)
+}
+
+/// Elevation of the storey nearest below `height`
+fn storey_floor_below(height: f32, scene_data: &IfcSceneData) -> Option<f32> {
+    scene_data
+        .entities
+        .iter()
+        .filter_map(|e| e.storey_elevation)
+        .map(|elevation| scene_data.world_elevation(elevation))
+        .filter(|elevation| *elevation <= height)
+        .max_by(f32::total_cmp)
+}
+
+/// Teleport to the floor under a click
+fn teleport_system(
+    cameras: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    batched_meshes: Query<PickableBatch>,
+    triangle_mapping: Res<TriangleEntityMapping>,
+    meshes: Res<Assets<Mesh>>,
+    walk: Res<WalkSettings>,
+    mut controller: ResMut<CameraController>,
+) {
+    if controller.mode != CameraMode::Walk || !controller.just_clicked {
+        return;
+    }
+    controller.just_clicked = false;
+    let Ok((camera, camera_transform)) = cameras.single() else {
+        return;
+    };
+    let Ok(ray) = camera.viewport_to_world(camera_transform, controller.drag_start_pos) else {
+        return;
+    };
+    let Some(hit) = pick_entity(&ray, &batched_meshes, &triangle_mapping, &meshes) else {
+        return;
+    };
+
+    // Triangles may face either way; a floor is flat whichever way it winds
+    let [a, b, c] = hit.triangle;
+    let normal = (b - a).cross(c - a).normalize_or_zero();
+    let floor = if normal.y.abs() >= FLOOR_NORMAL_Y {
+        hit.point
+    } else {
+        let in_front = hit.point - *ray.direction * WALL_CLEARANCE;
+        let Some(height) = floor_below(in_front, &batched_meshes, &triangle_mapping, &meshes)
+        else {
+            return;
+        };
+        Vec3::new(in_front.x, height, in_front.z)
+    };
+
+    let eye = controller.get_position();
+    let destination = floor + Vec3::Y * walk.eye_height;
+    crate::log(&format!(
+        "[Bevy] Teleport to ({:.2}, {:.2}, {:.2})",
+        floor.x, floor.y, floor.z
+    ));
+    controller.animation_target = Some(CameraAnimationTarget {
+        azimuth: controller.azimuth,
+        elevation: controller.elevation,
+        distance: controller.distance,
+        target: controller.target + (destination - eye),
+        duration: 0.5,
+        elapsed: 0.0,
+    });
+    controller.is_animating = true;
+}
+
+/// Keep the eye at eye height above the floor while walking
+#[allow(clippy::too_many_arguments)]
+fn stand_on_floor_system(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    time: Res<Time>,
+    scene_data: Res<IfcSceneData>,
+    batched_meshes: Query<PickableBatch>,
+    triangle_mapping: Res<TriangleEntityMapping>,
+    meshes: Res<Assets<Mesh>>,
+    walk: Res<WalkSettings>,
+    mut controller: ResMut<CameraController>,
+) {
+    if controller.mode != CameraMode::Walk
+        || !walk.snap_to_floor
+        || controller.animation_target.is_some()
+        || keyboard.any_pressed([KeyCode::KeyQ, KeyCode::KeyE])
+    {
+        return;
+    }
+
+    let eye = controller.get_position();
+    let feet = eye.y - walk.eye_height;
+    let floor = floor_below(eye, &batched_meshes, &triangle_mapping, &meshes)
+        .or_else(|| storey_floor_below(feet + walk.max_step, &scene_data));
+    // Tables and sills higher than a step are walked into, not climbed
+    let Some(floor) = floor.filter(|floor| *floor <= feet + walk.max_step) else {
+        return;
+    };
+
+    let rise = floor - feet;
+    if rise.abs() < 1e-3 {
+        return;
+    }
+    let t = 1.0 - (-SETTLE_RATE * time.delta_secs()).exp();
+    controller.target.y += rise * t;
+}
@@ -0,0 +1,76 @@
+//! Default element colors, matching the TypeScript viewer's
+//! default-materials.ts
+
+use ifc_lite_core::IfcType;
+
+/// Default RGBA color of an element, refined by its PredefinedType
+pub fn element_color(entity_type: &str, predefined_type: Option<&str>) -> [f32; 4] {
+    let ifc_type = ifc_lite_core::resolve_type(entity_type);
+    match (ifc_type, predefined_type) {
+        // Roof slabs and roofing - terracotta like IfcRoof
+        (IfcType::IfcSlab, Some("ROOF")) | (IfcType::IfcCovering, Some("ROOFING")) => {
+            return [0.7, 0.45, 0.35, 1.0]
+        }
+        // Base slabs - darker concrete
+        (IfcType::IfcSlab, Some("BASESLAB")) => return [0.62, 0.62, 0.64, 1.0],
+        // Ceilings - bright white
+        (IfcType::IfcCovering, Some("CEILING")) => return [0.96, 0.96, 0.94, 1.0],
+        // Flooring - light oak
+        (IfcType::IfcCovering, Some("FLOORING")) => return [0.78, 0.68, 0.55, 1.0],
+        // Cladding - stone
+        (IfcType::IfcCovering, Some("CLADDING")) => return [0.72, 0.68, 0.62, 1.0],
+        _ => {}
+    }
+    match ifc_type {
+        // Walls - warm white (matte plaster look)
+        IfcType::IfcWall | IfcType::IfcWallStandardCase => [0.95, 0.93, 0.88, 1.0],
+        // Slabs - cool gray (concrete)
+        IfcType::IfcSlab => [0.75, 0.75, 0.78, 1.0],
+        // Beams and columns - steel blue metallic
+        IfcType::IfcBeam | IfcType::IfcColumn => [0.55, 0.55, 0.6, 1.0],
+        // Doors - warm wood
+        IfcType::IfcDoor => [0.6, 0.45, 0.3, 1.0],
+        // Windows - sky blue transparent glass
+        IfcType::IfcWindow => [0.6, 0.8, 0.95, 0.3],
+        // Roof - terracotta tile
+        IfcType::IfcRoof => [0.7, 0.45, 0.35, 1.0],
+        // Stairs and ramps - light warm gray
+        IfcType::IfcStair | IfcType::IfcStairFlight | IfcType::IfcRamp | IfcType::IfcRampFlight => {
+            [0.8, 0.78, 0.75, 1.0]
+        }
+        // Railings - dark metal
+        IfcType::IfcRailing => [0.35, 0.35, 0.4, 1.0],
+        // Plates - steel
+        IfcType::IfcPlate => [0.6, 0.6, 0.65, 1.0],
+        // Members - steel
+        IfcType::IfcMember => [0.55, 0.55, 0.6, 1.0],
+        // Curtain walls - glass blue transparent
+        IfcType::IfcCurtainWall => [0.5, 0.7, 0.85, 0.4],
+        // Coverings - light gray
+        IfcType::IfcCovering => [0.85, 0.85, 0.85, 1.0],
+        // Footings - concrete gray
+        IfcType::IfcFooting => [0.65, 0.65, 0.68, 1.0],
+        // Piles - concrete
+        IfcType::IfcPile => [0.6, 0.6, 0.62, 1.0],
+        // Opening elements - invisible/very light
+        IfcType::IfcOpeningElement => [0.9, 0.9, 0.9, 0.1],
+        // Building element proxy - neutral gray
+        IfcType::IfcBuildingElementProxy => [0.7, 0.7, 0.7, 1.0],
+        // Reinforcing - dark steel
+        IfcType::IfcReinforcingBar | IfcType::IfcReinforcingMesh | IfcType::IfcTendon => {
+            [0.4, 0.4, 0.45, 1.0]
+        }
+        // Spaces - light blue, transparent room volume
+        IfcType::IfcSpace => [0.55, 0.75, 0.95, 0.25],
+        // Furniture - light wood
+        t if t.is_subtype_of(IfcType::IfcFurnishingElement) => [0.7, 0.55, 0.4, 1.0],
+        // Pipes, ducts and cables - industrial green
+        t if t.is_subtype_of(IfcType::IfcFlowSegment)
+            || t.is_subtype_of(IfcType::IfcFlowFitting) =>
+        {
+            [0.4, 0.6, 0.4, 1.0]
+        }
+        // Default - neutral warm gray
+        _ => [0.8, 0.78, 0.75, 1.0],
+    }
+}
@@ -0,0 +1,877 @@
+//! IFC-Lite Engine - Scene building shared by the viewer front-ends
+//!
+//! The native FFI, the Yew web viewer and the Bevy file loader all turn IFC
+//! text into the same things: a spatial structure, per-element records,
+//! colored meshes and a spatial tree. [`SceneBuilder`] does that once:
+//!
+//! 1. Index the file and check its schema
+//! 2. Scan the spatial structure and its relationships ([`SpatialStructure`])
+//! 3. Mesh every element with geometry, in parallel batches, handing each
+//!    mesh to a [`SceneSink`] with its default [`element_color`]; sinks can
+//!    take IfcMappedItem geometry as instances of shared meshes instead
+//! 4. Build the spatial tree from the project down to openings and fillings
+//!
+//! Front-ends convert the resulting [`Scene`] into their own records, so
+//! the UniFFI and serde types stay in the crates that expose them.
+//! Problems with single entities are recorded in [`Scene::diagnostics`] and
+//! the build carries on with the rest. Where the time went is recorded in
+//! [`Scene::profile`].
+
+mod color;
+pub mod profile;
+mod spatial;
+
+pub use color::element_color;
+pub use profile::{LoadProfile, ProcessorTime, StageTime};
+pub use spatial::{SpatialInfo, SpatialKind, SpatialNode, SpatialStructure, TYPE_GROUP_ID_BASE};
+
+use ifc_lite_core::{
+    build_entity_index, DiagnosticKind, Diagnostics, EntityDecoder, EntityIndex, EntityScanner,
+    LineIndex, SchemaVersion, Severity, StringInterner,
+};
+use ifc_lite_geometry::{
+    ElementGeometry, GeometryOptions, GeometryRouter, Mesh, ProcessorTimings, VoidIndex,
+};
+use profile::stage;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use web_time::Instant;
+
+/// Stage of a scene build
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildPhase {
+    /// Locating entities in the file
+    Index,
+    /// Reading projects, sites, buildings, storeys and their relationships
+    Spatial,
+    /// Tessellating element geometry
+    Geometry,
+    /// Building the spatial tree
+    Complete,
+}
+
+/// How a scene is built
+#[derive(Debug, Clone, PartialEq)]
+pub struct BuildOptions {
+    /// Move models placed far from the file origin next to it, so f32
+    /// positions stay precise. Off keeps geometry in file coordinates.
+    pub shift_origin: bool,
+    /// Elements meshed between progress reports; `None` reports 20 times
+    pub batch_size: Option<usize>,
+    /// Tessellation tolerances and element filters for the router
+    pub geometry: GeometryOptions,
+    /// Group the elements of each storey by entity type in [`Scene::tree`]
+    pub group_by_type: bool,
+}
+
+impl Default for BuildOptions {
+    fn default() -> Self {
+        Self {
+            shift_origin: true,
+            batch_size: None,
+            geometry: GeometryOptions::default(),
+            group_by_type: false,
+        }
+    }
+}
+
+/// An element with geometry, whether or not it could be meshed
+///
+/// Type and storey names are interned: elements of a type or storey share
+/// one string.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ElementInfo {
+    pub id: u32,
+    pub entity_type: Arc<str>,
+    pub name: Option<String>,
+    pub global_id: Option<String>,
+    /// PredefinedType enum (e.g. "ROOF" for a roof slab)
+    pub predefined_type: Option<String>,
+    pub object_type: Option<String>,
+    /// Name and elevation (metres) of the structure containing the element
+    pub storey: Option<Arc<str>>,
+    pub storey_elevation: Option<f32>,
+    /// Storeys referencing the element besides the one containing it
+    pub referenced_storeys: Vec<Arc<str>>,
+    /// Whether a mesh was handed to the sink
+    pub has_geometry: bool,
+}
+
+/// Axis-aligned bounds of all meshes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
+/// Receives progress and meshes while a scene is built
+pub trait SceneSink {
+    /// Overall progress, 0 to 100
+    fn progress(&mut self, _phase: BuildPhase, _percent: f32) {}
+
+    /// Whether elements of `entity_type` are meshed
+    ///
+    /// Asked before each batch, so a sink can lower quality part way
+    /// through a load. Elements not meshed are still recorded.
+    fn wants_geometry(&mut self, _entity_type: &str) -> bool {
+        true
+    }
+
+    /// Mesh of an element, with finite positions and its default color
+    fn mesh(&mut self, element: &ElementInfo, mesh: Mesh, color: [f32; 4]);
+
+    /// Whether IfcMappedItem geometry is handed over as instances of shared
+    /// meshes through [`Self::instanced`]; asked once per build
+    fn wants_instances(&mut self) -> bool {
+        false
+    }
+
+    /// Geometry of an element with at least one mapped item, only called
+    /// when [`Self::wants_instances`] is true
+    ///
+    /// `geometry.mesh` holds the rest of the element and may be empty.
+    /// Instances sharing an IfcRepresentationMap share their mesh, at least
+    /// within a batch. Merges everything into [`Self::mesh`] by default.
+    fn instanced(&mut self, element: &ElementInfo, geometry: ElementGeometry, color: [f32; 4]) {
+        self.mesh(element, geometry.into_mesh(), color);
+    }
+}
+
+/// A built scene: everything but the meshes, which went to the sink
+pub struct Scene<'a> {
+    /// Elements with geometry, in file order
+    pub elements: Vec<ElementInfo>,
+    pub structure: SpatialStructure,
+    /// Spatial tree from the project down, if the file has one
+    pub tree: Option<SpatialNode>,
+    pub bounds: Option<Bounds>,
+    /// Offset (metres) taken off all positions; add it back for model
+    /// coordinates
+    pub origin_shift: [f64; 3],
+    pub diagnostics: Diagnostics,
+    /// Time per build stage and geometry processor
+    pub profile: LoadProfile,
+    /// Decoder with the entity index and unit scale, for further queries
+    pub decoder: EntityDecoder<'a>,
+    /// Router the meshes were built with, for geometry in the same space
+    pub router: GeometryRouter,
+    pub void_index: VoidIndex,
+}
+
+/// Builds a [`Scene`] from IFC content
+pub struct SceneBuilder<'a> {
+    content: &'a str,
+    index: Option<EntityIndex>,
+    options: BuildOptions,
+}
+
+/// Progress reports over the geometry pass
+const PROGRESS_BATCHES: usize = 20;
+
+/// Attributes and mesh of one element, produced on a worker thread
+struct ProcessedElement {
+    name: Option<String>,
+    global_id: Option<String>,
+    predefined_type: Option<String>,
+    object_type: Option<String>,
+    /// Geometry or why it could not be built; `None` when not wanted
+    geometry: Option<Result<ElementGeometry, String>>,
+    /// Problems worked around while building the geometry
+    warnings: Vec<String>,
+}
+
+impl<'a> SceneBuilder<'a> {
+    pub fn new(content: &'a str) -> Self {
+        Self {
+            content,
+            index: None,
+            options: BuildOptions::default(),
+        }
+    }
+
+    /// Reuse an index of the content built beforehand, e.g. to plan the load
+    pub fn with_index(mut self, index: EntityIndex) -> Self {
+        self.index = Some(index);
+        self
+    }
+
+    pub fn with_options(mut self, options: BuildOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Build the scene, handing meshes to `sink` as they are made
+    pub fn build(self, sink: &mut dyn SceneSink) -> Scene<'a> {
+        let content = self.content;
+        let mut profile = LoadProfile::default();
+        let mut lap = Instant::now();
+
+        sink.progress(BuildPhase::Index, 0.0);
+        let index = self.index.unwrap_or_else(|| build_entity_index(content));
+        let mut decoder = EntityDecoder::with_index(content, index);
+        let lines = LineIndex::new(content);
+        let mut diagnostics = Diagnostics::new();
+        match ifc_lite_core::file_schema(content) {
+            None => diagnostics.file(
+                Severity::Warning,
+                DiagnosticKind::Schema,
+                "Missing FILE_SCHEMA header",
+            ),
+            Some(name) if SchemaVersion::from_identifier(name).is_none() => diagnostics.file(
+                Severity::Warning,
+                DiagnosticKind::Schema,
+                format!(
+                    "Unsupported schema {}, reading as {}",
+                    name,
+                    SchemaVersion::default()
+                ),
+            ),
+            Some(_) => {}
+        }
+
+        profile.lap(stage::INDEX, &mut lap);
+
+        sink.progress(BuildPhase::Spatial, 10.0);
+        let structure = SpatialStructure::scan(content, &mut decoder, &mut diagnostics);
+        profile.lap(stage::SPATIAL, &mut lap);
+
+        sink.progress(BuildPhase::Geometry, 25.0);
+        let mut router = GeometryRouter::with_units(content, &mut decoder);
+        router.set_options(self.options.geometry);
+        let timings = Arc::new(ProcessorTimings::new());
+        router.set_timings(Some(Arc::clone(&timings)));
+        let origin_shift = if self.options.shift_origin {
+            router
+                .detect_origin_shift(content, &mut decoder)
+                .map_or([0.0; 3], |shift| [shift.x, shift.y, shift.z])
+        } else {
+            [0.0; 3]
+        };
+        let void_index = VoidIndex::from_content(content, &mut decoder);
+
+        let mut interner = StringInterner::new();
+        let mut candidates: Vec<(u32, Arc<str>)> = Vec::new();
+        let mut scanner = EntityScanner::new(content);
+        while let Some((id, type_name, _, _)) = scanner.next_entity() {
+            // Legacy names such as IFCBEAMSTANDARDCASE resolve to the
+            // IFC4X3 type they are decoded and meshed as
+            if ifc_lite_core::has_geometry_by_name(type_name) {
+                candidates.push((id, interner.intern(type_name)));
+            }
+        }
+
+        // Geometry fills 25% to 95%
+        let batch_size = self
+            .options
+            .batch_size
+            .unwrap_or_else(|| candidates.len().div_ceil(PROGRESS_BATCHES))
+            .max(1);
+        let total = candidates.len().max(1);
+        let mut elements = Vec::with_capacity(candidates.len());
+        let mut min = [f32::MAX; 3];
+        let mut max = [f32::MIN; 3];
+        let mut sink_time = Duration::ZERO;
+        let instanced = sink.wants_instances();
+        // Whether each shared mesh is all finite, checked once per map
+        let mut finite_sources: HashMap<u32, bool> = HashMap::new();
+        profile.lap(stage::PREPARE, &mut lap);
+
+        for (batch_index, batch) in candidates.chunks(batch_size).enumerate() {
+            let ids: Vec<u32> = batch.iter().map(|(id, _)| *id).collect();
+            let skipped: HashSet<u32> = batch
+                .iter()
+                .filter(|(_, type_name)| !sink.wants_geometry(type_name))
+                .map(|(id, _)| *id)
+                .collect();
+            let results = ifc_lite_geometry::process_parallel(
+                &ids,
+                &mut decoder,
+                &router,
+                |id, decoder, router| {
+                    let entity = decoder.decode_by_id(id).map_err(|e| e.to_string())?;
+                    ifc_lite_geometry::take_warnings();
+                    // Bodiless doors/windows get an opening placeholder
+                    let geometry = (!skipped.contains(&id)).then(|| {
+                        if instanced {
+                            router.process_element_or_filling_instanced(
+                                &entity,
+                                decoder,
+                                &void_index,
+                            )
+                        } else {
+                            router
+                                .process_element_or_filling(&entity, decoder, &void_index)
+                                .map(|mesh| ElementGeometry {
+                                    mesh,
+                                    instances: Vec::new(),
+                                })
+                        }
+                        .map_err(|e| e.to_string())
+                    });
+                    Ok::<_, String>(ProcessedElement {
+                        name: entity.name().map(|s| s.to_string()),
+                        global_id: entity.global_id().map(|s| s.to_string()),
+                        predefined_type: entity.predefined_type().map(|s| s.to_string()),
+                        object_type: entity.object_type().map(|s| s.to_string()),
+                        geometry,
+                        warnings: ifc_lite_geometry::take_warnings(),
+                    })
+                },
+            );
+
+            for ((id, type_name), result) in batch.iter().zip(results) {
+                let id = *id;
+                let mut line = || decoder.entity_offset(id).map(|offset| lines.line(offset));
+                let processed = match result {
+                    Ok(processed) => processed,
+                    Err(e) => {
+                        diagnostics.entity(
+                            Severity::Error,
+                            DiagnosticKind::Parse,
+                            id,
+                            type_name,
+                            line(),
+                            format!("Malformed {}: {}", type_name, e),
+                        );
+                        continue;
+                    }
+                };
+
+                let storey = structure.storey_of(id);
+                let mut element = ElementInfo {
+                    id,
+                    entity_type: Arc::clone(type_name),
+                    name: processed.name,
+                    global_id: processed.global_id,
+                    predefined_type: processed.predefined_type,
+                    object_type: processed.object_type,
+                    storey: storey.map(|s| interner.intern(&s.name)),
+                    storey_elevation: storey.and_then(|s| s.elevation),
+                    referenced_storeys: structure
+                        .referenced_storeys(id)
+                        .iter()
+                        .map(|s| interner.intern(s))
+                        .collect(),
+                    has_geometry: false,
+                };
+
+                for warning in &processed.warnings {
+                    diagnostics.entity(
+                        Severity::Warning,
+                        DiagnosticKind::Geometry,
+                        id,
+                        type_name,
+                        line(),
+                        warning.clone(),
+                    );
+                }
+                let geometry = match processed.geometry {
+                    Some(Ok(geometry)) if !geometry.is_empty() => Some(geometry),
+                    Some(Ok(_)) | None => None,
+                    Some(Err(e)) => {
+                        diagnostics.entity(
+                            Severity::Warning,
+                            DiagnosticKind::Geometry,
+                            id,
+                            type_name,
+                            line(),
+                            format!("Geometry failed for {}: {}", type_name, e),
+                        );
+                        None
+                    }
+                };
+                if let Some(mut geometry) = geometry {
+                    // Shared meshes can't be cleaned in place; instances of
+                    // broken ones are merged into the element's own mesh
+                    let (mut instances, broken): (Vec<_>, Vec<_>) =
+                        std::mem::take(&mut geometry.instances)
+                            .into_iter()
+                            .partition(|instance| {
+                                *finite_sources.entry(instance.source_id).or_insert_with(|| {
+                                    let mesh = &instance.mesh;
+                                    mesh.positions
+                                        .iter()
+                                        .chain(&mesh.normals)
+                                        .all(|v| v.is_finite())
+                                }) && instance.transform.iter().all(|v| v.is_finite())
+                            });
+                    for instance in &broken {
+                        geometry.mesh.merge(&instance.to_mesh());
+                    }
+                    let mesh = &mut geometry.mesh;
+                    // NaN or infinite values would poison bounds and normals
+                    for v in mesh.positions.iter_mut().chain(mesh.normals.iter_mut()) {
+                        if !v.is_finite() {
+                            *v = 0.0;
+                        }
+                    }
+                    instances.retain(|instance| !instance.mesh.is_empty());
+                    let placed = instances.iter().flat_map(|instance| {
+                        instance.mesh.positions.chunks_exact(3).map(|p| {
+                            let p = instance.transform.transform_point(
+                                &ifc_lite_geometry::Point3::new(p[0], p[1], p[2]).cast::<f64>(),
+                            );
+                            [p.x as f32, p.y as f32, p.z as f32]
+                        })
+                    });
+                    let mut element_min = [f32::MAX; 3];
+                    let mut element_max = [f32::MIN; 3];
+                    for p in mesh
+                        .positions
+                        .chunks_exact(3)
+                        .map(|p| [p[0], p[1], p[2]])
+                        .chain(placed)
+                    {
+                        for axis in 0..3 {
+                            element_min[axis] = element_min[axis].min(p[axis]);
+                            element_max[axis] = element_max[axis].max(p[axis]);
+                        }
+                    }
+                    if element_min == [0.0; 3] && element_max == [0.0; 3] {
+                        diagnostics.entity(
+                            Severity::Warning,
+                            DiagnosticKind::Geometry,
+                            id,
+                            type_name,
+                            line(),
+                            "Degenerate geometry",
+                        );
+                    } else {
+                        for axis in 0..3 {
+                            min[axis] = min[axis].min(element_min[axis]);
+                            max[axis] = max[axis].max(element_max[axis]);
+                        }
+                        element.has_geometry = true;
+                        let color =
+                            element_color(&element.entity_type, element.predefined_type.as_deref());
+                        let start = Instant::now();
+                        if instances.is_empty() {
+                            sink.mesh(&element, geometry.mesh, color);
+                        } else {
+                            geometry.instances = instances;
+                            sink.instanced(&element, geometry, color);
+                        }
+                        sink_time += start.elapsed();
+                    }
+                }
+                elements.push(element);
+            }
+
+            let done = ((batch_index + 1) * batch_size).min(candidates.len());
+            sink.progress(
+                BuildPhase::Geometry,
+                25.0 + 70.0 * done as f32 / total as f32,
+            );
+        }
+
+        let sink_ms = sink_time.as_secs_f64() * 1000.0;
+        profile.lap(stage::GEOMETRY, &mut lap);
+        profile.record(stage::GEOMETRY, -sink_ms);
+        profile.record(stage::SINK, sink_ms);
+
+        sink.progress(BuildPhase::Complete, 95.0);
+        let bounds = (min[0] <= max[0]).then_some(Bounds { min, max });
+        let tree = structure.tree(&elements, &void_index, self.options.group_by_type);
+        profile.lap(stage::TREE, &mut lap);
+        router.set_timings(None);
+        profile.processors = timings
+            .snapshot()
+            .iter()
+            .map(|(ifc_type, timing)| ProcessorTime::new(ifc_type.name(), timing))
+            .collect();
+
+        Scene {
+            elements,
+            structure,
+            tree,
+            bounds,
+            origin_shift,
+            diagnostics,
+            profile,
+            decoder,
+            router,
+            void_index,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Sink keeping meshes by element id
+    #[derive(Default)]
+    struct Collect {
+        meshes: Vec<(u32, [f32; 4])>,
+        phases: Vec<BuildPhase>,
+        skip: Option<&'static str>,
+    }
+
+    impl SceneSink for Collect {
+        fn progress(&mut self, phase: BuildPhase, _percent: f32) {
+            if self.phases.last() != Some(&phase) {
+                self.phases.push(phase);
+            }
+        }
+
+        fn wants_geometry(&mut self, entity_type: &str) -> bool {
+            self.skip != Some(entity_type)
+        }
+
+        fn mesh(&mut self, element: &ElementInfo, _mesh: Mesh, color: [f32; 4]) {
+            self.meshes.push((element.id, color));
+        }
+    }
+
+    const HOUSE: &str = "ISO-10303-21;\nHEADER;\nFILE_SCHEMA(('IFC4'));\nENDSEC;\nDATA;\n\
+#1=IFCPROJECT('p',$,'House',$,$,$,$,(#20),#30);\n\
+#2=IFCSITE('s',$,$,$,$,$,$,$,$,$,$,$,$,$);\n\
+#3=IFCBUILDING('b',$,'Main',$,$,$,$,$,$,$,$,$);\n\
+#4=IFCBUILDINGSTOREY('g',$,'Ground',$,$,$,$,$,$,0.);\n\
+#5=IFCBUILDINGSTOREY('u',$,$,$,$,$,$,$,$,3000.);\n\
+#6=IFCRELAGGREGATES('a1',$,$,$,#1,(#2));\n\
+#7=IFCRELAGGREGATES('a2',$,$,$,#2,(#3));\n\
+#8=IFCRELAGGREGATES('a3',$,$,$,#3,(#4,#5));\n\
+#9=IFCCOLUMN('c',$,'C1',$,$,$,$,$,$);\n\
+#10=IFCWALL('w',$,'W1',$,$,$,$,$,$);\n\
+#11=IFCRELCONTAINEDINSPATIALSTRUCTURE('r1',$,$,$,(#9,#10),#4);\n\
+#12=IFCRELREFERENCEDINSPATIALSTRUCTURE('r2',$,$,$,(#9),#5);\n\
+#20=IFCGEOMETRICREPRESENTATIONCONTEXT($,'Model',3,1.E-05,#21,$);\n\
+#21=IFCAXIS2PLACEMENT3D(#22,$,$);\n\
+#22=IFCCARTESIANPOINT((0.,0.,0.));\n\
+#30=IFCUNITASSIGNMENT((#31));\n\
+#31=IFCSIUNIT(*,.LENGTHUNIT.,.MILLI.,.METRE.);\n\
+ENDSEC;\nEND-ISO-10303-21;\n";
+
+    #[test]
+    fn test_structure() {
+        let mut sink = Collect::default();
+        let scene = SceneBuilder::new(HOUSE).build(&mut sink);
+
+        assert_eq!(scene.structure.project, Some(1));
+        assert!((scene.structure.unit_scale - 0.001).abs() < 1e-12);
+        assert_eq!(
+            scene
+                .structure
+                .units
+                .get("LENGTHUNIT")
+                .map(|u| u.symbol.as_str()),
+            Some("mm")
+        );
+        assert_eq!(
+            sink.phases,
+            [
+                BuildPhase::Index,
+                BuildPhase::Spatial,
+                BuildPhase::Geometry,
+                BuildPhase::Complete
+            ]
+        );
+
+        // Elevations in metres, top floor first
+        let storeys = scene.structure.storeys();
+        assert_eq!(storeys[0].1.name, "Storey #5");
+        assert!((storeys[0].1.elevation.unwrap() - 3.0).abs() < 1e-6);
+        assert_eq!(storeys[1].1.name, "Ground");
+
+        let column = scene.elements.iter().find(|e| e.id == 9).unwrap();
+        assert_eq!(column.storey.as_deref(), Some("Ground"));
+        assert_eq!(column.storey_elevation, Some(0.0));
+        assert_eq!(column.referenced_storeys, [Arc::from("Storey #5")]);
+    }
+
+    #[test]
+    fn test_tree() {
+        let scene = SceneBuilder::new(HOUSE).build(&mut Collect::default());
+        let tree = scene.tree.expect("tree");
+        assert_eq!(tree.kind, SpatialKind::Project);
+        let site = &tree.children[0];
+        assert_eq!(site.name, "Site");
+        let building = &site.children[0];
+        assert_eq!(building.kind, SpatialKind::Building);
+
+        // Upper storey first, holding the column by reference only
+        let [upper, ground] = building.children.as_slice() else {
+            panic!("two storeys expected");
+        };
+        assert_eq!(upper.id, 5);
+        assert_eq!(upper.children.len(), 1);
+        assert!(upper.children[0].referenced);
+        let names: Vec<_> = ground.children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["C1", "W1"]);
+        assert!(ground.children.iter().all(|c| !c.referenced));
+    }
+
+    #[test]
+    fn test_type_groups() {
+        let options = BuildOptions {
+            group_by_type: true,
+            ..Default::default()
+        };
+        let scene = SceneBuilder::new(HOUSE)
+            .with_options(options)
+            .build(&mut Collect::default());
+        let tree = scene.tree.expect("tree");
+        let [upper, ground] = tree.children[0].children[0].children.as_slice() else {
+            panic!("two storeys expected");
+        };
+
+        let groups: Vec<_> = ground.children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(groups, ["IfcColumn", "IfcWall"]);
+        for group in &ground.children {
+            assert_eq!(group.kind, SpatialKind::TypeGroup);
+            assert!(group.id >= TYPE_GROUP_ID_BASE);
+            assert_eq!(group.children.len(), 1);
+            assert_eq!(group.children[0].entity_type, group.entity_type);
+        }
+        assert_ne!(ground.children[0].id, ground.children[1].id);
+
+        // Referenced elements are grouped too
+        assert_eq!(upper.children.len(), 1);
+        assert_eq!(upper.children[0].kind, SpatialKind::TypeGroup);
+        assert!(upper.children[0].children[0].referenced);
+    }
+
+    #[test]
+    fn test_inferred_hierarchy() {
+        let content = "ISO-10303-21;\nHEADER;\nFILE_SCHEMA(('IFC4'));\nENDSEC;\nDATA;\n\
+#1=IFCPROJECT('p',$,$,$,$,$,$,$,$);\n\
+#2=IFCBUILDING('b',$,$,$,$,$,$,$,$,$,$,$);\n\
+#3=IFCBUILDINGSTOREY('s1',$,'Level 01',$,$,$,$,$,$,3.);\n\
+#4=IFCBUILDINGSTOREY('s0',$,'Level 00',$,$,$,$,$,$,0.);\n\
+#5=IFCSPACE('r',$,'Room',$,$,$,$,$,$,$,$);\n\
+ENDSEC;\nEND-ISO-10303-21;\n";
+        let scene = SceneBuilder::new(content).build(&mut Collect::default());
+        assert_eq!(scene.structure.aggregates[&1], [2]);
+        assert_eq!(scene.structure.aggregates[&2], [3, 4]);
+        // Spaces land in the ground storey
+        assert_eq!(scene.structure.contained_in[&4], [5]);
+    }
+
+    #[test]
+    fn test_facility_names() {
+        let content = "ISO-10303-21;\nHEADER;\nFILE_SCHEMA(('IFC4X3_ADD2'));\nENDSEC;\nDATA;\n\
+#1=IFCPROJECT('p',$,'Project',$,$,$,$,$,$);\n\
+#2=IFCROAD('r',$,$,$,$,$,$,$,$,$);\n\
+#3=IFCROADPART('rp',$,$,$,$,$,$,$,$,.CARRIAGEWAY.,.LONGITUDINAL.);\n\
+#4=IFCRELAGGREGATES('a1',$,$,$,#1,(#2));\n\
+#5=IFCRELAGGREGATES('a2',$,$,$,#2,(#3));\n\
+ENDSEC;\nEND-ISO-10303-21;\n";
+        let scene = SceneBuilder::new(content).build(&mut Collect::default());
+        let road = &scene.tree.expect("tree").children[0];
+        assert_eq!(road.kind, SpatialKind::Facility);
+        assert_eq!(road.name, "Road #2");
+        assert_eq!(road.children[0].kind, SpatialKind::FacilityPart);
+        assert_eq!(road.children[0].name, "Part #3");
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let content = "ISO-10303-21;\nHEADER;\nENDSEC;\nDATA;\n\
+#1=IFCWALL('0a',$,'W1',$,$,$,$,$);\n\
+#2=IFCWALL('0b',$,'broken'\n\
+ENDSEC;\nEND-ISO-10303-21;\n";
+        let scene = SceneBuilder::new(content).build(&mut Collect::default());
+        let diagnostics: Vec<_> = scene.diagnostics.iter().collect();
+        assert!(diagnostics
+            .iter()
+            .any(|d| d.kind == DiagnosticKind::Schema && d.line.is_none()));
+        assert!(diagnostics
+            .iter()
+            .any(|d| d.kind == DiagnosticKind::Structure && d.severity == Severity::Error));
+        let broken = diagnostics
+            .iter()
+            .find(|d| d.entity_id == Some(2))
+            .expect("malformed wall recorded");
+        assert_eq!(broken.kind, DiagnosticKind::Parse);
+        assert_eq!(broken.line, Some(6));
+        // The wall without a body is still recorded
+        assert_eq!(scene.elements.len(), 1);
+        assert!(!scene.elements[0].has_geometry);
+        assert!(scene.bounds.is_none());
+    }
+
+    #[test]
+    fn test_standard_case_elements() {
+        let content = "ISO-10303-21;\nHEADER;\nFILE_SCHEMA(('IFC4'));\nENDSEC;\nDATA;\n\
+#1=IFCCARTESIANPOINT((0.,0.,0.));\n\
+#2=IFCAXIS2PLACEMENT3D(#1,$,$);\n\
+#3=IFCLOCALPLACEMENT($,#2);\n\
+#4=IFCAXIS2PLACEMENT2D(#1,$);\n\
+#5=IFCRECTANGLEPROFILEDEF(.AREA.,$,#4,0.2,0.4);\n\
+#6=IFCDIRECTION((0.,0.,1.));\n\
+#7=IFCEXTRUDEDAREASOLID(#5,#2,#6,3.);\n\
+#8=IFCSHAPEREPRESENTATION($,'Body','SweptSolid',(#7));\n\
+#9=IFCPRODUCTDEFINITIONSHAPE($,$,(#8));\n\
+#10=IFCBEAMSTANDARDCASE('b',$,'Beam',$,$,#3,#9,$,.BEAM.);\n\
+ENDSEC;\nEND-ISO-10303-21;\n";
+        let mut sink = Collect::default();
+        let scene = SceneBuilder::new(content).build(&mut sink);
+
+        // Meshed and colored as an IfcBeam, listed under the file's name
+        assert_eq!(sink.meshes, [(10, element_color("IFCBEAM", None))]);
+        let beam = &scene.elements[0];
+        assert_eq!(&*beam.entity_type, "IFCBEAMSTANDARDCASE");
+        assert_eq!(beam.name.as_deref(), Some("Beam"));
+        assert_eq!(beam.predefined_type.as_deref(), Some("BEAM"));
+        assert!(beam.has_geometry);
+    }
+
+    /// Sink taking mapped items as instances
+    #[derive(Default)]
+    struct Instanced {
+        meshes: Vec<u32>,
+        instances: Vec<(u32, ifc_lite_geometry::MeshInstance)>,
+    }
+
+    impl SceneSink for Instanced {
+        fn mesh(&mut self, element: &ElementInfo, _mesh: Mesh, _color: [f32; 4]) {
+            self.meshes.push(element.id);
+        }
+
+        fn wants_instances(&mut self) -> bool {
+            true
+        }
+
+        fn instanced(
+            &mut self,
+            element: &ElementInfo,
+            geometry: ElementGeometry,
+            _color: [f32; 4],
+        ) {
+            assert!(geometry.mesh.is_empty());
+            self.instances
+                .extend(geometry.instances.into_iter().map(|i| (element.id, i)));
+        }
+    }
+
+    #[test]
+    fn test_instances() {
+        let content = "ISO-10303-21;\nHEADER;\nFILE_SCHEMA(('IFC4'));\nENDSEC;\nDATA;\n\
+#1=IFCCARTESIANPOINT((0.,0.,0.));\n\
+#2=IFCAXIS2PLACEMENT3D(#1,$,$);\n\
+#3=IFCLOCALPLACEMENT($,#2);\n\
+#4=IFCAXIS2PLACEMENT2D(#1,$);\n\
+#5=IFCRECTANGLEPROFILEDEF(.AREA.,$,#4,1.,1.);\n\
+#6=IFCDIRECTION((0.,0.,1.));\n\
+#7=IFCEXTRUDEDAREASOLID(#5,#2,#6,1.);\n\
+#8=IFCSHAPEREPRESENTATION($,'Body','SweptSolid',(#7));\n\
+#9=IFCREPRESENTATIONMAP(#2,#8);\n\
+#10=IFCCARTESIANPOINT((10.,0.,0.));\n\
+#11=IFCCARTESIANTRANSFORMATIONOPERATOR3D($,$,#10,$,$);\n\
+#12=IFCMAPPEDITEM(#9,#11);\n\
+#13=IFCSHAPEREPRESENTATION($,'Body','MappedRepresentation',(#12));\n\
+#14=IFCPRODUCTDEFINITIONSHAPE($,$,(#13));\n\
+#15=IFCFURNISHINGELEMENT('f1',$,$,$,$,#3,#14,$);\n\
+#16=IFCFURNISHINGELEMENT('f2',$,$,$,$,#3,#14,$);\n\
+#17=IFCWALL('w',$,$,$,$,#3,#18,$,$);\n\
+#18=IFCPRODUCTDEFINITIONSHAPE($,$,(#8));\n\
+ENDSEC;\nEND-ISO-10303-21;\n";
+        let mut sink = Instanced::default();
+        let scene = SceneBuilder::new(content).build(&mut sink);
+
+        // The wall is meshed as usual, the furniture placed
+        assert_eq!(sink.meshes, [17]);
+        let ids: Vec<u32> = sink.instances.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, [15, 16]);
+        assert!(sink.instances.iter().all(|(_, i)| i.source_id == 9));
+        assert!(Arc::ptr_eq(
+            &sink.instances[0].1.mesh,
+            &sink.instances[1].1.mesh
+        ));
+        assert!(scene.elements.iter().all(|e| e.has_geometry));
+
+        // Bounds take in the placed copies
+        let bounds = scene.bounds.expect("bounds");
+        assert!((bounds.max[0] - 10.5).abs() < 1e-5);
+
+        // Sinks not asking for instances get them merged
+        let mut sink = Collect::default();
+        SceneBuilder::new(content).build(&mut sink);
+        assert_eq!(sink.meshes.len(), 3);
+    }
+
+    #[test]
+    fn test_meshes_and_colors() {
+        let content = std::fs::read_to_string("../../tests/models/test.ifc")
+            .expect("Failed to read test.ifc");
+        let mut sink = Collect::default();
+        let scene = SceneBuilder::new(&content).build(&mut sink);
+
+        assert!(!sink.meshes.is_empty());
+        assert!(scene.bounds.is_some());
+        let stages: Vec<_> = scene
+            .profile
+            .stages
+            .iter()
+            .map(|s| s.name.as_str())
+            .collect();
+        assert_eq!(
+            stages,
+            [
+                stage::INDEX,
+                stage::SPATIAL,
+                stage::PREPARE,
+                stage::GEOMETRY,
+                stage::SINK,
+                stage::TREE
+            ]
+        );
+        assert!(scene
+            .profile
+            .processors
+            .iter()
+            .any(|p| p.name == "IfcExtrudedAreaSolid" && p.calls > 0));
+        for (id, color) in &sink.meshes {
+            let element = scene.elements.iter().find(|e| e.id == *id).unwrap();
+            assert!(element.has_geometry);
+            assert_eq!(
+                *color,
+                element_color(&element.entity_type, element.predefined_type.as_deref())
+            );
+        }
+
+        // Elements of a type share one interned name
+        let walls: Vec<&ElementInfo> = scene
+            .elements
+            .iter()
+            .filter(|e| &*e.entity_type == "IFCWALLSTANDARDCASE")
+            .collect();
+        assert_eq!(walls.len(), 2);
+        assert!(Arc::ptr_eq(&walls[0].entity_type, &walls[1].entity_type));
+
+        // Skipped types are recorded without geometry
+        let walls = |scene: &Scene| {
+            scene
+                .elements
+                .iter()
+                .filter(|e| matches!(&*e.entity_type, "IFCWALL" | "IFCWALLSTANDARDCASE"))
+                .count()
+        };
+        let mut skipping = Collect {
+            skip: Some("IFCWALLSTANDARDCASE"),
+            ..Default::default()
+        };
+        let skipped = SceneBuilder::new(&content).build(&mut skipping);
+        assert_eq!(walls(&skipped), walls(&scene));
+        assert!(skipped
+            .elements
+            .iter()
+            .filter(|e| &*e.entity_type == "IFCWALLSTANDARDCASE")
+            .all(|e| !e.has_geometry));
+    }
+
+    #[test]
+    fn test_element_color() {
+        assert_eq!(
+            element_color("IFCSLAB", Some("ROOF")),
+            element_color("IFCROOF", None)
+        );
+        assert!(element_color("IFCSPACE", None)[3] < 1.0);
+        assert!(element_color("IFCWINDOW", None)[3] < 1.0);
+        assert_eq!(element_color("IFCPIPESEGMENT", None), [0.4, 0.6, 0.4, 1.0]);
+        assert_eq!(element_color("IFCFURNITURE", None), [0.7, 0.55, 0.4, 1.0]);
+        assert_eq!(element_color("IFCANNOTATION", None), [0.8, 0.78, 0.75, 1.0]);
+    }
+}
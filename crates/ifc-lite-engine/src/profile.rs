@@ -0,0 +1,153 @@
+//! Where the time of a load went
+//!
+//! [`SceneBuilder`](crate::SceneBuilder) records its own stages and the time
+//! per geometry processor into [`Scene::profile`](crate::Scene::profile).
+//! Front-ends add the stages that follow, such as batching meshes and
+//! uploading them to the GPU, and show or export the report.
+
+use ifc_lite_geometry::ProcessorTiming;
+use std::fmt;
+use web_time::Instant;
+
+/// Stage names, in the order they run
+pub mod stage {
+    /// Locating entities in the file
+    pub const INDEX: &str = "index";
+    /// Spatial structure and relationships
+    pub const SPATIAL: &str = "spatial";
+    /// Units, grids, face colors, origin shift and the void index
+    pub const PREPARE: &str = "prepare";
+    /// Decoding and tessellating elements, without the sink
+    pub const GEOMETRY: &str = "geometry";
+    /// Handing meshes to the [`SceneSink`](crate::SceneSink)
+    pub const SINK: &str = "sink";
+    /// Building the spatial tree
+    pub const TREE: &str = "tree";
+    /// Combining meshes into draw batches, recorded by front-ends
+    pub const BATCHING: &str = "batching";
+    /// Uploading batches to the GPU, recorded by front-ends
+    pub const UPLOAD: &str = "upload";
+}
+
+/// Wall time of one stage
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StageTime {
+    pub name: String,
+    pub ms: f64,
+}
+
+/// Calls and time of one geometry processor
+///
+/// Processors run in parallel, so this is CPU time summed over threads.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProcessorTime {
+    /// Representation item type, e.g. "IfcExtrudedAreaSolid"
+    pub name: String,
+    pub calls: u64,
+    pub ms: f64,
+}
+
+impl ProcessorTime {
+    pub fn new(name: impl Into<String>, timing: &ProcessorTiming) -> Self {
+        Self {
+            name: name.into(),
+            calls: timing.calls,
+            ms: timing.time.as_secs_f64() * 1000.0,
+        }
+    }
+}
+
+/// Timing report of one load
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LoadProfile {
+    /// Stages in the order they ran
+    pub stages: Vec<StageTime>,
+    /// Geometry processors, slowest first
+    pub processors: Vec<ProcessorTime>,
+}
+
+impl LoadProfile {
+    /// Add `ms` to stage `name`, appending it if new
+    pub fn record(&mut self, name: &str, ms: f64) {
+        match self.stages.iter_mut().find(|s| s.name == name) {
+            Some(stage) => stage.ms += ms,
+            None => self.stages.push(StageTime {
+                name: name.to_string(),
+                ms,
+            }),
+        }
+    }
+
+    /// Add the time since `since` to stage `name` and restart `since`
+    pub fn lap(&mut self, name: &str, since: &mut Instant) {
+        let now = Instant::now();
+        self.record(name, (now - *since).as_secs_f64() * 1000.0);
+        *since = now;
+    }
+
+    /// Time of stage `name`, if recorded
+    pub fn stage(&self, name: &str) -> Option<f64> {
+        self.stages.iter().find(|s| s.name == name).map(|s| s.ms)
+    }
+
+    /// Wall time of all stages
+    pub fn total_ms(&self) -> f64 {
+        self.stages.iter().map(|s| s.ms).sum()
+    }
+}
+
+impl fmt::Display for LoadProfile {
+    /// Stages with their share of the total, then the processors
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.total_ms();
+        writeln!(f, "Load profile: {:.1} ms", total)?;
+        for stage in &self.stages {
+            let share = if total > 0.0 {
+                100.0 * stage.ms / total
+            } else {
+                0.0
+            };
+            writeln!(
+                f,
+                "  {:<10} {:>10.1} ms {:>5.1}%",
+                stage.name, stage.ms, share
+            )?;
+        }
+        if !self.processors.is_empty() {
+            writeln!(f, "Geometry processors (CPU time):")?;
+            for processor in &self.processors {
+                writeln!(
+                    f,
+                    "  {:<32} {:>8} calls {:>10.1} ms",
+                    processor.name, processor.calls, processor.ms
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_report() {
+        let mut profile = LoadProfile::default();
+        profile.record(stage::INDEX, 2.0);
+        profile.record(stage::GEOMETRY, 6.0);
+        profile.record(stage::INDEX, 2.0);
+        assert_eq!(profile.stages.len(), 2);
+        assert_eq!(profile.stage(stage::INDEX), Some(4.0));
+        assert_eq!(profile.stage(stage::UPLOAD), None);
+        assert_eq!(profile.total_ms(), 10.0);
+
+        let report = profile.to_string();
+        assert!(report.starts_with("Load profile: 10.0 ms"));
+        assert!(report.contains("geometry"));
+        assert!(report.contains("60.0%"));
+    }
+}
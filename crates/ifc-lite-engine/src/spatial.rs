@@ -0,0 +1,670 @@
+//! Spatial structure - projects, sites, buildings, storeys, spaces and the
+//! relationships between them and the elements they hold
+
+use crate::ElementInfo;
+use ifc_lite_core::{
+    Attribute, DiagnosticKind, Diagnostics, EntityDecoder, EntityScanner, Severity, UnitAssignment,
+};
+use ifc_lite_geometry::VoidIndex;
+use std::cell::Cell;
+use std::collections::HashMap;
+
+/// Kind of a node in the spatial tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpatialKind {
+    Project,
+    Site,
+    Building,
+    Storey,
+    Space,
+    /// IFC4.3 road, bridge, railway or marine facility
+    Facility,
+    /// Part of a facility, e.g. IfcRoadPart
+    FacilityPart,
+    /// Opening in an element, leading on to the doors and windows filling it
+    Opening,
+    Element,
+    /// Elements of one type under a storey; synthetic, with an id from
+    /// [`TYPE_GROUP_ID_BASE`]
+    TypeGroup,
+}
+
+/// Ids of type group nodes count up from here, above the entity ids of
+/// any practical file
+pub const TYPE_GROUP_ID_BASE: u32 = 0x8000_0000;
+
+impl SpatialKind {
+    /// Kind of a spatial structure entity; anything else is an element
+    pub fn of(entity_type: &str) -> Self {
+        Self::of_upper(&entity_type.to_uppercase())
+    }
+
+    fn of_upper(entity_type: &str) -> Self {
+        match entity_type {
+            "IFCPROJECT" => Self::Project,
+            "IFCSITE" => Self::Site,
+            "IFCBUILDING" => Self::Building,
+            "IFCBUILDINGSTOREY" => Self::Storey,
+            "IFCSPACE" => Self::Space,
+            "IFCFACILITY" | "IFCROAD" | "IFCBRIDGE" | "IFCRAILWAY" | "IFCMARINEFACILITY" => {
+                Self::Facility
+            }
+            "IFCFACILITYPART"
+            | "IFCFACILITYPARTCOMMON"
+            | "IFCROADPART"
+            | "IFCBRIDGEPART"
+            | "IFCRAILWAYPART"
+            | "IFCMARINEPART" => Self::FacilityPart,
+            _ => Self::Element,
+        }
+    }
+
+    /// Name of the kind, e.g. "Storey"
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Project => "Project",
+            Self::Site => "Site",
+            Self::Building => "Building",
+            Self::Storey => "Storey",
+            Self::Space => "Space",
+            Self::Facility => "Facility",
+            Self::FacilityPart => "FacilityPart",
+            Self::Opening => "Opening",
+            Self::Element => "Element",
+            Self::TypeGroup => "TypeGroup",
+        }
+    }
+
+    /// Whether nodes of this kind are spatial structure rather than elements
+    pub fn is_spatial(self) -> bool {
+        !matches!(self, Self::Opening | Self::Element | Self::TypeGroup)
+    }
+}
+
+/// A spatial structure entity
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpatialInfo {
+    pub name: String,
+    pub entity_type: String,
+    /// Storey elevation in metres
+    pub elevation: Option<f32>,
+}
+
+/// Node in the spatial tree
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpatialNode {
+    pub id: u32,
+    pub kind: SpatialKind,
+    pub name: String,
+    pub entity_type: String,
+    /// PredefinedType of elements
+    pub predefined_type: Option<String>,
+    pub elevation: Option<f32>,
+    pub has_geometry: bool,
+    /// Element referenced by this structure but contained in another
+    /// (e.g. a column spanning several storeys)
+    pub referenced: bool,
+    pub children: Vec<SpatialNode>,
+}
+
+/// Spatial structure and relationships of a model, from one scan of the file
+#[derive(Debug, Default)]
+pub struct SpatialStructure {
+    pub project: Option<u32>,
+    /// Spatial structure entities by id
+    pub spatial: HashMap<u32, SpatialInfo>,
+    /// IfcRelAggregates, IfcRelDecomposes and IfcRelNests: parent -> children
+    pub aggregates: HashMap<u32, Vec<u32>>,
+    /// IfcRelContainedInSpatialStructure: structure -> contained elements
+    pub contained_in: HashMap<u32, Vec<u32>>,
+    /// IfcRelReferencedInSpatialStructure: structure -> referenced elements
+    pub referenced_in: HashMap<u32, Vec<u32>>,
+    /// Element -> structure containing it
+    pub element_to_storey: HashMap<u32, u32>,
+    /// Element -> structures referencing it (multi-storey columns, curtain walls)
+    pub element_referenced_by: HashMap<u32, Vec<u32>>,
+    /// IfcRelDefinesByProperties: element -> property definition ids
+    pub element_properties: HashMap<u32, Vec<u32>>,
+    /// IfcRelDefinesByType: element -> type id
+    pub element_to_type: HashMap<u32, u32>,
+    /// True north angle from the geometric representation context
+    pub true_north: Option<f32>,
+    /// Length unit of the project in metres
+    pub unit_scale: f64,
+    /// All units of the project, for values without an explicit unit
+    pub units: UnitAssignment,
+}
+
+/// Fallback name for an unnamed IFC4.3 facility
+fn facility_label(entity_type: &str) -> &'static str {
+    match entity_type {
+        "IFCROAD" => "Road",
+        "IFCBRIDGE" => "Bridge",
+        "IFCRAILWAY" => "Railway",
+        "IFCMARINEFACILITY" => "Marine Facility",
+        _ => "Facility",
+    }
+}
+
+/// Read a relationship's single ref and ref list by attribute name
+/// Uses selective decoding so unrelated attributes are never tokenized
+fn decode_rel_refs(
+    decoder: &mut EntityDecoder,
+    id: u32,
+    type_name: &str,
+    single: Attribute,
+    list: Attribute,
+) -> Option<(u32, Vec<u32>)> {
+    let rel_type = ifc_lite_core::resolve_type(type_name);
+    let single_index = ifc_lite_core::attribute_index_in(decoder.schema(), rel_type, single)?;
+    let list_index = ifc_lite_core::attribute_index_in(decoder.schema(), rel_type, list)?;
+    let attrs = decoder
+        .decode_attrs_by_id(id, &[single_index, list_index])
+        .ok()?;
+    let single = attrs[0].as_entity_ref()?;
+    let list = attrs[1]
+        .as_list()?
+        .iter()
+        .filter_map(|v| v.as_entity_ref())
+        .collect();
+    Some((single, list))
+}
+
+impl SpatialStructure {
+    /// Collect the spatial structure and relationships of `content`
+    ///
+    /// The project's length unit is cached in `decoder`, so geometry built
+    /// with it comes out in metres. Elevations are converted to metres.
+    pub fn scan(content: &str, decoder: &mut EntityDecoder, diagnostics: &mut Diagnostics) -> Self {
+        let mut structure = Self::default();
+
+        let mut scanner = EntityScanner::new(content);
+        while let Some((id, type_name, _, _)) = scanner.next_entity() {
+            let type_upper = type_name.to_uppercase();
+            let kind = SpatialKind::of_upper(&type_upper);
+            if kind != SpatialKind::Element {
+                if kind == SpatialKind::Project {
+                    structure.project = Some(id);
+                }
+                let Ok(entity) = decoder.decode_by_id(id) else {
+                    continue;
+                };
+                let name = entity
+                    .name()
+                    .map(|s| s.to_string())
+                    .unwrap_or_else(|| match kind {
+                        SpatialKind::Project => "Project".to_string(),
+                        SpatialKind::Site => "Site".to_string(),
+                        SpatialKind::Building => "Building".to_string(),
+                        SpatialKind::Facility => format!("{} #{}", facility_label(&type_upper), id),
+                        SpatialKind::FacilityPart => format!("Part #{}", id),
+                        SpatialKind::Storey => format!("Storey #{}", id),
+                        _ => format!("Space #{}", id),
+                    });
+                let elevation = if kind == SpatialKind::Storey {
+                    entity.elevation().map(|e| e as f32)
+                } else {
+                    None
+                };
+                structure.spatial.insert(
+                    id,
+                    SpatialInfo {
+                        name,
+                        entity_type: type_name.to_string(),
+                        elevation,
+                    },
+                );
+                continue;
+            }
+
+            match type_upper.as_str() {
+                "IFCGEOMETRICREPRESENTATIONCONTEXT" if structure.true_north.is_none() => {
+                    structure.true_north = ifc_lite_core::GeoRefExtractor::true_north(
+                        decoder,
+                        &[(
+                            id,
+                            ifc_lite_core::IfcType::IfcGeometricRepresentationContext,
+                        )],
+                    )
+                    .ok()
+                    .flatten()
+                    .map(|angle| angle as f32);
+                }
+                // IfcRelDecomposes is the IFC2x3 parent of IfcRelAggregates;
+                // IfcRelNests can also define hierarchy
+                "IFCRELAGGREGATES" | "IFCRELDECOMPOSES" | "IFCRELNESTS" => {
+                    if let Some((parent_id, children)) = decode_rel_refs(
+                        decoder,
+                        id,
+                        &type_upper,
+                        Attribute::RelatingObject,
+                        Attribute::RelatedObjects,
+                    ) {
+                        structure
+                            .aggregates
+                            .entry(parent_id)
+                            .or_default()
+                            .extend(children);
+                    }
+                }
+                "IFCRELCONTAINEDINSPATIALSTRUCTURE" => {
+                    if let Some((structure_id, elements)) = decode_rel_refs(
+                        decoder,
+                        id,
+                        &type_upper,
+                        Attribute::RelatingStructure,
+                        Attribute::RelatedElements,
+                    ) {
+                        for &elem_id in &elements {
+                            structure.element_to_storey.insert(elem_id, structure_id);
+                        }
+                        structure
+                            .contained_in
+                            .entry(structure_id)
+                            .or_default()
+                            .extend(elements);
+                    }
+                }
+                "IFCRELREFERENCEDINSPATIALSTRUCTURE" => {
+                    if let Some((structure_id, elements)) = decode_rel_refs(
+                        decoder,
+                        id,
+                        &type_upper,
+                        Attribute::RelatingStructure,
+                        Attribute::RelatedElements,
+                    ) {
+                        for &elem_id in &elements {
+                            structure
+                                .element_referenced_by
+                                .entry(elem_id)
+                                .or_default()
+                                .push(structure_id);
+                        }
+                        structure
+                            .referenced_in
+                            .entry(structure_id)
+                            .or_default()
+                            .extend(elements);
+                    }
+                }
+                "IFCRELDEFINESBYPROPERTIES" => {
+                    if let Some((prop_def_id, related_objects)) = decode_rel_refs(
+                        decoder,
+                        id,
+                        &type_upper,
+                        Attribute::RelatingPropertyDefinition,
+                        Attribute::RelatedObjects,
+                    ) {
+                        for obj_id in related_objects {
+                            structure
+                                .element_properties
+                                .entry(obj_id)
+                                .or_default()
+                                .push(prop_def_id);
+                        }
+                    }
+                }
+                "IFCRELDEFINESBYTYPE" => {
+                    if let Some((type_id, related_objects)) = decode_rel_refs(
+                        decoder,
+                        id,
+                        &type_upper,
+                        Attribute::RelatingType,
+                        Attribute::RelatedObjects,
+                    ) {
+                        for obj_id in related_objects {
+                            structure.element_to_type.insert(obj_id, type_id);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Cached in the decoder for the geometry router
+        structure.unit_scale = match structure.project {
+            Some(project_id) => decoder.extract_unit_scale(project_id).ok(),
+            None => {
+                diagnostics.file(
+                    Severity::Error,
+                    DiagnosticKind::Structure,
+                    "No IfcProject found",
+                );
+                None
+            }
+        }
+        .unwrap_or_else(|| {
+            decoder.set_length_unit_scale(1.0);
+            1.0
+        });
+        if let Some(project_id) = structure.project {
+            structure.units = UnitAssignment::from_project(decoder, project_id);
+        }
+        for info in structure.spatial.values_mut() {
+            if let Some(elevation) = info.elevation.as_mut() {
+                *elevation = (*elevation as f64 * structure.unit_scale) as f32;
+            }
+        }
+
+        // Spaces decompose their storey instead of being contained in it
+        for (parent_id, children) in &structure.aggregates {
+            if structure.is_type(*parent_id, "IFCBUILDINGSTOREY") {
+                for &child in children {
+                    if structure.is_type(child, "IFCSPACE") {
+                        structure
+                            .element_to_storey
+                            .entry(child)
+                            .or_insert(*parent_id);
+                    }
+                }
+            }
+        }
+
+        if structure.aggregates.is_empty() && !structure.spatial.is_empty() {
+            structure.infer_hierarchy();
+        }
+        structure
+    }
+
+    fn is_type(&self, id: u32, type_name: &str) -> bool {
+        self.spatial
+            .get(&id)
+            .is_some_and(|info| info.entity_type.eq_ignore_ascii_case(type_name))
+    }
+
+    /// Link the structure by entity type when the file has no aggregation:
+    /// project -> sites -> buildings -> storeys, with spaces in the ground
+    /// storey
+    fn infer_hierarchy(&mut self) {
+        let mut projects = Vec::new();
+        let mut sites = Vec::new();
+        let mut buildings = Vec::new();
+        let mut storeys = Vec::new();
+        let mut spaces = Vec::new();
+        for (&id, info) in &self.spatial {
+            match SpatialKind::of(&info.entity_type) {
+                SpatialKind::Project => projects.push(id),
+                SpatialKind::Site => sites.push(id),
+                SpatialKind::Building | SpatialKind::Facility => buildings.push(id),
+                SpatialKind::Storey | SpatialKind::FacilityPart => storeys.push(id),
+                SpatialKind::Space => spaces.push(id),
+                _ => {}
+            }
+        }
+        // Map iteration order is arbitrary
+        for ids in [
+            &mut projects,
+            &mut sites,
+            &mut buildings,
+            &mut storeys,
+            &mut spaces,
+        ] {
+            ids.sort_unstable();
+        }
+
+        let below_project = if sites.is_empty() { &buildings } else { &sites };
+        for &id in &projects {
+            self.aggregates
+                .entry(id)
+                .or_default()
+                .extend(below_project.iter().copied());
+        }
+        if !buildings.is_empty() {
+            for &id in &sites {
+                self.aggregates
+                    .entry(id)
+                    .or_default()
+                    .extend(buildings.iter().copied());
+            }
+        }
+        if !storeys.is_empty() {
+            for &id in &buildings {
+                self.aggregates
+                    .entry(id)
+                    .or_default()
+                    .extend(storeys.iter().copied());
+            }
+        }
+
+        let ground_storey = storeys
+            .iter()
+            .find(|id| {
+                let name = self.spatial[*id].name.to_lowercase();
+                name.contains("00") || name.contains("ground") || name.contains("erdgeschoss")
+            })
+            .or(storeys.first())
+            .copied();
+        if let (Some(storey_id), false) = (ground_storey, spaces.is_empty()) {
+            self.contained_in
+                .entry(storey_id)
+                .or_default()
+                .extend(spaces);
+        }
+    }
+
+    /// Structure containing an element, usually its storey
+    pub fn storey_of(&self, id: u32) -> Option<&SpatialInfo> {
+        self.spatial.get(self.element_to_storey.get(&id)?)
+    }
+
+    /// Names of storeys referencing an element besides the one containing it
+    pub fn referenced_storeys(&self, id: u32) -> Vec<String> {
+        let container = self.element_to_storey.get(&id);
+        self.element_referenced_by
+            .get(&id)
+            .into_iter()
+            .flatten()
+            .filter(|&structure_id| container != Some(structure_id))
+            .filter_map(|structure_id| self.spatial.get(structure_id))
+            .filter(|s| s.entity_type.eq_ignore_ascii_case("IFCBUILDINGSTOREY"))
+            .map(|s| s.name.clone())
+            .collect()
+    }
+
+    /// Building storeys, top floor first
+    pub fn storeys(&self) -> Vec<(u32, &SpatialInfo)> {
+        let mut storeys: Vec<_> = self
+            .spatial
+            .iter()
+            .filter(|(_, s)| s.entity_type.eq_ignore_ascii_case("IFCBUILDINGSTOREY"))
+            .map(|(&id, s)| (id, s))
+            .collect();
+        storeys.sort_by(|a, b| {
+            b.1.elevation
+                .unwrap_or(0.0)
+                .total_cmp(&a.1.elevation.unwrap_or(0.0))
+                .then(a.0.cmp(&b.0))
+        });
+        storeys
+    }
+
+    /// Spatial tree from the project down to the elements
+    ///
+    /// Elements hold their openings, and openings the doors and windows
+    /// filling them. Elements referenced by a structure but contained in
+    /// another are listed with `referenced` set. With `group_by_type`, the
+    /// elements of each storey sit in one [`SpatialKind::TypeGroup`] node
+    /// per entity type.
+    pub fn tree(
+        &self,
+        elements: &[ElementInfo],
+        void_index: &VoidIndex,
+        group_by_type: bool,
+    ) -> Option<SpatialNode> {
+        let root = self.spatial.iter().find_map(|(&id, info)| {
+            (SpatialKind::of(&info.entity_type) == SpatialKind::Project).then_some(id)
+        })?;
+        let by_id: HashMap<u32, &ElementInfo> = elements.iter().map(|e| (e.id, e)).collect();
+        TreeBuilder {
+            structure: self,
+            elements: &by_id,
+            void_index,
+            group_by_type,
+            next_group_id: Cell::new(TYPE_GROUP_ID_BASE),
+        }
+        .structure_node(root, 0)
+    }
+}
+
+/// Deepest aggregation followed, against cycles in malformed files
+const MAX_DEPTH: usize = 64;
+
+struct TreeBuilder<'a> {
+    structure: &'a SpatialStructure,
+    elements: &'a HashMap<u32, &'a ElementInfo>,
+    void_index: &'a VoidIndex,
+    group_by_type: bool,
+    next_group_id: Cell<u32>,
+}
+
+impl TreeBuilder<'_> {
+    fn structure_node(&self, id: u32, depth: usize) -> Option<SpatialNode> {
+        let info = self.structure.spatial.get(&id)?;
+        let mut children = Vec::new();
+
+        // Aggregated structure (site -> building -> storey)
+        if depth < MAX_DEPTH {
+            if let Some(child_ids) = self.structure.aggregates.get(&id) {
+                children.extend(
+                    child_ids
+                        .iter()
+                        .filter_map(|&child| self.structure_node(child, depth + 1)),
+                );
+            }
+        }
+
+        // Contained elements with their openings
+        let contained = self
+            .structure
+            .contained_in
+            .get(&id)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        for &elem_id in contained {
+            if let Some(mut node) = self.element_node(elem_id, false) {
+                node.children = self.opening_nodes(elem_id);
+                children.push(node);
+            }
+        }
+
+        // Referenced elements that live in another structure
+        if let Some(element_ids) = self.structure.referenced_in.get(&id) {
+            children.extend(
+                element_ids
+                    .iter()
+                    .filter(|elem_id| !contained.contains(elem_id))
+                    .filter_map(|&elem_id| self.element_node(elem_id, true)),
+            );
+        }
+
+        // Structure first, storeys top down, then elements by type and name
+        children.sort_by(|a, b| {
+            b.kind.is_spatial().cmp(&a.kind.is_spatial()).then_with(|| {
+                if a.kind == SpatialKind::Storey && b.kind == SpatialKind::Storey {
+                    b.elevation
+                        .partial_cmp(&a.elevation)
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                } else {
+                    a.entity_type
+                        .cmp(&b.entity_type)
+                        .then_with(|| a.name.cmp(&b.name))
+                }
+            })
+        });
+
+        let kind = SpatialKind::of(&info.entity_type);
+        if self.group_by_type && matches!(kind, SpatialKind::Storey | SpatialKind::FacilityPart) {
+            children = self.type_groups(children);
+        }
+
+        Some(SpatialNode {
+            id,
+            kind,
+            name: info.name.clone(),
+            entity_type: info.entity_type.clone(),
+            predefined_type: None,
+            elevation: info.elevation,
+            // Spatial structures don't have geometry
+            has_geometry: false,
+            referenced: false,
+            children,
+        })
+    }
+
+    /// Replace the elements among sorted `children` with one group per
+    /// entity type, keeping the structure nodes in front
+    fn type_groups(&self, children: Vec<SpatialNode>) -> Vec<SpatialNode> {
+        let mut grouped: Vec<SpatialNode> = Vec::new();
+        for child in children {
+            if child.kind.is_spatial() {
+                grouped.push(child);
+                continue;
+            }
+            match grouped.last_mut() {
+                Some(group)
+                    if group.kind == SpatialKind::TypeGroup
+                        && group.entity_type == child.entity_type =>
+                {
+                    group.has_geometry |= child.has_geometry;
+                    group.children.push(child);
+                }
+                _ => {
+                    let id = self.next_group_id.get();
+                    self.next_group_id.set(id + 1);
+                    let name = match ifc_lite_core::resolve_type(&child.entity_type) {
+                        ifc_lite_core::IfcType::Unknown(_) => child.entity_type.clone(),
+                        ifc_type => ifc_type.name().to_string(),
+                    };
+                    grouped.push(SpatialNode {
+                        id,
+                        kind: SpatialKind::TypeGroup,
+                        name,
+                        entity_type: child.entity_type.clone(),
+                        predefined_type: None,
+                        elevation: None,
+                        has_geometry: child.has_geometry,
+                        referenced: false,
+                        children: vec![child],
+                    });
+                }
+            }
+        }
+        grouped
+    }
+
+    /// Leaf node of an element, if it was loaded
+    fn element_node(&self, id: u32, referenced: bool) -> Option<SpatialNode> {
+        let element = self.elements.get(&id)?;
+        Some(SpatialNode {
+            id,
+            kind: SpatialKind::Element,
+            name: element.name.clone().unwrap_or_else(|| format!("#{}", id)),
+            entity_type: element.entity_type.to_string(),
+            predefined_type: element.predefined_type.clone(),
+            elevation: None,
+            has_geometry: element.has_geometry,
+            referenced,
+            children: Vec::new(),
+        })
+    }
+
+    /// Openings of an element with the doors and windows filling them, so a
+    /// wall leads to its openings and on to what fills them
+    fn opening_nodes(&self, host_id: u32) -> Vec<SpatialNode> {
+        self.void_index
+            .get_voids(host_id)
+            .iter()
+            .filter_map(|&opening_id| {
+                let mut opening = self.element_node(opening_id, false)?;
+                opening.kind = SpatialKind::Opening;
+                opening.children = self
+                    .void_index
+                    .get_fillings(opening_id)
+                    .iter()
+                    .filter_map(|&filling| self.element_node(filling, false))
+                    .collect();
+                Some(opening)
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,236 @@
+//! Golden mesh tests
+//!
+//! Builds scenes from the bundled test models and compares a summary of the
+//! meshes - vertex and triangle counts, bounds and per-type totals - with
+//! the snapshots in `tests/golden/`. A geometry change that moves any of
+//! them fails here first.
+//!
+//! After an intended change, regenerate the snapshots and review the diff:
+//!
+//! ```text
+//! IFC_LITE_UPDATE_GOLDEN=1 cargo test -p ifc-lite-engine --test golden
+//! ```
+//!
+//! Snapshots are only ever written with `IFC_LITE_UPDATE_GOLDEN` set; a
+//! missing one fails the test like a differing one.
+
+use ifc_lite_engine::{BuildOptions, ElementInfo, SceneBuilder, SceneSink};
+use ifc_lite_geometry::Mesh;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Largest difference accepted between bounds, in metres
+const BOUNDS_TOLERANCE: f64 = 1e-3;
+
+/// Mesh totals of all elements of one type
+#[derive(Default)]
+struct Totals {
+    elements: usize,
+    meshed: usize,
+    vertices: usize,
+    triangles: usize,
+}
+
+/// Sink adding up the meshes per entity type
+#[derive(Default)]
+struct Summary {
+    types: BTreeMap<String, Totals>,
+    min: [f64; 3],
+    max: [f64; 3],
+    /// Non-empty meshes taken into the bounds
+    bounded: usize,
+}
+
+impl SceneSink for Summary {
+    fn mesh(&mut self, element: &ElementInfo, mesh: Mesh, _color: [f32; 4]) {
+        let totals = self
+            .types
+            .entry(element.entity_type.to_string())
+            .or_default();
+        totals.meshed += 1;
+        totals.vertices += mesh.vertex_count();
+        totals.triangles += mesh.triangle_count();
+        if mesh.is_empty() {
+            return;
+        }
+        let (min, max) = mesh.bounds();
+        let (min, max) = ([min.x, min.y, min.z], [max.x, max.y, max.z]);
+        for axis in 0..3 {
+            if self.bounded == 0 {
+                self.min[axis] = min[axis] as f64;
+                self.max[axis] = max[axis] as f64;
+            } else {
+                self.min[axis] = self.min[axis].min(min[axis] as f64);
+                self.max[axis] = self.max[axis].max(max[axis] as f64);
+            }
+        }
+        self.bounded += 1;
+    }
+}
+
+fn round(value: f64) -> f64 {
+    (value * 1e4).round() / 1e4
+}
+
+/// Snapshot of the scene built from a model, in file coordinates
+fn snapshot(model: &str) -> Value {
+    let path = models_dir().join(model);
+    let content = std::fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("Failed to read {}: {}", path.display(), e));
+    let mut summary = Summary::default();
+    let options = BuildOptions {
+        shift_origin: false,
+        ..Default::default()
+    };
+    let scene = SceneBuilder::new(&content)
+        .with_options(options)
+        .build(&mut summary);
+
+    for element in &scene.elements {
+        summary
+            .types
+            .entry(element.entity_type.to_string())
+            .or_default()
+            .elements += 1;
+    }
+
+    let mut types = Map::new();
+    for (entity_type, totals) in &summary.types {
+        types.insert(
+            entity_type.clone(),
+            json!({
+                "elements": totals.elements,
+                "meshed": totals.meshed,
+                "vertices": totals.vertices,
+                "triangles": totals.triangles,
+            }),
+        );
+    }
+    let bounds = if summary.bounded > 0 {
+        json!({
+            "min": summary.min.map(round),
+            "max": summary.max.map(round),
+        })
+    } else {
+        Value::Null
+    };
+    json!({
+        "model": model,
+        "elements": scene.elements.len(),
+        "meshed": summary.types.values().map(|t| t.meshed).sum::<usize>(),
+        "vertices": summary.types.values().map(|t| t.vertices).sum::<usize>(),
+        "triangles": summary.types.values().map(|t| t.triangles).sum::<usize>(),
+        "bounds": bounds,
+        "types": types,
+    })
+}
+
+fn models_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../tests/models")
+}
+
+fn golden_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("golden")
+        .join(format!("{}.json", name))
+}
+
+/// Differences between an expected and an actual snapshot, one per line
+fn diff(path: &str, expected: &Value, actual: &Value, out: &mut Vec<String>) {
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => {
+            for (key, value) in expected {
+                let path = format!("{}/{}", path, key);
+                match actual.get(key) {
+                    Some(actual) => diff(&path, value, actual, out),
+                    None => out.push(format!("{}: missing, expected {}", path, value)),
+                }
+            }
+            for (key, value) in actual {
+                if !expected.contains_key(key) {
+                    out.push(format!("{}/{}: unexpected {}", path, key, value));
+                }
+            }
+        }
+        (Value::Array(expected), Value::Array(actual)) if expected.len() == actual.len() => {
+            for (i, (expected, actual)) in expected.iter().zip(actual).enumerate() {
+                diff(&format!("{}/{}", path, i), expected, actual, out);
+            }
+        }
+        (Value::Number(e), Value::Number(a)) if e.is_f64() || a.is_f64() => {
+            let (e, a) = (e.as_f64().unwrap(), a.as_f64().unwrap());
+            if (e - a).abs() > BOUNDS_TOLERANCE {
+                out.push(format!("{}: expected {}, got {}", path, e, a));
+            }
+        }
+        _ if expected != actual => {
+            out.push(format!("{}: expected {}, got {}", path, expected, actual));
+        }
+        _ => {}
+    }
+}
+
+/// Compare the snapshot of `model` with golden `name`, or record it with
+/// `IFC_LITE_UPDATE_GOLDEN` set
+fn check(name: &str, model: &str) {
+    let actual = snapshot(model);
+    let path = golden_path(name);
+    if std::env::var_os("IFC_LITE_UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let text = serde_json::to_string_pretty(&actual).unwrap() + "\n";
+        std::fs::write(&path, text).unwrap();
+        eprintln!("Recorded golden {}", path.display());
+        return;
+    }
+
+    let text = std::fs::read_to_string(&path).unwrap_or_else(|e| {
+        panic!(
+            "Failed to read golden {}: {}\nRecord it with IFC_LITE_UPDATE_GOLDEN=1",
+            path.display(),
+            e
+        )
+    });
+    let expected: Value = serde_json::from_str(&text)
+        .unwrap_or_else(|e| panic!("Invalid golden {}: {}", path.display(), e));
+    let mut differences = Vec::new();
+    diff("", &expected, &actual, &mut differences);
+    assert!(
+        differences.is_empty(),
+        "{} differs from golden {}:\n  {}\nRerun with IFC_LITE_UPDATE_GOLDEN=1 if the change is intended",
+        model,
+        path.display(),
+        differences.join("\n  ")
+    );
+}
+
+macro_rules! golden {
+    ($($name:ident => $model:literal,)*) => {
+        $(
+            #[test]
+            fn $name() {
+                check(stringify!($name), $model);
+            }
+        )*
+    };
+}
+
+golden! {
+    test_model => "test.ifc",
+    cylinders => "ifcopenshell/cylinders.ifc",
+    sphere => "ifcopenshell/1030-sphere.ifc",
+    curved_line_segment => "ifcopenshell/452--line-segment--curved.ifc",
+    composite_curve => "ifcopenshell/single-circle-compcurve.ifc",
+    column => "ifcopenshell/1019-column.ifc",
+    faceted_brep => "ifcopenshell/faceted_brep.ifc",
+    faceted_brep_csg => "ifcopenshell/faceted_brep_csg.ifc",
+    advanced_brep => "ifcopenshell/advanced_brep.ifc",
+    duct_fitting => "ifcopenshell/1269--Project_IfcDuctFitting.ifc",
+    tessellated_item => "buildingsmart/tessellated-item.ifc",
+    basin_tessellation => "buildingsmart/basin-tessellation.ifc",
+    wall_with_opening_and_window => "buildingsmart/wall-with-opening-and-window.ifc",
+    composite_profile => "ara3d/ISSUE_044_test_IFCCOMPOSITEPROFILEDEF.ifc",
+    open_house => "ara3d/IfcOpenHouse_IFC4.ifc",
+    building_architecture => "buildingsmart/Building-Architecture.ifc",
+}
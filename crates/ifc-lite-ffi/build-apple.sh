@@ -10,6 +10,8 @@ CRATE_NAME="ifc-lite-ffi"
 LIB_NAME="libifc_lite_ffi"
 OUTPUT_DIR="$SCRIPT_DIR/output"
 XCFRAMEWORK_NAME="IfcLiteFFI"
+# Release with panic = "unwind", so panics become errors instead of aborting the app
+PROFILE="release-ffi"
 
 echo "🔧 Building IFC-Lite FFI for Apple platforms..."
 echo "   Project root: $PROJECT_ROOT"
@@ -60,7 +62,7 @@ echo "🏗️  Building for all targets..."
 build_target() {
     local target=$1
     echo "   Building for $target..."
-    cargo build -p "$CRATE_NAME" --release --target "$target" 2>&1 | grep -E "(Compiling|Finished|error)" || true
+    cargo build -p "$CRATE_NAME" --profile "$PROFILE" --target "$target" 2>&1 | grep -E "(Compiling|Finished|error)" || true
 }
 
 # Build all targets in parallel
@@ -82,20 +84,20 @@ mkdir -p "$OUTPUT_DIR/macos"
 echo "📦 Copying libraries..."
 
 # iOS Device (arm64 only)
-cp "target/aarch64-apple-ios/release/$LIB_NAME.a" "$OUTPUT_DIR/ios-device/"
+cp "target/aarch64-apple-ios/$PROFILE/$LIB_NAME.a" "$OUTPUT_DIR/ios-device/"
 
 # iOS Simulator (universal: arm64 + x86_64)
 echo "   Creating iOS Simulator universal binary..."
 lipo -create \
-    "target/aarch64-apple-ios-sim/release/$LIB_NAME.a" \
-    "target/x86_64-apple-ios/release/$LIB_NAME.a" \
+    "target/aarch64-apple-ios-sim/$PROFILE/$LIB_NAME.a" \
+    "target/x86_64-apple-ios/$PROFILE/$LIB_NAME.a" \
     -output "$OUTPUT_DIR/ios-simulator/$LIB_NAME.a"
 
 # macOS (universal: arm64 + x86_64)
 echo "   Creating macOS universal binary..."
 lipo -create \
-    "target/aarch64-apple-darwin/release/$LIB_NAME.a" \
-    "target/x86_64-apple-darwin/release/$LIB_NAME.a" \
+    "target/aarch64-apple-darwin/$PROFILE/$LIB_NAME.a" \
+    "target/x86_64-apple-darwin/$PROFILE/$LIB_NAME.a" \
     -output "$OUTPUT_DIR/macos/$LIB_NAME.a"
 
 # Verify universal binaries
@@ -0,0 +1,95 @@
+#ifndef IFC_LITE_H
+#define IFC_LITE_H
+
+/* Generated with cbindgen from src/capi.rs - do not edit by hand */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Floats per interleaved vertex: position, normal, RGBA
+#define IFC_LITE_VERTEX_STRIDE 10
+
+// A loaded model with its batched geometry (opaque to C)
+typedef struct IfcLiteScene IfcLiteScene;
+
+// Batched geometry, pre-transformed to world space (Y-up)
+//
+// `vertices` holds `vertex_count * IFC_LITE_VERTEX_STRIDE` floats laid out as
+// `[x, y, z, nx, ny, nz, r, g, b, a]`. The buffers belong to the scene and stay
+// valid until `ifc_lite_free`.
+typedef struct IfcLiteBatchedMesh {
+  const float *vertices;
+  uintptr_t vertex_count;
+  const uint32_t *indices;
+  uintptr_t index_count;
+  bool is_transparent;
+} IfcLiteBatchedMesh;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Load an IFC file; returns NULL on failure (see `ifc_lite_last_error`)
+//
+// Panics count as failures only in `release-ffi` builds, which unwind;
+// builds with `panic = 'abort'` abort the process instead.
+//
+// # Safety
+// `path` must be a valid NUL-terminated string.
+struct IfcLiteScene *ifc_lite_load(const char *path);
+
+// Load IFC content from memory; returns NULL on failure
+//
+// # Safety
+// `data` must point to `len` readable bytes.
+struct IfcLiteScene *ifc_lite_load_bytes(const uint8_t *data, uintptr_t len);
+
+// Number of batched meshes: one for opaque and one for transparent geometry,
+// each only when present
+//
+// # Safety
+// `scene` must be NULL or a handle returned by `ifc_lite_load*`.
+uintptr_t ifc_lite_batched_mesh_count(const struct IfcLiteScene *scene);
+
+// Batched mesh at `index`, or NULL when out of range
+//
+// # Safety
+// `scene` must be NULL or a handle returned by `ifc_lite_load*`.
+const struct IfcLiteBatchedMesh *ifc_lite_get_batched_mesh(const struct IfcLiteScene *scene,
+                                                           uintptr_t index);
+
+// Write the scene bounds (min xyz, max xyz; Y-up) to `out`; false when empty
+//
+// # Safety
+// `scene` must be NULL or a valid handle; `out` must have room for 6 floats.
+bool ifc_lite_get_bounds(const struct IfcLiteScene *scene, float *out);
+
+// Number of entities in the scene
+//
+// # Safety
+// `scene` must be NULL or a handle returned by `ifc_lite_load*`.
+uintptr_t ifc_lite_entity_count(const struct IfcLiteScene *scene);
+
+// Release a scene and all mesh buffers obtained from it
+//
+// # Safety
+// `scene` must be NULL or a handle returned by `ifc_lite_load*` that was not
+// freed before.
+void ifc_lite_free(struct IfcLiteScene *scene);
+
+// Message of the last failed call on this thread, or NULL
+//
+// The string stays valid until the next failing call on the same thread.
+const char *ifc_lite_last_error(void);
+
+// Library version as a static NUL-terminated string
+const char *ifc_lite_version(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* IFC_LITE_H */
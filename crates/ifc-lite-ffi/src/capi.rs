@@ -0,0 +1,288 @@
+//! Plain C ABI for game engines and C++ hosts
+//!
+//! Unreal, Unity (P/Invoke) and native C++ code cannot consume UniFFI
+//! scaffolding, so the batched geometry is also exposed through a handful of
+//! `extern "C"` functions. They wrap the same [`IfcScene`] as the UniFFI
+//! bindings. The header lives in `include/ifc_lite.h` and is generated with
+//! `cbindgen --config cbindgen.toml --output include/ifc_lite.h`.
+//!
+//! Typical use:
+//! ```c
+//! IfcLiteScene *scene = ifc_lite_load("model.ifc");
+//! if (!scene) { puts(ifc_lite_last_error()); return; }
+//! for (size_t i = 0; i < ifc_lite_batched_mesh_count(scene); i++) {
+//!     const IfcLiteBatchedMesh *mesh = ifc_lite_get_batched_mesh(scene, i);
+//!     upload(mesh->vertices, mesh->vertex_count, mesh->indices, mesh->index_count);
+//! }
+//! ifc_lite_free(scene);
+//! ```
+//!
+//! Panics while loading are caught and reported as errors only when they
+//! unwind. The workspace `release` profile sets `panic = 'abort'`, so build
+//! the library for hosts with `cargo build -p ifc-lite-ffi --profile
+//! release-ffi`; with the plain release profile a panic still aborts the host.
+
+use crate::{BatchedMeshData, IfcError, IfcScene};
+use std::cell::RefCell;
+use std::ffi::{c_char, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+/// Floats per interleaved vertex: position, normal, RGBA
+pub const IFC_LITE_VERTEX_STRIDE: usize = 10;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(message: String) {
+    let message = CString::new(message.replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(message));
+}
+
+/// A loaded model with its batched geometry (opaque to C)
+pub struct IfcLiteScene {
+    scene: IfcScene,
+    /// Owns the buffers that `views` point into; never modified after load
+    _batches: Vec<BatchedMeshData>,
+    views: Vec<IfcLiteBatchedMesh>,
+}
+
+impl IfcLiteScene {
+    fn new(scene: IfcScene) -> Box<Self> {
+        let batches = scene.get_batched_meshes();
+        let views = batches
+            .iter()
+            .map(|batch| IfcLiteBatchedMesh {
+                vertices: batch.vertices.as_ptr(),
+                vertex_count: batch.vertex_count as usize,
+                indices: batch.indices.as_ptr(),
+                index_count: batch.indices.len(),
+                is_transparent: batch.is_transparent,
+            })
+            .collect();
+        Box::new(Self {
+            scene,
+            _batches: batches,
+            views,
+        })
+    }
+}
+
+/// Batched geometry, pre-transformed to world space (Y-up)
+///
+/// `vertices` holds `vertex_count * IFC_LITE_VERTEX_STRIDE` floats laid out as
+/// `[x, y, z, nx, ny, nz, r, g, b, a]`. The buffers belong to the scene and stay
+/// valid until `ifc_lite_free`.
+#[repr(C)]
+pub struct IfcLiteBatchedMesh {
+    pub vertices: *const f32,
+    pub vertex_count: usize,
+    pub indices: *const u32,
+    pub index_count: usize,
+    pub is_transparent: bool,
+}
+
+/// Run a load and hand out the scene; failures and panics return NULL
+///
+/// A panic unwinding into the host would abort it, so it is caught and
+/// reported like any other error. Builds with `panic = 'abort'` abort before
+/// getting here.
+fn load_handle(load: impl FnOnce() -> Result<IfcScene, IfcError>) -> *mut IfcLiteScene {
+    match panic::catch_unwind(AssertUnwindSafe(|| load().map(IfcLiteScene::new))) {
+        Ok(Ok(scene)) => Box::into_raw(scene),
+        Ok(Err(e)) => {
+            set_last_error(e.to_string());
+            ptr::null_mut()
+        }
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .copied()
+                .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+                .unwrap_or("unknown cause");
+            set_last_error(format!("Panic while loading: {}", message));
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Load an IFC file; returns NULL on failure (see `ifc_lite_last_error`)
+///
+/// Panics count as failures only in `release-ffi` builds, which unwind;
+/// builds with `panic = 'abort'` abort the process instead.
+///
+/// # Safety
+/// `path` must be a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn ifc_lite_load(path: *const c_char) -> *mut IfcLiteScene {
+    if path.is_null() {
+        set_last_error("path is NULL".to_string());
+        return ptr::null_mut();
+    }
+    let path = CStr::from_ptr(path).to_string_lossy().into_owned();
+    load_handle(|| {
+        let scene = IfcScene::new();
+        scene.load_file(path).map(|_| scene)
+    })
+}
+
+/// Load IFC content from memory; returns NULL on failure
+///
+/// # Safety
+/// `data` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn ifc_lite_load_bytes(data: *const u8, len: usize) -> *mut IfcLiteScene {
+    if data.is_null() {
+        set_last_error("data is NULL".to_string());
+        return ptr::null_mut();
+    }
+    let bytes = std::slice::from_raw_parts(data, len).to_vec();
+    load_handle(|| {
+        let scene = IfcScene::new();
+        scene.load_bytes(bytes).map(|_| scene)
+    })
+}
+
+/// Number of batched meshes: one for opaque and one for transparent geometry,
+/// each only when present
+///
+/// # Safety
+/// `scene` must be NULL or a handle returned by `ifc_lite_load*`.
+#[no_mangle]
+pub unsafe extern "C" fn ifc_lite_batched_mesh_count(scene: *const IfcLiteScene) -> usize {
+    scene.as_ref().map_or(0, |scene| scene.views.len())
+}
+
+/// Batched mesh at `index`, or NULL when out of range
+///
+/// # Safety
+/// `scene` must be NULL or a handle returned by `ifc_lite_load*`.
+#[no_mangle]
+pub unsafe extern "C" fn ifc_lite_get_batched_mesh(
+    scene: *const IfcLiteScene,
+    index: usize,
+) -> *const IfcLiteBatchedMesh {
+    scene
+        .as_ref()
+        .and_then(|scene| scene.views.get(index))
+        .map_or(ptr::null(), |view| view as *const _)
+}
+
+/// Write the scene bounds (min xyz, max xyz; Y-up) to `out`; false when empty
+///
+/// # Safety
+/// `scene` must be NULL or a valid handle; `out` must have room for 6 floats.
+#[no_mangle]
+pub unsafe extern "C" fn ifc_lite_get_bounds(scene: *const IfcLiteScene, out: *mut f32) -> bool {
+    let Some(bounds) = scene.as_ref().and_then(|s| s.scene.get_bounds()) else {
+        return false;
+    };
+    if out.is_null() {
+        return false;
+    }
+    let values = [
+        bounds.min_x,
+        bounds.min_y,
+        bounds.min_z,
+        bounds.max_x,
+        bounds.max_y,
+        bounds.max_z,
+    ];
+    ptr::copy_nonoverlapping(values.as_ptr(), out, values.len());
+    true
+}
+
+/// Number of entities in the scene
+///
+/// # Safety
+/// `scene` must be NULL or a handle returned by `ifc_lite_load*`.
+#[no_mangle]
+pub unsafe extern "C" fn ifc_lite_entity_count(scene: *const IfcLiteScene) -> usize {
+    scene
+        .as_ref()
+        .map_or(0, |scene| scene.scene.data.read().entities.len())
+}
+
+/// Release a scene and all mesh buffers obtained from it
+///
+/// # Safety
+/// `scene` must be NULL or a handle returned by `ifc_lite_load*` that was not
+/// freed before.
+#[no_mangle]
+pub unsafe extern "C" fn ifc_lite_free(scene: *mut IfcLiteScene) {
+    if !scene.is_null() {
+        drop(Box::from_raw(scene));
+    }
+}
+
+/// Message of the last failed call on this thread, or NULL
+///
+/// The string stays valid until the next failing call on the same thread.
+#[no_mangle]
+pub extern "C" fn ifc_lite_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ref().map_or(ptr::null(), |s| s.as_ptr()))
+}
+
+/// Library version as a static NUL-terminated string
+#[no_mangle]
+pub extern "C" fn ifc_lite_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr() as *const c_char
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_c_api_round_trip() {
+        let content = include_bytes!("../../../tests/models/test.ifc");
+        unsafe {
+            let scene = ifc_lite_load_bytes(content.as_ptr(), content.len());
+            assert!(!scene.is_null());
+            assert!(ifc_lite_entity_count(scene) > 0);
+
+            let count = ifc_lite_batched_mesh_count(scene);
+            assert!(count > 0);
+            let mesh = &*ifc_lite_get_batched_mesh(scene, 0);
+            assert!(mesh.index_count > 0 && mesh.index_count.is_multiple_of(3));
+            let indices = std::slice::from_raw_parts(mesh.indices, mesh.index_count);
+            assert!(indices.iter().all(|&i| (i as usize) < mesh.vertex_count));
+            assert!(ifc_lite_get_batched_mesh(scene, count).is_null());
+
+            let mut bounds = [0.0f32; 6];
+            assert!(ifc_lite_get_bounds(scene, bounds.as_mut_ptr()));
+            assert!(bounds[3] > bounds[0]);
+
+            ifc_lite_free(scene);
+        }
+    }
+
+    #[test]
+    fn test_c_api_errors() {
+        let path = CString::new("/nonexistent/model.ifc").unwrap();
+        unsafe {
+            assert!(ifc_lite_load(path.as_ptr()).is_null());
+            let message = CStr::from_ptr(ifc_lite_last_error()).to_string_lossy();
+            assert!(message.contains("IO error"), "{}", message);
+
+            assert_eq!(ifc_lite_batched_mesh_count(ptr::null()), 0);
+            ifc_lite_free(ptr::null_mut());
+        }
+        let version = unsafe { CStr::from_ptr(ifc_lite_version()) };
+        assert_eq!(version.to_str().unwrap(), env!("CARGO_PKG_VERSION"));
+    }
+
+    #[test]
+    fn test_c_api_catches_panics() {
+        let scene = load_handle(|| panic!("tessellation blew up"));
+        assert!(scene.is_null());
+        let message = unsafe { CStr::from_ptr(ifc_lite_last_error()) }.to_string_lossy();
+        assert_eq!(message, "Panic while loading: tessellation blew up");
+
+        let scene = load_handle(|| panic!("{} elements", 3));
+        assert!(scene.is_null());
+        let message = unsafe { CStr::from_ptr(ifc_lite_last_error()) }.to_string_lossy();
+        assert!(message.ends_with("3 elements"), "{}", message);
+    }
+}
@@ -2,22 +2,32 @@
 //!
 //! This crate provides cross-platform bindings to the IFC-Lite library,
 //! allowing native iOS, macOS, and Android apps to load and interact with IFC files.
+//! Game engines and C++ hosts use the plain C ABI in [`capi`] instead.
 
-use ifc_lite_core::DecodedEntity;
+use ifc_lite_core::{
+    Attribute, ContentHash, DecodedEntity, EntityDecoder, GeoReference, SiteLocation,
+    SystemAssignments, TypeAssignments,
+};
+use ifc_lite_geometry::{EntityBox, SpatialIndex, VoidIndex};
 use parking_lot::RwLock;
-use std::collections::HashSet;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 /// Helper to extract entity refs from a list attribute
-fn get_ref_list(entity: &DecodedEntity, index: usize) -> Option<Vec<u32>> {
+fn get_ref_list(entity: &DecodedEntity, attribute: Attribute) -> Option<Vec<u32>> {
     entity
-        .get_list(index)
+        .attribute_list(attribute)
         .map(|list| list.iter().filter_map(|v| v.as_entity_ref()).collect())
 }
 
 // Export UniFFI scaffolding
 uniffi::setup_scaffolding!();
 
+pub mod capi;
+#[cfg(feature = "snapshot")]
+mod snapshot;
+
 /// Library version
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 
@@ -33,6 +43,12 @@ pub fn get_version() -> String {
     VERSION.to_string()
 }
 
+/// Geometry options a plain load uses
+#[uniffi::export]
+pub fn default_geometry_options() -> GeometryOptions {
+    ifc_lite_geometry::GeometryOptions::default().into()
+}
+
 /// Error type for FFI operations
 #[derive(Debug, thiserror::Error, uniffi::Error)]
 pub enum IfcError {
@@ -42,10 +58,14 @@ pub enum IfcError {
     GeometryError { msg: String },
     #[error("IO error: {msg}")]
     IoError { msg: String },
+    #[error("Query error: {msg}")]
+    QueryError { msg: String },
     #[error("Invalid handle")]
     InvalidHandle,
     #[error("Scene not loaded")]
     NotLoaded,
+    #[error("Render error: {msg}")]
+    RenderError { msg: String },
 }
 
 impl From<std::io::Error> for IfcError {
@@ -63,6 +83,25 @@ pub struct EntityInfo {
     pub global_id: Option<String>,
     pub storey: Option<String>,
     pub storey_elevation: Option<f32>,
+    /// PredefinedType enum (e.g. "ROOF" for a roof slab)
+    pub predefined_type: Option<String>,
+    /// ObjectType string
+    pub object_type: Option<String>,
+}
+
+impl From<&ifc_lite_engine::ElementInfo> for EntityInfo {
+    fn from(element: &ifc_lite_engine::ElementInfo) -> Self {
+        Self {
+            id: element.id as u64,
+            entity_type: element.entity_type.to_string(),
+            name: element.name.clone(),
+            global_id: element.global_id.clone(),
+            storey: element.storey.as_deref().map(str::to_string),
+            storey_elevation: element.storey_elevation,
+            predefined_type: element.predefined_type.clone(),
+            object_type: element.object_type.clone(),
+        }
+    }
 }
 
 /// Mesh data for rendering (per-entity, use for individual mesh access)
@@ -74,16 +113,45 @@ pub struct MeshData {
     pub positions: Vec<f32>,
     pub normals: Vec<f32>,
     pub indices: Vec<u32>,
-    pub color: Vec<f32>,     // RGBA
+    pub color: Vec<f32>, // RGBA
+    /// Per-vertex RGBA from indexed colour maps or styled faces, empty when uncolored.
+    /// A negative alpha means the vertex uses `color`.
+    pub vertex_colors: Vec<f32>,
     pub transform: Vec<f32>, // 4x4 matrix
 }
 
+/// Column-major identity transform of meshes in world coordinates
+const IDENTITY: [f32; 16] = [
+    1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0,
+];
+
+impl MeshData {
+    /// Positions with `transform` applied, in IFC coordinates like `positions`
+    fn world_positions(&self) -> Cow<'_, [f32]> {
+        if self.transform.len() != 16 || self.transform[..] == IDENTITY[..] {
+            return Cow::Borrowed(&self.positions);
+        }
+        // The transform works on Y-up coordinates
+        let transform = nalgebra::Matrix4::from_column_slice(&self.transform);
+        Cow::Owned(
+            self.positions
+                .chunks_exact(3)
+                .flat_map(|p| {
+                    let p = transform.transform_point(&nalgebra::Point3::new(p[0], p[2], -p[1]));
+                    [p.x, -p.z, p.y]
+                })
+                .collect(),
+        )
+    }
+}
+
 /// Batched mesh data for efficient rendering
 /// All vertices are pre-transformed to world space and combined into single buffers.
 /// Use this for maximum rendering performance (2 draw calls instead of N).
 #[derive(Debug, Clone, uniffi::Record)]
 pub struct BatchedMeshData {
     /// Interleaved vertex data: [x, y, z, nx, ny, nz, r, g, b, a, ...] (10 floats per vertex)
+    /// Colors are per face where the model assigns them, otherwise per element
     pub vertices: Vec<f32>,
     /// Triangle indices
     pub indices: Vec<u32>,
@@ -106,7 +174,119 @@ pub struct SceneBounds {
     pub max_z: f32,
 }
 
+impl From<ifc_lite_engine::Bounds> for SceneBounds {
+    fn from(bounds: ifc_lite_engine::Bounds) -> Self {
+        let ([min_x, min_y, min_z], [max_x, max_y, max_z]) = (bounds.min, bounds.max);
+        Self {
+            min_x,
+            min_y,
+            min_z,
+            max_x,
+            max_y,
+            max_z,
+        }
+    }
+}
+
+/// Entity hit by a ray
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct RayHit {
+    pub entity_id: u64,
+    /// Distance from the ray origin, in units of the ray direction
+    pub distance: f32,
+}
+
+/// Cut outline of one element in a storey plan
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct PlanOutline {
+    pub entity_id: u64,
+    /// Model X/Y pairs: `[x0, y0, x1, y1, ...]`
+    pub points: Vec<f32>,
+    /// The last point connects back to the first
+    pub closed: bool,
+}
+
+/// 2D floor plan of a storey, for mini-maps and plan views
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct StoreyPlan {
+    pub storey: String,
+    pub elevation: f32,
+    /// Height of the horizontal cut, `elevation` plus the cut height
+    pub cut_elevation: f32,
+    pub outlines: Vec<PlanOutline>,
+}
+
+/// Room volume shown in space mode
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct SpaceInfo {
+    pub entity_id: u64,
+    /// Name, usually the room number
+    pub name: Option<String>,
+    pub storey: Option<String>,
+    /// Label anchor at the centre of the space's bounds, in scene coordinates
+    pub centroid: Vec<f32>,
+    /// Whether the space is drawn with the current space settings
+    pub visible: bool,
+}
+
+/// Type object (IfcTypeObject) shared by the occurrences of a product family
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct TypeInfo {
+    pub id: u64,
+    pub entity_type: String,
+    pub name: Option<String>,
+}
+
+impl From<&ifc_lite_core::TypeObject> for TypeInfo {
+    fn from(type_object: &ifc_lite_core::TypeObject) -> Self {
+        Self {
+            id: type_object.id as u64,
+            entity_type: type_object.entity_type.clone(),
+            name: type_object.name.clone(),
+        }
+    }
+}
+
+/// System or zone (IfcSystem subtype) grouping elements via
+/// IfcRelAssignsToGroup
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct SystemInfo {
+    pub id: u64,
+    pub entity_type: String,
+    pub name: Option<String>,
+    pub member_count: u32,
+}
+
+/// Where the model sits on the map and on the globe
+///
+/// Map conversion values are in metres and default to no offset, rotation
+/// or scaling when the model only carries a site location.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct GeolocationInfo {
+    /// Target CRS name, e.g. "EPSG:32632"
+    pub crs_name: Option<String>,
+    pub epsg_code: Option<u32>,
+    pub geodetic_datum: Option<String>,
+    pub vertical_datum: Option<String>,
+    pub map_projection: Option<String>,
+    pub eastings: f64,
+    pub northings: f64,
+    pub orthogonal_height: f64,
+    /// Angle from map east to the model x axis, counter-clockwise in radians
+    pub rotation: f64,
+    pub scale: f64,
+    /// IfcSite RefLatitude in decimal degrees
+    pub latitude: Option<f64>,
+    /// IfcSite RefLongitude in decimal degrees
+    pub longitude: Option<f64>,
+    /// IfcSite RefElevation in metres
+    pub elevation: Option<f64>,
+}
+
 /// Spatial hierarchy node
+///
+/// Elements of a storey are grouped by entity type: `node_type`
+/// "TypeGroup" nodes have a synthetic id and the elements as children.
 #[derive(Debug, Clone, uniffi::Record)]
 pub struct SpatialNode {
     pub id: u64,
@@ -118,6 +298,29 @@ pub struct SpatialNode {
     pub children: Vec<SpatialNode>,
 }
 
+impl From<ifc_lite_engine::SpatialNode> for SpatialNode {
+    fn from(node: ifc_lite_engine::SpatialNode) -> Self {
+        Self {
+            id: node.id as u64,
+            node_type: node.kind.name().to_string(),
+            name: node.name,
+            entity_type: node.entity_type,
+            elevation: node.elevation,
+            has_geometry: node.has_geometry,
+            // Referenced elements can't be told apart here, so each element
+            // shows only under the structure containing it
+            children: node
+                .children
+                .into_iter()
+                .filter(|child| !child.referenced)
+                .map(SpatialNode::from)
+                // Groups that only held referenced elements
+                .filter(|child| child.node_type != "TypeGroup" || !child.children.is_empty())
+                .collect(),
+        }
+    }
+}
+
 /// Property set
 #[derive(Debug, Clone, uniffi::Record)]
 pub struct PropertySet {
@@ -129,8 +332,97 @@ pub struct PropertySet {
 #[derive(Debug, Clone, uniffi::Record)]
 pub struct PropertyValue {
     pub name: String,
+    /// Formatted value for display
     pub value: String,
     pub unit: Option<String>,
+    /// Typed value (None when the property has no value)
+    pub data: Option<PropertyData>,
+}
+
+/// Typed property value
+#[derive(Debug, Clone, PartialEq, uniffi::Enum)]
+pub enum PropertyData {
+    Text {
+        value: String,
+    },
+    Real {
+        value: f64,
+        unit: Option<String>,
+    },
+    Integer {
+        value: i64,
+    },
+    Boolean {
+        value: bool,
+    },
+    Enum {
+        value: String,
+    },
+    EntityRef {
+        id: u64,
+    },
+    List {
+        items: Vec<PropertyData>,
+    },
+    Bounded {
+        lower: Option<f64>,
+        upper: Option<f64>,
+        set_point: Option<f64>,
+        unit: Option<String>,
+    },
+    Table {
+        defining: Vec<PropertyData>,
+        defined: Vec<PropertyData>,
+    },
+}
+
+impl From<ifc_lite_core::PropertyData> for PropertyData {
+    fn from(data: ifc_lite_core::PropertyData) -> Self {
+        use ifc_lite_core::PropertyData as Core;
+        match data {
+            Core::Text(value) => Self::Text { value },
+            Core::Real { value, unit } => Self::Real { value, unit },
+            Core::Integer(value) => Self::Integer { value },
+            Core::Boolean(value) => Self::Boolean { value },
+            Core::Enum(value) => Self::Enum { value },
+            Core::EntityRef(id) => Self::EntityRef { id: id as u64 },
+            Core::List(items) => Self::List {
+                items: items.into_iter().map(Self::from).collect(),
+            },
+            Core::Bounded {
+                lower,
+                upper,
+                set_point,
+                unit,
+            } => Self::Bounded {
+                lower,
+                upper,
+                set_point,
+                unit,
+            },
+            Core::Table { defining, defined } => Self::Table {
+                defining: defining.into_iter().map(Self::from).collect(),
+                defined: defined.into_iter().map(Self::from).collect(),
+            },
+        }
+    }
+}
+
+impl PropertyValue {
+    /// Build from a typed value, filling the display fields
+    fn from_data(name: String, data: Option<ifc_lite_core::PropertyData>) -> Self {
+        let value = data
+            .as_ref()
+            .map(|d| d.to_string())
+            .unwrap_or_else(|| "—".to_string());
+        let unit = data.as_ref().and_then(|d| d.unit()).map(|u| u.to_string());
+        Self {
+            name,
+            value,
+            unit,
+            data: data.map(PropertyData::from),
+        }
+    }
 }
 
 /// Load result
@@ -141,6 +433,120 @@ pub struct LoadResult {
     pub spatial_tree: Option<SpatialNode>,
     pub bounds: Option<SceneBounds>,
     pub load_time_ms: u64,
+    /// Stable hex hash of the file content (cache/session key)
+    pub content_hash: String,
+}
+
+/// Wall time of one load stage
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct StageMetric {
+    pub name: String,
+    pub ms: f64,
+}
+
+/// Calls and CPU time of one geometry processor, summed over threads
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct ProcessorMetric {
+    /// Representation item type, e.g. "IfcExtrudedAreaSolid"
+    pub name: String,
+    pub calls: u64,
+    pub ms: f64,
+}
+
+/// Where the time of the last load went, for app telemetry
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct LoadMetrics {
+    /// Wall time of all stages
+    pub total_ms: f64,
+    /// Stages in the order they ran: index, spatial, prepare, geometry,
+    /// sink, tree and finish, then batching and any the app recorded
+    pub stages: Vec<StageMetric>,
+    /// Geometry processors, slowest first
+    pub processors: Vec<ProcessorMetric>,
+    pub content_bytes: u64,
+    pub entity_count: u64,
+    pub mesh_count: u64,
+    pub triangle_count: u64,
+}
+
+/// IFC release a file was written against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum SchemaVersion {
+    Ifc2x3,
+    Ifc4,
+    Ifc4x3,
+}
+
+impl From<ifc_lite_core::SchemaVersion> for SchemaVersion {
+    fn from(schema: ifc_lite_core::SchemaVersion) -> Self {
+        match schema {
+            ifc_lite_core::SchemaVersion::Ifc2x3 => Self::Ifc2x3,
+            ifc_lite_core::SchemaVersion::Ifc4 => Self::Ifc4,
+            ifc_lite_core::SchemaVersion::Ifc4x3 => Self::Ifc4x3,
+        }
+    }
+}
+
+/// How serious a load diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum DiagnosticSeverity {
+    Warning,
+    Error,
+}
+
+/// Processing stage a load diagnostic was raised in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum DiagnosticKind {
+    Schema,
+    Parse,
+    Structure,
+    Geometry,
+    Output,
+}
+
+/// Problem recorded while loading; the entity was skipped or left without geometry
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct DiagnosticInfo {
+    pub severity: DiagnosticSeverity,
+    pub kind: DiagnosticKind,
+    pub entity_id: Option<u64>,
+    /// Uppercase STEP type name
+    pub entity_type: Option<String>,
+    /// 1-based line in the file
+    pub line: Option<u32>,
+    pub message: String,
+}
+
+impl From<ifc_lite_core::Diagnostic> for DiagnosticInfo {
+    fn from(diagnostic: ifc_lite_core::Diagnostic) -> Self {
+        use ifc_lite_core::{DiagnosticKind as Kind, Severity};
+        Self {
+            severity: match diagnostic.severity {
+                Severity::Warning => DiagnosticSeverity::Warning,
+                Severity::Error => DiagnosticSeverity::Error,
+            },
+            kind: match diagnostic.kind {
+                Kind::Schema => DiagnosticKind::Schema,
+                Kind::Parse => DiagnosticKind::Parse,
+                Kind::Structure => DiagnosticKind::Structure,
+                Kind::Geometry => DiagnosticKind::Geometry,
+                Kind::Output => DiagnosticKind::Output,
+            },
+            entity_id: diagnostic.entity_id.map(u64::from),
+            entity_type: diagnostic.entity_type,
+            line: diagnostic.line.map(|l| l as u32),
+            message: diagnostic.message,
+        }
+    }
+}
+
+/// Problems recorded by the last load
+#[derive(Debug, Clone, Default, uniffi::Record)]
+pub struct DiagnosticsReport {
+    /// Kept problems, sorted by line
+    pub items: Vec<DiagnosticInfo>,
+    /// Further problems past the diagnostics limit, only counted
+    pub dropped: u64,
 }
 
 /// Camera state
@@ -152,21 +558,128 @@ pub struct CameraState {
     pub target_x: f32,
     pub target_y: f32,
     pub target_z: f32,
+    /// Parallel projection instead of perspective
+    pub orthographic: bool,
 }
 
 impl Default for CameraState {
     fn default() -> Self {
+        let (azimuth, elevation) = StandardView::Iso.angles();
         Self {
-            azimuth: 0.785,   // 45 degrees
-            elevation: 0.615, // ~35 degrees (isometric)
+            azimuth,
+            elevation,
             distance: 100.0,
             target_x: 0.0,
             target_y: 0.0,
             target_z: 0.0,
+            orthographic: false,
+        }
+    }
+}
+
+/// One stop of a camera path
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct CameraKeyframe {
+    pub camera: CameraState,
+    /// Seconds to get here from the previous keyframe
+    pub duration: f32,
+}
+
+/// Camera path as the viewers store and play it
+#[derive(serde::Serialize, serde::Deserialize)]
+struct CameraPathJson {
+    keyframes: Vec<CameraKeyframeJson>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct CameraKeyframeJson {
+    camera: CameraJson,
+    duration: f32,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct CameraJson {
+    azimuth: f32,
+    elevation: f32,
+    distance: f32,
+    target: [f32; 3],
+    #[serde(default)]
+    orthographic: bool,
+}
+
+impl From<&CameraKeyframe> for CameraKeyframeJson {
+    fn from(keyframe: &CameraKeyframe) -> Self {
+        let camera = &keyframe.camera;
+        Self {
+            camera: CameraJson {
+                azimuth: camera.azimuth,
+                elevation: camera.elevation,
+                distance: camera.distance,
+                target: [camera.target_x, camera.target_y, camera.target_z],
+                orthographic: camera.orthographic,
+            },
+            duration: keyframe.duration,
+        }
+    }
+}
+
+impl From<CameraKeyframeJson> for CameraKeyframe {
+    fn from(keyframe: CameraKeyframeJson) -> Self {
+        let camera = keyframe.camera;
+        Self {
+            camera: CameraState {
+                azimuth: camera.azimuth,
+                elevation: camera.elevation,
+                distance: camera.distance,
+                target_x: camera.target[0],
+                target_y: camera.target[1],
+                target_z: camera.target[2],
+                orthographic: camera.orthographic,
+            },
+            duration: keyframe.duration,
+        }
+    }
+}
+
+/// Standard camera views, named after the side of the model in view
+///
+/// Front looks along IFC +Y; Iso is the home view from above the front
+/// right corner. The order matches the view numbers of the native viewer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum StandardView {
+    Top,
+    Bottom,
+    Front,
+    Back,
+    Left,
+    Right,
+    Iso,
+}
+
+impl StandardView {
+    /// Azimuth and elevation of the view in radians, as in [`CameraState`]
+    pub fn angles(self) -> (f32, f32) {
+        use std::f32::consts::{FRAC_PI_2, PI};
+        match self {
+            StandardView::Top => (0.0, FRAC_PI_2 - 0.001),
+            StandardView::Bottom => (0.0, -FRAC_PI_2 + 0.001),
+            StandardView::Front => (0.0, 0.0),
+            StandardView::Back => (PI, 0.0),
+            StandardView::Left => (-FRAC_PI_2, 0.0),
+            StandardView::Right => (FRAC_PI_2, 0.0),
+            StandardView::Iso => (0.785, 0.615), // 45°, 35.264°
         }
     }
 }
 
+/// Quality preset of an optional rendering effect of the native viewer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum GraphicsQuality {
+    Off,
+    Low,
+    High,
+}
+
 /// Selection state
 #[derive(Debug, Clone, uniffi::Record)]
 pub struct SelectionState {
@@ -208,11 +721,425 @@ impl Default for SectionPlane {
     }
 }
 
+/// Most section planes a scene holds
+pub const MAX_SECTION_PLANES: usize = 6;
+
+/// Section box: everything outside the box is cut away
+#[derive(Debug, Clone, Default, uniffi::Record)]
+pub struct SectionBox {
+    pub enabled: bool,
+    pub min_x: f32,
+    pub min_y: f32,
+    pub min_z: f32,
+    pub max_x: f32,
+    pub max_y: f32,
+    pub max_z: f32,
+}
+
+/// Comment on a BCF topic
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct BcfCommentInfo {
+    pub guid: String,
+    pub author: String,
+    /// ISO 8601 date-time
+    pub date: String,
+    pub text: String,
+}
+
+/// BCF topic (issue) with the entities it refers to
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct BcfTopicInfo {
+    pub guid: String,
+    pub title: String,
+    pub description: Option<String>,
+    pub topic_type: Option<String>,
+    pub status: Option<String>,
+    pub author: String,
+    /// ISO 8601 date-time
+    pub creation_date: String,
+    pub comments: Vec<BcfCommentInfo>,
+    /// GlobalIds selected in the topic's viewpoints
+    pub global_ids: Vec<String>,
+    /// Loaded entities matching `global_ids`
+    pub entity_ids: Vec<u64>,
+    /// PNG snapshot of the first viewpoint
+    pub snapshot_png: Option<Vec<u8>>,
+}
+
+/// Quantities of one element, in m, m² and m³
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct ElementQuantityInfo {
+    pub entity_id: u64,
+    pub global_id: Option<String>,
+    pub entity_type: String,
+    pub name: Option<String>,
+    pub storey: Option<String>,
+    pub material: Option<String>,
+    pub volume: Option<f64>,
+    pub footprint_area: Option<f64>,
+    pub surface_area: Option<f64>,
+    pub length: Option<f64>,
+    /// Some measures were computed from the mesh
+    pub estimated: bool,
+}
+
+/// How an element's material is defined
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum MaterialKind {
+    Single,
+    LayerSet,
+    ProfileSet,
+    ConstituentSet,
+    List,
+}
+
+impl From<ifc_lite_core::MaterialKind> for MaterialKind {
+    fn from(kind: ifc_lite_core::MaterialKind) -> Self {
+        match kind {
+            ifc_lite_core::MaterialKind::Single => Self::Single,
+            ifc_lite_core::MaterialKind::LayerSet => Self::LayerSet,
+            ifc_lite_core::MaterialKind::ProfileSet => Self::ProfileSet,
+            ifc_lite_core::MaterialKind::ConstituentSet => Self::ConstituentSet,
+            ifc_lite_core::MaterialKind::List => Self::List,
+        }
+    }
+}
+
+/// One layer, profile or constituent of a composite material
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct MaterialComponentInfo {
+    pub name: Option<String>,
+    pub material: Option<String>,
+    /// Layer thickness in metres
+    pub thickness: Option<f64>,
+    /// Share of the whole for constituents, 0 to 1
+    pub fraction: Option<f64>,
+    pub profile: Option<String>,
+}
+
+/// Material of an element with its layers, profiles or constituents
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct MaterialInfo {
+    pub name: String,
+    pub kind: MaterialKind,
+    /// Sum of the layer thicknesses in metres
+    pub total_thickness: Option<f64>,
+    pub components: Vec<MaterialComponentInfo>,
+}
+
+impl From<ifc_lite_core::ElementMaterial> for MaterialInfo {
+    fn from(material: ifc_lite_core::ElementMaterial) -> Self {
+        Self {
+            total_thickness: material.total_thickness(),
+            name: material.name,
+            kind: material.kind.into(),
+            components: material
+                .components
+                .into_iter()
+                .map(|c| MaterialComponentInfo {
+                    name: c.name,
+                    material: c.material,
+                    thickness: c.thickness,
+                    fraction: c.fraction,
+                    profile: c.profile,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Totals of the elements sharing a type, storey or material
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct QuantityGroupInfo {
+    pub key: String,
+    pub count: u32,
+    pub volume: f64,
+    pub footprint_area: f64,
+    pub surface_area: f64,
+    pub length: f64,
+    pub estimated_count: u32,
+}
+
+/// Quantity takeoff of the loaded model
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct QuantityReportInfo {
+    pub elements: Vec<ElementQuantityInfo>,
+    pub by_type: Vec<QuantityGroupInfo>,
+    pub by_storey: Vec<QuantityGroupInfo>,
+    pub by_material: Vec<QuantityGroupInfo>,
+    /// Volumes per single material, layers and constituents split
+    pub by_base_material: Vec<QuantityGroupInfo>,
+}
+
+impl From<ifc_lite_core::QuantityGroup> for QuantityGroupInfo {
+    fn from(group: ifc_lite_core::QuantityGroup) -> Self {
+        Self {
+            key: group.key,
+            count: group.count as u32,
+            volume: group.volume,
+            footprint_area: group.footprint_area,
+            surface_area: group.surface_area,
+            length: group.length,
+            estimated_count: group.estimated_count as u32,
+        }
+    }
+}
+
+impl From<ifc_lite_core::QuantityReport> for QuantityReportInfo {
+    fn from(report: ifc_lite_core::QuantityReport) -> Self {
+        let groups = |groups: Vec<ifc_lite_core::QuantityGroup>| {
+            groups.into_iter().map(QuantityGroupInfo::from).collect()
+        };
+        Self {
+            elements: report
+                .elements
+                .into_iter()
+                .map(|e| ElementQuantityInfo {
+                    entity_id: e.entity_id as u64,
+                    global_id: e.global_id,
+                    entity_type: e.ifc_type,
+                    name: e.name,
+                    storey: e.storey,
+                    material: e.material,
+                    volume: e.measures.volume,
+                    footprint_area: e.measures.footprint_area,
+                    surface_area: e.measures.surface_area,
+                    length: e.measures.length,
+                    estimated: e.estimated,
+                })
+                .collect(),
+            by_type: groups(report.by_type),
+            by_storey: groups(report.by_storey),
+            by_material: groups(report.by_material),
+            by_base_material: groups(report.by_base_material),
+        }
+    }
+}
+
+/// Stage of [`IfcScene::load_string_with_progress`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum LoadPhase {
+    /// Locating entities in the file
+    Index,
+    /// Reading projects, sites, buildings, storeys and their relationships
+    Spatial,
+    /// Tessellating element geometry
+    Geometry,
+    /// Building the spatial tree and storing the scene
+    Complete,
+}
+
+impl From<ifc_lite_engine::BuildPhase> for LoadPhase {
+    fn from(phase: ifc_lite_engine::BuildPhase) -> Self {
+        use ifc_lite_engine::BuildPhase;
+        match phase {
+            BuildPhase::Index => Self::Index,
+            BuildPhase::Spatial => Self::Spatial,
+            BuildPhase::Geometry => Self::Geometry,
+            BuildPhase::Complete => Self::Complete,
+        }
+    }
+}
+
+/// How geometry is tessellated and which elements get any, for
+/// [`IfcScene::load_string_with_options`]
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct GeometryOptions {
+    /// Maximum distance between a tessellated curve and the true curve, in metres
+    pub deflection: f64,
+    /// Upper bound on the segments of a full circle
+    pub max_segments: u32,
+    pub skip_openings: bool,
+    pub skip_spaces: bool,
+    /// Elements whose bounding box diagonal is shorter (metres) get no mesh
+    pub min_feature_size: f64,
+}
+
+impl From<ifc_lite_geometry::GeometryOptions> for GeometryOptions {
+    fn from(options: ifc_lite_geometry::GeometryOptions) -> Self {
+        Self {
+            deflection: options.deflection,
+            max_segments: options.max_segments as u32,
+            skip_openings: options.skip_openings,
+            skip_spaces: options.skip_spaces,
+            min_feature_size: options.min_feature_size,
+        }
+    }
+}
+
+impl From<GeometryOptions> for ifc_lite_geometry::GeometryOptions {
+    fn from(options: GeometryOptions) -> Self {
+        Self {
+            deflection: options.deflection,
+            max_segments: options.max_segments as usize,
+            skip_openings: options.skip_openings,
+            skip_spaces: options.skip_spaces,
+            min_feature_size: options.min_feature_size,
+        }
+    }
+}
+
+/// Receives load progress from [`IfcScene::load_string_with_progress`]
+///
+/// Called on the loading thread; implementations should hand the update to
+/// their UI thread.
+#[uniffi::export(callback_interface)]
+pub trait LoadProgressListener: Send + Sync {
+    /// `percent` runs from 0 to 100 over the whole load and never decreases
+    fn on_progress(&self, phase: LoadPhase, percent: f32);
+}
+
+/// Receives viewer events from [`IfcScene::set_observer`], so UIs need not
+/// poll [`IfcScene::get_selection`]
+///
+/// Called on the thread that made the change once it is made, so the
+/// scene can be read back; implementations should hand the update to their
+/// UI thread.
+#[uniffi::export(callback_interface)]
+pub trait SceneObserver: Send + Sync {
+    fn on_selection_changed(&self, selection: SelectionState);
+    /// `None` when the pointer left the model
+    fn on_hover_changed(&self, entity_id: Option<u64>);
+    /// Hidden, isolated, storey filter or space visibility changed
+    fn on_visibility_changed(&self, visibility: VisibilityState);
+    /// Progress of every load, as for [`LoadProgressListener::on_progress`]
+    fn on_load_progress(&self, phase: LoadPhase, percent: f32);
+}
+
+/// Batch meshes into an opaque and a transparent buffer of world space,
+/// Y-up vertices, see [`IfcScene::get_batched_meshes`]
+fn batch_meshes<'a>(meshes: impl IntoIterator<Item = &'a MeshData>) -> Vec<BatchedMeshData> {
+    // Separate opaque and transparent
+    let mut opaque_vertices: Vec<f32> = Vec::new();
+    let mut opaque_indices: Vec<u32> = Vec::new();
+    let mut transparent_vertices: Vec<f32> = Vec::new();
+    let mut transparent_indices: Vec<u32> = Vec::new();
+
+    for mesh in meshes {
+        let is_transparent = (mesh.color.len() >= 4 && mesh.color[3] < 1.0)
+            || mesh
+                .vertex_colors
+                .chunks_exact(4)
+                .any(|c| (0.0..1.0).contains(&c[3]));
+        let (vertices, indices) = if is_transparent {
+            (&mut transparent_vertices, &mut transparent_indices)
+        } else {
+            (&mut opaque_vertices, &mut opaque_indices)
+        };
+
+        let vertex_offset = (vertices.len() / 10) as u32;
+        let vertex_count = mesh.positions.len() / 3;
+
+        // Get transform matrix
+        let transform = if mesh.transform.len() == 16 {
+            nalgebra::Matrix4::from_column_slice(&mesh.transform)
+        } else {
+            nalgebra::Matrix4::identity()
+        };
+
+        // Get color (RGBA)
+        let color = if mesh.color.len() >= 4 {
+            [mesh.color[0], mesh.color[1], mesh.color[2], mesh.color[3]]
+        } else if mesh.color.len() >= 3 {
+            [mesh.color[0], mesh.color[1], mesh.color[2], 1.0]
+        } else {
+            [0.8, 0.8, 0.8, 1.0]
+        };
+
+        // Add vertices with transform applied
+        for i in 0..vertex_count {
+            let idx = i * 3;
+
+            // Position (IFC Z-up to Y-up)
+            let local_pos = nalgebra::Point3::new(
+                mesh.positions[idx],
+                mesh.positions.get(idx + 2).copied().unwrap_or(0.0), // Z -> Y
+                -mesh.positions.get(idx + 1).copied().unwrap_or(0.0), // -Y -> Z
+            );
+            let world_pos = transform.transform_point(&local_pos);
+
+            // Normal (IFC Z-up to Y-up)
+            let local_normal = if mesh.normals.len() > idx + 2 {
+                nalgebra::Vector3::new(
+                    mesh.normals[idx],
+                    mesh.normals[idx + 2],  // Z -> Y
+                    -mesh.normals[idx + 1], // -Y -> Z
+                )
+            } else {
+                nalgebra::Vector3::new(0.0, 1.0, 0.0)
+            };
+            let world_normal = transform.fixed_view::<3, 3>(0, 0).into_owned() * local_normal;
+
+            // Interleaved: [x, y, z, nx, ny, nz, r, g, b, a]
+            vertices.push(world_pos.x);
+            vertices.push(world_pos.y);
+            vertices.push(world_pos.z);
+            vertices.push(world_normal.x);
+            vertices.push(world_normal.y);
+            vertices.push(world_normal.z);
+            match mesh.vertex_colors.get(i * 4..i * 4 + 4) {
+                Some(vertex_color) if vertex_color[3] >= 0.0 => {
+                    vertices.extend_from_slice(vertex_color)
+                }
+                _ => vertices.extend_from_slice(&color),
+            }
+        }
+
+        // Add indices with offset
+        for idx in &mesh.indices {
+            indices.push(idx + vertex_offset);
+        }
+    }
+
+    let mut result = Vec::new();
+
+    if !opaque_vertices.is_empty() {
+        result.push(BatchedMeshData {
+            vertex_count: (opaque_vertices.len() / 10) as u32,
+            triangle_count: (opaque_indices.len() / 3) as u32,
+            vertices: opaque_vertices,
+            indices: opaque_indices,
+            is_transparent: false,
+        });
+    }
+
+    if !transparent_vertices.is_empty() {
+        result.push(BatchedMeshData {
+            vertex_count: (transparent_vertices.len() / 10) as u32,
+            triangle_count: (transparent_indices.len() / 3) as u32,
+            vertices: transparent_vertices,
+            indices: transparent_indices,
+            is_transparent: true,
+        });
+    }
+
+    result
+}
+
 /// Internal scene data
 #[derive(Default)]
 struct SceneData {
     meshes: Vec<MeshData>,
+    /// Key: entity ID, Value: position in `meshes`
+    mesh_by_entity: HashMap<u64, usize>,
+    /// Entity bounds in model coordinates
+    spatial_index: SpatialIndex,
     entities: Vec<EntityInfo>,
+    /// IfcRelDefinesByType assignments
+    type_assignments: TypeAssignments,
+    /// IfcRelVoidsElement and IfcRelFillsElement links
+    void_index: VoidIndex,
+    /// Systems and zones with their members
+    systems: SystemAssignments,
+    /// Map conversion for geometry in metres
+    georef: Option<GeoReference>,
+    /// IfcSite location, elevation in metres
+    site_location: Option<SiteLocation>,
+    /// Place the scene in map coordinates, see `get_scene_transform`
+    map_conversion_applied: bool,
+    /// Taken off model coordinates (metres) before the f32 cast
+    origin_shift: [f64; 3],
+    ids_by_global_id: HashMap<String, u64>,
     spatial_tree: Option<SpatialNode>,
     bounds: Option<SceneBounds>,
 
@@ -222,18 +1149,33 @@ struct SceneData {
     hidden_ids: HashSet<u64>,
     isolated_ids: Option<HashSet<u64>>,
     storey_filter: Option<String>,
+    /// Space mode: IfcSpace volumes are hidden unless it is on
+    show_spaces: bool,
+    /// Storeys whose spaces stay hidden in space mode
+    hidden_space_storeys: HashSet<String>,
     camera: CameraState,
-    section_plane: SectionPlane,
+    camera_path: Vec<CameraKeyframe>,
+    section_planes: Vec<SectionPlane>,
+    section_box: SectionBox,
+    bcf_topics: Vec<ifc_lite_core::BcfTopic>,
 
     // Original content for property lookups
-    #[allow(dead_code)]
     content: Option<String>,
+    content_hash: Option<String>,
+    /// Schema from the FILE_SCHEMA header, if recognized
+    schema: Option<SchemaVersion>,
+    /// Problems recorded by the last load
+    diagnostics: DiagnosticsReport,
+    /// Timings of the last load
+    load_profile: Option<ifc_lite_engine::LoadProfile>,
 }
 
 /// Main IFC Scene interface - thread-safe
 #[derive(uniffi::Object)]
 pub struct IfcScene {
     data: Arc<RwLock<SceneData>>,
+    /// Kept apart from `data` so observers can read the scene back
+    observer: RwLock<Option<Box<dyn SceneObserver>>>,
 }
 
 #[uniffi::export]
@@ -243,9 +1185,21 @@ impl IfcScene {
     pub fn new() -> Self {
         Self {
             data: Arc::new(RwLock::new(SceneData::default())),
+            observer: RwLock::new(None),
         }
     }
 
+    /// Push selection, hover, visibility and load events to `observer`,
+    /// replacing any previous one
+    pub fn set_observer(&self, observer: Box<dyn SceneObserver>) {
+        *self.observer.write() = Some(observer);
+    }
+
+    /// Stop pushing events
+    pub fn clear_observer(&self) {
+        *self.observer.write() = None;
+    }
+
     /// Load IFC from file path (native only)
     pub fn load_file(&self, path: String) -> Result<LoadResult, IfcError> {
         let content = std::fs::read_to_string(&path)?;
@@ -262,45 +1216,135 @@ impl IfcScene {
 
     /// Load IFC from string content
     pub fn load_string(&self, content: String) -> Result<LoadResult, IfcError> {
-        let start = std::time::Instant::now();
-
-        // Parse and process the IFC content
-        let (meshes, entities, spatial_tree, bounds) = process_ifc_content(&content)?;
-
-        let load_time_ms = start.elapsed().as_millis() as u64;
+        self.load_content(content, Default::default(), &mut |_, _| {})
+    }
 
-        // Update scene data
-        {
-            let mut data = self.data.write();
-            data.meshes = meshes.clone();
-            data.entities = entities.clone();
-            data.spatial_tree = spatial_tree.clone();
-            data.bounds = bounds.clone();
-            data.content = Some(content);
+    /// Load IFC from string content with custom tessellation and filters
+    pub fn load_string_with_options(
+        &self,
+        content: String,
+        options: GeometryOptions,
+    ) -> Result<LoadResult, IfcError> {
+        self.load_content(content, options.into(), &mut |_, _| {})
+    }
 
-            // Reset state
-            data.selected_ids.clear();
-            data.hovered_id = None;
-            data.hidden_ids.clear();
-            data.isolated_ids = None;
-            data.storey_filter = None;
-        }
+    /// Load IFC from file path with custom tessellation and filters (native only)
+    pub fn load_file_with_options(
+        &self,
+        path: String,
+        options: GeometryOptions,
+    ) -> Result<LoadResult, IfcError> {
+        let content = std::fs::read_to_string(&path)?;
+        self.load_string_with_options(content, options)
+    }
 
-        Ok(LoadResult {
-            meshes,
-            entities,
-            spatial_tree,
-            bounds,
-            load_time_ms,
+    /// Load IFC from string content, reporting progress to `listener`
+    pub fn load_string_with_progress(
+        &self,
+        content: String,
+        listener: Box<dyn LoadProgressListener>,
+    ) -> Result<LoadResult, IfcError> {
+        self.load_content(content, Default::default(), &mut |phase, percent| {
+            listener.on_progress(phase, percent)
         })
     }
 
+    /// Load IFC from file path, reporting progress to `listener` (native only)
+    pub fn load_file_with_progress(
+        &self,
+        path: String,
+        listener: Box<dyn LoadProgressListener>,
+    ) -> Result<LoadResult, IfcError> {
+        let content = std::fs::read_to_string(&path)?;
+        self.load_string_with_progress(content, listener)
+    }
+
     /// Check if scene has data
     pub fn is_loaded(&self) -> bool {
         let data = self.data.read();
         !data.entities.is_empty()
     }
 
+    /// Get content hash of the loaded file (hex), if any
+    pub fn get_content_hash(&self) -> Option<String> {
+        self.data.read().content_hash.clone()
+    }
+
+    /// Get the schema version of the loaded file, if its header names one
+    pub fn get_schema(&self) -> Option<SchemaVersion> {
+        self.data.read().schema
+    }
+
+    /// Get problems recorded while loading, sorted by line
+    ///
+    /// Malformed entities are skipped and failed geometry leaves the entity
+    /// without a mesh; both are listed here instead of failing the load.
+    /// Past the diagnostics limit problems are only counted in `dropped`.
+    pub fn get_diagnostics(&self) -> DiagnosticsReport {
+        self.data.read().diagnostics.clone()
+    }
+
+    /// Timings of the last load, or None before the first
+    ///
+    /// Covers the build stages and each geometry processor, plus batching
+    /// once [`Self::get_batched_meshes`] ran and stages recorded with
+    /// [`Self::record_load_stage`].
+    pub fn get_load_metrics(&self) -> Option<LoadMetrics> {
+        let data = self.data.read();
+        let profile = data.load_profile.as_ref()?;
+        Some(LoadMetrics {
+            total_ms: profile.total_ms(),
+            stages: profile
+                .stages
+                .iter()
+                .map(|s| StageMetric {
+                    name: s.name.clone(),
+                    ms: s.ms,
+                })
+                .collect(),
+            processors: profile
+                .processors
+                .iter()
+                .map(|p| ProcessorMetric {
+                    name: p.name.clone(),
+                    calls: p.calls,
+                    ms: p.ms,
+                })
+                .collect(),
+            content_bytes: data.content.as_ref().map_or(0, |c| c.len() as u64),
+            entity_count: data.entities.len() as u64,
+            mesh_count: data.meshes.len() as u64,
+            triangle_count: data
+                .meshes
+                .iter()
+                .map(|m| (m.indices.len() / 3) as u64)
+                .sum(),
+        })
+    }
+
+    /// Add `ms` to stage `name` of the load metrics, for stages the app
+    /// runs itself such as uploading meshes to the GPU
+    pub fn record_load_stage(&self, name: String, ms: f64) {
+        if let Some(profile) = self.data.write().load_profile.as_mut() {
+            profile.record(&name, ms);
+        }
+    }
+
+    /// Check the loaded model's structure: dangling references, duplicate
+    /// GlobalIds, missing mandatory attributes and unplaced spaces
+    ///
+    /// Empty when nothing is loaded.
+    pub fn validate(&self) -> Vec<DiagnosticInfo> {
+        let data = self.data.read();
+        let Some(content) = data.content.as_deref() else {
+            return Vec::new();
+        };
+        ifc_lite_core::validate(content)
+            .into_iter()
+            .map(DiagnosticInfo::from)
+            .collect()
+    }
+
     /// Get all entities
     pub fn get_entities(&self) -> Vec<EntityInfo> {
         self.data.read().entities.clone()
@@ -316,6 +1360,17 @@ impl IfcScene {
             .cloned()
     }
 
+    /// Get entity by IFC GlobalId, the id that stays stable across exports
+    pub fn get_entity_by_global_id(&self, global_id: String) -> Option<EntityInfo> {
+        let id = self.resolve_global_ids(vec![global_id]).pop()?;
+        self.get_entity(id)
+    }
+
+    /// Entity ids of the given GlobalIds; unknown GlobalIds are skipped
+    pub fn resolve_global_ids(&self, global_ids: Vec<String>) -> Vec<u64> {
+        self.data.read().resolve(&global_ids)
+    }
+
     /// Get spatial hierarchy tree
     pub fn get_spatial_tree(&self) -> Option<SpatialNode> {
         self.data.read().spatial_tree.clone()
@@ -346,153 +1401,258 @@ impl IfcScene {
     /// All vertices are pre-transformed to world space with vertex colors.
     /// Use this for maximum rendering performance.
     pub fn get_batched_meshes(&self) -> Vec<BatchedMeshData> {
-        let data = self.data.read();
-        let meshes = &data.meshes;
-
-        if meshes.is_empty() {
-            return Vec::new();
-        }
+        let start = std::time::Instant::now();
+        let batches = batch_meshes(&self.data.read().meshes);
+        self.record_load_stage(
+            ifc_lite_engine::profile::stage::BATCHING.to_string(),
+            start.elapsed().as_secs_f64() * 1000.0,
+        );
+        batches
+    }
 
-        // Separate opaque and transparent
-        let mut opaque_vertices: Vec<f32> = Vec::new();
-        let mut opaque_indices: Vec<u32> = Vec::new();
-        let mut transparent_vertices: Vec<f32> = Vec::new();
-        let mut transparent_indices: Vec<u32> = Vec::new();
+    /// Get properties for entity
+    pub fn get_properties(&self, entity_id: u64) -> Vec<PropertySet> {
+        let data = self.data.read();
+        let content = match &data.content {
+            Some(c) => c,
+            None => return Vec::new(),
+        };
 
-        for mesh in meshes {
-            let is_transparent = mesh.color.len() >= 4 && mesh.color[3] < 1.0;
-            let (vertices, indices) = if is_transparent {
-                (&mut transparent_vertices, &mut transparent_indices)
-            } else {
-                (&mut opaque_vertices, &mut opaque_indices)
-            };
+        extract_properties(content, entity_id as u32)
+    }
 
-            let vertex_offset = (vertices.len() / 10) as u32;
-            let vertex_count = mesh.positions.len() / 3;
+    /// Material of an element, via IfcRelAssociatesMaterial, with its layer
+    /// build-up, profiles or constituents
+    pub fn get_materials(&self, entity_id: u64) -> Option<MaterialInfo> {
+        let data = self.data.read();
+        let content = data.content.as_ref()?;
+        let mut decoder =
+            EntityDecoder::with_index(content, ifc_lite_core::build_entity_index(content));
+        ifc_lite_core::element_materials(content, &mut decoder)
+            .remove(&(entity_id as u32))
+            .map(MaterialInfo::from)
+    }
 
-            // Get transform matrix
-            let transform = if mesh.transform.len() == 16 {
-                nalgebra::Matrix4::from_column_slice(&mesh.transform)
-            } else {
-                nalgebra::Matrix4::identity()
-            };
+    /// Type object of an occurrence, via IfcRelDefinesByType
+    pub fn get_type_of(&self, entity_id: u64) -> Option<TypeInfo> {
+        self.data
+            .read()
+            .type_assignments
+            .type_of(entity_id as u32)
+            .map(TypeInfo::from)
+    }
 
-            // Get color (RGBA)
-            let color = if mesh.color.len() >= 4 {
-                [mesh.color[0], mesh.color[1], mesh.color[2], mesh.color[3]]
-            } else if mesh.color.len() >= 3 {
-                [mesh.color[0], mesh.color[1], mesh.color[2], 1.0]
-            } else {
-                [0.8, 0.8, 0.8, 1.0]
-            };
+    /// Occurrences of a type object, in id order
+    pub fn get_instances_of_type(&self, type_id: u64) -> Vec<u64> {
+        self.data
+            .read()
+            .type_assignments
+            .instances_of(type_id as u32)
+            .iter()
+            .map(|&id| id as u64)
+            .collect()
+    }
 
-            // Add vertices with transform applied
-            for i in 0..vertex_count {
-                let idx = i * 3;
+    /// Openings cut into an element, via IfcRelVoidsElement
+    pub fn get_openings(&self, element_id: u64) -> Vec<u64> {
+        self.data
+            .read()
+            .void_index
+            .get_voids(element_id as u32)
+            .iter()
+            .map(|&id| id as u64)
+            .collect()
+    }
 
-                // Position (IFC Z-up to Y-up)
-                let local_pos = nalgebra::Point3::new(
-                    mesh.positions[idx],
-                    mesh.positions.get(idx + 2).copied().unwrap_or(0.0), // Z -> Y
-                    -mesh.positions.get(idx + 1).copied().unwrap_or(0.0), // -Y -> Z
-                );
-                let world_pos = transform.transform_point(&local_pos);
-
-                // Normal (IFC Z-up to Y-up)
-                let local_normal = if mesh.normals.len() > idx + 2 {
-                    nalgebra::Vector3::new(
-                        mesh.normals[idx],
-                        mesh.normals[idx + 2],  // Z -> Y
-                        -mesh.normals[idx + 1], // -Y -> Z
-                    )
-                } else {
-                    nalgebra::Vector3::new(0.0, 1.0, 0.0)
-                };
-                let world_normal = transform.fixed_view::<3, 3>(0, 0).into_owned() * local_normal;
-
-                // Interleaved: [x, y, z, nx, ny, nz, r, g, b, a]
-                vertices.push(world_pos.x);
-                vertices.push(world_pos.y);
-                vertices.push(world_pos.z);
-                vertices.push(world_normal.x);
-                vertices.push(world_normal.y);
-                vertices.push(world_normal.z);
-                vertices.push(color[0]);
-                vertices.push(color[1]);
-                vertices.push(color[2]);
-                vertices.push(color[3]);
-            }
+    /// Door, window or other element filling an opening, via
+    /// IfcRelFillsElement
+    pub fn get_filling(&self, opening_id: u64) -> Option<u64> {
+        self.data
+            .read()
+            .void_index
+            .get_fillings(opening_id as u32)
+            .first()
+            .map(|&id| id as u64)
+    }
 
-            // Add indices with offset
-            for idx in &mesh.indices {
-                indices.push(idx + vertex_offset);
-            }
-        }
+    /// Systems and zones of the model, sorted by name
+    pub fn get_systems(&self) -> Vec<SystemInfo> {
+        let data = self.data.read();
+        data.systems
+            .systems()
+            .iter()
+            .map(|system| SystemInfo {
+                id: system.id as u64,
+                entity_type: system.entity_type.clone(),
+                name: system.name.clone(),
+                member_count: data.systems.members(system.id).len() as u32,
+            })
+            .collect()
+    }
 
-        let mut result = Vec::new();
+    /// Members of a system or zone, in id order
+    pub fn get_members(&self, system_id: u64) -> Vec<u64> {
+        self.data
+            .read()
+            .systems
+            .members(system_id as u32)
+            .iter()
+            .map(|&id| id as u64)
+            .collect()
+    }
 
-        if !opaque_vertices.is_empty() {
-            result.push(BatchedMeshData {
-                vertex_count: (opaque_vertices.len() / 10) as u32,
-                triangle_count: (opaque_indices.len() / 3) as u32,
-                vertices: opaque_vertices,
-                indices: opaque_indices,
-                is_transparent: false,
-            });
+    /// Georeferencing of the model, if it has a map conversion or a site
+    /// location
+    pub fn get_geolocation(&self) -> Option<GeolocationInfo> {
+        let data = self.data.read();
+        if data.georef.is_none() && data.site_location.is_none() {
+            return None;
         }
+        let georef = data.georef.clone().unwrap_or_default();
+        let site = data.site_location;
+        Some(GeolocationInfo {
+            epsg_code: georef.epsg_code(),
+            rotation: georef.rotation(),
+            crs_name: georef.crs_name,
+            geodetic_datum: georef.geodetic_datum,
+            vertical_datum: georef.vertical_datum,
+            map_projection: georef.map_projection,
+            eastings: georef.eastings,
+            northings: georef.northings,
+            orthogonal_height: georef.orthogonal_height,
+            scale: georef.scale,
+            latitude: site.map(|s| s.latitude),
+            longitude: site.map(|s| s.longitude),
+            elevation: site.and_then(|s| s.elevation),
+        })
+    }
 
-        if !transparent_vertices.is_empty() {
-            result.push(BatchedMeshData {
-                vertex_count: (transparent_vertices.len() / 10) as u32,
-                triangle_count: (transparent_indices.len() / 3) as u32,
-                vertices: transparent_vertices,
-                indices: transparent_indices,
-                is_transparent: true,
-            });
-        }
+    /// Place the scene in map coordinates instead of the local engineering
+    /// system, e.g. to align it with survey data or a web map
+    pub fn set_map_conversion_applied(&self, applied: bool) {
+        self.data.write().map_conversion_applied = applied;
+    }
 
-        result
+    pub fn is_map_conversion_applied(&self) -> bool {
+        self.data.read().map_conversion_applied
     }
 
-    /// Get properties for entity
-    pub fn get_properties(&self, entity_id: u64) -> Vec<PropertySet> {
+    /// Transform for the scene root as a column-major 4x4 matrix
+    ///
+    /// The map conversion, including the origin shift, while it is applied
+    /// and the model has one; the identity otherwise. Kept in f64 since map
+    /// offsets are far beyond f32 precision.
+    pub fn get_scene_transform(&self) -> Vec<f64> {
         let data = self.data.read();
-        let content = match &data.content {
-            Some(c) => c,
-            None => return Vec::new(),
+        let (Some(georef), true) = (&data.georef, data.map_conversion_applied) else {
+            return GeoReference::default().to_matrix().to_vec();
         };
+        let [x, y, z] = data.origin_shift;
+        let (e, n, h) = georef.local_to_map(x, y, z);
+        let mut matrix = georef.to_matrix();
+        matrix[12..15].copy_from_slice(&[e, n, h]);
+        matrix.to_vec()
+    }
 
-        extract_properties(content, entity_id as u32)
+    /// Offset `[x, y, z]` in metres taken off the model coordinates of far-off
+    /// models so mesh positions stay precise; zero for models near the origin
+    pub fn get_origin_shift(&self) -> Vec<f64> {
+        self.data.read().origin_shift.to_vec()
+    }
+
+    /// Original model coordinates in metres of a scene position `[x, y, z]`,
+    /// e.g. a picked or measured point
+    pub fn to_model_coordinates(&self, point: Vec<f32>) -> Vec<f64> {
+        let shift = self.data.read().origin_shift;
+        point
+            .iter()
+            .zip(shift)
+            .map(|(&p, s)| p as f64 + s)
+            .collect()
+    }
+
+    /// Find entities by type and property predicates
+    ///
+    /// Example: `IfcWall[Pset_WallCommon.FireRating=F90]`
+    pub fn query(&self, expr: String) -> Result<Vec<u64>, IfcError> {
+        let query = ifc_lite_core::Query::parse(&expr)
+            .map_err(|e| IfcError::QueryError { msg: e.to_string() })?;
+        let data = self.data.read();
+        let content = data.content.as_ref().ok_or(IfcError::NotLoaded)?;
+        Ok(query
+            .execute(content)
+            .into_iter()
+            .map(|id| id as u64)
+            .collect())
     }
 
     // Selection methods
     pub fn select(&self, entity_id: u64) {
-        let mut data = self.data.write();
-        data.selected_ids.clear();
-        data.selected_ids.insert(entity_id);
+        self.update_selection(|data| {
+            data.selected_ids.clear();
+            data.selected_ids.insert(entity_id);
+        });
     }
 
     pub fn add_to_selection(&self, entity_id: u64) {
-        self.data.write().selected_ids.insert(entity_id);
+        self.update_selection(|data| {
+            data.selected_ids.insert(entity_id);
+        });
     }
 
     pub fn remove_from_selection(&self, entity_id: u64) {
-        self.data.write().selected_ids.remove(&entity_id);
+        self.update_selection(|data| {
+            data.selected_ids.remove(&entity_id);
+        });
     }
 
     pub fn clear_selection(&self) {
-        self.data.write().selected_ids.clear();
+        self.update_selection(|data| data.selected_ids.clear());
+    }
+
+    /// Replace the selection, e.g. with the result of a box selection
+    pub fn set_selection(&self, entity_ids: Vec<u64>) {
+        self.update_selection(|data| data.selected_ids = entity_ids.into_iter().collect());
     }
 
     pub fn toggle_selection(&self, entity_id: u64) {
-        let mut data = self.data.write();
-        if data.selected_ids.contains(&entity_id) {
-            data.selected_ids.remove(&entity_id);
-        } else {
-            data.selected_ids.insert(entity_id);
+        self.update_selection(|data| {
+            if data.selected_ids.contains(&entity_id) {
+                data.selected_ids.remove(&entity_id);
+            } else {
+                data.selected_ids.insert(entity_id);
+            }
+        });
+    }
+
+    /// Replace the selection with the entities of the given GlobalIds
+    pub fn set_selection_by_global_ids(&self, global_ids: Vec<String>) {
+        self.update_selection(|data| {
+            data.selected_ids = data.resolve(&global_ids).into_iter().collect();
+        });
+    }
+
+    /// Entity under the pointer, `None` when it is over no entity
+    pub fn set_hovered(&self, entity_id: Option<u64>) {
+        let changed = {
+            let mut data = self.data.write();
+            std::mem::replace(&mut data.hovered_id, entity_id) != entity_id
+        };
+        if changed {
+            self.notify(|observer| observer.on_hover_changed(entity_id));
         }
     }
 
+    /// GlobalIds of the selected entities that have one
+    pub fn get_selected_global_ids(&self) -> Vec<String> {
+        let data = self.data.read();
+        data.entities
+            .iter()
+            .filter(|e| data.selected_ids.contains(&e.id))
+            .filter_map(|e| e.global_id.clone())
+            .collect()
+    }
+
     pub fn get_selection(&self) -> SelectionState {
         let data = self.data.read();
         SelectionState {
@@ -503,33 +1663,98 @@ impl IfcScene {
 
     // Visibility methods
     pub fn hide_entity(&self, entity_id: u64) {
-        self.data.write().hidden_ids.insert(entity_id);
+        self.update_visibility(|data| {
+            data.hidden_ids.insert(entity_id);
+        });
     }
 
     pub fn show_entity(&self, entity_id: u64) {
-        self.data.write().hidden_ids.remove(&entity_id);
+        self.update_visibility(|data| {
+            data.hidden_ids.remove(&entity_id);
+        });
+    }
+
+    /// Hide several entities, e.g. the members of a type group
+    pub fn hide_entities(&self, entity_ids: Vec<u64>) {
+        self.update_visibility(|data| {
+            data.hidden_ids.extend(entity_ids);
+        });
+    }
+
+    pub fn show_entities(&self, entity_ids: Vec<u64>) {
+        self.update_visibility(|data| {
+            for id in &entity_ids {
+                data.hidden_ids.remove(id);
+            }
+        });
     }
 
     pub fn isolate_entity(&self, entity_id: u64) {
-        let mut data = self.data.write();
-        let mut isolated = HashSet::new();
-        isolated.insert(entity_id);
-        data.isolated_ids = Some(isolated);
+        self.update_visibility(|data| {
+            let mut isolated = HashSet::new();
+            isolated.insert(entity_id);
+            data.isolated_ids = Some(isolated);
+        });
     }
 
     pub fn isolate_entities(&self, entity_ids: Vec<u64>) {
-        let mut data = self.data.write();
-        data.isolated_ids = Some(entity_ids.into_iter().collect());
+        self.update_visibility(|data| {
+            data.isolated_ids = Some(entity_ids.into_iter().collect());
+        });
+    }
+
+    pub fn hide_by_global_ids(&self, global_ids: Vec<String>) {
+        self.update_visibility(|data| {
+            let ids = data.resolve(&global_ids);
+            data.hidden_ids.extend(ids);
+        });
+    }
+
+    pub fn show_by_global_ids(&self, global_ids: Vec<String>) {
+        self.update_visibility(|data| {
+            for id in data.resolve(&global_ids) {
+                data.hidden_ids.remove(&id);
+            }
+        });
+    }
+
+    pub fn isolate_by_global_ids(&self, global_ids: Vec<String>) {
+        self.update_visibility(|data| {
+            data.isolated_ids = Some(data.resolve(&global_ids).into_iter().collect());
+        });
+    }
+
+    /// Hide every entity of an IFC type, e.g. "IfcWindow"
+    pub fn hide_type(&self, type_name: String) {
+        self.update_visibility(|data| {
+            let ids = data.of_type(&type_name);
+            data.hidden_ids.extend(ids);
+        });
+    }
+
+    pub fn show_type(&self, type_name: String) {
+        self.update_visibility(|data| {
+            for id in data.of_type(&type_name) {
+                data.hidden_ids.remove(&id);
+            }
+        });
+    }
+
+    pub fn isolate_type(&self, type_name: String) {
+        self.update_visibility(|data| {
+            data.isolated_ids = Some(data.of_type(&type_name).into_iter().collect());
+        });
     }
 
     pub fn show_all(&self) {
-        let mut data = self.data.write();
-        data.hidden_ids.clear();
-        data.isolated_ids = None;
+        self.update_visibility(|data| {
+            data.hidden_ids.clear();
+            data.isolated_ids = None;
+        });
     }
 
     pub fn set_storey_filter(&self, storey: Option<String>) {
-        self.data.write().storey_filter = storey;
+        self.update_visibility(|data| data.storey_filter = storey);
     }
 
     pub fn get_visibility(&self) -> VisibilityState {
@@ -545,30 +1770,7 @@ impl IfcScene {
     }
 
     pub fn is_entity_visible(&self, entity_id: u64) -> bool {
-        let data = self.data.read();
-
-        // Hidden check
-        if data.hidden_ids.contains(&entity_id) {
-            return false;
-        }
-
-        // Isolated check
-        if let Some(ref isolated) = data.isolated_ids {
-            if !isolated.contains(&entity_id) {
-                return false;
-            }
-        }
-
-        // Storey filter check
-        if let Some(ref storey_filter) = data.storey_filter {
-            if let Some(entity) = data.entities.iter().find(|e| e.id == entity_id) {
-                if entity.storey.as_ref() != Some(storey_filter) {
-                    return false;
-                }
-            }
-        }
-
-        true
+        self.data.read().is_visible(entity_id)
     }
 
     pub fn get_visible_count(&self) -> u32 {
@@ -577,6 +1779,7 @@ impl IfcScene {
             .iter()
             .filter(|e| {
                 !data.hidden_ids.contains(&e.id)
+                    && !data.is_space_hidden(e)
                     && data
                         .isolated_ids
                         .as_ref()
@@ -589,6 +1792,48 @@ impl IfcScene {
             .count() as u32
     }
 
+    // Spaces
+    /// Draw IfcSpace volumes; they are hidden by default
+    pub fn show_spaces(&self, show: bool) {
+        self.update_visibility(|data| data.show_spaces = show);
+    }
+
+    pub fn is_showing_spaces(&self) -> bool {
+        self.data.read().show_spaces
+    }
+
+    /// Show or hide the spaces of one storey while space mode is on
+    pub fn set_storey_spaces_visible(&self, storey: String, visible: bool) {
+        self.update_visibility(|data| {
+            if visible {
+                data.hidden_space_storeys.remove(&storey);
+            } else {
+                data.hidden_space_storeys.insert(storey);
+            }
+        });
+    }
+
+    /// Spaces with geometry and where to label them
+    pub fn get_spaces(&self) -> Vec<SpaceInfo> {
+        let data = self.data.read();
+        data.entities
+            .iter()
+            .filter(|e| e.entity_type.eq_ignore_ascii_case("IFCSPACE"))
+            .filter_map(|e| {
+                let entity_box = data.spatial_index.entity_box(e.id)?;
+                Some(SpaceInfo {
+                    entity_id: e.id,
+                    name: e.name.clone(),
+                    storey: e.storey.clone(),
+                    centroid: (0..3)
+                        .map(|i| (entity_box.min[i] + entity_box.max[i]) * 0.5)
+                        .collect(),
+                    visible: !data.is_space_hidden(e) && !data.hidden_ids.contains(&e.id),
+                })
+            })
+            .collect()
+    }
+
     // Camera
     pub fn set_camera_state(&self, state: CameraState) {
         self.data.write().camera = state;
@@ -598,885 +1843,1744 @@ impl IfcScene {
         self.data.read().camera.clone()
     }
 
-    // Section plane
-    pub fn set_section_plane(&self, plane: SectionPlane) {
-        self.data.write().section_plane = plane;
+    /// Append the current camera to the camera path, reached `duration`
+    /// seconds after the previous keyframe; returns the number of keyframes
+    pub fn add_keyframe(&self, duration: f32) -> u32 {
+        let mut data = self.data.write();
+        let camera = data.camera.clone();
+        data.camera_path.push(CameraKeyframe {
+            camera,
+            duration: duration.max(0.0),
+        });
+        data.camera_path.len() as u32
     }
 
-    pub fn get_section_plane(&self) -> SectionPlane {
-        self.data.read().section_plane.clone()
+    pub fn get_keyframes(&self) -> Vec<CameraKeyframe> {
+        self.data.read().camera_path.clone()
     }
 
-    /// Clear all scene data
-    pub fn clear(&self) {
-        *self.data.write() = SceneData::default();
+    pub fn clear_keyframes(&self) {
+        self.data.write().camera_path.clear();
     }
-}
 
-impl Default for IfcScene {
-    fn default() -> Self {
-        Self::new()
+    /// Camera path as JSON, the format the viewers play
+    pub fn export_path_json(&self) -> String {
+        let path = CameraPathJson {
+            keyframes: self
+                .data
+                .read()
+                .camera_path
+                .iter()
+                .map(Into::into)
+                .collect(),
+        };
+        serde_json::to_string(&path).unwrap_or_default()
     }
-}
 
-/// Spatial structure entity info (internal)
-struct SpatialInfo {
-    name: String,
-    entity_type: String,
-    elevation: Option<f32>,
-}
+    /// Replace the camera path with one exported by [`Self::export_path_json`];
+    /// returns the number of keyframes
+    pub fn import_path_json(&self, json: String) -> Result<u32, IfcError> {
+        let path: CameraPathJson =
+            serde_json::from_str(&json).map_err(|e| IfcError::ParseError {
+                msg: format!("Invalid camera path: {}", e),
+            })?;
+        let mut data = self.data.write();
+        data.camera_path = path.keyframes.into_iter().map(Into::into).collect();
+        Ok(data.camera_path.len() as u32)
+    }
 
-/// Result type for processed IFC content
-type ProcessedIfcContent = (
-    Vec<MeshData>,
-    Vec<EntityInfo>,
-    Option<SpatialNode>,
-    Option<SceneBounds>,
-);
+    /// Switch the camera between perspective and orthographic projection
+    pub fn set_projection(&self, orthographic: bool) {
+        self.data.write().camera.orthographic = orthographic;
+    }
 
-/// Process IFC content and extract meshes, entities, and spatial tree
-fn process_ifc_content(content: &str) -> Result<ProcessedIfcContent, IfcError> {
-    use ifc_lite_core::{build_entity_index, EntityDecoder, EntityScanner};
-    use ifc_lite_geometry::GeometryRouter;
-    use std::collections::HashMap;
+    /// Turn the camera to a standard view, keeping target and distance
+    pub fn set_standard_view(&self, view: StandardView) {
+        let (azimuth, elevation) = view.angles();
+        let camera = &mut self.data.write().camera;
+        camera.azimuth = azimuth;
+        camera.elevation = elevation;
+    }
 
-    // Build entity index for O(1) lookups
-    let index = build_entity_index(content);
+    // Section planes
+    /// Replace all section planes with a single one
+    pub fn set_section_plane(&self, plane: SectionPlane) {
+        self.data.write().section_planes = vec![plane];
+    }
 
-    // Create decoder with pre-built index
-    let mut decoder = EntityDecoder::with_index(content, index);
+    /// First section plane
+    pub fn get_section_plane(&self) -> SectionPlane {
+        self.data
+            .read()
+            .section_planes
+            .first()
+            .cloned()
+            .unwrap_or_default()
+    }
 
-    // ============ First Pass: Collect spatial structure ============
-    // Spatial entities: Project, Site, Building, Storey, Space
-    let mut spatial_entities: HashMap<u32, SpatialInfo> = HashMap::new();
-    // IfcRelAggregates: parent -> children (for Project->Site->Building->Storey)
-    let mut aggregates: HashMap<u32, Vec<u32>> = HashMap::new();
-    // IfcRelContainedInSpatialStructure: spatial_element -> contained elements
-    let mut contained_in: HashMap<u32, Vec<u32>> = HashMap::new();
-    // Element to storey mapping
-    let mut element_to_storey: HashMap<u32, u32> = HashMap::new();
-    // Track project ID for unit extraction
-    let mut project_id: Option<u32> = None;
-
-    // Use EntityScanner for first pass to handle multiline entities
-    let mut first_scanner = EntityScanner::new(content);
-    let mut rel_count = 0;
-    let mut entity_count = 0;
-    while let Some((id, type_name, _, _)) = first_scanner.next_entity() {
-        entity_count += 1;
-        let type_upper = type_name.to_uppercase();
-
-        // Debug: count any relationship entities
-        if type_upper.contains("REL") {
-            rel_count += 1;
-            if rel_count <= 5 {
-                eprintln!(
-                    "DEBUG FFI: Found relationship entity #{}: {}",
-                    id, type_name
-                );
-            }
+    /// Replace the section planes; planes past [`MAX_SECTION_PLANES`] are dropped
+    pub fn set_section_planes(&self, mut planes: Vec<SectionPlane>) {
+        planes.truncate(MAX_SECTION_PLANES);
+        self.data.write().section_planes = planes;
+    }
+
+    pub fn get_section_planes(&self) -> Vec<SectionPlane> {
+        self.data.read().section_planes.clone()
+    }
+
+    pub fn set_section_box(&self, section_box: SectionBox) {
+        self.data.write().section_box = section_box;
+    }
+
+    pub fn get_section_box(&self) -> SectionBox {
+        self.data.read().section_box.clone()
+    }
+
+    // BCF issues
+    /// Load the topics of a `.bcfzip` file, replacing the current ones
+    pub fn load_bcf(&self, data: Vec<u8>) -> Result<Vec<BcfTopicInfo>, IfcError> {
+        let archive = ifc_lite_core::BcfArchive::read(&data)
+            .map_err(|e| IfcError::ParseError { msg: e.to_string() })?;
+        let mut scene = self.data.write();
+        scene.bcf_topics = archive.topics;
+        Ok(scene.bcf_infos())
+    }
+
+    pub fn get_bcf_topics(&self) -> Vec<BcfTopicInfo> {
+        self.data.read().bcf_infos()
+    }
+
+    /// Create a topic from the current selection and visibility
+    ///
+    /// Entities without a GlobalId cannot be referenced in BCF and are left
+    /// out. The viewpoint has no camera; `snapshot_png` shows the view.
+    pub fn create_bcf_topic(
+        &self,
+        title: String,
+        description: Option<String>,
+        author: String,
+        snapshot_png: Option<Vec<u8>>,
+    ) -> Result<BcfTopicInfo, IfcError> {
+        let mut data = self.data.write();
+        if data.entities.is_empty() {
+            return Err(IfcError::NotLoaded);
         }
 
-        // Debug: check for specific IDs we know are IFCRELAGGREGATES
-        if id == 38331 || id == 38275 || id == 38276 {
-            eprintln!(
-                "DEBUG FFI: Entity #{} has type '{}' (len={}, bytes={:?})",
-                id,
-                type_name,
-                type_name.len(),
-                type_name.as_bytes()
-            );
+        let mut viewpoint = ifc_lite_core::BcfViewpoint::new(random_guid());
+        let mut selection: Vec<String> = data
+            .entities
+            .iter()
+            .filter(|e| data.selected_ids.contains(&e.id))
+            .filter_map(|e| e.global_id.clone())
+            .collect();
+        selection.sort_unstable();
+        viewpoint.selection = selection;
+        viewpoint.exceptions = data
+            .entities
+            .iter()
+            .filter(|e| data.hidden_ids.contains(&e.id))
+            .filter_map(|e| e.global_id.clone())
+            .collect();
+
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or_default();
+        let description = description.filter(|d| !d.trim().is_empty());
+        data.bcf_topics.push(ifc_lite_core::BcfTopic {
+            guid: random_guid(),
+            title,
+            description,
+            topic_type: Some("Issue".to_string()),
+            status: Some("Open".to_string()),
+            creation_date: ifc_lite_core::bcf::format_timestamp(now),
+            author,
+            comments: Vec::new(),
+            viewpoints: vec![ifc_lite_core::BcfTopicViewpoint {
+                viewpoint,
+                snapshot: snapshot_png,
+            }],
+        });
+
+        let topic = data.bcf_topics.last().expect("topic was just added");
+        Ok(data.bcf_info(topic))
+    }
+
+    /// Write the current topics as a `.bcfzip` file
+    pub fn export_bcf(&self) -> Vec<u8> {
+        ifc_lite_core::BcfArchive {
+            topics: self.data.read().bcf_topics.clone(),
         }
+        .write()
+    }
 
-        // Parse spatial structure entities
-        match type_upper.as_str() {
-            "IFCPROJECT" => {
-                project_id = Some(id);
-                if let Ok(entity) = decoder.decode_by_id(id) {
-                    let name = entity
-                        .get_string(2)
-                        .map(|s| s.to_string())
-                        .unwrap_or_else(|| "Project".to_string());
-                    spatial_entities.insert(
-                        id,
-                        SpatialInfo {
-                            name,
-                            entity_type: type_name.to_string(),
-                            elevation: None,
-                        },
-                    );
-                }
-            }
-            "IFCSITE" => {
-                if let Ok(entity) = decoder.decode_by_id(id) {
-                    let name = entity
-                        .get_string(2)
-                        .map(|s| s.to_string())
-                        .unwrap_or_else(|| "Site".to_string());
-                    spatial_entities.insert(
-                        id,
-                        SpatialInfo {
-                            name,
-                            entity_type: type_name.to_string(),
-                            elevation: None,
-                        },
-                    );
-                }
-            }
-            "IFCBUILDING" => {
-                if let Ok(entity) = decoder.decode_by_id(id) {
-                    let name = entity
-                        .get_string(2)
-                        .map(|s| s.to_string())
-                        .unwrap_or_else(|| "Building".to_string());
-                    spatial_entities.insert(
-                        id,
-                        SpatialInfo {
-                            name,
-                            entity_type: type_name.to_string(),
-                            elevation: None,
-                        },
-                    );
-                }
-            }
-            "IFCBUILDINGSTOREY" => {
-                if let Ok(entity) = decoder.decode_by_id(id) {
-                    let name = entity
-                        .get_string(2)
-                        .map(|s| s.to_string())
-                        .unwrap_or_else(|| format!("Storey #{}", id));
-                    let elevation = entity.get_float(9).map(|e| e as f32);
-                    spatial_entities.insert(
-                        id,
-                        SpatialInfo {
-                            name,
-                            entity_type: type_name.to_string(),
-                            elevation,
-                        },
-                    );
-                }
-            }
-            "IFCSPACE" => {
-                if let Ok(entity) = decoder.decode_by_id(id) {
-                    let name = entity
-                        .get_string(2)
-                        .map(|s| s.to_string())
-                        .unwrap_or_else(|| format!("Space #{}", id));
-                    spatial_entities.insert(
-                        id,
-                        SpatialInfo {
-                            name,
-                            entity_type: type_name.to_string(),
-                            elevation: None,
-                        },
-                    );
-                }
-            }
-            // Parse IfcRelAggregates for parent-child relationships
-            // Structure: (GlobalId, OwnerHistory, Name, Description, RelatingObject, RelatedObjects)
-            "IFCRELAGGREGATES" => {
-                eprintln!("DEBUG FFI: Found IFCRELAGGREGATES #{}", id);
-                if let Ok(entity) = decoder.decode_by_id(id) {
-                    let parent_id = entity.get_ref(4);
-                    let children = get_ref_list(&entity, 5);
-                    eprintln!(
-                        "DEBUG FFI:   parent={:?}, children={:?}",
-                        parent_id,
-                        children.as_ref().map(|c| c.len())
-                    );
-                    if let (Some(parent_id), Some(children)) = (parent_id, children) {
-                        aggregates.entry(parent_id).or_default().extend(children);
+    /// Volumes, areas and lengths of all elements, totalled by type, storey and material
+    ///
+    /// Elements without IfcElementQuantity get measures from their mesh.
+    pub fn get_quantity_report(&self) -> Result<QuantityReportInfo, IfcError> {
+        Ok(self.data.read().quantity_report()?.into())
+    }
+
+    /// Quantity takeoff as CSV, one row per element
+    pub fn export_quantity_csv(&self) -> Result<String, IfcError> {
+        Ok(self.data.read().quantity_report()?.to_csv())
+    }
+
+    // Spatial queries, in model coordinates (Z up) like `get_bounds`
+
+    /// Entities whose bounding boxes overlap a box
+    pub fn entities_in_box(&self, bounds: SceneBounds) -> Vec<u64> {
+        self.data.read().spatial_index.entities_in_box(
+            [bounds.min_x, bounds.min_y, bounds.min_z],
+            [bounds.max_x, bounds.max_y, bounds.max_z],
+        )
+    }
+
+    /// Visible entity whose geometry a ray hits first
+    ///
+    /// `origin` and `direction` are `[x, y, z]`.
+    pub fn nearest_entity(&self, origin: Vec<f32>, direction: Vec<f32>) -> Option<RayHit> {
+        let origin: [f32; 3] = origin.try_into().ok()?;
+        let direction: [f32; 3] = direction.try_into().ok()?;
+        let data = self.data.read();
+        let (entity_id, distance) =
+            data.spatial_index
+                .nearest_entity_by(origin, direction, |entity_id, _| {
+                    if !data.is_visible(entity_id) {
+                        return None;
                     }
-                }
+                    let mesh = &data.meshes[*data.mesh_by_entity.get(&entity_id)?];
+                    let positions = mesh.world_positions();
+                    let vertex = |i: u32| {
+                        let i = i as usize * 3;
+                        Some([
+                            *positions.get(i)?,
+                            *positions.get(i + 1)?,
+                            *positions.get(i + 2)?,
+                        ])
+                    };
+                    mesh.indices
+                        .chunks_exact(3)
+                        .filter_map(|t| {
+                            let (a, b, c) = (vertex(t[0])?, vertex(t[1])?, vertex(t[2])?);
+                            ifc_lite_geometry::bvh::ray_triangle(origin, direction, a, b, c)
+                        })
+                        .min_by(f32::total_cmp)
+                })?;
+        Some(RayHit {
+            entity_id,
+            distance,
+        })
+    }
+
+    /// Entities whose bounding boxes a plane passes through
+    ///
+    /// The plane runs through `origin` with `normal`, both `[x, y, z]`.
+    pub fn entities_intersecting_plane(&self, origin: Vec<f32>, normal: Vec<f32>) -> Vec<u64> {
+        let (Ok(origin), Ok(normal)) = (<[f32; 3]>::try_from(origin), <[f32; 3]>::try_from(normal))
+        else {
+            return Vec::new();
+        };
+        let offset = (0..3).map(|i| origin[i] * normal[i]).sum();
+        self.data
+            .read()
+            .spatial_index
+            .entities_intersecting(normal, offset)
+    }
+
+    /// Floor plan of a storey: where a horizontal plane `cut_height` above
+    /// the storey elevation cuts the visible elements
+    pub fn get_storey_plan(&self, storey: String, cut_height: f32) -> Result<StoreyPlan, IfcError> {
+        let data = self.data.read();
+        if data.content.is_none() {
+            return Err(IfcError::NotLoaded);
+        }
+        let elevation = data
+            .entities
+            .iter()
+            .filter(|e| e.storey.as_ref() == Some(&storey))
+            .find_map(|e| e.storey_elevation)
+            .ok_or_else(|| IfcError::QueryError {
+                msg: format!("Unknown storey '{}'", storey),
+            })?;
+        let cut_elevation = elevation + cut_height;
+
+        let mut ids = data
+            .spatial_index
+            .entities_intersecting([0.0, 0.0, 1.0], cut_elevation);
+        ids.sort_unstable();
+        let outlines = ids
+            .into_iter()
+            .filter(|&id| data.is_visible(id))
+            .filter_map(|id| Some((id, &data.meshes[*data.mesh_by_entity.get(&id)?])))
+            .flat_map(|(id, mesh)| {
+                ifc_lite_geometry::section_polylines(
+                    &mesh.world_positions(),
+                    &mesh.indices,
+                    cut_elevation,
+                )
+                .into_iter()
+                .map(move |polyline| PlanOutline {
+                    entity_id: id,
+                    points: polyline.points.into_iter().flatten().collect(),
+                    closed: polyline.closed,
+                })
+            })
+            .collect();
+        Ok(StoreyPlan {
+            storey,
+            elevation,
+            cut_elevation,
+            outlines,
+        })
+    }
+
+    /// Render the visible elements as seen from `camera` into a PNG of
+    /// `width` x `height` pixels
+    ///
+    /// Renders offscreen on the GPU, without a viewer or window, e.g. for
+    /// reports or BCF snapshots on a server. Sizes are clamped to 8192.
+    /// Fails with a render error when no GPU adapter is available or the
+    /// library was built without the `snapshot` feature.
+    pub fn render_snapshot(
+        &self,
+        width: u32,
+        height: u32,
+        camera: CameraState,
+    ) -> Result<Vec<u8>, IfcError> {
+        let batches = {
+            let data = self.data.read();
+            if data.content.is_none() {
+                return Err(IfcError::NotLoaded);
             }
-            // Also check IfcRelDecomposes (parent class of IfcRelAggregates in IFC2x3)
-            "IFCRELDECOMPOSES" => {
-                if let Ok(entity) = decoder.decode_by_id(id) {
-                    let parent_id = entity.get_ref(4);
-                    let children = get_ref_list(&entity, 5);
-                    if let (Some(parent_id), Some(children)) = (parent_id, children) {
-                        aggregates.entry(parent_id).or_default().extend(children);
-                    }
+            batch_meshes(data.meshes.iter().filter(|m| data.is_visible(m.entity_id)))
+        };
+        #[cfg(feature = "snapshot")]
+        {
+            snapshot::render_png(&batches, &camera, width, height)
+                .map_err(|msg| IfcError::RenderError { msg })
+        }
+        #[cfg(not(feature = "snapshot"))]
+        {
+            let _ = (batches, camera, width, height);
+            Err(IfcError::RenderError {
+                msg: "Built without the snapshot feature".to_string(),
+            })
+        }
+    }
+
+    /// Clear all scene data
+    pub fn clear(&self) {
+        *self.data.write() = SceneData::default();
+    }
+}
+
+impl SceneData {
+    fn set_meshes(&mut self, meshes: Vec<MeshData>) {
+        self.mesh_by_entity = meshes
+            .iter()
+            .enumerate()
+            .map(|(i, m)| (m.entity_id, i))
+            .collect();
+        self.spatial_index = SpatialIndex::build(meshes.iter().filter_map(|mesh| {
+            let positions = mesh.world_positions();
+            let mut points = positions.chunks_exact(3);
+            let first = points.next()?;
+            let (mut min, mut max) = (
+                [first[0], first[1], first[2]],
+                [first[0], first[1], first[2]],
+            );
+            for p in points {
+                for i in 0..3 {
+                    min[i] = min[i].min(p[i]);
+                    max[i] = max[i].max(p[i]);
                 }
             }
-            // IfcRelNests can also define hierarchy
-            "IFCRELNESTS" => {
-                if let Ok(entity) = decoder.decode_by_id(id) {
-                    let parent_id = entity.get_ref(4);
-                    let children = get_ref_list(&entity, 5);
-                    if let (Some(parent_id), Some(children)) = (parent_id, children) {
-                        aggregates.entry(parent_id).or_default().extend(children);
-                    }
-                }
+            Some(EntityBox {
+                id: mesh.entity_id,
+                min,
+                max,
+            })
+        }));
+        self.meshes = meshes;
+    }
+
+    /// Whether an entity passes the hidden, isolation and storey filters
+    fn is_visible(&self, entity_id: u64) -> bool {
+        if self.hidden_ids.contains(&entity_id) {
+            return false;
+        }
+        if let Some(ref isolated) = self.isolated_ids {
+            if !isolated.contains(&entity_id) {
+                return false;
             }
-            // Parse IfcRelContainedInSpatialStructure
-            // Structure: (GlobalId, OwnerHistory, Name, Description, RelatedElements, RelatingStructure)
-            "IFCRELCONTAINEDINSPATIALSTRUCTURE" => {
-                eprintln!("DEBUG FFI: Found IFCRELCONTAINEDINSPATIALSTRUCTURE #{}", id);
-                if let Ok(entity) = decoder.decode_by_id(id) {
-                    let structure_id = entity.get_ref(5);
-                    let elements = get_ref_list(&entity, 4);
-                    eprintln!(
-                        "DEBUG FFI:   structure_id={:?}, elements={:?}",
-                        structure_id,
-                        elements.as_ref().map(|e| e.len())
-                    );
-                    if let Some(structure_id) = structure_id {
-                        if let Some(elements) = elements {
-                            contained_in
-                                .entry(structure_id)
-                                .or_default()
-                                .extend(elements.clone());
-                            // Also track element -> storey for flat view
-                            for elem_id in elements {
-                                element_to_storey.insert(elem_id, structure_id);
-                            }
-                        }
-                    }
+        }
+        if let Some(entity) = self.entities.iter().find(|e| e.id == entity_id) {
+            if self.is_space_hidden(entity) {
+                return false;
+            }
+            if let Some(ref storey_filter) = self.storey_filter {
+                if entity.storey.as_ref() != Some(storey_filter) {
+                    return false;
                 }
             }
-            _ => {}
         }
+        true
     }
 
-    // Extract unit scale from project
-    let unit_scale = if let Some(proj_id) = project_id {
-        match ifc_lite_core::extract_length_unit_scale(&mut decoder, proj_id) {
-            Ok(scale) => scale as f32,
-            Err(_) => 1.0,
-        }
-    } else {
-        1.0
-    };
+    /// Whether `entity` is a space hidden by the space mode
+    fn is_space_hidden(&self, entity: &EntityInfo) -> bool {
+        entity.entity_type.eq_ignore_ascii_case("IFCSPACE")
+            && (!self.show_spaces
+                || entity
+                    .storey
+                    .as_ref()
+                    .is_some_and(|storey| self.hidden_space_storeys.contains(storey)))
+    }
+
+    fn set_entities(&mut self, entities: Vec<EntityInfo>) {
+        self.ids_by_global_id = entities
+            .iter()
+            .filter_map(|e| Some((e.global_id.clone()?, e.id)))
+            .collect();
+        self.entities = entities;
+    }
+
+    fn resolve(&self, global_ids: &[String]) -> Vec<u64> {
+        global_ids
+            .iter()
+            .filter_map(|guid| self.ids_by_global_id.get(guid).copied())
+            .collect()
+    }
 
-    // Apply unit scale to elevations
-    for info in spatial_entities.values_mut() {
-        if let Some(ref mut elev) = info.elevation {
-            *elev *= unit_scale;
+    /// Entities of an IFC type, matched case-insensitively
+    fn of_type(&self, type_name: &str) -> Vec<u64> {
+        self.entities
+            .iter()
+            .filter(|e| e.entity_type.eq_ignore_ascii_case(type_name))
+            .map(|e| e.id)
+            .collect()
+    }
+
+    fn bcf_info(&self, topic: &ifc_lite_core::BcfTopic) -> BcfTopicInfo {
+        let global_ids = topic.global_ids();
+        let entity_ids = self.resolve(&global_ids);
+        BcfTopicInfo {
+            guid: topic.guid.clone(),
+            title: topic.title.clone(),
+            description: topic.description.clone(),
+            topic_type: topic.topic_type.clone(),
+            status: topic.status.clone(),
+            author: topic.author.clone(),
+            creation_date: topic.creation_date.clone(),
+            comments: topic
+                .comments
+                .iter()
+                .map(|c| BcfCommentInfo {
+                    guid: c.guid.clone(),
+                    author: c.author.clone(),
+                    date: c.date.clone(),
+                    text: c.text.clone(),
+                })
+                .collect(),
+            global_ids,
+            entity_ids,
+            snapshot_png: topic.viewpoints.iter().find_map(|v| v.snapshot.clone()),
         }
     }
 
-    // ============ Second Pass: Process geometry ============
-    let router = GeometryRouter::with_units(content, &mut decoder);
-    let mut meshes = Vec::new();
-    let mut entities = Vec::new();
-    let mut scanner = EntityScanner::new(content);
+    fn bcf_infos(&self) -> Vec<BcfTopicInfo> {
+        self.bcf_topics.iter().map(|t| self.bcf_info(t)).collect()
+    }
 
-    // Bounds tracking
-    let mut min = [f32::MAX; 3];
-    let mut max = [f32::MIN; 3];
+    fn quantity_report(&self) -> Result<ifc_lite_core::QuantityReport, IfcError> {
+        let content = self.content.as_ref().ok_or(IfcError::NotLoaded)?;
+        let mut decoder =
+            EntityDecoder::with_index(content, ifc_lite_core::build_entity_index(content));
+        let mut ifc_measures = ifc_lite_core::ifc_measures(content, &mut decoder);
+        let mut materials = ifc_lite_core::element_materials(content, &mut decoder);
+        let meshes: HashMap<u64, &MeshData> =
+            self.meshes.iter().map(|m| (m.entity_id, m)).collect();
 
-    // Track which entities have geometry
-    let mut entities_with_geometry: HashSet<u64> = HashSet::new();
+        let elements = self
+            .entities
+            .iter()
+            .filter_map(|entity| {
+                let id = entity.id as u32;
+                let mut measures = ifc_measures.remove(&id).unwrap_or_default();
+                let mesh = meshes.get(&entity.id);
+                if measures.is_empty() && mesh.is_none() {
+                    return None;
+                }
+                let estimated = mesh.is_some_and(|mesh| {
+                    measures.fill_from(&ifc_lite_geometry::mesh_measures(
+                        &ifc_lite_geometry::Mesh {
+                            positions: mesh.world_positions().into_owned(),
+                            normals: Vec::new(),
+                            indices: mesh.indices.clone(),
+                            colors: Vec::new(),
+                        },
+                    ))
+                });
+                let material = materials.remove(&id);
+                Some(ifc_lite_core::ElementQuantities {
+                    entity_id: id,
+                    global_id: entity.global_id.clone(),
+                    ifc_type: entity.entity_type.clone(),
+                    name: entity.name.clone(),
+                    storey: entity.storey.clone(),
+                    material: material.as_ref().map(|m| m.name.clone()),
+                    material_shares: material
+                        .as_ref()
+                        .map(|m| m.volume_shares())
+                        .unwrap_or_default(),
+                    measures,
+                    estimated,
+                })
+            })
+            .collect();
+        Ok(ifc_lite_core::QuantityReport::new(elements))
+    }
+}
 
-    // Collect elements with geometry
-    let mut element_ids: Vec<(u32, String)> = Vec::new();
+/// Random GUID for new BCF topics and viewpoints
+fn random_guid() -> String {
+    use std::hash::{BuildHasher, Hasher};
+
+    // Each RandomState is seeded differently; mix in the time for good measure
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    let half = |salt: u64| {
+        let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+        hasher.write_u128(nanos);
+        hasher.write_u64(salt);
+        hasher.finish() as u128
+    };
+    ifc_lite_core::bcf::format_guid((half(0) << 64) | half(1))
+}
 
-    while let Some((id, type_name, _, _)) = scanner.next_entity() {
-        if ifc_lite_core::has_geometry_by_name(type_name) {
-            let ifc_type = ifc_lite_core::IfcType::from_str(type_name);
-            if !matches!(ifc_type, ifc_lite_core::IfcType::Unknown(_)) {
-                element_ids.push((id, type_name.to_string()));
-            }
+impl Default for IfcScene {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IfcScene {
+    /// Call the observer, if one is set
+    fn notify(&self, event: impl FnOnce(&dyn SceneObserver)) {
+        if let Some(observer) = self.observer.read().as_deref() {
+            event(observer);
         }
     }
 
-    // Process each element
-    for (id, type_name) in element_ids {
-        let entity = match decoder.decode_by_id(id) {
-            Ok(e) => e,
-            Err(_) => continue,
+    /// Change the selection, telling the observer if it changed
+    fn update_selection(&self, change: impl FnOnce(&mut SceneData)) {
+        let changed = {
+            let mut data = self.data.write();
+            let before = data.selected_ids.clone();
+            change(&mut data);
+            data.selected_ids != before
         };
+        if changed {
+            self.notify(|observer| observer.on_selection_changed(self.get_selection()));
+        }
+    }
 
-        // Get entity name
-        let name = entity.get_string(2).map(|s| s.to_string());
+    /// Change what is shown, telling the observer if it changed
+    fn update_visibility(&self, change: impl FnOnce(&mut SceneData)) {
+        let changed = {
+            let mut data = self.data.write();
+            let before = (
+                data.hidden_ids.clone(),
+                data.isolated_ids.clone(),
+                data.storey_filter.clone(),
+                data.show_spaces,
+                data.hidden_space_storeys.clone(),
+            );
+            change(&mut data);
+            before.0 != data.hidden_ids
+                || before.1 != data.isolated_ids
+                || before.2 != data.storey_filter
+                || before.3 != data.show_spaces
+                || before.4 != data.hidden_space_storeys
+        };
+        if changed {
+            self.notify(|observer| observer.on_visibility_changed(self.get_visibility()));
+        }
+    }
 
-        // Look up storey information
-        let (storey_name, storey_elevation) = if let Some(&storey_id) = element_to_storey.get(&id) {
-            if let Some(storey) = spatial_entities.get(&storey_id) {
-                (Some(storey.name.clone()), storey.elevation)
-            } else {
-                (None, None)
-            }
-        } else {
-            (None, None)
+    /// Parse `content` into the scene, reporting progress to `on_progress`
+    /// and the observer
+    fn load_content(
+        &self,
+        content: String,
+        options: ifc_lite_geometry::GeometryOptions,
+        on_progress: &mut dyn FnMut(LoadPhase, f32),
+    ) -> Result<LoadResult, IfcError> {
+        let on_progress = &mut |phase: LoadPhase, percent: f32| {
+            on_progress(phase, percent);
+            self.notify(|observer| observer.on_load_progress(phase, percent));
         };
+        let start = std::time::Instant::now();
+        let content_hash = ContentHash::of_str(&content).to_hex();
 
-        // Always add to entities for hierarchy (even if geometry fails)
-        entities.push(EntityInfo {
-            id: id as u64,
-            entity_type: type_name.clone(),
-            name: name.clone(),
-            global_id: None,
-            storey: storey_name,
-            storey_elevation,
-        });
+        // Parse and process the IFC content
+        let (meshes, scene) = build_scene(&content, options, on_progress);
+        let ifc_lite_engine::Scene {
+            elements,
+            tree,
+            bounds,
+            origin_shift,
+            mut diagnostics,
+            mut profile,
+            mut decoder,
+            void_index,
+            ..
+        } = scene;
+        let finish = std::time::Instant::now();
+        diagnostics.sort();
+        let entities: Vec<EntityInfo> = elements.iter().map(EntityInfo::from).collect();
+        let spatial_tree = tree.map(SpatialNode::from);
+        let bounds = bounds.map(SceneBounds::from);
+        let type_assignments = TypeAssignments::from_content(&content, &mut decoder);
+        let systems = SystemAssignments::from_content(&content, &mut decoder);
+        let (georef, site_location) = extract_geolocation(&content, &mut decoder);
+        // Release the borrow of the content, which moves into the scene data
+        drop(decoder);
+        profile.record("finish", finish.elapsed().as_secs_f64() * 1000.0);
 
-        // Process geometry
-        let mesh = match router.process_element(&entity, &mut decoder) {
-            Ok(m) => m,
-            Err(_) => continue,
-        };
+        let load_time_ms = start.elapsed().as_millis() as u64;
 
-        if mesh.is_empty() {
-            continue;
+        // Update scene data
+        {
+            let mut data = self.data.write();
+            data.set_meshes(meshes.clone());
+            data.set_entities(entities.clone());
+            data.type_assignments = type_assignments;
+            data.void_index = void_index;
+            data.systems = systems;
+            data.georef = georef;
+            data.site_location = site_location;
+            data.origin_shift = origin_shift;
+            data.spatial_tree = spatial_tree.clone();
+            data.bounds = bounds.clone();
+            data.schema = ifc_lite_core::SchemaVersion::detect(&content).map(SchemaVersion::from);
+            data.diagnostics = DiagnosticsReport {
+                dropped: diagnostics.dropped() as u64,
+                items: diagnostics.into_iter().map(DiagnosticInfo::from).collect(),
+            };
+            data.load_profile = Some(profile);
+            data.content = Some(content);
+            data.content_hash = Some(content_hash.clone());
+
+            // Reset state
+            data.selected_ids.clear();
+            data.hovered_id = None;
+            data.hidden_ids.clear();
+            data.isolated_ids = None;
+            data.storey_filter = None;
+            data.hidden_space_storeys.clear();
         }
 
-        // Track that this entity has geometry
-        entities_with_geometry.insert(id as u64);
+        on_progress(LoadPhase::Complete, 100.0);
+        // The load reset them
+        self.notify(|observer| {
+            observer.on_selection_changed(self.get_selection());
+            observer.on_hover_changed(None);
+            observer.on_visibility_changed(self.get_visibility());
+        });
+        Ok(LoadResult {
+            meshes,
+            entities,
+            spatial_tree,
+            bounds,
+            load_time_ms,
+            content_hash,
+        })
+    }
+}
+
+/// Meshes, entities, spatial tree, bounds and origin shift of a model
+#[cfg(test)]
+type ProcessedIfcContent = (
+    Vec<MeshData>,
+    Vec<EntityInfo>,
+    Option<SpatialNode>,
+    Option<SceneBounds>,
+    [f64; 3],
+);
+
+/// Process IFC content and extract meshes, entities, and spatial tree
+#[cfg(test)]
+fn process_ifc_content(content: &str) -> Result<ProcessedIfcContent, IfcError> {
+    let (meshes, scene) = build_scene(content, Default::default(), &mut |_, _| {});
+    Ok((
+        meshes,
+        scene.elements.iter().map(EntityInfo::from).collect(),
+        scene.tree.map(SpatialNode::from),
+        scene.bounds.map(SceneBounds::from),
+        scene.origin_shift,
+    ))
+}
+
+/// Map conversion and site location, converted to metres like the geometry
+fn extract_geolocation(
+    content: &str,
+    decoder: &mut EntityDecoder,
+) -> (Option<GeoReference>, Option<SiteLocation>) {
+    use ifc_lite_core::{EntityScanner, GeoRefExtractor, IfcType};
 
-        // Update bounds
-        for chunk in mesh.positions.chunks(3) {
-            if chunk.len() == 3 {
-                min[0] = min[0].min(chunk[0]);
-                min[1] = min[1].min(chunk[1]);
-                min[2] = min[2].min(chunk[2]);
-                max[0] = max[0].max(chunk[0]);
-                max[1] = max[1].max(chunk[1]);
-                max[2] = max[2].max(chunk[2]);
+    let mut length_scale = 1.0;
+    let mut entity_types = Vec::new();
+    let mut scanner = EntityScanner::new(content);
+    while let Some((id, type_name, _, _)) = scanner.next_entity() {
+        match type_name {
+            "IFCPROJECT" => length_scale = decoder.extract_unit_scale(id).unwrap_or(1.0),
+            "IFCMAPCONVERSION" | "IFCPROJECTEDCRS" | "IFCPROPERTYSET" | "IFCSITE" => {
+                entity_types.push((id, IfcType::from_str(type_name)))
             }
+            _ => {}
         }
+    }
 
-        // Get color for entity type
-        let color = get_element_color(&type_name);
+    let georef = GeoRefExtractor::extract(decoder, &entity_types)
+        .ok()
+        .flatten()
+        .map(|georef| georef.in_metres(length_scale));
+    let site_location = GeoRefExtractor::site_location(decoder, &entity_types)
+        .ok()
+        .flatten()
+        .map(|site| SiteLocation {
+            elevation: site.elevation.map(|e| e * length_scale),
+            ..site
+        });
+    (georef, site_location)
+}
 
-        // Debug first few meshes
-        if meshes.len() < 3 {
-            eprintln!(
-                "DEBUG FFI Mesh #{}: positions={}, normals={}, indices={}",
-                id,
-                mesh.positions.len(),
-                mesh.normals.len(),
-                mesh.indices.len()
-            );
-        }
+/// Collects the meshes of a scene build for the bindings
+struct MeshCollector<'p> {
+    meshes: Vec<MeshData>,
+    on_progress: &'p mut dyn FnMut(LoadPhase, f32),
+}
 
-        meshes.push(MeshData {
-            entity_id: id as u64,
-            entity_type: type_name,
-            name,
+impl ifc_lite_engine::SceneSink for MeshCollector<'_> {
+    fn progress(&mut self, phase: ifc_lite_engine::BuildPhase, percent: f32) {
+        (self.on_progress)(phase.into(), percent);
+    }
+
+    fn mesh(
+        &mut self,
+        element: &ifc_lite_engine::ElementInfo,
+        mesh: ifc_lite_geometry::Mesh,
+        color: [f32; 4],
+    ) {
+        self.meshes.push(MeshData {
+            entity_id: element.id as u64,
+            entity_type: element.entity_type.to_string(),
+            name: element.name.clone(),
             positions: mesh.positions,
             normals: mesh.normals,
             indices: mesh.indices,
             color: color.to_vec(),
-            transform: vec![
-                1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0,
-            ],
+            vertex_colors: mesh.colors,
+            transform: IDENTITY.to_vec(),
         });
     }
 
-    eprintln!("DEBUG FFI: Total meshes created: {}", meshes.len());
+    fn wants_instances(&mut self) -> bool {
+        true
+    }
 
-    // Calculate bounds
-    let bounds = if min[0] < max[0] {
-        Some(SceneBounds {
-            min_x: min[0],
-            min_y: min[1],
-            min_z: min[2],
-            max_x: max[0],
-            max_y: max[1],
-            max_z: max[2],
-        })
-    } else {
-        None
+    fn instanced(
+        &mut self,
+        element: &ifc_lite_engine::ElementInfo,
+        mut geometry: ifc_lite_geometry::ElementGeometry,
+        color: [f32; 4],
+    ) {
+        // One mesh per entity: a lone mapped item keeps its map geometry and
+        // placement, anything more is merged
+        if !geometry.mesh.is_empty() || geometry.instances.len() != 1 {
+            self.mesh(element, geometry.into_mesh(), color);
+            return;
+        }
+        let Some(instance) = geometry.instances.pop() else {
+            return;
+        };
+        let mesh = instance.mesh.as_ref().clone();
+        self.meshes.push(MeshData {
+            entity_id: element.id as u64,
+            entity_type: element.entity_type.to_string(),
+            name: element.name.clone(),
+            positions: mesh.positions,
+            normals: mesh.normals,
+            indices: mesh.indices,
+            color: color.to_vec(),
+            vertex_colors: mesh.colors,
+            transform: instance.y_up_transform().to_vec(),
+        });
+    }
+}
+
+/// Build the scene of IFC content, reporting the phase and overall percentage
+///
+/// Entities that fail to decode or mesh are recorded in the scene's
+/// diagnostics and processing continues with the rest.
+fn build_scene<'a>(
+    content: &'a str,
+    geometry: ifc_lite_geometry::GeometryOptions,
+    on_progress: &mut dyn FnMut(LoadPhase, f32),
+) -> (Vec<MeshData>, ifc_lite_engine::Scene<'a>) {
+    let mut collector = MeshCollector {
+        meshes: Vec::new(),
+        on_progress,
     };
+    let options = ifc_lite_engine::BuildOptions {
+        geometry,
+        group_by_type: true,
+        ..Default::default()
+    };
+    let scene = ifc_lite_engine::SceneBuilder::new(content)
+        .with_options(options)
+        .build(&mut collector);
+    (collector.meshes, scene)
+}
 
-    // ============ Build spatial tree ============
-    // Debug output
-    eprintln!(
-        "DEBUG FFI: First pass scanned {} entities total",
-        entity_count
-    );
-    eprintln!(
-        "DEBUG FFI: Total relationship entities found: {}",
-        rel_count
-    );
-    eprintln!(
-        "DEBUG FFI: Found {} spatial entities",
-        spatial_entities.len()
-    );
-    eprintln!(
-        "DEBUG FFI: Found {} aggregate relationships",
-        aggregates.len()
-    );
-    eprintln!(
-        "DEBUG FFI: Found {} containment relationships",
-        contained_in.len()
-    );
-
-    // If no relationships found, infer hierarchy from entity types
-    // Standard hierarchy: Project -> Site -> Building -> Storey -> Space
-    if aggregates.is_empty() && !spatial_entities.is_empty() {
-        eprintln!("DEBUG FFI: No relationships found, inferring hierarchy from types");
-
-        // Collect entities by type
-        let mut projects: Vec<u32> = Vec::new();
-        let mut sites: Vec<u32> = Vec::new();
-        let mut buildings: Vec<u32> = Vec::new();
-        let mut storeys: Vec<u32> = Vec::new();
-        let mut spaces: Vec<u32> = Vec::new();
-
-        for (id, info) in &spatial_entities {
-            match info.entity_type.to_uppercase().as_str() {
-                "IFCPROJECT" => projects.push(*id),
-                "IFCSITE" => sites.push(*id),
-                "IFCBUILDING" => buildings.push(*id),
-                "IFCBUILDINGSTOREY" => storeys.push(*id),
-                "IFCSPACE" => spaces.push(*id),
-                _ => {}
-            }
-        }
+/// Extract properties for a specific entity
+fn extract_properties(content: &str, entity_id: u32) -> Vec<PropertySet> {
+    use ifc_lite_core::{
+        build_entity_index, EntityDecoder, EntityScanner, PropertyData, UnitAssignment,
+    };
 
-        // Build inferred hierarchy
-        // Project -> Sites
-        for &proj_id in &projects {
-            if !sites.is_empty() {
-                aggregates.entry(proj_id).or_default().extend(sites.clone());
-            } else if !buildings.is_empty() {
-                // If no sites, project contains buildings directly
-                aggregates
-                    .entry(proj_id)
-                    .or_default()
-                    .extend(buildings.clone());
-            }
-        }
+    let index = build_entity_index(content);
+    let mut decoder = EntityDecoder::with_index(content, index);
 
-        // Sites -> Buildings
-        for &site_id in &sites {
-            if !buildings.is_empty() {
-                aggregates
-                    .entry(site_id)
-                    .or_default()
-                    .extend(buildings.clone());
-            }
-        }
+    // Step 1: Find all IFCRELDEFINESBYPROPERTIES that reference this entity
+    let mut property_set_ids: Vec<u32> = Vec::new();
 
-        // Buildings -> Storeys
-        for &building_id in &buildings {
-            if !storeys.is_empty() {
-                aggregates
-                    .entry(building_id)
-                    .or_default()
-                    .extend(storeys.clone());
-            }
-        }
+    // Project units, for values without an explicit unit
+    let mut units = UnitAssignment::default();
 
-        // Storeys -> Spaces (add spaces as contained elements)
-        // Distribute spaces to storeys based on naming or just first storey
-        if !spaces.is_empty() && !storeys.is_empty() {
-            // For now, put all spaces under the first storey with "00" or "ground" in name
-            // or just the first storey if none match
-            let ground_storey = storeys
-                .iter()
-                .find(|&&id| {
-                    spatial_entities
-                        .get(&id)
-                        .map(|info| {
-                            let name = info.name.to_lowercase();
-                            name.contains("00")
-                                || name.contains("ground")
-                                || name.contains("erdgeschoss")
-                        })
-                        .unwrap_or(false)
-                })
-                .or(storeys.first())
-                .copied();
-
-            if let Some(storey_id) = ground_storey {
-                contained_in
-                    .entry(storey_id)
-                    .or_default()
-                    .extend(spaces.clone());
+    let mut scanner = EntityScanner::new(content);
+    while let Some((id, type_name, _, _)) = scanner.next_entity() {
+        if type_name == "IFCPROJECT" {
+            units = UnitAssignment::from_project(&mut decoder, id);
+        } else if type_name.to_uppercase() == "IFCRELDEFINESBYPROPERTIES" {
+            if let Ok(entity) = decoder.decode_by_id(id) {
+                if let Some(related) = get_ref_list(&entity, Attribute::RelatedObjects) {
+                    if related.contains(&entity_id) {
+                        if let Some(pset_id) =
+                            entity.attribute_ref(Attribute::RelatingPropertyDefinition)
+                        {
+                            property_set_ids.push(pset_id);
+                        }
+                    }
+                }
             }
         }
+    }
 
-        eprintln!(
-            "DEBUG FFI: Inferred {} aggregate relationships",
-            aggregates.len()
-        );
-        eprintln!(
-            "DEBUG FFI: projects={}, sites={}, buildings={}, storeys={}, spaces={}",
-            projects.len(),
-            sites.len(),
-            buildings.len(),
-            storeys.len(),
-            spaces.len()
-        );
-        for (parent, children) in &aggregates {
-            if let Some(p_info) = spatial_entities.get(parent) {
-                eprintln!(
-                    "DEBUG FFI:   {} ({}) -> {} children",
-                    p_info.name,
-                    p_info.entity_type,
-                    children.len()
-                );
+    // Step 2: For each property set ID, extract the property set and its properties
+    let mut result: Vec<PropertySet> = Vec::new();
+
+    for pset_id in property_set_ids {
+        if let Ok(pset_entity) = decoder.decode_by_id(pset_id) {
+            let pset_type = pset_entity.ifc_type.to_string().to_uppercase();
+
+            if pset_type == "IFCPROPERTYSET" {
+                let pset_name = pset_entity
+                    .name()
+                    .map(|s| s.to_string())
+                    .unwrap_or_else(|| format!("PropertySet #{}", pset_id));
+
+                let mut properties: Vec<PropertyValue> = Vec::new();
+
+                if let Some(prop_ids) = get_ref_list(&pset_entity, Attribute::HasProperties) {
+                    for prop_id in prop_ids {
+                        if let Ok(prop_entity) = decoder.decode_by_id(prop_id) {
+                            if is_simple_property(&prop_entity.ifc_type) {
+                                // Name at index 0 for all simple property types
+                                let prop_name = prop_entity
+                                    .get_string(0)
+                                    .map(|s| s.to_string())
+                                    .unwrap_or_else(|| format!("Property #{}", prop_id));
+
+                                let data = PropertyData::from_property_with_units(
+                                    &prop_entity,
+                                    &mut decoder,
+                                    &units,
+                                );
+                                properties.push(PropertyValue::from_data(prop_name, data));
+                            }
+                        }
+                    }
+                }
+
+                if !properties.is_empty() {
+                    result.push(PropertySet {
+                        name: pset_name,
+                        properties,
+                    });
+                }
+            } else if pset_type == "IFCELEMENTQUANTITY" {
+                // IfcElementQuantity for quantities
+                let pset_name = pset_entity
+                    .name()
+                    .map(|s| s.to_string())
+                    .unwrap_or_else(|| format!("Quantities #{}", pset_id));
+
+                let mut properties: Vec<PropertyValue> = Vec::new();
+
+                // Complex quantities are flattened
+                if let Some(qty_ids) = get_ref_list(&pset_entity, Attribute::Quantities) {
+                    for qty_id in qty_ids {
+                        for qty in ifc_lite_core::decode_quantity(&mut decoder, qty_id) {
+                            let (value, unit) = qty.display_value(&units);
+                            let data = PropertyData::Real {
+                                value,
+                                unit: Some(unit).filter(|u| !u.is_empty()),
+                            };
+                            properties
+                                .push(PropertyValue::from_data(qty.qualified_name(), Some(data)));
+                        }
+                    }
+                }
+
+                if !properties.is_empty() {
+                    result.push(PropertySet {
+                        name: pset_name,
+                        properties,
+                    });
+                }
             }
         }
     }
 
-    let spatial_tree = build_spatial_tree(
-        &spatial_entities,
-        &aggregates,
-        &contained_in,
-        &entities,
-        &entities_with_geometry,
-    );
-
-    eprintln!("DEBUG FFI: spatial_tree = {:?}", spatial_tree.is_some());
-
-    Ok((meshes, entities, spatial_tree, bounds))
-}
-
-/// Get node type string from entity type
-fn get_node_type(entity_type: &str) -> &'static str {
-    match entity_type.to_uppercase().as_str() {
-        "IFCPROJECT" => "Project",
-        "IFCSITE" => "Site",
-        "IFCBUILDING" => "Building",
-        "IFCBUILDINGSTOREY" => "Storey",
-        "IFCSPACE" => "Space",
-        _ => "Element",
-    }
-}
-
-/// Build spatial tree from collected data
-fn build_spatial_tree(
-    spatial_entities: &std::collections::HashMap<u32, SpatialInfo>,
-    aggregates: &std::collections::HashMap<u32, Vec<u32>>,
-    contained_in: &std::collections::HashMap<u32, Vec<u32>>,
-    entities: &[EntityInfo],
-    entities_with_geometry: &HashSet<u64>,
-) -> Option<SpatialNode> {
-    // Find root (usually IfcProject)
-    let root_id = spatial_entities
-        .iter()
-        .find(|(_, info)| info.entity_type.to_uppercase() == "IFCPROJECT")
-        .map(|(id, _)| *id)?;
-
-    build_node(
-        root_id,
-        spatial_entities,
-        aggregates,
-        contained_in,
-        entities,
-        entities_with_geometry,
+    result
+}
+
+/// Property types with a directly readable value
+fn is_simple_property(ifc_type: &ifc_lite_core::IfcType) -> bool {
+    use ifc_lite_core::IfcType;
+
+    matches!(
+        ifc_type,
+        IfcType::IfcPropertySingleValue
+            | IfcType::IfcPropertyBoundedValue
+            | IfcType::IfcPropertyEnumeratedValue
+            | IfcType::IfcPropertyListValue
+            | IfcType::IfcPropertyTableValue
     )
 }
 
-/// Recursively build a spatial node
-fn build_node(
-    id: u32,
-    spatial_entities: &std::collections::HashMap<u32, SpatialInfo>,
-    aggregates: &std::collections::HashMap<u32, Vec<u32>>,
-    contained_in: &std::collections::HashMap<u32, Vec<u32>>,
-    entities: &[EntityInfo],
-    entities_with_geometry: &HashSet<u64>,
-) -> Option<SpatialNode> {
-    let info = spatial_entities.get(&id)?;
-    let node_type = get_node_type(&info.entity_type);
-
-    let mut children: Vec<SpatialNode> = Vec::new();
-
-    // Add aggregated children (Site->Building->Storey hierarchy)
-    if let Some(child_ids) = aggregates.get(&id) {
-        for &child_id in child_ids {
-            if let Some(child_node) = build_node(
-                child_id,
-                spatial_entities,
-                aggregates,
-                contained_in,
-                entities,
-                entities_with_geometry,
-            ) {
-                children.push(child_node);
-            }
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_scene() {
+        let scene = IfcScene::new();
+        assert!(!scene.is_loaded());
+    }
+
+    #[test]
+    fn test_schema_version() {
+        let scene = IfcScene::new();
+        assert_eq!(scene.get_schema(), None);
+
+        let content = "ISO-10303-21;\nHEADER;\nFILE_SCHEMA(('IFC2X3'));\nENDSEC;\nDATA;\n\
+#1=IFCPROJECT('p',$,'Project',$,$,$,$,$,$);\n\
+ENDSEC;\nEND-ISO-10303-21;\n";
+        scene.load_string(content.to_string()).unwrap();
+        assert_eq!(scene.get_schema(), Some(SchemaVersion::Ifc2x3));
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let content = "ISO-10303-21;\nHEADER;\nENDSEC;\nDATA;\n\
+#1=IFCWALL('0a',$,'W1',$,$,$,$,$);\n\
+#2=IFCWALL('0b',$,'broken'\n\
+ENDSEC;\nEND-ISO-10303-21;\n";
+        let scene = IfcScene::new();
+        scene.load_string(content.to_string()).unwrap();
+
+        let report = scene.get_diagnostics();
+        assert_eq!(report.dropped, 0);
+        let diagnostics = report.items;
+        assert!(diagnostics
+            .iter()
+            .any(|d| d.kind == DiagnosticKind::Schema && d.line.is_none()));
+        assert!(diagnostics.iter().any(
+            |d| d.kind == DiagnosticKind::Structure && d.severity == DiagnosticSeverity::Error
+        ));
+        let broken = diagnostics
+            .iter()
+            .find(|d| d.entity_id == Some(2))
+            .expect("malformed wall recorded");
+        assert_eq!(broken.kind, DiagnosticKind::Parse);
+        assert_eq!(broken.line, Some(6));
+        assert_eq!(broken.entity_type.as_deref(), Some("IFCWALLSTANDARDCASE"));
+    }
+
+    #[test]
+    fn test_validate() {
+        let scene = IfcScene::new();
+        assert!(scene.validate().is_empty());
+
+        let content = "ISO-10303-21;\nHEADER;\nFILE_SCHEMA(('IFC4'));\nENDSEC;\nDATA;\n\
+#1=IFCPROJECT('0YvctVUKr0kugbFTf53O9L',$,'Project',$,$,$,$,$,$);\n\
+#2=IFCWALL('1YvctVUKr0kugbFTf53O9L',$,'W1',$,$,#99,$,$,$);\n\
+#3=IFCWALL('1YvctVUKr0kugbFTf53O9L',$,'W2',$,$,$,$,$,$);\n\
+ENDSEC;\nEND-ISO-10303-21;\n";
+        scene.load_string(content.to_string()).unwrap();
+
+        let findings = scene.validate();
+        assert_eq!(findings.len(), 2, "{:?}", findings);
+        assert!(findings
+            .iter()
+            .all(|f| f.severity == DiagnosticSeverity::Error));
+        assert_eq!(findings[0].entity_id, Some(2));
+        assert_eq!(findings[0].line, Some(7));
+        assert!(findings[1].message.starts_with("Duplicate GlobalId"));
+    }
+
+    #[test]
+    fn test_selection() {
+        let scene = IfcScene::new();
+        scene.select(1);
+        assert_eq!(scene.get_selection().selected_ids, vec![1]);
+
+        scene.add_to_selection(2);
+        let sel = scene.get_selection();
+        assert!(sel.selected_ids.contains(&1));
+        assert!(sel.selected_ids.contains(&2));
+
+        scene.clear_selection();
+        assert!(scene.get_selection().selected_ids.is_empty());
+    }
+
+    #[test]
+    fn test_set_selection_replaces() {
+        let scene = IfcScene::new();
+        scene.select(1);
+        scene.set_selection(vec![2, 3, 3]);
+
+        let mut ids = scene.get_selection().selected_ids;
+        ids.sort_unstable();
+        assert_eq!(ids, vec![2, 3]);
+    }
+
+    #[test]
+    fn test_section_planes_capped() {
+        let scene = IfcScene::new();
+        let plane = |z: f32| SectionPlane {
+            enabled: true,
+            origin_z: z,
+            ..SectionPlane::default()
+        };
+        scene.set_section_planes((0..8).map(|i| plane(i as f32)).collect());
+        assert_eq!(scene.get_section_planes().len(), MAX_SECTION_PLANES);
+
+        scene.set_section_plane(plane(3.0));
+        let planes = scene.get_section_planes();
+        assert_eq!(planes.len(), 1);
+        assert_eq!(scene.get_section_plane().origin_z, 3.0);
+    }
+
+    #[test]
+    fn test_bcf_topic_round_trip() {
+        let scene = IfcScene::new();
+        let entity = EntityInfo {
+            id: 42,
+            entity_type: "IFCWALLSTANDARDCASE".to_string(),
+            name: Some("Wall".to_string()),
+            global_id: Some("2O2Fr$t4X7Zf8NOew3FLOH".to_string()),
+            storey: None,
+            storey_elevation: None,
+            predefined_type: None,
+            object_type: None,
+        };
+        scene.data.write().set_entities(vec![entity.clone()]);
+
+        scene.select(entity.id);
+        let topic = scene
+            .create_bcf_topic(
+                "Check clearance".to_string(),
+                Some(" ".to_string()),
+                "qa@example.com".to_string(),
+                Some(vec![0x89, b'P', b'N', b'G']),
+            )
+            .unwrap();
+        assert_eq!(topic.description, None);
+        assert_eq!(topic.entity_ids, vec![entity.id]);
+
+        let bytes = scene.export_bcf();
+        scene.clear_selection();
+        let topics = scene.load_bcf(bytes).unwrap();
+        assert_eq!(topics.len(), 1);
+        assert_eq!(topics[0].guid, topic.guid);
+        assert_eq!(topics[0].title, "Check clearance");
+        assert_eq!(topics[0].global_ids, vec![entity.global_id.unwrap()]);
+        assert_eq!(topics[0].entity_ids, vec![entity.id]);
+        assert_eq!(topics[0].snapshot_png, topic.snapshot_png);
+
+        assert!(scene.load_bcf(b"not a zip".to_vec()).is_err());
+        assert_eq!(scene.get_bcf_topics().len(), 1);
+    }
+
+    #[test]
+    fn test_global_id_addressing() {
+        let content = std::fs::read_to_string("../../tests/models/test.ifc")
+            .expect("Failed to read test.ifc");
+        let scene = IfcScene::new();
+        scene.load_string(content).expect("Failed to load IFC");
+
+        let entity = scene
+            .get_entities()
+            .into_iter()
+            .find(|e| e.name.as_deref() == Some("Test Wall"))
+            .expect("Test Wall");
+        assert_eq!(entity.global_id.as_deref(), Some("wall001"));
+        let global_id = entity.global_id.clone().unwrap();
+
+        let found = scene.get_entity_by_global_id(global_id.clone()).unwrap();
+        assert_eq!(found.id, entity.id);
+        assert!(scene
+            .get_entity_by_global_id("0000000000000000000000".to_string())
+            .is_none());
+
+        scene.set_selection_by_global_ids(vec![global_id.clone(), "unknown".to_string()]);
+        assert_eq!(scene.get_selection().selected_ids, vec![entity.id]);
+        assert_eq!(scene.get_selected_global_ids(), vec![global_id.clone()]);
+
+        scene.hide_by_global_ids(vec![global_id.clone()]);
+        assert!(!scene.is_entity_visible(entity.id));
+        scene.show_by_global_ids(vec![global_id.clone()]);
+        assert!(scene.is_entity_visible(entity.id));
+
+        scene.isolate_by_global_ids(vec![global_id]);
+        assert_eq!(scene.get_visible_count(), 1);
+    }
+
+    #[test]
+    fn test_type_visibility() {
+        let content = std::fs::read_to_string("../../tests/models/test.ifc")
+            .expect("Failed to read test.ifc");
+        let scene = IfcScene::new();
+        scene.load_string(content).expect("Failed to load IFC");
+
+        let entities = scene.get_entities();
+        let walls: Vec<u64> = entities
+            .iter()
+            .filter(|e| e.entity_type == "IFCWALLSTANDARDCASE")
+            .map(|e| e.id)
+            .collect();
+        assert!(!walls.is_empty());
+
+        scene.hide_type("IfcWallStandardCase".to_string());
+        assert!(walls.iter().all(|id| !scene.is_entity_visible(*id)));
+        assert_eq!(
+            scene.get_visible_count() as usize,
+            entities.len() - walls.len()
+        );
+        scene.show_type("IfcWallStandardCase".to_string());
+        assert!(walls.iter().all(|id| scene.is_entity_visible(*id)));
+
+        scene.isolate_type("IfcWallStandardCase".to_string());
+        assert_eq!(scene.get_visible_count() as usize, walls.len());
+        scene.show_all();
+        assert_eq!(scene.get_visible_count() as usize, entities.len());
+    }
+
+    #[test]
+    fn test_quantity_report_from_geometry() {
+        let scene = IfcScene::new();
+        assert!(matches!(
+            scene.get_quantity_report(),
+            Err(IfcError::NotLoaded)
+        ));
+
+        let content = std::fs::read_to_string("../../tests/models/test.ifc")
+            .expect("Failed to read test.ifc");
+        scene.load_string(content).expect("Failed to load IFC");
+        let report = scene.get_quantity_report().unwrap();
+
+        // No quantity sets in the file, so the wall is measured from its mesh
+        let wall = report
+            .elements
+            .iter()
+            .find(|e| e.global_id.as_deref() == Some("wall001"))
+            .expect("Test Wall");
+        assert!(wall.estimated);
+        // 5000 x 200 x 3000 extrusion in a metre project
+        assert!((wall.volume.unwrap() / 3.0e9 - 1.0).abs() < 1e-4);
+        assert!((wall.footprint_area.unwrap() / 1.0e6 - 1.0).abs() < 1e-4);
+        assert!((wall.length.unwrap() - 5000.0).abs() < 1e-2);
+
+        let level = report
+            .by_storey
+            .iter()
+            .find(|g| g.key == "Level 0")
+            .expect("Level 0");
+        assert_eq!(level.count as usize, report.elements.len());
+        assert_eq!(level.estimated_count, level.count);
+
+        let csv = scene.export_quantity_csv().unwrap();
+        assert_eq!(csv.lines().count(), report.elements.len() + 1);
+        assert!(csv.contains("100,wall001,IFCWALLSTANDARDCASE,Test Wall,Level 0,,"));
     }
 
-    // Add contained elements (elements in this storey/space)
-    if let Some(element_ids) = contained_in.get(&id) {
-        for &elem_id in element_ids {
-            // Find the entity data for this element
-            if let Some(elem) = entities.iter().find(|e| e.id == elem_id as u64) {
-                let has_geometry = entities_with_geometry.contains(&(elem_id as u64));
-                children.push(SpatialNode {
-                    id: elem_id as u64,
-                    node_type: "Element".to_string(),
-                    name: elem.name.clone().unwrap_or_else(|| format!("#{}", elem_id)),
-                    entity_type: elem.entity_type.clone(),
-                    elevation: None,
-                    has_geometry,
-                    children: Vec::new(),
-                });
+    #[test]
+    fn test_spatial_queries() {
+        let content = std::fs::read_to_string("../../tests/models/test.ifc")
+            .expect("Failed to read test.ifc");
+        let scene = IfcScene::new();
+        scene.load_string(content).expect("Failed to load IFC");
+        let bounds = scene.get_bounds().unwrap();
+        let wall = scene
+            .get_entities()
+            .into_iter()
+            .find(|e| e.global_id.as_deref() == Some("wall001"))
+            .expect("Test Wall")
+            .id;
+
+        assert!(scene.entities_in_box(bounds.clone()).contains(&wall));
+
+        let positions = scene.get_mesh(wall).unwrap().positions;
+        let (mut min, mut max) = ([f32::MAX; 3], [f32::MIN; 3]);
+        for p in positions.chunks_exact(3) {
+            for i in 0..3 {
+                min[i] = min[i].min(p[i]);
+                max[i] = max[i].max(p[i]);
             }
         }
+        let center = [0, 1, 2].map(|i| (min[i] + max[i]) / 2.0);
+        let across = scene.entities_intersecting_plane(center.to_vec(), vec![1.0, 0.0, 0.0]);
+        assert!(across.contains(&wall));
+        let beyond = vec![bounds.max_x + 1.0, center[1], center[2]];
+        assert!(scene
+            .entities_intersecting_plane(beyond, vec![1.0, 0.0, 0.0])
+            .is_empty());
+
+        // Straight down onto the top face
+        let origin = vec![center[0], center[1], max[2] + 10.0];
+        let hit = scene
+            .nearest_entity(origin.clone(), vec![0.0, 0.0, -1.0])
+            .expect("hit");
+        assert_eq!(hit.entity_id, wall);
+        assert!((hit.distance - 10.0).abs() < 1e-2);
+
+        scene.hide_entity(wall);
+        let hit = scene.nearest_entity(origin, vec![0.0, 0.0, -1.0]);
+        assert!(hit.is_none_or(|hit| hit.entity_id != wall));
     }
 
-    // Sort children: spatial structures first (by elevation desc), then elements by type/name
-    children.sort_by(|a, b| {
-        // Spatial structures come first
-        let a_is_spatial = a.node_type != "Element";
-        let b_is_spatial = b.node_type != "Element";
-        if a_is_spatial != b_is_spatial {
-            return b_is_spatial.cmp(&a_is_spatial);
-        }
-        // For storeys, sort by elevation (descending)
-        if a.node_type == "Storey" && b.node_type == "Storey" {
-            return b
-                .elevation
-                .partial_cmp(&a.elevation)
-                .unwrap_or(std::cmp::Ordering::Equal);
-        }
-        // Otherwise sort by type then name
-        match a.entity_type.cmp(&b.entity_type) {
-            std::cmp::Ordering::Equal => a.name.cmp(&b.name),
-            other => other,
-        }
-    });
-
-    Some(SpatialNode {
-        id: id as u64,
-        node_type: node_type.to_string(),
-        name: info.name.clone(),
-        entity_type: info.entity_type.clone(),
-        elevation: info.elevation,
-        has_geometry: false, // Spatial structures don't have geometry
-        children,
-    })
-}
-
-/// Get default color for entity type
-fn get_element_color(entity_type: &str) -> [f32; 4] {
-    let upper = entity_type.to_uppercase();
-
-    if upper.contains("WALL") {
-        [0.95, 0.92, 0.85, 1.0] // Warm off-white
-    } else if upper.contains("SLAB") || upper.contains("FLOOR") {
-        [0.75, 0.75, 0.78, 1.0] // Cool gray
-    } else if upper.contains("ROOF") {
-        [0.7, 0.45, 0.35, 1.0] // Terracotta
-    } else if upper.contains("BEAM") || upper.contains("COLUMN") {
-        [0.55, 0.55, 0.6, 1.0] // Steel blue-gray
-    } else if upper.contains("DOOR") {
-        [0.6, 0.45, 0.3, 1.0] // Warm wood
-    } else if upper.contains("WINDOW") || upper.contains("CURTAINWALL") {
-        [0.6, 0.8, 0.95, 0.4] // Sky blue, transparent
-    } else if upper.contains("STAIR") || upper.contains("RAILING") {
-        [0.65, 0.6, 0.55, 1.0] // Warm gray
-    } else if upper.contains("COVERING") {
-        [0.9, 0.88, 0.82, 1.0] // Light cream
-    } else if upper.contains("FURNITURE") || upper.contains("FURNISHING") {
-        [0.7, 0.55, 0.4, 1.0] // Light wood
-    } else if upper.contains("PIPE") || upper.contains("DUCT") || upper.contains("CABLE") {
-        [0.4, 0.6, 0.4, 1.0] // Industrial green
-    } else {
-        [0.8, 0.78, 0.75, 1.0] // Default warm gray
+    #[test]
+    fn test_mapped_item_instance() {
+        let content = "ISO-10303-21;\nHEADER;\nFILE_SCHEMA(('IFC4'));\nENDSEC;\nDATA;\n\
+#1=IFCCARTESIANPOINT((0.,0.,0.));\n\
+#2=IFCAXIS2PLACEMENT3D(#1,$,$);\n\
+#3=IFCLOCALPLACEMENT($,#2);\n\
+#4=IFCAXIS2PLACEMENT2D(#1,$);\n\
+#5=IFCRECTANGLEPROFILEDEF(.AREA.,$,#4,1.,1.);\n\
+#6=IFCDIRECTION((0.,0.,1.));\n\
+#7=IFCEXTRUDEDAREASOLID(#5,#2,#6,1.);\n\
+#8=IFCSHAPEREPRESENTATION($,'Body','SweptSolid',(#7));\n\
+#9=IFCREPRESENTATIONMAP(#2,#8);\n\
+#10=IFCCARTESIANPOINT((10.,0.,0.));\n\
+#11=IFCCARTESIANTRANSFORMATIONOPERATOR3D($,$,#10,$,$);\n\
+#12=IFCMAPPEDITEM(#9,#11);\n\
+#13=IFCSHAPEREPRESENTATION($,'Body','MappedRepresentation',(#12));\n\
+#14=IFCPRODUCTDEFINITIONSHAPE($,$,(#13));\n\
+#15=IFCFURNISHINGELEMENT('f1',$,$,$,$,#3,#14,$);\n\
+ENDSEC;\nEND-ISO-10303-21;\n";
+        let scene = IfcScene::new();
+        scene.load_string(content.to_string()).unwrap();
+
+        // Map geometry with the placement as transform
+        let mesh = scene.get_mesh(15).expect("furniture mesh");
+        assert!(mesh.positions.chunks_exact(3).all(|p| p[0] <= 0.5 + 1e-5));
+        assert!((mesh.transform[12] - 10.0).abs() < 1e-5);
+
+        // Queries and batches see the placed copy
+        let hit = scene
+            .nearest_entity(vec![10.0, 0.0, 10.0], vec![0.0, 0.0, -1.0])
+            .expect("hit");
+        assert_eq!(hit.entity_id, 15);
+        assert!((hit.distance - 9.0).abs() < 1e-3);
+        assert!(scene
+            .nearest_entity(vec![0.0, 0.0, 10.0], vec![0.0, 0.0, -1.0])
+            .is_none());
+        let vertices: Vec<f32> = scene
+            .get_batched_meshes()
+            .into_iter()
+            .flat_map(|batch| batch.vertices)
+            .collect();
+        assert!(vertices.chunks_exact(10).all(|v| v[0] > 9.0));
     }
-}
 
-/// Extract properties for a specific entity
-fn extract_properties(content: &str, entity_id: u32) -> Vec<PropertySet> {
-    use ifc_lite_core::{build_entity_index, EntityDecoder, EntityScanner};
-
-    let index = build_entity_index(content);
-    let mut decoder = EntityDecoder::with_index(content, index);
+    #[test]
+    fn test_projection_and_standard_view() {
+        let scene = IfcScene::new();
+        scene.set_camera_state(CameraState {
+            distance: 42.0,
+            target_x: 1.0,
+            ..CameraState::default()
+        });
+        assert!(!scene.get_camera_state().orthographic);
+
+        scene.set_projection(true);
+        scene.set_standard_view(StandardView::Right);
+        let camera = scene.get_camera_state();
+        assert!(camera.orthographic);
+        assert_eq!(camera.azimuth, std::f32::consts::FRAC_PI_2);
+        assert_eq!(camera.elevation, 0.0);
+        assert_eq!(camera.distance, 42.0);
+        assert_eq!(camera.target_x, 1.0);
+
+        scene.set_standard_view(StandardView::Top);
+        assert!(scene.get_camera_state().elevation > 1.5);
+        scene.set_projection(false);
+        assert!(!scene.get_camera_state().orthographic);
+    }
 
-    // Step 1: Find all IFCRELDEFINESBYPROPERTIES that reference this entity
-    let mut property_set_ids: Vec<u32> = Vec::new();
+    #[test]
+    fn test_camera_path() {
+        let scene = IfcScene::new();
+        assert_eq!(scene.export_path_json(), r#"{"keyframes":[]}"#);
+
+        scene.set_standard_view(StandardView::Front);
+        assert_eq!(scene.add_keyframe(2.0), 1);
+        scene.set_standard_view(StandardView::Top);
+        scene.set_projection(true);
+        assert_eq!(scene.add_keyframe(-1.0), 2);
+        let keyframes = scene.get_keyframes();
+        assert_eq!(keyframes[0].camera.elevation, 0.0);
+        assert_eq!(keyframes[1].duration, 0.0);
+        assert!(keyframes[1].camera.orthographic);
+
+        let json = scene.export_path_json();
+        assert!(json.contains(r#""target":[0.0,0.0,0.0]"#));
+        scene.clear_keyframes();
+        assert!(scene.get_keyframes().is_empty());
+        assert_eq!(scene.import_path_json(json).unwrap(), 2);
+        let imported = scene.get_keyframes();
+        assert_eq!(imported[0].duration, 2.0);
+        assert!(imported[1].camera.orthographic);
+        assert!(matches!(
+            scene.import_path_json("[]".into()),
+            Err(IfcError::ParseError { .. })
+        ));
+    }
 
-    let mut scanner = EntityScanner::new(content);
-    while let Some((id, type_name, _, _)) = scanner.next_entity() {
-        if type_name.to_uppercase() == "IFCRELDEFINESBYPROPERTIES" {
-            if let Ok(entity) = decoder.decode_by_id(id) {
-                // RelatedObjects is at index 4 (list of entity refs)
-                if let Some(related) = get_ref_list(&entity, 4) {
-                    if related.contains(&entity_id) {
-                        // RelatingPropertyDefinition is at index 5
-                        if let Some(pset_id) = entity.get_ref(5) {
-                            property_set_ids.push(pset_id);
-                        }
-                    }
-                }
+    #[test]
+    fn test_render_snapshot() {
+        let content = std::fs::read_to_string("../../tests/models/test.ifc")
+            .expect("Failed to read test.ifc");
+        let scene = IfcScene::new();
+        assert!(matches!(
+            scene.render_snapshot(64, 48, CameraState::default()),
+            Err(IfcError::NotLoaded)
+        ));
+        scene.load_string(content).expect("Failed to load IFC");
+        let bounds = scene.get_bounds().expect("bounds");
+        let camera = CameraState {
+            distance: 50.0,
+            target_x: (bounds.min_x + bounds.max_x) / 2.0,
+            target_y: (bounds.min_y + bounds.max_y) / 2.0,
+            target_z: (bounds.min_z + bounds.max_z) / 2.0,
+            ..CameraState::default()
+        };
+        // Machines without a GPU can't render, but must say so
+        match scene.render_snapshot(64, 48, camera) {
+            Ok(png) => {
+                assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+                // IHDR width and height
+                assert_eq!(&png[16..24], &[0, 0, 0, 64, 0, 0, 0, 48]);
             }
+            Err(e) => assert!(matches!(e, IfcError::RenderError { .. }), "{}", e),
         }
     }
 
-    // Step 2: For each property set ID, extract the property set and its properties
-    let mut result: Vec<PropertySet> = Vec::new();
-
-    for pset_id in property_set_ids {
-        if let Ok(pset_entity) = decoder.decode_by_id(pset_id) {
-            let pset_type = pset_entity.ifc_type.to_string().to_uppercase();
-
-            if pset_type == "IFCPROPERTYSET" {
-                // Name is at index 2
-                let pset_name = pset_entity
-                    .get_string(2)
-                    .map(|s| s.to_string())
-                    .unwrap_or_else(|| format!("PropertySet #{}", pset_id));
+    #[test]
+    fn test_storey_plan() {
+        let content = std::fs::read_to_string("../../tests/models/test.ifc")
+            .expect("Failed to read test.ifc");
+        let scene = IfcScene::new();
+        assert!(matches!(
+            scene.get_storey_plan("Level 0".into(), 1.0),
+            Err(IfcError::NotLoaded)
+        ));
+        scene.load_string(content).expect("Failed to load IFC");
+        let wall = scene
+            .get_entities()
+            .into_iter()
+            .find(|e| e.global_id.as_deref() == Some("wall001"))
+            .expect("Test Wall")
+            .id;
+
+        let plan = scene.get_storey_plan("Level 0".into(), 1.0).unwrap();
+        assert_eq!(plan.elevation, 0.0);
+        assert_eq!(plan.cut_elevation, 1.0);
+        let outline = plan
+            .outlines
+            .iter()
+            .find(|o| o.entity_id == wall)
+            .expect("wall outline");
+        assert!(outline.closed);
+        assert_eq!(outline.points.len() % 2, 0);
+        assert!(outline.points.len() >= 8);
 
-                // HasProperties is at index 4 (list of property refs)
-                let mut properties: Vec<PropertyValue> = Vec::new();
+        scene.hide_entity(wall);
+        let plan = scene.get_storey_plan("Level 0".into(), 1.0).unwrap();
+        assert!(plan.outlines.iter().all(|o| o.entity_id != wall));
 
-                if let Some(prop_ids) = get_ref_list(&pset_entity, 4) {
-                    for prop_id in prop_ids {
-                        if let Ok(prop_entity) = decoder.decode_by_id(prop_id) {
-                            let prop_type = prop_entity.ifc_type.to_string().to_uppercase();
+        assert!(scene.get_storey_plan("Roof".into(), 1.0).is_err());
+    }
 
-                            if prop_type == "IFCPROPERTYSINGLEVALUE" {
-                                // Name at index 0, NominalValue at index 2
-                                let prop_name = prop_entity
-                                    .get_string(0)
-                                    .map(|s| s.to_string())
-                                    .unwrap_or_else(|| format!("Property #{}", prop_id));
+    #[test]
+    fn test_space_mode() {
+        let content =
+            std::fs::read_to_string("../../tests/models/buildingsmart/Building-Architecture.ifc")
+                .expect("Failed to read Building-Architecture.ifc");
+        let scene = IfcScene::new();
+        scene.load_string(content).expect("Failed to load IFC");
 
-                                // Extract value - could be various IFC types
-                                let prop_value = extract_property_value(&prop_entity, 2);
+        let spaces = scene.get_spaces();
+        let living_room = spaces
+            .iter()
+            .find(|s| s.name.as_deref() == Some("living room"))
+            .expect("living room");
+        assert_eq!(living_room.storey.as_deref(), Some("00 groundfloor"));
+        assert_eq!(living_room.centroid.len(), 3);
+        assert!(!living_room.visible);
+        let id = living_room.entity_id;
+        assert!(!scene.is_entity_visible(id));
+        let color = scene.get_mesh(id).unwrap().color;
+        assert!(color[3] < 1.0);
+
+        scene.show_spaces(true);
+        assert!(scene.is_showing_spaces());
+        assert!(scene.is_entity_visible(id));
+        assert!(scene.get_spaces().iter().all(|s| s.visible));
+
+        scene.set_storey_spaces_visible("00 groundfloor".into(), false);
+        assert!(!scene.is_entity_visible(id));
+        scene.set_storey_spaces_visible("00 groundfloor".into(), true);
+        assert!(scene.is_entity_visible(id));
+    }
 
-                                // Unit at index 3 (optional)
-                                let unit = prop_entity.get_string(3).map(|s| s.to_string());
+    #[test]
+    fn test_type_objects() {
+        let content = std::fs::read_to_string(
+            "../../tests/models/buildingsmart/wall-with-opening-and-window.ifc",
+        )
+        .expect("Failed to read wall-with-opening-and-window.ifc");
+        let scene = IfcScene::new();
+        scene.load_string(content).expect("Failed to load IFC");
 
-                                properties.push(PropertyValue {
-                                    name: prop_name,
-                                    value: prop_value,
-                                    unit,
-                                });
-                            }
-                        }
-                    }
-                }
+        let window_type = scene.get_type_of(102).expect("window type");
+        assert_eq!(window_type.id, 107);
+        assert_eq!(window_type.entity_type, "IFCWINDOWTYPE");
+        assert_eq!(window_type.name.as_deref(), Some("Window for Test Example"));
+        assert_eq!(scene.get_instances_of_type(107), vec![102]);
 
-                if !properties.is_empty() {
-                    result.push(PropertySet {
-                        name: pset_name,
-                        properties,
-                    });
-                }
-            } else if pset_type == "IFCELEMENTQUANTITY" {
-                // IfcElementQuantity for quantities
-                let pset_name = pset_entity
-                    .get_string(2)
-                    .map(|s| s.to_string())
-                    .unwrap_or_else(|| format!("Quantities #{}", pset_id));
+        assert!(scene.get_type_of(107).is_none());
+        assert!(scene.get_instances_of_type(102).is_empty());
+    }
 
-                let mut properties: Vec<PropertyValue> = Vec::new();
+    #[test]
+    fn test_openings() {
+        let content = std::fs::read_to_string(
+            "../../tests/models/buildingsmart/wall-with-opening-and-window.ifc",
+        )
+        .expect("Failed to read wall-with-opening-and-window.ifc");
+        let scene = IfcScene::new();
+        let result = scene.load_string(content).expect("Failed to load IFC");
 
-                // Quantities at index 5
-                if let Some(qty_ids) = get_ref_list(&pset_entity, 5) {
-                    for qty_id in qty_ids {
-                        if let Ok(qty_entity) = decoder.decode_by_id(qty_id) {
-                            // Name at index 0
-                            let qty_name = qty_entity
-                                .get_string(0)
-                                .map(|s| s.to_string())
-                                .unwrap_or_else(|| format!("Quantity #{}", qty_id));
-
-                            // Value depends on quantity type
-                            let qty_value = extract_quantity_value(&qty_entity);
-
-                            properties.push(PropertyValue {
-                                name: qty_name,
-                                value: qty_value,
-                                unit: None,
-                            });
-                        }
-                    }
-                }
+        assert_eq!(scene.get_openings(45), vec![80]);
+        assert_eq!(scene.get_filling(80), Some(102));
+        assert!(scene.get_openings(102).is_empty());
+        assert_eq!(scene.get_filling(45), None);
 
-                if !properties.is_empty() {
-                    result.push(PropertySet {
-                        name: pset_name,
-                        properties,
-                    });
-                }
+        // Storey -> wall group -> wall -> opening -> window
+        fn find(node: &SpatialNode, id: u64) -> Option<&SpatialNode> {
+            if node.id == id {
+                return Some(node);
+            }
+            node.children.iter().find_map(|child| find(child, id))
+        }
+        fn parent(node: &SpatialNode, id: u64) -> Option<&SpatialNode> {
+            if node.children.iter().any(|child| child.id == id) {
+                return Some(node);
             }
+            node.children.iter().find_map(|child| parent(child, id))
         }
+        let tree = result.spatial_tree.expect("spatial tree");
+        let group = parent(&tree, 45).expect("wall group");
+        assert_eq!(group.node_type, "TypeGroup");
+        assert_eq!(group.name, "IfcWall");
+        assert!(group.id >= ifc_lite_engine::TYPE_GROUP_ID_BASE as u64);
+        let wall = find(&tree, 45).expect("wall node");
+        assert_eq!(wall.children.len(), 1);
+        let opening = &wall.children[0];
+        assert_eq!(opening.id, 80);
+        assert_eq!(opening.node_type, "Opening");
+        assert_eq!(opening.children.len(), 1);
+        assert_eq!(opening.children[0].id, 102);
+        assert_eq!(opening.children[0].entity_type, "IFCWINDOW");
     }
 
-    result
-}
+    #[test]
+    fn test_systems() {
+        let content = std::fs::read_to_string("../../tests/models/buildingsmart/Building-Hvac.ifc")
+            .expect("Failed to read Building-Hvac.ifc");
+        let scene = IfcScene::new();
+        scene.load_string(content).expect("Failed to load IFC");
+
+        let systems = scene.get_systems();
+        assert_eq!(systems.len(), 1);
+        assert_eq!(systems[0].id, 63);
+        assert_eq!(systems[0].entity_type, "IFCDISTRIBUTIONSYSTEM");
+        assert_eq!(systems[0].name.as_deref(), Some("house - chimney flue"));
+        assert_eq!(systems[0].member_count, 3);
+        assert_eq!(scene.get_members(63), vec![67, 85, 103]);
+        assert!(scene.get_members(67).is_empty());
+    }
 
-/// Extract value from a property entity at given index
-fn extract_property_value(entity: &ifc_lite_core::DecodedEntity, index: usize) -> String {
-    use ifc_lite_core::AttributeValue;
-
-    if let Some(attr) = entity.get(index) {
-        match attr {
-            AttributeValue::String(s) => return s.clone(),
-            AttributeValue::Float(f) => return format!("{:.4}", f),
-            AttributeValue::Integer(i) => return i.to_string(),
-            AttributeValue::Enum(e) => return e.clone(),
-            AttributeValue::List(list) => {
-                // For wrapped types like IFCLABEL('value')
-                if let Some(AttributeValue::String(s)) = list.first() {
-                    return s.clone();
-                }
-                if let Some(AttributeValue::Float(f)) = list.first() {
-                    return format!("{:.4}", f);
-                }
-                if let Some(AttributeValue::Integer(i)) = list.first() {
-                    return i.to_string();
-                }
-            }
-            AttributeValue::Null | AttributeValue::Derived => return "—".to_string(),
-            AttributeValue::EntityRef(_) => return "—".to_string(),
-        }
+    #[test]
+    fn test_geolocation() {
+        let content =
+            std::fs::read_to_string("../../tests/models/buildingsmart/Building-Architecture.ifc")
+                .expect("Failed to read Building-Architecture.ifc");
+        let scene = IfcScene::new();
+        scene.load_string(content).expect("Failed to load IFC");
+
+        // Millimetre map unit, reported in metres
+        let geo = scene.get_geolocation().expect("geolocation");
+        assert_eq!(geo.epsg_code, Some(32760));
+        assert_eq!(geo.geodetic_datum.as_deref(), Some("WGS 84"));
+        assert!((geo.eastings - 729013.3488297004).abs() < 1e-6);
+        assert!((geo.northings - 9063992.684697363).abs() < 1e-6);
+        assert!((geo.orthogonal_height - 1.3).abs() < 1e-9);
+        assert!((geo.rotation.to_degrees() - 60.0).abs() < 1e-9);
+        assert!((geo.scale - 1.0).abs() < 1e-9);
+        assert!(geo.latitude.is_none());
+
+        let identity = scene.get_scene_transform();
+        assert_eq!(identity[12], 0.0);
+        scene.set_map_conversion_applied(true);
+        let transform = scene.get_scene_transform();
+        assert!((transform[12] - geo.eastings).abs() < 1e-6);
+        assert!((transform[1] - 0.8660254037844387).abs() < 1e-9);
     }
 
-    "—".to_string()
-}
+    #[test]
+    fn test_origin_shift() {
+        let content = std::fs::read_to_string(
+            "../../tests/models/buildingsmart/wall-with-opening-and-window.ifc",
+        )
+        .expect("Failed to read wall-with-opening-and-window.ifc");
+        let scene = IfcScene::new();
+        scene.load_string(content).expect("Failed to load IFC");
 
-/// Extract value from a quantity entity
-fn extract_quantity_value(entity: &ifc_lite_core::DecodedEntity) -> String {
-    use ifc_lite_core::AttributeValue;
+        // Placed at the origin: nothing to shift
+        assert_eq!(scene.get_origin_shift(), vec![0.0, 0.0, 0.0]);
+        assert_eq!(
+            scene.to_model_coordinates(vec![1.0, 2.0, 3.0]),
+            vec![1.0, 2.0, 3.0]
+        );
+    }
 
-    let qty_type = entity.ifc_type.to_string().to_uppercase();
+    #[test]
+    fn test_materials() {
+        let content = std::fs::read_to_string(
+            "../../tests/models/buildingsmart/wall-with-opening-and-window.ifc",
+        )
+        .expect("Failed to read wall-with-opening-and-window.ifc");
+        let scene = IfcScene::new();
+        scene.load_string(content).expect("Failed to load IFC");
+
+        // 300 mm layer in a millimetre project
+        let wall = scene.get_materials(45).expect("wall material");
+        assert_eq!(wall.kind, MaterialKind::LayerSet);
+        assert_eq!(wall.name, "Name of the material used for the wall");
+        assert_eq!(wall.components.len(), 1);
+        assert!((wall.components[0].thickness.unwrap() - 0.3).abs() < 1e-9);
+        assert!((wall.total_thickness.unwrap() - 0.3).abs() < 1e-9);
+
+        let window = scene.get_materials(102).expect("window material");
+        assert_eq!(window.kind, MaterialKind::ConstituentSet);
+        let materials: Vec<_> = window
+            .components
+            .iter()
+            .map(|c| c.material.as_deref().unwrap())
+            .collect();
+        assert_eq!(materials, ["Glass", "Wood"]);
+        assert!(scene.get_materials(80).is_none());
+    }
 
-    // Different quantity types have value at different indices
-    let value_index = match qty_type.as_str() {
-        "IFCQUANTITYLENGTH" => 3,
-        "IFCQUANTITYAREA" => 3,
-        "IFCQUANTITYVOLUME" => 3,
-        "IFCQUANTITYCOUNT" => 3,
-        "IFCQUANTITYWEIGHT" => 3,
-        "IFCQUANTITYTIME" => 3,
-        _ => 3,
-    };
+    #[test]
+    fn test_batched_face_colors() {
+        let content = r#"ISO-10303-21;
+DATA;
+#1=IFCCARTESIANPOINTLIST3D(((0.,0.,0.),(1.,0.,0.),(1.,1.,0.),(0.,1.,0.)));
+#2=IFCTRIANGULATEDFACESET(#1,$,.F.,((1,2,3),(1,3,4)),$);
+#3=IFCCOLOURRGBLIST(((1.,0.,0.)));
+#4=IFCINDEXEDCOLOURMAP(#2,$,#3,(1,1));
+#5=IFCSHAPEREPRESENTATION($,'Body','Tessellation',(#2));
+#6=IFCPRODUCTDEFINITIONSHAPE($,$,(#5));
+#7=IFCBUILDINGELEMENTPROXY('proxy',$,'Panel',$,$,$,#6,$,$);
+ENDSEC;
+END-ISO-10303-21;
+"#;
+        let scene = IfcScene::new();
+        scene.load_string(content.to_string()).unwrap();
 
-    if let Some(attr) = entity.get(value_index) {
-        match attr {
-            AttributeValue::Float(f) => return format!("{:.4}", *f),
-            AttributeValue::Integer(i) => return i.to_string(),
-            _ => {}
+        let batches = scene.get_batched_meshes();
+        assert_eq!(batches.len(), 1);
+        assert!(!batches[0].is_transparent);
+        for vertex in batches[0].vertices.chunks_exact(10) {
+            assert_eq!(&vertex[6..10], &[1.0, 0.0, 0.0, 1.0]);
         }
     }
 
-    "—".to_string()
-}
+    #[test]
+    fn test_load_progress() {
+        struct Recorder(Arc<parking_lot::Mutex<Vec<(LoadPhase, f32)>>>);
+        impl LoadProgressListener for Recorder {
+            fn on_progress(&self, phase: LoadPhase, percent: f32) {
+                self.0.lock().push((phase, percent));
+            }
+        }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        let content = std::fs::read_to_string("../../tests/models/test.ifc")
+            .expect("Failed to read test.ifc");
+        let updates = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let scene = IfcScene::new();
+        scene
+            .load_string_with_progress(content, Box::new(Recorder(updates.clone())))
+            .unwrap();
+
+        let updates = updates.lock();
+        assert_eq!(updates.first(), Some(&(LoadPhase::Index, 0.0)));
+        assert_eq!(updates.last(), Some(&(LoadPhase::Complete, 100.0)));
+        for pair in updates.windows(2) {
+            assert!(
+                pair[0].1 <= pair[1].1,
+                "progress went backwards: {:?}",
+                pair
+            );
+        }
+        for phase in [LoadPhase::Spatial, LoadPhase::Geometry] {
+            assert!(updates.iter().any(|(p, _)| *p == phase));
+        }
+    }
 
     #[test]
-    fn test_create_scene() {
+    fn test_scene_observer() {
+        #[derive(Debug, PartialEq)]
+        enum Event {
+            Selection(Vec<u64>),
+            Hover(Option<u64>),
+            Visibility(Vec<u64>),
+            Progress,
+        }
+        struct Recorder(Arc<parking_lot::Mutex<Vec<Event>>>);
+        impl SceneObserver for Recorder {
+            fn on_selection_changed(&self, selection: SelectionState) {
+                let mut ids = selection.selected_ids;
+                ids.sort();
+                self.0.lock().push(Event::Selection(ids));
+            }
+            fn on_hover_changed(&self, entity_id: Option<u64>) {
+                self.0.lock().push(Event::Hover(entity_id));
+            }
+            fn on_visibility_changed(&self, visibility: VisibilityState) {
+                let mut ids = visibility.hidden_ids;
+                ids.sort();
+                self.0.lock().push(Event::Visibility(ids));
+            }
+            fn on_load_progress(&self, _phase: LoadPhase, _percent: f32) {
+                self.0.lock().push(Event::Progress);
+            }
+        }
+
+        let content = std::fs::read_to_string("../../tests/models/test.ifc")
+            .expect("Failed to read test.ifc");
+        let events = Arc::new(parking_lot::Mutex::new(Vec::new()));
         let scene = IfcScene::new();
-        assert!(!scene.is_loaded());
+        scene.set_observer(Box::new(Recorder(events.clone())));
+        scene.load_string(content).unwrap();
+        assert!(events.lock().contains(&Event::Progress));
+        events.lock().clear();
+
+        scene.select(7);
+        scene.select(7);
+        scene.add_to_selection(3);
+        scene.set_hovered(Some(3));
+        scene.set_hovered(Some(3));
+        scene.hide_entity(5);
+        scene.show_entity(6);
+        scene.set_hovered(None);
+        scene.clear_selection();
+        assert_eq!(
+            *events.lock(),
+            vec![
+                Event::Selection(vec![7]),
+                Event::Selection(vec![3, 7]),
+                Event::Hover(Some(3)),
+                Event::Visibility(vec![5]),
+                Event::Hover(None),
+                Event::Selection(vec![]),
+            ]
+        );
+
+        scene.clear_observer();
+        scene.select(1);
+        assert_eq!(events.lock().len(), 6);
     }
 
     #[test]
-    fn test_selection() {
+    fn test_load_with_geometry_options() {
+        let content = std::fs::read_to_string("../../tests/models/test.ifc")
+            .expect("Failed to read test.ifc");
         let scene = IfcScene::new();
-        scene.select(1);
-        assert_eq!(scene.get_selection().selected_ids, vec![1]);
+        scene.load_string(content.clone()).unwrap();
+        let entities = scene.get_entities().len();
+        assert!(!scene.get_meshes().is_empty());
+
+        // Nothing in the model is a kilometre across
+        let options = GeometryOptions {
+            min_feature_size: 1000.0,
+            ..default_geometry_options()
+        };
+        scene.load_string_with_options(content, options).unwrap();
+        assert!(scene.get_meshes().is_empty());
+        assert_eq!(scene.get_entities().len(), entities);
+    }
 
-        scene.add_to_selection(2);
-        let sel = scene.get_selection();
-        assert!(sel.selected_ids.contains(&1));
-        assert!(sel.selected_ids.contains(&2));
+    #[test]
+    fn test_load_metrics() {
+        let scene = IfcScene::new();
+        assert!(scene.get_load_metrics().is_none());
 
-        scene.clear_selection();
-        assert!(scene.get_selection().selected_ids.is_empty());
+        let content = std::fs::read_to_string("../../tests/models/test.ifc")
+            .expect("Failed to read test.ifc");
+        scene.load_string(content).unwrap();
+        scene.get_batched_meshes();
+        scene.record_load_stage("upload".to_string(), 5.0);
+
+        let metrics = scene.get_load_metrics().unwrap();
+        let stages: Vec<_> = metrics.stages.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(stages.first(), Some(&"index"));
+        assert!(stages.contains(&"geometry"));
+        assert_eq!(stages[stages.len() - 2..], ["batching", "upload"]);
+        assert!(metrics.total_ms >= 5.0);
+        assert!(!metrics.processors.is_empty());
+        assert_eq!(metrics.mesh_count, scene.get_meshes().len() as u64);
+        assert!(metrics.triangle_count > 0);
     }
 
     #[test]
@@ -1484,7 +3588,7 @@ mod tests {
         let content = std::fs::read_to_string("../../tests/models/test.ifc")
             .expect("Failed to read test.ifc");
 
-        let (meshes, entities, spatial_tree, bounds) =
+        let (meshes, entities, spatial_tree, bounds, _) =
             process_ifc_content(&content).expect("Failed to process IFC");
 
         println!("Meshes: {}", meshes.len());
@@ -1508,6 +3612,29 @@ mod tests {
         assert!(spatial_tree.is_some(), "Spatial tree should be built");
     }
 
+    #[test]
+    fn test_spatial_tree_road() {
+        let content = "ISO-10303-21;\nHEADER;\nFILE_SCHEMA(('IFC4X3_ADD2'));\nENDSEC;\nDATA;\n\
+#1=IFCPROJECT('p',$,'Project',$,$,$,$,$,$);\n\
+#2=IFCSITE('s',$,'Site',$,$,$,$,$,$,$,$,$,$,$);\n\
+#3=IFCROAD('r',$,$,$,$,$,$,$,$,$);\n\
+#4=IFCROADPART('rp',$,'Carriageway',$,$,$,$,$,$,.CARRIAGEWAY.,.LONGITUDINAL.);\n\
+#5=IFCRELAGGREGATES('a1',$,$,$,#1,(#2));\n\
+#6=IFCRELAGGREGATES('a2',$,$,$,#2,(#3));\n\
+#7=IFCRELAGGREGATES('a3',$,$,$,#3,(#4));\n\
+ENDSEC;\nEND-ISO-10303-21;\n";
+
+        let (_, _, spatial_tree, _, _) =
+            process_ifc_content(content).expect("Failed to process IFC");
+        let tree = spatial_tree.expect("Spatial tree should be built");
+        let site = &tree.children[0];
+        let road = &site.children[0];
+        assert_eq!(road.node_type, "Facility");
+        assert_eq!(road.name, "Road #3");
+        assert_eq!(road.children[0].node_type, "FacilityPart");
+        assert_eq!(road.children[0].name, "Carriageway");
+    }
+
     #[test]
     fn test_spatial_tree_duplex() {
         let content = std::fs::read_to_string("../../tests/models/ara3d/duplex.ifc")
@@ -1515,7 +3642,7 @@ mod tests {
 
         println!("File size: {} bytes", content.len());
 
-        let (meshes, entities, spatial_tree, bounds) =
+        let (meshes, entities, spatial_tree, bounds, _) =
             process_ifc_content(&content).expect("Failed to process IFC");
 
         println!("Meshes: {}", meshes.len());
@@ -1614,6 +3741,16 @@ mod bevy_viewer {
             }
         }
 
+        /// Load geometry in the binary transfer format (faster than JSON)
+        pub fn load_geometry_binary(&self, data: Vec<u8>) -> bool {
+            let guard = self.app.lock().unwrap();
+            if let Some(app) = *guard {
+                unsafe { ifc_lite_bevy::ffi::load_geometry_binary(app, data.as_ptr(), data.len()) }
+            } else {
+                false
+            }
+        }
+
         /// Load entity metadata
         pub fn load_entities(&self, entities_json: String) -> bool {
             let guard = self.app.lock().unwrap();
@@ -1635,6 +3772,31 @@ mod bevy_viewer {
             }
         }
 
+        /// IDs of the selected entities, including box selections
+        pub fn get_selected_ids(&self) -> Vec<u64> {
+            let guard = self.app.lock().unwrap();
+            let Some(app) = *guard else {
+                return Vec::new();
+            };
+            unsafe {
+                let count = ifc_lite_bevy::ffi::get_selection(app, std::ptr::null_mut(), 0);
+                let mut ids = vec![0u64; count];
+                let written = ifc_lite_bevy::ffi::get_selection(app, ids.as_mut_ptr(), count);
+                ids.truncate(written.min(count));
+                ids
+            }
+        }
+
+        /// Switch left drag between camera navigation and box selection
+        pub fn set_box_select(&self, enabled: bool) {
+            let guard = self.app.lock().unwrap();
+            if let Some(app) = *guard {
+                unsafe {
+                    ifc_lite_bevy::ffi::set_box_select(app, enabled);
+                }
+            }
+        }
+
         /// Clear selection
         pub fn clear_selection(&self) {
             let guard = self.app.lock().unwrap();
@@ -1675,6 +3837,69 @@ mod bevy_viewer {
             }
         }
 
+        /// Set x-ray mode
+        ///
+        /// Everything except `ifc_type` (or the selection, if `None`) is drawn
+        /// translucent while `enabled`.
+        pub fn set_xray(&self, enabled: bool, ifc_type: Option<String>) {
+            let guard = self.app.lock().unwrap();
+            if let Some(app) = *guard {
+                let c_string = ifc_type.map(|t| std::ffi::CString::new(t).unwrap());
+                let ptr = c_string.as_ref().map_or(std::ptr::null(), |s| s.as_ptr());
+                unsafe {
+                    ifc_lite_bevy::ffi::set_xray(app, enabled, ptr);
+                }
+            }
+        }
+
+        /// Draw IfcSpace volumes translucent, or hide them
+        pub fn show_spaces(&self, show: bool) {
+            let guard = self.app.lock().unwrap();
+            if let Some(app) = *guard {
+                unsafe {
+                    ifc_lite_bevy::ffi::show_spaces(app, show);
+                }
+            }
+        }
+
+        /// Cut the model with the enabled planes (at most six) and the box
+        pub fn set_section(
+            &self,
+            planes: Vec<super::SectionPlane>,
+            section_box: super::SectionBox,
+        ) {
+            let guard = self.app.lock().unwrap();
+            if let Some(app) = *guard {
+                let values: Vec<f32> = planes
+                    .iter()
+                    .filter(|p| p.enabled)
+                    .take(super::MAX_SECTION_PLANES)
+                    .flat_map(|p| {
+                        [
+                            p.origin_x, p.origin_y, p.origin_z, p.normal_x, p.normal_y, p.normal_z,
+                        ]
+                    })
+                    .collect();
+                let b = &section_box;
+                let min = [b.min_x, b.min_y, b.min_z];
+                let max = [b.max_x, b.max_y, b.max_z];
+                let (min_ptr, max_ptr) = if b.enabled {
+                    (min.as_ptr(), max.as_ptr())
+                } else {
+                    (std::ptr::null(), std::ptr::null())
+                };
+                unsafe {
+                    ifc_lite_bevy::ffi::set_section(
+                        app,
+                        values.as_ptr(),
+                        values.len() / 6,
+                        min_ptr,
+                        max_ptr,
+                    );
+                }
+            }
+        }
+
         /// Reset camera to home view
         pub fn camera_home(&self) {
             let guard = self.app.lock().unwrap();
@@ -1695,6 +3920,50 @@ mod bevy_viewer {
             }
         }
 
+        /// Switch between perspective and orthographic projection
+        pub fn camera_set_projection(&self, orthographic: bool) {
+            let guard = self.app.lock().unwrap();
+            if let Some(app) = *guard {
+                unsafe {
+                    ifc_lite_bevy::ffi::camera_set_projection(app, orthographic);
+                }
+            }
+        }
+
+        /// Animate the camera to a standard view
+        pub fn camera_set_standard_view(&self, view: super::StandardView) {
+            let guard = self.app.lock().unwrap();
+            if let Some(app) = *guard {
+                unsafe {
+                    ifc_lite_bevy::ffi::camera_set_standard_view(app, view as u32);
+                }
+            }
+        }
+
+        /// Play a camera path from `IfcScene::export_path_json`; false if
+        /// it does not parse
+        pub fn play_path(&self, path_json: String, looping: bool) -> bool {
+            let guard = self.app.lock().unwrap();
+            if let Some(app) = *guard {
+                let Ok(c_string) = std::ffi::CString::new(path_json) else {
+                    return false;
+                };
+                unsafe { ifc_lite_bevy::ffi::camera_play_path(app, c_string.as_ptr(), looping) }
+            } else {
+                false
+            }
+        }
+
+        /// Stop the camera path being played
+        pub fn stop_path(&self) {
+            let guard = self.app.lock().unwrap();
+            if let Some(app) = *guard {
+                unsafe {
+                    ifc_lite_bevy::ffi::camera_stop_path(app);
+                }
+            }
+        }
+
         /// Focus camera on a specific entity
         pub fn camera_focus_entity(&self, entity_id: u64) {
             let guard = self.app.lock().unwrap();
@@ -1755,6 +4024,24 @@ mod bevy_viewer {
             }
         }
 
+        /// Set the shadow and ambient occlusion quality
+        pub fn set_graphics_quality(
+            &self,
+            shadows: super::GraphicsQuality,
+            ambient_occlusion: super::GraphicsQuality,
+        ) {
+            let guard = self.app.lock().unwrap();
+            if let Some(app) = *guard {
+                unsafe {
+                    ifc_lite_bevy::ffi::set_graphics_quality(
+                        app,
+                        shadows as u32,
+                        ambient_occlusion as u32,
+                    );
+                }
+            }
+        }
+
         /// Stop and release the Bevy app
         pub fn stop(&self) {
             let mut guard = self.app.lock().unwrap();
@@ -0,0 +1,481 @@
+//! Snapshots - offscreen rendering of the scene to PNG
+//!
+//! Draws the batched meshes with wgpu into a texture that never reaches a
+//! window, for hosts without a viewer: report generation, BCF snapshots on a
+//! server, thumbnails in a file browser. The camera follows the viewer's
+//! orbit conventions (Y up, 45° vertical field of view) so a snapshot of a
+//! [`CameraState`] frames what the viewer shows. Shading is a plain
+//! headlight, the picture is about the geometry rather than the lighting.
+
+use crate::{BatchedMeshData, CameraState};
+use nalgebra::{Matrix4, Orthographic3, Perspective3, Point3, Vector3};
+use std::borrow::Cow;
+use wgpu::util::DeviceExt;
+
+/// Vertical field of view of the viewers, in degrees
+const FIELD_OF_VIEW: f32 = 45.0;
+/// Largest edge of a snapshot, within what every adapter supports
+pub const MAX_SNAPSHOT_SIZE: u32 = 8192;
+/// Light grey, as the light theme of the viewer
+const BACKGROUND: wgpu::Color = wgpu::Color {
+    r: 0.95,
+    g: 0.95,
+    b: 0.95,
+    a: 1.0,
+};
+const COLOR_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth32Float;
+const SAMPLE_COUNT: u32 = 4;
+/// Floats per vertex of [`BatchedMeshData::vertices`]
+const VERTEX_FLOATS: usize = 10;
+
+const SHADER: &str = r#"
+struct Uniforms {
+    view_proj: mat4x4<f32>,
+    // Towards the viewer
+    light: vec4<f32>,
+};
+
+@group(0) @binding(0) var<uniform> uniforms: Uniforms;
+
+struct VertexOut {
+    @builtin(position) position: vec4<f32>,
+    @location(0) normal: vec3<f32>,
+    @location(1) color: vec4<f32>,
+};
+
+@vertex
+fn vs_main(
+    @location(0) position: vec3<f32>,
+    @location(1) normal: vec3<f32>,
+    @location(2) color: vec4<f32>,
+) -> VertexOut {
+    var out: VertexOut;
+    out.position = uniforms.view_proj * vec4<f32>(position, 1.0);
+    out.normal = normal;
+    out.color = color;
+    return out;
+}
+
+@fragment
+fn fs_main(in: VertexOut) -> @location(0) vec4<f32> {
+    var light = 1.0;
+    if (dot(in.normal, in.normal) > 0.0) {
+        light = 0.35 + 0.65 * abs(dot(normalize(in.normal), uniforms.light.xyz));
+    }
+    return vec4<f32>(in.color.rgb * light, in.color.a);
+}
+"#;
+
+/// Eye point of an orbit camera, as the viewers place it
+fn eye(camera: &CameraState) -> Point3<f32> {
+    let (azimuth, elevation) = (camera.azimuth, camera.elevation);
+    Point3::new(
+        camera.target_x + camera.distance * elevation.cos() * azimuth.sin(),
+        camera.target_y + camera.distance * elevation.sin(),
+        camera.target_z + camera.distance * elevation.cos() * azimuth.cos(),
+    )
+}
+
+/// View-projection matrix of `camera` with wgpu's 0..1 clip depth
+///
+/// `radius` bounds the scene around the target, so the depth range covers
+/// it without wasting precision.
+pub(crate) fn view_projection(camera: &CameraState, aspect: f32, radius: f32) -> Matrix4<f32> {
+    let target = Point3::new(camera.target_x, camera.target_y, camera.target_z);
+    let eye = eye(camera);
+    let view = Matrix4::look_at_rh(&eye, &target, &Vector3::y());
+    let distance = camera.distance.max(1e-3);
+    let far = distance + radius.max(distance) * 2.0;
+    let fov = FIELD_OF_VIEW.to_radians();
+    let projection = if camera.orthographic {
+        // As high as the perspective view at the target
+        let half_height = distance * (fov / 2.0).tan();
+        let half_width = half_height * aspect;
+        Orthographic3::new(
+            -half_width,
+            half_width,
+            -half_height,
+            half_height,
+            0.0f32.min(distance - far),
+            far,
+        )
+        .to_homogeneous()
+    } else {
+        let near = (distance * 1e-3).max(1e-3);
+        Perspective3::new(aspect, fov, near, far).to_homogeneous()
+    };
+    // nalgebra follows OpenGL's -1..1 depth
+    #[rustfmt::skip]
+    let depth_zero_to_one = Matrix4::new(
+        1.0, 0.0, 0.0, 0.0,
+        0.0, 1.0, 0.0, 0.0,
+        0.0, 0.0, 0.5, 0.5,
+        0.0, 0.0, 0.0, 1.0,
+    );
+    depth_zero_to_one * projection * view
+}
+
+/// Bytes per row of a texture copy, padded as wgpu requires
+pub(crate) fn padded_row_bytes(width: u32) -> u32 {
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    (width * 4).div_ceil(align) * align
+}
+
+/// Render `batches` as seen by `camera` into a PNG
+///
+/// Opaque batches are drawn first, translucent ones blended over them
+/// without writing depth. Every call sets up its own device; snapshots are
+/// occasional enough that keeping one alive is not worth it.
+pub(crate) fn render_png(
+    batches: &[BatchedMeshData],
+    camera: &CameraState,
+    width: u32,
+    height: u32,
+) -> Result<Vec<u8>, String> {
+    let width = width.clamp(1, MAX_SNAPSHOT_SIZE);
+    let height = height.clamp(1, MAX_SNAPSHOT_SIZE);
+    let pixels = pollster::block_on(render_rgba(batches, camera, width, height))?;
+
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+    writer
+        .write_image_data(&pixels)
+        .map_err(|e| e.to_string())?;
+    writer.finish().map_err(|e| e.to_string())?;
+    Ok(png)
+}
+
+async fn render_rgba(
+    batches: &[BatchedMeshData],
+    camera: &CameraState,
+    width: u32,
+    height: u32,
+) -> Result<Vec<u8>, String> {
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::from_env_or_default());
+    let adapter = instance
+        .request_adapter(&wgpu::RequestAdapterOptions::default())
+        .await
+        .map_err(|e| format!("No GPU adapter: {}", e))?;
+    let (device, queue) = adapter
+        .request_device(&wgpu::DeviceDescriptor::default())
+        .await
+        .map_err(|e| format!("No GPU device: {}", e))?;
+
+    // Scene radius around the target, for the depth range
+    let target = Vector3::new(camera.target_x, camera.target_y, camera.target_z);
+    let radius = batches
+        .iter()
+        .flat_map(|b| b.vertices.chunks_exact(VERTEX_FLOATS))
+        .map(|v| (Vector3::new(v[0], v[1], v[2]) - target).norm())
+        .fold(0.0f32, f32::max);
+    let view_proj = view_projection(camera, width as f32 / height as f32, radius);
+    let light = (eye(camera).coords - target)
+        .try_normalize(1e-6)
+        .unwrap_or_else(Vector3::y);
+    let uniforms: Vec<u8> = view_proj
+        .as_slice()
+        .iter()
+        .chain(&[light.x, light.y, light.z, 0.0])
+        .flat_map(|v| v.to_le_bytes())
+        .collect();
+    let uniform_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+        label: Some("snapshot uniforms"),
+        contents: &uniforms,
+        usage: wgpu::BufferUsages::UNIFORM,
+    });
+    let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("snapshot"),
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX_FRAGMENT,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
+    });
+    let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("snapshot"),
+        layout: &bind_group_layout,
+        entries: &[wgpu::BindGroupEntry {
+            binding: 0,
+            resource: uniform_buffer.as_entire_binding(),
+        }],
+    });
+
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("snapshot"),
+        source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(SHADER)),
+    });
+    let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("snapshot"),
+        bind_group_layouts: &[&bind_group_layout],
+        push_constant_ranges: &[],
+    });
+    let vertex_layout = wgpu::VertexBufferLayout {
+        array_stride: (VERTEX_FLOATS * 4) as wgpu::BufferAddress,
+        step_mode: wgpu::VertexStepMode::Vertex,
+        attributes: &wgpu::vertex_attr_array![
+            0 => Float32x3,
+            1 => Float32x3,
+            2 => Float32x4,
+        ],
+    };
+    let pipeline = |translucent: bool| {
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("snapshot"),
+            layout: Some(&layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: Some("vs_main"),
+                compilation_options: Default::default(),
+                buffers: std::slice::from_ref(&vertex_layout),
+            },
+            primitive: wgpu::PrimitiveState {
+                // IFC winding is not reliable enough to cull
+                cull_mode: None,
+                ..Default::default()
+            },
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: DEPTH_FORMAT,
+                depth_write_enabled: !translucent,
+                depth_compare: wgpu::CompareFunction::Less,
+                stencil: Default::default(),
+                bias: Default::default(),
+            }),
+            multisample: wgpu::MultisampleState {
+                count: SAMPLE_COUNT,
+                ..Default::default()
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: Some("fs_main"),
+                compilation_options: Default::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format: COLOR_FORMAT,
+                    blend: translucent.then_some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            multiview: None,
+            cache: None,
+        })
+    };
+    let (opaque, translucent) = (pipeline(false), pipeline(true));
+
+    let size = wgpu::Extent3d {
+        width,
+        height,
+        depth_or_array_layers: 1,
+    };
+    let texture = |label, format, sample_count, usage| {
+        device.create_texture(&wgpu::TextureDescriptor {
+            label: Some(label),
+            size,
+            mip_level_count: 1,
+            sample_count,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage,
+            view_formats: &[],
+        })
+    };
+    let multisampled = texture(
+        "snapshot msaa",
+        COLOR_FORMAT,
+        SAMPLE_COUNT,
+        wgpu::TextureUsages::RENDER_ATTACHMENT,
+    );
+    let resolved = texture(
+        "snapshot",
+        COLOR_FORMAT,
+        1,
+        wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+    );
+    let depth = texture(
+        "snapshot depth",
+        DEPTH_FORMAT,
+        SAMPLE_COUNT,
+        wgpu::TextureUsages::RENDER_ATTACHMENT,
+    );
+    let multisampled_view = multisampled.create_view(&Default::default());
+    let resolved_view = resolved.create_view(&Default::default());
+    let depth_view = depth.create_view(&Default::default());
+
+    let buffers: Vec<(bool, wgpu::Buffer, wgpu::Buffer, u32)> = batches
+        .iter()
+        .filter(|b| !b.indices.is_empty())
+        .map(|batch| {
+            let vertices: Vec<u8> = batch
+                .vertices
+                .iter()
+                .flat_map(|v| v.to_le_bytes())
+                .collect();
+            let indices: Vec<u8> = batch.indices.iter().flat_map(|i| i.to_le_bytes()).collect();
+            let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("snapshot vertices"),
+                contents: &vertices,
+                usage: wgpu::BufferUsages::VERTEX,
+            });
+            let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("snapshot indices"),
+                contents: &indices,
+                usage: wgpu::BufferUsages::INDEX,
+            });
+            (
+                batch.is_transparent,
+                vertex_buffer,
+                index_buffer,
+                batch.indices.len() as u32,
+            )
+        })
+        .collect();
+
+    let row_bytes = padded_row_bytes(width);
+    let readback = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("snapshot readback"),
+        size: row_bytes as u64 * height as u64,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+
+    let mut encoder = device.create_command_encoder(&Default::default());
+    {
+        let mut pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("snapshot"),
+            color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                view: &multisampled_view,
+                depth_slice: None,
+                resolve_target: Some(&resolved_view),
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(BACKGROUND),
+                    store: wgpu::StoreOp::Discard,
+                },
+            })],
+            depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                view: &depth_view,
+                depth_ops: Some(wgpu::Operations {
+                    load: wgpu::LoadOp::Clear(1.0),
+                    store: wgpu::StoreOp::Discard,
+                }),
+                stencil_ops: None,
+            }),
+            timestamp_writes: None,
+            occlusion_query_set: None,
+        });
+        pass.set_bind_group(0, &bind_group, &[]);
+        for pass_translucent in [false, true] {
+            pass.set_pipeline(if pass_translucent {
+                &translucent
+            } else {
+                &opaque
+            });
+            for (is_transparent, vertices, indices, count) in &buffers {
+                if *is_transparent != pass_translucent {
+                    continue;
+                }
+                pass.set_vertex_buffer(0, vertices.slice(..));
+                pass.set_index_buffer(indices.slice(..), wgpu::IndexFormat::Uint32);
+                pass.draw_indexed(0..*count, 0, 0..1);
+            }
+        }
+    }
+    encoder.copy_texture_to_buffer(
+        resolved.as_image_copy(),
+        wgpu::TexelCopyBufferInfo {
+            buffer: &readback,
+            layout: wgpu::TexelCopyBufferLayout {
+                offset: 0,
+                bytes_per_row: Some(row_bytes),
+                rows_per_image: Some(height),
+            },
+        },
+        size,
+    );
+    queue.submit([encoder.finish()]);
+
+    let slice = readback.slice(..);
+    let (sender, receiver) = std::sync::mpsc::channel();
+    slice.map_async(wgpu::MapMode::Read, move |result| {
+        let _ = sender.send(result);
+    });
+    device
+        .poll(wgpu::PollType::wait_indefinitely())
+        .map_err(|e| format!("{:?}", e))?;
+    receiver
+        .recv()
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?;
+
+    let mapped = slice.get_mapped_range();
+    let pixels = mapped
+        .chunks_exact(row_bytes as usize)
+        .flat_map(|row| &row[..width as usize * 4])
+        .copied()
+        .collect();
+    drop(mapped);
+    readback.unmap();
+    Ok(pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(m: &Matrix4<f32>, p: [f32; 3]) -> [f32; 3] {
+        let clip = m * nalgebra::Vector4::new(p[0], p[1], p[2], 1.0);
+        [clip.x / clip.w, clip.y / clip.w, clip.z / clip.w]
+    }
+
+    #[test]
+    fn test_target_projects_to_center() {
+        for orthographic in [false, true] {
+            let camera = CameraState {
+                target_x: 3.0,
+                target_y: 1.0,
+                distance: 20.0,
+                orthographic,
+                ..CameraState::default()
+            };
+            let m = view_projection(&camera, 1.5, 10.0);
+            let [x, y, z] = project(&m, [3.0, 1.0, 0.0]);
+            assert!(x.abs() < 1e-4 && y.abs() < 1e-4, "{} {}", x, y);
+            assert!((0.0..1.0).contains(&z), "depth {}", z);
+        }
+    }
+
+    #[test]
+    fn test_orthographic_matches_perspective_at_target() {
+        // A point at the top of the view at the target distance lands on the
+        // top edge in both projections
+        let camera = CameraState {
+            azimuth: 0.0,
+            elevation: 0.0,
+            distance: 10.0,
+            ..CameraState::default()
+        };
+        let half_height = 10.0 * (FIELD_OF_VIEW.to_radians() / 2.0).tan();
+        for orthographic in [false, true] {
+            let camera = CameraState {
+                orthographic,
+                ..camera.clone()
+            };
+            let m = view_projection(&camera, 1.0, 5.0);
+            let [_, y, _] = project(&m, [0.0, half_height, 0.0]);
+            assert!((y - 1.0).abs() < 1e-4, "y {}", y);
+        }
+    }
+
+    #[test]
+    fn test_padded_row_bytes() {
+        assert_eq!(padded_row_bytes(64), 256);
+        assert_eq!(padded_row_bytes(65), 512);
+        assert_eq!(padded_row_bytes(1), 256);
+    }
+}
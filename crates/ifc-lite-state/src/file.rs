@@ -0,0 +1,124 @@
+//! Filesystem store for native viewers
+
+use crate::{Callback, Result, StateStore, StoreError, Subscribers, SubscriptionId};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// Store keeping one file per key in a directory
+///
+/// Keys become file names, so they are limited to ASCII letters, digits,
+/// `_`, `-` and `.`. Writes go to a temporary file that is renamed into
+/// place, so readers never see a partial value.
+pub struct FileStore {
+    dir: PathBuf,
+    subscribers: Subscribers,
+}
+
+impl FileStore {
+    /// Open a store in `dir`, creating the directory if needed
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            subscribers: Subscribers::new(),
+        })
+    }
+
+    /// Directory holding the values
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, key: &str) -> Result<PathBuf> {
+        let valid = !key.is_empty()
+            && !key.starts_with('.')
+            && key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+        if valid {
+            Ok(self.dir.join(key))
+        } else {
+            Err(StoreError::InvalidKey(key.to_string()))
+        }
+    }
+}
+
+impl StateStore for FileStore {
+    fn get(&self, key: &str) -> Option<String> {
+        fs::read_to_string(self.path(key).ok()?).ok()
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<()> {
+        let path = self.path(key)?;
+        let temp = self.dir.join(format!(".{}.tmp", key));
+        fs::write(&temp, value)?;
+        fs::rename(&temp, &path)?;
+        self.subscribers.notify(key, Some(value));
+        Ok(())
+    }
+
+    fn remove(&self, key: &str) -> Result<()> {
+        match fs::remove_file(self.path(key)?) {
+            Ok(()) => {
+                self.subscribers.notify(key, None);
+                Ok(())
+            }
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn subscribe(&self, key: &str, callback: Callback) -> SubscriptionId {
+        self.subscribers.add(key, callback)
+    }
+
+    fn unsubscribe(&self, id: SubscriptionId) {
+        self.subscribers.remove(id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_store(name: &str) -> FileStore {
+        let dir =
+            std::env::temp_dir().join(format!("ifc-lite-state-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        FileStore::open(dir).unwrap()
+    }
+
+    #[test]
+    fn test_values_persist_across_instances() {
+        let store = temp_store("persist");
+        store.set("ifc_lite_camera", "{\"distance\":5}").unwrap();
+
+        let reopened = FileStore::open(store.dir()).unwrap();
+        assert_eq!(
+            reopened.get("ifc_lite_camera").as_deref(),
+            Some("{\"distance\":5}")
+        );
+
+        reopened.remove("ifc_lite_camera").unwrap();
+        reopened.remove("ifc_lite_camera").unwrap();
+        assert_eq!(store.get("ifc_lite_camera"), None);
+
+        let _ = fs::remove_dir_all(store.dir());
+    }
+
+    #[test]
+    fn test_rejects_path_keys() {
+        let store = temp_store("keys");
+        for key in ["", "../escape", "a/b", ".hidden"] {
+            assert!(matches!(
+                store.set(key, "x"),
+                Err(StoreError::InvalidKey(_))
+            ));
+            assert_eq!(store.get(key), None);
+        }
+
+        let _ = fs::remove_dir_all(store.dir());
+    }
+}
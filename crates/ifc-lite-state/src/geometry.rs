@@ -0,0 +1,285 @@
+//! Binary geometry format of the bridge
+//!
+//! The Yew UI hands tessellated meshes to the Bevy renderer, and caches
+//! them, in this layout rather than JSON so large models transfer without
+//! the overhead. Both sides go through [`encode_geometry`] and
+//! [`decode_geometry`], so they can't drift apart.
+//!
+//! All integers and floats are little endian:
+//! - u32: magic (0x49464342 = "IFCB"), u32: version (2), u32: mesh_count
+//! - per mesh:
+//!   - u64: entity_id
+//!   - u32 count + `f32[]` each for positions, normals
+//!   - u32 count + `u32[]` indices
+//!   - u32 count + `f32[]` vertex colors (absent in version 1)
+//!   - `f32[4]` color, `f32[16]` transform
+//!   - u8 length + utf8 entity_type, u8 length + utf8 name (0 if none)
+
+/// Header magic number
+const BINARY_MAGIC: u32 = 0x49464342; // "IFCB" in ASCII
+/// Newest format version; version 1 lacks vertex colors
+const BINARY_VERSION: u32 = 2;
+/// Smallest encoded mesh: id, four counts, color, transform and two lengths
+const MIN_MESH_BYTES: usize = 8 + 4 * 4 + 4 * 20 + 2;
+
+/// A mesh to encode, borrowed from the front-end's own mesh type
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GeometryMeshRef<'a> {
+    pub entity_id: u64,
+    pub positions: &'a [f32],
+    pub normals: &'a [f32],
+    pub indices: &'a [u32],
+    /// Per-vertex RGBA, empty if the mesh has none
+    pub colors: &'a [f32],
+    pub color: [f32; 4],
+    /// Column-major 4x4 matrix
+    pub transform: [f32; 16],
+    pub entity_type: &'a str,
+    pub name: Option<&'a str>,
+}
+
+/// A decoded mesh
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GeometryMesh {
+    pub entity_id: u64,
+    pub positions: Vec<f32>,
+    pub normals: Vec<f32>,
+    pub indices: Vec<u32>,
+    /// Per-vertex RGBA, empty if the mesh has none
+    pub colors: Vec<f32>,
+    pub color: [f32; 4],
+    /// Column-major 4x4 matrix
+    pub transform: [f32; 16],
+    pub entity_type: String,
+    pub name: Option<String>,
+}
+
+impl GeometryMesh {
+    /// Borrow the mesh for encoding
+    pub fn view(&self) -> GeometryMeshRef<'_> {
+        GeometryMeshRef {
+            entity_id: self.entity_id,
+            positions: &self.positions,
+            normals: &self.normals,
+            indices: &self.indices,
+            colors: &self.colors,
+            color: self.color,
+            transform: self.transform,
+            entity_type: &self.entity_type,
+            name: self.name.as_deref(),
+        }
+    }
+}
+
+/// Serialize meshes to the binary format
+///
+/// Type names and names longer than 255 bytes are cut at a char boundary.
+pub fn encode_geometry(meshes: &[GeometryMeshRef<'_>]) -> Vec<u8> {
+    let size = 12
+        + meshes
+            .iter()
+            .map(|m| {
+                MIN_MESH_BYTES
+                    + 4 * (m.positions.len() + m.normals.len() + m.indices.len() + m.colors.len())
+                    + m.entity_type.len().min(255)
+                    + m.name.map_or(0, |n| n.len().min(255))
+            })
+            .sum::<usize>();
+    let mut buf = Vec::with_capacity(size);
+
+    buf.extend_from_slice(&BINARY_MAGIC.to_le_bytes());
+    buf.extend_from_slice(&BINARY_VERSION.to_le_bytes());
+    buf.extend_from_slice(&(meshes.len() as u32).to_le_bytes());
+
+    for mesh in meshes {
+        buf.extend_from_slice(&mesh.entity_id.to_le_bytes());
+        write_f32s(&mut buf, mesh.positions, true);
+        write_f32s(&mut buf, mesh.normals, true);
+        buf.extend_from_slice(&(mesh.indices.len() as u32).to_le_bytes());
+        buf.extend(mesh.indices.iter().flat_map(|i| i.to_le_bytes()));
+        write_f32s(&mut buf, mesh.colors, true);
+        write_f32s(&mut buf, &mesh.color, false);
+        write_f32s(&mut buf, &mesh.transform, false);
+        write_short_str(&mut buf, mesh.entity_type);
+        write_short_str(&mut buf, mesh.name.unwrap_or(""));
+    }
+
+    buf
+}
+
+fn write_f32s(buf: &mut Vec<u8>, values: &[f32], with_len: bool) {
+    if with_len {
+        buf.extend_from_slice(&(values.len() as u32).to_le_bytes());
+    }
+    buf.extend(values.iter().flat_map(|v| v.to_le_bytes()));
+}
+
+fn write_short_str(buf: &mut Vec<u8>, value: &str) {
+    let mut len = value.len().min(255);
+    while !value.is_char_boundary(len) {
+        len -= 1;
+    }
+    buf.push(len as u8);
+    buf.extend_from_slice(&value.as_bytes()[..len]);
+}
+
+/// Parse meshes from the binary format (versions 1 and 2)
+///
+/// `None` on a wrong magic, an unknown version or truncated data.
+pub fn decode_geometry(data: &[u8]) -> Option<Vec<GeometryMesh>> {
+    let mut reader = BinaryReader { data, cursor: 0 };
+
+    if reader.u32()? != BINARY_MAGIC {
+        return None;
+    }
+    let version = reader.u32()?;
+    if !(1..=BINARY_VERSION).contains(&version) {
+        return None;
+    }
+
+    let mesh_count = reader.u32()? as usize;
+    // Bounds a bogus count
+    let mut meshes = Vec::with_capacity(mesh_count.min(data.len() / MIN_MESH_BYTES));
+    for _ in 0..mesh_count {
+        let entity_id = u64::from_le_bytes(reader.take(8)?.try_into().ok()?);
+        let len = reader.u32()? as usize;
+        let positions = reader.f32s(len)?;
+        let len = reader.u32()? as usize;
+        let normals = reader.f32s(len)?;
+        let len = reader.u32()? as usize;
+        let indices = reader
+            .take(len.checked_mul(4)?)?
+            .chunks_exact(4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+            .collect();
+        let colors = if version >= 2 {
+            let len = reader.u32()? as usize;
+            reader.f32s(len)?
+        } else {
+            Vec::new()
+        };
+        let color = reader.f32s(4)?.try_into().ok()?;
+        let transform = reader.f32s(16)?.try_into().ok()?;
+        let entity_type = reader.short_str()?;
+        let name = Some(reader.short_str()?).filter(|n| !n.is_empty());
+
+        meshes.push(GeometryMesh {
+            entity_id,
+            positions,
+            normals,
+            indices,
+            colors,
+            color,
+            transform,
+            entity_type,
+            name,
+        });
+    }
+
+    Some(meshes)
+}
+
+/// Cursor over binary geometry
+struct BinaryReader<'a> {
+    data: &'a [u8],
+    cursor: usize,
+}
+
+impl<'a> BinaryReader<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        let slice = self.data.get(self.cursor..self.cursor.checked_add(n)?)?;
+        self.cursor += n;
+        Some(slice)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?))
+    }
+
+    /// `count` floats in one bulk copy
+    fn f32s(&mut self, count: usize) -> Option<Vec<f32>> {
+        Some(
+            self.take(count.checked_mul(4)?)?
+                .chunks_exact(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect(),
+        )
+    }
+
+    fn short_str(&mut self) -> Option<String> {
+        let len = self.take(1)?[0] as usize;
+        Some(String::from_utf8_lossy(self.take(len)?).into_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triangle(entity_id: u64, name: Option<&str>) -> GeometryMesh {
+        let mut transform = [0.0; 16];
+        for i in 0..4 {
+            transform[i * 5] = 1.0;
+        }
+        transform[12] = 5.0;
+        GeometryMesh {
+            entity_id,
+            positions: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0],
+            normals: vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0],
+            indices: vec![0, 1, 2],
+            colors: Vec::new(),
+            color: [0.5, 0.6, 0.7, 1.0],
+            transform,
+            entity_type: "IFCWALL".to_string(),
+            name: name.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut colored = triangle(2, None);
+        colored.colors = vec![1.0, 0.0, 0.0, 1.0].repeat(3);
+        let meshes = vec![triangle(1, Some("Wall")), colored];
+        let refs: Vec<_> = meshes.iter().map(GeometryMesh::view).collect();
+
+        let data = encode_geometry(&refs);
+        assert_eq!(decode_geometry(&data), Some(meshes));
+        assert_eq!(decode_geometry(&encode_geometry(&[])), Some(Vec::new()));
+    }
+
+    #[test]
+    fn test_truncated_and_foreign_data() {
+        let mesh = triangle(1, Some("Wall"));
+        let data = encode_geometry(&[mesh.view()]);
+        for len in 0..data.len() {
+            assert_eq!(decode_geometry(&data[..len]), None, "cut at {}", len);
+        }
+
+        let mut foreign = data.clone();
+        foreign[0] ^= 0xff;
+        assert_eq!(decode_geometry(&foreign), None);
+        let mut future = data;
+        future[4..8].copy_from_slice(&(BINARY_VERSION + 1).to_le_bytes());
+        assert_eq!(decode_geometry(&future), None);
+
+        // A huge count with no meshes behind it
+        let mut bogus = encode_geometry(&[]);
+        bogus[8..12].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(decode_geometry(&bogus), None);
+    }
+
+    #[test]
+    fn test_long_names_cut_at_char_boundary() {
+        // 2-byte chars put byte 255 inside a char
+        let name = "ä".repeat(200);
+        let entity_type = "X".repeat(300);
+        let mut mesh = triangle(1, Some(&name));
+        mesh.entity_type = entity_type.clone();
+
+        let decoded = decode_geometry(&encode_geometry(&[mesh.view()])).unwrap();
+        let decoded = &decoded[0];
+        assert_eq!(decoded.name.as_deref(), Some(&name[..254]));
+        assert_eq!(decoded.entity_type, entity_type[..255]);
+        assert_eq!(decoded.positions, mesh.positions);
+    }
+}
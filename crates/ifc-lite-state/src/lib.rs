@@ -0,0 +1,172 @@
+//! IFC-Lite State - Pluggable key-value storage for viewer bridge state
+//!
+//! The Yew UI and the Bevy renderer exchange selection, camera, section and
+//! overlay state as JSON strings under well-known keys. [`StateStore`] hides
+//! where those strings live:
+//!
+//! - [`MemoryStore`] - in-process map, for the unified build and tests
+//! - [`FileStore`] - one file per key, for native viewers (not on wasm)
+//! - `LocalStorageStore` - browser `localStorage` (feature `web`)
+//! - `IndexedDbStore` - browser IndexedDB with an in-memory cache (feature `web`)
+//!
+//! Meshes cross the bridge in the binary format of [`geometry`], which both
+//! front-ends encode and decode through this crate.
+//!
+//! Parsed models are cached separately, as binary records keyed by content
+//! hash: [`model_cache`] defines the records and their eviction, and
+//! `IndexedDbModelCache` (feature `web`) persists them in the browser.
+//!
+//! Front-ends read and write through [`global`], which defaults to
+//! `localStorage` in the browser and to a [`MemoryStore`] elsewhere. Install
+//! another store with [`set_global`] before the first access: the unified
+//! build installs a [`MemoryStore`], native hosts a [`FileStore`].
+
+pub mod geometry;
+mod memory;
+pub mod model_cache;
+mod subscribers;
+
+#[cfg(not(target_arch = "wasm32"))]
+mod file;
+#[cfg(feature = "web")]
+mod web;
+#[cfg(feature = "web")]
+mod web_cache;
+
+pub use geometry::{decode_geometry, encode_geometry, GeometryMesh, GeometryMeshRef};
+pub use memory::MemoryStore;
+pub use model_cache::{CacheLimits, CachedModel};
+pub use subscribers::Subscribers;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use file::FileStore;
+#[cfg(feature = "web")]
+pub use web::{IndexedDbStore, LocalStorageStore};
+#[cfg(feature = "web")]
+pub use web_cache::IndexedDbModelCache;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::sync::{Arc, OnceLock};
+use thiserror::Error;
+
+/// Errors raised by state stores
+#[derive(Error, Debug)]
+pub enum StoreError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Invalid key: {0}")]
+    InvalidKey(String),
+
+    #[error("Storage unavailable: {0}")]
+    Unavailable(String),
+}
+
+pub type Result<T> = std::result::Result<T, StoreError>;
+
+/// Called with the key and its new value (`None` once removed)
+pub type Callback = Arc<dyn Fn(&str, Option<&str>) + Send + Sync>;
+
+/// Handle returned by [`StateStore::subscribe`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(pub u64);
+
+/// String key-value store with change notification
+///
+/// Subscribers are notified of writes made through the same store instance.
+/// Writes from other processes or wasm instances (another tab, or the Bevy
+/// canvas in the split build) are not observed and still have to be polled.
+pub trait StateStore: Send + Sync {
+    /// Value stored under `key`
+    fn get(&self, key: &str) -> Option<String>;
+
+    /// Store `value` under `key`, replacing any previous value
+    fn set(&self, key: &str, value: &str) -> Result<()>;
+
+    /// Remove `key`; removing a missing key is not an error
+    fn remove(&self, key: &str) -> Result<()>;
+
+    /// Call `callback` whenever `key` is set or removed
+    fn subscribe(&self, key: &str, callback: Callback) -> SubscriptionId;
+
+    /// Stop notifying a subscriber
+    fn unsubscribe(&self, id: SubscriptionId);
+
+    /// Whether every write goes through this instance, so subscribers see
+    /// all changes and nothing needs polling
+    fn observes_all_writes(&self) -> bool {
+        false
+    }
+}
+
+/// Read and deserialize a JSON value; missing or malformed values are `None`
+pub fn get_json<T: DeserializeOwned>(store: &dyn StateStore, key: &str) -> Option<T> {
+    serde_json::from_str(&store.get(key)?).ok()
+}
+
+/// Serialize and store a JSON value
+pub fn set_json<T: Serialize + ?Sized>(store: &dyn StateStore, key: &str, value: &T) -> Result<()> {
+    store.set(key, &serde_json::to_string(value)?)
+}
+
+static GLOBAL: OnceLock<Arc<dyn StateStore>> = OnceLock::new();
+
+/// Process-wide store shared by all front-ends
+pub fn global() -> &'static dyn StateStore {
+    GLOBAL.get_or_init(default_store).as_ref()
+}
+
+/// Install the process-wide store
+///
+/// Fails, handing the store back, once [`global`] has been used.
+pub fn set_global(store: Arc<dyn StateStore>) -> std::result::Result<(), Arc<dyn StateStore>> {
+    GLOBAL.set(store)
+}
+
+#[cfg(all(feature = "web", target_arch = "wasm32"))]
+fn default_store() -> Arc<dyn StateStore> {
+    Arc::new(LocalStorageStore::new())
+}
+
+#[cfg(not(all(feature = "web", target_arch = "wasm32")))]
+fn default_store() -> Arc<dyn StateStore> {
+    Arc::new(MemoryStore::new())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Camera {
+        distance: f32,
+        target: [f32; 3],
+    }
+
+    #[test]
+    fn test_json_roundtrip() {
+        let store = MemoryStore::new();
+        let camera = Camera {
+            distance: 12.5,
+            target: [1.0, 2.0, 3.0],
+        };
+        set_json(&store, "camera", &camera).unwrap();
+        assert_eq!(get_json::<Camera>(&store, "camera"), Some(camera));
+
+        store.set("camera", "not json").unwrap();
+        assert_eq!(get_json::<Camera>(&store, "camera"), None);
+        assert_eq!(get_json::<Camera>(&store, "missing"), None);
+    }
+
+    #[test]
+    fn test_global_defaults_to_memory() {
+        global().set("ifc_lite_test_global", "1").unwrap();
+        assert_eq!(global().get("ifc_lite_test_global").as_deref(), Some("1"));
+        assert!(set_global(Arc::new(MemoryStore::new())).is_err());
+    }
+}
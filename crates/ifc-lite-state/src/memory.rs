@@ -0,0 +1,126 @@
+//! In-memory store
+
+use crate::{Callback, Result, StateStore, Subscribers, SubscriptionId};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Store backed by a map in process memory
+///
+/// Used by the unified build, where UI and renderer share one wasm instance
+/// and need no DOM round trip, and by tests.
+#[derive(Default)]
+pub struct MemoryStore {
+    values: Mutex<HashMap<String, String>>,
+    subscribers: Subscribers,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl StateStore for MemoryStore {
+    fn get(&self, key: &str) -> Option<String> {
+        self.values.lock().ok()?.get(key).cloned()
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<()> {
+        if let Ok(mut values) = self.values.lock() {
+            values.insert(key.to_string(), value.to_string());
+        }
+        self.subscribers.notify(key, Some(value));
+        Ok(())
+    }
+
+    fn remove(&self, key: &str) -> Result<()> {
+        let removed = match self.values.lock() {
+            Ok(mut values) => values.remove(key).is_some(),
+            Err(_) => false,
+        };
+        if removed {
+            self.subscribers.notify(key, None);
+        }
+        Ok(())
+    }
+
+    fn subscribe(&self, key: &str, callback: Callback) -> SubscriptionId {
+        self.subscribers.add(key, callback)
+    }
+
+    fn unsubscribe(&self, id: SubscriptionId) {
+        self.subscribers.remove(id);
+    }
+
+    fn observes_all_writes(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_set_get_remove() {
+        let store = MemoryStore::new();
+        assert_eq!(store.get("a"), None);
+
+        store.set("a", "1").unwrap();
+        store.set("a", "2").unwrap();
+        assert_eq!(store.get("a").as_deref(), Some("2"));
+
+        store.remove("a").unwrap();
+        store.remove("a").unwrap();
+        assert_eq!(store.get("a"), None);
+    }
+
+    #[test]
+    fn test_subscribe_notifies_matching_key() {
+        let store = MemoryStore::new();
+        assert!(store.observes_all_writes());
+        let seen = Arc::new(Mutex::new(Vec::new()));
+
+        let sink = seen.clone();
+        let id = store.subscribe(
+            "selection",
+            Arc::new(move |key, value| {
+                sink.lock()
+                    .unwrap()
+                    .push((key.to_string(), value.map(str::to_string)));
+            }),
+        );
+
+        store.set("selection", "[1]").unwrap();
+        store.set("camera", "{}").unwrap();
+        store.remove("selection").unwrap();
+        store.unsubscribe(id);
+        store.set("selection", "[2]").unwrap();
+
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![
+                ("selection".to_string(), Some("[1]".to_string())),
+                ("selection".to_string(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_callback_may_write_to_store() {
+        let store = Arc::new(MemoryStore::new());
+        let inner = store.clone();
+        store.subscribe(
+            "focus",
+            Arc::new(move |_, value| {
+                if value.is_some() {
+                    let _ = inner.set("focus_seen", "1");
+                }
+            }),
+        );
+
+        store.set("focus", "42").unwrap();
+        assert_eq!(store.get("focus_seen").as_deref(), Some("1"));
+    }
+}
@@ -0,0 +1,226 @@
+//! Model cache records and eviction
+//!
+//! Parsed models are cached by content hash so reopening a file skips
+//! parsing and tessellation. A record holds the geometry in the binary
+//! bridge format, ready to hand to the renderer as-is, next to the UI
+//! metadata of the front-end that wrote it. Both are opaque bytes here.
+//!
+//! The storage backend lives elsewhere (`IndexedDbModelCache` in the
+//! browser); this module only defines the record layout and the
+//! least-recently-used bookkeeping they share.
+
+use serde::{Deserialize, Serialize};
+
+/// Record header magic number
+const RECORD_MAGIC: u32 = 0x49464d43; // "IFMC" in ASCII
+
+/// One cached model
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CachedModel {
+    /// Data version of the writer; records of another version are stale
+    pub version: u32,
+    /// Geometry in the binary bridge format
+    pub geometry: Vec<u8>,
+    /// Front-end metadata (entities, spatial tree, ...)
+    pub metadata: Vec<u8>,
+}
+
+impl CachedModel {
+    /// Serialize to the record layout
+    ///
+    /// Format:
+    /// - u32: magic (0x49464d43 = "IFMC")
+    /// - u32: version
+    /// - u32: geometry_len, `u8[]`: geometry
+    /// - u32: metadata_len, `u8[]`: metadata
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(16 + self.geometry.len() + self.metadata.len());
+        buf.extend_from_slice(&RECORD_MAGIC.to_le_bytes());
+        buf.extend_from_slice(&self.version.to_le_bytes());
+        for section in [&self.geometry, &self.metadata] {
+            buf.extend_from_slice(&(section.len() as u32).to_le_bytes());
+            buf.extend_from_slice(section);
+        }
+        buf
+    }
+
+    /// Parse a record; `None` if it is truncated or not a record
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let mut cursor = 0;
+        let mut read = |n: usize| {
+            let slice = data.get(cursor..cursor + n)?;
+            cursor += n;
+            Some(slice)
+        };
+        let mut read_u32 = || Some(u32::from_le_bytes(read(4)?.try_into().ok()?));
+
+        if read_u32()? != RECORD_MAGIC {
+            return None;
+        }
+        let version = read_u32()?;
+        let geometry_len = read_u32()? as usize;
+        let geometry = read(geometry_len)?.to_vec();
+        let metadata_len = u32::from_le_bytes(read(4)?.try_into().ok()?) as usize;
+        let metadata = read(metadata_len)?.to_vec();
+
+        Some(Self {
+            version,
+            geometry,
+            metadata,
+        })
+    }
+}
+
+/// Bounds of the model cache
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CacheLimits {
+    /// Total size of all records in bytes
+    pub max_bytes: u64,
+    /// Number of cached models
+    pub max_entries: usize,
+}
+
+impl Default for CacheLimits {
+    fn default() -> Self {
+        Self {
+            max_bytes: 512 * 1024 * 1024,
+            max_entries: 16,
+        }
+    }
+}
+
+/// Bookkeeping entry for one record
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub key: String,
+    /// Encoded record size in bytes
+    pub size: u64,
+    /// Last read or write, in milliseconds since the epoch
+    pub last_used: f64,
+}
+
+/// Sizes and access times of all cached records, stored next to them
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct CacheIndex {
+    pub entries: Vec<CacheEntry>,
+}
+
+impl CacheIndex {
+    /// Total size of all records in bytes
+    pub fn total_bytes(&self) -> u64 {
+        self.entries.iter().map(|e| e.size).sum()
+    }
+
+    pub fn contains(&self, key: &str) -> bool {
+        self.entries.iter().any(|e| e.key == key)
+    }
+
+    /// Mark a record as used; false if it is not indexed
+    pub fn touch(&mut self, key: &str, now: f64) -> bool {
+        match self.entries.iter_mut().find(|e| e.key == key) {
+            Some(entry) => {
+                entry.last_used = now;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Add or replace a record, evicting the least recently used ones until
+    /// the limits hold again
+    ///
+    /// Returns the keys of the evicted records, or `None` (leaving the index
+    /// unchanged) when the record alone exceeds the size limit.
+    pub fn insert(
+        &mut self,
+        key: &str,
+        size: u64,
+        now: f64,
+        limits: CacheLimits,
+    ) -> Option<Vec<String>> {
+        if size > limits.max_bytes || limits.max_entries == 0 {
+            return None;
+        }
+        self.remove(key);
+
+        self.entries
+            .sort_by(|a, b| a.last_used.total_cmp(&b.last_used));
+        let mut total = self.total_bytes();
+        let mut evicted = Vec::new();
+        while !self.entries.is_empty()
+            && (total + size > limits.max_bytes || self.entries.len() >= limits.max_entries)
+        {
+            let entry = self.entries.remove(0);
+            total -= entry.size;
+            evicted.push(entry.key);
+        }
+
+        self.entries.push(CacheEntry {
+            key: key.to_string(),
+            size,
+            last_used: now,
+        });
+        Some(evicted)
+    }
+
+    /// Forget a record; false if it was not indexed
+    pub fn remove(&mut self, key: &str) -> bool {
+        let len = self.entries.len();
+        self.entries.retain(|e| e.key != key);
+        self.entries.len() != len
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_roundtrip() {
+        let model = CachedModel {
+            version: 3,
+            geometry: vec![1, 2, 3, 4, 5],
+            metadata: b"{\"entities\":[]}".to_vec(),
+        };
+        let bytes = model.encode();
+        assert_eq!(CachedModel::decode(&bytes), Some(model));
+
+        assert_eq!(CachedModel::decode(&bytes[..bytes.len() - 1]), None);
+        assert_eq!(CachedModel::decode(b"not a record at all"), None);
+    }
+
+    #[test]
+    fn test_lru_eviction() {
+        let limits = CacheLimits {
+            max_bytes: 100,
+            max_entries: 3,
+        };
+        let mut index = CacheIndex::default();
+        assert_eq!(index.insert("a", 40, 1.0, limits), Some(vec![]));
+        assert_eq!(index.insert("b", 40, 2.0, limits), Some(vec![]));
+        assert!(index.touch("a", 3.0));
+
+        // Over the byte limit: "b" is the least recently used
+        assert_eq!(
+            index.insert("c", 40, 4.0, limits),
+            Some(vec!["b".to_string()])
+        );
+        assert_eq!(index.total_bytes(), 80);
+
+        // Over the entry limit
+        assert_eq!(index.insert("d", 10, 5.0, limits), Some(vec![]));
+        assert_eq!(
+            index.insert("e", 10, 6.0, limits),
+            Some(vec!["a".to_string()])
+        );
+        assert!(!index.contains("a") && index.contains("e"));
+
+        // Replacing a record does not evict it
+        assert_eq!(index.insert("e", 20, 7.0, limits), Some(vec![]));
+        assert_eq!(index.entries.len(), 3);
+
+        // Too large to cache at all
+        assert_eq!(index.insert("f", 101, 8.0, limits), None);
+        assert!(!index.contains("f"));
+    }
+}
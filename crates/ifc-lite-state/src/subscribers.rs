@@ -0,0 +1,52 @@
+//! Subscriber registry shared by the store implementations
+
+use crate::{Callback, SubscriptionId};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// Callbacks registered per key
+#[derive(Default)]
+pub struct Subscribers {
+    next_id: AtomicU64,
+    entries: Mutex<Vec<(SubscriptionId, String, Callback)>>,
+}
+
+impl Subscribers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a callback for `key`
+    pub fn add(&self, key: &str, callback: Callback) -> SubscriptionId {
+        let id = SubscriptionId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.push((id, key.to_string(), callback));
+        }
+        id
+    }
+
+    /// Drop a callback
+    pub fn remove(&self, id: SubscriptionId) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.retain(|(entry_id, _, _)| *entry_id != id);
+        }
+    }
+
+    /// Call every subscriber of `key`
+    ///
+    /// Callbacks run outside the lock, so they may write to the store or
+    /// (un)subscribe themselves.
+    pub fn notify(&self, key: &str, value: Option<&str>) {
+        let callbacks: Vec<Callback> = match self.entries.lock() {
+            Ok(entries) => entries
+                .iter()
+                .filter(|(_, entry_key, _)| entry_key == key)
+                .map(|(_, _, callback)| callback.clone())
+                .collect(),
+            Err(_) => return,
+        };
+        for callback in callbacks {
+            callback(key, value);
+        }
+    }
+}
@@ -0,0 +1,270 @@
+//! Browser stores - localStorage and IndexedDB
+
+use crate::{Callback, Result, StateStore, StoreError, Subscribers, SubscriptionId};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{IdbDatabase, IdbRequest, IdbTransactionMode};
+
+/// Object store holding the values inside each IndexedDB database
+const OBJECT_STORE: &str = "state";
+const DB_VERSION: u32 = 1;
+
+pub(crate) fn js_error(error: JsValue) -> StoreError {
+    StoreError::Unavailable(format!("{:?}", error))
+}
+
+/// Store backed by `window.localStorage`
+///
+/// Values are shared with every wasm instance and script on the page, which
+/// is what the split Yew/Bevy build relies on. Subscribers only see writes
+/// made through this instance.
+#[derive(Default)]
+pub struct LocalStorageStore {
+    subscribers: Subscribers,
+}
+
+impl LocalStorageStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn storage() -> Result<web_sys::Storage> {
+        web_sys::window()
+            .ok_or_else(|| StoreError::Unavailable("no window".to_string()))?
+            .local_storage()
+            .map_err(js_error)?
+            .ok_or_else(|| StoreError::Unavailable("localStorage disabled".to_string()))
+    }
+}
+
+impl StateStore for LocalStorageStore {
+    fn get(&self, key: &str) -> Option<String> {
+        Self::storage().ok()?.get_item(key).ok()?
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<()> {
+        Self::storage()?.set_item(key, value).map_err(js_error)?;
+        self.subscribers.notify(key, Some(value));
+        Ok(())
+    }
+
+    fn remove(&self, key: &str) -> Result<()> {
+        Self::storage()?.remove_item(key).map_err(js_error)?;
+        self.subscribers.notify(key, None);
+        Ok(())
+    }
+
+    fn subscribe(&self, key: &str, callback: Callback) -> SubscriptionId {
+        self.subscribers.add(key, callback)
+    }
+
+    fn unsubscribe(&self, id: SubscriptionId) {
+        self.subscribers.remove(id);
+    }
+}
+
+/// Store persisted in an IndexedDB database
+///
+/// IndexedDB is asynchronous while [`StateStore`] is not, so all entries are
+/// loaded into memory by [`IndexedDbStore::open`]. Reads are served from that
+/// cache; writes update it immediately and are persisted in the background,
+/// one after the other in the order they were made.
+/// Unlike localStorage there is no 5 MB quota, so larger state fits.
+pub struct IndexedDbStore {
+    db_name: String,
+    cache: Mutex<HashMap<String, String>>,
+    writes: Arc<Mutex<WriteQueue>>,
+    subscribers: Subscribers,
+}
+
+/// Changes waiting to be persisted
+#[derive(Default)]
+struct WriteQueue {
+    /// Key and new value (`None` to delete), oldest first
+    pending: VecDeque<(String, Option<String>)>,
+    /// Whether a task is writing the queue out
+    draining: bool,
+}
+
+impl IndexedDbStore {
+    /// Open (or create) the database and load its entries
+    pub async fn open(db_name: &str) -> Result<Self> {
+        let db = open_database(db_name, DB_VERSION, &[OBJECT_STORE]).await?;
+        let entries = load_entries(&db).await;
+        db.close();
+        Ok(Self {
+            db_name: db_name.to_string(),
+            cache: Mutex::new(entries?),
+            writes: Arc::default(),
+            subscribers: Subscribers::new(),
+        })
+    }
+
+    /// Persist one change without blocking the caller
+    ///
+    /// Changes are queued and written by a single task, so a slow write can't
+    /// land after a newer one for the same key.
+    fn persist(&self, key: &str, value: Option<&str>) {
+        let Ok(mut writes) = self.writes.lock() else {
+            return;
+        };
+        writes
+            .pending
+            .push_back((key.to_string(), value.map(str::to_string)));
+        if !writes.draining {
+            writes.draining = true;
+            wasm_bindgen_futures::spawn_local(drain_writes(
+                self.db_name.clone(),
+                Arc::clone(&self.writes),
+            ));
+        }
+    }
+}
+
+/// Write queued changes in order, through one connection, until the queue
+/// is empty
+async fn drain_writes(db_name: String, writes: Arc<Mutex<WriteQueue>>) {
+    let db = open_database(&db_name, DB_VERSION, &[OBJECT_STORE]).await;
+    loop {
+        let Some((key, value)) = writes.lock().ok().and_then(|mut writes| {
+            let next = writes.pending.pop_front();
+            writes.draining = next.is_some();
+            next
+        }) else {
+            break;
+        };
+        let result = match &db {
+            Ok(db) => write_entry(db, &key, value.as_deref()).await,
+            Err(e) => Err(StoreError::Unavailable(e.to_string())),
+        };
+        if let Err(e) = result {
+            web_sys::console::warn_1(
+                &format!("[State] IndexedDB write of {} failed: {}", key, e).into(),
+            );
+        }
+    }
+    if let Ok(db) = db {
+        db.close();
+    }
+}
+
+impl StateStore for IndexedDbStore {
+    fn get(&self, key: &str) -> Option<String> {
+        self.cache.lock().ok()?.get(key).cloned()
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<()> {
+        if let Ok(mut cache) = self.cache.lock() {
+            cache.insert(key.to_string(), value.to_string());
+        }
+        self.persist(key, Some(value));
+        self.subscribers.notify(key, Some(value));
+        Ok(())
+    }
+
+    fn remove(&self, key: &str) -> Result<()> {
+        let removed = match self.cache.lock() {
+            Ok(mut cache) => cache.remove(key).is_some(),
+            Err(_) => false,
+        };
+        if removed {
+            self.persist(key, None);
+            self.subscribers.notify(key, None);
+        }
+        Ok(())
+    }
+
+    fn subscribe(&self, key: &str, callback: Callback) -> SubscriptionId {
+        self.subscribers.add(key, callback)
+    }
+
+    fn unsubscribe(&self, id: SubscriptionId) {
+        self.subscribers.remove(id);
+    }
+}
+
+/// Resolve once an IndexedDB request succeeds, with its result
+///
+/// The handlers are attached immediately, before anything is awaited, so a
+/// request that completes while another one is being awaited is not missed.
+pub(crate) fn request_result(
+    request: &IdbRequest,
+) -> impl std::future::Future<Output = Result<JsValue>> {
+    let promise = js_sys::Promise::new(&mut |resolve, reject| {
+        let source = request.clone();
+        let on_success = Closure::once_into_js(move |_: web_sys::Event| {
+            let result = source.result().unwrap_or(JsValue::UNDEFINED);
+            let _ = resolve.call1(&JsValue::UNDEFINED, &result);
+        });
+        let on_error = Closure::once_into_js(move |event: web_sys::Event| {
+            let _ = reject.call1(&JsValue::UNDEFINED, &event);
+        });
+        request.set_onsuccess(Some(on_success.unchecked_ref()));
+        request.set_onerror(Some(on_error.unchecked_ref()));
+    });
+    let future = JsFuture::from(promise);
+    async move { future.await.map_err(js_error) }
+}
+
+/// Open a database, creating missing object stores on upgrade
+pub(crate) async fn open_database(
+    db_name: &str,
+    version: u32,
+    object_stores: &'static [&'static str],
+) -> Result<IdbDatabase> {
+    let factory = web_sys::window()
+        .ok_or_else(|| StoreError::Unavailable("no window".to_string()))?
+        .indexed_db()
+        .map_err(js_error)?
+        .ok_or_else(|| StoreError::Unavailable("IndexedDB disabled".to_string()))?;
+    let request = factory.open_with_u32(db_name, version).map_err(js_error)?;
+
+    let source = request.clone();
+    let on_upgrade = Closure::once_into_js(move |_: web_sys::Event| {
+        if let Ok(db) = source.result() {
+            let db = db.unchecked_into::<IdbDatabase>();
+            for name in object_stores {
+                if !db.object_store_names().contains(name) {
+                    let _ = db.create_object_store(name);
+                }
+            }
+        }
+    });
+    request.set_onupgradeneeded(Some(on_upgrade.unchecked_ref()));
+
+    Ok(request_result(&request).await?.unchecked_into())
+}
+
+async fn load_entries(db: &IdbDatabase) -> Result<HashMap<String, String>> {
+    let store = db
+        .transaction_with_str(OBJECT_STORE)
+        .and_then(|tx| tx.object_store(OBJECT_STORE))
+        .map_err(js_error)?;
+    let keys = request_result(&store.get_all_keys().map_err(js_error)?);
+    let values = request_result(&store.get_all().map_err(js_error)?);
+    let keys: js_sys::Array = keys.await?.unchecked_into();
+    let values: js_sys::Array = values.await?.unchecked_into();
+
+    Ok(keys
+        .iter()
+        .zip(values.iter())
+        .filter_map(|(key, value)| Some((key.as_string()?, value.as_string()?)))
+        .collect())
+}
+
+async fn write_entry(db: &IdbDatabase, key: &str, value: Option<&str>) -> Result<()> {
+    let store = db
+        .transaction_with_str_and_mode(OBJECT_STORE, IdbTransactionMode::Readwrite)
+        .and_then(|tx| tx.object_store(OBJECT_STORE))
+        .map_err(js_error)?;
+    let key = JsValue::from_str(key);
+    let request = match value {
+        Some(value) => store.put_with_key(&JsValue::from_str(value), &key),
+        None => store.delete(&key),
+    }
+    .map_err(js_error)?;
+    request_result(&request).await.map(|_| ())
+}
@@ -0,0 +1,175 @@
+//! Browser model cache - IndexedDB
+//!
+//! localStorage caps out at a few megabytes and only holds strings, so parsed
+//! models go to IndexedDB as binary records. Every operation opens the
+//! database and closes it again, like [`crate::IndexedDbStore`] writes do, so
+//! several tabs can share the cache.
+
+use crate::model_cache::{CacheIndex, CacheLimits, CachedModel};
+use crate::web::{js_error, open_database, request_result};
+use crate::{Result, StoreError};
+use js_sys::Uint8Array;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{IdbDatabase, IdbObjectStore, IdbTransactionMode};
+
+/// Encoded records by key
+const MODELS_STORE: &str = "models";
+/// The [`CacheIndex`] as JSON, under [`INDEX_KEY`]
+const INDEX_STORE: &str = "index";
+const INDEX_KEY: &str = "index";
+const DB_VERSION: u32 = 1;
+
+/// Parsed models in an IndexedDB database, keyed by content hash
+///
+/// Records written with another `version` are treated as misses and dropped,
+/// so bumping the version invalidates everything cached by older builds.
+/// The least recently used records are evicted once [`CacheLimits`] are hit.
+pub struct IndexedDbModelCache {
+    db_name: String,
+    version: u32,
+    limits: CacheLimits,
+}
+
+impl IndexedDbModelCache {
+    pub fn new(db_name: &str, version: u32, limits: CacheLimits) -> Self {
+        Self {
+            db_name: db_name.to_string(),
+            version,
+            limits,
+        }
+    }
+
+    /// Cached model for `key`, if present and of the current version
+    pub async fn get(&self, key: &str) -> Result<Option<CachedModel>> {
+        let db = self.open().await?;
+        let result = self.get_in(&db, key).await;
+        db.close();
+        result
+    }
+
+    /// Cache a model, evicting older ones as needed
+    ///
+    /// Models larger than the whole cache are skipped silently.
+    pub async fn put(&self, key: &str, geometry: Vec<u8>, metadata: Vec<u8>) -> Result<()> {
+        let record = CachedModel {
+            version: self.version,
+            geometry,
+            metadata,
+        }
+        .encode();
+
+        let db = self.open().await?;
+        let result = self.put_in(&db, key, &record).await;
+        db.close();
+        result
+    }
+
+    /// Drop every cached model
+    pub async fn clear(&self) -> Result<()> {
+        let db = self.open().await?;
+        let result = async {
+            request_result(&store(&db, MODELS_STORE, true)?.clear().map_err(js_error)?).await?;
+            request_result(&store(&db, INDEX_STORE, true)?.clear().map_err(js_error)?).await?;
+            Ok::<_, StoreError>(())
+        }
+        .await;
+        db.close();
+        result
+    }
+
+    async fn open(&self) -> Result<IdbDatabase> {
+        open_database(&self.db_name, DB_VERSION, &[MODELS_STORE, INDEX_STORE]).await
+    }
+
+    async fn get_in(&self, db: &IdbDatabase, key: &str) -> Result<Option<CachedModel>> {
+        let value = request_result(
+            &store(db, MODELS_STORE, false)?
+                .get(&JsValue::from_str(key))
+                .map_err(js_error)?,
+        )
+        .await?;
+        if value.is_undefined() || value.is_null() {
+            return Ok(None);
+        }
+
+        let mut index = load_index(db).await?;
+        let record = value
+            .dyn_into::<Uint8Array>()
+            .ok()
+            .and_then(|bytes| CachedModel::decode(&bytes.to_vec()))
+            .filter(|record| record.version == self.version);
+        match record {
+            Some(record) => {
+                index.touch(key, js_sys::Date::now());
+                save_index(db, &index).await?;
+                Ok(Some(record))
+            }
+            None => {
+                // Stale or corrupt
+                index.remove(key);
+                delete_record(db, key).await?;
+                save_index(db, &index).await?;
+                Ok(None)
+            }
+        }
+    }
+
+    async fn put_in(&self, db: &IdbDatabase, key: &str, record: &[u8]) -> Result<()> {
+        let mut index = load_index(db).await?;
+        let Some(evicted) =
+            index.insert(key, record.len() as u64, js_sys::Date::now(), self.limits)
+        else {
+            return Ok(());
+        };
+        for old in &evicted {
+            delete_record(db, old).await?;
+        }
+
+        let bytes = Uint8Array::new_with_length(record.len() as u32);
+        bytes.copy_from(record);
+        let request = store(db, MODELS_STORE, true)?
+            .put_with_key(&bytes, &JsValue::from_str(key))
+            .map_err(js_error)?;
+        request_result(&request).await?;
+        save_index(db, &index).await
+    }
+}
+
+/// Object store in a fresh single-store transaction
+fn store(db: &IdbDatabase, name: &str, write: bool) -> Result<IdbObjectStore> {
+    let mode = if write {
+        IdbTransactionMode::Readwrite
+    } else {
+        IdbTransactionMode::Readonly
+    };
+    db.transaction_with_str_and_mode(name, mode)
+        .and_then(|tx| tx.object_store(name))
+        .map_err(js_error)
+}
+
+async fn load_index(db: &IdbDatabase) -> Result<CacheIndex> {
+    let request = store(db, INDEX_STORE, false)?
+        .get(&JsValue::from_str(INDEX_KEY))
+        .map_err(js_error)?;
+    Ok(request_result(&request)
+        .await?
+        .as_string()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default())
+}
+
+async fn save_index(db: &IdbDatabase, index: &CacheIndex) -> Result<()> {
+    let json = serde_json::to_string(index)?;
+    let request = store(db, INDEX_STORE, true)?
+        .put_with_key(&JsValue::from_str(&json), &JsValue::from_str(INDEX_KEY))
+        .map_err(js_error)?;
+    request_result(&request).await.map(|_| ())
+}
+
+async fn delete_record(db: &IdbDatabase, key: &str) -> Result<()> {
+    let request = store(db, MODELS_STORE, true)?
+        .delete(&JsValue::from_str(key))
+        .map_err(js_error)?;
+    request_result(&request).await.map(|_| ())
+}
@@ -118,6 +118,14 @@ struct SpatialNodeView: View {
         node.nodeType == "Element"
     }
 
+    private var isTypeGroup: Bool {
+        node.nodeType == "TypeGroup"
+    }
+
+    private var groupIds: [UInt64] {
+        node.children.map { $0.id }
+    }
+
     private var matchesSearch: Bool {
         if viewModel.searchQuery.isEmpty {
             return true
@@ -189,6 +197,23 @@ struct SpatialNodeView: View {
                         }
                         .buttonStyle(.borderless)
                     }
+
+                    // Visibility toggle for all elements of a type group
+                    if isTypeGroup {
+                        let groupVisible = groupIds.contains { viewModel.isEntityVisible($0) }
+                        Button(action: {
+                            if groupVisible {
+                                viewModel.hideEntities(groupIds)
+                            } else {
+                                viewModel.showEntities(groupIds)
+                            }
+                        }) {
+                            Image(systemName: groupVisible ? "eye" : "eye.slash")
+                                .font(.caption)
+                                .foregroundColor(groupVisible ? .secondary : .red)
+                        }
+                        .buttonStyle(.borderless)
+                    }
                 }
                 .padding(.leading, CGFloat(depth * 16 + 8))
                 .padding(.trailing, 8)
@@ -202,6 +227,14 @@ struct SpatialNodeView: View {
                         viewModel.toggleExpanded(node.id)
                     }
                 }
+                .contextMenu {
+                    if isTypeGroup {
+                        Button("Select All") { viewModel.selectAll(groupIds) }
+                        Button("Isolate") { viewModel.isolateEntities(groupIds) }
+                        Button("Hide") { viewModel.hideEntities(groupIds) }
+                        Button("Show") { viewModel.showEntities(groupIds) }
+                    }
+                }
 
                 // Children (if expanded)
                 if isExpanded {
@@ -230,6 +263,8 @@ struct SpatialNodeView: View {
         case "Building": return "🏢"
         case "Storey": return "📐"
         case "Space": return "🚪"
+        case "Facility": return "🛣️"
+        case "FacilityPart": return "🧭"
         default: return iconForEntityType(node.entityType)
         }
     }
@@ -200,8 +200,68 @@ struct NoSelectionView: View {
     }
 }
 
+/// Type counts, summed takeoff measures and shared properties of a multi-selection
+struct SelectionSummary {
+    struct CommonSet {
+        let name: String
+        /// `nil` values differ between the selected entities
+        var properties: [(name: String, value: String?)]
+    }
+
+    var byType: [(type: String, count: Int)] = []
+    var volume: Double?
+    var footprintArea: Double?
+    var commonSets: [CommonSet] = []
+
+    init() {}
+
+    @MainActor
+    init(viewModel: ViewerViewModel) {
+        let ids = viewModel.selectedIds
+        let counts = Dictionary(grouping: viewModel.entities.filter { ids.contains($0.id) }) {
+            $0.entityType
+        }.mapValues { $0.count }
+        byType = counts
+            .sorted { $0.value != $1.value ? $0.value > $1.value : $0.key < $1.key }
+            .map { (type: $0.key, count: $0.value) }
+
+        if let report = try? viewModel.scene.getQuantityReport() {
+            for element in report.elements where ids.contains(element.entityId) {
+                if let value = element.volume { volume = (volume ?? 0) + value }
+                if let value = element.footprintArea { footprintArea = (footprintArea ?? 0) + value }
+            }
+        }
+
+        var common: [CommonSet]?
+        for id in ids {
+            let sets = viewModel.scene.getProperties(entityId: id)
+            let display = { (prop: PropertyValue) in
+                prop.unit != nil ? "\(prop.value) \(prop.unit!)" : prop.value
+            }
+            guard var shared = common else {
+                common = sets.map { pset in
+                    CommonSet(name: pset.name, properties: pset.properties.map { (name: $0.name, value: display($0)) })
+                }
+                continue
+            }
+            shared = shared.compactMap { set in
+                guard let pset = sets.first(where: { $0.name == set.name }) else { return nil }
+                var set = set
+                set.properties = set.properties.compactMap { property in
+                    guard let other = pset.properties.first(where: { $0.name == property.name }) else { return nil }
+                    return (name: property.name, value: property.value == display(other) ? property.value : nil)
+                }
+                return set.properties.isEmpty ? nil : set
+            }
+            common = shared
+        }
+        commonSets = common ?? []
+    }
+}
+
 struct MultiSelectionView: View {
     @EnvironmentObject var viewModel: ViewerViewModel
+    @State private var summary = SelectionSummary()
 
     var body: some View {
         VStack(spacing: 16) {
@@ -212,6 +272,35 @@ struct MultiSelectionView: View {
             Text("\(viewModel.selectedIds.count) items selected")
                 .font(.headline)
 
+            ScrollView {
+                VStack(alignment: .leading, spacing: 16) {
+                    PropertySection(title: "By Type") {
+                        ForEach(Array(summary.byType.enumerated()), id: \.offset) { _, entry in
+                            PropertyRow(label: entry.type, value: "\(entry.count)")
+                        }
+                    }
+
+                    if summary.volume != nil || summary.footprintArea != nil {
+                        PropertySection(title: "Takeoff Totals") {
+                            if let volume = summary.volume {
+                                PropertyRow(label: "Volume", value: String(format: "%.3f m³", volume))
+                            }
+                            if let area = summary.footprintArea {
+                                PropertyRow(label: "Footprint area", value: String(format: "%.3f m²", area))
+                            }
+                        }
+                    }
+
+                    ForEach(Array(summary.commonSets.enumerated()), id: \.offset) { _, pset in
+                        PropertySection(title: pset.name) {
+                            ForEach(Array(pset.properties.enumerated()), id: \.offset) { _, property in
+                                PropertyRow(label: property.name, value: property.value ?? "<varies>")
+                            }
+                        }
+                    }
+                }
+            }
+
             VStack(spacing: 8) {
                 Button("Isolate All") {
                     viewModel.isolateSelection()
@@ -233,6 +322,9 @@ struct MultiSelectionView: View {
         }
         .padding()
         .frame(maxWidth: .infinity, maxHeight: .infinity)
+        .task(id: viewModel.selectedIds) {
+            summary = SelectionSummary(viewModel: viewModel)
+        }
     }
 }
 
@@ -217,13 +217,19 @@ class ViewerViewModel: ObservableObject {
         scene.toggleSelection(entityId: id)
     }
 
+    func selectAll(_ ids: [UInt64]) {
+        selectedIds = Set(ids)
+        scene.setSelection(entityIds: ids)
+    }
+
     func clearSelection() {
         selectedIds.removeAll()
         scene.clearSelection()
     }
 
+    /// The selected entity, when exactly one is selected
     var selectedEntity: EntityInfo? {
-        guard let id = selectedIds.first else { return nil }
+        guard selectedIds.count == 1, let id = selectedIds.first else { return nil }
         return entities.first { $0.id == id }
     }
 
@@ -239,11 +245,48 @@ class ViewerViewModel: ObservableObject {
         scene.showEntity(entityId: id)
     }
 
+    func hideEntities(_ ids: [UInt64]) {
+        hiddenIds.formUnion(ids)
+        scene.hideEntities(entityIds: ids)
+    }
+
+    func showEntities(_ ids: [UInt64]) {
+        hiddenIds.subtract(ids)
+        scene.showEntities(entityIds: ids)
+    }
+
+    func isolateEntities(_ ids: [UInt64]) {
+        isolatedIds = Set(ids)
+        scene.isolateEntities(entityIds: ids)
+    }
+
     func isolateEntity(_ id: UInt64) {
         isolatedIds = [id]
         scene.isolateEntity(entityId: id)
     }
 
+    /// Entities of an IFC type, matched case-insensitively like the scene does
+    private func ids(ofType typeName: String) -> [UInt64] {
+        entities
+            .filter { $0.entityType.caseInsensitiveCompare(typeName) == .orderedSame }
+            .map(\.id)
+    }
+
+    func hideType(_ typeName: String) {
+        hiddenIds.formUnion(ids(ofType: typeName))
+        scene.hideType(typeName: typeName)
+    }
+
+    func showType(_ typeName: String) {
+        hiddenIds.subtract(ids(ofType: typeName))
+        scene.showType(typeName: typeName)
+    }
+
+    func isolateType(_ typeName: String) {
+        isolatedIds = Set(ids(ofType: typeName))
+        scene.isolateType(typeName: typeName)
+    }
+
     func isolateSelection() {
         guard !selectedIds.isEmpty else { return }
         isolatedIds = selectedIds
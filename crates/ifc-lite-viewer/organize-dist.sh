@@ -17,8 +17,10 @@ cd "$DIST_DIR"
 mkdir -p wasm js styles
 
 # Move files to appropriate directories
-mv *.wasm wasm/ 2>/dev/null || true
-mv *.js js/ 2>/dev/null || true
+# The parse worker (ifc_lite_worker*) stays at the root: it is spawned by path
+# and loads its wasm relative to itself
+for f in *.wasm; do [[ $f == ifc_lite_worker* ]] || mv "$f" wasm/; done 2>/dev/null || true
+for f in *.js; do [[ $f == ifc_lite_worker* ]] || mv "$f" js/; done 2>/dev/null || true
 mv *.css styles/ 2>/dev/null || true
 
 # Update index.html references
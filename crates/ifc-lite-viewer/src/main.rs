@@ -1,6 +1,10 @@
 //! IFC-Lite Viewer Application
 //!
 //! Main entry point for the web-based IFC viewer.
+//!
+//! By default Bevy is a separate wasm module, loaded by `bevy-loader.js`,
+//! and the two share state through localStorage. With the `unified` feature
+//! Bevy is linked in and both use one in-memory store.
 
 use ifc_lite_yew::{bridge, ViewerLayout};
 use yew::prelude::*;
@@ -21,6 +25,15 @@ fn main() {
     // Initialize debug mode from URL (?debug=1)
     bridge::init_debug_from_url();
 
+    #[cfg(feature = "unified")]
+    {
+        let store = std::sync::Arc::new(ifc_lite_state::MemoryStore::new());
+        if ifc_lite_state::set_global(store).is_err() {
+            bridge::log_error("[Viewer] State store already in use");
+        }
+        bridge::set_embedded_renderer(ifc_lite_bevy::run_on_canvas);
+    }
+
     // Start the Yew application
     yew::Renderer::<App>::new().render();
 }
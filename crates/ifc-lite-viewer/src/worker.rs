@@ -0,0 +1,8 @@
+//! IFC-Lite parse worker
+//!
+//! Runs IFC parsing and geometry processing in a web worker.
+
+fn main() {
+    console_error_panic_hook::set_once();
+    ifc_lite_yew::worker::register_worker();
+}
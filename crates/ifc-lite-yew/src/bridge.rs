@@ -1,11 +1,14 @@
 //! Bridge between Yew UI and Bevy renderer
 //!
-//! Handles data transfer via localStorage and JavaScript FFI.
+//! Handles data transfer via the shared [`ifc_lite_state`] store (localStorage
+//! unless another store is installed) and JavaScript FFI.
 //! Uses binary format for geometry data to reduce memory usage and improve performance.
 
+use ifc_lite_state::{get_json, set_json, GeometryMeshRef, StateStore};
 use js_sys::Uint8Array;
 use serde::{Deserialize, Serialize};
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
 use wasm_bindgen::prelude::*;
 
 /// Global debug mode flag (set from URL parameter ?debug=1)
@@ -41,6 +44,39 @@ pub const SELECTION_SOURCE_KEY: &str = "ifc_lite_selection_source";
 pub const SECTION_KEY: &str = "ifc_lite_section";
 pub const FOCUS_KEY: &str = "ifc_lite_focus";
 pub const CAMERA_CMD_KEY: &str = "ifc_lite_camera_cmd";
+pub const CONTENT_HASH_KEY: &str = "ifc_lite_content_hash";
+pub const SCENE_INFO_KEY: &str = "ifc_lite_scene_info";
+pub const OVERLAY_KEY: &str = "ifc_lite_overlay";
+pub const RENDER_SETTINGS_KEY: &str = "ifc_lite_render_settings";
+pub const SCALE_BAR_KEY: &str = "ifc_lite_scale_bar";
+pub const SCENE_BOUNDS_KEY: &str = "ifc_lite_scene_bounds";
+pub const XRAY_KEY: &str = "ifc_lite_xray";
+pub const MEASURE_POINTS_KEY: &str = "ifc_lite_measure_points";
+pub const MEASUREMENTS_KEY: &str = "ifc_lite_measurements";
+pub const MEASURE_LABELS_KEY: &str = "ifc_lite_measure_labels";
+pub const GRID_LABELS_KEY: &str = "ifc_lite_grid_labels";
+pub const SPACES_KEY: &str = "ifc_lite_spaces";
+pub const SPACE_LABELS_KEY: &str = "ifc_lite_space_labels";
+pub const ANNOTATIONS_KEY: &str = "ifc_lite_annotations";
+pub const ANNOTATION_PICKS_KEY: &str = "ifc_lite_annotation_picks";
+pub const ANNOTATION_LABELS_KEY: &str = "ifc_lite_annotation_labels";
+pub const EXPLODE_KEY: &str = "ifc_lite_explode";
+pub const NAVIGATION_KEY: &str = "ifc_lite_navigation";
+pub const PALETTE_KEY: &str = "ifc_lite_palette";
+pub const PLAN_KEY: &str = "ifc_lite_plan";
+pub const CAMERA_PATH_KEY: &str = "ifc_lite_camera_path";
+pub const HOVER_KEY: &str = "ifc_lite_hover";
+pub const CONTEXT_MENU_KEY: &str = "ifc_lite_context_menu";
+pub const BATCH_TIMINGS_KEY: &str = "ifc_lite_batch_timings";
+pub const FRAME_STATS_KEY: &str = "ifc_lite_frame_stats";
+pub const CURSOR_KEY: &str = "ifc_lite_cursor";
+pub const SPLIT_VIEW_KEY: &str = "ifc_lite_split_view";
+pub const THUMBNAIL_REQUESTS_KEY: &str = "ifc_lite_thumbnail_requests";
+pub const THUMBNAILS_KEY: &str = "ifc_lite_thumbnails";
+/// Named views are stored per model, under this prefix plus the content hash
+pub const VIEWS_KEY_PREFIX: &str = "ifc_lite_views_";
+/// Annotations are stored per model, under this prefix plus the content hash
+pub const MODEL_ANNOTATIONS_KEY_PREFIX: &str = "ifc_lite_model_annotations_";
 
 // JavaScript FFI functions
 #[wasm_bindgen]
@@ -49,6 +85,10 @@ extern "C" {
     #[wasm_bindgen(js_name = loadBevyViewer, catch)]
     pub async fn load_bevy_viewer() -> Result<(), JsValue>;
 
+    /// Fetch and compile the Bevy module without starting it
+    #[wasm_bindgen(js_name = preloadBevyViewer)]
+    pub fn preload_bevy_viewer();
+
     /// Check if Bevy is loaded
     #[wasm_bindgen(js_name = isBevyLoaded)]
     pub fn is_bevy_loaded() -> bool;
@@ -61,22 +101,69 @@ extern "C" {
     #[wasm_bindgen(js_name = setIfcGeometryBinary)]
     pub fn set_ifc_geometry_binary(data: &Uint8Array);
 
+    /// Start a new geometry stream, clearing the scene
+    #[wasm_bindgen(js_name = beginIfcGeometryStream)]
+    fn begin_ifc_geometry_stream();
+
+    /// Queue a geometry chunk for Bevy
+    #[wasm_bindgen(js_name = appendIfcGeometryChunk)]
+    fn append_ifc_geometry_chunk(data: &Uint8Array);
+
+    /// Store the complete geometry of the current stream
+    #[wasm_bindgen(js_name = finishIfcGeometryStream)]
+    fn finish_ifc_geometry_stream(data: &Uint8Array);
+
     /// Set entity data via JS bridge
     #[wasm_bindgen(js_name = setIfcEntities)]
     pub fn set_ifc_entities(json: &str);
 }
 
-/// Get localStorage
-fn get_storage() -> Option<web_sys::Storage> {
-    web_sys::window()?.local_storage().ok()?
+/// Renderer linked into this wasm module, see [`set_embedded_renderer`]
+static EMBEDDED_RENDERER: OnceLock<fn(&str)> = OnceLock::new();
+/// Whether the embedded renderer has been started
+static EMBEDDED_STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Run Bevy inside this wasm module instead of loading its own
+///
+/// The unified build calls this before rendering the UI, with Bevy's
+/// `run_on_canvas`; the viewport starts it on its canvas once a model has
+/// geometry.
+pub fn set_embedded_renderer(start: fn(&str)) {
+    let _ = EMBEDDED_RENDERER.set(start);
+}
+
+/// Whether Bevy runs inside this wasm module
+pub fn has_embedded_renderer() -> bool {
+    EMBEDDED_RENDERER.get().is_some()
+}
+
+/// Start the embedded renderer on the canvas matching `selector`, once
+pub fn start_embedded_renderer(selector: &str) {
+    if let Some(start) = EMBEDDED_RENDERER.get() {
+        if !EMBEDDED_STARTED.swap(true, Ordering::Relaxed) {
+            start(selector);
+        }
+    }
+}
+
+/// Whether Bevy is running, embedded or as its own module
+pub fn is_bevy_running() -> bool {
+    if has_embedded_renderer() {
+        EMBEDDED_STARTED.load(Ordering::Relaxed)
+    } else {
+        is_bevy_loaded()
+    }
+}
+
+/// State store shared with Bevy
+fn store() -> &'static dyn StateStore {
+    ifc_lite_state::global()
 }
 
 /// Update timestamp to trigger Bevy reload
 pub fn update_timestamp() {
-    if let Some(storage) = get_storage() {
-        let ts = js_sys::Date::now().to_string();
-        let _ = storage.set_item(TIMESTAMP_KEY, &ts);
-    }
+    let ts = js_sys::Date::now().to_string();
+    let _ = store().set(TIMESTAMP_KEY, &ts);
 }
 
 /// Geometry data for Bevy
@@ -86,6 +173,9 @@ pub struct GeometryData {
     pub positions: Vec<f32>,
     pub normals: Vec<f32>,
     pub indices: Vec<u32>,
+    /// Per-vertex RGBA for face-colored meshes, empty otherwise
+    #[serde(default)]
+    pub colors: Vec<f32>,
     pub color: [f32; 4],
     pub transform: [f32; 16],
     pub entity_type: String,
@@ -98,8 +188,18 @@ pub struct EntityData {
     pub id: u64,
     pub entity_type: String,
     pub name: Option<String>,
+    /// IfcRoot GlobalId, stable across exports of the model
+    #[serde(default)]
+    pub global_id: Option<String>,
     pub storey: Option<String>,
     pub storey_elevation: Option<f32>,
+    /// Further storeys that reference the element without containing it
+    #[serde(default)]
+    pub referenced_storeys: Vec<String>,
+    #[serde(default)]
+    pub predefined_type: Option<String>,
+    #[serde(default)]
+    pub object_type: Option<String>,
 }
 
 /// Selection state for storage
@@ -114,26 +214,60 @@ pub struct SelectionData {
 pub struct VisibilityData {
     pub hidden: Vec<u64>,
     pub isolated: Option<Vec<u64>>,
+    /// Only show entities on this storey
+    #[serde(default)]
+    pub storey: Option<String>,
 }
 
 /// Camera state for storage
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct CameraData {
     pub azimuth: f32,
     pub elevation: f32,
     pub distance: f32,
-    pub target: [f32; 3],
+    /// Orbit centre in file coordinates (Y up)
+    pub target: [f64; 3],
+    /// Parallel projection; views saved before it existed are perspective
+    #[serde(default)]
+    pub orthographic: bool,
 }
 
-/// Section plane state for storage
+/// Section planes and box for storage; no planes and no box means no cut
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct SectionData {
-    pub enabled: bool,
+    pub planes: Vec<SectionPlaneData>,
+    pub section_box: Option<SectionBoxData>,
+    /// Colour of cut surfaces, "#rrggbb"
+    pub cap_color: Option<String>,
+    pub storey: Option<StoreySectionData>,
+}
+
+/// Horizontal cut above a storey; Bevy hides the storeys above it
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StoreySectionData {
+    pub storey: String,
+    /// Storey elevation (IFC Z), in metres
+    pub elevation: f32,
+    /// Height of the cut above the elevation, in metres
+    pub cut_height: f32,
+}
+
+/// Section plane for storage, relative to the scene bounds on an IFC axis
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SectionPlaneData {
+    /// "x", "y" or "z"
     pub axis: String,
     pub position: f32,
     pub flipped: bool,
 }
 
+/// Section box for storage, relative to the scene bounds per IFC axis
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SectionBoxData {
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
 /// Focus command for zooming to entity
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct FocusData {
@@ -144,129 +278,401 @@ pub struct FocusData {
 /// Camera command for view controls
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CameraCommand {
-    /// Command type: "home", "fit_all", "set_mode"
+    /// Command type: "home", "fit_all", "set_mode", "set_view",
+    /// "set_projection", "set_standard_view"
     pub cmd: String,
-    /// Optional mode for set_mode: "orbit", "pan", "walk"
+    /// Argument: the mode for set_mode ("orbit", "pan", "walk"), the
+    /// projection for set_projection ("orthographic", "perspective") or the
+    /// view for set_standard_view ("top", "front", "iso", ...)
     pub mode: Option<String>,
+    /// Camera to move to for set_view
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub view: Option<CameraData>,
 }
 
-/// Binary format header magic number
-const BINARY_MAGIC: u32 = 0x49464342; // "IFCB" in ASCII
-
-/// Serialize geometry data to compact binary format
-/// Format:
-/// - u32: magic (0x49464342 = "IFCB")
-/// - u32: version (1)
-/// - u32: mesh_count
-/// - For each mesh:
-///   - u64: entity_id
-///   - u32: positions_len (number of f32s)
-///   - `f32[]`: positions
-///   - u32: normals_len
-///   - `f32[]`: normals
-///   - u32: indices_len
-///   - `u32[]`: indices
-///   - `f32[4]`: color
-///   - `f32[16]`: transform
-///   - u8: entity_type_len
-///   - `utf8[]`: entity_type
-///   - u8: name_len (0 if None)
-///   - `utf8[]`: name (if any)
-fn serialize_geometry_binary(geometry: &[GeometryData]) -> Vec<u8> {
-    // Estimate capacity: header + meshes
-    let estimated_size: usize = 12
-        + geometry
-            .iter()
-            .map(|g| {
-                8 + 4
-                    + g.positions.len() * 4
-                    + 4
-                    + g.normals.len() * 4
-                    + 4
-                    + g.indices.len() * 4
-                    + 16
-                    + 64
-                    + 1
-                    + g.entity_type.len()
-                    + 1
-                    + g.name.as_ref().map(|n| n.len()).unwrap_or(0)
-            })
-            .sum::<usize>();
-
-    let mut buf = Vec::with_capacity(estimated_size);
-
-    // Header
-    buf.extend_from_slice(&BINARY_MAGIC.to_le_bytes());
-    buf.extend_from_slice(&1u32.to_le_bytes()); // version
-    buf.extend_from_slice(&(geometry.len() as u32).to_le_bytes());
-
-    for mesh in geometry {
-        // entity_id
-        buf.extend_from_slice(&mesh.entity_id.to_le_bytes());
-
-        // positions
-        buf.extend_from_slice(&(mesh.positions.len() as u32).to_le_bytes());
-        for &p in &mesh.positions {
-            buf.extend_from_slice(&p.to_le_bytes());
-        }
+/// Bounds of the loaded geometry from Bevy, in IFC file coordinates (Z up)
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SceneBoundsData {
+    pub min: [f64; 3],
+    pub max: [f64; 3],
+}
 
-        // normals
-        buf.extend_from_slice(&(mesh.normals.len() as u32).to_le_bytes());
-        for &n in &mesh.normals {
-            buf.extend_from_slice(&n.to_le_bytes());
-        }
+/// IfcGrid axis for Bevy, in IFC file coordinates (Z-up, metres)
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GridAxisData {
+    /// Axis tag, e.g. "A" or "1"
+    pub tag: String,
+    pub start: [f64; 3],
+    pub end: [f64; 3],
+}
 
-        // indices
-        buf.extend_from_slice(&(mesh.indices.len() as u32).to_le_bytes());
-        for &i in &mesh.indices {
-            buf.extend_from_slice(&i.to_le_bytes());
-        }
+/// Crossing of two grid axes, in IFC file coordinates (Z-up, metres)
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GridIntersectionData {
+    /// Tags of both axes, e.g. "A/1"
+    pub label: String,
+    pub point: [f64; 3],
+}
 
-        // color
-        for &c in &mesh.color {
-            buf.extend_from_slice(&c.to_le_bytes());
-        }
+/// Model-wide information for Bevy
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SceneInfo {
+    /// Angle in radians from model +Y to true north, counter-clockwise
+    pub true_north: Option<f32>,
+    pub grid_axes: Vec<GridAxisData>,
+    pub grid_intersections: Vec<GridIntersectionData>,
+    /// Offset (metres, IFC Z-up) taken off the geometry; everything else
+    /// exchanged with Bevy is in file coordinates
+    #[serde(default)]
+    pub origin_shift: [f64; 3],
+}
 
-        // transform
-        for &t in &mesh.transform {
-            buf.extend_from_slice(&t.to_le_bytes());
-        }
+/// Overlay toggles for Bevy
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OverlayData {
+    pub origin_axes: bool,
+    pub north_arrow: bool,
+    pub scale_bar: bool,
+    pub minimap: bool,
+    pub view_cube: bool,
+    pub grid_axes: bool,
+    pub stats: bool,
+}
 
-        // entity_type
-        let type_bytes = mesh.entity_type.as_bytes();
-        buf.push(type_bytes.len() as u8);
-        buf.extend_from_slice(type_bytes);
-
-        // name
-        if let Some(ref name) = mesh.name {
-            let name_bytes = name.as_bytes();
-            buf.push(name_bytes.len().min(255) as u8);
-            buf.extend_from_slice(&name_bytes[..name_bytes.len().min(255)]);
-        } else {
-            buf.push(0);
-        }
-    }
+/// Renderer defaults picked for the device, with the graphics settings
+/// applied
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RenderSettingsData {
+    /// MSAA sample count (1 = off)
+    pub msaa_samples: u32,
+    pub shadows: crate::device::GraphicsQuality,
+    pub ambient_occlusion: crate::device::GraphicsQuality,
+    /// Edge length of spatial batching chunks, in metres
+    pub chunk_size: f32,
+    /// Largest on-screen LOD error, in pixels
+    pub lod_error_px: f32,
+    /// Smooth-shade meshes without normals
+    pub smooth_normals: bool,
+    /// Crease angle for smoothing, in degrees
+    pub crease_angle: f32,
+    /// Weld vertices and reorder indices for the GPU while batching
+    pub optimize_meshes: bool,
+}
+
+/// Mouse navigation preferences for Bevy
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NavigationData {
+    /// Orbit about the point under the cursor instead of the view centre
+    pub orbit_around_cursor: bool,
+    /// Zoom toward the cursor instead of the view centre
+    pub zoom_to_cursor: bool,
+}
+
+/// X-ray mode for Bevy
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct XrayData {
+    pub enabled: bool,
+    /// Keep this IFC type opaque instead of the selection
+    pub ifc_type: Option<String>,
+}
+
+/// Space mode for Bevy
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct SpaceData {
+    pub visible: bool,
+    /// Storeys whose spaces stay hidden
+    pub hidden_storeys: Vec<String>,
+}
+
+/// Exploded view for Bevy
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExplodeData {
+    /// "off", "storeys" or "elements"
+    pub mode: String,
+    pub factor: f32,
+}
+
+/// Entities Bevy draws in one palette color
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PaletteGroupData {
+    /// Legend label, e.g. the type object name
+    pub label: String,
+    pub color: [f32; 3],
+    pub entities: Vec<u64>,
+}
+
+/// Color overrides for Bevy; no groups shows the model colors
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PaletteData {
+    pub groups: Vec<PaletteGroupData>,
+}
+
+/// Storey plan for Bevy; no storey shows the 3D view
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlanData {
+    pub storey: Option<String>,
+    /// Height of the cut above the storey elevation, in metres
+    pub cut_height: f32,
+}
+
+/// Split view for Bevy: a second viewport next to the main one
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SplitViewData {
+    pub enabled: bool,
+    /// Both views pan and zoom together, each keeping its view direction
+    pub synchronized: bool,
+}
+
+/// Thumbnail for Bevy to render
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ThumbnailRequestData {
+    /// Name the thumbnail comes back under, e.g. "storey:Level 1"
+    pub key: String,
+    /// Entities drawn in it
+    pub entities: Vec<u64>,
+}
+
+/// Thumbnail rendered by Bevy
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ThumbnailData {
+    pub key: String,
+    /// Base64 PNG; `None` if none of the entities has geometry
+    pub png: Option<String>,
+}
+
+/// One stop of a camera path
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CameraKeyframeData {
+    pub camera: CameraData,
+    /// Seconds to get here from the previous keyframe
+    pub duration: f32,
+}
+
+/// Camera path for Bevy; Bevy starts playing when `playing` comes with a
+/// new `run`
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CameraPathData {
+    pub keyframes: Vec<CameraKeyframeData>,
+    pub looping: bool,
+    pub playing: bool,
+    pub run: u32,
+}
+
+/// Point picked by Bevy's measure tool, in IFC file coordinates (Z-up,
+/// metres)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MeasurePointData {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    /// What the point snapped to: "vertex", "edge", "face" or "grid"
+    pub snap: String,
+}
+
+/// Measurement for Bevy to draw, in IFC file coordinates
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MeasurementData {
+    pub id: u32,
+    pub start: [f64; 3],
+    pub end: [f64; 3],
+}
+
+/// Measurements for Bevy, plus the first point of one in progress
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct MeasurementsData {
+    pub measurements: Vec<MeasurementData>,
+    pub pending: Option<[f64; 3]>,
+}
+
+/// Where Bevy wants a measurement's distance label
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MeasureLabelData {
+    pub id: u32,
+    /// Label anchor in CSS pixels from the top left of the viewport
+    pub x: f32,
+    pub y: f32,
+    /// Length in metres
+    pub distance: f32,
+}
+
+/// Where Bevy wants a grid axis tag
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GridLabelData {
+    pub tag: String,
+    /// Label anchor in CSS pixels from the top left of the viewport
+    pub x: f32,
+    pub y: f32,
+}
+
+/// Where Bevy wants a space name
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpaceLabelData {
+    pub id: u64,
+    pub name: String,
+    /// Label anchor in CSS pixels from the top left of the viewport
+    pub x: f32,
+    pub y: f32,
+}
+
+/// Annotation anchor for Bevy to draw: a point in IFC file coordinates, or
+/// the centre of an entity
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AnnotationData {
+    pub id: u32,
+    pub entity_id: Option<u64>,
+    pub point: Option<[f64; 3]>,
+}
+
+/// Point clicked by Bevy's annotate tool, waiting for its text
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AnnotationPickData {
+    /// Clicked point on the model, in IFC file coordinates
+    pub point: [f64; 3],
+    pub entity_id: u64,
+}
+
+/// Where Bevy wants an annotation's label
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AnnotationLabelData {
+    pub id: u32,
+    /// Label anchor in CSS pixels from the top left of the viewport
+    pub x: f32,
+    pub y: f32,
+}
+
+/// Entity Bevy reports under the resting cursor
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HoverData {
+    pub id: u64,
+    /// Cursor in CSS pixels from the top left of the viewport
+    pub x: f32,
+    pub y: f32,
+}
+
+/// Model point Bevy reports under the cursor
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CursorData {
+    /// Entity the point lies on
+    pub id: u64,
+    /// Point in IFC file coordinates (Z up), metres
+    pub point: [f64; 3],
+}
+
+/// Entity Bevy reports under a right click
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ContextMenuData {
+    pub id: u64,
+    /// Click in CSS pixels from the top left of the viewport
+    pub x: f32,
+    pub y: f32,
+}
 
-    buf
+/// Scale bar published by Bevy in plan view
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScaleBarData {
+    /// Bar length in metres
+    pub length: f32,
+    /// Bar width in CSS pixels
+    pub width_px: f32,
+}
+
+/// Time Bevy spent batching the loaded geometry and uploading it
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct BatchTimingsData {
+    pub batching_ms: f64,
+    pub upload_ms: f64,
+    pub meshes: usize,
+}
+
+/// Rendering statistics Bevy publishes while the stats overlay is on
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct FrameStatsData {
+    pub fps: f32,
+    pub frame_ms: f32,
+    pub frame_ms_max: f32,
+    pub triangles: usize,
+    pub draws: usize,
+    pub batches: usize,
+    /// Linear memory of the Bevy wasm instance
+    pub heap_bytes: Option<u64>,
+}
+
+/// Serialize geometry to the binary format Bevy reads, see
+/// [`ifc_lite_state::geometry`]
+fn encode_geometry(geometry: &[GeometryData]) -> Vec<u8> {
+    let meshes: Vec<GeometryMeshRef> = geometry
+        .iter()
+        .map(|g| GeometryMeshRef {
+            entity_id: g.entity_id,
+            positions: &g.positions,
+            normals: &g.normals,
+            indices: &g.indices,
+            colors: &g.colors,
+            color: g.color,
+            transform: g.transform,
+            entity_type: &g.entity_type,
+            name: g.name.as_deref(),
+        })
+        .collect();
+    ifc_lite_state::encode_geometry(&meshes)
 }
 
 /// Save geometry data for Bevy (uses binary format via JS bridge)
 pub fn save_geometry(geometry: &[GeometryData]) {
-    let binary = serialize_geometry_binary(geometry);
+    let binary = encode_geometry(geometry);
     log(&format!(
         "[Yew] Geometry binary size: {} bytes ({} meshes)",
         binary.len(),
         geometry.len()
     ));
+    save_geometry_binary(&binary);
+}
 
+/// Send already serialized geometry to Bevy, replacing the scene
+pub fn save_geometry_binary(binary: &[u8]) {
     // Create Uint8Array and copy data
     let array = Uint8Array::new_with_length(binary.len() as u32);
-    array.copy_from(&binary);
+    array.copy_from(binary);
 
     set_ifc_geometry_binary(&array);
     log("[Yew] Geometry sent via JS bridge (binary)");
 }
 
+/// Start streaming geometry to Bevy; the current scene is cleared
+pub fn begin_geometry_stream() {
+    begin_ifc_geometry_stream();
+}
+
+/// Send one chunk of a geometry stream, shown as soon as Bevy polls it
+pub fn append_geometry_chunk(geometry: &[GeometryData]) {
+    if geometry.is_empty() {
+        return;
+    }
+    let binary = encode_geometry(geometry);
+    let array = Uint8Array::new_with_length(binary.len() as u32);
+    array.copy_from(&binary);
+    append_ifc_geometry_chunk(&array);
+}
+
+/// Complete a geometry stream
+///
+/// Bevy already holds the streamed chunks; the full binary is kept for a
+/// viewer that starts later, and the timestamp bump loads entity data.
+/// Returns the binary so it can be cached.
+pub fn finish_geometry_stream(geometry: &[GeometryData]) -> Vec<u8> {
+    let binary = encode_geometry(geometry);
+    log(&format!(
+        "[Yew] Geometry stream finished: {} bytes ({} meshes)",
+        binary.len(),
+        geometry.len()
+    ));
+
+    let array = Uint8Array::new_with_length(binary.len() as u32);
+    array.copy_from(&binary);
+    finish_ifc_geometry_stream(&array);
+    binary
+}
+
 /// Save entity data for Bevy (uses JS bridge)
 pub fn save_entities(entities: &[EntityData]) {
     if let Ok(json) = serde_json::to_string(entities) {
@@ -276,86 +682,309 @@ pub fn save_entities(entities: &[EntityData]) {
 
 /// Save selection state for Bevy (marks source as "yew")
 pub fn save_selection(selection: &SelectionData) {
-    if let Some(storage) = get_storage() {
-        if let Ok(json) = serde_json::to_string(selection) {
-            let _ = storage.set_item(SELECTION_KEY, &json);
-            let _ = storage.set_item(SELECTION_SOURCE_KEY, "yew");
-            update_timestamp();
-        }
+    if set_json(store(), SELECTION_KEY, selection).is_ok() {
+        let _ = store().set(SELECTION_SOURCE_KEY, "yew");
+        update_timestamp();
     }
 }
 
 /// Get the source of the last selection change ("yew" or "bevy")
 pub fn get_selection_source() -> Option<String> {
-    let storage = get_storage()?;
-    storage.get_item(SELECTION_SOURCE_KEY).ok()?
+    store().get(SELECTION_SOURCE_KEY)
 }
 
 /// Load selection state from Bevy
 pub fn load_selection() -> Option<SelectionData> {
-    let storage = get_storage()?;
-    let json = storage.get_item(SELECTION_KEY).ok()??;
-    serde_json::from_str(&json).ok()
+    get_json(store(), SELECTION_KEY)
 }
 
 /// Save visibility state for Bevy
 pub fn save_visibility(visibility: &VisibilityData) {
-    if let Some(storage) = get_storage() {
-        if let Ok(json) = serde_json::to_string(visibility) {
-            let _ = storage.set_item(VISIBILITY_KEY, &json);
-            update_timestamp();
-        }
+    if set_json(store(), VISIBILITY_KEY, visibility).is_ok() {
+        update_timestamp();
     }
 }
 
 /// Load camera state from Bevy
 pub fn load_camera() -> Option<CameraData> {
-    let storage = get_storage()?;
-    let json = storage.get_item(CAMERA_KEY).ok()??;
-    serde_json::from_str(&json).ok()
+    get_json(store(), CAMERA_KEY)
 }
 
-/// Save section plane state for Bevy
+/// Save section planes and box for Bevy
 pub fn save_section(section: &SectionData) {
-    if let Some(storage) = get_storage() {
-        if let Ok(json) = serde_json::to_string(section) {
-            let _ = storage.set_item(SECTION_KEY, &json);
-            update_timestamp();
-        }
+    if set_json(store(), SECTION_KEY, section).is_ok() {
+        update_timestamp();
     }
 }
 
 /// Save focus command for Bevy (zoom to entity)
 pub fn save_focus(focus: &FocusData) {
-    if let Some(storage) = get_storage() {
-        if let Ok(json) = serde_json::to_string(focus) {
-            let _ = storage.set_item(FOCUS_KEY, &json);
-            update_timestamp();
-        }
+    if set_json(store(), FOCUS_KEY, focus).is_ok() {
+        update_timestamp();
     }
 }
 
 /// Save camera command for Bevy (home, fit_all, set_mode)
 pub fn save_camera_cmd(cmd: &CameraCommand) {
-    if let Some(storage) = get_storage() {
-        if let Ok(json) = serde_json::to_string(cmd) {
-            let _ = storage.set_item(CAMERA_CMD_KEY, &json);
-            update_timestamp();
-        }
+    if set_json(store(), CAMERA_CMD_KEY, cmd).is_ok() {
+        update_timestamp();
+    }
+}
+
+/// Save content hash of the loaded model so session state can be keyed by it
+pub fn save_content_hash(hash: &str) {
+    let _ = store().set(CONTENT_HASH_KEY, hash);
+}
+
+/// Save the named views of a model
+pub fn save_views(content_hash: &str, views: &[crate::state::NamedView]) {
+    let key = format!("{}{}", VIEWS_KEY_PREFIX, content_hash);
+    let result = if views.is_empty() {
+        store().remove(&key)
+    } else {
+        set_json(store(), &key, views)
+    };
+    if let Err(e) = result {
+        log(&format!("[Yew] Failed to save views: {}", e));
     }
 }
 
+/// Load the named views saved for a model
+pub fn load_views(content_hash: &str) -> Vec<crate::state::NamedView> {
+    get_json::<Vec<crate::state::NamedView>>(
+        store(),
+        &format!("{}{}", VIEWS_KEY_PREFIX, content_hash),
+    )
+    .unwrap_or_default()
+    .into_iter()
+    .map(crate::state::NamedView::migrated)
+    .collect()
+}
+
+/// Save the annotations of a model
+pub fn save_model_annotations(content_hash: &str, annotations: &[crate::state::Annotation]) {
+    let key = format!("{}{}", MODEL_ANNOTATIONS_KEY_PREFIX, content_hash);
+    let result = if annotations.is_empty() {
+        store().remove(&key)
+    } else {
+        set_json(store(), &key, annotations)
+    };
+    if let Err(e) = result {
+        log(&format!("[Yew] Failed to save annotations: {}", e));
+    }
+}
+
+/// Load the annotations saved for a model
+pub fn load_model_annotations(content_hash: &str) -> Vec<crate::state::Annotation> {
+    get_json(
+        store(),
+        &format!("{}{}", MODEL_ANNOTATIONS_KEY_PREFIX, content_hash),
+    )
+    .unwrap_or_default()
+}
+
+/// Save model-wide scene info for Bevy
+pub fn save_scene_info(info: &SceneInfo) {
+    let _ = set_json(store(), SCENE_INFO_KEY, info);
+}
+
+/// Save overlay toggles for Bevy
+pub fn save_overlay(overlay: &OverlayData) {
+    let _ = set_json(store(), OVERLAY_KEY, overlay);
+}
+
+/// Save renderer settings for Bevy
+pub fn save_render_settings(settings: &RenderSettingsData) {
+    let _ = set_json(store(), RENDER_SETTINGS_KEY, settings);
+}
+
+/// Save x-ray mode for Bevy
+pub fn save_xray(xray: &XrayData) {
+    let _ = set_json(store(), XRAY_KEY, xray);
+}
+
+/// Save space mode for Bevy
+pub fn save_spaces(spaces: &SpaceData) {
+    let _ = set_json(store(), SPACES_KEY, spaces);
+}
+
+/// Save mouse navigation preferences for Bevy
+pub fn save_navigation(navigation: &NavigationData) {
+    let _ = set_json(store(), NAVIGATION_KEY, navigation);
+}
+
+/// Save the exploded view for Bevy
+pub fn save_explode(explode: &ExplodeData) {
+    let _ = set_json(store(), EXPLODE_KEY, explode);
+}
+
+/// Save palette color overrides for Bevy
+pub fn save_palette(palette: &PaletteData) {
+    let _ = set_json(store(), PALETTE_KEY, palette);
+}
+
+pub fn save_plan(plan: &PlanData) {
+    let _ = set_json(store(), PLAN_KEY, plan);
+}
+
+pub fn save_split_view(split_view: &SplitViewData) {
+    let _ = set_json(store(), SPLIT_VIEW_KEY, split_view);
+}
+
+/// Ask Bevy for thumbnails, after those it has not picked up yet
+pub fn request_thumbnails(requests: &[ThumbnailRequestData]) {
+    let mut queued: Vec<ThumbnailRequestData> =
+        get_json(store(), THUMBNAIL_REQUESTS_KEY).unwrap_or_default();
+    queued.extend_from_slice(requests);
+    let _ = set_json(store(), THUMBNAIL_REQUESTS_KEY, &queued);
+}
+
+/// Take the thumbnails Bevy rendered since the last call
+pub fn take_thumbnails() -> Vec<ThumbnailData> {
+    let thumbnails = get_json(store(), THUMBNAILS_KEY).unwrap_or_default();
+    let _ = store().remove(THUMBNAILS_KEY);
+    thumbnails
+}
+
+pub fn save_camera_path(path: &CameraPathData) {
+    let _ = set_json(store(), CAMERA_PATH_KEY, path);
+}
+
+/// Take the points Bevy's measure tool picked since the last call
+pub fn take_measure_points() -> Vec<MeasurePointData> {
+    let points = get_json(store(), MEASURE_POINTS_KEY).unwrap_or_default();
+    let _ = store().remove(MEASURE_POINTS_KEY);
+    points
+}
+
+/// Save measurements for Bevy to draw
+pub fn save_measurements(measurements: &MeasurementsData) {
+    let _ = set_json(store(), MEASUREMENTS_KEY, measurements);
+}
+
+/// Load the measurement label positions Bevy published
+pub fn load_measure_labels() -> Vec<MeasureLabelData> {
+    get_json(store(), MEASURE_LABELS_KEY).unwrap_or_default()
+}
+
+/// Load the grid axis tag positions Bevy published
+pub fn load_grid_labels() -> Vec<GridLabelData> {
+    get_json(store(), GRID_LABELS_KEY).unwrap_or_default()
+}
+
+/// Load the space name positions Bevy published
+pub fn load_space_labels() -> Vec<SpaceLabelData> {
+    get_json(store(), SPACE_LABELS_KEY).unwrap_or_default()
+}
+
+/// Save annotation anchors for Bevy to draw
+pub fn save_annotations(annotations: &[AnnotationData]) {
+    let _ = set_json(store(), ANNOTATIONS_KEY, annotations);
+}
+
+/// Take the points Bevy's annotate tool picked since the last call
+pub fn take_annotation_picks() -> Vec<AnnotationPickData> {
+    let picks = get_json(store(), ANNOTATION_PICKS_KEY).unwrap_or_default();
+    let _ = store().remove(ANNOTATION_PICKS_KEY);
+    picks
+}
+
+/// Load the annotation label positions Bevy published
+pub fn load_annotation_labels() -> Vec<AnnotationLabelData> {
+    get_json(store(), ANNOTATION_LABELS_KEY).unwrap_or_default()
+}
+
+/// Load the hovered entity Bevy published for the tooltip
+pub fn load_hover() -> Option<HoverData> {
+    get_json(store(), HOVER_KEY)
+}
+
+/// Take the right click Bevy published since the last call
+pub fn take_context_menu() -> Option<ContextMenuData> {
+    let menu = get_json(store(), CONTEXT_MENU_KEY);
+    let _ = store().remove(CONTEXT_MENU_KEY);
+    menu
+}
+
+/// Load the scale bar Bevy shows in plan view
+pub fn load_scale_bar() -> Option<ScaleBarData> {
+    get_json(store(), SCALE_BAR_KEY)
+}
+
+/// Load the geometry bounds Bevy last published
+pub fn load_scene_bounds() -> Option<SceneBoundsData> {
+    get_json(store(), SCENE_BOUNDS_KEY)
+}
+
+pub fn load_batch_timings() -> Option<BatchTimingsData> {
+    get_json(store(), BATCH_TIMINGS_KEY)
+}
+
+pub fn load_frame_stats() -> Option<FrameStatsData> {
+    get_json(store(), FRAME_STATS_KEY)
+}
+
+/// Load the model point under the cursor Bevy last published
+pub fn load_cursor() -> Option<CursorData> {
+    get_json(store(), CURSOR_KEY)
+}
+
+/// Switch Bevy's camera between perspective and orthographic projection
+pub fn save_projection(orthographic: bool) {
+    save_camera_cmd(&CameraCommand {
+        cmd: "set_projection".to_string(),
+        mode: Some(
+            if orthographic {
+                "orthographic"
+            } else {
+                "perspective"
+            }
+            .to_string(),
+        ),
+        view: None,
+    });
+}
+
+/// Turn Bevy's camera to a standard view: "top", "bottom", "front",
+/// "back", "left", "right" or "iso"
+pub fn save_standard_view(view: &str) {
+    save_camera_cmd(&CameraCommand {
+        cmd: "set_standard_view".to_string(),
+        mode: Some(view.to_string()),
+        view: None,
+    });
+}
+
+/// Move Bevy's camera to a saved orbit camera
+pub fn save_camera_view(camera: &CameraData) {
+    save_camera_cmd(&CameraCommand {
+        cmd: "set_view".to_string(),
+        mode: None,
+        view: Some(camera.clone()),
+    });
+}
+
 /// Clear all storage
 pub fn clear_storage() {
-    if let Some(storage) = get_storage() {
-        let _ = storage.remove_item(GEOMETRY_KEY);
-        let _ = storage.remove_item(ENTITIES_KEY);
-        let _ = storage.remove_item(SELECTION_KEY);
-        let _ = storage.remove_item(VISIBILITY_KEY);
-        let _ = storage.remove_item(SECTION_KEY);
-        let _ = storage.remove_item(FOCUS_KEY);
-        update_timestamp();
+    for key in [
+        GEOMETRY_KEY,
+        ENTITIES_KEY,
+        SELECTION_KEY,
+        VISIBILITY_KEY,
+        SECTION_KEY,
+        FOCUS_KEY,
+        CONTENT_HASH_KEY,
+        SCENE_INFO_KEY,
+        SCENE_BOUNDS_KEY,
+        BATCH_TIMINGS_KEY,
+        FRAME_STATS_KEY,
+        CURSOR_KEY,
+        THUMBNAIL_REQUESTS_KEY,
+        THUMBNAILS_KEY,
+    ] {
+        let _ = store().remove(key);
     }
+    update_timestamp();
 }
 
 /// Log to browser console (only in debug mode)
@@ -0,0 +1,87 @@
+//! Model cache - reopening a file skips parsing
+//!
+//! After a load the geometry binary sent to Bevy and the [`ProcessedModel`]
+//! are stored in IndexedDB under the content hash. Loading the same content
+//! again hands both straight to Bevy and the UI. Models loaded with other
+//! than the default [`GeometrySettings`] are kept apart. `?cache=0` in the
+//! URL turns the cache off.
+
+use crate::bridge;
+use crate::components::ProcessedModel;
+use crate::state::GeometrySettings;
+use ifc_lite_core::ContentHash;
+use ifc_lite_state::{CacheLimits, IndexedDbModelCache};
+
+const DB_NAME: &str = "ifc_lite_model_cache";
+
+/// Bump whenever the geometry binary layout or [`ProcessedModel`] changes
+const CACHE_VERSION: u32 = 7;
+
+fn cache() -> IndexedDbModelCache {
+    IndexedDbModelCache::new(DB_NAME, CACHE_VERSION, CacheLimits::default())
+}
+
+/// The cache is on unless the page was opened with `?cache=0`
+fn enabled() -> bool {
+    web_sys::window()
+        .and_then(|w| w.location().search().ok())
+        .is_none_or(|search| !search.contains("cache=0"))
+}
+
+/// Cache key of IFC content loaded with the given geometry settings
+pub fn key(content: &str, geometry: &GeometrySettings) -> String {
+    hash_key(&ContentHash::of_str(content).to_hex(), geometry)
+}
+
+/// Cache key of a content hash; the default settings keep the bare hash
+fn hash_key(content_hash: &str, geometry: &GeometrySettings) -> String {
+    if *geometry == GeometrySettings::default() {
+        return content_hash.to_string();
+    }
+    format!(
+        "{}-d{}-s{}-o{}-p{}-f{}",
+        content_hash,
+        geometry.deflection,
+        geometry.max_segments,
+        geometry.skip_openings as u8,
+        geometry.skip_spaces as u8,
+        geometry.min_feature_size
+    )
+}
+
+/// Cached model and geometry binary for a content hash
+pub async fn load(content_hash: &str) -> Option<(ProcessedModel, Vec<u8>)> {
+    if !enabled() {
+        return None;
+    }
+    let record = match cache().get(content_hash).await {
+        Ok(record) => record?,
+        Err(e) => {
+            bridge::log_warn(&format!("[Cache] Lookup failed: {}", e));
+            return None;
+        }
+    };
+    let model = serde_json::from_slice(&record.metadata).ok()?;
+    bridge::log(&format!(
+        "[Cache] Hit for {} ({} bytes of geometry)",
+        content_hash,
+        record.geometry.len()
+    ));
+    Some((model, record.geometry))
+}
+
+/// Store a processed model, loaded with `settings`, in the background
+pub fn store(model: &ProcessedModel, geometry: Vec<u8>, settings: &GeometrySettings) {
+    if !enabled() {
+        return;
+    }
+    let Ok(metadata) = serde_json::to_vec(model) else {
+        return;
+    };
+    let content_hash = hash_key(&model.content_hash, settings);
+    wasm_bindgen_futures::spawn_local(async move {
+        if let Err(e) = cache().put(&content_hash, geometry, metadata).await {
+            bridge::log_warn(&format!("[Cache] Storing {} failed: {}", content_hash, e));
+        }
+    });
+}
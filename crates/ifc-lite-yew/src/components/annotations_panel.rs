@@ -0,0 +1,152 @@
+//! Annotations panel - text notes of the loaded model, with JSON export
+
+use crate::bridge::{self, FocusData};
+use crate::state::{Annotation, ViewerAction, ViewerStateContext};
+use crate::utils::{download_file, file_stem};
+use gloo_file::callbacks::FileReader;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+/// Annotations of the loaded model, editable in place
+#[function_component]
+pub fn AnnotationsPanel() -> Html {
+    let state = use_context::<ViewerStateContext>().expect("ViewerStateContext not found");
+    let import_input_ref = use_node_ref();
+    let import_reader = use_state(|| None::<FileReader>);
+
+    if state.entities.is_empty() {
+        return html! {};
+    }
+
+    let on_export = {
+        let annotations = state.annotations.clone();
+        let stem = state
+            .file_name
+            .as_deref()
+            .map(|name| file_stem(name.trim_end_matches(".ifc")))
+            .unwrap_or_else(|| "model".to_string());
+        Callback::from(
+            move |_: MouseEvent| match serde_json::to_string_pretty(&annotations) {
+                Ok(json) => download_file(
+                    &format!("{}-annotations.json", stem),
+                    "application/json",
+                    json.as_bytes(),
+                ),
+                Err(e) => bridge::log_error(&format!("Failed to export annotations: {}", e)),
+            },
+        )
+    };
+
+    // Import annotations exported from this or another version of the model
+    let on_import = {
+        let state = state.clone();
+        let import_reader = import_reader.clone();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let Some(file) = input.files().and_then(|files| files.get(0)) else {
+                return;
+            };
+            input.set_value("");
+
+            let state = state.clone();
+            let reader =
+                gloo_file::callbacks::read_as_text(&gloo_file::File::from(file), move |result| {
+                    let parsed = result.map_err(|e| e.to_string()).and_then(|json| {
+                        serde_json::from_str::<Vec<Annotation>>(&json).map_err(|e| e.to_string())
+                    });
+                    match parsed {
+                        Ok(annotations) => {
+                            state.dispatch(ViewerAction::ImportAnnotations(annotations))
+                        }
+                        Err(e) => {
+                            bridge::log_error(&format!("Failed to import annotations: {}", e))
+                        }
+                    }
+                });
+            import_reader.set(Some(reader));
+        })
+    };
+
+    html! {
+        <div class="views-panel">
+            <div class="views-save annotations-header">
+                <div class="section-header">{"Annotations"}</div>
+                <input
+                    ref={import_input_ref.clone()}
+                    type="file"
+                    accept=".json"
+                    style="display: none"
+                    onchange={on_import}
+                />
+                <button
+                    class="copy-btn"
+                    onclick={Callback::from(move |_: MouseEvent| {
+                        if let Some(input) = import_input_ref.cast::<HtmlInputElement>() {
+                            input.click();
+                        }
+                    })}
+                    title="Import annotations (.json)"
+                >
+                    {"📥"}
+                </button>
+                <button
+                    class="copy-btn"
+                    disabled={state.annotations.is_empty()}
+                    onclick={on_export}
+                    title="Export annotations as JSON"
+                >
+                    {"📤"}
+                </button>
+            </div>
+            if state.annotations.is_empty() {
+                <div class="annotations-hint">
+                    {"Use the 📝 tool or right-click an element to annotate"}
+                </div>
+            }
+            <div class="views-list">
+                { for state.annotations.iter().map(|annotation| {
+                    let id = annotation.id;
+                    let on_edit = {
+                        let state = state.clone();
+                        Callback::from(move |e: Event| {
+                            let input: HtmlInputElement = e.target_unchecked_into();
+                            state.dispatch(ViewerAction::EditAnnotation(id, input.value()));
+                        })
+                    };
+                    let on_zoom = annotation.entity_id.map(|entity_id| {
+                        Callback::from(move |_: MouseEvent| {
+                            bridge::save_focus(&FocusData { entity_id })
+                        })
+                    });
+                    let on_delete = {
+                        let state = state.clone();
+                        Callback::from(move |_: MouseEvent| {
+                            state.dispatch(ViewerAction::RemoveAnnotation(id))
+                        })
+                    };
+                    html! {
+                        <div class="view-row" key={id}>
+                            <input
+                                type="text"
+                                class="annotation-text"
+                                value={annotation.text.clone()}
+                                onchange={on_edit}
+                            />
+                            <button
+                                class="copy-btn"
+                                disabled={on_zoom.is_none()}
+                                onclick={on_zoom.unwrap_or_default()}
+                                title="Zoom to annotated element"
+                            >
+                                {"🔍"}
+                            </button>
+                            <button class="copy-btn" onclick={on_delete} title="Delete annotation">
+                                {"✕"}
+                            </button>
+                        </div>
+                    }
+                })}
+            </div>
+        </div>
+    }
+}
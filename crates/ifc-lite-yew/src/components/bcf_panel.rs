@@ -0,0 +1,260 @@
+//! BCF panel - issues about the model, exchanged as .bcfzip
+
+use crate::bridge;
+use crate::state::{NamedView, ViewerAction, ViewerStateContext};
+use crate::utils::{capture_canvas_png, download_file, file_stem, png_data_url};
+use crate::viewpoint;
+use gloo_file::callbacks::FileReader;
+use ifc_lite_core::{BcfArchive, BcfTopic, BcfTopicViewpoint};
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+/// Topics of the loaded model: import, create from the current view, export
+#[function_component]
+pub fn BcfPanel() -> Html {
+    let state = use_context::<ViewerStateContext>().expect("ViewerStateContext not found");
+    let title = use_state(String::new);
+    let description = use_state(String::new);
+    let author = use_state(String::new);
+    let open_topic = use_state(|| None::<String>);
+    let import_input_ref = use_node_ref();
+    let import_reader = use_state(|| None::<FileReader>);
+
+    if state.entities.is_empty() {
+        return html! {};
+    }
+
+    // New topic from the current camera, section, visibility and selection
+    let on_create = {
+        let state = state.clone();
+        let title = title.clone();
+        let description = description.clone();
+        let author = author.clone();
+        let open_topic = open_topic.clone();
+        Callback::from(move |_: MouseEvent| {
+            let topic_title = title.trim().to_string();
+            if topic_title.is_empty() {
+                return;
+            }
+            let view =
+                NamedView::capture(topic_title.clone(), &state).with_camera(bridge::load_camera());
+            let bounds = bridge::load_scene_bounds();
+            let bcf = viewpoint::to_bcf(
+                &view,
+                &state.entities,
+                bounds.as_ref(),
+                viewpoint::new_guid(),
+            );
+            let topic = BcfTopic {
+                guid: viewpoint::new_guid(),
+                title: topic_title,
+                description: Some(description.trim().to_string()).filter(|d| !d.is_empty()),
+                topic_type: Some("Issue".to_string()),
+                status: Some("Open".to_string()),
+                creation_date: js_sys::Date::new_0().to_iso_string().into(),
+                author: Some(author.trim().to_string())
+                    .filter(|a| !a.is_empty())
+                    .unwrap_or_else(|| "ifc-lite".to_string()),
+                comments: Vec::new(),
+                viewpoints: vec![BcfTopicViewpoint {
+                    viewpoint: bcf,
+                    snapshot: capture_canvas_png("bevy-canvas"),
+                }],
+            };
+            open_topic.set(Some(topic.guid.clone()));
+            state.dispatch(ViewerAction::ImportBcfTopics(vec![topic]));
+            title.set(String::new());
+            description.set(String::new());
+        })
+    };
+
+    let on_import = {
+        let state = state.clone();
+        let import_reader = import_reader.clone();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let Some(file) = input.files().and_then(|files| files.get(0)) else {
+                return;
+            };
+            input.set_value("");
+
+            let state = state.clone();
+            let reader =
+                gloo_file::callbacks::read_as_bytes(&gloo_file::File::from(file), move |result| {
+                    let parsed = result
+                        .map_err(|e| e.to_string())
+                        .and_then(|bytes| BcfArchive::read(&bytes).map_err(|e| e.to_string()));
+                    match parsed {
+                        Ok(archive) => {
+                            state.dispatch(ViewerAction::ImportBcfTopics(archive.topics));
+                        }
+                        Err(e) => bridge::log_error(&format!("Failed to import BCF: {}", e)),
+                    }
+                });
+            import_reader.set(Some(reader));
+        })
+    };
+
+    let on_export = {
+        let state = state.clone();
+        Callback::from(move |_: MouseEvent| {
+            let archive = BcfArchive {
+                topics: (*state.bcf_topics).clone(),
+            };
+            let stem = state
+                .file_name
+                .as_deref()
+                .map(|name| file_stem(name.trim_end_matches(".ifc")))
+                .unwrap_or_else(|| "issues".to_string());
+            download_file(
+                &format!("{}.bcfzip", stem),
+                "application/zip",
+                &archive.write(),
+            );
+        })
+    };
+
+    let text_input = |value: &UseStateHandle<String>, placeholder: &'static str| {
+        let value = value.clone();
+        html! {
+            <input
+                type="text"
+                class="search-input"
+                placeholder={placeholder}
+                value={(*value).clone()}
+                oninput={Callback::from(move |e: InputEvent| {
+                    let input: HtmlInputElement = e.target_unchecked_into();
+                    value.set(input.value());
+                })}
+            />
+        }
+    };
+
+    html! {
+        <div class="bcf-panel">
+            <div class="bcf-header">
+                <span class="section-header">{"Issues (BCF)"}</span>
+                <input
+                    ref={import_input_ref.clone()}
+                    type="file"
+                    accept=".bcfzip,.bcf"
+                    style="display: none"
+                    onchange={on_import}
+                />
+                <button
+                    class="copy-btn"
+                    onclick={Callback::from(move |_: MouseEvent| {
+                        if let Some(input) = import_input_ref.cast::<HtmlInputElement>() {
+                            input.click();
+                        }
+                    })}
+                    title="Import topics from a .bcfzip file"
+                >
+                    {"📥"}
+                </button>
+                <button
+                    class="copy-btn"
+                    onclick={on_export}
+                    disabled={state.bcf_topics.is_empty()}
+                    title="Export all topics as .bcfzip"
+                >
+                    {"📤"}
+                </button>
+            </div>
+            <div class="bcf-new">
+                { text_input(&title, "New topic title…") }
+                { text_input(&description, "Description") }
+                <div class="views-save">
+                    { text_input(&author, "Author") }
+                    <button
+                        class="copy-btn"
+                        onclick={on_create}
+                        disabled={title.trim().is_empty()}
+                        title="Create a topic from the current view, selection and a screenshot"
+                    >
+                        {"➕"}
+                    </button>
+                </div>
+            </div>
+            <div class="views-list">
+                { for state.bcf_topics.iter().map(|topic| {
+                    let is_open = open_topic.as_deref() == Some(topic.guid.as_str());
+                    let apply = {
+                        let state = state.clone();
+                        let open_topic = open_topic.clone();
+                        let guid = topic.guid.clone();
+                        Callback::from(move |_| {
+                            open_topic.set(Some(guid.clone()));
+                            let Some(topic) = state.bcf_topics.iter().find(|t| t.guid == guid)
+                            else {
+                                return;
+                            };
+                            let Some(first) = topic.viewpoints.first() else {
+                                return;
+                            };
+                            let bounds = bridge::load_scene_bounds();
+                            let view = viewpoint::from_bcf(
+                                topic.title.clone(),
+                                &first.viewpoint,
+                                &state.entities,
+                                bounds.as_ref(),
+                            );
+                            if let Some(camera) = &view.camera {
+                                bridge::save_camera_view(camera);
+                            }
+                            state.dispatch(ViewerAction::RestoreView(view));
+                        })
+                    };
+                    let delete = {
+                        let state = state.clone();
+                        let guid = topic.guid.clone();
+                        Callback::from(move |e: MouseEvent| {
+                            e.stop_propagation();
+                            state.dispatch(ViewerAction::DeleteBcfTopic(guid.clone()));
+                        })
+                    };
+                    let snapshot = topic.viewpoints.iter().find_map(|v| v.snapshot.as_deref());
+                    html! {
+                        <div class={classes!("bcf-topic", is_open.then_some("active"))}>
+                            <div class="view-row" onclick={apply} title="Show topic viewpoint">
+                                if let Some(png) = snapshot {
+                                    <img class="bcf-thumbnail" src={png_data_url(png)} alt="" />
+                                }
+                                <span class="view-name">{&topic.title}</span>
+                                if let Some(status) = &topic.status {
+                                    <span class="bcf-status">{status}</span>
+                                }
+                                if !topic.comments.is_empty() {
+                                    <span class="bcf-count" title="Comments">
+                                        {format!("💬{}", topic.comments.len())}
+                                    </span>
+                                }
+                                <button class="copy-btn" onclick={delete} title="Delete topic">
+                                    {"✕"}
+                                </button>
+                            </div>
+                            if is_open {
+                                <div class="bcf-details">
+                                    <div class="bcf-meta">
+                                        {format!("{} · {}", topic.author, topic.creation_date)}
+                                    </div>
+                                    if let Some(text) = &topic.description {
+                                        <div class="bcf-description">{text}</div>
+                                    }
+                                    { for topic.comments.iter().map(|comment| html! {
+                                        <div class="bcf-comment">
+                                            <div class="bcf-meta">
+                                                {format!("{} · {}", comment.author, comment.date)}
+                                            </div>
+                                            <div>{&comment.text}</div>
+                                        </div>
+                                    })}
+                                </div>
+                            }
+                        </div>
+                    }
+                })}
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,132 @@
+//! Color legend - picks what the viewport colors encode and explains them
+//!
+//! Shown over the viewport while a color mode other than the model colors
+//! is active. Clicking a row selects the entities drawn in that color.
+
+use crate::palette::{css_color, property_choices, use_palette, REMOVED_COLOR};
+use crate::state::{ColorMode, ViewerAction, ViewerStateContext};
+use ifc_lite_core::DiffStatus;
+use web_sys::HtmlSelectElement;
+use yew::prelude::*;
+
+/// Legend of the active color mode with a mode picker
+#[function_component]
+pub fn ColorLegend() -> Html {
+    let state = use_context::<ViewerStateContext>().expect("ViewerStateContext not found");
+    let palette = use_palette(&state);
+    let choices = {
+        let entities = state.clone();
+        use_memo(
+            (state.content_hash.clone(), state.entities.len()),
+            move |_| property_choices(&entities.entities),
+        )
+    };
+
+    let on_mode = {
+        let state = state.clone();
+        let choices = choices.clone();
+        Callback::from(move |e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            let value = select.value();
+            let mode = match value.as_str() {
+                "type" => ColorMode::TypeObject,
+                "material" => ColorMode::Material,
+                "system" => ColorMode::System,
+                "diff" => ColorMode::Diff,
+                _ => match value.parse::<usize>().ok().and_then(|i| choices.get(i)) {
+                    Some((pset, property)) => ColorMode::Property {
+                        pset: pset.clone(),
+                        property: property.clone(),
+                    },
+                    None => return,
+                },
+            };
+            state.dispatch(ViewerAction::SetColorMode(mode));
+        })
+    };
+
+    let on_close = {
+        let state = state.clone();
+        Callback::from(move |_: MouseEvent| {
+            state.dispatch(ViewerAction::SetColorMode(ColorMode::Model))
+        })
+    };
+
+    html! {
+        <div class="color-legend">
+            <div class="color-legend-header">
+                <select class="color-legend-mode" onchange={on_mode} title="Color by">
+                    <option value="type" selected={state.color_mode == ColorMode::TypeObject}>
+                        {"Type Object"}
+                    </option>
+                    <option value="material" selected={state.color_mode == ColorMode::Material}>
+                        {"Material"}
+                    </option>
+                    if !state.systems.is_empty() {
+                        <option value="system" selected={state.color_mode == ColorMode::System}>
+                            {"System"}
+                        </option>
+                    }
+                    if state.diff.is_some() {
+                        <option value="diff" selected={state.color_mode == ColorMode::Diff}>
+                            {"Changes"}
+                        </option>
+                    }
+                    { for choices.iter().enumerate().map(|(i, (pset, property))| {
+                        let selected = matches!(
+                            &state.color_mode,
+                            ColorMode::Property { pset: p, property: q } if p == pset && q == property
+                        );
+                        html! {
+                            <option value={i.to_string()} {selected}>
+                                {format!("{}.{}", pset, property)}
+                            </option>
+                        }
+                    }) }
+                </select>
+                <button class="copy-btn" onclick={on_close} title="Back to model colors">
+                    {"✕"}
+                </button>
+            </div>
+            <div class="color-legend-list">
+                { for palette.groups.iter().map(|group| {
+                    let onclick = {
+                        let state = state.clone();
+                        let entities = group.entities.clone();
+                        Callback::from(move |_: MouseEvent| {
+                            state.dispatch(ViewerAction::ClearSelection);
+                            for &id in &entities {
+                                state.dispatch(ViewerAction::AddToSelection(id));
+                            }
+                        })
+                    };
+                    html! {
+                        <div class="color-legend-row" {onclick} title="Select these elements">
+                            <span
+                                class="color-legend-swatch"
+                                style={format!("background: {}", css_color(group.color))}
+                            />
+                            <span class="color-legend-label">{&group.label}</span>
+                            <span class="color-legend-count">{group.entities.len()}</span>
+                        </div>
+                    }
+                }) }
+                // Removed elements have no geometry here, so only count them
+                if let (ColorMode::Diff, Some(diff)) = (&state.color_mode, &state.diff) {
+                    if diff.count(DiffStatus::Removed) > 0 {
+                        <div class="color-legend-row" title="Only in the compared version">
+                            <span
+                                class="color-legend-swatch"
+                                style={format!("background: {}", css_color(REMOVED_COLOR))}
+                            />
+                            <span class="color-legend-label">{"Removed"}</span>
+                            <span class="color-legend-count">
+                                {diff.count(DiffStatus::Removed)}
+                            </span>
+                        </div>
+                    }
+                }
+            </div>
+        </div>
+    }
+}
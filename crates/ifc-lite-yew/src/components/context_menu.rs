@@ -0,0 +1,126 @@
+//! Context menu - entity actions at a right click in the viewport
+//!
+//! Bevy publishes the entity under a right click; the menu opens at the
+//! click and closes on the next click anywhere else.
+
+use crate::bridge::{save_focus, take_context_menu, ContextMenuData, FocusData};
+use crate::state::{AnnotationAnchor, ViewerAction, ViewerStateContext};
+use crate::utils::{copy_to_clipboard, get_entity_display_name};
+use yew::prelude::*;
+
+/// Right-click menu over the viewport
+#[function_component]
+pub fn ContextMenu() -> Html {
+    let state = use_context::<ViewerStateContext>().expect("ViewerStateContext not found");
+    let menu = use_state(|| None::<ContextMenuData>);
+
+    {
+        let menu = menu.clone();
+        use_effect_with((), move |_| {
+            let interval = gloo::timers::callback::Interval::new(50, move || {
+                if let Some(latest) = take_context_menu() {
+                    menu.set(Some(latest));
+                }
+            });
+            move || drop(interval)
+        });
+    }
+
+    let Some(open) = &*menu else {
+        return html! {};
+    };
+    let Some(entity) = state.entities.iter().find(|e| e.id == open.id) else {
+        return html! {};
+    };
+
+    // Run an action on the clicked entity, then close the menu
+    let action = |f: Box<dyn Fn()>| {
+        let menu = menu.clone();
+        Callback::from(move |e: MouseEvent| {
+            e.stop_propagation();
+            f();
+            menu.set(None);
+        })
+    };
+    let dispatch = |make: fn(u64) -> ViewerAction| {
+        let state = state.clone();
+        let id = entity.id;
+        action(Box::new(move || state.dispatch(make(id))))
+    };
+
+    let on_zoom = {
+        let entity_id = entity.id;
+        action(Box::new(move || save_focus(&FocusData { entity_id })))
+    };
+    let on_properties = {
+        let state = state.clone();
+        let id = entity.id;
+        action(Box::new(move || {
+            state.dispatch(ViewerAction::Select(id));
+            state.dispatch(ViewerAction::SetRightPanelCollapsed(false));
+        }))
+    };
+    let on_annotate = {
+        let state = state.clone();
+        let id = entity.id;
+        action(Box::new(move || {
+            state.dispatch(ViewerAction::BeginAnnotation(AnnotationAnchor {
+                entity_id: Some(id),
+                point: None,
+            }))
+        }))
+    };
+    let on_copy = entity
+        .global_id
+        .clone()
+        .map(|global_id| action(Box::new(move || copy_to_clipboard(&global_id))));
+
+    let on_close = {
+        let menu = menu.clone();
+        Callback::from(move |e: MouseEvent| {
+            e.prevent_default();
+            menu.set(None);
+        })
+    };
+
+    html! {
+        <div
+            class="context-menu-backdrop"
+            onclick={on_close.clone()}
+            oncontextmenu={on_close}
+        >
+            <div
+                class="context-menu"
+                style={format!("left: {:.0}px; top: {:.0}px", open.x, open.y)}
+                onclick={Callback::from(|e: MouseEvent| e.stop_propagation())}
+            >
+                <div class="context-menu-title">
+                    {get_entity_display_name(&entity.entity_type)}
+                    if let Some(name) = &entity.name {
+                        {" · "}{name}
+                    }
+                </div>
+                <button class="context-menu-item" onclick={dispatch(ViewerAction::HideEntity)}>
+                    {"Hide"}
+                </button>
+                <button class="context-menu-item" onclick={dispatch(ViewerAction::IsolateEntity)}>
+                    {"Isolate"}
+                </button>
+                <button class="context-menu-item" onclick={on_zoom}>{"Zoom To"}</button>
+                <button class="context-menu-item" onclick={on_properties}>
+                    {"Show Properties"}
+                </button>
+                <button class="context-menu-item" onclick={on_annotate}>
+                    {"Add Annotation"}
+                </button>
+                <button
+                    class="context-menu-item"
+                    disabled={on_copy.is_none()}
+                    onclick={on_copy.unwrap_or_default()}
+                >
+                    {"Copy GlobalId"}
+                </button>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,190 @@
+//! Debug overlay - frame statistics and where the time of the last load went
+//!
+//! Toggled with F12 and open from the start with `?debug=1`. The top shows
+//! the frame rate, the triangles and meshes Bevy draws, the batch count and
+//! the wasm heap of the viewer and the UI. Below are the load stages of the
+//! engine, the time Bevy took to batch and upload the geometry, and the
+//! slowest geometry processors. The load report can be logged or exported.
+
+use crate::bridge::{self, load_batch_timings, load_frame_stats, BatchTimingsData, FrameStatsData};
+use crate::state::ViewerStateContext;
+use crate::utils::{copy_to_clipboard, download_file, file_stem, format_file_size};
+use ifc_lite_engine::profile::stage;
+use ifc_lite_engine::LoadProfile;
+use yew::prelude::*;
+
+/// Processors listed in the overlay; exports have all of them
+const SHOWN_PROCESSORS: usize = 8;
+
+/// Load profile with the batching and upload stages reported by Bevy
+fn full_profile(profile: &LoadProfile, batch: Option<&BatchTimingsData>) -> LoadProfile {
+    let mut profile = profile.clone();
+    if let Some(batch) = batch {
+        profile.record(stage::BATCHING, batch.batching_ms);
+        profile.record(stage::UPLOAD, batch.upload_ms);
+    }
+    profile
+}
+
+fn format_heap(bytes: Option<u64>) -> String {
+    bytes
+        .map(|b| format_file_size(b as usize))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// Frame rate, draw counts and heap sizes
+fn frame_rows(frame: Option<&FrameStatsData>) -> Html {
+    let Some(frame) = frame else {
+        return html! { <span class="debug-overlay-note">{"Waiting for the renderer"}</span> };
+    };
+    let row = |label: &'static str, value: String| {
+        html! {
+            <tr>
+                <td>{label}</td>
+                <td class="debug-overlay-ms">{value}</td>
+            </tr>
+        }
+    };
+    html! {
+        <table class="debug-overlay-table">
+            {row("FPS", format!("{:.0}", frame.fps))}
+            {row("Frame", format!("{:.1} ms (max {:.1})", frame.frame_ms, frame.frame_ms_max))}
+            {row("Triangles", frame.triangles.to_string())}
+            {row("Draws / batches", format!("{} / {}", frame.draws, frame.batches))}
+            {row("Heap (viewer)", format_heap(frame.heap_bytes))}
+            {row("Heap (UI)", format_heap(crate::memory::heap_bytes()))}
+        </table>
+    }
+}
+
+/// Frame statistics and load timings over the viewport
+#[function_component]
+pub fn DebugOverlay() -> Html {
+    let state = use_context::<ViewerStateContext>().expect("ViewerStateContext not found");
+    let batch = use_state(|| None::<BatchTimingsData>);
+    let frame = use_state(|| None::<FrameStatsData>);
+    let collapsed = use_state(|| false);
+
+    {
+        let batch = batch.clone();
+        let frame = frame.clone();
+        use_effect_with((), move |_| {
+            let interval = gloo::timers::callback::Interval::new(500, move || {
+                let latest = load_batch_timings();
+                if *batch != latest {
+                    batch.set(latest);
+                }
+                let latest = load_frame_stats();
+                if *frame != latest {
+                    frame.set(latest);
+                }
+            });
+            move || drop(interval)
+        });
+    }
+
+    let on_toggle = {
+        let collapsed = collapsed.clone();
+        Callback::from(move |_: MouseEvent| collapsed.set(!*collapsed))
+    };
+    let header = html! {
+        <div class="debug-overlay-header" onclick={on_toggle}>
+            <span>{"Stats"}</span>
+            <span class="debug-overlay-total">
+                { frame.as_ref().map(|f| format!("{:.0} fps", f.fps)).unwrap_or_default() }
+            </span>
+        </div>
+    };
+    if *collapsed {
+        return html! { <div class="debug-overlay">{header}</div> };
+    }
+
+    let Some(profile) = state.load_profile.as_deref() else {
+        return html! {
+            <div class="debug-overlay">
+                {header}
+                {frame_rows(frame.as_ref())}
+                <div class="debug-overlay-section">{"Load profile"}</div>
+                <span class="debug-overlay-note">
+                    { if state.entities.is_empty() { "No model loaded" } else { "Loaded from cache" } }
+                </span>
+            </div>
+        };
+    };
+    let profile = full_profile(profile, batch.as_ref());
+    let total = profile.total_ms();
+
+    let on_log = {
+        let report = profile.to_string();
+        Callback::from(move |_: MouseEvent| bridge::log_info(&report))
+    };
+    let json = serde_json::to_string_pretty(&profile).unwrap_or_default();
+    let on_copy = {
+        let json = json.clone();
+        Callback::from(move |_: MouseEvent| copy_to_clipboard(&json))
+    };
+    let on_export = {
+        let stem = state
+            .file_name
+            .as_deref()
+            .map(|name| file_stem(name.trim_end_matches(".ifc")))
+            .unwrap_or_else(|| "model".to_string());
+        Callback::from(move |_: MouseEvent| {
+            download_file(
+                &format!("{}-load-profile.json", stem),
+                "application/json",
+                json.as_bytes(),
+            )
+        })
+    };
+
+    html! {
+        <div class="debug-overlay">
+            {header}
+            {frame_rows(frame.as_ref())}
+            <div class="debug-overlay-section">
+                {format!("Load profile: {:.0} ms", total)}
+            </div>
+            <table class="debug-overlay-table">
+                { for profile.stages.iter().map(|s| {
+                    let share = if total > 0.0 { 100.0 * s.ms / total } else { 0.0 };
+                    html! {
+                        <tr>
+                            <td>{&s.name}</td>
+                            <td class="debug-overlay-ms">{format!("{:.1}", s.ms)}</td>
+                            <td class="debug-overlay-bar-cell">
+                                <div
+                                    class="debug-overlay-bar"
+                                    style={format!("width: {:.0}%", share)}
+                                />
+                            </td>
+                        </tr>
+                    }
+                })}
+            </table>
+            if !profile.processors.is_empty() {
+                <div class="debug-overlay-section">{"Processors (CPU ms)"}</div>
+                <table class="debug-overlay-table">
+                    { for profile.processors.iter().take(SHOWN_PROCESSORS).map(|p| html! {
+                        <tr>
+                            <td title={p.name.clone()}>
+                                {p.name.trim_start_matches("Ifc")}
+                            </td>
+                            <td class="debug-overlay-ms">{p.calls}</td>
+                            <td class="debug-overlay-ms">{format!("{:.1}", p.ms)}</td>
+                        </tr>
+                    })}
+                </table>
+            }
+            <div class="debug-overlay-actions">
+                <button onclick={on_log} title="Print the load report to the console">
+                    {"Log"}
+                </button>
+                <button onclick={on_copy} title="Copy the load report as JSON">{"Copy"}</button>
+                <button onclick={on_export} title="Download the load report as JSON">
+                    {"Export"}
+                </button>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,76 @@
+//! Diagnostics panel - entities the last load skipped or could not mesh
+
+use crate::state::{ViewerAction, ViewerStateContext};
+use ifc_lite_core::{Diagnostic, Severity};
+use yew::prelude::*;
+
+/// Table row for one diagnostic; rows of loaded entities select them
+pub(super) fn diagnostic_row(diagnostic: &Diagnostic, state: &ViewerStateContext) -> Html {
+    let (icon, class) = match diagnostic.severity {
+        Severity::Error => ("✖", "status-error"),
+        Severity::Warning => ("⚠", "status-warning"),
+    };
+    let location = match (diagnostic.line, diagnostic.entity_id) {
+        (Some(line), Some(id)) => format!("{}: #{}", line, id),
+        (None, Some(id)) => format!("#{}", id),
+        (Some(line), None) => line.to_string(),
+        (None, None) => "file".to_string(),
+    };
+    // Rows for entities that made it into the model select them
+    let onclick = diagnostic
+        .entity_id
+        .map(u64::from)
+        .filter(|id| state.entities.iter().any(|e| e.id == *id))
+        .map(|id| {
+            let state = state.clone();
+            Callback::from(move |_: MouseEvent| state.dispatch(ViewerAction::Select(id)))
+        });
+    let title = diagnostic.entity_type.clone();
+
+    html! {
+        <tr class="diagnostic-row" {onclick} {title}>
+            <td class={class}>{icon}</td>
+            <td class="stats-label">{location}</td>
+            <td>{&diagnostic.message}</td>
+        </tr>
+    }
+}
+
+/// Modal listing the problems recorded while loading the model
+#[function_component]
+pub fn DiagnosticsPanel() -> Html {
+    let state = use_context::<ViewerStateContext>().expect("ViewerStateContext not found");
+
+    let close = {
+        let state = state.clone();
+        Callback::from(move |_: MouseEvent| state.dispatch(ViewerAction::ToggleDiagnosticsPanel))
+    };
+
+    let errors = state
+        .diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .count();
+    let warnings = state.diagnostics.len() - errors;
+
+    html! {
+        <div class="dialog-backdrop" onclick={close.clone()}>
+            <div class="dialog" onclick={Callback::from(|e: MouseEvent| e.stop_propagation())}>
+                <div class="dialog-header">
+                    <span class="dialog-title">
+                        {format!("Load issues: {} errors, {} warnings", errors, warnings)}
+                    </span>
+                    <button class="copy-btn" onclick={close} title="Close">{"✕"}</button>
+                </div>
+
+                if state.diagnostics.is_empty() {
+                    <div class="stats-label">{"No problems found"}</div>
+                } else {
+                    <table class="stats-table">
+                        {for state.diagnostics.iter().map(|d| diagnostic_row(d, &state))}
+                    </table>
+                }
+            </div>
+        </div>
+    }
+}
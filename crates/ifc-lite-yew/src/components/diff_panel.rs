@@ -0,0 +1,169 @@
+//! Diff panel - compares the loaded model with an earlier version of it
+
+use crate::bridge;
+use crate::edits;
+use crate::state::{ViewerAction, ViewerStateContext};
+use crate::utils::{download_file, file_stem};
+use gloo_file::callbacks::FileReader;
+use ifc_lite_core::{DiffStatus, ModelDiff};
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+/// Elements added, removed or modified since a chosen earlier IFC file,
+/// matched by GlobalId
+#[function_component]
+pub fn DiffPanel() -> Html {
+    let state = use_context::<ViewerStateContext>().expect("ViewerStateContext not found");
+    let input_ref = use_node_ref();
+    let reader = use_state(|| None::<FileReader>);
+
+    if state.entities.is_empty() {
+        return html! {};
+    }
+
+    let on_file = {
+        let state = state.clone();
+        let reader = reader.clone();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let Some(file) = input.files().and_then(|files| files.get(0)) else {
+                return;
+            };
+            input.set_value("");
+            let Some(source) = edits::source() else {
+                bridge::log_error("Failed to compare: no model loaded");
+                return;
+            };
+
+            let state = state.clone();
+            let file_reader =
+                gloo_file::callbacks::read_as_text(&gloo_file::File::from(file), move |result| {
+                    match result {
+                        Ok(old) => {
+                            let diff = ModelDiff::compare(&old, &source);
+                            bridge::log(&format!(
+                                "Compared: {} added, {} removed, {} modified",
+                                diff.count(DiffStatus::Added),
+                                diff.count(DiffStatus::Removed),
+                                diff.count(DiffStatus::Modified)
+                            ));
+                            state.dispatch(ViewerAction::SetDiff(Some(diff)));
+                        }
+                        Err(e) => bridge::log_error(&format!("Failed to compare: {}", e)),
+                    }
+                });
+            reader.set(Some(file_reader));
+        })
+    };
+
+    let on_pick = Callback::from(move |_: MouseEvent| {
+        if let Some(input) = input_ref.cast::<HtmlInputElement>() {
+            input.click();
+        }
+    });
+
+    let summary = match &state.diff {
+        None => "no comparison".to_string(),
+        Some(diff) if diff.is_empty() => "no changes".to_string(),
+        Some(diff) => format!(
+            "+{} −{} ~{}",
+            diff.count(DiffStatus::Added),
+            diff.count(DiffStatus::Removed),
+            diff.count(DiffStatus::Modified)
+        ),
+    };
+
+    html! {
+        <div class="qto-panel">
+            <div class="qto-header">
+                <span class="section-header">{"Compare"}</span>
+                <span class="stats-label">{summary}</span>
+                <input
+                    ref={input_ref.clone()}
+                    type="file"
+                    accept=".ifc"
+                    style="display: none"
+                    onchange={on_file}
+                />
+                <button
+                    class="copy-btn"
+                    onclick={on_pick}
+                    title="Compare with an earlier version of this model"
+                >
+                    {"📂"}
+                </button>
+                if let Some(diff) = &state.diff {
+                    <button
+                        class="copy-btn"
+                        onclick={{
+                            let csv = diff.to_csv();
+                            let stem = state
+                                .file_name
+                                .as_deref()
+                                .map(|name| file_stem(name.trim_end_matches(".ifc")))
+                                .unwrap_or_else(|| "model".to_string());
+                            Callback::from(move |_: MouseEvent| {
+                                download_file(
+                                    &format!("{}-changes.csv", stem),
+                                    "text/csv",
+                                    csv.as_bytes(),
+                                );
+                            })
+                        }}
+                        disabled={diff.is_empty()}
+                        title="Export the changes as CSV"
+                    >
+                        {"⬇"}
+                    </button>
+                    <button
+                        class="copy-btn"
+                        onclick={{
+                            let state = state.clone();
+                            Callback::from(move |_: MouseEvent| {
+                                state.dispatch(ViewerAction::SetDiff(None))
+                            })
+                        }}
+                        title="Clear the comparison"
+                    >
+                        {"✕"}
+                    </button>
+                }
+            </div>
+            if let Some(diff) = &state.diff {
+                <div class="qto-list">
+                    <table class="stats-table">
+                        { for diff.elements.iter().map(|element| {
+                            // Removed elements only exist in the earlier version
+                            let onclick = element.new_id.map(|id| {
+                                let state = state.clone();
+                                Callback::from(move |_: MouseEvent| {
+                                    state.dispatch(ViewerAction::Select(u64::from(id)))
+                                })
+                            });
+                            let class = match element.status {
+                                DiffStatus::Added => "diff-added",
+                                DiffStatus::Removed => "diff-removed",
+                                DiffStatus::Modified => "diff-modified",
+                            };
+                            let mut changed = Vec::new();
+                            if element.attributes_changed {
+                                changed.push("attributes");
+                            }
+                            if element.geometry_changed {
+                                changed.push("geometry");
+                            }
+                            html! {
+                                <tr class="diagnostic-row" {onclick} title={element.global_id.clone()}>
+                                    <td class={class}>{element.status.as_str()}</td>
+                                    <td class="stats-label">{&element.ifc_type}</td>
+                                    <td>{element.name.as_deref().unwrap_or("-")}</td>
+                                    <td class="stats-label">{changed.join(", ")}</td>
+                                </tr>
+                            }
+                        }) }
+                    </table>
+                </div>
+            }
+        </div>
+    }
+}
@@ -0,0 +1,400 @@
+//! Entity table - every entity as a row, with property columns and CSV export
+//!
+//! The type, name, storey and GlobalId columns are followed by the property
+//! columns picked in the column chooser. A property column names its
+//! property like a query predicate: `Pset_WallCommon.FireRating`, or just
+//! `FireRating` for a property in any set. Rows follow the hierarchy search
+//! and sort by a click on a column header.
+
+use super::hierarchy_panel::{search_as_query, visible_range, ROW_HEIGHT};
+use crate::state::{EntityInfo, ViewerAction, ViewerStateContext};
+use crate::utils::{download_file, file_stem};
+use ifc_lite_core::qto::csv_field;
+use ifc_lite_core::{Predicate, Query, UnitSystem};
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+use web_sys::{Element, HtmlInputElement};
+use yew::prelude::*;
+
+/// Columns of every table, before the property columns
+const FIXED_COLUMNS: [&str; 4] = ["Type", "Name", "Storey", "GlobalId"];
+
+/// Sorted column and direction
+#[derive(Clone, Copy, PartialEq)]
+struct Sort {
+    column: usize,
+    descending: bool,
+}
+
+/// Entity with the text of each column
+#[derive(Clone, PartialEq)]
+struct TableRow {
+    id: u64,
+    cells: Vec<String>,
+}
+
+/// Property a column names, if it parses as a predicate
+fn column_predicate(column: &str) -> Option<Predicate> {
+    let query = Query::parse(&format!("[{}]", column)).ok()?;
+    query
+        .predicates
+        .into_iter()
+        .next()
+        .filter(|p| p.condition.is_none())
+}
+
+/// Column naming a property of a set, quoting names the query syntax
+/// would split
+fn column_name(pset: &str, property: &str) -> String {
+    let part = |name: &str| {
+        if name.contains(['.', '=', '!', '<', '>', '~', '[', ']']) {
+            format!("\"{}\"", name)
+        } else {
+            name.to_string()
+        }
+    };
+    if pset.is_empty() {
+        part(property)
+    } else {
+        format!("{}.{}", part(pset), part(property))
+    }
+}
+
+/// Property and quantity columns the loaded entities have values for
+fn available_columns(entities: &[EntityInfo]) -> Vec<String> {
+    let mut columns = BTreeSet::new();
+    for entity in entities {
+        for pset in &entity.property_sets {
+            for property in &pset.properties {
+                columns.insert(column_name(&pset.name, &property.name));
+            }
+        }
+        for quantity in &entity.quantities {
+            let (set, name) = quantity
+                .name
+                .split_once(": ")
+                .unwrap_or(("", &quantity.name));
+            columns.insert(column_name(set, name));
+        }
+    }
+    columns.into_iter().collect()
+}
+
+fn row_of(entity: &EntityInfo, predicates: &[Option<Predicate>], system: UnitSystem) -> TableRow {
+    let fixed = [
+        entity.entity_type.clone(),
+        entity.name.clone().unwrap_or_default(),
+        entity.storey.clone().unwrap_or_default(),
+        entity.global_id.clone().unwrap_or_default(),
+    ];
+    let properties = predicates.iter().map(|predicate| {
+        predicate
+            .as_ref()
+            .and_then(|p| entity.property_text(p, system))
+            .unwrap_or_default()
+    });
+    TableRow {
+        id: entity.id,
+        cells: fixed.into_iter().chain(properties).collect(),
+    }
+}
+
+/// Numbers (with or without a unit) compare by value, the rest as text
+fn compare_cells(a: &str, b: &str) -> Ordering {
+    let number = |cell: &str| cell.split(' ').next()?.parse::<f64>().ok();
+    match (number(a), number(b)) {
+        (Some(x), Some(y)) => x.total_cmp(&y),
+        _ => a.to_lowercase().cmp(&b.to_lowercase()),
+    }
+}
+
+/// Rows matching the search, sorted
+fn table_rows(
+    entities: &[EntityInfo],
+    columns: &[String],
+    search_query: &str,
+    sort: Option<Sort>,
+    system: UnitSystem,
+) -> Vec<TableRow> {
+    let predicates: Vec<_> = columns.iter().map(|c| column_predicate(c)).collect();
+    let trimmed = search_query.trim();
+    let query = search_as_query(trimmed);
+    let text = trimmed.to_lowercase();
+
+    let mut rows: Vec<TableRow> = entities
+        .iter()
+        .filter(|e| query.as_ref().is_none_or(|q| e.matches_query(q)))
+        .map(|e| row_of(e, &predicates, system))
+        .filter(|row| {
+            query.is_some()
+                || text.is_empty()
+                || row.cells.iter().any(|c| c.to_lowercase().contains(&text))
+        })
+        .collect();
+
+    if let Some(sort) = sort {
+        rows.sort_by(|a, b| {
+            let order = compare_cells(&a.cells[sort.column], &b.cells[sort.column]);
+            if sort.descending {
+                order.reverse()
+            } else {
+                order
+            }
+        });
+    }
+    rows
+}
+
+/// Rows as CSV, with the entity id in front
+fn to_csv(headers: &[String], rows: &[TableRow]) -> String {
+    let mut out = String::from("Id");
+    for header in headers {
+        out.push(',');
+        out.push_str(&csv_field(header));
+    }
+    out.push('\n');
+    for row in rows {
+        out.push_str(&row.id.to_string());
+        for cell in &row.cells {
+            out.push(',');
+            out.push_str(&csv_field(cell));
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Sortable table of all entities with a column chooser
+#[function_component]
+pub fn EntityTable() -> Html {
+    let state = use_context::<ViewerStateContext>().expect("ViewerStateContext not found");
+    let sort = use_state(|| None::<Sort>);
+    let chooser_open = use_state(|| false);
+    let first_row = use_state_eq(|| 0_usize);
+    let rows_in_view = use_state_eq(|| 16_usize);
+    let scroll_ref = use_node_ref();
+
+    let rows = {
+        let entities = state.clone();
+        use_memo(
+            (
+                state.content_hash.clone(),
+                state.entities.len(),
+                state.table_columns.clone(),
+                state.search_query.clone(),
+                *sort,
+                state.unit_system,
+            ),
+            move |(_, _, columns, search_query, sort, system)| {
+                table_rows(&entities.entities, columns, search_query, *sort, *system)
+            },
+        )
+    };
+    let available = {
+        let entities = state.clone();
+        use_memo(
+            (state.content_hash.clone(), state.entities.len()),
+            move |_| available_columns(&entities.entities),
+        )
+    };
+    let headers: Vec<String> = FIXED_COLUMNS
+        .iter()
+        .map(|c| c.to_string())
+        .chain(state.table_columns.iter().cloned())
+        .collect();
+
+    let measure = {
+        let first_row = first_row.clone();
+        let rows_in_view = rows_in_view.clone();
+        let scroll_ref = scroll_ref.clone();
+        Callback::from(move |_: ()| {
+            if let Some(element) = scroll_ref.cast::<Element>() {
+                first_row.set((element.scroll_top() as f64 / ROW_HEIGHT) as usize);
+                rows_in_view.set((element.client_height() as f64 / ROW_HEIGHT).ceil() as usize);
+            }
+        })
+    };
+    {
+        let measure = measure.clone();
+        use_effect_with((), move |_| {
+            measure.emit(());
+            let window = gloo::utils::window();
+            let listener =
+                gloo::events::EventListener::new(&window, "resize", move |_| measure.emit(()));
+            move || drop(listener)
+        });
+    }
+    let onscroll = Callback::from(move |_: Event| measure.emit(()));
+
+    let on_sort = {
+        let sort = sort.clone();
+        Callback::from(move |column: usize| {
+            sort.set(Some(Sort {
+                column,
+                descending: (*sort).is_some_and(|s| s.column == column && !s.descending),
+            }));
+        })
+    };
+
+    let set_column = {
+        let state = state.clone();
+        let sort = sort.clone();
+        Callback::from(move |(column, shown): (String, bool)| {
+            let mut columns = state.table_columns.clone();
+            if shown && !columns.contains(&column) {
+                columns.push(column);
+            } else if !shown {
+                columns.retain(|c| *c != column);
+                // Indices of later columns shift
+                sort.set(None);
+            }
+            state.dispatch(ViewerAction::SetTableColumns(columns));
+        })
+    };
+
+    // Columns typed in, e.g. "FireRating" for that property in any set
+    let on_custom_column = {
+        let set_column = set_column.clone();
+        Callback::from(move |e: KeyboardEvent| {
+            if e.key() != "Enter" {
+                return;
+            }
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let column = input.value().trim().to_string();
+            if column_predicate(&column).is_some() {
+                set_column.emit((column, true));
+                input.set_value("");
+            } else {
+                crate::bridge::log_error(&format!("Not a property column: {}", column));
+            }
+        })
+    };
+
+    let on_export = {
+        let rows = rows.clone();
+        let headers = headers.clone();
+        let stem = state
+            .file_name
+            .as_deref()
+            .map(|name| file_stem(name.trim_end_matches(".ifc")))
+            .unwrap_or_else(|| "model".to_string());
+        Callback::from(move |_: MouseEvent| {
+            download_file(
+                &format!("{}-entities.csv", stem),
+                "text/csv",
+                to_csv(&headers, &rows).as_bytes(),
+            );
+        })
+    };
+
+    let range = visible_range(*first_row, *rows_in_view, rows.len());
+    let spacer_top = range.start as f64 * ROW_HEIGHT;
+    let total_height = rows.len() as f64 * ROW_HEIGHT;
+
+    html! {
+        <div class="entity-table">
+            <div class="tree-controls">
+                <span class="tree-count-total">
+                    {format!("{} of {} entities", rows.len(), state.entities.len())}
+                </span>
+                <button
+                    class={classes!("tree-control-btn", (*chooser_open).then_some("active"))}
+                    onclick={
+                        let chooser_open = chooser_open.clone();
+                        Callback::from(move |_: MouseEvent| chooser_open.set(!*chooser_open))
+                    }
+                    title="Choose property columns"
+                >
+                    {"☰"}
+                </button>
+                <button
+                    class="tree-control-btn"
+                    onclick={on_export}
+                    disabled={rows.is_empty()}
+                    title="Export the rows as CSV"
+                >
+                    {"📤"}
+                </button>
+            </div>
+
+            if *chooser_open {
+                <div class="entity-table-chooser">
+                    <input
+                        type="text"
+                        class="search-input"
+                        placeholder="Pset.Property, then Enter"
+                        onkeydown={on_custom_column}
+                    />
+                    <div class="entity-table-chooser-list">
+                        { for state.table_columns.iter().chain(
+                            available.iter().filter(|c| !state.table_columns.contains(c)),
+                        ).map(|column| {
+                            let shown = state.table_columns.contains(column);
+                            let onchange = {
+                                let set_column = set_column.clone();
+                                let column = column.clone();
+                                Callback::from(move |_: Event| {
+                                    set_column.emit((column.clone(), !shown))
+                                })
+                            };
+                            html! {
+                                <label class="entity-table-chooser-item" title={column.clone()}>
+                                    <input type="checkbox" checked={shown} {onchange} />
+                                    {column}
+                                </label>
+                            }
+                        })}
+                    </div>
+                </div>
+            }
+
+            <div class="entity-table-scroll" ref={scroll_ref} {onscroll}>
+                <div class="entity-table-row entity-table-header">
+                    { for headers.iter().enumerate().map(|(i, header)| {
+                        let arrow = match *sort {
+                            Some(s) if s.column == i => if s.descending { " ▼" } else { " ▲" },
+                            _ => "",
+                        };
+                        let onclick = {
+                            let on_sort = on_sort.clone();
+                            Callback::from(move |_: MouseEvent| on_sort.emit(i))
+                        };
+                        html! {
+                            <span class="entity-table-cell" title={header.clone()} {onclick}>
+                                {format!("{}{}", header, arrow)}
+                            </span>
+                        }
+                    })}
+                </div>
+                <div style={format!("height: {}px;", total_height)}>
+                    <div style={format!("height: {}px;", spacer_top)} />
+                    { for rows[range].iter().map(|row| {
+                        let id = row.id;
+                        let onclick = {
+                            let state = state.clone();
+                            Callback::from(move |_: MouseEvent| {
+                                state.dispatch(ViewerAction::Select(id))
+                            })
+                        };
+                        html! {
+                            <div
+                                key={id}
+                                class={classes!(
+                                    "entity-table-row",
+                                    state.selected_ids.contains(&id).then_some("selected")
+                                )}
+                                {onclick}
+                            >
+                                { for row.cells.iter().map(|cell| html! {
+                                    <span class="entity-table-cell" title={cell.clone()}>
+                                        {cell}
+                                    </span>
+                                })}
+                            </div>
+                        }
+                    })}
+                </div>
+            </div>
+        </div>
+    }
+}
@@ -1,16 +1,23 @@
 //! Hierarchy panel - entity tree view with virtual scrolling
 
+use super::entity_table::EntityTable;
+use super::types_panel::TypesPanel;
 use crate::bridge;
-use crate::components::toolbar::parse_and_process_ifc;
-use crate::state::{Progress, SpatialNode, SpatialNodeType, ViewerAction, ViewerStateContext};
+use crate::state::{
+    EntityInfo, Progress, SpatialNode, SpatialNodeType, SystemInfo, ViewerAction,
+    ViewerStateContext,
+};
+use crate::thumbnail::{request_thumbnails, storey_entities, storey_key, thumbnail_url};
 use gloo_file::callbacks::FileReader;
-use std::collections::HashSet;
-use wasm_bindgen_futures::spawn_local;
+use ifc_lite_core::Query;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::rc::Rc;
 use web_sys::{DragEvent, Element, HtmlInputElement};
 use yew::prelude::*;
 
 /// Row height in pixels (must match CSS)
-const ROW_HEIGHT: f64 = 28.0;
+pub(super) const ROW_HEIGHT: f64 = 28.0;
 /// Number of extra rows to render above/below viewport for smooth scrolling
 const OVERSCAN: usize = 5;
 
@@ -22,10 +29,66 @@ fn get_node_icon(node_type: &SpatialNodeType, entity_type: &str) -> &'static str
         SpatialNodeType::Building => "🏢",
         SpatialNodeType::Storey => "📐",
         SpatialNodeType::Space => "🚪",
-        SpatialNodeType::Element => crate::utils::get_entity_icon(entity_type),
+        SpatialNodeType::Facility => "🛣️",
+        SpatialNodeType::FacilityPart => "🧭",
+        SpatialNodeType::System => "🔗",
+        SpatialNodeType::TypeGroup | SpatialNodeType::Element => {
+            crate::utils::get_entity_icon(entity_type)
+        }
     }
 }
 
+/// Which hierarchy the panel shows
+#[derive(Clone, Copy, PartialEq)]
+enum HierarchyTab {
+    /// Project, site, building and storeys
+    Spatial,
+    /// Systems and zones with their members
+    Systems,
+    /// All entities as a table
+    Table,
+    /// IFC types with visibility toggles
+    Types,
+}
+
+/// One tree per system with its members as leaves
+fn system_trees(systems: &[SystemInfo], entities: &[EntityInfo]) -> Vec<SpatialNode> {
+    let by_id: HashMap<u64, &EntityInfo> = entities.iter().map(|e| (e.id, e)).collect();
+    systems
+        .iter()
+        .map(|system| SpatialNode {
+            id: system.id,
+            node_type: SpatialNodeType::System,
+            name: system.label(),
+            entity_type: system.entity_type.clone(),
+            predefined_type: None,
+            elevation: None,
+            // Members without a loaded entity (e.g. nested systems) are skipped
+            children: system
+                .members
+                .iter()
+                .filter_map(|id| by_id.get(id))
+                .map(|entity| SpatialNode {
+                    id: entity.id,
+                    node_type: SpatialNodeType::Element,
+                    name: entity
+                        .name
+                        .clone()
+                        .unwrap_or_else(|| format!("#{}", entity.id)),
+                    entity_type: entity.entity_type.clone(),
+                    predefined_type: entity.predefined_type.clone(),
+                    elevation: None,
+                    children: Vec::new(),
+                    has_geometry: true,
+                    referenced: false,
+                })
+                .collect(),
+            has_geometry: false,
+            referenced: false,
+        })
+        .collect()
+}
+
 /// Flattened tree row for virtual scrolling
 #[derive(Clone, PartialEq)]
 struct FlatRow {
@@ -36,49 +99,108 @@ struct FlatRow {
     depth: usize,
     has_children: bool,
     has_geometry: bool,
+    referenced: bool,
     child_count: usize,
+    /// Elements of a type group, empty for other nodes
+    members: Rc<[u64]>,
+}
+
+/// Hierarchy search: plain text, or a property query such as
+/// `IfcWall[Pset_WallCommon.FireRating=F90]`
+enum SearchFilter {
+    None,
+    Text(String),
+    Ids(HashSet<u64>),
+}
+
+/// Property query in a trimmed search, if it is one
+///
+/// Anything that parses as a query is one; partial input falls back to text.
+pub(super) fn search_as_query(trimmed: &str) -> Option<Query> {
+    let looks_like_query = trimmed.starts_with('[')
+        || trimmed
+            .get(..3)
+            .is_some_and(|p| p.eq_ignore_ascii_case("ifc"));
+    looks_like_query
+        .then(|| Query::parse(trimmed).ok())
+        .flatten()
+}
+
+impl SearchFilter {
+    fn new(search_query: &str, entities: &[EntityInfo]) -> Self {
+        let trimmed = search_query.trim();
+        if trimmed.is_empty() {
+            return Self::None;
+        }
+        if let Some(query) = search_as_query(trimmed) {
+            return Self::Ids(
+                entities
+                    .iter()
+                    .filter(|e| e.matches_query(&query))
+                    .map(|e| e.id)
+                    .collect(),
+            );
+        }
+        Self::Text(trimmed.to_lowercase())
+    }
+
+    /// Node itself matches, ignoring its descendants
+    fn matches_own(&self, n: &SpatialNode) -> bool {
+        match self {
+            Self::None => true,
+            Self::Text(q) => {
+                n.name.to_lowercase().contains(q)
+                    || n.entity_type.to_lowercase().contains(q)
+                    || n.predefined_type
+                        .as_ref()
+                        .is_some_and(|p| p.to_lowercase().contains(q))
+            }
+            Self::Ids(ids) => ids.contains(&n.id),
+        }
+    }
+
+    /// IDs of the nodes that match or have a matching descendant, `None`
+    /// without a search
+    fn matching_ids(&self, trees: &[&SpatialNode]) -> Option<HashSet<u64>> {
+        fn collect(filter: &SearchFilter, node: &SpatialNode, ids: &mut HashSet<u64>) -> bool {
+            let mut matched = filter.matches_own(node);
+            for child in &node.children {
+                matched |= collect(filter, child, ids);
+            }
+            if matched {
+                ids.insert(node.id);
+            }
+            matched
+        }
+
+        if matches!(self, Self::None) {
+            return None;
+        }
+        let mut ids = HashSet::new();
+        for tree in trees {
+            collect(self, tree, &mut ids);
+        }
+        Some(ids)
+    }
 }
 
 /// Flatten tree into visible rows based on expanded state
+///
+/// With a search, only nodes in `matching` (see
+/// [`SearchFilter::matching_ids`]) are kept.
 fn flatten_tree(
     node: &SpatialNode,
     depth: usize,
     expanded: &HashSet<u64>,
-    search_query: &str,
+    matching: Option<&HashSet<u64>>,
     rows: &mut Vec<FlatRow>,
 ) {
-    // Filter check for search
-    if !search_query.is_empty() {
-        let query = search_query.to_lowercase();
-        fn matches_query(n: &SpatialNode, q: &str) -> bool {
-            n.name.to_lowercase().contains(q)
-                || n.entity_type.to_lowercase().contains(q)
-                || n.children.iter().any(|c| matches_query(c, q))
-        }
-        if !matches_query(node, &query) {
-            return;
-        }
+    let is_match = |n: &SpatialNode| matching.is_none_or(|ids| ids.contains(&n.id));
+    if !is_match(node) {
+        return;
     }
 
-    let is_expanded = expanded.contains(&node.id);
-
-    // Count visible children (respecting search filter)
-    let visible_children: Vec<_> = if search_query.is_empty() {
-        node.children.iter().collect()
-    } else {
-        let query = search_query.to_lowercase();
-        node.children
-            .iter()
-            .filter(|child| {
-                fn matches_query(n: &SpatialNode, q: &str) -> bool {
-                    n.name.to_lowercase().contains(q)
-                        || n.entity_type.to_lowercase().contains(q)
-                        || n.children.iter().any(|c| matches_query(c, q))
-                }
-                matches_query(child, &query)
-            })
-            .collect()
-    };
+    let visible_children: Vec<_> = node.children.iter().filter(|c| is_match(c)).collect();
 
     rows.push(FlatRow {
         id: node.id,
@@ -88,17 +210,29 @@ fn flatten_tree(
         depth,
         has_children: !visible_children.is_empty(),
         has_geometry: node.has_geometry,
+        referenced: node.referenced,
         child_count: visible_children.len(),
+        members: match node.node_type {
+            SpatialNodeType::TypeGroup => node.children.iter().map(|c| c.id).collect(),
+            _ => Rc::from([]),
+        },
     });
 
     // Recurse into children if expanded
-    if is_expanded {
+    if expanded.contains(&node.id) {
         for child in visible_children {
-            flatten_tree(child, depth + 1, expanded, search_query, rows);
+            flatten_tree(child, depth + 1, expanded, matching, rows);
         }
     }
 }
 
+/// Rows to materialize for a scroll position, with overscan on both sides
+pub(super) fn visible_range(first_row: usize, rows_in_view: usize, len: usize) -> Range<usize> {
+    let start = first_row.saturating_sub(OVERSCAN).min(len);
+    let end = (first_row + rows_in_view + OVERSCAN).min(len);
+    start..end
+}
+
 /// Single row component (memoized for performance)
 #[derive(Properties, PartialEq)]
 struct RowProps {
@@ -106,15 +240,26 @@ struct RowProps {
     is_expanded: bool,
     is_selected: bool,
     is_hidden: bool,
+    /// Whether a storey's spaces are hidden, while space mode is on
+    spaces_hidden: Option<bool>,
+    /// Rendered thumbnail of a storey, as a data URL
+    thumbnail: Option<String>,
     on_toggle: Callback<u64>,
     on_select: Callback<u64>,
     on_toggle_visibility: Callback<u64>,
+    on_isolate: Callback<u64>,
+    on_toggle_spaces: Callback<String>,
+    /// Select, isolate or show/hide the members of a type group
+    on_select_members: Callback<Rc<[u64]>>,
+    on_isolate_members: Callback<Rc<[u64]>>,
+    on_set_members_hidden: Callback<(Rc<[u64]>, bool)>,
 }
 
 #[function_component]
 fn TreeRow(props: &RowProps) -> Html {
     let row = &props.row;
     let is_element = matches!(row.node_type, SpatialNodeType::Element);
+    let is_group = matches!(row.node_type, SpatialNodeType::TypeGroup);
 
     let on_toggle_click = {
         let on_toggle = props.on_toggle.clone();
@@ -125,14 +270,19 @@ fn TreeRow(props: &RowProps) -> Html {
         })
     };
 
+    // Type groups select all their members
     let on_name_click = {
         let on_select = props.on_select.clone();
+        let on_select_members = props.on_select_members.clone();
         let on_toggle = props.on_toggle.clone();
         let id = row.id;
         let is_elem = is_element;
+        let members = is_group.then(|| row.members.clone());
         Callback::from(move |_| {
             if is_elem {
                 on_select.emit(id);
+            } else if let Some(members) = &members {
+                on_select_members.emit(members.clone());
             } else {
                 on_toggle.emit(id);
             }
@@ -141,10 +291,39 @@ fn TreeRow(props: &RowProps) -> Html {
 
     let on_visibility_click = {
         let on_toggle_visibility = props.on_toggle_visibility.clone();
+        let on_set_members_hidden = props.on_set_members_hidden.clone();
+        let id = row.id;
+        let members = is_group.then(|| row.members.clone());
+        let is_hidden = props.is_hidden;
+        Callback::from(move |e: MouseEvent| {
+            e.stop_propagation();
+            match &members {
+                Some(members) => on_set_members_hidden.emit((members.clone(), !is_hidden)),
+                None => on_toggle_visibility.emit(id),
+            }
+        })
+    };
+
+    let on_isolate_click = {
+        let on_isolate = props.on_isolate.clone();
+        let on_isolate_members = props.on_isolate_members.clone();
         let id = row.id;
+        let members = is_group.then(|| row.members.clone());
+        Callback::from(move |e: MouseEvent| {
+            e.stop_propagation();
+            match &members {
+                Some(members) => on_isolate_members.emit(members.clone()),
+                None => on_isolate.emit(id),
+            }
+        })
+    };
+
+    let on_spaces_click = {
+        let on_toggle_spaces = props.on_toggle_spaces.clone();
+        let storey = row.name.clone();
         Callback::from(move |e: MouseEvent| {
             e.stop_propagation();
-            on_toggle_visibility.emit(id);
+            on_toggle_spaces.emit(storey.clone());
         })
     };
 
@@ -173,18 +352,47 @@ fn TreeRow(props: &RowProps) -> Html {
             // Icon
             <span class="tree-icon">{get_node_icon(&row.node_type, &row.entity_type)}</span>
 
+            if let Some(thumbnail) = props.thumbnail.clone() {
+                <img class="tree-thumbnail" src={thumbnail} alt="" />
+            }
+
             // Name
             <span class="tree-name" onclick={on_name_click}>
                 {&row.name}
             </span>
 
+            // Referenced elements are contained in another structure
+            if row.referenced {
+                <span class="tree-badge" title="Referenced here, contained in another structure">
+                    {"referenced"}
+                </span>
+            }
+
             // Child count badge
             if row.child_count > 0 && !is_element {
                 <span class="tree-count">{row.child_count}</span>
             }
 
-            // Visibility toggle for elements
-            if is_element && row.has_geometry {
+            // Systems and type groups isolate their members
+            if matches!(row.node_type, SpatialNodeType::System | SpatialNodeType::TypeGroup) {
+                <button class="visibility-btn" onclick={on_isolate_click} title="Isolate members">
+                    {"◎"}
+                </button>
+            }
+
+            // Storeys show or hide their spaces in space mode
+            if let Some(spaces_hidden) = props.spaces_hidden {
+                <button
+                    class={classes!("visibility-btn", spaces_hidden.then_some("hidden"))}
+                    onclick={on_spaces_click}
+                    title={if spaces_hidden { "Show spaces" } else { "Hide spaces" }}
+                >
+                    {"🚪"}
+                </button>
+            }
+
+            // Visibility toggle for elements and type groups
+            if (is_element || is_group) && row.has_geometry {
                 <button
                     class={classes!("visibility-btn", props.is_hidden.then_some("hidden"))}
                     onclick={on_visibility_click}
@@ -203,32 +411,81 @@ pub fn HierarchyPanel() -> Html {
     let state = use_context::<ViewerStateContext>().expect("ViewerStateContext not found");
     let is_dragging = use_state(|| false);
     let file_reader = use_state(|| None::<FileReader>);
-    let scroll_top = use_state(|| 0.0_f64);
-    let container_height = use_state(|| 400.0_f64);
+    // First row in view and rows that fit; rows only re-render when these change
+    let first_row = use_state_eq(|| 0_usize);
+    let rows_in_view = use_state_eq(|| 16_usize);
     let scroll_container_ref = use_node_ref();
+    // Selected entity to scroll to once its row exists
+    let pending_scroll = use_mut_ref(|| None::<u64>);
+    let tab = use_state(|| HierarchyTab::Spatial);
+    let systems = {
+        let entities = state.clone();
+        use_memo(
+            (
+                state.systems.clone(),
+                state.content_hash.clone(),
+                state.entities.len(),
+            ),
+            move |(systems, _, _)| system_trees(systems, &entities.entities),
+        )
+    };
+    // Back to the spatial tree when a model without systems loads
+    let tab_value = if state.systems.is_empty() && *tab == HierarchyTab::Systems {
+        HierarchyTab::Spatial
+    } else {
+        *tab
+    };
 
-    // Handle scroll events
-    let onscroll = {
-        let scroll_top = scroll_top.clone();
-        let container_height = container_height.clone();
+    let measure = {
+        let first_row = first_row.clone();
+        let rows_in_view = rows_in_view.clone();
         let scroll_container_ref = scroll_container_ref.clone();
-        Callback::from(move |_: Event| {
+        Callback::from(move |_: ()| {
             if let Some(element) = scroll_container_ref.cast::<Element>() {
-                scroll_top.set(element.scroll_top() as f64);
-                container_height.set(element.client_height() as f64);
+                first_row.set((element.scroll_top() as f64 / ROW_HEIGHT) as usize);
+                rows_in_view.set((element.client_height() as f64 / ROW_HEIGHT).ceil() as usize);
             }
         })
     };
 
-    // Update container height on mount
+    // Handle scroll events
+    let onscroll = {
+        let measure = measure.clone();
+        Callback::from(move |_: Event| measure.emit(()))
+    };
+
+    // Storey thumbnails, once the model is in Bevy
     {
-        let container_height = container_height.clone();
-        let scroll_container_ref = scroll_container_ref.clone();
-        use_effect_with((), move |_| {
-            if let Some(element) = scroll_container_ref.cast::<Element>() {
-                container_height.set(element.client_height() as f64);
-            }
-            || ()
+        let state = state.clone();
+        let ready = state.has_geometry && !state.loading;
+        use_effect_with(
+            (state.content_hash.clone(), state.storeys.len(), ready),
+            move |(_, _, ready)| {
+                if *ready {
+                    let requests = state
+                        .storeys
+                        .iter()
+                        .map(|s| {
+                            let entities = storey_entities(&state.entities, &s.name);
+                            (storey_key(&s.name), entities)
+                        })
+                        .collect();
+                    request_thumbnails(&state, requests);
+                }
+            },
+        );
+    }
+
+    // Measure on mount, when the tree replaces the table and whenever the
+    // window resizes
+    {
+        let measure = measure.clone();
+        use_effect_with(tab_value, move |_| {
+            measure.emit(());
+            let window = gloo::utils::window();
+            let listener =
+                gloo::events::EventListener::new(&window, "resize", move |_| measure.emit(()));
+            move || drop(listener)
         });
     }
 
@@ -261,8 +518,8 @@ pub fn HierarchyPanel() -> Html {
 
                         let content = String::from_utf8_lossy(&bytes).to_string();
                         let state_inner = state_clone.clone();
-                        spawn_local(async move {
-                            match parse_and_process_ifc(&content, &state_inner) {
+                        crate::worker::load_ifc(content, state_clone.clone(), move |result| {
+                            match result {
                                 Ok(_) => {
                                     bridge::log_info("IFC file loaded successfully");
                                     state_inner.dispatch(ViewerAction::SetLoading(false));
@@ -271,6 +528,7 @@ pub fn HierarchyPanel() -> Html {
                                     bridge::save_camera_cmd(&bridge::CameraCommand {
                                         cmd: "fit_all".to_string(),
                                         mode: None,
+                                        view: None,
                                     });
                                 }
                                 Err(e) => {
@@ -354,45 +612,151 @@ pub fn HierarchyPanel() -> Html {
         })
     };
 
-    // Flatten tree and compute visible range
-    let (rows, total_height, visible_rows) = if let Some(ref tree) = state.spatial_tree {
-        let mut rows = Vec::new();
-        flatten_tree(
-            tree,
-            0,
-            &state.expanded_nodes,
-            &state.search_query,
-            &mut rows,
-        );
+    let on_isolate = {
+        let state = state.clone();
+        Callback::from(move |id: u64| {
+            if let Some(system) = state.systems.iter().find(|s| s.id == id) {
+                state.dispatch(ViewerAction::IsolateEntities(
+                    system.members.iter().copied().collect(),
+                ));
+            }
+        })
+    };
 
-        let total_height = rows.len() as f64 * ROW_HEIGHT;
-        let start_idx = ((*scroll_top / ROW_HEIGHT) as usize).saturating_sub(OVERSCAN);
-        let visible_count = ((*container_height / ROW_HEIGHT) as usize) + OVERSCAN * 2;
-        let end_idx = (start_idx + visible_count).min(rows.len());
+    let on_toggle_spaces = {
+        let state = state.clone();
+        Callback::from(move |storey: String| {
+            state.dispatch(ViewerAction::ToggleStoreySpaces(storey));
+        })
+    };
 
-        let visible: Vec<_> = rows[start_idx..end_idx]
-            .iter()
-            .enumerate()
-            .map(|(i, row)| (start_idx + i, row.clone()))
-            .collect();
+    let on_select_members = {
+        let state = state.clone();
+        Callback::from(move |members: Rc<[u64]>| {
+            state.dispatch(ViewerAction::SetSelection(
+                members.iter().copied().collect(),
+            ));
+        })
+    };
 
-        (rows, total_height, visible)
-    } else {
-        (Vec::new(), 0.0, Vec::new())
+    let on_isolate_members = {
+        let state = state.clone();
+        Callback::from(move |members: Rc<[u64]>| {
+            state.dispatch(ViewerAction::IsolateEntities(
+                members.iter().copied().collect(),
+            ));
+        })
     };
 
-    let spacer_top = if !visible_rows.is_empty() {
-        visible_rows[0].0 as f64 * ROW_HEIGHT
+    let on_set_members_hidden = {
+        let state = state.clone();
+        Callback::from(move |(members, hidden): (Rc<[u64]>, bool)| {
+            let ids = members.iter().copied().collect();
+            state.dispatch(if hidden {
+                ViewerAction::HideEntities(ids)
+            } else {
+                ViewerAction::ShowEntities(ids)
+            });
+        })
+    };
+
+    // Flattening walks the whole tree, so it only reruns when the tree, the
+    // expanded nodes or the search change, not on scroll
+    let rows = {
+        let tree_state = state.clone();
+        let systems = systems.clone();
+        use_memo(
+            (
+                tab_value,
+                state.spatial_tree.as_ref().map(Rc::as_ptr),
+                Rc::as_ptr(&systems),
+                state.expanded_nodes.clone(),
+                state.search_query.clone(),
+                state.entities.len(),
+            ),
+            move |(tab, ..)| {
+                let trees: Vec<&SpatialNode> = match tab {
+                    HierarchyTab::Spatial => {
+                        tree_state.spatial_tree.as_deref().into_iter().collect()
+                    }
+                    HierarchyTab::Systems => systems.iter().collect(),
+                    HierarchyTab::Table | HierarchyTab::Types => Vec::new(),
+                };
+                let filter = SearchFilter::new(&tree_state.search_query, &tree_state.entities);
+                let matching = filter.matching_ids(&trees);
+                let mut rows = Vec::new();
+                for tree in trees {
+                    flatten_tree(
+                        tree,
+                        0,
+                        &tree_state.expanded_nodes,
+                        matching.as_ref(),
+                        &mut rows,
+                    );
+                }
+                rows
+            },
+        )
+    };
+    let has_tree = match tab_value {
+        HierarchyTab::Spatial => state.spatial_tree.is_some(),
+        HierarchyTab::Systems => !systems.is_empty(),
+        HierarchyTab::Table | HierarchyTab::Types => false,
+    };
+
+    // Expand the tree down to an entity selected elsewhere, e.g. in the viewport
+    let selected = if state.selected_ids.len() == 1 {
+        state.selected_ids.iter().next().copied()
     } else {
-        0.0
+        None
     };
+    {
+        let state = state.clone();
+        let pending_scroll = pending_scroll.clone();
+        use_effect_with(selected, move |selected| {
+            if let Some(id) = *selected {
+                *pending_scroll.borrow_mut() = Some(id);
+                if tab_value == HierarchyTab::Spatial {
+                    state.dispatch(ViewerAction::RevealNode(id));
+                }
+            }
+            || ()
+        });
+    }
+
+    // Then scroll its row into view, once the expanded rows contain it
+    {
+        let rows = rows.clone();
+        let pending_scroll = pending_scroll.clone();
+        let scroll_container_ref = scroll_container_ref.clone();
+        use_effect_with((Rc::as_ptr(&rows), selected), move |_| {
+            let target = *pending_scroll.borrow();
+            let index = target.and_then(|id| rows.iter().position(|row| row.id == id));
+            if let (Some(index), Some(element)) = (index, scroll_container_ref.cast::<Element>()) {
+                pending_scroll.borrow_mut().take();
+                let top = index as f64 * ROW_HEIGHT;
+                let view_top = element.scroll_top() as f64;
+                let view_height = element.client_height() as f64;
+                if top < view_top || top + ROW_HEIGHT > view_top + view_height {
+                    let options = web_sys::ScrollToOptions::new();
+                    options.set_top((top - (view_height - ROW_HEIGHT) / 2.0).max(0.0));
+                    options.set_behavior(web_sys::ScrollBehavior::Smooth);
+                    element.scroll_to_with_scroll_to_options(&options);
+                }
+            }
+            || ()
+        });
+    }
 
-    let spacer_bottom = if !visible_rows.is_empty() {
-        let last_idx = visible_rows.last().map(|(i, _)| *i).unwrap_or(0);
-        ((rows.len() - 1 - last_idx) as f64 * ROW_HEIGHT).max(0.0)
+    // Only the rows in view (plus overscan) become DOM nodes
+    let list_len = if has_tree {
+        rows.len()
     } else {
-        0.0
+        state.entities.len()
     };
+    let range = visible_range(*first_row, *rows_in_view, list_len);
+    let total_height = list_len as f64 * ROW_HEIGHT;
+    let spacer_top = range.start as f64 * ROW_HEIGHT;
 
     html! {
         <div
@@ -406,7 +770,7 @@ pub fn HierarchyPanel() -> Html {
                 <input
                     type="text"
                     class="search-input"
-                    placeholder="Search entities..."
+                    placeholder="Search, or IfcWall[Pset.Prop=value]"
                     value={state.search_query.clone()}
                     oninput={
                         let state = state.clone();
@@ -431,111 +795,184 @@ pub fn HierarchyPanel() -> Html {
                 }
             </div>
 
-            // Expand/collapse all buttons + entity count
-            if state.spatial_tree.is_some() {
-                <div class="tree-controls">
-                    <span class="tree-count-total">{format!("{} items", rows.len())}</span>
+            // Spatial structure, systems, the entity table or the types
+            if !state.entities.is_empty() {
+                <div class="hierarchy-tabs">
                     <button
-                        class="tree-control-btn"
+                        class={classes!("hierarchy-tab", (tab_value == HierarchyTab::Spatial).then_some("active"))}
                         onclick={
-                            let state = state.clone();
-                            Callback::from(move |_| {
-                                state.dispatch(ViewerAction::ExpandAll);
-                            })
+                            let tab = tab.clone();
+                            Callback::from(move |_: MouseEvent| tab.set(HierarchyTab::Spatial))
                         }
-                        title="Expand all"
                     >
-                        {"⊞"}
+                        {"Spatial"}
                     </button>
+                    if !state.systems.is_empty() {
+                        <button
+                            class={classes!("hierarchy-tab", (tab_value == HierarchyTab::Systems).then_some("active"))}
+                            onclick={
+                                let tab = tab.clone();
+                                Callback::from(move |_: MouseEvent| tab.set(HierarchyTab::Systems))
+                            }
+                        >
+                            {format!("Systems ({})", state.systems.len())}
+                        </button>
+                    }
                     <button
-                        class="tree-control-btn"
+                        class={classes!("hierarchy-tab", (tab_value == HierarchyTab::Table).then_some("active"))}
                         onclick={
-                            let state = state.clone();
-                            Callback::from(move |_| {
-                                state.dispatch(ViewerAction::CollapseAll);
-                            })
+                            let tab = tab.clone();
+                            Callback::from(move |_: MouseEvent| tab.set(HierarchyTab::Table))
+                        }
+                    >
+                        {"Table"}
+                    </button>
+                    <button
+                        class={classes!("hierarchy-tab", (tab_value == HierarchyTab::Types).then_some("active"))}
+                        onclick={
+                            let tab = tab.clone();
+                            Callback::from(move |_: MouseEvent| tab.set(HierarchyTab::Types))
                         }
-                        title="Collapse all"
                     >
-                        {"⊟"}
+                        {"Types"}
                     </button>
                 </div>
             }
 
-            // Entity tree with virtual scrolling
-            <div
-                class="entity-list"
-                ref={scroll_container_ref}
-                onscroll={onscroll}
-            >
-                if state.spatial_tree.is_none() && state.entities.is_empty() {
-                    <div class={classes!("empty-state", "drop-zone", (*is_dragging).then_some("active"))}>
-                        <span class="empty-icon">{if *is_dragging { "📥" } else { "📂" }}</span>
-                        <span class="empty-text">{if *is_dragging { "Drop IFC file here" } else { "No model loaded" }}</span>
-                        <span class="empty-hint">{"Drag & drop an IFC file or use the toolbar"}</span>
-                    </div>
-                } else if state.spatial_tree.is_some() {
-                    // Virtual scrolling container
-                    <div class="virtual-scroll-content" style={format!("height: {}px;", total_height)}>
-                        // Top spacer
-                        <div style={format!("height: {}px;", spacer_top)} />
-
-                        // Visible rows
-                        { for visible_rows.iter().map(|(_, row)| {
-                            let is_expanded = state.expanded_nodes.contains(&row.id);
-                            let is_selected = state.selected_ids.contains(&row.id);
-                            let is_hidden = state.hidden_ids.contains(&row.id);
-
-                            html! {
-                                <TreeRow
-                                    row={row.clone()}
-                                    is_expanded={is_expanded}
-                                    is_selected={is_selected}
-                                    is_hidden={is_hidden}
-                                    on_toggle={on_toggle.clone()}
-                                    on_select={on_select.clone()}
-                                    on_toggle_visibility={on_toggle_visibility.clone()}
-                                />
+            // Expand/collapse all buttons + entity count
+            if has_tree {
+                <div class="tree-controls">
+                    <span class="tree-count-total">{format!("{} items", rows.len())}</span>
+                    if tab_value == HierarchyTab::Spatial {
+                        <button
+                            class="tree-control-btn"
+                            onclick={
+                                let state = state.clone();
+                                Callback::from(move |_| {
+                                    state.dispatch(ViewerAction::ExpandAll);
+                                })
                             }
-                        })}
-
-                        // Bottom spacer
-                        <div style={format!("height: {}px;", spacer_bottom)} />
-                    </div>
-                } else {
-                    // Fallback to flat list if no tree (also virtualized)
-                    <div class="flat-list">
-                        { for state.entities.iter().map(|entity| {
-                            let is_selected = state.selected_ids.contains(&entity.id);
-                            let is_hidden = state.hidden_ids.contains(&entity.id);
-                            let entity_id = entity.id;
-
-                            html! {
-                                <div
-                                    class={classes!(
-                                        "entity-row",
-                                        is_selected.then_some("selected"),
-                                        is_hidden.then_some("hidden")
-                                    )}
-                                    onclick={
-                                        let state = state.clone();
-                                        Callback::from(move |_| {
-                                            state.dispatch(ViewerAction::Select(entity_id));
-                                        })
-                                    }
-                                >
-                                    <span class="entity-icon">
-                                        {crate::utils::get_entity_icon(&entity.entity_type)}
-                                    </span>
-                                    <span class="entity-name">
-                                        {entity.name.as_deref().unwrap_or(&entity.entity_type)}
-                                    </span>
-                                </div>
+                            title="Expand all"
+                        >
+                            {"⊞"}
+                        </button>
+                        <button
+                            class="tree-control-btn"
+                            onclick={
+                                let state = state.clone();
+                                Callback::from(move |_| {
+                                    state.dispatch(ViewerAction::CollapseAll);
+                                })
                             }
-                        })}
-                    </div>
-                }
-            </div>
+                            title="Collapse all"
+                        >
+                            {"⊟"}
+                        </button>
+                    }
+                </div>
+            }
+
+            if tab_value == HierarchyTab::Table {
+                <EntityTable />
+            } else if tab_value == HierarchyTab::Types {
+                <TypesPanel />
+            } else {
+                // Entity tree with virtual scrolling
+                <div
+                    class="entity-list"
+                    ref={scroll_container_ref}
+                    onscroll={onscroll}
+                >
+                    if state.spatial_tree.is_none() && state.entities.is_empty() {
+                        <div class={classes!("empty-state", "drop-zone", (*is_dragging).then_some("active"))}>
+                            <span class="empty-icon">{if *is_dragging { "📥" } else { "📂" }}</span>
+                            <span class="empty-text">{if *is_dragging { "Drop IFC file here" } else { "No model loaded" }}</span>
+                            <span class="empty-hint">{"Drag & drop an IFC file or use the toolbar"}</span>
+                        </div>
+                    } else if has_tree {
+                        // Virtual scrolling container
+                        <div class="virtual-scroll-content" style={format!("height: {}px;", total_height)}>
+                            // Top spacer
+                            <div style={format!("height: {}px;", spacer_top)} />
+
+                            // Visible rows
+                            { for rows[range.clone()].iter().map(|row| {
+                                let is_expanded = state.expanded_nodes.contains(&row.id);
+                                // Type groups are selected or hidden when all their members are
+                                let (is_selected, is_hidden) = if row.members.is_empty() {
+                                    (
+                                        state.selected_ids.contains(&row.id),
+                                        state.hidden_ids.contains(&row.id),
+                                    )
+                                } else {
+                                    (
+                                        row.members.iter().all(|id| state.selected_ids.contains(id)),
+                                        row.members.iter().all(|id| state.hidden_ids.contains(id)),
+                                    )
+                                };
+                                let spaces_hidden = (state.spaces.visible
+                                    && matches!(row.node_type, SpatialNodeType::Storey))
+                                    .then(|| !state.spaces.is_storey_visible(&row.name));
+                                let thumbnail = matches!(row.node_type, SpatialNodeType::Storey)
+                                    .then(|| thumbnail_url(&state, &storey_key(&row.name)))
+                                    .flatten();
+
+                                html! {
+                                    <TreeRow
+                                        row={row.clone()}
+                                        is_expanded={is_expanded}
+                                        is_selected={is_selected}
+                                        is_hidden={is_hidden}
+                                        on_toggle={on_toggle.clone()}
+                                        on_select={on_select.clone()}
+                                        on_toggle_visibility={on_toggle_visibility.clone()}
+                                        spaces_hidden={spaces_hidden}
+                                        thumbnail={thumbnail}
+                                        on_isolate={on_isolate.clone()}
+                                        on_toggle_spaces={on_toggle_spaces.clone()}
+                                        on_select_members={on_select_members.clone()}
+                                        on_isolate_members={on_isolate_members.clone()}
+                                        on_set_members_hidden={on_set_members_hidden.clone()}
+                                    />
+                                }
+                            })}
+                        </div>
+                    } else {
+                        // Fallback to flat list if no tree (also virtualized)
+                        <div class="virtual-scroll-content flat-list" style={format!("height: {}px;", total_height)}>
+                            <div style={format!("height: {}px;", spacer_top)} />
+                            { for state.entities[range].iter().map(|entity| {
+                                let is_selected = state.selected_ids.contains(&entity.id);
+                                let is_hidden = state.hidden_ids.contains(&entity.id);
+                                let entity_id = entity.id;
+
+                                html! {
+                                    <div
+                                        class={classes!(
+                                            "entity-row",
+                                            is_selected.then_some("selected"),
+                                            is_hidden.then_some("hidden")
+                                        )}
+                                        onclick={
+                                            let state = state.clone();
+                                            Callback::from(move |_| {
+                                                state.dispatch(ViewerAction::Select(entity_id));
+                                            })
+                                        }
+                                    >
+                                        <span class="entity-icon">
+                                            {crate::utils::get_entity_icon(&entity.entity_type)}
+                                        </span>
+                                        <span class="entity-name">
+                                            {entity.name.as_deref().unwrap_or(&entity.entity_type)}
+                                        </span>
+                                    </div>
+                                }
+                            })}
+                        </div>
+                    }
+                </div>
+            }
         </div>
     }
 }
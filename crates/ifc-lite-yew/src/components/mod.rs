@@ -1,15 +1,49 @@
 //! Yew UI Components for IFC-Lite Viewer
 
+mod annotations_panel;
+mod bcf_panel;
+mod color_legend;
+mod context_menu;
+mod debug_overlay;
+mod diagnostics_panel;
+mod diff_panel;
+mod entity_table;
 mod hierarchy_panel;
 mod properties_panel;
+mod property_compare;
+mod qto_panel;
+mod quantity_summary;
+mod section_panel;
+mod start_screen;
+mod statistics_dialog;
 mod status_bar;
 mod toolbar;
+mod types_panel;
+mod validation_panel;
 mod viewer_layout;
 mod viewport;
+mod views_panel;
 
+pub use annotations_panel::AnnotationsPanel;
+pub use bcf_panel::BcfPanel;
+pub use color_legend::ColorLegend;
+pub use context_menu::ContextMenu;
+pub use debug_overlay::DebugOverlay;
+pub use diagnostics_panel::DiagnosticsPanel;
+pub use diff_panel::DiffPanel;
 pub use hierarchy_panel::HierarchyPanel;
 pub use properties_panel::PropertiesPanel;
+pub use qto_panel::QtoPanel;
+pub use quantity_summary::QuantitySummary;
+pub use section_panel::SectionPanel;
+pub use start_screen::StartScreen;
+pub use statistics_dialog::StatisticsDialog;
 pub use status_bar::StatusBar;
-pub use toolbar::{parse_and_process_ifc, Toolbar};
+pub use toolbar::{
+    apply_cached_model, apply_processed_model, parse_and_process_ifc, process_ifc, ProcessEvent,
+    ProcessedModel, Toolbar,
+};
+pub use validation_panel::ValidationPanel;
 pub use viewer_layout::ViewerLayout;
 pub use viewport::Viewport;
+pub use views_panel::ViewsPanel;
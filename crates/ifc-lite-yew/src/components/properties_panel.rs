@@ -1,7 +1,18 @@
 //! Properties panel - shows selected entity details
+//!
+//! In edit mode single property values become inputs and property sets can
+//! be added. Edits are tracked in the state's change set until they are
+//! exported as a patched IFC file or a JSON diff, or discarded.
 
-use crate::state::{ViewerAction, ViewerStateContext};
+use super::property_compare::PropertyCompare;
+use super::quantity_summary::QuantitySummary;
+use crate::bridge;
+use crate::state::{ColorMode, ViewerAction, ViewerStateContext};
+use crate::thumbnail::{entity_key, request_thumbnails, thumbnail_url};
+use crate::utils::{convert_unit, download_file, file_stem};
+use ifc_lite_core::{MaterialComponent, MaterialKind, PropertyData, QuantityKind, UnitSystem};
 use std::collections::HashSet;
+use web_sys::HtmlInputElement;
 use yew::prelude::*;
 
 /// Properties panel component
@@ -9,25 +20,109 @@ use yew::prelude::*;
 pub fn PropertiesPanel() -> Html {
     let state = use_context::<ViewerStateContext>().expect("ViewerStateContext not found");
 
-    // Get selected entity
+    // Single selected entity; several are aggregated below
     let selected_entity = state
         .selected_ids
         .iter()
         .next()
+        .filter(|_| state.selected_ids.len() == 1)
         .and_then(|id| state.entities.iter().find(|e| e.id == *id));
 
+    // Thumbnail of the selected entity, rendered on first selection
+    let selected_id = selected_entity.map(|e| e.id);
+    {
+        let state = state.clone();
+        use_effect_with(selected_id, move |id| {
+            if let Some(id) = *id {
+                request_thumbnails(&state, vec![(entity_key(id), vec![id])]);
+            }
+        });
+    }
+    let thumbnail = selected_id.and_then(|id| thumbnail_url(&state, &entity_key(id)));
+
+    // A pinned entity is compared with any other selected one
+    let comparison = selected_entity.and_then(|entity| {
+        state
+            .pinned_id
+            .filter(|id| *id != entity.id)
+            .map(|id| (id, entity.id))
+    });
+
+    // Occurrences sharing the selected entity's type object
+    let type_instances: Vec<u64> = selected_entity
+        .and_then(|entity| entity.type_object.as_ref())
+        .map(|type_object| {
+            state
+                .entities
+                .iter()
+                .filter(|e| {
+                    e.type_object
+                        .as_ref()
+                        .is_some_and(|t| t.id == type_object.id)
+                })
+                .map(|e| e.id)
+                .collect()
+        })
+        .unwrap_or_default();
+
     html! {
         <div class="properties-panel">
-            if let Some(entity) = selected_entity {
+            if !state.changes.is_empty() {
+                <ChangesBar />
+            }
+            if let Some((pinned, selected)) = comparison {
+                <PropertyCompare {pinned} {selected} />
+            } else if let Some(entity) = selected_entity {
                 // Entity info section
                 <div class="property-section">
                     <div class="section-header">{"Entity Info"}</div>
 
+                    if let Some(thumbnail) = thumbnail {
+                        <img class="properties-thumbnail" src={thumbnail} alt="" />
+                    }
+
                     <div class="property-row">
                         <span class="property-label">{"Type"}</span>
-                        <span class="property-value">{&entity.entity_type}</span>
+                        <span class="property-value">
+                            {&entity.entity_type}
+                            if let Some(ref predefined) = entity.predefined_type {
+                                <span class="predefined-type">{format!(" · {}", predefined)}</span>
+                            }
+                        </span>
                     </div>
 
+                    if let Some(ref object_type) = entity.object_type {
+                        <div class="property-row">
+                            <span class="property-label">{"Object Type"}</span>
+                            <span class="property-value">{object_type}</span>
+                        </div>
+                    }
+
+                    if let Some(ref type_object) = entity.type_object {
+                        <div class="property-row">
+                            <span class="property-label">{"Type Object"}</span>
+                            <span class="property-value">
+                                {type_object.name.clone().unwrap_or_else(|| format!("#{}", type_object.id))}
+                                <span class="predefined-type">{format!(" · {}", type_object.entity_type)}</span>
+                                <button
+                                    class="copy-btn"
+                                    onclick={
+                                        let state = state.clone();
+                                        let instances = type_instances.clone();
+                                        Callback::from(move |_| {
+                                            for &id in &instances {
+                                                state.dispatch(ViewerAction::AddToSelection(id));
+                                            }
+                                        })
+                                    }
+                                    title="Select all occurrences of this type"
+                                >
+                                    {format!("📑 {}", type_instances.len())}
+                                </button>
+                            </span>
+                        </div>
+                    }
+
                     if let Some(ref name) = entity.name {
                         <div class="property-row">
                             <span class="property-label">{"Name"}</span>
@@ -45,7 +140,7 @@ pub fn PropertiesPanel() -> Html {
                                     onclick={
                                         let gid = global_id.clone();
                                         Callback::from(move |_| {
-                                            copy_to_clipboard(&gid);
+                                            crate::utils::copy_to_clipboard(&gid);
                                         })
                                     }
                                     title="Copy to clipboard"
@@ -63,10 +158,19 @@ pub fn PropertiesPanel() -> Html {
                         </div>
                     }
 
+                    if !entity.referenced_storeys.is_empty() {
+                        <div class="property-row">
+                            <span class="property-label">{"Referenced in"}</span>
+                            <span class="property-value">{entity.referenced_storeys.join(", ")}</span>
+                        </div>
+                    }
+
                     if let Some(elevation) = entity.storey_elevation {
                         <div class="property-row">
                             <span class="property-label">{"Elevation"}</span>
-                            <span class="property-value">{format!("{:.2} m", elevation)}</span>
+                            <span class="property-value">
+                                {state.unit_system.format(QuantityKind::Length, elevation as f64, 2)}
+                            </span>
                         </div>
                     }
                 </div>
@@ -123,10 +227,11 @@ pub fn PropertiesPanel() -> Html {
                             onclick={
                                 let state = state.clone();
                                 let entity_type = entity.entity_type.clone();
+                                let predefined_type = entity.predefined_type.clone();
                                 Callback::from(move |_| {
-                                    // Select all entities of the same type
+                                    // Select all entities of the same type and predefined type
                                     let same_type_ids: HashSet<u64> = state.entities.iter()
-                                        .filter(|e| e.entity_type == entity_type)
+                                        .filter(|e| e.entity_type == entity_type && e.predefined_type == predefined_type)
                                         .map(|e| e.id)
                                         .collect();
                                     for id in same_type_ids {
@@ -138,24 +243,51 @@ pub fn PropertiesPanel() -> Html {
                         >
                             {"📑 Select Similar"}
                         </button>
+
+                        <button
+                            class={classes!("action-btn", (state.pinned_id == Some(entity.id)).then_some("active"))}
+                            onclick={
+                                let state = state.clone();
+                                let entity_id = entity.id;
+                                let pinned = state.pinned_id == Some(entity_id);
+                                Callback::from(move |_| {
+                                    state.dispatch(ViewerAction::SetPinned((!pinned).then_some(entity_id)));
+                                })
+                            }
+                            title="Keep these properties to compare with the next selection"
+                        >
+                            {if state.pinned_id == Some(entity.id) { "📌 Unpin" } else { "📌 Pin" }}
+                        </button>
+
+                        <button
+                            class={classes!("action-btn", state.editing.then_some("active"))}
+                            onclick={
+                                let state = state.clone();
+                                Callback::from(move |_| state.dispatch(ViewerAction::ToggleEditing))
+                            }
+                            title="Edit property values and add property sets"
+                        >
+                            {if state.editing { "✔ Done" } else { "✏️ Edit" }}
+                        </button>
                     </div>
                 </div>
 
-                // Property Sets
-                if !entity.property_sets.is_empty() {
+                // Property Sets, with edits laid over the loaded values
+                if !entity.property_sets.is_empty() || state.changes.added_sets(entity.id as u32).next().is_some() {
                     { for entity.property_sets.iter().map(|pset| html! {
                         <div class="property-section">
                             <div class="section-header">{&pset.name}</div>
-                            { for pset.properties.iter().map(|prop| html! {
-                                <div class="property-row">
-                                    <span class="property-label">{&prop.name}</span>
-                                    <span class="property-value">
-                                        {&prop.value}
-                                        if let Some(ref unit) = prop.unit {
-                                            <span class="property-unit">{format!(" {}", unit)}</span>
-                                        }
-                                    </span>
-                                </div>
+                            { for pset.properties.iter().map(|prop| {
+                                let edited = state.changes.value_of(entity.id as u32, &pset.name, &prop.name);
+                                property_row(&state, entity.id, &pset.name, &prop.name, edited.unwrap_or(&prop.value), edited.is_some())
+                            })}
+                        </div>
+                    })}
+                    { for state.changes.added_sets(entity.id as u32).map(|(name, properties)| html! {
+                        <div class="property-section">
+                            <div class="section-header">{name}</div>
+                            { for properties.iter().map(|(property, value)| {
+                                property_row(&state, entity.id, name, property, value, true)
                             })}
                         </div>
                     })}
@@ -168,20 +300,47 @@ pub fn PropertiesPanel() -> Html {
                     </div>
                 }
 
-                // Quantities
-                if !entity.quantities.is_empty() {
+                if state.editing {
+                    <AddPropertySetForm entity_id={entity.id} />
+                }
+
+                // Material build-up
+                if let Some(ref material) = entity.material_definition {
                     <div class="property-section">
-                        <div class="section-header">{"Quantities"}</div>
-                        { for entity.quantities.iter().map(|qty| html! {
+                        <div class="section-header">{"Material"}</div>
+                        <div class="property-row">
+                            <span class="property-label">{kind_label(material.kind)}</span>
+                            <span class="property-value">{&material.name}</span>
+                        </div>
+                        { for material.components.iter().map(|c| material_row(c, state.unit_system)) }
+                        if let Some(total) = material.total_thickness() {
                             <div class="property-row">
-                                <span class="property-label">{&qty.name}</span>
+                                <span class="property-label">{"Total"}</span>
                                 <span class="property-value">
-                                    {format!("{:.3}", qty.value)}
-                                    if !qty.unit.is_empty() {
-                                        <span class="property-unit">{format!(" {}", qty.unit)}</span>
-                                    }
+                                    {state.unit_system.format_length(total)}
                                 </span>
                             </div>
+                        }
+                    </div>
+                }
+
+                // Quantities
+                if !entity.quantities.is_empty() {
+                    <div class="property-section">
+                        <div class="section-header">{"Quantities"}</div>
+                        { for entity.quantities.iter().map(|qty| {
+                            let (value, unit) = convert_unit(qty.value, &qty.unit, state.unit_system);
+                            html! {
+                                <div class="property-row">
+                                    <span class="property-label">{&qty.name}</span>
+                                    <span class="property-value">
+                                        {format!("{:.3}", value)}
+                                        if !unit.is_empty() {
+                                            <span class="property-unit">{format!(" {}", unit)}</span>
+                                        }
+                                    </span>
+                                </div>
+                            }
                         })}
                     </div>
                 } else {
@@ -200,6 +359,8 @@ pub fn PropertiesPanel() -> Html {
                         {format!("{} entities selected", state.selected_ids.len())}
                     </span>
 
+                    <QuantitySummary />
+
                     <div class="action-buttons">
                         <button
                             class="action-btn"
@@ -253,12 +414,236 @@ pub fn PropertiesPanel() -> Html {
     }
 }
 
-/// Copy text to clipboard using JS eval
-fn copy_to_clipboard(text: &str) {
-    // Simple approach using JS eval
-    let js_code = format!(
-        "navigator.clipboard.writeText('{}').catch(e => console.warn('Copy failed:', e))",
-        text.replace('\'', "\\'")
+/// One property, as an input in edit mode when the value kind allows it
+fn property_row(
+    state: &ViewerStateContext,
+    entity: u64,
+    pset: &str,
+    property: &str,
+    value: &PropertyData,
+    edited: bool,
+) -> Html {
+    let text = value.to_string();
+    let editable = state.editing && value.parse_as(&text).is_some();
+    // Inputs edit the value as written, read-only values follow the unit system
+    let shown = if editable {
+        value.clone()
+    } else {
+        value.in_unit_system(state.unit_system)
+    };
+    let onchange = {
+        let state = state.clone();
+        let (pset, property, value) = (pset.to_string(), property.to_string(), value.clone());
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            match value.parse_as(&input.value()) {
+                Some(new_value) if new_value != value => {
+                    state.dispatch(ViewerAction::EditProperty {
+                        entity,
+                        pset: pset.clone(),
+                        property: property.clone(),
+                        value: new_value,
+                    })
+                }
+                Some(_) => {}
+                None => {
+                    bridge::log_warn(&format!(
+                        "'{}' is not a valid {} value",
+                        input.value(),
+                        value.kind()
+                    ));
+                    input.set_value(&value.to_string());
+                }
+            }
+        })
+    };
+
+    let color_mode = ColorMode::Property {
+        pset: pset.to_string(),
+        property: property.to_string(),
+    };
+    let coloring = state.color_mode == color_mode;
+    let on_color = {
+        let state = state.clone();
+        Callback::from(move |_: MouseEvent| {
+            state.dispatch(ViewerAction::SetColorMode(if coloring {
+                ColorMode::Model
+            } else {
+                color_mode.clone()
+            }))
+        })
+    };
+
+    html! {
+        <div class={classes!("property-row", edited.then_some("edited"))}>
+            <span class="property-label">{property}</span>
+            <span class="property-value">
+                if editable {
+                    <input class="property-input" value={text} {onchange} />
+                } else {
+                    {shown.to_string()}
+                }
+                if let Some(unit) = shown.unit() {
+                    <span class="property-unit">{format!(" {}", unit)}</span>
+                }
+                if !editable {
+                    <button
+                        class={classes!("copy-btn", coloring.then_some("active"))}
+                        onclick={on_color}
+                        title="Color the model by this property"
+                    >
+                        {"🎨"}
+                    </button>
+                }
+            </span>
+        </div>
+    }
+}
+
+fn kind_label(kind: MaterialKind) -> &'static str {
+    match kind {
+        MaterialKind::Single => "Material",
+        MaterialKind::LayerSet => "Layer set",
+        MaterialKind::ProfileSet => "Profile set",
+        MaterialKind::ConstituentSet => "Constituents",
+        MaterialKind::List => "Material list",
+    }
+}
+
+/// One layer, profile or constituent with its thickness or share
+fn material_row(component: &MaterialComponent, system: UnitSystem) -> Html {
+    let label = component
+        .material
+        .as_deref()
+        .or(component.name.as_deref())
+        .unwrap_or("-");
+    // Only show the part's own name when it differs from its material
+    let part = component.name.as_deref().filter(|name| *name != label);
+
+    html! {
+        <div class="property-row">
+            <span class="property-label">{label}</span>
+            <span class="property-value">
+                if let Some(thickness) = component.thickness {
+                    {system.format_length(thickness)}
+                } else if let Some(fraction) = component.fraction {
+                    {format!("{:.0}", fraction * 100.0)}
+                    <span class="property-unit">{" %"}</span>
+                }
+                if let Some(ref profile) = component.profile {
+                    <span class="predefined-type">{format!(" {}", profile)}</span>
+                }
+                if let Some(part) = part {
+                    <span class="predefined-type">{format!(" · {}", part)}</span>
+                }
+            </span>
+        </div>
+    }
+}
+
+#[derive(Properties, PartialEq)]
+struct AddPropertySetFormProps {
+    entity_id: u64,
+}
+
+/// Inputs for a new property set with a first text property
+#[function_component]
+fn AddPropertySetForm(props: &AddPropertySetFormProps) -> Html {
+    let state = use_context::<ViewerStateContext>().expect("ViewerStateContext not found");
+    let name = use_state(String::new);
+    let property = use_state(String::new);
+    let value = use_state(String::new);
+
+    let input = |handle: &UseStateHandle<String>| {
+        let handle = handle.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            handle.set(input.value());
+        })
+    };
+    let on_add = {
+        let entity = props.entity_id;
+        let (name, property, value) = (name.clone(), property.clone(), value.clone());
+        Callback::from(move |_: MouseEvent| {
+            state.dispatch(ViewerAction::AddPropertySet {
+                entity,
+                name: (*name).clone(),
+                property: (*property).clone(),
+                value: PropertyData::Text((*value).clone()),
+            });
+            property.set(String::new());
+            value.set(String::new());
+        })
+    };
+    let complete = !name.trim().is_empty() && !property.trim().is_empty();
+
+    html! {
+        <div class="property-section">
+            <div class="section-header">{"Add Property"}</div>
+            <div class="property-form">
+                <input placeholder="Property set, e.g. Pset_Custom" value={(*name).clone()} oninput={input(&name)} />
+                <input placeholder="Property name" value={(*property).clone()} oninput={input(&property)} />
+                <input placeholder="Value" value={(*value).clone()} oninput={input(&value)} />
+                <button class="action-btn" onclick={on_add} disabled={!complete}>{"➕ Add"}</button>
+            </div>
+        </div>
+    }
+}
+
+/// Pending edits with export and discard
+#[function_component]
+fn ChangesBar() -> Html {
+    let state = use_context::<ViewerStateContext>().expect("ViewerStateContext not found");
+    let stem = state
+        .file_name
+        .as_deref()
+        .map(|name| file_stem(name.trim_end_matches(".ifc")))
+        .unwrap_or_else(|| "model".to_string());
+
+    let export = |export: fn(&ifc_lite_core::ChangeSet) -> Result<String, String>,
+                  file_name: String,
+                  mime_type: &'static str| {
+        let state = state.clone();
+        Callback::from(move |_: MouseEvent| match export(&state.changes) {
+            Ok(text) => download_file(&file_name, mime_type, text.as_bytes()),
+            Err(e) => {
+                bridge::log_error(&format!("[Edit] Export failed: {}", e));
+                state.dispatch(ViewerAction::SetWarning(format!("Export failed: {}", e)));
+            }
+        })
+    };
+    let on_export_ifc = export(
+        crate::edits::export_ifc,
+        format!("{}-edited.ifc", stem),
+        "application/x-step",
+    );
+    let on_export_json = export(
+        crate::edits::export_json,
+        format!("{}-changes.json", stem),
+        "application/json",
     );
-    let _ = js_sys::eval(&js_code);
+    let on_discard = {
+        let state = state.clone();
+        Callback::from(move |_: MouseEvent| state.dispatch(ViewerAction::DiscardChanges))
+    };
+
+    let count = state.changes.len();
+    html! {
+        <div class="property-section changes-bar">
+            <div class="section-header">
+                {format!("{} unsaved change{}", count, if count == 1 { "" } else { "s" })}
+            </div>
+            <div class="action-buttons">
+                <button class="action-btn" onclick={on_export_ifc} title="Download the IFC file with the edits applied">
+                    {"📤 Export IFC"}
+                </button>
+                <button class="action-btn" onclick={on_export_json} title="Download the edits as a JSON diff">
+                    {"📄 Export JSON"}
+                </button>
+                <button class="action-btn" onclick={on_discard} title="Drop all edits">
+                    {"✖ Discard"}
+                </button>
+            </div>
+        </div>
+    }
 }
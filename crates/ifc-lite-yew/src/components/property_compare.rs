@@ -0,0 +1,124 @@
+//! Property comparison - the pinned entity next to the selected one
+//!
+//! Rows with differing values are highlighted; a property missing on one
+//! side shows a dash there.
+
+use crate::state::{compare_property_sets, EntityInfo, ViewerAction, ViewerStateContext};
+use ifc_lite_core::{PropertyData, UnitSystem};
+use yew::prelude::*;
+
+#[derive(Properties, PartialEq)]
+pub struct PropertyCompareProps {
+    pub pinned: u64,
+    pub selected: u64,
+}
+
+fn value_text(value: Option<&PropertyData>, system: UnitSystem) -> String {
+    match value.map(|v| v.in_unit_system(system)) {
+        Some(value) => match value.unit() {
+            Some(unit) => format!("{} {}", value, unit),
+            None => value.to_string(),
+        },
+        None => "–".to_string(),
+    }
+}
+
+fn label(entity: &EntityInfo) -> String {
+    entity
+        .name
+        .clone()
+        .unwrap_or_else(|| format!("#{}", entity.id))
+}
+
+fn compare_row(name: &str, pinned: String, selected: String, differs: bool) -> Html {
+    html! {
+        <div class={classes!("compare-row", differs.then_some("differs"))}>
+            <span class="property-label" title={name.to_string()}>{name}</span>
+            <span class="compare-value" title={pinned.clone()}>{pinned}</span>
+            <span class="compare-value" title={selected.clone()}>{selected}</span>
+        </div>
+    }
+}
+
+/// Two-column comparison of the pinned and the selected entity
+#[function_component]
+pub fn PropertyCompare(props: &PropertyCompareProps) -> Html {
+    let state = use_context::<ViewerStateContext>().expect("ViewerStateContext not found");
+    let only_differences = use_state(|| false);
+
+    let find = |id: u64| state.entities.iter().find(|e| e.id == id);
+    let (Some(pinned), Some(selected)) = (find(props.pinned), find(props.selected)) else {
+        return html! {};
+    };
+
+    let info = |value: fn(&EntityInfo) -> Option<String>| {
+        let (a, b) = (value(pinned), value(selected));
+        let differs = a != b;
+        (
+            a.unwrap_or_else(|| "–".to_string()),
+            b.unwrap_or_else(|| "–".to_string()),
+            differs,
+        )
+    };
+    let info_rows = [
+        ("Type", info(|e| Some(e.entity_type.clone()))),
+        ("Predefined", info(|e| e.predefined_type.clone())),
+        ("Object Type", info(|e| e.object_type.clone())),
+        ("Storey", info(|e| e.storey.clone())),
+        ("Material", info(|e| e.material.clone())),
+    ];
+    let sets = compare_property_sets(&pinned.property_sets, &selected.property_sets);
+    let shown = |differs: bool| differs || !*only_differences;
+
+    let on_unpin = {
+        let state = state.clone();
+        Callback::from(move |_: MouseEvent| state.dispatch(ViewerAction::SetPinned(None)))
+    };
+    let on_only_differences = {
+        let only_differences = only_differences.clone();
+        Callback::from(move |_: Event| only_differences.set(!*only_differences))
+    };
+
+    html! {
+        <div class="property-compare">
+            <div class="property-section">
+                <div class="section-header">
+                    {"Compare"}
+                    <button class="copy-btn active" onclick={on_unpin} title="Unpin">
+                        {"📌"}
+                    </button>
+                </div>
+                <label class="compare-filter">
+                    <input
+                        type="checkbox"
+                        checked={*only_differences}
+                        onchange={on_only_differences}
+                    />
+                    {"Only differences"}
+                </label>
+                <div class="compare-row compare-heading">
+                    <span class="property-label"></span>
+                    <span class="compare-value" title="Pinned">{format!("📌 {}", label(pinned))}</span>
+                    <span class="compare-value">{label(selected)}</span>
+                </div>
+                { for info_rows.into_iter()
+                    .filter(|(_, (_, _, differs))| shown(*differs))
+                    .map(|(name, (a, b, differs))| compare_row(name, a, b, differs)) }
+            </div>
+
+            { for sets.iter().filter(|set| shown(set.differs())).map(|set| html! {
+                <div class="property-section">
+                    <div class="section-header">{&set.name}</div>
+                    { for set.properties.iter().filter(|p| shown(p.differs())).map(|p| {
+                        compare_row(
+                            &p.name,
+                            value_text(p.pinned.as_ref(), state.unit_system),
+                            value_text(p.selected.as_ref(), state.unit_system),
+                            p.differs(),
+                        )
+                    })}
+                </div>
+            })}
+        </div>
+    }
+}
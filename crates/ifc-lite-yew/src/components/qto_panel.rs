@@ -0,0 +1,120 @@
+//! Quantity takeoff panel - model totals by type, storey or material
+
+use crate::state::ViewerStateContext;
+use crate::utils::{download_file, file_stem};
+use ifc_lite_core::{format_real, QuantityKind};
+use web_sys::HtmlSelectElement;
+use yew::prelude::*;
+
+/// Grouping of the totals
+#[derive(Clone, Copy, PartialEq)]
+enum GroupBy {
+    Type,
+    Storey,
+    Material,
+    /// Volumes split over the layers and constituents of each element
+    BaseMaterial,
+}
+
+/// Volume and area totals of the whole model, with CSV export
+#[function_component]
+pub fn QtoPanel() -> Html {
+    let state = use_context::<ViewerStateContext>().expect("ViewerStateContext not found");
+    let group_by = use_state(|| GroupBy::Type);
+
+    if state.entities.is_empty() {
+        return html! {};
+    }
+
+    let report = state.quantity_report();
+    let system = state.unit_system;
+    let groups = match *group_by {
+        GroupBy::Type => &report.by_type,
+        GroupBy::Storey => &report.by_storey,
+        GroupBy::Material => &report.by_material,
+        GroupBy::BaseMaterial => &report.by_base_material,
+    };
+
+    let on_group = {
+        let group_by = group_by.clone();
+        Callback::from(move |e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            group_by.set(match select.value().as_str() {
+                "storey" => GroupBy::Storey,
+                "material" => GroupBy::Material,
+                "base" => GroupBy::BaseMaterial,
+                _ => GroupBy::Type,
+            });
+        })
+    };
+
+    let on_export = {
+        let csv = report.to_csv();
+        let stem = state
+            .file_name
+            .as_deref()
+            .map(|name| file_stem(name.trim_end_matches(".ifc")))
+            .unwrap_or_else(|| "model".to_string());
+        Callback::from(move |_: MouseEvent| {
+            download_file(
+                &format!("{}-quantities.csv", stem),
+                "text/csv",
+                csv.as_bytes(),
+            );
+        })
+    };
+
+    html! {
+        <div class="qto-panel">
+            <div class="qto-header">
+                <span class="section-header">{"Quantity Takeoff"}</span>
+                <select class="qto-group" onchange={on_group}>
+                    <option value="type" selected={*group_by == GroupBy::Type}>{"Type"}</option>
+                    <option value="storey" selected={*group_by == GroupBy::Storey}>{"Storey"}</option>
+                    <option value="material" selected={*group_by == GroupBy::Material}>{"Material"}</option>
+                    <option value="base" selected={*group_by == GroupBy::BaseMaterial}>{"Base material"}</option>
+                </select>
+                <button
+                    class="copy-btn"
+                    onclick={on_export}
+                    disabled={report.elements.is_empty()}
+                    title="Export quantities of all elements as CSV"
+                >
+                    {"📤"}
+                </button>
+            </div>
+            <div class="qto-list">
+                <div class="qto-row qto-columns">
+                    <span class="qto-key"></span>
+                    <span class="qto-count">{"#"}</span>
+                    <span class="qto-value">{system.unit(QuantityKind::Volume).0}</span>
+                    <span class="qto-value">
+                        {format!("{} plan", system.unit(QuantityKind::Area).0)}
+                    </span>
+                </div>
+                { for groups.iter().map(|group| {
+                    let title = if group.estimated_count > 0 {
+                        format!("{} of {} elements measured from geometry", group.estimated_count, group.count)
+                    } else {
+                        format!("{} elements", group.count)
+                    };
+                    let (volume, _) = system.from_si(QuantityKind::Volume, group.volume);
+                    let (area, _) = system.from_si(QuantityKind::Area, group.footprint_area);
+                    html! {
+                        <div class="qto-row" title={title}>
+                            <span class="qto-key">
+                                {&group.key}
+                                if group.estimated_count > 0 {
+                                    <span class="qto-estimated">{"*"}</span>
+                                }
+                            </span>
+                            <span class="qto-count">{group.count}</span>
+                            <span class="qto-value">{format_real((volume * 100.0).round() / 100.0)}</span>
+                            <span class="qto-value">{format_real((area * 100.0).round() / 100.0)}</span>
+                        </div>
+                    }
+                })}
+            </div>
+        </div>
+    }
+}
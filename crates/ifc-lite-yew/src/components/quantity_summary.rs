@@ -0,0 +1,127 @@
+//! Quantity summary - totals for the current multi-selection
+//!
+//! Besides the type counts and summed quantities, the property sets all
+//! selected entities share are listed, with "<varies>" for the properties
+//! whose values differ.
+
+use crate::state::{SelectionTotals, ViewerStateContext};
+use crate::utils::convert_unit;
+use ifc_lite_core::{format_real, QuantityKind, UnitSystem};
+use yew::prelude::*;
+
+/// Summed takeoff measure, if any selected entity has it
+fn measure_row(
+    label: &'static str,
+    total: Option<f64>,
+    kind: QuantityKind,
+    count: usize,
+    system: UnitSystem,
+) -> Html {
+    let Some(total) = total else {
+        return html! {};
+    };
+    let (total, unit) = system.from_si(kind, total);
+    html! {
+        <div class="property-row" title={format!("Sum over {} measured elements", count)}>
+            <span class="property-label">{label}</span>
+            <span class="property-value">
+                {format_real((total * 1000.0).round() / 1000.0)}
+                <span class="property-unit">{format!(" {}", unit)}</span>
+            </span>
+        </div>
+    }
+}
+
+/// Aggregated quantities, type counts and shared properties of the
+/// selected entities
+#[function_component]
+pub fn QuantitySummary() -> Html {
+    let state = use_context::<ViewerStateContext>().expect("ViewerStateContext not found");
+
+    let totals = SelectionTotals::from_entities(
+        state
+            .entities
+            .iter()
+            .filter(|e| state.selected_ids.contains(&e.id)),
+    );
+
+    let system = state.unit_system;
+    let on_copy = {
+        let tsv = totals.to_tsv(system);
+        Callback::from(move |_| crate::utils::copy_to_clipboard(&tsv))
+    };
+
+    html! {
+        <div class="quantity-summary">
+            <div class="property-section">
+                <div class="section-header">{"By Type"}</div>
+                { for totals.by_type.iter().map(|(ifc_type, count)| html! {
+                    <div class="property-row">
+                        <span class="property-label">{ifc_type}</span>
+                        <span class="property-value">{*count}</span>
+                    </div>
+                })}
+            </div>
+
+            if totals.measured > 0 {
+                <div class="property-section">
+                    <div class="section-header">{"Takeoff Totals"}</div>
+                    {measure_row("Volume", totals.measures.volume, QuantityKind::Volume, totals.measured, system)}
+                    {measure_row("Footprint area", totals.measures.footprint_area, QuantityKind::Area, totals.measured, system)}
+                    {measure_row("Surface area", totals.measures.surface_area, QuantityKind::Area, totals.measured, system)}
+                    {measure_row("Length", totals.measures.length, QuantityKind::Length, totals.measured, system)}
+                </div>
+            }
+
+            <div class="property-section">
+                <div class="section-header">
+                    {"Quantity Totals"}
+                    <button class="copy-btn" onclick={on_copy} title="Copy as table">
+                        {"📋"}
+                    </button>
+                </div>
+                if totals.quantities.is_empty() {
+                    <div class="empty-state small">
+                        <span class="empty-text">{"No quantities"}</span>
+                    </div>
+                } else {
+                    { for totals.quantities.iter().map(|q| {
+                        let (total, unit) = convert_unit(q.total, &q.unit, system);
+                        html! {
+                            <div class="property-row" title={format!("{} of {} elements", q.quantity_type, q.count)}>
+                                <span class="property-label">{&q.name}</span>
+                                <span class="property-value">
+                                    {format_real(total)}
+                                    if !unit.is_empty() {
+                                        <span class="property-unit">{format!(" {}", unit)}</span>
+                                    }
+                                </span>
+                            </div>
+                        }
+                    })}
+                }
+            </div>
+
+            { for totals.common_properties.iter().map(|pset| html! {
+                <div class="property-section">
+                    <div class="section-header">{&pset.name}</div>
+                    { for pset.properties.iter().map(|property| html! {
+                        <div class="property-row">
+                            <span class="property-label">{&property.name}</span>
+                            <span class="property-value">
+                                if let Some(value) = property.value.as_ref().map(|v| v.in_unit_system(system)) {
+                                    {value.to_string()}
+                                    if let Some(unit) = value.unit() {
+                                        <span class="property-unit">{format!(" {}", unit)}</span>
+                                    }
+                                } else {
+                                    <span class="property-varies">{"<varies>"}</span>
+                                }
+                            </span>
+                        </div>
+                    })}
+                </div>
+            })}
+        </div>
+    }
+}
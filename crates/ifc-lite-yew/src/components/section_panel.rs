@@ -0,0 +1,257 @@
+//! Section panel - section planes, section box, storey sections, cap colour
+//! and storey plans
+
+use crate::state::{SectionAxis, ViewerAction, ViewerStateContext, MAX_SECTION_PLANES};
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+fn input_value(e: &Event) -> String {
+    e.target_unchecked_into::<HtmlInputElement>().value()
+}
+
+/// Range slider over 0..1 in steps of 0.01
+fn slider(value: f32, title: String, on_change: Callback<f32>) -> Html {
+    let oninput = Callback::from(move |e: InputEvent| {
+        if let Ok(value) = input_value(&e).parse::<f32>() {
+            on_change.emit(value);
+        }
+    });
+    html! {
+        <input
+            type="range"
+            class="section-slider"
+            min="0"
+            max="1"
+            step="0.01"
+            value={value.to_string()}
+            {title}
+            {oninput}
+        />
+    }
+}
+
+/// Floating panel of the Section tool
+#[function_component]
+pub fn SectionPanel() -> Html {
+    let state = use_context::<ViewerStateContext>().expect("ViewerStateContext not found");
+    let section = &state.section;
+    let dispatch = |action: fn(usize) -> ViewerAction, index: usize| {
+        let state = state.clone();
+        Callback::from(move |_: MouseEvent| state.dispatch(action(index)))
+    };
+
+    let plane_rows = section.planes.iter().enumerate().map(|(index, plane)| {
+        let on_enabled = {
+            let state = state.clone();
+            Callback::from(move |e: Event| {
+                let checked = e.target_unchecked_into::<HtmlInputElement>().checked();
+                state.dispatch(ViewerAction::SetSectionEnabled(index, checked));
+            })
+        };
+        let on_axis = {
+            let state = state.clone();
+            Callback::from(move |e: Event| {
+                let axis = match input_value(&e).as_str() {
+                    "x" => SectionAxis::X,
+                    "y" => SectionAxis::Y,
+                    _ => SectionAxis::Z,
+                };
+                state.dispatch(ViewerAction::SetSectionAxis(index, axis));
+            })
+        };
+        let on_position = {
+            let state = state.clone();
+            Callback::from(move |position| {
+                state.dispatch(ViewerAction::SetSectionPosition(index, position))
+            })
+        };
+        html! {
+            <div class="section-row">
+                <input type="checkbox" checked={plane.enabled} onchange={on_enabled} title="Enable plane" />
+                <select class="section-axis" onchange={on_axis}>
+                    { for SectionAxis::ALL.iter().map(|axis| html! {
+                        <option value={axis.as_str()} selected={*axis == plane.axis}>
+                            {axis.as_str().to_uppercase()}
+                        </option>
+                    }) }
+                </select>
+                {slider(plane.position, format!("{:.0}%", plane.position * 100.0), on_position)}
+                <button
+                    class={classes!("copy-btn", plane.flipped.then_some("active"))}
+                    onclick={dispatch(ViewerAction::ToggleSectionFlip, index)}
+                    title="Flip the side that is cut away"
+                >
+                    {"⇅"}
+                </button>
+                <button
+                    class="copy-btn"
+                    onclick={dispatch(ViewerAction::RemoveSectionPlane, index)}
+                    title="Remove plane"
+                >
+                    {"✕"}
+                </button>
+            </div>
+        }
+    });
+
+    let section_box = &section.section_box;
+    let on_box_enabled = {
+        let state = state.clone();
+        Callback::from(move |e: Event| {
+            let checked = e.target_unchecked_into::<HtmlInputElement>().checked();
+            state.dispatch(ViewerAction::SetSectionBoxEnabled(checked));
+        })
+    };
+    let box_rows = SectionAxis::ALL.iter().map(|axis| {
+        let axis = axis.index();
+        let (min, max) = (section_box.min[axis], section_box.max[axis]);
+        let on_min = {
+            let state = state.clone();
+            Callback::from(move |min: f32| {
+                state.dispatch(ViewerAction::SetSectionBoxRange {
+                    axis,
+                    min: min.min(max),
+                    max,
+                })
+            })
+        };
+        let on_max = {
+            let state = state.clone();
+            Callback::from(move |max: f32| {
+                state.dispatch(ViewerAction::SetSectionBoxRange {
+                    axis,
+                    min,
+                    max: max.max(min),
+                })
+            })
+        };
+        html! {
+            <div class="section-row">
+                <span class="section-axis-label">{SectionAxis::ALL[axis].as_str().to_uppercase()}</span>
+                {slider(min, format!("Min {:.0}%", min * 100.0), on_min)}
+                {slider(max, format!("Max {:.0}%", max * 100.0), on_max)}
+            </div>
+        }
+    });
+
+    let on_cap_color = {
+        let state = state.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            state.dispatch(ViewerAction::SetSectionCapColor(input.value()));
+        })
+    };
+
+    let on_section_storey = {
+        let state = state.clone();
+        Callback::from(move |e: Event| {
+            let storey = input_value(&e);
+            state.dispatch(ViewerAction::SetStoreySection(
+                (!storey.is_empty()).then_some(storey),
+            ));
+        })
+    };
+    let section_storey = section.storey.as_ref().map(|s| &s.storey);
+
+    let plan = &state.plan;
+    let on_plan_storey = {
+        let state = state.clone();
+        Callback::from(move |e: Event| {
+            let storey = input_value(&e);
+            state.dispatch(ViewerAction::SetPlanStorey(
+                (!storey.is_empty()).then_some(storey),
+            ));
+        })
+    };
+    let on_cut_height = {
+        let state = state.clone();
+        Callback::from(move |e: Event| {
+            if let Ok(height) = input_value(&e).parse::<f32>() {
+                state.dispatch(ViewerAction::SetPlanCutHeight(height));
+            }
+        })
+    };
+
+    html! {
+        <div class="section-panel">
+            <div class="section-header">{"Section planes"}</div>
+            { for plane_rows }
+            <div class="section-row">
+                <button
+                    class="copy-btn"
+                    disabled={section.planes.len() >= MAX_SECTION_PLANES}
+                    onclick={dispatch(|_| ViewerAction::AddSectionPlane, 0)}
+                    title={format!("Add a plane (up to {})", MAX_SECTION_PLANES)}
+                >
+                    {"+ Add plane"}
+                </button>
+                <button
+                    class="copy-btn"
+                    disabled={!section.is_active()}
+                    onclick={dispatch(|_| ViewerAction::ClearSection, 0)}
+                    title="Remove all planes, the box and the storey section"
+                >
+                    {"Clear"}
+                </button>
+            </div>
+
+            <div class="section-header">
+                <label class="section-toggle">
+                    <input type="checkbox" checked={section_box.enabled} onchange={on_box_enabled} />
+                    {"Section box"}
+                </label>
+            </div>
+            if section_box.enabled {
+                { for box_rows }
+            }
+
+            <div class="section-header">{"Storey section"}</div>
+            <div class="section-row">
+                <select
+                    class="section-storey"
+                    onchange={on_section_storey}
+                    title="Cut just above a storey and hide the storeys over it"
+                >
+                    <option value="" selected={section_storey.is_none()}>{"None"}</option>
+                    { for state.storeys.iter().map(|storey| html! {
+                        <option
+                            value={storey.name.clone()}
+                            selected={section_storey == Some(&storey.name)}
+                        >
+                            {format!("{} ({:+.2} m)", storey.name, storey.elevation)}
+                        </option>
+                    }) }
+                </select>
+            </div>
+
+            <div class="section-header">{"Floor plan"}</div>
+            <div class="section-row">
+                <select class="section-storey" onchange={on_plan_storey} title="Storey shown as a 2D plan">
+                    <option value="" selected={plan.storey.is_none()}>{"3D view"}</option>
+                    { for state.storeys.iter().map(|storey| html! {
+                        <option
+                            value={storey.name.clone()}
+                            selected={plan.storey.as_ref() == Some(&storey.name)}
+                        >
+                            {format!("{} ({:+.2} m)", storey.name, storey.elevation)}
+                        </option>
+                    }) }
+                </select>
+                <input
+                    type="number"
+                    class="section-cut-height"
+                    min="0"
+                    step="0.1"
+                    value={format!("{:.2}", plan.cut_height)}
+                    onchange={on_cut_height}
+                    title="Cut height above the storey elevation, for plans and storey sections (m)"
+                />
+            </div>
+
+            <div class="section-row">
+                <span class="section-axis-label">{"Cut fill"}</span>
+                <input type="color" value={section.cap_color.clone()} oninput={on_cap_color} />
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,58 @@
+//! Start screen - sample model gallery shown before a file is opened
+
+use crate::samples::{load_manifest, SampleManifest};
+use crate::state::ViewerStateContext;
+use crate::utils::format_file_size;
+use wasm_bindgen_futures::spawn_local;
+use yew::prelude::*;
+
+/// Gallery of sample models; one click downloads and opens a model
+#[function_component]
+pub fn StartScreen() -> Html {
+    let state = use_context::<ViewerStateContext>().expect("ViewerStateContext not found");
+    let manifest = use_state(SampleManifest::default);
+
+    {
+        let manifest = manifest.clone();
+        use_effect_with((), move |_| {
+            spawn_local(async move {
+                manifest.set(load_manifest().await);
+            });
+        });
+    }
+
+    html! {
+        <div class="start-screen">
+            <span class="loading-text">{"Open an IFC file to view it in 3D"}</span>
+            if !manifest.samples.is_empty() {
+                <span class="start-screen-hint">{"or try a sample model"}</span>
+                <div class="sample-gallery">
+                    { for manifest.samples.iter().map(|sample| {
+                        let onclick = {
+                            let state = state.clone();
+                            let url = sample.url();
+                            Callback::from(move |_: MouseEvent| {
+                                crate::worker::load_ifc_url(url.clone(), state.clone())
+                            })
+                        };
+                        let details = [
+                            sample.schema.clone(),
+                            sample.size.map(format_file_size),
+                        ]
+                        .into_iter()
+                        .flatten()
+                        .collect::<Vec<_>>()
+                        .join(" · ");
+                        html! {
+                            <button class="sample-card" {onclick} title={sample.file.clone()}>
+                                <span class="sample-name">{&sample.name}</span>
+                                <span class="sample-description">{&sample.description}</span>
+                                <span class="sample-details">{details}</span>
+                            </button>
+                        }
+                    })}
+                </div>
+            }
+        </div>
+    }
+}
@@ -0,0 +1,311 @@
+//! Statistics dialog - model counts, the device profile, graphics,
+//! navigation and geometry settings
+
+use crate::device::{GraphicsQuality, AMBIENT_OCCLUSION_SUPPORTED};
+use crate::state::{GeometrySettings, ViewerAction, ViewerStateContext};
+use std::collections::HashSet;
+use web_sys::{HtmlInputElement, HtmlSelectElement};
+use yew::prelude::*;
+
+fn row(label: &str, value: String) -> Html {
+    html! {
+        <tr>
+            <td class="stats-label">{label}</td>
+            <td class="stats-value">{value}</td>
+        </tr>
+    }
+}
+
+/// Preset picker; options above `max` are disabled
+fn quality_select(
+    selected: Option<GraphicsQuality>,
+    auto: Option<GraphicsQuality>,
+    max: GraphicsQuality,
+    disabled: bool,
+    onchange: Callback<Event>,
+) -> Html {
+    html! {
+        <select class="qto-group" {disabled} {onchange}>
+            if let Some(auto) = auto {
+                <option value="auto" selected={selected.is_none()}>
+                    {format!("Auto ({})", auto.label())}
+                </option>
+            }
+            {for GraphicsQuality::ALL.into_iter().map(|quality| html! {
+                <option
+                    value={quality.as_str()}
+                    selected={selected == Some(quality)}
+                    disabled={quality > max}
+                >
+                    {quality.label()}
+                </option>
+            })}
+        </select>
+    }
+}
+
+/// Parse a positive length typed in millimetres, as metres
+fn parse_mm(input: &HtmlInputElement) -> Option<f64> {
+    input
+        .value()
+        .parse::<f64>()
+        .ok()
+        .filter(|mm| mm.is_finite() && *mm >= 0.0)
+        .map(|mm| mm / 1000.0)
+}
+
+/// Modal with model statistics, the defaults picked for this device, the
+/// graphics, navigation and geometry settings
+#[function_component]
+pub fn StatisticsDialog() -> Html {
+    let state = use_context::<ViewerStateContext>().expect("ViewerStateContext not found");
+
+    let close = {
+        let state = state.clone();
+        Callback::from(move |_: MouseEvent| state.dispatch(ViewerAction::ToggleStatisticsDialog))
+    };
+
+    let type_count = state
+        .entities
+        .iter()
+        .map(|e| e.entity_type.as_str())
+        .collect::<HashSet<_>>()
+        .len();
+    let defaults = state.device_defaults();
+    let max_shadows = state
+        .device
+        .as_ref()
+        .map_or(GraphicsQuality::High, |d| d.max_shadow_quality());
+    let on_shadows = {
+        let state = state.clone();
+        Callback::from(move |e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            state.dispatch(ViewerAction::SetShadowQuality(GraphicsQuality::parse(
+                &select.value(),
+            )));
+        })
+    };
+    let on_ambient_occlusion = {
+        let state = state.clone();
+        Callback::from(move |e: Event| {
+            let select: HtmlSelectElement = e.target_unchecked_into();
+            if let Some(quality) = GraphicsQuality::parse(&select.value()) {
+                state.dispatch(ViewerAction::SetAmbientOcclusion(quality));
+            }
+        })
+    };
+    let on_orbit_around_cursor = {
+        let state = state.clone();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            state.dispatch(ViewerAction::SetOrbitAroundCursor(input.checked()));
+        })
+    };
+    let on_zoom_to_cursor = {
+        let state = state.clone();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            state.dispatch(ViewerAction::SetZoomToCursor(input.checked()));
+        })
+    };
+    let update_geometry = |apply: fn(&mut GeometrySettings, &HtmlInputElement)| {
+        let state = state.clone();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let mut settings = state.geometry;
+            apply(&mut settings, &input);
+            state.dispatch(ViewerAction::SetGeometrySettings(settings));
+        })
+    };
+    let on_deflection = update_geometry(|settings, input| {
+        if let Some(deflection) = parse_mm(input).filter(|d| *d > 0.0) {
+            settings.deflection = deflection;
+        }
+    });
+    let on_max_segments = update_geometry(|settings, input| {
+        if let Ok(segments) = input.value().parse::<usize>() {
+            settings.max_segments = segments.max(3);
+        }
+    });
+    let on_skip_openings = update_geometry(|settings, input| {
+        settings.skip_openings = input.checked();
+    });
+    let on_skip_spaces = update_geometry(|settings, input| {
+        settings.skip_spaces = input.checked();
+    });
+    let on_min_feature_size = update_geometry(|settings, input| {
+        if let Some(size) = parse_mm(input) {
+            settings.min_feature_size = size;
+        }
+    });
+    let geometry = state.geometry;
+    let unknown = || "unknown".to_string();
+
+    html! {
+        <div class="dialog-backdrop" onclick={close.clone()}>
+            <div class="dialog" onclick={Callback::from(|e: MouseEvent| e.stop_propagation())}>
+                <div class="dialog-header">
+                    <span class="dialog-title">{"Statistics"}</span>
+                    <button class="copy-btn" onclick={close} title="Close">{"✕"}</button>
+                </div>
+
+                <div class="section-header">{"Model"}</div>
+                <table class="stats-table">
+                    {row("File", state.file_name.clone().unwrap_or_else(|| "none".to_string()))}
+                    {row("Entities", state.entities.len().to_string())}
+                    {row("Types", type_count.to_string())}
+                    {row("Storeys", state.storeys.len().to_string())}
+                    {row("Content hash", state.content_hash.clone().unwrap_or_else(|| "none".to_string()))}
+                </table>
+
+                if let Some(device) = &state.device {
+                    <div class="section-header">{"Device"}</div>
+                    <table class="stats-table">
+                        {row("Tier", device.tier.label().to_string())}
+                        {row("Memory", device.memory_gib.map_or_else(unknown, |m| format!("{} GiB", m)))}
+                        {row("CPU cores", device.cores.map_or_else(unknown, |c| c.to_string()))}
+                        {row("GPU", device.gpu.clone().unwrap_or_else(unknown))}
+                        {row("WebGPU", if device.webgpu { "available" } else { "not available" }.to_string())}
+                        {row("Input", if device.touch { "touch" } else { "mouse" }.to_string())}
+                    </table>
+                }
+
+                <div class="section-header">{"Defaults"}</div>
+                <table class="stats-table">
+                    {row("Streaming batch", format!("{} meshes", defaults.geometry_batch_size))}
+                    {row("Curve deflection", format!("{} mm", defaults.deflection * 1000.0))}
+                    {row("Batch chunk size", format!("{} m", defaults.chunk_size))}
+                    {row("LOD error", format!("{} px", defaults.lod_error_px))}
+                    {row("Shadows", defaults.shadows.label().to_string())}
+                    {row("Smooth shading", if defaults.smooth_normals {
+                        format!("{}° crease", defaults.crease_angle)
+                    } else {
+                        "off".to_string()
+                    })}
+                    {row("Mesh optimization", if defaults.optimize_meshes { "on" } else { "off" }.to_string())}
+                    {row("Anti-aliasing", if defaults.msaa_samples > 1 {
+                        format!("{}x MSAA", defaults.msaa_samples)
+                    } else {
+                        "off".to_string()
+                    })}
+                </table>
+
+                <div class="section-header">{"Graphics"}</div>
+                <table class="stats-table">
+                    <tr title={(max_shadows < GraphicsQuality::High)
+                        .then_some("High quality shadows need WebGPU")}>
+                        <td class="stats-label">{"Shadows"}</td>
+                        <td class="stats-value">
+                            {quality_select(
+                                state.shadow_quality,
+                                Some(defaults.shadows.min(max_shadows)),
+                                max_shadows,
+                                false,
+                                on_shadows,
+                            )}
+                        </td>
+                    </tr>
+                    <tr title={(!AMBIENT_OCCLUSION_SUPPORTED)
+                        .then_some("Not supported by the browser renderer")}>
+                        <td class="stats-label">{"Ambient occlusion"}</td>
+                        <td class="stats-value">
+                            {quality_select(
+                                Some(state.ambient_occlusion),
+                                None,
+                                GraphicsQuality::High,
+                                !AMBIENT_OCCLUSION_SUPPORTED,
+                                on_ambient_occlusion,
+                            )}
+                        </td>
+                    </tr>
+                </table>
+
+                <div class="section-header">{"Navigation"}</div>
+                <table class="stats-table">
+                    <tr title="Drag turns about the model point under the cursor">
+                        <td class="stats-label">{"Orbit around cursor"}</td>
+                        <td class="stats-value">
+                            <input
+                                type="checkbox"
+                                checked={state.orbit_around_cursor}
+                                onchange={on_orbit_around_cursor}
+                            />
+                        </td>
+                    </tr>
+                    <tr title="The wheel zooms toward the point under the cursor">
+                        <td class="stats-label">{"Zoom to cursor"}</td>
+                        <td class="stats-value">
+                            <input
+                                type="checkbox"
+                                checked={state.zoom_to_cursor}
+                                onchange={on_zoom_to_cursor}
+                            />
+                        </td>
+                    </tr>
+                </table>
+
+                <div class="section-header" title="Applies from the next load on">
+                    {"Geometry"}
+                </div>
+                <table class="stats-table">
+                    <tr title="Largest gap between a curve and its facets">
+                        <td class="stats-label">{"Curve tolerance (mm)"}</td>
+                        <td class="stats-value">
+                            <input
+                                type="number"
+                                min="0.1"
+                                step="1"
+                                value={(geometry.deflection * 1000.0).to_string()}
+                                onchange={on_deflection}
+                            />
+                        </td>
+                    </tr>
+                    <tr title="Most segments a full circle is split into">
+                        <td class="stats-label">{"Max circle segments"}</td>
+                        <td class="stats-value">
+                            <input
+                                type="number"
+                                min="3"
+                                step="1"
+                                value={geometry.max_segments.to_string()}
+                                onchange={on_max_segments}
+                            />
+                        </td>
+                    </tr>
+                    <tr>
+                        <td class="stats-label">{"Skip openings"}</td>
+                        <td class="stats-value">
+                            <input
+                                type="checkbox"
+                                checked={geometry.skip_openings}
+                                onchange={on_skip_openings}
+                            />
+                        </td>
+                    </tr>
+                    <tr>
+                        <td class="stats-label">{"Skip spaces"}</td>
+                        <td class="stats-value">
+                            <input
+                                type="checkbox"
+                                checked={geometry.skip_spaces}
+                                onchange={on_skip_spaces}
+                            />
+                        </td>
+                    </tr>
+                    <tr title="Elements smaller than this get no geometry; 0 keeps all">
+                        <td class="stats-label">{"Min feature size (mm)"}</td>
+                        <td class="stats-value">
+                            <input
+                                type="number"
+                                min="0"
+                                step="1"
+                                value={(geometry.min_feature_size * 1000.0).to_string()}
+                                onchange={on_min_feature_size}
+                            />
+                        </td>
+                    </tr>
+                </table>
+            </div>
+        </div>
+    }
+}
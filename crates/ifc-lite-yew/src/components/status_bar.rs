@@ -1,12 +1,50 @@
 //! Status bar component
+//!
+//! Besides load state and entity counts it shows the model coordinates under
+//! the cursor, which Bevy publishes with every hover pick, and the summed
+//! volume and footprint area of the selection.
 
-use crate::state::ViewerStateContext;
+use crate::bridge::{load_cursor, CursorData};
+use crate::state::{ViewerAction, ViewerState, ViewerStateContext};
+use ifc_lite_core::{ContentHash, QuantityKind, UnitSystem};
 use yew::prelude::*;
 
+/// Summed volume and footprint area of the selected entities having them
+fn selection_measures(state: &ViewerState) -> (Option<f64>, Option<f64>) {
+    let add = |total: Option<f64>, value: Option<f64>| match (total, value) {
+        (Some(total), Some(value)) => Some(total + value),
+        (total, value) => total.or(value),
+    };
+    state
+        .entities
+        .iter()
+        .filter(|e| state.selected_ids.contains(&e.id))
+        .fold((None, None), |(volume, area), e| {
+            (
+                add(volume, e.measures.volume),
+                add(area, e.measures.footprint_area),
+            )
+        })
+}
+
 /// Status bar component
 #[function_component]
 pub fn StatusBar() -> Html {
     let state = use_context::<ViewerStateContext>().expect("ViewerStateContext not found");
+    let cursor = use_state(|| None::<CursorData>);
+
+    {
+        let cursor = cursor.clone();
+        use_effect_with((), move |_| {
+            let interval = gloo::timers::callback::Interval::new(100, move || {
+                let latest = load_cursor();
+                if *cursor != latest {
+                    cursor.set(latest);
+                }
+            });
+            move || drop(interval)
+        });
+    }
 
     // Calculate visible entity count
     let visible_count = state
@@ -25,7 +63,7 @@ pub fn StatusBar() -> Html {
             }
             // Not filtered by storey
             if let Some(ref storey) = state.storey_filter {
-                if e.storey.as_ref() != Some(storey) {
+                if !e.is_on_storey(storey) {
                     return false;
                 }
             }
@@ -34,6 +72,37 @@ pub fn StatusBar() -> Html {
         .count();
 
     let total_count = state.entities.len();
+    let issue_count = state.diagnostics.len();
+    let toggle_diagnostics = {
+        let state = state.clone();
+        Callback::from(move |_: MouseEvent| state.dispatch(ViewerAction::ToggleDiagnosticsPanel))
+    };
+
+    let system = state.unit_system;
+    let selection_summary = if state.selected_ids.is_empty() {
+        None
+    } else {
+        let mut summary = format!("{} selected", state.selected_ids.len());
+        let (volume, area) = selection_measures(&state);
+        if let Some(volume) = volume {
+            summary.push_str(&format!(
+                " · {}",
+                system.format(QuantityKind::Volume, volume, 2)
+            ));
+        }
+        if let Some(area) = area {
+            summary.push_str(&format!(
+                " · {}",
+                system.format(QuantityKind::Area, area, 2)
+            ));
+        }
+        Some(summary)
+    };
+
+    let toggle_units = {
+        let state = state.clone();
+        Callback::from(move |_: MouseEvent| state.dispatch(ViewerAction::ToggleUnitSystem))
+    };
 
     html! {
         <div class="status-bar">
@@ -56,6 +125,20 @@ pub fn StatusBar() -> Html {
                 } else {
                     <span class="status-idle">{"No file loaded"}</span>
                 }
+                if let Some(ref warning) = state.warning {
+                    <span class="status-warning" title={warning.clone()}>
+                        {"⚠️ "}{warning}
+                    </span>
+                }
+                if issue_count > 0 {
+                    <button
+                        class="status-warning status-issues"
+                        onclick={toggle_diagnostics}
+                        title="Entities skipped or without geometry"
+                    >
+                        {format!("⚠️ {} {}", issue_count, if issue_count == 1 { "issue" } else { "issues" })}
+                    </button>
+                }
             </div>
 
             // Center: Counts
@@ -73,21 +156,54 @@ pub fn StatusBar() -> Html {
                     </span>
                 }
 
-                // Selection count
-                if !state.selected_ids.is_empty() {
-                    <span class="status-selection">
-                        {format!("{} selected", state.selected_ids.len())}
+                if state.plan.storey.is_some() {
+                    <span class="status-filter" title="Floor plan view">
+                        {format!(
+                            "📐 Plan at +{}",
+                            state.unit_system.format(QuantityKind::Length, state.plan.cut_height as f64, 2)
+                        )}
+                    </span>
+                }
+
+                // Selection count, volume and footprint area
+                if let Some(summary) = selection_summary {
+                    <span
+                        class="status-selection"
+                        title="Selected entities, their volume and footprint area"
+                    >
+                        {summary}
                     </span>
                 }
             </div>
 
             // Right: File info
             <div class="status-right">
+                if let Some(ref cursor) = *cursor {
+                    <span class="status-coords" title="Model coordinates under the cursor">
+                        { for ["X", "Y", "Z"].iter().zip(cursor.point).map(|(axis, value)| html! {
+                            <span>
+                                {format!("{} {}", axis, system.format(QuantityKind::Length, value, 2))}
+                            </span>
+                        })}
+                    </span>
+                }
+                <button
+                    class="status-units"
+                    onclick={toggle_units}
+                    title="Display units (click to switch)"
+                >
+                    {if state.unit_system == UnitSystem::Metric { "m" } else { "ft" }}
+                </button>
                 if let Some(ref file_name) = state.file_name {
                     <span class="status-filename" title={file_name.clone()}>
                         {file_name}
                     </span>
                 }
+                if let Some(ref hash) = state.content_hash {
+                    <span class="status-hash" title={format!("Content hash: {}", hash)}>
+                        {"#"}{hash.parse::<ContentHash>().map_or_else(|_| hash.clone(), |h| h.short())}
+                    </span>
+                }
             </div>
         </div>
     }
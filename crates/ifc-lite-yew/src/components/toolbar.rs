@@ -2,18 +2,20 @@
 
 use crate::bridge::{self, EntityData, GeometryData};
 use crate::state::{
-    Progress, PropertySet, PropertyValue, QuantityValue, Tool, ViewerAction, ViewerStateContext,
+    ColorMode, ExplodeMode, OverlayLayer, Progress, PropertySet, PropertyValue, QuantityValue,
+    SpatialNode, SpatialNodeType, StoreyInfo, Tool, ViewerAction, ViewerStateContext,
 };
+use crate::utils::{capture_canvas_png, download_file, file_stem};
 use gloo_file::callbacks::FileReader;
-use ifc_lite_core::DecodedEntity;
-use wasm_bindgen_futures::spawn_local;
-use web_sys::HtmlInputElement;
+use ifc_lite_core::{Attribute, DecodedEntity};
+use serde::{Deserialize, Serialize};
+use web_sys::{HtmlInputElement, HtmlSelectElement};
 use yew::prelude::*;
 
 /// Helper to extract entity refs from a list attribute
-fn get_ref_list(entity: &DecodedEntity, index: usize) -> Option<Vec<u32>> {
+fn get_ref_list(entity: &DecodedEntity, attribute: Attribute) -> Option<Vec<u32>> {
     entity
-        .get_list(index)
+        .attribute_list(attribute)
         .map(|list| list.iter().filter_map(|v| v.as_entity_ref()).collect())
 }
 
@@ -62,30 +64,37 @@ pub fn Toolbar() -> Html {
                                 // Parse the IFC file
                                 let content = String::from_utf8_lossy(&bytes).to_string();
 
-                                // Use spawn_local for the async parsing work
+                                // Parse in the background worker
                                 let state_inner = state_clone.clone();
-                                spawn_local(async move {
-                                    match parse_and_process_ifc(&content, &state_inner) {
-                                        Ok(_) => {
-                                            bridge::log_info("IFC file loaded successfully");
-                                            state_inner.dispatch(ViewerAction::SetLoading(false));
-                                            state_inner.dispatch(ViewerAction::ClearProgress);
-                                            // Trigger "Fit All" to frame the loaded model
-                                            bridge::save_camera_cmd(&bridge::CameraCommand {
-                                                cmd: "fit_all".to_string(),
-                                                mode: None,
-                                            });
+                                crate::worker::load_ifc(
+                                    content,
+                                    state_clone.clone(),
+                                    move |result| {
+                                        match result {
+                                            Ok(_) => {
+                                                bridge::log_info("IFC file loaded successfully");
+                                                state_inner
+                                                    .dispatch(ViewerAction::SetLoading(false));
+                                                state_inner.dispatch(ViewerAction::ClearProgress);
+                                                // Trigger "Fit All" to frame the loaded model
+                                                bridge::save_camera_cmd(&bridge::CameraCommand {
+                                                    cmd: "fit_all".to_string(),
+                                                    mode: None,
+                                                    view: None,
+                                                });
+                                            }
+                                            Err(e) => {
+                                                bridge::log_error(&format!(
+                                                    "Failed to process IFC: {}",
+                                                    e
+                                                ));
+                                                state_inner
+                                                    .dispatch(ViewerAction::SetLoading(false));
+                                                state_inner.dispatch(ViewerAction::ClearProgress);
+                                            }
                                         }
-                                        Err(e) => {
-                                            bridge::log_error(&format!(
-                                                "Failed to process IFC: {}",
-                                                e
-                                            ));
-                                            state_inner.dispatch(ViewerAction::SetLoading(false));
-                                            state_inner.dispatch(ViewerAction::ClearProgress);
-                                        }
-                                    }
-                                });
+                                    },
+                                );
                             }
                             Err(e) => {
                                 bridge::log_error(&format!("Failed to read file: {:?}", e));
@@ -114,12 +123,16 @@ pub fn Toolbar() -> Html {
                         Tool::Pan => Some("pan"),
                         Tool::Orbit => Some("orbit"),
                         Tool::Walk => Some("walk"),
+                        Tool::BoxSelect => Some("box_select"),
+                        Tool::Measure => Some("measure"),
+                        Tool::Annotate => Some("annotate"),
                         _ => Some("orbit"), // Default to orbit for other tools
                     };
                     if let Some(m) = mode {
                         crate::bridge::save_camera_cmd(&crate::bridge::CameraCommand {
                             cmd: "set_mode".to_string(),
                             mode: Some(m.to_string()),
+                            view: None,
                         });
                     }
                 })}
@@ -130,6 +143,45 @@ pub fn Toolbar() -> Html {
         }
     };
 
+    let overlay_button = |layer: OverlayLayer,
+                          icon: &'static str,
+                          title: &'static str,
+                          state: &ViewerStateContext| {
+        let is_active = state.overlay.is_enabled(layer);
+        let state = state.clone();
+        html! {
+            <button
+                class={classes!("tool-btn", is_active.then_some("active"))}
+                onclick={Callback::from(move |_| {
+                    state.dispatch(ViewerAction::ToggleOverlay(layer));
+                })}
+                {title}
+            >
+                {icon}
+            </button>
+        }
+    };
+
+    let path_button = |action: fn() -> ViewerAction,
+                       icon: &'static str,
+                       title: String,
+                       active: bool,
+                       state: &ViewerStateContext| {
+        let disabled = state.camera_path.keyframes.is_empty();
+        let state = state.clone();
+        html! {
+            <button
+                class={classes!("tool-btn", active.then_some("active"))}
+                onclick={Callback::from(move |_| state.dispatch(action()))}
+                {disabled}
+                {title}
+            >
+                {icon}
+            </button>
+        }
+    };
+    let keyframe_count = state.camera_path.keyframes.len();
+
     html! {
         <div class="toolbar">
             // File operations
@@ -155,6 +207,44 @@ pub fn Toolbar() -> Html {
                 >
                     {"📁"}
                 </button>
+                <button
+                    class="tool-btn"
+                    onclick={
+                        let state = state.clone();
+                        Callback::from(move |_| {
+                            let url = web_sys::window()
+                                .and_then(|w| w.prompt_with_message("IFC file URL:").ok())
+                                .flatten()
+                                .map(|url| url.trim().to_string())
+                                .filter(|url| !url.is_empty());
+                            if let Some(url) = url {
+                                crate::worker::load_ifc_url(url, state.clone());
+                            }
+                        })
+                    }
+                    title="Load IFC from URL"
+                >
+                    {"🌐"}
+                </button>
+                <button
+                    class="tool-btn"
+                    onclick={
+                        let stem = state
+                            .file_name
+                            .as_deref()
+                            .map(|name| file_stem(name.trim_end_matches(".ifc")))
+                            .unwrap_or_else(|| "model".to_string());
+                        Callback::from(move |_| match capture_canvas_png("bevy-canvas") {
+                            Some(png) => {
+                                download_file(&format!("{}-view.png", stem), "image/png", &png)
+                            }
+                            None => bridge::log("Could not capture the 3D view"),
+                        })
+                    }
+                    title="Save Screenshot (PNG)"
+                >
+                    {"📷"}
+                </button>
             </div>
 
             <div class="toolbar-separator" />
@@ -172,6 +262,7 @@ pub fn Toolbar() -> Html {
             <div class="toolbar-group">
                 {tool_button(Tool::Measure, &state)}
                 {tool_button(Tool::Section, &state)}
+                {tool_button(Tool::Annotate, &state)}
                 {tool_button(Tool::BoxSelect, &state)}
             </div>
 
@@ -232,6 +323,7 @@ pub fn Toolbar() -> Html {
                         crate::bridge::save_camera_cmd(&crate::bridge::CameraCommand {
                             cmd: "home".to_string(),
                             mode: None,
+                            view: None,
                         });
                     })}
                     title="Home View (H)"
@@ -244,12 +336,225 @@ pub fn Toolbar() -> Html {
                         crate::bridge::save_camera_cmd(&crate::bridge::CameraCommand {
                             cmd: "fit_all".to_string(),
                             mode: None,
+                            view: None,
                         });
                     })}
                     title="Fit All (F)"
                 >
                     {"⬚"}
                 </button>
+                <button
+                    class="tool-btn"
+                    onclick={Callback::from(|_| {
+                        let orthographic = bridge::load_camera().is_some_and(|c| c.orthographic);
+                        bridge::save_projection(!orthographic);
+                    })}
+                    title="Toggle Perspective / Orthographic (O)"
+                >
+                    {"⧈"}
+                </button>
+                <select
+                    class="view-select"
+                    onchange={Callback::from(|e: Event| {
+                        let select: HtmlSelectElement = e.target_unchecked_into();
+                        bridge::save_standard_view(&select.value());
+                        // Back to the label, so picking the same view again fires
+                        select.set_value("");
+                    })}
+                    title="Standard View"
+                >
+                    <option value="" selected=true disabled=true>{"View"}</option>
+                    <option value="top">{"Top"}</option>
+                    <option value="bottom">{"Bottom"}</option>
+                    <option value="front">{"Front"}</option>
+                    <option value="back">{"Back"}</option>
+                    <option value="left">{"Left"}</option>
+                    <option value="right">{"Right"}</option>
+                    <option value="iso">{"Iso"}</option>
+                </select>
+            </div>
+
+            <div class="toolbar-separator" />
+
+            // Camera path
+            <div class="toolbar-group">
+                <button
+                    class="tool-btn"
+                    onclick={
+                        let state = state.clone();
+                        Callback::from(move |_| {
+                            if let Some(camera) = bridge::load_camera() {
+                                state.dispatch(ViewerAction::AddKeyframe(camera));
+                            }
+                        })
+                    }
+                    title={format!("Add Camera Keyframe ({} recorded)", keyframe_count)}
+                >
+                    {"🎥"}
+                </button>
+                {path_button(|| ViewerAction::PlayPath, "▶", "Play Camera Path".to_string(), false, &state)}
+                {path_button(|| ViewerAction::StopPath, "⏹", "Stop Camera Path".to_string(), false, &state)}
+                {path_button(
+                    || ViewerAction::ToggleLoopPath,
+                    "🔁",
+                    "Loop Camera Path".to_string(),
+                    state.camera_path.looping,
+                    &state,
+                )}
+                {path_button(
+                    || ViewerAction::ClearKeyframes,
+                    "🗑",
+                    format!("Clear {} Keyframes", keyframe_count),
+                    false,
+                    &state,
+                )}
+            </div>
+
+            <div class="toolbar-separator" />
+
+            // Overlay toggles
+            <div class="toolbar-group">
+                {overlay_button(OverlayLayer::OriginAxes, "✛", "Origin Axes", &state)}
+                {overlay_button(OverlayLayer::NorthArrow, "🧭", "True North Arrow", &state)}
+                {overlay_button(OverlayLayer::ScaleBar, "📏", "Scale Bar (plan view)", &state)}
+                {overlay_button(OverlayLayer::Minimap, "🗺️", "Mini-map (click to move the camera)", &state)}
+                {overlay_button(OverlayLayer::ViewCube, "🧊", "View Cube (click a face to snap the view)", &state)}
+                {overlay_button(OverlayLayer::GridAxes, "#", "Grid Axes", &state)}
+                if !state.grid_intersections.is_empty() {
+                    <select
+                        class="view-select"
+                        title="Go to Grid Intersection"
+                        onchange={
+                            let state = state.clone();
+                            Callback::from(move |e: Event| {
+                                let select: HtmlSelectElement = e.target_unchecked_into();
+                                let index = select.value().parse::<usize>().ok();
+                                // Back to the label, so picking the same one again fires
+                                select.set_value("");
+                                if let Some(intersection) =
+                                    index.and_then(|i| state.grid_intersections.get(i))
+                                {
+                                    go_to_point(intersection.point);
+                                }
+                            })
+                        }
+                    >
+                        <option value="" selected=true disabled=true>{"Grid"}</option>
+                        { for state.grid_intersections.iter().enumerate().map(|(i, intersection)| html! {
+                            <option value={i.to_string()}>{&intersection.label}</option>
+                        }) }
+                    </select>
+                }
+                <button
+                    class={classes!("tool-btn", state.xray.is_some().then_some("active"))}
+                    onclick={
+                        let state = state.clone();
+                        Callback::from(move |_| {
+                            state.dispatch(ViewerAction::ToggleXray);
+                        })
+                    }
+                    title="X-Ray (everything but the selection)"
+                >
+                    {"👻"}
+                </button>
+                <button
+                    class={classes!("tool-btn", state.spaces.visible.then_some("active"))}
+                    onclick={
+                        let state = state.clone();
+                        Callback::from(move |_| {
+                            state.dispatch(ViewerAction::ToggleSpaces);
+                        })
+                    }
+                    title="Spaces (room volumes with names)"
+                >
+                    {"🚪"}
+                </button>
+                <button
+                    class={classes!("tool-btn", state.split_view.enabled.then_some("active"))}
+                    onclick={
+                        let state = state.clone();
+                        Callback::from(move |_| {
+                            state.dispatch(ViewerAction::ToggleSplitView);
+                        })
+                    }
+                    title="Split View (two cameras side by side)"
+                >
+                    {"◫"}
+                </button>
+                if state.split_view.enabled {
+                    <button
+                        class={classes!("tool-btn", state.split_view.synchronized.then_some("active"))}
+                        onclick={
+                            let state = state.clone();
+                            Callback::from(move |_| {
+                                state.dispatch(ViewerAction::ToggleSplitSync);
+                            })
+                        }
+                        title="Synchronize Split Views (pan and zoom together)"
+                    >
+                        {"🔗"}
+                    </button>
+                }
+                <select
+                    class="view-select"
+                    title="Exploded View"
+                    onchange={
+                        let state = state.clone();
+                        Callback::from(move |e: Event| {
+                            let select: HtmlSelectElement = e.target_unchecked_into();
+                            if let Some(mode) = ExplodeMode::ALL
+                                .into_iter()
+                                .find(|m| m.as_str() == select.value())
+                            {
+                                state.dispatch(ViewerAction::SetExplodeMode(mode));
+                            }
+                        })
+                    }
+                >
+                    { for ExplodeMode::ALL.iter().map(|mode| html! {
+                        <option
+                            value={mode.as_str()}
+                            selected={state.explode.mode == *mode}
+                        >
+                            {mode.label()}
+                        </option>
+                    }) }
+                </select>
+                if state.explode.mode != ExplodeMode::Off {
+                    <input
+                        type="range"
+                        class="explode-slider"
+                        min="0"
+                        max="2"
+                        step="0.05"
+                        value={state.explode.factor.to_string()}
+                        title={format!("Explode Factor ({:.2})", state.explode.factor)}
+                        oninput={
+                            let state = state.clone();
+                            Callback::from(move |e: InputEvent| {
+                                let input: HtmlInputElement = e.target_unchecked_into();
+                                if let Ok(factor) = input.value().parse::<f32>() {
+                                    state.dispatch(ViewerAction::SetExplodeFactor(factor));
+                                }
+                            })
+                        }
+                    />
+                }
+                <button
+                    class={classes!("tool-btn", (state.color_mode != ColorMode::Model).then_some("active"))}
+                    onclick={
+                        let state = state.clone();
+                        Callback::from(move |_| {
+                            state.dispatch(ViewerAction::SetColorMode(match state.color_mode {
+                                ColorMode::Model => ColorMode::TypeObject,
+                                _ => ColorMode::Model,
+                            }));
+                        })
+                    }
+                    title="Color by Type Object or Property"
+                >
+                    {"🎨"}
+                </button>
             </div>
 
             // Spacer
@@ -281,6 +586,18 @@ pub fn Toolbar() -> Html {
                 >
                     {"⌨"}
                 </button>
+                <button
+                    class={classes!("tool-btn", state.show_statistics_dialog.then_some("active"))}
+                    onclick={
+                        let state = state.clone();
+                        Callback::from(move |_| {
+                            state.dispatch(ViewerAction::ToggleStatisticsDialog);
+                        })
+                    }
+                    title="Statistics and Settings"
+                >
+                    {"📊"}
+                </button>
             </div>
 
             // Loading indicator
@@ -298,22 +615,13 @@ pub fn Toolbar() -> Html {
     }
 }
 
-/// Spatial structure entity info
-#[allow(dead_code)]
-struct SpatialInfo {
-    id: u32,
-    name: String,
-    entity_type: String,
-    elevation: Option<f32>,
-}
-
 /// Extract property sets and quantities for an element
 fn extract_properties_and_quantities(
     element_id: u32,
     element_properties: &std::collections::HashMap<u32, Vec<u32>>,
     element_to_type: &std::collections::HashMap<u32, u32>,
     decoder: &mut ifc_lite_core::EntityDecoder,
-    unit_scale: f64,
+    units: &ifc_lite_core::UnitAssignment,
 ) -> (Vec<PropertySet>, Vec<QuantityValue>) {
     let mut property_sets = Vec::new();
     let mut quantities = Vec::new();
@@ -352,38 +660,28 @@ fn extract_properties_and_quantities(
 
         match prop_def.ifc_type {
             ifc_lite_core::IfcType::IfcPropertySet => {
-                // IfcPropertySet: (GlobalId, OwnerHistory, Name, Description, HasProperties)
                 let pset_name = prop_def
-                    .get_string(2)
+                    .name()
                     .map(|s| s.to_string())
                     .unwrap_or_else(|| format!("PropertySet #{}", prop_def_id));
 
                 let mut properties = Vec::new();
 
-                // Get HasProperties list (attribute 4)
-                if let Some(prop_refs) = get_ref_list(&prop_def, 4) {
+                if let Some(prop_refs) = get_ref_list(&prop_def, Attribute::HasProperties) {
                     for prop_id in prop_refs {
                         if let Ok(prop) = decoder.decode_by_id(prop_id) {
-                            // IfcPropertySingleValue: (Name, Description, NominalValue, Unit)
-                            if prop.ifc_type == ifc_lite_core::IfcType::IfcPropertySingleValue {
-                                let name = prop
-                                    .get_string(0)
-                                    .map(|s| s.to_string())
-                                    .unwrap_or_default();
-
-                                // Get value - can be various types
-                                let value = if let Some(val) = prop.get(2) {
-                                    format_property_value(val)
-                                } else {
-                                    String::new()
-                                };
-
-                                // Get unit if present
-                                let unit = prop.get_string(3).map(|s| s.to_string());
-
-                                if !name.is_empty() {
-                                    properties.push(PropertyValue { name, value, unit });
-                                }
+                            // Single, bounded, enumerated, list and table values; Name at index 0
+                            let name = prop
+                                .get_string(0)
+                                .map(|s| s.to_string())
+                                .unwrap_or_default();
+
+                            let value = ifc_lite_core::PropertyData::from_property_with_units(
+                                &prop, decoder, units,
+                            );
+
+                            if let Some(value) = value.filter(|_| !name.is_empty()) {
+                                properties.push(PropertyValue { name, value });
                             }
                         }
                     }
@@ -402,64 +700,26 @@ fn extract_properties_and_quantities(
                 }
             }
             ifc_lite_core::IfcType::IfcElementQuantity => {
-                // IfcElementQuantity: (GlobalId, OwnerHistory, Name, Description, MethodOfMeasurement, Quantities)
                 let qset_name = prop_def
-                    .get_string(2)
+                    .name()
                     .map(|s| s.to_string())
                     .unwrap_or_else(|| format!("Quantities #{}", prop_def_id));
 
-                // Get Quantities list (attribute 5)
-                if let Some(qty_refs) = get_ref_list(&prop_def, 5) {
+                // Complex quantities are flattened
+                if let Some(qty_refs) = get_ref_list(&prop_def, Attribute::Quantities) {
                     for qty_id in qty_refs {
-                        if let Ok(qty) = decoder.decode_by_id(qty_id) {
-                            // IfcPhysicalQuantity subtypes: Name, Description, ...values
-                            let name = qty.get_string(0).map(|s| s.to_string()).unwrap_or_default();
-
-                            // Apply unit scale: length * scale, area * scale², volume * scale³
-                            let (value, unit, qty_type) = match qty.ifc_type {
-                                ifc_lite_core::IfcType::IfcQuantityLength => {
-                                    // IfcQuantityLength: (Name, Description, Unit, LengthValue, Formula)
-                                    let val = qty.get_float(3).unwrap_or(0.0) * unit_scale;
-                                    (val, "m".to_string(), "Length".to_string())
-                                }
-                                ifc_lite_core::IfcType::IfcQuantityArea => {
-                                    let val =
-                                        qty.get_float(3).unwrap_or(0.0) * unit_scale * unit_scale;
-                                    (val, "m²".to_string(), "Area".to_string())
-                                }
-                                ifc_lite_core::IfcType::IfcQuantityVolume => {
-                                    let val = qty.get_float(3).unwrap_or(0.0)
-                                        * unit_scale
-                                        * unit_scale
-                                        * unit_scale;
-                                    (val, "m³".to_string(), "Volume".to_string())
-                                }
-                                ifc_lite_core::IfcType::IfcQuantityCount => {
-                                    // Count doesn't need scaling
-                                    let val = qty.get_float(3).unwrap_or(0.0);
-                                    (val, "".to_string(), "Count".to_string())
-                                }
-                                ifc_lite_core::IfcType::IfcQuantityWeight => {
-                                    // Weight doesn't use length scale
-                                    let val = qty.get_float(3).unwrap_or(0.0);
-                                    (val, "kg".to_string(), "Weight".to_string())
-                                }
-                                ifc_lite_core::IfcType::IfcQuantityTime => {
-                                    // Time doesn't use length scale
-                                    let val = qty.get_float(3).unwrap_or(0.0);
-                                    (val, "s".to_string(), "Time".to_string())
-                                }
-                                _ => continue,
-                            };
-
-                            if !name.is_empty() {
-                                quantities.push(QuantityValue {
-                                    name: format!("{}: {}", qset_name, name),
-                                    value,
-                                    unit,
-                                    quantity_type: qty_type,
-                                });
+                        for qty in ifc_lite_core::decode_quantity(decoder, qty_id) {
+                            if qty.name.is_empty() {
+                                continue;
                             }
+                            // Explicit unit as written, otherwise project units scaled to SI
+                            let (value, unit) = qty.display_value(units);
+                            quantities.push(QuantityValue {
+                                name: format!("{}: {}", qset_name, qty.qualified_name()),
+                                value,
+                                unit,
+                                quantity_type: qty.kind.name().to_string(),
+                            });
                         }
                     }
                 }
@@ -471,680 +731,526 @@ fn extract_properties_and_quantities(
     (property_sets, quantities)
 }
 
-/// Format a property value for display
-fn format_property_value(val: &ifc_lite_core::AttributeValue) -> String {
-    match val {
-        ifc_lite_core::AttributeValue::String(s) => s.clone(),
-        ifc_lite_core::AttributeValue::Float(f) => format!("{:.4}", f),
-        ifc_lite_core::AttributeValue::Integer(i) => i.to_string(),
-        ifc_lite_core::AttributeValue::Enum(e) => e.clone(),
-        ifc_lite_core::AttributeValue::List(items) => {
-            // Check if this is a typed value (first element is type name)
-            if items.len() >= 2 {
-                if let ifc_lite_core::AttributeValue::String(type_name) = &items[0] {
-                    // Format as TypeName(value)
-                    let value_parts: Vec<String> =
-                        items[1..].iter().map(format_property_value).collect();
-                    return format!("{}({})", type_name, value_parts.join(", "));
-                }
-            }
-            // Regular list
-            let formatted: Vec<String> = items.iter().map(format_property_value).collect();
-            format!("[{}]", formatted.join(", "))
-        }
-        ifc_lite_core::AttributeValue::EntityRef(id) => format!("#{}", id),
-        ifc_lite_core::AttributeValue::Null => "".to_string(),
-        ifc_lite_core::AttributeValue::Derived => "*".to_string(),
-    }
+/// Streamed output of [`process_ifc`]
+pub enum ProcessEvent {
+    Progress(Progress),
+    Geometry(Vec<GeometryData>),
+    /// The load was degraded to stay within memory
+    Warning(String),
+}
+
+/// Everything [`process_ifc`] produces besides geometry
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ProcessedModel {
+    pub content_hash: String,
+    pub entity_data: Vec<EntityData>,
+    pub entities: Vec<crate::state::EntityInfo>,
+    pub storeys: Vec<StoreyInfo>,
+    pub spatial_tree: Option<SpatialNode>,
+    /// Systems and zones with their members
+    #[serde(default)]
+    pub systems: Vec<crate::state::SystemInfo>,
+    /// True north angle from the geometric representation context
+    #[serde(default)]
+    pub true_north: Option<f32>,
+    /// IfcGrid axes and their crossings, in file coordinates
+    #[serde(default)]
+    pub grid_axes: Vec<bridge::GridAxisData>,
+    #[serde(default)]
+    pub grid_intersections: Vec<bridge::GridIntersectionData>,
+    /// Offset (metres, IFC Z-up) taken off the geometry of a model placed
+    /// far from the file origin
+    #[serde(default)]
+    pub origin_shift: [f64; 3],
+    /// Entities skipped or left without geometry, sorted by line
+    #[serde(default)]
+    pub diagnostics: Vec<ifc_lite_core::Diagnostic>,
+    /// Structural findings of [`ifc_lite_core::validate`]
+    #[serde(default)]
+    pub validation: Vec<ifc_lite_core::Diagnostic>,
+    /// Where the time of processing went
+    #[serde(default)]
+    pub profile: ifc_lite_engine::LoadProfile,
 }
 
-/// Parse IFC content and send geometry to Bevy via localStorage
+/// Parse IFC content and send geometry to Bevy, on the calling thread
 pub fn parse_and_process_ifc(content: &str, state: &ViewerStateContext) -> Result<(), String> {
-    use crate::state::{SpatialNode, SpatialNodeType};
-    use ifc_lite_core::{build_entity_index, EntityDecoder, EntityScanner};
-    use ifc_lite_geometry::GeometryRouter;
-    use std::collections::HashMap;
+    let mut geometry = Vec::new();
+    bridge::begin_geometry_stream();
+    let batch_size = state.device_defaults().geometry_batch_size;
+    let model = process_ifc(
+        content,
+        batch_size,
+        &state.geometry,
+        &mut |event| match event {
+            ProcessEvent::Progress(progress) => state.dispatch(ViewerAction::SetProgress(progress)),
+            ProcessEvent::Geometry(batch) => {
+                if geometry.is_empty() && !batch.is_empty() {
+                    state.dispatch(ViewerAction::GeometryAvailable);
+                }
+                bridge::append_geometry_chunk(&batch);
+                geometry.extend(batch);
+            }
+            ProcessEvent::Warning(warning) => state.dispatch(ViewerAction::SetWarning(warning)),
+        },
+    )?;
+    apply_processed_model(model, &geometry, state);
+    Ok(())
+}
 
-    bridge::log("Starting IFC parsing...");
+/// Hand a processed model to Bevy and the UI state
+///
+/// The geometry must already have been streamed via
+/// [`bridge::append_geometry_chunk`] after [`bridge::begin_geometry_stream`].
+/// The model is cached for the next load of the same content.
+pub fn apply_processed_model(
+    model: ProcessedModel,
+    geometry: &[GeometryData],
+    state: &ViewerStateContext,
+) {
+    // Scene info first: finishing the stream triggers Bevy's reload
+    save_scene_info(&model);
+    let binary = bridge::finish_geometry_stream(geometry);
+    crate::cache::store(&model, binary, &state.geometry);
+    state.dispatch(ViewerAction::SetLoadProfile(Some(model.profile.clone())));
+    apply_model_data(model, state);
 
-    // Build entity index for O(1) lookups
-    let index = build_entity_index(content);
-    let entity_count = index.len();
+    bridge::log(&format!(
+        "Geometry sent to Bevy viewer: {} entities",
+        geometry.len()
+    ));
+}
 
-    bridge::log(&format!("Found {} entities in IFC file", entity_count));
+/// Hand a model from the cache to Bevy and the UI state
+pub fn apply_cached_model(model: ProcessedModel, geometry: &[u8], state: &ViewerStateContext) {
+    save_scene_info(&model);
+    bridge::save_geometry_binary(geometry);
+    state.dispatch(ViewerAction::GeometryAvailable);
+    // Its timings are those of the load that filled the cache
+    state.dispatch(ViewerAction::SetLoadProfile(None));
+    apply_model_data(model, state);
+}
 
-    // Create decoder with pre-built index
-    let mut decoder = EntityDecoder::with_index(content, index);
+/// Orbit Bevy's camera around a point in IFC coordinates, keeping its angle
+/// and distance
+fn go_to_point([x, y, z]: [f64; 3]) {
+    let camera = bridge::load_camera().unwrap_or(bridge::CameraData {
+        azimuth: 0.785,
+        elevation: 0.615,
+        distance: 10.0,
+        target: [0.0; 3],
+        orthographic: false,
+    });
+    bridge::save_camera_view(&bridge::CameraData {
+        // Bevy is Y-up with IFC +Y along -Z
+        target: [x, z, -y],
+        ..camera
+    });
+}
 
-    state.dispatch(ViewerAction::SetProgress(Progress {
-        phase: "Building spatial hierarchy".to_string(),
-        percent: 10.0,
-    }));
+fn save_scene_info(model: &ProcessedModel) {
+    bridge::save_scene_info(&bridge::SceneInfo {
+        true_north: model.true_north,
+        grid_axes: model.grid_axes.clone(),
+        grid_intersections: model.grid_intersections.clone(),
+        origin_shift: model.origin_shift,
+    });
+}
 
-    // First pass: collect spatial structure
-    // Spatial entities: Project, Site, Building, Storey, Space
-    let mut spatial_entities: HashMap<u32, SpatialInfo> = HashMap::new();
-    // IfcRelAggregates: parent -> children (for Project->Site->Building->Storey)
-    let mut aggregates: HashMap<u32, Vec<u32>> = HashMap::new();
-    // IfcRelContainedInSpatialStructure: spatial_element -> contained elements
-    let mut contained_in: HashMap<u32, Vec<u32>> = HashMap::new();
-    // Element to storey mapping for flat view
-    let mut element_to_storey: HashMap<u32, u32> = HashMap::new();
-    // IfcRelDefinesByProperties: element -> property definition IDs
-    let mut element_properties: HashMap<u32, Vec<u32>> = HashMap::new();
-    // IfcRelDefinesByType: element -> type ID
-    let mut element_to_type: HashMap<u32, u32> = HashMap::new();
-    // Track project ID for unit extraction
-    let mut project_id: Option<u32> = None;
-
-    // Use simple line-by-line parsing for reliability (scanner has issues with large files)
-    // Scan for spatial structure entities and relationships
-    let mut scan_count = 0;
-    for line in content.lines() {
-        let line = line.trim();
-        if !line.starts_with('#') {
-            continue;
-        }
+/// Entity data, session state and UI state of a model whose geometry was sent
+fn apply_model_data(model: ProcessedModel, state: &ViewerStateContext) {
+    bridge::save_entities(&model.entity_data);
+    bridge::save_content_hash(&model.content_hash);
+
+    let views = bridge::load_views(&model.content_hash);
+    let annotations = bridge::load_model_annotations(&model.content_hash);
+    state.dispatch(ViewerAction::SetContentHash(model.content_hash));
+    state.dispatch(ViewerAction::SetViews(views));
+    state.dispatch(ViewerAction::SetAnnotations(annotations));
+    if let Some(tree) = model.spatial_tree {
+        state.dispatch(ViewerAction::SetSpatialTree(tree));
+    }
+    state.dispatch(ViewerAction::SetEntities(model.entities));
+    state.dispatch(ViewerAction::SetStoreys(model.storeys));
+    state.dispatch(ViewerAction::SetSystems(model.systems));
+    state.dispatch(ViewerAction::SetGridIntersections(model.grid_intersections));
+    state.dispatch(ViewerAction::SetDiagnostics(model.diagnostics));
+    state.dispatch(ViewerAction::SetValidation(model.validation));
+
+    let linked = crate::utils::get_select_param();
+    if !linked.is_empty() {
+        state.dispatch(ViewerAction::SelectGlobalIds(linked));
+    }
+}
 
-        // Parse: #ID=TYPE(...)
-        let eq_pos = match line.find('=') {
-            Some(p) => p,
-            None => continue,
-        };
+/// Elements meshed between progress reports
+const GEOMETRY_PROGRESS_STEP: usize = 50;
+
+/// Hands the engine's meshes on in [`ProcessEvent::Geometry`] batches,
+/// within the memory budget
+struct GeometrySink<'e> {
+    on_event: &'e mut dyn FnMut(ProcessEvent),
+    batch_size: usize,
+    batch: Vec<GeometryData>,
+    quality: crate::memory::LoadQuality,
+    watchdog: crate::memory::GeometryWatchdog,
+    /// Measured before any bounding box stand-in replaces a mesh
+    measures: std::collections::HashMap<u32, ifc_lite_core::ElementMeasures>,
+}
 
-        let id_str = &line[1..eq_pos];
-        let id: u32 = match id_str.parse() {
-            Ok(i) => i,
-            Err(_) => continue,
+impl ifc_lite_engine::SceneSink for GeometrySink<'_> {
+    fn progress(&mut self, phase: ifc_lite_engine::BuildPhase, percent: f32) {
+        use ifc_lite_engine::BuildPhase;
+        let phase = match phase {
+            BuildPhase::Index => "Indexing",
+            BuildPhase::Spatial => "Building spatial hierarchy",
+            BuildPhase::Geometry => "Processing geometry",
+            BuildPhase::Complete => "Building spatial tree",
         };
-
-        let rest = &line[eq_pos + 1..];
-        let paren_pos = rest.find('(').unwrap_or(rest.len());
-        let type_name = rest[..paren_pos].trim();
-        let type_upper = type_name.to_uppercase();
-
-        scan_count += 1;
-
-        // Parse spatial structure entities
-        match type_upper.as_str() {
-            "IFCPROJECT" => {
-                project_id = Some(id);
-                if let Ok(entity) = decoder.decode_by_id(id) {
-                    let name = entity
-                        .get_string(2)
-                        .map(|s| s.to_string())
-                        .unwrap_or_else(|| "Project".to_string());
-                    spatial_entities.insert(
-                        id,
-                        SpatialInfo {
-                            id,
-                            name,
-                            entity_type: type_name.to_string(),
-                            elevation: None,
-                        },
-                    );
-                }
-            }
-            "IFCSITE" => {
-                if let Ok(entity) = decoder.decode_by_id(id) {
-                    let name = entity
-                        .get_string(2)
-                        .map(|s| s.to_string())
-                        .unwrap_or_else(|| "Site".to_string());
-                    spatial_entities.insert(
-                        id,
-                        SpatialInfo {
-                            id,
-                            name,
-                            entity_type: type_name.to_string(),
-                            elevation: None,
-                        },
-                    );
-                }
-            }
-            "IFCBUILDING" => {
-                if let Ok(entity) = decoder.decode_by_id(id) {
-                    let name = entity
-                        .get_string(2)
-                        .map(|s| s.to_string())
-                        .unwrap_or_else(|| "Building".to_string());
-                    spatial_entities.insert(
-                        id,
-                        SpatialInfo {
-                            id,
-                            name,
-                            entity_type: type_name.to_string(),
-                            elevation: None,
-                        },
-                    );
-                }
-            }
-            "IFCBUILDINGSTOREY" => {
-                if let Ok(entity) = decoder.decode_by_id(id) {
-                    let name = entity
-                        .get_string(2)
-                        .map(|s| s.to_string())
-                        .unwrap_or_else(|| format!("Storey #{}", id));
-                    let elevation = entity.get_float(9).map(|e| e as f32);
-                    spatial_entities.insert(
-                        id,
-                        SpatialInfo {
-                            id,
-                            name,
-                            entity_type: type_name.to_string(),
-                            elevation,
-                        },
-                    );
-                }
-            }
-            "IFCSPACE" => {
-                if let Ok(entity) = decoder.decode_by_id(id) {
-                    let name = entity
-                        .get_string(2)
-                        .map(|s| s.to_string())
-                        .unwrap_or_else(|| format!("Space #{}", id));
-                    spatial_entities.insert(
-                        id,
-                        SpatialInfo {
-                            id,
-                            name,
-                            entity_type: type_name.to_string(),
-                            elevation: None,
-                        },
-                    );
-                }
-            }
-            // Parse IfcRelAggregates for parent-child relationships
-            // Structure: (GlobalId, OwnerHistory, Name, Description, RelatingObject, RelatedObjects)
-            "IFCRELAGGREGATES" => {
-                if let Ok(entity) = decoder.decode_by_id(id) {
-                    bridge::log(&format!(
-                        "IfcRelAggregates #{}: {} attributes",
-                        id,
-                        entity.attributes.len()
-                    ));
-                    let parent_id = entity.get_ref(4);
-                    let children = get_ref_list(&entity, 5);
-                    bridge::log(&format!(
-                        "  parent: {:?}, children: {:?}",
-                        parent_id, children
-                    ));
-                    if let (Some(parent_id), Some(children)) = (parent_id, children) {
-                        aggregates.entry(parent_id).or_default().extend(children);
-                    }
-                }
-            }
-            // Parse IfcRelContainedInSpatialStructure
-            // Structure: (GlobalId, OwnerHistory, Name, Description, RelatedElements, RelatingStructure)
-            "IFCRELCONTAINEDINSPATIALSTRUCTURE" => {
-                if let Ok(entity) = decoder.decode_by_id(id) {
-                    if let Some(structure_id) = entity.get_ref(5) {
-                        if let Some(elements) = get_ref_list(&entity, 4) {
-                            contained_in
-                                .entry(structure_id)
-                                .or_default()
-                                .extend(elements.clone());
-                            // Also track element -> storey for flat view
-                            for elem_id in elements {
-                                element_to_storey.insert(elem_id, structure_id);
-                            }
-                        }
-                    }
-                }
-            }
-            // Parse IfcRelDefinesByProperties
-            // Structure: (GlobalId, OwnerHistory, Name, Description, RelatedObjects, RelatingPropertyDefinition)
-            "IFCRELDEFINESBYPROPERTIES" => {
-                if let Ok(entity) = decoder.decode_by_id(id) {
-                    if let Some(prop_def_id) = entity.get_ref(5) {
-                        if let Some(related_objects) = get_ref_list(&entity, 4) {
-                            for obj_id in related_objects {
-                                element_properties
-                                    .entry(obj_id)
-                                    .or_default()
-                                    .push(prop_def_id);
-                            }
-                        }
-                    }
-                }
-            }
-            // Parse IfcRelDefinesByType
-            // Structure: (GlobalId, OwnerHistory, Name, Description, RelatedObjects, RelatingType)
-            "IFCRELDEFINESBYTYPE" => {
-                if let Ok(entity) = decoder.decode_by_id(id) {
-                    if let Some(type_id) = entity.get_ref(5) {
-                        if let Some(related_objects) = get_ref_list(&entity, 4) {
-                            for obj_id in related_objects {
-                                element_to_type.insert(obj_id, type_id);
-                            }
-                        }
-                    }
-                }
-            }
-            _ => {}
-        }
+        (self.on_event)(ProcessEvent::Progress(Progress {
+            phase: phase.to_string(),
+            percent,
+        }));
     }
 
-    bridge::log(&format!("Scanned {} entities total", scan_count));
-    bridge::log(&format!(
-        "Found {} spatial entities, {} aggregate rels, {} containment rels, {} property rels, {} type rels",
-        spatial_entities.len(),
-        aggregates.len(),
-        contained_in.len(),
-        element_properties.len(),
-        element_to_type.len()
-    ));
+    fn wants_geometry(&mut self, entity_type: &str) -> bool {
+        self.quality.includes_type(entity_type)
+    }
 
-    // Extract and cache unit scale from project (default to 1.0 if not found)
-    let unit_scale = if let Some(proj_id) = project_id {
-        match decoder.extract_unit_scale(proj_id) {
-            Ok(scale) => {
-                bridge::log(&format!(
-                    "Unit scale: {} (from project #{}) - cached in decoder",
-                    scale, proj_id
-                ));
-                scale as f32
-            }
-            Err(e) => {
-                bridge::log(&format!("Failed to extract unit scale: {:?}, using 1.0", e));
-                decoder.set_length_unit_scale(1.0);
-                1.0
-            }
+    fn mesh(
+        &mut self,
+        element: &ifc_lite_engine::ElementInfo,
+        mut mesh: ifc_lite_geometry::Mesh,
+        color: [f32; 4],
+    ) {
+        use crate::memory::LoadQuality;
+        self.measures
+            .insert(element.id, ifc_lite_geometry::mesh_measures(&mesh));
+        if self.quality == LoadQuality::BoundingBoxes {
+            mesh = crate::memory::bounding_box_mesh(&mesh);
         }
-    } else {
-        bridge::log("No IFCPROJECT found, using unit scale 1.0");
-        decoder.set_length_unit_scale(1.0);
-        1.0
-    };
-
-    // Apply unit scale to elevations in spatial entities
-    for info in spatial_entities.values_mut() {
-        if let Some(ref mut elev) = info.elevation {
-            *elev *= unit_scale;
+        if self.watchdog.record(&mesh) {
+            self.quality = LoadQuality::BoundingBoxes;
+            let warning =
+                "Model exceeds the memory budget, showing remaining elements as bounding boxes"
+                    .to_string();
+            bridge::log(&warning);
+            (self.on_event)(ProcessEvent::Warning(warning));
         }
-    }
 
-    // Debug: log spatial entities
-    for (id, info) in &spatial_entities {
-        bridge::log(&format!(
-            "Spatial entity #{}: {} ({})",
-            id, info.name, info.entity_type
-        ));
+        // Spaces get a translucent color each
+        let color = if element.entity_type.eq_ignore_ascii_case("IFCSPACE") {
+            get_space_color(element.id)
+        } else {
+            color
+        };
+        self.batch.push(GeometryData {
+            entity_id: element.id as u64,
+            positions: mesh.positions,
+            normals: mesh.normals,
+            indices: mesh.indices,
+            colors: mesh.colors,
+            color,
+            // Identity transform (placement already applied by router)
+            transform: [
+                1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0,
+            ],
+            entity_type: element.entity_type.to_string(),
+            name: element.name.clone(),
+        });
+        if self.batch.len() >= self.batch_size {
+            (self.on_event)(ProcessEvent::Geometry(std::mem::take(&mut self.batch)));
+        }
     }
+}
 
-    // Debug: log aggregates
-    for (parent, children) in &aggregates {
-        bridge::log(&format!("Aggregate: #{} -> {:?}", parent, children));
+/// Spatial tree node for the hierarchy panel
+///
+/// Openings are listed as elements, so they select like the rest.
+fn spatial_node(node: ifc_lite_engine::SpatialNode) -> SpatialNode {
+    use ifc_lite_engine::SpatialKind;
+    SpatialNode {
+        id: node.id as u64,
+        node_type: match node.kind {
+            SpatialKind::Project => SpatialNodeType::Project,
+            SpatialKind::Site => SpatialNodeType::Site,
+            SpatialKind::Building => SpatialNodeType::Building,
+            SpatialKind::Storey => SpatialNodeType::Storey,
+            SpatialKind::Space => SpatialNodeType::Space,
+            SpatialKind::Facility => SpatialNodeType::Facility,
+            SpatialKind::FacilityPart => SpatialNodeType::FacilityPart,
+            SpatialKind::TypeGroup => SpatialNodeType::TypeGroup,
+            SpatialKind::Opening | SpatialKind::Element => SpatialNodeType::Element,
+        },
+        name: node.name,
+        entity_type: node.entity_type,
+        predefined_type: node.predefined_type,
+        elevation: node.elevation,
+        children: node.children.into_iter().map(spatial_node).collect(),
+        has_geometry: node.has_geometry,
+        referenced: node.referenced,
     }
+}
 
-    // Create geometry router
-    let router = GeometryRouter::new();
-
-    state.dispatch(ViewerAction::SetProgress(Progress {
-        phase: "Processing geometry".to_string(),
-        percent: 30.0,
-    }));
+/// Parse IFC content into geometry batches and UI data
+///
+/// Geometry is emitted in [`ProcessEvent::Geometry`] batches of `batch_size`
+/// meshes, tessellated and filtered by `geometry`. Touches neither the DOM
+/// nor the viewer state, so it can run in a web worker.
+pub fn process_ifc(
+    content: &str,
+    batch_size: usize,
+    geometry: &crate::state::GeometrySettings,
+    on_event: &mut dyn FnMut(ProcessEvent),
+) -> Result<ProcessedModel, String> {
+    use ifc_lite_core::{build_entity_index, ContentHash, Severity};
+    use ifc_lite_engine::{BuildOptions, SceneBuilder};
 
-    // Second pass: process geometry
-    let mut scanner = EntityScanner::new(content);
-    let mut geometry_data: Vec<GeometryData> = Vec::new();
-    let mut entity_data: Vec<EntityData> = Vec::new();
-    let mut processed = 0;
-    let mut errors = 0;
-
-    while let Some((id, type_name, _start, _end)) = scanner.next_entity() {
-        // Check if this is an element with potential geometry (using comprehensive check)
-        if ifc_lite_core::has_geometry_by_name(type_name) {
-            let ifc_type = ifc_lite_core::IfcType::from_str(type_name);
-            // Skip Unknown types - we can't properly process them
-            if matches!(ifc_type, ifc_lite_core::IfcType::Unknown(_)) {
-                bridge::log(&format!(
-                    "Skipping #{} ({}): Unknown IFC type",
-                    id, type_name
-                ));
-                continue;
-            }
+    bridge::log("Starting IFC parsing...");
 
-            // Decode the entity
-            match decoder.decode_by_id(id) {
-                Ok(entity) => {
-                    // Get entity name (attribute 2 for most building elements)
-                    let name = entity.get_string(2).map(|s| s.to_string());
-
-                    // Look up storey information from spatial_entities
-                    let (storey_name, storey_elevation) =
-                        if let Some(&storey_id) = element_to_storey.get(&id) {
-                            if let Some(storey) = spatial_entities.get(&storey_id) {
-                                (Some(storey.name.clone()), storey.elevation)
-                            } else {
-                                (None, None)
-                            }
-                        } else {
-                            (None, None)
-                        };
-
-                    // Always add to entity_data for hierarchy panel (even if geometry fails)
-                    // Use original type_name to preserve the actual IFC type
-                    entity_data.push(EntityData {
-                        id: id as u64,
-                        entity_type: type_name.to_string(),
-                        name: name.clone(),
-                        storey: storey_name,
-                        storey_elevation,
-                    });
+    let content_hash = ContentHash::of_str(content).to_hex();
+    bridge::log(&format!("Content hash: {}", content_hash));
 
-                    // Process geometry
-                    match router.process_element(&entity, &mut decoder) {
-                        Ok(mesh) => {
-                            if !mesh.is_empty() {
-                                // Convert mesh to bridge format
-                                // Mesh has positions/normals as flat f32 arrays, indices as u32
-                                // Sanitize values: replace NaN/Infinity with 0.0
-                                let sanitize = |arr: &[f32]| -> Vec<f32> {
-                                    arr.iter()
-                                        .map(|v| if v.is_finite() { *v } else { 0.0 })
-                                        .collect()
-                                };
-
-                                let positions = sanitize(&mesh.positions);
-                                let normals = sanitize(&mesh.normals);
-                                let indices = mesh.indices.clone();
-
-                                // Skip if all positions are zero (degenerate mesh)
-                                if positions.iter().all(|v| *v == 0.0) {
-                                    bridge::log(&format!(
-                                        "Skipping #{} ({}): degenerate geometry",
-                                        id, type_name
-                                    ));
-                                    errors += 1;
-                                    continue;
-                                }
+    // Build entity index for O(1) lookups
+    let index = build_entity_index(content);
+    let entity_count = index.len();
+    bridge::log(&format!("Found {} entities in IFC file", entity_count));
 
-                                // Default color based on element type
-                                let color = get_element_color(&ifc_type);
-
-                                // Identity transform (placement already applied by router)
-                                let transform = [
-                                    1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, 0.0,
-                                    0.0, 0.0, 0.0, 1.0,
-                                ];
-
-                                geometry_data.push(GeometryData {
-                                    entity_id: id as u64,
-                                    positions,
-                                    normals,
-                                    indices,
-                                    color,
-                                    transform,
-                                    entity_type: type_name.to_string(),
-                                    name: name.clone(),
-                                });
-
-                                processed += 1;
-                            }
-                        }
-                        Err(e) => {
-                            // Log but don't fail - some entities may not have geometry
-                            bridge::log(&format!("Skipping #{} ({}): {}", id, type_name, e));
-                            errors += 1;
-                        }
-                    }
-                }
-                Err(e) => {
-                    bridge::log_error(&format!("Failed to decode #{}: {:?}", id, e));
-                    errors += 1;
-                }
-            }
+    // Check the memory budget before tessellating anything
+    let plan =
+        crate::memory::LoadPlan::new(content.len(), entity_count, crate::memory::memory_budget());
+    if let Some(warning) = plan.warning() {
+        bridge::log(&warning);
+        on_event(ProcessEvent::Warning(warning));
+    }
 
-            // Update progress periodically
-            if processed % 50 == 0 {
-                let percent = 30.0 + (processed as f32 / entity_count as f32) * 50.0;
-                state.dispatch(ViewerAction::SetProgress(Progress {
-                    phase: format!("Processing geometry ({}/{})", processed, entity_count),
-                    percent,
-                }));
-            }
-        }
+    let mut sink = GeometrySink {
+        on_event: &mut *on_event,
+        batch_size,
+        batch: Vec::new(),
+        quality: plan.quality,
+        watchdog: crate::memory::GeometryWatchdog::new(plan.geometry_budget(content.len())),
+        measures: Default::default(),
+    };
+    // Models placed in map coordinates are shifted so they don't jitter in
+    // f32; Bevy adds the shift back to everything it shows the UI
+    let options = BuildOptions {
+        shift_origin: true,
+        batch_size: Some(GEOMETRY_PROGRESS_STEP),
+        geometry: geometry.options(),
+        group_by_type: true,
+    };
+    let scene = SceneBuilder::new(content)
+        .with_index(index)
+        .with_options(options)
+        .build(&mut sink);
+    let GeometrySink {
+        batch,
+        measures: geometry_measures,
+        ..
+    } = sink;
+    if !batch.is_empty() {
+        on_event(ProcessEvent::Geometry(batch));
     }
 
+    let ifc_lite_engine::Scene {
+        elements,
+        structure,
+        tree,
+        mut diagnostics,
+        mut profile,
+        mut decoder,
+        router,
+        origin_shift,
+        ..
+    } = scene;
+    let properties_start = js_sys::Date::now();
+    bridge::log(&format!(
+        "Found {} spatial entities, {} aggregate rels, {} containment rels, {} reference rels",
+        structure.spatial.len(),
+        structure.aggregates.len(),
+        structure.contained_in.len(),
+        structure.referenced_in.len(),
+    ));
     bridge::log(&format!(
-        "Processed {} meshes ({} errors)",
-        processed, errors
+        "Processed {} meshes ({} diagnostics)",
+        elements.iter().filter(|e| e.has_geometry).count(),
+        diagnostics.len()
     ));
 
-    state.dispatch(ViewerAction::SetProgress(Progress {
-        phase: "Sending to viewer".to_string(),
-        percent: 90.0,
+    on_event(ProcessEvent::Progress(Progress {
+        phase: "Reading properties".to_string(),
+        percent: 96.0,
     }));
 
-    // Save to localStorage for Bevy
-    bridge::save_geometry(&geometry_data);
-    bridge::save_entities(&entity_data);
+    let entity_data: Vec<EntityData> = elements
+        .into_iter()
+        .map(|e| EntityData {
+            id: e.id as u64,
+            entity_type: e.entity_type.to_string(),
+            name: e.name,
+            global_id: e.global_id,
+            storey: e.storey.as_deref().map(str::to_string),
+            storey_elevation: e.storey_elevation,
+            referenced_storeys: e.referenced_storeys.iter().map(|s| s.to_string()).collect(),
+            predefined_type: e.predefined_type,
+            object_type: e.object_type,
+        })
+        .collect();
 
-    // Build storey info for UI (from spatial_entities that are storeys)
-    let mut storey_infos: Vec<crate::state::StoreyInfo> = spatial_entities
-        .values()
-        .filter(|s| s.entity_type.to_uppercase() == "IFCBUILDINGSTOREY")
-        .map(|s| {
+    // Storeys for the UI, top floors first
+    let storey_infos: Vec<StoreyInfo> = structure
+        .storeys()
+        .into_iter()
+        .map(|(_, s)| {
             let entity_count = entity_data
                 .iter()
-                .filter(|e| e.storey.as_ref() == Some(&s.name))
+                .filter(|e| {
+                    e.storey.as_ref() == Some(&s.name) || e.referenced_storeys.contains(&s.name)
+                })
                 .count();
-            crate::state::StoreyInfo {
+            StoreyInfo {
                 name: s.name.clone(),
                 elevation: s.elevation.unwrap_or(0.0),
                 entity_count,
             }
         })
         .collect();
-    // Sort by elevation (descending - top floors first)
-    storey_infos.sort_by(|a, b| {
-        b.elevation
-            .partial_cmp(&a.elevation)
-            .unwrap_or(std::cmp::Ordering::Equal)
-    });
+
+    // Quantity takeoff inputs: file quantities first, mesh measures fill the gaps
+    let mut ifc_measures = ifc_lite_core::ifc_measures(content, &mut decoder);
+    let mut materials = ifc_lite_core::element_materials(content, &mut decoder);
+    let type_assignments = ifc_lite_core::TypeAssignments::from_content(content, &mut decoder);
+    let system_assignments = ifc_lite_core::SystemAssignments::from_content(content, &mut decoder);
+    let systems: Vec<crate::state::SystemInfo> = system_assignments
+        .systems()
+        .iter()
+        .map(|s| crate::state::SystemInfo {
+            id: s.id as u64,
+            entity_type: s.entity_type.clone(),
+            name: s.name.clone(),
+            members: system_assignments
+                .members(s.id)
+                .iter()
+                .map(|&id| id as u64)
+                .collect(),
+        })
+        .collect();
 
     // Build entity_infos for flat view with properties and quantities
     let entity_infos: Vec<crate::state::EntityInfo> = entity_data
         .iter()
         .map(|e| {
+            let mut measures = ifc_measures.remove(&(e.id as u32)).unwrap_or_default();
+            let measures_estimated = geometry_measures
+                .get(&(e.id as u32))
+                .is_some_and(|geometry| measures.fill_from(geometry));
             let (property_sets, quantities) = extract_properties_and_quantities(
                 e.id as u32,
-                &element_properties,
-                &element_to_type,
+                &structure.element_properties,
+                &structure.element_to_type,
                 &mut decoder,
-                unit_scale as f64,
+                &structure.units,
             );
+            let material_definition = materials.remove(&(e.id as u32));
             crate::state::EntityInfo {
                 id: e.id,
                 entity_type: e.entity_type.clone(),
                 name: e.name.clone(),
-                global_id: None,
+                global_id: e.global_id.clone(),
                 storey: e.storey.clone(),
                 storey_elevation: e.storey_elevation,
+                referenced_storeys: e.referenced_storeys.clone(),
+                predefined_type: e.predefined_type.clone(),
+                object_type: e.object_type.clone(),
+                type_object: type_assignments.type_of(e.id as u32).map(|t| {
+                    crate::state::TypeObjectInfo {
+                        id: t.id as u64,
+                        entity_type: t.entity_type.clone(),
+                        name: t.name.clone(),
+                    }
+                }),
                 property_sets,
                 quantities,
+                material: material_definition.as_ref().map(|m| m.name.clone()),
+                material_definition,
+                measures,
+                measures_estimated,
             }
         })
         .collect();
 
-    // Track which entities have geometry
-    let entities_with_geometry: std::collections::HashSet<u64> =
-        geometry_data.iter().map(|g| g.entity_id).collect();
-
-    // Build spatial tree
-    // Helper to get node type from entity type
-    let get_node_type = |entity_type: &str| -> SpatialNodeType {
-        match entity_type.to_uppercase().as_str() {
-            "IFCPROJECT" => SpatialNodeType::Project,
-            "IFCSITE" => SpatialNodeType::Site,
-            "IFCBUILDING" => SpatialNodeType::Building,
-            "IFCBUILDINGSTOREY" => SpatialNodeType::Storey,
-            "IFCSPACE" => SpatialNodeType::Space,
-            _ => SpatialNodeType::Element,
-        }
-    };
-
-    // Recursive function to build tree
-    fn build_node(
-        id: u32,
-        spatial_entities: &HashMap<u32, SpatialInfo>,
-        aggregates: &HashMap<u32, Vec<u32>>,
-        contained_in: &HashMap<u32, Vec<u32>>,
-        entity_data: &[EntityData],
-        entities_with_geometry: &std::collections::HashSet<u64>,
-        get_node_type: &dyn Fn(&str) -> SpatialNodeType,
-    ) -> Option<SpatialNode> {
-        let info = spatial_entities.get(&id)?;
-        let node_type = get_node_type(&info.entity_type);
-
-        let mut children: Vec<SpatialNode> = Vec::new();
-
-        // Add aggregated children (Site->Building->Storey hierarchy)
-        if let Some(child_ids) = aggregates.get(&id) {
-            for &child_id in child_ids {
-                if let Some(child_node) = build_node(
-                    child_id,
-                    spatial_entities,
-                    aggregates,
-                    contained_in,
-                    entity_data,
-                    entities_with_geometry,
-                    get_node_type,
-                ) {
-                    children.push(child_node);
-                }
-            }
-        }
-
-        // Add contained elements (elements in this storey/space)
-        if let Some(element_ids) = contained_in.get(&id) {
-            for &elem_id in element_ids {
-                // Find the entity data for this element
-                if let Some(elem) = entity_data.iter().find(|e| e.id == elem_id as u64) {
-                    let has_geometry = entities_with_geometry.contains(&(elem_id as u64));
-                    children.push(SpatialNode {
-                        id: elem_id as u64,
-                        node_type: SpatialNodeType::Element,
-                        name: elem.name.clone().unwrap_or_else(|| format!("#{}", elem_id)),
-                        entity_type: elem.entity_type.clone(),
-                        elevation: None,
-                        children: Vec::new(),
-                        has_geometry,
-                    });
-                }
-            }
-        }
-
-        // Sort children: spatial structures first (by elevation desc), then elements by type/name
-        children.sort_by(|a, b| {
-            // Spatial structures come first
-            let a_is_spatial = !matches!(a.node_type, SpatialNodeType::Element);
-            let b_is_spatial = !matches!(b.node_type, SpatialNodeType::Element);
-            if a_is_spatial != b_is_spatial {
-                return b_is_spatial.cmp(&a_is_spatial);
-            }
-            // For storeys, sort by elevation (descending)
-            if matches!(a.node_type, SpatialNodeType::Storey)
-                && matches!(b.node_type, SpatialNodeType::Storey)
-            {
-                return b
-                    .elevation
-                    .partial_cmp(&a.elevation)
-                    .unwrap_or(std::cmp::Ordering::Equal);
-            }
-            // Otherwise sort by type then name
-            match a.entity_type.cmp(&b.entity_type) {
-                std::cmp::Ordering::Equal => a.name.cmp(&b.name),
-                other => other,
-            }
-        });
+    on_event(ProcessEvent::Progress(Progress {
+        phase: "Validating".to_string(),
+        percent: 98.0,
+    }));
+    let validation = ifc_lite_core::validate(content);
+    bridge::log(&format!(
+        "Validation: {} errors, {} warnings",
+        validation.count(Severity::Error),
+        validation.count(Severity::Warning)
+    ));
 
-        Some(SpatialNode {
-            id: id as u64,
-            node_type,
-            name: info.name.clone(),
-            entity_type: info.entity_type.clone(),
-            elevation: info.elevation,
-            children,
-            has_geometry: false, // Spatial structures don't have geometry
-        })
+    diagnostics.sort();
+    if diagnostics.dropped() > 0 {
+        bridge::log(&format!(
+            "{} further diagnostics not kept",
+            diagnostics.dropped()
+        ));
     }
 
-    // Find the root (usually IfcProject)
-    let root_id = spatial_entities
+    // Grid axes are placed in the shifted scene; keep them in file coordinates
+    let grid_axes = if content.contains("IFCGRID(") {
+        router.grid_axes(content, &mut decoder)
+    } else {
+        Vec::new()
+    };
+    let to_file = |p: &ifc_lite_geometry::Point3<f64>| {
+        [
+            p.x + origin_shift[0],
+            p.y + origin_shift[1],
+            p.z + origin_shift[2],
+        ]
+    };
+    let grid_intersections = ifc_lite_geometry::grid_intersections(&grid_axes)
+        .into_iter()
+        .map(|i| bridge::GridIntersectionData {
+            label: format!("{}/{}", i.tags.0, i.tags.1),
+            point: to_file(&i.point),
+        })
+        .collect();
+    let grid_axes = grid_axes
         .iter()
-        .find(|(_, info)| info.entity_type.to_uppercase() == "IFCPROJECT")
-        .map(|(id, _)| *id);
-
-    if let Some(root_id) = root_id {
-        if let Some(tree) = build_node(
-            root_id,
-            &spatial_entities,
-            &aggregates,
-            &contained_in,
-            &entity_data,
-            &entities_with_geometry,
-            &get_node_type,
-        ) {
-            state.dispatch(ViewerAction::SetSpatialTree(tree));
-        }
-    }
-
-    state.dispatch(ViewerAction::SetEntities(entity_infos));
-    state.dispatch(ViewerAction::SetStoreys(storey_infos));
-
-    bridge::log(&format!(
-        "Geometry sent to Bevy viewer: {} entities",
-        geometry_data.len()
-    ));
+        .map(|axis| bridge::GridAxisData {
+            tag: axis.tag.clone(),
+            start: to_file(&axis.start),
+            end: to_file(&axis.end),
+        })
+        .collect();
 
-    Ok(())
+    // Entity records, quantities, validation and grids
+    profile.record("properties", js_sys::Date::now() - properties_start);
+    bridge::log(&profile.to_string());
+
+    Ok(ProcessedModel {
+        content_hash,
+        entity_data,
+        entities: entity_infos,
+        storeys: storey_infos,
+        spatial_tree: tree.map(spatial_node),
+        systems,
+        true_north: structure.true_north,
+        grid_axes,
+        grid_intersections,
+        origin_shift,
+        diagnostics: diagnostics.into_vec(),
+        validation: validation.into_vec(),
+        profile,
+    })
 }
 
-/// Get default color for element type (matches TypeScript viewer default-materials.ts)
-fn get_element_color(ifc_type: &ifc_lite_core::IfcType) -> [f32; 4] {
-    use ifc_lite_core::IfcType;
-    match ifc_type {
-        // Walls - warm white (matte plaster look)
-        IfcType::IfcWall | IfcType::IfcWallStandardCase => [0.95, 0.93, 0.88, 1.0],
-        // Slabs - cool gray (concrete)
-        IfcType::IfcSlab => [0.75, 0.75, 0.78, 1.0],
-        // Beams - steel blue metallic
-        IfcType::IfcBeam => [0.55, 0.55, 0.6, 1.0],
-        // Columns - steel blue metallic
-        IfcType::IfcColumn => [0.55, 0.55, 0.6, 1.0],
-        // Doors - warm wood
-        IfcType::IfcDoor => [0.6, 0.45, 0.3, 1.0],
-        // Windows - sky blue transparent glass
-        IfcType::IfcWindow => [0.6, 0.8, 0.95, 0.3],
-        // Roof - terracotta tile
-        IfcType::IfcRoof => [0.7, 0.45, 0.35, 1.0],
-        // Stairs - light warm gray
-        IfcType::IfcStair => [0.8, 0.78, 0.75, 1.0],
-        // Railings - dark metal
-        IfcType::IfcRailing => [0.35, 0.35, 0.4, 1.0],
-        // Plates - steel
-        IfcType::IfcPlate => [0.6, 0.6, 0.65, 1.0],
-        // Members - steel
-        IfcType::IfcMember => [0.55, 0.55, 0.6, 1.0],
-        // Curtain walls - glass blue transparent
-        IfcType::IfcCurtainWall => [0.5, 0.7, 0.85, 0.4],
-        // Coverings - light gray
-        IfcType::IfcCovering => [0.85, 0.85, 0.85, 1.0],
-        // Footings - concrete gray
-        IfcType::IfcFooting => [0.65, 0.65, 0.68, 1.0],
-        // Piles - concrete
-        IfcType::IfcPile => [0.6, 0.6, 0.62, 1.0],
-        // Opening elements - invisible/very light
-        IfcType::IfcOpeningElement => [0.9, 0.9, 0.9, 0.1],
-        // Building element proxy - neutral gray
-        IfcType::IfcBuildingElementProxy => [0.7, 0.7, 0.7, 1.0],
-        // Reinforcing - dark steel
-        IfcType::IfcReinforcingBar | IfcType::IfcReinforcingMesh | IfcType::IfcTendon => {
-            [0.4, 0.4, 0.45, 1.0]
-        }
-        // Default - neutral warm gray
-        _ => [0.8, 0.78, 0.75, 1.0],
-    }
+/// Translucent color of a space volume, stable per space
+fn get_space_color(id: u32) -> [f32; 4] {
+    let [r, g, b] = crate::palette::group_color(id as usize);
+    [r, g, b, 0.25]
 }
@@ -0,0 +1,127 @@
+//! Types panel - every IFC type in the model with a visibility checkbox
+//!
+//! Unchecking a type hides all its entities (all windows, all furniture) in
+//! one click; checking a partly hidden type shows the rest. Clicking a type
+//! selects its entities and the target button isolates them.
+
+use crate::state::{EntityInfo, ViewerAction, ViewerStateContext};
+use crate::utils::get_entity_display_name;
+use std::collections::{BTreeMap, HashSet};
+use std::rc::Rc;
+use yew::prelude::*;
+
+/// Entities per IFC type, by type name
+fn type_members(entities: &[EntityInfo]) -> Vec<(String, Rc<HashSet<u64>>)> {
+    let mut by_type: BTreeMap<&str, HashSet<u64>> = BTreeMap::new();
+    for entity in entities {
+        by_type
+            .entry(entity.entity_type.as_str())
+            .or_default()
+            .insert(entity.id);
+    }
+    by_type
+        .into_iter()
+        .map(|(ifc_type, ids)| (ifc_type.to_string(), Rc::new(ids)))
+        .collect()
+}
+
+/// IFC types with counts and show/hide/isolate controls
+#[function_component]
+pub fn TypesPanel() -> Html {
+    let state = use_context::<ViewerStateContext>().expect("ViewerStateContext not found");
+
+    let types = {
+        let entities = state.clone();
+        use_memo(
+            (state.content_hash.clone(), state.entities.len()),
+            move |_| type_members(&entities.entities),
+        )
+    };
+
+    let on_show_all = {
+        let state = state.clone();
+        Callback::from(move |_: MouseEvent| state.dispatch(ViewerAction::ShowAll))
+    };
+
+    html! {
+        <div class="types-panel">
+            <div class="tree-controls">
+                <span class="tree-count-total">{format!("{} types", types.len())}</span>
+                <button class="tree-control-btn" onclick={on_show_all} title="Show all">
+                    {"👁"}
+                </button>
+            </div>
+            <div class="types-list">
+                { for types.iter().map(|(ifc_type, ids)| {
+                    let hidden = ids.iter().filter(|id| state.hidden_ids.contains(id)).count();
+                    let isolated = state
+                        .isolated_ids
+                        .as_ref()
+                        .is_some_and(|isolated| *isolated == **ids);
+
+                    let on_toggle = {
+                        let state = state.clone();
+                        let ids = ids.clone();
+                        // Partly hidden types are shown first
+                        let show = hidden > 0;
+                        Callback::from(move |_: Event| {
+                            let ids = (*ids).clone();
+                            state.dispatch(if show {
+                                ViewerAction::ShowEntities(ids)
+                            } else {
+                                ViewerAction::HideEntities(ids)
+                            });
+                        })
+                    };
+                    let on_select = {
+                        let state = state.clone();
+                        let ids = ids.clone();
+                        Callback::from(move |_: MouseEvent| {
+                            state.dispatch(ViewerAction::SetSelection((*ids).clone()))
+                        })
+                    };
+                    let on_isolate = {
+                        let state = state.clone();
+                        let ids = ids.clone();
+                        Callback::from(move |_: MouseEvent| {
+                            state.dispatch(if isolated {
+                                ViewerAction::ShowAll
+                            } else {
+                                ViewerAction::IsolateEntities((*ids).clone())
+                            })
+                        })
+                    };
+
+                    html! {
+                        <div
+                            key={ifc_type.clone()}
+                            class={classes!(
+                                "types-row",
+                                (hidden == ids.len()).then_some("hidden"),
+                                (hidden > 0 && hidden < ids.len()).then_some("partial")
+                            )}
+                        >
+                            <input
+                                type="checkbox"
+                                checked={hidden == 0}
+                                onchange={on_toggle}
+                                title="Show or hide all entities of this type"
+                            />
+                            <span class="types-name" title={ifc_type.clone()} onclick={on_select}>
+                                {get_entity_display_name(ifc_type)}
+                            </span>
+                            <span class="types-count">{ids.len()}</span>
+                            <button
+                                class={classes!("tree-control-btn", isolated.then_some("active"))}
+                                onclick={on_isolate}
+                                title={if isolated { "Show all" } else { "Isolate this type" }}
+                            >
+                                {"🎯"}
+                            </button>
+                        </div>
+                    }
+                })}
+            </div>
+        </div>
+    }
+}
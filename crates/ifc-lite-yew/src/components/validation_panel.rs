@@ -0,0 +1,55 @@
+//! Validation panel - structural findings about the loaded model
+
+use super::diagnostics_panel::diagnostic_row;
+use crate::state::ViewerStateContext;
+use ifc_lite_core::Severity;
+use yew::prelude::*;
+
+/// Dangling references, duplicate GlobalIds, missing attributes and
+/// unplaced spaces found by [`ifc_lite_core::validate`]
+#[function_component]
+pub fn ValidationPanel() -> Html {
+    let state = use_context::<ViewerStateContext>().expect("ViewerStateContext not found");
+    let expanded = use_state(|| false);
+
+    if state.entities.is_empty() {
+        return html! {};
+    }
+
+    let errors = state
+        .validation
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .count();
+    let warnings = state.validation.len() - errors;
+    let summary = if state.validation.is_empty() {
+        "no issues".to_string()
+    } else {
+        format!("{} errors, {} warnings", errors, warnings)
+    };
+
+    let on_toggle = {
+        let expanded = expanded.clone();
+        Callback::from(move |_: MouseEvent| expanded.set(!*expanded))
+    };
+
+    html! {
+        <div class="qto-panel">
+            <div class="qto-header" onclick={on_toggle}>
+                <span class="section-header">
+                    {if *expanded { "▾ " } else { "▸ " }}{"Validation"}
+                </span>
+                <span class={if errors > 0 { "status-error" } else { "stats-label" }}>
+                    {summary}
+                </span>
+            </div>
+            if *expanded && !state.validation.is_empty() {
+                <div class="qto-list">
+                    <table class="stats-table">
+                        {for state.validation.iter().map(|d| diagnostic_row(d, &state))}
+                    </table>
+                </div>
+            }
+        </div>
+    }
+}
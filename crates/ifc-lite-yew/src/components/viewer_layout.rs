@@ -2,10 +2,23 @@
 //!
 //! Three-panel layout: hierarchy (left), viewport (center), properties (right)
 
-use super::{parse_and_process_ifc, HierarchyPanel, PropertiesPanel, StatusBar, Toolbar, Viewport};
-use crate::bridge::{self, VisibilityData};
-use crate::state::{use_viewer_state, Progress, ViewerAction, ViewerStateContext};
-use crate::utils::{build_ifc_url, fetch_ifc_file, get_file_param};
+use super::{
+    AnnotationsPanel, BcfPanel, DiagnosticsPanel, DiffPanel, HierarchyPanel, PropertiesPanel,
+    QtoPanel, StatisticsDialog, StatusBar, Toolbar, ValidationPanel, Viewport, ViewsPanel,
+};
+use crate::bridge::{
+    self, AnnotationData, CameraPathData, ExplodeData, MeasurementData, MeasurementsData,
+    NavigationData, OverlayData, PlanData, RenderSettingsData, SectionBoxData, SectionData,
+    SectionPlaneData, SpaceData, SplitViewData, StoreySectionData, VisibilityData, XrayData,
+};
+use crate::samples::load_manifest;
+use crate::state::{
+    use_viewer_state, AnnotationAnchor, MeasurePoint, OverlayLayer, ViewerAction,
+    ViewerStateContext, XrayFocus,
+};
+use crate::utils::{build_ifc_url, get_file_param, get_model_param, get_sample_param};
+use std::rc::Rc;
+use wasm_bindgen::JsCast;
 use wasm_bindgen_futures::spawn_local;
 use yew::prelude::*;
 
@@ -18,57 +31,17 @@ fn UrlLoader() -> Html {
     use_effect_with((), {
         let state = state.clone();
         move |_| {
-            // Check for ?file= parameter
-            if let Some(file_param) = get_file_param() {
-                let url = build_ifc_url(&file_param);
-                bridge::log(&format!("[Yew] Loading IFC from URL: {}", url));
-
-                // Extract filename for display
-                let file_name = file_param
-                    .rsplit('/')
-                    .next()
-                    .unwrap_or(&file_param)
-                    .to_string();
-
-                state.dispatch(ViewerAction::SetFileName(file_name));
-                state.dispatch(ViewerAction::SetLoading(true));
-                state.dispatch(ViewerAction::SetProgress(Progress {
-                    phase: "Fetching file".to_string(),
-                    percent: 0.0,
-                }));
-
-                // Fetch and parse
+            // ?model= takes any URL, ?file= a file in the server's /ifc directory
+            let url = get_model_param().or_else(|| get_file_param().map(|f| build_ifc_url(&f)));
+            if let Some(url) = url {
+                crate::worker::load_ifc_url(url, state);
+            } else if let Some(id) = get_sample_param() {
+                // Demo links name a sample from the manifest
                 spawn_local(async move {
-                    match fetch_ifc_file(&url).await {
-                        Ok(content) => {
-                            bridge::log(&format!("[Yew] Fetched {} bytes", content.len()));
-                            state.dispatch(ViewerAction::SetProgress(Progress {
-                                phase: "Parsing IFC".to_string(),
-                                percent: 10.0,
-                            }));
-
-                            match parse_and_process_ifc(&content, &state) {
-                                Ok(_) => {
-                                    bridge::log("[Yew] IFC file processed successfully");
-                                    state.dispatch(ViewerAction::SetLoading(false));
-                                    state.dispatch(ViewerAction::ClearProgress);
-                                }
-                                Err(e) => {
-                                    bridge::log_error(&format!(
-                                        "[Yew] Failed to process IFC: {}",
-                                        e
-                                    ));
-                                    state.dispatch(ViewerAction::SetError(e));
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            bridge::log_error(&format!("[Yew] Failed to fetch IFC: {}", e));
-                            state.dispatch(ViewerAction::SetError(format!(
-                                "Failed to load file: {}",
-                                e
-                            )));
-                        }
+                    let manifest = load_manifest().await;
+                    match manifest.samples.iter().find(|sample| sample.id == id) {
+                        Some(sample) => crate::worker::load_ifc_url(sample.url(), state),
+                        None => bridge::log_error(&format!("[Yew] Unknown sample: {}", id)),
                     }
                 });
             }
@@ -88,17 +61,21 @@ fn StateBridge() -> Html {
     // Track last known selection to avoid infinite loops
     let last_bevy_selection = use_state(std::collections::HashSet::<u64>::new);
 
-    // Sync visibility state to Bevy when hidden_ids or isolated_ids change
+    // Sync visibility state to Bevy when hidden, isolated or storey filter change
     {
-        let hidden_ids = state.hidden_ids.clone();
-        let isolated_ids = state.isolated_ids.clone();
-
         use_effect_with(
-            (hidden_ids.len(), isolated_ids.as_ref().map(|s| s.len())),
-            move |_| {
+            (
+                state.hidden_ids.clone(),
+                state.isolated_ids.clone(),
+                state.storey_filter.clone(),
+            ),
+            move |(hidden_ids, isolated_ids, storey_filter)| {
                 let visibility = VisibilityData {
                     hidden: hidden_ids.iter().copied().collect(),
-                    isolated: isolated_ids.map(|ids| ids.iter().copied().collect()),
+                    isolated: isolated_ids
+                        .as_ref()
+                        .map(|ids| ids.iter().copied().collect()),
+                    storey: storey_filter.clone(),
                 };
                 bridge::save_visibility(&visibility);
                 bridge::log(&format!(
@@ -111,6 +88,319 @@ fn StateBridge() -> Html {
         );
     }
 
+    // Sync section planes, box and storey section to Bevy
+    {
+        use_effect_with(
+            (state.section.clone(), state.storeys.clone()),
+            move |(section, storeys)| {
+                // Storeys without a known elevation are cut as if they stood at zero
+                let storey = section.storey.as_ref().map(|cut| StoreySectionData {
+                    storey: cut.storey.clone(),
+                    elevation: storeys
+                        .iter()
+                        .find(|s| s.name == cut.storey)
+                        .map_or(0.0, |s| s.elevation),
+                    cut_height: cut.cut_height,
+                });
+                bridge::save_section(&SectionData {
+                    planes: section
+                        .planes
+                        .iter()
+                        .filter(|p| p.enabled)
+                        .map(|p| SectionPlaneData {
+                            axis: p.axis.as_str().to_string(),
+                            position: p.position,
+                            flipped: p.flipped,
+                        })
+                        .collect(),
+                    section_box: section.section_box.enabled.then_some(SectionBoxData {
+                        min: section.section_box.min,
+                        max: section.section_box.max,
+                    }),
+                    cap_color: Some(section.cap_color.clone()),
+                    storey,
+                });
+                || ()
+            },
+        );
+    }
+
+    // Persist named views with the model they belong to
+    {
+        use_effect_with(
+            (state.content_hash.clone(), state.views.clone()),
+            move |(content_hash, views)| {
+                if let Some(content_hash) = content_hash {
+                    bridge::save_views(content_hash, views);
+                }
+                || ()
+            },
+        );
+    }
+
+    // Persist annotations with the model they belong to
+    {
+        use_effect_with(
+            (state.content_hash.clone(), state.annotations.clone()),
+            move |(content_hash, annotations)| {
+                if let Some(content_hash) = content_hash {
+                    bridge::save_model_annotations(content_hash, annotations);
+                }
+                || ()
+            },
+        );
+    }
+
+    // Hand the device defaults to Bevy (profiled once at startup), with the
+    // graphics settings on top
+    {
+        use_effect_with(state.device.clone(), move |device| {
+            if let Some(device) = device {
+                bridge::log(&format!(
+                    "[Yew] Device profile: {} tier ({:?} GiB, {:?} cores, GPU {:?}, touch {})",
+                    device.tier.label(),
+                    device.memory_gib,
+                    device.cores,
+                    device.gpu,
+                    device.touch
+                ));
+            }
+            || ()
+        });
+        use_effect_with(
+            (
+                state.device.clone(),
+                state.shadow_quality(),
+                state.ambient_occlusion,
+            ),
+            move |(device, shadows, ambient_occlusion)| {
+                if let Some(device) = device {
+                    let defaults = device.defaults();
+                    bridge::save_render_settings(&RenderSettingsData {
+                        msaa_samples: defaults.msaa_samples,
+                        shadows: *shadows,
+                        ambient_occlusion: *ambient_occlusion,
+                        chunk_size: defaults.chunk_size,
+                        lod_error_px: defaults.lod_error_px,
+                        smooth_normals: defaults.smooth_normals,
+                        crease_angle: defaults.crease_angle,
+                        optimize_meshes: defaults.optimize_meshes,
+                    });
+                }
+                || ()
+            },
+        );
+    }
+
+    // Sync mouse navigation preferences to Bevy
+    {
+        use_effect_with(
+            (state.orbit_around_cursor, state.zoom_to_cursor),
+            move |(orbit_around_cursor, zoom_to_cursor)| {
+                bridge::save_navigation(&NavigationData {
+                    orbit_around_cursor: *orbit_around_cursor,
+                    zoom_to_cursor: *zoom_to_cursor,
+                });
+                || ()
+            },
+        );
+    }
+
+    // Sync overlay toggles to Bevy
+    {
+        let overlay = state.overlay.clone();
+
+        use_effect_with(overlay, move |overlay| {
+            bridge::save_overlay(&OverlayData {
+                origin_axes: overlay.origin_axes,
+                north_arrow: overlay.north_arrow,
+                scale_bar: overlay.scale_bar,
+                minimap: overlay.minimap,
+                view_cube: overlay.view_cube,
+                grid_axes: overlay.grid_axes,
+                stats: overlay.stats,
+            });
+            || ()
+        });
+    }
+
+    // Sync x-ray mode to Bevy
+    {
+        use_effect_with(state.xray.clone(), move |xray| {
+            bridge::save_xray(&XrayData {
+                enabled: xray.is_some(),
+                ifc_type: match xray {
+                    Some(XrayFocus::Type(ifc_type)) => Some(ifc_type.clone()),
+                    _ => None,
+                },
+            });
+            || ()
+        });
+    }
+
+    // Sync space mode to Bevy
+    {
+        use_effect_with(state.spaces.clone(), move |spaces| {
+            bridge::save_spaces(&SpaceData {
+                visible: spaces.visible,
+                hidden_storeys: spaces.hidden_storeys.clone(),
+            });
+            || ()
+        });
+    }
+
+    // Sync the exploded view to Bevy
+    {
+        use_effect_with(state.explode.clone(), move |explode| {
+            bridge::save_explode(&ExplodeData {
+                mode: explode.mode.as_str().to_string(),
+                factor: explode.factor,
+            });
+            || ()
+        });
+    }
+
+    // Sync the camera path and playback to Bevy
+    {
+        use_effect_with(state.camera_path.clone(), move |path| {
+            bridge::save_camera_path(&CameraPathData {
+                keyframes: path.keyframes.clone(),
+                looping: path.looping,
+                playing: path.playing,
+                run: path.run,
+            });
+            || ()
+        });
+    }
+
+    // Sync the storey plan to Bevy
+    {
+        use_effect_with(state.plan.clone(), move |plan| {
+            bridge::save_plan(&PlanData {
+                storey: plan.storey.clone(),
+                cut_height: plan.cut_height,
+            });
+            || ()
+        });
+    }
+
+    // Sync the split view to Bevy
+    {
+        use_effect_with(state.split_view.clone(), move |split_view| {
+            bridge::save_split_view(&SplitViewData {
+                enabled: split_view.enabled,
+                synchronized: split_view.synchronized,
+            });
+            || ()
+        });
+    }
+
+    // Sync palette colors to Bevy; the memo hands out a new Rc only when
+    // the palette was recomputed, which is cheaper to compare than its groups
+    {
+        let palette = crate::palette::use_palette(&state);
+        use_effect_with(Rc::as_ptr(&palette) as usize, move |_| {
+            bridge::save_palette(&palette);
+            || ()
+        });
+    }
+
+    // Sync measurements to Bevy (Yew -> Bevy)
+    {
+        let measurements = state.measurements.clone();
+        let pending = state.pending_measure_point.clone();
+        use_effect_with((measurements, pending), move |(measurements, pending)| {
+            let point = |p: &MeasurePoint| [p.x, p.y, p.z];
+            bridge::save_measurements(&MeasurementsData {
+                measurements: measurements
+                    .iter()
+                    .map(|m| MeasurementData {
+                        id: m.id,
+                        start: point(&m.start),
+                        end: point(&m.end),
+                    })
+                    .collect(),
+                pending: pending.as_ref().map(point),
+            });
+            || ()
+        });
+    }
+
+    // Poll measure points picked in Bevy (Bevy -> Yew)
+    {
+        let state = state.clone();
+        use_effect_with((), move |_| {
+            let interval = gloo::timers::callback::Interval::new(100, move || {
+                for point in bridge::take_measure_points() {
+                    bridge::log(&format!(
+                        "[Yew] Measure point ({:.3}, {:.3}, {:.3}) snapped to {}",
+                        point.x, point.y, point.z, point.snap
+                    ));
+                    state.dispatch(ViewerAction::AddMeasurePoint(MeasurePoint {
+                        x: point.x,
+                        y: point.y,
+                        z: point.z,
+                    }));
+                }
+            });
+            move || drop(interval)
+        });
+    }
+
+    // Poll thumbnails rendered by Bevy (Bevy -> Yew)
+    {
+        let state = state.clone();
+        use_effect_with((), move |_| {
+            let interval = gloo::timers::callback::Interval::new(250, move || {
+                let rendered: Vec<_> = bridge::take_thumbnails()
+                    .into_iter()
+                    .map(|t| {
+                        let url = t.png.map(|png| format!("data:image/png;base64,{}", png));
+                        (t.key, url)
+                    })
+                    .collect();
+                if !rendered.is_empty() {
+                    state.dispatch(ViewerAction::SetThumbnails(rendered));
+                }
+            });
+            move || drop(interval)
+        });
+    }
+
+    // Sync annotation anchors to Bevy
+    {
+        use_effect_with(state.annotations.clone(), move |annotations| {
+            bridge::save_annotations(
+                &annotations
+                    .iter()
+                    .map(|a| AnnotationData {
+                        id: a.id,
+                        entity_id: a.entity_id,
+                        point: a.point,
+                    })
+                    .collect::<Vec<_>>(),
+            );
+            || ()
+        });
+    }
+
+    // Poll annotation points picked in Bevy (Bevy -> Yew)
+    {
+        let state = state.clone();
+        use_effect_with((), move |_| {
+            let interval = gloo::timers::callback::Interval::new(100, move || {
+                // A later click moves the annotation still waiting for text
+                if let Some(pick) = bridge::take_annotation_picks().pop() {
+                    state.dispatch(ViewerAction::BeginAnnotation(AnnotationAnchor {
+                        entity_id: Some(pick.entity_id),
+                        point: Some(pick.point),
+                    }));
+                }
+            });
+            move || drop(interval)
+        });
+    }
+
     // Poll selection from Bevy (Bevy -> Yew)
     // Only applies when selection source is "bevy" to avoid race conditions
     {
@@ -166,7 +456,7 @@ fn StateBridge() -> Html {
         let selected_ids = state.selected_ids.clone();
         let hovered_id = state.hovered_id;
 
-        use_effect_with((selected_ids.len(), hovered_id), move |_| {
+        use_effect_with((selected_ids.clone(), hovered_id), move |_| {
             let selection = bridge::SelectionData {
                 selected_ids: selected_ids.iter().copied().collect(),
                 hovered_id,
@@ -191,6 +481,24 @@ pub struct ViewerLayoutProps {
 pub fn ViewerLayout(props: &ViewerLayoutProps) -> Html {
     let state = use_viewer_state();
 
+    // F12 toggles the stats overlay
+    {
+        let dispatcher = state.dispatcher();
+        use_effect_with((), move |_| {
+            let window = gloo::utils::window();
+            let listener = gloo::events::EventListener::new(&window, "keydown", move |e| {
+                let Some(e) = e.dyn_ref::<KeyboardEvent>() else {
+                    return;
+                };
+                if e.key() == "F12" {
+                    e.prevent_default();
+                    dispatcher.dispatch(ViewerAction::ToggleOverlay(OverlayLayer::Stats));
+                }
+            });
+            move || drop(listener)
+        });
+    }
+
     // Theme class
     let theme_class = match state.theme {
         crate::state::Theme::Dark => "theme-dark",
@@ -223,6 +531,12 @@ pub fn ViewerLayout(props: &ViewerLayoutProps) -> Html {
                             </button>
                         </div>
                         <HierarchyPanel />
+                        <ViewsPanel />
+                        <AnnotationsPanel />
+                        <BcfPanel />
+                        <QtoPanel />
+                        <ValidationPanel />
+                        <DiffPanel />
                     </div>
                 } else {
                     <button
@@ -280,6 +594,12 @@ pub fn ViewerLayout(props: &ViewerLayoutProps) -> Html {
                         {"◀"}
                     </button>
                 }
+                if state.show_statistics_dialog {
+                    <StatisticsDialog />
+                }
+                if state.show_diagnostics_panel {
+                    <DiagnosticsPanel />
+                }
             </div>
         </ContextProvider<ViewerStateContext>>
     }
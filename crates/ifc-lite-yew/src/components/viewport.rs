@@ -1,8 +1,17 @@
 //! Viewport component - embeds Bevy canvas
 
-use crate::bridge::{is_bevy_loaded, load_bevy_viewer, log, log_error};
-use crate::state::ViewerStateContext;
+use super::{ColorLegend, ContextMenu, DebugOverlay, SectionPanel, StartScreen};
+use crate::bridge::{
+    has_embedded_renderer, is_bevy_running, load_annotation_labels, load_bevy_viewer,
+    load_grid_labels, load_hover, load_measure_labels, load_scale_bar, load_space_labels, log,
+    log_error, preload_bevy_viewer, start_embedded_renderer, AnnotationLabelData, GridLabelData,
+    HoverData, MeasureLabelData, ScaleBarData, SpaceLabelData,
+};
+use crate::state::{ColorMode, Tool, ViewerAction, ViewerStateContext};
+use crate::utils::get_entity_display_name;
+use ifc_lite_core::UnitSystem;
 use wasm_bindgen_futures::spawn_local;
+use web_sys::HtmlInputElement;
 use yew::prelude::*;
 
 /// Bevy loading state
@@ -14,50 +23,391 @@ enum BevyState {
     Error,
 }
 
+/// Format a scale bar length in metres for display
+fn format_scale_length(length: f32, system: UnitSystem) -> String {
+    if system == UnitSystem::Imperial {
+        system.format_length(length as f64)
+    } else if length >= 1000.0 {
+        format!("{} km", length / 1000.0)
+    } else if length >= 1.0 {
+        format!("{} m", length)
+    } else if length >= 0.01 {
+        format!("{} cm", (length * 100.0).round())
+    } else {
+        format!("{} mm", (length * 1000.0).round())
+    }
+}
+
+/// Scale bar overlay, shown while Bevy reports a plan view
+#[function_component]
+fn ScaleBar() -> Html {
+    let state = use_context::<ViewerStateContext>().expect("ViewerStateContext not found");
+    let scale_bar = use_state(|| None::<ScaleBarData>);
+
+    {
+        let scale_bar = scale_bar.clone();
+        use_effect_with((), move |_| {
+            let interval = gloo::timers::callback::Interval::new(200, move || {
+                let latest = load_scale_bar();
+                if *scale_bar != latest {
+                    scale_bar.set(latest);
+                }
+            });
+            move || drop(interval)
+        });
+    }
+
+    match &*scale_bar {
+        Some(bar) if bar.length > 0.0 => html! {
+            <div class="scale-bar">
+                <div class="scale-bar-line" style={format!("width: {:.0}px", bar.width_px)} />
+                <span class="scale-bar-label">{format_scale_length(bar.length, state.unit_system)}</span>
+            </div>
+        },
+        _ => html! {},
+    }
+}
+
+/// Distance labels at the positions Bevy publishes for each measurement
+#[function_component]
+fn MeasureLabels() -> Html {
+    let state = use_context::<ViewerStateContext>().expect("ViewerStateContext not found");
+    let labels = use_state(Vec::<MeasureLabelData>::new);
+
+    {
+        let labels = labels.clone();
+        use_effect_with((), move |_| {
+            let interval = gloo::timers::callback::Interval::new(50, move || {
+                let latest = load_measure_labels();
+                if *labels != latest {
+                    labels.set(latest);
+                }
+            });
+            move || drop(interval)
+        });
+    }
+
+    html! {
+        <>
+            { for labels.iter().map(|label| {
+                let remove = {
+                    let state = state.clone();
+                    let id = label.id;
+                    Callback::from(move |_: MouseEvent| {
+                        state.dispatch(ViewerAction::RemoveMeasurement(id))
+                    })
+                };
+                html! {
+                    <div
+                        class="measure-label"
+                        style={format!("left: {:.0}px; top: {:.0}px", label.x, label.y)}
+                    >
+                        <span>{state.unit_system.format_length(label.distance as f64)}</span>
+                        <button class="measure-remove" onclick={remove} title="Remove measurement">
+                            {"✕"}
+                        </button>
+                    </div>
+                }
+            })}
+        </>
+    }
+}
+
+/// Grid axis tags at the positions Bevy publishes for each axis end
+#[function_component]
+fn GridLabels() -> Html {
+    let labels = use_state(Vec::<GridLabelData>::new);
+
+    {
+        let labels = labels.clone();
+        use_effect_with((), move |_| {
+            let interval = gloo::timers::callback::Interval::new(50, move || {
+                let latest = load_grid_labels();
+                if *labels != latest {
+                    labels.set(latest);
+                }
+            });
+            move || drop(interval)
+        });
+    }
+
+    html! {
+        <>
+            { for labels.iter().map(|label| html! {
+                <div
+                    class="grid-label"
+                    style={format!("left: {:.0}px; top: {:.0}px", label.x, label.y)}
+                >
+                    {&label.tag}
+                </div>
+            })}
+        </>
+    }
+}
+
+/// Space names at the centres Bevy publishes for the visible spaces
+#[function_component]
+fn SpaceLabels() -> Html {
+    let labels = use_state(Vec::<SpaceLabelData>::new);
+
+    {
+        let labels = labels.clone();
+        use_effect_with((), move |_| {
+            let interval = gloo::timers::callback::Interval::new(50, move || {
+                let latest = load_space_labels();
+                if *labels != latest {
+                    labels.set(latest);
+                }
+            });
+            move || drop(interval)
+        });
+    }
+
+    html! {
+        <>
+            { for labels.iter().map(|label| html! {
+                <div
+                    class="space-label"
+                    style={format!("left: {:.0}px; top: {:.0}px", label.x, label.y)}
+                >
+                    {&label.name}
+                </div>
+            })}
+        </>
+    }
+}
+
+/// Annotation texts at the positions Bevy publishes for their anchors
+#[function_component]
+fn AnnotationLabels() -> Html {
+    let state = use_context::<ViewerStateContext>().expect("ViewerStateContext not found");
+    let labels = use_state(Vec::<AnnotationLabelData>::new);
+
+    {
+        let labels = labels.clone();
+        use_effect_with((), move |_| {
+            let interval = gloo::timers::callback::Interval::new(50, move || {
+                let latest = load_annotation_labels();
+                if *labels != latest {
+                    labels.set(latest);
+                }
+            });
+            move || drop(interval)
+        });
+    }
+
+    html! {
+        <>
+            { for labels.iter().filter_map(|label| {
+                let annotation = state.annotations.iter().find(|a| a.id == label.id)?;
+                let remove = {
+                    let state = state.clone();
+                    let id = label.id;
+                    Callback::from(move |_: MouseEvent| {
+                        state.dispatch(ViewerAction::RemoveAnnotation(id))
+                    })
+                };
+                Some(html! {
+                    <div
+                        class="annotation-label"
+                        style={format!("left: {:.0}px; top: {:.0}px", label.x, label.y)}
+                    >
+                        <span>{&annotation.text}</span>
+                        <button class="measure-remove" onclick={remove} title="Remove annotation">
+                            {"✕"}
+                        </button>
+                    </div>
+                })
+            })}
+        </>
+    }
+}
+
+/// Text field for the annotation waiting to be placed
+#[function_component]
+fn AnnotationEditor() -> Html {
+    let state = use_context::<ViewerStateContext>().expect("ViewerStateContext not found");
+    let text = use_state(String::new);
+    let input_ref = use_node_ref();
+
+    {
+        let input_ref = input_ref.clone();
+        use_effect_with((), move |_| {
+            if let Some(input) = input_ref.cast::<HtmlInputElement>() {
+                let _ = input.focus();
+            }
+            || ()
+        });
+    }
+
+    let on_add = {
+        let state = state.clone();
+        let text = text.clone();
+        Callback::from(move |_| {
+            state.dispatch(ViewerAction::AddAnnotation((*text).clone()));
+            text.set(String::new());
+        })
+    };
+    let on_cancel = {
+        let state = state.clone();
+        Callback::from(move |_| state.dispatch(ViewerAction::CancelAnnotation))
+    };
+    let on_keydown = {
+        let on_add = on_add.clone();
+        let on_cancel = on_cancel.clone();
+        Callback::from(move |e: KeyboardEvent| match e.key().as_str() {
+            "Enter" => on_add.emit(()),
+            "Escape" => on_cancel.emit(()),
+            _ => {}
+        })
+    };
+
+    html! {
+        <div class="annotation-editor">
+            <input
+                ref={input_ref}
+                type="text"
+                class="search-input"
+                placeholder="Annotation text…"
+                value={(*text).clone()}
+                oninput={
+                    let text = text.clone();
+                    Callback::from(move |e: InputEvent| {
+                        let input: HtmlInputElement = e.target_unchecked_into();
+                        text.set(input.value());
+                    })
+                }
+                onkeydown={on_keydown}
+            />
+            <button
+                class="copy-btn"
+                disabled={text.trim().is_empty()}
+                onclick={on_add.reform(|_: MouseEvent| ())}
+                title="Add annotation"
+            >
+                {"✓"}
+            </button>
+            <button
+                class="copy-btn"
+                onclick={on_cancel.reform(|_: MouseEvent| ())}
+                title="Cancel"
+            >
+                {"✕"}
+            </button>
+        </div>
+    }
+}
+
+/// Type, name and storey of the entity Bevy reports under the cursor
+#[function_component]
+fn HoverTooltip() -> Html {
+    let state = use_context::<ViewerStateContext>().expect("ViewerStateContext not found");
+    let hover = use_state(|| None::<HoverData>);
+
+    {
+        let hover = hover.clone();
+        use_effect_with((), move |_| {
+            let interval = gloo::timers::callback::Interval::new(50, move || {
+                let latest = load_hover();
+                if *hover != latest {
+                    hover.set(latest);
+                }
+            });
+            move || drop(interval)
+        });
+    }
+
+    let Some(hover) = &*hover else {
+        return html! {};
+    };
+    let Some(entity) = state.entities.iter().find(|e| e.id == hover.id) else {
+        return html! {};
+    };
+
+    html! {
+        <div
+            class="hover-tooltip"
+            style={format!("left: {:.0}px; top: {:.0}px", hover.x + 14.0, hover.y + 14.0)}
+        >
+            <span class="hover-tooltip-type">{get_entity_display_name(&entity.entity_type)}</span>
+            if let Some(name) = &entity.name {
+                <span class="hover-tooltip-name">{name}</span>
+            }
+            if let Some(storey) = &entity.storey {
+                <span class="hover-tooltip-storey">{storey}</span>
+            }
+        </div>
+    }
+}
+
 /// Viewport component
 #[function_component]
 pub fn Viewport() -> Html {
-    let _state = use_context::<ViewerStateContext>().expect("ViewerStateContext not found");
+    let state = use_context::<ViewerStateContext>().expect("ViewerStateContext not found");
     let bevy_state = use_state(|| BevyState::NotLoaded);
     let error_msg = use_state(String::new);
 
-    // Load Bevy on mount
+    // Compile Bevy while a model parses, so it is ready when geometry arrives
+    use_effect_with(state.loading, |loading| {
+        if *loading && !has_embedded_renderer() && !is_bevy_running() {
+            preload_bevy_viewer();
+        }
+    });
+
+    // Start Bevy once the model has geometry; property-only sessions never pay for it
     {
         let bevy_state = bevy_state.clone();
         let error_msg = error_msg.clone();
 
-        use_effect_with((), move |_| {
-            // Check if already loaded
-            if is_bevy_loaded() {
-                bevy_state.set(BevyState::Loaded);
-                return;
-            }
+        use_effect_with(
+            (*bevy_state, state.has_geometry),
+            move |(current, has_geometry)| {
+                if *current != BevyState::NotLoaded {
+                    return;
+                }
+                // Already running from an earlier mount
+                if is_bevy_running() {
+                    bevy_state.set(BevyState::Loaded);
+                    return;
+                }
+                if !*has_geometry {
+                    return;
+                }
 
-            bevy_state.set(BevyState::Loading);
-            log("[Yew] Loading Bevy viewer...");
+                // Unified build: Bevy is linked in, nothing to fetch
+                if has_embedded_renderer() {
+                    log("[Yew] Starting embedded Bevy viewer");
+                    start_embedded_renderer("#bevy-canvas");
+                    bevy_state.set(BevyState::Loaded);
+                    return;
+                }
 
-            spawn_local(async move {
-                match load_bevy_viewer().await {
-                    Ok(_) => {
-                        log("[Yew] Bevy viewer loaded successfully");
-                        bevy_state.set(BevyState::Loaded);
-                    }
-                    Err(e) => {
-                        // Bevy uses exceptions for control flow - check if this is one
-                        let error_str = format!("{:?}", e);
-                        if error_str.contains("Using exceptions for control flow") {
-                            // This is normal Bevy behavior, not an error
-                            log("[Yew] Bevy event loop started");
+                bevy_state.set(BevyState::Loading);
+                log("[Yew] Loading Bevy viewer...");
+
+                spawn_local(async move {
+                    match load_bevy_viewer().await {
+                        Ok(_) => {
+                            log("[Yew] Bevy viewer loaded successfully");
                             bevy_state.set(BevyState::Loaded);
-                        } else {
-                            log_error(&format!("[Yew] Failed to load Bevy: {}", error_str));
-                            error_msg.set(error_str);
-                            bevy_state.set(BevyState::Error);
+                        }
+                        Err(e) => {
+                            // Bevy uses exceptions for control flow - check if this is one
+                            let error_str = format!("{:?}", e);
+                            if error_str.contains("Using exceptions for control flow") {
+                                // This is normal Bevy behavior, not an error
+                                log("[Yew] Bevy event loop started");
+                                bevy_state.set(BevyState::Loaded);
+                            } else {
+                                log_error(&format!("[Yew] Failed to load Bevy: {}", error_str));
+                                error_msg.set(error_str);
+                                bevy_state.set(BevyState::Error);
+                            }
                         }
                     }
-                }
-            });
-        });
+                });
+            },
+        );
     }
 
     html! {
@@ -66,8 +416,54 @@ pub fn Viewport() -> Html {
             <canvas
                 id="bevy-canvas"
                 class="viewport-canvas"
+                oncontextmenu={Callback::from(|e: MouseEvent| e.prevent_default())}
             />
 
+            if *bevy_state == BevyState::Loaded && state.split_view.enabled {
+                <div class="viewport-split-divider" />
+            }
+
+            if *bevy_state == BevyState::Loaded && state.overlay.scale_bar {
+                <ScaleBar />
+            }
+
+            if *bevy_state == BevyState::Loaded && state.overlay.stats {
+                <DebugOverlay />
+            }
+
+            if *bevy_state == BevyState::Loaded && state.overlay.grid_axes {
+                <GridLabels />
+            }
+
+            if *bevy_state == BevyState::Loaded && state.spaces.visible {
+                <SpaceLabels />
+            }
+
+            if *bevy_state == BevyState::Loaded && !state.measurements.is_empty() {
+                <MeasureLabels />
+            }
+
+            if *bevy_state == BevyState::Loaded && !state.annotations.is_empty() {
+                <AnnotationLabels />
+            }
+
+            if *bevy_state == BevyState::Loaded && state.pending_annotation.is_some() {
+                <AnnotationEditor />
+            }
+
+            if *bevy_state == BevyState::Loaded {
+                <HoverTooltip />
+                <ContextMenu />
+            }
+
+            if *bevy_state == BevyState::Loaded && state.color_mode != ColorMode::Model {
+                <ColorLegend />
+            }
+
+            if *bevy_state == BevyState::Loaded && state.active_tool == Tool::Section {
+                <SectionPanel />
+            }
+
             // Loading overlay
             if *bevy_state == BevyState::Loading {
                 <div class="viewport-overlay loading-overlay">
@@ -101,12 +497,22 @@ pub fn Viewport() -> Html {
                 </div>
             }
 
-            // Not loaded overlay (before effect runs)
+            // Not loaded overlay: the renderer starts with the first model geometry
             if *bevy_state == BevyState::NotLoaded {
                 <div class="viewport-overlay">
-                    <div class="loading-content">
-                        <span class="loading-text">{"Initializing..."}</span>
-                    </div>
+                    if !state.loading && state.entities.is_empty() {
+                        <StartScreen />
+                    } else {
+                        <div class="loading-content">
+                            <span class="loading-text">
+                                { if state.loading {
+                                    "Preparing 3D viewer..."
+                                } else {
+                                    "This model has no geometry"
+                                } }
+                            </span>
+                        </div>
+                    }
                 </div>
             }
         </div>
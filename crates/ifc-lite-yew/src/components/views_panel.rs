@@ -0,0 +1,189 @@
+//! Views panel - named viewpoints with camera, section, visibility and selection
+
+use crate::bridge;
+use crate::state::{NamedView, ViewerAction, ViewerStateContext};
+use crate::utils::{download_file, file_stem};
+use crate::viewpoint;
+use gloo_file::callbacks::FileReader;
+use ifc_lite_core::BcfViewpoint;
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+
+/// Saved viewpoints of the loaded model, applied with one click
+#[function_component]
+pub fn ViewsPanel() -> Html {
+    let state = use_context::<ViewerStateContext>().expect("ViewerStateContext not found");
+    let name = use_state(String::new);
+    let import_input_ref = use_node_ref();
+    let import_reader = use_state(|| None::<FileReader>);
+
+    if state.entities.is_empty() {
+        return html! {};
+    }
+
+    let on_save = {
+        let state = state.clone();
+        let name = name.clone();
+        Callback::from(move |_| {
+            if !name.trim().is_empty() {
+                let view =
+                    NamedView::capture((*name).clone(), &state).with_camera(bridge::load_camera());
+                state.dispatch(ViewerAction::SaveView(view));
+                name.set(String::new());
+            }
+        })
+    };
+    let on_keydown = {
+        let on_save = on_save.clone();
+        Callback::from(move |e: KeyboardEvent| {
+            if e.key() == "Enter" {
+                on_save.emit(());
+            }
+        })
+    };
+
+    // Import a BCF viewpoint (.bcfv) as a new view named after the file
+    let on_import = {
+        let state = state.clone();
+        let import_reader = import_reader.clone();
+        Callback::from(move |e: Event| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            let Some(file) = input.files().and_then(|files| files.get(0)) else {
+                return;
+            };
+            let view_name = file
+                .name()
+                .trim_end_matches(".bcfv")
+                .trim_end_matches(".xml")
+                .to_string();
+            input.set_value("");
+
+            let state = state.clone();
+            let reader =
+                gloo_file::callbacks::read_as_text(&gloo_file::File::from(file), move |result| {
+                    let parsed = result
+                        .map_err(|e| e.to_string())
+                        .and_then(|xml| BcfViewpoint::from_xml(&xml).map_err(|e| e.to_string()));
+                    match parsed {
+                        Ok(bcf) => {
+                            let bounds = bridge::load_scene_bounds();
+                            let view = viewpoint::from_bcf(
+                                view_name,
+                                &bcf,
+                                &state.entities,
+                                bounds.as_ref(),
+                            );
+                            state.dispatch(ViewerAction::SaveView(view));
+                        }
+                        Err(e) => bridge::log_error(&format!("Failed to import viewpoint: {}", e)),
+                    }
+                });
+            import_reader.set(Some(reader));
+        })
+    };
+
+    html! {
+        <div class="views-panel">
+            <div class="section-header">{"Viewpoints"}</div>
+            <div class="views-save">
+                <input
+                    type="text"
+                    class="search-input"
+                    placeholder="Save current view as…"
+                    value={(*name).clone()}
+                    oninput={
+                        let name = name.clone();
+                        Callback::from(move |e: InputEvent| {
+                            let input: HtmlInputElement = e.target_unchecked_into();
+                            name.set(input.value());
+                        })
+                    }
+                    onkeydown={on_keydown}
+                />
+                <button
+                    class="copy-btn"
+                    onclick={on_save.reform(|_: MouseEvent| ())}
+                    title="Save camera, section, visibility and selection as a viewpoint"
+                >
+                    {"💾"}
+                </button>
+                <input
+                    ref={import_input_ref.clone()}
+                    type="file"
+                    accept=".bcfv,.xml"
+                    style="display: none"
+                    onchange={on_import}
+                />
+                <button
+                    class="copy-btn"
+                    onclick={Callback::from(move |_: MouseEvent| {
+                        if let Some(input) = import_input_ref.cast::<HtmlInputElement>() {
+                            input.click();
+                        }
+                    })}
+                    title="Import a BCF viewpoint (.bcfv)"
+                >
+                    {"📥"}
+                </button>
+            </div>
+            <div class="views-list">
+                { for state.views.iter().map(|view| {
+                    let is_current = view.is_current(&state);
+                    let apply = {
+                        let state = state.clone();
+                        let name = view.name.clone();
+                        let camera = view.camera.clone();
+                        Callback::from(move |_| {
+                            if let Some(camera) = &camera {
+                                bridge::save_camera_view(camera);
+                            }
+                            state.dispatch(ViewerAction::ApplyView(name.clone()));
+                        })
+                    };
+                    let export = {
+                        let state = state.clone();
+                        let view = view.clone();
+                        Callback::from(move |e: MouseEvent| {
+                            e.stop_propagation();
+                            let bounds = bridge::load_scene_bounds();
+                            let bcf = viewpoint::to_bcf(
+                                &view,
+                                &state.entities,
+                                bounds.as_ref(),
+                                viewpoint::new_guid(),
+                            );
+                            download_file(
+                                &format!("{}.bcfv", file_stem(&view.name)),
+                                "application/xml",
+                                bcf.to_xml().as_bytes(),
+                            );
+                        })
+                    };
+                    let delete = {
+                        let state = state.clone();
+                        let name = view.name.clone();
+                        Callback::from(move |e: MouseEvent| {
+                            e.stop_propagation();
+                            state.dispatch(ViewerAction::DeleteView(name.clone()));
+                        })
+                    };
+                    html! {
+                        <div
+                            class={classes!("view-row", is_current.then_some("active"))}
+                            onclick={apply}
+                            title="Apply viewpoint"
+                        >
+                            <span class="view-name">{&view.name}</span>
+                            <button class="copy-btn" onclick={export} title="Export as BCF viewpoint">
+                                {"📤"}
+                            </button>
+                            <button class="copy-btn" onclick={delete} title="Delete viewpoint">
+                                {"✕"}
+                            </button>
+                        </div>
+                    }
+                })}
+            </div>
+        </div>
+    }
+}
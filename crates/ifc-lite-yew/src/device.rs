@@ -0,0 +1,370 @@
+//! Device profiling - pick defaults that suit the hardware
+//!
+//! At startup the viewer looks at what the browser reveals about the device:
+//! memory, CPU cores, the GPU behind WebGL and whether the primary pointer is
+//! a finger. The result is a coarse [`DeviceTier`] that selects streaming
+//! batch sizes, tessellation quality and renderer defaults (anti-aliasing,
+//! shadows, level of detail), so phones stay responsive and workstations get
+//! full quality.
+//! Browsers hide some of these values; unknown ones do not count against
+//! the device.
+
+use js_sys::{Function, Reflect};
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::{JsCast, JsValue};
+
+/// Renderers without a real GPU behind them
+const SOFTWARE_GPUS: &[&str] = &[
+    "swiftshader",
+    "llvmpipe",
+    "softpipe",
+    "software",
+    "microsoft basic render",
+];
+
+/// GPU names that indicate discrete or high-end integrated graphics
+const HIGH_END_GPUS: &[&str] = &[
+    "nvidia",
+    "geforce",
+    "rtx",
+    "quadro",
+    "radeon rx",
+    "radeon pro",
+    "apple m",
+    "arc(tm) a",
+];
+
+/// Quality preset of an optional rendering effect
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphicsQuality {
+    #[default]
+    Off,
+    Low,
+    High,
+}
+
+impl GraphicsQuality {
+    pub const ALL: [GraphicsQuality; 3] = [
+        GraphicsQuality::Off,
+        GraphicsQuality::Low,
+        GraphicsQuality::High,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            GraphicsQuality::Off => "Off",
+            GraphicsQuality::Low => "Low",
+            GraphicsQuality::High => "High",
+        }
+    }
+
+    /// Value of a `<select>` option
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GraphicsQuality::Off => "off",
+            GraphicsQuality::Low => "low",
+            GraphicsQuality::High => "high",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|q| q.as_str() == value)
+    }
+}
+
+/// Coarse performance class of the device
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeviceTier {
+    Low,
+    Medium,
+    High,
+}
+
+impl DeviceTier {
+    pub fn label(&self) -> &'static str {
+        match self {
+            DeviceTier::Low => "Low",
+            DeviceTier::Medium => "Medium",
+            DeviceTier::High => "High",
+        }
+    }
+
+    /// Out-of-the-box settings for this tier
+    pub fn defaults(&self) -> DeviceDefaults {
+        match self {
+            DeviceTier::Low => DeviceDefaults {
+                geometry_batch_size: 200,
+                deflection: 0.05,
+                chunk_size: 32.0,
+                lod_error_px: 4.0,
+                shadows: GraphicsQuality::Off,
+                msaa_samples: 1,
+                smooth_normals: false,
+                crease_angle: 30.0,
+                optimize_meshes: false,
+            },
+            DeviceTier::Medium => DeviceDefaults::default(),
+            DeviceTier::High => DeviceDefaults {
+                geometry_batch_size: 1000,
+                deflection: 0.005,
+                chunk_size: 16.0,
+                lod_error_px: 1.0,
+                shadows: GraphicsQuality::High,
+                msaa_samples: 4,
+                smooth_normals: true,
+                crease_angle: 30.0,
+                optimize_meshes: true,
+            },
+        }
+    }
+}
+
+/// Settings derived from a [`DeviceTier`]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DeviceDefaults {
+    /// Meshes per geometry chunk streamed to Bevy while parsing
+    pub geometry_batch_size: usize,
+    /// Curve tessellation tolerance, in metres (see
+    /// [`ifc_lite_geometry::GeometryOptions::deflection`])
+    #[serde(default = "default_deflection")]
+    pub deflection: f64,
+    /// Edge length of Bevy's spatial batching chunks, in metres
+    pub chunk_size: f32,
+    /// Largest on-screen LOD decimation error, in pixels
+    pub lod_error_px: f32,
+    /// Shadows of the key light
+    pub shadows: GraphicsQuality,
+    /// MSAA sample count (1 = off)
+    pub msaa_samples: u32,
+    /// Smooth-shade meshes without normals (costs CPU while batching)
+    pub smooth_normals: bool,
+    /// Edges sharper than this stay hard when smoothing, in degrees
+    pub crease_angle: f32,
+    /// Weld vertices and reorder indices for the GPU (costs CPU while batching)
+    #[serde(default)]
+    pub optimize_meshes: bool,
+}
+
+fn default_deflection() -> f64 {
+    ifc_lite_geometry::DEFAULT_DEFLECTION
+}
+
+impl Default for DeviceDefaults {
+    fn default() -> Self {
+        Self {
+            geometry_batch_size: 500,
+            deflection: default_deflection(),
+            chunk_size: 16.0,
+            lod_error_px: 2.0,
+            shadows: GraphicsQuality::Off,
+            msaa_samples: 4,
+            smooth_normals: true,
+            crease_angle: 30.0,
+            optimize_meshes: true,
+        }
+    }
+}
+
+/// What the browser reports about the device
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DeviceProfile {
+    pub tier: DeviceTier,
+    /// `navigator.deviceMemory`, in GiB (Chromium only, capped at 8)
+    pub memory_gib: Option<f64>,
+    /// `navigator.hardwareConcurrency`
+    pub cores: Option<u32>,
+    /// Unmasked WebGL renderer string
+    pub gpu: Option<String>,
+    /// The primary pointer is coarse (finger rather than mouse)
+    pub touch: bool,
+    /// `navigator.gpu` is present
+    pub webgpu: bool,
+}
+
+impl DeviceProfile {
+    /// Profile the device the page runs on
+    pub fn detect() -> Self {
+        let navigator = Reflect::get(&js_sys::global(), &"navigator".into()).ok();
+        let number = |name: &str| {
+            navigator
+                .as_ref()
+                .and_then(|n| Reflect::get(n, &name.into()).ok())
+                .and_then(|v| v.as_f64())
+                .filter(|v| *v > 0.0)
+        };
+        let memory_gib = number("deviceMemory");
+        let cores = number("hardwareConcurrency").map(|c| c as u32);
+        let webgpu = navigator
+            .as_ref()
+            .and_then(|n| Reflect::get(n, &"gpu".into()).ok())
+            .is_some_and(|gpu| !gpu.is_undefined() && !gpu.is_null());
+        let gpu = webgl_renderer();
+        let touch = coarse_pointer();
+
+        Self {
+            tier: classify(memory_gib, cores, gpu.as_deref(), touch),
+            memory_gib,
+            cores,
+            gpu,
+            touch,
+            webgpu,
+        }
+    }
+
+    pub fn defaults(&self) -> DeviceDefaults {
+        self.tier.defaults()
+    }
+
+    /// Best shadow preset the browser can render: without WebGPU the viewer
+    /// falls back to WebGL2, which has a single shadow cascade
+    pub fn max_shadow_quality(&self) -> GraphicsQuality {
+        if self.webgpu {
+            GraphicsQuality::High
+        } else {
+            GraphicsQuality::Low
+        }
+    }
+}
+
+/// Whether the browser renderer can draw ambient occlusion
+///
+/// Bevy's SSAO runs on neither WebGL2 nor WebGPU yet; the native viewers
+/// have it.
+pub const AMBIENT_OCCLUSION_SUPPORTED: bool = false;
+
+/// Tier from the reported hardware; unknown values are not held against it
+pub fn classify(
+    memory_gib: Option<f64>,
+    cores: Option<u32>,
+    gpu: Option<&str>,
+    touch: bool,
+) -> DeviceTier {
+    let gpu = gpu.map(str::to_ascii_lowercase);
+    let gpu_matches = |names: &[&str]| {
+        gpu.as_deref()
+            .is_some_and(|gpu| names.iter().any(|name| gpu.contains(name)))
+    };
+
+    let low_memory = memory_gib.is_some_and(|m| m <= 2.0);
+    let few_cores = cores.is_some_and(|c| c <= 2);
+    let small_touch_device =
+        touch && (memory_gib.is_some_and(|m| m <= 4.0) || cores.is_some_and(|c| c <= 4));
+    if gpu_matches(SOFTWARE_GPUS) || low_memory || few_cores || small_touch_device {
+        return DeviceTier::Low;
+    }
+
+    let plenty_memory = memory_gib.is_none_or(|m| m >= 8.0);
+    let many_cores = cores.is_some_and(|c| c >= 8);
+    if gpu_matches(HIGH_END_GPUS) && plenty_memory && many_cores && !touch {
+        DeviceTier::High
+    } else {
+        DeviceTier::Medium
+    }
+}
+
+/// Renderer string of a throwaway WebGL context
+///
+/// Needs a document, so it is `None` inside workers.
+fn webgl_renderer() -> Option<String> {
+    let document = web_sys::window()?.document()?;
+    let canvas = document.create_element("canvas").ok()?;
+    let context = call(&canvas, "getContext", &["webgl2".into()])
+        .filter(|c| !c.is_null())
+        .or_else(|| call(&canvas, "getContext", &["webgl".into()]))
+        .filter(|c| !c.is_null())?;
+
+    // The unmasked name needs the debug extension; fall back to RENDERER
+    let parameter = call(
+        &context,
+        "getExtension",
+        &["WEBGL_debug_renderer_info".into()],
+    )
+    .filter(|ext| !ext.is_null())
+    .and_then(|ext| Reflect::get(&ext, &"UNMASKED_RENDERER_WEBGL".into()).ok())
+    .or_else(|| Reflect::get(&context, &"RENDERER".into()).ok())?;
+    let renderer = call(&context, "getParameter", &[parameter])?.as_string();
+
+    // Give the context back right away; browsers limit how many are live
+    if let Some(ext) = call(&context, "getExtension", &["WEBGL_lose_context".into()]) {
+        if !ext.is_null() {
+            let _ = call(&ext, "loseContext", &[]);
+        }
+    }
+    renderer
+}
+
+/// Whether the primary pointer is a finger
+fn coarse_pointer() -> bool {
+    web_sys::window()
+        .and_then(|window| call(&window, "matchMedia", &["(pointer: coarse)".into()]))
+        .and_then(|query| Reflect::get(&query, &"matches".into()).ok())
+        .and_then(|matches| matches.as_bool())
+        .unwrap_or(false)
+}
+
+/// Call a method by name, `None` if it is missing or throws
+fn call(target: &JsValue, method: &str, args: &[JsValue]) -> Option<JsValue> {
+    let function: Function = Reflect::get(target, &method.into()).ok()?.dyn_into().ok()?;
+    let args: js_sys::Array = args.iter().collect();
+    function.apply(target, &args).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_tier_boundaries() {
+        const NVIDIA: Option<&str> = Some("NVIDIA GeForce RTX 4070");
+        const INTEL: Option<&str> = Some("Intel(R) UHD Graphics 630");
+        const APPLE: Option<&str> = Some("Apple M2 Pro");
+        const SWIFTSHADER: Option<&str> = Some("Google SwiftShader");
+        const LLVMPIPE: Option<&str> = Some("llvmpipe (LLVM 15.0.7)");
+        // (memory GiB, cores, GPU, touch, expected)
+        let cases = [
+            (None, None, None, false, DeviceTier::Medium),
+            (Some(8.0), Some(16), SWIFTSHADER, false, DeviceTier::Low),
+            (Some(8.0), Some(16), LLVMPIPE, false, DeviceTier::Low),
+            // Memory: 2 GiB and less is low
+            (Some(2.0), Some(8), INTEL, false, DeviceTier::Low),
+            (Some(4.0), Some(8), INTEL, false, DeviceTier::Medium),
+            // Cores: 2 and fewer is low
+            (Some(8.0), Some(2), INTEL, false, DeviceTier::Low),
+            (Some(8.0), Some(3), INTEL, false, DeviceTier::Medium),
+            // Touch devices with up to 4 GiB or 4 cores are low
+            (Some(4.0), Some(8), None, true, DeviceTier::Low),
+            (Some(8.0), Some(4), None, true, DeviceTier::Low),
+            (Some(8.0), Some(6), None, true, DeviceTier::Medium),
+            // High needs a fast GPU, 8 GiB, 8 cores and a mouse
+            (Some(8.0), Some(8), NVIDIA, false, DeviceTier::High),
+            (None, Some(8), NVIDIA, false, DeviceTier::High),
+            (Some(8.0), Some(8), APPLE, false, DeviceTier::High),
+            (Some(4.0), Some(8), NVIDIA, false, DeviceTier::Medium),
+            (Some(8.0), Some(7), NVIDIA, false, DeviceTier::Medium),
+            (Some(8.0), None, NVIDIA, false, DeviceTier::Medium),
+            (Some(8.0), Some(8), INTEL, false, DeviceTier::Medium),
+            (Some(8.0), Some(8), NVIDIA, true, DeviceTier::Medium),
+        ];
+        for (memory, cores, gpu, touch, expected) in cases {
+            assert_eq!(
+                classify(memory, cores, gpu, touch),
+                expected,
+                "{:?} GiB, {:?} cores, {:?}, touch {}",
+                memory,
+                cores,
+                gpu,
+                touch
+            );
+        }
+    }
+
+    #[test]
+    fn test_tessellation_follows_tier() {
+        let low = DeviceTier::Low.defaults().deflection;
+        let medium = DeviceTier::Medium.defaults().deflection;
+        let high = DeviceTier::High.defaults().deflection;
+        assert_eq!(medium, ifc_lite_geometry::DEFAULT_DEFLECTION);
+        assert!(low > medium && medium > high);
+    }
+}
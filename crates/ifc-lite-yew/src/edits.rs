@@ -0,0 +1,34 @@
+//! Model edits - patched IFC and JSON diff export
+//!
+//! Property edits are tracked in [`crate::state::ViewerState::changes`].
+//! Writing them back needs the original file text, so the content of the
+//! loaded model is kept here until the next load.
+
+use ifc_lite_core::ChangeSet;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+thread_local! {
+    static SOURCE: RefCell<Option<Rc<str>>> = const { RefCell::new(None) };
+}
+
+/// Remember the content of the model being loaded
+pub fn set_source(content: &str) {
+    SOURCE.with(|s| *s.borrow_mut() = Some(Rc::from(content)));
+}
+
+/// Content of the loaded model, as read from its file
+pub fn source() -> Option<Rc<str>> {
+    SOURCE.with(|s| s.borrow().clone())
+}
+
+/// The loaded IFC file with `changes` applied
+pub fn export_ifc(changes: &ChangeSet) -> Result<String, String> {
+    let source = source().ok_or_else(|| "No model loaded".to_string())?;
+    changes.apply_to(&source).map_err(|e| e.to_string())
+}
+
+/// `changes` as a JSON diff
+pub fn export_json(changes: &ChangeSet) -> Result<String, String> {
+    serde_json::to_string_pretty(changes).map_err(|e| e.to_string())
+}
@@ -3,9 +3,18 @@
 //! This crate provides the web UI for the IFC-Lite viewer using Yew framework.
 
 pub mod bridge;
+pub mod cache;
 pub mod components;
+pub mod device;
+pub mod edits;
+pub mod memory;
+pub mod palette;
+pub mod samples;
 pub mod state;
+pub mod thumbnail;
 pub mod utils;
+pub mod viewpoint;
+pub mod worker;
 
 // Re-exports
 pub use bridge::*;
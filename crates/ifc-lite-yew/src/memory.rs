@@ -0,0 +1,263 @@
+//! Memory guard - keep large loads from crashing the tab
+//!
+//! Before tessellation the pipeline estimates how much memory a file needs
+//! and compares it with what the browser can give a wasm instance. Loads
+//! that do not fit are degraded step by step: detail element types are
+//! skipped, then elements are reduced to bounding boxes. A watchdog counts
+//! the geometry actually produced and drops to bounding boxes mid-load if
+//! the estimate was too optimistic.
+
+use crate::utils::format_file_size;
+use ifc_lite_geometry::Mesh;
+
+/// wasm32 linear memory tops out at 4 GiB; browsers often fail well before
+const WASM_MEMORY_LIMIT: u64 = 2 * 1024 * 1024 * 1024;
+/// Share of the device memory (`navigator.deviceMemory`) one tab may use
+const DEVICE_MEMORY_SHARE: f64 = 0.25;
+/// Tessellated bytes per byte of IFC text (measured 0.6-0.9, with headroom)
+const GEOMETRY_PER_FILE_BYTE: f64 = 1.5;
+/// Geometry is held twice: as batches and as the final binary for Bevy
+const GEOMETRY_COPIES: f64 = 2.0;
+/// Content string plus entity index and decoder caches, per file byte
+const PARSE_PER_FILE_BYTE: f64 = 1.5;
+/// Entity metadata and hierarchy nodes, per entity
+const BYTES_PER_ENTITY: u64 = 48;
+
+/// Element types skipped by [`LoadQuality::Reduced`]: many triangles, little
+/// value in an overview
+const DETAIL_TYPES: &[&str] = &[
+    "IFCFURNISHINGELEMENT",
+    "IFCFURNITURE",
+    "IFCSYSTEMFURNITUREELEMENT",
+    "IFCFLOWSEGMENT",
+    "IFCFLOWFITTING",
+    "IFCFLOWTERMINAL",
+    "IFCPIPESEGMENT",
+    "IFCPIPEFITTING",
+    "IFCDUCTSEGMENT",
+    "IFCDUCTFITTING",
+    "IFCCABLESEGMENT",
+    "IFCCABLEFITTING",
+    "IFCCABLECARRIERSEGMENT",
+    "IFCCABLECARRIERFITTING",
+    "IFCFASTENER",
+    "IFCMECHANICALFASTENER",
+    "IFCDISCRETEACCESSORY",
+    "IFCREINFORCINGBAR",
+    "IFCREINFORCINGMESH",
+    "IFCTENDON",
+];
+
+/// How much geometry a load produces
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LoadQuality {
+    /// Everything at full detail
+    Full,
+    /// Detail element types are skipped
+    Reduced,
+    /// Detail types skipped, everything else drawn as bounding boxes
+    BoundingBoxes,
+}
+
+impl LoadQuality {
+    /// Whether elements of this type (upper-case STEP name) get geometry
+    pub fn includes_type(self, type_name: &str) -> bool {
+        self == LoadQuality::Full
+            || !DETAIL_TYPES
+                .iter()
+                .any(|t| t.eq_ignore_ascii_case(type_name))
+    }
+
+    fn description(self) -> &'static str {
+        match self {
+            LoadQuality::Full => "full detail",
+            LoadQuality::Reduced => "reduced detail (furniture, MEP and rebar skipped)",
+            LoadQuality::BoundingBoxes => "bounding box preview",
+        }
+    }
+}
+
+/// Outcome of the pre-load memory check
+#[derive(Clone, Debug)]
+pub struct LoadPlan {
+    pub quality: LoadQuality,
+    pub estimated_bytes: u64,
+    pub budget_bytes: u64,
+}
+
+impl LoadPlan {
+    /// Check a file against the memory budget
+    pub fn new(file_size: usize, entity_count: usize, budget_bytes: u64) -> Self {
+        let estimated_bytes = estimate_bytes(file_size, entity_count);
+        let quality = if estimated_bytes <= budget_bytes {
+            LoadQuality::Full
+        } else if estimated_bytes <= budget_bytes.saturating_mul(2) {
+            LoadQuality::Reduced
+        } else {
+            LoadQuality::BoundingBoxes
+        };
+        Self {
+            quality,
+            estimated_bytes,
+            budget_bytes,
+        }
+    }
+
+    /// Bytes of tessellated geometry the budget leaves room for
+    pub fn geometry_budget(&self, file_size: usize) -> u64 {
+        let parse = (file_size as f64 * PARSE_PER_FILE_BYTE) as u64;
+        (self.budget_bytes.saturating_sub(parse) as f64 / GEOMETRY_COPIES) as u64
+    }
+
+    /// Message for the user when the load is degraded
+    pub fn warning(&self) -> Option<String> {
+        (self.quality != LoadQuality::Full).then(|| {
+            format!(
+                "Large model: needs about {} but only {} is available, showing {}",
+                format_file_size(self.estimated_bytes as usize),
+                format_file_size(self.budget_bytes as usize),
+                self.quality.description()
+            )
+        })
+    }
+}
+
+/// Rough peak memory of loading a file at full detail
+pub fn estimate_bytes(file_size: usize, entity_count: usize) -> u64 {
+    let file_size = file_size as f64;
+    let geometry = file_size * GEOMETRY_PER_FILE_BYTE * GEOMETRY_COPIES;
+    (file_size * PARSE_PER_FILE_BYTE + geometry) as u64 + entity_count as u64 * BYTES_PER_ENTITY
+}
+
+/// Memory available to the pipeline
+///
+/// Uses `navigator.deviceMemory` where the browser exposes it (also inside
+/// workers), capped by the wasm address space.
+pub fn memory_budget() -> u64 {
+    let global = js_sys::global();
+    let device_memory = js_sys::Reflect::get(&global, &"navigator".into())
+        .and_then(|navigator| js_sys::Reflect::get(&navigator, &"deviceMemory".into()))
+        .ok()
+        .and_then(|value| value.as_f64());
+
+    match device_memory {
+        Some(gib) if gib > 0.0 => {
+            let share = gib * DEVICE_MEMORY_SHARE * (1024.0 * 1024.0 * 1024.0);
+            (share as u64).min(WASM_MEMORY_LIMIT)
+        }
+        _ => WASM_MEMORY_LIMIT,
+    }
+}
+
+/// Current linear memory of the UI's wasm instance
+pub fn heap_bytes() -> Option<u64> {
+    use wasm_bindgen::JsCast;
+    let memory = wasm_bindgen::memory()
+        .dyn_into::<js_sys::WebAssembly::Memory>()
+        .ok()?;
+    let buffer = memory.buffer().dyn_into::<js_sys::ArrayBuffer>().ok()?;
+    Some(buffer.byte_length() as u64)
+}
+
+/// Counts retained geometry and trips once it passes the budget
+#[derive(Debug)]
+pub struct GeometryWatchdog {
+    retained: u64,
+    limit: u64,
+}
+
+impl GeometryWatchdog {
+    pub fn new(limit: u64) -> Self {
+        Self { retained: 0, limit }
+    }
+
+    /// Record a mesh; true exactly once, when the limit is first exceeded
+    pub fn record(&mut self, mesh: &Mesh) -> bool {
+        let was_within = self.retained <= self.limit;
+        self.retained +=
+            ((mesh.positions.len() + mesh.normals.len() + mesh.indices.len()) * 4) as u64;
+        was_within && self.retained > self.limit
+    }
+}
+
+/// Axis-aligned box around a mesh, 12 triangles with flat normals
+pub fn bounding_box_mesh(mesh: &Mesh) -> Mesh {
+    let (min, max) = mesh.bounds();
+    let corner = |x: bool, y: bool, z: bool| {
+        [
+            if x { max.x } else { min.x },
+            if y { max.y } else { min.y },
+            if z { max.z } else { min.z },
+        ]
+    };
+
+    // Each face: normal and its corners in counter-clockwise order
+    let faces: [([f32; 3], [[f32; 3]; 4]); 6] = [
+        (
+            [-1.0, 0.0, 0.0],
+            [
+                corner(false, false, false),
+                corner(false, false, true),
+                corner(false, true, true),
+                corner(false, true, false),
+            ],
+        ),
+        (
+            [1.0, 0.0, 0.0],
+            [
+                corner(true, false, false),
+                corner(true, true, false),
+                corner(true, true, true),
+                corner(true, false, true),
+            ],
+        ),
+        (
+            [0.0, -1.0, 0.0],
+            [
+                corner(false, false, false),
+                corner(true, false, false),
+                corner(true, false, true),
+                corner(false, false, true),
+            ],
+        ),
+        (
+            [0.0, 1.0, 0.0],
+            [
+                corner(false, true, false),
+                corner(false, true, true),
+                corner(true, true, true),
+                corner(true, true, false),
+            ],
+        ),
+        (
+            [0.0, 0.0, -1.0],
+            [
+                corner(false, false, false),
+                corner(false, true, false),
+                corner(true, true, false),
+                corner(true, false, false),
+            ],
+        ),
+        (
+            [0.0, 0.0, 1.0],
+            [
+                corner(false, false, true),
+                corner(true, false, true),
+                corner(true, true, true),
+                corner(false, true, true),
+            ],
+        ),
+    ];
+
+    let mut result = Mesh::with_capacity(24, 36);
+    for (normal, corners) in faces {
+        let base = result.vertex_count() as u32;
+        for position in corners {
+            result.positions.extend_from_slice(&position);
+            result.normals.extend_from_slice(&normal);
+        }
+        result.add_triangle(base, base + 1, base + 2);
+        result.add_triangle(base, base + 2, base + 3);
+    }
+    result
+}
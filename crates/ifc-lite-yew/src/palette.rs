@@ -0,0 +1,215 @@
+//! Palette - viewport colors that encode model data
+//!
+//! Entities are grouped by what the [`ColorMode`] encodes (their type
+//! object, material, system or the value of a property) and each group gets a color
+//! of its own. Groups are ordered by label and walk the hue circle in
+//! golden-angle steps, so neighbouring groups stay apart and a model gets
+//! the same colors every time it opens. Entities without a value share a
+//! trailing grey group. The diff mode uses fixed colors instead: added
+//! green, modified yellow, unchanged grey. Bevy applies the groups as vertex
+//! color overrides.
+
+use crate::bridge::{PaletteData, PaletteGroupData};
+use crate::state::{ColorMode, EntityInfo, SystemInfo, ViewerStateContext};
+use ifc_lite_core::{ChangeSet, DiffStatus, ModelDiff};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::rc::Rc;
+use yew::prelude::*;
+
+/// Hue step between consecutive groups, in degrees
+const GOLDEN_ANGLE: f32 = 137.507_77;
+
+/// Color of entities the color mode has no value for
+const NO_VALUE_COLOR: [f32; 3] = [0.6, 0.6, 0.6];
+const NO_VALUE_LABEL: &str = "No value";
+
+/// Colors of the diff mode, also used for removed elements in its legend
+pub const ADDED_COLOR: [f32; 3] = [0.2, 0.75, 0.3];
+pub const REMOVED_COLOR: [f32; 3] = [0.85, 0.2, 0.2];
+pub const MODIFIED_COLOR: [f32; 3] = [0.95, 0.8, 0.15];
+
+/// Palette of the current color mode, recomputed when the mode, the model,
+/// its edits or the compared version change
+#[hook]
+pub fn use_palette(state: &ViewerStateContext) -> Rc<PaletteData> {
+    let deps = (
+        state.color_mode.clone(),
+        state.content_hash.clone(),
+        state.entities.len(),
+        state.changes.clone(),
+        state.diff.clone(),
+        state.systems.clone(),
+    );
+    let state = state.clone();
+    use_memo(deps, move |(mode, _, _, changes, diff, systems)| {
+        palette(&state.entities, mode, changes, diff.as_deref(), systems)
+    })
+}
+
+/// Color groups for a color mode, with unsaved edits laid over property
+/// values; none for the model colors
+pub fn palette(
+    entities: &[EntityInfo],
+    mode: &ColorMode,
+    changes: &ChangeSet,
+    diff: Option<&ModelDiff>,
+    systems: &[SystemInfo],
+) -> PaletteData {
+    match mode {
+        ColorMode::Model => return PaletteData::default(),
+        ColorMode::Diff => return diff.map(|d| diff_palette(entities, d)).unwrap_or_default(),
+        _ => {}
+    }
+
+    // Elements in several systems take the first by name
+    let mut system_of: HashMap<u64, &SystemInfo> = HashMap::new();
+    if *mode == ColorMode::System {
+        for system in systems {
+            for &member in &system.members {
+                system_of.entry(member).or_insert(system);
+            }
+        }
+    }
+
+    // Keyed by label plus an id, so distinct types of the same name stay apart
+    let mut groups: BTreeMap<(String, u64), Vec<u64>> = BTreeMap::new();
+    let mut no_value = Vec::new();
+    for entity in entities {
+        match group_key(entity, mode, changes, &system_of) {
+            Some(key) => groups.entry(key).or_default().push(entity.id),
+            None => no_value.push(entity.id),
+        }
+    }
+
+    let mut groups: Vec<PaletteGroupData> = groups
+        .into_iter()
+        .enumerate()
+        .map(|(i, ((label, _), entities))| PaletteGroupData {
+            label,
+            color: group_color(i),
+            entities,
+        })
+        .collect();
+    if !no_value.is_empty() {
+        groups.push(PaletteGroupData {
+            label: NO_VALUE_LABEL.to_string(),
+            color: NO_VALUE_COLOR,
+            entities: no_value,
+        });
+    }
+    PaletteData { groups }
+}
+
+/// Fixed groups by change status; removed elements aren't in the model
+fn diff_palette(entities: &[EntityInfo], diff: &ModelDiff) -> PaletteData {
+    let status: HashMap<u64, DiffStatus> = diff
+        .elements
+        .iter()
+        .filter_map(|e| Some((u64::from(e.new_id?), e.status)))
+        .collect();
+    let mut added = Vec::new();
+    let mut modified = Vec::new();
+    let mut unchanged = Vec::new();
+    for entity in entities {
+        match status.get(&entity.id) {
+            Some(DiffStatus::Added) => added.push(entity.id),
+            Some(DiffStatus::Modified) => modified.push(entity.id),
+            _ => unchanged.push(entity.id),
+        }
+    }
+
+    let groups = [
+        ("Added", ADDED_COLOR, added),
+        ("Modified", MODIFIED_COLOR, modified),
+        ("Unchanged", NO_VALUE_COLOR, unchanged),
+    ];
+    PaletteData {
+        groups: groups
+            .into_iter()
+            .filter(|(_, _, entities)| !entities.is_empty())
+            .map(|(label, color, entities)| PaletteGroupData {
+                label: label.to_string(),
+                color,
+                entities,
+            })
+            .collect(),
+    }
+}
+
+fn group_key(
+    entity: &EntityInfo,
+    mode: &ColorMode,
+    changes: &ChangeSet,
+    system_of: &HashMap<u64, &SystemInfo>,
+) -> Option<(String, u64)> {
+    match mode {
+        ColorMode::Model | ColorMode::Diff => None,
+        ColorMode::TypeObject => {
+            let type_object = entity.type_object.as_ref()?;
+            let label = type_object
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("#{}", type_object.id));
+            Some((label, type_object.id))
+        }
+        ColorMode::Material => Some((entity.material.clone()?, 0)),
+        ColorMode::System => {
+            let system = system_of.get(&entity.id)?;
+            Some((system.label(), system.id))
+        }
+        ColorMode::Property { pset, property } => {
+            let value = match changes.value_of(entity.id as u32, pset, property) {
+                Some(value) => value.to_string(),
+                None => entity
+                    .property_sets
+                    .iter()
+                    .filter(|set| set.name == *pset)
+                    .flat_map(|set| &set.properties)
+                    .find(|p| p.name == *property)?
+                    .value
+                    .to_string(),
+            };
+            Some((value, 0))
+        }
+    }
+}
+
+/// Property sets and properties present in a model, sorted, as color mode
+/// candidates
+pub fn property_choices(entities: &[EntityInfo]) -> Vec<(String, String)> {
+    entities
+        .iter()
+        .flat_map(|e| &e.property_sets)
+        .flat_map(|set| set.properties.iter().map(|p| (&*set.name, &*p.name)))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .map(|(pset, property)| (pset.to_string(), property.to_string()))
+        .collect()
+}
+
+/// Color of the `index`-th group
+pub fn group_color(index: usize) -> [f32; 3] {
+    hsl_to_rgb((index as f32 * GOLDEN_ANGLE) % 360.0, 0.6, 0.55)
+}
+
+/// CSS color of a palette color, for legend swatches
+pub fn css_color(color: [f32; 3]) -> String {
+    let [r, g, b] = color.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn hsl_to_rgb(hue: f32, saturation: f32, lightness: f32) -> [f32; 3] {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let sector = hue / 60.0;
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = lightness - chroma / 2.0;
+    [r + m, g + m, b + m]
+}
@@ -0,0 +1,57 @@
+//! Sample models - the start screen gallery and `?sample=` demo links
+//!
+//! The viewer ships `samples.json`, a manifest of public sample models. The
+//! models themselves are only fetched when picked, from the server's `/ifc`
+//! directory unless an entry gives an absolute URL.
+
+use crate::utils::{build_ifc_url, fetch_ifc_file};
+use serde::{Deserialize, Serialize};
+
+/// Where trunk copies the manifest
+const MANIFEST_URL: &str = "/samples.json";
+
+/// Sample model list
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SampleManifest {
+    pub samples: Vec<SampleModel>,
+}
+
+/// One entry of the gallery
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SampleModel {
+    /// Stable name for `?sample=<id>`
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// IFC schema, e.g. "IFC4"
+    #[serde(default)]
+    pub schema: Option<String>,
+    /// File name in `/ifc`, or an absolute URL
+    pub file: String,
+    /// Download size in bytes
+    #[serde(default)]
+    pub size: Option<usize>,
+}
+
+impl SampleModel {
+    /// URL to download the model from
+    pub fn url(&self) -> String {
+        build_ifc_url(&self.file)
+    }
+}
+
+/// Fetch the manifest; an unreachable or malformed one is an empty gallery
+pub async fn load_manifest() -> SampleManifest {
+    // Small enough that download progress does not matter
+    match fetch_ifc_file(MANIFEST_URL, |_, _| {}).await {
+        Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+            crate::bridge::log_error(&format!("[Yew] Invalid sample manifest: {}", e));
+            SampleManifest::default()
+        }),
+        Err(e) => {
+            crate::bridge::log(&format!("[Yew] No sample manifest: {}", e));
+            SampleManifest::default()
+        }
+    }
+}
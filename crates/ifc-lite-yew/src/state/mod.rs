@@ -2,8 +2,15 @@
 //!
 //! Uses Yew's reducer pattern for predictable state updates.
 
+use crate::bridge::{CameraData, CameraKeyframeData, GridIntersectionData};
+use crate::device::{DeviceDefaults, DeviceProfile, GraphicsQuality};
+use ifc_lite_core::{
+    format_real, BcfTopic, ChangeSet, Diagnostic, ElementMaterial, ElementMeasures,
+    ElementQuantities, IfcType, ModelDiff, Predicate, PropertyData, QuantityKind, QuantityReport,
+    Query, UnitSystem,
+};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 use yew::prelude::*;
 
@@ -21,6 +28,7 @@ pub enum Tool {
     Measure,
     Section,
     BoxSelect,
+    Annotate,
 }
 
 impl Tool {
@@ -33,6 +41,7 @@ impl Tool {
             Tool::Measure => "📏",
             Tool::Section => "✂️",
             Tool::BoxSelect => "⬚",
+            Tool::Annotate => "📝",
         }
     }
 
@@ -45,6 +54,7 @@ impl Tool {
             Tool::Measure => "Measure (M)",
             Tool::Section => "Section (X)",
             Tool::BoxSelect => "Box Select (B)",
+            Tool::Annotate => "Annotate (N)",
         }
     }
 }
@@ -57,17 +67,40 @@ pub enum Theme {
     Dark,
 }
 
-/// Section plane axis
+/// Most section planes at a time, besides the section box
+pub const MAX_SECTION_PLANES: usize = 6;
+
+/// Section plane axis, along IFC axes (Z up)
 #[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
 pub enum SectionAxis {
     X,
-    #[default]
     Y,
+    #[default]
     Z,
 }
 
+impl SectionAxis {
+    pub const ALL: [SectionAxis; 3] = [SectionAxis::X, SectionAxis::Y, SectionAxis::Z];
+
+    pub fn index(&self) -> usize {
+        match self {
+            SectionAxis::X => 0,
+            SectionAxis::Y => 1,
+            SectionAxis::Z => 2,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SectionAxis::X => "x",
+            SectionAxis::Y => "y",
+            SectionAxis::Z => "z",
+        }
+    }
+}
+
 /// Section plane state
-#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct SectionPlaneState {
     pub enabled: bool,
     pub axis: SectionAxis,
@@ -75,12 +108,456 @@ pub struct SectionPlaneState {
     pub flipped: bool,
 }
 
-/// Measurement point
+impl Default for SectionPlaneState {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            axis: SectionAxis::Z,
+            position: 0.5,
+            flipped: false,
+        }
+    }
+}
+
+/// Section box, relative to the scene bounds per IFC axis (0.0 to 1.0)
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SectionBoxState {
+    pub enabled: bool,
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
+impl Default for SectionBoxState {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min: [0.0; 3],
+            max: [1.0; 3],
+        }
+    }
+}
+
+/// Cut height of storey plans when none was chosen, in metres
+pub const DEFAULT_PLAN_CUT_HEIGHT: f32 = 1.2;
+
+/// Storey shown as a 2D floor plan
+#[derive(Clone, Debug, PartialEq)]
+pub struct PlanState {
+    /// `None` is the normal 3D view
+    pub storey: Option<String>,
+    /// Height of the horizontal cut above the storey elevation, in metres
+    pub cut_height: f32,
+}
+
+impl Default for PlanState {
+    fn default() -> Self {
+        Self {
+            storey: None,
+            cut_height: DEFAULT_PLAN_CUT_HEIGHT,
+        }
+    }
+}
+
+/// Two viewports side by side, each with its own camera
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SplitViewState {
+    pub enabled: bool,
+    /// Both views pan and zoom together, each keeping its view direction
+    pub synchronized: bool,
+}
+
+/// Space mode: IfcSpace volumes drawn as translucent rooms with labels
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SpaceState {
+    pub visible: bool,
+    /// Storeys whose spaces stay hidden in space mode
+    pub hidden_storeys: Vec<String>,
+}
+
+impl SpaceState {
+    pub fn is_storey_visible(&self, storey: &str) -> bool {
+        !self.hidden_storeys.iter().any(|s| s == storey)
+    }
+}
+
+/// How the exploded view pulls the model apart
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExplodeMode {
+    #[default]
+    Off,
+    /// Storeys lifted apart
+    Storeys,
+    /// Elements pushed out from the model centre
+    Elements,
+}
+
+impl ExplodeMode {
+    pub const ALL: [ExplodeMode; 3] = [
+        ExplodeMode::Off,
+        ExplodeMode::Storeys,
+        ExplodeMode::Elements,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExplodeMode::Off => "off",
+            ExplodeMode::Storeys => "storeys",
+            ExplodeMode::Elements => "elements",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExplodeMode::Off => "Assembled",
+            ExplodeMode::Storeys => "Explode Storeys",
+            ExplodeMode::Elements => "Explode Elements",
+        }
+    }
+}
+
+/// Exploded view, animated by Bevy
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExplodeState {
+    pub mode: ExplodeMode,
+    /// Spread; 1 lifts each storey by an average storey height
+    pub factor: f32,
+}
+
+impl Default for ExplodeState {
+    fn default() -> Self {
+        Self {
+            mode: ExplodeMode::Off,
+            factor: 1.0,
+        }
+    }
+}
+
+/// Seconds between camera path keyframes as they are recorded
+pub const DEFAULT_KEYFRAME_SECONDS: f32 = 3.0;
+
+/// Recorded camera path and its playback
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CameraPathState {
+    pub keyframes: Vec<CameraKeyframeData>,
+    pub looping: bool,
+    pub playing: bool,
+    /// Bumped on every play, so Bevy restarts a path that is still playing
+    pub run: u32,
+}
+
+/// Horizontal cut just above a storey, with the storeys above it hidden
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StoreySectionState {
+    pub storey: String,
+    /// Height of the cut above the storey elevation, in metres
+    pub cut_height: f32,
+}
+
+/// Section planes, section box and the colour of cut surfaces
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SectionState {
+    pub planes: Vec<SectionPlaneState>,
+    pub section_box: SectionBoxState,
+    /// "#rrggbb"
+    pub cap_color: String,
+    #[serde(default)]
+    pub storey: Option<StoreySectionState>,
+}
+
+impl Default for SectionState {
+    fn default() -> Self {
+        Self {
+            planes: Vec::new(),
+            section_box: SectionBoxState::default(),
+            cap_color: "#cc4d40".to_string(),
+            storey: None,
+        }
+    }
+}
+
+impl SectionState {
+    /// Whether anything is cut away
+    pub fn is_active(&self) -> bool {
+        self.section_box.enabled || self.planes.iter().any(|p| p.enabled) || self.storey.is_some()
+    }
+
+    /// State from the single plane views were saved with before
+    ///
+    /// That plane ran along Bevy's Y-up axes, so Y and Z swap and the old Z
+    /// (IFC -Y) runs backwards.
+    fn from_legacy(plane: SectionPlaneState) -> Self {
+        let plane = match plane.axis {
+            SectionAxis::X => plane,
+            SectionAxis::Y => SectionPlaneState {
+                axis: SectionAxis::Z,
+                ..plane
+            },
+            SectionAxis::Z => SectionPlaneState {
+                axis: SectionAxis::Y,
+                position: 1.0 - plane.position,
+                flipped: !plane.flipped,
+                ..plane
+            },
+        };
+        Self {
+            planes: if plane.enabled {
+                vec![plane]
+            } else {
+                Vec::new()
+            },
+            ..Self::default()
+        }
+    }
+}
+
+/// Named viewpoint, e.g. "Level 2 MEP coordination"
+///
+/// Section and visibility define the view; camera and selection are
+/// restored with it but may change without leaving the view. Exchanged with
+/// other BIM tools as BCF visualization info, see [`crate::viewpoint`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NamedView {
+    pub name: String,
+    /// Orbit camera when the view was saved
+    #[serde(default)]
+    pub camera: Option<CameraData>,
+    #[serde(default)]
+    pub selected_ids: Vec<u64>,
+    #[serde(default)]
+    pub section: SectionState,
+    /// Single plane of views saved before multiple planes, see [`Self::migrated`]
+    #[serde(default, skip_serializing)]
+    pub section_plane: Option<SectionPlaneState>,
+    pub hidden_ids: Vec<u64>,
+    pub isolated_ids: Option<Vec<u64>>,
+    #[serde(default)]
+    pub storey_filter: Option<String>,
+}
+
+impl NamedView {
+    /// Capture the current section, visibility and selection under `name`
+    ///
+    /// The camera lives in Bevy; add it with [`Self::with_camera`].
+    pub fn capture(name: String, state: &ViewerState) -> Self {
+        let sorted = |ids: &HashSet<u64>| {
+            let mut ids: Vec<u64> = ids.iter().copied().collect();
+            ids.sort_unstable();
+            ids
+        };
+        Self {
+            name,
+            camera: None,
+            selected_ids: sorted(&state.selected_ids),
+            section: state.section.clone(),
+            section_plane: None,
+            hidden_ids: sorted(&state.hidden_ids),
+            isolated_ids: state.isolated_ids.as_ref().map(sorted),
+            storey_filter: state.storey_filter.clone(),
+        }
+    }
+
+    pub fn with_camera(mut self, camera: Option<CameraData>) -> Self {
+        self.camera = camera;
+        self
+    }
+
+    /// Whether the viewer currently shows this view's section and visibility
+    pub fn is_current(&self, state: &ViewerState) -> bool {
+        let current = Self::capture(self.name.clone(), state);
+        self.section == current.section
+            && self.hidden_ids == current.hidden_ids
+            && self.isolated_ids == current.isolated_ids
+            && self.storey_filter == current.storey_filter
+    }
+
+    /// Convert a view saved with a single section plane
+    pub fn migrated(mut self) -> Self {
+        if let Some(plane) = self.section_plane.take() {
+            self.section = SectionState::from_legacy(plane);
+        }
+        self
+    }
+}
+
+/// Viewport overlay layer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverlayLayer {
+    OriginAxes,
+    NorthArrow,
+    ScaleBar,
+    Minimap,
+    ViewCube,
+    GridAxes,
+    /// Frame rate, draw and memory statistics (F12 or `?debug=1`)
+    Stats,
+}
+
+/// Tessellation and element filters for the next load, see
+/// [`ifc_lite_geometry::GeometryOptions`]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GeometrySettings {
+    /// Maximum distance between a tessellated curve and the true curve, in metres
+    pub deflection: f64,
+    /// Upper bound on the segments of a full circle
+    pub max_segments: usize,
+    pub skip_openings: bool,
+    pub skip_spaces: bool,
+    /// Elements whose bounding box diagonal is shorter (metres) get no mesh
+    pub min_feature_size: f64,
+}
+
+impl Default for GeometrySettings {
+    fn default() -> Self {
+        let options = ifc_lite_geometry::GeometryOptions::default();
+        Self {
+            deflection: options.deflection,
+            max_segments: options.max_segments,
+            skip_openings: options.skip_openings,
+            skip_spaces: options.skip_spaces,
+            min_feature_size: options.min_feature_size,
+        }
+    }
+}
+
+impl GeometrySettings {
+    pub fn options(&self) -> ifc_lite_geometry::GeometryOptions {
+        ifc_lite_geometry::GeometryOptions {
+            deflection: self.deflection,
+            max_segments: self.max_segments,
+            skip_openings: self.skip_openings,
+            skip_spaces: self.skip_spaces,
+            min_feature_size: self.min_feature_size,
+        }
+    }
+}
+
+/// Viewport overlay toggles
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OverlaySettings {
+    pub origin_axes: bool,
+    pub north_arrow: bool,
+    pub scale_bar: bool,
+    pub minimap: bool,
+    pub view_cube: bool,
+    pub grid_axes: bool,
+    pub stats: bool,
+}
+
+impl Default for OverlaySettings {
+    fn default() -> Self {
+        Self {
+            origin_axes: true,
+            north_arrow: true,
+            scale_bar: true,
+            minimap: true,
+            view_cube: true,
+            grid_axes: true,
+            // Debug sessions start with the stats overlay open
+            stats: crate::bridge::is_debug(),
+        }
+    }
+}
+
+impl OverlaySettings {
+    pub fn is_enabled(&self, layer: OverlayLayer) -> bool {
+        match layer {
+            OverlayLayer::OriginAxes => self.origin_axes,
+            OverlayLayer::NorthArrow => self.north_arrow,
+            OverlayLayer::ScaleBar => self.scale_bar,
+            OverlayLayer::Minimap => self.minimap,
+            OverlayLayer::ViewCube => self.view_cube,
+            OverlayLayer::GridAxes => self.grid_axes,
+            OverlayLayer::Stats => self.stats,
+        }
+    }
+
+    pub fn toggle(&mut self, layer: OverlayLayer) {
+        let flag = match layer {
+            OverlayLayer::OriginAxes => &mut self.origin_axes,
+            OverlayLayer::NorthArrow => &mut self.north_arrow,
+            OverlayLayer::ScaleBar => &mut self.scale_bar,
+            OverlayLayer::Minimap => &mut self.minimap,
+            OverlayLayer::ViewCube => &mut self.view_cube,
+            OverlayLayer::GridAxes => &mut self.grid_axes,
+            OverlayLayer::Stats => &mut self.stats,
+        };
+        *flag = !*flag;
+    }
+}
+
+/// Entities that stay opaque in x-ray mode
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum XrayFocus {
+    /// The current selection
+    Selection,
+    /// Every entity of this IFC type
+    Type(String),
+}
+
+/// What the viewport colors encode
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum ColorMode {
+    /// Colors from the model
+    #[default]
+    Model,
+    /// One color per type object, so every occurrence of a family matches
+    TypeObject,
+    /// One color per material name
+    Material,
+    /// One color per distinct value of a property
+    Property { pset: String, property: String },
+    /// One color per system or zone
+    System,
+    /// Added, modified and unchanged elements against a compared version
+    Diff,
+}
+
+impl ColorMode {
+    /// Legend title
+    pub fn title(&self) -> String {
+        match self {
+            Self::Model => "Model colors".to_string(),
+            Self::TypeObject => "Type Object".to_string(),
+            Self::Material => "Material".to_string(),
+            Self::Property { pset, property } => format!("{}.{}", pset, property),
+            Self::System => "System".to_string(),
+            Self::Diff => "Changes".to_string(),
+        }
+    }
+}
+
+/// Type object (IfcTypeObject) an element is an occurrence of
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TypeObjectInfo {
+    pub id: u64,
+    /// Uppercase STEP name, e.g. "IFCDOORTYPE"
+    pub entity_type: String,
+    pub name: Option<String>,
+}
+
+/// System or zone (IfcSystem subtype) and the elements assigned to it
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SystemInfo {
+    pub id: u64,
+    /// Uppercase STEP name, e.g. "IFCDISTRIBUTIONSYSTEM"
+    pub entity_type: String,
+    pub name: Option<String>,
+    pub members: Vec<u64>,
+}
+
+impl SystemInfo {
+    /// Name, or the id for unnamed systems
+    pub fn label(&self) -> String {
+        self.name.clone().unwrap_or_else(|| format!("#{}", self.id))
+    }
+}
+
+/// Measurement point, in IFC file coordinates
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct MeasurePoint {
-    pub x: f32,
-    pub y: f32,
-    pub z: f32,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
 }
 
 /// Measurement between two points
@@ -91,8 +568,30 @@ pub struct Measurement {
     pub end: MeasurePoint,
 }
 
+/// Text note anchored to a point, or to the centre of an entity
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Annotation {
+    pub id: u32,
+    pub text: String,
+    #[serde(default)]
+    pub entity_id: Option<u64>,
+    /// GlobalId of the entity, so imported notes find it in another file
+    #[serde(default)]
+    pub global_id: Option<String>,
+    /// Anchor point in IFC file coordinates; the entity centre when `None`
+    #[serde(default)]
+    pub point: Option<[f64; 3]>,
+}
+
+/// Anchor of an annotation waiting for its text
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnnotationAnchor {
+    pub entity_id: Option<u64>,
+    pub point: Option<[f64; 3]>,
+}
+
 impl Measurement {
-    pub fn distance(&self) -> f32 {
+    pub fn distance(&self) -> f64 {
         let dx = self.end.x - self.start.x;
         let dy = self.end.y - self.start.y;
         let dz = self.end.z - self.start.z;
@@ -104,8 +603,7 @@ impl Measurement {
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct PropertyValue {
     pub name: String,
-    pub value: String,
-    pub unit: Option<String>,
+    pub value: PropertyData,
 }
 
 /// A property set containing multiple properties
@@ -124,6 +622,293 @@ pub struct QuantityValue {
     pub quantity_type: String, // "Length", "Area", "Volume", "Count", "Weight", "Time"
 }
 
+/// Sum of one quantity over several entities
+#[derive(Clone, Debug, PartialEq)]
+pub struct QuantityTotal {
+    /// Quantity name without its set prefix ("NetVolume")
+    pub name: String,
+    pub quantity_type: String,
+    pub unit: String,
+    pub total: f64,
+    /// Number of entities contributing
+    pub count: usize,
+}
+
+/// Property that every entity of a selection has
+#[derive(Clone, Debug, PartialEq)]
+pub struct CommonProperty {
+    pub name: String,
+    /// Shared value; `None` where the entities differ
+    pub value: Option<PropertyData>,
+}
+
+/// Property set that every entity of a selection has, with the properties
+/// they all have in it
+#[derive(Clone, Debug, PartialEq)]
+pub struct CommonPropertySet {
+    pub name: String,
+    pub properties: Vec<CommonProperty>,
+}
+
+/// Aggregate of the current multi-selection
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SelectionTotals {
+    /// Entity count per IFC type, most frequent first
+    pub by_type: Vec<(String, usize)>,
+    /// Totals grouped by kind (Length, Area, Volume, ...) then name
+    pub quantities: Vec<QuantityTotal>,
+    /// Sum of the quantity takeoff measures, including those estimated
+    /// from geometry
+    pub measures: ElementMeasures,
+    /// Entities contributing to `measures`
+    pub measured: usize,
+    /// Property sets in file order of the first entity
+    pub common_properties: Vec<CommonPropertySet>,
+}
+
+impl SelectionTotals {
+    /// Aggregate quantities of the given entities
+    ///
+    /// Same-named quantities from different sets (e.g. `Qto_WallBaseQuantities`
+    /// and `Qto_SlabBaseQuantities` `NetVolume`) are summed together; each
+    /// entity contributes once per name.
+    pub fn from_entities<'a>(entities: impl IntoIterator<Item = &'a EntityInfo>) -> Self {
+        let mut by_type: Vec<(String, usize)> = Vec::new();
+        let mut quantities: Vec<QuantityTotal> = Vec::new();
+        let mut measures = ElementMeasures::default();
+        let mut measured = 0;
+        let mut common: Option<Vec<CommonPropertySet>> = None;
+
+        for entity in entities {
+            match by_type.iter_mut().find(|(t, _)| *t == entity.entity_type) {
+                Some((_, n)) => *n += 1,
+                None => by_type.push((entity.entity_type.clone(), 1)),
+            }
+
+            if !entity.measures.is_empty() {
+                measured += 1;
+                let add = |total: &mut Option<f64>, value: Option<f64>| {
+                    if let Some(value) = value {
+                        *total = Some(total.unwrap_or(0.0) + value);
+                    }
+                };
+                add(&mut measures.volume, entity.measures.volume);
+                add(&mut measures.footprint_area, entity.measures.footprint_area);
+                add(&mut measures.surface_area, entity.measures.surface_area);
+                add(&mut measures.length, entity.measures.length);
+            }
+
+            match common.as_mut() {
+                None => {
+                    common = Some(
+                        entity
+                            .property_sets
+                            .iter()
+                            .map(|pset| CommonPropertySet {
+                                name: pset.name.clone(),
+                                properties: pset
+                                    .properties
+                                    .iter()
+                                    .map(|p| CommonProperty {
+                                        name: p.name.clone(),
+                                        value: Some(p.value.clone()),
+                                    })
+                                    .collect(),
+                            })
+                            .collect(),
+                    )
+                }
+                Some(common) => common.retain_mut(|set| {
+                    let Some(pset) = entity.property_sets.iter().find(|p| p.name == set.name)
+                    else {
+                        return false;
+                    };
+                    set.properties.retain_mut(|property| {
+                        let Some(other) = pset.properties.iter().find(|p| p.name == property.name)
+                        else {
+                            return false;
+                        };
+                        if property.value.as_ref() != Some(&other.value) {
+                            property.value = None;
+                        }
+                        true
+                    });
+                    !set.properties.is_empty()
+                }),
+            }
+
+            let mut seen: Vec<(&str, &str)> = Vec::new();
+            for q in &entity.quantities {
+                let name = q.name.split_once(": ").map_or(q.name.as_str(), |(_, n)| n);
+                if seen.contains(&(name, q.unit.as_str())) {
+                    continue;
+                }
+                seen.push((name, q.unit.as_str()));
+
+                match quantities
+                    .iter_mut()
+                    .find(|t| t.name == name && t.unit == q.unit)
+                {
+                    Some(total) => {
+                        total.total += q.value;
+                        total.count += 1;
+                    }
+                    None => quantities.push(QuantityTotal {
+                        name: name.to_string(),
+                        quantity_type: q.quantity_type.clone(),
+                        unit: q.unit.clone(),
+                        total: q.value,
+                        count: 1,
+                    }),
+                }
+            }
+        }
+
+        by_type.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        let kind_order = |t: &str| {
+            ["Length", "Area", "Volume", "Weight", "Count", "Time"]
+                .iter()
+                .position(|k| *k == t)
+                .unwrap_or(usize::MAX)
+        };
+        quantities.sort_by(|a, b| {
+            kind_order(&a.quantity_type)
+                .cmp(&kind_order(&b.quantity_type))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+
+        Self {
+            by_type,
+            quantities,
+            measures,
+            measured,
+            common_properties: common.unwrap_or_default(),
+        }
+    }
+
+    /// Tab-separated table for pasting into a spreadsheet, in the display
+    /// unit system
+    pub fn to_tsv(&self, system: UnitSystem) -> String {
+        let mut out = String::from("Quantity\tTotal\tUnit\tElements\n");
+        for q in &self.quantities {
+            let (total, unit) = crate::utils::convert_unit(q.total, &q.unit, system);
+            out.push_str(&format!(
+                "{}\t{}\t{}\t{}\n",
+                q.name,
+                ifc_lite_core::format_real(total),
+                unit,
+                q.count
+            ));
+        }
+        let measures = [
+            ("Volume", self.measures.volume, QuantityKind::Volume),
+            (
+                "Footprint area",
+                self.measures.footprint_area,
+                QuantityKind::Area,
+            ),
+            (
+                "Surface area",
+                self.measures.surface_area,
+                QuantityKind::Area,
+            ),
+            ("Length", self.measures.length, QuantityKind::Length),
+        ];
+        for (name, total, kind) in measures {
+            if let Some(total) = total {
+                let (total, unit) = system.from_si(kind, total);
+                out.push_str(&format!(
+                    "{} (takeoff)\t{}\t{}\t{}\n",
+                    name,
+                    ifc_lite_core::format_real(total),
+                    unit,
+                    self.measured
+                ));
+            }
+        }
+        out.push_str("\nType\tCount\n");
+        for (ifc_type, count) in &self.by_type {
+            out.push_str(&format!("{}\t{}\n", ifc_type, count));
+        }
+        out
+    }
+}
+
+/// One property of two entities compared side by side
+#[derive(Clone, Debug, PartialEq)]
+pub struct PropertyComparison {
+    pub name: String,
+    /// Value of the pinned entity, `None` if it lacks the property
+    pub pinned: Option<PropertyData>,
+    /// Value of the selected entity, `None` if it lacks the property
+    pub selected: Option<PropertyData>,
+}
+
+impl PropertyComparison {
+    pub fn differs(&self) -> bool {
+        self.pinned != self.selected
+    }
+}
+
+/// A property set of either of two compared entities
+#[derive(Clone, Debug, PartialEq)]
+pub struct PropertySetComparison {
+    pub name: String,
+    pub properties: Vec<PropertyComparison>,
+}
+
+impl PropertySetComparison {
+    pub fn differs(&self) -> bool {
+        self.properties.iter().any(PropertyComparison::differs)
+    }
+}
+
+/// Property sets of a pinned and a selected entity, matched by name
+///
+/// Sets and properties keep the order of the pinned entity; those only the
+/// selected entity has follow.
+pub fn compare_property_sets(
+    pinned: &[PropertySet],
+    selected: &[PropertySet],
+) -> Vec<PropertySetComparison> {
+    let mut sets: Vec<PropertySetComparison> = Vec::new();
+    for (side, is_pinned) in [(pinned, true), (selected, false)] {
+        for pset in side {
+            let index = sets
+                .iter()
+                .position(|s| s.name == pset.name)
+                .unwrap_or_else(|| {
+                    sets.push(PropertySetComparison {
+                        name: pset.name.clone(),
+                        properties: Vec::new(),
+                    });
+                    sets.len() - 1
+                });
+            let properties = &mut sets[index].properties;
+            for property in &pset.properties {
+                let index = properties
+                    .iter()
+                    .position(|p| p.name == property.name)
+                    .unwrap_or_else(|| {
+                        properties.push(PropertyComparison {
+                            name: property.name.clone(),
+                            pinned: None,
+                            selected: None,
+                        });
+                        properties.len() - 1
+                    });
+                let value = Some(property.value.clone());
+                if is_pinned {
+                    properties[index].pinned = value;
+                } else {
+                    properties[index].selected = value;
+                }
+            }
+        }
+    }
+    sets
+}
+
 /// Entity info for display
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct EntityInfo {
@@ -133,8 +918,97 @@ pub struct EntityInfo {
     pub global_id: Option<String>,
     pub storey: Option<String>,
     pub storey_elevation: Option<f32>,
+    /// Further storeys that reference the element without containing it
+    pub referenced_storeys: Vec<String>,
+    /// PredefinedType enum (e.g. "CEILING" for a ceiling covering)
+    pub predefined_type: Option<String>,
+    /// ObjectType string
+    pub object_type: Option<String>,
+    /// Type object assigned through IfcRelDefinesByType
+    pub type_object: Option<TypeObjectInfo>,
     pub property_sets: Vec<PropertySet>,
     pub quantities: Vec<QuantityValue>,
+    /// Name of the associated material or material set
+    pub material: Option<String>,
+    /// Layers, profiles or constituents behind `material`
+    #[serde(default)]
+    pub material_definition: Option<ElementMaterial>,
+    /// Volume, areas and length in m, m², m³ for the quantity takeoff
+    pub measures: ElementMeasures,
+    /// Some of `measures` were computed from the mesh
+    pub measures_estimated: bool,
+}
+
+impl EntityInfo {
+    /// Contained in or referenced by the named storey
+    pub fn is_on_storey(&self, storey: &str) -> bool {
+        self.storey.as_deref() == Some(storey)
+            || self.referenced_storeys.iter().any(|s| s == storey)
+    }
+
+    /// Evaluate a property query against this entity's properties and quantities
+    pub fn matches_query(&self, query: &Query) -> bool {
+        let ifc_type = IfcType::from_str(&self.entity_type);
+        if !query.matches_type(&ifc_type) {
+            return false;
+        }
+
+        // Quantities are named "Qto_Set: Name"
+        let quantities: Vec<(&str, &str, PropertyData)> = self
+            .quantities
+            .iter()
+            .map(|q| {
+                let (set, name) = q.name.split_once(": ").unwrap_or(("", &q.name));
+                let value = PropertyData::Real {
+                    value: q.value,
+                    unit: (!q.unit.is_empty()).then(|| q.unit.clone()),
+                };
+                (set, name, value)
+            })
+            .collect();
+
+        let properties = self
+            .property_sets
+            .iter()
+            .flat_map(|pset| {
+                pset.properties
+                    .iter()
+                    .map(move |p| (pset.name.as_str(), p.name.as_str(), &p.value))
+            })
+            .chain(
+                quantities
+                    .iter()
+                    .map(|(set, name, value)| (*set, *name, value)),
+            );
+        query.matches(&ifc_type, properties)
+    }
+
+    /// Value with unit of the first property or quantity `column` names, in
+    /// the display unit system
+    pub fn property_text(&self, column: &Predicate, system: UnitSystem) -> Option<String> {
+        let with_unit = |value: String, unit: Option<&str>| match unit {
+            Some(unit) if !unit.is_empty() => format!("{} {}", value, unit),
+            _ => value,
+        };
+        let property = self.property_sets.iter().find_map(|pset| {
+            pset.properties
+                .iter()
+                .find(|p| column.matches_name(&pset.name, &p.name))
+                .map(|p| {
+                    let value = p.value.in_unit_system(system);
+                    with_unit(value.to_string(), value.unit())
+                })
+        });
+        property.or_else(|| {
+            self.quantities.iter().find_map(|q| {
+                let (set, name) = q.name.split_once(": ").unwrap_or(("", &q.name));
+                column.matches_name(set, name).then(|| {
+                    let (value, unit) = crate::utils::convert_unit(q.value, &q.unit, system);
+                    with_unit(format_real(value), Some(unit))
+                })
+            })
+        })
+    }
 }
 
 /// Storey info
@@ -153,6 +1027,14 @@ pub enum SpatialNodeType {
     Building,
     Storey,
     Space,
+    /// IFC4.3 road, bridge, railway or marine facility
+    Facility,
+    /// Part of a facility, e.g. IfcRoadPart
+    FacilityPart,
+    /// System or zone, in the systems tab
+    System,
+    /// Elements of one entity type within a storey
+    TypeGroup,
     Element,
 }
 
@@ -163,13 +1045,34 @@ pub struct SpatialNode {
     pub node_type: SpatialNodeType,
     pub name: String,
     pub entity_type: String,
+    /// PredefinedType of elements, matched by the search filter
+    pub predefined_type: Option<String>,
     pub elevation: Option<f32>,
     pub children: Vec<SpatialNode>,
     pub has_geometry: bool,
+    /// Element referenced by this structure but contained in another
+    /// (e.g. a column spanning several storeys)
+    #[serde(default)]
+    pub referenced: bool,
+}
+
+impl SpatialNode {
+    /// IDs of the nodes above `id`, root first, if `id` is in this tree
+    pub fn ancestors_of(&self, id: u64) -> Option<Vec<u64>> {
+        if self.id == id {
+            return Some(Vec::new());
+        }
+        self.children.iter().find_map(|child| {
+            child.ancestors_of(id).map(|mut path| {
+                path.insert(0, self.id);
+                path
+            })
+        })
+    }
 }
 
 /// Progress state
-#[derive(Clone, Debug, Default, PartialEq)]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
 pub struct Progress {
     pub phase: String,
     pub percent: f32,
@@ -182,12 +1085,31 @@ pub struct ViewerState {
     pub loading: bool,
     pub progress: Option<Progress>,
     pub error: Option<String>,
+    /// Non-fatal load notice, e.g. a degraded load
+    pub warning: Option<String>,
+    /// Entities skipped or left without geometry by the last load
+    pub diagnostics: Rc<Vec<Diagnostic>>,
+    /// Structural findings about the loaded model
+    pub validation: Rc<Vec<Diagnostic>>,
+    /// Timings of the last load; None for a model from the cache
+    pub load_profile: Option<Rc<ifc_lite_engine::LoadProfile>>,
+    /// Changes since an earlier version of the model, once one is compared
+    pub diff: Option<Rc<ModelDiff>>,
 
     // Data
     pub entities: Vec<EntityInfo>,
     pub storeys: Vec<StoreyInfo>,
-    pub spatial_tree: Option<SpatialNode>,
+    /// Shared, so state updates do not copy the tree of a large model
+    pub spatial_tree: Option<Rc<SpatialNode>>,
+    /// Systems and zones, sorted by name
+    pub systems: Rc<Vec<SystemInfo>>,
+    /// Crossings of the IfcGrid axes, for "go to intersection"
+    pub grid_intersections: Rc<Vec<GridIntersectionData>>,
     pub file_name: Option<String>,
+    /// Content hash of the loaded file (hex), used as cache/session key
+    pub content_hash: Option<String>,
+    /// The loaded model produced geometry, so the 3D viewer is needed
+    pub has_geometry: bool,
 
     // UI state for tree
     pub expanded_nodes: HashSet<u64>,
@@ -195,6 +1117,8 @@ pub struct ViewerState {
     // Selection
     pub selected_ids: HashSet<u64>,
     pub hovered_id: Option<u64>,
+    /// Entity whose properties are kept for comparison with the selection
+    pub pinned_id: Option<u64>,
 
     // Visibility
     pub hidden_ids: HashSet<u64>,
@@ -204,18 +1128,142 @@ pub struct ViewerState {
     // UI
     pub active_tool: Tool,
     pub theme: Theme,
+    /// Units lengths, areas, volumes and masses are displayed in
+    pub unit_system: UnitSystem,
     pub left_panel_collapsed: bool,
     pub right_panel_collapsed: bool,
     pub show_shortcuts_dialog: bool,
+    pub show_statistics_dialog: bool,
+    pub show_diagnostics_panel: bool,
+    pub overlay: OverlaySettings,
+    /// X-ray mode: everything outside the focus is drawn translucent
+    pub xray: Option<XrayFocus>,
+    pub spaces: SpaceState,
+    pub explode: ExplodeState,
+    pub color_mode: ColorMode,
 
     // Tools
-    pub section_plane: SectionPlaneState,
+    pub section: SectionState,
+    pub plan: PlanState,
+    pub split_view: SplitViewState,
+    pub camera_path: CameraPathState,
     pub measurements: Vec<Measurement>,
     pub pending_measure_point: Option<MeasurePoint>,
     pub next_measure_id: u32,
+    pub annotations: Vec<Annotation>,
+    pub pending_annotation: Option<AnnotationAnchor>,
+
+    // Named views of the loaded model
+    pub views: Vec<NamedView>,
+    /// BCF issues about the loaded model; shared because snapshots are large
+    pub bcf_topics: Rc<Vec<BcfTopic>>,
+    /// Thumbnails of the loaded model by key, as PNG data URLs; `None`
+    /// while Bevy renders one, or if there was nothing to draw
+    pub thumbnails: Rc<HashMap<String, Option<String>>>,
+
+    // Editing
+    /// Property values can be edited in the properties panel
+    pub editing: bool,
+    /// Unsaved property edits of the loaded model
+    pub changes: ChangeSet,
 
     // Search
     pub search_query: String,
+    /// Property columns of the entity table, e.g. "Pset_WallCommon.FireRating"
+    pub table_columns: Vec<String>,
+
+    /// Hardware profile taken at startup
+    pub device: Option<DeviceProfile>,
+    /// Shadow preset picked in the graphics settings; `None` follows the
+    /// device defaults
+    pub shadow_quality: Option<GraphicsQuality>,
+    /// Ambient occlusion preset picked in the graphics settings
+    pub ambient_occlusion: GraphicsQuality,
+    /// Orbit about the model point under the cursor
+    pub orbit_around_cursor: bool,
+    /// Zoom toward the cursor rather than the view centre
+    pub zoom_to_cursor: bool,
+    /// Tessellation and element filters, applied from the next load on
+    pub geometry: GeometrySettings,
+}
+
+impl ViewerState {
+    /// Entity with the given IFC GlobalId
+    pub fn entity_by_global_id(&self, global_id: &str) -> Option<&EntityInfo> {
+        self.entities
+            .iter()
+            .find(|e| e.global_id.as_deref() == Some(global_id))
+    }
+
+    /// Entity ids of the given GlobalIds; unknown GlobalIds are skipped
+    pub fn resolve_global_ids(&self, global_ids: &[String]) -> Vec<u64> {
+        let wanted: HashSet<&str> = global_ids.iter().map(String::as_str).collect();
+        self.entities
+            .iter()
+            .filter(|e| e.global_id.as_deref().is_some_and(|g| wanted.contains(g)))
+            .map(|e| e.id)
+            .collect()
+    }
+
+    /// Quantity takeoff of all measured entities
+    pub fn quantity_report(&self) -> QuantityReport {
+        QuantityReport::new(
+            self.entities
+                .iter()
+                .filter(|e| !e.measures.is_empty())
+                .map(|e| ElementQuantities {
+                    entity_id: e.id as u32,
+                    global_id: e.global_id.clone(),
+                    ifc_type: e.entity_type.clone(),
+                    name: e.name.clone(),
+                    storey: e.storey.clone(),
+                    material: e.material.clone(),
+                    material_shares: e
+                        .material_definition
+                        .as_ref()
+                        .map(ElementMaterial::volume_shares)
+                        .unwrap_or_default(),
+                    measures: e.measures,
+                    estimated: e.measures_estimated,
+                })
+                .collect(),
+        )
+    }
+
+    /// ID for a new annotation, above those of the loaded ones
+    fn next_annotation_id(&self) -> u32 {
+        self.annotations.iter().map(|a| a.id).max().unwrap_or(0) + 1
+    }
+
+    /// Take over section, visibility and selection of a view
+    fn restore_view(&mut self, view: NamedView) {
+        self.section = view.section;
+        self.hidden_ids = view.hidden_ids.into_iter().collect();
+        self.isolated_ids = view.isolated_ids.map(|ids| ids.into_iter().collect());
+        self.storey_filter = view.storey_filter;
+        self.selected_ids = view.selected_ids.into_iter().collect();
+    }
+
+    /// Defaults for the profiled device, or middle-of-the-road ones before
+    /// profiling
+    pub fn device_defaults(&self) -> DeviceDefaults {
+        self.device
+            .as_ref()
+            .map(DeviceProfile::defaults)
+            .unwrap_or_default()
+    }
+
+    /// Shadow preset to render with: the one picked, or the device default,
+    /// capped at what the browser can render
+    pub fn shadow_quality(&self) -> GraphicsQuality {
+        let quality = self
+            .shadow_quality
+            .unwrap_or_else(|| self.device_defaults().shadows);
+        match &self.device {
+            Some(device) => quality.min(device.max_shadow_quality()),
+            None => quality,
+        }
+    }
 }
 
 impl Default for ViewerState {
@@ -224,26 +1272,61 @@ impl Default for ViewerState {
             loading: false,
             progress: None,
             error: None,
+            warning: None,
+            diagnostics: Rc::default(),
+            validation: Rc::default(),
+            load_profile: None,
+            diff: None,
             entities: Vec::new(),
             storeys: Vec::new(),
             spatial_tree: None,
+            systems: Rc::default(),
+            grid_intersections: Rc::default(),
             file_name: None,
+            content_hash: None,
+            has_geometry: false,
             expanded_nodes: HashSet::default(),
             selected_ids: HashSet::default(),
             hovered_id: None,
+            pinned_id: None,
             hidden_ids: HashSet::default(),
             isolated_ids: None,
             storey_filter: None,
             active_tool: Tool::Select,
             theme: Theme::Dark,
+            unit_system: UnitSystem::Metric,
             left_panel_collapsed: false,
             right_panel_collapsed: false,
             show_shortcuts_dialog: false,
-            section_plane: SectionPlaneState::default(),
+            show_statistics_dialog: false,
+            show_diagnostics_panel: false,
+            overlay: OverlaySettings::default(),
+            xray: None,
+            spaces: SpaceState::default(),
+            explode: ExplodeState::default(),
+            color_mode: ColorMode::Model,
+            section: SectionState::default(),
+            plan: PlanState::default(),
+            split_view: SplitViewState::default(),
+            camera_path: CameraPathState::default(),
             measurements: Vec::new(),
             pending_measure_point: None,
             next_measure_id: 1,
+            annotations: Vec::new(),
+            pending_annotation: None,
+            views: Vec::new(),
+            bcf_topics: Rc::default(),
+            thumbnails: Rc::default(),
+            editing: false,
+            changes: ChangeSet::default(),
             search_query: String::new(),
+            table_columns: Vec::new(),
+            device: None,
+            shadow_quality: None,
+            ambient_occlusion: GraphicsQuality::Off,
+            orbit_around_cursor: true,
+            zoom_to_cursor: true,
+            geometry: GeometrySettings::default(),
         }
     }
 }
@@ -256,48 +1339,127 @@ pub enum ViewerAction {
     ClearProgress,
     SetError(String),
     ClearError,
+    SetWarning(String),
+    ClearWarning,
+    SetDiagnostics(Vec<Diagnostic>),
+    SetValidation(Vec<Diagnostic>),
+    SetLoadProfile(Option<ifc_lite_engine::LoadProfile>),
+    /// Show a comparison with an earlier version, or drop it
+    SetDiff(Option<ModelDiff>),
 
     // Data
     SetEntities(Vec<EntityInfo>),
     SetStoreys(Vec<StoreyInfo>),
     SetSpatialTree(SpatialNode),
+    SetSystems(Vec<SystemInfo>),
+    SetGridIntersections(Vec<GridIntersectionData>),
     SetFileName(String),
+    SetContentHash(String),
+    /// The first geometry of a model arrived
+    GeometryAvailable,
     ClearData,
 
     // Tree UI
     ToggleNodeExpanded(u64),
     ExpandAll,
     CollapseAll,
+    /// Expand the spatial tree down to this node
+    RevealNode(u64),
 
     // Selection
     Select(u64),
     AddToSelection(u64),
     RemoveFromSelection(u64),
     ToggleSelection(u64),
+    /// Replace the selection with these entities
+    SetSelection(HashSet<u64>),
     ClearSelection,
+    /// Replace the selection with the entities of these GlobalIds
+    SelectGlobalIds(Vec<String>),
     SetHovered(Option<u64>),
+    /// Pin an entity for comparison, or unpin with `None`
+    SetPinned(Option<u64>),
 
     // Visibility
     HideEntity(u64),
     ShowEntity(u64),
+    HideEntities(HashSet<u64>),
+    ShowEntities(HashSet<u64>),
     ToggleVisibility(u64),
     IsolateEntity(u64),
     IsolateEntities(HashSet<u64>),
+    HideGlobalIds(Vec<String>),
+    IsolateGlobalIds(Vec<String>),
     ShowAll,
     SetStoreyFilter(Option<String>),
 
     // UI
     SetActiveTool(Tool),
     ToggleTheme,
+    ToggleUnitSystem,
     SetLeftPanelCollapsed(bool),
     SetRightPanelCollapsed(bool),
     ToggleShortcutsDialog,
+    ToggleStatisticsDialog,
+    ToggleDiagnosticsPanel,
+    /// Shadow preset; `None` goes back to the device default
+    SetShadowQuality(Option<GraphicsQuality>),
+    SetAmbientOcclusion(GraphicsQuality),
+    SetOrbitAroundCursor(bool),
+    SetZoomToCursor(bool),
+    SetGeometrySettings(GeometrySettings),
+    ToggleOverlay(OverlayLayer),
+    /// Toggle x-ray of everything but the selection
+    ToggleXray,
+    SetXray(Option<XrayFocus>),
+    ToggleSpaces,
+    /// Show or hide the spaces of a storey in space mode
+    ToggleStoreySpaces(String),
+    SetExplodeMode(ExplodeMode),
+    SetExplodeFactor(f32),
+    SetColorMode(ColorMode),
+
+    // Section planes, by index
+    AddSectionPlane,
+    RemoveSectionPlane(usize),
+    SetSectionEnabled(usize, bool),
+    SetSectionAxis(usize, SectionAxis),
+    SetSectionPosition(usize, f32),
+    ToggleSectionFlip(usize),
+    SetSectionBoxEnabled(bool),
+    /// Set the box range along one IFC axis (0 = X, 1 = Y, 2 = Z)
+    SetSectionBoxRange {
+        axis: usize,
+        min: f32,
+        max: f32,
+    },
+    SetSectionCapColor(String),
+    /// Cut above a storey and hide the storeys over it, or stop
+    SetStoreySection(Option<String>),
+    ClearSection,
+
+    // Storey plan
+    /// Show a storey as a floor plan, filtered to that storey; `None` closes it
+    SetPlanStorey(Option<String>),
+    SetPlanCutHeight(f32),
+
+    // Split view
+    ToggleSplitView,
+    ToggleSplitSync,
+
+    // Thumbnails
+    /// Thumbnails were asked of Bevy under these keys
+    ThumbnailsRequested(Vec<String>),
+    /// Thumbnails rendered by Bevy, as data URLs
+    SetThumbnails(Vec<(String, Option<String>)>),
 
-    // Section plane
-    SetSectionEnabled(bool),
-    SetSectionAxis(SectionAxis),
-    SetSectionPosition(f32),
-    ToggleSectionFlip,
+    // Camera path
+    /// Append a camera, reached [`DEFAULT_KEYFRAME_SECONDS`] after the last
+    AddKeyframe(CameraData),
+    ClearKeyframes,
+    PlayPath,
+    StopPath,
+    ToggleLoopPath,
 
     // Measurements
     AddMeasurePoint(MeasurePoint),
@@ -305,8 +1467,54 @@ pub enum ViewerAction {
     RemoveMeasurement(u32),
     ClearMeasurements,
 
+    // Annotations
+    /// Annotations saved for the loaded model
+    SetAnnotations(Vec<Annotation>),
+    /// Ask for the text of a new annotation at this anchor
+    BeginAnnotation(AnnotationAnchor),
+    CancelAnnotation,
+    /// Add the pending annotation with this text
+    AddAnnotation(String),
+    EditAnnotation(u32, String),
+    RemoveAnnotation(u32),
+    /// Add annotations from a file, attached by GlobalId where it resolves
+    ImportAnnotations(Vec<Annotation>),
+
+    // Named views
+    SetViews(Vec<NamedView>),
+    /// Add a viewpoint, replacing one of the same name
+    SaveView(NamedView),
+    ApplyView(String),
+    DeleteView(String),
+    /// Apply a view that is not in the saved list, e.g. a BCF topic's
+    RestoreView(NamedView),
+
+    // BCF topics
+    /// Add topics, replacing ones with the same GUID
+    ImportBcfTopics(Vec<BcfTopic>),
+    DeleteBcfTopic(String),
+
+    // Editing
+    ToggleEditing,
+    /// Set a property value of an entity
+    EditProperty {
+        entity: u64,
+        pset: String,
+        property: String,
+        value: PropertyData,
+    },
+    /// Add a property set with a first property to an entity
+    AddPropertySet {
+        entity: u64,
+        name: String,
+        property: String,
+        value: PropertyData,
+    },
+    DiscardChanges,
+
     // Search
     SetSearchQuery(String),
+    SetTableColumns(Vec<String>),
 }
 
 impl Reducible for ViewerState {
@@ -319,6 +1527,9 @@ impl Reducible for ViewerState {
             // Loading
             ViewerAction::SetLoading(loading) => {
                 next.loading = loading;
+                if loading {
+                    next.warning = None;
+                }
             }
             ViewerAction::SetProgress(progress) => {
                 next.progress = Some(progress);
@@ -333,12 +1544,40 @@ impl Reducible for ViewerState {
             ViewerAction::ClearError => {
                 next.error = None;
             }
+            ViewerAction::SetWarning(warning) => {
+                next.warning = Some(warning);
+            }
+            ViewerAction::ClearWarning => {
+                next.warning = None;
+            }
+            ViewerAction::SetDiagnostics(diagnostics) => {
+                next.diagnostics = Rc::new(diagnostics);
+            }
+            ViewerAction::SetValidation(findings) => {
+                next.validation = Rc::new(findings);
+            }
+            ViewerAction::SetLoadProfile(profile) => {
+                next.load_profile = profile.map(Rc::new);
+            }
+            ViewerAction::SetDiff(diff) => {
+                next.color_mode = if diff.is_some() {
+                    ColorMode::Diff
+                } else if next.color_mode == ColorMode::Diff {
+                    ColorMode::Model
+                } else {
+                    next.color_mode.clone()
+                };
+                next.diff = diff.map(Rc::new);
+            }
 
             // Data
             ViewerAction::SetEntities(entities) => {
                 next.entities = entities;
             }
             ViewerAction::SetStoreys(storeys) => {
+                next.spaces
+                    .hidden_storeys
+                    .retain(|hidden| storeys.iter().any(|s| s.name == *hidden));
                 next.storeys = storeys;
             }
             ViewerAction::SetSpatialTree(tree) => {
@@ -347,21 +1586,59 @@ impl Reducible for ViewerState {
                 for child in &tree.children {
                     next.expanded_nodes.insert(child.id);
                 }
-                next.spatial_tree = Some(tree);
+                next.spatial_tree = Some(Rc::new(tree));
+            }
+            ViewerAction::SetSystems(systems) => {
+                next.systems = Rc::new(systems);
+            }
+            ViewerAction::SetGridIntersections(intersections) => {
+                next.grid_intersections = Rc::new(intersections);
             }
             ViewerAction::SetFileName(name) => {
                 next.file_name = Some(name);
             }
+            ViewerAction::SetContentHash(hash) => {
+                // Edits belong to the model they were made on
+                if next.content_hash.as_ref() != Some(&hash) {
+                    next.changes = ChangeSet::default();
+                }
+                next.content_hash = Some(hash);
+            }
+            ViewerAction::GeometryAvailable => {
+                next.has_geometry = true;
+            }
             ViewerAction::ClearData => {
                 next.entities.clear();
                 next.storeys.clear();
                 next.spatial_tree = None;
+                next.systems = Rc::default();
+                next.grid_intersections = Rc::default();
                 next.expanded_nodes.clear();
                 next.file_name = None;
+                next.content_hash = None;
+                next.has_geometry = false;
                 next.selected_ids.clear();
+                next.pinned_id = None;
                 next.hidden_ids.clear();
                 next.isolated_ids = None;
+                next.plan.storey = None;
+                next.camera_path = CameraPathState::default();
                 next.measurements.clear();
+                next.annotations.clear();
+                next.pending_annotation = None;
+                next.views.clear();
+                next.bcf_topics = Rc::default();
+                next.thumbnails = Rc::default();
+                next.diagnostics = Rc::default();
+                next.validation = Rc::default();
+                next.load_profile = None;
+                next.diff = None;
+                // Modes that only exist for the model being cleared
+                if matches!(next.color_mode, ColorMode::Diff | ColorMode::System) {
+                    next.color_mode = ColorMode::Model;
+                }
+                next.show_diagnostics_panel = false;
+                next.changes = ChangeSet::default();
             }
 
             // Tree UI
@@ -390,6 +1667,11 @@ impl Reducible for ViewerState {
                     next.expanded_nodes.insert(tree.id);
                 }
             }
+            ViewerAction::RevealNode(id) => {
+                if let Some(path) = next.spatial_tree.as_ref().and_then(|t| t.ancestors_of(id)) {
+                    next.expanded_nodes.extend(path);
+                }
+            }
 
             // Selection
             ViewerAction::Select(id) => {
@@ -409,12 +1691,21 @@ impl Reducible for ViewerState {
                     next.selected_ids.insert(id);
                 }
             }
+            ViewerAction::SetSelection(ids) => {
+                next.selected_ids = ids;
+            }
             ViewerAction::ClearSelection => {
                 next.selected_ids.clear();
             }
+            ViewerAction::SelectGlobalIds(global_ids) => {
+                next.selected_ids = next.resolve_global_ids(&global_ids).into_iter().collect();
+            }
             ViewerAction::SetHovered(id) => {
                 next.hovered_id = id;
             }
+            ViewerAction::SetPinned(id) => {
+                next.pinned_id = id;
+            }
 
             // Visibility
             ViewerAction::HideEntity(id) => {
@@ -423,6 +1714,12 @@ impl Reducible for ViewerState {
             ViewerAction::ShowEntity(id) => {
                 next.hidden_ids.remove(&id);
             }
+            ViewerAction::HideEntities(ids) => {
+                next.hidden_ids.extend(ids);
+            }
+            ViewerAction::ShowEntities(ids) => {
+                next.hidden_ids.retain(|id| !ids.contains(id));
+            }
             ViewerAction::ToggleVisibility(id) => {
                 if next.hidden_ids.contains(&id) {
                     next.hidden_ids.remove(&id);
@@ -438,6 +1735,14 @@ impl Reducible for ViewerState {
             ViewerAction::IsolateEntities(ids) => {
                 next.isolated_ids = Some(ids);
             }
+            ViewerAction::HideGlobalIds(global_ids) => {
+                let ids = next.resolve_global_ids(&global_ids);
+                next.hidden_ids.extend(ids);
+            }
+            ViewerAction::IsolateGlobalIds(global_ids) => {
+                next.isolated_ids =
+                    Some(next.resolve_global_ids(&global_ids).into_iter().collect());
+            }
             ViewerAction::ShowAll => {
                 next.hidden_ids.clear();
                 next.isolated_ids = None;
@@ -456,6 +1761,9 @@ impl Reducible for ViewerState {
                     Theme::Dark => Theme::Light,
                 };
             }
+            ViewerAction::ToggleUnitSystem => {
+                next.unit_system = next.unit_system.toggled();
+            }
             ViewerAction::SetLeftPanelCollapsed(collapsed) => {
                 next.left_panel_collapsed = collapsed;
             }
@@ -465,19 +1773,178 @@ impl Reducible for ViewerState {
             ViewerAction::ToggleShortcutsDialog => {
                 next.show_shortcuts_dialog = !next.show_shortcuts_dialog;
             }
+            ViewerAction::ToggleStatisticsDialog => {
+                next.show_statistics_dialog = !next.show_statistics_dialog;
+            }
+            ViewerAction::ToggleDiagnosticsPanel => {
+                next.show_diagnostics_panel = !next.show_diagnostics_panel;
+            }
+            ViewerAction::SetShadowQuality(quality) => {
+                next.shadow_quality = quality;
+            }
+            ViewerAction::SetAmbientOcclusion(quality) => {
+                next.ambient_occlusion = quality;
+            }
+            ViewerAction::SetOrbitAroundCursor(enabled) => {
+                next.orbit_around_cursor = enabled;
+            }
+            ViewerAction::SetZoomToCursor(enabled) => {
+                next.zoom_to_cursor = enabled;
+            }
+            ViewerAction::SetGeometrySettings(settings) => {
+                next.geometry = settings;
+            }
+            ViewerAction::ToggleOverlay(layer) => {
+                next.overlay.toggle(layer);
+            }
+            ViewerAction::ToggleXray => {
+                next.xray = match next.xray {
+                    Some(_) => None,
+                    None => Some(XrayFocus::Selection),
+                };
+            }
+            ViewerAction::SetXray(focus) => {
+                next.xray = focus;
+            }
+            ViewerAction::ToggleSpaces => {
+                next.spaces.visible = !next.spaces.visible;
+            }
+            ViewerAction::ToggleStoreySpaces(storey) => {
+                let hidden = &mut next.spaces.hidden_storeys;
+                match hidden.iter().position(|s| *s == storey) {
+                    Some(index) => {
+                        hidden.remove(index);
+                    }
+                    None => hidden.push(storey),
+                }
+            }
+            ViewerAction::SetExplodeMode(mode) => {
+                next.explode.mode = mode;
+            }
+            ViewerAction::SetExplodeFactor(factor) => {
+                next.explode.factor = factor.max(0.0);
+            }
+            ViewerAction::SetColorMode(mode) => {
+                next.color_mode = mode;
+            }
 
-            // Section plane
-            ViewerAction::SetSectionEnabled(enabled) => {
-                next.section_plane.enabled = enabled;
+            // Section planes
+            ViewerAction::AddSectionPlane => {
+                if next.section.planes.len() < MAX_SECTION_PLANES {
+                    next.section.planes.push(SectionPlaneState::default());
+                }
             }
-            ViewerAction::SetSectionAxis(axis) => {
-                next.section_plane.axis = axis;
+            ViewerAction::RemoveSectionPlane(index) => {
+                if index < next.section.planes.len() {
+                    next.section.planes.remove(index);
+                }
+            }
+            ViewerAction::SetSectionEnabled(index, enabled) => {
+                if let Some(plane) = next.section.planes.get_mut(index) {
+                    plane.enabled = enabled;
+                }
+            }
+            ViewerAction::SetSectionAxis(index, axis) => {
+                if let Some(plane) = next.section.planes.get_mut(index) {
+                    plane.axis = axis;
+                }
+            }
+            ViewerAction::SetSectionPosition(index, position) => {
+                if let Some(plane) = next.section.planes.get_mut(index) {
+                    plane.position = position.clamp(0.0, 1.0);
+                }
+            }
+            ViewerAction::ToggleSectionFlip(index) => {
+                if let Some(plane) = next.section.planes.get_mut(index) {
+                    plane.flipped = !plane.flipped;
+                }
             }
-            ViewerAction::SetSectionPosition(position) => {
-                next.section_plane.position = position.clamp(0.0, 1.0);
+            ViewerAction::SetSectionBoxEnabled(enabled) => {
+                next.section.section_box.enabled = enabled;
+            }
+            ViewerAction::SetSectionBoxRange { axis, min, max } => {
+                if axis < 3 {
+                    let min = min.clamp(0.0, 1.0);
+                    let section_box = &mut next.section.section_box;
+                    section_box.min[axis] = min;
+                    section_box.max[axis] = max.clamp(min, 1.0);
+                }
+            }
+            ViewerAction::SetSectionCapColor(color) => {
+                next.section.cap_color = color;
+            }
+            ViewerAction::SetStoreySection(storey) => {
+                next.section.storey = storey.map(|storey| StoreySectionState {
+                    storey,
+                    cut_height: next.plan.cut_height,
+                });
+            }
+            ViewerAction::ClearSection => {
+                next.section = SectionState::default();
+            }
+
+            // Storey plan
+            ViewerAction::SetPlanStorey(storey) => {
+                if storey.is_some() || next.storey_filter == next.plan.storey {
+                    next.storey_filter = storey.clone();
+                }
+                next.plan.storey = storey;
+            }
+            ViewerAction::SetPlanCutHeight(height) => {
+                next.plan.cut_height = height.max(0.0);
+                if let Some(section) = next.section.storey.as_mut() {
+                    section.cut_height = next.plan.cut_height;
+                }
+            }
+
+            // Split view
+            ViewerAction::ToggleSplitView => {
+                next.split_view.enabled = !next.split_view.enabled;
             }
-            ViewerAction::ToggleSectionFlip => {
-                next.section_plane.flipped = !next.section_plane.flipped;
+            ViewerAction::ToggleSplitSync => {
+                next.split_view.synchronized = !next.split_view.synchronized;
+            }
+
+            // Thumbnails
+            ViewerAction::ThumbnailsRequested(keys) => {
+                let thumbnails = Rc::make_mut(&mut next.thumbnails);
+                for key in keys {
+                    thumbnails.entry(key).or_insert(None);
+                }
+            }
+            ViewerAction::SetThumbnails(rendered) => {
+                let thumbnails = Rc::make_mut(&mut next.thumbnails);
+                for (key, url) in rendered {
+                    // Thumbnails asked for before the model was cleared are
+                    // dropped
+                    if let Some(thumbnail) = thumbnails.get_mut(&key) {
+                        *thumbnail = url;
+                    }
+                }
+            }
+
+            // Camera path
+            ViewerAction::AddKeyframe(camera) => {
+                next.camera_path.keyframes.push(CameraKeyframeData {
+                    camera,
+                    duration: DEFAULT_KEYFRAME_SECONDS,
+                });
+            }
+            ViewerAction::ClearKeyframes => {
+                next.camera_path.keyframes.clear();
+                next.camera_path.playing = false;
+            }
+            ViewerAction::PlayPath => {
+                if !next.camera_path.keyframes.is_empty() {
+                    next.camera_path.playing = true;
+                    next.camera_path.run = next.camera_path.run.wrapping_add(1);
+                }
+            }
+            ViewerAction::StopPath => {
+                next.camera_path.playing = false;
+            }
+            ViewerAction::ToggleLoopPath => {
+                next.camera_path.looping = !next.camera_path.looping;
             }
 
             // Measurements
@@ -507,20 +1974,156 @@ impl Reducible for ViewerState {
                 next.pending_measure_point = None;
             }
 
+            // Annotations
+            ViewerAction::SetAnnotations(annotations) => {
+                next.annotations = annotations;
+                next.pending_annotation = None;
+            }
+            ViewerAction::BeginAnnotation(anchor) => {
+                next.pending_annotation = Some(anchor);
+            }
+            ViewerAction::CancelAnnotation => {
+                next.pending_annotation = None;
+            }
+            ViewerAction::AddAnnotation(text) => {
+                let text = text.trim();
+                if let (Some(anchor), false) = (next.pending_annotation.take(), text.is_empty()) {
+                    let global_id = anchor
+                        .entity_id
+                        .and_then(|id| next.entities.iter().find(|e| e.id == id))
+                        .and_then(|e| e.global_id.clone());
+                    next.annotations.push(Annotation {
+                        id: next.next_annotation_id(),
+                        text: text.to_string(),
+                        entity_id: anchor.entity_id,
+                        global_id,
+                        point: anchor.point,
+                    });
+                }
+            }
+            ViewerAction::EditAnnotation(id, text) => {
+                let text = text.trim();
+                if !text.is_empty() {
+                    if let Some(annotation) = next.annotations.iter_mut().find(|a| a.id == id) {
+                        annotation.text = text.to_string();
+                    }
+                }
+            }
+            ViewerAction::RemoveAnnotation(id) => {
+                next.annotations.retain(|a| a.id != id);
+            }
+            ViewerAction::ImportAnnotations(annotations) => {
+                for mut annotation in annotations {
+                    // Express IDs differ between files; the GlobalId does not
+                    if let Some(global_id) = &annotation.global_id {
+                        annotation.entity_id = next.entity_by_global_id(global_id).map(|e| e.id);
+                    }
+                    if annotation.entity_id.is_none() && annotation.point.is_none() {
+                        continue;
+                    }
+                    annotation.id = next.next_annotation_id();
+                    next.annotations.push(annotation);
+                }
+            }
+
+            // Named views
+            ViewerAction::SetViews(views) => {
+                next.views = views;
+            }
+            ViewerAction::SaveView(mut view) => {
+                view.name = view.name.trim().to_string();
+                if !view.name.is_empty() {
+                    match next.views.iter_mut().find(|v| v.name == view.name) {
+                        Some(existing) => *existing = view,
+                        None => next.views.push(view),
+                    }
+                }
+            }
+            ViewerAction::ApplyView(name) => {
+                if let Some(view) = next.views.iter().find(|v| v.name == name).cloned() {
+                    next.restore_view(view);
+                }
+            }
+            ViewerAction::DeleteView(name) => {
+                next.views.retain(|v| v.name != name);
+            }
+            ViewerAction::RestoreView(view) => {
+                next.restore_view(view);
+            }
+
+            // BCF topics
+            ViewerAction::ImportBcfTopics(topics) => {
+                let existing = Rc::make_mut(&mut next.bcf_topics);
+                for topic in topics {
+                    match existing.iter_mut().find(|t| t.guid == topic.guid) {
+                        Some(slot) => *slot = topic,
+                        None => existing.push(topic),
+                    }
+                }
+            }
+            ViewerAction::DeleteBcfTopic(guid) => {
+                Rc::make_mut(&mut next.bcf_topics).retain(|t| t.guid != guid);
+            }
+
+            // Editing
+            ViewerAction::ToggleEditing => {
+                next.editing = !next.editing;
+            }
+            ViewerAction::EditProperty {
+                entity,
+                pset,
+                property,
+                value,
+            } => {
+                next.changes
+                    .set_property(entity as u32, &pset, &property, value);
+            }
+            ViewerAction::AddPropertySet {
+                entity,
+                name,
+                property,
+                value,
+            } => {
+                let (name, property) = (name.trim(), property.trim());
+                if !name.is_empty() && !property.is_empty() {
+                    next.changes.add_property_set(
+                        entity as u32,
+                        name,
+                        vec![(property.to_string(), value)],
+                    );
+                }
+            }
+            ViewerAction::DiscardChanges => {
+                next.changes = ChangeSet::default();
+            }
+
             // Search
             ViewerAction::SetSearchQuery(query) => {
                 next.search_query = query;
             }
+            ViewerAction::SetTableColumns(columns) => {
+                next.table_columns = columns;
+            }
         }
 
         Rc::new(next)
     }
 }
 
-/// Hook to use viewer state
+/// Hook to use viewer state, starting from a profile of the device
 #[hook]
 pub fn use_viewer_state() -> UseReducerHandle<ViewerState> {
-    use_reducer(ViewerState::default)
+    use_reducer(|| {
+        let device = DeviceProfile::detect();
+        ViewerState {
+            geometry: GeometrySettings {
+                deflection: device.defaults().deflection,
+                ..GeometrySettings::default()
+            },
+            device: Some(device),
+            ..ViewerState::default()
+        }
+    })
 }
 
 /// Context type for viewer state
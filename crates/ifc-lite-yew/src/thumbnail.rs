@@ -0,0 +1,56 @@
+//! Thumbnails - small isometric renders of storeys and entities
+//!
+//! Bevy renders them offscreen and hands them back as PNG. Each key is asked
+//! for once per model: [`ViewerState::thumbnails`] records what was asked
+//! for and what came back, and is cleared with the model.
+//!
+//! [`ViewerState::thumbnails`]: crate::state::ViewerState::thumbnails
+
+use crate::bridge::{self, ThumbnailRequestData};
+use crate::state::{EntityInfo, ViewerAction, ViewerStateContext};
+
+/// Left out of storey thumbnails: volumes that would cover the elements
+const VOLUME_TYPES: [&str; 2] = ["IFCSPACE", "IFCOPENINGELEMENT"];
+
+pub fn storey_key(storey: &str) -> String {
+    format!("storey:{}", storey)
+}
+
+pub fn entity_key(id: u64) -> String {
+    format!("entity:{}", id)
+}
+
+/// Elements contained in a storey, as drawn in its thumbnail
+pub fn storey_entities(entities: &[EntityInfo], storey: &str) -> Vec<u64> {
+    entities
+        .iter()
+        .filter(|e| e.storey.as_deref() == Some(storey))
+        .filter(|e| {
+            !VOLUME_TYPES
+                .iter()
+                .any(|t| e.entity_type.eq_ignore_ascii_case(t))
+        })
+        .map(|e| e.id)
+        .collect()
+}
+
+/// Ask Bevy for the thumbnails not asked for yet, as (key, entities)
+pub fn request_thumbnails(state: &ViewerStateContext, requests: Vec<(String, Vec<u64>)>) {
+    let requests: Vec<ThumbnailRequestData> = requests
+        .into_iter()
+        .filter(|(key, _)| !state.thumbnails.contains_key(key))
+        .map(|(key, entities)| ThumbnailRequestData { key, entities })
+        .collect();
+    if requests.is_empty() {
+        return;
+    }
+    bridge::request_thumbnails(&requests);
+    state.dispatch(ViewerAction::ThumbnailsRequested(
+        requests.into_iter().map(|r| r.key).collect(),
+    ));
+}
+
+/// Data URL of a rendered thumbnail
+pub fn thumbnail_url(state: &ViewerStateContext, key: &str) -> Option<String> {
+    state.thumbnails.get(key).cloned().flatten()
+}
@@ -1,5 +1,7 @@
 //! Utility functions for the Yew UI
 
+use ifc_lite_core::UnitSystem;
+
 /// Format a number with appropriate units
 pub fn format_distance(meters: f32) -> String {
     if meters >= 1000.0 {
@@ -13,6 +15,12 @@ pub fn format_distance(meters: f32) -> String {
     }
 }
 
+/// Value with a known unit converted to the display unit system;
+/// other units are kept
+pub fn convert_unit(value: f64, unit: &str, system: UnitSystem) -> (f64, &str) {
+    system.convert(value, unit).unwrap_or((value, unit))
+}
+
 /// Format file size
 pub fn format_file_size(bytes: usize) -> String {
     if bytes >= 1_000_000_000 {
@@ -107,6 +115,13 @@ impl Throttle {
     }
 }
 
+/// Copy text to the system clipboard (fire and forget)
+pub fn copy_to_clipboard(text: &str) {
+    if let Some(window) = web_sys::window() {
+        let _ = window.navigator().clipboard().write_text(text);
+    }
+}
+
 /// Get the `file` URL query parameter if present.
 /// Example: `?file=house.ifc` returns `Some("house.ifc")`
 pub fn get_file_param() -> Option<String> {
@@ -120,6 +135,43 @@ pub fn get_file_param() -> Option<String> {
     params.get("file")
 }
 
+/// Get the `model` URL query parameter if present: an IFC file URL,
+/// absolute or relative to the page.
+/// Example: `?model=https://example.com/house.ifc`
+pub fn get_model_param() -> Option<String> {
+    let search = web_sys::window()?.location().search().ok()?;
+    let params = web_sys::UrlSearchParams::new_with_str(&search).ok()?;
+    params.get("model").filter(|model| !model.is_empty())
+}
+
+/// Get the `sample` URL query parameter if present: an id from the sample
+/// manifest.
+/// Example: `?sample=duplex` opens the viewer with the duplex model
+pub fn get_sample_param() -> Option<String> {
+    let search = web_sys::window()?.location().search().ok()?;
+    let params = web_sys::UrlSearchParams::new_with_str(&search).ok()?;
+    params.get("sample").filter(|id| !id.is_empty())
+}
+
+/// GlobalIds in the `select` URL query parameter, comma separated
+/// Example: `?file=house.ifc&select=2O2Fr$t4X7Zf8NOew3FLOH` links to one element
+pub fn get_select_param() -> Vec<String> {
+    let params = web_sys::window()
+        .and_then(|window| window.location().search().ok())
+        .and_then(|search| web_sys::UrlSearchParams::new_with_str(&search).ok());
+    params
+        .and_then(|params| params.get("select"))
+        .map(|select| {
+            select
+                .split(',')
+                .map(str::trim)
+                .filter(|id| !id.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 /// Build the full URL to fetch an IFC file from the server's /ifc directory.
 /// If the file parameter is a relative path, it's resolved relative to /ifc/.
 /// If it's already an absolute URL, it's returned as-is.
@@ -140,22 +192,36 @@ pub fn build_ifc_url(file_param: &str) -> String {
     format!("{}/ifc/{}", base, clean_file)
 }
 
-/// Fetch IFC file content from a URL.
-/// Returns the file content as a String, or an error message.
-pub async fn fetch_ifc_file(url: &str) -> Result<String, String> {
+/// Fetch IFC file content from a URL, streaming the body.
+///
+/// `on_progress` is called with the bytes received so far and the total
+/// from `Content-Length`, when the server sends one. Returns the file
+/// content as a String, or an error message.
+pub async fn fetch_ifc_file(
+    url: &str,
+    on_progress: impl Fn(usize, Option<usize>),
+) -> Result<String, String> {
     use wasm_bindgen::JsCast;
     use wasm_bindgen_futures::JsFuture;
 
     let window = web_sys::window().ok_or("No window object")?;
 
     // Create request
-    let request = web_sys::Request::new_with_str(url)
+    let init = web_sys::RequestInit::new();
+    init.set_mode(web_sys::RequestMode::Cors);
+    let request = web_sys::Request::new_with_str_and_init(url, &init)
         .map_err(|e| format!("Failed to create request: {:?}", e))?;
 
-    // Fetch
+    // Fetch; the browser rejects without details on CORS and network errors
     let resp_value = JsFuture::from(window.fetch_with_request(&request))
         .await
-        .map_err(|e| format!("Fetch failed: {:?}", e))?;
+        .map_err(|_| {
+            format!(
+                "Could not reach {}. The server may be down or may not allow \
+                 cross-origin downloads (CORS).",
+                url
+            )
+        })?;
 
     let resp: web_sys::Response = resp_value
         .dyn_into()
@@ -169,15 +235,107 @@ pub async fn fetch_ifc_file(url: &str) -> Result<String, String> {
         ));
     }
 
-    // Get text body
-    let text_promise = resp
-        .text()
-        .map_err(|e| format!("Failed to get text: {:?}", e))?;
-    let text_value = JsFuture::from(text_promise)
-        .await
-        .map_err(|e| format!("Failed to read response: {:?}", e))?;
+    let total = resp
+        .headers()
+        .get("content-length")
+        .ok()
+        .flatten()
+        .and_then(|length| length.parse::<usize>().ok());
+    let Some(body) = resp.body() else {
+        return Ok(String::new());
+    };
+    let reader: web_sys::ReadableStreamDefaultReader = body
+        .get_reader()
+        .dyn_into()
+        .map_err(|_| "Response body is not readable")?;
 
-    text_value
-        .as_string()
-        .ok_or_else(|| "Response is not a string".to_string())
+    let mut bytes = Vec::with_capacity(total.unwrap_or(0));
+    on_progress(0, total);
+    loop {
+        let chunk = JsFuture::from(reader.read())
+            .await
+            .map_err(|e| format!("Failed to read response: {:?}", e))?;
+        let done = js_sys::Reflect::get(&chunk, &"done".into())
+            .ok()
+            .and_then(|done| done.as_bool())
+            .unwrap_or(true);
+        if done {
+            break;
+        }
+        let value = js_sys::Reflect::get(&chunk, &"value".into())
+            .map_err(|e| format!("Failed to read response: {:?}", e))?;
+        bytes.extend(js_sys::Uint8Array::new(&value).to_vec());
+        on_progress(bytes.len(), total);
+    }
+
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Offer `bytes` to the user as a file download
+pub fn download_file(file_name: &str, mime_type: &str, bytes: &[u8]) {
+    use wasm_bindgen::JsCast;
+
+    let blob = gloo_file::Blob::new_with_options(bytes, Some(mime_type));
+    let url = gloo_file::ObjectUrl::from(blob);
+    let link = web_sys::window()
+        .and_then(|window| window.document())
+        .and_then(|document| document.create_element("a").ok())
+        .and_then(|element| element.dyn_into::<web_sys::HtmlElement>().ok());
+    if let Some(link) = link {
+        let _ = link.set_attribute("href", &url);
+        let _ = link.set_attribute("download", file_name);
+        link.click();
+    }
+    // Revoking the URL right away can cancel the download
+    gloo::timers::callback::Timeout::new(10_000, move || drop(url)).forget();
+}
+
+/// PNG image of what a canvas currently shows
+///
+/// WebGL canvases may read back blank between frames, so callers should
+/// treat the image as a best effort.
+pub fn capture_canvas_png(canvas_id: &str) -> Option<Vec<u8>> {
+    use base64::Engine;
+    use wasm_bindgen::JsCast;
+
+    let canvas = web_sys::window()?
+        .document()?
+        .get_element_by_id(canvas_id)?
+        .dyn_into::<web_sys::HtmlCanvasElement>()
+        .ok()?;
+    let data_url = canvas.to_data_url_with_type("image/png").ok()?;
+    let (_, encoded) = data_url.split_once("base64,")?;
+    base64::engine::general_purpose::STANDARD
+        .decode(encoded)
+        .ok()
+}
+
+/// Data URL of a PNG image, for `<img src>`
+pub fn png_data_url(png: &[u8]) -> String {
+    use base64::Engine;
+
+    format!(
+        "data:image/png;base64,{}",
+        base64::engine::general_purpose::STANDARD.encode(png)
+    )
+}
+
+/// File name made from free text, e.g. a view name
+pub fn file_stem(name: &str) -> String {
+    let stem: String = name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if stem.is_empty() {
+        "viewpoint".to_string()
+    } else {
+        stem
+    }
 }
@@ -0,0 +1,302 @@
+//! Viewpoints as BCF visualization info
+//!
+//! Converts between [`NamedView`] and [`BcfViewpoint`]. Views keep the orbit
+//! camera Bevy uses (Y up) and section planes relative to the model bounds;
+//! BCF wants an eye point and absolute clipping planes in IFC coordinates
+//! (Z up), and names components by GlobalId rather than express id. The
+//! bounds come from Bevy, so planes are only exchanged once a model is shown.
+
+use crate::bridge::{CameraData, SceneBoundsData};
+use crate::state::{
+    EntityInfo, NamedView, SectionAxis, SectionPlaneState, SectionState, MAX_SECTION_PLANES,
+};
+use ifc_lite_core::{BcfCamera, BcfClippingPlane, BcfProjection, BcfViewpoint};
+use std::collections::{HashMap, HashSet};
+
+/// Vertical field of view of Bevy's camera, in degrees
+const FIELD_OF_VIEW: f64 = 45.0;
+
+type Vec3 = [f64; 3];
+
+/// Bevy (Y-up) to IFC (Z-up) coordinates
+fn world_to_ifc([x, y, z]: Vec3) -> Vec3 {
+    [x, -z, y]
+}
+
+/// IFC (Z-up) to Bevy (Y-up) coordinates
+fn ifc_to_world([x, y, z]: Vec3) -> Vec3 {
+    [x, z, -y]
+}
+
+fn sub(a: Vec3, b: Vec3) -> Vec3 {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: Vec3, b: Vec3) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn normalize(v: Vec3) -> Option<Vec3> {
+    let length = dot(v, v).sqrt();
+    (length > 1e-9).then(|| [v[0] / length, v[1] / length, v[2] / length])
+}
+
+/// Fresh random GUID for a viewpoint or topic
+pub fn new_guid() -> String {
+    let mut bits = 0u128;
+    for _ in 0..4 {
+        bits = (bits << 32) | (js_sys::Math::random() * u32::MAX as f64) as u128;
+    }
+    ifc_lite_core::bcf::format_guid(bits)
+}
+
+/// Eye point and orientation of Bevy's orbit camera, in IFC coordinates
+fn camera_to_bcf(camera: &CameraData) -> BcfCamera {
+    let (azimuth, elevation) = (camera.azimuth as f64, camera.elevation as f64);
+    // Same spherical convention as Bevy's CameraController::get_position
+    let offset = [
+        elevation.cos() * azimuth.sin(),
+        elevation.sin(),
+        elevation.cos() * azimuth.cos(),
+    ];
+    let target = camera.target;
+    let distance = camera.distance as f64;
+    let position = [
+        target[0] + offset[0] * distance,
+        target[1] + offset[1] * distance,
+        target[2] + offset[2] * distance,
+    ];
+    // Up is perpendicular to the view direction, tilted towards world up
+    let up = [
+        -elevation.sin() * azimuth.sin(),
+        elevation.cos(),
+        -elevation.sin() * azimuth.cos(),
+    ];
+    BcfCamera {
+        position: world_to_ifc(position),
+        direction: world_to_ifc(offset.map(|v| -v)),
+        up: world_to_ifc(up),
+        projection: if camera.orthographic {
+            // Bevy's orthographic view is as high as the perspective one at the target
+            BcfProjection::Orthogonal {
+                view_to_world_scale: 2.0 * distance * (FIELD_OF_VIEW.to_radians() / 2.0).tan(),
+            }
+        } else {
+            BcfProjection::Perspective {
+                field_of_view: FIELD_OF_VIEW,
+            }
+        },
+    }
+}
+
+/// Orbit camera looking along a BCF camera
+///
+/// The orbit target is put where the view direction passes the model
+/// centre, so orbiting afterwards turns around the model.
+fn camera_from_bcf(camera: &BcfCamera, bounds: Option<&SceneBoundsData>) -> Option<CameraData> {
+    let position = ifc_to_world(camera.position);
+    let direction = normalize(ifc_to_world(camera.direction))?;
+    let distance = bounds
+        .map(|b| {
+            let (min, max) = (b.min, b.max);
+            let center = ifc_to_world([
+                (min[0] + max[0]) / 2.0,
+                (min[1] + max[1]) / 2.0,
+                (min[2] + max[2]) / 2.0,
+            ]);
+            dot(sub(center, position), direction)
+        })
+        .filter(|d| *d > 1.0)
+        .unwrap_or(10.0);
+    let target = [
+        position[0] + direction[0] * distance,
+        position[1] + direction[1] * distance,
+        position[2] + direction[2] * distance,
+    ];
+    let (distance, orthographic) = match camera.projection {
+        // The view height sets the zoom; keep the target and pull the orbit
+        // distance to match
+        BcfProjection::Orthogonal {
+            view_to_world_scale,
+        } if view_to_world_scale > 0.0 => (
+            view_to_world_scale / (2.0 * (FIELD_OF_VIEW.to_radians() / 2.0).tan()),
+            true,
+        ),
+        _ => (distance, false),
+    };
+    Some(CameraData {
+        azimuth: (-direction[0]).atan2(-direction[2]) as f32,
+        elevation: (-direction[1]).clamp(-1.0, 1.0).asin() as f32,
+        distance: distance as f32,
+        target,
+        orthographic,
+    })
+}
+
+/// Absolute clipping planes of the enabled section planes and box
+fn section_to_bcf(section: &SectionState, bounds: &SceneBoundsData) -> Vec<BcfClippingPlane> {
+    let (min, max) = (bounds.min, bounds.max);
+    let plane = |axis: usize, fraction: f32, outwards: bool| {
+        let mut location = min;
+        location[axis] = min[axis] + (max[axis] - min[axis]) * fraction.clamp(0.0, 1.0) as f64;
+        let mut direction = [0.0; 3];
+        direction[axis] = if outwards { 1.0 } else { -1.0 };
+        BcfClippingPlane {
+            location,
+            direction,
+        }
+    };
+
+    let mut planes: Vec<BcfClippingPlane> = section
+        .planes
+        .iter()
+        .filter(|p| p.enabled)
+        .map(|p| plane(p.axis.index(), p.position, !p.flipped))
+        .collect();
+    let section_box = &section.section_box;
+    if section_box.enabled {
+        for axis in 0..3 {
+            planes.push(plane(axis, section_box.max[axis], true));
+            planes.push(plane(axis, section_box.min[axis], false));
+        }
+    }
+    planes
+}
+
+/// Section planes from BCF clipping planes that run along an IFC axis
+///
+/// Oblique planes have no equivalent here and are dropped.
+fn section_from_bcf(planes: &[BcfClippingPlane], bounds: &SceneBoundsData) -> SectionState {
+    let (min, max) = (bounds.min, bounds.max);
+    let planes = planes
+        .iter()
+        .filter_map(|plane| {
+            let direction = normalize(plane.direction)?;
+            let axis = (0..3).max_by(|a, b| direction[*a].abs().total_cmp(&direction[*b].abs()))?;
+            if direction[axis].abs() < 0.999 {
+                return None;
+            }
+            let size = max[axis] - min[axis];
+            let position = if size > 0.0 {
+                ((plane.location[axis] - min[axis]) / size) as f32
+            } else {
+                0.5
+            };
+            Some(SectionPlaneState {
+                enabled: true,
+                axis: SectionAxis::ALL[axis],
+                position: position.clamp(0.0, 1.0),
+                flipped: direction[axis] < 0.0,
+            })
+        })
+        .take(MAX_SECTION_PLANES)
+        .collect();
+    SectionState {
+        planes,
+        ..SectionState::default()
+    }
+}
+
+/// BCF visualization info of a viewpoint
+pub fn to_bcf(
+    view: &NamedView,
+    entities: &[EntityInfo],
+    bounds: Option<&SceneBoundsData>,
+    guid: String,
+) -> BcfViewpoint {
+    let hidden: HashSet<u64> = view.hidden_ids.iter().copied().collect();
+    let isolated: Option<HashSet<u64>> = view
+        .isolated_ids
+        .as_ref()
+        .map(|ids| ids.iter().copied().collect());
+    let is_visible = |entity: &EntityInfo| {
+        !hidden.contains(&entity.id)
+            && isolated.as_ref().is_none_or(|ids| ids.contains(&entity.id))
+            && view
+                .storey_filter
+                .as_deref()
+                .is_none_or(|storey| entity.is_on_storey(storey))
+    };
+
+    // List whichever of visible and hidden is shorter as the exceptions
+    let (visible, invisible): (Vec<&EntityInfo>, Vec<&EntityInfo>) = entities
+        .iter()
+        .filter(|e| e.global_id.is_some())
+        .partition(|e| is_visible(e));
+    let default_visibility = visible.len() >= invisible.len();
+    let exceptions = if default_visibility {
+        invisible
+    } else {
+        visible
+    };
+    let global_ids = |entities: Vec<&EntityInfo>| {
+        entities
+            .into_iter()
+            .filter_map(|e| e.global_id.clone())
+            .collect()
+    };
+
+    let selected: HashSet<u64> = view.selected_ids.iter().copied().collect();
+    BcfViewpoint {
+        guid,
+        camera: view.camera.as_ref().map(camera_to_bcf),
+        clipping_planes: bounds
+            .map(|bounds| section_to_bcf(&view.section, bounds))
+            .unwrap_or_default(),
+        selection: global_ids(
+            entities
+                .iter()
+                .filter(|e| selected.contains(&e.id))
+                .collect(),
+        ),
+        default_visibility,
+        exceptions: global_ids(exceptions),
+    }
+}
+
+/// Viewpoint from BCF visualization info
+///
+/// Components that are not in the loaded model are ignored.
+pub fn from_bcf(
+    name: String,
+    viewpoint: &BcfViewpoint,
+    entities: &[EntityInfo],
+    bounds: Option<&SceneBoundsData>,
+) -> NamedView {
+    let ids_by_guid: HashMap<&str, u64> = entities
+        .iter()
+        .filter_map(|e| Some((e.global_id.as_deref()?, e.id)))
+        .collect();
+    let ids = |guids: &[String]| -> Vec<u64> {
+        let mut ids: Vec<u64> = guids
+            .iter()
+            .filter_map(|guid| ids_by_guid.get(guid.as_str()).copied())
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    };
+
+    let exceptions = ids(&viewpoint.exceptions);
+    let (hidden_ids, isolated_ids) = if viewpoint.default_visibility {
+        (exceptions, None)
+    } else {
+        (Vec::new(), Some(exceptions))
+    };
+
+    NamedView {
+        name,
+        camera: viewpoint
+            .camera
+            .as_ref()
+            .and_then(|camera| camera_from_bcf(camera, bounds)),
+        selected_ids: ids(&viewpoint.selection),
+        section: bounds
+            .map(|bounds| section_from_bcf(&viewpoint.clipping_planes, bounds))
+            .unwrap_or_default(),
+        section_plane: None,
+        hidden_ids,
+        isolated_ids,
+        storey_filter: None,
+    }
+}
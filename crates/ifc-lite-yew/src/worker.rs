@@ -0,0 +1,346 @@
+//! Background parsing - runs the IFC pipeline in a web worker
+//!
+//! The viewer ships a second wasm binary (`ifc_lite_worker`) that registers
+//! [`ParseWorker`]. The UI posts the file content to it and receives progress
+//! and geometry batches while it keeps rendering. `?worker=0` in the URL
+//! parses on the main thread instead, as does every load once the worker
+//! failed to answer within `WORKER_START_TIMEOUT_MS`. Content that was
+//! loaded before comes from [`crate::cache`] without parsing at all.
+//! [`load_ifc_url`] downloads a remote file first.
+
+use crate::bridge::{self, GeometryData};
+use crate::components::{
+    apply_cached_model, apply_processed_model, parse_and_process_ifc, process_ifc,
+};
+use crate::components::{ProcessEvent, ProcessedModel};
+use crate::state::{GeometrySettings, Progress, ViewerAction, ViewerStateContext};
+use crate::utils::{fetch_ifc_file, format_file_size};
+use gloo::timers::callback::Timeout;
+use gloo::worker::{HandlerId, Registrable, Spawnable, Worker, WorkerBridge, WorkerScope};
+use serde::{Deserialize, Serialize};
+use std::cell::{Cell, RefCell};
+use wasm_bindgen_futures::spawn_local;
+
+/// Script emitted by trunk for the worker binary, next to index.html
+const WORKER_SCRIPT: &str = "ifc_lite_worker.js";
+
+/// Time for the worker to start and report progress before the load
+/// falls back to the main thread
+const WORKER_START_TIMEOUT_MS: u32 = 20_000;
+
+/// Request to parse one file
+#[derive(Serialize, Deserialize)]
+pub struct ParseRequest {
+    /// Echoed in every reply so stale results can be dropped
+    pub request_id: u32,
+    pub content: String,
+    /// Meshes per geometry batch
+    pub batch_size: usize,
+    pub geometry: GeometrySettings,
+}
+
+/// Reply stream for a [`ParseRequest`]
+#[derive(Serialize, Deserialize)]
+pub enum ParseEvent {
+    Progress(Progress),
+    Geometry(Vec<GeometryData>),
+    Warning(String),
+    Done(Box<ProcessedModel>),
+    Failed(String),
+}
+
+/// Worker running [`process_ifc`]
+pub struct ParseWorker;
+
+impl Worker for ParseWorker {
+    type Message = ();
+    type Input = ParseRequest;
+    type Output = (u32, ParseEvent);
+
+    fn create(_scope: &WorkerScope<Self>) -> Self {
+        Self
+    }
+
+    fn update(&mut self, _scope: &WorkerScope<Self>, _msg: Self::Message) {}
+
+    fn received(&mut self, scope: &WorkerScope<Self>, msg: Self::Input, id: HandlerId) {
+        let request_id = msg.request_id;
+        let result = process_ifc(&msg.content, msg.batch_size, &msg.geometry, &mut |event| {
+            let event = match event {
+                ProcessEvent::Progress(progress) => ParseEvent::Progress(progress),
+                ProcessEvent::Geometry(batch) => ParseEvent::Geometry(batch),
+                ProcessEvent::Warning(warning) => ParseEvent::Warning(warning),
+            };
+            scope.respond(id, (request_id, event));
+        });
+        let event = match result {
+            Ok(model) => ParseEvent::Done(Box::new(model)),
+            Err(e) => ParseEvent::Failed(e),
+        };
+        scope.respond(id, (request_id, event));
+    }
+}
+
+/// Worker binary entry point
+pub fn register_worker() {
+    ParseWorker::registrar().register();
+}
+
+/// Load in progress on the UI side
+struct PendingLoad {
+    request_id: u32,
+    state: ViewerStateContext,
+    geometry: Vec<GeometryData>,
+    on_done: Box<dyn FnOnce(Result<(), String>)>,
+    /// Kept for parsing on the main thread until the worker's first event
+    content: Option<String>,
+    /// Fires if that first event does not arrive in time
+    watchdog: Option<Timeout>,
+}
+
+thread_local! {
+    static WORKER: RefCell<Option<WorkerBridge<ParseWorker>>> = const { RefCell::new(None) };
+    /// Set once the worker failed to start; later loads stay on the main thread
+    static WORKER_FAILED: Cell<bool> = const { Cell::new(false) };
+    static PENDING: RefCell<Option<PendingLoad>> = const { RefCell::new(None) };
+    static NEXT_REQUEST: Cell<u32> = const { Cell::new(0) };
+}
+
+/// Parse IFC content off the main thread and apply it to the viewer
+///
+/// `on_done` runs after the model has been handed to Bevy and the UI state.
+/// A newer call supersedes an unfinished one.
+pub fn load_ifc(
+    content: String,
+    state: ViewerStateContext,
+    on_done: impl FnOnce(Result<(), String>) + 'static,
+) {
+    let request_id = NEXT_REQUEST.with(|n| {
+        n.set(n.get().wrapping_add(1));
+        n.get()
+    });
+    crate::edits::set_source(&content);
+
+    spawn_local(async move {
+        let cached = crate::cache::load(&crate::cache::key(&content, &state.geometry)).await;
+        if NEXT_REQUEST.with(Cell::get) != request_id {
+            return;
+        }
+        if let Some((model, geometry)) = cached {
+            apply_cached_model(model, &geometry, &state);
+            on_done(Ok(()));
+        } else if worker_enabled() {
+            parse_in_worker(request_id, content, state, Box::new(on_done));
+        } else {
+            on_done(parse_and_process_ifc(&content, &state));
+        }
+    });
+}
+
+/// Download an IFC file and load it as [`load_ifc`] does
+///
+/// The download fills the first tenth of the progress bar; failures,
+/// including CORS rejections, end up in the error banner.
+pub fn load_ifc_url(url: String, state: ViewerStateContext) {
+    let file_name = url
+        .split(['?', '#'])
+        .next()
+        .and_then(|path| path.rsplit('/').next())
+        .filter(|name| !name.is_empty())
+        .unwrap_or("model.ifc")
+        .to_string();
+    bridge::log(&format!("[Yew] Loading IFC from URL: {}", url));
+
+    state.dispatch(ViewerAction::ClearError);
+    state.dispatch(ViewerAction::SetFileName(file_name));
+    state.dispatch(ViewerAction::SetLoading(true));
+    state.dispatch(ViewerAction::SetProgress(Progress {
+        phase: "Downloading".to_string(),
+        percent: 0.0,
+    }));
+
+    spawn_local(async move {
+        let progress_state = state.clone();
+        let last_percent = Cell::new(-1.0f32);
+        let fetched = fetch_ifc_file(&url, move |received, total| {
+            let (phase, percent) = match total.filter(|&total| total > 0) {
+                Some(total) => (
+                    format!(
+                        "Downloading {} of {}",
+                        format_file_size(received),
+                        format_file_size(total)
+                    ),
+                    (received as f32 / total as f32 * 10.0).min(10.0),
+                ),
+                None => (format!("Downloading {}", format_file_size(received)), 0.0),
+            };
+            // Chunks arrive far more often than the bar can visibly move
+            if (percent - last_percent.get()).abs() >= 0.1 || total.is_none() {
+                last_percent.set(percent);
+                progress_state.dispatch(ViewerAction::SetProgress(Progress { phase, percent }));
+            }
+        })
+        .await;
+
+        let content = match fetched {
+            Ok(content) => content,
+            Err(e) => {
+                bridge::log_error(&format!("[Yew] Failed to fetch IFC: {}", e));
+                state.dispatch(ViewerAction::SetError(format!(
+                    "Failed to load file: {}",
+                    e
+                )));
+                return;
+            }
+        };
+        bridge::log(&format!("[Yew] Fetched {} bytes", content.len()));
+        state.dispatch(ViewerAction::SetProgress(Progress {
+            phase: "Parsing IFC".to_string(),
+            percent: 10.0,
+        }));
+
+        let state_done = state.clone();
+        load_ifc(content, state, move |result| match result {
+            Ok(_) => {
+                bridge::log("[Yew] IFC file processed successfully");
+                state_done.dispatch(ViewerAction::SetLoading(false));
+                state_done.dispatch(ViewerAction::ClearProgress);
+                bridge::save_camera_cmd(&bridge::CameraCommand {
+                    cmd: "fit_all".to_string(),
+                    mode: None,
+                    view: None,
+                });
+            }
+            Err(e) => {
+                bridge::log_error(&format!("[Yew] Failed to process IFC: {}", e));
+                state_done.dispatch(ViewerAction::SetError(e));
+            }
+        });
+    });
+}
+
+fn parse_in_worker(
+    request_id: u32,
+    content: String,
+    state: ViewerStateContext,
+    on_done: Box<dyn FnOnce(Result<(), String>)>,
+) {
+    let batch_size = state.device_defaults().geometry_batch_size;
+    let geometry = state.geometry;
+    bridge::begin_geometry_stream();
+    PENDING.with(|p| {
+        *p.borrow_mut() = Some(PendingLoad {
+            request_id,
+            state,
+            geometry: Vec::new(),
+            on_done,
+            content: Some(content.clone()),
+            watchdog: Some(Timeout::new(WORKER_START_TIMEOUT_MS, move || {
+                worker_failed(request_id)
+            })),
+        })
+    });
+
+    WORKER.with(|w| {
+        w.borrow_mut()
+            .get_or_insert_with(|| {
+                let url = worker_url();
+                bridge::log(&format!("[Worker] Spawning parse worker from {}", url));
+                ParseWorker::spawner()
+                    .callback(|(request_id, event)| handle_event(request_id, event))
+                    .spawn(&url)
+            })
+            .send(ParseRequest {
+                request_id,
+                content,
+                batch_size,
+                geometry,
+            });
+    });
+}
+
+fn handle_event(request_id: u32, event: ParseEvent) {
+    let finished = PENDING.with(|p| {
+        let mut pending = p.borrow_mut();
+        let load = pending.as_mut().filter(|l| l.request_id == request_id)?;
+        // The worker is up; dropping the watchdog cancels it
+        load.content = None;
+        load.watchdog = None;
+        match event {
+            ParseEvent::Progress(progress) => {
+                load.state.dispatch(ViewerAction::SetProgress(progress));
+                None
+            }
+            ParseEvent::Geometry(batch) => {
+                if load.geometry.is_empty() && !batch.is_empty() {
+                    load.state.dispatch(ViewerAction::GeometryAvailable);
+                }
+                bridge::append_geometry_chunk(&batch);
+                load.geometry.extend(batch);
+                None
+            }
+            ParseEvent::Warning(warning) => {
+                load.state.dispatch(ViewerAction::SetWarning(warning));
+                None
+            }
+            ParseEvent::Done(model) => pending.take().map(|l| (l, Ok(*model))),
+            ParseEvent::Failed(e) => pending.take().map(|l| (l, Err(e))),
+        }
+    });
+
+    // Applied outside the borrow: dispatching may re-enter load_ifc
+    if let Some((load, result)) = finished {
+        let result = result.map(|model| apply_processed_model(model, &load.geometry, &load.state));
+        (load.on_done)(result);
+    }
+}
+
+/// Parse on the main thread when the worker never answered
+///
+/// gloo does not report failed spawns (a missing script, a worker that
+/// panics while starting), so the only sign is the silence.
+fn worker_failed(request_id: u32) {
+    let load = PENDING.with(|p| {
+        let mut pending = p.borrow_mut();
+        let waiting = pending
+            .as_ref()
+            .is_some_and(|l| l.request_id == request_id && l.content.is_some());
+        if waiting {
+            pending.take()
+        } else {
+            None
+        }
+    });
+    let Some(mut load) = load else {
+        return;
+    };
+    bridge::log_error("[Worker] Parse worker did not respond, parsing on the main thread");
+    WORKER_FAILED.with(|f| f.set(true));
+    WORKER.with(|w| w.borrow_mut().take());
+    // Runs inside the watchdog's own callback, which must outlive the call
+    if let Some(watchdog) = load.watchdog.take() {
+        watchdog.forget();
+    }
+    let content = load.content.take().unwrap_or_default();
+    (load.on_done)(parse_and_process_ifc(&content, &load.state));
+}
+
+/// Worker script resolved against the document base, so deployments
+/// under a sub-path find it
+fn worker_url() -> String {
+    web_sys::window()
+        .and_then(|w| w.document())
+        .and_then(|d| d.base_uri().ok().flatten())
+        .and_then(|base| web_sys::Url::new_with_base(WORKER_SCRIPT, &base).ok())
+        .map(|url| url.href())
+        .unwrap_or_else(|| WORKER_SCRIPT.to_string())
+}
+
+/// Workers are on unless the page was opened with `?worker=0` or the
+/// worker failed to start
+fn worker_enabled() -> bool {
+    !WORKER_FAILED.with(Cell::get)
+        && web_sys::window()
+            .and_then(|w| w.location().search().ok())
+            .is_none_or(|search| !search.contains("worker=0"))
+}
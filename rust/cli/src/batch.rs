@@ -0,0 +1,563 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Batch Processing - Analyse every IFC file in a folder
+//!
+//! Files are processed in parallel on a rayon pool. Each file yields a
+//! [`FileReport`]; a file that cannot be read or panics during processing
+//! is reported as failed instead of aborting the run.
+
+use crate::error::{Error, Result};
+use crate::model::{Issue, ProcessedModel, QuantityTotal, Severity};
+use crate::{glb, thumbnail};
+use ifc_lite_core::DiagnosticKind;
+use rayon::prelude::*;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// Batch run configuration
+#[derive(Debug, Clone)]
+pub struct BatchOptions {
+    /// Descend into subfolders
+    pub recursive: bool,
+    /// Write a GLB next to each report entry
+    pub export_glb: bool,
+    /// Write a PNG thumbnail for each file
+    pub export_thumbnails: bool,
+    /// Thumbnail edge length in pixels
+    pub thumbnail_size: u32,
+    /// Folder for exported files (defaults to the input file's folder).
+    /// Subfolders of the inputs are kept below it.
+    pub output_dir: Option<PathBuf>,
+    /// Worker threads (defaults to one per core)
+    pub threads: Option<usize>,
+    /// Skip geometry entirely (statistics and quantities only)
+    pub skip_geometry: bool,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            recursive: false,
+            export_glb: false,
+            export_thumbnails: false,
+            thumbnail_size: 256,
+            output_dir: None,
+            threads: None,
+            skip_geometry: false,
+        }
+    }
+}
+
+/// Result for one file
+#[derive(Debug, Clone, Serialize)]
+pub struct FileReport {
+    pub path: PathBuf,
+    pub file_size: u64,
+    pub schema: Option<String>,
+    pub entity_count: usize,
+    pub type_counts: BTreeMap<String, usize>,
+    pub element_count: usize,
+    pub meshed_count: usize,
+    pub triangle_count: usize,
+    pub vertex_count: usize,
+    /// Model bounds in metres (IFC Z-up)
+    pub bounds: Option<([f32; 3], [f32; 3])>,
+    pub quantities: Vec<QuantityTotal>,
+    pub issues: Vec<Issue>,
+    /// Files written for this model (GLB, thumbnail)
+    pub outputs: Vec<PathBuf>,
+    pub parse_ms: f64,
+    pub geometry_ms: f64,
+    pub total_ms: f64,
+    /// Set when the file could not be processed at all
+    pub error: Option<String>,
+}
+
+impl FileReport {
+    fn failed(path: &Path, error: String) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            file_size: 0,
+            schema: None,
+            entity_count: 0,
+            type_counts: BTreeMap::new(),
+            element_count: 0,
+            meshed_count: 0,
+            triangle_count: 0,
+            vertex_count: 0,
+            bounds: None,
+            quantities: Vec::new(),
+            issues: Vec::new(),
+            outputs: Vec::new(),
+            parse_ms: 0.0,
+            geometry_ms: 0.0,
+            total_ms: 0.0,
+            error: Some(error),
+        }
+    }
+
+    /// Count of issues with the given severity
+    pub fn issue_count(&self, severity: Severity) -> usize {
+        self.issues
+            .iter()
+            .filter(|i| i.severity == severity)
+            .count()
+    }
+
+    /// Whether the file failed or has error-level issues
+    pub fn has_errors(&self) -> bool {
+        self.error.is_some() || self.issue_count(Severity::Error) > 0
+    }
+}
+
+/// Number of most frequent entity types listed by the detailed report
+const TOP_TYPES: usize = 10;
+
+/// Detailed multi-line statistics for one file (`ifc-lite stats`)
+impl fmt::Display for FileReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.path.display())?;
+        if let Some(error) = &self.error {
+            return writeln!(f, "  FAILED: {}", error);
+        }
+
+        writeln!(f, "  Schema:     {}", self.schema.as_deref().unwrap_or("?"))?;
+        writeln!(f, "  Size:       {:.1} KiB", self.file_size as f64 / 1024.0)?;
+        writeln!(
+            f,
+            "  Entities:   {} ({} types)",
+            self.entity_count,
+            self.type_counts.len()
+        )?;
+        writeln!(
+            f,
+            "  Elements:   {} meshed of {}",
+            self.meshed_count, self.element_count
+        )?;
+        writeln!(
+            f,
+            "  Geometry:   {} triangles, {} vertices",
+            self.triangle_count, self.vertex_count
+        )?;
+        if let Some((min, max)) = self.bounds {
+            writeln!(
+                f,
+                "  Extent:     {:.2} x {:.2} x {:.2} m",
+                max[0] - min[0],
+                max[1] - min[1],
+                max[2] - min[2]
+            )?;
+        }
+
+        let mut types: Vec<(&String, &usize)> = self.type_counts.iter().collect();
+        types.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+        writeln!(f, "  Top types:")?;
+        for (name, count) in types.into_iter().take(TOP_TYPES) {
+            writeln!(f, "    {:<32} {}", name, count)?;
+        }
+
+        if !self.quantities.is_empty() {
+            writeln!(f, "  Quantities:")?;
+            for q in &self.quantities {
+                writeln!(
+                    f,
+                    "    {}.{}: {:.3} {} ({} elements)",
+                    q.set, q.name, q.total, q.unit, q.count
+                )?;
+            }
+        }
+
+        writeln!(
+            f,
+            "  Issues:     {} errors, {} warnings",
+            self.issue_count(Severity::Error),
+            self.issue_count(Severity::Warning)
+        )?;
+        writeln!(
+            f,
+            "  Time:       {:.0} ms (parse {:.0} ms, geometry {:.0} ms)",
+            self.total_ms, self.parse_ms, self.geometry_ms
+        )
+    }
+}
+
+/// Combined result of a batch run
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchReport {
+    pub files: Vec<FileReport>,
+    /// Wall-clock time of the whole run
+    pub total_ms: f64,
+}
+
+impl BatchReport {
+    /// Number of files that failed or have error-level issues
+    pub fn failed_count(&self) -> usize {
+        self.files.iter().filter(|f| f.has_errors()).count()
+    }
+
+    /// Entity counts per type summed over all files
+    pub fn type_totals(&self) -> BTreeMap<String, usize> {
+        let mut totals = BTreeMap::new();
+        for (name, count) in self.files.iter().flat_map(|f| &f.type_counts) {
+            *totals.entry(name.clone()).or_default() += count;
+        }
+        totals
+    }
+
+    /// Report as pretty-printed JSON
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// One row per file, tab separated
+    pub fn to_tsv(&self) -> String {
+        let mut out = String::from(
+            "File\tSchema\tEntities\tElements\tMeshed\tTriangles\tErrors\tWarnings\tTime (ms)\tStatus\n",
+        );
+        for file in &self.files {
+            out.push_str(&format!(
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{:.0}\t{}\n",
+                file.path.display(),
+                file.schema.as_deref().unwrap_or("-"),
+                file.entity_count,
+                file.element_count,
+                file.meshed_count,
+                file.triangle_count,
+                file.issue_count(Severity::Error),
+                file.issue_count(Severity::Warning),
+                file.total_ms,
+                file.error.as_deref().unwrap_or("ok"),
+            ));
+        }
+        out
+    }
+}
+
+impl fmt::Display for BatchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for file in &self.files {
+            let status = match &file.error {
+                Some(error) => format!("FAILED: {}", error),
+                None => format!(
+                    "{} entities, {}/{} elements meshed, {} triangles, {} errors, {} warnings",
+                    file.entity_count,
+                    file.meshed_count,
+                    file.element_count,
+                    file.triangle_count,
+                    file.issue_count(Severity::Error),
+                    file.issue_count(Severity::Warning),
+                ),
+            };
+            writeln!(
+                f,
+                "{} [{}] {} ({:.0} ms)",
+                file.path.display(),
+                file.schema.as_deref().unwrap_or("?"),
+                status,
+                file.total_ms
+            )?;
+        }
+
+        let entities: usize = self.files.iter().map(|f| f.entity_count).sum();
+        let triangles: usize = self.files.iter().map(|f| f.triangle_count).sum();
+        writeln!(
+            f,
+            "\n{} files, {} failed, {} entities, {} triangles in {:.1} s",
+            self.files.len(),
+            self.failed_count(),
+            entities,
+            triangles,
+            self.total_ms / 1000.0
+        )
+    }
+}
+
+/// Collect `.ifc` files in a folder, sorted by path
+pub fn find_ifc_files(dir: &Path, recursive: bool) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        let entries = fs::read_dir(&dir).map_err(|e| Error::io(&dir, e))?;
+        for entry in entries {
+            let path = entry.map_err(|e| Error::io(&dir, e))?.path();
+            if path.is_dir() {
+                if recursive {
+                    pending.push(path);
+                }
+            } else if path
+                .extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("ifc"))
+            {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Process every IFC file in a folder
+pub fn process_folder(dir: &Path, options: &BatchOptions) -> Result<BatchReport> {
+    let files = find_ifc_files(dir, options.recursive)?;
+    process_files_in(&files, Some(dir), options)
+}
+
+/// Process a list of IFC files in parallel
+///
+/// Exports keep each file's folder relative to the folder the inputs share.
+pub fn process_files(files: &[PathBuf], options: &BatchOptions) -> Result<BatchReport> {
+    process_files_in(files, common_root(files).as_deref(), options)
+}
+
+fn process_files_in(
+    files: &[PathBuf],
+    root: Option<&Path>,
+    options: &BatchOptions,
+) -> Result<BatchReport> {
+    let start = Instant::now();
+
+    if let Some(dir) = &options.output_dir {
+        fs::create_dir_all(dir).map_err(|e| Error::io(dir, e))?;
+    }
+
+    let run = || -> Vec<FileReport> {
+        files
+            .par_iter()
+            .map(|path| {
+                std::panic::catch_unwind(|| process_file_in(path, root, options)).unwrap_or_else(
+                    |_| FileReport::failed(path, "panicked during processing".to_string()),
+                )
+            })
+            .collect()
+    };
+
+    let reports = match options.threads {
+        Some(threads) => rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()?
+            .install(run),
+        None => run(),
+    };
+
+    Ok(BatchReport {
+        files: reports,
+        total_ms: start.elapsed().as_secs_f64() * 1000.0,
+    })
+}
+
+/// Deepest folder containing all of `files`
+fn common_root(files: &[PathBuf]) -> Option<PathBuf> {
+    let mut parents = files.iter().filter_map(|path| path.parent());
+    let mut root = parents.next()?.to_path_buf();
+    for parent in parents {
+        while !parent.starts_with(&root) {
+            if !root.pop() {
+                return None;
+            }
+        }
+    }
+    Some(root)
+}
+
+/// Where the export of `path` with `extension` goes
+///
+/// Only the last extension is replaced, so `model.v1.ifc` and `model.v2.ifc`
+/// don't both become `model.glb`.
+fn output_path(
+    path: &Path,
+    root: Option<&Path>,
+    options: &BatchOptions,
+    extension: &str,
+) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let parent = path.parent().unwrap_or(Path::new(""));
+    let dir = match &options.output_dir {
+        Some(out_dir) => match root.and_then(|root| parent.strip_prefix(root).ok()) {
+            Some(relative) => out_dir.join(relative),
+            None => out_dir.clone(),
+        },
+        None => parent.to_path_buf(),
+    };
+    dir.join(format!("{stem}.{extension}"))
+}
+
+/// Process one IFC file, writing any requested exports
+pub fn process_file(path: &Path, options: &BatchOptions) -> FileReport {
+    process_file_in(path, path.parent(), options)
+}
+
+/// Process one IFC file, with exports placed relative to `root`
+fn process_file_in(path: &Path, root: Option<&Path>, options: &BatchOptions) -> FileReport {
+    let start = Instant::now();
+
+    let content = match fs::read(path) {
+        Ok(bytes) => String::from_utf8_lossy(&bytes).into_owned(),
+        Err(e) => return FileReport::failed(path, e.to_string()),
+    };
+
+    let needs_geometry = !options.skip_geometry || options.export_glb || options.export_thumbnails;
+    let model = ProcessedModel::from_content(&content, needs_geometry);
+
+    let mut outputs = Vec::new();
+    let mut issues = model.issues.clone();
+
+    let mut export = |extension: &str, write: &dyn Fn(&Path) -> Result<()>| {
+        let target = output_path(path, root, options, extension);
+        let written = match target.parent() {
+            Some(dir) => fs::create_dir_all(dir).map_err(|e| Error::io(dir, e)),
+            None => Ok(()),
+        }
+        .and_then(|()| write(&target));
+        match written {
+            Ok(()) => outputs.push(target),
+            Err(e) => issues.push(Issue {
+                severity: Severity::Error,
+                kind: DiagnosticKind::Output,
+                entity_id: None,
+                entity_type: None,
+                line: None,
+                message: format!("Export to {} failed: {}", target.display(), e),
+            }),
+        }
+    };
+
+    if options.export_glb {
+        export("glb", &|target| {
+            fs::write(target, glb::to_glb(&model.elements)).map_err(|e| Error::io(target, e))
+        });
+    }
+    if options.export_thumbnails {
+        export("png", &|target| {
+            let file = fs::File::create(target).map_err(|e| Error::io(target, e))?;
+            thumbnail::render(&model.elements, options.thumbnail_size)
+                .write_png(std::io::BufWriter::new(file))
+        });
+    }
+
+    FileReport {
+        path: path.to_path_buf(),
+        file_size: content.len() as u64,
+        schema: model.schema.clone(),
+        entity_count: model.entity_count,
+        type_counts: model.type_counts.clone(),
+        element_count: model.element_count,
+        meshed_count: model.elements.len(),
+        triangle_count: model.triangle_count(),
+        vertex_count: model.vertex_count(),
+        bounds: model.bounds(),
+        quantities: model.quantities,
+        issues,
+        outputs,
+        parse_ms: model.parse_time.as_secs_f64() * 1000.0,
+        geometry_ms: model.geometry_time.as_secs_f64() * 1000.0,
+        total_ms: start.elapsed().as_secs_f64() * 1000.0,
+        error: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_folder() {
+        let dir = std::env::temp_dir().join(format!("ifc-lite-batch-{}", std::process::id()));
+        let nested = dir.join("nested");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(
+            dir.join("a.ifc"),
+            include_str!("../../../tests/models/test.ifc"),
+        )
+        .unwrap();
+        fs::write(
+            nested.join("b.IFC"),
+            "DATA;\n#1=IFCWALL('0a',$,$,$,$,$,$,$,$);\n",
+        )
+        .unwrap();
+        fs::write(dir.join("notes.txt"), "not a model").unwrap();
+
+        let options = BatchOptions {
+            recursive: true,
+            export_glb: true,
+            export_thumbnails: true,
+            thumbnail_size: 32,
+            output_dir: Some(dir.join("out")),
+            threads: Some(2),
+            ..Default::default()
+        };
+        let report = process_folder(&dir, &options).unwrap();
+
+        assert_eq!(report.files.len(), 2);
+        let model = &report.files[0];
+        assert!(model.path.ends_with("a.ifc"));
+        assert_eq!(model.meshed_count, 3);
+        assert!(!model.has_errors(), "{:?}", model.issues);
+        assert_eq!(model.outputs.len(), 2);
+        assert!(model.outputs.iter().all(|p| p.exists()));
+
+        // No IfcProject in the second file
+        assert!(report.files[1].has_errors());
+        assert_eq!(report.failed_count(), 1);
+        assert_eq!(report.type_totals().get("IfcWall"), Some(&1));
+        assert!(report.to_json().unwrap().contains("\"entity_count\""));
+        assert_eq!(report.to_tsv().lines().count(), 3);
+
+        let details = model.to_string();
+        assert!(details.contains("Elements:   3 meshed of 3"));
+        assert!(details.contains("IfcCartesianPoint"));
+        assert!(report.files[1].to_string().contains("1 errors"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_export_names_do_not_collide() {
+        let dir = std::env::temp_dir().join(format!("ifc-lite-names-{}", std::process::id()));
+        let files = [
+            dir.join("x").join("model.v1.ifc"),
+            dir.join("x").join("model.v2.ifc"),
+            dir.join("y").join("model.v1.ifc"),
+        ];
+        for file in &files {
+            fs::create_dir_all(file.parent().unwrap()).unwrap();
+            fs::write(file, include_str!("../../../tests/models/test.ifc")).unwrap();
+        }
+
+        let out = dir.join("out");
+        let options = BatchOptions {
+            export_glb: true,
+            output_dir: Some(out.clone()),
+            ..Default::default()
+        };
+        let report = process_files(&files, &options).unwrap();
+
+        let outputs: Vec<_> = report
+            .files
+            .iter()
+            .flat_map(|f| f.outputs.clone())
+            .collect();
+        assert_eq!(
+            outputs,
+            vec![
+                out.join("x").join("model.v1.glb"),
+                out.join("x").join("model.v2.glb"),
+                out.join("y").join("model.v1.glb"),
+            ]
+        );
+        assert!(outputs.iter().all(|p| p.exists()));
+
+        // A single file goes straight into the output folder
+        let single = process_file(&files[2], &options);
+        assert_eq!(single.outputs, vec![out.join("model.v1.glb")]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,95 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Convert - Single-file export for pipelines
+//!
+//! The output format follows the file extension, so `ifc-lite convert
+//! model.ifc --out model.glb` needs no further flags.
+
+use crate::error::{Error, Result};
+use crate::model::ProcessedModel;
+use crate::{glb, thumbnail};
+use std::fs;
+use std::io::BufWriter;
+use std::path::Path;
+
+/// Export format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Binary glTF with one node per element
+    Glb,
+    /// Isometric PNG thumbnail
+    Png,
+}
+
+impl OutputFormat {
+    /// Pick the format from an output file extension
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        match extension.as_deref() {
+            Some("glb") => Ok(Self::Glb),
+            Some("png") => Ok(Self::Png),
+            _ => Err(Error::Usage(format!(
+                "cannot tell the output format of '{}' (use .glb or .png)",
+                path.display()
+            ))),
+        }
+    }
+}
+
+/// Convert one IFC file, returning the processed model for reporting
+///
+/// `image_size` is the edge length of PNG output in pixels.
+pub fn convert_file(input: &Path, output: &Path, image_size: u32) -> Result<ProcessedModel> {
+    let format = OutputFormat::from_path(output)?;
+    let bytes = fs::read(input).map_err(|e| Error::io(input, e))?;
+    let model = ProcessedModel::from_content(&String::from_utf8_lossy(&bytes), true);
+
+    if let Some(parent) = output.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(|e| Error::io(parent, e))?;
+    }
+    match format {
+        OutputFormat::Glb => {
+            fs::write(output, glb::to_glb(&model.elements)).map_err(|e| Error::io(output, e))?
+        }
+        OutputFormat::Png => {
+            let file = fs::File::create(output).map_err(|e| Error::io(output, e))?;
+            thumbnail::render(&model.elements, image_size).write_png(BufWriter::new(file))?;
+        }
+    }
+
+    Ok(model)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert_by_extension() {
+        let dir = std::env::temp_dir().join(format!("ifc-lite-convert-{}", std::process::id()));
+        let input = dir.join("model.ifc");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(&input, include_str!("../../../tests/models/test.ifc")).unwrap();
+
+        let output = dir.join("out").join("model.glb");
+        let model = convert_file(&input, &output, 64).unwrap();
+        assert_eq!(model.elements.len(), 3);
+        assert_eq!(&fs::read(&output).unwrap()[..4], b"glTF");
+
+        let image = dir.join("model.PNG");
+        convert_file(&input, &image, 16).unwrap();
+        assert_eq!(&fs::read(&image).unwrap()[1..4], b"PNG");
+
+        assert!(matches!(
+            convert_file(&input, &dir.join("model.obj"), 16),
+            Err(Error::Usage(_))
+        ));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
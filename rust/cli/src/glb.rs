@@ -0,0 +1,274 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! GLB Export - Binary glTF 2.0 writer for element meshes
+//!
+//! One node and mesh per element, one material per distinct colour. IFC is
+//! Z-up while glTF is Y-up, so positions and normals are rotated on export.
+//! Node `extras` carry the express ID and IFC type for round-tripping.
+
+use crate::model::ElementMesh;
+use serde_json::{json, Value};
+use std::io::{self, Write};
+
+const GLB_MAGIC: u32 = 0x4654_6C67; // "glTF"
+const GLB_VERSION: u32 = 2;
+const CHUNK_JSON: u32 = 0x4E4F_534A; // "JSON"
+const CHUNK_BIN: u32 = 0x004E_4942; // "BIN\0"
+
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+
+/// Encode element meshes as a GLB file in memory
+pub fn to_glb(elements: &[ElementMesh]) -> Vec<u8> {
+    let mut bin: Vec<u8> = Vec::new();
+    let mut buffer_views = Vec::new();
+    let mut accessors = Vec::new();
+    let mut meshes = Vec::new();
+    let mut nodes = Vec::new();
+    let mut materials: Vec<Value> = Vec::new();
+    let mut material_colors: Vec<[u32; 4]> = Vec::new();
+
+    for element in elements {
+        let mesh = &element.mesh;
+        if mesh.is_empty() {
+            continue;
+        }
+
+        // Z-up -> Y-up: (x, y, z) -> (x, z, -y)
+        let positions: Vec<[f32; 3]> = mesh
+            .positions
+            .chunks_exact(3)
+            .map(|p| [p[0], p[2], -p[1]])
+            .collect();
+        let normals: Vec<[f32; 3]> = mesh
+            .normals
+            .chunks_exact(3)
+            .map(|n| [n[0], n[2], -n[1]])
+            .collect();
+
+        let mut min = [f32::MAX; 3];
+        let mut max = [f32::MIN; 3];
+        for p in &positions {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(p[axis]);
+                max[axis] = max[axis].max(p[axis]);
+            }
+        }
+
+        let position_view = push_view(
+            &mut bin,
+            &mut buffer_views,
+            bytes_of(&positions),
+            ARRAY_BUFFER,
+        );
+        let position_accessor = accessors.len();
+        accessors.push(json!({
+            "bufferView": position_view,
+            "componentType": FLOAT,
+            "count": positions.len(),
+            "type": "VEC3",
+            "min": min,
+            "max": max,
+        }));
+
+        let mut attributes = json!({ "POSITION": position_accessor });
+        if normals.len() == positions.len() {
+            let normal_view = push_view(
+                &mut bin,
+                &mut buffer_views,
+                bytes_of(&normals),
+                ARRAY_BUFFER,
+            );
+            attributes["NORMAL"] = json!(accessors.len());
+            accessors.push(json!({
+                "bufferView": normal_view,
+                "componentType": FLOAT,
+                "count": normals.len(),
+                "type": "VEC3",
+            }));
+        }
+
+        let index_bytes: Vec<u8> = mesh.indices.iter().flat_map(|i| i.to_le_bytes()).collect();
+        let index_view = push_view(
+            &mut bin,
+            &mut buffer_views,
+            index_bytes,
+            ELEMENT_ARRAY_BUFFER,
+        );
+        let index_accessor = accessors.len();
+        accessors.push(json!({
+            "bufferView": index_view,
+            "componentType": UNSIGNED_INT,
+            "count": mesh.indices.len(),
+            "type": "SCALAR",
+        }));
+
+        // Share materials between elements of the same colour
+        let color_key = element
+            .color
+            .map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u32);
+        let material = match material_colors.iter().position(|c| *c == color_key) {
+            Some(index) => index,
+            None => {
+                material_colors.push(color_key);
+                let mut material = json!({
+                    "pbrMetallicRoughness": {
+                        "baseColorFactor": element.color,
+                        "metallicFactor": 0.0,
+                        "roughnessFactor": 0.8,
+                    },
+                    "doubleSided": true,
+                });
+                if element.color[3] < 1.0 {
+                    material["alphaMode"] = json!("BLEND");
+                }
+                materials.push(material);
+                materials.len() - 1
+            }
+        };
+
+        meshes.push(json!({
+            "primitives": [{
+                "attributes": attributes,
+                "indices": index_accessor,
+                "material": material,
+            }],
+        }));
+
+        let label = match &element.name {
+            Some(name) => format!("{} #{} {}", element.ifc_type, element.id, name),
+            None => format!("{} #{}", element.ifc_type, element.id),
+        };
+        nodes.push(json!({
+            "name": label,
+            "mesh": meshes.len() - 1,
+            "extras": {
+                "expressID": element.id,
+                "ifcType": element.ifc_type.name(),
+            },
+        }));
+    }
+
+    let mut document = json!({
+        "asset": { "version": "2.0", "generator": "ifc-lite" },
+        "scene": 0,
+        "scenes": [{ "nodes": (0..nodes.len()).collect::<Vec<_>>() }],
+        "nodes": nodes,
+        "meshes": meshes,
+        "materials": materials,
+        "accessors": accessors,
+        "bufferViews": buffer_views,
+    });
+    if !bin.is_empty() {
+        document["buffers"] = json!([{ "byteLength": bin.len() }]);
+    }
+
+    let mut json_chunk = serde_json::to_vec(&document).unwrap_or_default();
+    pad_to_four(&mut json_chunk, b' ');
+    pad_to_four(&mut bin, 0);
+
+    let mut total = 12 + 8 + json_chunk.len();
+    if !bin.is_empty() {
+        total += 8 + bin.len();
+    }
+
+    let mut glb = Vec::with_capacity(total);
+    glb.extend_from_slice(&GLB_MAGIC.to_le_bytes());
+    glb.extend_from_slice(&GLB_VERSION.to_le_bytes());
+    glb.extend_from_slice(&(total as u32).to_le_bytes());
+    glb.extend_from_slice(&(json_chunk.len() as u32).to_le_bytes());
+    glb.extend_from_slice(&CHUNK_JSON.to_le_bytes());
+    glb.extend_from_slice(&json_chunk);
+    if !bin.is_empty() {
+        glb.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+        glb.extend_from_slice(&CHUNK_BIN.to_le_bytes());
+        glb.extend_from_slice(&bin);
+    }
+    glb
+}
+
+/// Write element meshes as GLB
+pub fn write_glb(elements: &[ElementMesh], writer: &mut impl Write) -> io::Result<()> {
+    writer.write_all(&to_glb(elements))
+}
+
+/// Append data to the binary chunk and return its buffer view index
+fn push_view(bin: &mut Vec<u8>, views: &mut Vec<Value>, data: Vec<u8>, target: u32) -> usize {
+    pad_to_four(bin, 0);
+    views.push(json!({
+        "buffer": 0,
+        "byteOffset": bin.len(),
+        "byteLength": data.len(),
+        "target": target,
+    }));
+    bin.extend_from_slice(&data);
+    views.len() - 1
+}
+
+fn bytes_of(vectors: &[[f32; 3]]) -> Vec<u8> {
+    vectors
+        .iter()
+        .flatten()
+        .flat_map(|v| v.to_le_bytes())
+        .collect()
+}
+
+fn pad_to_four(data: &mut Vec<u8>, fill: u8) {
+    while !data.len().is_multiple_of(4) {
+        data.push(fill);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ifc_lite_core::IfcType;
+    use ifc_lite_geometry::Mesh;
+
+    fn read_u32(data: &[u8], offset: usize) -> u32 {
+        u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn test_glb_layout() {
+        let mesh = Mesh {
+            positions: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0],
+            normals: vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0],
+            indices: vec![0, 1, 2],
+            colors: Vec::new(),
+        };
+        let element = ElementMesh {
+            id: 42,
+            ifc_type: IfcType::IfcSlab,
+            name: Some("Floor".to_string()),
+            color: [0.5, 0.5, 0.5, 1.0],
+            mesh,
+        };
+
+        let glb = to_glb(&[element]);
+        assert_eq!(read_u32(&glb, 0), GLB_MAGIC);
+        assert_eq!(read_u32(&glb, 8) as usize, glb.len());
+        assert_eq!(glb.len() % 4, 0);
+
+        let json_len = read_u32(&glb, 12) as usize;
+        assert_eq!(read_u32(&glb, 16), CHUNK_JSON);
+        let document: Value = serde_json::from_slice(&glb[20..20 + json_len]).unwrap();
+        assert_eq!(document["nodes"][0]["extras"]["expressID"], 42);
+        assert_eq!(document["accessors"][0]["count"], 3);
+
+        // Normal (0, 0, 1) in IFC is +Y in glTF
+        let bin_start = 20 + json_len + 8;
+        assert_eq!(read_u32(&glb, 20 + json_len + 4), CHUNK_BIN);
+        let normal_offset = document["bufferViews"][1]["byteOffset"].as_u64().unwrap() as usize;
+        let ny = f32::from_le_bytes(
+            glb[bin_start + normal_offset + 4..bin_start + normal_offset + 8]
+                .try_into()
+                .unwrap(),
+        );
+        assert_eq!(ny, 1.0);
+    }
+}
@@ -0,0 +1,24 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! IFC-Lite CLI - Headless processing of IFC models
+//!
+//! Library side of the `ifc-lite` command: single-file conversion, statistics
+//! and validation, batch analysis of model folders, GLB export and thumbnail
+//! rendering, usable without any viewer.
+
+pub mod batch;
+pub mod convert;
+pub mod error;
+pub mod glb;
+pub mod model;
+pub mod thumbnail;
+
+pub use batch::{
+    process_file, process_files, process_folder, BatchOptions, BatchReport, FileReport,
+};
+pub use convert::{convert_file, OutputFormat};
+pub use error::{Error, Result};
+pub use model::{ElementMesh, Issue, ProcessedModel, QuantityTotal, Severity};
+pub use thumbnail::Thumbnail;
@@ -0,0 +1,320 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! `ifc-lite` command-line tool
+
+use ifc_lite_cli::{
+    convert_file, process_file, process_folder, BatchOptions, Error, ProcessedModel, Result,
+    Severity,
+};
+use ifc_lite_core::{DiffStatus, ModelDiff};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: ifc-lite <command> [options]
+
+Commands:
+  convert <file> --out <file>   Export a model; format from the extension (.glb, .png)
+  stats <file>                  Print entity, geometry and quantity statistics
+  validate <file>               Check a model's structure and geometry and list its issues
+  batch <folder>                Process every IFC file in a folder and print a summary
+  diff <old> <new>              List elements added, removed or modified between versions
+
+Convert options:
+  -o, --out <file>        Output file
+      --size N            PNG size in pixels (default 512)
+
+Stats options:
+      --no-geometry       Skip geometry (statistics and quantities only)
+      --json              Print the report as JSON
+
+Validate options:
+      --no-geometry       Skip geometry checks
+      --strict            Fail on warnings too
+      --json              Print the issues as JSON
+
+Batch options:
+  -r, --recursive         Include subfolders
+      --glb               Export a GLB per model
+      --thumbnails        Export a PNG thumbnail per model
+      --thumbnail-size N  Thumbnail size in pixels (default 256)
+  -o, --out <dir>         Folder for exported files
+  -j, --threads N         Worker threads (default: one per core)
+      --no-geometry       Skip geometry (statistics and quantities only)
+      --json <file>       Write the full report as JSON
+      --tsv <file>        Write a per-file summary table
+
+Diff options:
+      --json              Print the differences as JSON
+      --csv               Print the differences as CSV
+
+Exits with status 1 when a model fails or has errors, 2 on usage errors.";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("convert") => run_convert(&args[1..]),
+        Some("stats") => run_stats(&args[1..]),
+        Some("validate") => run_validate(&args[1..]),
+        Some("batch") => run_batch(&args[1..]),
+        Some("diff") => run_diff(&args[1..]),
+        Some("-h" | "--help") | None => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Some(other) => Err(Error::Usage(format!("unknown command '{}'", other))),
+    };
+
+    match result {
+        Ok(code) => code,
+        Err(Error::Usage(message)) => {
+            eprintln!("error: {}\n\n{}", message, USAGE);
+            ExitCode::from(2)
+        }
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run_convert(args: &[String]) -> Result<ExitCode> {
+    let mut input = None;
+    let mut output = None;
+    let mut size = 512;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .cloned()
+                .ok_or_else(|| Error::Usage(format!("{} needs a value", name)))
+        };
+        match arg.as_str() {
+            "-o" | "--out" => output = Some(PathBuf::from(value(arg)?)),
+            "--size" => size = parse_number(arg, &value(arg)?)?,
+            other if other.starts_with('-') => {
+                return Err(Error::Usage(format!("unknown option '{}'", other)));
+            }
+            other => input = Some(PathBuf::from(other)),
+        }
+    }
+
+    let input = input.ok_or_else(|| Error::Usage("convert needs an input file".to_string()))?;
+    let output = output.ok_or_else(|| Error::Usage("convert needs --out".to_string()))?;
+    let model = convert_file(&input, &output, size)?;
+    println!(
+        "{} -> {}: {} elements, {} triangles",
+        input.display(),
+        output.display(),
+        model.elements.len(),
+        model.triangle_count()
+    );
+    Ok(exit_code(&model, false))
+}
+
+fn run_stats(args: &[String]) -> Result<ExitCode> {
+    let mut options = BatchOptions::default();
+    let mut input = None;
+    let mut json = false;
+
+    for arg in args {
+        match arg.as_str() {
+            "--no-geometry" => options.skip_geometry = true,
+            "--json" => json = true,
+            other if other.starts_with('-') => {
+                return Err(Error::Usage(format!("unknown option '{}'", other)));
+            }
+            other => input = Some(PathBuf::from(other)),
+        }
+    }
+
+    let input = input.ok_or_else(|| Error::Usage("stats needs an input file".to_string()))?;
+    let report = process_file(&input, &options);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        print!("{}", report);
+    }
+    Ok(if report.error.is_some() {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+fn run_validate(args: &[String]) -> Result<ExitCode> {
+    let mut input = None;
+    let mut with_geometry = true;
+    let mut strict = false;
+    let mut json = false;
+
+    for arg in args {
+        match arg.as_str() {
+            "--no-geometry" => with_geometry = false,
+            "--strict" => strict = true,
+            "--json" => json = true,
+            other if other.starts_with('-') => {
+                return Err(Error::Usage(format!("unknown option '{}'", other)));
+            }
+            other => input = Some(PathBuf::from(other)),
+        }
+    }
+
+    let input = input.ok_or_else(|| Error::Usage("validate needs an input file".to_string()))?;
+    let bytes = std::fs::read(&input).map_err(|e| Error::io(&input, e))?;
+    let content = String::from_utf8_lossy(&bytes);
+    let mut model = ProcessedModel::from_content(&content, with_geometry);
+    model.validate(&content);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&model.issues)?);
+    } else {
+        for issue in &model.issues {
+            println!("{}: {}", input.display(), issue);
+        }
+        let errors = model
+            .issues
+            .iter()
+            .filter(|i| i.severity == Severity::Error)
+            .count();
+        println!(
+            "{}: {} errors, {} warnings",
+            input.display(),
+            errors,
+            model.issues.len() - errors
+        );
+    }
+    Ok(exit_code(&model, strict))
+}
+
+/// Failure when the model has errors, or any issue at all when strict
+fn exit_code(model: &ProcessedModel, strict: bool) -> ExitCode {
+    let failed = model
+        .issues
+        .iter()
+        .any(|i| strict || i.severity == Severity::Error);
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+fn run_diff(args: &[String]) -> Result<ExitCode> {
+    let mut inputs = Vec::new();
+    let mut json = false;
+    let mut csv = false;
+
+    for arg in args {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--csv" => csv = true,
+            other if other.starts_with('-') => {
+                return Err(Error::Usage(format!("unknown option '{}'", other)));
+            }
+            other => inputs.push(PathBuf::from(other)),
+        }
+    }
+
+    let [old, new] = inputs.as_slice() else {
+        return Err(Error::Usage("diff needs an old and a new file".to_string()));
+    };
+    let read = |path: &PathBuf| -> Result<String> {
+        let bytes = std::fs::read(path).map_err(|e| Error::io(path, e))?;
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    };
+    let diff = ModelDiff::compare(&read(old)?, &read(new)?);
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&diff)?);
+    } else if csv {
+        print!("{}", diff.to_csv());
+    } else {
+        for element in &diff.elements {
+            let mut changed = Vec::new();
+            if element.attributes_changed {
+                changed.push("attributes");
+            }
+            if element.geometry_changed {
+                changed.push("geometry");
+            }
+            println!(
+                "{:<8} {} {} {}{}",
+                element.status.as_str(),
+                element.global_id,
+                element.ifc_type,
+                element.name.as_deref().unwrap_or("-"),
+                if changed.is_empty() {
+                    String::new()
+                } else {
+                    format!(" ({})", changed.join(", "))
+                }
+            );
+        }
+        println!(
+            "{} added, {} removed, {} modified, {} unchanged",
+            diff.count(DiffStatus::Added),
+            diff.count(DiffStatus::Removed),
+            diff.count(DiffStatus::Modified),
+            diff.unchanged
+        );
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn run_batch(args: &[String]) -> Result<ExitCode> {
+    let mut options = BatchOptions::default();
+    let mut folder = None;
+    let mut json_path = None;
+    let mut tsv_path = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .cloned()
+                .ok_or_else(|| Error::Usage(format!("{} needs a value", name)))
+        };
+        match arg.as_str() {
+            "-r" | "--recursive" => options.recursive = true,
+            "--glb" => options.export_glb = true,
+            "--thumbnails" => options.export_thumbnails = true,
+            "--thumbnail-size" => options.thumbnail_size = parse_number(arg, &value(arg)?)?,
+            "-o" | "--out" => options.output_dir = Some(PathBuf::from(value(arg)?)),
+            "-j" | "--threads" => options.threads = Some(parse_number(arg, &value(arg)?)?),
+            "--no-geometry" => options.skip_geometry = true,
+            "--json" => json_path = Some(PathBuf::from(value(arg)?)),
+            "--tsv" => tsv_path = Some(PathBuf::from(value(arg)?)),
+            other if other.starts_with('-') => {
+                return Err(Error::Usage(format!("unknown option '{}'", other)));
+            }
+            other => folder = Some(PathBuf::from(other)),
+        }
+    }
+
+    let folder = folder.ok_or_else(|| Error::Usage("batch needs a folder".to_string()))?;
+    let report = process_folder(&folder, &options)?;
+    print!("{}", report);
+
+    if let Some(path) = json_path {
+        std::fs::write(&path, report.to_json()?).map_err(|e| Error::io(&path, e))?;
+    }
+    if let Some(path) = tsv_path {
+        std::fs::write(&path, report.to_tsv()).map_err(|e| Error::io(&path, e))?;
+    }
+
+    Ok(if report.failed_count() > 0 {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+fn parse_number<T: std::str::FromStr>(option: &str, value: &str) -> Result<T> {
+    value
+        .parse()
+        .map_err(|_| Error::Usage(format!("{} expects a number, got '{}'", option, value)))
+}
@@ -0,0 +1,411 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Processed Model - Parsing, checks, quantities and geometry for one file
+//!
+//! Processing never stops at the first problem: malformed entities and
+//! failed geometry are recorded as [`Issue`]s and the rest of the file is
+//! still analysed.
+
+pub use ifc_lite_core::Severity;
+use ifc_lite_core::{
+    build_entity_index, decode_quantity, extract_length_unit_scale, file_schema, parse_entity,
+    resolve_type, validate, Attribute, Diagnostic, DiagnosticKind, Diagnostics, EntityDecoder,
+    EntityScanner, IfcType, LineIndex, SchemaVersion, UnitAssignment,
+};
+use ifc_lite_engine::{BuildOptions, ElementInfo, SceneBuilder, SceneSink};
+use ifc_lite_geometry::Mesh;
+use rustc_hash::FxHashMap;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// A problem found while processing a file
+pub type Issue = Diagnostic;
+
+/// Sum of one quantity over all elements that carry it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct QuantityTotal {
+    /// Quantity set name, e.g. "Qto_WallBaseQuantities"
+    pub set: String,
+    /// Quantity name, prefixed with its complex quantity path
+    pub name: String,
+    pub kind: String,
+    pub unit: String,
+    pub total: f64,
+    /// Number of elements contributing to the total
+    pub count: usize,
+}
+
+/// Mesh of one element in IFC world coordinates (metres, Z-up)
+#[derive(Debug, Clone)]
+pub struct ElementMesh {
+    pub id: u32,
+    pub ifc_type: IfcType,
+    pub name: Option<String>,
+    pub color: [f32; 4],
+    pub mesh: Mesh,
+}
+
+/// Result of processing one IFC file
+#[derive(Debug, Clone)]
+pub struct ProcessedModel {
+    /// Schema from the FILE_SCHEMA header as written, e.g. "IFC4X3_ADD2"
+    pub schema: Option<String>,
+    /// Release the attribute layout was read as; IFC4 if not recognized
+    pub schema_version: SchemaVersion,
+    pub entity_count: usize,
+    /// Entity count per type name
+    pub type_counts: BTreeMap<String, usize>,
+    /// Metres per file length unit
+    pub length_scale: f64,
+    /// Products with a representation
+    pub element_count: usize,
+    /// Elements that produced a non-empty mesh
+    pub elements: Vec<ElementMesh>,
+    pub quantities: Vec<QuantityTotal>,
+    pub issues: Vec<Issue>,
+    pub parse_time: Duration,
+    pub geometry_time: Duration,
+}
+
+impl ProcessedModel {
+    /// Parse, check and mesh IFC content
+    ///
+    /// With `with_geometry` false only the parse, statistics and quantity
+    /// passes run.
+    pub fn from_content(content: &str, with_geometry: bool) -> Self {
+        let parse_start = Instant::now();
+        let mut issues = Diagnostics::with_limit(usize::MAX);
+        let lines = LineIndex::new(content);
+
+        let mut type_counts: BTreeMap<String, usize> = BTreeMap::new();
+        let mut entity_count = 0;
+        let mut project_id = None;
+        let mut product_ids = Vec::new();
+        let mut property_rels = Vec::new();
+
+        let mut scanner = EntityScanner::new(content);
+        while let Some((id, type_name, start, end)) = scanner.next_entity() {
+            entity_count += 1;
+
+            if let Err(e) = parse_entity(&content[start..end]) {
+                issues.entity(
+                    Severity::Error,
+                    DiagnosticKind::Parse,
+                    id,
+                    type_name,
+                    Some(lines.line(start)),
+                    format!("Malformed {}: {}", type_name, e),
+                );
+                continue;
+            }
+
+            let ifc_type = IfcType::from_str(type_name);
+            let key = match &ifc_type {
+                IfcType::Unknown(_) => type_name.to_uppercase(),
+                known => known.name().to_string(),
+            };
+            *type_counts.entry(key).or_default() += 1;
+
+            // Legacy names such as IFCBEAMSTANDARDCASE count as their
+            // IFC4X3 type
+            match resolve_type(type_name) {
+                IfcType::IfcProject => project_id = Some(id),
+                IfcType::IfcRelDefinesByProperties => property_rels.push(id),
+                _ if ifc_type.has_geometry() => product_ids.push(id),
+                _ => {}
+            }
+        }
+
+        let schema = file_schema(content).map(str::to_string);
+        let detected = schema.as_deref().and_then(SchemaVersion::from_identifier);
+        match &schema {
+            None => issues.file(
+                Severity::Warning,
+                DiagnosticKind::Schema,
+                "Missing FILE_SCHEMA header",
+            ),
+            Some(name) if detected.is_none() => issues.file(
+                Severity::Warning,
+                DiagnosticKind::Schema,
+                format!(
+                    "Unsupported schema {}, reading as {}",
+                    name,
+                    SchemaVersion::default()
+                ),
+            ),
+            Some(_) => {}
+        }
+
+        let mut decoder = EntityDecoder::with_index(content, build_entity_index(content));
+        let schema_version = decoder.schema();
+        let (length_scale, units) = match project_id {
+            Some(id) => (
+                extract_length_unit_scale(&mut decoder, id).unwrap_or(1.0),
+                UnitAssignment::from_project(&mut decoder, id),
+            ),
+            None => {
+                issues.file(
+                    Severity::Error,
+                    DiagnosticKind::Structure,
+                    "No IfcProject found",
+                );
+                (1.0, UnitAssignment::default())
+            }
+        };
+
+        let representation = IfcType::IfcProduct
+            .attribute_index(Attribute::Representation)
+            .expect("IfcProduct has a Representation");
+        let element_ids: Vec<u32> = product_ids
+            .into_iter()
+            .filter(|&id| {
+                decoder
+                    .decode_attrs_by_id(id, &[representation])
+                    .is_ok_and(|attrs| attrs.first().is_some_and(|a| !a.is_null()))
+            })
+            .collect();
+
+        let quantities = collect_quantities(&mut decoder, &property_rels, &units);
+        let parse_time = parse_start.elapsed();
+
+        let geometry_start = Instant::now();
+        let elements = if with_geometry {
+            mesh_elements(content, &mut issues)
+        } else {
+            Vec::new()
+        };
+
+        Self {
+            schema,
+            schema_version,
+            entity_count,
+            type_counts,
+            length_scale,
+            element_count: element_ids.len(),
+            elements,
+            quantities,
+            issues: issues.into_vec(),
+            parse_time,
+            geometry_time: geometry_start.elapsed(),
+        }
+    }
+
+    /// Add the structural checks of [`ifc_lite_core::validate`] to `issues`
+    ///
+    /// Findings already recorded while processing (malformed entities, a
+    /// missing project) are not repeated.
+    pub fn validate(&mut self, content: &str) {
+        for finding in validate(content) {
+            if !self.issues.contains(&finding) {
+                self.issues.push(finding);
+            }
+        }
+    }
+
+    /// Total triangles over all element meshes
+    pub fn triangle_count(&self) -> usize {
+        self.elements.iter().map(|e| e.mesh.triangle_count()).sum()
+    }
+
+    /// Total vertices over all element meshes
+    pub fn vertex_count(&self) -> usize {
+        self.elements.iter().map(|e| e.mesh.vertex_count()).sum()
+    }
+
+    /// Axis-aligned bounds of all element meshes
+    pub fn bounds(&self) -> Option<([f32; 3], [f32; 3])> {
+        let mut min = [f32::MAX; 3];
+        let mut max = [f32::MIN; 3];
+        for position in self
+            .elements
+            .iter()
+            .flat_map(|e| e.mesh.positions.chunks_exact(3))
+        {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(position[axis]);
+                max[axis] = max[axis].max(position[axis]);
+            }
+        }
+        (min[0] <= max[0]).then_some((min, max))
+    }
+}
+
+/// Sum IfcElementQuantity values over the elements they are assigned to
+fn collect_quantities(
+    decoder: &mut EntityDecoder,
+    property_rels: &[u32],
+    units: &UnitAssignment,
+) -> Vec<QuantityTotal> {
+    let mut totals: FxHashMap<(String, String, String), QuantityTotal> = FxHashMap::default();
+
+    for &rel_id in property_rels {
+        let Ok(rel) = decoder.decode_by_id(rel_id) else {
+            continue;
+        };
+        let related = rel
+            .attribute_list(Attribute::RelatedObjects)
+            .map(|l| l.len())
+            .unwrap_or(0);
+        let Some(definition) = rel
+            .attribute_ref(Attribute::RelatingPropertyDefinition)
+            .and_then(|id| decoder.decode_by_id(id).ok())
+        else {
+            continue;
+        };
+        if related == 0 || definition.ifc_type != IfcType::IfcElementQuantity {
+            continue;
+        }
+
+        let set = definition.name().unwrap_or_default().to_string();
+        let quantity_ids: Vec<u32> = definition
+            .attribute_list(Attribute::Quantities)
+            .map(|l| l.iter().filter_map(|v| v.as_entity_ref()).collect())
+            .unwrap_or_default();
+
+        for quantity_id in quantity_ids {
+            for quantity in decode_quantity(decoder, quantity_id) {
+                let (value, unit) = quantity.display_value(units);
+                let name = quantity.qualified_name();
+                let total = totals
+                    .entry((set.clone(), name.clone(), unit.clone()))
+                    .or_insert_with(|| QuantityTotal {
+                        set: set.clone(),
+                        name,
+                        kind: quantity.kind.name().to_string(),
+                        unit,
+                        total: 0.0,
+                        count: 0,
+                    });
+                total.total += value * related as f64;
+                total.count += related;
+            }
+        }
+    }
+
+    let mut totals: Vec<QuantityTotal> = totals.into_values().collect();
+    totals.sort_by(|a, b| (&a.set, &a.name, &a.unit).cmp(&(&b.set, &b.name, &b.unit)));
+    totals
+}
+
+/// Collects the element meshes of a scene build
+#[derive(Default)]
+struct ElementCollector {
+    elements: Vec<ElementMesh>,
+}
+
+impl SceneSink for ElementCollector {
+    fn mesh(&mut self, element: &ElementInfo, mesh: Mesh, color: [f32; 4]) {
+        self.elements.push(ElementMesh {
+            id: element.id,
+            ifc_type: resolve_type(&element.entity_type),
+            name: element.name.clone(),
+            color,
+            mesh,
+        });
+    }
+}
+
+/// Generate meshes for all geometry-capable elements
+///
+/// Meshed like the viewers, with their default colours; geometry problems
+/// are added to `issues`.
+fn mesh_elements(content: &str, issues: &mut Diagnostics) -> Vec<ElementMesh> {
+    let mut collector = ElementCollector::default();
+    // Exports stay in file coordinates
+    let options = BuildOptions {
+        shift_origin: false,
+        ..BuildOptions::default()
+    };
+    let scene = SceneBuilder::new(content)
+        .with_options(options)
+        .build(&mut collector);
+    // Parse and schema problems are already recorded by the parse pass
+    for diagnostic in scene.diagnostics.into_vec() {
+        if diagnostic.kind == DiagnosticKind::Geometry {
+            issues.push(diagnostic);
+        }
+    }
+    collector.elements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_IFC: &str = include_str!("../../../tests/models/test.ifc");
+
+    #[test]
+    fn test_process_model() {
+        let model = ProcessedModel::from_content(TEST_IFC, true);
+
+        assert_eq!(model.schema.as_deref(), Some("IFC2X3"));
+        assert_eq!(model.schema_version, SchemaVersion::Ifc2x3);
+        assert_eq!(model.type_counts.get("IfcWallStandardCase"), Some(&2));
+        assert_eq!(model.element_count, 3);
+        assert_eq!(model.elements.len(), 3);
+        assert!(model.triangle_count() > 0);
+        assert!(model.issues.is_empty(), "{:?}", model.issues);
+
+        // Same colours as the viewers
+        let wall = model
+            .elements
+            .iter()
+            .find(|e| e.ifc_type == IfcType::IfcWallStandardCase)
+            .expect("wall mesh");
+        assert_eq!(
+            wall.color,
+            ifc_lite_engine::element_color("IFCWALLSTANDARDCASE", None)
+        );
+
+        let (min, max) = model.bounds().unwrap();
+        assert!(max[2] - min[2] > 2.9);
+    }
+
+    #[test]
+    fn test_issues_and_quantities() {
+        let content = "ISO-10303-21;\nHEADER;\nENDSEC;\nDATA;\n\
+#1=IFCWALL('0a',$,'W1',$,$,$,$,$,$);\n\
+#2=IFCWALL('0b',$,'W2',$,$,$,$,$,$);\n\
+#3=IFCQUANTITYAREA('NetSideArea',$,$,10.,$);\n\
+#4=IFCELEMENTQUANTITY('0c',$,'Qto_WallBaseQuantities',$,$,(#3));\n\
+#5=IFCRELDEFINESBYPROPERTIES('0d',$,$,$,(#1,#2),#4);\n\
+#6=IFCWALL('0e',$,'broken'\n\
+ENDSEC;\n";
+        let model = ProcessedModel::from_content(content, false);
+
+        assert!(model
+            .issues
+            .iter()
+            .any(|i| i.severity == Severity::Error && i.message.contains("IfcProject")));
+        assert!(model
+            .issues
+            .iter()
+            .any(|i| i.message.contains("FILE_SCHEMA")));
+        assert!(model
+            .issues
+            .iter()
+            .any(|i| i.to_string().starts_with("error line 10 #6: Malformed")));
+
+        let mut validated = model.clone();
+        validated.validate(content);
+        let project_errors = validated
+            .issues
+            .iter()
+            .filter(|i| i.message == "No IfcProject found")
+            .count();
+        assert_eq!(project_errors, 1);
+        assert!(validated
+            .issues
+            .iter()
+            .any(|i| i.entity_id == Some(1) && i.message.starts_with("Malformed GlobalId")));
+
+        assert_eq!(model.quantities.len(), 1);
+        let area = &model.quantities[0];
+        assert_eq!(area.name, "NetSideArea");
+        assert_eq!(area.count, 2);
+        assert!((area.total - 20.0).abs() < 1e-9);
+    }
+}
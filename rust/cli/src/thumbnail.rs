@@ -0,0 +1,202 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Thumbnails - CPU rasterized isometric previews
+//!
+//! A small z-buffered rasterizer with flat shading, so thumbnails can be
+//! produced on headless machines without a GPU.
+
+use crate::error::{Error, Result};
+use crate::model::ElementMesh;
+use std::f32::consts::FRAC_1_SQRT_2;
+use std::io::Write;
+
+/// Direction towards the viewer (IFC Z-up): south-east, from above
+const VIEW_DIR: [f32; 3] = [0.577_350_3, -0.577_350_3, 0.577_350_3];
+/// Screen right and up vectors for `VIEW_DIR`
+const RIGHT: [f32; 3] = [FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0.0];
+const UP: [f32; 3] = [-0.408_248_3, 0.408_248_3, 0.816_496_6];
+/// Fraction of the image left empty around the model
+const MARGIN: f32 = 0.06;
+
+/// RGBA8 image
+#[derive(Debug, Clone)]
+pub struct Thumbnail {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Thumbnail {
+    /// Encode as PNG
+    pub fn write_png(&self, writer: impl Write) -> Result<()> {
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+        writer.finish().map_err(Error::from)
+    }
+}
+
+/// Render an isometric view of the elements into a square image
+///
+/// The background is transparent; translucent elements (spaces, openings)
+/// are skipped so they do not hide the model.
+pub fn render(elements: &[ElementMesh], size: u32) -> Thumbnail {
+    let size = size.max(1);
+    let pixel_count = (size * size) as usize;
+    let mut pixels = vec![0u8; pixel_count * 4];
+    let mut depth = vec![f32::MIN; pixel_count];
+
+    let visible: Vec<&ElementMesh> = elements.iter().filter(|e| e.color[3] >= 0.35).collect();
+
+    // Fit the projected bounds into the image
+    let mut min = [f32::MAX; 2];
+    let mut max = [f32::MIN; 2];
+    for p in visible
+        .iter()
+        .flat_map(|e| e.mesh.positions.chunks_exact(3))
+    {
+        let (x, y) = (dot(p, &RIGHT), dot(p, &UP));
+        min = [min[0].min(x), min[1].min(y)];
+        max = [max[0].max(x), max[1].max(y)];
+    }
+    if min[0] > max[0] {
+        return Thumbnail {
+            width: size,
+            height: size,
+            pixels,
+        };
+    }
+
+    let extent = (max[0] - min[0]).max(max[1] - min[1]).max(f32::EPSILON);
+    let scale = size as f32 * (1.0 - 2.0 * MARGIN) / extent;
+    let center = [(min[0] + max[0]) / 2.0, (min[1] + max[1]) / 2.0];
+    let half = size as f32 / 2.0;
+    let project = |p: &[f32]| -> [f32; 3] {
+        [
+            half + (dot(p, &RIGHT) - center[0]) * scale,
+            half - (dot(p, &UP) - center[1]) * scale,
+            dot(p, &VIEW_DIR),
+        ]
+    };
+
+    for element in visible {
+        let positions = &element.mesh.positions;
+        for triangle in element.mesh.indices.chunks_exact(3) {
+            let corner = |i: usize| {
+                let start = triangle[i] as usize * 3;
+                positions.get(start..start + 3)
+            };
+            let (Some(a), Some(b), Some(c)) = (corner(0), corner(1), corner(2)) else {
+                continue;
+            };
+
+            // Flat shading from the face normal, lit from the viewer
+            let normal = cross(&sub(b, a), &sub(c, a));
+            let length = dot(&normal, &normal).sqrt();
+            if length <= f32::EPSILON {
+                continue;
+            }
+            let light = 0.35 + 0.65 * (dot(&normal, &VIEW_DIR) / length).abs();
+            let rgb = [0, 1, 2].map(|i| (element.color[i] * light * 255.0).clamp(0.0, 255.0) as u8);
+
+            fill_triangle(
+                [project(a), project(b), project(c)],
+                size,
+                &mut depth,
+                &mut pixels,
+                rgb,
+            );
+        }
+    }
+
+    Thumbnail {
+        width: size,
+        height: size,
+        pixels,
+    }
+}
+
+/// Rasterize one screen-space triangle with a depth test
+fn fill_triangle(v: [[f32; 3]; 3], size: u32, depth: &mut [f32], pixels: &mut [u8], rgb: [u8; 3]) {
+    let area = edge(&v[0], &v[1], &v[2]);
+    if area.abs() <= f32::EPSILON {
+        return;
+    }
+
+    let limit = size as f32 - 1.0;
+    let min_x = v.iter().map(|p| p[0]).fold(f32::MAX, f32::min).max(0.0) as u32;
+    let max_x = v.iter().map(|p| p[0]).fold(f32::MIN, f32::max).min(limit) as u32;
+    let min_y = v.iter().map(|p| p[1]).fold(f32::MAX, f32::min).max(0.0) as u32;
+    let max_y = v.iter().map(|p| p[1]).fold(f32::MIN, f32::max).min(limit) as u32;
+
+    for y in min_y..=max_y {
+        for x in min_x..=max_x {
+            let p = [x as f32 + 0.5, y as f32 + 0.5, 0.0];
+            let w0 = edge(&v[1], &v[2], &p) / area;
+            let w1 = edge(&v[2], &v[0], &p) / area;
+            let w2 = edge(&v[0], &v[1], &p) / area;
+            if w0 < 0.0 || w1 < 0.0 || w2 < 0.0 {
+                continue;
+            }
+
+            let z = w0 * v[0][2] + w1 * v[1][2] + w2 * v[2][2];
+            let index = (y * size + x) as usize;
+            if z <= depth[index] {
+                continue;
+            }
+            depth[index] = z;
+            pixels[index * 4..index * 4 + 4].copy_from_slice(&[rgb[0], rgb[1], rgb[2], 255]);
+        }
+    }
+}
+
+fn edge(a: &[f32; 3], b: &[f32; 3], p: &[f32; 3]) -> f32 {
+    (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0])
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn sub(a: &[f32], b: &[f32]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn cross(a: &[f32; 3], b: &[f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::ProcessedModel;
+
+    #[test]
+    fn test_render_covers_model() {
+        let model =
+            ProcessedModel::from_content(include_str!("../../../tests/models/test.ifc"), true);
+        let thumbnail = render(&model.elements, 64);
+
+        assert_eq!(thumbnail.pixels.len(), 64 * 64 * 4);
+        let opaque = thumbnail
+            .pixels
+            .chunks_exact(4)
+            .filter(|p| p[3] == 255)
+            .count();
+        assert!(opaque > 64 * 64 / 10, "only {} pixels drawn", opaque);
+        // Corners stay transparent thanks to the margin
+        assert_eq!(thumbnail.pixels[3], 0);
+
+        let mut png = Vec::new();
+        thumbnail.write_png(&mut png).unwrap();
+        assert_eq!(&png[1..4], b"PNG");
+    }
+}
@@ -0,0 +1,148 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! `.bcfzip` archives
+
+use super::{zip, BcfTopic};
+use crate::error::{Error, Result};
+use std::collections::HashMap;
+
+const VERSION_FILE: &str = "bcf.version";
+const VERSION_XML: &str = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+<Version VersionId=\"2.1\">\n  <DetailedVersion>2.1</DetailedVersion>\n</Version>\n";
+
+/// Topics of a `.bcfzip` file
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BcfArchive {
+    pub topics: Vec<BcfTopic>,
+}
+
+impl BcfArchive {
+    /// Read a `.bcfzip` file
+    ///
+    /// Every folder with a `markup.bcf` is a topic; project and extension
+    /// files are ignored. Topics come out sorted by creation date.
+    pub fn read(bytes: &[u8]) -> Result<Self> {
+        let files: HashMap<String, Vec<u8>> = zip::read(bytes)?.into_iter().collect();
+
+        let mut topics = Vec::new();
+        for (path, markup) in &files {
+            let Some(folder) = path.strip_suffix("/markup.bcf") else {
+                continue;
+            };
+            let topic = BcfTopic::from_markup_xml(&String::from_utf8_lossy(markup), |name| {
+                files.get(&format!("{}/{}", folder, name)).cloned()
+            })
+            .map_err(|e| Error::bcf(format!("{}: {}", path, e)))?;
+            topics.push(topic);
+        }
+        if topics.is_empty() && !files.contains_key(VERSION_FILE) {
+            return Err(Error::bcf("no bcf.version or topics in archive"));
+        }
+
+        topics.sort_by(|a, b| {
+            a.creation_date
+                .cmp(&b.creation_date)
+                .then_with(|| a.guid.cmp(&b.guid))
+        });
+        Ok(Self { topics })
+    }
+
+    /// Write a `.bcfzip` file
+    pub fn write(&self) -> Vec<u8> {
+        let mut files = vec![(VERSION_FILE.to_string(), VERSION_XML.as_bytes().to_vec())];
+        for topic in &self.topics {
+            files.push((
+                format!("{}/markup.bcf", topic.guid),
+                topic.to_markup_xml().into_bytes(),
+            ));
+            for (index, viewpoint) in topic.viewpoints.iter().enumerate() {
+                files.push((
+                    format!("{}/{}", topic.guid, BcfTopic::viewpoint_file(index)),
+                    viewpoint.viewpoint.to_xml().into_bytes(),
+                ));
+                if let Some(snapshot) = &viewpoint.snapshot {
+                    files.push((
+                        format!("{}/{}", topic.guid, BcfTopic::snapshot_file(index)),
+                        snapshot.clone(),
+                    ));
+                }
+            }
+        }
+        zip::write(&files)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bcf::{BcfComment, BcfTopicViewpoint, BcfViewpoint};
+
+    fn topic(guid: &str, date: &str) -> BcfTopic {
+        let mut viewpoint = BcfViewpoint::new(format!("{}-vp", guid));
+        viewpoint.selection = vec!["2O2Fr$t4X7Zf8NOew3FLOH".to_string()];
+        BcfTopic {
+            guid: guid.to_string(),
+            title: "Duct <clashes> with beam & slab".to_string(),
+            description: Some("Level 2, grid C/4".to_string()),
+            topic_type: Some("Clash".to_string()),
+            status: Some("Open".to_string()),
+            creation_date: date.to_string(),
+            author: "reviewer@example.com".to_string(),
+            comments: vec![BcfComment {
+                guid: format!("{}-c1", guid),
+                date: date.to_string(),
+                author: "reviewer@example.com".to_string(),
+                text: "Please reroute".to_string(),
+                viewpoint_guid: Some(format!("{}-vp", guid)),
+            }],
+            viewpoints: vec![BcfTopicViewpoint {
+                viewpoint,
+                snapshot: Some(vec![0x89, b'P', b'N', b'G']),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_archive_round_trip() {
+        let archive = BcfArchive {
+            topics: vec![
+                topic("a", "2026-01-01T10:00:00Z"),
+                topic("b", "2026-02-01T10:00:00Z"),
+            ],
+        };
+        let read = BcfArchive::read(&archive.write()).unwrap();
+        assert_eq!(read, archive);
+        assert_eq!(
+            read.topics[0].global_ids(),
+            vec!["2O2Fr$t4X7Zf8NOew3FLOH".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_topic_without_viewpoint_file() {
+        let mut topic = topic("a", "2026-01-01T10:00:00Z");
+        topic.viewpoints[0].snapshot = None;
+        let mut files = zip::read(
+            &BcfArchive {
+                topics: vec![topic],
+            }
+            .write(),
+        )
+        .unwrap();
+        files.retain(|(name, _)| !name.ends_with(".bcfv"));
+
+        let read = BcfArchive::read(&zip::write(&files)).unwrap();
+        assert_eq!(read.topics.len(), 1);
+        assert!(read.topics[0].viewpoints.is_empty());
+        assert_eq!(read.topics[0].comments.len(), 1);
+    }
+
+    #[test]
+    fn test_not_bcf() {
+        let files = vec![("readme.txt".to_string(), b"hello".to_vec())];
+        assert!(BcfArchive::read(&zip::write(&files)).is_err());
+    }
+}
@@ -0,0 +1,193 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! BCF topics (`markup.bcf`)
+
+use super::{child, child_text, escape, BcfViewpoint};
+use crate::error::{Error, Result};
+use std::fmt::Write;
+
+/// Comment on a topic
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BcfComment {
+    pub guid: String,
+    /// ISO 8601 date-time
+    pub date: String,
+    pub author: String,
+    pub text: String,
+    /// Viewpoint of the topic the comment refers to
+    pub viewpoint_guid: Option<String>,
+}
+
+/// Viewpoint of a topic with its snapshot
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BcfTopicViewpoint {
+    pub viewpoint: BcfViewpoint,
+    /// PNG image of the view
+    pub snapshot: Option<Vec<u8>>,
+}
+
+/// Issue with its comments and viewpoints
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BcfTopic {
+    pub guid: String,
+    pub title: String,
+    pub description: Option<String>,
+    /// E.g. "Issue", "Request", "Clash"
+    pub topic_type: Option<String>,
+    /// E.g. "Open", "Closed"
+    pub status: Option<String>,
+    /// ISO 8601 date-time
+    pub creation_date: String,
+    pub author: String,
+    pub comments: Vec<BcfComment>,
+    pub viewpoints: Vec<BcfTopicViewpoint>,
+}
+
+impl BcfTopic {
+    /// GlobalIds of the components selected in the topic's viewpoints
+    pub fn global_ids(&self) -> Vec<String> {
+        let mut ids: Vec<String> = Vec::new();
+        for guid in self.viewpoints.iter().flat_map(|v| &v.viewpoint.selection) {
+            if !ids.contains(guid) {
+                ids.push(guid.clone());
+            }
+        }
+        ids
+    }
+
+    /// File name of viewpoint `index` inside the topic folder
+    pub(super) fn viewpoint_file(index: usize) -> String {
+        if index == 0 {
+            "viewpoint.bcfv".to_string()
+        } else {
+            format!("viewpoint{}.bcfv", index + 1)
+        }
+    }
+
+    /// File name of the snapshot of viewpoint `index` inside the topic folder
+    pub(super) fn snapshot_file(index: usize) -> String {
+        if index == 0 {
+            "snapshot.png".to_string()
+        } else {
+            format!("snapshot{}.png", index + 1)
+        }
+    }
+
+    /// `markup.bcf` of this topic
+    pub(super) fn to_markup_xml(&self) -> String {
+        let element = |xml: &mut String, indent: &str, name: &str, text: &str| {
+            let _ = writeln!(xml, "{indent}<{name}>{}</{name}>", escape(text));
+        };
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<Markup>\n");
+        let _ = write!(xml, "  <Topic Guid=\"{}\"", escape(&self.guid));
+        if let Some(topic_type) = &self.topic_type {
+            let _ = write!(xml, " TopicType=\"{}\"", escape(topic_type));
+        }
+        if let Some(status) = &self.status {
+            let _ = write!(xml, " TopicStatus=\"{}\"", escape(status));
+        }
+        xml.push_str(">\n");
+        element(&mut xml, "    ", "Title", &self.title);
+        element(&mut xml, "    ", "CreationDate", &self.creation_date);
+        element(&mut xml, "    ", "CreationAuthor", &self.author);
+        if let Some(description) = &self.description {
+            element(&mut xml, "    ", "Description", description);
+        }
+        xml.push_str("  </Topic>\n");
+
+        for comment in &self.comments {
+            let _ = writeln!(xml, "  <Comment Guid=\"{}\">", escape(&comment.guid));
+            element(&mut xml, "    ", "Date", &comment.date);
+            element(&mut xml, "    ", "Author", &comment.author);
+            element(&mut xml, "    ", "Comment", &comment.text);
+            if let Some(viewpoint) = &comment.viewpoint_guid {
+                let _ = writeln!(xml, "    <Viewpoint Guid=\"{}\" />", escape(viewpoint));
+            }
+            xml.push_str("  </Comment>\n");
+        }
+
+        for (index, viewpoint) in self.viewpoints.iter().enumerate() {
+            let _ = writeln!(
+                xml,
+                "  <Viewpoints Guid=\"{}\">",
+                escape(&viewpoint.viewpoint.guid)
+            );
+            element(&mut xml, "    ", "Viewpoint", &Self::viewpoint_file(index));
+            if viewpoint.snapshot.is_some() {
+                element(&mut xml, "    ", "Snapshot", &Self::snapshot_file(index));
+            }
+            xml.push_str("  </Viewpoints>\n");
+        }
+
+        xml.push_str("</Markup>\n");
+        xml
+    }
+
+    /// Topic from its `markup.bcf`
+    ///
+    /// `file` loads another file of the topic folder by name; viewpoints
+    /// whose file is missing are skipped.
+    pub(super) fn from_markup_xml(
+        xml: &str,
+        mut file: impl FnMut(&str) -> Option<Vec<u8>>,
+    ) -> Result<Self> {
+        let document = roxmltree::Document::parse(xml).map_err(|e| Error::bcf(e.to_string()))?;
+        let root = document.root_element();
+        let topic =
+            child(root, "Topic").ok_or_else(|| Error::bcf("markup.bcf has no Topic element"))?;
+
+        let comments = root
+            .children()
+            .filter(|n| n.has_tag_name("Comment"))
+            .map(|comment| BcfComment {
+                guid: comment.attribute("Guid").unwrap_or_default().to_string(),
+                date: child_text(comment, "Date").unwrap_or_default(),
+                author: child_text(comment, "Author").unwrap_or_default(),
+                text: child_text(comment, "Comment").unwrap_or_default(),
+                viewpoint_guid: child(comment, "Viewpoint")
+                    .and_then(|v| v.attribute("Guid"))
+                    .map(str::to_string),
+            })
+            .collect();
+
+        let mut viewpoints = Vec::new();
+        for (index, entry) in root
+            .children()
+            .filter(|n| n.has_tag_name("Viewpoints"))
+            .enumerate()
+        {
+            let file_name =
+                child_text(entry, "Viewpoint").unwrap_or_else(|| Self::viewpoint_file(index));
+            let Some(bytes) = file(&file_name) else {
+                continue;
+            };
+            let mut viewpoint = BcfViewpoint::from_xml(&String::from_utf8_lossy(&bytes))?;
+            if let Some(guid) = entry.attribute("Guid") {
+                viewpoint.guid = guid.to_string();
+            }
+            let snapshot = child_text(entry, "Snapshot").and_then(|name| file(&name));
+            viewpoints.push(BcfTopicViewpoint {
+                viewpoint,
+                snapshot,
+            });
+        }
+
+        Ok(Self {
+            guid: topic.attribute("Guid").unwrap_or_default().to_string(),
+            title: child_text(topic, "Title").unwrap_or_default(),
+            description: child_text(topic, "Description"),
+            topic_type: topic.attribute("TopicType").map(str::to_string),
+            status: topic.attribute("TopicStatus").map(str::to_string),
+            creation_date: child_text(topic, "CreationDate").unwrap_or_default(),
+            author: child_text(topic, "CreationAuthor").unwrap_or_default(),
+            comments,
+            viewpoints,
+        })
+    }
+}
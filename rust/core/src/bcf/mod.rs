@@ -0,0 +1,111 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! BIM Collaboration Format (BCF 2.1)
+//!
+//! BCF is how BIM tools exchange issues about a model. A `.bcfzip` archive
+//! holds one folder per topic with its markup (title, status, comments), one
+//! or more viewpoints and their snapshots:
+//!
+//! - [`BcfViewpoint`] - camera, clipping planes and component visibility
+//! - [`BcfTopic`] - an issue with its comments and viewpoints
+//! - [`BcfArchive`] - reads and writes `.bcfzip` files
+//!
+//! Components are referenced by IFC GlobalId, so topics carry over between
+//! tools that loaded the same model.
+
+mod archive;
+mod markup;
+mod visinfo;
+mod zip;
+
+pub use archive::BcfArchive;
+pub use markup::{BcfComment, BcfTopic, BcfTopicViewpoint};
+pub use visinfo::{BcfCamera, BcfClippingPlane, BcfProjection, BcfViewpoint};
+
+/// Format 128 random bits as a version 4 UUID, the GUID form BCF uses
+pub fn format_guid(random: u128) -> String {
+    let value = (random & !(0xf << 76) & !(0x3 << 62)) | (0x4 << 76) | (0x2 << 62);
+    let hex = format!("{:032x}", value);
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+/// Format seconds since the Unix epoch as an ISO 8601 UTC date-time
+pub fn format_timestamp(unix_seconds: i64) -> String {
+    let days = unix_seconds.div_euclid(86_400);
+    let seconds = unix_seconds.rem_euclid(86_400);
+
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm)
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn child<'a, 'input>(
+    node: roxmltree::Node<'a, 'input>,
+    name: &str,
+) -> Option<roxmltree::Node<'a, 'input>> {
+    node.children().find(|n| n.has_tag_name(name))
+}
+
+/// Trimmed text of a child element, `None` if missing or empty
+fn child_text(node: roxmltree::Node, name: &str) -> Option<String> {
+    child(node, name)
+        .and_then(|n| n.text())
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_guid() {
+        let guid = format_guid(u128::MAX);
+        assert_eq!(guid, "ffffffff-ffff-4fff-bfff-ffffffffffff");
+        assert_eq!(format_guid(0), "00000000-0000-4000-8000-000000000000");
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_timestamp(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(format_timestamp(1_791_374_400), "2026-10-07T12:00:00Z");
+        assert_eq!(format_timestamp(-1), "1969-12-31T23:59:59Z");
+    }
+}
@@ -0,0 +1,347 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! BCF visualization info (`viewpoint.bcfv`)
+//!
+//! Camera, clipping planes, selected components and component visibility:
+//! what other BIM tools need to restore the view of an issue. Coordinates
+//! are IFC world coordinates in metres (Z up) and components are identified
+//! by their IFC GlobalId.
+
+use super::{child, escape};
+use crate::error::{Error, Result};
+use std::fmt::Write;
+
+/// Camera projection of a viewpoint
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BcfProjection {
+    /// Vertical field of view in degrees
+    Perspective { field_of_view: f64 },
+    /// World units shown across the view height
+    Orthogonal { view_to_world_scale: f64 },
+}
+
+/// Viewpoint camera
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BcfCamera {
+    pub position: [f64; 3],
+    pub direction: [f64; 3],
+    pub up: [f64; 3],
+    pub projection: BcfProjection,
+}
+
+/// Clipping plane; everything on the side `direction` points to is hidden
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BcfClippingPlane {
+    pub location: [f64; 3],
+    pub direction: [f64; 3],
+}
+
+/// BCF 2.1 visualization info
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BcfViewpoint {
+    pub guid: String,
+    pub camera: Option<BcfCamera>,
+    pub clipping_planes: Vec<BcfClippingPlane>,
+    /// GlobalIds of selected components
+    pub selection: Vec<String>,
+    /// Visibility of components not listed in `exceptions`
+    pub default_visibility: bool,
+    /// GlobalIds whose visibility is the opposite of `default_visibility`
+    pub exceptions: Vec<String>,
+}
+
+impl BcfViewpoint {
+    /// Empty viewpoint with everything visible
+    pub fn new(guid: impl Into<String>) -> Self {
+        Self {
+            guid: guid.into(),
+            camera: None,
+            clipping_planes: Vec::new(),
+            selection: Vec::new(),
+            default_visibility: true,
+            exceptions: Vec::new(),
+        }
+    }
+
+    /// Serialize as a `viewpoint.bcfv` document
+    pub fn to_xml(&self) -> String {
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        let _ = writeln!(xml, "<VisualizationInfo Guid=\"{}\">", escape(&self.guid));
+
+        xml.push_str("  <Components>\n");
+        if !self.selection.is_empty() {
+            xml.push_str("    <Selection>\n");
+            write_components(&mut xml, &self.selection, "      ");
+            xml.push_str("    </Selection>\n");
+        }
+        let _ = writeln!(
+            xml,
+            "    <Visibility DefaultVisibility=\"{}\">",
+            self.default_visibility
+        );
+        if !self.exceptions.is_empty() {
+            xml.push_str("      <Exceptions>\n");
+            write_components(&mut xml, &self.exceptions, "        ");
+            xml.push_str("      </Exceptions>\n");
+        }
+        xml.push_str("    </Visibility>\n");
+        xml.push_str("  </Components>\n");
+
+        if let Some(camera) = &self.camera {
+            let element = match camera.projection {
+                BcfProjection::Perspective { .. } => "PerspectiveCamera",
+                BcfProjection::Orthogonal { .. } => "OrthogonalCamera",
+            };
+            let _ = writeln!(xml, "  <{}>", element);
+            write_point(&mut xml, "    ", "CameraViewPoint", camera.position);
+            write_point(&mut xml, "    ", "CameraDirection", camera.direction);
+            write_point(&mut xml, "    ", "CameraUpVector", camera.up);
+            match camera.projection {
+                BcfProjection::Perspective { field_of_view } => {
+                    let _ = writeln!(xml, "    <FieldOfView>{}</FieldOfView>", field_of_view);
+                }
+                BcfProjection::Orthogonal {
+                    view_to_world_scale,
+                } => {
+                    let _ = writeln!(
+                        xml,
+                        "    <ViewToWorldScale>{}</ViewToWorldScale>",
+                        view_to_world_scale
+                    );
+                }
+            }
+            let _ = writeln!(xml, "  </{}>", element);
+        }
+
+        if !self.clipping_planes.is_empty() {
+            xml.push_str("  <ClippingPlanes>\n");
+            for plane in &self.clipping_planes {
+                xml.push_str("    <ClippingPlane>\n");
+                write_point(&mut xml, "      ", "Location", plane.location);
+                write_point(&mut xml, "      ", "Direction", plane.direction);
+                xml.push_str("    </ClippingPlane>\n");
+            }
+            xml.push_str("  </ClippingPlanes>\n");
+        }
+
+        xml.push_str("</VisualizationInfo>\n");
+        xml
+    }
+
+    /// Parse a `viewpoint.bcfv` document
+    ///
+    /// Parts this viewer has no use for (lines, bitmaps, colouring,
+    /// view setup hints) are skipped.
+    pub fn from_xml(xml: &str) -> Result<Self> {
+        let document = roxmltree::Document::parse(xml).map_err(|e| Error::bcf(e.to_string()))?;
+        let root = document.root_element();
+        if root.tag_name().name() != "VisualizationInfo" {
+            return Err(Error::bcf(format!(
+                "expected VisualizationInfo, found {}",
+                root.tag_name().name()
+            )));
+        }
+
+        let mut viewpoint = Self::new(root.attribute("Guid").unwrap_or_default());
+        if let Some(components) = child(root, "Components") {
+            if let Some(selection) = child(components, "Selection") {
+                viewpoint.selection = component_guids(selection);
+            }
+            if let Some(visibility) = child(components, "Visibility") {
+                viewpoint.default_visibility = visibility
+                    .attribute("DefaultVisibility")
+                    .is_none_or(|v| v.eq_ignore_ascii_case("true") || v == "1");
+                if let Some(exceptions) = child(visibility, "Exceptions") {
+                    viewpoint.exceptions = component_guids(exceptions);
+                }
+            }
+        }
+
+        if let Some(camera) = child(root, "PerspectiveCamera") {
+            viewpoint.camera = Some(read_camera(
+                camera,
+                BcfProjection::Perspective {
+                    field_of_view: read_number(camera, "FieldOfView")?,
+                },
+            )?);
+        } else if let Some(camera) = child(root, "OrthogonalCamera") {
+            viewpoint.camera = Some(read_camera(
+                camera,
+                BcfProjection::Orthogonal {
+                    view_to_world_scale: read_number(camera, "ViewToWorldScale")?,
+                },
+            )?);
+        }
+
+        if let Some(planes) = child(root, "ClippingPlanes") {
+            for plane in planes
+                .children()
+                .filter(|n| n.has_tag_name("ClippingPlane"))
+            {
+                viewpoint.clipping_planes.push(BcfClippingPlane {
+                    location: read_point(plane, "Location")?,
+                    direction: read_point(plane, "Direction")?,
+                });
+            }
+        }
+
+        Ok(viewpoint)
+    }
+}
+
+fn write_components(xml: &mut String, guids: &[String], indent: &str) {
+    for guid in guids {
+        let _ = writeln!(xml, "{}<Component IfcGuid=\"{}\" />", indent, escape(guid));
+    }
+}
+
+fn write_point(xml: &mut String, indent: &str, element: &str, [x, y, z]: [f64; 3]) {
+    let _ = writeln!(
+        xml,
+        "{indent}<{element}><X>{x}</X><Y>{y}</Y><Z>{z}</Z></{element}>"
+    );
+}
+
+fn component_guids(node: roxmltree::Node) -> Vec<String> {
+    node.children()
+        .filter(|n| n.has_tag_name("Component"))
+        .filter_map(|n| n.attribute("IfcGuid"))
+        .map(str::to_string)
+        .collect()
+}
+
+fn read_number(node: roxmltree::Node, name: &str) -> Result<f64> {
+    child(node, name)
+        .and_then(|n| n.text())
+        .and_then(|text| text.trim().parse().ok())
+        .ok_or_else(|| {
+            Error::bcf(format!(
+                "{} has no numeric {}",
+                node.tag_name().name(),
+                name
+            ))
+        })
+}
+
+fn read_point(node: roxmltree::Node, name: &str) -> Result<[f64; 3]> {
+    let point = child(node, name)
+        .ok_or_else(|| Error::bcf(format!("{} has no {}", node.tag_name().name(), name)))?;
+    Ok([
+        read_number(point, "X")?,
+        read_number(point, "Y")?,
+        read_number(point, "Z")?,
+    ])
+}
+
+fn read_camera(node: roxmltree::Node, projection: BcfProjection) -> Result<BcfCamera> {
+    Ok(BcfCamera {
+        position: read_point(node, "CameraViewPoint")?,
+        direction: read_point(node, "CameraDirection")?,
+        up: read_point(node, "CameraUpVector")?,
+        projection,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> BcfViewpoint {
+        BcfViewpoint {
+            guid: "0f3a1c2e-4b5d-4e6f-8a7b-9c0d1e2f3a4b".to_string(),
+            camera: Some(BcfCamera {
+                position: [10.0, -5.5, 3.25],
+                direction: [-0.6, 0.8, 0.0],
+                up: [0.0, 0.0, 1.0],
+                projection: BcfProjection::Perspective {
+                    field_of_view: 60.0,
+                },
+            }),
+            clipping_planes: vec![
+                BcfClippingPlane {
+                    location: [0.0, 0.0, 2.5],
+                    direction: [0.0, 0.0, 1.0],
+                },
+                BcfClippingPlane {
+                    location: [4.0, 0.0, 0.0],
+                    direction: [-1.0, 0.0, 0.0],
+                },
+            ],
+            selection: vec!["2O2Fr$t4X7Zf8NOew3FLOH".to_string()],
+            default_visibility: false,
+            exceptions: vec![
+                "2O2Fr$t4X7Zf8NOew3FLOH".to_string(),
+                "1hOSvn6df7F8_7GcBWlR72".to_string(),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_viewpoint_round_trip() {
+        let viewpoint = sample();
+        let xml = viewpoint.to_xml();
+        assert!(xml.contains("<PerspectiveCamera>"));
+        assert!(xml.contains("<Visibility DefaultVisibility=\"false\">"));
+        assert_eq!(BcfViewpoint::from_xml(&xml).unwrap(), viewpoint);
+    }
+
+    #[test]
+    fn test_orthogonal_camera_round_trip() {
+        let mut viewpoint = sample();
+        viewpoint.camera.as_mut().unwrap().projection = BcfProjection::Orthogonal {
+            view_to_world_scale: 42.5,
+        };
+        viewpoint.clipping_planes.clear();
+        let xml = viewpoint.to_xml();
+        assert!(!xml.contains("ClippingPlanes"));
+        assert_eq!(BcfViewpoint::from_xml(&xml).unwrap(), viewpoint);
+    }
+
+    #[test]
+    fn test_parse_foreign_viewpoint() {
+        let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+<VisualizationInfo Guid="abc">
+  <Components>
+    <ViewSetupHints SpacesVisible="false" />
+    <Visibility DefaultVisibility="true" />
+    <Coloring><Color Color="FF0000"><Component IfcGuid="x" /></Color></Coloring>
+  </Components>
+  <OrthogonalCamera>
+    <CameraViewPoint><X>1</X><Y>2</Y><Z>3</Z></CameraViewPoint>
+    <CameraDirection><X>0</X><Y>0</Y><Z>-1</Z></CameraDirection>
+    <CameraUpVector><X>0</X><Y>1</Y><Z>0</Z></CameraUpVector>
+    <ViewToWorldScale>15</ViewToWorldScale>
+  </OrthogonalCamera>
+  <Lines />
+</VisualizationInfo>"#;
+        let viewpoint = BcfViewpoint::from_xml(xml).unwrap();
+        assert_eq!(viewpoint.guid, "abc");
+        assert!(viewpoint.default_visibility);
+        assert!(viewpoint.selection.is_empty());
+        let camera = viewpoint.camera.unwrap();
+        assert_eq!(camera.position, [1.0, 2.0, 3.0]);
+        assert_eq!(
+            camera.projection,
+            BcfProjection::Orthogonal {
+                view_to_world_scale: 15.0
+            }
+        );
+    }
+
+    #[test]
+    fn test_escape_and_errors() {
+        let viewpoint = BcfViewpoint::new("a&b");
+        let xml = viewpoint.to_xml();
+        assert!(xml.contains("Guid=\"a&amp;b\""));
+        assert_eq!(BcfViewpoint::from_xml(&xml).unwrap().guid, "a&b");
+
+        assert!(BcfViewpoint::from_xml("<Markup />").is_err());
+        assert!(BcfViewpoint::from_xml("not xml").is_err());
+    }
+}
@@ -0,0 +1,253 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Minimal ZIP container for `.bcfzip`
+//!
+//! Reads stored and deflated entries through the central directory, checking
+//! their CRC-32, and writes deflated entries without timestamps. Offsets
+//! from the archive are untrusted and checked before use. ZIP64, encryption and
+//! multi-disk archives are not supported; BCF archives never need them.
+
+use crate::error::{Error, Result};
+
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+const CENTRAL_HEADER: u32 = 0x0201_4b50;
+const END_OF_DIRECTORY: u32 = 0x0605_4b50;
+
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+
+/// DOS date of 1980-01-01, the earliest ZIP can express
+const DOS_DATE: u16 = (1 << 5) | 1;
+
+/// `len` bytes at `offset`, if the archive holds them
+fn bytes_at(bytes: &[u8], offset: usize, len: usize) -> Result<&[u8]> {
+    offset
+        .checked_add(len)
+        .and_then(|end| bytes.get(offset..end))
+        .ok_or_else(|| Error::bcf("truncated zip archive"))
+}
+
+fn u16_at(bytes: &[u8], offset: usize) -> Result<u16> {
+    bytes_at(bytes, offset, 2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+}
+
+fn u32_at(bytes: &[u8], offset: usize) -> Result<u32> {
+    bytes_at(bytes, offset, 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+/// Sum of header values, which would wrap on 32-bit targets for a
+/// malicious archive
+fn offset_sum(parts: &[usize]) -> Result<usize> {
+    parts
+        .iter()
+        .try_fold(0usize, |sum, &part| sum.checked_add(part))
+        .ok_or_else(|| Error::bcf("corrupt zip offsets"))
+}
+
+/// Entries of a ZIP archive as (path, contents), directories left out
+pub fn read(bytes: &[u8]) -> Result<Vec<(String, Vec<u8>)>> {
+    // The end record sits at the very end, followed by a comment of up to 64 KiB
+    let search_start = bytes.len().saturating_sub(22 + u16::MAX as usize);
+    let end = (search_start..=bytes.len().saturating_sub(22))
+        .rev()
+        .find(|&offset| u32_at(bytes, offset).ok() == Some(END_OF_DIRECTORY))
+        .ok_or_else(|| Error::bcf("not a zip archive"))?;
+
+    let count = u16_at(bytes, end + 10)? as usize;
+    let mut offset = u32_at(bytes, end + 16)? as usize;
+    let mut entries = Vec::with_capacity(count);
+
+    for _ in 0..count {
+        if u32_at(bytes, offset)? != CENTRAL_HEADER {
+            return Err(Error::bcf("corrupt zip central directory"));
+        }
+        let field = |at: usize| offset_sum(&[offset, at]);
+        let method = u16_at(bytes, field(10)?)?;
+        let crc = u32_at(bytes, field(16)?)?;
+        let compressed_size = u32_at(bytes, field(20)?)? as usize;
+        let size = u32_at(bytes, field(24)?)? as usize;
+        let name_len = u16_at(bytes, field(28)?)? as usize;
+        let extra_len = u16_at(bytes, field(30)?)? as usize;
+        let comment_len = u16_at(bytes, field(32)?)? as usize;
+        let local = u32_at(bytes, field(42)?)? as usize;
+        let name = bytes_at(bytes, field(46)?, name_len)?;
+        let name = String::from_utf8_lossy(name).replace('\\', "/");
+        offset = offset_sum(&[offset, 46, name_len, extra_len, comment_len])?;
+
+        if name.ends_with('/') {
+            continue;
+        }
+
+        // Local header lengths may differ from the central directory's
+        if u32_at(bytes, local)? != LOCAL_HEADER {
+            return Err(Error::bcf(format!("corrupt zip entry {}", name)));
+        }
+        let local_name_len = u16_at(bytes, offset_sum(&[local, 26])?)? as usize;
+        let local_extra_len = u16_at(bytes, offset_sum(&[local, 28])?)? as usize;
+        let data_start = offset_sum(&[local, 30, local_name_len, local_extra_len])?;
+        let data = bytes_at(bytes, data_start, compressed_size)
+            .map_err(|_| Error::bcf(format!("truncated zip entry {}", name)))?;
+
+        let contents = match method {
+            STORED => data.to_vec(),
+            DEFLATED => miniz_oxide::inflate::decompress_to_vec_with_limit(data, size)
+                .map_err(|e| Error::bcf(format!("cannot inflate {}: {:?}", name, e.status)))?,
+            other => {
+                return Err(Error::bcf(format!(
+                    "unsupported compression method {} for {}",
+                    other, name
+                )))
+            }
+        };
+        if contents.len() != size || crc32fast::hash(&contents) != crc {
+            return Err(Error::bcf(format!("corrupt zip entry {}", name)));
+        }
+        entries.push((name, contents));
+    }
+
+    Ok(entries)
+}
+
+/// ZIP archive of deflated entries
+pub fn write(entries: &[(String, Vec<u8>)]) -> Vec<u8> {
+    let mut archive = Vec::new();
+    let mut directory = Vec::new();
+
+    for (name, contents) in entries {
+        let compressed = miniz_oxide::deflate::compress_to_vec(contents, 6);
+        let crc = crc32fast::hash(contents);
+        let offset = archive.len() as u32;
+
+        // Fields shared by the local and central headers, from "version needed"
+        let mut common = Vec::with_capacity(26);
+        common.extend_from_slice(&20u16.to_le_bytes()); // version needed
+        common.extend_from_slice(&(1u16 << 11).to_le_bytes()); // UTF-8 names
+        common.extend_from_slice(&DEFLATED.to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes()); // time
+        common.extend_from_slice(&DOS_DATE.to_le_bytes());
+        common.extend_from_slice(&crc.to_le_bytes());
+        common.extend_from_slice(&(compressed.len() as u32).to_le_bytes());
+        common.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+        common.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        common.extend_from_slice(&0u16.to_le_bytes()); // extra length
+
+        archive.extend_from_slice(&LOCAL_HEADER.to_le_bytes());
+        archive.extend_from_slice(&common);
+        archive.extend_from_slice(name.as_bytes());
+        archive.extend_from_slice(&compressed);
+
+        directory.extend_from_slice(&CENTRAL_HEADER.to_le_bytes());
+        directory.extend_from_slice(&20u16.to_le_bytes()); // version made by
+        directory.extend_from_slice(&common);
+        directory.extend_from_slice(&0u16.to_le_bytes()); // comment length
+        directory.extend_from_slice(&0u16.to_le_bytes()); // disk
+        directory.extend_from_slice(&0u16.to_le_bytes()); // internal attributes
+        directory.extend_from_slice(&0u32.to_le_bytes()); // external attributes
+        directory.extend_from_slice(&offset.to_le_bytes());
+        directory.extend_from_slice(name.as_bytes());
+    }
+
+    let directory_offset = archive.len() as u32;
+    archive.extend_from_slice(&directory);
+    archive.extend_from_slice(&END_OF_DIRECTORY.to_le_bytes());
+    archive.extend_from_slice(&0u16.to_le_bytes()); // disk
+    archive.extend_from_slice(&0u16.to_le_bytes()); // directory disk
+    archive.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    archive.extend_from_slice(&(entries.len() as u16).to_le_bytes());
+    archive.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+    archive.extend_from_slice(&directory_offset.to_le_bytes());
+    archive.extend_from_slice(&0u16.to_le_bytes()); // comment length
+    archive
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zip_round_trip() {
+        let entries = vec![
+            ("bcf.version".to_string(), b"<Version />".to_vec()),
+            ("a/markup.bcf".to_string(), "ü".repeat(1000).into_bytes()),
+            ("a/empty".to_string(), Vec::new()),
+        ];
+        let archive = write(&entries);
+        assert_eq!(read(&archive).unwrap(), entries);
+    }
+
+    #[test]
+    fn test_stored_entry_and_directory() {
+        // Hand-built archive: a directory and one stored file
+        let mut entries = Vec::new();
+        let mut directory = Vec::new();
+        for (name, data) in [("dir/", &b""[..]), ("dir/a.txt", &b"hello"[..])] {
+            let offset = entries.len() as u32;
+            let mut common = Vec::new();
+            common.extend_from_slice(&10u16.to_le_bytes());
+            common.extend_from_slice(&0u16.to_le_bytes());
+            common.extend_from_slice(&STORED.to_le_bytes());
+            common.extend_from_slice(&[0; 4]);
+            common.extend_from_slice(&crc32fast::hash(data).to_le_bytes());
+            common.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            common.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            common.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            common.extend_from_slice(&0u16.to_le_bytes());
+            entries.extend_from_slice(&LOCAL_HEADER.to_le_bytes());
+            entries.extend_from_slice(&common);
+            entries.extend_from_slice(name.as_bytes());
+            entries.extend_from_slice(data);
+            directory.extend_from_slice(&CENTRAL_HEADER.to_le_bytes());
+            directory.extend_from_slice(&10u16.to_le_bytes());
+            directory.extend_from_slice(&common);
+            directory.extend_from_slice(&[0; 10]);
+            directory.extend_from_slice(&offset.to_le_bytes());
+            directory.extend_from_slice(name.as_bytes());
+        }
+        let mut archive = entries.clone();
+        archive.extend_from_slice(&END_OF_DIRECTORY.to_le_bytes());
+        archive.extend_from_slice(&[0; 4]);
+        archive.extend_from_slice(&2u16.to_le_bytes());
+        archive.extend_from_slice(&2u16.to_le_bytes());
+        archive.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+        archive.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+        archive.extend_from_slice(&0u16.to_le_bytes());
+        archive.splice(entries.len()..entries.len(), directory);
+
+        assert_eq!(
+            read(&archive).unwrap(),
+            vec![("dir/a.txt".to_string(), b"hello".to_vec())]
+        );
+    }
+
+    /// Offset of the central directory of an archive without comment
+    fn central_directory(archive: &[u8]) -> usize {
+        u32_at(archive, archive.len() - 22 + 16).unwrap() as usize
+    }
+
+    #[test]
+    fn test_crc_mismatch() {
+        let mut archive = write(&[("markup.bcf".to_string(), b"<Markup />".to_vec())]);
+        let crc = central_directory(&archive) + 16;
+        archive[crc] ^= 0xff;
+        assert!(read(&archive).is_err());
+    }
+
+    #[test]
+    fn test_offsets_out_of_range() {
+        let mut archive = write(&[("markup.bcf".to_string(), b"<Markup />".to_vec())]);
+        let local = central_directory(&archive) + 42;
+        archive[local..local + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(read(&archive).is_err());
+
+        assert!(offset_sum(&[usize::MAX - 10, 30]).is_err());
+        assert_eq!(offset_sum(&[10, 30, 2]).unwrap(), 42);
+    }
+
+    #[test]
+    fn test_not_a_zip() {
+        assert!(read(b"").is_err());
+        assert!(read(b"PK\x03\x04 but nothing else").is_err());
+    }
+}
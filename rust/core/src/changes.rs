@@ -0,0 +1,379 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Change Sets - Tracked property edits
+//!
+//! A [`ChangeSet`] collects property edits by entity, set and property name
+//! rather than by the STEP IDs they end up touching. Applied through an
+//! [`IfcWriter`] it produces a patched IFC file; with the `serde` feature it
+//! doubles as a JSON diff that can be reviewed or replayed on the same file.
+
+use crate::error::{Error, Result};
+use crate::parser::Token;
+use crate::property::PropertyData;
+use crate::writer::{IfcWriter, StepValue};
+
+/// One edit
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "kind", rename_all = "snake_case")
+)]
+pub enum Change {
+    /// New NominalValue of an existing IfcPropertySingleValue
+    SetProperty {
+        entity: u32,
+        pset: String,
+        property: String,
+        value: PropertyData,
+    },
+    /// New IfcPropertySet of single values
+    AddPropertySet {
+        entity: u32,
+        name: String,
+        properties: Vec<(String, PropertyData)>,
+    },
+}
+
+/// Ordered property edits of one model
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ChangeSet {
+    pub changes: Vec<Change>,
+}
+
+impl ChangeSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    /// Set a property value
+    ///
+    /// Properties of a set added by this change set are updated in that set,
+    /// and a later edit of the same property replaces the earlier one.
+    pub fn set_property(&mut self, entity: u32, pset: &str, property: &str, value: PropertyData) {
+        for change in &mut self.changes {
+            match change {
+                Change::AddPropertySet {
+                    entity: e,
+                    name,
+                    properties,
+                } if *e == entity && name == pset => {
+                    match properties.iter_mut().find(|(n, _)| n == property) {
+                        Some(slot) => slot.1 = value,
+                        None => properties.push((property.to_string(), value)),
+                    }
+                    return;
+                }
+                Change::SetProperty {
+                    entity: e,
+                    pset: p,
+                    property: n,
+                    value: slot,
+                } if *e == entity && p == pset && n == property => {
+                    *slot = value;
+                    return;
+                }
+                _ => {}
+            }
+        }
+        self.changes.push(Change::SetProperty {
+            entity,
+            pset: pset.to_string(),
+            property: property.to_string(),
+            value,
+        });
+    }
+
+    /// Add a property set, or properties to one added before
+    pub fn add_property_set(
+        &mut self,
+        entity: u32,
+        name: &str,
+        properties: Vec<(String, PropertyData)>,
+    ) {
+        let added = self.changes.iter().any(|c| {
+            matches!(c, Change::AddPropertySet { entity: e, name: n, .. } if *e == entity && n == name)
+        });
+        if added {
+            for (property, value) in properties {
+                self.set_property(entity, name, &property, value);
+            }
+        } else {
+            self.changes.push(Change::AddPropertySet {
+                entity,
+                name: name.to_string(),
+                properties,
+            });
+        }
+    }
+
+    /// Edited or added value of a property
+    pub fn value_of(&self, entity: u32, pset: &str, property: &str) -> Option<&PropertyData> {
+        self.changes.iter().find_map(|change| match change {
+            Change::SetProperty {
+                entity: e,
+                pset: p,
+                property: n,
+                value,
+            } if *e == entity && p == pset && n == property => Some(value),
+            Change::AddPropertySet {
+                entity: e,
+                name,
+                properties,
+            } if *e == entity && name == pset => properties
+                .iter()
+                .find(|(n, _)| n == property)
+                .map(|(_, value)| value),
+            _ => None,
+        })
+    }
+
+    /// Whether a property was edited or added
+    pub fn is_edited(&self, entity: u32, pset: &str, property: &str) -> bool {
+        self.value_of(entity, pset, property).is_some()
+    }
+
+    /// Property sets added to an entity, as `(name, properties)`
+    pub fn added_sets(
+        &self,
+        entity: u32,
+    ) -> impl Iterator<Item = (&str, &[(String, PropertyData)])> + '_ {
+        self.changes.iter().filter_map(move |change| match change {
+            Change::AddPropertySet {
+                entity: e,
+                name,
+                properties,
+            } if *e == entity => Some((name.as_str(), properties.as_slice())),
+            _ => None,
+        })
+    }
+
+    /// Record all changes on a writer
+    ///
+    /// Edited values keep the IFC type of the value they replace (an
+    /// `IFCLENGTHMEASURE` stays one); new values are written as `IFCLABEL`,
+    /// `IFCREAL`, `IFCINTEGER` or `IFCBOOLEAN`.
+    pub fn apply(&self, writer: &mut IfcWriter) -> Result<()> {
+        for change in &self.changes {
+            match change {
+                Change::SetProperty {
+                    entity,
+                    pset,
+                    property,
+                    value,
+                } => {
+                    let property_id =
+                        writer
+                            .find_property(*entity, pset, property)
+                            .ok_or_else(|| {
+                                Error::change(format!(
+                                    "#{} has no property {}.{}",
+                                    entity, pset, property
+                                ))
+                            })?;
+                    // IfcPropertySingleValue: (Name, Description, NominalValue, Unit)
+                    let original = writer.original_attribute(property_id, 2);
+                    let value = step_value(value, original.as_ref()).ok_or_else(|| {
+                        Error::change(format!("{} values cannot be written", value.kind()))
+                    })?;
+                    writer.set_property_value(property_id, value)?;
+                }
+                Change::AddPropertySet {
+                    entity,
+                    name,
+                    properties,
+                } => {
+                    let properties = properties
+                        .iter()
+                        .map(|(property, value)| {
+                            step_value(value, None)
+                                .map(|v| (property.clone(), v))
+                                .ok_or_else(|| {
+                                    Error::change(format!(
+                                        "{} values cannot be written",
+                                        value.kind()
+                                    ))
+                                })
+                        })
+                        .collect::<Result<Vec<_>>>()?;
+                    writer.add_property_set(*entity, name, properties)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Patched copy of `content`
+    pub fn apply_to(&self, content: &str) -> Result<String> {
+        let mut writer = IfcWriter::new(content);
+        self.apply(&mut writer)?;
+        Ok(writer.write())
+    }
+}
+
+/// Typed STEP value for a property, reusing the type of `original` when it
+/// held the same kind of data
+fn step_value(data: &PropertyData, original: Option<&Token>) -> Option<StepValue> {
+    let (type_name, inner) = match original {
+        Some(Token::TypedValue(name, args)) if args.len() == 1 => (Some(*name), args.first()),
+        _ => (None, None),
+    };
+    let typed = |default: &str, fits: bool, value: StepValue| {
+        StepValue::typed(type_name.filter(|_| fits).unwrap_or(default), value)
+    };
+    Some(match data {
+        PropertyData::Text(text) => typed(
+            "IFCLABEL",
+            matches!(inner, Some(Token::String(_))),
+            StepValue::String(text.clone()),
+        ),
+        PropertyData::Real { value, .. } => typed(
+            "IFCREAL",
+            matches!(inner, Some(Token::Float(_) | Token::Integer(_)))
+                && !matches!(type_name, Some("IFCINTEGER" | "IFCCOUNTMEASURE")),
+            StepValue::Real(*value),
+        ),
+        PropertyData::Integer(i) => typed(
+            "IFCINTEGER",
+            matches!(inner, Some(Token::Integer(_))),
+            StepValue::Integer(*i),
+        ),
+        PropertyData::Boolean(b) => typed(
+            "IFCBOOLEAN",
+            matches!(inner, Some(Token::Enum(_))),
+            StepValue::Enum(if *b { "T" } else { "F" }.to_string()),
+        ),
+        PropertyData::Enum(e) if matches!(inner, Some(Token::Enum(_))) => {
+            typed("IFCLOGICAL", true, StepValue::Enum(e.clone()))
+        }
+        PropertyData::Enum(e) => StepValue::label(e),
+        PropertyData::EntityRef(_)
+        | PropertyData::List(_)
+        | PropertyData::Bounded { .. }
+        | PropertyData::Table { .. } => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODEL: &str = "DATA;
+#1=IFCOWNERHISTORY(#2,#3,$,.ADDED.,$,$,$,0);
+#10=IFCWALL('w1',#1,'Wall',$,$,$,$,$);
+#20=IFCPROPERTYSINGLEVALUE('Width',$,IFCLENGTHMEASURE(250.),$);
+#21=IFCPROPERTYSINGLEVALUE('FireRating',$,IFCLABEL('F30'),$);
+#22=IFCPROPERTYSET('p',#1,'Pset_WallCommon',$,(#20,#21));
+#23=IFCRELDEFINESBYPROPERTIES('r',#1,$,$,(#10),#22);
+ENDSEC;
+";
+
+    fn real(value: f64) -> PropertyData {
+        PropertyData::Real { value, unit: None }
+    }
+
+    #[test]
+    fn test_edits_merge() {
+        let mut changes = ChangeSet::new();
+        changes.set_property(10, "Pset_WallCommon", "Width", real(300.0));
+        changes.set_property(10, "Pset_WallCommon", "Width", real(320.0));
+        assert_eq!(changes.len(), 1);
+
+        changes.add_property_set(
+            10,
+            "Custom",
+            vec![("Note".to_string(), PropertyData::Text("a".into()))],
+        );
+        changes.set_property(10, "Custom", "Note", PropertyData::Text("b".into()));
+        changes.add_property_set(
+            10,
+            "Custom",
+            vec![("Flag".to_string(), PropertyData::Boolean(true))],
+        );
+        assert_eq!(changes.len(), 2);
+        assert!(changes.is_edited(10, "Custom", "Flag"));
+        assert!(changes.is_edited(10, "Pset_WallCommon", "Width"));
+        assert!(!changes.is_edited(10, "Pset_WallCommon", "FireRating"));
+        assert_eq!(
+            changes.value_of(10, "Pset_WallCommon", "Width"),
+            Some(&real(320.0))
+        );
+        assert_eq!(changes.added_sets(10).count(), 1);
+        assert_eq!(changes.added_sets(11).count(), 0);
+        assert_eq!(
+            changes.changes[1],
+            Change::AddPropertySet {
+                entity: 10,
+                name: "Custom".to_string(),
+                properties: vec![
+                    ("Note".to_string(), PropertyData::Text("b".into())),
+                    ("Flag".to_string(), PropertyData::Boolean(true)),
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_apply() {
+        let mut changes = ChangeSet::new();
+        changes.set_property(10, "Pset_WallCommon", "Width", real(300.0));
+        changes.set_property(
+            10,
+            "pset_wallcommon",
+            "FireRating",
+            PropertyData::Text("F90".into()),
+        );
+        changes.add_property_set(
+            10,
+            "Custom",
+            vec![("Checked".to_string(), PropertyData::Boolean(true))],
+        );
+
+        let out = changes.apply_to(MODEL).unwrap();
+        // The measure type of the replaced value is kept
+        assert!(out.contains("#20=IFCPROPERTYSINGLEVALUE('Width',$,IFCLENGTHMEASURE(300.),$);\n"));
+        assert!(out.contains("#21=IFCPROPERTYSINGLEVALUE('FireRating',$,IFCLABEL('F90'),$);\n"));
+        assert!(out.contains("#24=IFCPROPERTYSINGLEVALUE('Checked',$,IFCBOOLEAN(.T.),$);\n"));
+        assert!(out.contains("',#1,'Custom',$,(#24));\n"));
+        assert!(out.contains("',#1,$,$,(#10),#25);\nENDSEC;\n"));
+        // Applying again gives the same GlobalIds
+        assert_eq!(changes.apply_to(MODEL).unwrap(), out);
+
+        let mut missing = ChangeSet::new();
+        missing.set_property(10, "Pset_WallCommon", "Height", real(1.0));
+        assert!(matches!(missing.apply_to(MODEL), Err(Error::Change(_))));
+    }
+
+    #[test]
+    fn test_step_values() {
+        let count = Token::TypedValue("IFCCOUNTMEASURE", vec![Token::Integer(3)]);
+        assert_eq!(
+            step_value(&real(2.5), Some(&count)),
+            Some(StepValue::typed("IFCREAL", StepValue::Real(2.5)))
+        );
+        assert_eq!(
+            step_value(&PropertyData::Integer(4), Some(&count)),
+            Some(StepValue::typed("IFCCOUNTMEASURE", StepValue::Integer(4)))
+        );
+        let logical = Token::TypedValue("IFCLOGICAL", vec![Token::Enum("T")]);
+        assert_eq!(
+            step_value(&PropertyData::Enum("UNKNOWN".into()), Some(&logical)),
+            Some(StepValue::typed(
+                "IFCLOGICAL",
+                StepValue::Enum("UNKNOWN".into())
+            ))
+        );
+        assert_eq!(step_value(&PropertyData::EntityRef(1), None), None);
+    }
+}
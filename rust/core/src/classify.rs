@@ -0,0 +1,492 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Schema-driven entity classification
+//!
+//! Whether an entity is a spatial element, a physical element or a
+//! relationship, whether it can carry geometry and at which position its
+//! Name or Elevation sits all follow from the generated IFC4X3 hierarchy.
+//! IFC2x3 and IFC4 entities that were removed or renamed in IFC4X3 resolve
+//! through [`legacy_type`] to their closest IFC4X3 equivalent, so the same
+//! tables serve all three schemas. Positions that moved between releases
+//! are corrected per [`SchemaVersion`] in [`attribute_index_in`].
+
+use crate::generated::IfcType;
+use crate::schema_gen::{AttributeValue, DecodedEntity};
+use crate::schema_version::SchemaVersion;
+
+/// Broad role of an entity in the schema
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EntityClass {
+    /// IfcSpatialElement subtypes: site, building, storey, space, facility
+    Spatial,
+    /// IfcElement subtypes: walls, doors, equipment, openings
+    Element,
+    /// Other IfcProduct subtypes: annotations, grids, ports, structural items
+    Product,
+    /// IfcRelationship subtypes
+    Relationship,
+    /// IfcTypeObject subtypes: shared type definitions
+    TypeObject,
+    /// Property sets and element quantities
+    PropertyDefinition,
+    /// IfcContext subtypes: project and project library
+    Context,
+    /// Other IfcObject subtypes: groups, systems, zones, processes, actors
+    Object,
+    /// Resource entities outside the IfcRoot tree (geometry, placements)
+    Resource,
+    /// Not part of any supported schema
+    Unknown,
+}
+
+/// Named attribute looked up through [`attribute_index`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Attribute {
+    GlobalId,
+    OwnerHistory,
+    Name,
+    Description,
+    ObjectType,
+    ObjectPlacement,
+    Representation,
+    Tag,
+    LongName,
+    /// PredefinedType, or the IFC2x3 attribute it replaced (e.g. ShapeType)
+    PredefinedType,
+    Elevation,
+    RefLatitude,
+    RefLongitude,
+    RefElevation,
+    ElevationOfRefHeight,
+    UnitsInContext,
+    ApplicableOccurrence,
+    HasPropertySets,
+    RepresentationMaps,
+    HasProperties,
+    Quantities,
+    RelatingObject,
+    RelatedObjects,
+    RelatingStructure,
+    RelatedElements,
+    RelatingType,
+    RelatingPropertyDefinition,
+    RelatingMaterial,
+    RelatingGroup,
+    RelatingSystem,
+    RelatedBuildings,
+    RelatingBuildingElement,
+    RelatedOpeningElement,
+    RelatingOpeningElement,
+    RelatedBuildingElement,
+}
+
+/// Attribute positions declared directly on an entity
+///
+/// Inherited attributes are found by walking up the hierarchy, so each
+/// position is listed once, on the entity that introduces it.
+fn declared_index(ifc_type: IfcType, attribute: Attribute) -> Option<usize> {
+    use Attribute as A;
+    use IfcType as T;
+
+    let index = match (ifc_type, attribute) {
+        (T::IfcRoot, A::GlobalId) => 0,
+        (T::IfcRoot, A::OwnerHistory) => 1,
+        (T::IfcRoot, A::Name) => 2,
+        (T::IfcRoot, A::Description) => 3,
+
+        (T::IfcObject, A::ObjectType) => 4,
+        (T::IfcContext, A::ObjectType) => 4,
+        (T::IfcContext, A::LongName) => 5,
+        (T::IfcContext, A::UnitsInContext) => 8,
+
+        (T::IfcProduct, A::ObjectPlacement) => 5,
+        (T::IfcProduct, A::Representation) => 6,
+        (T::IfcElement, A::Tag) => 7,
+        (T::IfcSpatialElement, A::LongName) => 7,
+        (T::IfcAnnotation, A::PredefinedType) => 7,
+        (T::IfcDistributionPort, A::PredefinedType) => 8,
+        (T::IfcGrid, A::PredefinedType) => 10,
+
+        // Elements declare PredefinedType right after Tag unless they add
+        // attributes of their own first
+        (T::IfcElement, A::PredefinedType) => 8,
+        (T::IfcElementAssembly, A::PredefinedType) => 9,
+        (T::IfcTendon | T::IfcTendonAnchor | T::IfcTendonConduit, A::PredefinedType) => 9,
+        (T::IfcDoor | T::IfcWindow | T::IfcMechanicalFastener, A::PredefinedType) => 10,
+        (T::IfcStairFlight | T::IfcReinforcingBar, A::PredefinedType) => 12,
+        (T::IfcReinforcingMesh, A::PredefinedType) => 17,
+
+        (T::IfcSpatialZone | T::IfcExternalSpatialElement, A::PredefinedType) => 8,
+        (T::IfcSpace, A::PredefinedType) => 9,
+        (T::IfcBridge | T::IfcRoad | T::IfcRailway | T::IfcMarineFacility, A::PredefinedType) => 9,
+        (
+            T::IfcBridgePart
+            | T::IfcRoadPart
+            | T::IfcRailwayPart
+            | T::IfcMarinePart
+            | T::IfcFacilityPartCommon,
+            A::PredefinedType,
+        ) => 10,
+        (T::IfcBuildingStorey, A::Elevation) => 9,
+        (T::IfcSite, A::RefLatitude) => 9,
+        (T::IfcSite, A::RefLongitude) => 10,
+        (T::IfcSite, A::RefElevation) => 11,
+        (T::IfcBuilding, A::ElevationOfRefHeight) => 9,
+
+        (T::IfcTypeObject, A::ApplicableOccurrence) => 4,
+        (T::IfcTypeObject, A::HasPropertySets) => 5,
+        (T::IfcTypeProduct, A::RepresentationMaps) => 6,
+        (T::IfcTypeProduct, A::Tag) => 7,
+
+        (T::IfcPropertySet, A::HasProperties) => 4,
+        (T::IfcElementQuantity, A::Quantities) => 5,
+
+        (T::IfcRelAggregates | T::IfcRelNests, A::RelatingObject) => 4,
+        (T::IfcRelAggregates | T::IfcRelNests, A::RelatedObjects) => 5,
+        (
+            T::IfcRelContainedInSpatialStructure | T::IfcRelReferencedInSpatialStructure,
+            A::RelatedElements,
+        ) => 4,
+        (
+            T::IfcRelContainedInSpatialStructure | T::IfcRelReferencedInSpatialStructure,
+            A::RelatingStructure,
+        ) => 5,
+        (
+            T::IfcRelAssigns
+            | T::IfcRelAssociates
+            | T::IfcRelDefinesByProperties
+            | T::IfcRelDefinesByType,
+            A::RelatedObjects,
+        ) => 4,
+        (T::IfcRelDefinesByProperties, A::RelatingPropertyDefinition) => 5,
+        (T::IfcRelDefinesByType, A::RelatingType) => 5,
+        (T::IfcRelAssociatesMaterial, A::RelatingMaterial) => 5,
+        (T::IfcRelAssignsToGroup, A::RelatingGroup) => 6,
+        (T::IfcRelServicesBuildings, A::RelatingSystem) => 4,
+        (T::IfcRelServicesBuildings, A::RelatedBuildings) => 5,
+        (T::IfcRelVoidsElement, A::RelatingBuildingElement) => 4,
+        (T::IfcRelVoidsElement, A::RelatedOpeningElement) => 5,
+        (T::IfcRelFillsElement, A::RelatingOpeningElement) => 4,
+        (T::IfcRelFillsElement, A::RelatedBuildingElement) => 5,
+        _ => return None,
+    };
+    Some(index)
+}
+
+/// Positions that differ from IFC4X3 in an older schema
+///
+/// `Some(None)` means the entity has no such attribute in that schema.
+fn schema_index(
+    schema: SchemaVersion,
+    ifc_type: IfcType,
+    attribute: Attribute,
+) -> Option<Option<usize>> {
+    use Attribute as A;
+    use IfcType as T;
+
+    match (schema, attribute) {
+        // Few IFC2x3 elements had a type enumeration; the enums other
+        // elements carry there (CompositionType, InteriorOrExteriorSpace,
+        // BarRole) mean something else
+        (SchemaVersion::Ifc2x3, A::PredefinedType) => Some(match ifc_type {
+            T::IfcSlab
+            | T::IfcCovering
+            | T::IfcRailing
+            | T::IfcFooting
+            | T::IfcPile
+            | T::IfcRoof
+            | T::IfcStair
+            | T::IfcRamp
+            | T::IfcTransportElement => Some(8),
+            T::IfcElementAssembly => Some(9),
+            _ => None,
+        }),
+        (SchemaVersion::Ifc4, A::PredefinedType) if ifc_type == T::IfcAnnotation => Some(None),
+        _ => None,
+    }
+}
+
+/// Position of a named attribute on an entity, including inherited ones
+///
+/// Uses IFC4X3 positions; see [`attribute_index_in`] for older files.
+/// Returns `None` if the entity doesn't have the attribute.
+pub fn attribute_index(ifc_type: IfcType, attribute: Attribute) -> Option<usize> {
+    attribute_index_in(SchemaVersion::Ifc4x3, ifc_type, attribute)
+}
+
+/// Position of a named attribute on an entity in a given schema version
+pub fn attribute_index_in(
+    schema: SchemaVersion,
+    ifc_type: IfcType,
+    attribute: Attribute,
+) -> Option<usize> {
+    if let Some(index) = schema_index(schema, ifc_type, attribute) {
+        return index;
+    }
+    let mut current = Some(ifc_type);
+    while let Some(t) = current {
+        if let Some(index) = declared_index(t, attribute) {
+            return Some(index);
+        }
+        current = t.parent();
+    }
+    None
+}
+
+/// Closest IFC4X3 entity for an IFC2x3 or IFC4 name removed from IFC4X3
+///
+/// Expects the uppercase STEP name. Attribute positions of the returned
+/// type are valid for the legacy entity.
+pub fn legacy_type(type_name: &str) -> Option<IfcType> {
+    let ifc_type = match type_name {
+        // IFC4 StandardCase/ElementedCase subtypes
+        "IFCBEAMSTANDARDCASE" => IfcType::IfcBeam,
+        "IFCCOLUMNSTANDARDCASE" => IfcType::IfcColumn,
+        "IFCDOORSTANDARDCASE" => IfcType::IfcDoor,
+        "IFCMEMBERSTANDARDCASE" => IfcType::IfcMember,
+        "IFCOPENINGSTANDARDCASE" => IfcType::IfcOpeningElement,
+        "IFCPLATESTANDARDCASE" => IfcType::IfcPlate,
+        "IFCSLABSTANDARDCASE" | "IFCSLABELEMENTEDCASE" => IfcType::IfcSlab,
+        "IFCWALLELEMENTEDCASE" => IfcType::IfcWall,
+        "IFCWINDOWSTANDARDCASE" => IfcType::IfcWindow,
+        // Renamed in IFC4X3
+        "IFCBUILDINGELEMENT" => IfcType::IfcBuiltElement,
+        "IFCBUILDINGELEMENTTYPE" => IfcType::IfcBuiltElementType,
+        // IFC2x3 elements dropped in IFC4
+        "IFCELECTRICDISTRIBUTIONPOINT" => IfcType::IfcFlowController,
+        "IFCEQUIPMENTELEMENT" | "IFCELECTRICALELEMENT" => IfcType::IfcElement,
+        "IFCPROXY" => IfcType::IfcProduct,
+        "IFCDOORSTYLE" | "IFCWINDOWSTYLE" => IfcType::IfcTypeProduct,
+        // IFC2x3 relationships dropped in IFC4
+        "IFCRELOVERRIDESPROPERTIES" => IfcType::IfcRelDefinesByProperties,
+        "IFCRELASSIGNSTASKS" | "IFCRELSCHEDULESCOSTITEMS" | "IFCRELASSIGNSTOPROJECTORDER" => {
+            IfcType::IfcRelAssignsToControl
+        }
+        "IFCRELOCCUPIESSPACES" => IfcType::IfcRelAssignsToActor,
+        "IFCRELASSOCIATESAPPLIEDVALUE" | "IFCRELASSOCIATESPROFILEPROPERTIES" => {
+            IfcType::IfcRelAssociates
+        }
+        "IFCRELCONNECTSSTRUCTURALELEMENT" => IfcType::IfcRelConnects,
+        _ => return None,
+    };
+    Some(ifc_type)
+}
+
+/// Resolve a STEP type name against IFC4X3, falling back to [`legacy_type`]
+pub fn resolve_type(type_name: &str) -> IfcType {
+    match IfcType::from_str(type_name) {
+        IfcType::Unknown(hash) => legacy_type(type_name).unwrap_or(IfcType::Unknown(hash)),
+        ifc_type => ifc_type,
+    }
+}
+
+/// Classify a STEP type name, including IFC2x3/IFC4 names
+pub fn classify_name(type_name: &str) -> EntityClass {
+    resolve_type(type_name).class()
+}
+
+/// Check if a type name (string) represents an element with potential geometry
+///
+/// True for concrete IfcProduct subtypes in any supported schema. The
+/// entity may still lack a Representation.
+pub fn has_geometry_by_name(type_name: &str) -> bool {
+    match IfcType::from_str(type_name) {
+        // Legacy entities were concrete in their own schema
+        IfcType::Unknown(_) => legacy_type(type_name).is_some_and(|t| t.is_product()),
+        ifc_type => ifc_type.has_geometry(),
+    }
+}
+
+impl IfcType {
+    /// Broad role of this entity in the schema
+    pub fn class(&self) -> EntityClass {
+        if matches!(self, IfcType::Unknown(_)) {
+            return EntityClass::Unknown;
+        }
+        let mut current = Some(*self);
+        while let Some(t) = current {
+            match t {
+                IfcType::IfcSpatialElement => return EntityClass::Spatial,
+                IfcType::IfcElement => return EntityClass::Element,
+                IfcType::IfcProduct => return EntityClass::Product,
+                IfcType::IfcRelationship => return EntityClass::Relationship,
+                IfcType::IfcTypeObject => return EntityClass::TypeObject,
+                IfcType::IfcPropertyDefinition => return EntityClass::PropertyDefinition,
+                IfcType::IfcContext => return EntityClass::Context,
+                IfcType::IfcObject => return EntityClass::Object,
+                _ => current = t.parent(),
+            }
+        }
+        EntityClass::Resource
+    }
+
+    /// Subtype of IfcProduct (placed in the model)
+    pub fn is_product(&self) -> bool {
+        self.is_subtype_of(IfcType::IfcProduct)
+    }
+
+    /// Subtype of IfcSpatialElement
+    pub fn is_spatial(&self) -> bool {
+        self.is_subtype_of(IfcType::IfcSpatialElement)
+    }
+
+    /// Subtype of IfcRelationship
+    pub fn is_relationship(&self) -> bool {
+        self.is_subtype_of(IfcType::IfcRelationship)
+    }
+
+    /// Subtype of IfcTypeObject
+    pub fn is_type_object(&self) -> bool {
+        self.is_subtype_of(IfcType::IfcTypeObject)
+    }
+
+    /// Whether instances of this entity can carry a product representation
+    pub fn has_geometry(&self) -> bool {
+        !self.is_abstract() && self.is_product()
+    }
+
+    /// Position of a named attribute, see [`attribute_index`]
+    pub fn attribute_index(&self, attribute: Attribute) -> Option<usize> {
+        attribute_index(*self, attribute)
+    }
+}
+
+impl DecodedEntity {
+    /// Get an attribute by name, at its position in the entity's schema
+    pub fn attribute(&self, attribute: Attribute) -> Option<&AttributeValue> {
+        self.get(attribute_index_in(self.schema, self.ifc_type, attribute)?)
+    }
+
+    /// Get the Name of an IfcRoot subtype
+    pub fn name(&self) -> Option<&str> {
+        self.attribute(Attribute::Name).and_then(|v| v.as_string())
+    }
+
+    /// Get the Elevation of a building storey
+    pub fn elevation(&self) -> Option<f64> {
+        self.attribute(Attribute::Elevation)
+            .and_then(|v| v.as_float())
+    }
+
+    /// Get an entity reference attribute by name
+    pub fn attribute_ref(&self, attribute: Attribute) -> Option<u32> {
+        self.attribute(attribute).and_then(|v| v.as_entity_ref())
+    }
+
+    /// Get a list attribute by name
+    pub fn attribute_list(&self, attribute: Attribute) -> Option<&[AttributeValue]> {
+        self.attribute(attribute).and_then(|v| v.as_list())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entity_classes() {
+        assert_eq!(IfcType::IfcWall.class(), EntityClass::Element);
+        assert_eq!(IfcType::IfcBuildingStorey.class(), EntityClass::Spatial);
+        assert_eq!(IfcType::IfcRelAggregates.class(), EntityClass::Relationship);
+        assert_eq!(IfcType::IfcWallType.class(), EntityClass::TypeObject);
+        assert_eq!(
+            IfcType::IfcPropertySet.class(),
+            EntityClass::PropertyDefinition
+        );
+        assert_eq!(IfcType::IfcProject.class(), EntityClass::Context);
+        assert_eq!(IfcType::IfcGrid.class(), EntityClass::Product);
+        assert_eq!(IfcType::IfcCartesianPoint.class(), EntityClass::Resource);
+        assert_eq!(classify_name("IFCSLABSTANDARDCASE"), EntityClass::Element);
+        assert_eq!(classify_name("IFCNOTANENTITY"), EntityClass::Unknown);
+    }
+
+    #[test]
+    fn test_geometry_eligibility() {
+        assert!(has_geometry_by_name("IFCWALL"));
+        assert!(has_geometry_by_name("IFCSPACE"));
+        assert!(has_geometry_by_name("IFCWALLSTANDARDCASE"));
+        assert!(has_geometry_by_name("IFCBEAMSTANDARDCASE"));
+        assert!(has_geometry_by_name("IFCELECTRICDISTRIBUTIONPOINT"));
+        assert!(!has_geometry_by_name("IFCWALLTYPE"));
+        assert!(!has_geometry_by_name("IFCRELAGGREGATES"));
+        assert!(!has_geometry_by_name("IFCPROPERTYSET"));
+        assert!(!has_geometry_by_name("IFCPROJECT"));
+        assert!(!has_geometry_by_name("IFCLOCALPLACEMENT"));
+    }
+
+    #[test]
+    fn test_attribute_indices() {
+        use Attribute as A;
+        assert_eq!(IfcType::IfcWall.attribute_index(A::Name), Some(2));
+        assert_eq!(IfcType::IfcWall.attribute_index(A::Tag), Some(7));
+        assert_eq!(IfcType::IfcWall.attribute_index(A::Elevation), None);
+        assert_eq!(
+            IfcType::IfcBuildingStorey.attribute_index(A::Elevation),
+            Some(9)
+        );
+        assert_eq!(
+            IfcType::IfcBuildingStorey.attribute_index(A::LongName),
+            Some(7)
+        );
+        assert_eq!(IfcType::IfcProject.attribute_index(A::LongName), Some(5));
+        assert_eq!(
+            IfcType::IfcRelAggregates.attribute_index(A::RelatingObject),
+            Some(4)
+        );
+        assert_eq!(
+            IfcType::IfcRelContainedInSpatialStructure.attribute_index(A::RelatingStructure),
+            Some(5)
+        );
+        assert_eq!(
+            IfcType::IfcRelDefinesByProperties.attribute_index(A::RelatedObjects),
+            Some(4)
+        );
+        assert_eq!(IfcType::IfcCartesianPoint.attribute_index(A::Name), None);
+    }
+
+    #[test]
+    fn test_schema_specific_indices() {
+        use Attribute as A;
+        use SchemaVersion as S;
+        let index = |schema, ifc_type| attribute_index_in(schema, ifc_type, A::PredefinedType);
+
+        assert_eq!(index(S::Ifc4, IfcType::IfcWall), Some(8));
+        assert_eq!(index(S::Ifc2x3, IfcType::IfcWall), None);
+        assert_eq!(index(S::Ifc2x3, IfcType::IfcSlab), Some(8));
+        assert_eq!(index(S::Ifc4, IfcType::IfcDoor), Some(10));
+        assert_eq!(index(S::Ifc4, IfcType::IfcSpace), Some(9));
+        assert_eq!(index(S::Ifc2x3, IfcType::IfcSpace), None);
+        assert_eq!(index(S::Ifc4, IfcType::IfcBuildingStorey), None);
+        assert_eq!(index(S::Ifc4x3, IfcType::IfcAnnotation), Some(7));
+        assert_eq!(index(S::Ifc4, IfcType::IfcAnnotation), None);
+        assert_eq!(
+            attribute_index_in(S::Ifc2x3, IfcType::IfcBuildingStorey, A::Elevation),
+            Some(9)
+        );
+    }
+
+    #[test]
+    fn test_decoded_entity_accessors() {
+        let storey = DecodedEntity::new(
+            1,
+            IfcType::IfcBuildingStorey,
+            vec![
+                AttributeValue::String("guid".into()),
+                AttributeValue::Null,
+                AttributeValue::String("Level 1".into()),
+                AttributeValue::Null,
+                AttributeValue::Null,
+                AttributeValue::EntityRef(2),
+                AttributeValue::Null,
+                AttributeValue::Null,
+                AttributeValue::Enum("ELEMENT".into()),
+                AttributeValue::Float(3.5),
+            ],
+        );
+        assert_eq!(storey.name(), Some("Level 1"));
+        assert_eq!(storey.elevation(), Some(3.5));
+        assert_eq!(storey.attribute_ref(Attribute::ObjectPlacement), Some(2));
+    }
+}
@@ -7,8 +7,10 @@
 //! Lazily decode IFC entities from byte offsets without loading entire file into memory.
 
 use crate::error::{Error, Result};
-use crate::parser::parse_entity;
+use crate::intern::StringInterner;
+use crate::parser::{parse_attribute, parse_entity};
 use crate::schema_gen::{AttributeValue, DecodedEntity};
+use crate::schema_version::SchemaVersion;
 use rustc_hash::FxHashMap;
 use std::sync::Arc;
 
@@ -114,6 +116,54 @@ fn parse_u32_inline(bytes: &[u8], start: usize, end: usize) -> u32 {
     result
 }
 
+/// Find byte spans of the first `limit` top-level attributes in a raw entity
+/// Handles nested lists, typed values and quoted strings (with '' escapes)
+fn split_attribute_spans(raw: &str, limit: usize) -> Vec<(usize, usize)> {
+    let bytes = raw.as_bytes();
+    let mut spans = Vec::with_capacity(limit);
+    if limit == 0 {
+        return spans;
+    }
+
+    // Attribute list starts after the first '(' (following "#id=TYPE")
+    let Some(open) = memchr::memchr(b'(', bytes) else {
+        return spans;
+    };
+
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut start = open + 1;
+
+    for (pos, &byte) in bytes.iter().enumerate().skip(open + 1) {
+        if in_string {
+            // '' inside a string toggles twice and stays in the string
+            if byte == b'\'' {
+                in_string = false;
+            }
+            continue;
+        }
+        match byte {
+            b'\'' => in_string = true,
+            b'(' => depth += 1,
+            b')' if depth == 0 => {
+                spans.push((start, pos));
+                break;
+            }
+            b')' => depth -= 1,
+            b',' if depth == 0 => {
+                spans.push((start, pos));
+                if spans.len() == limit {
+                    break;
+                }
+                start = pos + 1;
+            }
+            _ => {}
+        }
+    }
+
+    spans
+}
+
 /// Entity decoder for lazy parsing - uses Arc for efficient cache sharing
 pub struct EntityDecoder<'a> {
     content: &'a str,
@@ -121,11 +171,40 @@ pub struct EntityDecoder<'a> {
     /// Using Arc avoids expensive clones on cache hits
     cache: FxHashMap<u32, Arc<DecodedEntity>>,
     /// Index of entity offsets (entity_id -> (start, end))
-    /// Can be pre-built or built lazily
-    entity_index: Option<EntityIndex>,
+    /// Can be pre-built or built lazily; shared by forked decoders
+    entity_index: Option<Arc<EntityIndex>>,
     /// Cached length unit scale (None = not yet extracted)
     /// This is the multiplier to convert IFC length values to meters
     length_unit_scale: Option<f64>,
+    /// Pool of enum values shared by all decoded attributes
+    interner: StringInterner,
+    /// Schema from the FILE_SCHEMA header, IFC4 if absent
+    schema: SchemaVersion,
+}
+
+/// Memory held by an [`EntityDecoder`], see [`EntityDecoder::memory_stats`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DecoderMemoryStats {
+    /// Number of entities in the decode cache
+    pub cached_entities: usize,
+    /// Heap bytes of cached entities (attribute vectors and lists), which
+    /// are not interned
+    pub cache_bytes: usize,
+    /// Number of distinct interned strings, type names and enum values
+    pub interned_strings: usize,
+    /// Bytes of interned string data
+    pub interned_bytes: usize,
+    /// Entries in the entity offset index
+    pub index_entries: usize,
+    /// Approximate heap bytes of the entity offset index
+    pub index_bytes: usize,
+}
+
+impl DecoderMemoryStats {
+    /// Total heap bytes
+    pub fn total_bytes(&self) -> usize {
+        self.cache_bytes + self.interned_bytes + self.index_bytes
+    }
 }
 
 impl<'a> EntityDecoder<'a> {
@@ -136,16 +215,42 @@ impl<'a> EntityDecoder<'a> {
             cache: FxHashMap::default(),
             entity_index: None,
             length_unit_scale: None,
+            interner: StringInterner::new(),
+            schema: SchemaVersion::detect(content).unwrap_or_default(),
         }
     }
 
     /// Create decoder with pre-built index (faster for repeated lookups)
     pub fn with_index(content: &'a str, index: EntityIndex) -> Self {
+        Self::with_shared_index(content, Arc::new(index))
+    }
+
+    /// Create decoder with a pre-built index shared with other decoders
+    pub fn with_shared_index(content: &'a str, index: Arc<EntityIndex>) -> Self {
         Self {
             content,
             cache: FxHashMap::default(),
             entity_index: Some(index),
             length_unit_scale: None,
+            interner: StringInterner::new(),
+            schema: SchemaVersion::detect(content).unwrap_or_default(),
+        }
+    }
+
+    /// Create a decoder for another thread
+    ///
+    /// The fork shares this decoder's content, entity index and unit scale
+    /// but starts with an empty cache, so each thread decodes independently.
+    /// Builds the index first if needed, so it is scanned only once.
+    pub fn fork(&mut self) -> EntityDecoder<'a> {
+        self.build_index();
+        Self {
+            content: self.content,
+            cache: FxHashMap::default(),
+            entity_index: self.entity_index.clone(),
+            length_unit_scale: self.length_unit_scale,
+            interner: self.interner.clone(),
+            schema: self.schema,
         }
     }
 
@@ -155,7 +260,17 @@ impl<'a> EntityDecoder<'a> {
         if self.entity_index.is_some() {
             return; // Already built
         }
-        self.entity_index = Some(build_entity_index(self.content));
+        self.entity_index = Some(Arc::new(build_entity_index(self.content)));
+    }
+
+    /// Schema version the file declares, IFC4 if its header names none
+    pub fn schema(&self) -> SchemaVersion {
+        self.schema
+    }
+
+    /// Override the schema version, e.g. for content without a header
+    pub fn set_schema(&mut self, schema: SchemaVersion) {
+        self.schema = schema;
     }
 
     /// Get the cached length unit scale (multiplier to convert to meters)
@@ -206,12 +321,13 @@ impl<'a> EntityDecoder<'a> {
         }
 
         // Convert tokens to AttributeValues
+        let interner = &mut self.interner;
         let attributes = tokens
             .iter()
-            .map(|token| AttributeValue::from_token(token))
+            .map(|token| AttributeValue::from_token_interned(token, interner))
             .collect();
 
-        let entity = DecodedEntity::new(id, ifc_type, attributes);
+        let entity = DecodedEntity::new(id, ifc_type, attributes).with_schema(self.schema);
         self.cache.insert(id, Arc::new(entity.clone()));
         Ok(entity)
     }
@@ -237,6 +353,42 @@ impl<'a> EntityDecoder<'a> {
         self.decode_at(start, end)
     }
 
+    /// Decode only selected attributes of an entity by ID
+    ///
+    /// Splits the raw attribute list at top-level commas and tokenizes only the
+    /// requested indices, e.g. `decode_attrs_by_id(id, &[4, 5])` for the two
+    /// refs of a relationship. Values are returned in the order of `indices`;
+    /// out-of-range indices yield `AttributeValue::Null`. Partial results are
+    /// not cached, but already cached entities are served from the cache.
+    pub fn decode_attrs_by_id(
+        &mut self,
+        entity_id: u32,
+        indices: &[usize],
+    ) -> Result<Vec<AttributeValue>> {
+        if let Some(entity_arc) = self.cache.get(&entity_id) {
+            return Ok(indices
+                .iter()
+                .map(|&i| entity_arc.get(i).cloned().unwrap_or(AttributeValue::Null))
+                .collect());
+        }
+
+        let raw = self
+            .get_raw_content(entity_id)
+            .ok_or_else(|| Error::parse(0, format!("Entity #{} not found", entity_id)))?;
+
+        let limit = indices.iter().max().map(|&i| i + 1).unwrap_or(0);
+        let spans = split_attribute_spans(raw, limit);
+
+        indices
+            .iter()
+            .map(|&i| match spans.get(i) {
+                Some(&(start, end)) => parse_attribute(&raw[start..end])
+                    .map(|tok| AttributeValue::from_token_interned(&tok, &mut self.interner)),
+                None => Ok(AttributeValue::Null),
+            })
+            .collect()
+    }
+
     /// Resolve entity reference (follow #ID)
     /// Returns None for null/derived values
     #[inline]
@@ -277,6 +429,36 @@ impl<'a> EntityDecoder<'a> {
         self.cache.len()
     }
 
+    /// Report the memory held by the cache, interner and index
+    ///
+    /// Sizes are estimates from capacities; allocator overhead is not counted.
+    pub fn memory_stats(&self) -> DecoderMemoryStats {
+        let index_entries = self.entity_index.as_ref().map_or(0, |idx| idx.len());
+        let index_capacity = self.entity_index.as_ref().map_or(0, |idx| idx.capacity());
+        DecoderMemoryStats {
+            cached_entities: self.cache.len(),
+            cache_bytes: self.cache.capacity() * std::mem::size_of::<(u32, Arc<DecodedEntity>)>()
+                + self
+                    .cache
+                    .values()
+                    .map(|e| std::mem::size_of::<DecodedEntity>() + e.heap_size())
+                    .sum::<usize>(),
+            interned_strings: self.interner.len(),
+            interned_bytes: self.interner.heap_bytes(),
+            index_entries,
+            index_bytes: index_capacity * std::mem::size_of::<(u32, (usize, usize))>(),
+        }
+    }
+
+    /// Byte offset where an entity starts, e.g. for line numbers in diagnostics
+    pub fn entity_offset(&mut self, entity_id: u32) -> Option<usize> {
+        self.build_index();
+        self.entity_index
+            .as_ref()?
+            .get(&entity_id)
+            .map(|&(start, _)| start)
+    }
+
     /// Get raw bytes for an entity (for direct/fast parsing)
     /// Returns the full entity line including type and attributes
     #[inline]
@@ -590,6 +772,112 @@ mod tests {
     use super::*;
     use crate::IfcType;
 
+    #[test]
+    fn test_decode_attrs_by_id() {
+        let content = r#"
+#1=IFCWALL('guid',$,'Wall, (north)',$,$,$,$,$);
+#2=IFCRELFILLSELEMENT('guid',$,'It''s, a name',$,#10,#11);
+#3=IFCRELAGGREGATES('guid',$,$,$,#1,(#20,#21));
+"#;
+
+        let mut decoder = EntityDecoder::new(content);
+
+        let attrs = decoder.decode_attrs_by_id(2, &[4, 5]).unwrap();
+        assert_eq!(attrs[0].as_entity_ref(), Some(10));
+        assert_eq!(attrs[1].as_entity_ref(), Some(11));
+
+        // Order follows the requested indices; out of range is Null
+        let attrs = decoder.decode_attrs_by_id(3, &[5, 4, 9]).unwrap();
+        assert_eq!(attrs[0].as_list().map(|l| l.len()), Some(2));
+        assert_eq!(attrs[1].as_entity_ref(), Some(1));
+        assert!(attrs[2].is_null());
+
+        // Commas and parentheses inside strings don't split attributes
+        let attrs = decoder.decode_attrs_by_id(1, &[2, 3]).unwrap();
+        assert_eq!(attrs[0].as_string(), Some("Wall, (north)"));
+        assert!(attrs[1].is_null());
+
+        // Selective decoding does not populate the cache
+        assert_eq!(decoder.cache_size(), 0);
+        let full = decoder.decode_by_id(2).unwrap();
+        let attrs = decoder.decode_attrs_by_id(2, &[2]).unwrap();
+        assert_eq!(attrs[0].as_string(), full.get_string(2));
+    }
+
+    #[test]
+    fn test_enum_interning_and_memory_stats() {
+        let content = r#"
+#1=IFCWALL('a',$,$,$,$,$,$,$,.NOTDEFINED.);
+#2=IFCWALL('b',$,$,$,$,$,$,$,.NOTDEFINED.);
+#3=IFCSLAB('c',$,$,$,$,$,$,$,.FLOOR.);
+"#;
+
+        let mut decoder = EntityDecoder::new(content);
+        let a = decoder.decode_by_id(1).unwrap();
+        let b = decoder.decode_by_id(2).unwrap();
+        decoder.decode_by_id(3).unwrap();
+
+        // Same enum value in two entities shares one allocation
+        match (a.get(8), b.get(8)) {
+            (Some(AttributeValue::Enum(x)), Some(AttributeValue::Enum(y))) => {
+                assert!(Arc::ptr_eq(x, y));
+            }
+            other => panic!("expected enums, got {:?}", other),
+        }
+
+        let stats = decoder.memory_stats();
+        assert_eq!(stats.cached_entities, 3);
+        // 'a', 'b', 'c', NOTDEFINED and FLOOR
+        assert_eq!(stats.interned_strings, 5);
+        assert_eq!(stats.interned_bytes, 3 + "NOTDEFINED".len() + "FLOOR".len());
+        assert_eq!(stats.index_entries, 3);
+        assert!(stats.cache_bytes > 0);
+
+        decoder.clear_cache();
+        assert_eq!(decoder.memory_stats().cached_entities, 0);
+    }
+
+    #[test]
+    fn test_string_interning_saving() {
+        // Property values repeat their name and measure type
+        let count = 500;
+        let content: String = (1..=count)
+            .map(|id| {
+                format!(
+                    "#{}=IFCPROPERTYSINGLEVALUE('Width',$,IFCLENGTHMEASURE({}.),$);\n",
+                    id, id
+                )
+            })
+            .collect();
+
+        let mut decoder = EntityDecoder::new(&content);
+        let first = decoder.decode_by_id(1).unwrap();
+        for id in 2..=count {
+            decoder.decode_by_id(id).unwrap();
+        }
+        let last = decoder.decode_by_id(count).unwrap();
+
+        let type_name = |entity: &DecodedEntity| match entity.get(2).and_then(|v| v.as_list()) {
+            Some([AttributeValue::String(name), _]) => Arc::clone(name),
+            other => panic!("expected a typed value, got {:?}", other),
+        };
+        assert!(Arc::ptr_eq(&type_name(&first), &type_name(&last)));
+        match (first.get(0), last.get(0)) {
+            (Some(AttributeValue::String(x)), Some(AttributeValue::String(y))) => {
+                assert!(Arc::ptr_eq(x, y));
+            }
+            other => panic!("expected strings, got {:?}", other),
+        }
+
+        // 21 bytes of string data for all 500 entities instead of 21 each
+        let stats = decoder.memory_stats();
+        assert_eq!(stats.interned_strings, 2);
+        assert_eq!(
+            stats.interned_bytes,
+            "Width".len() + "IFCLENGTHMEASURE".len()
+        );
+    }
+
     #[test]
     fn test_decode_entity() {
         let content = r#"
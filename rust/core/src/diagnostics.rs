@@ -0,0 +1,259 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Diagnostics - per-entity problems collected while loading a model
+//!
+//! Loaders keep going when an entity is malformed or its geometry fails and
+//! record what happened here instead, with the line it sits on, so users
+//! can see which parts of the file were dropped and why.
+
+use std::fmt;
+
+/// How serious a diagnostic is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// Processing stage a diagnostic was raised in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum DiagnosticKind {
+    /// Header or schema problems
+    Schema,
+    /// Entity could not be tokenized or decoded
+    Parse,
+    /// Required entities missing or references that don't resolve
+    Structure,
+    /// Entity decoded but its geometry could not be built
+    Geometry,
+    /// Writing an export of the model failed
+    Output,
+}
+
+/// One recorded problem
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub kind: DiagnosticKind,
+    /// Entity the problem belongs to, if any
+    pub entity_id: Option<u32>,
+    /// Uppercase STEP type name of that entity
+    pub entity_type: Option<String>,
+    /// 1-based line number in the file, if known
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(f, "{}", severity)?;
+        if let Some(line) = self.line {
+            write!(f, " line {}", line)?;
+        }
+        if let Some(id) = self.entity_id {
+            write!(f, " #{}", id)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+/// Byte offset to line number lookup, built once per file
+#[derive(Debug, Clone)]
+pub struct LineIndex {
+    /// Offsets of every '\n'
+    newlines: Vec<usize>,
+}
+
+impl LineIndex {
+    pub fn new(content: &str) -> Self {
+        Self {
+            newlines: memchr::memchr_iter(b'\n', content.as_bytes()).collect(),
+        }
+    }
+
+    /// 1-based line number of a byte offset
+    pub fn line(&self, offset: usize) -> usize {
+        self.newlines.partition_point(|&n| n < offset) + 1
+    }
+}
+
+/// Collector for [`Diagnostic`]s
+///
+/// Keeps at most `limit` entries so a badly broken file can't grow the
+/// report without bound; the rest are only counted.
+#[derive(Debug, Clone)]
+pub struct Diagnostics {
+    items: Vec<Diagnostic>,
+    limit: usize,
+    dropped: usize,
+}
+
+impl Default for Diagnostics {
+    fn default() -> Self {
+        Self::with_limit(Self::DEFAULT_LIMIT)
+    }
+}
+
+impl Diagnostics {
+    /// Entries kept by [`Diagnostics::new`]
+    pub const DEFAULT_LIMIT: usize = 1000;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_limit(limit: usize) -> Self {
+        Self {
+            items: Vec::new(),
+            limit,
+            dropped: 0,
+        }
+    }
+
+    /// Record a diagnostic
+    pub fn push(&mut self, diagnostic: Diagnostic) {
+        if self.items.len() < self.limit {
+            self.items.push(diagnostic);
+        } else {
+            self.dropped += 1;
+        }
+    }
+
+    /// Record a problem with one entity
+    pub fn entity(
+        &mut self,
+        severity: Severity,
+        kind: DiagnosticKind,
+        entity_id: u32,
+        entity_type: &str,
+        line: Option<usize>,
+        message: impl Into<String>,
+    ) {
+        self.push(Diagnostic {
+            severity,
+            kind,
+            entity_id: Some(entity_id),
+            entity_type: Some(entity_type.to_ascii_uppercase()),
+            line,
+            message: message.into(),
+        });
+    }
+
+    /// Record a problem with the file as a whole
+    pub fn file(&mut self, severity: Severity, kind: DiagnosticKind, message: impl Into<String>) {
+        self.push(Diagnostic {
+            severity,
+            kind,
+            entity_id: None,
+            entity_type: None,
+            line: None,
+            message: message.into(),
+        });
+    }
+
+    /// Append another collector's entries, e.g. from a worker thread
+    pub fn extend(&mut self, other: Diagnostics) {
+        self.dropped += other.dropped;
+        for diagnostic in other.items {
+            self.push(diagnostic);
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.items.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty() && self.dropped == 0
+    }
+
+    /// Entries past the limit that were counted but not kept
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// Number of kept entries with the given severity
+    pub fn count(&self, severity: Severity) -> usize {
+        self.items.iter().filter(|d| d.severity == severity).count()
+    }
+
+    /// Sort by line, file-level entries first
+    pub fn sort(&mut self) {
+        self.items.sort_by_key(|d| (d.line, d.entity_id));
+    }
+
+    pub fn into_vec(self) -> Vec<Diagnostic> {
+        self.items
+    }
+}
+
+impl IntoIterator for Diagnostics {
+    type Item = Diagnostic;
+    type IntoIter = std::vec::IntoIter<Diagnostic>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.items.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_index() {
+        let lines = LineIndex::new("a\nbb\n\nc");
+        assert_eq!(lines.line(0), 1);
+        assert_eq!(lines.line(1), 1);
+        assert_eq!(lines.line(2), 2);
+        assert_eq!(lines.line(5), 3);
+        assert_eq!(lines.line(6), 4);
+    }
+
+    #[test]
+    fn test_collect_and_limit() {
+        let mut diagnostics = Diagnostics::with_limit(2);
+        diagnostics.entity(
+            Severity::Error,
+            DiagnosticKind::Parse,
+            7,
+            "IfcWall",
+            Some(12),
+            "unexpected end of input",
+        );
+        diagnostics.file(
+            Severity::Warning,
+            DiagnosticKind::Schema,
+            "Missing FILE_SCHEMA header",
+        );
+        diagnostics.file(Severity::Warning, DiagnosticKind::Schema, "dropped");
+        diagnostics.sort();
+
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics.dropped(), 1);
+        assert_eq!(diagnostics.count(Severity::Error), 1);
+        let first = diagnostics.iter().next().unwrap();
+        assert_eq!(first.line, None);
+        let wall = diagnostics.iter().nth(1).unwrap();
+        assert_eq!(wall.entity_type.as_deref(), Some("IFCWALL"));
+        assert_eq!(
+            wall.to_string(),
+            "error line 12 #7: unexpected end of input"
+        );
+    }
+}
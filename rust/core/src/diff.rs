@@ -0,0 +1,389 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Model comparison - what changed between two versions of a model
+//!
+//! Elements are matched by GlobalId, since entity ids are renumbered on
+//! every export. Each matched pair is compared by two hashes: one over the
+//! element's own attributes and one over its placement and representation.
+//! Referenced entities are hashed by content rather than id (a Merkle hash
+//! of the subgraph), so a renumbered but otherwise identical file shows no
+//! changes while a moved storey marks everything placed relative to it.
+
+use crate::classify::{attribute_index_in, resolve_type, Attribute};
+use crate::decoder::{build_entity_index, EntityIndex};
+use crate::parser::{parse_entity, EntityScanner, Token};
+use crate::schema_gen::{AttributeValue, DecodedEntity};
+use crate::schema_version::SchemaVersion;
+use rustc_hash::{FxHashMap, FxHasher};
+use std::hash::{Hash, Hasher};
+
+/// Resolution real values are compared at, so re-exports that only change
+/// the number of written digits don't count as changes
+const REAL_RESOLUTION: f64 = 1e-6;
+
+/// How an element differs between the old and the new model
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum DiffStatus {
+    Added,
+    Removed,
+    Modified,
+}
+
+impl DiffStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Added => "added",
+            Self::Removed => "removed",
+            Self::Modified => "modified",
+        }
+    }
+}
+
+/// One element that was added, removed or modified
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ElementDiff {
+    pub global_id: String,
+    pub status: DiffStatus,
+    /// IFC type name, from the new model unless the element was removed
+    pub ifc_type: String,
+    pub name: Option<String>,
+    /// Entity id in the old model
+    pub old_id: Option<u32>,
+    /// Entity id in the new model
+    pub new_id: Option<u32>,
+    /// Own attributes differ (name, tag, predefined type, ...)
+    pub attributes_changed: bool,
+    /// Placement or representation differ
+    pub geometry_changed: bool,
+}
+
+/// Differences between two versions of a model
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ModelDiff {
+    /// Changed elements, sorted by status and GlobalId
+    pub elements: Vec<ElementDiff>,
+    /// Elements present and identical in both models
+    pub unchanged: usize,
+}
+
+impl ModelDiff {
+    /// Compare the products of two IFC files by GlobalId
+    pub fn compare(old: &str, new: &str) -> Self {
+        let old = Snapshot::new(old).elements();
+        let mut new = Snapshot::new(new).elements();
+
+        let mut diff = ModelDiff::default();
+        for (global_id, before) in old {
+            match new.remove(&global_id) {
+                Some(after) => {
+                    let attributes_changed = before.attributes != after.attributes;
+                    let geometry_changed = before.geometry != after.geometry;
+                    if attributes_changed || geometry_changed {
+                        diff.elements.push(ElementDiff {
+                            global_id,
+                            status: DiffStatus::Modified,
+                            ifc_type: after.ifc_type,
+                            name: after.name,
+                            old_id: Some(before.id),
+                            new_id: Some(after.id),
+                            attributes_changed,
+                            geometry_changed,
+                        });
+                    } else {
+                        diff.unchanged += 1;
+                    }
+                }
+                None => diff.elements.push(ElementDiff {
+                    global_id,
+                    status: DiffStatus::Removed,
+                    ifc_type: before.ifc_type,
+                    name: before.name,
+                    old_id: Some(before.id),
+                    new_id: None,
+                    attributes_changed: false,
+                    geometry_changed: false,
+                }),
+            }
+        }
+        diff.elements
+            .extend(new.into_iter().map(|(global_id, after)| ElementDiff {
+                global_id,
+                status: DiffStatus::Added,
+                ifc_type: after.ifc_type,
+                name: after.name,
+                old_id: None,
+                new_id: Some(after.id),
+                attributes_changed: false,
+                geometry_changed: false,
+            }));
+
+        diff.elements
+            .sort_by(|a, b| (a.status, &a.global_id).cmp(&(b.status, &b.global_id)));
+        diff
+    }
+
+    /// Whether the models contain the same elements, unchanged
+    pub fn is_empty(&self) -> bool {
+        self.elements.is_empty()
+    }
+
+    /// Number of elements with the given status
+    pub fn count(&self, status: DiffStatus) -> usize {
+        self.elements.iter().filter(|e| e.status == status).count()
+    }
+
+    /// Status of an entity of the new model, if it changed
+    pub fn status_of(&self, new_id: u32) -> Option<DiffStatus> {
+        self.elements
+            .iter()
+            .find(|e| e.new_id == Some(new_id))
+            .map(|e| e.status)
+    }
+
+    /// One line per changed element, with a header
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "GlobalId,Status,Type,Name,OldId,NewId,AttributesChanged,GeometryChanged\n",
+        );
+        let id = |id: Option<u32>| id.map(|id| format!("#{}", id)).unwrap_or_default();
+        for e in &self.elements {
+            csv.push_str(&format!(
+                "{},{},{},\"{}\",{},{},{},{}\n",
+                e.global_id,
+                e.status.as_str(),
+                e.ifc_type,
+                e.name.as_deref().unwrap_or("").replace('"', "\"\""),
+                id(e.old_id),
+                id(e.new_id),
+                e.attributes_changed,
+                e.geometry_changed
+            ));
+        }
+        csv
+    }
+}
+
+/// Hashes of one product
+struct ElementHashes {
+    id: u32,
+    ifc_type: String,
+    name: Option<String>,
+    attributes: u64,
+    geometry: u64,
+}
+
+/// One model being compared, with memoized subgraph hashes
+struct Snapshot<'a> {
+    content: &'a str,
+    index: EntityIndex,
+    schema: SchemaVersion,
+    hashes: FxHashMap<u32, u64>,
+}
+
+impl<'a> Snapshot<'a> {
+    fn new(content: &'a str) -> Self {
+        Self {
+            content,
+            index: build_entity_index(content),
+            schema: SchemaVersion::detect(content).unwrap_or_default(),
+            hashes: FxHashMap::default(),
+        }
+    }
+
+    /// Hashes of every product with a GlobalId, keyed by GlobalId
+    fn elements(mut self) -> FxHashMap<String, ElementHashes> {
+        let content = self.content;
+        let schema = self.schema;
+        let mut elements = FxHashMap::default();
+        let mut scanner = EntityScanner::new(content);
+        while let Some((id, type_name, start, end)) = scanner.next_entity() {
+            let ifc_type = resolve_type(&type_name.to_ascii_uppercase());
+            if !ifc_type.is_product() {
+                continue;
+            }
+            let Ok((_, _, tokens)) = parse_entity(&content[start..end]) else {
+                continue;
+            };
+            let entity = DecodedEntity::new(
+                id,
+                ifc_type,
+                tokens.iter().map(AttributeValue::from_token).collect(),
+            )
+            .with_schema(schema);
+            let Some(global_id) = entity
+                .attribute(Attribute::GlobalId)
+                .and_then(|v| v.as_string())
+            else {
+                continue;
+            };
+
+            let position = |attribute| attribute_index_in(schema, ifc_type, attribute);
+            let geometry_positions = [
+                position(Attribute::ObjectPlacement),
+                position(Attribute::Representation),
+            ];
+            // OwnerHistory changes on every save
+            let skipped = [position(Attribute::OwnerHistory)];
+
+            let mut attributes = FxHasher::default();
+            let mut geometry = FxHasher::default();
+            type_name.to_ascii_uppercase().hash(&mut attributes);
+            for (i, token) in tokens.iter().enumerate() {
+                if skipped.contains(&Some(i)) {
+                    continue;
+                }
+                if geometry_positions.contains(&Some(i)) {
+                    self.hash_token(token, &mut geometry);
+                } else {
+                    self.hash_token(token, &mut attributes);
+                }
+            }
+
+            elements.insert(
+                global_id.to_string(),
+                ElementHashes {
+                    id,
+                    ifc_type: ifc_type.name().to_string(),
+                    name: entity.name().map(str::to_string),
+                    attributes: attributes.finish(),
+                    geometry: geometry.finish(),
+                },
+            );
+        }
+        elements
+    }
+
+    /// Content hash of an entity and everything it references
+    fn entity_hash(&mut self, id: u32) -> u64 {
+        if let Some(&hash) = self.hashes.get(&id) {
+            return hash;
+        }
+        // Placeholder breaks reference cycles
+        self.hashes.insert(id, 0);
+
+        let content = self.content;
+        let hash = match self
+            .index
+            .get(&id)
+            .map(|&(start, end)| &content[start..end])
+        {
+            Some(raw) => match parse_entity(raw) {
+                Ok((_, ifc_type, tokens)) => {
+                    let mut hasher = FxHasher::default();
+                    ifc_type.hash(&mut hasher);
+                    for token in &tokens {
+                        self.hash_token(token, &mut hasher);
+                    }
+                    hasher.finish()
+                }
+                Err(_) => raw.len() as u64,
+            },
+            // Dangling reference
+            None => u64::MAX,
+        };
+        self.hashes.insert(id, hash);
+        hash
+    }
+
+    fn hash_token(&mut self, token: &Token, hasher: &mut FxHasher) {
+        match token {
+            Token::EntityRef(id) => {
+                0u8.hash(hasher);
+                self.entity_hash(*id).hash(hasher);
+            }
+            Token::String(s) => {
+                1u8.hash(hasher);
+                s.hash(hasher);
+            }
+            Token::Integer(i) => {
+                2u8.hash(hasher);
+                i.hash(hasher);
+            }
+            Token::Float(f) => {
+                3u8.hash(hasher);
+                ((f / REAL_RESOLUTION).round() as i64).hash(hasher);
+            }
+            Token::Enum(e) => {
+                4u8.hash(hasher);
+                e.hash(hasher);
+            }
+            Token::List(items) => {
+                5u8.hash(hasher);
+                items.len().hash(hasher);
+                for item in items {
+                    self.hash_token(item, hasher);
+                }
+            }
+            Token::TypedValue(type_name, args) => {
+                6u8.hash(hasher);
+                type_name.hash(hasher);
+                for arg in args {
+                    self.hash_token(arg, hasher);
+                }
+            }
+            Token::Null => 7u8.hash(hasher),
+            Token::Derived => 8u8.hash(hasher),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD: &str = "DATA;\n\
+#1=IFCCARTESIANPOINT((0.,0.,0.));\n\
+#2=IFCAXIS2PLACEMENT3D(#1,$,$);\n\
+#3=IFCLOCALPLACEMENT($,#2);\n\
+#10=IFCWALL('0a00000000000000000000',$,'Wall',$,$,#3,$,$,$);\n\
+#11=IFCWALL('0b00000000000000000000',$,'Kept',$,$,#3,$,$,$);\n\
+#12=IFCDOOR('0c00000000000000000000',$,'Door',$,$,#3,$,$,$,$,$,$,$);\n\
+#13=IFCSLAB('0d00000000000000000000',$,'Slab',$,$,#3,$,$,$);\n";
+
+    // Renumbered, wall moved, slab renamed, door removed, column added
+    const NEW: &str = "DATA;\n\
+#5=IFCCARTESIANPOINT((0.,0.,0.));\n\
+#6=IFCAXIS2PLACEMENT3D(#5,$,$);\n\
+#7=IFCLOCALPLACEMENT($,#6);\n\
+#8=IFCCARTESIANPOINT((1.,0.,0.));\n\
+#9=IFCAXIS2PLACEMENT3D(#8,$,$);\n\
+#20=IFCLOCALPLACEMENT($,#9);\n\
+#21=IFCWALL('0a00000000000000000000',#99,'Wall',$,$,#20,$,$,$);\n\
+#22=IFCWALL('0b00000000000000000000',$,'Kept',$,$,#7,$,$,$);\n\
+#23=IFCSLAB('0d00000000000000000000',$,'Floor',$,$,#7,$,$,$);\n\
+#24=IFCCOLUMN('0e00000000000000000000',$,'Column',$,$,#7,$,$,$);\n";
+
+    #[test]
+    fn test_compare() {
+        let diff = ModelDiff::compare(OLD, NEW);
+        assert_eq!(diff.unchanged, 1);
+        assert_eq!(diff.count(DiffStatus::Added), 1);
+        assert_eq!(diff.count(DiffStatus::Removed), 1);
+        assert_eq!(diff.count(DiffStatus::Modified), 2);
+
+        let by_id = |global_id: &str| {
+            diff.elements
+                .iter()
+                .find(|e| e.global_id == global_id)
+                .unwrap()
+        };
+        let wall = by_id("0a00000000000000000000");
+        assert!(wall.geometry_changed && !wall.attributes_changed);
+        assert_eq!((wall.old_id, wall.new_id), (Some(10), Some(21)));
+        let slab = by_id("0d00000000000000000000");
+        assert!(slab.attributes_changed && !slab.geometry_changed);
+        assert_eq!(slab.name.as_deref(), Some("Floor"));
+        assert_eq!(by_id("0c00000000000000000000").status, DiffStatus::Removed);
+        assert_eq!(diff.status_of(24), Some(DiffStatus::Added));
+        assert_eq!(diff.status_of(22), None);
+
+        assert!(ModelDiff::compare(OLD, OLD).is_empty());
+        assert_eq!(diff.to_csv().lines().count(), 5);
+    }
+}
@@ -16,6 +16,9 @@ pub enum Error {
     #[error("Invalid entity reference: #{0}")]
     InvalidEntityRef(u32),
 
+    #[error("Entity #{entity} has no attribute {index}")]
+    InvalidAttribute { entity: u32, index: usize },
+
     #[error("Invalid IFC type: {0}")]
     InvalidIfcType(String),
 
@@ -26,6 +29,15 @@ pub enum Error {
         got: String,
     },
 
+    #[error("Invalid query at position {position}: {message}")]
+    InvalidQuery { position: usize, message: String },
+
+    #[error("Invalid BCF: {0}")]
+    Bcf(String),
+
+    #[error("Cannot apply change: {0}")]
+    Change(String),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
@@ -41,6 +53,21 @@ impl Error {
         }
     }
 
+    pub fn bcf(message: impl Into<String>) -> Self {
+        Self::Bcf(message.into())
+    }
+
+    pub fn change(message: impl Into<String>) -> Self {
+        Self::Change(message.into())
+    }
+
+    pub fn query(position: usize, message: impl Into<String>) -> Self {
+        Self::InvalidQuery {
+            position,
+            message: message.into(),
+        }
+    }
+
     pub fn unexpected(
         position: usize,
         expected: impl Into<String>,
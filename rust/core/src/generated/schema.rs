@@ -6510,17 +6510,6 @@ impl fmt::Display for IfcType {
     }
 }
 
-// ===== MANUALLY ADDED HELPER FUNCTION =====
-
-/// Check if a type name (string) represents an element with potential geometry
-/// Simplified check: IFC prefix, not a TYPE template, not a relationship, not a property
-pub fn has_geometry_by_name(type_name: &str) -> bool {
-    type_name.starts_with("IFC")
-        && !type_name.ends_with("TYPE")
-        && !type_name.starts_with("IFCREL")
-        && !type_name.starts_with("IFCPROPERTY")
-}
-
 /// CRC32 hash function for unknown types
 fn crc32_hash(s: &str) -> u32 {
     const TABLE: [u32; 256] = [
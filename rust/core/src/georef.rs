@@ -6,11 +6,14 @@
 //!
 //! Handles IfcMapConversion and IfcProjectedCRS for coordinate transformations.
 //! Supports both IFC4 native entities and IFC2X3 ePSet_MapConversion fallback.
+//! The IfcSite reference latitude and longitude are read separately, since
+//! many models carry those without a map conversion.
 
 use crate::decoder::EntityDecoder;
 use crate::error::Result;
 use crate::generated::IfcType;
-use crate::schema_gen::DecodedEntity;
+use crate::schema_gen::{AttributeValue, DecodedEntity};
+use crate::units::{get_conversion_based_unit_factor, get_si_prefix_multiplier};
 
 /// Georeferencing information extracted from IFC model
 #[derive(Debug, Clone)]
@@ -35,6 +38,8 @@ pub struct GeoReference {
     pub x_axis_ordinate: f64,
     /// Scale factor (default 1.0)
     pub scale: f64,
+    /// Metres per map unit, from the CRS MapUnit (default 1.0)
+    pub map_unit_scale: f64,
 }
 
 impl Default for GeoReference {
@@ -50,6 +55,7 @@ impl Default for GeoReference {
             x_axis_abscissa: 1.0, // No rotation (cos(0) = 1)
             x_axis_ordinate: 0.0, // No rotation (sin(0) = 0)
             scale: 1.0,
+            map_unit_scale: 1.0,
         }
     }
 }
@@ -75,6 +81,40 @@ impl GeoReference {
         self.x_axis_ordinate.atan2(self.x_axis_abscissa)
     }
 
+    /// EPSG code of the target CRS, if its name carries one
+    ///
+    /// Accepts "EPSG:32632", OGC URNs like "urn:ogc:def:crs:EPSG::32632"
+    /// and URLs like "http://www.opengis.net/def/crs/EPSG/0/32632".
+    pub fn epsg_code(&self) -> Option<u32> {
+        let name = self.crs_name.as_deref()?.to_ascii_uppercase();
+        let rest = &name[name.find("EPSG")? + 4..];
+        // Skip the "0" version segment of OGC URLs
+        rest.split(|c: char| !c.is_ascii_digit())
+            .filter(|part| !part.is_empty() && *part != "0")
+            .find_map(|part| part.parse().ok())
+    }
+
+    /// The same conversion for local coordinates in metres, mapping to metres
+    ///
+    /// Offsets are given in the map unit and Scale converts project length
+    /// units to map units; `length_scale` is the project length unit in
+    /// metres, as used for the geometry.
+    pub fn in_metres(&self, length_scale: f64) -> GeoReference {
+        let length_scale = if length_scale > 0.0 {
+            length_scale
+        } else {
+            1.0
+        };
+        GeoReference {
+            eastings: self.eastings * self.map_unit_scale,
+            northings: self.northings * self.map_unit_scale,
+            orthogonal_height: self.orthogonal_height * self.map_unit_scale,
+            scale: self.scale * self.map_unit_scale / length_scale,
+            map_unit_scale: 1.0,
+            ..self.clone()
+        }
+    }
+
     /// Transform local coordinates to map coordinates
     #[inline]
     pub fn local_to_map(&self, x: f64, y: f64, z: f64) -> (f64, f64, f64) {
@@ -140,6 +180,17 @@ impl GeoReference {
     }
 }
 
+/// Reference location of a site on the globe
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SiteLocation {
+    /// Latitude in decimal degrees, north positive
+    pub latitude: f64,
+    /// Longitude in decimal degrees, east positive
+    pub longitude: f64,
+    /// Elevation above sea level in project length units
+    pub elevation: Option<f64>,
+}
+
 /// Extract georeferencing from IFC content
 pub struct GeoRefExtractor;
 
@@ -185,7 +236,7 @@ impl GeoRefExtractor {
         //             MapProjection, MapZone, MapUnit
         if let Some(id) = projected_crs_id {
             let entity = decoder.decode_by_id(id)?;
-            Self::parse_projected_crs(&entity, &mut georef);
+            Self::parse_projected_crs(decoder, &entity, &mut georef);
         }
 
         if georef.has_georef() {
@@ -195,6 +246,68 @@ impl GeoRefExtractor {
         }
     }
 
+    /// Extract the true north angle from the model's IfcGeometricRepresentationContext
+    ///
+    /// Returns the counter-clockwise angle in radians from the model +Y axis to
+    /// true north, or `None` when the context carries no TrueNorth direction.
+    pub fn true_north(
+        decoder: &mut EntityDecoder,
+        entity_types: &[(u32, IfcType)],
+    ) -> Result<Option<f64>> {
+        // Sub-contexts derive TrueNorth from their parent, so only the main
+        // context is inspected.
+        for (id, ifc_type) in entity_types {
+            if *ifc_type != IfcType::IfcGeometricRepresentationContext {
+                continue;
+            }
+            let context = decoder.decode_by_id(*id)?;
+            // Index 5: TrueNorth (IfcDirection)
+            let Some(direction_id) = context.get_ref(5) else {
+                continue;
+            };
+            let direction = decoder.decode_by_id(direction_id)?;
+            // IfcDirection: DirectionRatios (0)
+            let ratios: Vec<f64> = direction
+                .get_list(0)
+                .map(|list| list.iter().filter_map(|v| v.as_float()).collect())
+                .unwrap_or_default();
+            if ratios.len() < 2 {
+                continue;
+            }
+            let (x, y) = (ratios[0], ratios[1]);
+            if x.abs() < f64::EPSILON && y.abs() < f64::EPSILON {
+                continue;
+            }
+            return Ok(Some((-x).atan2(y)));
+        }
+        Ok(None)
+    }
+
+    /// Reference latitude and longitude of the first IfcSite that has both
+    pub fn site_location(
+        decoder: &mut EntityDecoder,
+        entity_types: &[(u32, IfcType)],
+    ) -> Result<Option<SiteLocation>> {
+        for (id, ifc_type) in entity_types {
+            if *ifc_type != IfcType::IfcSite {
+                continue;
+            }
+            let site = decoder.decode_by_id(*id)?;
+            // Index 9: RefLatitude, 10: RefLongitude, 11: RefElevation
+            let latitude = site.get_list(9).and_then(compound_angle);
+            let longitude = site.get_list(10).and_then(compound_angle);
+            let (Some(latitude), Some(longitude)) = (latitude, longitude) else {
+                continue;
+            };
+            return Ok(Some(SiteLocation {
+                latitude,
+                longitude,
+                elevation: site.get_float(11),
+            }));
+        }
+        Ok(None)
+    }
+
     /// Parse IfcMapConversion entity
     fn parse_map_conversion(entity: &DecodedEntity, georef: &mut GeoReference) {
         // Index 2: Eastings
@@ -224,7 +337,11 @@ impl GeoRefExtractor {
     }
 
     /// Parse IfcProjectedCRS entity
-    fn parse_projected_crs(entity: &DecodedEntity, georef: &mut GeoReference) {
+    fn parse_projected_crs(
+        decoder: &mut EntityDecoder,
+        entity: &DecodedEntity,
+        georef: &mut GeoReference,
+    ) {
         // Index 0: Name (e.g., "EPSG:32632")
         if let Some(name) = entity.get_string(0) {
             georef.crs_name = Some(name.to_string());
@@ -241,6 +358,13 @@ impl GeoRefExtractor {
         if let Some(proj) = entity.get_string(4) {
             georef.map_projection = Some(proj.to_string());
         }
+        // Index 6: MapUnit (IfcNamedUnit)
+        if let Some(scale) = entity
+            .get_ref(6)
+            .and_then(|unit_id| length_unit_scale(decoder, unit_id))
+        {
+            georef.map_unit_scale = scale;
+        }
     }
 
     /// Extract from IFC2X3 property sets (fallback)
@@ -323,6 +447,37 @@ impl GeoRefExtractor {
     }
 }
 
+/// Decimal degrees of an IfcCompoundPlaneAngleMeasure
+///
+/// Parts are degrees, minutes, seconds and optionally millionths of a
+/// second, all carrying the same sign.
+fn compound_angle(parts: &[AttributeValue]) -> Option<f64> {
+    let parts: Vec<f64> = parts.iter().filter_map(|p| p.as_float()).collect();
+    if !(3..=4).contains(&parts.len()) {
+        return None;
+    }
+    let divisors = [1.0, 60.0, 3600.0, 3_600_000_000.0];
+    Some(parts.iter().zip(divisors).map(|(part, d)| part / d).sum())
+}
+
+/// Metres per unit of an IfcSIUnit or IfcConversionBasedUnit
+fn length_unit_scale(decoder: &mut EntityDecoder, unit_id: u32) -> Option<f64> {
+    let unit = decoder.decode_by_id(unit_id).ok()?;
+    match unit.ifc_type {
+        // Index 2: Prefix
+        IfcType::IfcSIUnit => Some(
+            unit.get(2)
+                .and_then(|prefix| prefix.as_enum())
+                .map_or(1.0, get_si_prefix_multiplier),
+        ),
+        // Index 2: Name
+        IfcType::IfcConversionBasedUnit => unit
+            .get_string(2)
+            .and_then(get_conversion_based_unit_factor),
+        _ => None,
+    }
+}
+
 /// RTC (Relative-To-Center) coordinate handler for large coordinates
 #[derive(Debug, Clone, Default)]
 pub struct RtcOffset {
@@ -419,6 +574,89 @@ mod tests {
         assert!((n - 10.0).abs() < 1e-10);
     }
 
+    #[test]
+    fn test_true_north() {
+        let content = "#1=IFCDIRECTION((-1.,0.));\n\
+#2=IFCGEOMETRICREPRESENTATIONCONTEXT($,'Model',3,1.E-05,$,#1);\n\
+#3=IFCGEOMETRICREPRESENTATIONSUBCONTEXT('Body','Model',*,*,*,*,#2,$,.MODEL_VIEW.,$);\n";
+        let mut decoder = EntityDecoder::new(content);
+        let types = [
+            (1, IfcType::IfcDirection),
+            (2, IfcType::IfcGeometricRepresentationContext),
+            (3, IfcType::IfcGeometricRepresentationSubContext),
+        ];
+
+        // North pointing along model -X is a quarter turn counter-clockwise
+        let angle = GeoRefExtractor::true_north(&mut decoder, &types)
+            .unwrap()
+            .unwrap();
+        assert!((angle - std::f64::consts::FRAC_PI_2).abs() < 1e-10);
+
+        let mut decoder =
+            EntityDecoder::new("#1=IFCGEOMETRICREPRESENTATIONCONTEXT($,'Model',3,1.E-05,$,$);\n");
+        let types = [(1, IfcType::IfcGeometricRepresentationContext)];
+        assert!(GeoRefExtractor::true_north(&mut decoder, &types)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_epsg_code() {
+        let mut georef = GeoReference::new();
+        for (name, code) in [
+            ("EPSG:32632", Some(32632)),
+            ("urn:ogc:def:crs:EPSG::25832", Some(25832)),
+            ("http://www.opengis.net/def/crs/EPSG/0/2056", Some(2056)),
+            ("Local grid", None),
+        ] {
+            georef.crs_name = Some(name.to_string());
+            assert_eq!(georef.epsg_code(), code, "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_map_conversion_in_metres() {
+        let content = "#1=IFCSIUNIT(*,.LENGTHUNIT.,.MILLI.,.METRE.);\n\
+#2=IFCPROJECTEDCRS('EPSG:32760',$,'WGS 84',$,$,$,#1);\n\
+#3=IFCMAPCONVERSION($,#2,500000000.,1000000.,2000.,1.,0.,1.);\n";
+        let mut decoder = EntityDecoder::new(content);
+        let types = [
+            (1, IfcType::IfcSIUnit),
+            (2, IfcType::IfcProjectedCRS),
+            (3, IfcType::IfcMapConversion),
+        ];
+        let georef = GeoRefExtractor::extract(&mut decoder, &types)
+            .unwrap()
+            .unwrap();
+        assert_eq!(georef.epsg_code(), Some(32760));
+        assert!((georef.map_unit_scale - 0.001).abs() < 1e-12);
+
+        // Millimetre project and map units, geometry in metres
+        let (e, n, h) = georef.in_metres(0.001).local_to_map(10.0, 20.0, 5.0);
+        assert!((e - 500010.0).abs() < 1e-6);
+        assert!((n - 1020.0).abs() < 1e-6);
+        assert!((h - 7.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_site_location() {
+        let content = "#1=IFCSITE('s',$,'Site',$,$,$,$,$,.ELEMENT.,(52,31,12,345600),(-13,-24,0),34.5,$,$);\n";
+        let mut decoder = EntityDecoder::new(content);
+        let types = [(1, IfcType::IfcSite)];
+        let site = GeoRefExtractor::site_location(&mut decoder, &types)
+            .unwrap()
+            .unwrap();
+        assert!((site.latitude - (52.0 + 31.0 / 60.0 + 12.3456 / 3600.0)).abs() < 1e-9);
+        assert!((site.longitude + 13.4).abs() < 1e-9);
+        assert_eq!(site.elevation, Some(34.5));
+
+        let mut decoder =
+            EntityDecoder::new("#1=IFCSITE('s',$,'Site',$,$,$,$,$,.ELEMENT.,$,$,0.,$,$);\n");
+        assert!(GeoRefExtractor::site_location(&mut decoder, &types)
+            .unwrap()
+            .is_none());
+    }
+
     #[test]
     fn test_rtc_offset() {
         let positions = vec![
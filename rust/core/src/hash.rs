@@ -0,0 +1,81 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Content hashing for loaded IFC files
+//!
+//! Provides a stable, platform-independent fingerprint of the raw file bytes
+//! (XXH3-64). The same file always yields the same hash, which makes it
+//! suitable as a key for caches, saved sessions and shareable deep links.
+
+use std::fmt;
+use std::str::FromStr;
+use xxhash_rust::xxh3::xxh3_64;
+
+/// Stable 64-bit content hash of an IFC file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ContentHash(pub u64);
+
+impl ContentHash {
+    /// Hash raw file bytes
+    #[inline]
+    pub fn of(content: &[u8]) -> Self {
+        Self(xxh3_64(content))
+    }
+
+    /// Hash file content held as a string
+    #[inline]
+    pub fn of_str(content: &str) -> Self {
+        Self::of(content.as_bytes())
+    }
+
+    /// Full 16-character lowercase hex representation
+    pub fn to_hex(&self) -> String {
+        format!("{:016x}", self.0)
+    }
+
+    /// Short 8-character prefix for display
+    pub fn short(&self) -> String {
+        self.to_hex()[..8].to_string()
+    }
+}
+
+impl fmt::Display for ContentHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+impl FromStr for ContentHash {
+    type Err = std::num::ParseIntError;
+
+    /// Parse the hex form produced by [`ContentHash::to_hex`]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        u64::from_str_radix(s, 16).map(Self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hash_is_stable() {
+        let content = "ISO-10303-21;\nHEADER;\nENDSEC;\nDATA;\n#1=IFCPROJECT('guid',$,$,$,$,$,$,$,$);\nENDSEC;\nEND-ISO-10303-21;\n";
+        let a = ContentHash::of_str(content);
+        let b = ContentHash::of(content.as_bytes());
+        assert_eq!(a, b);
+        assert_ne!(a, ContentHash::of_str("#1=IFCWALL();"));
+    }
+
+    #[test]
+    fn test_hex_roundtrip() {
+        let hash = ContentHash::of_str("#1=IFCWALL();");
+        let hex = hash.to_hex();
+        assert_eq!(hex.len(), 16);
+        assert_eq!(hex, hash.to_string());
+        assert_eq!(hex.parse::<ContentHash>().unwrap(), hash);
+        assert!(hex.starts_with(&hash.short()));
+    }
+}
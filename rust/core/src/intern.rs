@@ -0,0 +1,88 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! String interning for repeated entity metadata
+//!
+//! Entity type names and storey names repeat across hundreds of thousands of
+//! entities. Interning hands out shared `Arc<str>` handles so each distinct
+//! value is stored once and clones are a reference-count bump. The entity
+//! decoder interns string attributes and enumeration values the same way.
+
+use crate::generated::IfcType;
+use rustc_hash::FxHashSet;
+use std::sync::Arc;
+
+/// Pool of shared strings
+#[derive(Debug, Default, Clone)]
+pub struct StringInterner {
+    pool: FxHashSet<Arc<str>>,
+}
+
+impl StringInterner {
+    /// Create an empty interner
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the shared handle for `value`, inserting it on first use
+    #[inline]
+    pub fn intern(&mut self, value: &str) -> Arc<str> {
+        if let Some(existing) = self.pool.get(value) {
+            return Arc::clone(existing);
+        }
+        let interned: Arc<str> = Arc::from(value);
+        self.pool.insert(Arc::clone(&interned));
+        interned
+    }
+
+    /// Intern the canonical name of a known IFC type
+    ///
+    /// Fast path for entity types: avoids hashing the raw (possibly
+    /// differently cased) type string from the file.
+    #[inline]
+    pub fn intern_type(&mut self, ifc_type: &IfcType) -> Arc<str> {
+        self.intern(ifc_type.name())
+    }
+
+    /// Number of distinct strings held
+    pub fn len(&self) -> usize {
+        self.pool.len()
+    }
+
+    /// Bytes of string data held by the pool
+    pub fn heap_bytes(&self) -> usize {
+        self.pool.iter().map(|s| s.len()).sum()
+    }
+
+    /// Check if the pool is empty
+    pub fn is_empty(&self) -> bool {
+        self.pool.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intern_shares_allocation() {
+        let mut interner = StringInterner::new();
+        let a = interner.intern("Level 1");
+        let b = interner.intern("Level 1");
+        let c = interner.intern("Level 2");
+
+        assert!(Arc::ptr_eq(&a, &b));
+        assert!(!Arc::ptr_eq(&a, &c));
+        assert_eq!(interner.len(), 2);
+    }
+
+    #[test]
+    fn test_intern_type() {
+        let mut interner = StringInterner::new();
+        let wall = interner.intern_type(&IfcType::IfcWall);
+        let again = interner.intern(IfcType::IfcWall.name());
+        assert!(Arc::ptr_eq(&wall, &again));
+        assert_eq!(&*wall, IfcType::IfcWall.name());
+    }
+}
@@ -13,40 +13,58 @@
 //!
 //! - **STEP Tokenization**: Zero-copy parsing of STEP file format
 //! - **Entity Scanning**: SIMD-accelerated entity discovery using [memchr](https://docs.rs/memchr)
-//! - **Lazy Decoding**: On-demand attribute parsing for memory efficiency
+//! - **Lazy Decoding**: On-demand attribute parsing with interned strings and enum values
+//! - **Classification**: Entity roles, geometry eligibility and attribute
+//!   positions looked up from the schema hierarchy, per IFC2x3/IFC4/IFC4X3
 //! - **Streaming Parser**: Event-based parsing for large files
+//! - **Diagnostics**: Per-entity parse and geometry problems with line numbers
+//! - **Validation**: Dangling references, duplicate GlobalIds, missing
+//!   mandatory attributes and unplaced spaces
+//! - **Model Diff**: Added, removed and modified elements between two versions
+//! - **Systems**: Distribution systems and zones with their members
+//! - **Writer**: Round-trip export of edited models back to STEP text
 //!
 //! ## Quick Start
 //!
-//! ```rust,ignore
+//! ```rust
 //! use ifc_lite_core::{EntityScanner, parse_entity, IfcType};
 //!
 //! // Scan for entities
-//! let content = r#"#1=IFCPROJECT('guid',$,$,$,$,$,$,$,$);"#;
+//! let content = "#1=IFCPROJECT('guid',$,$,$,$,$,$,$,$);\n#2=IFCWALL('guid2',$,$,$,$,$,$,$);";
 //! let mut scanner = EntityScanner::new(content);
 //!
-//! while let Some((id, type_name, start, end)) = scanner.next_entity() {
-//!     println!("Found entity #{}: {}", id, type_name);
+//! let mut found = Vec::new();
+//! while let Some((id, type_name, _start, _end)) = scanner.next_entity() {
+//!     found.push((id, type_name));
 //! }
+//! assert_eq!(found, [(1, "IFCPROJECT"), (2, "IFCWALL")]);
 //!
 //! // Parse individual entity
 //! let input = "#123=IFCWALL('guid',$,$,$,$,$,$,$);";
 //! let (id, ifc_type, attrs) = parse_entity(input).unwrap();
+//! assert_eq!(id, 123);
 //! assert_eq!(ifc_type, IfcType::IfcWall);
+//! assert_eq!(attrs.len(), 8);
 //! ```
 //!
 //! ## Streaming Parser
 //!
 //! For large files, use the streaming parser to process entities in batches:
 //!
-//! ```rust,ignore
+//! ```rust
+//! use futures_util::StreamExt;
 //! use ifc_lite_core::{parse_stream, StreamConfig, ParseEvent};
 //!
-//! let config = StreamConfig::default();
-//! for event in parse_stream(content, config) {
+//! let content = "#1=IFCPROJECT('guid',$,$,$,$,$,$,$,$);\n#2=IFCWALL('guid2',$,$,$,$,$,$,$);";
+//!
+//! # tokio::runtime::Builder::new_current_thread().build().unwrap().block_on(async {
+//! let mut events = parse_stream(content, StreamConfig::default());
+//! let mut scanned = 0;
+//! while let Some(event) = events.next().await {
 //!     match event {
-//!         ParseEvent::Entity { id, type_name, .. } => {
-//!             println!("Entity #{}: {}", id, type_name);
+//!         ParseEvent::EntityScanned { id, ifc_type, .. } => {
+//!             println!("Entity #{}: {}", id, ifc_type);
+//!             scanned += 1;
 //!         }
 //!         ParseEvent::Progress { percent, .. } => {
 //!             println!("Progress: {:.1}%", percent);
@@ -54,6 +72,8 @@
 //!         _ => {}
 //!     }
 //! }
+//! assert_eq!(scanned, 2);
+//! # });
 //! ```
 //!
 //! ## Performance
@@ -65,27 +85,74 @@
 //! ## Feature Flags
 //!
 //! - `serde`: Enable serialization support for parsed data
+//! - `bcf`: Read and write BCF issues and `.bcfzip` archives
 
+#[cfg(feature = "bcf")]
+pub mod bcf;
+pub mod changes;
+pub mod classify;
 pub mod decoder;
+pub mod diagnostics;
+pub mod diff;
 pub mod error;
 pub mod fast_parse;
 pub mod generated;
 pub mod georef;
+pub mod hash;
+pub mod intern;
+pub mod material;
 pub mod parser;
+pub mod property;
+pub mod qto;
+pub mod quantity;
+pub mod query;
 pub mod schema_gen;
+pub mod schema_version;
 pub mod streaming;
+pub mod system;
+pub mod type_object;
 pub mod units;
+pub mod validate;
+pub mod writer;
 
-pub use decoder::{build_entity_index, EntityDecoder, EntityIndex};
+#[cfg(feature = "bcf")]
+pub use bcf::{
+    BcfArchive, BcfCamera, BcfClippingPlane, BcfComment, BcfProjection, BcfTopic,
+    BcfTopicViewpoint, BcfViewpoint,
+};
+pub use changes::{Change, ChangeSet};
+pub use classify::{
+    attribute_index, attribute_index_in, classify_name, has_geometry_by_name, legacy_type,
+    resolve_type, Attribute, EntityClass,
+};
+pub use decoder::{build_entity_index, DecoderMemoryStats, EntityDecoder, EntityIndex};
+pub use diagnostics::{Diagnostic, DiagnosticKind, Diagnostics, LineIndex, Severity};
+pub use diff::{DiffStatus, ElementDiff, ModelDiff};
 pub use error::{Error, Result};
 pub use fast_parse::{
     extract_coordinate_list_from_entity, extract_entity_refs_from_list, extract_entity_type_name,
     extract_face_indices_from_entity, extract_first_entity_ref, parse_coordinates_direct,
     parse_indices_direct, process_triangulated_faceset_direct, should_use_fast_path, FastMeshData,
 };
-pub use generated::{has_geometry_by_name, IfcType};
-pub use georef::{GeoRefExtractor, GeoReference, RtcOffset};
+pub use generated::IfcType;
+pub use georef::{GeoRefExtractor, GeoReference, RtcOffset, SiteLocation};
+pub use hash::ContentHash;
+pub use intern::StringInterner;
+pub use material::{
+    element_materials, material_names, ElementMaterial, MaterialComponent, MaterialKind,
+};
 pub use parser::{parse_entity, EntityScanner, Token};
+pub use property::{format_real, unit_symbol, PropertyData};
+pub use qto::{ifc_measures, ElementMeasures, ElementQuantities, QuantityGroup, QuantityReport};
+pub use quantity::{decode_quantity, PhysicalQuantity, QuantityKind};
+pub use query::{CompareOp, Predicate, Query};
 pub use schema_gen::{AttributeValue, DecodedEntity, GeometryCategory, IfcSchema, ProfileCategory};
+pub use schema_version::{file_schema, SchemaVersion};
 pub use streaming::{parse_stream, ParseEvent, StreamConfig};
-pub use units::{extract_length_unit_scale, get_si_prefix_multiplier};
+pub use system::{System, SystemAssignments};
+pub use type_object::{TypeAssignments, TypeObject};
+pub use units::{
+    extract_length_unit_scale, get_si_prefix_multiplier, ProjectUnit, UnitAssignment, UnitSystem,
+};
+pub use validate::validate;
+pub use writer::{format_step_real, ifc_guid, IfcWriter, StepValue};
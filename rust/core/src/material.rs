@@ -0,0 +1,423 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Materials - element to material assignment via IfcRelAssociatesMaterial
+//!
+//! Every material select (single material, layer and profile sets with or
+//! without usage, constituent sets, material lists) is reduced to one
+//! display name, e.g. for grouping quantities by material. The build-up
+//! behind that name (layer thicknesses, profiles, constituent fractions) is
+//! kept in [`ElementMaterial`].
+
+use crate::classify::Attribute;
+use crate::decoder::EntityDecoder;
+use crate::generated::IfcType;
+use crate::parser::EntityScanner;
+use rustc_hash::FxHashMap;
+
+/// Nesting limit when following material definitions
+const MAX_DEPTH: usize = 4;
+
+/// How an element's material is defined
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MaterialKind {
+    /// One IfcMaterial
+    Single,
+    /// Layers with thicknesses, e.g. a wall build-up
+    LayerSet,
+    /// Materials along profiles, e.g. a composite beam
+    ProfileSet,
+    /// Named parts with optional volume fractions
+    ConstituentSet,
+    /// Legacy IfcMaterialList without further structure
+    List,
+}
+
+/// One layer, profile or constituent of a composite material
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MaterialComponent {
+    /// Name of the layer, profile or constituent itself
+    pub name: Option<String>,
+    /// Material it is made of
+    pub material: Option<String>,
+    /// Layer thickness in metres
+    pub thickness: Option<f64>,
+    /// Share of the whole for constituents, 0 to 1
+    pub fraction: Option<f64>,
+    /// Profile name for material profiles
+    pub profile: Option<String>,
+}
+
+/// Material associated with an element, with its build-up
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ElementMaterial {
+    /// Display name, see [`material_names`]
+    pub name: String,
+    pub kind: MaterialKind,
+    /// Layers, profiles, constituents or list entries in file order; empty
+    /// for single materials
+    pub components: Vec<MaterialComponent>,
+}
+
+impl ElementMaterial {
+    /// Sum of the layer thicknesses in metres
+    pub fn total_thickness(&self) -> Option<f64> {
+        if self.kind != MaterialKind::LayerSet {
+            return None;
+        }
+        self.components.iter().map(|c| c.thickness).sum()
+    }
+
+    /// Share of the element's volume per base material
+    ///
+    /// Layers split by thickness and constituents by fraction; everything
+    /// else, or sets with missing values, stays whole under the display
+    /// name. Shares of the same material are merged and add up to 1.
+    pub fn volume_shares(&self) -> Vec<(String, f64)> {
+        let weights: Option<Vec<f64>> = match self.kind {
+            MaterialKind::LayerSet => self.components.iter().map(|c| c.thickness).collect(),
+            MaterialKind::ConstituentSet => self.components.iter().map(|c| c.fraction).collect(),
+            _ => None,
+        };
+        let total: f64 = weights.iter().flatten().sum();
+        let (Some(weights), true) = (weights, total > 0.0) else {
+            return vec![(self.name.clone(), 1.0)];
+        };
+
+        let mut shares: Vec<(String, f64)> = Vec::new();
+        for (component, weight) in self.components.iter().zip(weights) {
+            let material = component
+                .material
+                .clone()
+                .or_else(|| component.name.clone())
+                .unwrap_or_else(|| self.name.clone());
+            match shares.iter_mut().find(|(name, _)| *name == material) {
+                Some((_, share)) => *share += weight / total,
+                None => shares.push((material, weight / total)),
+            }
+        }
+        shares
+    }
+}
+
+/// Material name of every element with an associated material
+pub fn material_names(content: &str, decoder: &mut EntityDecoder) -> FxHashMap<u32, String> {
+    element_materials(content, decoder)
+        .into_iter()
+        .map(|(id, material)| (id, material.name))
+        .collect()
+}
+
+/// Material and build-up of every element with an associated material
+///
+/// Layer thicknesses are scaled to metres with the project's length unit.
+pub fn element_materials(
+    content: &str,
+    decoder: &mut EntityDecoder,
+) -> FxHashMap<u32, ElementMaterial> {
+    let mut length_scale = 1.0;
+    let mut assignments: Vec<(Vec<u32>, u32)> = Vec::new();
+
+    let mut scanner = EntityScanner::new(content);
+    while let Some((id, type_name, _, _)) = scanner.next_entity() {
+        match type_name {
+            "IFCPROJECT" => length_scale = decoder.extract_unit_scale(id).unwrap_or(1.0),
+            "IFCRELASSOCIATESMATERIAL" => {
+                let Ok(rel) = decoder.decode_by_id(id) else {
+                    continue;
+                };
+                let (Some(related), Some(material)) = (
+                    rel.attribute_list(Attribute::RelatedObjects),
+                    rel.attribute_ref(Attribute::RelatingMaterial),
+                ) else {
+                    continue;
+                };
+                let related = related.iter().filter_map(|r| r.as_entity_ref()).collect();
+                assignments.push((related, material));
+            }
+            _ => {}
+        }
+    }
+
+    let mut by_material: FxHashMap<u32, Option<ElementMaterial>> = FxHashMap::default();
+    let mut materials = FxHashMap::default();
+    for (related, material) in assignments {
+        let definition = by_material
+            .entry(material)
+            .or_insert_with(|| element_material(decoder, material, length_scale));
+        if let Some(definition) = definition {
+            for object in related {
+                materials.insert(object, definition.clone());
+            }
+        }
+    }
+    materials
+}
+
+/// Display name, kind and components of a material select
+fn element_material(
+    decoder: &mut EntityDecoder,
+    material_id: u32,
+    length_scale: f64,
+) -> Option<ElementMaterial> {
+    let name = material_name(decoder, material_id, 0)?;
+    let mut entity = decoder.decode_by_id(material_id).ok()?;
+    // Usages only add placement; the set behind them holds the build-up
+    if matches!(
+        entity.ifc_type,
+        IfcType::IfcMaterialLayerSetUsage
+            | IfcType::IfcMaterialProfileSetUsage
+            | IfcType::IfcMaterialProfileSetUsageTapering
+    ) {
+        entity = decoder.decode_by_id(entity.get_ref(0)?).ok()?;
+    }
+    let refs = |index: usize| -> Vec<u32> {
+        entity
+            .get_list(index)
+            .map(|l| l.iter().filter_map(|r| r.as_entity_ref()).collect())
+            .unwrap_or_default()
+    };
+
+    let (kind, parts) = match entity.ifc_type {
+        IfcType::IfcMaterial => (MaterialKind::Single, Vec::new()),
+        IfcType::IfcMaterialLayerSet => (MaterialKind::LayerSet, refs(0)),
+        IfcType::IfcMaterialProfileSet => (MaterialKind::ProfileSet, refs(2)),
+        IfcType::IfcMaterialConstituentSet => (MaterialKind::ConstituentSet, refs(2)),
+        IfcType::IfcMaterialList => (MaterialKind::List, refs(0)),
+        _ => return None,
+    };
+    let components = parts
+        .into_iter()
+        .filter_map(|id| material_component(decoder, id, length_scale))
+        .collect();
+    Some(ElementMaterial {
+        name,
+        kind,
+        components,
+    })
+}
+
+/// One layer, profile, constituent or list entry
+fn material_component(
+    decoder: &mut EntityDecoder,
+    id: u32,
+    length_scale: f64,
+) -> Option<MaterialComponent> {
+    let entity = decoder.decode_by_id(id).ok()?;
+    let string = |index: usize| {
+        entity
+            .get_string(index)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    };
+    let material = |decoder: &mut EntityDecoder, index: usize| {
+        material_name(decoder, entity.get_ref(index)?, 1)
+    };
+
+    let component = match entity.ifc_type {
+        IfcType::IfcMaterial => MaterialComponent {
+            material: string(0),
+            ..MaterialComponent::default()
+        },
+        // (Material, LayerThickness, IsVentilated, Name, ...)
+        IfcType::IfcMaterialLayer | IfcType::IfcMaterialLayerWithOffsets => MaterialComponent {
+            name: string(3),
+            material: material(decoder, 0),
+            thickness: entity.get_float(1).map(|t| t * length_scale),
+            ..MaterialComponent::default()
+        },
+        // (Name, Description, Material, Profile, Priority, Category)
+        IfcType::IfcMaterialProfile | IfcType::IfcMaterialProfileWithOffsets => {
+            let profile = entity
+                .get_ref(3)
+                .and_then(|profile| decoder.decode_by_id(profile).ok())
+                // (ProfileType, ProfileName, ...)
+                .and_then(|profile| profile.get_string(1).map(str::to_string))
+                .filter(|s| !s.is_empty());
+            MaterialComponent {
+                name: string(0),
+                material: material(decoder, 2),
+                profile,
+                ..MaterialComponent::default()
+            }
+        }
+        // (Name, Description, Material, Fraction, Category)
+        IfcType::IfcMaterialConstituent => MaterialComponent {
+            name: string(0),
+            material: material(decoder, 2),
+            fraction: entity.get_float(3),
+            ..MaterialComponent::default()
+        },
+        _ => return None,
+    };
+    Some(component)
+}
+
+/// Display name of a material select
+fn material_name(decoder: &mut EntityDecoder, material_id: u32, depth: usize) -> Option<String> {
+    if depth > MAX_DEPTH {
+        return None;
+    }
+    let entity = decoder.decode_by_id(material_id).ok()?;
+    let own_name = |index: usize| {
+        entity
+            .get_string(index)
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    };
+    let refs = |index: usize| -> Vec<u32> {
+        entity
+            .get_list(index)
+            .map(|l| l.iter().filter_map(|r| r.as_entity_ref()).collect())
+            .unwrap_or_default()
+    };
+
+    match entity.ifc_type {
+        // (Name, Description, Category)
+        IfcType::IfcMaterial => own_name(0),
+        // (ForLayerSet, ...) / (ForProfileSet, ...)
+        IfcType::IfcMaterialLayerSetUsage
+        | IfcType::IfcMaterialProfileSetUsage
+        | IfcType::IfcMaterialProfileSetUsageTapering => {
+            material_name(decoder, entity.get_ref(0)?, depth + 1)
+        }
+        // (MaterialLayers, LayerSetName, Description)
+        IfcType::IfcMaterialLayerSet => {
+            own_name(1).or_else(|| joined_names(decoder, &refs(0), depth))
+        }
+        // (Material, LayerThickness, IsVentilated, Name, ...)
+        IfcType::IfcMaterialLayer | IfcType::IfcMaterialLayerWithOffsets => {
+            material_name(decoder, entity.get_ref(0)?, depth + 1).or_else(|| own_name(3))
+        }
+        // (Name, Description, MaterialProfiles, CompositeProfile)
+        IfcType::IfcMaterialProfileSet => {
+            own_name(0).or_else(|| joined_names(decoder, &refs(2), depth))
+        }
+        // (Name, Description, Material, Profile, Priority, Category)
+        IfcType::IfcMaterialProfile | IfcType::IfcMaterialProfileWithOffsets => {
+            material_name(decoder, entity.get_ref(2)?, depth + 1).or_else(|| own_name(0))
+        }
+        // (Name, Description, MaterialConstituents)
+        IfcType::IfcMaterialConstituentSet => {
+            own_name(0).or_else(|| joined_names(decoder, &refs(2), depth))
+        }
+        // (Name, Description, Material, Fraction, Category)
+        IfcType::IfcMaterialConstituent => {
+            material_name(decoder, entity.get_ref(2)?, depth + 1).or_else(|| own_name(0))
+        }
+        // (Materials)
+        IfcType::IfcMaterialList => joined_names(decoder, &refs(0), depth),
+        _ => None,
+    }
+}
+
+/// Distinct names of several materials, joined with " / "
+fn joined_names(decoder: &mut EntityDecoder, ids: &[u32], depth: usize) -> Option<String> {
+    let mut names: Vec<String> = Vec::new();
+    for &id in ids {
+        if let Some(name) = material_name(decoder, id, depth + 1) {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+    (!names.is_empty()).then(|| names.join(" / "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::build_entity_index;
+
+    #[test]
+    fn test_material_names() {
+        let content = "#1=IFCMATERIAL('Concrete',$,$);\n\
+#2=IFCMATERIAL('Insulation',$,$);\n\
+#3=IFCMATERIALLAYER(#1,0.2,$,$,$,$,$);\n\
+#4=IFCMATERIALLAYER(#2,0.1,$,$,$,$,$);\n\
+#5=IFCMATERIALLAYERSET((#3,#4),$,$);\n\
+#6=IFCMATERIALLAYERSETUSAGE(#5,.AXIS2.,.POSITIVE.,0.,$);\n\
+#7=IFCMATERIALLAYERSET((#3),'Exterior wall',$);\n\
+#10=IFCWALL('w1',$,$,$,$,$,$,$,$);\n\
+#11=IFCWALL('w2',$,$,$,$,$,$,$,$);\n\
+#12=IFCCOLUMN('c1',$,$,$,$,$,$,$,$);\n\
+#13=IFCSLAB('s1',$,$,$,$,$,$,$,$);\n\
+#20=IFCRELASSOCIATESMATERIAL('r1',$,$,$,(#10),#6);\n\
+#21=IFCRELASSOCIATESMATERIAL('r2',$,$,$,(#11),#7);\n\
+#22=IFCRELASSOCIATESMATERIAL('r3',$,$,$,(#12),#1);\n";
+        let mut decoder = EntityDecoder::with_index(content, build_entity_index(content));
+        let names = material_names(content, &mut decoder);
+
+        assert_eq!(
+            names.get(&10).map(String::as_str),
+            Some("Concrete / Insulation")
+        );
+        assert_eq!(names.get(&11).map(String::as_str), Some("Exterior wall"));
+        assert_eq!(names.get(&12).map(String::as_str), Some("Concrete"));
+        assert_eq!(names.get(&13), None);
+    }
+
+    #[test]
+    fn test_element_materials() {
+        let content = "#1=IFCMATERIAL('Concrete',$,$);\n\
+#2=IFCMATERIAL('Insulation',$,$);\n\
+#3=IFCMATERIALLAYER(#1,0.2,$,'Core',$,$,$);\n\
+#4=IFCMATERIALLAYER(#2,0.1,$,$,$,$,$);\n\
+#5=IFCMATERIALLAYER(#1,0.1,$,$,$,$,$);\n\
+#6=IFCMATERIALLAYERSET((#3,#4,#5),'Sandwich',$);\n\
+#7=IFCMATERIALLAYERSETUSAGE(#6,.AXIS2.,.POSITIVE.,0.,$);\n\
+#8=IFCISHAPEPROFILEDEF(.AREA.,'IPE200',$,0.1,0.2,0.0056,0.0085,$,$,$,$);\n\
+#9=IFCMATERIALPROFILE('Web',$,#1,#8,$,$);\n\
+#10=IFCMATERIALPROFILESET('Beam',$,(#9),$);\n\
+#11=IFCMATERIALCONSTITUENT('Frame',$,#1,0.25,$);\n\
+#12=IFCMATERIALCONSTITUENT('Glass',$,#2,0.75,$);\n\
+#13=IFCMATERIALCONSTITUENTSET('Window',$,(#11,#12));\n\
+#20=IFCWALL('w1',$,$,$,$,$,$,$,$);\n\
+#21=IFCBEAM('b1',$,$,$,$,$,$,$,$);\n\
+#22=IFCWINDOW('f1',$,$,$,$,$,$,$,$,$,$,$,$);\n\
+#23=IFCCOLUMN('c1',$,$,$,$,$,$,$,$);\n\
+#30=IFCRELASSOCIATESMATERIAL('r1',$,$,$,(#20),#7);\n\
+#31=IFCRELASSOCIATESMATERIAL('r2',$,$,$,(#21),#10);\n\
+#32=IFCRELASSOCIATESMATERIAL('r3',$,$,$,(#22),#13);\n\
+#33=IFCRELASSOCIATESMATERIAL('r4',$,$,$,(#23),#1);\n";
+        let mut decoder = EntityDecoder::with_index(content, build_entity_index(content));
+        let materials = element_materials(content, &mut decoder);
+
+        let wall = &materials[&20];
+        assert_eq!(wall.name, "Sandwich");
+        assert_eq!(wall.kind, MaterialKind::LayerSet);
+        assert_eq!(wall.components.len(), 3);
+        assert_eq!(wall.components[0].name.as_deref(), Some("Core"));
+        assert_eq!(wall.components[1].material.as_deref(), Some("Insulation"));
+        assert_eq!(wall.components[1].thickness, Some(0.1));
+        assert!((wall.total_thickness().unwrap() - 0.4).abs() < 1e-9);
+        let shares = wall.volume_shares();
+        assert_eq!(shares.len(), 2);
+        assert_eq!(shares[0].0, "Concrete");
+        assert!((shares[0].1 - 0.75).abs() < 1e-9);
+
+        let beam = &materials[&21];
+        assert_eq!(beam.kind, MaterialKind::ProfileSet);
+        assert_eq!(beam.components[0].profile.as_deref(), Some("IPE200"));
+        assert_eq!(beam.volume_shares(), [("Beam".to_string(), 1.0)]);
+
+        let window = &materials[&22];
+        assert_eq!(window.kind, MaterialKind::ConstituentSet);
+        assert_eq!(
+            window.volume_shares(),
+            [
+                ("Concrete".to_string(), 0.25),
+                ("Insulation".to_string(), 0.75)
+            ]
+        );
+
+        let column = &materials[&23];
+        assert_eq!(column.kind, MaterialKind::Single);
+        assert!(column.components.is_empty());
+        assert_eq!(column.total_thickness(), None);
+    }
+}
@@ -202,8 +202,18 @@ fn list(input: &str) -> IResult<&str, Token<'_>> {
     )(input)
 }
 
+/// Parse a single attribute value (e.g. `#12`, `'name'`, `(#1,#2)`)
+pub(crate) fn parse_attribute(input: &str) -> Result<Token<'_>> {
+    token(input)
+        .map(|(_, tok)| tok)
+        .map_err(|e| Error::parse(0, format!("Failed to parse attribute: {}", e)))
+}
+
 /// Parse a complete entity line
 /// Example: #123=IFCWALL('guid','owner',$,$,'name',$,$,$);
+///
+/// Names dropped from IFC4X3 resolve to their current type, see
+/// [`resolve_type`](crate::resolve_type).
 pub fn parse_entity(input: &str) -> Result<(u32, IfcType, Vec<Token<'_>>)> {
     let result: IResult<&str, (u32, &str, Vec<Token>)> = tuple((
         // Entity ID: #123
@@ -232,7 +242,7 @@ pub fn parse_entity(input: &str) -> Result<(u32, IfcType, Vec<Token<'_>>)> {
 
     match result {
         Ok((_, (id, type_str, args))) => {
-            let ifc_type = IfcType::from_str(type_str);
+            let ifc_type = crate::classify::resolve_type(type_str);
             Ok((id, ifc_type, args))
         }
         Err(e) => Err(Error::parse(0, format!("Failed to parse entity: {}", e))),
@@ -479,6 +489,10 @@ mod tests {
         assert_eq!(id, 123);
         assert_eq!(ifc_type, IfcType::IfcWall);
         assert_eq!(args.len(), 8);
+
+        // IFC4 StandardCase subtypes read as their IFC4X3 supertype
+        let (_, ifc_type, _) = parse_entity("#7=IFCBEAMSTANDARDCASE($,$,$,$,$,$,$,$,$);").unwrap();
+        assert_eq!(ifc_type, IfcType::IfcBeam);
     }
 
     #[test]
@@ -0,0 +1,605 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Property Values - Typed IFC property data
+//!
+//! Property values arrive as STEP typed values such as `IFCLABEL('Concrete')`,
+//! `IFCLENGTHMEASURE(2.5)` or `IFCBOOLEAN(.T.)`. [`PropertyData`] keeps the
+//! value type so callers can filter and aggregate without re-parsing strings.
+
+use crate::decoder::EntityDecoder;
+use crate::generated::IfcType;
+use crate::schema_gen::{AttributeValue, DecodedEntity};
+use crate::units::{ProjectUnit, UnitAssignment, UnitSystem};
+use std::fmt;
+
+/// Typed property value
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PropertyData {
+    /// Label, text, identifier
+    Text(String),
+    /// Measure or real number, with an optional unit symbol
+    Real { value: f64, unit: Option<String> },
+    /// Integer or count
+    Integer(i64),
+    /// IfcBoolean / IfcLogical (.T. / .F.)
+    Boolean(bool),
+    /// Enumeration value (without dots)
+    Enum(String),
+    /// Reference to another entity
+    EntityRef(u32),
+    /// List of values
+    List(Vec<PropertyData>),
+    /// Numeric range (IfcPropertyBoundedValue)
+    Bounded {
+        lower: Option<f64>,
+        upper: Option<f64>,
+        set_point: Option<f64>,
+        unit: Option<String>,
+    },
+    /// Defining -> defined value pairs (IfcPropertyTableValue)
+    Table {
+        defining: Vec<PropertyData>,
+        defined: Vec<PropertyData>,
+    },
+}
+
+impl PropertyData {
+    /// Convert a decoded attribute (None for `$` and `*`)
+    pub fn from_attribute(attr: &AttributeValue) -> Option<Self> {
+        match attr {
+            AttributeValue::String(s) => Some(Self::Text(s.to_string())),
+            AttributeValue::Float(f) => Some(Self::Real {
+                value: *f,
+                unit: None,
+            }),
+            AttributeValue::Integer(i) => Some(Self::Integer(*i)),
+            AttributeValue::Enum(e) => Some(Self::Enum(e.to_string())),
+            AttributeValue::EntityRef(id) => Some(Self::EntityRef(*id)),
+            AttributeValue::List(items) => {
+                // Typed values decode as [type name, value]
+                if let [AttributeValue::String(type_name), inner] = items.as_slice() {
+                    if type_name.starts_with("IFC") {
+                        return Self::from_typed(type_name, inner);
+                    }
+                }
+                Some(Self::List(
+                    items.iter().filter_map(Self::from_attribute).collect(),
+                ))
+            }
+            AttributeValue::Null | AttributeValue::Derived => None,
+        }
+    }
+
+    /// Convert the inner value of a typed value like IFCBOOLEAN(.T.)
+    fn from_typed(type_name: &str, inner: &AttributeValue) -> Option<Self> {
+        match (type_name, inner) {
+            ("IFCBOOLEAN" | "IFCLOGICAL", AttributeValue::Enum(e)) => match &**e {
+                "T" | "TRUE" => Some(Self::Boolean(true)),
+                "F" | "FALSE" => Some(Self::Boolean(false)),
+                _ => Some(Self::Enum("UNKNOWN".to_string())),
+            },
+            // Integer-valued measures written without a decimal point
+            (_, AttributeValue::Integer(i))
+                if !matches!(type_name, "IFCINTEGER" | "IFCCOUNTMEASURE") =>
+            {
+                Some(Self::Real {
+                    value: *i as f64,
+                    unit: None,
+                })
+            }
+            _ => Self::from_attribute(inner),
+        }
+    }
+
+    /// Read the NominalValue and Unit of an IfcPropertySingleValue
+    pub fn from_single_value(prop: &DecodedEntity, decoder: &mut EntityDecoder) -> Option<Self> {
+        // IfcPropertySingleValue: (Name, Description, NominalValue, Unit)
+        let data = Self::from_attribute(prop.get(2)?)?;
+        match prop.get_ref(3).and_then(|id| unit_symbol(decoder, id)) {
+            Some(unit) => Some(data.with_unit(unit)),
+            None => Some(data),
+        }
+    }
+
+    /// Read the value of any simple property
+    ///
+    /// Handles IfcPropertySingleValue, IfcPropertyBoundedValue,
+    /// IfcPropertyEnumeratedValue, IfcPropertyListValue and IfcPropertyTableValue.
+    /// Returns None for other property types and for properties without a value.
+    pub fn from_property(prop: &DecodedEntity, decoder: &mut EntityDecoder) -> Option<Self> {
+        match prop.ifc_type {
+            IfcType::IfcPropertySingleValue => Self::from_single_value(prop, decoder),
+            IfcType::IfcPropertyBoundedValue => Self::from_bounded_value(prop, decoder),
+            IfcType::IfcPropertyEnumeratedValue => {
+                // (Name, Description, EnumerationValues, EnumerationReference)
+                let data = Self::from_value_list(prop.get(2)?)?;
+                // IfcPropertyEnumeration: (Name, EnumerationValues, Unit)
+                let unit = prop
+                    .get_ref(3)
+                    .and_then(|id| decoder.decode_by_id(id).ok())
+                    .and_then(|enumeration| enumeration.get_ref(2))
+                    .and_then(|id| unit_symbol(decoder, id));
+                Some(match unit {
+                    Some(unit) => data.with_unit(unit),
+                    None => data,
+                })
+            }
+            IfcType::IfcPropertyListValue => {
+                // (Name, Description, ListValues, Unit)
+                let data = Self::from_value_list(prop.get(2)?)?;
+                match prop.get_ref(3).and_then(|id| unit_symbol(decoder, id)) {
+                    Some(unit) => Some(data.with_unit(unit)),
+                    None => Some(data),
+                }
+            }
+            IfcType::IfcPropertyTableValue => {
+                // (Name, Description, DefiningValues, DefinedValues, Expression,
+                //  DefiningUnit, DefinedUnit, CurveInterpolation)
+                let column = |index: usize, decoder: &mut EntityDecoder| {
+                    let unit = prop
+                        .get_ref(index + 3)
+                        .and_then(|id| unit_symbol(decoder, id));
+                    match (prop.get(index).and_then(Self::from_value_list), unit) {
+                        (Some(Self::List(values)), Some(unit)) => values
+                            .into_iter()
+                            .map(|v| v.with_unit(unit.clone()))
+                            .collect(),
+                        (Some(Self::List(values)), None) => values,
+                        _ => Vec::new(),
+                    }
+                };
+                let defining = column(2, decoder);
+                let defined = column(3, decoder);
+                if defining.is_empty() && defined.is_empty() {
+                    return None;
+                }
+                Some(Self::Table { defining, defined })
+            }
+            _ => None,
+        }
+    }
+
+    /// Read a property like [`Self::from_property`], labelling measures
+    /// without an explicit unit with the project unit of their measure type
+    pub fn from_property_with_units(
+        prop: &DecodedEntity,
+        decoder: &mut EntityDecoder,
+        units: &UnitAssignment,
+    ) -> Option<Self> {
+        let data = Self::from_property(prop, decoder)?;
+        if data.unit().is_some() {
+            return Some(data);
+        }
+        let unit = Self::measure_type(prop)
+            .and_then(|measure| units.for_measure(measure))
+            .filter(|unit| !unit.symbol.is_empty());
+        Some(match unit {
+            Some(unit) => data.with_unit(unit.symbol.clone()),
+            None => data,
+        })
+    }
+
+    /// Type name of the first typed value of a property ("IFCLENGTHMEASURE")
+    fn measure_type(prop: &DecodedEntity) -> Option<&str> {
+        fn typed(attr: &AttributeValue) -> Option<&str> {
+            match attr.as_list()? {
+                [AttributeValue::String(type_name), _] => Some(type_name),
+                _ => None,
+            }
+        }
+        match prop.ifc_type {
+            IfcType::IfcPropertySingleValue => typed(prop.get(2)?),
+            // UpperBoundValue, LowerBoundValue, SetPointValue
+            IfcType::IfcPropertyBoundedValue => [2, 3, 5]
+                .into_iter()
+                .find_map(|i| prop.get(i).and_then(typed)),
+            IfcType::IfcPropertyListValue | IfcType::IfcPropertyEnumeratedValue => {
+                prop.get(2)?.as_list()?.iter().find_map(typed)
+            }
+            _ => None,
+        }
+    }
+
+    /// Read an IfcPropertyBoundedValue
+    fn from_bounded_value(prop: &DecodedEntity, decoder: &mut EntityDecoder) -> Option<Self> {
+        // (Name, Description, UpperBoundValue, LowerBoundValue, Unit, SetPointValue)
+        let upper = prop.get(2).and_then(Self::from_attribute);
+        let lower = prop.get(3).and_then(Self::from_attribute);
+        let set_point = prop.get(5).and_then(Self::from_attribute);
+        let unit = prop.get_ref(4).and_then(|id| unit_symbol(decoder, id));
+
+        let values = [&lower, &upper, &set_point];
+        if values.iter().all(|v| v.is_none()) {
+            return None;
+        }
+
+        // Non-numeric bounds (e.g. dates) fall back to a plain list
+        if values
+            .iter()
+            .any(|v| v.as_ref().is_some_and(|d| d.as_f64().is_none()))
+        {
+            return Some(Self::List(
+                [lower, upper, set_point].into_iter().flatten().collect(),
+            ));
+        }
+
+        Some(Self::Bounded {
+            lower: lower.and_then(|d| d.as_f64()),
+            upper: upper.and_then(|d| d.as_f64()),
+            set_point: set_point.and_then(|d| d.as_f64()),
+            unit,
+        })
+    }
+
+    /// Convert an aggregate of typed values into a List
+    fn from_value_list(attr: &AttributeValue) -> Option<Self> {
+        let items = attr.as_list()?;
+        Some(Self::List(
+            items.iter().filter_map(Self::from_attribute).collect(),
+        ))
+    }
+
+    /// Attach a unit symbol to real values
+    pub fn with_unit(self, unit: String) -> Self {
+        match self {
+            Self::Real { value, .. } => Self::Real {
+                value,
+                unit: Some(unit),
+            },
+            Self::Integer(i) => Self::Real {
+                value: i as f64,
+                unit: Some(unit),
+            },
+            Self::List(items) => Self::List(
+                items
+                    .into_iter()
+                    .map(|item| item.with_unit(unit.clone()))
+                    .collect(),
+            ),
+            Self::Bounded {
+                lower,
+                upper,
+                set_point,
+                ..
+            } => Self::Bounded {
+                lower,
+                upper,
+                set_point,
+                unit: Some(unit),
+            },
+            other => other,
+        }
+    }
+
+    /// Values with a known length, area, volume or mass unit converted to
+    /// a unit system; other values are kept
+    pub fn in_unit_system(&self, system: UnitSystem) -> Self {
+        let unit_of = |unit: &Option<String>| {
+            unit.as_deref()
+                .and_then(|u| system.convert(1.0, u))
+                .map(|(factor, symbol)| (factor, symbol.to_string()))
+        };
+        match self {
+            Self::Real { value, unit } => match unit_of(unit) {
+                Some((factor, symbol)) => Self::Real {
+                    value: value * factor,
+                    unit: Some(symbol),
+                },
+                None => self.clone(),
+            },
+            Self::Bounded {
+                lower,
+                upper,
+                set_point,
+                unit,
+            } => match unit_of(unit) {
+                Some((factor, symbol)) => Self::Bounded {
+                    lower: lower.map(|v| v * factor),
+                    upper: upper.map(|v| v * factor),
+                    set_point: set_point.map(|v| v * factor),
+                    unit: Some(symbol),
+                },
+                None => self.clone(),
+            },
+            Self::List(items) => {
+                Self::List(items.iter().map(|i| i.in_unit_system(system)).collect())
+            }
+            Self::Table { defining, defined } => Self::Table {
+                defining: defining.iter().map(|i| i.in_unit_system(system)).collect(),
+                defined: defined.iter().map(|i| i.in_unit_system(system)).collect(),
+            },
+            other => other.clone(),
+        }
+    }
+
+    /// Parse user input as a value of the same kind, keeping the unit
+    ///
+    /// Returns None when the text does not fit the kind, and for kinds that
+    /// cannot be edited as a single value (references, lists, ranges, tables).
+    pub fn parse_as(&self, text: &str) -> Option<Self> {
+        let trimmed = text.trim();
+        match self {
+            Self::Text(_) => Some(Self::Text(text.to_string())),
+            Self::Real { unit, .. } => Some(Self::Real {
+                value: trimmed.replace(',', ".").parse().ok()?,
+                unit: unit.clone(),
+            }),
+            Self::Integer(_) => trimmed.parse().ok().map(Self::Integer),
+            Self::Boolean(_) => match trimmed.to_ascii_lowercase().as_str() {
+                "yes" | "true" | "t" | "1" => Some(Self::Boolean(true)),
+                "no" | "false" | "f" | "0" => Some(Self::Boolean(false)),
+                _ => None,
+            },
+            Self::Enum(_) => (!trimmed.is_empty()
+                && trimmed
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_'))
+            .then(|| Self::Enum(trimmed.to_ascii_uppercase())),
+            Self::EntityRef(_) | Self::List(_) | Self::Bounded { .. } | Self::Table { .. } => None,
+        }
+    }
+
+    /// Numeric value, if any
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Real { value, .. } => Some(*value),
+            Self::Integer(i) => Some(*i as f64),
+            _ => None,
+        }
+    }
+
+    /// Unit symbol of a real value (first unit found for lists)
+    pub fn unit(&self) -> Option<&str> {
+        match self {
+            Self::Real { unit, .. } | Self::Bounded { unit, .. } => unit.as_deref(),
+            Self::List(items) => items.iter().find_map(|item| item.unit()),
+            _ => None,
+        }
+    }
+
+    /// Short kind name for UI and filtering
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Text(_) => "Text",
+            Self::Real { .. } => "Real",
+            Self::Integer(_) => "Integer",
+            Self::Boolean(_) => "Boolean",
+            Self::Enum(_) => "Enum",
+            Self::EntityRef(_) => "EntityRef",
+            Self::List(_) => "List",
+            Self::Bounded { .. } => "Bounded",
+            Self::Table { .. } => "Table",
+        }
+    }
+}
+
+/// Display the value without its unit; use [`PropertyData::unit`] for the unit
+impl fmt::Display for PropertyData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Text(s) | Self::Enum(s) => f.write_str(s),
+            Self::Real { value, .. } => f.write_str(&format_real(*value)),
+            Self::Integer(i) => write!(f, "{}", i),
+            Self::Boolean(b) => f.write_str(if *b { "Yes" } else { "No" }),
+            Self::EntityRef(id) => write!(f, "#{}", id),
+            Self::List(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                Ok(())
+            }
+            Self::Bounded {
+                lower,
+                upper,
+                set_point,
+                ..
+            } => {
+                match (lower, upper) {
+                    (Some(l), Some(u)) => write!(f, "{} – {}", format_real(*l), format_real(*u))?,
+                    (Some(l), None) => write!(f, "≥ {}", format_real(*l))?,
+                    (None, Some(u)) => write!(f, "≤ {}", format_real(*u))?,
+                    (None, None) => {}
+                }
+                if let Some(sp) = set_point {
+                    if lower.is_some() || upper.is_some() {
+                        f.write_str(" ")?;
+                    }
+                    write!(f, "(set point {})", format_real(*sp))?;
+                }
+                Ok(())
+            }
+            Self::Table { defining, defined } => {
+                for (i, (x, y)) in defining.iter().zip(defined).enumerate() {
+                    if i > 0 {
+                        f.write_str("; ")?;
+                    }
+                    write!(f, "{} → {}", x, y)?;
+                    if let Some(unit) = y.unit() {
+                        write!(f, " {}", unit)?;
+                    }
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Format a real with up to 4 decimals, trimming trailing zeros
+pub fn format_real(value: f64) -> String {
+    let s = format!("{:.4}", value);
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    if s == "-0" {
+        "0".to_string()
+    } else {
+        s.to_string()
+    }
+}
+
+/// Resolve an IfcUnit reference to a display symbol (e.g. "mm", "kN", "m²",
+/// "ft", "W/(m²·K)")
+pub fn unit_symbol(decoder: &mut EntityDecoder, unit_id: u32) -> Option<String> {
+    ProjectUnit::decode(decoder, unit_id).map(|unit| unit.symbol)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(content: &str, id: u32) -> (EntityDecoder<'_>, DecodedEntity) {
+        let mut decoder = EntityDecoder::new(content);
+        let entity = decoder.decode_by_id(id).unwrap();
+        (decoder, entity)
+    }
+
+    #[test]
+    fn test_parse_as() {
+        let width = PropertyData::Real {
+            value: 250.0,
+            unit: Some("mm".to_string()),
+        };
+        assert_eq!(
+            width.parse_as(" 300,5 "),
+            Some(PropertyData::Real {
+                value: 300.5,
+                unit: Some("mm".to_string())
+            })
+        );
+        assert_eq!(width.parse_as("wide"), None);
+        assert_eq!(
+            PropertyData::Boolean(true).parse_as("No"),
+            Some(PropertyData::Boolean(false))
+        );
+        assert_eq!(
+            PropertyData::Integer(3).parse_as("4"),
+            Some(PropertyData::Integer(4))
+        );
+        assert_eq!(
+            PropertyData::Enum("NOTDEFINED".into()).parse_as("userdefined"),
+            Some(PropertyData::Enum("USERDEFINED".into()))
+        );
+        assert_eq!(PropertyData::EntityRef(1).parse_as("#2"), None);
+    }
+
+    #[test]
+    fn test_typed_values() {
+        let content = "#1=IFCPROPERTYSINGLEVALUE('IsExternal',$,IFCBOOLEAN(.T.),$);\n\
+#2=IFCPROPERTYSINGLEVALUE('Reference',$,IFCIDENTIFIER('W-01'),$);\n\
+#3=IFCPROPERTYSINGLEVALUE('Width',$,IFCLENGTHMEASURE(250.),#10);\n\
+#4=IFCPROPERTYSINGLEVALUE('Count',$,IFCINTEGER(3),$);\n\
+#5=IFCPROPERTYSINGLEVALUE('Empty',$,$,$);\n\
+#10=IFCSIUNIT(*,.LENGTHUNIT.,.MILLI.,.METRE.);\n";
+
+        let (mut decoder, p1) = decode(content, 1);
+        assert_eq!(
+            PropertyData::from_single_value(&p1, &mut decoder),
+            Some(PropertyData::Boolean(true))
+        );
+
+        let p2 = decoder.decode_by_id(2).unwrap();
+        assert_eq!(
+            PropertyData::from_single_value(&p2, &mut decoder),
+            Some(PropertyData::Text("W-01".to_string()))
+        );
+
+        let p3 = decoder.decode_by_id(3).unwrap();
+        let width = PropertyData::from_single_value(&p3, &mut decoder).unwrap();
+        assert_eq!(width.as_f64(), Some(250.0));
+        assert_eq!(width.unit(), Some("mm"));
+        assert_eq!(width.to_string(), "250");
+
+        let p4 = decoder.decode_by_id(4).unwrap();
+        assert_eq!(
+            PropertyData::from_single_value(&p4, &mut decoder),
+            Some(PropertyData::Integer(3))
+        );
+
+        let p5 = decoder.decode_by_id(5).unwrap();
+        assert_eq!(PropertyData::from_single_value(&p5, &mut decoder), None);
+    }
+
+    #[test]
+    fn test_complex_value_kinds() {
+        let content = "#1=IFCPROPERTYBOUNDEDVALUE('Temp',$,IFCTHERMODYNAMICTEMPERATUREMEASURE(30.),IFCTHERMODYNAMICTEMPERATUREMEASURE(10.),#10,$);\n\
+#2=IFCPROPERTYENUMERATEDVALUE('Finish',$,(IFCLABEL('Matt'),IFCLABEL('Gloss')),$);\n\
+#3=IFCPROPERTYLISTVALUE('Sizes',$,(IFCLENGTHMEASURE(10.),IFCLENGTHMEASURE(20.)),#11);\n\
+#4=IFCPROPERTYTABLEVALUE('Curve',$,(IFCREAL(0.),IFCREAL(1.)),(IFCREAL(5.),IFCREAL(7.5)),$,$,$,$);\n\
+#10=IFCSIUNIT(*,.THERMODYNAMICTEMPERATUREUNIT.,$,.DEGREE_CELSIUS.);\n\
+#11=IFCSIUNIT(*,.LENGTHUNIT.,.MILLI.,.METRE.);\n";
+        let mut decoder = EntityDecoder::new(content);
+
+        let bounded = decoder.decode_by_id(1).unwrap();
+        let data = PropertyData::from_property(&bounded, &mut decoder).unwrap();
+        assert_eq!(data.to_string(), "10 – 30");
+        assert_eq!(data.unit(), Some("°C"));
+
+        let enumerated = decoder.decode_by_id(2).unwrap();
+        let data = PropertyData::from_property(&enumerated, &mut decoder).unwrap();
+        assert_eq!(data.to_string(), "Matt, Gloss");
+
+        let list = decoder.decode_by_id(3).unwrap();
+        let data = PropertyData::from_property(&list, &mut decoder).unwrap();
+        assert_eq!(data.to_string(), "10, 20");
+        assert_eq!(data.unit(), Some("mm"));
+
+        let table = decoder.decode_by_id(4).unwrap();
+        let data = PropertyData::from_property(&table, &mut decoder).unwrap();
+        assert_eq!(data.kind(), "Table");
+        assert_eq!(data.to_string(), "0 → 5; 1 → 7.5");
+    }
+
+    #[test]
+    fn test_unit_symbols() {
+        let content = "#1=IFCSIUNIT(*,.AREAUNIT.,.MILLI.,.SQUARE_METRE.);\n\
+#2=IFCSIUNIT(*,.FORCEUNIT.,.KILO.,.NEWTON.);\n";
+        let mut decoder = EntityDecoder::new(content);
+        assert_eq!(unit_symbol(&mut decoder, 1).as_deref(), Some("mm²"));
+        assert_eq!(unit_symbol(&mut decoder, 2).as_deref(), Some("kN"));
+    }
+
+    #[test]
+    fn test_project_and_display_units() {
+        let content = "#1=IFCPROPERTYSINGLEVALUE('Width',$,IFCPOSITIVELENGTHMEASURE(304.8),$);\n\
+#2=IFCPROPERTYSINGLEVALUE('Load',$,IFCREAL(2.),$);\n\
+#3=IFCPROPERTYSINGLEVALUE('Depth',$,IFCLENGTHMEASURE(2.),#10);\n\
+#10=IFCSIUNIT(*,.LENGTHUNIT.,$,.METRE.);\n";
+        let mut decoder = EntityDecoder::new(content);
+        let units = UnitAssignment {
+            units: vec![ProjectUnit {
+                unit_type: "LENGTHUNIT".to_string(),
+                symbol: "mm".to_string(),
+                scale: 0.001,
+            }],
+        };
+
+        let width = decoder.decode_by_id(1).unwrap();
+        let data = PropertyData::from_property_with_units(&width, &mut decoder, &units).unwrap();
+        assert_eq!(data.unit(), Some("mm"));
+        let imperial = data.in_unit_system(UnitSystem::Imperial);
+        assert_eq!(imperial.unit(), Some("ft"));
+        assert_eq!(imperial.to_string(), "1");
+        assert_eq!(data.in_unit_system(UnitSystem::Metric), data);
+
+        let load = decoder.decode_by_id(2).unwrap();
+        let data = PropertyData::from_property_with_units(&load, &mut decoder, &units).unwrap();
+        assert_eq!(data.unit(), None);
+
+        // Explicit units win over project units
+        let depth = decoder.decode_by_id(3).unwrap();
+        let data = PropertyData::from_property_with_units(&depth, &mut decoder, &units).unwrap();
+        assert_eq!(data.unit(), Some("m"));
+    }
+
+    #[test]
+    fn test_format_real() {
+        assert_eq!(format_real(2.5), "2.5");
+        assert_eq!(format_real(3.0), "3");
+        assert_eq!(format_real(0.123456), "0.1235");
+        assert_eq!(format_real(-0.00001), "0");
+    }
+}
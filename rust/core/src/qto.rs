@@ -0,0 +1,473 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Quantity takeoff - per-element measures aggregated by type, storey and material
+//!
+//! Measures come from the element's IfcElementQuantity where the file has
+//! them. Gaps are filled from the generated mesh (see
+//! `ifc_lite_geometry::mesh_measures`), and such elements are flagged as
+//! estimated.
+
+use crate::classify::Attribute;
+use crate::decoder::EntityDecoder;
+use crate::generated::IfcType;
+use crate::parser::EntityScanner;
+use crate::property::format_real;
+use crate::quantity::{decode_quantity, PhysicalQuantity, QuantityKind};
+use crate::units::UnitAssignment;
+use rustc_hash::FxHashMap;
+
+/// Key of elements without storey or material in the grouped totals
+pub const UNASSIGNED: &str = "(unassigned)";
+
+/// Quantity names per measure, most specific first
+const VOLUME_NAMES: &[&str] = &["NetVolume", "GrossVolume", "Volume"];
+const FOOTPRINT_NAMES: &[&str] = &[
+    "NetFootprintArea",
+    "GrossFootprintArea",
+    "FootprintArea",
+    "NetArea",
+    "GrossArea",
+    "CrossSectionArea",
+];
+const SURFACE_NAMES: &[&str] = &[
+    "NetSurfaceArea",
+    "GrossSurfaceArea",
+    "OuterSurfaceArea",
+    "TotalSurfaceArea",
+];
+const LENGTH_NAMES: &[&str] = &["Length", "NetLength", "GrossLength"];
+
+/// Geometric measures of one element in SI units (m, m², m³)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ElementMeasures {
+    pub volume: Option<f64>,
+    /// Area of the element seen from above
+    pub footprint_area: Option<f64>,
+    /// Area of the whole outer surface
+    pub surface_area: Option<f64>,
+    /// Extent along the element's main horizontal axis
+    pub length: Option<f64>,
+}
+
+impl ElementMeasures {
+    /// Measures found in an element's quantity sets
+    ///
+    /// Quantities without a unit are in the project `units`. Quantities
+    /// with an explicit non-SI unit are skipped.
+    pub fn from_quantities(quantities: &[PhysicalQuantity], units: &UnitAssignment) -> Self {
+        let find = |names: &[&str], kind: QuantityKind| {
+            names.iter().find_map(|name| {
+                quantities
+                    .iter()
+                    .filter(|q| q.kind == kind && q.path.is_empty() && q.name == *name)
+                    .find_map(|q| {
+                        let (value, unit) = q.display_value(units);
+                        (unit == kind.default_unit()).then_some(value)
+                    })
+            })
+        };
+        Self {
+            volume: find(VOLUME_NAMES, QuantityKind::Volume),
+            footprint_area: find(FOOTPRINT_NAMES, QuantityKind::Area),
+            surface_area: find(SURFACE_NAMES, QuantityKind::Area),
+            length: find(LENGTH_NAMES, QuantityKind::Length),
+        }
+    }
+
+    /// Fill missing measures from `fallback`; true if any was filled
+    pub fn fill_from(&mut self, fallback: &Self) -> bool {
+        let mut filled = false;
+        for (value, fallback) in [
+            (&mut self.volume, fallback.volume),
+            (&mut self.footprint_area, fallback.footprint_area),
+            (&mut self.surface_area, fallback.surface_area),
+            (&mut self.length, fallback.length),
+        ] {
+            if value.is_none() && fallback.is_some() {
+                *value = fallback;
+                filled = true;
+            }
+        }
+        filled
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Measures from the IfcElementQuantity sets of every element
+///
+/// Quantities are scaled to SI with the project's units. An element
+/// with several quantity sets takes each measure from the first set having it.
+pub fn ifc_measures(content: &str, decoder: &mut EntityDecoder) -> FxHashMap<u32, ElementMeasures> {
+    let mut units = UnitAssignment::default();
+    let mut assignments: Vec<(Vec<u32>, u32)> = Vec::new();
+
+    let mut scanner = EntityScanner::new(content);
+    while let Some((id, type_name, _, _)) = scanner.next_entity() {
+        match type_name {
+            "IFCPROJECT" => units = UnitAssignment::from_project(decoder, id),
+            "IFCRELDEFINESBYPROPERTIES" => {
+                let Ok(rel) = decoder.decode_by_id(id) else {
+                    continue;
+                };
+                let (Some(related), Some(definition)) = (
+                    rel.attribute_list(Attribute::RelatedObjects),
+                    rel.attribute_ref(Attribute::RelatingPropertyDefinition),
+                ) else {
+                    continue;
+                };
+                let related = related.iter().filter_map(|r| r.as_entity_ref()).collect();
+                assignments.push((related, definition));
+            }
+            _ => {}
+        }
+    }
+
+    let mut by_set: FxHashMap<u32, Option<ElementMeasures>> = FxHashMap::default();
+    let mut measures: FxHashMap<u32, ElementMeasures> = FxHashMap::default();
+    for (related, definition) in assignments {
+        let set_measures = *by_set.entry(definition).or_insert_with(|| {
+            let set = decoder.decode_by_id(definition).ok()?;
+            if set.ifc_type != IfcType::IfcElementQuantity {
+                return None;
+            }
+            let ids: Vec<u32> = set
+                .attribute_list(Attribute::Quantities)?
+                .iter()
+                .filter_map(|r| r.as_entity_ref())
+                .collect();
+            let quantities: Vec<PhysicalQuantity> = ids
+                .into_iter()
+                .flat_map(|id| decode_quantity(decoder, id))
+                .collect();
+            let set_measures = ElementMeasures::from_quantities(&quantities, &units);
+            (!set_measures.is_empty()).then_some(set_measures)
+        });
+        let Some(set_measures) = set_measures else {
+            continue;
+        };
+        for object in related {
+            measures.entry(object).or_default().fill_from(&set_measures);
+        }
+    }
+    measures
+}
+
+/// Quantities of one element
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ElementQuantities {
+    pub entity_id: u32,
+    pub global_id: Option<String>,
+    pub ifc_type: String,
+    pub name: Option<String>,
+    pub storey: Option<String>,
+    pub material: Option<String>,
+    /// Share of the volume per base material, see
+    /// [`crate::ElementMaterial::volume_shares`]; empty without a material
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub material_shares: Vec<(String, f64)>,
+    pub measures: ElementMeasures,
+    /// Some measures were computed from geometry rather than read from the file
+    pub estimated: bool,
+}
+
+/// Totals of a group of elements
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QuantityGroup {
+    /// IFC type, storey or material name
+    pub key: String,
+    pub count: usize,
+    pub volume: f64,
+    pub footprint_area: f64,
+    pub surface_area: f64,
+    pub length: f64,
+    /// Elements with measures computed from geometry
+    pub estimated_count: usize,
+}
+
+impl QuantityGroup {
+    fn add(&mut self, element: &ElementQuantities) {
+        let m = &element.measures;
+        self.count += 1;
+        self.volume += m.volume.unwrap_or(0.0);
+        self.footprint_area += m.footprint_area.unwrap_or(0.0);
+        self.surface_area += m.surface_area.unwrap_or(0.0);
+        self.length += m.length.unwrap_or(0.0);
+        if element.estimated {
+            self.estimated_count += 1;
+        }
+    }
+
+    /// Add the part of an element made of one material; only its volume
+    /// splits, so areas and length are left out
+    fn add_share(&mut self, element: &ElementQuantities, share: f64) {
+        self.count += 1;
+        self.volume += element.measures.volume.unwrap_or(0.0) * share;
+        if element.estimated {
+            self.estimated_count += 1;
+        }
+    }
+}
+
+/// Quantity takeoff of a model
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QuantityReport {
+    pub elements: Vec<ElementQuantities>,
+    pub by_type: Vec<QuantityGroup>,
+    pub by_storey: Vec<QuantityGroup>,
+    pub by_material: Vec<QuantityGroup>,
+    /// Volumes per single material, with layered and composite elements
+    /// split by layer thickness or constituent fraction
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub by_base_material: Vec<QuantityGroup>,
+}
+
+impl QuantityReport {
+    /// Group elements by type, storey and material; groups sorted by key
+    pub fn new(elements: Vec<ElementQuantities>) -> Self {
+        fn group<'a>(groups: &'a mut Vec<QuantityGroup>, key: &str) -> &'a mut QuantityGroup {
+            let index = match groups.iter().position(|g| g.key == key) {
+                Some(index) => index,
+                None => {
+                    groups.push(QuantityGroup {
+                        key: key.to_string(),
+                        ..QuantityGroup::default()
+                    });
+                    groups.len() - 1
+                }
+            };
+            &mut groups[index]
+        }
+        fn group_by(
+            elements: &[ElementQuantities],
+            key: impl Fn(&ElementQuantities) -> Option<&str>,
+        ) -> Vec<QuantityGroup> {
+            let mut groups: Vec<QuantityGroup> = Vec::new();
+            for element in elements {
+                group(&mut groups, key(element).unwrap_or(UNASSIGNED)).add(element);
+            }
+            groups.sort_by(|a, b| a.key.cmp(&b.key));
+            groups
+        }
+
+        let mut by_base_material: Vec<QuantityGroup> = Vec::new();
+        for element in &elements {
+            if element.material_shares.is_empty() {
+                group(&mut by_base_material, UNASSIGNED).add_share(element, 1.0);
+            }
+            for (material, share) in &element.material_shares {
+                group(&mut by_base_material, material).add_share(element, *share);
+            }
+        }
+        by_base_material.sort_by(|a, b| a.key.cmp(&b.key));
+
+        Self {
+            by_type: group_by(&elements, |e| Some(&e.ifc_type)),
+            by_storey: group_by(&elements, |e| e.storey.as_deref()),
+            by_material: group_by(&elements, |e| e.material.as_deref()),
+            by_base_material,
+            elements,
+        }
+    }
+
+    /// One CSV row per element
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from(
+            "Id,GlobalId,Type,Name,Storey,Material,Volume (m³),Footprint area (m²),\
+             Surface area (m²),Length (m),Source\n",
+        );
+        let number = |value: Option<f64>| value.map(format_real).unwrap_or_default();
+        for e in &self.elements {
+            let fields = [
+                e.entity_id.to_string(),
+                csv_field(e.global_id.as_deref().unwrap_or_default()),
+                csv_field(&e.ifc_type),
+                csv_field(e.name.as_deref().unwrap_or_default()),
+                csv_field(e.storey.as_deref().unwrap_or_default()),
+                csv_field(e.material.as_deref().unwrap_or_default()),
+                number(e.measures.volume),
+                number(e.measures.footprint_area),
+                number(e.measures.surface_area),
+                number(e.measures.length),
+                if e.estimated { "geometry" } else { "ifc" }.to_string(),
+            ];
+            out.push_str(&fields.join(","));
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Quote a CSV field if it holds a separator, quote or line break
+pub fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quantity(
+        name: &str,
+        kind: QuantityKind,
+        value: f64,
+        unit: Option<&str>,
+    ) -> PhysicalQuantity {
+        PhysicalQuantity {
+            name: name.to_string(),
+            kind,
+            value,
+            unit: unit.map(str::to_string),
+            path: Vec::new(),
+        }
+    }
+
+    fn element(id: u32, ifc_type: &str, storey: Option<&str>, volume: f64) -> ElementQuantities {
+        ElementQuantities {
+            entity_id: id,
+            global_id: None,
+            ifc_type: ifc_type.to_string(),
+            name: None,
+            storey: storey.map(str::to_string),
+            material: None,
+            material_shares: Vec::new(),
+            measures: ElementMeasures {
+                volume: Some(volume),
+                ..ElementMeasures::default()
+            },
+            estimated: false,
+        }
+    }
+
+    #[test]
+    fn test_measures_from_quantities() {
+        let quantities = [
+            quantity("GrossVolume", QuantityKind::Volume, 2.0e9, None),
+            quantity("NetVolume", QuantityKind::Volume, 1.5e9, None),
+            quantity("Length", QuantityKind::Length, 4000.0, None),
+            quantity("NetSideArea", QuantityKind::Area, 1.0e6, None),
+            quantity("GrossFootprintArea", QuantityKind::Area, 5.0, Some("ft²")),
+        ];
+        // Millimetre project
+        let measures = ElementMeasures::from_quantities(
+            &quantities,
+            &UnitAssignment::with_length_scale(0.001),
+        );
+        assert!((measures.volume.unwrap() - 1.5).abs() < 1e-9);
+        assert!((measures.length.unwrap() - 4.0).abs() < 1e-9);
+        assert_eq!(measures.footprint_area, None);
+        assert_eq!(measures.surface_area, None);
+    }
+
+    #[test]
+    fn test_ifc_measures() {
+        let content = "#1=IFCQUANTITYVOLUME('NetVolume',$,$,0.8,$);\n\
+#2=IFCQUANTITYLENGTH('Length',$,$,4.,$);\n\
+#3=IFCQUANTITYAREA('NetSideArea',$,$,3.2,$);\n\
+#4=IFCELEMENTQUANTITY('q1',$,'Qto_WallBaseQuantities',$,$,(#1,#3));\n\
+#5=IFCELEMENTQUANTITY('q2',$,'Custom',$,$,(#2));\n\
+#6=IFCPROPERTYSET('p1',$,'Pset_WallCommon',$,());\n\
+#10=IFCWALL('w1',$,$,$,$,$,$,$,$);\n\
+#11=IFCWALL('w2',$,$,$,$,$,$,$,$);\n\
+#20=IFCRELDEFINESBYPROPERTIES('r1',$,$,$,(#10,#11),#4);\n\
+#21=IFCRELDEFINESBYPROPERTIES('r2',$,$,$,(#10),#5);\n\
+#22=IFCRELDEFINESBYPROPERTIES('r3',$,$,$,(#11),#6);\n";
+        let mut decoder =
+            EntityDecoder::with_index(content, crate::decoder::build_entity_index(content));
+        let measures = ifc_measures(content, &mut decoder);
+
+        assert_eq!(measures.len(), 2);
+        assert_eq!(measures[&10].volume, Some(0.8));
+        assert_eq!(measures[&10].length, Some(4.0));
+        assert_eq!(measures[&11].volume, Some(0.8));
+        assert_eq!(measures[&11].length, None);
+    }
+
+    #[test]
+    fn test_fill_from() {
+        let mut measures = ElementMeasures {
+            volume: Some(1.0),
+            ..ElementMeasures::default()
+        };
+        assert!(!measures.fill_from(&ElementMeasures {
+            volume: Some(9.0),
+            ..ElementMeasures::default()
+        }));
+        assert!(measures.fill_from(&ElementMeasures {
+            volume: Some(9.0),
+            length: Some(3.0),
+            ..ElementMeasures::default()
+        }));
+        assert_eq!(measures.volume, Some(1.0));
+        assert_eq!(measures.length, Some(3.0));
+    }
+
+    #[test]
+    fn test_report_groups() {
+        let mut estimated = element(3, "IfcSlab", None, 4.0);
+        estimated.estimated = true;
+        let report = QuantityReport::new(vec![
+            element(1, "IfcWall", Some("Level 1"), 1.0),
+            element(2, "IfcWall", Some("Level 2"), 2.0),
+            estimated,
+        ]);
+
+        assert_eq!(report.by_type.len(), 2);
+        assert_eq!(report.by_type[0].key, "IfcSlab");
+        assert_eq!(report.by_type[1].count, 2);
+        assert_eq!(report.by_type[1].volume, 3.0);
+        assert_eq!(report.by_storey[0].key, UNASSIGNED);
+        assert_eq!(report.by_storey[0].estimated_count, 1);
+        assert_eq!(report.by_material.len(), 1);
+        assert_eq!(report.by_material[0].volume, 7.0);
+    }
+
+    #[test]
+    fn test_base_material_groups() {
+        let mut layered = element(1, "IfcWall", None, 2.0);
+        layered.material = Some("Sandwich".to_string());
+        layered.material_shares = vec![
+            ("Concrete".to_string(), 0.75),
+            ("Insulation".to_string(), 0.25),
+        ];
+        let mut column = element(2, "IfcColumn", None, 1.0);
+        column.material = Some("Concrete".to_string());
+        column.material_shares = vec![("Concrete".to_string(), 1.0)];
+        let report = QuantityReport::new(vec![layered, column, element(3, "IfcSlab", None, 4.0)]);
+
+        let keys: Vec<&str> = report
+            .by_base_material
+            .iter()
+            .map(|g| g.key.as_str())
+            .collect();
+        assert_eq!(keys, [UNASSIGNED, "Concrete", "Insulation"]);
+        assert_eq!(report.by_base_material[1].count, 2);
+        assert_eq!(report.by_base_material[1].volume, 2.5);
+        assert_eq!(report.by_base_material[2].volume, 0.5);
+        assert_eq!(report.by_material[1].key, "Concrete");
+    }
+
+    #[test]
+    fn test_csv() {
+        let mut wall = element(1, "IfcWall", Some("Level 1"), 1.25);
+        wall.name = Some("Wall, \"north\"".to_string());
+        let csv = QuantityReport::new(vec![wall]).to_csv();
+        let row = csv.lines().nth(1).unwrap();
+        assert_eq!(
+            row,
+            "1,,IfcWall,\"Wall, \"\"north\"\"\",Level 1,,1.25,,,,ifc"
+        );
+    }
+}
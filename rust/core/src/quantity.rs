@@ -0,0 +1,224 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Quantities - IfcElementQuantity value extraction
+//!
+//! Reads IfcPhysicalSimpleQuantity subtypes and flattens nested
+//! IfcPhysicalComplexQuantity groups (formwork, layers, ...). An explicit
+//! Unit reference on a quantity takes precedence over the project units.
+
+use crate::decoder::EntityDecoder;
+use crate::generated::IfcType;
+use crate::property::unit_symbol;
+use crate::units::UnitAssignment;
+
+/// Nesting limit for complex quantities (guards against reference cycles)
+const MAX_COMPLEX_DEPTH: usize = 8;
+
+/// Measure kind of a simple quantity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum QuantityKind {
+    Length,
+    Area,
+    Volume,
+    Count,
+    Weight,
+    Time,
+}
+
+impl QuantityKind {
+    /// Kind for an IfcQuantity* entity type
+    pub fn from_type(ifc_type: &IfcType) -> Option<Self> {
+        match ifc_type {
+            IfcType::IfcQuantityLength => Some(Self::Length),
+            IfcType::IfcQuantityArea => Some(Self::Area),
+            IfcType::IfcQuantityVolume => Some(Self::Volume),
+            IfcType::IfcQuantityCount => Some(Self::Count),
+            IfcType::IfcQuantityWeight => Some(Self::Weight),
+            IfcType::IfcQuantityTime => Some(Self::Time),
+            _ => None,
+        }
+    }
+
+    /// Display name ("Length", "Area", ...)
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Length => "Length",
+            Self::Area => "Area",
+            Self::Volume => "Volume",
+            Self::Count => "Count",
+            Self::Weight => "Weight",
+            Self::Time => "Time",
+        }
+    }
+
+    /// Power of the length unit in this measure (m, m², m³)
+    pub fn length_exponent(&self) -> i32 {
+        match self {
+            Self::Length => 1,
+            Self::Area => 2,
+            Self::Volume => 3,
+            Self::Count | Self::Weight | Self::Time => 0,
+        }
+    }
+
+    /// SI unit symbol used when the quantity has no explicit unit
+    pub fn default_unit(&self) -> &'static str {
+        match self {
+            Self::Length => "m",
+            Self::Area => "m²",
+            Self::Volume => "m³",
+            Self::Count => "",
+            Self::Weight => "kg",
+            Self::Time => "s",
+        }
+    }
+}
+
+/// One simple quantity value
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PhysicalQuantity {
+    pub name: String,
+    pub kind: QuantityKind,
+    /// Value as written in the file
+    pub value: f64,
+    /// Symbol of the explicit Unit reference, if any
+    pub unit: Option<String>,
+    /// Names of the enclosing complex quantities, outermost first
+    pub path: Vec<String>,
+}
+
+impl PhysicalQuantity {
+    /// Name prefixed with its complex quantity path ("Formwork / Area")
+    pub fn qualified_name(&self) -> String {
+        let mut name = String::new();
+        for group in &self.path {
+            name.push_str(group);
+            name.push_str(" / ");
+        }
+        name.push_str(&self.name);
+        name
+    }
+
+    /// Value and unit symbol for display
+    ///
+    /// Quantities with an explicit unit are reported as written. Otherwise the
+    /// value is converted from the project unit of its kind to SI.
+    pub fn display_value(&self, units: &UnitAssignment) -> (f64, String) {
+        match &self.unit {
+            Some(unit) => (self.value, unit.clone()),
+            None => (
+                self.value * units.scale(self.kind),
+                self.kind.default_unit().to_string(),
+            ),
+        }
+    }
+}
+
+/// Decode a quantity entity, flattening complex quantities
+///
+/// Returns an empty list for entities that are not quantities.
+pub fn decode_quantity(decoder: &mut EntityDecoder, quantity_id: u32) -> Vec<PhysicalQuantity> {
+    let mut result = Vec::new();
+    collect_quantity(decoder, quantity_id, &mut Vec::new(), &mut result);
+    result
+}
+
+fn collect_quantity(
+    decoder: &mut EntityDecoder,
+    quantity_id: u32,
+    path: &mut Vec<String>,
+    out: &mut Vec<PhysicalQuantity>,
+) {
+    let Ok(entity) = decoder.decode_by_id(quantity_id) else {
+        return;
+    };
+    let name = entity.get_string(0).unwrap_or_default().to_string();
+
+    if entity.ifc_type == IfcType::IfcPhysicalComplexQuantity {
+        // IfcPhysicalComplexQuantity: (Name, Description, HasQuantities, Discrimination, Quality, Usage)
+        if path.len() >= MAX_COMPLEX_DEPTH {
+            return;
+        }
+        let Some(children) = entity.get(2).and_then(|a| a.as_list()) else {
+            return;
+        };
+        let child_ids: Vec<u32> = children.iter().filter_map(|c| c.as_entity_ref()).collect();
+        path.push(name);
+        for child_id in child_ids {
+            collect_quantity(decoder, child_id, path, out);
+        }
+        path.pop();
+        return;
+    }
+
+    // IfcPhysicalSimpleQuantity subtypes: (Name, Description, Unit, Value, Formula)
+    let Some(kind) = QuantityKind::from_type(&entity.ifc_type) else {
+        return;
+    };
+    let Some(value) = entity.get_float(3) else {
+        return;
+    };
+    let unit = entity.get_ref(2).and_then(|id| unit_symbol(decoder, id));
+
+    out.push(PhysicalQuantity {
+        name,
+        kind,
+        value,
+        unit,
+        path: path.clone(),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simple_quantity_units() {
+        let content = "#1=IFCQUANTITYLENGTH('Width',$,$,250.,$);\n\
+#2=IFCQUANTITYAREA('NetArea',$,#10,12.5,$);\n\
+#10=IFCSIUNIT(*,.AREAUNIT.,$,.SQUARE_METRE.);\n";
+        let mut decoder = EntityDecoder::new(content);
+
+        let width = decode_quantity(&mut decoder, 1);
+        assert_eq!(width.len(), 1);
+        assert_eq!(width[0].kind, QuantityKind::Length);
+        // Project in millimetres: converted to metres
+        let (value, unit) = width[0].display_value(&UnitAssignment::with_length_scale(0.001));
+        assert!((value - 0.25).abs() < 1e-12);
+        assert_eq!(unit, "m");
+
+        // Explicit unit wins over project scale
+        let area = decode_quantity(&mut decoder, 2);
+        assert_eq!(
+            area[0].display_value(&UnitAssignment::with_length_scale(0.001)),
+            (12.5, "m²".to_string())
+        );
+    }
+
+    #[test]
+    fn test_complex_quantity_nesting() {
+        let content = "#1=IFCQUANTITYAREA('Area',$,$,4.,$);\n\
+#2=IFCQUANTITYVOLUME('Volume',$,$,0.8,$);\n\
+#3=IFCPHYSICALCOMPLEXQUANTITY('Layer 1',$,(#1,#2),'Layer',$,$);\n\
+#4=IFCQUANTITYCOUNT('Ties',$,$,12.,$);\n\
+#5=IFCPHYSICALCOMPLEXQUANTITY('Formwork',$,(#3,#4),'Formwork',$,$);\n";
+        let mut decoder = EntityDecoder::new(content);
+
+        let quantities = decode_quantity(&mut decoder, 5);
+        let names: Vec<String> = quantities.iter().map(|q| q.qualified_name()).collect();
+        assert_eq!(
+            names,
+            [
+                "Formwork / Layer 1 / Area",
+                "Formwork / Layer 1 / Volume",
+                "Formwork / Ties"
+            ]
+        );
+        assert_eq!(quantities[2].kind, QuantityKind::Count);
+    }
+}
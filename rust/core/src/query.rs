@@ -0,0 +1,598 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Query - Type and property filtering
+//!
+//! A query is an optional entity type followed by bracketed property
+//! predicates, all of which must hold:
+//!
+//! ```text
+//! IfcWall[Pset_WallCommon.FireRating=F90][IsExternal=TRUE]
+//! IfcSlab[Qto_SlabBaseQuantities.NetVolume>2.5]
+//! [Pset_WallCommon.LoadBearing]
+//! ```
+//!
+//! The type matches subtypes too (`IfcWall` matches `IfcWallStandardCase`).
+//! A predicate without a property set name matches a property in any set.
+//! Operators: `=`, `!=`, `<`, `<=`, `>`, `>=`, `~=` (contains); no operator
+//! tests for presence. Names and text compare case-insensitively.
+
+use crate::classify::Attribute;
+use crate::decoder::{build_entity_index, EntityDecoder};
+use crate::error::{Error, Result};
+use crate::generated::IfcType;
+use crate::parser::EntityScanner;
+use crate::property::PropertyData;
+use crate::quantity::decode_quantity;
+use crate::units::UnitAssignment;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+/// Comparison operator of a predicate
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+}
+
+/// Single `[Pset.Property op value]` condition
+#[derive(Debug, Clone, PartialEq)]
+pub struct Predicate {
+    /// Property set name; `None` matches any set
+    pub pset: Option<String>,
+    pub property: String,
+    /// Operator and operand; `None` tests for presence
+    pub condition: Option<(CompareOp, String)>,
+}
+
+impl Predicate {
+    /// Check a single property value against the condition
+    pub fn test_value(&self, value: &PropertyData) -> bool {
+        match &self.condition {
+            None => true,
+            Some((op, operand)) => compare(value, *op, operand),
+        }
+    }
+
+    /// Whether the predicate names this property, e.g. to pick a table column
+    pub fn matches_name(&self, pset: &str, property: &str) -> bool {
+        self.property.eq_ignore_ascii_case(property)
+            && self
+                .pset
+                .as_ref()
+                .is_none_or(|p| p.eq_ignore_ascii_case(pset))
+    }
+}
+
+/// Parsed query expression
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    /// Entity type filter (subtypes included)
+    pub ifc_type: Option<IfcType>,
+    pub predicates: Vec<Predicate>,
+}
+
+impl Query {
+    /// Parse a query expression
+    pub fn parse(expr: &str) -> Result<Self> {
+        QueryParser::new(expr).parse()
+    }
+
+    /// Check whether an entity type passes the type filter
+    pub fn matches_type(&self, ifc_type: &IfcType) -> bool {
+        self.ifc_type.is_none_or(|t| ifc_type.is_subtype_of(t))
+    }
+
+    /// Evaluate against an entity's type and its `(pset, property, value)` triples
+    pub fn matches<'a, I>(&self, ifc_type: &IfcType, properties: I) -> bool
+    where
+        I: IntoIterator<Item = (&'a str, &'a str, &'a PropertyData)>,
+    {
+        if !self.matches_type(ifc_type) {
+            return false;
+        }
+        if self.predicates.is_empty() {
+            return true;
+        }
+        let properties: Vec<_> = properties.into_iter().collect();
+        self.predicates.iter().all(|pred| {
+            properties
+                .iter()
+                .any(|(pset, name, value)| pred.matches_name(pset, name) && pred.test_value(value))
+        })
+    }
+
+    /// Run the query over an IFC file, returning matching entity IDs in file order
+    pub fn execute(&self, content: &str) -> Vec<u32> {
+        let mut decoder = EntityDecoder::with_index(content, build_entity_index(content));
+        self.execute_with(content, &mut decoder)
+    }
+
+    /// Run the query using an existing decoder
+    ///
+    /// Property sets assigned through the element's type object are included;
+    /// quantities are compared in SI units.
+    pub fn execute_with(&self, content: &str, decoder: &mut EntityDecoder) -> Vec<u32> {
+        let mut candidates = Vec::new();
+        let mut definitions: FxHashMap<u32, Vec<u32>> = FxHashMap::default();
+        let mut type_objects: Vec<(u32, Vec<u32>)> = Vec::new();
+        let mut units = UnitAssignment::default();
+
+        let mut scanner = EntityScanner::new(content);
+        while let Some((id, type_name, _, _)) = scanner.next_entity() {
+            match type_name {
+                "IFCPROJECT" => {
+                    units = UnitAssignment::from_project(decoder, id);
+                }
+                "IFCRELDEFINESBYPROPERTIES" | "IFCRELDEFINESBYTYPE" => {
+                    if self.predicates.is_empty() && self.ifc_type.is_some() {
+                        continue;
+                    }
+                    let Ok(rel) = decoder.decode_by_id(id) else {
+                        continue;
+                    };
+                    let relating = match rel.ifc_type {
+                        IfcType::IfcRelDefinesByType => Attribute::RelatingType,
+                        _ => Attribute::RelatingPropertyDefinition,
+                    };
+                    let (Some(related), Some(relating)) = (
+                        rel.attribute_list(Attribute::RelatedObjects),
+                        rel.attribute_ref(relating),
+                    ) else {
+                        continue;
+                    };
+                    let related: Vec<u32> =
+                        related.iter().filter_map(|r| r.as_entity_ref()).collect();
+                    if type_name == "IFCRELDEFINESBYTYPE" {
+                        type_objects.push((relating, related));
+                    } else {
+                        for object in related {
+                            definitions.entry(object).or_default().push(relating);
+                        }
+                    }
+                }
+                _ => {
+                    if self.ifc_type.is_some() && self.matches_type(&IfcType::from_str(type_name)) {
+                        candidates.push(id);
+                    }
+                }
+            }
+        }
+
+        // Type objects carry their own property sets (IfcTypeObject.HasPropertySets)
+        for (type_id, related) in type_objects {
+            let psets: Vec<u32> = match decoder.decode_by_id(type_id) {
+                Ok(type_object) => type_object
+                    .attribute_list(Attribute::HasPropertySets)
+                    .map(|l| l.iter().filter_map(|r| r.as_entity_ref()).collect())
+                    .unwrap_or_default(),
+                Err(_) => continue,
+            };
+            for object in related {
+                definitions.entry(object).or_default().extend(&psets);
+            }
+        }
+
+        if self.ifc_type.is_none() {
+            candidates = definitions.keys().copied().collect();
+            candidates.sort_unstable();
+        }
+        if self.predicates.is_empty() {
+            return candidates;
+        }
+
+        let mut cache: FxHashMap<u32, Vec<(String, String, PropertyData)>> = FxHashMap::default();
+        let mut result = Vec::new();
+        for id in candidates {
+            let Some(pset_ids) = definitions.get(&id) else {
+                continue;
+            };
+            let Ok(entity) = decoder.decode_by_id(id) else {
+                continue;
+            };
+            let mut seen = FxHashSet::default();
+            for &pset_id in pset_ids {
+                if seen.insert(pset_id) && !cache.contains_key(&pset_id) {
+                    let props = decode_definition(decoder, pset_id, &units);
+                    cache.insert(pset_id, props);
+                }
+            }
+            let properties = seen
+                .iter()
+                .flat_map(|pset_id| &cache[pset_id])
+                .map(|(pset, name, value)| (pset.as_str(), name.as_str(), value));
+            if self.matches(&entity.ifc_type, properties) {
+                result.push(id);
+            }
+        }
+        result
+    }
+}
+
+/// Flatten a property set or element quantity into `(set, name, value)` triples
+fn decode_definition(
+    decoder: &mut EntityDecoder,
+    definition_id: u32,
+    units: &UnitAssignment,
+) -> Vec<(String, String, PropertyData)> {
+    let Ok(definition) = decoder.decode_by_id(definition_id) else {
+        return Vec::new();
+    };
+    let set_name = definition.name().unwrap_or_default().to_string();
+    let mut out = Vec::new();
+
+    match definition.ifc_type {
+        IfcType::IfcPropertySet => {
+            let prop_ids: Vec<u32> = definition
+                .attribute_list(Attribute::HasProperties)
+                .map(|l| l.iter().filter_map(|r| r.as_entity_ref()).collect())
+                .unwrap_or_default();
+            for prop_id in prop_ids {
+                let Ok(prop) = decoder.decode_by_id(prop_id) else {
+                    continue;
+                };
+                let name = prop.get_string(0).unwrap_or_default().to_string();
+                if let Some(value) = PropertyData::from_property(&prop, decoder) {
+                    out.push((set_name.clone(), name, value));
+                }
+            }
+        }
+        IfcType::IfcElementQuantity => {
+            let quantity_ids: Vec<u32> = definition
+                .attribute_list(Attribute::Quantities)
+                .map(|l| l.iter().filter_map(|r| r.as_entity_ref()).collect())
+                .unwrap_or_default();
+            for quantity_id in quantity_ids {
+                for quantity in decode_quantity(decoder, quantity_id) {
+                    let (value, unit) = quantity.display_value(units);
+                    out.push((
+                        set_name.clone(),
+                        quantity.qualified_name(),
+                        PropertyData::Real {
+                            value,
+                            unit: (!unit.is_empty()).then_some(unit),
+                        },
+                    ));
+                }
+            }
+        }
+        _ => {}
+    }
+    out
+}
+
+/// Compare a property value against a query operand
+fn compare(value: &PropertyData, op: CompareOp, operand: &str) -> bool {
+    match value {
+        PropertyData::List(items) => match op {
+            // A list fails != if any item equals the operand
+            CompareOp::Ne => !items.iter().any(|v| compare(v, CompareOp::Eq, operand)),
+            _ => items.iter().any(|v| compare(v, op, operand)),
+        },
+        PropertyData::Bounded {
+            lower,
+            upper,
+            set_point,
+            ..
+        } => {
+            // A range equals any value it contains; ordering uses the set point
+            let Ok(x) = operand.parse::<f64>() else {
+                return op == CompareOp::Ne;
+            };
+            match op {
+                CompareOp::Eq | CompareOp::Ne => {
+                    let inside = lower.is_none_or(|l| x >= l) && upper.is_none_or(|u| x <= u);
+                    inside == (op == CompareOp::Eq)
+                }
+                CompareOp::Contains => false,
+                _ => set_point
+                    .or(*lower)
+                    .or(*upper)
+                    .is_some_and(|v| compare_numbers(v, op, x)),
+            }
+        }
+        PropertyData::Boolean(b) => match (parse_bool(operand), op) {
+            (Some(x), CompareOp::Eq) => *b == x,
+            (Some(x), CompareOp::Ne) => *b != x,
+            (None, CompareOp::Ne) => true,
+            _ => false,
+        },
+        _ => {
+            if let (Some(v), Ok(x)) = (value.as_f64(), operand.parse::<f64>()) {
+                if op != CompareOp::Contains {
+                    return compare_numbers(v, op, x);
+                }
+            }
+            let text = value.to_string().to_lowercase();
+            let operand = operand.to_lowercase();
+            match op {
+                CompareOp::Eq => text == operand,
+                CompareOp::Ne => text != operand,
+                CompareOp::Contains => text.contains(&operand),
+                // Text ordering is not meaningful for building data
+                _ => false,
+            }
+        }
+    }
+}
+
+fn compare_numbers(v: f64, op: CompareOp, x: f64) -> bool {
+    let eps = 1e-9 * v.abs().max(x.abs()).max(1.0);
+    match op {
+        CompareOp::Eq => (v - x).abs() <= eps,
+        CompareOp::Ne => (v - x).abs() > eps,
+        CompareOp::Lt => v < x - eps,
+        CompareOp::Le => v <= x + eps,
+        CompareOp::Gt => v > x + eps,
+        CompareOp::Ge => v >= x - eps,
+        CompareOp::Contains => false,
+    }
+}
+
+fn parse_bool(s: &str) -> Option<bool> {
+    match s.to_ascii_uppercase().as_str() {
+        "TRUE" | "T" | "YES" => Some(true),
+        "FALSE" | "F" | "NO" => Some(false),
+        _ => None,
+    }
+}
+
+/// Recursive-descent parser over the query string
+struct QueryParser<'a> {
+    input: &'a str,
+    pos: usize,
+}
+
+impl<'a> QueryParser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self { input, pos: 0 }
+    }
+
+    fn parse(mut self) -> Result<Query> {
+        self.skip_ws();
+        let ifc_type = match self.peek() {
+            Some('[') | None => None,
+            Some('*') => {
+                self.pos += 1;
+                None
+            }
+            Some(_) => {
+                let start = self.pos;
+                let name = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_');
+                let ifc_type = IfcType::from_str(name);
+                if name.is_empty() || matches!(ifc_type, IfcType::Unknown(_)) {
+                    return Err(Error::query(start, format!("unknown IFC type '{}'", name)));
+                }
+                Some(ifc_type)
+            }
+        };
+
+        let mut predicates = Vec::new();
+        loop {
+            self.skip_ws();
+            match self.peek() {
+                None => break,
+                Some('[') => {
+                    self.pos += 1;
+                    predicates.push(self.predicate()?);
+                }
+                Some(c) => {
+                    return Err(Error::query(self.pos, format!("expected '[', got '{}'", c)))
+                }
+            }
+        }
+
+        if ifc_type.is_none() && predicates.is_empty() {
+            return Err(Error::query(0, "empty query"));
+        }
+        Ok(Query {
+            ifc_type,
+            predicates,
+        })
+    }
+
+    fn predicate(&mut self) -> Result<Predicate> {
+        let first = self.name()?;
+        self.skip_ws();
+        let (pset, property) = if self.peek() == Some('.') {
+            self.pos += 1;
+            (Some(first), self.name()?)
+        } else {
+            (None, first)
+        };
+
+        self.skip_ws();
+        let condition = match self.operator() {
+            Some(op) => {
+                self.skip_ws();
+                Some((op, self.value()?))
+            }
+            None => None,
+        };
+
+        self.skip_ws();
+        if self.peek() != Some(']') {
+            return Err(Error::query(self.pos, "expected ']'"));
+        }
+        self.pos += 1;
+        Ok(Predicate {
+            pset,
+            property,
+            condition,
+        })
+    }
+
+    /// Property set or property name, bare or quoted
+    fn name(&mut self) -> Result<String> {
+        self.skip_ws();
+        let start = self.pos;
+        let name = match self.peek() {
+            Some('"' | '\'') => self.quoted()?,
+            _ => self
+                .take_while(|c| !matches!(c, '.' | '=' | '!' | '<' | '>' | '~' | '[' | ']'))
+                .trim()
+                .to_string(),
+        };
+        if name.is_empty() {
+            return Err(Error::query(start, "expected property name"));
+        }
+        Ok(name)
+    }
+
+    /// Operand up to the closing bracket, bare or quoted
+    fn value(&mut self) -> Result<String> {
+        match self.peek() {
+            Some('"' | '\'') => self.quoted(),
+            _ => Ok(self.take_while(|c| c != ']').trim().to_string()),
+        }
+    }
+
+    fn operator(&mut self) -> Option<CompareOp> {
+        let rest = &self.input[self.pos..];
+        let (op, len) = if rest.starts_with("!=") {
+            (CompareOp::Ne, 2)
+        } else if rest.starts_with("<=") {
+            (CompareOp::Le, 2)
+        } else if rest.starts_with(">=") {
+            (CompareOp::Ge, 2)
+        } else if rest.starts_with("~=") {
+            (CompareOp::Contains, 2)
+        } else if rest.starts_with('=') {
+            (CompareOp::Eq, 1)
+        } else if rest.starts_with('<') {
+            (CompareOp::Lt, 1)
+        } else if rest.starts_with('>') {
+            (CompareOp::Gt, 1)
+        } else {
+            return None;
+        };
+        self.pos += len;
+        Some(op)
+    }
+
+    fn quoted(&mut self) -> Result<String> {
+        let start = self.pos;
+        let quote = self.peek().unwrap_or('"');
+        self.pos += quote.len_utf8();
+        let text = self.take_while(|c| c != quote).to_string();
+        if self.peek() != Some(quote) {
+            return Err(Error::query(start, "unterminated string"));
+        }
+        self.pos += quote.len_utf8();
+        Ok(text)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    fn skip_ws(&mut self) {
+        self.take_while(char::is_whitespace);
+    }
+
+    fn take_while(&mut self, pred: impl Fn(char) -> bool) -> &'a str {
+        let rest = &self.input[self.pos..];
+        let len = rest.find(|c| !pred(c)).unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODEL: &str = "#1=IFCPROJECT('p',$,'P',$,$,$,$,$,#2);\n\
+#2=IFCUNITASSIGNMENT((#3));\n\
+#3=IFCSIUNIT(*,.LENGTHUNIT.,.MILLI.,.METRE.);\n\
+#10=IFCWALLSTANDARDCASE('w1',$,'Wall A',$,$,$,$,$);\n\
+#11=IFCWALL('w2',$,'Wall B',$,$,$,$,$);\n\
+#12=IFCSLAB('s1',$,'Slab',$,$,$,$,$,.FLOOR.);\n\
+#20=IFCPROPERTYSINGLEVALUE('FireRating',$,IFCLABEL('F90'),$);\n\
+#21=IFCPROPERTYSINGLEVALUE('IsExternal',$,IFCBOOLEAN(.T.),$);\n\
+#22=IFCPROPERTYSET('ps1',$,'Pset_WallCommon',$,(#20,#21));\n\
+#23=IFCRELDEFINESBYPROPERTIES('r1',$,$,$,(#10),#22);\n\
+#30=IFCPROPERTYSINGLEVALUE('FireRating',$,IFCLABEL('F30'),$);\n\
+#31=IFCPROPERTYSET('ps2',$,'Pset_WallCommon',$,(#30));\n\
+#32=IFCWALLTYPE('t1',$,'Type',$,$,(#31),$,$,$,.STANDARD.);\n\
+#33=IFCRELDEFINESBYTYPE('r2',$,$,$,(#11),#32);\n\
+#40=IFCQUANTITYVOLUME('NetVolume',$,$,3000000000.,$);\n\
+#41=IFCELEMENTQUANTITY('q1',$,'Qto_SlabBaseQuantities',$,$,(#40));\n\
+#42=IFCRELDEFINESBYPROPERTIES('r3',$,$,$,(#12),#41);\n";
+
+    #[test]
+    fn test_parse_query() {
+        let query =
+            Query::parse("IfcWall[Pset_WallCommon.FireRating=F90][ \"Is External\" ]").unwrap();
+        assert_eq!(query.ifc_type, Some(IfcType::IfcWall));
+        assert_eq!(query.predicates.len(), 2);
+        assert_eq!(query.predicates[0].pset.as_deref(), Some("Pset_WallCommon"));
+        assert_eq!(
+            query.predicates[0].condition,
+            Some((CompareOp::Eq, "F90".to_string()))
+        );
+        assert_eq!(query.predicates[1].property, "Is External");
+        assert_eq!(query.predicates[1].condition, None);
+        assert!(query.predicates[0].matches_name("pset_wallcommon", "FIRERATING"));
+        assert!(!query.predicates[0].matches_name("Pset_Other", "FireRating"));
+        assert!(query.predicates[1].matches_name("Pset_Any", "is external"));
+
+        assert!(Query::parse("IfcNotAType").is_err());
+        assert!(Query::parse("IfcWall[FireRating=F90").is_err());
+        assert!(Query::parse("").is_err());
+    }
+
+    #[test]
+    fn test_execute_type_and_properties() {
+        // Subtypes are included
+        assert_eq!(Query::parse("IfcWall").unwrap().execute(MODEL), [10, 11]);
+
+        let fire = Query::parse("IfcWall[Pset_WallCommon.FireRating=f90]").unwrap();
+        assert_eq!(fire.execute(MODEL), [10]);
+
+        // Property inherited from the type object
+        let typed = Query::parse("[FireRating=F30]").unwrap();
+        assert_eq!(typed.execute(MODEL), [11]);
+
+        let external = Query::parse("IfcWall[IsExternal=TRUE]").unwrap();
+        assert_eq!(external.execute(MODEL), [10]);
+    }
+
+    #[test]
+    fn test_execute_quantity_in_si() {
+        // 3e9 mm³ = 3 m³
+        let big = Query::parse("IfcSlab[Qto_SlabBaseQuantities.NetVolume>2.5]").unwrap();
+        assert_eq!(big.execute(MODEL), [12]);
+        let small = Query::parse("IfcSlab[NetVolume<2.5]").unwrap();
+        assert!(small.execute(MODEL).is_empty());
+    }
+
+    #[test]
+    fn test_compare_values() {
+        let range = PropertyData::Bounded {
+            lower: Some(10.0),
+            upper: Some(30.0),
+            set_point: None,
+            unit: None,
+        };
+        assert!(compare(&range, CompareOp::Eq, "20"));
+        assert!(!compare(&range, CompareOp::Eq, "40"));
+
+        let list = PropertyData::List(vec![
+            PropertyData::Enum("STEEL".into()),
+            PropertyData::Enum("CONCRETE".into()),
+        ]);
+        assert!(compare(&list, CompareOp::Eq, "concrete"));
+        assert!(!compare(&list, CompareOp::Ne, "STEEL"));
+        assert!(compare(
+            &PropertyData::Text("Fire door".into()),
+            CompareOp::Contains,
+            "FIRE"
+        ));
+    }
+}
@@ -7,9 +7,13 @@
 //! Generated from IFC4 EXPRESS schema for maintainability.
 //! All types are handled generically through enum dispatch.
 
+use crate::classify::Attribute;
 use crate::generated::IfcType;
+use crate::intern::StringInterner;
 use crate::parser::Token;
+use crate::schema_version::SchemaVersion;
 use std::collections::HashMap;
+use std::sync::Arc;
 
 /// Geometry representation categories (internal use only)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -36,14 +40,14 @@ pub enum ProfileCategory {
 pub enum AttributeValue {
     /// Entity reference
     EntityRef(u32),
-    /// String value
-    String(String),
+    /// String value, shared between entities when decoded with an interner
+    String(Arc<str>),
     /// Integer value
     Integer(i64),
     /// Float value
     Float(f64),
-    /// Enum value
-    Enum(String),
+    /// Enum value, shared between entities when decoded with an interner
+    Enum(Arc<str>),
     /// List of values
     List(Vec<AttributeValue>),
     /// Null/undefined
@@ -57,17 +61,17 @@ impl AttributeValue {
     pub fn from_token(token: &Token) -> Self {
         match token {
             Token::EntityRef(id) => AttributeValue::EntityRef(*id),
-            Token::String(s) => AttributeValue::String(s.to_string()),
+            Token::String(s) => AttributeValue::String(Arc::from(*s)),
             Token::Integer(i) => AttributeValue::Integer(*i),
             Token::Float(f) => AttributeValue::Float(*f),
-            Token::Enum(e) => AttributeValue::Enum(e.to_string()),
+            Token::Enum(e) => AttributeValue::Enum(Arc::from(*e)),
             Token::List(items) => {
                 AttributeValue::List(items.iter().map(Self::from_token).collect())
             }
             Token::TypedValue(type_name, args) => {
                 // For typed values like IFCPARAMETERVALUE(0.), extract the inner value
                 // Store as a list with the type name first, followed by args
-                let mut values = vec![AttributeValue::String(type_name.to_string())];
+                let mut values = vec![AttributeValue::String(Arc::from(*type_name))];
                 values.extend(args.iter().map(Self::from_token));
                 AttributeValue::List(values)
             }
@@ -76,6 +80,44 @@ impl AttributeValue {
         }
     }
 
+    /// Convert from Token, interning strings and enum values
+    ///
+    /// Enumerations such as `.NOTDEFINED.`, typed value names such as
+    /// `IFCLENGTHMEASURE` and names such as `'Width'` repeat across most
+    /// entities; interned they are stored once per decoder. Lists are still
+    /// allocated per entity.
+    pub fn from_token_interned(token: &Token, interner: &mut StringInterner) -> Self {
+        match token {
+            Token::String(s) => AttributeValue::String(interner.intern(s)),
+            Token::Enum(e) => AttributeValue::Enum(interner.intern(e)),
+            Token::List(items) => AttributeValue::List(
+                items
+                    .iter()
+                    .map(|t| Self::from_token_interned(t, interner))
+                    .collect(),
+            ),
+            Token::TypedValue(type_name, args) => {
+                let mut values = vec![AttributeValue::String(interner.intern(type_name))];
+                values.extend(args.iter().map(|t| Self::from_token_interned(t, interner)));
+                AttributeValue::List(values)
+            }
+            other => Self::from_token(other),
+        }
+    }
+
+    /// Heap bytes owned by this value
+    ///
+    /// Strings and enum values are shared and counted by their interner instead.
+    pub fn heap_size(&self) -> usize {
+        match self {
+            AttributeValue::List(items) => {
+                items.capacity() * std::mem::size_of::<AttributeValue>()
+                    + items.iter().map(Self::heap_size).sum::<usize>()
+            }
+            _ => 0,
+        }
+    }
+
     /// Get as entity reference
     #[inline]
     pub fn as_entity_ref(&self) -> Option<u32> {
@@ -226,6 +268,8 @@ pub struct DecodedEntity {
     pub id: u32,
     pub ifc_type: IfcType,
     pub attributes: Vec<AttributeValue>,
+    /// Schema of the file the entity was read from, selects attribute positions
+    pub schema: SchemaVersion,
 }
 
 impl DecodedEntity {
@@ -235,9 +279,26 @@ impl DecodedEntity {
             id,
             ifc_type,
             attributes,
+            schema: SchemaVersion::default(),
         }
     }
 
+    /// Set the schema the attributes are laid out in
+    pub fn with_schema(mut self, schema: SchemaVersion) -> Self {
+        self.schema = schema;
+        self
+    }
+
+    /// Heap bytes owned by this entity's attributes
+    pub fn heap_size(&self) -> usize {
+        self.attributes.capacity() * std::mem::size_of::<AttributeValue>()
+            + self
+                .attributes
+                .iter()
+                .map(AttributeValue::heap_size)
+                .sum::<usize>()
+    }
+
     /// Get attribute by index
     pub fn get(&self, index: usize) -> Option<&AttributeValue> {
         self.attributes.get(index)
@@ -262,6 +323,33 @@ impl DecodedEntity {
     pub fn get_list(&self, index: usize) -> Option<&[AttributeValue]> {
         self.get(index).and_then(|v| v.as_list())
     }
+
+    /// Get GlobalId (attribute 0 of IfcRoot subtypes)
+    ///
+    /// Files that do not use 22-character compressed GUIDs still get their
+    /// ids back as written, since other tools address elements by them.
+    pub fn global_id(&self) -> Option<&str> {
+        self.get_string(0).filter(|s| !s.is_empty())
+    }
+
+    /// Get ObjectType string (attribute 4 of IfcObject subtypes)
+    pub fn object_type(&self) -> Option<&str> {
+        self.get_string(4).filter(|s| !s.is_empty())
+    }
+
+    /// Get PredefinedType of an element or spatial element
+    ///
+    /// Read from its position in the entity's schema, so IFC2x3 enums such
+    /// as CompositionType are not mistaken for it. `.USERDEFINED.` resolves
+    /// to ObjectType, `.NOTDEFINED.` yields `None`.
+    pub fn predefined_type(&self) -> Option<&str> {
+        let value = self.attribute(Attribute::PredefinedType)?.as_enum()?;
+        match value {
+            "NOTDEFINED" => None,
+            "USERDEFINED" => self.object_type(),
+            other => Some(other),
+        }
+    }
 }
 
 /// IFC schema metadata for dynamic processing
@@ -356,46 +444,10 @@ impl IfcSchema {
     }
 
     /// Check if type has geometry
+    ///
+    /// Any concrete IfcProduct subtype, see [`IfcType::has_geometry`].
     pub fn has_geometry(&self, ifc_type: &IfcType) -> bool {
-        // Building elements, furnishing, etc.
-        let name = ifc_type.name();
-        (matches!(
-            ifc_type,
-            IfcType::IfcWall
-                | IfcType::IfcWallStandardCase
-                | IfcType::IfcSlab
-                | IfcType::IfcBeam
-                | IfcType::IfcColumn
-                | IfcType::IfcRoof
-                | IfcType::IfcStair
-                | IfcType::IfcRamp
-                | IfcType::IfcRailing
-                | IfcType::IfcPlate
-                | IfcType::IfcMember
-                | IfcType::IfcFooting
-                | IfcType::IfcPile
-                | IfcType::IfcCovering
-                | IfcType::IfcCurtainWall
-                | IfcType::IfcDoor
-                | IfcType::IfcWindow
-                | IfcType::IfcChimney
-                | IfcType::IfcShadingDevice
-                | IfcType::IfcBuildingElementProxy
-                | IfcType::IfcBuildingElementPart
-        ) || name.contains("Reinforc"))
-            || matches!(
-                ifc_type,
-                IfcType::IfcFurnishingElement
-                | IfcType::IfcFurniture
-                | IfcType::IfcDuctSegment
-                | IfcType::IfcPipeSegment
-                | IfcType::IfcCableSegment
-                | IfcType::IfcProduct // Base type for all products
-                | IfcType::IfcDistributionElement
-                | IfcType::IfcFlowSegment
-                | IfcType::IfcFlowFitting
-                | IfcType::IfcFlowTerminal
-            )
+        ifc_type.has_geometry()
     }
 }
 
@@ -459,4 +511,67 @@ mod tests {
         assert_eq!(entity.get_string(1), Some("Wall-001"));
         assert_eq!(entity.get_float(2), Some(3.5));
     }
+
+    #[test]
+    fn test_predefined_type() {
+        use crate::decoder::EntityDecoder;
+
+        let content = "#1=IFCSLAB('0a',$,'Roof',$,$,$,$,$,.ROOF.);\n\
+#2=IFCCOVERING('0b',$,$,$,'Acoustic panel',$,$,$,.USERDEFINED.);\n\
+#3=IFCSPACE('0c',$,'101',$,$,$,$,'Office',.ELEMENT.,.INTERNAL.,$);\n\
+#4=IFCWALL('0d',$,$,$,$,$,$,$,.NOTDEFINED.);\n";
+        let mut decoder = EntityDecoder::new(content);
+
+        let slab = decoder.decode_by_id(1).unwrap();
+        assert_eq!(slab.predefined_type(), Some("ROOF"));
+        assert_eq!(slab.object_type(), None);
+
+        let covering = decoder.decode_by_id(2).unwrap();
+        assert_eq!(covering.predefined_type(), Some("Acoustic panel"));
+
+        // CompositionType is skipped
+        let space = decoder.decode_by_id(3).unwrap();
+        assert_eq!(space.predefined_type(), Some("INTERNAL"));
+
+        let wall = decoder.decode_by_id(4).unwrap();
+        assert_eq!(wall.predefined_type(), None);
+    }
+
+    #[test]
+    fn test_predefined_type_ifc2x3() {
+        use crate::decoder::EntityDecoder;
+
+        let content = "ISO-10303-21;\nHEADER;\nFILE_SCHEMA(('IFC2X3'));\nENDSEC;\nDATA;\n\
+#1=IFCBUILDINGELEMENTPROXY('0a',$,$,$,$,$,$,$,.ELEMENT.);\n\
+#2=IFCSPACE('0b',$,'101',$,$,$,$,'Office',.ELEMENT.,.INTERNAL.,$);\n\
+#3=IFCSLAB('0c',$,$,$,$,$,$,$,.FLOOR.);\n\
+ENDSEC;\n";
+        let mut decoder = EntityDecoder::new(content);
+        assert_eq!(decoder.schema(), SchemaVersion::Ifc2x3);
+
+        // CompositionType and InteriorOrExteriorSpace are not type enums
+        let proxy = decoder.decode_by_id(1).unwrap();
+        assert_eq!(proxy.predefined_type(), None);
+        let space = decoder.decode_by_id(2).unwrap();
+        assert_eq!(space.predefined_type(), None);
+
+        let slab = decoder.decode_by_id(3).unwrap();
+        assert_eq!(slab.schema, SchemaVersion::Ifc2x3);
+        assert_eq!(slab.predefined_type(), Some("FLOOR"));
+    }
+
+    #[test]
+    fn test_global_id() {
+        use crate::decoder::EntityDecoder;
+
+        let content = "#1=IFCWALL('2O2Fr$t4X7Zf8NOew3FLOH',$,$,$,$,$,$,$,$);\n\
+#2=IFCSLAB('',$,$,$,$,$,$,$,$);\n\
+#3=IFCCARTESIANPOINT((0.,0.,0.));\n";
+        let mut decoder = EntityDecoder::new(content);
+
+        let wall = decoder.decode_by_id(1).unwrap();
+        assert_eq!(wall.global_id(), Some("2O2Fr$t4X7Zf8NOew3FLOH"));
+        assert_eq!(decoder.decode_by_id(2).unwrap().global_id(), None);
+        assert_eq!(decoder.decode_by_id(3).unwrap().global_id(), None);
+    }
 }
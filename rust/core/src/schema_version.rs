@@ -0,0 +1,98 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Schema version from the FILE_SCHEMA header
+//!
+//! Most attribute positions are shared by IFC2x3, IFC4 and IFC4X3, but some
+//! entities gained, lost or renamed attributes between releases (IFC2x3
+//! walls have no PredefinedType, an IFC2x3 proxy has CompositionType where
+//! IFC4 has PredefinedType). The detected version selects the right
+//! positions in [`crate::classify`].
+
+use std::fmt;
+
+/// IFC release a file was written against
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SchemaVersion {
+    Ifc2x3,
+    /// Assumed when the header is missing or names an unknown schema
+    #[default]
+    Ifc4,
+    Ifc4x3,
+}
+
+impl SchemaVersion {
+    /// Parse a FILE_SCHEMA identifier, e.g. "IFC2X3", "IFC4" or "IFC4X3_ADD2"
+    pub fn from_identifier(identifier: &str) -> Option<Self> {
+        let upper = identifier.trim().to_ascii_uppercase();
+        if upper.starts_with("IFC4X3") {
+            Some(Self::Ifc4x3)
+        } else if upper.starts_with("IFC4") {
+            Some(Self::Ifc4)
+        } else if upper.starts_with("IFC2X3") {
+            Some(Self::Ifc2x3)
+        } else {
+            None
+        }
+    }
+
+    /// Detect the version from a file's FILE_SCHEMA header
+    pub fn detect(content: &str) -> Option<Self> {
+        file_schema(content).and_then(Self::from_identifier)
+    }
+
+    /// Canonical schema name
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ifc2x3 => "IFC2X3",
+            Self::Ifc4 => "IFC4",
+            Self::Ifc4x3 => "IFC4X3",
+        }
+    }
+}
+
+impl fmt::Display for SchemaVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// First schema identifier in the FILE_SCHEMA header, as written
+pub fn file_schema(content: &str) -> Option<&str> {
+    let header_end = content.find("DATA;").unwrap_or(content.len());
+    let header = &content[..header_end];
+    let start = header.find("FILE_SCHEMA")?;
+    let rest = &header[start..];
+    let open = rest.find('\'')?;
+    let close = rest[open + 1..].find('\'')?;
+    let schema = rest[open + 1..open + 1 + close].trim();
+    (!schema.is_empty()).then_some(schema)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_schema_version() {
+        let header = |schema: &str| {
+            format!("ISO-10303-21;\nHEADER;\nFILE_SCHEMA(('{schema}'));\nENDSEC;\nDATA;\nENDSEC;\n")
+        };
+        assert_eq!(
+            SchemaVersion::detect(&header("IFC2X3")),
+            Some(SchemaVersion::Ifc2x3)
+        );
+        assert_eq!(
+            SchemaVersion::detect(&header("IFC4")),
+            Some(SchemaVersion::Ifc4)
+        );
+        assert_eq!(
+            SchemaVersion::detect(&header("IFC4X3_ADD2")),
+            Some(SchemaVersion::Ifc4x3)
+        );
+        assert_eq!(SchemaVersion::detect(&header("IFC2X2_FINAL")), None);
+        assert_eq!(SchemaVersion::detect("DATA;\n#1=IFCWALL($);\n"), None);
+        assert_eq!(file_schema(&header(" IFC4X3_ADD2 ")), Some("IFC4X3_ADD2"));
+    }
+}
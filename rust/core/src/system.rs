@@ -0,0 +1,167 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Systems and zones - group membership via IfcRelAssignsToGroup
+//!
+//! Distribution systems, building systems and zones (any IfcSystem
+//! subtype) collect elements or spaces across the spatial structure.
+//! Membership is kept in both directions so callers can list a system's
+//! members and find the systems an element belongs to.
+
+use crate::classify::Attribute;
+use crate::decoder::EntityDecoder;
+use crate::generated::IfcType;
+use crate::parser::EntityScanner;
+use rustc_hash::FxHashMap;
+
+/// A system or zone
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct System {
+    pub id: u32,
+    /// Uppercase STEP name, e.g. "IFCDISTRIBUTIONSYSTEM"
+    pub entity_type: String,
+    pub name: Option<String>,
+}
+
+/// Systems of a model and their members, in both directions
+#[derive(Clone, Debug, Default)]
+pub struct SystemAssignments {
+    /// Sorted by name, then id
+    systems: Vec<System>,
+    members: FxHashMap<u32, Vec<u32>>,
+    systems_of: FxHashMap<u32, Vec<u32>>,
+}
+
+impl SystemAssignments {
+    /// Collect every IfcRelAssignsToGroup of a model whose group is a system
+    /// or zone
+    pub fn from_content(content: &str, decoder: &mut EntityDecoder) -> Self {
+        let mut assignments = Self::default();
+
+        let mut scanner = EntityScanner::new(content);
+        while let Some((id, type_name, _, _)) = scanner.next_entity() {
+            if !matches!(
+                type_name,
+                "IFCRELASSIGNSTOGROUP" | "IFCRELASSIGNSTOGROUPBYFACTOR"
+            ) {
+                continue;
+            }
+            let Ok(rel) = decoder.decode_by_id(id) else {
+                continue;
+            };
+            let (Some(related), Some(group_id)) = (
+                rel.attribute_list(Attribute::RelatedObjects),
+                rel.attribute_ref(Attribute::RelatingGroup),
+            ) else {
+                continue;
+            };
+            let related: Vec<u32> = related.iter().filter_map(|r| r.as_entity_ref()).collect();
+
+            if !assignments.members.contains_key(&group_id) {
+                let Ok(group) = decoder.decode_by_id(group_id) else {
+                    continue;
+                };
+                if !group.ifc_type.is_subtype_of(IfcType::IfcSystem) {
+                    continue;
+                }
+                assignments.systems.push(System {
+                    id: group_id,
+                    entity_type: group.ifc_type.as_str().to_string(),
+                    name: group.name().filter(|s| !s.is_empty()).map(str::to_string),
+                });
+            }
+
+            // A group may be split over several relationships
+            assignments
+                .members
+                .entry(group_id)
+                .or_default()
+                .extend(related);
+        }
+
+        assignments
+            .systems
+            .sort_by(|a, b| (&a.name, a.id).cmp(&(&b.name, b.id)));
+        for system in &assignments.systems {
+            let members = assignments.members.entry(system.id).or_default();
+            members.sort_unstable();
+            members.dedup();
+            for &object in members.iter() {
+                assignments
+                    .systems_of
+                    .entry(object)
+                    .or_default()
+                    .push(system.id);
+            }
+        }
+        assignments
+    }
+
+    /// All systems and zones, sorted by name
+    pub fn systems(&self) -> &[System] {
+        &self.systems
+    }
+
+    /// Look up a system by id
+    pub fn system(&self, id: u32) -> Option<&System> {
+        self.systems.iter().find(|s| s.id == id)
+    }
+
+    /// Members of a system, in id order
+    pub fn members(&self, system_id: u32) -> &[u32] {
+        self.members
+            .get(&system_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// Systems an entity is a member of, in name order
+    pub fn systems_of(&self, entity_id: u32) -> &[u32] {
+        self.systems_of
+            .get(&entity_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.systems.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::build_entity_index;
+
+    #[test]
+    fn test_system_assignments() {
+        let content = "#1=IFCDISTRIBUTIONSYSTEM('s1',$,'Supply air',$,$,$,.VENTILATION.);\n\
+#2=IFCZONE('z1',$,'Fire zone A',$,$,$);\n\
+#3=IFCGROUP('g1',$,'Not a system',$,$);\n\
+#10=IFCDUCTSEGMENT('d1',$,$,$,$,$,$,$,$);\n\
+#11=IFCDUCTSEGMENT('d2',$,$,$,$,$,$,$,$);\n\
+#12=IFCSPACE('sp1',$,$,$,$,$,$,$,$,$,$);\n\
+#20=IFCRELASSIGNSTOGROUP('r1',$,$,$,(#11,#10),$,#1);\n\
+#21=IFCRELASSIGNSTOGROUP('r2',$,$,$,(#12,#10),$,#2);\n\
+#22=IFCRELASSIGNSTOGROUP('r3',$,$,$,(#11),$,#3);\n";
+        let mut decoder = EntityDecoder::with_index(content, build_entity_index(content));
+        let assignments = SystemAssignments::from_content(content, &mut decoder);
+
+        let names: Vec<_> = assignments
+            .systems()
+            .iter()
+            .map(|s| s.name.as_deref().unwrap())
+            .collect();
+        assert_eq!(names, ["Fire zone A", "Supply air"]);
+        assert_eq!(
+            assignments.system(1).unwrap().entity_type,
+            "IFCDISTRIBUTIONSYSTEM"
+        );
+        assert_eq!(assignments.members(1), &[10, 11]);
+        assert_eq!(assignments.members(2), &[10, 12]);
+        assert!(assignments.members(3).is_empty());
+        assert_eq!(assignments.systems_of(10), &[2, 1]);
+        assert_eq!(assignments.systems_of(11), &[1]);
+    }
+}
@@ -0,0 +1,144 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Type objects - occurrence to type assignment via IfcRelDefinesByType
+//!
+//! Occurrences of the same product family (a door style, a column size)
+//! share one IfcTypeObject. The assignment is kept in both directions so
+//! callers can go from an element to its type and back to its siblings.
+
+use crate::classify::Attribute;
+use crate::decoder::EntityDecoder;
+use crate::parser::EntityScanner;
+use rustc_hash::FxHashMap;
+use std::collections::hash_map::Entry;
+
+/// Type object of an occurrence
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TypeObject {
+    pub id: u32,
+    /// Uppercase STEP name, e.g. "IFCDOORTYPE"
+    pub entity_type: String,
+    pub name: Option<String>,
+}
+
+/// Occurrence to type assignment of a model, in both directions
+#[derive(Clone, Debug, Default)]
+pub struct TypeAssignments {
+    type_of: FxHashMap<u32, u32>,
+    instances: FxHashMap<u32, Vec<u32>>,
+    types: FxHashMap<u32, TypeObject>,
+}
+
+impl TypeAssignments {
+    /// Collect every IfcRelDefinesByType of a model
+    pub fn from_content(content: &str, decoder: &mut EntityDecoder) -> Self {
+        let mut assignments = Self::default();
+
+        let mut scanner = EntityScanner::new(content);
+        while let Some((id, type_name, _, _)) = scanner.next_entity() {
+            if type_name != "IFCRELDEFINESBYTYPE" {
+                continue;
+            }
+            let Ok(rel) = decoder.decode_by_id(id) else {
+                continue;
+            };
+            let (Some(related), Some(type_id)) = (
+                rel.attribute_list(Attribute::RelatedObjects),
+                rel.attribute_ref(Attribute::RelatingType),
+            ) else {
+                continue;
+            };
+            let related: Vec<u32> = related.iter().filter_map(|r| r.as_entity_ref()).collect();
+
+            if let Entry::Vacant(slot) = assignments.types.entry(type_id) {
+                let Ok(type_entity) = decoder.decode_by_id(type_id) else {
+                    continue;
+                };
+                slot.insert(TypeObject {
+                    id: type_id,
+                    entity_type: type_entity.ifc_type.as_str().to_string(),
+                    name: type_entity
+                        .name()
+                        .filter(|s| !s.is_empty())
+                        .map(str::to_string),
+                });
+            }
+
+            for object in related {
+                assignments.assign(object, type_id);
+            }
+        }
+        for instances in assignments.instances.values_mut() {
+            instances.sort_unstable();
+        }
+        assignments
+    }
+
+    fn assign(&mut self, object: u32, type_id: u32) {
+        if let Some(previous) = self.type_of.insert(object, type_id) {
+            if let Some(siblings) = self.instances.get_mut(&previous) {
+                siblings.retain(|&id| id != object);
+            }
+        }
+        self.instances.entry(type_id).or_default().push(object);
+    }
+
+    /// Type object of an occurrence
+    pub fn type_of(&self, entity_id: u32) -> Option<&TypeObject> {
+        self.types.get(self.type_of.get(&entity_id)?)
+    }
+
+    /// Occurrences of a type object, in id order
+    pub fn instances_of(&self, type_id: u32) -> &[u32] {
+        self.instances
+            .get(&type_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    /// All type objects with at least one occurrence
+    pub fn types(&self) -> impl Iterator<Item = &TypeObject> {
+        self.types.values()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.type_of.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decoder::build_entity_index;
+
+    #[test]
+    fn test_type_assignments() {
+        let content =
+            "#1=IFCDOORTYPE('t1',$,'Single door',$,$,$,$,$,$,.DOOR.,.SINGLE_SWING_LEFT.,.F.,$);\n\
+#2=IFCWALLTYPE('t2',$,$,$,$,$,$,$,$,.STANDARD.);\n\
+#10=IFCDOOR('d1',$,$,$,$,$,$,$,$,$,$,$,$);\n\
+#11=IFCDOOR('d2',$,$,$,$,$,$,$,$,$,$,$,$);\n\
+#12=IFCWALL('w1',$,$,$,$,$,$,$,$);\n\
+#13=IFCWALL('w2',$,$,$,$,$,$,$,$);\n\
+#20=IFCRELDEFINESBYTYPE('r1',$,$,$,(#11,#10),#1);\n\
+#21=IFCRELDEFINESBYTYPE('r2',$,$,$,(#12),#2);\n";
+        let mut decoder = EntityDecoder::with_index(content, build_entity_index(content));
+        let assignments = TypeAssignments::from_content(content, &mut decoder);
+
+        let door_type = assignments.type_of(10).unwrap();
+        assert_eq!(door_type.id, 1);
+        assert_eq!(door_type.entity_type, "IFCDOORTYPE");
+        assert_eq!(door_type.name.as_deref(), Some("Single door"));
+        assert_eq!(assignments.instances_of(1), &[10, 11]);
+
+        let wall_type = assignments.type_of(12).unwrap();
+        assert_eq!(wall_type.name, None);
+        assert_eq!(assignments.instances_of(2), &[12]);
+
+        assert_eq!(assignments.type_of(13), None);
+        assert!(assignments.instances_of(13).is_empty());
+        assert_eq!(assignments.types().count(), 2);
+    }
+}
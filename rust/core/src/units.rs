@@ -6,9 +6,21 @@
 //!
 //! Handles parsing of IFCSIUNIT and IFCCONVERSIONBASEDUNIT (imperial units)
 //! and applying appropriate multipliers to geometry coordinates.
+//!
+//! [`UnitAssignment`] reads every unit of the project, including derived
+//! units (W/(m²·K), kg/m³, ...) and the currency, so values written in
+//! project units can be labelled and brought to SI. [`UnitSystem`] converts
+//! SI values and values with a known unit symbol to metric or imperial
+//! units for display.
 
 use crate::decoder::EntityDecoder;
 use crate::error::Result;
+use crate::generated::IfcType;
+use crate::property::{format_real, PropertyData};
+use crate::quantity::QuantityKind;
+
+/// Nesting limit for units defined by other units (guards against cycles)
+const MAX_UNIT_DEPTH: usize = 4;
 
 /// SI Prefix multipliers as defined in IFC specification
 /// Maps IfcSIPrefix enum values to their numeric multipliers
@@ -45,10 +57,491 @@ pub fn get_conversion_based_unit_factor(name: &str) -> Option<f64> {
         "INCH" | "'INCH'" => Some(0.0254),
         "YARD" | "'YARD'" => Some(0.9144),
         "MILE" | "'MILE'" => Some(1609.344),
+        // Area, volume, mass and angle units to SI
+        "SQUARE FOOT" | "SQUARE_FOOT" => Some(0.09290304),
+        "SQUARE INCH" | "SQUARE_INCH" => Some(0.00064516),
+        "SQUARE YARD" | "SQUARE_YARD" => Some(0.83612736),
+        "ACRE" => Some(4046.8564224),
+        "CUBIC FOOT" | "CUBIC_FOOT" => Some(0.028316846592),
+        "CUBIC INCH" | "CUBIC_INCH" => Some(0.000016387064),
+        "CUBIC YARD" | "CUBIC_YARD" => Some(0.764554857984),
+        "GALLON" | "US GALLON" | "GALLON US" => Some(0.003785411784),
+        "LITRE" | "LITER" => Some(0.001),
+        "POUND" | "POUND-MASS" => Some(0.45359237),
+        "TON" | "TONNE" => Some(1000.0),
+        "DEGREE" => Some(std::f64::consts::PI / 180.0),
         _ => None,
     }
 }
 
+/// Display symbol of a conversion based unit, from its name
+fn conversion_based_unit_symbol(name: &str) -> String {
+    let normalized = name.trim_matches('\'').to_uppercase().replace('_', " ");
+    match normalized.as_str() {
+        "FOOT" | "FEET" => "ft",
+        "INCH" => "in",
+        "YARD" => "yd",
+        "MILE" => "mi",
+        "SQUARE FOOT" => "ft²",
+        "SQUARE INCH" => "in²",
+        "SQUARE YARD" => "yd²",
+        "ACRE" => "ac",
+        "CUBIC FOOT" => "ft³",
+        "CUBIC INCH" => "in³",
+        "CUBIC YARD" => "yd³",
+        "GALLON" | "US GALLON" | "GALLON US" => "gal",
+        "LITRE" | "LITER" => "l",
+        "POUND" | "POUND-MASS" => "lb",
+        "TON" | "TONNE" => "t",
+        "DEGREE" => "°",
+        "FAHRENHEIT" | "DEGREE FAHRENHEIT" => "°F",
+        _ => return name.trim_matches('\'').to_lowercase(),
+    }
+    .to_string()
+}
+
+/// Symbol of an IfcSIPrefix ("k" for KILO)
+fn si_prefix_symbol(prefix: &str) -> &'static str {
+    match prefix {
+        "EXA" => "E",
+        "PETA" => "P",
+        "TERA" => "T",
+        "GIGA" => "G",
+        "MEGA" => "M",
+        "KILO" => "k",
+        "HECTO" => "h",
+        "DECA" => "da",
+        "DECI" => "d",
+        "CENTI" => "c",
+        "MILLI" => "m",
+        "MICRO" => "µ",
+        "NANO" => "n",
+        "PICO" => "p",
+        "FEMTO" => "f",
+        "ATTO" => "a",
+        _ => "",
+    }
+}
+
+/// Symbol of an IfcSIUnitName ("m²" for SQUARE_METRE)
+fn si_unit_symbol(name: &str) -> String {
+    match name {
+        "METRE" => "m",
+        "SQUARE_METRE" => "m²",
+        "CUBIC_METRE" => "m³",
+        "GRAM" => "g",
+        "SECOND" => "s",
+        "AMPERE" => "A",
+        "KELVIN" => "K",
+        "DEGREE_CELSIUS" => "°C",
+        "MOLE" => "mol",
+        "CANDELA" => "cd",
+        "RADIAN" => "rad",
+        "STERADIAN" => "sr",
+        "HERTZ" => "Hz",
+        "NEWTON" => "N",
+        "PASCAL" => "Pa",
+        "JOULE" => "J",
+        "WATT" => "W",
+        "COULOMB" => "C",
+        "VOLT" => "V",
+        "FARAD" => "F",
+        "OHM" => "Ω",
+        "SIEMENS" => "S",
+        "WEBER" => "Wb",
+        "TESLA" => "T",
+        "HENRY" => "H",
+        "LUMEN" => "lm",
+        "LUX" => "lx",
+        "BECQUEREL" => "Bq",
+        "GRAY" => "Gy",
+        "SIEVERT" => "Sv",
+        other => return other.to_lowercase(),
+    }
+    .to_string()
+}
+
+/// Superscript form of an exponent ("²", "⁻¹")
+fn superscript(exponent: i64) -> String {
+    exponent
+        .to_string()
+        .chars()
+        .map(|c| match c {
+            '-' => '⁻',
+            '0' => '⁰',
+            '1' => '¹',
+            '2' => '²',
+            '3' => '³',
+            '4' => '⁴',
+            '5' => '⁵',
+            '6' => '⁶',
+            '7' => '⁷',
+            '8' => '⁸',
+            _ => '⁹',
+        })
+        .collect()
+}
+
+/// One unit of the project's unit assignment, or an explicit unit
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProjectUnit {
+    /// IfcUnitEnum or IfcDerivedUnitEnum value ("LENGTHUNIT",
+    /// "THERMALTRANSMITTANCEUNIT"), the UserDefinedType of a user defined
+    /// derived unit, or "MONETARYUNIT" for the currency
+    pub unit_type: String,
+    /// Display symbol ("mm", "ft²", "W/(m²·K)", "EUR")
+    pub symbol: String,
+    /// Value of one unit in the coherent SI unit (0.001 for mm, 1 for a currency)
+    pub scale: f64,
+}
+
+impl ProjectUnit {
+    /// Decode an IfcSIUnit, IfcConversionBasedUnit, IfcContextDependentUnit,
+    /// IfcDerivedUnit or IfcMonetaryUnit
+    pub fn decode(decoder: &mut EntityDecoder, unit_id: u32) -> Option<Self> {
+        Self::decode_nested(decoder, unit_id, 0)
+    }
+
+    fn decode_nested(decoder: &mut EntityDecoder, unit_id: u32, depth: usize) -> Option<Self> {
+        if depth > MAX_UNIT_DEPTH {
+            return None;
+        }
+        let unit = decoder.decode_by_id(unit_id).ok()?;
+        match unit.ifc_type {
+            IfcType::IfcSIUnit => {
+                // IfcSIUnit: (Dimensions, UnitType, Prefix, Name)
+                let name = unit.get(3)?.as_enum()?;
+                let prefix = unit.get(2).and_then(|a| a.as_enum()).unwrap_or("");
+                let exponent = match name {
+                    "SQUARE_METRE" => 2,
+                    "CUBIC_METRE" => 3,
+                    _ => 1,
+                };
+                let mut scale = get_si_prefix_multiplier(prefix).powi(exponent);
+                if name == "GRAM" {
+                    // The coherent SI unit of mass is the kilogram
+                    scale *= 1e-3;
+                }
+                Some(Self {
+                    unit_type: unit.get(1)?.as_enum()?.to_string(),
+                    symbol: format!("{}{}", si_prefix_symbol(prefix), si_unit_symbol(name)),
+                    scale,
+                })
+            }
+            // IfcConversionBasedUnit: (Dimensions, UnitType, Name, ConversionFactor[, ConversionOffset])
+            IfcType::IfcConversionBasedUnit | IfcType::IfcConversionBasedUnitWithOffset => {
+                let name = unit.get_string(2)?.to_string();
+                let scale = get_conversion_based_unit_factor(&name)
+                    .or_else(|| {
+                        // IfcMeasureWithUnit: (ValueComponent, UnitComponent)
+                        let factor = decoder.decode_by_id(unit.get_ref(3)?).ok()?;
+                        let value = PropertyData::from_attribute(factor.get(0)?)?.as_f64()?;
+                        let base = factor
+                            .get_ref(1)
+                            .and_then(|id| Self::decode_nested(decoder, id, depth + 1))
+                            .map_or(1.0, |base| base.scale);
+                        Some(value * base)
+                    })
+                    .filter(|scale| *scale > 0.0)
+                    .unwrap_or(1.0);
+                Some(Self {
+                    unit_type: unit.get(1)?.as_enum()?.to_string(),
+                    symbol: conversion_based_unit_symbol(&name),
+                    scale,
+                })
+            }
+            IfcType::IfcContextDependentUnit => {
+                // IfcContextDependentUnit: (Dimensions, UnitType, Name)
+                Some(Self {
+                    unit_type: unit.get(1)?.as_enum()?.to_string(),
+                    symbol: unit.get_string(2)?.to_lowercase(),
+                    scale: 1.0,
+                })
+            }
+            IfcType::IfcDerivedUnit => {
+                // IfcDerivedUnit: (Elements, UnitType, UserDefinedType[, Name])
+                let element_ids: Vec<u32> = unit
+                    .get(0)?
+                    .as_list()?
+                    .iter()
+                    .filter_map(|e| e.as_entity_ref())
+                    .collect();
+                let mut numerator = Vec::new();
+                let mut denominator = Vec::new();
+                let mut scale = 1.0;
+                for element_id in element_ids {
+                    // IfcDerivedUnitElement: (Unit, Exponent)
+                    let element = decoder.decode_by_id(element_id).ok()?;
+                    let exponent = element.get(1)?.as_int()?;
+                    let base = Self::decode_nested(decoder, element.get_ref(0)?, depth + 1)?;
+                    scale *= base.scale.powi(exponent as i32);
+                    let (side, power) = if exponent < 0 {
+                        (&mut denominator, -exponent)
+                    } else {
+                        (&mut numerator, exponent)
+                    };
+                    side.push(match power {
+                        1 => base.symbol,
+                        _ => format!("{}{}", base.symbol, superscript(power)),
+                    });
+                }
+                let mut symbol = if numerator.is_empty() {
+                    "1".to_string()
+                } else {
+                    numerator.join("·")
+                };
+                match denominator.len() {
+                    0 => {}
+                    1 => symbol = format!("{}/{}", symbol, denominator[0]),
+                    _ => symbol = format!("{}/({})", symbol, denominator.join("·")),
+                }
+                let unit_type = match unit.get(1)?.as_enum()? {
+                    "USERDEFINED" => unit.get_string(2).unwrap_or("USERDEFINED").to_string(),
+                    unit_type => unit_type.to_string(),
+                };
+                Some(Self {
+                    unit_type,
+                    symbol,
+                    scale,
+                })
+            }
+            IfcType::IfcMonetaryUnit => {
+                // IfcMonetaryUnit: (Currency), a label since IFC4, an enum in IFC2x3
+                let currency = unit.get(0)?;
+                let symbol = currency.as_string().or_else(|| currency.as_enum())?;
+                Some(Self {
+                    unit_type: "MONETARYUNIT".to_string(),
+                    symbol: symbol.to_string(),
+                    scale: 1.0,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Units of a project (IfcProject.UnitsInContext)
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UnitAssignment {
+    pub units: Vec<ProjectUnit>,
+}
+
+impl UnitAssignment {
+    /// Read the IfcUnitAssignment of a project; empty when there is none
+    pub fn from_project(decoder: &mut EntityDecoder, project_id: u32) -> Self {
+        // IFCPROJECT attribute 8: UnitsInContext
+        let unit_ids: Vec<u32> = decoder
+            .decode_by_id(project_id)
+            .ok()
+            .and_then(|project| project.get_ref(8))
+            .and_then(|id| decoder.decode_by_id(id).ok())
+            .filter(|assignment| assignment.ifc_type == IfcType::IfcUnitAssignment)
+            .and_then(|assignment| {
+                Some(
+                    assignment
+                        .get(0)?
+                        .as_list()?
+                        .iter()
+                        .filter_map(|u| u.as_entity_ref())
+                        .collect(),
+                )
+            })
+            .unwrap_or_default();
+        Self {
+            units: unit_ids
+                .into_iter()
+                .filter_map(|id| ProjectUnit::decode(decoder, id))
+                .collect(),
+        }
+    }
+
+    /// Assignment with just a length unit (`scale` metres per unit)
+    pub fn with_length_scale(scale: f64) -> Self {
+        Self {
+            units: vec![ProjectUnit {
+                unit_type: "LENGTHUNIT".to_string(),
+                symbol: String::new(),
+                scale,
+            }],
+        }
+    }
+
+    /// Unit assigned for a unit type ("LENGTHUNIT", "MONETARYUNIT", ...)
+    pub fn get(&self, unit_type: &str) -> Option<&ProjectUnit> {
+        self.units.iter().find(|u| u.unit_type == unit_type)
+    }
+
+    /// Metres per project length unit
+    pub fn length_scale(&self) -> f64 {
+        self.get("LENGTHUNIT").map_or(1.0, |u| u.scale)
+    }
+
+    /// Currency code, if the project has one
+    pub fn currency(&self) -> Option<&str> {
+        self.get("MONETARYUNIT").map(|u| u.symbol.as_str())
+    }
+
+    /// SI value of one project unit of a quantity kind
+    ///
+    /// Areas and volumes without their own unit use the length unit squared
+    /// and cubed.
+    pub fn scale(&self, kind: QuantityKind) -> f64 {
+        let unit_type = match kind {
+            QuantityKind::Length => "LENGTHUNIT",
+            QuantityKind::Area => "AREAUNIT",
+            QuantityKind::Volume => "VOLUMEUNIT",
+            QuantityKind::Weight => "MASSUNIT",
+            QuantityKind::Time => "TIMEUNIT",
+            QuantityKind::Count => return 1.0,
+        };
+        match self.get(unit_type) {
+            Some(unit) => unit.scale,
+            None => self.length_scale().powi(kind.length_exponent()),
+        }
+    }
+
+    /// Project unit of values of an IFC measure type
+    ///
+    /// `IFCLENGTHMEASURE` and `IFCPOSITIVELENGTHMEASURE` take the LENGTHUNIT,
+    /// `IFCTHERMALTRANSMITTANCEMEASURE` the THERMALTRANSMITTANCEUNIT, and so on.
+    pub fn for_measure(&self, measure_type: &str) -> Option<&ProjectUnit> {
+        let name = measure_type.to_uppercase();
+        let name = name.strip_prefix("IFC")?.strip_suffix("MEASURE")?;
+        let name = name
+            .strip_prefix("POSITIVE")
+            .or_else(|| name.strip_prefix("NONNEGATIVE"))
+            .unwrap_or(name);
+        self.get(&format!("{}UNIT", name))
+    }
+}
+
+/// Unit system values are displayed in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum UnitSystem {
+    #[default]
+    Metric,
+    Imperial,
+}
+
+/// Unit symbols the display conversion knows: (symbol, kind, SI value, imperial)
+const KNOWN_UNITS: &[(&str, QuantityKind, f64, bool)] = &[
+    ("m", QuantityKind::Length, 1.0, false),
+    ("km", QuantityKind::Length, 1e3, false),
+    ("dm", QuantityKind::Length, 1e-1, false),
+    ("cm", QuantityKind::Length, 1e-2, false),
+    ("mm", QuantityKind::Length, 1e-3, false),
+    ("m²", QuantityKind::Area, 1.0, false),
+    ("km²", QuantityKind::Area, 1e6, false),
+    ("dm²", QuantityKind::Area, 1e-2, false),
+    ("cm²", QuantityKind::Area, 1e-4, false),
+    ("mm²", QuantityKind::Area, 1e-6, false),
+    ("m³", QuantityKind::Volume, 1.0, false),
+    ("dm³", QuantityKind::Volume, 1e-3, false),
+    ("l", QuantityKind::Volume, 1e-3, false),
+    ("cm³", QuantityKind::Volume, 1e-6, false),
+    ("mm³", QuantityKind::Volume, 1e-9, false),
+    ("kg", QuantityKind::Weight, 1.0, false),
+    ("g", QuantityKind::Weight, 1e-3, false),
+    ("t", QuantityKind::Weight, 1e3, false),
+    ("ft", QuantityKind::Length, 0.3048, true),
+    ("in", QuantityKind::Length, 0.0254, true),
+    ("yd", QuantityKind::Length, 0.9144, true),
+    ("mi", QuantityKind::Length, 1609.344, true),
+    ("ft²", QuantityKind::Area, 0.09290304, true),
+    ("in²", QuantityKind::Area, 0.00064516, true),
+    ("yd²", QuantityKind::Area, 0.83612736, true),
+    ("ac", QuantityKind::Area, 4046.8564224, true),
+    ("ft³", QuantityKind::Volume, 0.028316846592, true),
+    ("in³", QuantityKind::Volume, 0.000016387064, true),
+    ("yd³", QuantityKind::Volume, 0.764554857984, true),
+    ("gal", QuantityKind::Volume, 0.003785411784, true),
+    ("lb", QuantityKind::Weight, 0.45359237, true),
+];
+
+impl UnitSystem {
+    /// Display name ("Metric", "Imperial")
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Metric => "Metric",
+            Self::Imperial => "Imperial",
+        }
+    }
+
+    /// The other system
+    pub fn toggled(&self) -> Self {
+        match self {
+            Self::Metric => Self::Imperial,
+            Self::Imperial => Self::Metric,
+        }
+    }
+
+    /// Symbol and SI value of the unit a quantity kind is shown in
+    pub fn unit(&self, kind: QuantityKind) -> (&'static str, f64) {
+        match (self, kind) {
+            (Self::Imperial, QuantityKind::Length) => ("ft", 0.3048),
+            (Self::Imperial, QuantityKind::Area) => ("ft²", 0.09290304),
+            (Self::Imperial, QuantityKind::Volume) => ("ft³", 0.028316846592),
+            (Self::Imperial, QuantityKind::Weight) => ("lb", 0.45359237),
+            (_, kind) => (kind.default_unit(), 1.0),
+        }
+    }
+
+    /// Convert an SI value (m, m², m³, kg, s) to this system
+    pub fn from_si(&self, kind: QuantityKind, value: f64) -> (f64, &'static str) {
+        let (symbol, scale) = self.unit(kind);
+        (value / scale, symbol)
+    }
+
+    /// Format an SI value with the unit of this system ("12.5 ft²")
+    pub fn format(&self, kind: QuantityKind, value: f64, decimals: usize) -> String {
+        let (value, symbol) = self.from_si(kind, value);
+        if symbol.is_empty() {
+            format!("{:.*}", decimals, value)
+        } else {
+            format!("{:.*} {}", decimals, value, symbol)
+        }
+    }
+
+    /// Format a length in metres, choosing the unit by size
+    ///
+    /// Metric lengths go from mm to km, imperial ones are given in inches
+    /// below a foot and in feet above.
+    pub fn format_length(&self, metres: f64) -> String {
+        let magnitude = metres.abs();
+        match self {
+            Self::Metric if magnitude >= 1000.0 => format!("{:.2} km", metres / 1000.0),
+            Self::Metric if magnitude >= 1.0 => format!("{:.3} m", metres),
+            Self::Metric => format!("{:.0} mm", metres * 1000.0),
+            Self::Imperial if magnitude >= 0.3048 => format!("{:.2} ft", metres / 0.3048),
+            Self::Imperial => format!("{:.2} in", metres / 0.0254),
+        }
+    }
+
+    /// Convert a value written with a unit symbol to this system
+    ///
+    /// Returns None when the symbol is unknown or already belongs to this
+    /// system; values in mm stay in mm in the metric system.
+    pub fn convert(&self, value: f64, symbol: &str) -> Option<(f64, &'static str)> {
+        let &(_, kind, scale, imperial) = KNOWN_UNITS.iter().find(|(s, ..)| *s == symbol)?;
+        if imperial == (*self == Self::Imperial) {
+            return None;
+        }
+        Some(self.from_si(kind, value * scale))
+    }
+
+    /// Format a value with a unit symbol, converted to this system
+    pub fn format_value(&self, value: f64, symbol: &str) -> String {
+        match self.convert(value, symbol) {
+            Some((value, symbol)) => format!("{} {}", format_real(value), symbol),
+            None if symbol.is_empty() => format_real(value),
+            None => format!("{} {}", format_real(value), symbol),
+        }
+    }
+}
+
 /// Extract length unit scale factor from IFC file
 ///
 /// Follows the chain: IFCPROJECT → IFCUNITASSIGNMENT → IFCSIUNIT
@@ -362,4 +855,83 @@ END-ISO-10303-21;
             scale
         );
     }
+
+    #[test]
+    fn test_unit_assignment() {
+        let ifc_content = r#"ISO-10303-21;
+HEADER;
+FILE_DESCRIPTION(('Test'),'2;1');
+FILE_NAME('test.ifc','2024-01-01',(''),(''),'','','');
+FILE_SCHEMA(('IFC4'));
+ENDSEC;
+DATA;
+#1=IFCPROJECT('guid',$,'Test',$,$,$,$,$,#3);
+#3=IFCUNITASSIGNMENT((#4,#5,#6,#7,#8,#11));
+#4=IFCSIUNIT(*,.LENGTHUNIT.,.MILLI.,.METRE.);
+#5=IFCSIUNIT(*,.AREAUNIT.,$,.SQUARE_METRE.);
+#6=IFCSIUNIT(*,.MASSUNIT.,.KILO.,.GRAM.);
+#7=IFCMONETARYUNIT('EUR');
+#8=IFCDERIVEDUNIT((#9,#10,#12),.THERMALTRANSMITTANCEUNIT.,$);
+#9=IFCDERIVEDUNITELEMENT(#13,1);
+#10=IFCDERIVEDUNITELEMENT(#5,-1);
+#11=IFCCONVERSIONBASEDUNIT(#14,.PLANEANGLEUNIT.,'DEGREE',#15);
+#12=IFCDERIVEDUNITELEMENT(#16,-1);
+#13=IFCSIUNIT(*,.POWERUNIT.,$,.WATT.);
+#14=IFCDIMENSIONALEXPONENTS(0,0,0,0,0,0,0);
+#15=IFCMEASUREWITHUNIT(IFCPLANEANGLEMEASURE(0.0174532925199433),#17);
+#16=IFCSIUNIT(*,.THERMODYNAMICTEMPERATUREUNIT.,$,.KELVIN.);
+#17=IFCSIUNIT(*,.PLANEANGLEUNIT.,$,.RADIAN.);
+ENDSEC;
+END-ISO-10303-21;
+"#;
+
+        let mut decoder = EntityDecoder::new(ifc_content);
+        let units = UnitAssignment::from_project(&mut decoder, 1);
+        assert_eq!(units.units.len(), 6);
+        assert!((units.length_scale() - 0.001).abs() < 1e-12);
+        assert_eq!(units.currency(), Some("EUR"));
+        assert_eq!(units.get("MASSUNIT").map(|u| u.scale), Some(1.0));
+        assert_eq!(units.get("PLANEANGLEUNIT").unwrap().symbol, "°");
+
+        let transmittance = units.for_measure("IFCTHERMALTRANSMITTANCEMEASURE").unwrap();
+        assert_eq!(transmittance.symbol, "W/(m²·K)");
+        assert_eq!(
+            units
+                .for_measure("IFCPOSITIVELENGTHMEASURE")
+                .unwrap()
+                .symbol,
+            "mm"
+        );
+        assert_eq!(units.for_measure("IFCLABEL"), None);
+
+        // The area unit is its own, the volume unit follows the length unit
+        assert_eq!(units.scale(QuantityKind::Area), 1.0);
+        assert!((units.scale(QuantityKind::Volume) - 1e-9).abs() < 1e-18);
+    }
+
+    #[test]
+    fn test_unit_system_conversion() {
+        let (value, symbol) = UnitSystem::Imperial.from_si(QuantityKind::Length, 3.048);
+        assert!((value - 10.0).abs() < 1e-9);
+        assert_eq!(symbol, "ft");
+        assert_eq!(
+            UnitSystem::Metric.format(QuantityKind::Area, 12.5, 2),
+            "12.50 m²"
+        );
+
+        // Written units stay within their own system
+        assert_eq!(UnitSystem::Metric.convert(250.0, "mm"), None);
+        assert_eq!(UnitSystem::Imperial.convert(10.0, "ft"), None);
+        let (value, symbol) = UnitSystem::Imperial.convert(304.8, "mm").unwrap();
+        assert!((value - 1.0).abs() < 1e-9);
+        assert_eq!(symbol, "ft");
+        let (value, symbol) = UnitSystem::Metric.convert(1.0, "ft³").unwrap();
+        assert!((value - 0.028316846592).abs() < 1e-12);
+        assert_eq!(symbol, "m³");
+        assert_eq!(UnitSystem::Imperial.convert(2.0, "kN"), None);
+
+        assert_eq!(UnitSystem::Imperial.format_value(2.0, "lb"), "2 lb");
+        assert_eq!(UnitSystem::Metric.format_length(0.25), "250 mm");
+        assert_eq!(UnitSystem::Imperial.format_length(0.0254), "1.00 in");
+    }
 }
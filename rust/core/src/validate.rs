@@ -0,0 +1,286 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Validation - structural checks over a whole model
+//!
+//! [`validate`] looks for problems that load fine but break downstream
+//! tools: references to entities that don't exist, GlobalIds used twice,
+//! missing mandatory attributes and spaces outside the spatial structure.
+//! Findings are [`Diagnostic`]s with kind [`DiagnosticKind::Structure`]
+//! (or `Parse` for entities that don't tokenize at all).
+
+use crate::classify::{resolve_type, Attribute};
+use crate::decoder::build_entity_index;
+use crate::diagnostics::{DiagnosticKind, Diagnostics, LineIndex, Severity};
+use crate::generated::IfcType;
+use crate::parser::{parse_entity, EntityScanner};
+use crate::schema_gen::{AttributeValue, DecodedEntity};
+use crate::schema_version::SchemaVersion;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+/// Relationship endpoints; all are mandatory where the entity declares them
+const RELATIONSHIP_ATTRIBUTES: [Attribute; 14] = [
+    Attribute::RelatingObject,
+    Attribute::RelatedObjects,
+    Attribute::RelatingStructure,
+    Attribute::RelatedElements,
+    Attribute::RelatingType,
+    Attribute::RelatingPropertyDefinition,
+    Attribute::RelatingMaterial,
+    Attribute::RelatingGroup,
+    Attribute::RelatingSystem,
+    Attribute::RelatedBuildings,
+    Attribute::RelatingBuildingElement,
+    Attribute::RelatedOpeningElement,
+    Attribute::RelatingOpeningElement,
+    Attribute::RelatedBuildingElement,
+];
+
+/// Check a model's structure and return the findings, sorted by line
+///
+/// Rules:
+/// - every `#id` reference resolves to an entity in the file
+/// - every IfcRoot has a well-formed GlobalId, unique in the file
+/// - IFC2x3 IfcRoot entities have an OwnerHistory
+/// - relationships have their relating and related ends
+/// - exactly one IfcProject
+/// - every IfcSpace is aggregated into or contained in the spatial structure
+pub fn validate(content: &str) -> Diagnostics {
+    let mut diagnostics = Diagnostics::new();
+    let schema = SchemaVersion::detect(content).unwrap_or_default();
+    let index = build_entity_index(content);
+    let lines = LineIndex::new(content);
+
+    let mut global_ids: FxHashMap<String, u32> = FxHashMap::default();
+    let mut projects = Vec::new();
+    let mut spaces = Vec::new();
+    // Objects placed in the spatial structure by aggregation or containment
+    let mut placed: FxHashSet<u32> = FxHashSet::default();
+
+    let mut scanner = EntityScanner::new(content);
+    while let Some((id, type_name, start, end)) = scanner.next_entity() {
+        let line = Some(lines.line(start));
+        let tokens = match parse_entity(&content[start..end]) {
+            Ok((_, _, tokens)) => tokens,
+            Err(e) => {
+                diagnostics.entity(
+                    Severity::Error,
+                    DiagnosticKind::Parse,
+                    id,
+                    type_name,
+                    line,
+                    format!("Malformed {}: {}", type_name, e),
+                );
+                continue;
+            }
+        };
+        let ifc_type = resolve_type(&type_name.to_ascii_uppercase());
+        let attributes: Vec<AttributeValue> =
+            tokens.iter().map(AttributeValue::from_token).collect();
+        let entity = DecodedEntity::new(id, ifc_type, attributes).with_schema(schema);
+        let mut report = |severity, message: String| {
+            diagnostics.entity(
+                severity,
+                DiagnosticKind::Structure,
+                id,
+                type_name,
+                line,
+                message,
+            )
+        };
+
+        let mut missing = Vec::new();
+        for value in &entity.attributes {
+            collect_refs(value, &mut |target| {
+                if !index.contains_key(&target) {
+                    missing.push(target);
+                }
+            });
+        }
+        for target in missing {
+            report(
+                Severity::Error,
+                format!("Reference to missing entity #{}", target),
+            );
+        }
+
+        if ifc_type.is_subtype_of(IfcType::IfcRoot) {
+            match entity
+                .attribute(Attribute::GlobalId)
+                .and_then(|v| v.as_string())
+            {
+                None => report(Severity::Error, "Missing GlobalId".to_string()),
+                Some(global_id) => {
+                    if !is_valid_global_id(global_id) {
+                        report(
+                            Severity::Warning,
+                            format!("Malformed GlobalId '{}'", global_id),
+                        );
+                    }
+                    if let Some(&first) = global_ids.get(global_id) {
+                        report(
+                            Severity::Error,
+                            format!(
+                                "Duplicate GlobalId '{}', also used by #{}",
+                                global_id, first
+                            ),
+                        );
+                    } else {
+                        global_ids.insert(global_id.to_string(), id);
+                    }
+                }
+            }
+            if schema == SchemaVersion::Ifc2x3
+                && entity
+                    .attribute(Attribute::OwnerHistory)
+                    .is_none_or(|v| v.is_null())
+            {
+                report(
+                    Severity::Warning,
+                    "Missing OwnerHistory, mandatory in IFC2x3".to_string(),
+                );
+            }
+        }
+
+        if ifc_type.is_relationship() {
+            for attribute in RELATIONSHIP_ATTRIBUTES {
+                let Some(value) = entity.attribute(attribute) else {
+                    continue;
+                };
+                let empty = value.is_null() || value.as_list().is_some_and(|l| l.is_empty());
+                if empty {
+                    report(Severity::Error, format!("Missing {:?}", attribute));
+                }
+            }
+        }
+
+        match ifc_type {
+            IfcType::IfcProject => projects.push(id),
+            IfcType::IfcSpace => spaces.push((id, line)),
+            IfcType::IfcRelAggregates => {
+                placed.extend(related_ids(&entity, Attribute::RelatedObjects));
+            }
+            IfcType::IfcRelContainedInSpatialStructure => {
+                placed.extend(related_ids(&entity, Attribute::RelatedElements));
+            }
+            _ => {}
+        }
+    }
+
+    match projects.as_slice() {
+        [] => diagnostics.file(
+            Severity::Error,
+            DiagnosticKind::Structure,
+            "No IfcProject found",
+        ),
+        [_] => {}
+        [_, rest @ ..] => {
+            for &id in rest {
+                diagnostics.entity(
+                    Severity::Error,
+                    DiagnosticKind::Structure,
+                    id,
+                    "IFCPROJECT",
+                    index.get(&id).map(|&(start, _)| lines.line(start)),
+                    "More than one IfcProject",
+                );
+            }
+        }
+    }
+
+    for (id, line) in spaces {
+        if !placed.contains(&id) {
+            diagnostics.entity(
+                Severity::Warning,
+                DiagnosticKind::Structure,
+                id,
+                "IFCSPACE",
+                line,
+                "Space is not part of the spatial structure",
+            );
+        }
+    }
+
+    diagnostics.sort();
+    diagnostics
+}
+
+/// Call `f` for every entity reference in a value, including nested lists
+fn collect_refs(value: &AttributeValue, f: &mut impl FnMut(u32)) {
+    match value {
+        AttributeValue::EntityRef(id) => f(*id),
+        AttributeValue::List(items) => items.iter().for_each(|item| collect_refs(item, f)),
+        _ => {}
+    }
+}
+
+fn related_ids(entity: &DecodedEntity, attribute: Attribute) -> impl Iterator<Item = u32> + '_ {
+    entity
+        .attribute_list(attribute)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|v| v.as_entity_ref())
+}
+
+/// 22 characters of the IFC base64 alphabet, the first one at most '3'
+fn is_valid_global_id(global_id: &str) -> bool {
+    let bytes = global_id.as_bytes();
+    bytes.len() == 22
+        && matches!(bytes[0], b'0'..=b'3')
+        && bytes
+            .iter()
+            .all(|&b| b.is_ascii_alphanumeric() || b == b'_' || b == b'$')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        let content = "ISO-10303-21;\nHEADER;\nFILE_SCHEMA(('IFC4'));\nENDSEC;\nDATA;\n\
+#1=IFCPROJECT('0YvctVUKr0kugbFTf53O9L',$,'P',$,$,$,$,$,$);\n\
+#2=IFCWALL('1YvctVUKr0kugbFTf53O9L',$,'W1',$,$,#99,$,$,$);\n\
+#3=IFCWALL('1YvctVUKr0kugbFTf53O9L',$,'W2',$,$,$,$,$,$);\n\
+#4=IFCSPACE('2YvctVUKr0kugbFTf53O9L',$,'S',$,$,$,$,$,$,$,$);\n\
+#5=IFCRELAGGREGATES('3YvctVUKr0kugbFTf53O9L',$,$,$,#1,());\n\
+#6=IFCSLAB('bad',$,$,$,$,$,$,$,$);\n\
+ENDSEC;\nEND-ISO-10303-21;\n";
+        let findings = validate(content).into_vec();
+        let on = |id: u32| -> Vec<&str> {
+            findings
+                .iter()
+                .filter(|d| d.entity_id == Some(id))
+                .map(|d| d.message.as_str())
+                .collect()
+        };
+
+        assert_eq!(on(1), Vec::<&str>::new());
+        assert_eq!(on(2), ["Reference to missing entity #99"]);
+        assert_eq!(
+            on(3),
+            ["Duplicate GlobalId '1YvctVUKr0kugbFTf53O9L', also used by #2"]
+        );
+        assert_eq!(on(4), ["Space is not part of the spatial structure"]);
+        assert_eq!(on(5), ["Missing RelatedObjects"]);
+        assert_eq!(on(6), ["Malformed GlobalId 'bad'"]);
+        assert_eq!(findings[0].line, Some(7));
+        assert!(findings.iter().all(|d| d.kind == DiagnosticKind::Structure));
+    }
+
+    #[test]
+    fn test_validate_project_count() {
+        let none = validate("DATA;\n#1=IFCWALL('1YvctVUKr0kugbFTf53O9L',$,$,$,$,$,$,$,$);\n");
+        assert_eq!(none.count(Severity::Error), 1);
+        assert_eq!(none.iter().next().unwrap().message, "No IfcProject found");
+
+        let two = validate(
+            "DATA;\n#1=IFCPROJECT('0YvctVUKr0kugbFTf53O9L',$,$,$,$,$,$,$,$);\n\
+#2=IFCPROJECT('1YvctVUKr0kugbFTf53O9L',$,$,$,$,$,$,$,$);\n",
+        );
+        let second = two.iter().next().unwrap();
+        assert_eq!(second.entity_id, Some(2));
+        assert_eq!(second.message, "More than one IfcProject");
+    }
+}
@@ -0,0 +1,804 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! IFC Writer - Round-trip export to STEP text
+//!
+//! [`IfcWriter`] records edits against the original file content and writes a
+//! patched copy. The header, comments and every untouched entity are copied
+//! byte for byte; only edited entities are serialized again, so writing
+//! without edits reproduces the input exactly.
+//!
+//! ```rust
+//! use ifc_lite_core::{IfcWriter, StepValue};
+//!
+//! let content = "DATA;\n#1=IFCWALL('guid',$,'Old',$,$,$,$,$);\n#2=IFCSLAB('guid2',$,$,$,$,$,$,$,$);\nENDSEC;\n";
+//! let mut writer = IfcWriter::new(content);
+//! writer.set_name(1, "New").unwrap();
+//! writer.delete(2).unwrap();
+//! assert_eq!(writer.write(), "DATA;\n#1=IFCWALL('guid',$,'New',$,$,$,$,$);\nENDSEC;\n");
+//! ```
+
+use crate::classify::Attribute;
+use crate::decoder::EntityDecoder;
+use crate::error::{Error, Result};
+use crate::generated::IfcType;
+use crate::parser::{parse_entity, EntityScanner, Token};
+use rustc_hash::{FxHashMap, FxHashSet};
+use std::fmt::{self, Write};
+
+/// Attribute value to write
+#[derive(Debug, Clone, PartialEq)]
+pub enum StepValue {
+    /// Entity reference: #123
+    EntityRef(u32),
+    /// Text, escaped on output
+    String(String),
+    Integer(i64),
+    Real(f64),
+    /// Enumeration value without dots: `ELEMENT` is written as `.ELEMENT.`
+    Enum(String),
+    List(Vec<StepValue>),
+    /// Typed value such as `IFCLABEL('Concrete')`
+    Typed(String, Box<StepValue>),
+    /// `$`
+    Null,
+}
+
+impl StepValue {
+    /// Typed value, e.g. `StepValue::typed("IFCLABEL", StepValue::String(..))`
+    pub fn typed(type_name: &str, value: StepValue) -> Self {
+        Self::Typed(type_name.to_ascii_uppercase(), Box::new(value))
+    }
+
+    /// `IFCLABEL('text')`
+    pub fn label(text: &str) -> Self {
+        Self::typed("IFCLABEL", Self::String(text.to_string()))
+    }
+
+    /// `IFCBOOLEAN(.T.)` or `IFCBOOLEAN(.F.)`
+    pub fn boolean(value: bool) -> Self {
+        Self::typed(
+            "IFCBOOLEAN",
+            Self::Enum(if value { "T" } else { "F" }.into()),
+        )
+    }
+
+    /// Drop references to deleted entities; true if a reference was lost
+    fn scrub(&mut self, deleted: &FxHashSet<u32>) -> bool {
+        match self {
+            Self::EntityRef(id) if deleted.contains(id) => {
+                *self = Self::Null;
+                true
+            }
+            Self::List(items) => {
+                let before = items.len();
+                items.retain(|v| !matches!(v, Self::EntityRef(id) if deleted.contains(id)));
+                let mut lost = items.len() != before;
+                for item in items.iter_mut() {
+                    lost |= item.scrub(deleted);
+                }
+                lost
+            }
+            Self::Typed(_, inner) => inner.scrub(deleted),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for StepValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::EntityRef(id) => write!(f, "#{}", id),
+            Self::String(s) => write_string(f, s),
+            Self::Integer(i) => write!(f, "{}", i),
+            Self::Real(r) => f.write_str(&format_step_real(*r)),
+            Self::Enum(e) => write!(f, ".{}.", e),
+            Self::List(items) => {
+                f.write_char('(')?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                f.write_char(')')
+            }
+            Self::Typed(name, inner) => write!(f, "{}({})", name, inner),
+            Self::Null => f.write_char('$'),
+        }
+    }
+}
+
+/// Quote text as a STEP string
+///
+/// Quotes and backslashes are doubled; characters outside printable ASCII
+/// use the `\X2\...\X0\` (UTF-16) or `\X4\...\X0\` (UTF-32) escapes.
+fn write_string(f: &mut impl Write, text: &str) -> fmt::Result {
+    f.write_char('\'')?;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => f.write_str("''")?,
+            '\\' => f.write_str("\\\\")?,
+            ' '..='~' => f.write_char(c)?,
+            _ => {
+                // Group a run of special characters into one escape
+                let mut run = vec![c];
+                while let Some(&next) = chars.peek() {
+                    if matches!(next, ' '..='~') {
+                        break;
+                    }
+                    run.push(next);
+                    chars.next();
+                }
+                if run.iter().all(|c| (*c as u32) <= 0xFFFF) {
+                    f.write_str("\\X2\\")?;
+                    for c in run {
+                        write!(f, "{:04X}", c as u32)?;
+                    }
+                } else {
+                    f.write_str("\\X4\\")?;
+                    for c in run {
+                        write!(f, "{:08X}", c as u32)?;
+                    }
+                }
+                f.write_str("\\X0\\")?;
+            }
+        }
+    }
+    f.write_char('\'')
+}
+
+/// Format a REAL so STEP readers accept it: always with a decimal point
+/// (`3000.`, `0.25`, `1.5E-10`)
+pub fn format_step_real(value: f64) -> String {
+    if !value.is_finite() {
+        return "0.".to_string();
+    }
+    let text = format!("{:?}", value);
+    let (mantissa, exponent) = match text.split_once('e') {
+        Some((m, e)) => (m, Some(e)),
+        None => (text.as_str(), None),
+    };
+    let mut out = mantissa.strip_suffix(".0").unwrap_or(mantissa).to_string();
+    if !out.contains('.') {
+        out.push('.');
+    }
+    if let Some(exponent) = exponent {
+        out.push('E');
+        out.push_str(exponent);
+    }
+    out
+}
+
+/// Write a parsed token back; strings are still in their encoded form
+fn write_token(out: &mut String, token: &Token) {
+    match token {
+        Token::EntityRef(id) => {
+            let _ = write!(out, "#{}", id);
+        }
+        Token::String(s) => {
+            out.push('\'');
+            out.push_str(s);
+            out.push('\'');
+        }
+        Token::Integer(i) => {
+            let _ = write!(out, "{}", i);
+        }
+        Token::Float(f) => out.push_str(&format_step_real(*f)),
+        Token::Enum(e) => {
+            let _ = write!(out, ".{}.", e);
+        }
+        Token::List(items) => write_tokens(out, items),
+        Token::TypedValue(name, args) => {
+            out.push_str(name);
+            write_tokens(out, args);
+        }
+        Token::Null => out.push('$'),
+        Token::Derived => out.push('*'),
+    }
+}
+
+fn write_tokens(out: &mut String, tokens: &[Token]) {
+    out.push('(');
+    for (i, token) in tokens.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        write_token(out, token);
+    }
+    out.push(')');
+}
+
+/// Token counterpart of [`StepValue::scrub`]
+fn scrub_token(token: &mut Token, deleted: &FxHashSet<u32>) -> bool {
+    match token {
+        Token::EntityRef(id) if deleted.contains(id) => {
+            *token = Token::Null;
+            true
+        }
+        Token::List(items) | Token::TypedValue(_, items) => {
+            let before = items.len();
+            items.retain(|t| !matches!(t, Token::EntityRef(id) if deleted.contains(id)));
+            let mut lost = items.len() != before;
+            for item in items.iter_mut() {
+                lost |= scrub_token(item, deleted);
+            }
+            lost
+        }
+        _ => false,
+    }
+}
+
+/// Attribute of an entity being rewritten
+enum Attribute<'a> {
+    Original(Token<'a>),
+    Edited(StepValue),
+}
+
+impl Attribute<'_> {
+    /// Drop references to deleted entities; true if that left the attribute
+    /// `$` or an empty list
+    fn scrub(&mut self, deleted: &FxHashSet<u32>) -> bool {
+        let lost = match self {
+            Self::Original(token) => scrub_token(token, deleted),
+            Self::Edited(value) => value.scrub(deleted),
+        };
+        lost && match self {
+            Self::Original(Token::Null) | Self::Edited(StepValue::Null) => true,
+            Self::Original(Token::List(items)) => items.is_empty(),
+            Self::Edited(StepValue::List(items)) => items.is_empty(),
+            _ => false,
+        }
+    }
+}
+
+/// Position of an entity instance in the content
+#[derive(Debug, Clone, Copy)]
+struct Span<'a> {
+    type_name: &'a str,
+    start: usize,
+    end: usize,
+}
+
+/// Edits on top of IFC content, written back as STEP text
+///
+/// Deleting an entity also removes references to it: they are dropped from
+/// lists and set to `$` elsewhere. Relationships (`IFCREL*`) that lose a
+/// referenced entity this way, or end up with an empty list, are deleted in
+/// turn, so no dangling references or empty relationships are written.
+pub struct IfcWriter<'a> {
+    content: &'a str,
+    /// Entity instances in file order
+    order: Vec<u32>,
+    spans: FxHashMap<u32, Span<'a>>,
+    /// Key: entity ID, Value: replaced attributes by index
+    edits: FxHashMap<u32, FxHashMap<usize, StepValue>>,
+    deleted: FxHashSet<u32>,
+    /// New entities as STEP lines, written after the last existing one
+    added: Vec<String>,
+    next_id: u32,
+}
+
+impl<'a> IfcWriter<'a> {
+    pub fn new(content: &'a str) -> Self {
+        let mut scanner = EntityScanner::new(content);
+        let mut order = Vec::new();
+        let mut spans = FxHashMap::default();
+        while let Some((id, type_name, start, end)) = scanner.next_entity() {
+            order.push(id);
+            spans.insert(
+                id,
+                Span {
+                    type_name,
+                    start,
+                    end,
+                },
+            );
+        }
+        let next_id = order.iter().max().map_or(1, |id| id + 1);
+        Self {
+            content,
+            order,
+            spans,
+            edits: FxHashMap::default(),
+            deleted: FxHashSet::default(),
+            added: Vec::new(),
+            next_id,
+        }
+    }
+
+    /// Whether an entity exists and was not deleted
+    pub fn contains(&self, id: u32) -> bool {
+        self.spans.contains_key(&id) && !self.deleted.contains(&id)
+    }
+
+    /// Whether any edit, addition or deletion was recorded
+    pub fn is_modified(&self) -> bool {
+        !self.edits.is_empty() || !self.deleted.is_empty() || !self.added.is_empty()
+    }
+
+    /// Replace the attribute at `index` (0-based, in schema order)
+    pub fn set_attribute(&mut self, id: u32, index: usize, value: StepValue) -> Result<()> {
+        let (_, _, attributes) = self.parse(id)?;
+        if index >= attributes.len() {
+            return Err(Error::InvalidAttribute { entity: id, index });
+        }
+        self.edits.entry(id).or_default().insert(index, value);
+        Ok(())
+    }
+
+    /// Rename an IfcRoot entity (objects, types, property sets, ...)
+    pub fn set_name(&mut self, id: u32, name: &str) -> Result<()> {
+        // IfcRoot: (GlobalId, OwnerHistory, Name, Description, ...)
+        self.set_attribute(id, 2, StepValue::String(name.to_string()))
+    }
+
+    /// Replace the NominalValue of an IfcPropertySingleValue
+    pub fn set_property_value(&mut self, property_id: u32, value: StepValue) -> Result<()> {
+        let span = self.span_of(property_id)?;
+        if !span
+            .type_name
+            .eq_ignore_ascii_case("IFCPROPERTYSINGLEVALUE")
+        {
+            return Err(Error::unexpected(
+                span.start,
+                "IFCPROPERTYSINGLEVALUE",
+                span.type_name,
+            ));
+        }
+        // IfcPropertySingleValue: (Name, Description, NominalValue, Unit)
+        self.set_attribute(property_id, 2, value)
+    }
+
+    /// Find the IfcPropertySingleValue `pset`.`property` of an object
+    ///
+    /// Names compare case-insensitively.
+    pub fn find_property(&self, object_id: u32, pset: &str, property: &str) -> Option<u32> {
+        let mut decoder = EntityDecoder::new(self.content);
+        for &rel_id in &self.order {
+            if !self.spans[&rel_id]
+                .type_name
+                .eq_ignore_ascii_case("IFCRELDEFINESBYPROPERTIES")
+            {
+                continue;
+            }
+            let Ok(rel) = decoder.decode_by_id(rel_id) else {
+                continue;
+            };
+            let related = rel
+                .attribute_list(Attribute::RelatedObjects)
+                .unwrap_or_default();
+            if !related.iter().any(|r| r.as_entity_ref() == Some(object_id)) {
+                continue;
+            }
+            let Some(set) = rel
+                .attribute_ref(Attribute::RelatingPropertyDefinition)
+                .and_then(|id| decoder.decode_by_id(id).ok())
+            else {
+                continue;
+            };
+            if !set.name().is_some_and(|n| n.eq_ignore_ascii_case(pset)) {
+                continue;
+            }
+            for prop_id in set
+                .attribute_list(Attribute::HasProperties)
+                .unwrap_or_default()
+                .iter()
+                .filter_map(|p| p.as_entity_ref())
+            {
+                let Ok(prop) = decoder.decode_by_id(prop_id) else {
+                    continue;
+                };
+                if prop
+                    .get_string(0)
+                    .is_some_and(|n| n.eq_ignore_ascii_case(property))
+                {
+                    return Some(prop_id);
+                }
+            }
+        }
+        None
+    }
+
+    /// Append a new entity and return its ID
+    ///
+    /// New entities are written as given and cannot be edited or deleted
+    /// afterwards.
+    pub fn add_entity(&mut self, type_name: &str, attributes: Vec<StepValue>) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.added.push(format!(
+            "#{}={}{};",
+            id,
+            type_name.to_ascii_uppercase(),
+            StepValue::List(attributes)
+        ));
+        id
+    }
+
+    /// Attach a new IfcPropertySet with single values to an object
+    ///
+    /// Adds the properties, the set and an IfcRelDefinesByProperties sharing
+    /// the object's OwnerHistory; returns the ID of the set. GlobalIds are
+    /// derived from the object, set name and new IDs, so applying the same
+    /// edits to the same file gives the same output.
+    pub fn add_property_set(
+        &mut self,
+        object_id: u32,
+        name: &str,
+        properties: Vec<(String, StepValue)>,
+    ) -> Result<u32> {
+        let (_, _, object) = self.parse(object_id)?;
+        if properties.is_empty() {
+            return Err(Error::change(format!(
+                "Property set {} has no properties",
+                name
+            )));
+        }
+        // IfcRoot: (GlobalId, OwnerHistory, ...)
+        let owner_history = match object.get(1) {
+            Some(Token::EntityRef(id)) => StepValue::EntityRef(*id),
+            _ => StepValue::Null,
+        };
+
+        let property_ids = properties
+            .into_iter()
+            .map(|(property, value)| {
+                self.add_entity(
+                    "IFCPROPERTYSINGLEVALUE",
+                    vec![
+                        StepValue::String(property),
+                        StepValue::Null,
+                        value,
+                        StepValue::Null,
+                    ],
+                )
+            })
+            .collect::<Vec<_>>();
+        let pset_guid = self.guid_for(object_id, name);
+        let pset_id = self.add_entity(
+            "IFCPROPERTYSET",
+            vec![
+                pset_guid,
+                owner_history.clone(),
+                StepValue::String(name.to_string()),
+                StepValue::Null,
+                StepValue::List(property_ids.into_iter().map(StepValue::EntityRef).collect()),
+            ],
+        );
+        let rel_guid = self.guid_for(object_id, name);
+        self.add_entity(
+            "IFCRELDEFINESBYPROPERTIES",
+            vec![
+                rel_guid,
+                owner_history,
+                StepValue::Null,
+                StepValue::Null,
+                StepValue::List(vec![StepValue::EntityRef(object_id)]),
+                StepValue::EntityRef(pset_id),
+            ],
+        );
+        Ok(pset_id)
+    }
+
+    /// Deterministic GlobalId for the next new entity
+    fn guid_for(&self, object_id: u32, name: &str) -> StepValue {
+        let seed = format!("{}:{}:{}", object_id, name, self.next_id);
+        StepValue::String(ifc_guid(xxhash_rust::xxh3::xxh3_128(seed.as_bytes())))
+    }
+
+    /// Delete an entity (see the type docs for what happens to references)
+    pub fn delete(&mut self, id: u32) -> Result<()> {
+        self.span_of(id)?;
+        self.edits.remove(&id);
+        self.deleted.insert(id);
+        Ok(())
+    }
+
+    /// The patched STEP text
+    pub fn write(&self) -> String {
+        let deleted = self.cascade_deletions();
+        let mut out = String::with_capacity(self.content.len());
+        let mut cursor = 0;
+        for id in &self.order {
+            let span = self.spans[id];
+            out.push_str(&self.content[cursor..span.start]);
+            cursor = span.end;
+            if deleted.contains(id) {
+                // Take the line break along
+                let rest = &self.content[cursor..];
+                let trimmed = rest.trim_start_matches([' ', '\t']);
+                let trimmed = trimmed
+                    .strip_prefix("\r\n")
+                    .or_else(|| trimmed.strip_prefix('\n'))
+                    .unwrap_or(trimmed);
+                cursor += rest.len() - trimmed.len();
+                continue;
+            }
+            match self.rewrite(*id, &deleted) {
+                Some((attributes, _)) => self.write_entity(&mut out, *id, &attributes),
+                None => out.push_str(&self.content[span.start..span.end]),
+            }
+        }
+        if !self.added.is_empty() {
+            // After the last entity, or right after `DATA;` in an empty file
+            let insert_at = self
+                .order
+                .last()
+                .map(|id| self.spans[id].end)
+                .or_else(|| self.content.find("DATA;").map(|i| i + 5))
+                .unwrap_or(self.content.len())
+                .max(cursor);
+            out.push_str(&self.content[cursor..insert_at]);
+            cursor = insert_at;
+            let newline = if self.content.contains("\r\n") {
+                "\r\n"
+            } else {
+                "\n"
+            };
+            for line in &self.added {
+                if !out.ends_with('\n') {
+                    out.push_str(newline);
+                }
+                out.push_str(line);
+            }
+            if !self.content[cursor..].starts_with(['\r', '\n']) {
+                out.push_str(newline);
+            }
+        }
+        out.push_str(&self.content[cursor..]);
+        out
+    }
+
+    /// Attribute of an existing entity as parsed from the file
+    pub(crate) fn original_attribute(&self, id: u32, index: usize) -> Option<Token<'a>> {
+        let (_, _, mut attributes) = self.parse(id).ok()?;
+        (index < attributes.len()).then(|| attributes.swap_remove(index))
+    }
+
+    fn span_of(&self, id: u32) -> Result<Span<'a>> {
+        match self.spans.get(&id) {
+            Some(span) if !self.deleted.contains(&id) => Ok(*span),
+            _ => Err(Error::InvalidEntityRef(id)),
+        }
+    }
+
+    fn parse(&self, id: u32) -> Result<(u32, IfcType, Vec<Token<'a>>)> {
+        let span = self.span_of(id)?;
+        parse_entity(&self.content[span.start..span.end])
+    }
+
+    /// Attributes of an entity that has to be written again, with whether
+    /// scrubbing broke one of them; `None` if the original text can be copied
+    fn rewrite(&self, id: u32, deleted: &FxHashSet<u32>) -> Option<(Vec<Attribute<'a>>, bool)> {
+        let edits = self.edits.get(&id);
+        let span = self.spans[&id];
+        let raw = &self.content[span.start..span.end];
+        let touches_deleted = !deleted.is_empty() && references_any(raw, deleted);
+        if edits.is_none() && !touches_deleted {
+            return None;
+        }
+        let (_, _, tokens) = parse_entity(raw).ok()?;
+        let mut attributes: Vec<Attribute> = tokens
+            .into_iter()
+            .enumerate()
+            .map(|(i, token)| match edits.and_then(|e| e.get(&i)) {
+                Some(value) => Attribute::Edited(value.clone()),
+                None => Attribute::Original(token),
+            })
+            .collect();
+        let mut broken = false;
+        if touches_deleted {
+            for attribute in &mut attributes {
+                broken |= attribute.scrub(deleted);
+            }
+        }
+        Some((attributes, broken))
+    }
+
+    /// Deleted entities plus the relationships their deletion breaks
+    fn cascade_deletions(&self) -> FxHashSet<u32> {
+        let mut deleted = self.deleted.clone();
+        loop {
+            let broken: Vec<u32> = self
+                .order
+                .iter()
+                .filter(|id| !deleted.contains(*id))
+                .filter(|id| {
+                    let type_name = self.spans[*id].type_name;
+                    type_name.len() > 6 && type_name[..6].eq_ignore_ascii_case("IFCREL")
+                })
+                .filter(|id| {
+                    self.rewrite(**id, &deleted)
+                        .is_some_and(|(_, broken)| broken)
+                })
+                .copied()
+                .collect();
+            if broken.is_empty() {
+                return deleted;
+            }
+            deleted.extend(broken);
+        }
+    }
+
+    fn write_entity(&self, out: &mut String, id: u32, attributes: &[Attribute]) {
+        let _ = write!(out, "#{}={}(", id, self.spans[&id].type_name);
+        for (i, attribute) in attributes.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            match attribute {
+                Attribute::Original(token) => write_token(out, token),
+                Attribute::Edited(value) => {
+                    let _ = write!(out, "{}", value);
+                }
+            }
+        }
+        out.push_str(");");
+    }
+}
+
+/// Encode 128 bits as a 22 character IFC GlobalId
+pub fn ifc_guid(bits: u128) -> String {
+    const ALPHABET: &[u8; 64] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz_$";
+    // 2 bits in the first character, 6 in each of the other 21
+    (0..22)
+        .map(|i| {
+            let shift = 6 * (21 - i);
+            ALPHABET[((bits >> shift) & 0x3f) as usize] as char
+        })
+        .collect()
+}
+
+/// Whether raw entity text references one of `ids` outside of strings
+fn references_any(raw: &str, ids: &FxHashSet<u32>) -> bool {
+    let bytes = raw.as_bytes();
+    // Skip the entity's own `#id=`
+    let mut i = bytes.iter().position(|&b| b == b'=').unwrap_or(0);
+    let mut in_string = false;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\'' => in_string = !in_string,
+            b'#' if !in_string => {
+                let digits = bytes[i + 1..]
+                    .iter()
+                    .take_while(|b| b.is_ascii_digit())
+                    .count();
+                if let Ok(id) = raw[i + 1..i + 1 + digits].parse::<u32>() {
+                    if ids.contains(&id) {
+                        return true;
+                    }
+                }
+                i += digits;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODEL: &str = "ISO-10303-21;
+HEADER;
+FILE_NAME('test.ifc','',(''),(''),'','','');
+ENDSEC;
+DATA;
+/* walls */
+#1=IFCWALL('w1',$,'Wall A',$,$,$,$,$);
+#2=IFCWALL('w2',$,'Wall B',$,$,$,$,$);
+#3=IFCBUILDINGSTOREY('s',$,'Level 0',$,$,$,$,$,.ELEMENT.,0.);
+#4=IFCRELCONTAINEDINSPATIALSTRUCTURE('r1',$,$,$,(#1,#2),#3);
+#5=IFCPROPERTYSINGLEVALUE('FireRating',$,IFCLABEL('F30'),$);
+#6=IFCPROPERTYSET('p',$,'Pset_WallCommon',$,(#5));
+#7=IFCRELDEFINESBYPROPERTIES('r2',$,$,$,(#1),#6);
+ENDSEC;
+END-ISO-10303-21;
+";
+
+    #[test]
+    fn test_unmodified_round_trip() {
+        let writer = IfcWriter::new(MODEL);
+        assert!(!writer.is_modified());
+        assert_eq!(writer.write(), MODEL);
+    }
+
+    #[test]
+    fn test_attribute_edits() {
+        let mut writer = IfcWriter::new(MODEL);
+        writer.set_name(1, "Wand 'Süd'").unwrap();
+        writer.set_attribute(3, 9, StepValue::Real(3000.0)).unwrap();
+        assert!(matches!(
+            writer.set_attribute(1, 8, StepValue::Null),
+            Err(Error::InvalidAttribute {
+                entity: 1,
+                index: 8
+            })
+        ));
+        assert!(matches!(
+            writer.set_name(99, "x"),
+            Err(Error::InvalidEntityRef(99))
+        ));
+
+        let out = writer.write();
+        assert!(out.contains("#1=IFCWALL('w1',$,'Wand ''S\\X2\\00FC\\X0\\d''',$,$,$,$,$);\n"));
+        assert!(out.contains("#3=IFCBUILDINGSTOREY('s',$,'Level 0',$,$,$,$,$,.ELEMENT.,3000.);\n"));
+        // Everything else is untouched
+        assert!(out.contains("/* walls */\n#1="));
+        assert!(out.contains("#2=IFCWALL('w2',$,'Wall B',$,$,$,$,$);\n"));
+        assert!(parse_entity(out.lines().find(|l| l.starts_with("#1=")).unwrap()).is_ok());
+    }
+
+    #[test]
+    fn test_property_value_edit() {
+        let mut writer = IfcWriter::new(MODEL);
+        let property = writer
+            .find_property(1, "pset_wallcommon", "FireRating")
+            .unwrap();
+        assert_eq!(property, 5);
+        assert_eq!(
+            writer.find_property(2, "Pset_WallCommon", "FireRating"),
+            None
+        );
+
+        writer
+            .set_property_value(property, StepValue::label("F90"))
+            .unwrap();
+        assert!(writer.set_property_value(6, StepValue::Null).is_err());
+        assert!(writer
+            .write()
+            .contains("#5=IFCPROPERTYSINGLEVALUE('FireRating',$,IFCLABEL('F90'),$);"));
+    }
+
+    #[test]
+    fn test_delete_scrubs_references() {
+        let mut writer = IfcWriter::new(MODEL);
+        writer.delete(1).unwrap();
+        assert!(!writer.contains(1));
+        assert!(writer.delete(1).is_err());
+
+        let out = writer.write();
+        assert!(!out.contains("#1="));
+        assert!(out.contains("#4=IFCRELCONTAINEDINSPATIALSTRUCTURE('r1',$,$,$,(#2),#3);\n"));
+        // The property relationship only pointed at the deleted wall
+        assert!(!out.contains("#7="));
+        assert!(out.contains("#6=IFCPROPERTYSET("));
+        assert!(out.ends_with(
+            "#6=IFCPROPERTYSET('p',$,'Pset_WallCommon',$,(#5));\nENDSEC;\nEND-ISO-10303-21;\n"
+        ));
+
+        // Deleting the storey breaks the containment relationship
+        writer.delete(3).unwrap();
+        assert!(!writer.write().contains("#4="));
+    }
+
+    #[test]
+    fn test_format_step_real() {
+        assert_eq!(format_step_real(3000.0), "3000.");
+        assert_eq!(format_step_real(-0.25), "-0.25");
+        assert_eq!(format_step_real(1.5e-10), "1.5E-10");
+        assert_eq!(format_step_real(1e20), "1.E20");
+    }
+
+    #[test]
+    fn test_test_model_round_trip() {
+        let content = std::fs::read_to_string("../../tests/models/test.ifc").unwrap();
+        let mut writer = IfcWriter::new(&content);
+        assert_eq!(writer.write(), content);
+
+        writer.set_name(100, "Renamed").unwrap();
+        let out = writer.write();
+        let mut scanner = EntityScanner::new(&out);
+        let mut count = 0;
+        while scanner.next_entity().is_some() {
+            count += 1;
+        }
+        assert_eq!(count, writer.order.len());
+        assert!(out.contains("'Renamed'"));
+    }
+}
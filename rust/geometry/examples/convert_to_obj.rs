@@ -0,0 +1,77 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Convert the geometry of an IFC file to Wavefront OBJ
+//!
+//! Usage: cargo run -p ifc-lite-geometry --example convert_to_obj [file.ifc] [out.obj]
+//! Without arguments the embedded sample model is written to stdout.
+
+use ifc_lite_core::{build_entity_index, has_geometry_by_name, EntityDecoder, EntityScanner};
+use ifc_lite_geometry::{calculate_normals, GeometryRouter};
+use std::fmt::Write as _;
+use std::fs;
+
+const SAMPLE: &str = include_str!("data/sample.ifc");
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let content = match args.next() {
+        Some(path) => fs::read_to_string(&path).expect("Failed to read IFC file"),
+        None => SAMPLE.to_string(),
+    };
+    let output = args.next();
+
+    let mut decoder = EntityDecoder::with_index(&content, build_entity_index(&content));
+    let router = GeometryRouter::with_units(&content, &mut decoder);
+
+    let mut obj = String::from("# Generated by ifc-lite\n");
+    let mut vertex_offset = 1; // OBJ indices are 1-based
+    let mut objects = 0;
+
+    let mut scanner = EntityScanner::new(&content);
+    while let Some((id, type_name, start, end)) = scanner.next_entity() {
+        if !has_geometry_by_name(type_name) {
+            continue;
+        }
+        let Ok(entity) = decoder.decode_at(start, end) else {
+            continue;
+        };
+        let Ok(mut mesh) = router.process_element(&entity, &mut decoder) else {
+            continue;
+        };
+        if mesh.is_empty() {
+            continue;
+        }
+        if mesh.normals.is_empty() {
+            calculate_normals(&mut mesh);
+        }
+
+        let name = entity.get_string(2).unwrap_or(type_name);
+        let _ = writeln!(obj, "o {}_{}", name.replace(' ', "_"), id);
+        for p in mesh.positions.chunks_exact(3) {
+            let _ = writeln!(obj, "v {} {} {}", p[0], p[1], p[2]);
+        }
+        for n in mesh.normals.chunks_exact(3) {
+            let _ = writeln!(obj, "vn {} {} {}", n[0], n[1], n[2]);
+        }
+        for t in mesh.indices.chunks_exact(3) {
+            let (a, b, c) = (
+                t[0] as usize + vertex_offset,
+                t[1] as usize + vertex_offset,
+                t[2] as usize + vertex_offset,
+            );
+            let _ = writeln!(obj, "f {a}//{a} {b}//{b} {c}//{c}");
+        }
+        vertex_offset += mesh.vertex_count();
+        objects += 1;
+    }
+
+    match output {
+        Some(path) => {
+            fs::write(&path, obj).expect("Failed to write OBJ file");
+            eprintln!("Wrote {} objects to {}", objects, path);
+        }
+        None => print!("{}", obj),
+    }
+}
@@ -0,0 +1,139 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Render an IFC model to an image without a GPU or window
+//!
+//! Software rasterizer with an isometric orthographic camera, z-buffer and
+//! simple directional shading. Writes a binary PPM image.
+//!
+//! Usage: cargo run -p ifc-lite-geometry --example headless_render [file.ifc] [out.ppm]
+
+use ifc_lite_core::{build_entity_index, has_geometry_by_name, EntityDecoder, EntityScanner};
+use ifc_lite_geometry::{GeometryRouter, Mesh, Vector3};
+use std::fs;
+
+const SAMPLE: &str = include_str!("data/sample.ifc");
+const WIDTH: usize = 800;
+const HEIGHT: usize = 600;
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let content = match args.next() {
+        Some(path) => fs::read_to_string(&path).expect("Failed to read IFC file"),
+        None => SAMPLE.to_string(),
+    };
+    let output = args.next().unwrap_or_else(|| "render.ppm".to_string());
+
+    // Collect all element geometry into one mesh
+    let mut decoder = EntityDecoder::with_index(&content, build_entity_index(&content));
+    let router = GeometryRouter::with_units(&content, &mut decoder);
+    let mut scene = Mesh::new();
+    let mut scanner = EntityScanner::new(&content);
+    while let Some((_id, type_name, start, end)) = scanner.next_entity() {
+        if !has_geometry_by_name(type_name) {
+            continue;
+        }
+        if let Ok(entity) = decoder.decode_at(start, end) {
+            if let Ok(mesh) = router.process_element(&entity, &mut decoder) {
+                scene.merge(&mesh);
+            }
+        }
+    }
+
+    if scene.is_empty() {
+        eprintln!("No geometry found");
+        return;
+    }
+
+    // Isometric camera basis (IFC is Z-up)
+    let forward = Vector3::new(-1.0f32, 1.0, -1.0).normalize();
+    let right = forward.cross(&Vector3::z()).normalize();
+    let up = right.cross(&forward);
+    let light = -forward;
+
+    let project = |p: &[f32]| {
+        let v = Vector3::new(p[0], p[1], p[2]);
+        (v.dot(&right), v.dot(&up), v.dot(&forward))
+    };
+
+    // Fit view to projected bounds
+    let (mut min_x, mut min_y, mut max_x, mut max_y) = (f32::MAX, f32::MAX, f32::MIN, f32::MIN);
+    for p in scene.positions.chunks_exact(3) {
+        let (x, y, _) = project(p);
+        min_x = min_x.min(x);
+        max_x = max_x.max(x);
+        min_y = min_y.min(y);
+        max_y = max_y.max(y);
+    }
+    let scale = 0.9 * (WIDTH as f32 / (max_x - min_x)).min(HEIGHT as f32 / (max_y - min_y));
+    let (cx, cy) = ((min_x + max_x) / 2.0, (min_y + max_y) / 2.0);
+    let to_screen = |p: &[f32]| {
+        let (x, y, z) = project(p);
+        (
+            WIDTH as f32 / 2.0 + (x - cx) * scale,
+            HEIGHT as f32 / 2.0 - (y - cy) * scale,
+            z,
+        )
+    };
+
+    let mut color = vec![[32u8, 32, 40]; WIDTH * HEIGHT];
+    let mut depth = vec![f32::MAX; WIDTH * HEIGHT];
+
+    for tri in scene.indices.chunks_exact(3) {
+        let vert = |i: u32| &scene.positions[i as usize * 3..i as usize * 3 + 3];
+        let (a, b, c) = (vert(tri[0]), vert(tri[1]), vert(tri[2]));
+
+        // Flat shading from the face normal
+        let pa = Vector3::new(a[0], a[1], a[2]);
+        let normal =
+            (Vector3::new(b[0], b[1], b[2]) - pa).cross(&(Vector3::new(c[0], c[1], c[2]) - pa));
+        let Some(normal) = normal.try_normalize(1e-12) else {
+            continue;
+        };
+        let shade = (0.25 + 0.75 * normal.dot(&light).abs()).min(1.0);
+        let rgb = [
+            (200.0 * shade) as u8,
+            (190.0 * shade) as u8,
+            (170.0 * shade) as u8,
+        ];
+
+        let (s0, s1, s2) = (to_screen(a), to_screen(b), to_screen(c));
+        let area = (s1.0 - s0.0) * (s2.1 - s0.1) - (s2.0 - s0.0) * (s1.1 - s0.1);
+        if area.abs() < 1e-6 {
+            continue;
+        }
+
+        let x0 = s0.0.min(s1.0).min(s2.0).floor().max(0.0) as usize;
+        let x1 = (s0.0.max(s1.0).max(s2.0).ceil() as usize).min(WIDTH - 1);
+        let y0 = s0.1.min(s1.1).min(s2.1).floor().max(0.0) as usize;
+        let y1 = (s0.1.max(s1.1).max(s2.1).ceil() as usize).min(HEIGHT - 1);
+
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+                let w0 = ((s1.0 - px) * (s2.1 - py) - (s2.0 - px) * (s1.1 - py)) / area;
+                let w1 = ((s2.0 - px) * (s0.1 - py) - (s0.0 - px) * (s2.1 - py)) / area;
+                let w2 = 1.0 - w0 - w1;
+                if w0 < 0.0 || w1 < 0.0 || w2 < 0.0 {
+                    continue;
+                }
+                let z = w0 * s0.2 + w1 * s1.2 + w2 * s2.2;
+                let idx = y * WIDTH + x;
+                if z < depth[idx] {
+                    depth[idx] = z;
+                    color[idx] = rgb;
+                }
+            }
+        }
+    }
+
+    let mut ppm = format!("P6\n{} {}\n255\n", WIDTH, HEIGHT).into_bytes();
+    ppm.extend(color.iter().flatten());
+    fs::write(&output, ppm).expect("Failed to write image");
+    println!(
+        "Rendered {} triangles to {}",
+        scene.triangle_count(),
+        output
+    );
+}
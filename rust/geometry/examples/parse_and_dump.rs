@@ -0,0 +1,62 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Parse an IFC file and dump a summary of its contents
+//!
+//! Usage: cargo run -p ifc-lite-geometry --example parse_and_dump [file.ifc]
+//! Without an argument the embedded sample model is used.
+
+use ifc_lite_core::{build_entity_index, ContentHash, EntityDecoder, EntityScanner};
+use std::fs;
+
+const SAMPLE: &str = include_str!("data/sample.ifc");
+
+fn main() {
+    let content = match std::env::args().nth(1) {
+        Some(path) => fs::read_to_string(&path).expect("Failed to read IFC file"),
+        None => SAMPLE.to_string(),
+    };
+
+    println!("Size:         {} bytes", content.len());
+    println!("Content hash: {}", ContentHash::of_str(&content));
+
+    // Entity counts by type, most frequent first
+    let mut counts: Vec<(String, usize)> = EntityScanner::new(&content)
+        .count_by_type()
+        .into_iter()
+        .collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+    println!(
+        "Entities:     {}",
+        counts.iter().map(|(_, n)| n).sum::<usize>()
+    );
+    for (type_name, count) in counts.iter().take(20) {
+        println!("  {:>8}  {}", count, type_name);
+    }
+
+    // Project units and storeys
+    let mut decoder = EntityDecoder::with_index(&content, build_entity_index(&content));
+    let mut scanner = EntityScanner::new(&content);
+    while let Some((id, type_name, _, _)) = scanner.next_entity() {
+        match type_name {
+            "IFCPROJECT" => {
+                if let Ok(scale) = decoder.extract_unit_scale(id) {
+                    println!("Length unit:  {} m", scale);
+                }
+            }
+            "IFCBUILDINGSTOREY" => {
+                if let Ok(storey) = decoder.decode_by_id(id) {
+                    println!(
+                        "Storey #{}:   {} (elevation {})",
+                        id,
+                        storey.get_string(2).unwrap_or("<unnamed>"),
+                        storey.get_float(9).unwrap_or(0.0)
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,716 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! IFC4.3 alignment curves
+//!
+//! Horizontal alignments (IfcCompositeCurve of IfcCurveSegment), their
+//! vertical profile (IfcGradientCurve) and IfcSegmentedReferenceCurve are
+//! sampled into a polyline with stationing. Positions along the alignment -
+//! IfcLinearPlacement, cross sections of IfcSectionedSolidHorizontal - are
+//! then looked up by distance.
+//!
+//! Supported segment parent curves are IfcLine, IfcCircle, IfcClothoid and
+//! IfcPolynomialCurve; other spirals are approximated by their chord. Cant
+//! (superelevation) of segmented reference curves is not applied.
+
+use crate::profiles::ProfileProcessor;
+use crate::{Error, Result};
+use ifc_lite_core::{AttributeValue, DecodedEntity, EntityDecoder, IfcType};
+use nalgebra::{Matrix4, Point2, Point3, Rotation2, Vector2, Vector3};
+
+/// Largest change of direction between two samples of an arc or spiral
+const MAX_ANGLE_STEP: f64 = std::f64::consts::PI / 90.0;
+/// Sample limit per curve segment
+const MAX_SEGMENT_SAMPLES: usize = 512;
+/// Samples of a polynomial segment
+const POLYNOMIAL_SAMPLES: usize = 32;
+/// Simpson intervals for the clothoid's Fresnel integrals
+const CLOTHOID_STEPS: usize = 32;
+/// Stations closer than this are merged
+const STATION_EPSILON: f64 = 1e-9;
+
+/// Curve sampled into a polyline, addressed by distance along it
+///
+/// Distances are measured along the horizontal projection, as alignment
+/// stationing is; curves without horizontal extent fall back to 3D length.
+#[derive(Debug, Clone)]
+pub struct AlignmentCurve {
+    points: Vec<Point3<f64>>,
+    stations: Vec<f64>,
+}
+
+impl AlignmentCurve {
+    /// Curve through `points`; consecutive duplicates are dropped
+    pub fn from_points(points: Vec<Point3<f64>>) -> Result<Self> {
+        let mut unique: Vec<Point3<f64>> = Vec::with_capacity(points.len());
+        for point in points {
+            if unique
+                .last()
+                .is_none_or(|last| (point - last).norm() > STATION_EPSILON)
+            {
+                unique.push(point);
+            }
+        }
+        if unique.len() < 2 {
+            return Err(Error::geometry(
+                "Alignment curve needs at least two distinct points".to_string(),
+            ));
+        }
+
+        let horizontal = |a: &Point3<f64>, b: &Point3<f64>| (b.xy() - a.xy()).norm();
+        let total: f64 = unique.windows(2).map(|w| horizontal(&w[0], &w[1])).sum();
+        let mut stations = Vec::with_capacity(unique.len());
+        let mut distance = 0.0;
+        stations.push(0.0);
+        for w in unique.windows(2) {
+            distance += if total > STATION_EPSILON {
+                horizontal(&w[0], &w[1])
+            } else {
+                (w[1] - w[0]).norm()
+            };
+            stations.push(distance);
+        }
+        Ok(Self {
+            points: unique,
+            stations,
+        })
+    }
+
+    /// Sample a directrix or alignment curve entity
+    ///
+    /// Curves other than alignment curves are sampled with `profiles`.
+    pub fn from_entity(
+        curve: &DecodedEntity,
+        decoder: &mut EntityDecoder,
+        profiles: &ProfileProcessor,
+    ) -> Result<Self> {
+        match curve.ifc_type {
+            // (Segments, SelfIntersect, BaseCurve, EndPoint)
+            IfcType::IfcGradientCurve => {
+                let base = resolve(curve, 2, decoder)?;
+                let horizontal = Self::from_entity(&base, decoder, profiles)?;
+                let profile = segment_chain(curve, decoder)?;
+                Ok(horizontal.with_profile(&profile))
+            }
+            // Cant is not applied, the base gradient curve carries the geometry
+            IfcType::IfcSegmentedReferenceCurve => {
+                let base = resolve(curve, 2, decoder)?;
+                Self::from_entity(&base, decoder, profiles)
+            }
+            IfcType::IfcCompositeCurve if has_curve_segments(curve, decoder)? => {
+                let points = segment_chain(curve, decoder)?;
+                Self::from_points(points.iter().map(|p| Point3::new(p.x, p.y, 0.0)).collect())
+            }
+            _ => Self::from_points(profiles.get_curve_points(curve, decoder)?),
+        }
+    }
+
+    /// Length along the horizontal projection
+    pub fn length(&self) -> f64 {
+        self.stations.last().copied().unwrap_or(0.0)
+    }
+
+    /// Sampled points
+    pub fn points(&self) -> &[Point3<f64>] {
+        &self.points
+    }
+
+    /// Point at `distance`, extrapolated along the end tangents outside the curve
+    pub fn point_at(&self, distance: f64) -> Point3<f64> {
+        let (i, t) = self.locate(distance);
+        self.points[i] + (self.points[i + 1] - self.points[i]) * t
+    }
+
+    /// Unit tangent at `distance`
+    pub fn tangent_at(&self, distance: f64) -> Vector3<f64> {
+        let (i, _) = self.locate(distance);
+        (self.points[i + 1] - self.points[i]).normalize()
+    }
+
+    /// Placement at `distance`, offset sideways and vertically
+    ///
+    /// X follows the curve, Z points up and Y to the left. A positive
+    /// `lateral` offset moves left, `vertical` moves up.
+    pub fn frame_at(&self, distance: f64, lateral: f64, vertical: f64) -> Matrix4<f64> {
+        let x = self.tangent_at(distance);
+        let mut left = Vector3::new(-x.y, x.x, 0.0);
+        if left.norm() < STATION_EPSILON {
+            left = Vector3::y();
+        }
+        let y = left.normalize();
+        let z = x.cross(&y).normalize();
+        let origin = self.point_at(distance) + y * lateral + Vector3::z() * vertical;
+
+        let mut frame = Matrix4::identity();
+        for (column, axis) in [x, y, z].iter().enumerate() {
+            frame[(0, column)] = axis.x;
+            frame[(1, column)] = axis.y;
+            frame[(2, column)] = axis.z;
+        }
+        frame[(0, 3)] = origin.x;
+        frame[(1, 3)] = origin.y;
+        frame[(2, 3)] = origin.z;
+        frame
+    }
+
+    /// `from`, every sample station strictly between, and `to`
+    pub fn stations_between(&self, from: f64, to: f64) -> Vec<f64> {
+        let (low, high) = if from <= to { (from, to) } else { (to, from) };
+        let mut stations = vec![from];
+        let inner = self
+            .stations
+            .iter()
+            .copied()
+            .filter(|&s| s > low + STATION_EPSILON && s < high - STATION_EPSILON);
+        if from <= to {
+            stations.extend(inner);
+        } else {
+            stations.extend(inner.rev());
+        }
+        stations.push(to);
+        stations
+    }
+
+    /// Index of the span holding `distance` and the position within it
+    fn locate(&self, distance: f64) -> (usize, f64) {
+        let last = self.stations.len() - 2;
+        let i = match self.stations.binary_search_by(|s| s.total_cmp(&distance)) {
+            Ok(i) => i.min(last),
+            Err(i) => i.saturating_sub(1).min(last),
+        };
+        let span = self.stations[i + 1] - self.stations[i];
+        let t = if span > STATION_EPSILON {
+            (distance - self.stations[i]) / span
+        } else {
+            0.0
+        };
+        (i, t)
+    }
+
+    /// Lift a horizontal curve onto a vertical profile of (distance, height) points
+    fn with_profile(&self, profile: &[Point2<f64>]) -> Self {
+        if profile.is_empty() {
+            return self.clone();
+        }
+        let height_at = |distance: f64| -> f64 {
+            let i = profile.partition_point(|p| p.x < distance);
+            if i == 0 {
+                return profile[0].y;
+            }
+            if i == profile.len() {
+                return profile[i - 1].y;
+            }
+            let (a, b) = (profile[i - 1], profile[i]);
+            let span = b.x - a.x;
+            if span > STATION_EPSILON {
+                a.y + (b.y - a.y) * (distance - a.x) / span
+            } else {
+                b.y
+            }
+        };
+
+        let mut stations: Vec<f64> = self
+            .stations
+            .iter()
+            .copied()
+            .chain(
+                profile
+                    .iter()
+                    .map(|p| p.x)
+                    .filter(|&d| d > 0.0 && d < self.length()),
+            )
+            .collect();
+        stations.sort_by(f64::total_cmp);
+        stations.dedup_by(|a, b| (*a - *b).abs() < STATION_EPSILON);
+
+        let points = stations
+            .iter()
+            .map(|&d| {
+                let p = self.point_at(d);
+                Point3::new(p.x, p.y, height_at(d))
+            })
+            .collect();
+        Self { points, stations }
+    }
+}
+
+/// Position given by IfcPointByDistanceExpression
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DistanceExpression {
+    /// Distance along the basis curve, including the longitudinal offset
+    pub distance: f64,
+    /// Offset to the left of the curve
+    pub lateral: f64,
+    /// Offset upwards
+    pub vertical: f64,
+    /// IfcCurve the distance is measured along
+    pub basis_curve: Option<u32>,
+}
+
+impl DistanceExpression {
+    /// Read an IfcAxis2PlacementLinear or an IfcPointByDistanceExpression
+    pub fn from_entity(entity: &DecodedEntity, decoder: &mut EntityDecoder) -> Result<Self> {
+        // IfcAxis2PlacementLinear: (Location, Axis, RefDirection)
+        if entity.ifc_type == IfcType::IfcAxis2PlacementLinear {
+            let location = resolve(entity, 0, decoder)?;
+            return Self::from_entity(&location, decoder);
+        }
+        if entity.ifc_type != IfcType::IfcPointByDistanceExpression {
+            return Err(Error::geometry(format!(
+                "Expected IfcPointByDistanceExpression, got {}",
+                entity.ifc_type
+            )));
+        }
+        // (DistanceAlong, OffsetLateral, OffsetVertical, OffsetLongitudinal, BasisCurve)
+        let along = entity
+            .get(0)
+            .and_then(measure)
+            .map(|(v, _)| v)
+            .unwrap_or(0.0);
+        Ok(Self {
+            distance: along + entity.get_float(3).unwrap_or(0.0),
+            lateral: entity.get_float(1).unwrap_or(0.0),
+            vertical: entity.get_float(2).unwrap_or(0.0),
+            basis_curve: entity.get_ref(4),
+        })
+    }
+}
+
+/// Resolve the entity referenced by attribute `index`
+fn resolve(
+    entity: &DecodedEntity,
+    index: usize,
+    decoder: &mut EntityDecoder,
+) -> Result<DecodedEntity> {
+    let attr = entity.get(index).ok_or_else(|| {
+        Error::geometry(format!("{} missing attribute {}", entity.ifc_type, index))
+    })?;
+    decoder.resolve_ref(attr)?.ok_or_else(|| {
+        Error::geometry(format!(
+            "{} attribute {} is not a reference",
+            entity.ifc_type, index
+        ))
+    })
+}
+
+/// Length or parameter measure, written plain or typed (`IFCLENGTHMEASURE(1.)`)
+///
+/// Returns the value and whether it is an IfcParameterValue.
+pub(crate) fn measure(value: &AttributeValue) -> Option<(f64, bool)> {
+    if let Some(v) = value.as_float() {
+        return Some((v, false));
+    }
+    let list = value.as_list()?;
+    let type_name = list.first()?.as_string()?;
+    let v = list.get(1)?.as_float()?;
+    Some((v, type_name.eq_ignore_ascii_case("IFCPARAMETERVALUE")))
+}
+
+/// Whether a composite curve is made of IFC4.3 IfcCurveSegment
+fn has_curve_segments(curve: &DecodedEntity, decoder: &mut EntityDecoder) -> Result<bool> {
+    let Some(first) = curve.get_list(0).and_then(|l| l.first()) else {
+        return Ok(false);
+    };
+    Ok(decoder
+        .resolve_ref(first)?
+        .is_some_and(|s| s.ifc_type == IfcType::IfcCurveSegment))
+}
+
+/// Sample the IfcCurveSegment list (attribute 0) of a composite curve in 2D
+fn segment_chain(curve: &DecodedEntity, decoder: &mut EntityDecoder) -> Result<Vec<Point2<f64>>> {
+    let segments = match curve.get(0) {
+        Some(attr) => decoder.resolve_ref_list(attr)?,
+        None => Vec::new(),
+    };
+    let mut points: Vec<Point2<f64>> = Vec::new();
+    for segment in segments {
+        if segment.ifc_type != IfcType::IfcCurveSegment {
+            continue;
+        }
+        for point in sample_segment(&segment, decoder)? {
+            if points
+                .last()
+                .is_none_or(|last| (point - last).norm() > STATION_EPSILON)
+            {
+                points.push(point);
+            }
+        }
+    }
+    Ok(points)
+}
+
+/// Sample one IfcCurveSegment
+///
+/// The parent curve is trimmed to [SegmentStart, SegmentStart + SegmentLength],
+/// moved so its start lies at the origin heading along +X, then positioned
+/// by the segment's Placement. A negative length runs the parent backwards.
+fn sample_segment(
+    segment: &DecodedEntity,
+    decoder: &mut EntityDecoder,
+) -> Result<Vec<Point2<f64>>> {
+    // (Transition, Placement, SegmentStart, SegmentLength, ParentCurve)
+    let placement = resolve(segment, 1, decoder)?;
+    let (location, direction) = read_placement_2d(&placement, decoder)?;
+    let parent = ParentCurve::from_entity(&resolve(segment, 4, decoder)?, decoder)?;
+    let (start, start_is_parameter) = segment.get(2).and_then(measure).unwrap_or((0.0, false));
+    let (length, length_is_parameter) = segment
+        .get(3)
+        .and_then(measure)
+        .ok_or_else(|| Error::geometry("IfcCurveSegment missing SegmentLength".to_string()))?;
+    let start = parent.to_length(start, start_is_parameter);
+    let length = parent.to_length(length, length_is_parameter);
+    if length.abs() < STATION_EPSILON {
+        return Ok(vec![location]);
+    }
+
+    let sense = length.signum();
+    let (origin, tangent) = parent.eval(start);
+    let to_segment = Rotation2::rotation_between(&(tangent * sense), &Vector2::x());
+    let to_world = Rotation2::rotation_between(&Vector2::x(), &direction);
+
+    let samples = parent.samples(start, length);
+    Ok((0..=samples)
+        .map(|i| {
+            let (point, _) = parent.eval(start + length * i as f64 / samples as f64);
+            location + to_world * (to_segment * (point - origin))
+        })
+        .collect())
+}
+
+/// Location and X direction of an IfcAxis2Placement2D, or of a 3D placement projected to XY
+fn read_placement_2d(
+    placement: &DecodedEntity,
+    decoder: &mut EntityDecoder,
+) -> Result<(Point2<f64>, Vector2<f64>)> {
+    let location = read_coordinates(&resolve(placement, 0, decoder)?);
+    let ref_index = if placement.ifc_type == IfcType::IfcAxis2Placement3D {
+        2
+    } else {
+        1
+    };
+    let direction = match placement.get(ref_index) {
+        Some(attr) if !attr.is_null() => decoder
+            .resolve_ref(attr)?
+            .map(|d| read_coordinates(&d))
+            .map(|d| Vector2::new(d[0], d[1]))
+            .filter(|d| d.norm() > STATION_EPSILON)
+            .unwrap_or_else(Vector2::x),
+        _ => Vector2::x(),
+    };
+    Ok((Point2::new(location[0], location[1]), direction.normalize()))
+}
+
+/// Coordinates of an IfcCartesianPoint or ratios of an IfcDirection, padded to 3
+fn read_coordinates(entity: &DecodedEntity) -> [f64; 3] {
+    let mut coords = [0.0; 3];
+    if let Some(list) = entity.get_list(0) {
+        for (coord, value) in coords.iter_mut().zip(list) {
+            *coord = value.as_float().unwrap_or(0.0);
+        }
+    }
+    coords
+}
+
+/// Parent curve of a segment in its own position
+enum ParentCurve {
+    Line {
+        origin: Point2<f64>,
+        direction: Vector2<f64>,
+        magnitude: f64,
+    },
+    Circle {
+        center: Point2<f64>,
+        x: Vector2<f64>,
+        y: Vector2<f64>,
+        radius: f64,
+    },
+    Clothoid {
+        origin: Point2<f64>,
+        x: Vector2<f64>,
+        y: Vector2<f64>,
+        constant: f64,
+    },
+    Polynomial {
+        origin: Point2<f64>,
+        x: Vector2<f64>,
+        y: Vector2<f64>,
+        coefficients_x: Vec<f64>,
+        coefficients_y: Vec<f64>,
+    },
+}
+
+impl ParentCurve {
+    fn from_entity(curve: &DecodedEntity, decoder: &mut EntityDecoder) -> Result<Self> {
+        let position =
+            |decoder: &mut EntityDecoder| -> Result<(Point2<f64>, Vector2<f64>, Vector2<f64>)> {
+                let (origin, x) = match curve.get(0) {
+                    Some(attr) if !attr.is_null() => match decoder.resolve_ref(attr)? {
+                        Some(placement) => read_placement_2d(&placement, decoder)?,
+                        None => (Point2::origin(), Vector2::x()),
+                    },
+                    _ => (Point2::origin(), Vector2::x()),
+                };
+                Ok((origin, x, Vector2::new(-x.y, x.x)))
+            };
+        let floats = |index: usize| -> Vec<f64> {
+            curve
+                .get_list(index)
+                .map(|l| l.iter().filter_map(|v| v.as_float()).collect())
+                .unwrap_or_default()
+        };
+
+        Ok(match curve.ifc_type {
+            // (Pnt, Dir) with Dir an IfcVector (Orientation, Magnitude)
+            IfcType::IfcLine => {
+                let origin = read_coordinates(&resolve(curve, 0, decoder)?);
+                let vector = resolve(curve, 1, decoder)?;
+                let orientation = read_coordinates(&resolve(&vector, 0, decoder)?);
+                let direction = Vector2::new(orientation[0], orientation[1]);
+                Self::Line {
+                    origin: Point2::new(origin[0], origin[1]),
+                    direction: if direction.norm() > STATION_EPSILON {
+                        direction.normalize()
+                    } else {
+                        Vector2::x()
+                    },
+                    magnitude: vector.get_float(1).unwrap_or(1.0),
+                }
+            }
+            // (Position, Radius)
+            IfcType::IfcCircle => {
+                let (center, x, y) = position(decoder)?;
+                let radius = curve
+                    .get_float(1)
+                    .filter(|r| *r > 0.0)
+                    .ok_or_else(|| Error::geometry("IfcCircle missing Radius".to_string()))?;
+                Self::Circle {
+                    center,
+                    x,
+                    y,
+                    radius,
+                }
+            }
+            // (Position, ClothoidConstant)
+            IfcType::IfcClothoid => {
+                let (origin, x, y) = position(decoder)?;
+                Self::Clothoid {
+                    origin,
+                    x,
+                    y,
+                    constant: curve.get_float(1).unwrap_or(0.0),
+                }
+            }
+            // (Position, CoefficientsX, CoefficientsY, CoefficientsZ)
+            IfcType::IfcPolynomialCurve => {
+                let (origin, x, y) = position(decoder)?;
+                Self::Polynomial {
+                    origin,
+                    x,
+                    y,
+                    coefficients_x: floats(1),
+                    coefficients_y: floats(2),
+                }
+            }
+            // Other spirals: straight chord, the segment placements keep the chain connected
+            _ => Self::Line {
+                origin: Point2::origin(),
+                direction: Vector2::x(),
+                magnitude: 1.0,
+            },
+        })
+    }
+
+    /// Parameter value to the curve's own length measure
+    fn to_length(&self, value: f64, is_parameter: bool) -> f64 {
+        match self {
+            Self::Line { magnitude, .. } if is_parameter => value * magnitude,
+            Self::Circle { radius, .. } if is_parameter => value * radius,
+            _ => value,
+        }
+    }
+
+    /// Point and (not normalized) tangent at `s`
+    fn eval(&self, s: f64) -> (Point2<f64>, Vector2<f64>) {
+        match self {
+            Self::Line {
+                origin, direction, ..
+            } => (origin + direction * s, *direction),
+            Self::Circle {
+                center,
+                x,
+                y,
+                radius,
+            } => {
+                let (sin, cos) = (s / radius).sin_cos();
+                (center + (x * cos + y * sin) * *radius, y * cos - x * sin)
+            }
+            Self::Clothoid {
+                origin,
+                x,
+                y,
+                constant,
+            } => {
+                let (u, v) = clothoid_point(*constant, s);
+                let (sin, cos) = clothoid_angle(*constant, s).sin_cos();
+                (origin + x * u + y * v, x * cos + y * sin)
+            }
+            Self::Polynomial {
+                origin,
+                x,
+                y,
+                coefficients_x,
+                coefficients_y,
+            } => {
+                let (u, du) = polynomial(coefficients_x, s);
+                let (v, dv) = polynomial(coefficients_y, s);
+                (origin + x * u + y * v, x * du + y * dv)
+            }
+        }
+    }
+
+    /// Number of spans to sample over [start, start + length]
+    fn samples(&self, start: f64, length: f64) -> usize {
+        let turn = match self {
+            Self::Line { .. } => return 1,
+            Self::Polynomial { .. } => return POLYNOMIAL_SAMPLES,
+            Self::Circle { radius, .. } => length.abs() / radius,
+            Self::Clothoid { constant, .. } => {
+                (clothoid_angle(*constant, start + length) - clothoid_angle(*constant, start)).abs()
+            }
+        };
+        ((turn / MAX_ANGLE_STEP).ceil() as usize).clamp(4, MAX_SEGMENT_SAMPLES)
+    }
+}
+
+/// Heading of a clothoid at arc length `s`; curvature grows as s / A²
+fn clothoid_angle(constant: f64, s: f64) -> f64 {
+    if constant == 0.0 {
+        return 0.0;
+    }
+    s * s / (2.0 * constant * constant.abs())
+}
+
+/// Clothoid point at arc length `s` by Simpson integration of the heading
+fn clothoid_point(constant: f64, s: f64) -> (f64, f64) {
+    let h = s / CLOTHOID_STEPS as f64;
+    let mut sum = (0.0, 0.0);
+    for i in 0..=CLOTHOID_STEPS {
+        let weight = if i == 0 || i == CLOTHOID_STEPS {
+            1.0
+        } else if i % 2 == 1 {
+            4.0
+        } else {
+            2.0
+        };
+        let (sin, cos) = clothoid_angle(constant, h * i as f64).sin_cos();
+        sum.0 += weight * cos;
+        sum.1 += weight * sin;
+    }
+    (sum.0 * h / 3.0, sum.1 * h / 3.0)
+}
+
+/// Polynomial value and derivative at `t`
+fn polynomial(coefficients: &[f64], t: f64) -> (f64, f64) {
+    let mut value = 0.0;
+    let mut derivative = 0.0;
+    for c in coefficients.iter().rev() {
+        derivative = derivative * t + value;
+        value = value * t + c;
+    }
+    (value, derivative)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ifc_lite_core::{build_entity_index, IfcSchema};
+
+    fn decoder(content: &str) -> EntityDecoder<'_> {
+        EntityDecoder::with_index(content, build_entity_index(content))
+    }
+
+    /// 100 m straight, then a 90° left curve of radius 50, then a 0.5% grade from 10 m
+    const ALIGNMENT: &str = "#1=IFCCARTESIANPOINT((0.,0.));\n\
+#2=IFCDIRECTION((1.,0.));\n\
+#3=IFCAXIS2PLACEMENT2D(#1,#2);\n\
+#4=IFCVECTOR(#2,1.);\n\
+#5=IFCLINE(#1,#4);\n\
+#6=IFCCURVESEGMENT(.CONTINUOUS.,#3,IFCLENGTHMEASURE(0.),IFCLENGTHMEASURE(100.),#5);\n\
+#7=IFCCARTESIANPOINT((100.,0.));\n\
+#8=IFCAXIS2PLACEMENT2D(#7,#2);\n\
+#9=IFCCIRCLE(#3,50.);\n\
+#10=IFCCURVESEGMENT(.CONTINUOUS.,#8,IFCLENGTHMEASURE(-39.26990816987241),IFCLENGTHMEASURE(78.53981633974483),#9);\n\
+#11=IFCCOMPOSITECURVE((#6,#10),.F.);\n\
+#12=IFCCARTESIANPOINT((0.,10.));\n\
+#13=IFCDIRECTION((1.,0.005));\n\
+#14=IFCAXIS2PLACEMENT2D(#12,#13);\n\
+#15=IFCCURVESEGMENT(.CONTINUOUS.,#14,IFCLENGTHMEASURE(0.),IFCLENGTHMEASURE(200.),#5);\n\
+#16=IFCGRADIENTCURVE((#15),.F.,#11,$);\n";
+
+    #[test]
+    fn test_horizontal_line_and_arc() {
+        let mut decoder = decoder(ALIGNMENT);
+        let profiles = ProfileProcessor::new(IfcSchema::new());
+        let curve = decoder.decode_by_id(11).unwrap();
+        let curve = AlignmentCurve::from_entity(&curve, &mut decoder, &profiles).unwrap();
+
+        let quarter = 100.0 + std::f64::consts::FRAC_PI_2 * 50.0;
+        assert!((curve.length() - quarter).abs() < 0.05);
+        let mid = curve.point_at(50.0);
+        assert!((mid - Point3::new(50.0, 0.0, 0.0)).norm() < 1e-9);
+
+        // The arc starts heading along +X at (100, 0) and curves left
+        let end = curve.point_at(curve.length());
+        assert!((end - Point3::new(150.0, 50.0, 0.0)).norm() < 0.05);
+        assert!((curve.tangent_at(curve.length()) - Vector3::y()).norm() < 0.05);
+    }
+
+    #[test]
+    fn test_gradient_curve() {
+        let mut decoder = decoder(ALIGNMENT);
+        let profiles = ProfileProcessor::new(IfcSchema::new());
+        let curve = decoder.decode_by_id(16).unwrap();
+        let curve = AlignmentCurve::from_entity(&curve, &mut decoder, &profiles).unwrap();
+
+        let p = curve.point_at(40.0);
+        assert!((p.x - 40.0).abs() < 1e-6);
+        assert!((p.z - (10.0 + 40.0 * 0.005)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_frame_offsets() {
+        let curve = AlignmentCurve::from_points(vec![
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(0.0, 10.0, 0.0),
+        ])
+        .unwrap();
+        // Heading north, left is -X
+        let frame = curve.frame_at(4.0, 2.0, 1.0);
+        let origin = frame.transform_point(&Point3::origin());
+        assert!((origin - Point3::new(-2.0, 4.0, 1.0)).norm() < 1e-9);
+        let x = frame.transform_vector(&Vector3::x());
+        assert!((x - Vector3::y()).norm() < 1e-9);
+    }
+
+    #[test]
+    fn test_clothoid_heading() {
+        // Heading after length L is L² / 2A²
+        let (a, l) = (100.0, 50.0);
+        assert!((clothoid_angle(a, l) - 0.125).abs() < 1e-12);
+        let (x, y) = clothoid_point(a, l);
+        // Series expansion: x ≈ L - L⁵/(40A⁴), y ≈ L³/(6A²) - L⁷/(336A⁶)
+        assert!((x - (l - l.powi(5) / (40.0 * a.powi(4)))).abs() < 1e-4);
+        assert!((y - (l.powi(3) / (6.0 * a * a) - l.powi(7) / (336.0 * a.powi(6)))).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_measure() {
+        assert_eq!(measure(&AttributeValue::Float(2.5)), Some((2.5, false)));
+        let typed = AttributeValue::List(vec![
+            AttributeValue::String("IFCPARAMETERVALUE".into()),
+            AttributeValue::Float(0.5),
+        ]);
+        assert_eq!(measure(&typed), Some((0.5, true)));
+    }
+}
@@ -0,0 +1,395 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Bounding Volume Hierarchy - Fast ray casts against triangle soups
+//!
+//! A binary tree of axis-aligned boxes over the triangles of an indexed
+//! mesh, split at the centroid median of the longest axis. The tree only
+//! stores triangle numbers; vertex data is passed in at query time, so the
+//! caller can keep positions in its own buffers and mask triangles by
+//! collapsing their indices without rebuilding the tree.
+
+/// Triangles per leaf; small leaves favour queries over build time
+const LEAF_SIZE: usize = 4;
+
+/// Tree node; children of an inner node are stored next to each other
+#[derive(Clone, Copy, Debug)]
+struct Node {
+    min: [f32; 3],
+    max: [f32; 3],
+    /// Leaf: first entry in `triangles`; inner: index of the left child
+    start: u32,
+    /// Triangles in a leaf, 0 for inner nodes
+    count: u32,
+}
+
+/// BVH over the triangles of an indexed mesh
+#[derive(Clone, Debug, Default)]
+pub struct TriangleBvh {
+    nodes: Vec<Node>,
+    /// Triangle numbers (index into `indices / 3`) in leaf order
+    triangles: Vec<u32>,
+}
+
+impl TriangleBvh {
+    /// Build the tree for `indices` (three per triangle) into `positions`
+    ///
+    /// Triangles referencing missing vertices are left out.
+    pub fn build(positions: &[[f32; 3]], indices: &[u32]) -> Self {
+        let mut bounds = Vec::with_capacity(indices.len() / 3);
+        let mut triangles = Vec::with_capacity(indices.len() / 3);
+        for (triangle, corners) in indices.chunks_exact(3).enumerate() {
+            let (Some(a), Some(b), Some(c)) = (
+                positions.get(corners[0] as usize),
+                positions.get(corners[1] as usize),
+                positions.get(corners[2] as usize),
+            ) else {
+                bounds.push(([0.0; 3], [0.0; 3]));
+                continue;
+            };
+            bounds.push((
+                [0, 1, 2].map(|i| a[i].min(b[i]).min(c[i])),
+                [0, 1, 2].map(|i| a[i].max(b[i]).max(c[i])),
+            ));
+            triangles.push(triangle as u32);
+        }
+
+        let mut bvh = Self {
+            nodes: Vec::with_capacity(2 * triangles.len() / LEAF_SIZE + 1),
+            triangles,
+        };
+        if !bvh.triangles.is_empty() {
+            bvh.nodes.push(Node {
+                min: [0.0; 3],
+                max: [0.0; 3],
+                start: 0,
+                count: 0,
+            });
+            bvh.subdivide(0, 0, bvh.triangles.len(), &bounds);
+        }
+        bvh
+    }
+
+    fn subdivide(
+        &mut self,
+        node: usize,
+        start: usize,
+        end: usize,
+        bounds: &[([f32; 3], [f32; 3])],
+    ) {
+        let mut min = [f32::INFINITY; 3];
+        let mut max = [f32::NEG_INFINITY; 3];
+        let mut centroid_min = [f32::INFINITY; 3];
+        let mut centroid_max = [f32::NEG_INFINITY; 3];
+        for &triangle in &self.triangles[start..end] {
+            let (tri_min, tri_max) = bounds[triangle as usize];
+            for axis in 0..3 {
+                min[axis] = min[axis].min(tri_min[axis]);
+                max[axis] = max[axis].max(tri_max[axis]);
+                let centroid = (tri_min[axis] + tri_max[axis]) * 0.5;
+                centroid_min[axis] = centroid_min[axis].min(centroid);
+                centroid_max[axis] = centroid_max[axis].max(centroid);
+            }
+        }
+        self.nodes[node].min = min;
+        self.nodes[node].max = max;
+
+        let extent = [0, 1, 2].map(|i| centroid_max[i] - centroid_min[i]);
+        let axis = (0..3)
+            .max_by(|&a, &b| extent[a].total_cmp(&extent[b]))
+            .unwrap_or(0);
+        if end - start <= LEAF_SIZE || extent[axis] <= 0.0 {
+            self.nodes[node].start = start as u32;
+            self.nodes[node].count = (end - start) as u32;
+            return;
+        }
+
+        let mid = start + (end - start) / 2;
+        let centroid = |triangle: u32| {
+            let (tri_min, tri_max) = bounds[triangle as usize];
+            tri_min[axis] + tri_max[axis]
+        };
+        self.triangles[start..end]
+            .select_nth_unstable_by(mid - start, |&a, &b| centroid(a).total_cmp(&centroid(b)));
+
+        let left = self.nodes.len();
+        let empty = Node {
+            min: [0.0; 3],
+            max: [0.0; 3],
+            start: 0,
+            count: 0,
+        };
+        self.nodes.push(empty);
+        self.nodes.push(empty);
+        self.nodes[node].start = left as u32;
+        self.subdivide(left, start, mid, bounds);
+        self.subdivide(left + 1, mid, end, bounds);
+    }
+
+    /// Number of triangles in the tree
+    pub fn triangle_count(&self) -> usize {
+        self.triangles.len()
+    }
+
+    /// Check if the tree holds no triangles
+    pub fn is_empty(&self) -> bool {
+        self.triangles.is_empty()
+    }
+
+    /// Closest hit of a ray as `(distance, triangle number)`
+    ///
+    /// `positions` and `indices` must be the buffers the tree was built
+    /// from, although triangles may since have been collapsed (all three
+    /// indices equal) to hide them. `distance` is in units of `direction`.
+    pub fn raycast(
+        &self,
+        positions: &[[f32; 3]],
+        indices: &[u32],
+        origin: [f32; 3],
+        direction: [f32; 3],
+    ) -> Option<(f32, usize)> {
+        let inv_dir = direction.map(|d| 1.0 / d);
+        let mut closest: Option<(f32, usize)> = None;
+        let mut stack: Vec<u32> = Vec::with_capacity(64);
+        if !self.nodes.is_empty() {
+            stack.push(0);
+        }
+
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index as usize];
+            let limit = closest.map_or(f32::INFINITY, |(t, _)| t);
+            if ray_box(origin, inv_dir, node.min, node.max).is_none_or(|t| t > limit) {
+                continue;
+            }
+
+            if node.count > 0 {
+                let leaf = &self.triangles[node.start as usize..(node.start + node.count) as usize];
+                for &triangle in leaf {
+                    let base = triangle as usize * 3;
+                    let Some(corners) = indices.get(base..base + 3) else {
+                        continue;
+                    };
+                    let (Some(a), Some(b), Some(c)) = (
+                        positions.get(corners[0] as usize),
+                        positions.get(corners[1] as usize),
+                        positions.get(corners[2] as usize),
+                    ) else {
+                        continue;
+                    };
+                    if let Some(t) = ray_triangle(origin, direction, *a, *b, *c) {
+                        if closest.is_none_or(|(best, _)| t < best) {
+                            closest = Some((t, triangle as usize));
+                        }
+                    }
+                }
+                continue;
+            }
+
+            // Visit the nearer child first so the far one can often be pruned
+            let left = node.start;
+            let right = left + 1;
+            let t_left = self.entry(left, origin, inv_dir);
+            let t_right = self.entry(right, origin, inv_dir);
+            let (near, far) = if t_left <= t_right {
+                (left, right)
+            } else {
+                (right, left)
+            };
+            stack.push(far);
+            stack.push(near);
+        }
+
+        closest
+    }
+
+    fn entry(&self, node: u32, origin: [f32; 3], inv_dir: [f32; 3]) -> f32 {
+        let node = &self.nodes[node as usize];
+        ray_box(origin, inv_dir, node.min, node.max).unwrap_or(f32::INFINITY)
+    }
+}
+
+/// Distance at which a ray enters a box (0 if it starts inside)
+fn ray_box(origin: [f32; 3], inv_dir: [f32; 3], min: [f32; 3], max: [f32; 3]) -> Option<f32> {
+    let mut t_enter = 0.0f32;
+    let mut t_exit = f32::INFINITY;
+    for axis in 0..3 {
+        let t1 = (min[axis] - origin[axis]) * inv_dir[axis];
+        let t2 = (max[axis] - origin[axis]) * inv_dir[axis];
+        // NaN (ray parallel to and on a slab plane) leaves the interval alone
+        t_enter = t_enter.max(t1.min(t2));
+        t_exit = t_exit.min(t1.max(t2));
+    }
+    (t_enter <= t_exit).then_some(t_enter)
+}
+
+/// Möller–Trumbore ray-triangle intersection, distance in units of `direction`
+///
+/// Triangles are two-sided; hits at or behind the origin are ignored.
+pub fn ray_triangle(
+    origin: [f32; 3],
+    direction: [f32; 3],
+    a: [f32; 3],
+    b: [f32; 3],
+    c: [f32; 3],
+) -> Option<f32> {
+    const EPSILON: f32 = 1e-7;
+
+    let edge1 = sub(b, a);
+    let edge2 = sub(c, a);
+    let h = cross(direction, edge2);
+    let det = dot(edge1, h);
+    // Parallel ray or collapsed triangle
+    if det.abs() < EPSILON {
+        return None;
+    }
+
+    let f = 1.0 / det;
+    let s = sub(origin, a);
+    let u = f * dot(s, h);
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let q = cross(s, edge1);
+    let v = f * dot(direction, q);
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+
+    let t = f * dot(edge2, q);
+    (t > EPSILON).then_some(t)
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Stacked `n` x `n` grids of unit quads at heights 0, 1, .., layers - 1
+    fn layered_grid(n: u32, layers: u32) -> (Vec<[f32; 3]>, Vec<u32>) {
+        let mut positions = Vec::new();
+        let mut indices = Vec::new();
+        for z in 0..layers {
+            let base = positions.len() as u32;
+            for y in 0..=n {
+                for x in 0..=n {
+                    positions.push([x as f32, y as f32, z as f32]);
+                }
+            }
+            let row = n + 1;
+            for y in 0..n {
+                for x in 0..n {
+                    let i = base + y * row + x;
+                    indices.extend_from_slice(&[i, i + 1, i + row + 1, i, i + row + 1, i + row]);
+                }
+            }
+        }
+        (positions, indices)
+    }
+
+    fn brute_force(
+        positions: &[[f32; 3]],
+        indices: &[u32],
+        origin: [f32; 3],
+        direction: [f32; 3],
+    ) -> Option<(f32, usize)> {
+        indices
+            .chunks_exact(3)
+            .enumerate()
+            .filter_map(|(i, c)| {
+                let t = ray_triangle(
+                    origin,
+                    direction,
+                    positions[c[0] as usize],
+                    positions[c[1] as usize],
+                    positions[c[2] as usize],
+                )?;
+                Some((t, i))
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+    }
+
+    #[test]
+    fn test_raycast_hits_nearest_layer() {
+        let (positions, indices) = layered_grid(16, 3);
+        let bvh = TriangleBvh::build(&positions, &indices);
+        assert_eq!(bvh.triangle_count(), 16 * 16 * 2 * 3);
+
+        // Straight down from above hits the top layer (z = 2)
+        let (t, triangle) = bvh
+            .raycast(&positions, &indices, [3.3, 7.6, 10.0], [0.0, 0.0, -1.0])
+            .unwrap();
+        assert!((t - 8.0).abs() < 1e-5);
+        assert!(triangle >= 16 * 16 * 2 * 2);
+
+        // Pointing away misses
+        assert!(bvh
+            .raycast(&positions, &indices, [3.3, 7.6, 10.0], [0.0, 0.0, 1.0])
+            .is_none());
+    }
+
+    #[test]
+    fn test_raycast_matches_brute_force() {
+        let (positions, indices) = layered_grid(12, 4);
+        let bvh = TriangleBvh::build(&positions, &indices);
+
+        // Deterministic pseudo-random rays from around the model
+        let mut seed = 0x2545_f491_u32;
+        let mut next = || {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            seed as f32 / u32::MAX as f32
+        };
+        for _ in 0..200 {
+            let origin = [
+                next() * 20.0 - 4.0,
+                next() * 20.0 - 4.0,
+                next() * 10.0 - 3.0,
+            ];
+            let target = [next() * 12.0, next() * 12.0, next() * 3.0];
+            let direction = sub(target, origin);
+
+            let expected = brute_force(&positions, &indices, origin, direction);
+            let actual = bvh.raycast(&positions, &indices, origin, direction);
+            match (expected, actual) {
+                (Some((te, _)), Some((ta, _))) => assert!((te - ta).abs() < 1e-4),
+                (None, None) => {}
+                other => panic!("mismatch: {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_collapsed_triangles_are_skipped() {
+        let (positions, mut indices) = layered_grid(4, 2);
+        let bvh = TriangleBvh::build(&positions, &indices);
+
+        // Hide the whole top layer by collapsing its triangles
+        let layer = indices.len() / 2;
+        for corners in indices[layer..].chunks_exact_mut(3) {
+            corners.fill(corners[0]);
+        }
+
+        let (t, triangle) = bvh
+            .raycast(&positions, &indices, [1.5, 2.5, 5.0], [0.0, 0.0, -1.0])
+            .unwrap();
+        assert!((t - 5.0).abs() < 1e-5);
+        assert!(triangle < layer / 3);
+    }
+}
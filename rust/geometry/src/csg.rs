@@ -646,6 +646,20 @@ impl ClippingProcessor {
         Self::csgrs_to_mesh(&result_csg)
     }
 
+    /// Intersect two meshes using csgrs CSG boolean operations
+    pub fn intersect_mesh(&self, mesh_a: &Mesh, mesh_b: &Mesh) -> Result<Mesh> {
+        use csgrs::traits::CSG;
+
+        // Fast path: nothing is shared with an empty mesh
+        if mesh_a.is_empty() || mesh_b.is_empty() {
+            return Ok(Mesh::new());
+        }
+
+        let csg_a = Self::mesh_to_csgrs(mesh_a)?;
+        let csg_b = Self::mesh_to_csgrs(mesh_b)?;
+        Self::csgrs_to_mesh(&csg_a.intersection(&csg_b))
+    }
+
     /// Union multiple meshes together
     ///
     /// Convenience method that sequentially unions all non-empty meshes.
@@ -0,0 +1,211 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Face Colors - per-face colors of explicit meshes
+//!
+//! Two IFC mechanisms color individual faces instead of whole items:
+//! - `IfcIndexedColourMap` assigns a color from an `IfcColourRgbList` to every
+//!   triangle of an `IfcTriangulatedFaceSet`
+//! - `IfcStyledItem` pointing at an `IfcFace` styles a single face of an
+//!   `IfcFacetedBrep`
+//!
+//! Both are indexed in one scan so the router can color meshes as it builds them.
+
+use ifc_lite_core::{AttributeValue, DecodedEntity, EntityDecoder, EntityScanner, IfcType};
+use rustc_hash::FxHashMap;
+
+/// RGBA color, components in 0..1
+pub type Rgba = [f32; 4];
+
+/// Per-face colors of a model
+#[derive(Debug, Clone, Default)]
+pub struct FaceColors {
+    /// Key: IfcTriangulatedFaceSet ID, Value: color of each triangle in CoordIndex order
+    face_sets: FxHashMap<u32, Vec<Option<Rgba>>>,
+    /// Key: IfcFace ID, Value: color of its surface style
+    faces: FxHashMap<u32, Rgba>,
+}
+
+impl FaceColors {
+    /// Check whether the content can carry per-face colors at all
+    /// (styled faces only matter inside faceted breps)
+    pub fn may_apply(content: &str) -> bool {
+        content.contains("IFCINDEXEDCOLOURMAP(")
+            || (content.contains("IFCFACETEDBREP(") && content.contains("IFCSTYLEDITEM("))
+    }
+
+    /// Scan the content for indexed colour maps and styled faces
+    pub fn index(content: &str, decoder: &mut EntityDecoder) -> Self {
+        let mut colors = Self::default();
+        let mut scanner = EntityScanner::new(content);
+
+        while let Some((_id, type_name, start, end)) = scanner.next_entity() {
+            match type_name {
+                "IFCINDEXEDCOLOURMAP" => {
+                    let Ok(map) = decoder.decode_at(start, end) else {
+                        continue;
+                    };
+                    if let Some((face_set_id, triangles)) = indexed_colour_map(&map, decoder) {
+                        colors.face_sets.insert(face_set_id, triangles);
+                    }
+                }
+                "IFCSTYLEDITEM" => {
+                    let Ok(styled_item) = decoder.decode_at(start, end) else {
+                        continue;
+                    };
+                    // IfcStyledItem: Item (0), Styles (1), Name (2)
+                    let Some(item_id) = styled_item.get_ref(0) else {
+                        continue;
+                    };
+                    if colors.faces.contains_key(&item_id) || !is_face(item_id, decoder) {
+                        continue;
+                    }
+                    if let Some(color) = styled_item
+                        .get(1)
+                        .and_then(|styles| color_from_styles(styles, decoder))
+                    {
+                        colors.faces.insert(item_id, color);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        colors
+    }
+
+    /// Check if no face carries a color
+    pub fn is_empty(&self) -> bool {
+        self.face_sets.is_empty() && self.faces.is_empty()
+    }
+
+    /// Check if any IfcFace is styled individually
+    pub fn has_styled_faces(&self) -> bool {
+        !self.faces.is_empty()
+    }
+
+    /// Triangle colors of an IfcTriangulatedFaceSet
+    pub fn face_set_colors(&self, face_set_id: u32) -> Option<&[Option<Rgba>]> {
+        self.face_sets.get(&face_set_id).map(Vec::as_slice)
+    }
+
+    /// Color of a styled IfcFace
+    pub fn face_color(&self, face_id: u32) -> Option<Rgba> {
+        self.faces.get(&face_id).copied()
+    }
+}
+
+/// Resolve an IfcIndexedColourMap into (face set ID, per-triangle colors)
+fn indexed_colour_map(
+    map: &DecodedEntity,
+    decoder: &mut EntityDecoder,
+) -> Option<(u32, Vec<Option<Rgba>>)> {
+    // IfcIndexedColourMap: MappedTo (0), Opacity (1), Colours (2), ColourIndex (3)
+    let face_set_id = map.get_ref(0)?;
+    let opacity = map.get(1).and_then(measure).unwrap_or(1.0) as f32;
+
+    // IfcColourRgbList: ColourList (0) - list of (r, g, b)
+    let list = decoder.decode_by_id(map.get_ref(2)?).ok()?;
+    let palette: Vec<Rgba> = list
+        .get_list(0)?
+        .iter()
+        .map(|rgb| {
+            let rgb = rgb.as_list().unwrap_or(&[]);
+            let channel = |i: usize| rgb.get(i).and_then(measure).unwrap_or(0.8) as f32;
+            [channel(0), channel(1), channel(2), opacity]
+        })
+        .collect();
+
+    // ColourIndex is 1-based and has one entry per triangle
+    let triangles = map
+        .get_list(3)?
+        .iter()
+        .map(|index| {
+            let index = index.as_int()?;
+            palette
+                .get(usize::try_from(index).ok()?.checked_sub(1)?)
+                .copied()
+        })
+        .collect();
+
+    Some((face_set_id, triangles))
+}
+
+/// Check whether an entity is an IfcFace without decoding it
+fn is_face(entity_id: u32, decoder: &mut EntityDecoder) -> bool {
+    let Some(bytes) = decoder.get_raw_bytes(entity_id) else {
+        return false;
+    };
+    let Some(eq) = bytes.iter().position(|&b| b == b'=') else {
+        return false;
+    };
+    let type_name = bytes[eq + 1..]
+        .iter()
+        .take_while(|&&b| b != b'(')
+        .map(|b| b.to_ascii_uppercase())
+        .filter(|b| !b.is_ascii_whitespace())
+        .collect::<Vec<u8>>();
+    type_name == b"IFCFACE"
+}
+
+/// Float value of a plain or typed measure (`0.5` or `IFCNORMALISEDRATIOMEASURE(0.5)`)
+fn measure(value: &AttributeValue) -> Option<f64> {
+    value.as_float().or_else(|| match value.as_list()? {
+        [AttributeValue::String(_), inner] => inner.as_float(),
+        _ => None,
+    })
+}
+
+/// Surface color of the first style that has one
+fn color_from_styles(styles: &AttributeValue, decoder: &mut EntityDecoder) -> Option<Rgba> {
+    match styles {
+        AttributeValue::List(list) => list
+            .iter()
+            .filter_map(|style| style.as_entity_ref())
+            .find_map(|style_id| color_from_style(style_id, decoder)),
+        _ => color_from_style(styles.as_entity_ref()?, decoder),
+    }
+}
+
+/// Surface color of an IfcSurfaceStyle or an IFC2x3 IfcPresentationStyleAssignment
+fn color_from_style(style_id: u32, decoder: &mut EntityDecoder) -> Option<Rgba> {
+    let style = decoder.decode_by_id(style_id).ok()?;
+    match style.ifc_type {
+        // IfcSurfaceStyle: Name (0), Side (1), Styles (2)
+        IfcType::IfcSurfaceStyle => style
+            .get_list(2)?
+            .iter()
+            .filter_map(|element| element.as_entity_ref())
+            .find_map(|element_id| color_from_rendering(element_id, decoder)),
+        // IfcPresentationStyleAssignment: Styles (0)
+        _ => color_from_styles(style.get(0)?, decoder),
+    }
+}
+
+/// Color of an IfcSurfaceStyleShading / IfcSurfaceStyleRendering
+fn color_from_rendering(rendering_id: u32, decoder: &mut EntityDecoder) -> Option<Rgba> {
+    let rendering = decoder.decode_by_id(rendering_id).ok()?;
+    if !matches!(
+        rendering.ifc_type,
+        IfcType::IfcSurfaceStyleShading | IfcType::IfcSurfaceStyleRendering
+    ) {
+        return None;
+    }
+
+    // SurfaceColour (0), Transparency (1)
+    let transparency = rendering.get(1).and_then(measure).unwrap_or(0.0) as f32;
+
+    // IfcColourRgb: Name (0), Red (1), Green (2), Blue (3)
+    let colour = decoder.decode_by_id(rendering.get_ref(0)?).ok()?;
+    if colour.ifc_type != IfcType::IfcColourRgb {
+        return None;
+    }
+    let channel = |i: usize| colour.get(i).and_then(measure).unwrap_or(0.8) as f32;
+    Some([
+        channel(1),
+        channel(2),
+        channel(3),
+        (1.0 - transparency).clamp(0.0, 1.0),
+    ])
+}
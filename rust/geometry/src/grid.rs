@@ -0,0 +1,210 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Grid Placement - Virtual grid intersection math
+//!
+//! Elements placed with `IfcGridPlacement` sit at an `IfcVirtualGridIntersection`:
+//! the crossing point of two grid axes, optionally shifted by offset distances.
+//! Axis curves live in the 2D coordinate system of their `IfcGrid`.
+//!
+//! For display, [`PlacedGridAxis`] holds an axis segment in scene
+//! coordinates and [`grid_intersections`] finds where axes cross.
+
+use nalgebra::{Point2, Point3, Vector2};
+
+/// Grid axis segment in scene coordinates (metres, origin shift applied)
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlacedGridAxis {
+    /// IfcGrid entity ID
+    pub grid_id: u32,
+    /// IfcGridAxis.AxisTag, e.g. "A" or "1"
+    pub tag: String,
+    pub start: Point3<f64>,
+    pub end: Point3<f64>,
+}
+
+/// Point where two grid axes of the same grid cross
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridIntersection {
+    pub tags: (String, String),
+    pub point: Point3<f64>,
+}
+
+/// Straight grid axis in grid-local 2D coordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GridAxisLine {
+    pub origin: Point2<f64>,
+    /// Unit direction along the axis
+    pub direction: Vector2<f64>,
+}
+
+impl GridAxisLine {
+    /// Create axis line through two points (None if the points coincide)
+    pub fn from_points(start: Point2<f64>, end: Point2<f64>) -> Option<Self> {
+        let delta = end - start;
+        let len = delta.norm();
+        if len < 1e-12 {
+            return None;
+        }
+        Some(Self {
+            origin: start,
+            direction: delta / len,
+        })
+    }
+
+    /// Reverse the axis direction (IfcGridAxis.SameSense = FALSE)
+    pub fn reversed(&self) -> Self {
+        Self {
+            origin: self.origin,
+            direction: -self.direction,
+        }
+    }
+
+    /// Parallel axis shifted by `distance` to the left of the direction
+    pub fn offset(&self, distance: f64) -> Self {
+        let left = Vector2::new(-self.direction.y, self.direction.x);
+        Self {
+            origin: self.origin + left * distance,
+            direction: self.direction,
+        }
+    }
+}
+
+/// Intersect two axis lines (None if parallel)
+pub fn intersect_axes(a: &GridAxisLine, b: &GridAxisLine) -> Option<Point2<f64>> {
+    let denom = a.direction.x * b.direction.y - a.direction.y * b.direction.x;
+    if denom.abs() < 1e-12 {
+        return None;
+    }
+    let diff = b.origin - a.origin;
+    let t = (diff.x * b.direction.y - diff.y * b.direction.x) / denom;
+    Some(a.origin + a.direction * t)
+}
+
+/// Resolve an IfcVirtualGridIntersection to a grid-local 3D point
+///
+/// `offsets` follows IfcVirtualGridIntersection.OffsetDistances: the first two
+/// values offset the respective axes, an optional third value is the elevation.
+pub fn virtual_intersection(
+    first: &GridAxisLine,
+    second: &GridAxisLine,
+    offsets: &[f64],
+) -> Option<Point3<f64>> {
+    let d1 = offsets.first().copied().unwrap_or(0.0);
+    let d2 = offsets.get(1).copied().unwrap_or(0.0);
+    let z = offsets.get(2).copied().unwrap_or(0.0);
+
+    let p = intersect_axes(&first.offset(d1), &second.offset(d2))?;
+    Some(Point3::new(p.x, p.y, z))
+}
+
+/// Crossings of axes belonging to the same grid, within both segments
+///
+/// Parallel axes never cross; the elevation is taken from the first axis.
+pub fn grid_intersections(axes: &[PlacedGridAxis]) -> Vec<GridIntersection> {
+    // Segments ending just short of each other still count as crossing
+    const TOLERANCE: f64 = 1e-3;
+
+    let planar = |p: &Point3<f64>| Point2::new(p.x, p.y);
+    let param = |axis: &PlacedGridAxis, p: &Point2<f64>| {
+        let (start, end) = (planar(&axis.start), planar(&axis.end));
+        let length = (end - start).norm();
+        let t = (p - start).dot(&(end - start)) / length;
+        (-TOLERANCE..=length + TOLERANCE).contains(&t)
+    };
+
+    let mut intersections = Vec::new();
+    for (i, a) in axes.iter().enumerate() {
+        let Some(line_a) = GridAxisLine::from_points(planar(&a.start), planar(&a.end)) else {
+            continue;
+        };
+        for b in axes[i + 1..].iter().filter(|b| b.grid_id == a.grid_id) {
+            let Some(line_b) = GridAxisLine::from_points(planar(&b.start), planar(&b.end)) else {
+                continue;
+            };
+            let Some(p) = intersect_axes(&line_a, &line_b) else {
+                continue;
+            };
+            if param(a, &p) && param(b, &p) {
+                intersections.push(GridIntersection {
+                    tags: (a.tag.clone(), b.tag.clone()),
+                    point: Point3::new(p.x, p.y, a.start.z),
+                });
+            }
+        }
+    }
+    intersections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn axis(x0: f64, y0: f64, x1: f64, y1: f64) -> GridAxisLine {
+        GridAxisLine::from_points(Point2::new(x0, y0), Point2::new(x1, y1)).unwrap()
+    }
+
+    #[test]
+    fn test_orthogonal_intersection() {
+        // Axis "A" along X at y=5, axis "1" along Y at x=3
+        let a = axis(-10.0, 5.0, 10.0, 5.0);
+        let b = axis(3.0, -10.0, 3.0, 10.0);
+        let p = intersect_axes(&a, &b).unwrap();
+        assert!((p.x - 3.0).abs() < 1e-9);
+        assert!((p.y - 5.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_parallel_axes() {
+        let a = axis(0.0, 0.0, 1.0, 0.0);
+        let b = axis(0.0, 2.0, 1.0, 2.0);
+        assert!(intersect_axes(&a, &b).is_none());
+        assert!(GridAxisLine::from_points(Point2::new(1.0, 1.0), Point2::new(1.0, 1.0)).is_none());
+    }
+
+    #[test]
+    fn test_virtual_intersection_with_offsets() {
+        let a = axis(0.0, 0.0, 10.0, 0.0);
+        let b = axis(0.0, 0.0, 0.0, 10.0);
+        // Offset of +1 moves axis "a" to y=1 (left of +X), -2 moves "b" to x=2 (right of +Y)
+        let p = virtual_intersection(&a, &b, &[1.0, -2.0, 3.5]).unwrap();
+        assert!((p.x - 2.0).abs() < 1e-9);
+        assert!((p.y - 1.0).abs() < 1e-9);
+        assert!((p.z - 3.5).abs() < 1e-9);
+
+        // Reversing an axis flips its offset side
+        let p = virtual_intersection(&a.reversed(), &b, &[1.0]).unwrap();
+        assert!((p.y + 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_grid_intersections() {
+        let placed = |tag: &str, start: [f64; 2], end: [f64; 2]| PlacedGridAxis {
+            grid_id: 1,
+            tag: tag.to_string(),
+            start: Point3::new(start[0], start[1], 0.0),
+            end: Point3::new(end[0], end[1], 0.0),
+        };
+        let axes = [
+            placed("A", [0.0, 0.0], [20.0, 0.0]),
+            placed("B", [0.0, 6.0], [20.0, 6.0]),
+            placed("1", [5.0, -1.0], [5.0, 7.0]),
+            // Stops short of axis B
+            placed("2", [15.0, -1.0], [15.0, 3.0]),
+        ];
+
+        let found: Vec<_> = grid_intersections(&axes)
+            .into_iter()
+            .map(|i| (i.tags.0, i.tags.1, i.point.x, i.point.y))
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("A".to_string(), "1".to_string(), 5.0, 0.0),
+                ("A".to_string(), "2".to_string(), 15.0, 0.0),
+                ("B".to_string(), "1".to_string(), 5.0, 6.0),
+            ]
+        );
+    }
+}
@@ -16,6 +16,18 @@
 //! - **Triangulation**: Polygon triangulation with hole support via earcutr
 //! - **CSG Operations**: Boolean clipping for wall openings
 //! - **Mesh Processing**: Normal calculation and coordinate transformations
+//! - **Smooth Shading**: Crease-angle normals for explicit meshes
+//! - **Face Colors**: Indexed colour maps and styled faces as vertex colors
+//! - **Level of Detail**: Vertex clustering decimation for distant geometry
+//! - **Mesh Optimization**: Vertex welding, vertex cache and overdraw ordering
+//! - **Ray Casting**: Triangle BVH for picking on large meshes
+//! - **Spatial Index**: Box, ray and plane queries over entity bounds
+//! - **Parallel Processing**: Elements on the rayon pool with forked routers and decoders
+//! - **Plan Sections**: Horizontal cuts as 2D outline polylines
+//! - **Origin Shift**: Far-off models moved to the scene origin in f64 before the f32 cast
+//! - **Geometry Options**: Curve deflection, segment caps and element filters per router
+//! - **Processor Timings**: Calls and time per geometry processor for load profiling
+//! - **Geometry Warnings**: Failures worked around while meshing, for loader diagnostics
 //!
 //! ## Supported Geometry Types
 //!
@@ -24,41 +36,53 @@
 //! | `IfcExtrudedAreaSolid` | Full | Most common - extruded profiles |
 //! | `IfcFacetedBrep` | Full | Boundary representation meshes |
 //! | `IfcTriangulatedFaceSet` | Full | Pre-triangulated (IFC4) |
-//! | `IfcBooleanClippingResult` | Partial | CSG difference operations |
+//! | `IfcAdvancedBrep` | Partial | Planar, cylindrical and B-spline faces |
+//! | `IfcBooleanResult` | Full | CSG difference, union and intersection, incl. half-spaces |
 //! | `IfcMappedItem` | Full | Instanced geometry |
 //! | `IfcSweptDiskSolid` | Full | Pipe/tube geometry |
 //!
 //! ## Quick Start
 //!
-//! ```rust,ignore
-//! use ifc_lite_geometry::{
-//!     Profile2D, extrude_profile, triangulate_polygon,
-//!     Point2, Point3, Vector3
-//! };
+//! ```rust
+//! use ifc_lite_geometry::{extrude_profile, profile::create_rectangle};
 //!
-//! // Create a rectangular profile
-//! let profile = Profile2D::rectangle(2.0, 1.0);
+//! // Create a 2m x 1m rectangular profile
+//! let profile = create_rectangle(2.0, 1.0);
 //!
-//! // Extrude to 3D
-//! let direction = Vector3::new(0.0, 0.0, 1.0);
-//! let mesh = extrude_profile(&profile, direction, 3.0)?;
+//! // Extrude 3m along +Z
+//! let mesh = extrude_profile(&profile, 3.0, None)?;
 //!
-//! println!("Generated {} triangles", mesh.triangle_count());
+//! assert_eq!(mesh.triangle_count(), 12);
+//! let (min, max) = mesh.bounds();
+//! assert!((max.z - min.z - 3.0).abs() < 1e-6);
+//! # Ok::<(), ifc_lite_geometry::Error>(())
 //! ```
 //!
 //! ## Geometry Router
 //!
-//! Use the [`GeometryRouter`] to automatically dispatch entities to appropriate processors:
-//!
-//! ```rust,ignore
-//! use ifc_lite_geometry::{GeometryRouter, GeometryProcessor};
-//!
-//! let router = GeometryRouter::new();
-//!
-//! // Process entity
-//! if let Some(mesh) = router.process(&decoder, &entity)? {
-//!     renderer.add_mesh(mesh);
+//! Use the [`GeometryRouter`] to automatically dispatch entities to appropriate processors.
+//! The example below loads the small sample model shipped in `examples/data`:
+//!
+//! ```rust
+//! use ifc_lite_core::{build_entity_index, EntityDecoder, EntityScanner};
+//! use ifc_lite_geometry::GeometryRouter;
+//!
+//! let content = include_str!(concat!(env!("CARGO_MANIFEST_DIR"), "/examples/data/sample.ifc"));
+//! let mut decoder = EntityDecoder::with_index(content, build_entity_index(content));
+//! let router = GeometryRouter::with_units(content, &mut decoder);
+//!
+//! let mut scanner = EntityScanner::new(content);
+//! let mut triangles = 0;
+//! while let Some((_id, type_name, start, end)) = scanner.next_entity() {
+//!     if type_name != "IFCWALL" && type_name != "IFCSLAB" {
+//!         continue;
+//!     }
+//!     let entity = decoder.decode_at(start, end)?;
+//!     let mesh = router.process_element(&entity, &mut decoder)?;
+//!     triangles += mesh.triangle_count();
 //! }
+//! assert_eq!(triangles, 24);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
 //! ```
 //!
 //! ## Performance
@@ -67,30 +91,60 @@
 //! - **Complex Breps**: ~200 entities/sec
 //! - **Boolean operations**: ~20 entities/sec
 
+pub mod alignment;
 pub mod bool2d;
+pub mod bvh;
 pub mod csg;
 pub mod error;
 pub mod extrusion;
+pub mod face_colors;
+pub mod grid;
+pub mod lod;
 pub mod mesh;
+pub mod normals;
+pub mod optimize;
+pub mod options;
+pub mod parallel;
+pub mod plan;
 pub mod processors;
 pub mod profile;
 pub mod profiles;
+pub mod quantities;
 pub mod router;
+pub mod spatial_index;
+pub mod timings;
 pub mod triangulation;
 pub mod void_analysis;
 pub mod void_index;
+pub mod warnings;
 
 // Re-export nalgebra types for convenience
 pub use nalgebra::{Point2, Point3, Vector2, Vector3};
 
+pub use alignment::AlignmentCurve;
 pub use bool2d::{
     compute_signed_area, ensure_ccw, ensure_cw, is_valid_contour, point_in_contour, subtract_2d,
     subtract_multiple_2d, union_contours,
 };
+pub use bvh::TriangleBvh;
 pub use csg::{calculate_normals, ClippingProcessor, Plane, Triangle};
 pub use error::{Error, Result};
 pub use extrusion::{extrude_profile, extrude_profile_with_voids};
-pub use mesh::Mesh;
+pub use face_colors::FaceColors;
+pub use grid::{
+    grid_intersections, intersect_axes, virtual_intersection, GridAxisLine, GridIntersection,
+    PlacedGridAxis,
+};
+pub use lod::{decimate, generate_lods};
+pub use mesh::{ElementGeometry, Mesh, MeshInstance, NO_COLOR};
+pub use normals::{smooth_normals, DEFAULT_CREASE_ANGLE};
+pub use optimize::{
+    average_cache_miss_ratio, optimize_mesh, optimize_overdraw, optimize_vertex_cache,
+    optimize_vertex_fetch, weld_vertices, OptimizeOptions, DEFAULT_WELD_TOLERANCE,
+};
+pub use options::{GeometryOptions, DEFAULT_DEFLECTION, DEFAULT_MAX_SEGMENTS};
+pub use parallel::process_parallel;
+pub use plan::{section_polylines, PlanPolyline};
 pub use processors::{
     AdvancedBrepProcessor, BooleanClippingProcessor, ExtrudedAreaSolidProcessor,
     FacetedBrepProcessor, MappedItemProcessor, RevolvedAreaSolidProcessor, SweptDiskSolidProcessor,
@@ -98,10 +152,14 @@ pub use processors::{
 };
 pub use profile::{Profile2D, Profile2DWithVoids, ProfileType, VoidInfo};
 pub use profiles::ProfileProcessor;
-pub use router::{GeometryProcessor, GeometryRouter};
+pub use quantities::mesh_measures;
+pub use router::{GeometryProcessor, GeometryRouter, ORIGIN_SHIFT_THRESHOLD};
+pub use spatial_index::{EntityBox, SpatialIndex};
+pub use timings::{ProcessorTiming, ProcessorTimings};
 pub use triangulation::triangulate_polygon;
 pub use void_analysis::{
     classify_voids_batch, extract_coplanar_voids, extract_nonplanar_voids, VoidAnalyzer,
     VoidClassification,
 };
 pub use void_index::{VoidIndex, VoidStatistics};
+pub use warnings::{take_warnings, warn};
@@ -0,0 +1,228 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Level of Detail - Vertex clustering mesh decimation
+//!
+//! Vertices are snapped to a uniform grid and merged per cell; triangles
+//! that collapse are dropped. Vertices are only merged when their normals
+//! point the same way, so the hard edges of flat-shaded IFC geometry stay
+//! sharp, and only when their colors match, so the faces of a mesh with
+//! per-vertex colors keep them. Clustering is linear in the vertex count, which matters more here
+//! than the quality of an edge-collapse simplifier.
+
+use crate::mesh::Mesh;
+use rustc_hash::FxHashMap;
+
+/// Normals are bucketed to this many steps per axis before merging
+const NORMAL_STEPS: f32 = 2.0;
+
+/// Decimate a mesh by clustering vertices in cubes of `cell_size`
+///
+/// Returns a copy of the mesh if `cell_size` is not positive.
+pub fn decimate(mesh: &Mesh, cell_size: f32) -> Mesh {
+    if cell_size <= 0.0 || !cell_size.is_finite() || mesh.is_empty() {
+        return mesh.clone();
+    }
+
+    let has_normals = mesh.normals.len() == mesh.positions.len();
+    let has_colors = mesh.colors.len() == mesh.vertex_count() * 4;
+    let mut clusters: FxHashMap<([i32; 6], [u32; 4]), u32> = FxHashMap::default();
+    // Sum of positions and normals per cluster, plus member count
+    let mut sums: Vec<([f32; 3], [f32; 3], u32)> = Vec::new();
+    // Color shared by the members of each cluster
+    let mut colors: Vec<f32> = Vec::new();
+    let mut remap = Vec::with_capacity(mesh.vertex_count());
+
+    for (i, p) in mesh.positions.chunks_exact(3).enumerate() {
+        let n = if has_normals {
+            [
+                mesh.normals[i * 3],
+                mesh.normals[i * 3 + 1],
+                mesh.normals[i * 3 + 2],
+            ]
+        } else {
+            [0.0; 3]
+        };
+        let key = [
+            (p[0] / cell_size).floor() as i32,
+            (p[1] / cell_size).floor() as i32,
+            (p[2] / cell_size).floor() as i32,
+            (n[0] * NORMAL_STEPS).round() as i32,
+            (n[1] * NORMAL_STEPS).round() as i32,
+            (n[2] * NORMAL_STEPS).round() as i32,
+        ];
+        let color: &[f32] = if has_colors {
+            &mesh.colors[i * 4..i * 4 + 4]
+        } else {
+            &[0.0; 4]
+        };
+        let color_key = [
+            color[0].to_bits(),
+            color[1].to_bits(),
+            color[2].to_bits(),
+            color[3].to_bits(),
+        ];
+        let cluster = *clusters.entry((key, color_key)).or_insert_with(|| {
+            sums.push(([0.0; 3], [0.0; 3], 0));
+            if has_colors {
+                colors.extend_from_slice(color);
+            }
+            (sums.len() - 1) as u32
+        });
+        let sum = &mut sums[cluster as usize];
+        for axis in 0..3 {
+            sum.0[axis] += p[axis];
+            sum.1[axis] += n[axis];
+        }
+        sum.2 += 1;
+        remap.push(cluster);
+    }
+
+    let mut result = Mesh::with_capacity(sums.len(), mesh.indices.len());
+    for (position, normal, count) in &sums {
+        let count = *count as f32;
+        result.positions.extend(position.iter().map(|v| v / count));
+        if has_normals {
+            let length = normal.iter().map(|v| v * v).sum::<f32>().sqrt();
+            let length = if length > f32::EPSILON { length } else { 1.0 };
+            result.normals.extend(normal.iter().map(|v| v / length));
+        }
+    }
+    result.colors = colors;
+
+    for triangle in mesh.indices.chunks_exact(3) {
+        let (Some(&a), Some(&b), Some(&c)) = (
+            remap.get(triangle[0] as usize),
+            remap.get(triangle[1] as usize),
+            remap.get(triangle[2] as usize),
+        ) else {
+            continue;
+        };
+        if a != b && b != c && a != c {
+            result.add_triangle(a, b, c);
+        }
+    }
+
+    result
+}
+
+/// Generate progressively coarser versions of a mesh, one per cell size
+///
+/// Each level is decimated from the previous one, so `cell_sizes` should be
+/// increasing. A level that would not remove at least a tenth of the
+/// triangles of its predecessor ends the list: such levels cost memory
+/// without saving any rendering work.
+pub fn generate_lods(mesh: &Mesh, cell_sizes: &[f32]) -> Vec<Mesh> {
+    let mut levels: Vec<Mesh> = Vec::with_capacity(cell_sizes.len());
+    for &cell_size in cell_sizes {
+        let previous = levels.last().unwrap_or(mesh);
+        let level = decimate(previous, cell_size);
+        if level.triangle_count() * 10 > previous.triangle_count() * 9 {
+            break;
+        }
+        levels.push(level);
+    }
+    levels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra::{Point3, Vector3};
+
+    /// Flat grid of `n` x `n` quads in the XY plane, 1 unit per quad
+    fn grid(n: u32) -> Mesh {
+        let mut mesh = Mesh::new();
+        for y in 0..=n {
+            for x in 0..=n {
+                mesh.add_vertex(Point3::new(x as f64, y as f64, 0.0), Vector3::z());
+            }
+        }
+        let row = n + 1;
+        for y in 0..n {
+            for x in 0..n {
+                let i = y * row + x;
+                mesh.add_triangle(i, i + 1, i + row + 1);
+                mesh.add_triangle(i, i + row + 1, i + row);
+            }
+        }
+        mesh
+    }
+
+    #[test]
+    fn test_decimate_reduces_grid() {
+        let mesh = grid(32);
+        let coarse = decimate(&mesh, 4.0);
+
+        assert!(coarse.triangle_count() < mesh.triangle_count() / 8);
+        assert!(coarse.triangle_count() > 0);
+        assert_eq!(coarse.positions.len(), coarse.normals.len());
+        assert!(coarse
+            .indices
+            .iter()
+            .all(|&i| (i as usize) < coarse.vertex_count()));
+        // Normals survive the merge
+        assert!(coarse.normals.chunks_exact(3).all(|n| n[2] > 0.99));
+    }
+
+    #[test]
+    fn test_decimate_keeps_hard_edges() {
+        // Two faces of a box corner share positions but not normals
+        let mut mesh = Mesh::new();
+        for normal in [Vector3::z(), Vector3::x()] {
+            for p in [[1.0, 0.0, 1.0], [1.0, 1.0, 1.0], [0.0, 0.0, 1.0]] {
+                mesh.add_vertex(Point3::new(p[0], p[1], p[2]), normal);
+            }
+        }
+        mesh.add_triangle(0, 1, 2);
+        mesh.add_triangle(3, 4, 5);
+
+        let coarse = decimate(&mesh, 0.1);
+        assert_eq!(coarse.vertex_count(), 6);
+        assert_eq!(coarse.triangle_count(), 2);
+    }
+
+    #[test]
+    fn test_decimate_keeps_colors() {
+        // Left and right half of the grid in different colors
+        let mut mesh = grid(32);
+        mesh.colors = mesh
+            .positions
+            .chunks_exact(3)
+            .flat_map(|p| {
+                if p[0] < 16.0 {
+                    [1.0, 0.0, 0.0, 1.0]
+                } else {
+                    [0.0, 0.0, 1.0, 1.0]
+                }
+            })
+            .collect();
+
+        let coarse = decimate(&mesh, 4.0);
+        assert!(coarse.triangle_count() < mesh.triangle_count() / 4);
+        assert_eq!(coarse.colors.len(), coarse.vertex_count() * 4);
+        for (p, c) in coarse
+            .positions
+            .chunks_exact(3)
+            .zip(coarse.colors.chunks_exact(4))
+        {
+            // The boundary falls on a cell edge, so no cluster mixes colors
+            let expected = if p[0] < 16.0 { 0.0 } else { 1.0 };
+            assert_eq!(c, [1.0 - expected, 0.0, expected, 1.0]);
+        }
+    }
+
+    #[test]
+    fn test_generate_lods_stops_when_no_gain() {
+        let mesh = grid(64);
+        let levels = generate_lods(&mesh, &[2.0, 8.0, 8.0]);
+
+        assert_eq!(levels.len(), 2);
+        assert!(levels[0].triangle_count() < mesh.triangle_count());
+        assert!(levels[1].triangle_count() < levels[0].triangle_count());
+
+        // A single triangle cannot get any simpler
+        assert!(generate_lods(&grid(1), &[0.1]).is_empty());
+    }
+}
@@ -4,7 +4,13 @@
 
 //! Mesh data structures
 
-use nalgebra::{Point3, Vector3};
+use nalgebra::{Matrix4, Point3, Vector3};
+use std::sync::Arc;
+
+/// Placeholder color for vertices without their own color.
+///
+/// The negative alpha tells consumers to fall back to the element color.
+pub const NO_COLOR: [f32; 4] = [0.0, 0.0, 0.0, -1.0];
 
 /// Triangle mesh
 #[derive(Debug, Clone)]
@@ -15,6 +21,10 @@ pub struct Mesh {
     pub normals: Vec<f32>,
     /// Triangle indices (i0, i1, i2)
     pub indices: Vec<u32>,
+    /// Optional vertex colors (r, g, b, a) - empty when the mesh is uncolored.
+    /// Vertices without a color of their own hold [`NO_COLOR`].
+    /// Boolean (CSG) results drop colors.
+    pub colors: Vec<f32>,
 }
 
 impl Mesh {
@@ -24,6 +34,7 @@ impl Mesh {
             positions: Vec::new(),
             normals: Vec::new(),
             indices: Vec::new(),
+            colors: Vec::new(),
         }
     }
 
@@ -33,6 +44,7 @@ impl Mesh {
             positions: Vec::with_capacity(vertex_count * 3),
             normals: Vec::with_capacity(vertex_count * 3),
             indices: Vec::with_capacity(index_count),
+            colors: Vec::new(),
         }
     }
 
@@ -70,6 +82,7 @@ impl Mesh {
         self.normals.reserve(other.normals.len());
         self.indices.reserve(other.indices.len());
 
+        self.merge_colors(other);
         self.positions.extend_from_slice(&other.positions);
         self.normals.extend_from_slice(&other.normals);
 
@@ -94,6 +107,7 @@ impl Mesh {
         for mesh in meshes {
             if !mesh.is_empty() {
                 let vertex_offset = (self.positions.len() / 3) as u32;
+                self.merge_colors(mesh);
                 self.positions.extend_from_slice(&mesh.positions);
                 self.normals.extend_from_slice(&mesh.normals);
                 self.indices
@@ -102,6 +116,72 @@ impl Mesh {
         }
     }
 
+    /// Append the colors of a mesh that is about to be merged, padding
+    /// whichever side is uncolored. Must run before positions are extended.
+    fn merge_colors(&mut self, other: &Mesh) {
+        if self.colors.is_empty() && other.colors.is_empty() {
+            return;
+        }
+        if self.colors.is_empty() {
+            self.colors = NO_COLOR.repeat(self.vertex_count());
+        }
+        if other.colors.is_empty() {
+            self.colors.extend(NO_COLOR.repeat(other.vertex_count()));
+        } else {
+            self.colors.extend_from_slice(&other.colors);
+        }
+    }
+
+    /// Check if any vertex carries its own color
+    #[inline]
+    pub fn has_colors(&self) -> bool {
+        !self.colors.is_empty()
+    }
+
+    /// Color of a vertex, if it has one
+    #[inline]
+    pub fn vertex_color(&self, index: usize) -> Option<[f32; 4]> {
+        let c = self.colors.get(index * 4..index * 4 + 4)?;
+        (c[3] >= 0.0).then(|| [c[0], c[1], c[2], c[3]])
+    }
+
+    /// Assign a color to each triangle (`None` keeps the element color).
+    ///
+    /// Vertices shared by triangles of different colors are duplicated so
+    /// that every triangle renders flat in its own color.
+    pub fn apply_triangle_colors(&mut self, triangle_colors: &[Option<[f32; 4]>]) {
+        if triangle_colors.iter().all(Option::is_none) {
+            return;
+        }
+        if self.colors.is_empty() {
+            self.colors = NO_COLOR.repeat(self.vertex_count());
+        }
+
+        let mut assigned = vec![false; self.vertex_count()];
+        for (t, color) in triangle_colors.iter().enumerate() {
+            let color = color.unwrap_or(NO_COLOR);
+            for k in 0..3 {
+                let Some(&vertex) = self.indices.get(t * 3 + k) else {
+                    return;
+                };
+                let v = vertex as usize;
+                if !assigned[v] {
+                    assigned[v] = true;
+                    self.colors[v * 4..v * 4 + 4].copy_from_slice(&color);
+                } else if self.colors[v * 4..v * 4 + 4] != color {
+                    let copy = self.vertex_count() as u32;
+                    self.positions.extend_from_within(v * 3..v * 3 + 3);
+                    if self.normals.len() >= v * 3 + 3 {
+                        self.normals.extend_from_within(v * 3..v * 3 + 3);
+                    }
+                    self.colors.extend_from_slice(&color);
+                    assigned.push(true);
+                    self.indices[t * 3 + k] = copy;
+                }
+            }
+        }
+    }
+
     /// Get vertex count
     #[inline]
     pub fn vertex_count(&self) -> usize {
@@ -150,6 +230,87 @@ impl Mesh {
         self.positions.clear();
         self.normals.clear();
         self.indices.clear();
+        self.colors.clear();
+    }
+
+    /// Transform positions and normals in place
+    pub fn transform(&mut self, transform: &Matrix4<f64>) {
+        self.positions.chunks_exact_mut(3).for_each(|chunk| {
+            let point = Point3::new(chunk[0] as f64, chunk[1] as f64, chunk[2] as f64);
+            let transformed = transform.transform_point(&point);
+            chunk[0] = transformed.x as f32;
+            chunk[1] = transformed.y as f32;
+            chunk[2] = transformed.z as f32;
+        });
+
+        // Normals only take the rotation part
+        let rotation = transform.fixed_view::<3, 3>(0, 0);
+        self.normals.chunks_exact_mut(3).for_each(|chunk| {
+            let normal = Vector3::new(chunk[0] as f64, chunk[1] as f64, chunk[2] as f64);
+            let transformed = (rotation * normal).normalize();
+            chunk[0] = transformed.x as f32;
+            chunk[1] = transformed.y as f32;
+            chunk[2] = transformed.z as f32;
+        });
+    }
+}
+
+/// One placement of shared geometry (IfcMappedItem)
+#[derive(Debug, Clone)]
+pub struct MeshInstance {
+    /// IfcRepresentationMap the geometry comes from
+    pub source_id: u32,
+    /// Geometry in representation map coordinates, shared by all instances
+    pub mesh: Arc<Mesh>,
+    /// Map coordinates to world coordinates
+    pub transform: Matrix4<f64>,
+}
+
+impl MeshInstance {
+    /// Copy the shared geometry into world coordinates
+    pub fn to_mesh(&self) -> Mesh {
+        let mut mesh = self.mesh.as_ref().clone();
+        mesh.transform(&self.transform);
+        mesh
+    }
+
+    /// [`Self::transform`] for Y-up viewers, column-major: applies to
+    /// positions already swapped from IFC (x, y, z) to (x, z, -y)
+    pub fn y_up_transform(&self) -> [f32; 16] {
+        #[rustfmt::skip]
+        let swap = Matrix4::new(
+            1.0, 0.0, 0.0, 0.0,
+            0.0, 0.0, 1.0, 0.0,
+            0.0, -1.0, 0.0, 0.0,
+            0.0, 0.0, 0.0, 1.0,
+        );
+        let transform = swap * self.transform * swap.transpose();
+        std::array::from_fn(|i| transform[i] as f32)
+    }
+}
+
+/// Element geometry with mapped items kept as instances
+#[derive(Debug, Clone, Default)]
+pub struct ElementGeometry {
+    /// Geometry unique to the element, in world coordinates
+    pub mesh: Mesh,
+    /// Instances of shared representation map geometry
+    pub instances: Vec<MeshInstance>,
+}
+
+impl ElementGeometry {
+    /// Check if there is no geometry at all
+    pub fn is_empty(&self) -> bool {
+        self.mesh.is_empty() && self.instances.iter().all(|i| i.mesh.is_empty())
+    }
+
+    /// Flatten into a single world-space mesh
+    pub fn into_mesh(self) -> Mesh {
+        let mut mesh = self.mesh;
+        for instance in &self.instances {
+            mesh.merge(&instance.to_mesh());
+        }
+        mesh
     }
 }
 
@@ -194,4 +355,94 @@ mod tests {
         assert_eq!(mesh1.vertex_count(), 2);
         assert_eq!(mesh1.triangle_count(), 2);
     }
+
+    #[test]
+    fn test_triangle_colors_split_shared_vertices() {
+        let mut mesh = Mesh::new();
+        for (x, y) in [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)] {
+            mesh.add_vertex(Point3::new(x, y, 0.0), Vector3::z());
+        }
+        mesh.add_triangle(0, 1, 2);
+        mesh.add_triangle(0, 2, 3);
+
+        let red = [1.0, 0.0, 0.0, 1.0];
+        mesh.apply_triangle_colors(&[Some(red), None]);
+
+        // Vertices 0 and 2 are shared with the uncolored triangle
+        assert_eq!(mesh.vertex_count(), 6);
+        assert_eq!(mesh.colors.len(), 24);
+        for &i in &mesh.indices[0..3] {
+            assert_eq!(mesh.vertex_color(i as usize), Some(red));
+        }
+        for &i in &mesh.indices[3..6] {
+            assert_eq!(mesh.vertex_color(i as usize), None);
+        }
+    }
+
+    #[test]
+    fn test_merge_pads_missing_colors() {
+        let mut plain = Mesh::new();
+        plain.add_vertex(Point3::origin(), Vector3::z());
+
+        let mut colored = Mesh::new();
+        colored.add_vertex(Point3::origin(), Vector3::z());
+        colored.colors = vec![0.0, 1.0, 0.0, 0.5];
+
+        plain.merge(&colored);
+        assert_eq!(plain.vertex_color(0), None);
+        assert_eq!(plain.vertex_color(1), Some([0.0, 1.0, 0.0, 0.5]));
+
+        let mut target = Mesh::new();
+        target.merge_all(&[colored, Mesh::new()]);
+        assert_eq!(target.colors.len(), 4);
+    }
+
+    #[test]
+    fn test_instances_share_geometry() {
+        let mut source = Mesh::new();
+        source.add_vertex(Point3::new(1.0, 0.0, 0.0), Vector3::x());
+        source.add_vertex(Point3::new(0.0, 1.0, 0.0), Vector3::x());
+        source.add_vertex(Point3::new(0.0, 0.0, 1.0), Vector3::x());
+        source.add_triangle(0, 1, 2);
+        let source = Arc::new(source);
+
+        let geometry = ElementGeometry {
+            mesh: Mesh::new(),
+            instances: (0..3)
+                .map(|i| MeshInstance {
+                    source_id: 7,
+                    mesh: Arc::clone(&source),
+                    transform: Matrix4::new_translation(&Vector3::new(i as f64 * 10.0, 0.0, 0.0)),
+                })
+                .collect(),
+        };
+        assert_eq!(Arc::strong_count(&source), 4);
+
+        let flat = geometry.into_mesh();
+        assert_eq!(flat.triangle_count(), 3);
+        assert_eq!(flat.positions[18], 21.0);
+    }
+
+    #[test]
+    fn test_instance_y_up_transform() {
+        let instance = MeshInstance {
+            source_id: 1,
+            mesh: Arc::new(Mesh::new()),
+            transform: Matrix4::new_translation(&Vector3::new(1.0, 2.0, 3.0)),
+        };
+        let transform = instance.y_up_transform();
+        // IFC (1, 2, 3) is (1, 3, -2) with Y up
+        assert_eq!(&transform[12..15], &[1.0, 3.0, -2.0]);
+
+        // A quarter turn about IFC Z is one about Y up, the other way round
+        let instance = MeshInstance {
+            transform: Matrix4::from_axis_angle(&Vector3::z_axis(), std::f64::consts::FRAC_PI_2),
+            ..instance
+        };
+        let transform = instance.y_up_transform();
+        // IFC +X goes to +Y, which is -Z with Y up
+        let x_axis = &transform[0..3];
+        assert!(x_axis[0].abs() < 1e-6 && x_axis[1].abs() < 1e-6);
+        assert!((x_axis[2] + 1.0).abs() < 1e-6);
+    }
 }
@@ -0,0 +1,203 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Normal Generation - smooth shading with hard creases
+//!
+//! Explicit meshes (faceted breps, swept disks) come without normals and are
+//! flat-shaded by default, so pipes and curved surfaces look faceted. Here
+//! corners that share a position average the normals of all adjacent faces
+//! that meet at less than the crease angle; sharper edges stay hard, which
+//! splits the vertex.
+
+use crate::mesh::Mesh;
+use nalgebra::{Point3, Vector3};
+use rustc_hash::FxHashMap;
+
+/// Output vertex identity: welded position, quantized normal, color bits
+type VertexKey = ([i64; 3], [i32; 3], [u32; 4]);
+
+/// Default crease angle: edges sharper than 30° stay hard
+pub const DEFAULT_CREASE_ANGLE: f64 = std::f64::consts::PI / 6.0;
+
+/// Replace the normals of a mesh with crease-aware smooth normals
+///
+/// `crease_angle` is in radians: 0 gives flat shading, π smooths everything.
+/// Vertices are duplicated wherever a crease runs through them, and welded
+/// where faces of the same smooth surface had separate copies. Vertex
+/// colors follow their vertices.
+pub fn smooth_normals(mesh: &mut Mesh, crease_angle: f64) {
+    let vertex_count = mesh.vertex_count();
+    let corner_count = mesh.indices.len() - mesh.indices.len() % 3;
+    if corner_count == 0
+        || mesh.indices[..corner_count]
+            .iter()
+            .any(|&i| i as usize >= vertex_count)
+    {
+        return;
+    }
+
+    let position = |i: u32| {
+        let i = i as usize * 3;
+        Point3::new(
+            mesh.positions[i] as f64,
+            mesh.positions[i + 1] as f64,
+            mesh.positions[i + 2] as f64,
+        )
+    };
+
+    // Unit face normals, and per corner weighted by the corner angle so the
+    // result does not depend on how a face was split into triangles
+    let face_units: Vec<Vector3<f64>> = mesh.indices[..corner_count]
+        .chunks_exact(3)
+        .map(|t| {
+            let p0 = position(t[0]);
+            (position(t[1]) - p0)
+                .cross(&(position(t[2]) - p0))
+                .try_normalize(1e-20)
+                .unwrap_or_else(Vector3::zeros)
+        })
+        .collect();
+    let corner_weighted: Vec<Vector3<f64>> = (0..corner_count)
+        .map(|corner| {
+            let t = corner - corner % 3;
+            let p = position(mesh.indices[corner]);
+            let next = position(mesh.indices[t + (corner + 1) % 3]);
+            let prev = position(mesh.indices[t + (corner + 2) % 3]);
+            face_units[corner / 3] * (next - p).angle(&(prev - p))
+        })
+        .collect();
+
+    // Corners at the same position, within a tolerance relative to the mesh size
+    let (min, max) = mesh.bounds();
+    let tolerance = ((max - min).norm() as f64 * 1e-6).max(1e-9);
+    let corner_keys: Vec<[i64; 3]> = mesh.indices[..corner_count]
+        .iter()
+        .map(|&vertex| {
+            let p = position(vertex);
+            [p.x, p.y, p.z].map(|c| (c / tolerance).round() as i64)
+        })
+        .collect();
+    let mut welded: FxHashMap<[i64; 3], Vec<usize>> = FxHashMap::default();
+    for (corner, key) in corner_keys.iter().enumerate() {
+        welded.entry(*key).or_default().push(corner);
+    }
+
+    let cos_limit = crease_angle.clamp(0.0, std::f64::consts::PI).cos() - 1e-9;
+    let mut corner_normals = vec![Vector3::z(); corner_count];
+    for corners in welded.values() {
+        for &corner in corners {
+            let face = face_units[corner / 3];
+            let sum: Vector3<f64> = corners
+                .iter()
+                .filter(|&&other| face_units[other / 3].dot(&face) >= cos_limit)
+                .map(|&other| corner_weighted[other])
+                .sum();
+            corner_normals[corner] = sum
+                .try_normalize(1e-20)
+                .or_else(|| face.try_normalize(1e-20))
+                .unwrap_or_else(Vector3::z);
+        }
+    }
+
+    // Emit one vertex per (position, normal, color)
+    let has_colors = mesh.colors.len() == vertex_count * 4;
+    let mut positions = Vec::with_capacity(mesh.positions.len());
+    let mut normals = Vec::with_capacity(mesh.positions.len());
+    let mut colors = Vec::new();
+    let mut indices = Vec::with_capacity(corner_count);
+    let mut emitted: FxHashMap<VertexKey, u32> = FxHashMap::default();
+    for (corner, &vertex) in mesh.indices[..corner_count].iter().enumerate() {
+        let normal = corner_normals[corner];
+        let v = vertex as usize;
+        let color = match has_colors {
+            true => [0, 1, 2, 3].map(|k| mesh.colors[v * 4 + k].to_bits()),
+            false => [0; 4],
+        };
+        let key = (
+            corner_keys[corner],
+            [normal.x, normal.y, normal.z].map(|c| (c * 1e4).round() as i32),
+            color,
+        );
+        let index = *emitted.entry(key).or_insert_with(|| {
+            positions.extend_from_slice(&mesh.positions[v * 3..v * 3 + 3]);
+            normals.extend_from_slice(&[normal.x as f32, normal.y as f32, normal.z as f32]);
+            if has_colors {
+                colors.extend_from_slice(&mesh.colors[v * 4..v * 4 + 4]);
+            }
+            (positions.len() / 3 - 1) as u32
+        });
+        indices.push(index);
+    }
+
+    mesh.positions = positions;
+    mesh.normals = normals;
+    mesh.colors = colors;
+    mesh.indices = indices;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Open cylinder side with separate vertices per quad, as faceted breps have
+    fn faceted_cylinder(segments: usize) -> Mesh {
+        let mut mesh = Mesh::new();
+        for i in 0..segments {
+            let a0 = i as f64 / segments as f64 * std::f64::consts::TAU;
+            let a1 = (i + 1) as f64 / segments as f64 * std::f64::consts::TAU;
+            let base = mesh.vertex_count() as u32;
+            for (a, z) in [(a0, 0.0), (a1, 0.0), (a1, 1.0), (a0, 1.0)] {
+                mesh.add_vertex(Point3::new(a.cos(), a.sin(), z), Vector3::zeros());
+            }
+            mesh.add_triangle(base, base + 1, base + 2);
+            mesh.add_triangle(base, base + 2, base + 3);
+        }
+        mesh.normals.clear();
+        mesh
+    }
+
+    #[test]
+    fn test_cylinder_is_smoothed() {
+        let mut mesh = faceted_cylinder(16);
+        smooth_normals(&mut mesh, DEFAULT_CREASE_ANGLE);
+
+        // Neighbouring quads share their edge vertices now
+        assert_eq!(mesh.vertex_count(), 32);
+        for (p, n) in mesh
+            .positions
+            .chunks_exact(3)
+            .zip(mesh.normals.chunks_exact(3))
+        {
+            // Radial normals
+            assert!((p[0] - n[0]).abs() < 1e-5 && (p[1] - n[1]).abs() < 1e-5);
+            assert!(n[2].abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_box_edges_stay_hard() {
+        let mut mesh = crate::extrusion::extrude_profile(
+            &crate::profile::create_rectangle(1.0, 1.0),
+            1.0,
+            None,
+        )
+        .unwrap();
+        mesh.normals.clear();
+        smooth_normals(&mut mesh, DEFAULT_CREASE_ANGLE);
+
+        // Every corner of the box carries three face normals
+        assert_eq!(mesh.vertex_count(), 24);
+        for n in mesh.normals.chunks_exact(3) {
+            let axis_aligned = n.iter().filter(|c| (c.abs() - 1.0).abs() < 1e-6).count();
+            assert_eq!(axis_aligned, 1);
+        }
+    }
+
+    #[test]
+    fn test_flat_crease_angle() {
+        let mut mesh = faceted_cylinder(8);
+        smooth_normals(&mut mesh, 0.0);
+        assert_eq!(mesh.vertex_count(), 32);
+    }
+}
@@ -0,0 +1,572 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Mesh Optimization - welding, vertex cache and overdraw ordering
+//!
+//! Explicit geometry emits one vertex per face corner, so a faceted brep box
+//! carries four copies of every corner with identical normals. Welding
+//! merges those copies; the index buffer is then reordered for the GPU's
+//! post-transform vertex cache (Forsyth's linear-speed algorithm) and, in
+//! clusters, front-to-back by facing to cut overdraw (Sander et al., as in
+//! meshoptimizer). Finally vertices are laid out in first-use order.
+//! Triangle count and winding never change, only their order.
+
+use crate::mesh::Mesh;
+use rustc_hash::FxHashMap;
+
+/// Welded vertex identity: grid cell, quantized normal, color bits
+type WeldKey = ([i64; 3], [i32; 3], [u32; 4]);
+
+/// Default weld tolerance: 0.01 mm, for meshes in metres
+pub const DEFAULT_WELD_TOLERANCE: f64 = 1e-5;
+
+/// Cache size the vertex cache optimizer scores against
+const CACHE_SIZE: usize = 32;
+/// Score of the vertices of the last emitted triangle
+const LAST_TRIANGLE_SCORE: f32 = 0.75;
+const CACHE_DECAY_POWER: f32 = 1.5;
+const VALENCE_BOOST_SCALE: f32 = 2.0;
+const VALENCE_BOOST_POWER: f32 = 0.5;
+/// FIFO cache size used to find overdraw clusters
+const FIFO_CACHE_SIZE: u32 = 16;
+
+/// Which optimization steps [`optimize_mesh`] runs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OptimizeOptions {
+    /// Merge vertices closer than this (mesh units); None keeps them apart
+    pub weld_tolerance: Option<f64>,
+    /// Reorder triangles for the post-transform vertex cache
+    pub vertex_cache: bool,
+    /// Reorder triangle clusters against overdraw, allowing the cache miss
+    /// ratio to grow by this factor (1.05 is a good default); None skips it
+    pub overdraw_threshold: Option<f32>,
+}
+
+impl Default for OptimizeOptions {
+    fn default() -> Self {
+        Self {
+            weld_tolerance: Some(DEFAULT_WELD_TOLERANCE),
+            vertex_cache: true,
+            overdraw_threshold: Some(1.05),
+        }
+    }
+}
+
+impl OptimizeOptions {
+    /// Weld tolerance given in file length units, for meshes that were
+    /// scaled to metres by `unit_scale` (e.g. 0.001 for millimetre files)
+    pub fn with_file_tolerance(mut self, tolerance: f64, unit_scale: f64) -> Self {
+        self.weld_tolerance = Some(tolerance * unit_scale);
+        self
+    }
+}
+
+/// Run the enabled optimization steps on a mesh
+pub fn optimize_mesh(mesh: &mut Mesh, options: &OptimizeOptions) {
+    let vertex_count = mesh.vertex_count();
+    if mesh.indices.len() < 3 || mesh.indices.iter().any(|&i| i as usize >= vertex_count) {
+        return;
+    }
+
+    if let Some(tolerance) = options.weld_tolerance {
+        weld_vertices(mesh, tolerance);
+    }
+    if options.vertex_cache {
+        let vertex_count = mesh.vertex_count();
+        optimize_vertex_cache(&mut mesh.indices, vertex_count);
+    }
+    if let Some(threshold) = options.overdraw_threshold {
+        optimize_overdraw(&mut mesh.indices, &mesh.positions, threshold);
+    }
+    optimize_vertex_fetch(mesh);
+}
+
+/// Merge vertices with the same position, normal and color
+///
+/// Positions match when they fall into the same cell of a `tolerance` grid;
+/// normals and colors have to match (nearly) exactly, so hard edges stay hard.
+pub fn weld_vertices(mesh: &mut Mesh, tolerance: f64) {
+    let vertex_count = mesh.vertex_count();
+    if vertex_count == 0 || tolerance <= 0.0 || !tolerance.is_finite() {
+        return;
+    }
+
+    let has_normals = mesh.normals.len() == mesh.positions.len();
+    let has_colors = mesh.colors.len() == vertex_count * 4;
+    let mut welded: FxHashMap<WeldKey, u32> = FxHashMap::default();
+    let mut remap = Vec::with_capacity(vertex_count);
+    let mut positions = Vec::with_capacity(mesh.positions.len());
+    let mut normals = Vec::new();
+    let mut colors = Vec::new();
+
+    for v in 0..vertex_count {
+        let p = &mesh.positions[v * 3..v * 3 + 3];
+        let normal = match has_normals {
+            true => [0, 1, 2].map(|k| (mesh.normals[v * 3 + k] * 1e4).round() as i32),
+            false => [0; 3],
+        };
+        let color = match has_colors {
+            true => [0, 1, 2, 3].map(|k| mesh.colors[v * 4 + k].to_bits()),
+            false => [0; 4],
+        };
+        let key = (
+            [0, 1, 2].map(|k| (p[k] as f64 / tolerance).round() as i64),
+            normal,
+            color,
+        );
+        let index = *welded.entry(key).or_insert_with(|| {
+            positions.extend_from_slice(p);
+            if has_normals {
+                normals.extend_from_slice(&mesh.normals[v * 3..v * 3 + 3]);
+            }
+            if has_colors {
+                colors.extend_from_slice(&mesh.colors[v * 4..v * 4 + 4]);
+            }
+            (positions.len() / 3 - 1) as u32
+        });
+        remap.push(index);
+    }
+
+    if positions.len() == mesh.positions.len() {
+        return;
+    }
+    for index in &mut mesh.indices {
+        *index = remap[*index as usize];
+    }
+    mesh.positions = positions;
+    if has_normals {
+        mesh.normals = normals;
+    }
+    if has_colors {
+        mesh.colors = colors;
+    }
+}
+
+/// Score of a vertex for the vertex cache optimizer
+fn vertex_score(cache_position: Option<usize>, remaining: u32) -> f32 {
+    if remaining == 0 {
+        return -1.0;
+    }
+    let cache_score = match cache_position {
+        Some(position) if position < 3 => LAST_TRIANGLE_SCORE,
+        Some(position) => {
+            let scale = 1.0 / (CACHE_SIZE - 3) as f32;
+            (1.0 - (position - 3) as f32 * scale).powf(CACHE_DECAY_POWER)
+        }
+        None => 0.0,
+    };
+    cache_score + VALENCE_BOOST_SCALE * (remaining as f32).powf(-VALENCE_BOOST_POWER)
+}
+
+/// Reorder triangles so consecutive ones reuse recently transformed vertices
+pub fn optimize_vertex_cache(indices: &mut [u32], vertex_count: usize) {
+    let triangle_count = indices.len() / 3;
+    if triangle_count < 2 || indices.iter().any(|&i| i as usize >= vertex_count) {
+        return;
+    }
+    let corners = &indices[..triangle_count * 3];
+
+    // Live triangles per vertex: adjacency[offsets[v]..offsets[v] + remaining[v]]
+    let mut offsets = vec![0u32; vertex_count + 1];
+    for &v in corners {
+        offsets[v as usize + 1] += 1;
+    }
+    for v in 0..vertex_count {
+        offsets[v + 1] += offsets[v];
+    }
+    let mut remaining: Vec<u32> = (0..vertex_count)
+        .map(|v| offsets[v + 1] - offsets[v])
+        .collect();
+    let mut adjacency = vec![0u32; corners.len()];
+    let mut fill = offsets.clone();
+    for (t, triangle) in corners.chunks_exact(3).enumerate() {
+        for &v in triangle {
+            adjacency[fill[v as usize] as usize] = t as u32;
+            fill[v as usize] += 1;
+        }
+    }
+
+    let mut scores: Vec<f32> = (0..vertex_count)
+        .map(|v| vertex_score(None, remaining[v]))
+        .collect();
+    let triangle_score = |t: usize, scores: &[f32]| -> f32 {
+        corners[t * 3..t * 3 + 3]
+            .iter()
+            .map(|&v| scores[v as usize])
+            .sum()
+    };
+
+    let mut emitted = vec![false; triangle_count];
+    let mut output = Vec::with_capacity(corners.len());
+    let mut cache: Vec<u32> = Vec::with_capacity(CACHE_SIZE + 3);
+    let mut cursor = 0;
+    let mut best = (0..triangle_count)
+        .max_by(|&a, &b| triangle_score(a, &scores).total_cmp(&triangle_score(b, &scores)));
+
+    while output.len() < corners.len() {
+        let t = match best {
+            Some(t) => t,
+            None => {
+                while emitted[cursor] {
+                    cursor += 1;
+                }
+                cursor
+            }
+        };
+        let triangle = [corners[t * 3], corners[t * 3 + 1], corners[t * 3 + 2]];
+        output.extend_from_slice(&triangle);
+        emitted[t] = true;
+
+        let mut new_cache = Vec::with_capacity(CACHE_SIZE + 3);
+        for &v in &triangle {
+            // Drop the triangle from the vertex's live list
+            let start = offsets[v as usize] as usize;
+            let live = &mut adjacency[start..start + remaining[v as usize] as usize];
+            if let Some(k) = live.iter().position(|&other| other as usize == t) {
+                let last = live.len() - 1;
+                live.swap(k, last);
+                remaining[v as usize] -= 1;
+            }
+            if !new_cache.contains(&v) {
+                new_cache.push(v);
+            }
+        }
+        for &v in &cache {
+            if !triangle.contains(&v) {
+                new_cache.push(v);
+            }
+        }
+        for &v in new_cache.iter().skip(CACHE_SIZE) {
+            scores[v as usize] = vertex_score(None, remaining[v as usize]);
+        }
+        new_cache.truncate(CACHE_SIZE);
+        for (position, &v) in new_cache.iter().enumerate() {
+            scores[v as usize] = vertex_score(Some(position), remaining[v as usize]);
+        }
+
+        // Next triangle: the best one touching the cache
+        best = None;
+        let mut best_score = f32::MIN;
+        for &v in &new_cache {
+            let start = offsets[v as usize] as usize;
+            for &other in &adjacency[start..start + remaining[v as usize] as usize] {
+                let score = triangle_score(other as usize, &scores);
+                if score > best_score {
+                    best_score = score;
+                    best = Some(other as usize);
+                }
+            }
+        }
+        cache = new_cache;
+    }
+
+    indices[..output.len()].copy_from_slice(&output);
+}
+
+/// Cache misses of a triangle in a simulated FIFO cache
+fn fifo_misses(triangle: &[u32], timestamps: &mut [u32], time: &mut u32) -> u32 {
+    let mut misses = 0;
+    for &v in triangle {
+        if *time - timestamps[v as usize] > FIFO_CACHE_SIZE {
+            timestamps[v as usize] = *time;
+            *time += 1;
+            misses += 1;
+        }
+    }
+    misses
+}
+
+/// Average cache miss ratio: transformed vertices per triangle in a
+/// simulated 16-entry FIFO cache (0.5 is ideal for grids, 3 is worst)
+pub fn average_cache_miss_ratio(indices: &[u32], vertex_count: usize) -> f32 {
+    let triangle_count = indices.len() / 3;
+    if triangle_count == 0 || indices.iter().any(|&i| i as usize >= vertex_count) {
+        return 0.0;
+    }
+    let mut timestamps = vec![0u32; vertex_count];
+    let mut time = FIFO_CACHE_SIZE + 1;
+    let misses: u32 = indices
+        .chunks_exact(3)
+        .map(|t| fifo_misses(t, &mut timestamps, &mut time))
+        .sum();
+    misses as f32 / triangle_count as f32
+}
+
+/// Reorder clusters of triangles so outward-facing ones are drawn first
+///
+/// Clusters start wherever the cache-optimized order restarts (a triangle
+/// with three misses) and are split further while the cache miss ratio stays
+/// within `threshold` of the cluster's. Run after [`optimize_vertex_cache`].
+pub fn optimize_overdraw(indices: &mut [u32], positions: &[f32], threshold: f32) {
+    let vertex_count = positions.len() / 3;
+    let triangle_count = indices.len() / 3;
+    if triangle_count < 2 || indices.iter().any(|&i| i as usize >= vertex_count) {
+        return;
+    }
+
+    // Hard boundaries: cache restarts
+    let mut timestamps = vec![0u32; vertex_count];
+    let mut time = FIFO_CACHE_SIZE + 1;
+    let mut hard = Vec::new();
+    for (t, triangle) in indices.chunks_exact(3).enumerate() {
+        if fifo_misses(triangle, &mut timestamps, &mut time) == 3 {
+            hard.push(t);
+        }
+    }
+    if hard.first() != Some(&0) {
+        hard.insert(0, 0);
+    }
+    hard.push(triangle_count);
+
+    // Soft boundaries: split a cluster where its local miss ratio is low enough
+    let mut clusters = Vec::new();
+    for window in hard.windows(2) {
+        let (start, end) = (window[0], window[1]);
+        let cluster = &indices[start * 3..end * 3];
+        let limit = average_cache_miss_ratio(cluster, vertex_count) * threshold;
+
+        let mut timestamps = vec![0u32; vertex_count];
+        let mut time = FIFO_CACHE_SIZE + 1;
+        let mut soft_start = start;
+        let mut misses = 0;
+        for t in start..end {
+            misses += fifo_misses(&indices[t * 3..t * 3 + 3], &mut timestamps, &mut time);
+            if t + 1 < end && misses as f32 / (t + 1 - soft_start) as f32 <= limit {
+                clusters.push(soft_start..t + 1);
+                soft_start = t + 1;
+                misses = 0;
+                time += FIFO_CACHE_SIZE + 1;
+            }
+        }
+        clusters.push(soft_start..end);
+    }
+    if clusters.len() < 2 {
+        return;
+    }
+
+    let position = |v: u32| {
+        let i = v as usize * 3;
+        [positions[i], positions[i + 1], positions[i + 2]]
+    };
+
+    // Area-weighted centroid and normal of each cluster, and of the mesh
+    let mut mesh_centroid = [0.0f32; 3];
+    let mut mesh_area = 0.0f32;
+    let cluster_shapes: Vec<([f32; 3], [f32; 3])> = clusters
+        .iter()
+        .map(|range| {
+            let mut centroid = [0.0f32; 3];
+            let mut normal = [0.0f32; 3];
+            let mut area = 0.0f32;
+            for t in range.clone() {
+                let [a, b, c] = [0, 1, 2].map(|k| position(indices[t * 3 + k]));
+                let u = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+                let w = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+                let n = [
+                    u[1] * w[2] - u[2] * w[1],
+                    u[2] * w[0] - u[0] * w[2],
+                    u[0] * w[1] - u[1] * w[0],
+                ];
+                let triangle_area = (n[0] * n[0] + n[1] * n[1] + n[2] * n[2]).sqrt();
+                for k in 0..3 {
+                    centroid[k] += (a[k] + b[k] + c[k]) / 3.0 * triangle_area;
+                    normal[k] += n[k];
+                }
+                area += triangle_area;
+            }
+            for k in 0..3 {
+                mesh_centroid[k] += centroid[k];
+            }
+            mesh_area += area;
+            if area > 0.0 {
+                centroid = centroid.map(|c| c / area);
+            }
+            (centroid, normal)
+        })
+        .collect();
+    if mesh_area > 0.0 {
+        mesh_centroid = mesh_centroid.map(|c| c / mesh_area);
+    }
+
+    let sort_keys: Vec<f32> = cluster_shapes
+        .iter()
+        .map(|(centroid, normal)| {
+            let length = (normal[0] * normal[0] + normal[1] * normal[1] + normal[2] * normal[2])
+                .sqrt()
+                .max(f32::MIN_POSITIVE);
+            (0..3)
+                .map(|k| (centroid[k] - mesh_centroid[k]) * normal[k] / length)
+                .sum()
+        })
+        .collect();
+    let mut order: Vec<usize> = (0..clusters.len()).collect();
+    order.sort_by(|&a, &b| sort_keys[b].total_cmp(&sort_keys[a]));
+
+    let mut output = Vec::with_capacity(triangle_count * 3);
+    for cluster in order {
+        let range = &clusters[cluster];
+        output.extend_from_slice(&indices[range.start * 3..range.end * 3]);
+    }
+    indices[..output.len()].copy_from_slice(&output);
+}
+
+/// Lay out vertices in the order the index buffer first uses them
+///
+/// Unreferenced vertices are dropped.
+pub fn optimize_vertex_fetch(mesh: &mut Mesh) {
+    let vertex_count = mesh.vertex_count();
+    if mesh.indices.iter().any(|&i| i as usize >= vertex_count) {
+        return;
+    }
+
+    let has_normals = mesh.normals.len() == mesh.positions.len();
+    let has_colors = mesh.colors.len() == vertex_count * 4;
+    let mut remap = vec![u32::MAX; vertex_count];
+    let mut positions = Vec::with_capacity(mesh.positions.len());
+    let mut normals = Vec::with_capacity(if has_normals { mesh.normals.len() } else { 0 });
+    let mut colors = Vec::with_capacity(if has_colors { mesh.colors.len() } else { 0 });
+
+    for index in &mut mesh.indices {
+        let v = *index as usize;
+        if remap[v] == u32::MAX {
+            remap[v] = (positions.len() / 3) as u32;
+            positions.extend_from_slice(&mesh.positions[v * 3..v * 3 + 3]);
+            if has_normals {
+                normals.extend_from_slice(&mesh.normals[v * 3..v * 3 + 3]);
+            }
+            if has_colors {
+                colors.extend_from_slice(&mesh.colors[v * 4..v * 4 + 4]);
+            }
+        }
+        *index = remap[v];
+    }
+
+    mesh.positions = positions;
+    if has_normals {
+        mesh.normals = normals;
+    }
+    if has_colors {
+        mesh.colors = colors;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Grid of `n` x `n` quads with separate vertices per quad and shuffled triangles
+    fn faceted_grid(n: usize) -> Mesh {
+        let mut mesh = Mesh::new();
+        for y in 0..n {
+            for x in 0..n {
+                let base = (mesh.positions.len() / 3) as u32;
+                for (dx, dy) in [(0, 0), (1, 0), (1, 1), (0, 1)] {
+                    mesh.positions
+                        .extend_from_slice(&[(x + dx) as f32, (y + dy) as f32, 0.0]);
+                    mesh.normals.extend_from_slice(&[0.0, 0.0, 1.0]);
+                }
+                mesh.indices.extend_from_slice(&[
+                    base,
+                    base + 1,
+                    base + 2,
+                    base,
+                    base + 2,
+                    base + 3,
+                ]);
+            }
+        }
+        // Deterministic shuffle of the triangle order
+        let triangle_count = mesh.indices.len() / 3;
+        let mut triangles: Vec<[u32; 3]> = mesh
+            .indices
+            .chunks_exact(3)
+            .map(|t| [t[0], t[1], t[2]])
+            .collect();
+        for i in 0..triangle_count {
+            triangles.swap(i, (i * 7919 + 13) % triangle_count);
+        }
+        mesh.indices = triangles.concat();
+        mesh
+    }
+
+    /// Triangles as sorted position triples, independent of vertex layout
+    fn triangle_set(mesh: &Mesh) -> Vec<[[i32; 3]; 3]> {
+        let mut set: Vec<[[i32; 3]; 3]> = mesh
+            .indices
+            .chunks_exact(3)
+            .map(|t| {
+                let corner = |v: u32| {
+                    let i = v as usize * 3;
+                    [0, 1, 2].map(|k| (mesh.positions[i + k] * 1000.0).round() as i32)
+                };
+                // Rotate so the smallest corner comes first, keeping winding
+                let corners = [corner(t[0]), corner(t[1]), corner(t[2])];
+                let first = (0..3).min_by_key(|&k| corners[k]).unwrap();
+                [0, 1, 2].map(|k| corners[(first + k) % 3])
+            })
+            .collect();
+        set.sort();
+        set
+    }
+
+    #[test]
+    fn test_weld_merges_corner_copies() {
+        let mut mesh = faceted_grid(4);
+        weld_vertices(&mut mesh, DEFAULT_WELD_TOLERANCE);
+        assert_eq!(mesh.vertex_count(), 25);
+        assert_eq!(mesh.normals.len(), mesh.positions.len());
+    }
+
+    #[test]
+    fn test_weld_keeps_hard_edges() {
+        let mut mesh = crate::extrusion::extrude_profile(
+            &crate::profile::create_rectangle(1.0, 1.0),
+            1.0,
+            None,
+        )
+        .unwrap();
+        weld_vertices(&mut mesh, DEFAULT_WELD_TOLERANCE);
+        // Every box corner keeps one vertex per adjacent side
+        assert_eq!(mesh.vertex_count(), 24);
+    }
+
+    #[test]
+    fn test_vertex_cache_lowers_miss_ratio() {
+        let mut mesh = faceted_grid(16);
+        weld_vertices(&mut mesh, DEFAULT_WELD_TOLERANCE);
+        let before = average_cache_miss_ratio(&mesh.indices, mesh.vertex_count());
+        let triangles = triangle_set(&mesh);
+
+        let vertex_count = mesh.vertex_count();
+        optimize_vertex_cache(&mut mesh.indices, vertex_count);
+        let after = average_cache_miss_ratio(&mesh.indices, mesh.vertex_count());
+        assert!(after < before * 0.75, "ACMR {} -> {}", before, after);
+        assert_eq!(triangle_set(&mesh), triangles);
+    }
+
+    #[test]
+    fn test_optimize_mesh_preserves_triangles() {
+        let mut mesh = faceted_grid(8);
+        let triangles = triangle_set(&mesh);
+        let triangle_count = mesh.triangle_count();
+
+        optimize_mesh(&mut mesh, &OptimizeOptions::default());
+        assert_eq!(mesh.triangle_count(), triangle_count);
+        assert_eq!(triangle_set(&mesh), triangles);
+        assert_eq!(mesh.vertex_count(), 81);
+
+        // Vertices come in first-use order
+        let mut next = 0;
+        for &i in &mesh.indices {
+            assert!(i <= next);
+            next = next.max(i + 1);
+        }
+    }
+
+    #[test]
+    fn test_file_tolerance() {
+        let options = OptimizeOptions::default().with_file_tolerance(0.01, 0.001);
+        assert_eq!(options.weld_tolerance, Some(0.01 * 0.001));
+    }
+}
@@ -0,0 +1,139 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Geometry Options - Tessellation tolerances and element filters
+//!
+//! One [`GeometryOptions`] value configures a [`crate::GeometryRouter`]:
+//! how finely curves are sampled and which elements are left out.
+
+use crate::Mesh;
+use ifc_lite_core::IfcType;
+use std::f64::consts::PI;
+
+/// Default maximum distance between a tessellated curve and the true curve, in metres
+pub const DEFAULT_DEFLECTION: f64 = 0.01;
+/// Default upper bound on the segments of a full circle
+pub const DEFAULT_MAX_SEGMENTS: usize = 128;
+/// Segments of a full circle below which curves are never sampled,
+/// however small the radius
+const MIN_CIRCLE_SEGMENTS: usize = 12;
+
+/// How geometry is tessellated and which elements are processed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GeometryOptions {
+    /// Maximum distance between a tessellated curve and the true curve, in
+    /// metres; smaller values give smoother circles and arcs
+    pub deflection: f64,
+    /// Upper bound on the segments of a full circle; arcs get their share
+    pub max_segments: usize,
+    /// Produce no geometry for IfcOpeningElement
+    pub skip_openings: bool,
+    /// Produce no geometry for IfcSpace
+    pub skip_spaces: bool,
+    /// Drop element meshes whose bounding box diagonal is shorter than
+    /// this, in metres; 0 keeps everything
+    pub min_feature_size: f64,
+}
+
+impl Default for GeometryOptions {
+    fn default() -> Self {
+        Self {
+            deflection: DEFAULT_DEFLECTION,
+            max_segments: DEFAULT_MAX_SEGMENTS,
+            skip_openings: false,
+            skip_spaces: false,
+            min_feature_size: 0.0,
+        }
+    }
+}
+
+impl GeometryOptions {
+    /// Number of segments for an arc of `radius` sweeping `sweep` radians
+    ///
+    /// `unit_scale` converts file units to metres, so the deflection is the
+    /// same whether the model is in millimetres or metres.
+    pub fn arc_segments(&self, radius: f64, sweep: f64, unit_scale: f64) -> usize {
+        let sweep = sweep.abs();
+        let turns = sweep / (2.0 * PI);
+        let tolerance = self.deflection.max(1e-6) / unit_scale;
+        let step = if radius > tolerance {
+            2.0 * (1.0 - tolerance / radius).acos()
+        } else {
+            2.0 * PI
+        };
+        let max = (turns * self.max_segments.max(3) as f64).ceil();
+        let min = (turns * MIN_CIRCLE_SEGMENTS as f64).ceil().min(max);
+        let segments = (sweep / step).ceil().clamp(min, max);
+        (segments as usize).max(2)
+    }
+
+    /// Number of segments for a full circle of `radius`, at least
+    /// `at_least` unless [`Self::max_segments`] is lower
+    pub fn circle_segments(&self, radius: f64, unit_scale: f64, at_least: usize) -> usize {
+        self.arc_segments(radius, 2.0 * PI, unit_scale)
+            .max(at_least)
+            .min(self.max_segments.max(3))
+    }
+
+    /// Whether elements of this type are left without geometry
+    pub fn skips(&self, ifc_type: IfcType) -> bool {
+        match ifc_type {
+            IfcType::IfcOpeningElement => self.skip_openings,
+            IfcType::IfcSpace => self.skip_spaces,
+            _ => false,
+        }
+    }
+
+    /// Whether a mesh in metres is smaller than [`Self::min_feature_size`]
+    pub fn is_below_feature_size(&self, mesh: &Mesh) -> bool {
+        if self.min_feature_size <= 0.0 || mesh.is_empty() {
+            return false;
+        }
+        let (min, max) = mesh.bounds();
+        ((max - min).norm() as f64) < self.min_feature_size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deflection_and_max_segments() {
+        let coarse = GeometryOptions::default();
+        let fine = GeometryOptions {
+            deflection: 0.001,
+            ..coarse
+        };
+        assert!(fine.arc_segments(1.0, 2.0 * PI, 1.0) > coarse.arc_segments(1.0, 2.0 * PI, 1.0));
+
+        let capped = GeometryOptions {
+            max_segments: 8,
+            ..fine
+        };
+        assert_eq!(capped.arc_segments(1.0, 2.0 * PI, 1.0), 8);
+        assert_eq!(capped.circle_segments(1.0, 1.0, 24), 8);
+        assert_eq!(coarse.circle_segments(0.1, 1.0, 24), 24);
+    }
+
+    #[test]
+    fn test_skips_and_feature_size() {
+        let options = GeometryOptions {
+            skip_spaces: true,
+            min_feature_size: 0.05,
+            ..Default::default()
+        };
+        assert!(options.skips(IfcType::IfcSpace));
+        assert!(!options.skips(IfcType::IfcOpeningElement));
+        assert!(!options.skips(IfcType::IfcWall));
+
+        let mut mesh = Mesh::new();
+        mesh.add_vertex(crate::Point3::new(0.0, 0.0, 0.0), crate::Vector3::z());
+        mesh.add_vertex(crate::Point3::new(0.01, 0.0, 0.0), crate::Vector3::z());
+        mesh.add_vertex(crate::Point3::new(0.0, 0.01, 0.0), crate::Vector3::z());
+        mesh.add_triangle(0, 1, 2);
+        assert!(options.is_below_feature_size(&mesh));
+        assert!(!GeometryOptions::default().is_below_feature_size(&mesh));
+    }
+}
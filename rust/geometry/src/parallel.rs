@@ -0,0 +1,100 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Parallel element processing on the rayon pool
+//!
+//! [`GeometryRouter`] and [`EntityDecoder`] keep per-instance caches and are
+//! not shared between threads. Each worker instead gets a fork of both: the
+//! file content and entity index are read-only and shared, the caches are
+//! per thread. On wasm without threads rayon runs everything on the calling
+//! thread, so the same code path works everywhere.
+
+use crate::router::GeometryRouter;
+use ifc_lite_core::EntityDecoder;
+use rayon::prelude::*;
+
+/// Chunks per thread, so threads that finish early can take more work
+const CHUNKS_PER_THREAD: usize = 4;
+
+/// Run `process` for every id in `ids` on the rayon pool
+///
+/// Ids are split into contiguous chunks, each handled with its own forked
+/// decoder and router. Results come back in the order of `ids`. With a single
+/// thread, `process` gets `decoder` and `router` themselves.
+pub fn process_parallel<'a, T, F>(
+    ids: &[u32],
+    decoder: &mut EntityDecoder<'a>,
+    router: &GeometryRouter,
+    process: F,
+) -> Vec<T>
+where
+    T: Send,
+    F: Fn(u32, &mut EntityDecoder<'a>, &GeometryRouter) -> T + Sync,
+{
+    let threads = rayon::current_num_threads();
+    if threads <= 1 || ids.len() <= 1 {
+        return ids.iter().map(|&id| process(id, decoder, router)).collect();
+    }
+
+    let chunk_size = ids.len().div_ceil(threads * CHUNKS_PER_THREAD).max(1);
+    let workers: Vec<(EntityDecoder<'a>, GeometryRouter)> = (0..ids.len().div_ceil(chunk_size))
+        .map(|_| (decoder.fork(), router.fork()))
+        .collect();
+
+    let chunks: Vec<Vec<T>> = ids
+        .par_chunks(chunk_size)
+        .zip(workers)
+        .map(|(chunk, (mut decoder, router))| {
+            chunk
+                .iter()
+                .map(|&id| process(id, &mut decoder, &router))
+                .collect()
+        })
+        .collect();
+    chunks.into_iter().flatten().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ifc_lite_core::{build_entity_index, EntityScanner};
+
+    #[test]
+    fn test_parallel_matches_sequential() {
+        let content = include_str!(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/examples/data/sample.ifc"
+        ));
+        let mut decoder = EntityDecoder::with_index(content, build_entity_index(content));
+        let router = GeometryRouter::with_units(content, &mut decoder);
+
+        let mut ids = Vec::new();
+        let mut scanner = EntityScanner::new(content);
+        while let Some((id, type_name, _, _)) = scanner.next_entity() {
+            if type_name == "IFCWALL" || type_name == "IFCSLAB" {
+                ids.push(id);
+            }
+        }
+        assert!(!ids.is_empty());
+
+        let triangles = |id: u32, decoder: &mut EntityDecoder, router: &GeometryRouter| {
+            let entity = decoder.decode_by_id(id).unwrap();
+            router
+                .process_element(&entity, decoder)
+                .map(|mesh| mesh.triangle_count())
+                .unwrap_or(0)
+        };
+        let sequential: Vec<usize> = ids
+            .iter()
+            .map(|&id| triangles(id, &mut decoder, &router))
+            .collect();
+
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(4)
+            .build()
+            .unwrap();
+        let parallel = pool.install(|| process_parallel(&ids, &mut decoder, &router, triangles));
+        assert_eq!(parallel, sequential);
+    }
+}
@@ -0,0 +1,212 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Plan Sections - Horizontal cuts through meshes as 2D polylines
+//!
+//! A floor plan is the outline left where a horizontal plane cuts the
+//! model. Each triangle crossing the plane contributes one segment; the
+//! segments are then chained end to end into polylines, closed where the
+//! cut goes all the way around a solid.
+
+use rustc_hash::FxHashMap;
+
+/// Endpoints closer than this (metres) are joined
+const JOIN_TOLERANCE: f32 = 1e-4;
+
+/// Outline of a plan cut in XY
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PlanPolyline {
+    pub points: Vec<[f32; 2]>,
+    /// The last point connects back to the first
+    pub closed: bool,
+}
+
+/// Segments where the plane `z = elevation` cuts the triangles of a mesh
+///
+/// Triangles lying in the plane are skipped, their edges are picked up by
+/// the neighbouring faces.
+pub fn section_segments(positions: &[f32], indices: &[u32], elevation: f32) -> Vec<[[f32; 2]; 2]> {
+    let vertex = |i: u32| {
+        let i = i as usize * 3;
+        [positions[i], positions[i + 1], positions[i + 2]]
+    };
+    // Interpolate with the lower index first so both triangles sharing an
+    // edge produce the exact same point
+    let cut = |a: u32, b: u32| {
+        let (a, b) = if a < b { (a, b) } else { (b, a) };
+        let (pa, pb) = (vertex(a), vertex(b));
+        let t = (elevation - pa[2]) / (pb[2] - pa[2]);
+        [pa[0] + t * (pb[0] - pa[0]), pa[1] + t * (pb[1] - pa[1])]
+    };
+
+    let mut segments = Vec::new();
+    for tri in indices.chunks_exact(3) {
+        if tri.iter().any(|&i| i as usize * 3 + 2 >= positions.len()) {
+            continue;
+        }
+        let above = [tri[0], tri[1], tri[2]].map(|i| vertex(i)[2] >= elevation);
+        let points: Vec<[f32; 2]> = (0..3)
+            .filter(|&e| above[e] != above[(e + 1) % 3])
+            .map(|e| cut(tri[e], tri[(e + 1) % 3]))
+            .collect();
+        if let [a, b] = points[..] {
+            if a != b {
+                segments.push([a, b]);
+            }
+        }
+    }
+    segments
+}
+
+/// Chain segments sharing endpoints into polylines
+///
+/// Points in the middle of straight runs are dropped, so a cut through a
+/// box comes out as four corners.
+pub fn chain_segments(segments: &[[[f32; 2]; 2]]) -> Vec<PlanPolyline> {
+    let key = |p: [f32; 2]| {
+        (
+            (p[0] / JOIN_TOLERANCE).round() as i64,
+            (p[1] / JOIN_TOLERANCE).round() as i64,
+        )
+    };
+    let mut at: FxHashMap<(i64, i64), Vec<usize>> = FxHashMap::default();
+    for (i, segment) in segments.iter().enumerate() {
+        at.entry(key(segment[0])).or_default().push(i);
+        at.entry(key(segment[1])).or_default().push(i);
+    }
+
+    let mut used = vec![false; segments.len()];
+    // Follow unused segments from `end`, appending their far endpoints
+    let walk = |end: [f32; 2], used: &mut Vec<bool>| {
+        let mut points = Vec::new();
+        let mut end = end;
+        while let Some(&next) = at
+            .get(&key(end))
+            .and_then(|ids| ids.iter().find(|&&i| !used[i]))
+        {
+            used[next] = true;
+            let [a, b] = segments[next];
+            end = if key(a) == key(end) { b } else { a };
+            points.push(end);
+        }
+        points
+    };
+
+    let mut polylines = Vec::new();
+    for start in 0..segments.len() {
+        if used[start] {
+            continue;
+        }
+        used[start] = true;
+        let [a, b] = segments[start];
+        let mut points = vec![a, b];
+        points.extend(walk(b, &mut used));
+        let closed = points.len() > 3 && key(points[0]) == key(points[points.len() - 1]);
+        if closed {
+            points.pop();
+        } else {
+            let mut back = walk(a, &mut used);
+            back.reverse();
+            back.extend(points);
+            points = back;
+        }
+        polylines.push(PlanPolyline {
+            points: simplify(points, closed),
+            closed,
+        });
+    }
+    polylines
+}
+
+/// Outline of a mesh cut at `z = elevation`
+pub fn section_polylines(positions: &[f32], indices: &[u32], elevation: f32) -> Vec<PlanPolyline> {
+    chain_segments(&section_segments(positions, indices, elevation))
+}
+
+/// Drop points lying on the line through their neighbours
+fn simplify(points: Vec<[f32; 2]>, closed: bool) -> Vec<[f32; 2]> {
+    let n = points.len();
+    if n < 3 {
+        return points;
+    }
+    let collinear = |a: [f32; 2], b: [f32; 2], c: [f32; 2]| {
+        let (ab, bc) = ([b[0] - a[0], b[1] - a[1]], [c[0] - b[0], c[1] - b[1]]);
+        let cross = ab[0] * bc[1] - ab[1] * bc[0];
+        let dot = ab[0] * bc[0] + ab[1] * bc[1];
+        let scale = (ab[0].hypot(ab[1]) * bc[0].hypot(bc[1])).max(f32::MIN_POSITIVE);
+        dot > 0.0 && cross.abs() / scale < 1e-4
+    };
+    let mut kept = Vec::with_capacity(n);
+    for i in 0..n {
+        let (prev, next) = match (closed, i) {
+            (false, 0) => {
+                kept.push(points[0]);
+                continue;
+            }
+            (false, i) if i == n - 1 => {
+                kept.push(points[i]);
+                continue;
+            }
+            _ => (points[(i + n - 1) % n], points[(i + 1) % n]),
+        };
+        if !collinear(prev, points[i], next) {
+            kept.push(points[i]);
+        }
+    }
+    kept
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extrusion::extrude_profile;
+    use crate::profile::Profile2D;
+    use nalgebra::Point2;
+
+    /// 2 x 1 box, 3 high, with its footprint split into extra triangles
+    fn block() -> crate::Mesh {
+        let outline = vec![
+            Point2::new(0.0, 0.0),
+            Point2::new(1.0, 0.0),
+            Point2::new(2.0, 0.0),
+            Point2::new(2.0, 1.0),
+            Point2::new(0.0, 1.0),
+        ];
+        extrude_profile(&Profile2D::new(outline), 3.0, None).unwrap()
+    }
+
+    #[test]
+    fn test_box_section_is_closed_rectangle() {
+        let mesh = block();
+        let plan = section_polylines(&mesh.positions, &mesh.indices, 1.5);
+        assert_eq!(plan.len(), 1);
+        assert!(plan[0].closed);
+        let mut corners = plan[0].points.clone();
+        corners.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(
+            corners,
+            vec![[0.0, 0.0], [0.0, 1.0], [2.0, 0.0], [2.0, 1.0]]
+        );
+    }
+
+    #[test]
+    fn test_plane_outside_mesh() {
+        let mesh = block();
+        assert!(section_polylines(&mesh.positions, &mesh.indices, 5.0).is_empty());
+        assert!(section_polylines(&mesh.positions, &mesh.indices, -0.1).is_empty());
+    }
+
+    #[test]
+    fn test_open_chain() {
+        let segments = [
+            [[1.0, 0.0], [2.0, 0.0]],
+            [[2.0, 0.0], [2.0, 1.0]],
+            [[0.0, 0.0], [1.0, 0.0]],
+        ];
+        let plan = chain_segments(&segments);
+        assert_eq!(plan.len(), 1);
+        assert!(!plan[0].closed);
+        assert_eq!(plan[0].points, vec![[0.0, 0.0], [2.0, 0.0], [2.0, 1.0]]);
+    }
+}
@@ -7,7 +7,11 @@
 //! High-priority processors for common IFC geometry types.
 
 use crate::{
+    alignment::{AlignmentCurve, DistanceExpression},
+    csg::calculate_normals,
     extrusion::{apply_transform, extrude_profile},
+    options::GeometryOptions,
+    profile::Profile2D,
     profiles::ProfileProcessor,
     Error, Mesh, Point3, Result, Vector3,
 };
@@ -152,8 +156,13 @@ pub struct ExtrudedAreaSolidProcessor {
 impl ExtrudedAreaSolidProcessor {
     /// Create new processor
     pub fn new(schema: IfcSchema) -> Self {
+        Self::with_options(schema, GeometryOptions::default())
+    }
+
+    /// Create processor sampling curves with the given options
+    pub fn with_options(schema: IfcSchema, options: GeometryOptions) -> Self {
         Self {
-            profile_processor: ProfileProcessor::new(schema),
+            profile_processor: ProfileProcessor::with_options(schema, options),
         }
     }
 }
@@ -539,6 +548,7 @@ impl GeometryProcessor for TriangulatedFaceSetProcessor {
             positions,
             normals: Vec::new(),
             indices,
+            colors: Vec::new(),
         })
     }
 
@@ -555,6 +565,7 @@ impl Default for TriangulatedFaceSetProcessor {
 
 /// Face data extracted from IFC for parallel triangulation
 struct FaceData {
+    face_id: u32,
     outer_points: Vec<Point3<f64>>,
     hole_points: Vec<Vec<Point3<f64>>>,
 }
@@ -852,7 +863,7 @@ impl FacetedBrepProcessor {
                         .get(1)
                         .map(|v| match v {
                             // Parser strips dots, so enum value is "T" or "F", not ".T." or ".F."
-                            ifc_lite_core::AttributeValue::Enum(e) => e != "F" && e != ".F.",
+                            ifc_lite_core::AttributeValue::Enum(e) => !matches!(&**e, "F" | ".F."),
                             _ => true,
                         })
                         .unwrap_or(true);
@@ -892,6 +903,7 @@ impl FacetedBrepProcessor {
                     all_faces.push((
                         brep_idx,
                         FaceData {
+                            face_id,
                             outer_points,
                             hole_points,
                         },
@@ -946,6 +958,7 @@ impl FacetedBrepProcessor {
                         positions,
                         normals: Vec::new(),
                         indices,
+                        colors: Vec::new(),
                     },
                 )
             })
@@ -953,13 +966,14 @@ impl FacetedBrepProcessor {
     }
 }
 
-impl GeometryProcessor for FacetedBrepProcessor {
-    fn process(
+impl FacetedBrepProcessor {
+    /// Triangulate a FacetedBrep, also returning the IfcFace ID of every triangle
+    /// so per-face styles can be applied afterwards
+    pub fn process_with_face_ids(
         &self,
         entity: &DecodedEntity,
         decoder: &mut EntityDecoder,
-        _schema: &IfcSchema,
-    ) -> Result<Mesh> {
+    ) -> Result<(Mesh, Vec<u32>)> {
         use rayon::prelude::*;
 
         // IfcFacetedBrep attributes:
@@ -1010,7 +1024,7 @@ impl GeometryProcessor for FacetedBrepProcessor {
                     .get(1)
                     .map(|v| match v {
                         // Parser strips dots, so enum value is "T" or "F", not ".T." or ".F."
-                        ifc_lite_core::AttributeValue::Enum(e) => e != "F" && e != ".F.",
+                        ifc_lite_core::AttributeValue::Enum(e) => !matches!(&**e, "F" | ".F."),
                         _ => true,
                     })
                     .unwrap_or(true);
@@ -1049,6 +1063,7 @@ impl GeometryProcessor for FacetedBrepProcessor {
 
             if let Some(outer_points) = outer_bound_points {
                 face_data_list.push(FaceData {
+                    face_id,
                     outer_points,
                     hole_points,
                 });
@@ -1069,10 +1084,12 @@ impl GeometryProcessor for FacetedBrepProcessor {
 
         let mut positions = Vec::with_capacity(total_positions);
         let mut indices = Vec::with_capacity(total_indices);
+        let mut triangle_faces = Vec::with_capacity(total_indices / 3);
 
-        for result in face_results {
+        for (face, result) in face_data_list.iter().zip(face_results) {
             let base_idx = (positions.len() / 3) as u32;
             positions.extend(result.positions);
+            triangle_faces.extend(std::iter::repeat_n(face.face_id, result.indices.len() / 3));
 
             // Offset indices by base
             for idx in result.indices {
@@ -1080,11 +1097,27 @@ impl GeometryProcessor for FacetedBrepProcessor {
             }
         }
 
-        Ok(Mesh {
-            positions,
-            normals: Vec::new(),
-            indices,
-        })
+        Ok((
+            Mesh {
+                positions,
+                normals: Vec::new(),
+                indices,
+                colors: Vec::new(),
+            },
+            triangle_faces,
+        ))
+    }
+}
+
+impl GeometryProcessor for FacetedBrepProcessor {
+    fn process(
+        &self,
+        entity: &DecodedEntity,
+        decoder: &mut EntityDecoder,
+        _schema: &IfcSchema,
+    ) -> Result<Mesh> {
+        self.process_with_face_ids(entity, decoder)
+            .map(|(mesh, _)| mesh)
     }
 
     fn supported_types(&self) -> Vec<IfcType> {
@@ -1099,12 +1132,23 @@ impl Default for FacetedBrepProcessor {
 }
 
 /// BooleanResult processor
-/// Handles IfcBooleanResult and IfcBooleanClippingResult - CSG operations
-/// Supports half-space clipping for DIFFERENCE operations
+/// Handles IfcBooleanResult, IfcBooleanClippingResult and IfcCsgSolid - CSG operations
+///
+/// DIFFERENCE, UNION and INTERSECTION are evaluated on meshes. Half-space
+/// operands are unbounded, so they are turned into a solid that encloses the
+/// other operand with some margin before the boolean.
 pub struct BooleanClippingProcessor {
     schema: IfcSchema,
 }
 
+/// Boolean operator of an IfcBooleanResult
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BooleanOperator {
+    Difference,
+    Union,
+    Intersection,
+}
+
 impl BooleanClippingProcessor {
     pub fn new() -> Self {
         Self {
@@ -1139,7 +1183,13 @@ impl BooleanClippingProcessor {
                 let processor = RevolvedAreaSolidProcessor::new(self.schema.clone());
                 processor.process(operand, decoder, &self.schema)
             }
-            IfcType::IfcBooleanResult | IfcType::IfcBooleanClippingResult => {
+            IfcType::IfcAdvancedBrep | IfcType::IfcAdvancedBrepWithVoids => {
+                let processor = AdvancedBrepProcessor::new();
+                processor.process(operand, decoder, &self.schema)
+            }
+            IfcType::IfcBooleanResult
+            | IfcType::IfcBooleanClippingResult
+            | IfcType::IfcCsgSolid => {
                 // Recursive case
                 self.process(operand, decoder, &self.schema)
             }
@@ -1147,16 +1197,25 @@ impl BooleanClippingProcessor {
         }
     }
 
-    /// Parse IfcHalfSpaceSolid to get clipping plane
-    /// Returns (plane_point, plane_normal, agreement_flag)
+    /// Parse the operator enumeration; the parser strips the dots
+    fn parse_operator(entity: &DecodedEntity) -> BooleanOperator {
+        match entity.get(0).and_then(|v| v.as_enum()) {
+            Some("UNION") => BooleanOperator::Union,
+            Some("INTERSECTION") => BooleanOperator::Intersection,
+            _ => BooleanOperator::Difference,
+        }
+    }
+
+    /// Parse IfcHalfSpaceSolid to get its plane
+    /// Returns (plane placement, agreement_flag)
     fn parse_half_space_solid(
         &self,
         half_space: &DecodedEntity,
         decoder: &mut EntityDecoder,
-    ) -> Result<(Point3<f64>, Vector3<f64>, bool)> {
+    ) -> Result<(Matrix4<f64>, bool)> {
         // IfcHalfSpaceSolid attributes:
         // 0: BaseSurface (IfcSurface - usually IfcPlane)
-        // 1: AgreementFlag (boolean - true means material is on positive side)
+        // 1: AgreementFlag (true means the surface normal points away from the material)
 
         let surface_attr = half_space
             .get(0)
@@ -1171,7 +1230,7 @@ impl BooleanClippingProcessor {
             .get(1)
             .map(|v| match v {
                 // Parser strips dots, so enum value is "T" or "F", not ".T." or ".F."
-                ifc_lite_core::AttributeValue::Enum(e) => e != "F" && e != ".F.",
+                ifc_lite_core::AttributeValue::Enum(e) => !matches!(&**e, "F" | ".F."),
                 _ => true,
             })
             .unwrap_or(true);
@@ -1185,84 +1244,128 @@ impl BooleanClippingProcessor {
         }
 
         // IfcPlane has one attribute: Position (IfcAxis2Placement3D)
-        let position_attr = surface
-            .get(0)
-            .ok_or_else(|| Error::geometry("IfcPlane missing Position".to_string()))?;
-
-        let position = decoder
-            .resolve_ref(position_attr)?
-            .ok_or_else(|| Error::geometry("Failed to resolve Plane position".to_string()))?;
-
-        // Parse IfcAxis2Placement3D
-        // Location (Point), Axis (Direction - Z), RefDirection (Direction - X)
-        let location = {
-            let loc_attr = position
-                .get(0)
-                .ok_or_else(|| Error::geometry("Axis2Placement3D missing Location".to_string()))?;
-            let loc = decoder
-                .resolve_ref(loc_attr)?
-                .ok_or_else(|| Error::geometry("Failed to resolve plane location".to_string()))?;
-            let coords = loc
-                .get(0)
-                .and_then(|v| v.as_list())
-                .ok_or_else(|| Error::geometry("Location missing coordinates".to_string()))?;
-            Point3::new(
-                coords.first().and_then(|v| v.as_float()).unwrap_or(0.0),
-                coords.get(1).and_then(|v| v.as_float()).unwrap_or(0.0),
-                coords.get(2).and_then(|v| v.as_float()).unwrap_or(0.0),
-            )
-        };
-
-        let normal = {
-            if let Some(axis_attr) = position.get(1) {
-                if !axis_attr.is_null() {
-                    let axis = decoder.resolve_ref(axis_attr)?.ok_or_else(|| {
-                        Error::geometry("Failed to resolve plane axis".to_string())
-                    })?;
-                    let coords = axis.get(0).and_then(|v| v.as_list()).ok_or_else(|| {
-                        Error::geometry("Axis missing direction ratios".to_string())
-                    })?;
-                    Vector3::new(
-                        coords.first().and_then(|v| v.as_float()).unwrap_or(0.0),
-                        coords.get(1).and_then(|v| v.as_float()).unwrap_or(0.0),
-                        coords.get(2).and_then(|v| v.as_float()).unwrap_or(1.0),
-                    )
-                    .normalize()
-                } else {
-                    Vector3::new(0.0, 0.0, 1.0)
-                }
-            } else {
-                Vector3::new(0.0, 0.0, 1.0)
-            }
-        };
+        let position = self.resolve_placement(&surface, 0, decoder)?;
+        Ok((position, agreement))
+    }
 
-        Ok((location, normal, agreement))
+    /// Resolve an IfcAxis2Placement3D attribute into a transformation matrix
+    fn resolve_placement(
+        &self,
+        entity: &DecodedEntity,
+        index: usize,
+        decoder: &mut EntityDecoder,
+    ) -> Result<Matrix4<f64>> {
+        let attr = entity
+            .get(index)
+            .ok_or_else(|| Error::geometry(format!("{} missing placement", entity.ifc_type)))?;
+        let placement = decoder
+            .resolve_ref(attr)?
+            .ok_or_else(|| Error::geometry("Failed to resolve placement".to_string()))?;
+        ExtrudedAreaSolidProcessor::new(self.schema.clone())
+            .parse_axis2_placement_3d(&placement, decoder)
     }
 
-    /// Apply half-space clipping to mesh
-    fn clip_mesh_with_half_space(
+    /// Build a closed mesh for a half-space operand that covers `other`
+    ///
+    /// Handles IfcHalfSpaceSolid, IfcBoxedHalfSpace (the enclosure is only
+    /// a bounding hint) and IfcPolygonalBoundedHalfSpace.
+    fn half_space_mesh(
         &self,
-        mesh: &Mesh,
-        plane_point: Point3<f64>,
-        plane_normal: Vector3<f64>,
-        agreement: bool,
+        half_space: &DecodedEntity,
+        other: &Mesh,
+        decoder: &mut EntityDecoder,
     ) -> Result<Mesh> {
-        use crate::csg::{ClippingProcessor, Plane};
-
-        // For DIFFERENCE operation with HalfSpaceSolid:
-        // - AgreementFlag=.T. means keep material on positive side of plane
-        // - AgreementFlag=.F. means keep material on negative side of plane
-        // But we're SUBTRACTING the half-space, so we invert
-        let clip_normal = if agreement {
-            -plane_normal // Subtract positive side = keep negative side
+        let (plane, agreement) = self.parse_half_space_solid(half_space, decoder)?;
+
+        // Material lies on the negative side of the plane when AgreementFlag is true
+        let (min, max, margin) = local_extent(other, &plane);
+        let (bottom, top) = if agreement {
+            (min.z - margin, 0.0)
         } else {
-            plane_normal // Subtract negative side = keep positive side
+            (0.0, max.z + margin)
         };
+        if top <= bottom {
+            return Ok(Mesh::new());
+        }
+        let slab = box_mesh(
+            Point3::new(min.x - margin, min.y - margin, bottom),
+            Point3::new(max.x + margin, max.y + margin, top),
+            &plane,
+        )?;
+
+        if half_space.ifc_type != IfcType::IfcPolygonalBoundedHalfSpace {
+            return Ok(slab);
+        }
 
-        let plane = Plane::new(plane_point, clip_normal);
-        let processor = ClippingProcessor::new();
-        processor.clip_mesh(mesh, &plane)
+        // IfcPolygonalBoundedHalfSpace: 2: Position, 3: PolygonalBoundary
+        let position = self.resolve_placement(half_space, 2, decoder)?;
+        let boundary_attr = half_space.get(3).ok_or_else(|| {
+            Error::geometry("PolygonalBoundedHalfSpace missing PolygonalBoundary".to_string())
+        })?;
+        let boundary = decoder
+            .resolve_ref(boundary_attr)?
+            .ok_or_else(|| Error::geometry("Failed to resolve PolygonalBoundary".to_string()))?;
+        let points =
+            ProfileProcessor::new(self.schema.clone()).get_curve_points(&boundary, decoder)?;
+        let mut outer: Vec<nalgebra::Point2<f64>> = points
+            .iter()
+            .map(|p| nalgebra::Point2::new(p.x, p.y))
+            .collect();
+        if outer.len() > 1 && outer.first() == outer.last() {
+            outer.pop();
+        }
+        if outer.len() < 3 {
+            return Ok(slab);
+        }
+        let signed_area: f64 = outer
+            .iter()
+            .zip(outer.iter().cycle().skip(1))
+            .map(|(a, b)| a.x * b.y - b.x * a.y)
+            .sum();
+        if signed_area < 0.0 {
+            outer.reverse();
+        }
+
+        // Prism along the Position Z axis, long enough to pass through the operand
+        let (min, max, margin) = local_extent(other, &position);
+        let start = min.z - margin;
+        let prism = extrude_profile(
+            &Profile2D::new(outer),
+            max.z + margin - start,
+            Some(position * Matrix4::new_translation(&Vector3::new(0.0, 0.0, start))),
+        )?;
+
+        crate::csg::ClippingProcessor::new().intersect_mesh(&slab, &prism)
+    }
+}
+
+/// Bounds of `mesh` in the frame of `placement`, with a margin for enclosing it
+fn local_extent(mesh: &Mesh, placement: &Matrix4<f64>) -> (Point3<f64>, Point3<f64>, f64) {
+    let inverse = placement.try_inverse().unwrap_or_else(Matrix4::identity);
+    let mut min = Point3::new(f64::INFINITY, f64::INFINITY, f64::INFINITY);
+    let mut max = Point3::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
+    for p in mesh.positions.chunks_exact(3) {
+        let local = inverse.transform_point(&Point3::new(p[0] as f64, p[1] as f64, p[2] as f64));
+        min = min.inf(&local);
+        max = max.sup(&local);
     }
+    let margin = (max - min).norm() * 0.1 + 1.0;
+    (min, max, margin)
+}
+
+/// Closed box between `min` and `max` in the frame of `placement`
+fn box_mesh(min: Point3<f64>, max: Point3<f64>, placement: &Matrix4<f64>) -> Result<Mesh> {
+    let rectangle = Profile2D::new(vec![
+        nalgebra::Point2::new(min.x, min.y),
+        nalgebra::Point2::new(max.x, min.y),
+        nalgebra::Point2::new(max.x, max.y),
+        nalgebra::Point2::new(min.x, max.y),
+    ]);
+    extrude_profile(
+        &rectangle,
+        max.z - min.z,
+        Some(placement * Matrix4::new_translation(&Vector3::new(0.0, 0.0, min.z))),
+    )
 }
 
 impl GeometryProcessor for BooleanClippingProcessor {
@@ -1272,19 +1375,22 @@ impl GeometryProcessor for BooleanClippingProcessor {
         decoder: &mut EntityDecoder,
         _schema: &IfcSchema,
     ) -> Result<Mesh> {
+        // IfcCsgSolid: 0: TreeRootExpression
+        if entity.ifc_type == IfcType::IfcCsgSolid {
+            let root_attr = entity.get(0).ok_or_else(|| {
+                Error::geometry("CsgSolid missing TreeRootExpression".to_string())
+            })?;
+            let root = decoder.resolve_ref(root_attr)?.ok_or_else(|| {
+                Error::geometry("Failed to resolve TreeRootExpression".to_string())
+            })?;
+            return self.process_operand(&root, decoder);
+        }
+
         // IfcBooleanResult attributes:
         // 0: Operator (.DIFFERENCE., .UNION., .INTERSECTION.)
         // 1: FirstOperand (base geometry)
         // 2: SecondOperand (clipping geometry)
-
-        // Get operator
-        let operator = entity
-            .get(0)
-            .and_then(|v| match v {
-                ifc_lite_core::AttributeValue::Enum(e) => Some(e.as_str()),
-                _ => None,
-            })
-            .unwrap_or(".DIFFERENCE.");
+        let operator = Self::parse_operator(entity);
 
         // Get first operand (base geometry)
         let first_operand_attr = entity
@@ -1298,7 +1404,7 @@ impl GeometryProcessor for BooleanClippingProcessor {
         // Process first operand to get base mesh
         let mesh = self.process_operand(&first_operand, decoder)?;
 
-        if mesh.is_empty() {
+        if mesh.is_empty() && operator != BooleanOperator::Union {
             return Ok(mesh);
         }
 
@@ -1311,40 +1417,46 @@ impl GeometryProcessor for BooleanClippingProcessor {
             .resolve_ref(second_operand_attr)?
             .ok_or_else(|| Error::geometry("Failed to resolve SecondOperand".to_string()))?;
 
-        // Handle DIFFERENCE operation
-        if operator == ".DIFFERENCE." {
-            // Check if second operand is a half-space solid (can clip efficiently)
-            if second_operand.ifc_type == IfcType::IfcHalfSpaceSolid
-                || second_operand.ifc_type == IfcType::IfcPolygonalBoundedHalfSpace
-            {
-                let (plane_point, plane_normal, agreement) =
-                    self.parse_half_space_solid(&second_operand, decoder)?;
-                return self.clip_mesh_with_half_space(&mesh, plane_point, plane_normal, agreement);
-            }
-
-            // For solid-solid difference, we can't do proper CSG without a full boolean library
-            // Just return the first operand for now - this gives approximate geometry
-            #[cfg(debug_assertions)]
-            eprintln!(
-                "[WARN] CSG operation {} not fully supported, returning first operand only",
-                operator
-            );
-        }
-
-        // For UNION and INTERSECTION, and solid-solid DIFFERENCE,
-        // just return the first operand for now
-        #[cfg(debug_assertions)]
-        if operator != ".DIFFERENCE." {
-            eprintln!(
-                "[WARN] CSG operation {} not fully supported, returning first operand only",
-                operator
-            );
+        let second = match second_operand.ifc_type {
+            IfcType::IfcHalfSpaceSolid
+            | IfcType::IfcBoxedHalfSpace
+            | IfcType::IfcPolygonalBoundedHalfSpace => {
+                // The union with an unbounded half-space is not a meaningful solid
+                if operator == BooleanOperator::Union {
+                    return Ok(mesh);
+                }
+                self.half_space_mesh(&second_operand, &mesh, decoder)?
+            }
+            _ => self.process_operand(&second_operand, decoder)?,
+        };
+
+        let clipper = crate::csg::ClippingProcessor::new();
+        let result = match operator {
+            BooleanOperator::Difference => clipper.subtract_mesh(&mesh, &second),
+            BooleanOperator::Union => clipper.union_mesh(&mesh, &second),
+            BooleanOperator::Intersection => clipper.intersect_mesh(&mesh, &second),
+        };
+
+        match result {
+            Ok(result) => Ok(result),
+            // Keep the first operand rather than dropping the item
+            Err(e) if operator != BooleanOperator::Intersection => {
+                crate::warnings::warn(format!(
+                    "{:?} of #{} failed, kept its first operand: {}",
+                    operator, entity.id, e
+                ));
+                Ok(mesh)
+            }
+            Err(e) => Err(e),
         }
-        Ok(mesh)
     }
 
     fn supported_types(&self) -> Vec<IfcType> {
-        vec![IfcType::IfcBooleanResult, IfcType::IfcBooleanClippingResult]
+        vec![
+            IfcType::IfcBooleanResult,
+            IfcType::IfcBooleanClippingResult,
+            IfcType::IfcCsgSolid,
+        ]
     }
 }
 
@@ -1423,7 +1535,9 @@ impl GeometryProcessor for MappedItemProcessor {
                     let processor = SweptDiskSolidProcessor::new(schema.clone());
                     processor.process(&item, decoder, schema)?
                 }
-                IfcType::IfcBooleanClippingResult | IfcType::IfcBooleanResult => {
+                IfcType::IfcBooleanClippingResult
+                | IfcType::IfcBooleanResult
+                | IfcType::IfcCsgSolid => {
                     let processor = BooleanClippingProcessor::new();
                     processor.process(&item, decoder, schema)?
                 }
@@ -1462,8 +1576,13 @@ pub struct SweptDiskSolidProcessor {
 
 impl SweptDiskSolidProcessor {
     pub fn new(schema: IfcSchema) -> Self {
+        Self::with_options(schema, GeometryOptions::default())
+    }
+
+    /// Create processor sampling curves with the given options
+    pub fn with_options(schema: IfcSchema, options: GeometryOptions) -> Self {
         Self {
-            profile_processor: ProfileProcessor::new(schema),
+            profile_processor: ProfileProcessor::with_options(schema, options),
         }
     }
 }
@@ -1508,7 +1627,12 @@ impl GeometryProcessor for SweptDiskSolidProcessor {
         }
 
         // Generate tube mesh by sweeping circle along curve
-        let segments = 12; // Number of segments around the circle
+        // At least 12 segments around the circle, more for wide tubes
+        let unit_scale = decoder.length_unit_scale().unwrap_or(1.0);
+        let segments = self
+            .profile_processor
+            .options()
+            .circle_segments(radius, unit_scale, 12);
         let mut positions = Vec::new();
         let mut indices = Vec::new();
 
@@ -1601,6 +1725,7 @@ impl GeometryProcessor for SweptDiskSolidProcessor {
             positions,
             normals: Vec::new(),
             indices,
+            colors: Vec::new(),
         })
     }
 
@@ -1615,6 +1740,160 @@ impl Default for SweptDiskSolidProcessor {
     }
 }
 
+/// SectionedSolidHorizontal processor
+/// Handles IfcSectionedSolidHorizontal - cross sections lofted along an alignment
+///
+/// Profiles stand upright across the directrix, their X axis to the left
+/// and Y up. Between two positions the section is interpolated when both
+/// profiles have the same number of points, otherwise the earlier one is
+/// kept. Profile holes are ignored.
+pub struct SectionedSolidHorizontalProcessor {
+    profile_processor: ProfileProcessor,
+}
+
+impl SectionedSolidHorizontalProcessor {
+    pub fn new(schema: IfcSchema) -> Self {
+        Self::with_options(schema, GeometryOptions::default())
+    }
+
+    /// Create processor sampling curves with the given options
+    pub fn with_options(schema: IfcSchema, options: GeometryOptions) -> Self {
+        Self {
+            profile_processor: ProfileProcessor::with_options(schema, options),
+        }
+    }
+}
+
+impl GeometryProcessor for SectionedSolidHorizontalProcessor {
+    fn process(
+        &self,
+        entity: &DecodedEntity,
+        decoder: &mut EntityDecoder,
+        _schema: &IfcSchema,
+    ) -> Result<Mesh> {
+        // IfcSectionedSolidHorizontal attributes:
+        // 0: Directrix (IfcCurve)
+        // 1: CrossSections (list of IfcProfileDef)
+        // 2: CrossSectionPositions (list of IfcAxis2PlacementLinear)
+        let directrix_attr = entity.get(0).ok_or_else(|| {
+            Error::geometry("SectionedSolidHorizontal missing Directrix".to_string())
+        })?;
+        let directrix = decoder
+            .resolve_ref(directrix_attr)?
+            .ok_or_else(|| Error::geometry("Failed to resolve Directrix".to_string()))?;
+        let curve = AlignmentCurve::from_entity(&directrix, decoder, &self.profile_processor)?;
+
+        let profiles = match entity.get(1) {
+            Some(attr) => decoder.resolve_ref_list(attr)?,
+            None => Vec::new(),
+        };
+        let positions = match entity.get(2) {
+            Some(attr) => decoder.resolve_ref_list(attr)?,
+            None => Vec::new(),
+        };
+        let mut sections: Vec<(Vec<nalgebra::Point2<f64>>, DistanceExpression)> = Vec::new();
+        for (profile, position) in profiles.iter().zip(&positions) {
+            let mut outer = self.profile_processor.process(profile, decoder)?.outer;
+            if outer.len() > 1 && outer.first() == outer.last() {
+                outer.pop();
+            }
+            if outer.len() < 3 {
+                continue;
+            }
+            sections.push((outer, DistanceExpression::from_entity(position, decoder)?));
+        }
+        if sections.len() < 2 {
+            return Ok(Mesh::new());
+        }
+
+        let mut mesh = Mesh::new();
+        for pair in sections.windows(2) {
+            let ((start_ring, start), (end_ring, end)) = (&pair[0], &pair[1]);
+            let span = end.distance - start.distance;
+            if span.abs() < 1e-9 {
+                continue;
+            }
+            let blend = start_ring.len() == end_ring.len();
+            let ring_len = start_ring.len() as u32;
+
+            let stations = curve.stations_between(start.distance, end.distance);
+            let first_vertex = (mesh.positions.len() / 3) as u32;
+            for (i, &station) in stations.iter().enumerate() {
+                let f = (station - start.distance) / span;
+                let lerp = |a: f64, b: f64| a + (b - a) * f;
+                let frame = curve.frame_at(
+                    station,
+                    lerp(start.lateral, end.lateral),
+                    lerp(start.vertical, end.vertical),
+                );
+                for (k, point) in start_ring.iter().enumerate() {
+                    let point = if blend {
+                        point + (end_ring[k] - point) * f
+                    } else {
+                        *point
+                    };
+                    let world = frame.transform_point(&Point3::new(0.0, point.x, point.y));
+                    mesh.positions
+                        .extend([world.x as f32, world.y as f32, world.z as f32]);
+                }
+
+                if i > 0 {
+                    let previous = first_vertex + (i as u32 - 1) * ring_len;
+                    let current = previous + ring_len;
+                    for k in 0..ring_len {
+                        let next = (k + 1) % ring_len;
+                        mesh.add_triangle(previous + k, previous + next, current + next);
+                        mesh.add_triangle(previous + k, current + next, current + k);
+                    }
+                }
+            }
+        }
+
+        // Caps at both ends, facing away from the solid
+        let cap = |mesh: &mut Mesh,
+                   ring: &[nalgebra::Point2<f64>],
+                   at: &DistanceExpression,
+                   flip: bool| {
+            let Ok(triangulation) = Profile2D::new(ring.to_vec()).triangulate() else {
+                return;
+            };
+            let frame = curve.frame_at(at.distance, at.lateral, at.vertical);
+            let base = (mesh.positions.len() / 3) as u32;
+            for point in &triangulation.points {
+                let world = frame.transform_point(&Point3::new(0.0, point.x, point.y));
+                mesh.positions
+                    .extend([world.x as f32, world.y as f32, world.z as f32]);
+            }
+            for tri in triangulation.indices.chunks_exact(3) {
+                let [a, b, c] = [tri[0] as u32, tri[1] as u32, tri[2] as u32];
+                if flip {
+                    mesh.add_triangle(base + a, base + c, base + b);
+                } else {
+                    mesh.add_triangle(base + a, base + b, base + c);
+                }
+            }
+        };
+        let (first_ring, first) = &sections[0];
+        let (last_ring, last) = &sections[sections.len() - 1];
+        let forward = last.distance >= first.distance;
+        cap(&mut mesh, first_ring, first, forward);
+        cap(&mut mesh, last_ring, last, !forward);
+
+        calculate_normals(&mut mesh);
+        Ok(mesh)
+    }
+
+    fn supported_types(&self) -> Vec<IfcType> {
+        vec![IfcType::IfcSectionedSolidHorizontal]
+    }
+}
+
+impl Default for SectionedSolidHorizontalProcessor {
+    fn default() -> Self {
+        Self::new(IfcSchema::new())
+    }
+}
+
 /// RevolvedAreaSolid processor
 /// Handles IfcRevolvedAreaSolid - rotates a 2D profile around an axis
 pub struct RevolvedAreaSolidProcessor {
@@ -1623,8 +1902,13 @@ pub struct RevolvedAreaSolidProcessor {
 
 impl RevolvedAreaSolidProcessor {
     pub fn new(schema: IfcSchema) -> Self {
+        Self::with_options(schema, GeometryOptions::default())
+    }
+
+    /// Create processor sampling curves with the given options
+    pub fn with_options(schema: IfcSchema, options: GeometryOptions) -> Self {
         Self {
-            profile_processor: ProfileProcessor::new(schema),
+            profile_processor: ProfileProcessor::with_options(schema, options),
         }
     }
 }
@@ -1714,12 +1998,22 @@ impl GeometryProcessor for RevolvedAreaSolidProcessor {
         };
 
         // Generate revolved mesh
-        // Number of segments depends on angle
+        // Number of segments depends on angle and the profile's distance from the axis
+        let options = self.profile_processor.options();
+        let unit_scale = decoder.length_unit_scale().unwrap_or(1.0);
+        let max_radius = profile_2d
+            .outer
+            .iter()
+            .map(|p| p.x.abs())
+            .fold(0.0, f64::max);
         let full_circle = angle.abs() >= std::f64::consts::PI * 1.99;
         let segments = if full_circle {
-            24 // Full revolution
+            options.circle_segments(max_radius, unit_scale, 24) // Full revolution
         } else {
-            ((angle.abs() / std::f64::consts::PI * 12.0).ceil() as usize).max(4)
+            let at_least = ((angle.abs() / std::f64::consts::PI * 12.0).ceil() as usize).max(4);
+            options
+                .arc_segments(max_radius, angle, unit_scale)
+                .max(at_least.min(options.max_segments))
         };
 
         let profile_points = &profile_2d.outer;
@@ -1829,6 +2123,7 @@ impl GeometryProcessor for RevolvedAreaSolidProcessor {
             positions,
             normals: Vec::new(),
             indices,
+            colors: Vec::new(),
         })
     }
 
@@ -1845,12 +2140,67 @@ impl Default for RevolvedAreaSolidProcessor {
 
 /// AdvancedBrep processor
 /// Handles IfcAdvancedBrep and IfcAdvancedBrepWithVoids - NURBS/B-spline surfaces
-/// Supports planar faces and B-spline surface tessellation
-pub struct AdvancedBrepProcessor;
+/// Supports planar, cylindrical and (rational) B-spline surface faces.
+/// Curved surfaces are tessellated so adjacent facets turn by at most the
+/// angular tolerance.
+pub struct AdvancedBrepProcessor {
+    /// Maximum angle (radians) spanned by one facet of a curved surface
+    angular_tolerance: f64,
+    /// Segment cap per surface direction
+    max_segments: usize,
+}
+
+/// Surface control net: rows of (point, weight)
+type ControlNet = Vec<Vec<(Point3<f64>, f64)>>;
+
+/// Local frame: origin, x, y, z axes
+type Frame = (Point3<f64>, Vector3<f64>, Vector3<f64>, Vector3<f64>);
+
+/// Points of one face bound, with circle edges sampled
+struct BoundLoop {
+    points: Vec<Point3<f64>>,
+    outer: bool,
+}
 
 impl AdvancedBrepProcessor {
+    /// Default angular tolerance (~11.5°)
+    pub const DEFAULT_ANGULAR_TOLERANCE: f64 = 0.2;
+
+    /// Default segment cap per surface direction
+    const MAX_SEGMENTS: usize = 64;
+
     pub fn new() -> Self {
-        Self
+        Self {
+            angular_tolerance: Self::DEFAULT_ANGULAR_TOLERANCE,
+            max_segments: Self::MAX_SEGMENTS,
+        }
+    }
+
+    /// Create processor with a custom angular tolerance (radians)
+    /// Smaller values give smoother curved surfaces and more triangles
+    pub fn with_angular_tolerance(angular_tolerance: f64) -> Self {
+        Self {
+            angular_tolerance: angular_tolerance.clamp(0.01, 1.0),
+            max_segments: Self::MAX_SEGMENTS,
+        }
+    }
+
+    /// Lower the segment cap per surface direction, e.g. to
+    /// [`GeometryOptions::max_segments`]; it never rises above the default
+    pub fn with_max_segments(mut self, max_segments: usize) -> Self {
+        self.max_segments = max_segments.clamp(1, Self::MAX_SEGMENTS);
+        self
+    }
+
+    /// Current angular tolerance (radians)
+    pub fn angular_tolerance(&self) -> f64 {
+        self.angular_tolerance
+    }
+
+    /// Segments needed to cover `angle` radians within tolerance
+    #[inline]
+    fn segments_for_angle(&self, angle: f64) -> usize {
+        ((angle.abs() / self.angular_tolerance).ceil() as usize).clamp(1, self.max_segments)
     }
 
     /// Evaluate a B-spline basis function (Cox-de Boor recursion)
@@ -1883,92 +2233,98 @@ impl AdvancedBrepProcessor {
         }
     }
 
-    /// Evaluate a B-spline surface at parameter (u, v)
+    /// Evaluate a (rational) B-spline surface at parameter (u, v)
     fn evaluate_bspline_surface(
         u: f64,
         v: f64,
         u_degree: usize,
         v_degree: usize,
-        control_points: &[Vec<Point3<f64>>],
+        control_points: &ControlNet,
         u_knots: &[f64],
         v_knots: &[f64],
     ) -> Point3<f64> {
-        let _n_u = control_points.len();
-
-        let mut result = Point3::new(0.0, 0.0, 0.0);
+        let mut sum = Vector3::zeros();
+        let mut weight_sum = 0.0;
 
         for (i, row) in control_points.iter().enumerate() {
             let n_i = Self::bspline_basis(i, u_degree, u, u_knots);
-            for (j, cp) in row.iter().enumerate() {
+            if n_i.abs() < 1e-10 {
+                continue;
+            }
+            for (j, (cp, w)) in row.iter().enumerate() {
                 let n_j = Self::bspline_basis(j, v_degree, v, v_knots);
-                let weight = n_i * n_j;
+                let weight = n_i * n_j * w;
                 if weight.abs() > 1e-10 {
-                    result.x += weight * cp.x;
-                    result.y += weight * cp.y;
-                    result.z += weight * cp.z;
+                    sum += cp.coords * weight;
+                    weight_sum += weight;
                 }
             }
         }
 
-        result
+        if weight_sum.abs() < 1e-12 {
+            return Point3::origin();
+        }
+        Point3::from(sum / weight_sum)
     }
 
     /// Tessellate a B-spline surface into triangles
     fn tessellate_bspline_surface(
         u_degree: usize,
         v_degree: usize,
-        control_points: &[Vec<Point3<f64>>],
+        control_points: &ControlNet,
         u_knots: &[f64],
         v_knots: &[f64],
         u_segments: usize,
         v_segments: usize,
     ) -> (Vec<f32>, Vec<u32>) {
-        let mut positions = Vec::new();
-        let mut indices = Vec::new();
-
         // Get parameter domain
         let u_min = u_knots[u_degree];
         let u_max = u_knots[u_knots.len() - u_degree - 1];
         let v_min = v_knots[v_degree];
         let v_max = v_knots[v_knots.len() - v_degree - 1];
 
-        // Evaluate surface on a grid
-        for i in 0..=u_segments {
-            let u = u_min + (u_max - u_min) * (i as f64 / u_segments as f64);
-            // Clamp u to slightly inside the domain to avoid edge issues
-            let u = u.min(u_max - 1e-6).max(u_min);
+        Self::tessellate_grid(u_segments, v_segments, |s, t| {
+            // Clamp to slightly inside the domain: the basis is zero at the closed end
+            let u = (u_min + (u_max - u_min) * s).min(u_max - 1e-6).max(u_min);
+            let v = (v_min + (v_max - v_min) * t).min(v_max - 1e-6).max(v_min);
+            Self::evaluate_bspline_surface(
+                u,
+                v,
+                u_degree,
+                v_degree,
+                control_points,
+                u_knots,
+                v_knots,
+            )
+        })
+    }
+
+    /// Evaluate `surface(s, t)` on a regular grid over [0,1]² and triangulate it
+    fn tessellate_grid(
+        u_segments: usize,
+        v_segments: usize,
+        surface: impl Fn(f64, f64) -> Point3<f64>,
+    ) -> (Vec<f32>, Vec<u32>) {
+        let mut positions = Vec::with_capacity((u_segments + 1) * (v_segments + 1) * 3);
+        let mut indices = Vec::with_capacity(u_segments * v_segments * 6);
 
+        for i in 0..=u_segments {
+            let s = i as f64 / u_segments as f64;
             for j in 0..=v_segments {
-                let v = v_min + (v_max - v_min) * (j as f64 / v_segments as f64);
-                let v = v.min(v_max - 1e-6).max(v_min);
-
-                let point = Self::evaluate_bspline_surface(
-                    u,
-                    v,
-                    u_degree,
-                    v_degree,
-                    control_points,
-                    u_knots,
-                    v_knots,
-                );
+                let t = j as f64 / v_segments as f64;
+                let point = surface(s, t);
 
                 positions.push(point.x as f32);
                 positions.push(point.y as f32);
                 positions.push(point.z as f32);
 
-                // Create triangles
+                // Two triangles per quad
                 if i < u_segments && j < v_segments {
                     let base = (i * (v_segments + 1) + j) as u32;
                     let next_u = base + (v_segments + 1) as u32;
 
-                    // Two triangles per quad
-                    indices.push(base);
-                    indices.push(base + 1);
-                    indices.push(next_u + 1);
-
-                    indices.push(base);
-                    indices.push(next_u + 1);
-                    indices.push(next_u);
+                    indices.extend_from_slice(&[base, base + 1, next_u + 1]);
+                    indices.extend_from_slice(&[base, next_u + 1, next_u]);
                 }
             }
         }
@@ -1976,12 +2332,349 @@ impl AdvancedBrepProcessor {
         (positions, indices)
     }
 
-    /// Parse control points from B-spline surface entity
+    /// Read coordinates of an IfcCartesianPoint
+    fn read_point(point: &DecodedEntity) -> Result<Point3<f64>> {
+        let coords = point
+            .get(0)
+            .and_then(|v| v.as_list())
+            .ok_or_else(|| Error::geometry("CartesianPoint missing coordinates".to_string()))?;
+
+        Ok(Point3::new(
+            coords.first().and_then(|v| v.as_float()).unwrap_or(0.0),
+            coords.get(1).and_then(|v| v.as_float()).unwrap_or(0.0),
+            coords.get(2).and_then(|v| v.as_float()).unwrap_or(0.0),
+        ))
+    }
+
+    /// Read an optional IfcDirection attribute, normalized
+    fn read_direction(
+        entity: &DecodedEntity,
+        index: usize,
+        decoder: &mut EntityDecoder,
+    ) -> Result<Option<Vector3<f64>>> {
+        let Some(attr) = entity.get(index).filter(|a| !a.is_null()) else {
+            return Ok(None);
+        };
+        let Some(direction) = decoder.resolve_ref(attr)? else {
+            return Ok(None);
+        };
+        let ratios = direction
+            .get(0)
+            .and_then(|v| v.as_list())
+            .ok_or_else(|| Error::geometry("Direction missing ratios".to_string()))?;
+
+        let v = Vector3::new(
+            ratios.first().and_then(|v| v.as_float()).unwrap_or(0.0),
+            ratios.get(1).and_then(|v| v.as_float()).unwrap_or(0.0),
+            ratios.get(2).and_then(|v| v.as_float()).unwrap_or(0.0),
+        );
+        Ok(v.try_normalize(1e-12))
+    }
+
+    /// Read IfcAxis2Placement3D as (origin, x axis, y axis, z axis)
+    fn read_placement(placement: &DecodedEntity, decoder: &mut EntityDecoder) -> Result<Frame> {
+        let location = match placement.get(0) {
+            Some(attr) => match decoder.resolve_ref(attr)? {
+                Some(point) => Self::read_point(&point)?,
+                None => Point3::origin(),
+            },
+            None => Point3::origin(),
+        };
+
+        let z = Self::read_direction(placement, 1, decoder)?.unwrap_or_else(Vector3::z);
+        let ref_x = Self::read_direction(placement, 2, decoder)?.unwrap_or_else(Vector3::x);
+
+        // Orthogonalize the reference direction against Z
+        let x = (ref_x - z * ref_x.dot(&z))
+            .try_normalize(1e-9)
+            .unwrap_or_else(|| {
+                let fallback = if z.x.abs() < 0.9 {
+                    Vector3::x()
+                } else {
+                    Vector3::y()
+                };
+                (fallback - z * fallback.dot(&z)).normalize()
+            });
+        let y = z.cross(&x);
+
+        Ok((location, x, y, z))
+    }
+
+    /// Resolve the placement attribute of a surface or conic
+    fn resolve_placement(entity: &DecodedEntity, decoder: &mut EntityDecoder) -> Result<Frame> {
+        let placement_attr = entity
+            .get(0)
+            .ok_or_else(|| Error::geometry(format!("{} missing Position", entity.ifc_type)))?;
+        match decoder.resolve_ref(placement_attr)? {
+            Some(placement) => Self::read_placement(&placement, decoder),
+            None => Ok((Point3::origin(), Vector3::x(), Vector3::y(), Vector3::z())),
+        }
+    }
+
+    /// Read the point of an IfcVertexPoint
+    fn read_vertex(
+        vertex_attr: Option<&ifc_lite_core::AttributeValue>,
+        decoder: &mut EntityDecoder,
+    ) -> Result<Option<(u32, Point3<f64>)>> {
+        let Some(vertex) = vertex_attr
+            .map(|a| decoder.resolve_ref(a))
+            .transpose()?
+            .flatten()
+        else {
+            return Ok(None);
+        };
+        let point_attr = vertex
+            .get(0)
+            .ok_or_else(|| Error::geometry("VertexPoint missing geometry".to_string()))?;
+        match decoder.resolve_ref(point_attr)? {
+            Some(point) => Ok(Some((vertex.id, Self::read_point(&point)?))),
+            None => Ok(None),
+        }
+    }
+
+    /// Sample points along one oriented edge, excluding its end point
+    fn sample_edge(
+        &self,
+        oriented_edge: &DecodedEntity,
+        decoder: &mut EntityDecoder,
+    ) -> Result<Vec<Point3<f64>>> {
+        // IfcOrientedEdge: EdgeStart*, EdgeEnd*, EdgeElement, Orientation
+        // (start/end are derived, so read them from the underlying IfcEdgeCurve)
+        let (edge, forward) = if oriented_edge.ifc_type == IfcType::IfcOrientedEdge {
+            let element_attr = oriented_edge
+                .get(2)
+                .ok_or_else(|| Error::geometry("OrientedEdge missing EdgeElement".to_string()))?;
+            let edge = decoder
+                .resolve_ref(element_attr)?
+                .ok_or_else(|| Error::geometry("Failed to resolve EdgeElement".to_string()))?;
+            let forward = oriented_edge.get(3).and_then(|a| a.as_enum()) != Some("F");
+            (edge, forward)
+        } else {
+            (oriented_edge.clone(), true)
+        };
+
+        // IfcEdgeCurve: EdgeStart, EdgeEnd, EdgeGeometry, SameSense
+        let Some((start_id, start)) = Self::read_vertex(edge.get(0), decoder)? else {
+            return Ok(Vec::new());
+        };
+        let end = Self::read_vertex(edge.get(1), decoder)?;
+
+        let mut points = vec![start];
+        let curve = match edge.get(2) {
+            Some(attr) => decoder.resolve_ref(attr)?,
+            None => None,
+        };
+
+        if let Some(curve) = curve.filter(|c| c.ifc_type == IfcType::IfcCircle) {
+            // IfcCircle: Position, Radius
+            let radius = curve.get_float(1).unwrap_or(0.0);
+            let (center, x, y, _) = Self::resolve_placement(&curve, decoder)?;
+            let angle_of = |p: &Point3<f64>| {
+                let d = p - center;
+                d.dot(&y).atan2(d.dot(&x))
+            };
+
+            let same_sense = edge.get(3).and_then(|a| a.as_enum()) != Some("F");
+            let a0 = angle_of(&start);
+            let mut sweep = match end {
+                Some((end_id, _)) if end_id == start_id => std::f64::consts::TAU,
+                Some((_, end)) => (angle_of(&end) - a0).rem_euclid(std::f64::consts::TAU),
+                None => 0.0,
+            };
+            if !same_sense && sweep < std::f64::consts::TAU {
+                sweep -= std::f64::consts::TAU;
+            } else if !same_sense {
+                sweep = -sweep;
+            }
+
+            let segments = self.segments_for_angle(sweep);
+            for k in 1..segments {
+                let a = a0 + sweep * k as f64 / segments as f64;
+                points.push(center + (x * a.cos() + y * a.sin()) * radius);
+            }
+        }
+
+        if !forward {
+            // Reversed traversal: walk from the edge end back to its start
+            if let Some((_, end)) = end {
+                points.push(end);
+                points.remove(0);
+            }
+            points.reverse();
+        }
+
+        Ok(points)
+    }
+
+    /// Collect the bounds of an advanced face as point loops
+    fn face_loops(
+        &self,
+        face: &DecodedEntity,
+        decoder: &mut EntityDecoder,
+    ) -> Result<Vec<BoundLoop>> {
+        let bounds_attr = face
+            .get(0)
+            .ok_or_else(|| Error::geometry("AdvancedFace missing Bounds".to_string()))?;
+        let bounds = decoder.resolve_ref_list(bounds_attr)?;
+
+        let mut loops = Vec::with_capacity(bounds.len());
+        for bound in bounds {
+            // IfcFaceBound / IfcFaceOuterBound: Bound, Orientation
+            let loop_attr = bound
+                .get(0)
+                .ok_or_else(|| Error::geometry("FaceBound missing Bound".to_string()))?;
+            let Some(edge_loop) = decoder.resolve_ref(loop_attr)? else {
+                continue;
+            };
+
+            let mut points = Vec::new();
+            if edge_loop.ifc_type == IfcType::IfcEdgeLoop {
+                let edges_attr = edge_loop
+                    .get(0)
+                    .ok_or_else(|| Error::geometry("EdgeLoop missing EdgeList".to_string()))?;
+                for edge in decoder.resolve_ref_list(edges_attr)? {
+                    points.extend(self.sample_edge(&edge, decoder)?);
+                }
+            } else if edge_loop.ifc_type == IfcType::IfcPolyLoop {
+                let polygon_attr = edge_loop
+                    .get(0)
+                    .ok_or_else(|| Error::geometry("PolyLoop missing Polygon".to_string()))?;
+                for point in decoder.resolve_ref_list(polygon_attr)? {
+                    points.push(Self::read_point(&point)?);
+                }
+            }
+
+            if bound.get(1).and_then(|a| a.as_enum()) == Some("F") {
+                points.reverse();
+            }
+
+            if points.len() >= 3 {
+                loops.push(BoundLoop {
+                    points,
+                    outer: bound.ifc_type == IfcType::IfcFaceOuterBound,
+                });
+            }
+        }
+
+        // Without an explicit outer bound, the largest loop is the outer one
+        if !loops.iter().any(|l| l.outer) {
+            if let Some(largest) = loops.iter_mut().max_by_key(|l| l.points.len()) {
+                largest.outer = true;
+            }
+        }
+
+        Ok(loops)
+    }
+
+    /// Process a planar face (IfcPlane surface), including inner bounds
+    fn process_planar_face(
+        &self,
+        face: &DecodedEntity,
+        decoder: &mut EntityDecoder,
+    ) -> Result<(Vec<f32>, Vec<u32>)> {
+        use crate::triangulation::{
+            calculate_polygon_normal, project_to_2d, project_to_2d_with_basis,
+            triangulate_polygon_with_holes,
+        };
+
+        let loops = self.face_loops(face, decoder)?;
+        let Some(outer) = loops.iter().find(|l| l.outer) else {
+            return Ok((Vec::new(), Vec::new()));
+        };
+        let holes: Vec<&BoundLoop> = loops.iter().filter(|l| !l.outer).collect();
+
+        let normal = calculate_polygon_normal(&outer.points);
+        let (outer_2d, u_axis, v_axis, origin) = project_to_2d(&outer.points, &normal);
+        let holes_2d: Vec<_> = holes
+            .iter()
+            .map(|h| project_to_2d_with_basis(&h.points, &u_axis, &v_axis, &origin))
+            .collect();
+
+        let triangles = match triangulate_polygon_with_holes(&outer_2d, &holes_2d) {
+            Ok(triangles) => triangles,
+            Err(_) => return Ok((Vec::new(), Vec::new())),
+        };
+
+        // Vertex order matches the triangulator: outer, then holes
+        let mut positions = Vec::new();
+        for point in outer
+            .points
+            .iter()
+            .chain(holes.iter().flat_map(|h| h.points.iter()))
+        {
+            positions.push(point.x as f32);
+            positions.push(point.y as f32);
+            positions.push(point.z as f32);
+        }
+        let indices = triangles.into_iter().map(|i| i as u32).collect();
+
+        Ok((positions, indices))
+    }
+
+    /// Process a cylindrical face (IfcCylindricalSurface)
+    ///
+    /// The face is tessellated over the angular and axial range covered by its
+    /// bounds, which is exact for the usual pipe and column patches bounded by
+    /// circles and seam lines.
+    fn process_cylindrical_face(
+        &self,
+        face: &DecodedEntity,
+        surface: &DecodedEntity,
+        decoder: &mut EntityDecoder,
+    ) -> Result<(Vec<f32>, Vec<u32>)> {
+        use std::f64::consts::TAU;
+
+        // IfcCylindricalSurface: Position, Radius
+        let radius = surface.get_float(1).unwrap_or(0.0);
+        if radius <= 0.0 {
+            return Ok((Vec::new(), Vec::new()));
+        }
+        let (center, x, y, z) = Self::resolve_placement(surface, decoder)?;
+
+        let loops = self.face_loops(face, decoder)?;
+        let mut angles = Vec::new();
+        let (mut z_min, mut z_max) = (f64::MAX, f64::MIN);
+        for point in loops.iter().flat_map(|l| l.points.iter()) {
+            let d = point - center;
+            angles.push(d.dot(&y).atan2(d.dot(&x)).rem_euclid(TAU));
+            z_min = z_min.min(d.dot(&z));
+            z_max = z_max.max(d.dot(&z));
+        }
+        if angles.len() < 2 || z_max - z_min < 1e-9 {
+            return Ok((Vec::new(), Vec::new()));
+        }
+
+        // Covered arc is the complement of the largest angular gap between bound points
+        angles.sort_by(|a, b| a.total_cmp(b));
+        let mut gap_end = angles[0];
+        let mut largest_gap = angles[0] + TAU - angles[angles.len() - 1];
+        for pair in angles.windows(2) {
+            if pair[1] - pair[0] > largest_gap {
+                largest_gap = pair[1] - pair[0];
+                gap_end = pair[1];
+            }
+        }
+        // Sampled full circles leave only tolerance-sized gaps
+        let (start, sweep) = if largest_gap <= self.angular_tolerance * 1.5 {
+            (0.0, TAU)
+        } else {
+            (gap_end, TAU - largest_gap)
+        };
+
+        let segments = self.segments_for_angle(sweep);
+        Ok(Self::tessellate_grid(segments, 1, |s, t| {
+            let a = start + sweep * s;
+            let h = z_min + (z_max - z_min) * t;
+            center + (x * a.cos() + y * a.sin()) * radius + z * h
+        }))
+    }
+
+    /// Parse control points (and weights for rational surfaces)
     fn parse_control_points(
         &self,
         bspline: &DecodedEntity,
         decoder: &mut EntityDecoder,
-    ) -> Result<Vec<Vec<Point3<f64>>>> {
+    ) -> Result<ControlNet> {
         // Attribute 2: ControlPointsList (LIST of LIST of IfcCartesianPoint)
         let cp_list_attr = bspline.get(2).ok_or_else(|| {
             Error::geometry("BSplineSurface missing ControlPointsList".to_string())
@@ -1991,26 +2684,30 @@ impl AdvancedBrepProcessor {
             .as_list()
             .ok_or_else(|| Error::geometry("Expected control point list".to_string()))?;
 
+        // IfcRationalBSplineSurfaceWithKnots attribute 12: WeightsData (LIST of LIST of REAL)
+        let weights = if bspline.ifc_type == IfcType::IfcRationalBSplineSurfaceWithKnots {
+            bspline.get(12).and_then(|a| a.as_list())
+        } else {
+            None
+        };
+
         let mut result = Vec::with_capacity(rows.len());
 
-        for row in rows {
+        for (i, row) in rows.iter().enumerate() {
             let cols = row
                 .as_list()
                 .ok_or_else(|| Error::geometry("Expected control point row".to_string()))?;
+            let row_weights = weights.and_then(|w| w.get(i)).and_then(|r| r.as_list());
 
             let mut row_points = Vec::with_capacity(cols.len());
-            for col in cols {
+            for (j, col) in cols.iter().enumerate() {
                 if let Some(point_id) = col.as_entity_ref() {
                     let point = decoder.decode_by_id(point_id)?;
-                    let coords = point.get(0).and_then(|v| v.as_list()).ok_or_else(|| {
-                        Error::geometry("CartesianPoint missing coordinates".to_string())
-                    })?;
-
-                    let x = coords.first().and_then(|v| v.as_float()).unwrap_or(0.0);
-                    let y = coords.get(1).and_then(|v| v.as_float()).unwrap_or(0.0);
-                    let z = coords.get(2).and_then(|v| v.as_float()).unwrap_or(0.0);
-
-                    row_points.push(Point3::new(x, y, z));
+                    let weight = row_weights
+                        .and_then(|r| r.get(j))
+                        .and_then(|w| w.as_float())
+                        .unwrap_or(1.0);
+                    row_points.push((Self::read_point(&point)?, weight));
                 }
             }
             result.push(row_points);
@@ -2046,49 +2743,29 @@ impl AdvancedBrepProcessor {
         // 10: VKnots (LIST of REAL)
         // 11: KnotSpec
 
-        // Get U multiplicities
-        let u_mult_attr = bspline
-            .get(7)
-            .ok_or_else(|| Error::geometry("BSplineSurface missing UMultiplicities".to_string()))?;
-        let u_mults: Vec<i64> = u_mult_attr
-            .as_list()
-            .ok_or_else(|| Error::geometry("Expected U multiplicities list".to_string()))?
-            .iter()
-            .filter_map(|v| v.as_int())
-            .collect();
-
-        // Get V multiplicities
-        let v_mult_attr = bspline
-            .get(8)
-            .ok_or_else(|| Error::geometry("BSplineSurface missing VMultiplicities".to_string()))?;
-        let v_mults: Vec<i64> = v_mult_attr
-            .as_list()
-            .ok_or_else(|| Error::geometry("Expected V multiplicities list".to_string()))?
-            .iter()
-            .filter_map(|v| v.as_int())
-            .collect();
-
-        // Get U knots
-        let u_knots_attr = bspline
-            .get(9)
-            .ok_or_else(|| Error::geometry("BSplineSurface missing UKnots".to_string()))?;
-        let u_knot_values: Vec<f64> = u_knots_attr
-            .as_list()
-            .ok_or_else(|| Error::geometry("Expected U knots list".to_string()))?
-            .iter()
-            .filter_map(|v| v.as_float())
-            .collect();
+        let int_list = |index: usize, name: &str| -> Result<Vec<i64>> {
+            Ok(bspline
+                .get(index)
+                .and_then(|a| a.as_list())
+                .ok_or_else(|| Error::geometry(format!("BSplineSurface missing {}", name)))?
+                .iter()
+                .filter_map(|v| v.as_int())
+                .collect())
+        };
+        let float_list = |index: usize, name: &str| -> Result<Vec<f64>> {
+            Ok(bspline
+                .get(index)
+                .and_then(|a| a.as_list())
+                .ok_or_else(|| Error::geometry(format!("BSplineSurface missing {}", name)))?
+                .iter()
+                .filter_map(|v| v.as_float())
+                .collect())
+        };
 
-        // Get V knots
-        let v_knots_attr = bspline
-            .get(10)
-            .ok_or_else(|| Error::geometry("BSplineSurface missing VKnots".to_string()))?;
-        let v_knot_values: Vec<f64> = v_knots_attr
-            .as_list()
-            .ok_or_else(|| Error::geometry("Expected V knots list".to_string()))?
-            .iter()
-            .filter_map(|v| v.as_float())
-            .collect();
+        let u_mults = int_list(7, "UMultiplicities")?;
+        let v_mults = int_list(8, "VMultiplicities")?;
+        let u_knot_values = float_list(9, "UKnots")?;
+        let v_knot_values = float_list(10, "VKnots")?;
 
         // Expand knot vectors with multiplicities
         let u_knots = Self::expand_knots(&u_knot_values, &u_mults);
@@ -2097,114 +2774,17 @@ impl AdvancedBrepProcessor {
         Ok((u_knots, v_knots))
     }
 
-    /// Process a planar face (IfcPlane surface)
-    fn process_planar_face(
-        &self,
-        face: &DecodedEntity,
-        decoder: &mut EntityDecoder,
-    ) -> Result<(Vec<f32>, Vec<u32>)> {
-        // Get bounds from face (attribute 0)
-        let bounds_attr = face
-            .get(0)
-            .ok_or_else(|| Error::geometry("AdvancedFace missing Bounds".to_string()))?;
-
-        let bounds = bounds_attr
-            .as_list()
-            .ok_or_else(|| Error::geometry("Expected bounds list".to_string()))?;
-
-        let mut positions = Vec::new();
-        let mut indices = Vec::new();
-
-        for bound in bounds {
-            if let Some(bound_id) = bound.as_entity_ref() {
-                let bound_entity = decoder.decode_by_id(bound_id)?;
-
-                // Get the loop (attribute 0: Bound)
-                let loop_attr = bound_entity
-                    .get(0)
-                    .ok_or_else(|| Error::geometry("FaceBound missing Bound".to_string()))?;
-
-                let loop_entity = decoder
-                    .resolve_ref(loop_attr)?
-                    .ok_or_else(|| Error::geometry("Failed to resolve loop".to_string()))?;
-
-                // Get oriented edges from edge loop
-                if loop_entity
-                    .ifc_type
-                    .as_str()
-                    .eq_ignore_ascii_case("IFCEDGELOOP")
-                {
-                    let edges_attr = loop_entity
-                        .get(0)
-                        .ok_or_else(|| Error::geometry("EdgeLoop missing EdgeList".to_string()))?;
-
-                    let edges = edges_attr
-                        .as_list()
-                        .ok_or_else(|| Error::geometry("Expected edge list".to_string()))?;
-
-                    let mut polygon_points = Vec::new();
-
-                    for edge_ref in edges {
-                        if let Some(edge_id) = edge_ref.as_entity_ref() {
-                            let oriented_edge = decoder.decode_by_id(edge_id)?;
-
-                            // IfcOrientedEdge: EdgeStart, EdgeEnd, EdgeElement, Orientation
-                            // We need EdgeStart vertex
-                            let start_attr = oriented_edge.get(0).ok_or_else(|| {
-                                Error::geometry("OrientedEdge missing EdgeStart".to_string())
-                            })?;
-
-                            if let Some(vertex) = decoder.resolve_ref(start_attr)? {
-                                // IfcVertexPoint has VertexGeometry (IfcCartesianPoint)
-                                let point_attr = vertex.get(0).ok_or_else(|| {
-                                    Error::geometry("VertexPoint missing geometry".to_string())
-                                })?;
-
-                                if let Some(point) = decoder.resolve_ref(point_attr)? {
-                                    let coords = point
-                                        .get(0)
-                                        .and_then(|v| v.as_list())
-                                        .ok_or_else(|| {
-                                            Error::geometry(
-                                                "CartesianPoint missing coords".to_string(),
-                                            )
-                                        })?;
-
-                                    let x =
-                                        coords.first().and_then(|v| v.as_float()).unwrap_or(0.0);
-                                    let y = coords.get(1).and_then(|v| v.as_float()).unwrap_or(0.0);
-                                    let z = coords.get(2).and_then(|v| v.as_float()).unwrap_or(0.0);
-
-                                    polygon_points.push(Point3::new(x, y, z));
-                                }
-                            }
-                        }
-                    }
-
-                    // Triangulate the polygon
-                    if polygon_points.len() >= 3 {
-                        let base_idx = (positions.len() / 3) as u32;
-
-                        for point in &polygon_points {
-                            positions.push(point.x as f32);
-                            positions.push(point.y as f32);
-                            positions.push(point.z as f32);
-                        }
-
-                        // TODO: Fan triangulation assumes convex polygons. For non-convex faces,
-                        // consider using triangulate_polygon_with_holes from FacetedBrepProcessor.
-                        // Fan triangulation for simple convex polygons
-                        for i in 1..polygon_points.len() - 1 {
-                            indices.push(base_idx);
-                            indices.push(base_idx + i as u32);
-                            indices.push(base_idx + i as u32 + 1);
-                        }
-                    }
-                }
-            }
-        }
-
-        Ok((positions, indices))
+    /// Total turning angle of a control polygon
+    fn turning_angle<'a>(points: impl Iterator<Item = &'a Point3<f64>>) -> f64 {
+        let points: Vec<&Point3<f64>> = points.collect();
+        points
+            .windows(3)
+            .filter_map(|w| {
+                let a = (w[1] - w[0]).try_normalize(1e-12)?;
+                let b = (w[2] - w[1]).try_normalize(1e-12)?;
+                Some(a.dot(&b).clamp(-1.0, 1.0).acos())
+            })
+            .sum()
     }
 
     /// Process a B-spline surface face
@@ -2223,16 +2803,42 @@ impl AdvancedBrepProcessor {
         // Parse knot vectors
         let (u_knots, v_knots) = self.parse_knot_vectors(bspline)?;
 
-        // Determine tessellation resolution based on surface complexity
-        let u_segments = (control_points.len() * 3).clamp(8, 24);
-        let v_segments = if !control_points.is_empty() {
-            (control_points[0].len() * 3).clamp(4, 24)
-        } else {
-            4
-        };
+        let n_u = control_points.len();
+        let n_v = control_points.first().map_or(0, |row| row.len());
+        if n_u <= u_degree
+            || n_v <= v_degree
+            || u_knots.len() != n_u + u_degree + 1
+            || v_knots.len() != n_v + v_degree + 1
+        {
+            return Err(Error::geometry(
+                "BSplineSurface knot vectors do not match control points".to_string(),
+            ));
+        }
+
+        // Resolution from how much the control net bends in each direction,
+        // with a floor of 8 x 4 segments (and one per control polygon leg)
+        let u_turn = (0..n_v)
+            .map(|j| {
+                Self::turning_angle(
+                    control_points
+                        .iter()
+                        .filter_map(|row| row.get(j).map(|cp| &cp.0)),
+                )
+            })
+            .fold(0.0, f64::max);
+        let v_turn = control_points
+            .iter()
+            .map(|row| Self::turning_angle(row.iter().map(|cp| &cp.0)))
+            .fold(0.0, f64::max);
+        let u_segments = self
+            .segments_for_angle(u_turn)
+            .max((n_u - 1).max(8).min(self.max_segments));
+        let v_segments = self
+            .segments_for_angle(v_turn)
+            .max((n_v - 1).max(4).min(self.max_segments));
 
         // Tessellate the surface
-        let (positions, indices) = Self::tessellate_bspline_surface(
+        Ok(Self::tessellate_bspline_surface(
             u_degree,
             v_degree,
             &control_points,
@@ -2240,9 +2846,60 @@ impl AdvancedBrepProcessor {
             &v_knots,
             u_segments,
             v_segments,
-        );
+        ))
+    }
 
-        Ok((positions, indices))
+    /// Tessellate all faces of a closed shell
+    fn process_shell(
+        &self,
+        shell: &DecodedEntity,
+        decoder: &mut EntityDecoder,
+        mesh: &mut Mesh,
+    ) -> Result<()> {
+        // Get faces from the shell (IfcClosedShell.CfsFaces)
+        let faces_attr = shell
+            .get(0)
+            .ok_or_else(|| Error::geometry("ClosedShell missing CfsFaces".to_string()))?;
+
+        for face in decoder.resolve_ref_list(faces_attr)? {
+            // IfcAdvancedFace has:
+            // 0: Bounds (list of FaceBound)
+            // 1: FaceSurface (IfcSurface - Plane, BSplineSurface, etc.)
+            // 2: SameSense (boolean)
+
+            let surface_attr = face
+                .get(1)
+                .ok_or_else(|| Error::geometry("AdvancedFace missing FaceSurface".to_string()))?;
+
+            let surface = decoder
+                .resolve_ref(surface_attr)?
+                .ok_or_else(|| Error::geometry("Failed to resolve FaceSurface".to_string()))?;
+
+            let (positions, indices) = match surface.ifc_type {
+                IfcType::IfcPlane => self.process_planar_face(&face, decoder)?,
+                IfcType::IfcCylindricalSurface => {
+                    self.process_cylindrical_face(&face, &surface, decoder)?
+                }
+                IfcType::IfcBSplineSurfaceWithKnots
+                | IfcType::IfcRationalBSplineSurfaceWithKnots => {
+                    // A malformed surface only drops its own face
+                    match self.process_bspline_face(&surface, decoder) {
+                        Ok(result) => result,
+                        Err(_) => continue,
+                    }
+                }
+                // Unsupported surface type - skip
+                _ => continue,
+            };
+
+            // Merge into combined mesh
+            let base_idx = (mesh.positions.len() / 3) as u32;
+            mesh.positions.extend(positions);
+            mesh.indices
+                .extend(indices.into_iter().map(|idx| base_idx + idx));
+        }
+
+        Ok(())
     }
 }
 
@@ -2255,8 +2912,8 @@ impl GeometryProcessor for AdvancedBrepProcessor {
     ) -> Result<Mesh> {
         // IfcAdvancedBrep attributes:
         // 0: Outer (IfcClosedShell)
+        // 1: Voids (SET of IfcClosedShell, IfcAdvancedBrepWithVoids only)
 
-        // Get the outer shell
         let shell_attr = entity
             .get(0)
             .ok_or_else(|| Error::geometry("AdvancedBrep missing Outer shell".to_string()))?;
@@ -2265,63 +2922,18 @@ impl GeometryProcessor for AdvancedBrepProcessor {
             .resolve_ref(shell_attr)?
             .ok_or_else(|| Error::geometry("Failed to resolve Outer shell".to_string()))?;
 
-        // Get faces from the shell (IfcClosedShell.CfsFaces)
-        let faces_attr = shell
-            .get(0)
-            .ok_or_else(|| Error::geometry("ClosedShell missing CfsFaces".to_string()))?;
-
-        let faces = faces_attr
-            .as_list()
-            .ok_or_else(|| Error::geometry("Expected face list".to_string()))?;
-
-        let mut all_positions = Vec::new();
-        let mut all_indices = Vec::new();
-
-        for face_ref in faces {
-            if let Some(face_id) = face_ref.as_entity_ref() {
-                let face = decoder.decode_by_id(face_id)?;
-
-                // IfcAdvancedFace has:
-                // 0: Bounds (list of FaceBound)
-                // 1: FaceSurface (IfcSurface - Plane, BSplineSurface, etc.)
-                // 2: SameSense (boolean)
-
-                let surface_attr = face.get(1).ok_or_else(|| {
-                    Error::geometry("AdvancedFace missing FaceSurface".to_string())
-                })?;
-
-                let surface = decoder
-                    .resolve_ref(surface_attr)?
-                    .ok_or_else(|| Error::geometry("Failed to resolve FaceSurface".to_string()))?;
-
-                let surface_type = surface.ifc_type.as_str().to_uppercase();
-                let (positions, indices) = if surface_type == "IFCPLANE" {
-                    // Planar face - extract boundary vertices
-                    self.process_planar_face(&face, decoder)?
-                } else if surface_type == "IFCBSPLINESURFACEWITHKNOTS"
-                    || surface_type == "IFCRATIONALBSPLINESURFACEWITHKNOTS"
-                {
-                    // B-spline surface - tessellate
-                    self.process_bspline_face(&surface, decoder)?
-                } else {
-                    // Unsupported surface type - skip
-                    continue;
-                };
+        let mut mesh = Mesh::new();
+        self.process_shell(&shell, decoder, &mut mesh)?;
 
-                // Merge into combined mesh
-                let base_idx = (all_positions.len() / 3) as u32;
-                all_positions.extend(positions);
-                for idx in indices {
-                    all_indices.push(base_idx + idx);
+        if entity.ifc_type == IfcType::IfcAdvancedBrepWithVoids {
+            if let Some(voids_attr) = entity.get(1) {
+                for void_shell in decoder.resolve_ref_list(voids_attr)? {
+                    self.process_shell(&void_shell, decoder, &mut mesh)?;
                 }
             }
         }
 
-        Ok(Mesh {
-            positions: all_positions,
-            normals: Vec::new(),
-            indices: all_indices,
-        })
+        Ok(mesh)
     }
 
     fn supported_types(&self) -> Vec<IfcType> {
@@ -2368,6 +2980,103 @@ mod tests {
         assert!(mesh.indices.len() >= 3 * 100, "Should have many triangles");
     }
 
+    #[test]
+    fn test_advanced_brep_cylinder() {
+        // Pipe segment: radius 1, height 2, cylindrical side and two circular caps
+        let content = r#"
+#1=IFCCARTESIANPOINT((0.,0.,0.));
+#2=IFCCARTESIANPOINT((0.,0.,2.));
+#3=IFCDIRECTION((0.,0.,1.));
+#4=IFCDIRECTION((1.,0.,0.));
+#5=IFCAXIS2PLACEMENT3D(#1,#3,#4);
+#6=IFCAXIS2PLACEMENT3D(#2,#3,#4);
+#10=IFCCARTESIANPOINT((1.,0.,0.));
+#11=IFCCARTESIANPOINT((1.,0.,2.));
+#12=IFCVERTEXPOINT(#10);
+#13=IFCVERTEXPOINT(#11);
+#20=IFCCIRCLE(#5,1.);
+#21=IFCCIRCLE(#6,1.);
+#22=IFCPOLYLINE((#10,#11));
+#30=IFCEDGECURVE(#12,#12,#20,.T.);
+#31=IFCEDGECURVE(#13,#13,#21,.T.);
+#32=IFCEDGECURVE(#12,#13,#22,.T.);
+#40=IFCORIENTEDEDGE(*,*,#30,.T.);
+#41=IFCORIENTEDEDGE(*,*,#31,.F.);
+#42=IFCORIENTEDEDGE(*,*,#32,.T.);
+#43=IFCORIENTEDEDGE(*,*,#32,.F.);
+#50=IFCEDGELOOP((#40,#42,#41,#43));
+#51=IFCEDGELOOP((#40));
+#52=IFCEDGELOOP((#31));
+#60=IFCFACEOUTERBOUND(#50,.T.);
+#61=IFCFACEOUTERBOUND(#51,.F.);
+#62=IFCFACEOUTERBOUND(#52,.T.);
+#70=IFCCYLINDRICALSURFACE(#5,1.);
+#71=IFCPLANE(#5);
+#72=IFCPLANE(#6);
+#80=IFCADVANCEDFACE((#60),#70,.T.);
+#81=IFCADVANCEDFACE((#61),#71,.F.);
+#82=IFCADVANCEDFACE((#62),#72,.T.);
+#90=IFCCLOSEDSHELL((#80,#81,#82));
+#91=IFCADVANCEDBREP(#90);
+"#;
+
+        let mut decoder = EntityDecoder::new(content);
+        let schema = IfcSchema::new();
+        let entity = decoder.decode_by_id(91).unwrap();
+
+        let coarse = AdvancedBrepProcessor::new()
+            .process(&entity, &mut decoder, &schema)
+            .unwrap();
+        let fine = AdvancedBrepProcessor::with_angular_tolerance(0.05)
+            .process(&entity, &mut decoder, &schema)
+            .unwrap();
+
+        // Side plus both caps, finer tolerance gives more triangles
+        assert!(coarse.triangle_count() > 60);
+        assert!(fine.triangle_count() > coarse.triangle_count() * 2);
+
+        let (min, max) = coarse.bounds();
+        assert!((min.x + 1.0).abs() < 1e-3 && (max.x - 1.0).abs() < 1e-3);
+        assert!((min.y + 1.0).abs() < 1e-2 && (max.y - 1.0).abs() < 1e-2);
+        assert!(min.z.abs() < 1e-6 && (max.z - 2.0).abs() < 1e-6);
+
+        // Every side vertex lies on the cylinder
+        for p in coarse.positions.chunks_exact(3) {
+            let r = (p[0] * p[0] + p[1] * p[1]).sqrt();
+            assert!(r <= 1.0 + 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_rational_bspline_quarter_circle() {
+        // Degree-2 rational patch: exact quarter cylinder (radius 1) extruded 1 along Z
+        let w = std::f64::consts::FRAC_1_SQRT_2;
+        let content = format!(
+            r#"
+#1=IFCCARTESIANPOINT((1.,0.,0.));
+#2=IFCCARTESIANPOINT((1.,0.,1.));
+#3=IFCCARTESIANPOINT((1.,1.,0.));
+#4=IFCCARTESIANPOINT((1.,1.,1.));
+#5=IFCCARTESIANPOINT((0.,1.,0.));
+#6=IFCCARTESIANPOINT((0.,1.,1.));
+#7=IFCRATIONALBSPLINESURFACEWITHKNOTS(2,1,((#1,#2),(#3,#4),(#5,#6)),.UNSPECIFIED.,.F.,.F.,.F.,(3,3),(2,2),(0.,1.),(0.,1.),.UNSPECIFIED.,((1.,1.),({w},{w}),(1.,1.)));
+"#
+        );
+
+        let mut decoder = EntityDecoder::new(&content);
+        let surface = decoder.decode_by_id(7).unwrap();
+        let processor = AdvancedBrepProcessor::new();
+        let (positions, indices) = processor
+            .process_bspline_face(&surface, &mut decoder)
+            .unwrap();
+
+        assert!(!indices.is_empty());
+        for p in positions.chunks_exact(3) {
+            let r = (p[0] * p[0] + p[1] * p[1]).sqrt();
+            assert!((r - 1.0).abs() < 1e-4, "radius {} off circle", r);
+        }
+    }
+
     #[test]
     fn test_extruded_area_solid() {
         let content = r#"
@@ -2444,6 +3153,135 @@ mod tests {
         // The mesh should have geometry (base extrusion clipped)
         assert!(!mesh.is_empty(), "BooleanResult should produce geometry");
         assert!(!mesh.positions.is_empty());
+
+        // The plane normal points away from the removed material below z=150
+        let (min, max) = mesh.bounds();
+        assert!((min.z - 150.0).abs() < 1e-3, "min z {}", min.z);
+        assert!((max.z - 300.0).abs() < 1e-3, "max z {}", max.z);
+        let volume = crate::quantities::mesh_measures(&mesh).volume.unwrap();
+        assert!(
+            (volume - 100.0 * 200.0 * 150.0).abs() < 1.0,
+            "volume {}",
+            volume
+        );
+    }
+
+    /// Two 2 x 2 x 2 boxes, the second shifted by 1 along X
+    const TWO_BOXES: &str = r#"
+#1=IFCRECTANGLEPROFILEDEF(.AREA.,$,$,2.0,2.0);
+#2=IFCDIRECTION((0.0,0.0,1.0));
+#3=IFCEXTRUDEDAREASOLID(#1,$,#2,2.0);
+#4=IFCCARTESIANPOINT((1.0,0.0,0.0));
+#5=IFCAXIS2PLACEMENT3D(#4,$,$);
+#6=IFCEXTRUDEDAREASOLID(#1,#5,#2,2.0);
+#7=IFCBOOLEANRESULT(.UNION.,#3,#6);
+#8=IFCBOOLEANRESULT(.INTERSECTION.,#3,#6);
+#9=IFCBOOLEANRESULT(.DIFFERENCE.,#3,#6);
+#10=IFCCSGSOLID(#8);
+"#;
+
+    fn boolean_volume(content: &str, id: u32) -> (f64, Mesh) {
+        let mut decoder = EntityDecoder::new(content);
+        let entity = decoder.decode_by_id(id).unwrap();
+        let mesh = BooleanClippingProcessor::new()
+            .process(&entity, &mut decoder, &IfcSchema::new())
+            .unwrap();
+        let volume = crate::quantities::mesh_measures(&mesh)
+            .volume
+            .unwrap_or(0.0);
+        (volume, mesh)
+    }
+
+    #[test]
+    fn test_boolean_solid_operators() {
+        let (union, mesh) = boolean_volume(TWO_BOXES, 7);
+        assert!((union - 12.0).abs() < 1e-3, "union {}", union);
+        let (min, max) = mesh.bounds();
+        assert!((min.x + 1.0).abs() < 1e-5 && (max.x - 2.0).abs() < 1e-5);
+
+        let (intersection, mesh) = boolean_volume(TWO_BOXES, 8);
+        assert!(
+            (intersection - 4.0).abs() < 1e-3,
+            "intersection {}",
+            intersection
+        );
+        let (min, max) = mesh.bounds();
+        assert!((min.x - 0.0).abs() < 1e-5 && (max.x - 1.0).abs() < 1e-5);
+
+        let (difference, _) = boolean_volume(TWO_BOXES, 9);
+        assert!((difference - 4.0).abs() < 1e-3, "difference {}", difference);
+
+        let (csg, _) = boolean_volume(TWO_BOXES, 10);
+        assert!((csg - 4.0).abs() < 1e-3, "csg solid {}", csg);
+    }
+
+    #[test]
+    fn test_boolean_half_space_agreement_and_operators() {
+        // 100 x 200 x 300 box cut by the plane z=150 with normal +Z
+        let content = r#"
+#1=IFCRECTANGLEPROFILEDEF(.AREA.,$,$,100.0,200.0);
+#2=IFCDIRECTION((0.0,0.0,1.0));
+#3=IFCEXTRUDEDAREASOLID(#1,$,#2,300.0);
+#4=IFCCARTESIANPOINT((0.0,0.0,150.0));
+#5=IFCAXIS2PLACEMENT3D(#4,#2,$);
+#6=IFCPLANE(#5);
+#7=IFCHALFSPACESOLID(#6,.T.);
+#8=IFCHALFSPACESOLID(#6,.F.);
+#9=IFCCARTESIANPOINT((-50.0,-100.0,0.0));
+#10=IFCBOUNDINGBOX(#9,100.0,200.0,300.0);
+#11=IFCBOXEDHALFSPACE(#6,.T.,#10);
+#20=IFCBOOLEANRESULT(.DIFFERENCE.,#3,#8);
+#21=IFCBOOLEANRESULT(.INTERSECTION.,#3,#7);
+#22=IFCBOOLEANRESULT(.INTERSECTION.,#3,#8);
+#23=IFCBOOLEANCLIPPINGRESULT(.DIFFERENCE.,#3,#11);
+#24=IFCBOOLEANRESULT(.UNION.,#3,#7);
+"#;
+        let half = 100.0 * 200.0 * 150.0;
+        // (entity, expected z range, expected volume)
+        let cases = [
+            // AgreementFlag .F.: the material is above the plane
+            (20, (0.0, 150.0), half),
+            (21, (0.0, 150.0), half),
+            (22, (150.0, 300.0), half),
+            // The box of a boxed half-space is only a bounding hint
+            (23, (150.0, 300.0), half),
+            // A union with an unbounded half-space keeps the first operand
+            (24, (0.0, 300.0), 2.0 * half),
+        ];
+        for (id, (bottom, top), expected) in cases {
+            let (volume, mesh) = boolean_volume(content, id);
+            let (min, max) = mesh.bounds();
+            assert!((min.z - bottom).abs() < 1e-3, "#{} min z {}", id, min.z);
+            assert!((max.z - top).abs() < 1e-3, "#{} max z {}", id, max.z);
+            assert!((min.x + 50.0).abs() < 1e-3 && (max.x - 50.0).abs() < 1e-3);
+            assert!((volume - expected).abs() < 1.0, "#{} volume {}", id, volume);
+        }
+    }
+
+    #[test]
+    fn test_boolean_polygonal_bounded_half_space() {
+        // 2 x 2 x 2 box; remove the part above z=1 within the quadrant x>0, y>0
+        let content = r#"
+#1=IFCRECTANGLEPROFILEDEF(.AREA.,$,$,2.0,2.0);
+#2=IFCDIRECTION((0.0,0.0,1.0));
+#3=IFCEXTRUDEDAREASOLID(#1,$,#2,2.0);
+#4=IFCCARTESIANPOINT((0.0,0.0,1.0));
+#5=IFCAXIS2PLACEMENT3D(#4,#2,$);
+#6=IFCPLANE(#5);
+#7=IFCCARTESIANPOINT((0.0,0.0,0.0));
+#8=IFCAXIS2PLACEMENT3D(#7,$,$);
+#10=IFCCARTESIANPOINT((0.0,0.0));
+#11=IFCCARTESIANPOINT((5.0,0.0));
+#12=IFCCARTESIANPOINT((5.0,5.0));
+#13=IFCCARTESIANPOINT((0.0,5.0));
+#14=IFCPOLYLINE((#10,#11,#12,#13,#10));
+#15=IFCPOLYGONALBOUNDEDHALFSPACE(#6,.F.,#8,#14);
+#16=IFCBOOLEANCLIPPINGRESULT(.DIFFERENCE.,#3,#15);
+"#;
+        let (volume, mesh) = boolean_volume(content, 16);
+        assert!((volume - 7.0).abs() < 1e-3, "volume {}", volume);
+        let (_, max) = mesh.bounds();
+        assert!((max.z - 2.0).abs() < 1e-5);
     }
 
     #[test]
@@ -6,6 +6,7 @@
 //!
 //! Dynamic profile processing for parametric, arbitrary, and composite profiles.
 
+use crate::options::GeometryOptions;
 use crate::profile::Profile2D;
 use crate::{Error, Point2, Point3, Result, Vector3};
 use ifc_lite_core::{DecodedEntity, EntityDecoder, IfcSchema, IfcType, ProfileCategory};
@@ -14,12 +15,23 @@ use std::f64::consts::PI;
 /// Profile processor - processes IFC profiles into 2D contours
 pub struct ProfileProcessor {
     schema: IfcSchema,
+    options: GeometryOptions,
 }
 
 impl ProfileProcessor {
     /// Create new profile processor
     pub fn new(schema: IfcSchema) -> Self {
-        Self { schema }
+        Self::with_options(schema, GeometryOptions::default())
+    }
+
+    /// Create profile processor sampling curves with the given options
+    pub fn with_options(schema: IfcSchema, options: GeometryOptions) -> Self {
+        Self { schema, options }
+    }
+
+    /// Options curves are sampled with
+    pub fn options(&self) -> &GeometryOptions {
+        &self.options
     }
 
     /// Process any IFC profile definition
@@ -50,8 +62,8 @@ impl ProfileProcessor {
         // First create the base profile shape
         let mut base_profile = match profile.ifc_type {
             IfcType::IfcRectangleProfileDef => self.process_rectangle(profile),
-            IfcType::IfcCircleProfileDef => self.process_circle(profile),
-            IfcType::IfcCircleHollowProfileDef => self.process_circle_hollow(profile),
+            IfcType::IfcCircleProfileDef => self.process_circle(profile, decoder),
+            IfcType::IfcCircleHollowProfileDef => self.process_circle_hollow(profile, decoder),
             IfcType::IfcRectangleHollowProfileDef => self.process_rectangle_hollow(profile),
             IfcType::IfcIShapeProfileDef => self.process_i_shape(profile),
             IfcType::IfcLShapeProfileDef => self.process_l_shape(profile),
@@ -200,14 +212,19 @@ impl ProfileProcessor {
     /// Process circle profile
     /// IfcCircleProfileDef: ProfileType, ProfileName, Position, Radius
     #[inline]
-    fn process_circle(&self, profile: &DecodedEntity) -> Result<Profile2D> {
+    fn process_circle(
+        &self,
+        profile: &DecodedEntity,
+        decoder: &EntityDecoder,
+    ) -> Result<Profile2D> {
         // Get radius (attribute 3)
         let radius = profile
             .get_float(3)
             .ok_or_else(|| Error::geometry("Circle missing Radius".to_string()))?;
 
-        // Generate circle with 24 segments (matches web-ifc typical quality)
-        let segments = 24;
+        // At least 24 segments (matches web-ifc typical quality), more for large circles
+        let unit_scale = decoder.length_unit_scale().unwrap_or(1.0);
+        let segments = self.options.circle_segments(radius, unit_scale, 24);
         let mut points = Vec::with_capacity(segments);
 
         for i in 0..segments {
@@ -266,7 +283,11 @@ impl ProfileProcessor {
 
     /// Process circle hollow profile (tube/pipe)
     /// IfcCircleHollowProfileDef: ProfileType, ProfileName, Position, Radius, WallThickness
-    fn process_circle_hollow(&self, profile: &DecodedEntity) -> Result<Profile2D> {
+    fn process_circle_hollow(
+        &self,
+        profile: &DecodedEntity,
+        decoder: &EntityDecoder,
+    ) -> Result<Profile2D> {
         let radius = profile
             .get_float(3)
             .ok_or_else(|| Error::geometry("CircleHollow missing Radius".to_string()))?;
@@ -275,7 +296,8 @@ impl ProfileProcessor {
             .ok_or_else(|| Error::geometry("CircleHollow missing WallThickness".to_string()))?;
 
         let inner_radius = radius - wall_thickness;
-        let segments = 24;
+        let unit_scale = decoder.length_unit_scale().unwrap_or(1.0);
+        let segments = self.options.circle_segments(radius, unit_scale, 24);
 
         // Outer circle
         let mut outer_points = Vec::with_capacity(segments);
@@ -718,8 +740,9 @@ impl ProfileProcessor {
             )
         };
 
-        // Generate circle points in 3D (16 segments for full circle)
-        let segments = 16usize;
+        // Generate circle points in 3D
+        let unit_scale = decoder.length_unit_scale().unwrap_or(1.0);
+        let segments = self.options.arc_segments(radius, 2.0 * PI, unit_scale);
         let mut points = Vec::with_capacity(segments + 1);
 
         for i in 0..=segments {
@@ -793,7 +816,7 @@ impl ProfileProcessor {
             let same_sense = segment
                 .get(1)
                 .and_then(|v| match v {
-                    ifc_lite_core::AttributeValue::Enum(e) => Some(e.as_str()),
+                    ifc_lite_core::AttributeValue::Enum(e) => Some(&**e),
                     _ => None,
                 })
                 .map(|e| e == "T" || e == "TRUE")
@@ -832,23 +855,67 @@ impl ProfileProcessor {
             .resolve_ref(basis_attr)?
             .ok_or_else(|| Error::geometry("Failed to resolve BasisCurve".to_string()))?;
 
-        // Get trim parameters
-        let trim1 = curve.get(1).and_then(|v| self.extract_trim_param(v));
-        let trim2 = curve.get(2).and_then(|v| self.extract_trim_param(v));
+        // Each trim is a set of an IfcCartesianPoint and/or an IfcParameterValue
+        let trim1 = self.extract_trim(curve.get(1), decoder)?;
+        let trim2 = self.extract_trim(curve.get(2), decoder)?;
 
         // Get sense agreement (attribute 3) - default true
         let sense = curve
             .get(3)
             .and_then(|v| match v {
-                ifc_lite_core::AttributeValue::Enum(s) => Some(s == "T"),
+                ifc_lite_core::AttributeValue::Enum(s) => Some(&**s == "T"),
                 _ => None,
             })
             .unwrap_or(true);
 
+        // Points win over parameters when the exporter says so, or when there is no parameter
+        let prefer_points = curve.get(4).and_then(|v| v.as_enum()) == Some("CARTESIAN");
+
         // Process basis curve based on type
         match basis_curve.ifc_type {
             IfcType::IfcCircle | IfcType::IfcEllipse => {
-                self.process_trimmed_conic(&basis_curve, trim1, trim2, sense, decoder)
+                let (center, rotation) = self.get_placement_2d(&basis_curve, decoder)?;
+                let radius = basis_curve.get_float(1).unwrap_or(1.0);
+                let radius2 = if basis_curve.ifc_type == IfcType::IfcEllipse {
+                    basis_curve.get_float(2).unwrap_or(radius)
+                } else {
+                    radius
+                };
+                // Angle of a trim point on the (unrotated, unit) conic
+                let point_angle = |p: Point2<f64>| {
+                    let (dx, dy) = (p.x - center.x, p.y - center.y);
+                    let (sin, cos) = rotation.sin_cos();
+                    let (x, y) = (dx * cos + dy * sin, -dx * sin + dy * cos);
+                    (y / radius2).atan2(x / radius)
+                };
+                let angle = |trim: &Trim| match (trim.point, trim.parameter) {
+                    (Some(p), _) if prefer_points => Some(point_angle(p)),
+                    (_, Some(t)) => Some(t.to_radians()),
+                    (Some(p), None) => Some(point_angle(p)),
+                    (None, None) => None,
+                };
+                self.process_trimmed_conic(
+                    &basis_curve,
+                    angle(&trim1),
+                    angle(&trim2),
+                    sense,
+                    decoder,
+                )
+            }
+            IfcType::IfcLine => {
+                let (origin, direction) = self.line_geometry(&basis_curve, decoder)?;
+                let point = |trim: &Trim| match (trim.point, trim.parameter) {
+                    (Some(p), _) if prefer_points => Some(p),
+                    (_, Some(t)) => Some(origin + direction * t),
+                    (Some(p), None) => Some(p),
+                    (None, None) => None,
+                };
+                match (point(&trim1), point(&trim2)) {
+                    (Some(start), Some(end)) => Ok(vec![start, end]),
+                    _ => Err(Error::geometry(
+                        "Trimmed IfcLine needs both trims".to_string(),
+                    )),
+                }
             }
             _ => {
                 // Fallback: try to process as a regular curve
@@ -857,6 +924,33 @@ impl ProfileProcessor {
         }
     }
 
+    /// Read one trim select of an IfcTrimmedCurve
+    fn extract_trim(
+        &self,
+        attr: Option<&ifc_lite_core::AttributeValue>,
+        decoder: &mut EntityDecoder,
+    ) -> Result<Trim> {
+        let mut trim = Trim::default();
+        let Some(list) = attr.and_then(|a| a.as_list()) else {
+            return Ok(trim);
+        };
+        for item in list {
+            if let Some(id) = item.as_entity_ref() {
+                let point = decoder.decode_by_id(id)?;
+                if let Some(coords) = point.get(0).and_then(|v| v.as_list()) {
+                    trim.point = Some(Point2::new(
+                        coords.first().and_then(|v| v.as_float()).unwrap_or(0.0),
+                        coords.get(1).and_then(|v| v.as_float()).unwrap_or(0.0),
+                    ));
+                }
+            } else if trim.parameter.is_none() {
+                trim.parameter = self
+                    .extract_trim_param(&ifc_lite_core::AttributeValue::List(vec![item.clone()]));
+            }
+        }
+        Ok(trim)
+    }
+
     /// Extract trim parameter (can be IFCPARAMETERVALUE or IFCCARTESIANPOINT)
     fn extract_trim_param(&self, attr: &ifc_lite_core::AttributeValue) -> Option<f64> {
         if let Some(list) = attr.as_list() {
@@ -879,12 +973,60 @@ impl ProfileProcessor {
         None
     }
 
+    /// Origin and parameter-scaled direction of an IfcLine
+    /// IfcLine: Pnt, Dir (IfcVector: Orientation, Magnitude)
+    fn line_geometry(
+        &self,
+        line: &DecodedEntity,
+        decoder: &mut EntityDecoder,
+    ) -> Result<(Point2<f64>, nalgebra::Vector2<f64>)> {
+        let coords = |entity: &DecodedEntity| {
+            entity.get(0).and_then(|v| v.as_list()).map(|c| {
+                (
+                    c.first().and_then(|v| v.as_float()).unwrap_or(0.0),
+                    c.get(1).and_then(|v| v.as_float()).unwrap_or(0.0),
+                )
+            })
+        };
+
+        let point = line
+            .get(0)
+            .map(|a| decoder.resolve_ref(a))
+            .transpose()?
+            .flatten()
+            .ok_or_else(|| Error::geometry("IfcLine missing Pnt".to_string()))?;
+        let (x, y) = coords(&point).unwrap_or((0.0, 0.0));
+
+        let vector = line
+            .get(1)
+            .map(|a| decoder.resolve_ref(a))
+            .transpose()?
+            .flatten()
+            .ok_or_else(|| Error::geometry("IfcLine missing Dir".to_string()))?;
+        let magnitude = vector.get_float(1).unwrap_or(1.0);
+        let orientation = vector
+            .get(0)
+            .map(|a| decoder.resolve_ref(a))
+            .transpose()?
+            .flatten()
+            .and_then(|d| coords(&d))
+            .unwrap_or((1.0, 0.0));
+        let direction = nalgebra::Vector2::new(orientation.0, orientation.1)
+            .try_normalize(1e-12)
+            .unwrap_or_else(|| nalgebra::Vector2::new(1.0, 0.0));
+
+        Ok((Point2::new(x, y), direction * magnitude))
+    }
+
     /// Process trimmed conic (circle or ellipse arc)
+    ///
+    /// Trims are angles in radians; a missing trim leaves the arc open at
+    /// that end, i.e. a full turn from the other one.
     fn process_trimmed_conic(
         &self,
         basis: &DecodedEntity,
-        trim1: Option<f64>,
-        trim2: Option<f64>,
+        start_angle: Option<f64>,
+        end_angle: Option<f64>,
         sense: bool,
         decoder: &mut EntityDecoder,
     ) -> Result<Vec<Point2<f64>>> {
@@ -897,29 +1039,28 @@ impl ProfileProcessor {
 
         let (center, rotation) = self.get_placement_2d(basis, decoder)?;
 
-        // Convert trim parameters to angles (in degrees usually)
-        let start_angle = trim1.unwrap_or(0.0).to_radians();
-        let end_angle = trim2.unwrap_or(360.0).to_radians();
-
-        // Calculate arc angle and adaptive segment count
-        // Use ~8 segments per 90° (quarter circle), minimum 2
-        let arc_angle = (end_angle - start_angle).abs();
-        let num_segments = ((arc_angle / std::f64::consts::FRAC_PI_2 * 8.0).ceil() as usize).max(2);
-        let mut points = Vec::with_capacity(num_segments + 1);
+        let start_angle = start_angle.unwrap_or(0.0);
+        let end_angle = end_angle.unwrap_or(start_angle + 2.0 * PI);
 
-        let angle_range = if sense {
-            end_angle - start_angle
+        // Sweep counter-clockwise with the sense, clockwise against it
+        let mut sweep = if sense {
+            (end_angle - start_angle).rem_euclid(2.0 * PI)
         } else {
-            start_angle - end_angle
+            -(start_angle - end_angle).rem_euclid(2.0 * PI)
         };
+        if sweep.abs() < 1e-9 {
+            sweep = if sense { 2.0 * PI } else { -2.0 * PI };
+        }
+
+        let unit_scale = decoder.length_unit_scale().unwrap_or(1.0);
+        let num_segments = self
+            .options
+            .arc_segments(radius.max(radius2), sweep, unit_scale);
+        let mut points = Vec::with_capacity(num_segments + 1);
 
         for i in 0..=num_segments {
             let t = i as f64 / num_segments as f64;
-            let angle = if sense {
-                start_angle + t * angle_range
-            } else {
-                start_angle - t * angle_range.abs()
-            };
+            let angle = start_angle + t * sweep;
 
             let x = radius * angle.cos();
             let y = radius2 * angle.sin();
@@ -996,7 +1137,8 @@ impl ProfileProcessor {
         let radius = curve.get_float(1).unwrap_or(1.0);
         let (center, rotation) = self.get_placement_2d(curve, decoder)?;
 
-        let segments = 24;
+        let unit_scale = decoder.length_unit_scale().unwrap_or(1.0);
+        let segments = self.options.arc_segments(radius, 2.0 * PI, unit_scale);
         let mut points = Vec::with_capacity(segments);
 
         for i in 0..segments {
@@ -1023,7 +1165,10 @@ impl ProfileProcessor {
         let semi_axis2 = curve.get_float(2).unwrap_or(1.0);
         let (center, rotation) = self.get_placement_2d(curve, decoder)?;
 
-        let segments = 24;
+        let unit_scale = decoder.length_unit_scale().unwrap_or(1.0);
+        let segments = self
+            .options
+            .arc_segments(semi_axis1.max(semi_axis2), 2.0 * PI, unit_scale);
         let mut points = Vec::with_capacity(segments);
 
         for i in 0..segments {
@@ -1148,23 +1293,8 @@ impl ProfileProcessor {
                     let p3 = all_points.get(idx_values[2]).copied();
 
                     if let (Some(start), Some(mid), Some(end)) = (p1, p2, p3) {
-                        // Approximate arc with adaptive segment count based on arc size
-                        // Calculate approximate arc angle from chord length vs radius
-                        let chord_len =
-                            ((end.x - start.x).powi(2) + (end.y - start.y).powi(2)).sqrt();
-                        let mid_chord = ((mid.x - (start.x + end.x) / 2.0).powi(2)
-                            + (mid.y - (start.y + end.y) / 2.0).powi(2))
-                        .sqrt();
-                        // Estimate arc angle: larger mid deviation = larger arc
-                        let arc_estimate = if chord_len > 1e-10 {
-                            (mid_chord / chord_len).abs().min(1.0).acos() * 2.0
-                        } else {
-                            0.5
-                        };
-                        let num_segments = ((arc_estimate / std::f64::consts::FRAC_PI_2 * 8.0)
-                            .ceil() as usize)
-                            .clamp(4, 16);
-                        let arc_points = self.approximate_arc_3pt(start, mid, end, num_segments);
+                        let unit_scale = decoder.length_unit_scale().unwrap_or(1.0);
+                        let arc_points = self.approximate_arc_3pt(start, mid, end, unit_scale);
                         for pt in arc_points {
                             if result_points.last() != Some(&pt) {
                                 result_points.push(pt);
@@ -1193,7 +1323,7 @@ impl ProfileProcessor {
         p1: Point2<f64>,
         p2: Point2<f64>,
         p3: Point2<f64>,
-        num_segments: usize,
+        unit_scale: f64,
     ) -> Vec<Point2<f64>> {
         // Find circle center from 3 points
         let ax = p1.x;
@@ -1244,6 +1374,9 @@ impl ProfileProcessor {
         }
 
         // Generate arc points
+        let num_segments = self
+            .options
+            .arc_segments(radius, end_angle - start_angle, unit_scale);
         let mut points = Vec::with_capacity(num_segments + 1);
         for i in 0..=num_segments {
             let t = i as f64 / num_segments as f64;
@@ -1275,7 +1408,10 @@ impl ProfileProcessor {
 
         for segment in segments {
             // IfcCompositeCurveSegment: Transition, SameSense, ParentCurve
-            if segment.ifc_type != IfcType::IfcCompositeCurveSegment {
+            if !matches!(
+                segment.ifc_type,
+                IfcType::IfcCompositeCurveSegment | IfcType::IfcReparametrisedCompositeCurveSegment
+            ) {
                 continue;
             }
 
@@ -1293,7 +1429,7 @@ impl ProfileProcessor {
             let same_sense = segment
                 .get(1)
                 .and_then(|v| match v {
-                    ifc_lite_core::AttributeValue::Enum(s) => Some(s == "T" || s == "TRUE"),
+                    ifc_lite_core::AttributeValue::Enum(s) => Some(matches!(&**s, "T" | "TRUE")),
                     _ => None,
                 })
                 .unwrap_or(true);
@@ -1349,6 +1485,13 @@ impl ProfileProcessor {
     }
 }
 
+/// One end of an IfcTrimmedCurve
+#[derive(Debug, Default, Clone, Copy)]
+struct Trim {
+    point: Option<Point2<f64>>,
+    parameter: Option<f64>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1425,4 +1568,97 @@ mod tests {
         assert_eq!(profile.outer.len(), 5); // 4 corners + closing point
         assert!(!profile.outer.is_empty());
     }
+
+    fn shoelace_area(points: &[Point2<f64>]) -> f64 {
+        points
+            .iter()
+            .zip(points.iter().cycle().skip(1))
+            .map(|(a, b)| a.x * b.y - b.x * a.y)
+            .sum::<f64>()
+            / 2.0
+    }
+
+    #[test]
+    fn test_composite_curve_with_trimmed_arc() {
+        // D shape: 10 x 10 square whose right side bulges out as a half circle of radius 5
+        let content = r#"
+#1=IFCCARTESIANPOINT((0.0,0.0));
+#2=IFCCARTESIANPOINT((10.0,0.0));
+#3=IFCCARTESIANPOINT((10.0,10.0));
+#4=IFCCARTESIANPOINT((0.0,10.0));
+#5=IFCDIRECTION((1.0,0.0));
+#6=IFCVECTOR(#5,1.0);
+#7=IFCLINE(#1,#6);
+#8=IFCTRIMMEDCURVE(#7,(#1),(#2),.T.,.CARTESIAN.);
+#9=IFCCARTESIANPOINT((10.0,5.0));
+#10=IFCAXIS2PLACEMENT2D(#9,$);
+#11=IFCCIRCLE(#10,5.0);
+#12=IFCTRIMMEDCURVE(#11,(IFCPARAMETERVALUE(270.0)),(IFCPARAMETERVALUE(90.0)),.T.,.PARAMETER.);
+#13=IFCTRIMMEDCURVE(#7,(IFCPARAMETERVALUE(0.0)),(IFCPARAMETERVALUE(10.0)),.T.,.PARAMETER.);
+#14=IFCPOLYLINE((#3,#4,#1));
+#15=IFCCOMPOSITECURVESEGMENT(.CONTINUOUS.,.T.,#8);
+#16=IFCCOMPOSITECURVESEGMENT(.CONTINUOUS.,.T.,#12);
+#17=IFCCOMPOSITECURVESEGMENT(.CONTINUOUS.,.T.,#14);
+#18=IFCCOMPOSITECURVE((#15,#16,#17),.F.);
+#19=IFCARBITRARYCLOSEDPROFILEDEF(.AREA.,$,#18);
+"#;
+
+        let mut decoder = EntityDecoder::new(content);
+        let processor = ProfileProcessor::new(IfcSchema::new());
+
+        let profile_entity = decoder.decode_by_id(19).unwrap();
+        let profile = processor.process(&profile_entity, &mut decoder).unwrap();
+        let expected = 100.0 + PI * 25.0 / 2.0;
+        let area = shoelace_area(&profile.outer);
+        assert!((area - expected).abs() / expected < 0.01, "area {}", area);
+        let max_x = profile.outer.iter().map(|p| p.x).fold(f64::MIN, f64::max);
+        // Chords stay within the 1 cm arc tolerance
+        assert!(
+            max_x <= 15.0 + 1e-9 && max_x > 15.0 - 0.01,
+            "max x {}",
+            max_x
+        );
+
+        // A parameter-trimmed line runs from Pnt + t0 * Dir to Pnt + t1 * Dir
+        let line = decoder.decode_by_id(13).unwrap();
+        let points = processor
+            .process_trimmed_curve(&line, &mut decoder)
+            .unwrap();
+        assert_eq!(points, vec![Point2::new(0.0, 0.0), Point2::new(10.0, 0.0)]);
+    }
+
+    #[test]
+    fn test_trimmed_circle_against_sense() {
+        // Clockwise from 10 deg to 350 deg is the short 20 deg arc through 0 deg
+        let content = r#"
+#1=IFCCARTESIANPOINT((0.0,0.0));
+#2=IFCAXIS2PLACEMENT2D(#1,$);
+#3=IFCCIRCLE(#2,1.0);
+#4=IFCTRIMMEDCURVE(#3,(IFCPARAMETERVALUE(10.0)),(IFCPARAMETERVALUE(350.0)),.F.,.PARAMETER.);
+"#;
+
+        let mut decoder = EntityDecoder::new(content);
+        let processor = ProfileProcessor::new(IfcSchema::new());
+
+        let curve = decoder.decode_by_id(4).unwrap();
+        let points = processor
+            .process_trimmed_curve(&curve, &mut decoder)
+            .unwrap();
+        assert!(points.len() >= 3);
+        assert!(points.iter().all(|p| p.x > 0.98));
+        assert!(points.first().unwrap().y > 0.0 && points.last().unwrap().y < 0.0);
+    }
+
+    #[test]
+    fn test_arc_segments_follow_unit_scale() {
+        let options = GeometryOptions::default();
+        // The same 5 m circle in metres and in millimetres
+        let metres = options.arc_segments(5.0, 2.0 * PI, 1.0);
+        let millimetres = options.arc_segments(5000.0, 2.0 * PI, 0.001);
+        assert_eq!(metres, millimetres);
+        // Larger radii need more segments, tiny ones keep a minimum
+        assert!(options.arc_segments(50.0, 2.0 * PI, 1.0) > metres);
+        assert_eq!(options.arc_segments(0.001, 2.0 * PI, 1.0), 12);
+        assert_eq!(options.arc_segments(0.001, PI / 2.0, 1.0), 3);
+    }
 }
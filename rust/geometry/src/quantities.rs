@@ -0,0 +1,145 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Geometric quantities of a mesh, for elements without IfcElementQuantity
+//!
+//! Meshes are expected in IFC axes (Z up) and metres. Volume is only
+//! meaningful for closed meshes; open shells give whatever the signed
+//! tetrahedra sum to.
+
+use crate::mesh::Mesh;
+use ifc_lite_core::ElementMeasures;
+
+/// Volume, footprint and surface area, and horizontal length of a mesh
+///
+/// All measures are `None` for a mesh without triangles.
+pub fn mesh_measures(mesh: &Mesh) -> ElementMeasures {
+    let vertex = |index: u32| -> Option<[f64; 3]> {
+        let i = index as usize * 3;
+        let p = mesh.positions.get(i..i + 3)?;
+        Some([p[0] as f64, p[1] as f64, p[2] as f64])
+    };
+    let Some(origin) = mesh.indices.first().and_then(|&i| vertex(i)) else {
+        return ElementMeasures::default();
+    };
+    // Relative to the first vertex to keep precision far from the origin
+    let local =
+        |index: u32| vertex(index).map(|p| [p[0] - origin[0], p[1] - origin[1], p[2] - origin[2]]);
+
+    let mut volume = 0.0;
+    let mut footprint = 0.0;
+    let mut surface = 0.0;
+    let mut triangles = 0;
+    for tri in mesh.indices.chunks_exact(3) {
+        let (Some(a), Some(b), Some(c)) = (local(tri[0]), local(tri[1]), local(tri[2])) else {
+            continue;
+        };
+        let u = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+        let v = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+        let cross = [
+            u[1] * v[2] - u[2] * v[1],
+            u[2] * v[0] - u[0] * v[2],
+            u[0] * v[1] - u[1] * v[0],
+        ];
+
+        volume += (a[0] * (b[1] * c[2] - b[2] * c[1])
+            + a[1] * (b[2] * c[0] - b[0] * c[2])
+            + a[2] * (b[0] * c[1] - b[1] * c[0]))
+            / 6.0;
+        surface += (cross[0] * cross[0] + cross[1] * cross[1] + cross[2] * cross[2]).sqrt() / 2.0;
+        // Upward-facing triangles projected onto the ground plane
+        if cross[2] > 0.0 {
+            footprint += cross[2] / 2.0;
+        }
+        triangles += 1;
+    }
+    if triangles == 0 {
+        return ElementMeasures::default();
+    }
+
+    ElementMeasures {
+        volume: Some(volume.abs()),
+        footprint_area: Some(footprint),
+        surface_area: Some(surface),
+        length: Some(horizontal_length(mesh)),
+    }
+}
+
+/// Extent of the vertices along their principal horizontal axis
+fn horizontal_length(mesh: &Mesh) -> f64 {
+    let points: Vec<[f64; 2]> = mesh
+        .positions
+        .chunks_exact(3)
+        .map(|p| [p[0] as f64, p[1] as f64])
+        .collect();
+    if points.is_empty() {
+        return 0.0;
+    }
+
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|p| p[0]).sum::<f64>() / n;
+    let mean_y = points.iter().map(|p| p[1]).sum::<f64>() / n;
+    let (mut cxx, mut cyy, mut cxy) = (0.0, 0.0, 0.0);
+    for p in &points {
+        let (dx, dy) = (p[0] - mean_x, p[1] - mean_y);
+        cxx += dx * dx;
+        cyy += dy * dy;
+        cxy += dx * dy;
+    }
+    let angle = 0.5 * (2.0 * cxy).atan2(cxx - cyy);
+    let (sin, cos) = angle.sin_cos();
+
+    let (mut min, mut max) = (f64::INFINITY, f64::NEG_INFINITY);
+    for p in &points {
+        let t = p[0] * cos + p[1] * sin;
+        min = min.min(t);
+        max = max.max(t);
+    }
+    max - min
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extrusion::extrude_profile;
+    use crate::profile::create_rectangle;
+    use nalgebra::{Matrix4, Vector3};
+
+    fn assert_close(value: Option<f64>, expected: f64, tolerance: f64) {
+        let value = value.unwrap();
+        assert!(
+            (value - expected).abs() < tolerance,
+            "{} != {}",
+            value,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_box_measures() {
+        let mesh = extrude_profile(&create_rectangle(2.0, 1.0), 3.0, None).unwrap();
+        let measures = mesh_measures(&mesh);
+        assert_close(measures.volume, 6.0, 1e-4);
+        assert_close(measures.footprint_area, 2.0, 1e-4);
+        assert_close(measures.surface_area, 22.0, 1e-4);
+        assert_close(measures.length, 2.0, 1e-4);
+    }
+
+    #[test]
+    fn test_measures_far_from_origin() {
+        let transform = Matrix4::new_translation(&Vector3::new(1000.0, -2000.0, 50.0))
+            * Matrix4::from_axis_angle(&Vector3::z_axis(), 0.5);
+        let mesh = extrude_profile(&create_rectangle(4.0, 0.25), 1.0, Some(transform)).unwrap();
+        let measures = mesh_measures(&mesh);
+        // Positions are f32, good to about 0.1 mm at 2 km
+        assert_close(measures.volume, 1.0, 1e-3);
+        assert_close(measures.footprint_area, 1.0, 1e-3);
+        assert_close(measures.length, 4.0, 1e-3);
+    }
+
+    #[test]
+    fn test_empty_mesh() {
+        assert!(mesh_measures(&Mesh::new()).is_empty());
+    }
+}
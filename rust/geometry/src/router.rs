@@ -6,19 +6,26 @@
 //!
 //! Routes IFC representation entities to appropriate processors based on type.
 
+use crate::alignment::{AlignmentCurve, DistanceExpression};
 use crate::bool2d::subtract_multiple_2d;
 use crate::csg::ClippingProcessor;
+use crate::face_colors::FaceColors;
+use crate::grid::{virtual_intersection, GridAxisLine, PlacedGridAxis};
+use crate::normals::smooth_normals;
+use crate::options::GeometryOptions;
 use crate::processors::{
     AdvancedBrepProcessor, BooleanClippingProcessor, ExtrudedAreaSolidProcessor,
-    FacetedBrepProcessor, MappedItemProcessor, RevolvedAreaSolidProcessor, SweptDiskSolidProcessor,
-    TriangulatedFaceSetProcessor,
+    FacetedBrepProcessor, MappedItemProcessor, RevolvedAreaSolidProcessor,
+    SectionedSolidHorizontalProcessor, SweptDiskSolidProcessor, TriangulatedFaceSetProcessor,
 };
 use crate::profile::{Profile2D, Profile2DWithVoids, VoidInfo};
+use crate::profiles::ProfileProcessor;
+use crate::timings::ProcessorTimings;
 use crate::void_analysis::{
     extract_coplanar_voids, extract_nonplanar_voids, VoidAnalyzer, VoidClassification,
 };
 use crate::void_index::VoidIndex;
-use crate::{Error, Mesh, Point3, Result, Vector3};
+use crate::{ElementGeometry, Error, Mesh, MeshInstance, Point3, Result, Vector3};
 use ifc_lite_core::{DecodedEntity, EntityDecoder, GeometryCategory, IfcSchema, IfcType};
 use nalgebra::{Matrix4, Point2};
 use rustc_hash::FxHashMap;
@@ -27,9 +34,15 @@ use std::collections::HashMap;
 use std::hash::{Hash, Hasher};
 use std::sync::Arc;
 
+/// Distance from the file origin (metres) beyond which
+/// [`GeometryRouter::detect_origin_shift`] moves the scene origin; f32
+/// positions lose millimetre precision past this
+pub const ORIGIN_SHIFT_THRESHOLD: f64 = 10_000.0;
+
 /// Geometry processor trait
-/// Each processor handles one type of IFC representation
-pub trait GeometryProcessor {
+/// Each processor handles one type of IFC representation; processors are
+/// shared by forked routers, so they must be usable from any thread
+pub trait GeometryProcessor: Send + Sync {
     /// Process entity into mesh
     fn process(
         &self,
@@ -57,40 +70,77 @@ pub struct GeometryRouter {
     /// Buildings with repeated floors have 99% identical geometry
     /// Key: Hash of mesh content, Value: Processed mesh
     geometry_hash_cache: RefCell<FxHashMap<u64, Arc<Mesh>>>,
+    /// Placement of the owning IfcGrid for each IfcGridAxis
+    /// Key: IfcGridAxis entity ID, Value: grid placement transform (file units)
+    grid_transforms: RefCell<FxHashMap<u32, Matrix4<f64>>>,
+    /// Sampled basis curves of IfcLinearPlacement
+    /// Key: IfcCurve entity ID, Value: curve (file units), None if it could not be sampled
+    alignment_curves: RefCell<FxHashMap<u32, Option<Arc<AlignmentCurve>>>>,
+    /// Indexed colour maps and styled faces, applied as vertex colors
+    face_colors: Arc<FaceColors>,
+    /// Crease angle (radians) for generating normals of meshes that have none
+    /// None leaves them without normals (flat shading downstream)
+    crease_angle: Option<f64>,
     /// Unit scale factor (e.g., 0.001 for millimeters -> meters)
     /// Applied to all mesh positions after processing
     unit_scale: f64,
+    /// Subtracted from world positions (metres) before they are cast to f32
+    origin_shift: Vector3<f64>,
+    /// Tessellation tolerances and element filters
+    options: GeometryOptions,
+    /// Time spent per processor, shared with forks; None records nothing
+    timings: Option<Arc<ProcessorTimings>>,
 }
 
 impl GeometryRouter {
     /// Create new router with default processors
     pub fn new() -> Self {
         let schema = IfcSchema::new();
-        let schema_clone = schema.clone();
         let mut router = Self {
             schema,
             processors: HashMap::new(),
             mapped_item_cache: RefCell::new(FxHashMap::default()),
             faceted_brep_cache: RefCell::new(FxHashMap::default()),
             geometry_hash_cache: RefCell::new(FxHashMap::default()),
+            grid_transforms: RefCell::new(FxHashMap::default()),
+            alignment_curves: RefCell::new(FxHashMap::default()),
+            face_colors: Arc::default(),
+            crease_angle: None,
             unit_scale: 1.0, // Default to base meters
+            origin_shift: Vector3::zeros(),
+            options: GeometryOptions::default(),
+            timings: None,
         };
+        router.register_default_processors();
+        router
+    }
 
-        // Register default P0 processors
-        router.register(Box::new(ExtrudedAreaSolidProcessor::new(
-            schema_clone.clone(),
+    /// Register the default P0 processors, configured by the current options
+    fn register_default_processors(&mut self) {
+        let schema = self.schema.clone();
+        let options = self.options;
+        self.register(Box::new(ExtrudedAreaSolidProcessor::with_options(
+            schema.clone(),
+            options,
         )));
-        router.register(Box::new(TriangulatedFaceSetProcessor::new()));
-        router.register(Box::new(MappedItemProcessor::new()));
-        router.register(Box::new(FacetedBrepProcessor::new()));
-        router.register(Box::new(BooleanClippingProcessor::new()));
-        router.register(Box::new(SweptDiskSolidProcessor::new(schema_clone.clone())));
-        router.register(Box::new(RevolvedAreaSolidProcessor::new(
-            schema_clone.clone(),
+        self.register(Box::new(TriangulatedFaceSetProcessor::new()));
+        self.register(Box::new(MappedItemProcessor::new()));
+        self.register(Box::new(FacetedBrepProcessor::new()));
+        self.register(Box::new(BooleanClippingProcessor::new()));
+        self.register(Box::new(SweptDiskSolidProcessor::with_options(
+            schema.clone(),
+            options,
+        )));
+        self.register(Box::new(RevolvedAreaSolidProcessor::with_options(
+            schema.clone(),
+            options,
+        )));
+        self.register(Box::new(
+            AdvancedBrepProcessor::new().with_max_segments(options.max_segments),
+        ));
+        self.register(Box::new(SectionedSolidHorizontalProcessor::with_options(
+            schema, options,
         )));
-        router.register(Box::new(AdvancedBrepProcessor::new()));
-
-        router
     }
 
     /// Create router and extract unit scale from IFC file
@@ -99,6 +149,13 @@ impl GeometryRouter {
     pub fn with_units(content: &str, decoder: &mut EntityDecoder) -> Self {
         let mut router = Self::new();
 
+        // Grid placements need the axis -> grid mapping; skip the scan when there are no grids
+        if content.contains("IFCGRID(") {
+            router.index_grids(content, decoder);
+        }
+
+        router.index_face_colors(content, decoder);
+
         // First check if decoder already has cached unit scale
         if let Some(scale) = decoder.length_unit_scale() {
             router.unit_scale = scale;
@@ -123,11 +180,253 @@ impl GeometryRouter {
         router
     }
 
+    /// Create a router for another thread
+    ///
+    /// The fork shares processors, units, grids and face colors and starts
+    /// its caches from this router's, so instanced geometry already processed
+    /// here is not processed again. Preprocessed faceted breps stay behind.
+    pub fn fork(&self) -> Self {
+        Self {
+            schema: self.schema.clone(),
+            processors: self.processors.clone(),
+            mapped_item_cache: self.mapped_item_cache.clone(),
+            faceted_brep_cache: RefCell::new(FxHashMap::default()),
+            geometry_hash_cache: self.geometry_hash_cache.clone(),
+            grid_transforms: self.grid_transforms.clone(),
+            alignment_curves: self.alignment_curves.clone(),
+            face_colors: Arc::clone(&self.face_colors),
+            crease_angle: self.crease_angle,
+            unit_scale: self.unit_scale,
+            origin_shift: self.origin_shift,
+            options: self.options,
+            timings: self.timings.clone(),
+        }
+    }
+
+    /// Index IfcGrid entities so IfcGridPlacement can be resolved
+    /// Maps every U/V/W axis to the placement transform of its grid
+    pub fn index_grids(&self, content: &str, decoder: &mut EntityDecoder) {
+        use ifc_lite_core::EntityScanner;
+        let mut scanner = EntityScanner::new(content);
+        let mut grid_transforms = FxHashMap::default();
+
+        while let Some((_id, type_name, start, end)) = scanner.next_entity() {
+            if type_name != "IFCGRID" {
+                continue;
+            }
+            let Ok(grid) = decoder.decode_at(start, end) else {
+                continue;
+            };
+
+            // IfcGrid: ..., ObjectPlacement (5), Representation (6), UAxes (7), VAxes (8), WAxes (9)
+            let transform = match grid.get(5) {
+                Some(attr) if !attr.is_null() => match decoder.resolve_ref(attr) {
+                    Ok(Some(placement)) => self
+                        .get_placement_transform(&placement, decoder)
+                        .unwrap_or_else(|_| Matrix4::identity()),
+                    _ => Matrix4::identity(),
+                },
+                _ => Matrix4::identity(),
+            };
+
+            for axes_index in 7..=9 {
+                if let Some(axes) = grid.get(axes_index).and_then(|a| a.as_list()) {
+                    for axis_id in axes.iter().filter_map(|a| a.as_entity_ref()) {
+                        grid_transforms.insert(axis_id, transform);
+                    }
+                }
+            }
+        }
+
+        self.grid_transforms.borrow_mut().extend(grid_transforms);
+    }
+
+    /// Axes of every IfcGrid as segments in scene coordinates, for display
+    ///
+    /// Polyline and line axis curves are supported; other curves are skipped.
+    pub fn grid_axes(&self, content: &str, decoder: &mut EntityDecoder) -> Vec<PlacedGridAxis> {
+        use ifc_lite_core::EntityScanner;
+        let mut scanner = EntityScanner::new(content);
+        let mut placed = Vec::new();
+
+        while let Some((id, type_name, _, _)) = scanner.next_entity() {
+            if type_name != "IFCGRID" {
+                continue;
+            }
+            let Ok(grid) = decoder.decode_by_id(id) else {
+                continue;
+            };
+            let Ok(mut transform) = self.get_placement_transform_from_element(&grid, decoder)
+            else {
+                continue;
+            };
+            self.scale_transform(&mut transform);
+            self.shift_transform(&mut transform);
+
+            // IfcGrid: UAxes (7), VAxes (8), WAxes (9)
+            for axes_index in 7..=9 {
+                let Some(axes_attr) = grid.get(axes_index) else {
+                    continue;
+                };
+                let Ok(axes) = decoder.resolve_ref_list(axes_attr) else {
+                    continue;
+                };
+                for axis in axes {
+                    let Ok((start, end)) = self.parse_grid_axis_segment(&axis, decoder) else {
+                        continue;
+                    };
+                    let to_scene = |p: Point2<f64>| {
+                        let p = Point3::new(p.x, p.y, 0.0) * self.unit_scale;
+                        transform.transform_point(&p)
+                    };
+                    placed.push(PlacedGridAxis {
+                        grid_id: id,
+                        tag: axis.get_string(0).unwrap_or_default().to_string(),
+                        start: to_scene(start),
+                        end: to_scene(end),
+                    });
+                }
+            }
+        }
+        placed
+    }
+
+    /// Index indexed colour maps and styled faces so meshes get vertex colors
+    /// Skips the scan when the content cannot contain any
+    pub fn index_face_colors(&mut self, content: &str, decoder: &mut EntityDecoder) {
+        if FaceColors::may_apply(content) {
+            self.face_colors = Arc::new(FaceColors::index(content, decoder));
+        }
+    }
+
+    /// Generate smooth normals with the given crease angle (radians) for
+    /// meshes that come without normals, such as faceted breps
+    pub fn set_crease_angle(&mut self, crease_angle: Option<f64>) {
+        self.crease_angle = crease_angle;
+    }
+
+    /// Smooth a freshly processed mesh if it has no normals yet
+    #[inline]
+    fn generate_normals(&self, mesh: &mut Mesh) {
+        if let Some(crease_angle) = self.crease_angle {
+            if mesh.normals.is_empty() && !mesh.is_empty() {
+                smooth_normals(mesh, crease_angle);
+            }
+        }
+    }
+
+    /// Tessellate and filter elements with the given options
+    ///
+    /// Re-registers the default processors, replacing custom ones registered
+    /// for the same types, and clears the geometry caches. Set options before
+    /// processing elements.
+    pub fn set_options(&mut self, options: GeometryOptions) {
+        self.options = options;
+        self.register_default_processors();
+        self.mapped_item_cache.borrow_mut().clear();
+        self.geometry_hash_cache.borrow_mut().clear();
+    }
+
+    /// Current tessellation tolerances and element filters
+    pub fn options(&self) -> &GeometryOptions {
+        &self.options
+    }
+
+    /// Record the time of every processor call into `timings`, shared with
+    /// routers forked from this one; None stops recording
+    pub fn set_timings(&mut self, timings: Option<Arc<ProcessorTimings>>) {
+        self.timings = timings;
+    }
+
+    /// Timings this router records into
+    pub fn timings(&self) -> Option<&Arc<ProcessorTimings>> {
+        self.timings.as_ref()
+    }
+
+    /// Run `f`, adding its time to the processor of `ifc_type` when recording
+    #[inline]
+    fn time<T>(&self, ifc_type: &IfcType, f: impl FnOnce() -> T) -> T {
+        match &self.timings {
+            Some(timings) => timings.time(ifc_type, f),
+            None => f(),
+        }
+    }
+
+    /// Process a representation item with `processor`, timed
+    #[inline]
+    fn run_processor(
+        &self,
+        processor: &dyn GeometryProcessor,
+        item: &DecodedEntity,
+        decoder: &mut EntityDecoder,
+    ) -> Result<Mesh> {
+        self.time(&item.ifc_type, || {
+            processor.process(item, decoder, &self.schema)
+        })
+    }
+
+    /// Drop a mesh smaller than the minimum feature size
+    #[inline]
+    fn filter_small(&self, mesh: Mesh) -> Mesh {
+        if self.options.is_below_feature_size(&mesh) {
+            Mesh::new()
+        } else {
+            mesh
+        }
+    }
+
     /// Get the current unit scale factor
     pub fn unit_scale(&self) -> f64 {
         self.unit_scale
     }
 
+    /// Offset (metres) subtracted from world positions; add it back to get
+    /// the original model coordinates
+    pub fn origin_shift(&self) -> Vector3<f64> {
+        self.origin_shift
+    }
+
+    /// Move the scene origin to `shift`, in metres
+    pub fn set_origin_shift(&mut self, shift: Vector3<f64>) {
+        self.origin_shift = shift;
+    }
+
+    /// Move the scene origin next to the model when it lies far from the
+    /// file origin, e.g. when placed in map coordinates
+    ///
+    /// Uses the placement of the first IfcSite or IfcBuilding further than
+    /// [`ORIGIN_SHIFT_THRESHOLD`] out, rounded to whole metres. Placements
+    /// are combined in f64 and the shift is taken off before positions are
+    /// cast to f32. Returns the shift, if one was applied.
+    pub fn detect_origin_shift(
+        &mut self,
+        content: &str,
+        decoder: &mut EntityDecoder,
+    ) -> Option<Vector3<f64>> {
+        use ifc_lite_core::EntityScanner;
+        let mut scanner = EntityScanner::new(content);
+
+        while let Some((id, type_name, _, _)) = scanner.next_entity() {
+            if !matches!(type_name, "IFCSITE" | "IFCBUILDING") {
+                continue;
+            }
+            let Ok(element) = decoder.decode_by_id(id) else {
+                continue;
+            };
+            let Ok(mut transform) = self.get_placement_transform_from_element(&element, decoder)
+            else {
+                continue;
+            };
+            self.scale_transform(&mut transform);
+            let position = Vector3::new(transform[(0, 3)], transform[(1, 3)], transform[(2, 3)]);
+            if position.amax() > ORIGIN_SHIFT_THRESHOLD {
+                self.origin_shift = position.map(f64::round);
+                return Some(self.origin_shift);
+            }
+        }
+        None
+    }
+
     /// Scale mesh positions from file units to meters
     /// Only applies scaling if unit_scale != 1.0
     #[inline]
@@ -151,6 +450,14 @@ impl GeometryRouter {
         }
     }
 
+    /// Move a scaled world transform to the shifted scene origin
+    #[inline]
+    fn shift_transform(&self, transform: &mut Matrix4<f64>) {
+        for i in 0..3 {
+            transform[(i, 3)] -= self.origin_shift[i];
+        }
+    }
+
     /// Register a geometry processor
     pub fn register(&mut self, processor: Box<dyn GeometryProcessor>) {
         let processor_arc: Arc<dyn GeometryProcessor> = Arc::from(processor);
@@ -163,7 +470,8 @@ impl GeometryRouter {
     /// Call this before processing elements to enable batch triangulation
     /// across all FacetedBrep entities instead of per-entity parallelism
     pub fn preprocess_faceted_breps(&self, brep_ids: &[u32], decoder: &mut EntityDecoder) {
-        if brep_ids.is_empty() {
+        // Styled faces need the per-face path in process_representation_item
+        if brep_ids.is_empty() || self.face_colors.has_styled_faces() {
             return;
         }
 
@@ -209,6 +517,11 @@ impl GeometryRouter {
             idx.hash(&mut hasher);
         }
 
+        // Identical shapes with different face colors must not share a mesh
+        for c in &mesh.colors {
+            c.to_bits().hash(&mut hasher);
+        }
+
         hasher.finish()
     }
 
@@ -241,12 +554,161 @@ impl GeometryRouter {
     /// Process building element (IfcWall, IfcBeam, etc.) into mesh
     /// Follows the representation chain:
     /// Element → Representation → ShapeRepresentation → Items
+    ///
+    /// Elements skipped by the [`GeometryOptions`], or smaller than their
+    /// minimum feature size, give an empty mesh.
     #[inline]
     pub fn process_element(
         &self,
         element: &DecodedEntity,
         decoder: &mut EntityDecoder,
     ) -> Result<Mesh> {
+        if self.options.skips(element.ifc_type) {
+            return Ok(Mesh::new());
+        }
+        let mesh = self.process_product(element, decoder)?;
+        Ok(self.filter_small(mesh))
+    }
+
+    /// Process a product into a mesh regardless of the element filters
+    /// Openings still cut their hosts when they are not rendered themselves
+    fn process_product(
+        &self,
+        element: &DecodedEntity,
+        decoder: &mut EntityDecoder,
+    ) -> Result<Mesh> {
+        let mut combined_mesh = Mesh::new();
+        for item in self.body_representation_items(element, decoder)? {
+            let mesh = self.process_representation_item(&item, decoder)?;
+            combined_mesh.merge(&mesh);
+        }
+        if combined_mesh.is_empty() && element.ifc_type == IfcType::IfcAlignment {
+            combined_mesh = self.alignment_ribbon(element, decoder)?;
+        }
+
+        // Apply placement transformation
+        self.apply_placement(element, decoder, &mut combined_mesh)?;
+
+        Ok(combined_mesh)
+    }
+
+    /// Flat strip along the axis curve of an IfcAlignment without body
+    ///
+    /// Prefers the 'Curve3D' representation so the strip follows the vertical
+    /// profile, otherwise takes any 'Axis' or 'Curve2D' one.
+    fn alignment_ribbon(
+        &self,
+        element: &DecodedEntity,
+        decoder: &mut EntityDecoder,
+    ) -> Result<Mesh> {
+        /// Strip width in metres
+        const RIBBON_WIDTH: f64 = 0.3;
+
+        let Some(shape) = element.get(6).filter(|a| !a.is_null()) else {
+            return Ok(Mesh::new());
+        };
+        let Some(shape) = decoder.resolve_ref(shape)? else {
+            return Ok(Mesh::new());
+        };
+        let representations = match shape.get(2) {
+            Some(attr) => decoder.resolve_ref_list(attr)?,
+            None => return Ok(Mesh::new()),
+        };
+
+        let mut axis = None;
+        for rep in &representations {
+            let rep_type = rep.get(2).and_then(|a| a.as_string()).unwrap_or("");
+            if !matches!(rep_type, "Curve3D" | "Curve2D" | "Axis" | "Curve") {
+                continue;
+            }
+            if axis.is_none() || rep_type == "Curve3D" {
+                axis = rep
+                    .get(3)
+                    .map(|items| decoder.resolve_ref_list(items))
+                    .transpose()?;
+            }
+        }
+
+        let profiles = ProfileProcessor::with_options(self.schema.clone(), self.options);
+        let half_width = RIBBON_WIDTH / 2.0 / self.unit_scale;
+        let mut mesh = Mesh::new();
+        for curve in axis.unwrap_or_default() {
+            let Ok(curve) = AlignmentCurve::from_entity(&curve, decoder, &profiles) else {
+                continue;
+            };
+            let base = mesh.vertex_count() as u32;
+            let stations = curve.stations_between(0.0, curve.length());
+            for &station in &stations {
+                let frame = curve.frame_at(station, 0.0, 0.0);
+                for side in [-half_width, half_width] {
+                    let p = frame.transform_point(&Point3::new(0.0, side, 0.0));
+                    mesh.add_vertex(p, Vector3::new(0.0, 0.0, 1.0));
+                }
+            }
+            for i in 1..stations.len() as u32 {
+                let (a, b) = (base + 2 * (i - 1), base + 2 * i);
+                mesh.add_triangle(a, a + 1, b + 1);
+                mesh.add_triangle(a, b + 1, b);
+            }
+        }
+        self.scale_mesh(&mut mesh);
+        Ok(mesh)
+    }
+
+    /// Process building element keeping IfcMappedItem geometry instanced
+    /// Mapped items share one cached mesh per IfcRepresentationMap and only
+    /// carry a per-instance transform; all other items are merged into a
+    /// world-space mesh as in [`Self::process_element`]
+    pub fn process_element_instanced(
+        &self,
+        element: &DecodedEntity,
+        decoder: &mut EntityDecoder,
+    ) -> Result<ElementGeometry> {
+        let mut geometry = ElementGeometry::default();
+        if self.options.skips(element.ifc_type) {
+            return Ok(geometry);
+        }
+        let items = self.body_representation_items(element, decoder)?;
+        if items.is_empty() {
+            return Ok(geometry);
+        }
+
+        let mut placement = self.get_placement_transform_from_element(element, decoder)?;
+        self.scale_transform(&mut placement);
+        self.shift_transform(&mut placement);
+
+        for item in items {
+            if item.ifc_type == IfcType::IfcMappedItem {
+                let (source_id, mesh, transform) = self.resolve_mapped_item(&item, decoder)?;
+                if !mesh.is_empty() {
+                    geometry.instances.push(MeshInstance {
+                        source_id,
+                        mesh,
+                        transform: placement * transform,
+                    });
+                }
+            } else {
+                let mesh = self.process_representation_item(&item, decoder)?;
+                geometry.mesh.merge(&mesh);
+            }
+        }
+
+        self.transform_mesh(&mut geometry.mesh, &placement);
+        geometry.mesh = self.filter_small(geometry.mesh);
+        geometry
+            .instances
+            .retain(|instance| !self.options.is_below_feature_size(&instance.mesh));
+        Ok(geometry)
+    }
+
+    /// Collect the representation items of an element's body representations
+    /// Follows Element → ProductDefinitionShape → ShapeRepresentation → Items,
+    /// skipping non-solid representations ('Axis', 'FootPrint', ...)
+    fn body_representation_items(
+        &self,
+        element: &DecodedEntity,
+        decoder: &mut EntityDecoder,
+    ) -> Result<Vec<DecodedEntity>> {
         // Get representation (attribute 6 for most building elements)
         // IfcProduct: GlobalId, OwnerHistory, Name, Description, ObjectType, ObjectPlacement, Representation, Tag
         let representation_attr = element.get(6).ok_or_else(|| {
@@ -257,7 +719,7 @@ impl GeometryRouter {
         })?;
 
         if representation_attr.is_null() {
-            return Ok(Mesh::new()); // No geometry
+            return Ok(Vec::new()); // No geometry
         }
 
         let representation = decoder
@@ -279,8 +741,7 @@ impl GeometryRouter {
 
         let representations = decoder.resolve_ref_list(representations_attr)?;
 
-        // Process all representations and merge meshes
-        let mut combined_mesh = Mesh::new();
+        let mut body_items = Vec::new();
 
         // First pass: check if we have any direct geometry representations
         // This prevents duplication when both direct and MappedRepresentation exist
@@ -351,17 +812,10 @@ impl GeometryRouter {
 
             let items = decoder.resolve_ref_list(items_attr)?;
 
-            // Process each representation item
-            for item in items {
-                let mesh = self.process_representation_item(&item, decoder)?;
-                combined_mesh.merge(&mesh);
-            }
+            body_items.extend(items);
         }
 
-        // Apply placement transformation
-        self.apply_placement(element, decoder, &mut combined_mesh)?;
-
-        Ok(combined_mesh)
+        Ok(body_items)
     }
 
     /// Process element with void subtraction (openings)
@@ -422,7 +876,7 @@ impl GeometryRouter {
                 Err(_) => continue,
             };
 
-            let opening_mesh = match self.process_element(&opening_entity, decoder) {
+            let opening_mesh = match self.process_product(&opening_entity, decoder) {
                 Ok(m) => m,
                 Err(_) => continue,
             };
@@ -526,6 +980,10 @@ impl GeometryRouter {
         decoder: &mut EntityDecoder,
         void_index: &VoidIndex,
     ) -> Result<Mesh> {
+        if self.options.skips(element.ifc_type) {
+            return Ok(Mesh::new());
+        }
+
         // Check if this element has any openings
         let opening_ids = void_index.get_voids(element.id);
 
@@ -537,7 +995,7 @@ impl GeometryRouter {
         // Try to extract extrusion parameters for 2D void processing
         // If the element isn't an extrusion, fall back to 3D CSG
         match self.try_process_extrusion_with_voids_2d(element, decoder, opening_ids) {
-            Ok(Some(mesh)) => Ok(mesh),
+            Ok(Some(mesh)) => Ok(self.filter_small(mesh)),
             Ok(None) | Err(_) => {
                 // Fall back to traditional 3D CSG approach
                 let void_map: FxHashMap<u32, Vec<u32>> =
@@ -547,6 +1005,98 @@ impl GeometryRouter {
         }
     }
 
+    /// Generate placeholder geometry for a filling element (door, window)
+    ///
+    /// Some models relate doors and windows to their openings via
+    /// IfcRelFillsElement but carry no body representation for them. The
+    /// opening's own geometry, at the opening's placement, is used as a
+    /// stand-in so the filled hole is not rendered empty.
+    pub fn process_filling_placeholder(
+        &self,
+        opening_id: u32,
+        decoder: &mut EntityDecoder,
+    ) -> Result<Mesh> {
+        let opening = decoder.decode_by_id(opening_id)?;
+        self.process_product(&opening, decoder)
+    }
+
+    /// Process element, falling back to an opening placeholder for bodiless fillings
+    ///
+    /// Returns the element's own geometry when it has any; otherwise, if the
+    /// element fills an opening (see [`VoidIndex::get_filled_opening`]), the
+    /// opening-sized placeholder from [`Self::process_filling_placeholder`].
+    /// A body that fails counts as missing; the error is then recorded with
+    /// [`crate::warnings::warn`] and the placeholder returned.
+    pub fn process_element_or_filling(
+        &self,
+        element: &DecodedEntity,
+        decoder: &mut EntityDecoder,
+        void_index: &VoidIndex,
+    ) -> Result<Mesh> {
+        if self.options.skips(element.ifc_type) {
+            return Ok(Mesh::new());
+        }
+        let has_representation = element.get(6).map(|a| !a.is_null()).unwrap_or(false);
+        let body = if has_representation {
+            self.process_product(element, decoder)
+        } else {
+            Ok(Mesh::new())
+        };
+
+        match body {
+            Ok(mesh) if !mesh.is_empty() => Ok(self.filter_small(mesh)),
+            body => self.filling_placeholder_or(element, body, decoder, void_index),
+        }
+    }
+
+    /// Placeholder for a filling whose body is empty or failed, else `body`
+    fn filling_placeholder_or(
+        &self,
+        element: &DecodedEntity,
+        body: Result<Mesh>,
+        decoder: &mut EntityDecoder,
+        void_index: &VoidIndex,
+    ) -> Result<Mesh> {
+        let Some(opening_id) = void_index.get_filled_opening(element.id) else {
+            return body;
+        };
+        if let Err(e) = &body {
+            crate::warnings::warn(format!(
+                "Body of {} failed, showing its opening instead: {}",
+                element.ifc_type, e
+            ));
+        }
+        Ok(self.filter_small(self.process_filling_placeholder(opening_id, decoder)?))
+    }
+
+    /// [`Self::process_element_or_filling`] with IfcMappedItem geometry kept
+    /// instanced, see [`Self::process_element_instanced`]
+    pub fn process_element_or_filling_instanced(
+        &self,
+        element: &DecodedEntity,
+        decoder: &mut EntityDecoder,
+        void_index: &VoidIndex,
+    ) -> Result<ElementGeometry> {
+        let has_representation = element.get(6).map(|a| !a.is_null()).unwrap_or(false);
+        if has_representation {
+            match self.process_element_instanced(element, decoder) {
+                Ok(geometry) if !geometry.is_empty() => return Ok(geometry),
+                Ok(_) => {}
+                Err(e) => {
+                    return Ok(ElementGeometry {
+                        mesh: self.filling_placeholder_or(element, Err(e), decoder, void_index)?,
+                        instances: Vec::new(),
+                    })
+                }
+            }
+        }
+        // Alignment ribbons and opening placeholders
+        Ok(ElementGeometry {
+            mesh: self.process_element_or_filling(element, decoder, void_index)?,
+            instances: Vec::new(),
+        })
+    }
+
     /// Try to process an extrusion with 2D void subtraction
     ///
     /// Returns Ok(Some(mesh)) if 2D processing was successful,
@@ -655,8 +1205,11 @@ impl GeometryRouter {
             Matrix4::identity()
         };
 
-        // Get element placement transform
-        let element_transform = self.get_placement_transform_from_element(element, decoder)?;
+        // Get element placement transform, at the shifted origin like the void meshes
+        let mut element_transform = self.get_placement_transform_from_element(element, decoder)?;
+        for i in 0..3 {
+            element_transform[(i, 3)] -= self.origin_shift[i] / self.unit_scale;
+        }
         let combined_transform = element_transform * position_transform;
 
         // Get swept area (profile) - attribute 0
@@ -685,7 +1238,7 @@ impl GeometryRouter {
                 Err(_) => continue,
             };
 
-            let opening_mesh = match self.process_element(&opening_entity, decoder) {
+            let opening_mesh = match self.process_product(&opening_entity, decoder) {
                 Ok(m) if !m.is_empty() => m,
                 _ => continue,
             };
@@ -697,7 +1250,7 @@ impl GeometryRouter {
             // No valid openings - just process the extrusion normally
             let processor = self.processors.get(&IfcType::IfcExtrudedAreaSolid);
             if let Some(proc) = processor {
-                let mut mesh = proc.process(extrusion, decoder, &self.schema)?;
+                let mut mesh = self.run_processor(proc.as_ref(), extrusion, decoder)?;
                 self.scale_mesh(&mut mesh);
                 self.apply_placement(element, decoder, &mut mesh)?;
                 return Ok(Some(mesh));
@@ -770,7 +1323,7 @@ impl GeometryRouter {
                 // Fall back to normal extrusion
                 let processor = self.processors.get(&IfcType::IfcExtrudedAreaSolid);
                 if let Some(proc) = processor {
-                    proc.process(extrusion, decoder, &self.schema)?
+                    self.run_processor(proc.as_ref(), extrusion, decoder)?
                 } else {
                     return Ok(None);
                 }
@@ -967,112 +1520,19 @@ impl GeometryRouter {
         element: &DecodedEntity,
         decoder: &mut EntityDecoder,
     ) -> Result<(Mesh, Matrix4<f64>)> {
-        // Get representation (attribute 6 for most building elements)
-        let representation_attr = element.get(6).ok_or_else(|| {
-            Error::geometry(format!(
-                "Element #{} has no representation attribute",
-                element.id
-            ))
-        })?;
-
-        if representation_attr.is_null() {
-            return Ok((Mesh::new(), Matrix4::identity())); // No geometry
-        }
-
-        let representation = decoder
-            .resolve_ref(representation_attr)?
-            .ok_or_else(|| Error::geometry("Failed to resolve representation".to_string()))?;
-
-        if representation.ifc_type != IfcType::IfcProductDefinitionShape {
-            return Err(Error::geometry(format!(
-                "Expected IfcProductDefinitionShape, got {}",
-                representation.ifc_type
-            )));
+        let mut combined_mesh = Mesh::new();
+        if !self.options.skips(element.ifc_type) {
+            for item in self.body_representation_items(element, decoder)? {
+                let mesh = self.process_representation_item(&item, decoder)?;
+                combined_mesh.merge(&mesh);
+            }
         }
 
-        // Get representations list (attribute 2)
-        let representations_attr = representation.get(2).ok_or_else(|| {
-            Error::geometry("IfcProductDefinitionShape missing Representations".to_string())
-        })?;
+        // Get placement transform WITHOUT applying it
+        let transform = self.get_placement_transform_from_element(element, decoder)?;
 
-        let representations = decoder.resolve_ref_list(representations_attr)?;
-
-        // Process all representations and merge meshes
-        let mut combined_mesh = Mesh::new();
-
-        // Check for direct geometry
-        let has_direct_geometry = representations.iter().any(|rep| {
-            if rep.ifc_type != IfcType::IfcShapeRepresentation {
-                return false;
-            }
-            if let Some(rep_type_attr) = rep.get(2) {
-                if let Some(rep_type) = rep_type_attr.as_string() {
-                    matches!(
-                        rep_type,
-                        "Body"
-                            | "SweptSolid"
-                            | "Brep"
-                            | "CSG"
-                            | "Clipping"
-                            | "SurfaceModel"
-                            | "Tessellation"
-                            | "AdvancedSweptSolid"
-                            | "AdvancedBrep"
-                    )
-                } else {
-                    false
-                }
-            } else {
-                false
-            }
-        });
-
-        for shape_rep in representations {
-            if shape_rep.ifc_type != IfcType::IfcShapeRepresentation {
-                continue;
-            }
-
-            if let Some(rep_type_attr) = shape_rep.get(2) {
-                if let Some(rep_type) = rep_type_attr.as_string() {
-                    if rep_type == "MappedRepresentation" && has_direct_geometry {
-                        continue;
-                    }
-
-                    if !matches!(
-                        rep_type,
-                        "Body"
-                            | "SweptSolid"
-                            | "Brep"
-                            | "CSG"
-                            | "Clipping"
-                            | "SurfaceModel"
-                            | "Tessellation"
-                            | "MappedRepresentation"
-                            | "AdvancedSweptSolid"
-                            | "AdvancedBrep"
-                    ) {
-                        continue;
-                    }
-                }
-            }
-
-            let items_attr = shape_rep.get(3).ok_or_else(|| {
-                Error::geometry("IfcShapeRepresentation missing Items".to_string())
-            })?;
-
-            let items = decoder.resolve_ref_list(items_attr)?;
-
-            for item in items {
-                let mesh = self.process_representation_item(&item, decoder)?;
-                combined_mesh.merge(&mesh);
-            }
-        }
-
-        // Get placement transform WITHOUT applying it
-        let transform = self.get_placement_transform_from_element(element, decoder)?;
-
-        Ok((combined_mesh, transform))
-    }
+        Ok((self.filter_small(combined_mesh), transform))
+    }
 
     /// Get placement transform from element without applying it
     fn get_placement_transform_from_element(
@@ -1110,7 +1570,22 @@ impl GeometryRouter {
 
         // Check FacetedBrep cache first (from batch preprocessing)
         if item.ifc_type == IfcType::IfcFacetedBrep {
+            if self.face_colors.has_styled_faces() {
+                let (mut mesh, triangle_faces) = self.time(&item.ifc_type, || {
+                    FacetedBrepProcessor::new().process_with_face_ids(item, decoder)
+                })?;
+                let colors: Vec<_> = triangle_faces
+                    .iter()
+                    .map(|&face_id| self.face_colors.face_color(face_id))
+                    .collect();
+                mesh.apply_triangle_colors(&colors);
+                self.generate_normals(&mut mesh);
+                self.scale_mesh(&mut mesh);
+                let cached = self.get_or_cache_by_hash(mesh);
+                return Ok((*cached).clone());
+            }
             if let Some(mut mesh) = self.take_cached_faceted_brep(item.id) {
+                self.generate_normals(&mut mesh);
                 self.scale_mesh(&mut mesh);
                 let cached = self.get_or_cache_by_hash(mesh);
                 return Ok((*cached).clone());
@@ -1119,7 +1594,11 @@ impl GeometryRouter {
 
         // Check if we have a processor for this type
         if let Some(processor) = self.processors.get(&item.ifc_type) {
-            let mut mesh = processor.process(item, decoder, &self.schema)?;
+            let mut mesh = self.run_processor(processor.as_ref(), item, decoder)?;
+            if let Some(colors) = self.face_colors.face_set_colors(item.id) {
+                mesh.apply_triangle_colors(colors);
+            }
+            self.generate_normals(&mut mesh);
             self.scale_mesh(&mut mesh);
 
             // Deduplicate by hash - buildings with repeated floors have identical geometry
@@ -1162,6 +1641,21 @@ impl GeometryRouter {
         item: &DecodedEntity,
         decoder: &mut EntityDecoder,
     ) -> Result<Mesh> {
+        let (_, source_mesh, transform) = self.resolve_mapped_item(item, decoder)?;
+        let mut mesh = source_mesh.as_ref().clone();
+        if transform != Matrix4::identity() {
+            self.transform_mesh(&mut mesh, &transform);
+        }
+        Ok(mesh)
+    }
+
+    /// Resolve an IfcMappedItem into its shared source mesh and instance transform
+    /// Returns (RepresentationMap ID, cached mesh in map coordinates, map-to-parent transform)
+    fn resolve_mapped_item(
+        &self,
+        item: &DecodedEntity,
+        decoder: &mut EntityDecoder,
+    ) -> Result<(u32, Arc<Mesh>, Matrix4<f64>)> {
         // IfcMappedItem attributes:
         // 0: MappingSource (IfcRepresentationMap)
         // 1: MappingTarget (IfcCartesianTransformationOperator)
@@ -1175,37 +1669,47 @@ impl GeometryRouter {
             .resolve_ref(source_attr)?
             .ok_or_else(|| Error::geometry("Failed to resolve MappingSource".to_string()))?;
 
-        let source_id = source_entity.id;
-
         // Get MappingTarget transformation (attribute 1: CartesianTransformationOperator)
-        let mapping_transform = if let Some(target_attr) = item.get(1) {
+        let mut target = Matrix4::identity();
+        if let Some(target_attr) = item.get(1) {
             if !target_attr.is_null() {
                 if let Some(target_entity) = decoder.resolve_ref(target_attr)? {
-                    Some(self.parse_cartesian_transformation_operator(&target_entity, decoder)?)
-                } else {
-                    None
+                    target =
+                        self.parse_cartesian_transformation_operator(&target_entity, decoder)?;
                 }
-            } else {
-                None
             }
-        } else {
-            None
-        };
+        }
 
-        // Check cache first
-        {
-            let cache = self.mapped_item_cache.borrow();
-            if let Some(cached_mesh) = cache.get(&source_id) {
-                let mut mesh = cached_mesh.as_ref().clone();
-                if let Some(mut transform) = mapping_transform {
-                    self.scale_transform(&mut transform);
-                    self.transform_mesh(&mut mesh, &transform);
+        // IfcRepresentationMap.MappingOrigin (attribute 0) positions the mapped
+        // representation before the MappingTarget is applied
+        let mut origin = Matrix4::identity();
+        if let Some(origin_attr) = source_entity.get(0) {
+            if !origin_attr.is_null() {
+                if let Some(origin_entity) = decoder.resolve_ref(origin_attr)? {
+                    if origin_entity.ifc_type == IfcType::IfcAxis2Placement3D {
+                        origin = self.parse_axis2_placement_3d(&origin_entity, decoder)?;
+                    }
                 }
-                return Ok(mesh);
             }
         }
 
-        // Cache miss - process the geometry
+        let mut transform = target * origin;
+        self.scale_transform(&mut transform);
+
+        let mesh = self.mapped_source_mesh(&source_entity, decoder)?;
+        Ok((source_entity.id, mesh, transform))
+    }
+
+    /// Get the geometry of an IfcRepresentationMap, processing it on first use
+    fn mapped_source_mesh(
+        &self,
+        source_entity: &DecodedEntity,
+        decoder: &mut EntityDecoder,
+    ) -> Result<Arc<Mesh>> {
+        if let Some(cached_mesh) = self.mapped_item_cache.borrow().get(&source_entity.id) {
+            return Ok(Arc::clone(cached_mesh));
+        }
+
         // IfcRepresentationMap has:
         // 0: MappingOrigin (IfcAxis2Placement)
         // 1: MappedRepresentation (IfcRepresentation)
@@ -1232,48 +1736,36 @@ impl GeometryRouter {
                 continue; // Skip nested MappedItems to avoid recursion
             }
             if let Some(processor) = self.processors.get(&sub_item.ifc_type) {
-                if let Ok(mut sub_mesh) = processor.process(&sub_item, decoder, &self.schema) {
+                if let Ok(mut sub_mesh) = self.run_processor(processor.as_ref(), &sub_item, decoder)
+                {
                     self.scale_mesh(&mut sub_mesh);
                     mesh.merge(&sub_mesh);
                 }
             }
         }
 
-        // Store in cache (before transformation, so cached mesh is in source coordinates)
-        {
-            let mut cache = self.mapped_item_cache.borrow_mut();
-            cache.insert(source_id, Arc::new(mesh.clone()));
-        }
-
-        // Apply MappingTarget transformation to this instance
-        if let Some(mut transform) = mapping_transform {
-            self.scale_transform(&mut transform);
-            self.transform_mesh(&mut mesh, &transform);
-        }
-
+        // Cached mesh stays in map coordinates; instances apply their own transform
+        let mesh = Arc::new(mesh);
+        self.mapped_item_cache
+            .borrow_mut()
+            .insert(source_entity.id, Arc::clone(&mesh));
         Ok(mesh)
     }
 
     /// Apply local placement transformation to mesh
+    /// Elements without placement are still moved to the shifted origin
     fn apply_placement(
         &self,
         element: &DecodedEntity,
         decoder: &mut EntityDecoder,
         mesh: &mut Mesh,
     ) -> Result<()> {
-        let placement_attr = match element.get(5) {
-            Some(attr) if !attr.is_null() => attr,
-            _ => return Ok(()), // No placement
-        };
-
-        let placement = match decoder.resolve_ref(placement_attr)? {
-            Some(p) => p,
-            None => return Ok(()),
-        };
-
-        let mut transform = self.get_placement_transform(&placement, decoder)?;
+        let mut transform = self.get_placement_transform_from_element(element, decoder)?;
         self.scale_transform(&mut transform);
-        self.transform_mesh(mesh, &transform);
+        self.shift_transform(&mut transform);
+        if transform != Matrix4::identity() {
+            self.transform_mesh(mesh, &transform);
+        }
         Ok(())
     }
 
@@ -1283,6 +1775,12 @@ impl GeometryRouter {
         placement: &DecodedEntity,
         decoder: &mut EntityDecoder,
     ) -> Result<Matrix4<f64>> {
+        if placement.ifc_type == IfcType::IfcGridPlacement {
+            return self.get_grid_placement_transform(placement, decoder);
+        }
+        if placement.ifc_type == IfcType::IfcLinearPlacement {
+            return self.get_linear_placement_transform(placement, decoder);
+        }
         if placement.ifc_type != IfcType::IfcLocalPlacement {
             return Ok(Matrix4::identity());
         }
@@ -1325,6 +1823,303 @@ impl GeometryRouter {
         Ok(parent_transform * local_transform)
     }
 
+    /// Resolve IfcLinearPlacement to a frame along its basis curve
+    ///
+    /// The frame's X axis follows the curve, Y points left and Z up; the
+    /// Axis and RefDirection of the IfcAxis2PlacementLinear rotate within
+    /// it. A CartesianPosition, when exported, is used as is.
+    fn get_linear_placement_transform(
+        &self,
+        placement: &DecodedEntity,
+        decoder: &mut EntityDecoder,
+    ) -> Result<Matrix4<f64>> {
+        // IfcLinearPlacement: PlacementRelTo, RelativePlacement, CartesianPosition
+        let parent_transform = match placement.get(0).filter(|a| !a.is_null()) {
+            Some(parent_attr) => match decoder.resolve_ref(parent_attr)? {
+                Some(parent) => self.get_placement_transform(&parent, decoder)?,
+                None => Matrix4::identity(),
+            },
+            None => Matrix4::identity(),
+        };
+
+        if let Some(cartesian) = placement.get(2).filter(|a| !a.is_null()) {
+            if let Some(cartesian) = decoder.resolve_ref(cartesian)? {
+                if cartesian.ifc_type == IfcType::IfcAxis2Placement3D {
+                    return Ok(
+                        parent_transform * self.parse_axis2_placement_3d(&cartesian, decoder)?
+                    );
+                }
+            }
+        }
+
+        let relative = placement
+            .get(1)
+            .and_then(|a| a.as_entity_ref())
+            .ok_or_else(|| {
+                Error::geometry("IfcLinearPlacement missing RelativePlacement".to_string())
+            })?;
+        let relative = decoder.decode_by_id(relative)?;
+        let position = DistanceExpression::from_entity(&relative, decoder)?;
+        let curve = position
+            .basis_curve
+            .and_then(|id| self.alignment_curve(id, decoder))
+            .ok_or_else(|| {
+                Error::geometry("IfcLinearPlacement basis curve not found".to_string())
+            })?;
+        let frame = curve.frame_at(position.distance, position.lateral, position.vertical);
+
+        // Axis and RefDirection apply only to IfcAxis2PlacementLinear
+        let mut rotation = Matrix4::identity();
+        if relative.ifc_type == IfcType::IfcAxis2PlacementLinear {
+            let mut direction = |index: usize, default: Vector3<f64>| -> Result<Vector3<f64>> {
+                match relative.get(index).filter(|a| !a.is_null()) {
+                    Some(attr) => match decoder.resolve_ref(attr)? {
+                        Some(dir) => self.parse_direction(&dir),
+                        None => Ok(default),
+                    },
+                    None => Ok(default),
+                }
+            };
+            let z_axis = direction(1, Vector3::z())?.normalize();
+            let x_axis = direction(2, Vector3::x())?;
+            let y_axis = z_axis.cross(&x_axis).normalize();
+            let x_axis = y_axis.cross(&z_axis);
+            rotation
+                .fixed_view_mut::<3, 3>(0, 0)
+                .copy_from(&nalgebra::Matrix3::from_columns(&[x_axis, y_axis, z_axis]));
+        }
+
+        Ok(parent_transform * frame * rotation)
+    }
+
+    /// Sampled basis curve for linear placements, cached per curve entity
+    fn alignment_curve(&self, id: u32, decoder: &mut EntityDecoder) -> Option<Arc<AlignmentCurve>> {
+        if let Some(cached) = self.alignment_curves.borrow().get(&id) {
+            return cached.clone();
+        }
+        let profiles = ProfileProcessor::with_options(self.schema.clone(), self.options);
+        let curve = decoder
+            .decode_by_id(id)
+            .ok()
+            .and_then(|entity| AlignmentCurve::from_entity(&entity, decoder, &profiles).ok())
+            .map(Arc::new);
+        self.alignment_curves.borrow_mut().insert(id, curve.clone());
+        curve
+    }
+
+    /// Resolve IfcGridPlacement to a transform at the virtual grid intersection
+    fn get_grid_placement_transform(
+        &self,
+        placement: &DecodedEntity,
+        decoder: &mut EntityDecoder,
+    ) -> Result<Matrix4<f64>> {
+        // IFC2x3/IFC4: PlacementLocation, PlacementRefDirection
+        // IFC4.3 adds PlacementRelTo in front
+        let offset = if placement.attributes.len() >= 3 {
+            1
+        } else {
+            0
+        };
+
+        // PlacementRelTo: the grid intersection is placed in its frame
+        let parent_transform = match placement.get(0).filter(|a| offset == 1 && !a.is_null()) {
+            Some(parent_attr) => match decoder.resolve_ref(parent_attr)? {
+                Some(parent) => self.get_placement_transform(&parent, decoder)?,
+                None => Matrix4::identity(),
+            },
+            None => Matrix4::identity(),
+        };
+
+        let location_attr = placement
+            .get(offset)
+            .ok_or_else(|| Error::geometry("IfcGridPlacement missing location".to_string()))?;
+        let location = decoder
+            .resolve_ref(location_attr)?
+            .ok_or_else(|| Error::geometry("Failed to resolve grid intersection".to_string()))?;
+        let (origin, grid_transform) =
+            self.resolve_virtual_grid_intersection(&location, decoder)?;
+
+        // PlacementRefDirection: another intersection (IFC2x3/IFC4) or IfcDirection (IFC4)
+        let mut x_dir = Vector3::new(1.0, 0.0, 0.0);
+        if let Some(attr) = placement.get(offset + 1) {
+            if let Some(ref_dir) = decoder.resolve_ref(attr)? {
+                let dir = match ref_dir.ifc_type {
+                    IfcType::IfcVirtualGridIntersection => self
+                        .resolve_virtual_grid_intersection(&ref_dir, decoder)
+                        .map(|(target, _)| target - origin)
+                        .ok(),
+                    IfcType::IfcDirection => self.parse_direction(&ref_dir).ok(),
+                    _ => None,
+                };
+                if let Some(dir) = dir {
+                    let planar = Vector3::new(dir.x, dir.y, 0.0);
+                    if planar.norm() > 1e-12 {
+                        x_dir = planar.normalize();
+                    }
+                }
+            }
+        }
+
+        let mut local = Matrix4::identity();
+        local[(0, 0)] = x_dir.x;
+        local[(1, 0)] = x_dir.y;
+        local[(0, 1)] = -x_dir.y;
+        local[(1, 1)] = x_dir.x;
+        local[(0, 3)] = origin.x;
+        local[(1, 3)] = origin.y;
+        local[(2, 3)] = origin.z;
+
+        Ok(parent_transform * grid_transform * local)
+    }
+
+    /// Resolve IfcVirtualGridIntersection to a grid-local point and its grid's transform
+    fn resolve_virtual_grid_intersection(
+        &self,
+        intersection: &DecodedEntity,
+        decoder: &mut EntityDecoder,
+    ) -> Result<(Point3<f64>, Matrix4<f64>)> {
+        // IfcVirtualGridIntersection: IntersectingAxes (0), OffsetDistances (1)
+        let axes_attr = intersection.get(0).ok_or_else(|| {
+            Error::geometry("IfcVirtualGridIntersection missing axes".to_string())
+        })?;
+        let axes = decoder.resolve_ref_list(axes_attr)?;
+        if axes.len() < 2 {
+            return Err(Error::geometry(
+                "IfcVirtualGridIntersection needs two axes".to_string(),
+            ));
+        }
+
+        let offsets: Vec<f64> = intersection
+            .get(1)
+            .and_then(|a| a.as_list())
+            .map(|list| list.iter().filter_map(|v| v.as_float()).collect())
+            .unwrap_or_default();
+
+        let first = self.parse_grid_axis_line(&axes[0], decoder)?;
+        let second = self.parse_grid_axis_line(&axes[1], decoder)?;
+        let point = virtual_intersection(&first, &second, &offsets)
+            .ok_or_else(|| Error::geometry("Grid axes are parallel".to_string()))?;
+
+        let grid_transform = self
+            .grid_transforms
+            .borrow()
+            .get(&axes[0].id)
+            .copied()
+            .unwrap_or_else(Matrix4::identity);
+
+        Ok((point, grid_transform))
+    }
+
+    /// Parse IfcGridAxis curve into a straight axis line
+    fn parse_grid_axis_line(
+        &self,
+        axis: &DecodedEntity,
+        decoder: &mut EntityDecoder,
+    ) -> Result<GridAxisLine> {
+        let mut curve = self.resolve_grid_axis_curve(axis, decoder)?;
+
+        // Trimmed curves: the axis direction comes from the basis curve
+        while curve.ifc_type == IfcType::IfcTrimmedCurve {
+            let basis_attr = curve
+                .get(0)
+                .ok_or_else(|| Error::geometry("IfcTrimmedCurve missing basis".to_string()))?;
+            curve = decoder
+                .resolve_ref(basis_attr)?
+                .ok_or_else(|| Error::geometry("Failed to resolve basis curve".to_string()))?;
+        }
+
+        let (start, end) = self.parse_grid_curve_points(&curve, decoder)?;
+        let line = GridAxisLine::from_points(start, end)
+            .ok_or_else(|| Error::geometry("Degenerate grid axis".to_string()))?;
+
+        // SameSense (.F. -> "F") reverses the axis direction
+        let same_sense = axis
+            .get(2)
+            .and_then(|a| a.as_enum())
+            .map(|v| v != "F")
+            .unwrap_or(true);
+
+        Ok(if same_sense { line } else { line.reversed() })
+    }
+
+    /// Parse IfcGridAxis curve into its grid-local end points
+    fn parse_grid_axis_segment(
+        &self,
+        axis: &DecodedEntity,
+        decoder: &mut EntityDecoder,
+    ) -> Result<(Point2<f64>, Point2<f64>)> {
+        let curve = self.resolve_grid_axis_curve(axis, decoder)?;
+        self.parse_grid_curve_points(&curve, decoder)
+    }
+
+    /// Resolve IfcGridAxis: AxisTag (0), AxisCurve (1), SameSense (2)
+    fn resolve_grid_axis_curve(
+        &self,
+        axis: &DecodedEntity,
+        decoder: &mut EntityDecoder,
+    ) -> Result<DecodedEntity> {
+        let curve_attr = axis
+            .get(1)
+            .ok_or_else(|| Error::geometry("IfcGridAxis missing curve".to_string()))?;
+        decoder
+            .resolve_ref(curve_attr)?
+            .ok_or_else(|| Error::geometry("Failed to resolve grid axis curve".to_string()))
+    }
+
+    /// First and last point of a polyline grid axis, or start and
+    /// start + vector of a line
+    fn parse_grid_curve_points(
+        &self,
+        curve: &DecodedEntity,
+        decoder: &mut EntityDecoder,
+    ) -> Result<(Point2<f64>, Point2<f64>)> {
+        match curve.ifc_type {
+            IfcType::IfcPolyline => {
+                let points_attr = curve
+                    .get(0)
+                    .ok_or_else(|| Error::geometry("IfcPolyline missing points".to_string()))?;
+                let points = decoder.resolve_ref_list(points_attr)?;
+                let coords = |p: &DecodedEntity| {
+                    let list = p.get(0).and_then(|a| a.as_list());
+                    let x = list.and_then(|l| l.first()).and_then(|v| v.as_float());
+                    let y = list.and_then(|l| l.get(1)).and_then(|v| v.as_float());
+                    Point2::new(x.unwrap_or(0.0), y.unwrap_or(0.0))
+                };
+                match (points.first(), points.last()) {
+                    (Some(start), Some(end)) => Ok((coords(start), coords(end))),
+                    _ => Err(Error::geometry("Empty grid axis polyline".to_string())),
+                }
+            }
+            IfcType::IfcLine => {
+                // IfcLine: Pnt (0), Dir (1) -> IfcVector: Orientation (0), Magnitude (1)
+                let start = self.parse_cartesian_point(curve, decoder, 0)?;
+                let vector_attr = curve
+                    .get(1)
+                    .ok_or_else(|| Error::geometry("IfcLine missing direction".to_string()))?;
+                let vector = decoder
+                    .resolve_ref(vector_attr)?
+                    .ok_or_else(|| Error::geometry("Failed to resolve IfcVector".to_string()))?;
+                let orientation = vector
+                    .get(0)
+                    .map(|attr| decoder.resolve_ref(attr))
+                    .transpose()?
+                    .flatten()
+                    .ok_or_else(|| Error::geometry("IfcVector missing orientation".to_string()))?;
+                let dir = self.parse_direction(&orientation)?;
+                let magnitude = vector.get_float(1).filter(|m| *m > 0.0).unwrap_or(1.0);
+                let start = Point2::new(start.x, start.y);
+                Ok((
+                    start,
+                    start + nalgebra::Vector2::new(dir.x, dir.y) * magnitude,
+                ))
+            }
+            other => Err(Error::geometry(format!(
+                "Unsupported grid axis curve type: {}",
+                other
+            ))),
+        }
+    }
+
     /// Parse IfcAxis2Placement3D into transformation matrix
     fn parse_axis2_placement_3d(
         &self,
@@ -1552,24 +2347,7 @@ impl GeometryRouter {
     /// Transform mesh by matrix - optimized with chunk-based iteration
     #[inline]
     fn transform_mesh(&self, mesh: &mut Mesh, transform: &Matrix4<f64>) {
-        // Use chunks for better cache locality and less indexing overhead
-        mesh.positions.chunks_exact_mut(3).for_each(|chunk| {
-            let point = Point3::new(chunk[0] as f64, chunk[1] as f64, chunk[2] as f64);
-            let transformed = transform.transform_point(&point);
-            chunk[0] = transformed.x as f32;
-            chunk[1] = transformed.y as f32;
-            chunk[2] = transformed.z as f32;
-        });
-
-        // Transform normals (without translation) - optimized chunk iteration
-        let rotation = transform.fixed_view::<3, 3>(0, 0);
-        mesh.normals.chunks_exact_mut(3).for_each(|chunk| {
-            let normal = Vector3::new(chunk[0] as f64, chunk[1] as f64, chunk[2] as f64);
-            let transformed = (rotation * normal).normalize();
-            chunk[0] = transformed.x as f32;
-            chunk[1] = transformed.y as f32;
-            chunk[2] = transformed.z as f32;
-        });
+        mesh.transform(transform);
     }
 
     /// Get schema reference
@@ -1631,4 +2409,540 @@ mod tests {
         assert_eq!(vec.y, 0.0);
         assert_eq!(vec.z, 0.0);
     }
+
+    #[test]
+    fn test_grid_placement() {
+        // Grid placed at (100, 0, 0) with axes A (along X at y=0) and 1 (along Y at x=5)
+        let content = r#"
+#1=IFCCARTESIANPOINT((100.0,0.0,0.0));
+#2=IFCAXIS2PLACEMENT3D(#1,$,$);
+#3=IFCLOCALPLACEMENT($,#2);
+#10=IFCCARTESIANPOINT((0.0,0.0));
+#11=IFCCARTESIANPOINT((20.0,0.0));
+#12=IFCPOLYLINE((#10,#11));
+#13=IFCGRIDAXIS('A',#12,.T.);
+#20=IFCCARTESIANPOINT((5.0,-10.0));
+#21=IFCCARTESIANPOINT((5.0,10.0));
+#22=IFCPOLYLINE((#20,#21));
+#23=IFCGRIDAXIS('1',#22,.T.);
+#30=IFCGRID('guid',$,$,$,$,#3,$,(#13),(#23),$);
+#40=IFCVIRTUALGRIDINTERSECTION((#13,#23),(2.0,0.0,3.0));
+#41=IFCGRIDPLACEMENT(#40,$);
+"#;
+
+        let mut decoder = EntityDecoder::new(content);
+        let router = GeometryRouter::new();
+        router.index_grids(content, &mut decoder);
+
+        let placement = decoder.decode_by_id(41).unwrap();
+        let transform = router
+            .get_placement_transform(&placement, &mut decoder)
+            .unwrap();
+
+        // Axis A offset by 2 to the left (y=2), crossing axis 1 at x=5, elevation 3
+        assert!((transform[(0, 3)] - 105.0).abs() < 1e-9);
+        assert!((transform[(1, 3)] - 2.0).abs() < 1e-9);
+        assert!((transform[(2, 3)] - 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_grid_placement_relative_to() {
+        // IFC4X3 attribute order, relative to a placement lifted by 10
+        let content = r#"
+#1=IFCCARTESIANPOINT((100.0,0.0,0.0));
+#2=IFCAXIS2PLACEMENT3D(#1,$,$);
+#3=IFCLOCALPLACEMENT($,#2);
+#4=IFCCARTESIANPOINT((0.0,0.0,10.0));
+#5=IFCAXIS2PLACEMENT3D(#4,$,$);
+#6=IFCLOCALPLACEMENT($,#5);
+#10=IFCCARTESIANPOINT((0.0,0.0));
+#11=IFCCARTESIANPOINT((20.0,0.0));
+#12=IFCPOLYLINE((#10,#11));
+#13=IFCGRIDAXIS('A',#12,.T.);
+#20=IFCCARTESIANPOINT((5.0,-10.0));
+#21=IFCCARTESIANPOINT((5.0,10.0));
+#22=IFCPOLYLINE((#20,#21));
+#23=IFCGRIDAXIS('1',#22,.T.);
+#30=IFCGRID('guid',$,$,$,$,#3,$,(#13),(#23),$);
+#40=IFCVIRTUALGRIDINTERSECTION((#13,#23),(2.0,0.0,3.0));
+#41=IFCGRIDPLACEMENT(#6,#40,$);
+#42=IFCGRIDPLACEMENT($,#40,$);
+"#;
+
+        let mut decoder = EntityDecoder::new(content);
+        let router = GeometryRouter::new();
+        router.index_grids(content, &mut decoder);
+
+        let placement = decoder.decode_by_id(41).unwrap();
+        let transform = router
+            .get_placement_transform(&placement, &mut decoder)
+            .unwrap();
+        assert!((transform[(0, 3)] - 105.0).abs() < 1e-9);
+        assert!((transform[(1, 3)] - 2.0).abs() < 1e-9);
+        assert!((transform[(2, 3)] - 13.0).abs() < 1e-9);
+
+        let placement = decoder.decode_by_id(42).unwrap();
+        let transform = router
+            .get_placement_transform(&placement, &mut decoder)
+            .unwrap();
+        assert!((transform[(2, 3)] - 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_grid_axes() {
+        // Grid placed at (100, 0, 0) with axes A (along X at y=0) and 1 (along Y at x=5)
+        let content = r#"
+#1=IFCCARTESIANPOINT((100.0,0.0,0.0));
+#2=IFCAXIS2PLACEMENT3D(#1,$,$);
+#3=IFCLOCALPLACEMENT($,#2);
+#10=IFCCARTESIANPOINT((0.0,0.0));
+#11=IFCCARTESIANPOINT((20.0,0.0));
+#12=IFCPOLYLINE((#10,#11));
+#13=IFCGRIDAXIS('A',#12,.T.);
+#20=IFCCARTESIANPOINT((5.0,-10.0));
+#21=IFCCARTESIANPOINT((5.0,10.0));
+#22=IFCPOLYLINE((#20,#21));
+#23=IFCGRIDAXIS('1',#22,.T.);
+#30=IFCGRID('guid',$,$,$,$,#3,$,(#13),(#23),$);
+"#;
+
+        let mut decoder = EntityDecoder::new(content);
+        let mut router = GeometryRouter::new();
+        router.set_origin_shift(Vector3::new(100.0, 0.0, 0.0));
+
+        let axes = router.grid_axes(content, &mut decoder);
+        assert_eq!(axes.len(), 2);
+        assert_eq!((axes[0].grid_id, axes[0].tag.as_str()), (30, "A"));
+        assert_eq!(axes[0].start, Point3::new(0.0, 0.0, 0.0));
+        assert_eq!(axes[0].end, Point3::new(20.0, 0.0, 0.0));
+        assert_eq!(axes[1].tag, "1");
+        assert_eq!(axes[1].start, Point3::new(5.0, -10.0, 0.0));
+
+        let intersections = crate::grid_intersections(&axes);
+        assert_eq!(intersections.len(), 1);
+        assert_eq!(intersections[0].tags, ("A".to_string(), "1".to_string()));
+        assert_eq!(intersections[0].point, Point3::new(5.0, 0.0, 0.0));
+    }
+
+    /// Straight 100 m alignment along +X starting at the origin
+    const STRAIGHT_ALIGNMENT: &str = r#"
+#1=IFCCARTESIANPOINT((0.0,0.0));
+#2=IFCDIRECTION((1.0,0.0));
+#3=IFCAXIS2PLACEMENT2D(#1,#2);
+#4=IFCVECTOR(#2,1.0);
+#5=IFCLINE(#1,#4);
+#6=IFCCURVESEGMENT(.CONTINUOUS.,#3,IFCLENGTHMEASURE(0.0),IFCLENGTHMEASURE(100.0),#5);
+#7=IFCCOMPOSITECURVE((#6),.F.);
+#8=IFCCARTESIANPOINT((0.0,0.0,0.0));
+#9=IFCAXIS2PLACEMENT3D(#8,$,$);
+#10=IFCLOCALPLACEMENT($,#9);
+"#;
+
+    #[test]
+    fn test_linear_placement() {
+        let content = format!(
+            "{}{}",
+            STRAIGHT_ALIGNMENT,
+            r#"
+#20=IFCPOINTBYDISTANCEEXPRESSION(IFCLENGTHMEASURE(30.0),2.0,1.0,$,#7);
+#21=IFCAXIS2PLACEMENTLINEAR(#20,$,$);
+#22=IFCLINEARPLACEMENT(#10,#21,$);
+"#
+        );
+        let mut decoder = EntityDecoder::new(&content);
+        let router = GeometryRouter::new();
+
+        let placement = decoder.decode_by_id(22).unwrap();
+        let transform = router
+            .get_placement_transform(&placement, &mut decoder)
+            .unwrap();
+
+        assert!((transform[(0, 3)] - 30.0).abs() < 1e-9);
+        assert!((transform[(1, 3)] - 2.0).abs() < 1e-9);
+        assert!((transform[(2, 3)] - 1.0).abs() < 1e-9);
+        // Local X follows the alignment
+        assert!((transform[(0, 0)] - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_sectioned_solid_horizontal() {
+        // 2 x 1 rectangle swept over the first 10 m of the alignment
+        let content = format!(
+            "{}{}",
+            STRAIGHT_ALIGNMENT,
+            r#"
+#20=IFCRECTANGLEPROFILEDEF(.AREA.,$,#3,2.0,1.0);
+#21=IFCPOINTBYDISTANCEEXPRESSION(IFCLENGTHMEASURE(0.0),$,$,$,#7);
+#22=IFCAXIS2PLACEMENTLINEAR(#21,$,$);
+#23=IFCPOINTBYDISTANCEEXPRESSION(IFCLENGTHMEASURE(10.0),$,$,$,#7);
+#24=IFCAXIS2PLACEMENTLINEAR(#23,$,$);
+#25=IFCSECTIONEDSOLIDHORIZONTAL(#7,(#20,#20),(#22,#24));
+#26=IFCSHAPEREPRESENTATION($,'Body','AdvancedSweptSolid',(#25));
+#27=IFCPRODUCTDEFINITIONSHAPE($,$,(#26));
+#28=IFCCOURSE('guid',$,$,$,$,#10,#27,$,$);
+"#
+        );
+        let mut decoder = EntityDecoder::new(&content);
+        let router = GeometryRouter::new();
+
+        let course = decoder.decode_by_id(28).unwrap();
+        let mesh = router.process_element(&course, &mut decoder).unwrap();
+        let (min, max) = mesh.bounds();
+        assert!((min.x - 0.0).abs() < 1e-5 && (max.x - 10.0).abs() < 1e-5);
+        assert!((min.y + 1.0).abs() < 1e-5 && (max.y - 1.0).abs() < 1e-5);
+        assert!((min.z + 0.5).abs() < 1e-5 && (max.z - 0.5).abs() < 1e-5);
+
+        let volume = crate::quantities::mesh_measures(&mesh).volume.unwrap();
+        assert!((volume - 20.0).abs() < 1e-3, "volume {}", volume);
+    }
+
+    #[test]
+    fn test_alignment_ribbon() {
+        let content = format!(
+            "{}{}",
+            STRAIGHT_ALIGNMENT,
+            r#"
+#20=IFCSHAPEREPRESENTATION($,'Axis','Curve2D',(#7));
+#21=IFCPRODUCTDEFINITIONSHAPE($,$,(#20));
+#22=IFCALIGNMENT('guid',$,$,$,$,#10,#21,$);
+"#
+        );
+        let mut decoder = EntityDecoder::new(&content);
+        let router = GeometryRouter::new();
+
+        let alignment = decoder.decode_by_id(22).unwrap();
+        let mesh = router.process_element(&alignment, &mut decoder).unwrap();
+        assert!(!mesh.is_empty());
+        let (min, max) = mesh.bounds();
+        assert!((max.x - 100.0).abs() < 1e-4);
+        assert!((max.y - min.y - 0.3).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_origin_shift() {
+        // 1x1x3 wall 10.125 m east and 20.25 m north of a site in map coordinates
+        let content = r#"
+#1=IFCCARTESIANPOINT((2600000.0,1200000.0,450.0));
+#2=IFCAXIS2PLACEMENT3D(#1,$,$);
+#3=IFCLOCALPLACEMENT($,#2);
+#4=IFCSITE('site',$,$,$,$,#3,$,$,$,$,$,$,$,$);
+#5=IFCCARTESIANPOINT((10.125,20.25,0.0));
+#6=IFCAXIS2PLACEMENT3D(#5,$,$);
+#7=IFCLOCALPLACEMENT(#3,#6);
+#8=IFCCARTESIANPOINT((0.0,0.0,0.0));
+#9=IFCAXIS2PLACEMENT3D(#8,$,$);
+#10=IFCAXIS2PLACEMENT2D(#8,$);
+#11=IFCRECTANGLEPROFILEDEF(.AREA.,$,#10,1.0,1.0);
+#12=IFCDIRECTION((0.0,0.0,1.0));
+#13=IFCEXTRUDEDAREASOLID(#11,#9,#12,3.0);
+#14=IFCSHAPEREPRESENTATION($,'Body','SweptSolid',(#13));
+#15=IFCPRODUCTDEFINITIONSHAPE($,$,(#14));
+#16=IFCWALL('wall',$,$,$,$,#7,#15,$,$);
+"#;
+        let mut decoder = EntityDecoder::new(content);
+        let mut router = GeometryRouter::new();
+
+        let shift = router.detect_origin_shift(content, &mut decoder).unwrap();
+        assert_eq!(shift, Vector3::new(2600000.0, 1200000.0, 450.0));
+
+        // Exact in f32 once the shift is taken off
+        let wall = decoder.decode_by_id(16).unwrap();
+        let mesh = router.process_element(&wall, &mut decoder).unwrap();
+        let (min, max) = mesh.bounds();
+        assert_eq!((min.x, min.y, min.z), (9.625, 19.75, 0.0));
+        assert_eq!((max.x, max.y, max.z), (10.625, 20.75, 3.0));
+
+        // Models near the origin stay where they are
+        let mut router = GeometryRouter::new();
+        let near = content.replace("2600000.0,1200000.0,450.0", "100.0,0.0,0.0");
+        let mut decoder = EntityDecoder::new(&near);
+        assert!(router.detect_origin_shift(&near, &mut decoder).is_none());
+        assert_eq!(router.origin_shift(), Vector3::zeros());
+    }
+
+    #[test]
+    fn test_failed_filling_body_falls_back_to_opening() {
+        // Door #30 fills opening #20, but its body refers to a missing entity
+        let content = r#"
+#1=IFCCARTESIANPOINT((0.0,0.0,0.0));
+#2=IFCAXIS2PLACEMENT3D(#1,$,$);
+#3=IFCLOCALPLACEMENT($,#2);
+#4=IFCAXIS2PLACEMENT2D(#1,$);
+#5=IFCRECTANGLEPROFILEDEF(.AREA.,$,#4,1.0,0.2);
+#6=IFCDIRECTION((0.0,0.0,1.0));
+#7=IFCEXTRUDEDAREASOLID(#5,#2,#6,2.0);
+#8=IFCSHAPEREPRESENTATION($,'Body','SweptSolid',(#7));
+#9=IFCPRODUCTDEFINITIONSHAPE($,$,(#8));
+#20=IFCOPENINGELEMENT('opening',$,$,$,$,#3,#9,$,$);
+#21=IFCSHAPEREPRESENTATION($,'Body','SweptSolid',(#99));
+#22=IFCPRODUCTDEFINITIONSHAPE($,$,(#21));
+#30=IFCDOOR('door',$,$,$,$,#3,#22,$,$,$,$,$,$);
+"#;
+        let mut decoder = EntityDecoder::new(content);
+        let router = GeometryRouter::new();
+        let door = decoder.decode_by_id(30).unwrap();
+        assert!(router.process_element(&door, &mut decoder).is_err());
+
+        // Without the fill relation the error stands
+        crate::warnings::take_warnings();
+        let empty = VoidIndex::new();
+        assert!(router
+            .process_element_or_filling(&door, &mut decoder, &empty)
+            .is_err());
+        assert!(crate::warnings::take_warnings().is_empty());
+
+        let mut void_index = VoidIndex::new();
+        void_index.add_filling(20, 30);
+        let mesh = router
+            .process_element_or_filling(&door, &mut decoder, &void_index)
+            .unwrap();
+        let (min, max) = mesh.bounds();
+        assert!((max.x - min.x - 1.0).abs() < 1e-5);
+        assert!((max.z - min.z - 2.0).abs() < 1e-5);
+        assert_eq!(crate::warnings::take_warnings().len(), 1);
+
+        let geometry = router
+            .process_element_or_filling_instanced(&door, &mut decoder, &void_index)
+            .unwrap();
+        assert!(!geometry.mesh.is_empty());
+        assert_eq!(crate::warnings::take_warnings().len(), 1);
+    }
+
+    #[test]
+    fn test_mapped_item_instancing() {
+        // One 1x1x1 box map placed twice: MappingTarget at x=10 and x=20
+        let content = r#"
+#1=IFCCARTESIANPOINT((0.0,0.0,0.0));
+#2=IFCAXIS2PLACEMENT3D(#1,$,$);
+#3=IFCLOCALPLACEMENT($,#2);
+#4=IFCAXIS2PLACEMENT2D(#1,$);
+#5=IFCRECTANGLEPROFILEDEF(.AREA.,$,#4,1.0,1.0);
+#6=IFCDIRECTION((0.0,0.0,1.0));
+#7=IFCEXTRUDEDAREASOLID(#5,#2,#6,1.0);
+#8=IFCSHAPEREPRESENTATION($,'Body','SweptSolid',(#7));
+#9=IFCREPRESENTATIONMAP(#2,#8);
+#10=IFCCARTESIANPOINT((10.0,0.0,0.0));
+#11=IFCCARTESIANTRANSFORMATIONOPERATOR3D($,$,#10,$,$);
+#12=IFCCARTESIANPOINT((20.0,0.0,0.0));
+#13=IFCCARTESIANTRANSFORMATIONOPERATOR3D($,$,#12,$,$);
+#14=IFCMAPPEDITEM(#9,#11);
+#15=IFCMAPPEDITEM(#9,#13);
+#16=IFCSHAPEREPRESENTATION($,'Body','MappedRepresentation',(#14,#15));
+#17=IFCPRODUCTDEFINITIONSHAPE($,$,(#16));
+#18=IFCFURNISHINGELEMENT('guid',$,$,$,$,#3,#17,$);
+"#;
+
+        let mut decoder = EntityDecoder::new(content);
+        let router = GeometryRouter::new();
+        let element = decoder.decode_by_id(18).unwrap();
+
+        let geometry = router
+            .process_element_instanced(&element, &mut decoder)
+            .unwrap();
+        assert!(geometry.mesh.is_empty());
+        assert_eq!(geometry.instances.len(), 2);
+        assert!(geometry.instances.iter().all(|i| i.source_id == 9));
+        assert!(Arc::ptr_eq(
+            &geometry.instances[0].mesh,
+            &geometry.instances[1].mesh
+        ));
+        assert!((geometry.instances[1].transform[(0, 3)] - 20.0).abs() < 1e-9);
+
+        // Flattened instances match the merged mesh
+        let merged = router.process_element(&element, &mut decoder).unwrap();
+        let flat = geometry.into_mesh();
+        assert_eq!(flat.triangle_count(), merged.triangle_count());
+        let (min, max) = flat.bounds();
+        assert!((min.x - 9.5).abs() < 1e-5);
+        assert!((max.x - 20.5).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_indexed_colour_map() {
+        // Two triangles of one square: the first red, the second half-transparent blue
+        let content = r#"
+#1=IFCCARTESIANPOINTLIST3D(((0.,0.,0.),(1.,0.,0.),(1.,1.,0.),(0.,1.,0.)));
+#2=IFCTRIANGULATEDFACESET(#1,$,.F.,((1,2,3),(1,3,4)),$);
+#3=IFCCOLOURRGBLIST(((1.,0.,0.),(0.,0.,1.)));
+#4=IFCINDEXEDCOLOURMAP(#2,0.5,#3,(1,2));
+"#;
+        let mut decoder = EntityDecoder::new(content);
+        let router = GeometryRouter::with_units(content, &mut decoder);
+
+        let face_set = decoder.decode_by_id(2).unwrap();
+        let mesh = router
+            .process_representation_item(&face_set, &mut decoder)
+            .unwrap();
+
+        // The diagonal vertices are split between the two colors
+        assert_eq!(mesh.vertex_count(), 6);
+        let color_of = |t: usize| mesh.vertex_color(mesh.indices[t * 3] as usize);
+        assert_eq!(color_of(0), Some([1.0, 0.0, 0.0, 0.5]));
+        assert_eq!(color_of(1), Some([0.0, 0.0, 1.0, 0.5]));
+    }
+
+    #[test]
+    fn test_faceted_brep_styled_face() {
+        // Tetrahedron with one green face; the others keep the element color
+        let content = r#"
+#1=IFCCARTESIANPOINT((0.,0.,0.));
+#2=IFCCARTESIANPOINT((1.,0.,0.));
+#3=IFCCARTESIANPOINT((0.,1.,0.));
+#4=IFCCARTESIANPOINT((0.,0.,1.));
+#10=IFCPOLYLOOP((#1,#3,#2));
+#11=IFCPOLYLOOP((#1,#2,#4));
+#12=IFCPOLYLOOP((#2,#3,#4));
+#13=IFCPOLYLOOP((#3,#1,#4));
+#20=IFCFACEOUTERBOUND(#10,.T.);
+#21=IFCFACEOUTERBOUND(#11,.T.);
+#22=IFCFACEOUTERBOUND(#12,.T.);
+#23=IFCFACEOUTERBOUND(#13,.T.);
+#30=IFCFACE((#20));
+#31=IFCFACE((#21));
+#32=IFCFACE((#22));
+#33=IFCFACE((#23));
+#40=IFCCLOSEDSHELL((#30,#31,#32,#33));
+#41=IFCFACETEDBREP(#40);
+#50=IFCCOLOURRGB($,0.,1.,0.);
+#51=IFCSURFACESTYLERENDERING(#50,0.,$,$,$,$,$,$,.NOTDEFINED.);
+#52=IFCSURFACESTYLE($,.BOTH.,(#51));
+#53=IFCSTYLEDITEM(#32,(#52),$);
+"#;
+        let mut decoder = EntityDecoder::new(content);
+        let router = GeometryRouter::with_units(content, &mut decoder);
+        router.preprocess_faceted_breps(&[41], &mut decoder);
+
+        let brep = decoder.decode_by_id(41).unwrap();
+        let mesh = router
+            .process_representation_item(&brep, &mut decoder)
+            .unwrap();
+        assert_eq!(mesh.triangle_count(), 4);
+
+        let colored: Vec<_> = (0..mesh.vertex_count())
+            .filter_map(|v| mesh.vertex_color(v))
+            .collect();
+        assert_eq!(colored, vec![[0.0, 1.0, 0.0, 1.0]; 3]);
+    }
+
+    #[test]
+    fn test_crease_angle_generates_normals() {
+        // Coplanar triangles with separate vertices weld into one smooth quad
+        let content = r#"
+#1=IFCCARTESIANPOINTLIST3D(((0.,0.,0.),(1.,0.,0.),(1.,1.,0.),(0.,0.,0.),(1.,1.,0.),(0.,1.,0.)));
+#2=IFCTRIANGULATEDFACESET(#1,$,.F.,((1,2,3),(4,5,6)),$);
+"#;
+        let mut decoder = EntityDecoder::new(content);
+        let face_set = decoder.decode_by_id(2).unwrap();
+
+        let router = GeometryRouter::new();
+        let mesh = router
+            .process_representation_item(&face_set, &mut decoder)
+            .unwrap();
+        assert!(mesh.normals.is_empty());
+
+        let mut router = GeometryRouter::new();
+        router.set_crease_angle(Some(crate::DEFAULT_CREASE_ANGLE));
+        let mesh = router
+            .process_representation_item(&face_set, &mut decoder)
+            .unwrap();
+        assert_eq!(mesh.vertex_count(), 4);
+        for n in mesh.normals.chunks_exact(3) {
+            assert_eq!(n, [0.0, 0.0, 1.0]);
+        }
+    }
+
+    #[test]
+    fn test_geometry_options() {
+        // A 1 m box space, a 1 cm box proxy and a 1 m round column
+        let content = r#"
+#1=IFCCARTESIANPOINT((0.0,0.0,0.0));
+#2=IFCAXIS2PLACEMENT3D(#1,$,$);
+#3=IFCLOCALPLACEMENT($,#2);
+#4=IFCAXIS2PLACEMENT2D(#1,$);
+#5=IFCRECTANGLEPROFILEDEF(.AREA.,$,#4,1.0,1.0);
+#6=IFCDIRECTION((0.0,0.0,1.0));
+#7=IFCEXTRUDEDAREASOLID(#5,#2,#6,1.0);
+#8=IFCSHAPEREPRESENTATION($,'Body','SweptSolid',(#7));
+#9=IFCPRODUCTDEFINITIONSHAPE($,$,(#8));
+#10=IFCSPACE('space',$,$,$,$,#3,#9,$,$,$,$);
+#11=IFCRECTANGLEPROFILEDEF(.AREA.,$,#4,0.01,0.01);
+#12=IFCEXTRUDEDAREASOLID(#11,#2,#6,0.01);
+#13=IFCSHAPEREPRESENTATION($,'Body','SweptSolid',(#12));
+#14=IFCPRODUCTDEFINITIONSHAPE($,$,(#13));
+#15=IFCBUILDINGELEMENTPROXY('proxy',$,$,$,$,#3,#14,$,$);
+#16=IFCCIRCLEPROFILEDEF(.AREA.,$,#4,1.0);
+#17=IFCEXTRUDEDAREASOLID(#16,#2,#6,3.0);
+#18=IFCSHAPEREPRESENTATION($,'Body','SweptSolid',(#17));
+#19=IFCPRODUCTDEFINITIONSHAPE($,$,(#18));
+#20=IFCCOLUMN('column',$,$,$,$,#3,#19,$,$);
+"#;
+        let mut decoder = EntityDecoder::new(content);
+        let space = decoder.decode_by_id(10).unwrap();
+        let proxy = decoder.decode_by_id(15).unwrap();
+        let column = decoder.decode_by_id(20).unwrap();
+
+        let router = GeometryRouter::new();
+        assert!(!router
+            .process_element(&space, &mut decoder)
+            .unwrap()
+            .is_empty());
+        assert!(!router
+            .process_element(&proxy, &mut decoder)
+            .unwrap()
+            .is_empty());
+        let smooth = router.process_element(&column, &mut decoder).unwrap();
+
+        let mut router = GeometryRouter::new();
+        router.set_options(GeometryOptions {
+            skip_spaces: true,
+            min_feature_size: 0.05,
+            max_segments: 8,
+            ..Default::default()
+        });
+        assert!(router
+            .process_element(&space, &mut decoder)
+            .unwrap()
+            .is_empty());
+        assert!(router
+            .process_element(&proxy, &mut decoder)
+            .unwrap()
+            .is_empty());
+        let coarse = router.process_element(&column, &mut decoder).unwrap();
+        assert!(!coarse.is_empty());
+        assert!(coarse.triangle_count() < smooth.triangle_count());
+    }
+
+    #[test]
+    fn test_processor_timings() {
+        let content = r#"
+#1=IFCCARTESIANPOINT((0.0,0.0,0.0));
+#2=IFCAXIS2PLACEMENT3D(#1,$,$);
+#3=IFCLOCALPLACEMENT($,#2);
+#4=IFCAXIS2PLACEMENT2D(#1,$);
+#5=IFCRECTANGLEPROFILEDEF(.AREA.,$,#4,1.0,1.0);
+#6=IFCDIRECTION((0.0,0.0,1.0));
+#7=IFCEXTRUDEDAREASOLID(#5,#2,#6,1.0);
+#8=IFCSHAPEREPRESENTATION($,'Body','SweptSolid',(#7));
+#9=IFCPRODUCTDEFINITIONSHAPE($,$,(#8));
+#10=IFCBUILDINGELEMENTPROXY('a',$,$,$,$,#3,#9,$,$);
+#11=IFCBUILDINGELEMENTPROXY('b',$,$,$,$,#3,#9,$,$);
+"#;
+        let mut decoder = EntityDecoder::new(content);
+        let a = decoder.decode_by_id(10).unwrap();
+        let b = decoder.decode_by_id(11).unwrap();
+
+        let mut router = GeometryRouter::new();
+        router.process_element(&a, &mut decoder).unwrap();
+        assert!(router.timings().is_none());
+
+        let timings = Arc::new(ProcessorTimings::new());
+        router.set_timings(Some(Arc::clone(&timings)));
+        router.process_element(&a, &mut decoder).unwrap();
+        // Forks record into the same timings
+        router.fork().process_element(&b, &mut decoder).unwrap();
+        let snapshot = timings.snapshot();
+        assert_eq!(snapshot.len(), 1);
+        assert_eq!(snapshot[0].0, IfcType::IfcExtrudedAreaSolid);
+        assert_eq!(snapshot[0].1.calls, 2);
+    }
 }
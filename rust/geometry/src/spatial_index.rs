@@ -0,0 +1,382 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Spatial Index - Entity-level queries over a processed model
+//!
+//! A bounding volume hierarchy over the world-space boxes of entities,
+//! built once after geometry processing. It answers the coarse questions
+//! that picking, sectioning and clash detection all start with: which
+//! entities lie in a box, which one a ray reaches first, which ones a plane
+//! cuts. Exact answers need the triangles; [`SpatialIndex::nearest_entity_by`]
+//! lets the caller refine ray hits against them.
+
+use crate::mesh::Mesh;
+
+/// Entities per leaf
+const LEAF_SIZE: usize = 4;
+
+/// Axis-aligned box of one entity
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EntityBox {
+    pub id: u64,
+    pub min: [f32; 3],
+    pub max: [f32; 3],
+}
+
+impl EntityBox {
+    /// Box of a mesh, `None` when it has no vertices
+    pub fn of_mesh(id: u64, mesh: &Mesh) -> Option<Self> {
+        if mesh.is_empty() {
+            return None;
+        }
+        let (min, max) = mesh.bounds();
+        Some(Self {
+            id,
+            min: [min.x, min.y, min.z],
+            max: [max.x, max.y, max.z],
+        })
+    }
+}
+
+/// Tree node; children of an inner node are stored next to each other
+#[derive(Clone, Copy, Debug)]
+struct Node {
+    min: [f32; 3],
+    max: [f32; 3],
+    /// Leaf: first entry in `boxes`; inner: index of the left child
+    start: u32,
+    /// Entities in a leaf, 0 for inner nodes
+    count: u32,
+}
+
+/// Bounding volume hierarchy over entity boxes
+#[derive(Clone, Debug, Default)]
+pub struct SpatialIndex {
+    nodes: Vec<Node>,
+    /// Entity boxes in leaf order
+    boxes: Vec<EntityBox>,
+}
+
+impl SpatialIndex {
+    /// Build the index; boxes with NaN coordinates are left out
+    pub fn build(boxes: impl IntoIterator<Item = EntityBox>) -> Self {
+        let boxes: Vec<EntityBox> = boxes
+            .into_iter()
+            .filter(|b| b.min.iter().chain(&b.max).all(|c| !c.is_nan()))
+            .collect();
+        let mut index = Self {
+            nodes: Vec::with_capacity(2 * boxes.len() / LEAF_SIZE + 1),
+            boxes,
+        };
+        if !index.boxes.is_empty() {
+            index.nodes.push(Node {
+                min: [0.0; 3],
+                max: [0.0; 3],
+                start: 0,
+                count: 0,
+            });
+            index.subdivide(0, 0, index.boxes.len());
+        }
+        index
+    }
+
+    /// Build the index from `(entity id, mesh)` pairs in world coordinates
+    pub fn from_meshes<'a>(meshes: impl IntoIterator<Item = (u64, &'a Mesh)>) -> Self {
+        Self::build(
+            meshes
+                .into_iter()
+                .filter_map(|(id, mesh)| EntityBox::of_mesh(id, mesh)),
+        )
+    }
+
+    fn subdivide(&mut self, node: usize, start: usize, end: usize) {
+        let mut min = [f32::INFINITY; 3];
+        let mut max = [f32::NEG_INFINITY; 3];
+        let mut centroid_min = [f32::INFINITY; 3];
+        let mut centroid_max = [f32::NEG_INFINITY; 3];
+        for b in &self.boxes[start..end] {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(b.min[axis]);
+                max[axis] = max[axis].max(b.max[axis]);
+                let centroid = (b.min[axis] + b.max[axis]) * 0.5;
+                centroid_min[axis] = centroid_min[axis].min(centroid);
+                centroid_max[axis] = centroid_max[axis].max(centroid);
+            }
+        }
+        self.nodes[node].min = min;
+        self.nodes[node].max = max;
+
+        let extent = [0, 1, 2].map(|i| centroid_max[i] - centroid_min[i]);
+        let axis = (0..3)
+            .max_by(|&a, &b| extent[a].total_cmp(&extent[b]))
+            .unwrap_or(0);
+        if end - start <= LEAF_SIZE || extent[axis] <= 0.0 {
+            self.nodes[node].start = start as u32;
+            self.nodes[node].count = (end - start) as u32;
+            return;
+        }
+
+        let mid = start + (end - start) / 2;
+        self.boxes[start..end].select_nth_unstable_by(mid - start, |a, b| {
+            (a.min[axis] + a.max[axis]).total_cmp(&(b.min[axis] + b.max[axis]))
+        });
+
+        let left = self.nodes.len();
+        let empty = Node {
+            min: [0.0; 3],
+            max: [0.0; 3],
+            start: 0,
+            count: 0,
+        };
+        self.nodes.push(empty);
+        self.nodes.push(empty);
+        self.nodes[node].start = left as u32;
+        self.subdivide(left, start, mid);
+        self.subdivide(left + 1, mid, end);
+    }
+
+    /// Number of indexed entities
+    pub fn len(&self) -> usize {
+        self.boxes.len()
+    }
+
+    /// Check if no entity is indexed
+    pub fn is_empty(&self) -> bool {
+        self.boxes.is_empty()
+    }
+
+    /// Box of an entity
+    pub fn entity_box(&self, id: u64) -> Option<&EntityBox> {
+        self.boxes.iter().find(|b| b.id == id)
+    }
+
+    /// Entities whose boxes overlap the box `min..max` (touching counts)
+    pub fn entities_in_box(&self, min: [f32; 3], max: [f32; 3]) -> Vec<u64> {
+        self.collect(|node_min, node_max| {
+            (0..3).all(|i| node_min[i] <= max[i] && node_max[i] >= min[i])
+        })
+    }
+
+    /// Entities whose boxes the plane `normal · p = offset` passes through
+    pub fn entities_intersecting(&self, normal: [f32; 3], offset: f32) -> Vec<u64> {
+        self.collect(|node_min, node_max| {
+            // Signed distances of the box corners nearest and farthest along the normal
+            let (mut low, mut high) = (0.0, 0.0);
+            for i in 0..3 {
+                let (a, b) = (normal[i] * node_min[i], normal[i] * node_max[i]);
+                low += a.min(b);
+                high += a.max(b);
+            }
+            low <= offset && high >= offset
+        })
+    }
+
+    /// Entity whose box a ray enters first, as `(entity id, distance)`
+    ///
+    /// Distances are in units of `direction`. A ray starting inside a box
+    /// hits it at distance 0.
+    pub fn nearest_entity(&self, origin: [f32; 3], direction: [f32; 3]) -> Option<(u64, f32)> {
+        self.nearest_entity_by(origin, direction, |_, box_distance| Some(box_distance))
+    }
+
+    /// Closest ray hit, refined per entity
+    ///
+    /// `hit` receives each entity whose box the ray reaches, with the
+    /// distance to that box, and returns the exact hit distance (for example
+    /// from the entity's triangles) or `None` for a miss. Boxes farther away
+    /// than the best hit so far are skipped.
+    pub fn nearest_entity_by(
+        &self,
+        origin: [f32; 3],
+        direction: [f32; 3],
+        mut hit: impl FnMut(u64, f32) -> Option<f32>,
+    ) -> Option<(u64, f32)> {
+        let inv_dir = direction.map(|d| 1.0 / d);
+        let mut closest: Option<(u64, f32)> = None;
+        let mut stack: Vec<u32> = Vec::with_capacity(64);
+        if !self.nodes.is_empty() {
+            stack.push(0);
+        }
+
+        while let Some(index) = stack.pop() {
+            let node = self.nodes[index as usize];
+            let limit = closest.map_or(f32::INFINITY, |(_, t)| t);
+            if ray_box(origin, inv_dir, node.min, node.max).is_none_or(|t| t > limit) {
+                continue;
+            }
+
+            if node.count > 0 {
+                for b in &self.boxes[node.start as usize..(node.start + node.count) as usize] {
+                    let Some(entry) = ray_box(origin, inv_dir, b.min, b.max) else {
+                        continue;
+                    };
+                    if closest.is_some_and(|(_, best)| entry > best) {
+                        continue;
+                    }
+                    if let Some(t) = hit(b.id, entry) {
+                        if closest.is_none_or(|(_, best)| t < best) {
+                            closest = Some((b.id, t));
+                        }
+                    }
+                }
+                continue;
+            }
+
+            // Nearer child on top of the stack
+            let (left, right) = (node.start, node.start + 1);
+            let entry = |child: u32| {
+                let n = &self.nodes[child as usize];
+                ray_box(origin, inv_dir, n.min, n.max).unwrap_or(f32::INFINITY)
+            };
+            if entry(left) <= entry(right) {
+                stack.push(right);
+                stack.push(left);
+            } else {
+                stack.push(left);
+                stack.push(right);
+            }
+        }
+
+        closest
+    }
+
+    /// Ids in all leaves whose node boxes pass `visit`, filtered by the same test
+    fn collect(&self, visit: impl Fn([f32; 3], [f32; 3]) -> bool) -> Vec<u64> {
+        let mut found = Vec::new();
+        let mut stack: Vec<u32> = Vec::with_capacity(64);
+        if !self.nodes.is_empty() {
+            stack.push(0);
+        }
+        while let Some(index) = stack.pop() {
+            let node = self.nodes[index as usize];
+            if !visit(node.min, node.max) {
+                continue;
+            }
+            if node.count > 0 {
+                found.extend(
+                    self.boxes[node.start as usize..(node.start + node.count) as usize]
+                        .iter()
+                        .filter(|b| visit(b.min, b.max))
+                        .map(|b| b.id),
+                );
+            } else {
+                stack.push(node.start);
+                stack.push(node.start + 1);
+            }
+        }
+        found
+    }
+
+    /// Pairs of entities whose boxes overlap, each pair once with the smaller id first
+    ///
+    /// The broad phase of clash detection.
+    pub fn overlapping_pairs(&self) -> Vec<(u64, u64)> {
+        let mut pairs = Vec::new();
+        for a in &self.boxes {
+            for id in self.entities_in_box(a.min, a.max) {
+                if a.id < id {
+                    pairs.push((a.id, id));
+                }
+            }
+        }
+        pairs.sort_unstable();
+        pairs.dedup();
+        pairs
+    }
+}
+
+/// Entry distance of a ray into a box (slab test), 0 when starting inside
+fn ray_box(origin: [f32; 3], inv_dir: [f32; 3], min: [f32; 3], max: [f32; 3]) -> Option<f32> {
+    let mut t_min = 0.0f32;
+    let mut t_max = f32::INFINITY;
+    for axis in 0..3 {
+        let t1 = (min[axis] - origin[axis]) * inv_dir[axis];
+        let t2 = (max[axis] - origin[axis]) * inv_dir[axis];
+        // NaN (ray parallel to and on a slab face) keeps the current bounds
+        t_min = t_min.max(t1.min(t2));
+        t_max = t_max.min(t1.max(t2));
+    }
+    (t_min <= t_max).then_some(t_min)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Unit cubes along X, one metre apart: entity i spans x = 2i..2i+1
+    fn row(count: u64) -> SpatialIndex {
+        SpatialIndex::build((0..count).map(|i| EntityBox {
+            id: i,
+            min: [2.0 * i as f32, 0.0, 0.0],
+            max: [2.0 * i as f32 + 1.0, 1.0, 1.0],
+        }))
+    }
+
+    #[test]
+    fn test_entities_in_box() {
+        let index = row(50);
+        assert_eq!(index.len(), 50);
+
+        let mut found = index.entities_in_box([3.5, 0.5, 0.5], [8.5, 0.6, 0.6]);
+        found.sort_unstable();
+        assert_eq!(found, vec![2, 3, 4]);
+        assert!(index
+            .entities_in_box([0.0, 5.0, 0.0], [100.0, 6.0, 1.0])
+            .is_empty());
+    }
+
+    #[test]
+    fn test_nearest_entity() {
+        let index = row(50);
+
+        // From the far end towards the origin
+        let (id, t) = index
+            .nearest_entity([200.0, 0.5, 0.5], [-1.0, 0.0, 0.0])
+            .unwrap();
+        assert_eq!(id, 49);
+        assert!((t - 101.0).abs() < 1e-4);
+
+        // Refinement can reject boxes, e.g. hidden entities
+        let (id, _) = index
+            .nearest_entity_by([200.0, 0.5, 0.5], [-1.0, 0.0, 0.0], |id, t| {
+                (id < 40).then_some(t)
+            })
+            .unwrap();
+        assert_eq!(id, 39);
+
+        assert_eq!(index.nearest_entity([0.5, 5.0, 0.5], [1.0, 0.0, 0.0]), None);
+    }
+
+    #[test]
+    fn test_entities_intersecting_plane() {
+        let index = row(50);
+
+        // x = 4.5 cuts the third cube
+        assert_eq!(index.entities_intersecting([1.0, 0.0, 0.0], 4.5), vec![2]);
+
+        // z = 0.5 cuts all of them
+        assert_eq!(index.entities_intersecting([0.0, 0.0, 1.0], 0.5).len(), 50);
+
+        // A diagonal plane through the origin only touches the first cube
+        assert_eq!(index.entities_intersecting([1.0, -1.0, 0.0], 0.0), vec![0]);
+    }
+
+    #[test]
+    fn test_overlapping_pairs() {
+        let mut boxes: Vec<EntityBox> = (0..3)
+            .map(|i| EntityBox {
+                id: i,
+                min: [2.0 * i as f32, 0.0, 0.0],
+                max: [2.0 * i as f32 + 1.0, 1.0, 1.0],
+            })
+            .collect();
+        boxes.push(EntityBox {
+            id: 10,
+            min: [0.5, 0.5, 0.5],
+            max: [2.5, 2.0, 2.0],
+        });
+        let index = SpatialIndex::build(boxes);
+        assert_eq!(index.overlapping_pairs(), vec![(0, 10), (1, 10)]);
+    }
+}
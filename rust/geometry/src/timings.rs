@@ -0,0 +1,91 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Processor Timings - Time spent in each geometry processor
+//!
+//! A [`crate::GeometryRouter`] given a [`ProcessorTimings`] adds the time of
+//! every processor call to it. Forked routers share the same timings, so
+//! with parallel processing the totals are CPU time summed over threads and
+//! can exceed the wall time of the load.
+
+use ifc_lite_core::IfcType;
+use rustc_hash::FxHashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use web_time::Instant;
+
+/// Calls and total time of one processor
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ProcessorTiming {
+    pub calls: u64,
+    pub time: Duration,
+}
+
+/// Time spent per processor, shared between threads
+#[derive(Debug, Default)]
+pub struct ProcessorTimings {
+    entries: Mutex<FxHashMap<IfcType, ProcessorTiming>>,
+}
+
+impl ProcessorTimings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `f` and add its time to the processor of `ifc_type`
+    pub fn time<T>(&self, ifc_type: &IfcType, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = f();
+        self.record(ifc_type, start.elapsed());
+        result
+    }
+
+    /// Add one call of `elapsed` to the processor of `ifc_type`
+    pub fn record(&self, ifc_type: &IfcType, elapsed: Duration) {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = entries.entry(*ifc_type).or_default();
+        entry.calls += 1;
+        entry.time += elapsed;
+    }
+
+    /// Timings of all processors called so far, slowest first
+    pub fn snapshot(&self) -> Vec<(IfcType, ProcessorTiming)> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let mut timings: Vec<_> = entries.iter().map(|(t, timing)| (*t, *timing)).collect();
+        timings.sort_by(|a, b| b.1.time.cmp(&a.1.time));
+        timings
+    }
+
+    /// Forget all recorded calls
+    pub fn clear(&self) {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_snapshot() {
+        let timings = ProcessorTimings::new();
+        timings.record(&IfcType::IfcExtrudedAreaSolid, Duration::from_millis(2));
+        timings.record(&IfcType::IfcExtrudedAreaSolid, Duration::from_millis(3));
+        timings.record(&IfcType::IfcFacetedBrep, Duration::from_millis(1));
+        let value = timings.time(&IfcType::IfcFacetedBrep, || 42);
+        assert_eq!(value, 42);
+
+        let snapshot = timings.snapshot();
+        assert_eq!(snapshot[0].0, IfcType::IfcExtrudedAreaSolid);
+        assert_eq!(snapshot[0].1.calls, 2);
+        assert_eq!(snapshot[0].1.time, Duration::from_millis(5));
+        assert_eq!(snapshot[1].1.calls, 2);
+
+        timings.clear();
+        assert!(timings.snapshot().is_empty());
+    }
+}
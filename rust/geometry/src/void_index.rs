@@ -10,6 +10,10 @@
 //! In IFC, voids are related to their host elements via `IfcRelVoidsElement`:
 //! - RelatingBuildingElement: The host (wall, slab, beam, etc.)
 //! - RelatedOpeningElement: The opening (IfcOpeningElement)
+//!
+//! Openings are in turn filled by doors and windows via `IfcRelFillsElement`:
+//! - RelatingOpeningElement: The opening
+//! - RelatedBuildingElement: The filling (IfcDoor, IfcWindow, ...)
 
 use ifc_lite_core::{EntityDecoder, EntityScanner};
 use rustc_hash::FxHashMap;
@@ -26,6 +30,10 @@ pub struct VoidIndex {
     void_to_host: FxHashMap<u32, u32>,
     /// Total number of void relationships
     relationship_count: usize,
+    /// Map from opening entity ID to the elements filling it
+    opening_to_fillings: FxHashMap<u32, Vec<u32>>,
+    /// Map from filling entity ID to the opening it fills (reverse lookup)
+    filling_to_opening: FxHashMap<u32, u32>,
 }
 
 impl VoidIndex {
@@ -35,13 +43,15 @@ impl VoidIndex {
             host_to_voids: FxHashMap::default(),
             void_to_host: FxHashMap::default(),
             relationship_count: 0,
+            opening_to_fillings: FxHashMap::default(),
+            filling_to_opening: FxHashMap::default(),
         }
     }
 
     /// Build void index from IFC content
     ///
-    /// Scans the content for `IfcRelVoidsElement` entities and builds
-    /// the host-to-void mapping.
+    /// Scans the content for `IfcRelVoidsElement` and `IfcRelFillsElement`
+    /// entities and builds the host-to-void and opening-to-filling mappings.
     ///
     /// # Arguments
     /// * `content` - The raw IFC file content
@@ -53,20 +63,31 @@ impl VoidIndex {
         let mut index = Self::new();
         let mut scanner = EntityScanner::new(content);
 
-        while let Some((_id, type_name, start, end)) = scanner.next_entity() {
+        while let Some((id, type_name, _start, _end)) = scanner.next_entity() {
             // Look for IfcRelVoidsElement relationships
             if type_name == "IFCRELVOIDSELEMENT" {
-                if let Ok(entity) = decoder.decode_at(start, end) {
+                if let Ok(attrs) = decoder.decode_attrs_by_id(id, &[4, 5]) {
                     // IfcRelVoidsElement structure:
                     // #id = IFCRELVOIDSELEMENT(GlobalId, OwnerHistory, Name, Description,
                     //                          RelatingBuildingElement, RelatedOpeningElement);
                     // Indices: 0=GlobalId, 1=OwnerHistory, 2=Name, 3=Description,
                     //          4=RelatingBuildingElement, 5=RelatedOpeningElement
 
-                    if let (Some(host_id), Some(void_id)) = (entity.get_ref(4), entity.get_ref(5)) {
+                    if let (Some(host_id), Some(void_id)) =
+                        (attrs[0].as_entity_ref(), attrs[1].as_entity_ref())
+                    {
                         index.add_relationship(host_id, void_id);
                     }
                 }
+            } else if type_name == "IFCRELFILLSELEMENT" {
+                if let Ok(attrs) = decoder.decode_attrs_by_id(id, &[4, 5]) {
+                    // IfcRelFillsElement: 4=RelatingOpeningElement, 5=RelatedBuildingElement
+                    if let (Some(opening_id), Some(filling_id)) =
+                        (attrs[0].as_entity_ref(), attrs[1].as_entity_ref())
+                    {
+                        index.add_filling(opening_id, filling_id);
+                    }
+                }
             }
         }
 
@@ -80,6 +101,28 @@ impl VoidIndex {
         self.relationship_count += 1;
     }
 
+    /// Add a filling relationship (door/window filling an opening)
+    pub fn add_filling(&mut self, opening_id: u32, filling_id: u32) {
+        self.opening_to_fillings
+            .entry(opening_id)
+            .or_default()
+            .push(filling_id);
+        self.filling_to_opening.insert(filling_id, opening_id);
+    }
+
+    /// Get the opening filled by an element, if any
+    pub fn get_filled_opening(&self, filling_id: u32) -> Option<u32> {
+        self.filling_to_opening.get(&filling_id).copied()
+    }
+
+    /// Get the elements filling an opening
+    pub fn get_fillings(&self, opening_id: u32) -> &[u32] {
+        self.opening_to_fillings
+            .get(&opening_id)
+            .map(|v| v.as_slice())
+            .unwrap_or(&[])
+    }
+
     /// Get void IDs for a host element
     ///
     /// # Arguments
@@ -271,6 +314,20 @@ mod tests {
         assert!(hosts.contains(&102));
     }
 
+    #[test]
+    fn test_void_index_fillings() {
+        let mut index = VoidIndex::new();
+        index.add_relationship(100, 200);
+        index.add_filling(200, 300);
+
+        assert_eq!(index.get_filled_opening(300), Some(200));
+        assert_eq!(index.get_fillings(200), &[300]);
+        assert_eq!(index.get_filled_opening(100), None);
+        assert!(index.get_fillings(999).is_empty());
+        // Fillings are not counted as void relationships
+        assert_eq!(index.total_relationships(), 1);
+    }
+
     #[test]
     fn test_void_statistics() {
         let mut index = VoidIndex::new();
@@ -0,0 +1,52 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Geometry Warnings - Problems worked around while building a mesh
+//!
+//! Some failures don't lose the element: a boolean that cannot be evaluated
+//! keeps its first operand, a door whose body fails still gets its opening
+//! placeholder. The mesh is returned as usual and the problem is recorded
+//! here, for the loader to report against the element it is processing.
+//!
+//! Processors are shared between threads and get no per-call context, so
+//! warnings are kept per thread. Call [`take_warnings`] on the thread that
+//! processed an element, right after processing it.
+
+use std::cell::RefCell;
+
+thread_local! {
+    static WARNINGS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Record a problem with the element being processed on this thread
+pub fn warn(message: impl Into<String>) {
+    let message = message.into();
+    WARNINGS.with(|warnings| warnings.borrow_mut().push(message));
+}
+
+/// Warnings recorded on this thread since the last call
+pub fn take_warnings() -> Vec<String> {
+    WARNINGS.with(|warnings| std::mem::take(&mut *warnings.borrow_mut()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_warnings_are_taken_once() {
+        take_warnings();
+        warn("first");
+        warn(String::from("second"));
+        assert_eq!(take_warnings(), vec!["first", "second"]);
+        assert!(take_warnings().is_empty());
+
+        // Other threads keep their own
+        warn("here");
+        std::thread::spawn(|| assert!(take_warnings().is_empty()))
+            .join()
+            .unwrap();
+        assert_eq!(take_warnings(), vec!["here"]);
+    }
+}
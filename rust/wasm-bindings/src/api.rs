@@ -12,6 +12,7 @@ use crate::zero_copy::{
     ZeroCopyMesh,
 };
 use ifc_lite_core::{EntityScanner, GeoReference, ParseEvent, RtcOffset, StreamConfig};
+use ifc_lite_geometry::GeometryOptions;
 use js_sys::{Function, Promise};
 use wasm_bindgen::prelude::*;
 use wasm_bindgen_futures::spawn_local;
@@ -203,6 +204,8 @@ impl MeshCollectionWithRtc {
 #[wasm_bindgen]
 pub struct IfcAPI {
     initialized: bool,
+    /// Tessellation and element filters for every parse
+    geometry_options: GeometryOptions,
 }
 
 #[wasm_bindgen]
@@ -213,7 +216,10 @@ impl IfcAPI {
         #[cfg(feature = "console_error_panic_hook")]
         console_error_panic_hook::set_once();
 
-        Self { initialized: true }
+        Self {
+            initialized: true,
+            geometry_options: GeometryOptions::default(),
+        }
     }
 
     /// Check if API is initialized
@@ -222,6 +228,38 @@ impl IfcAPI {
         self.initialized
     }
 
+    /// Set tessellation tolerances and element filters for later parses
+    /// Missing fields keep their defaults
+    ///
+    /// Example:
+    /// ```javascript
+    /// const api = new IfcAPI();
+    /// api.setGeometryOptions({
+    ///   deflection: 0.005,     // Max curve deviation in metres
+    ///   maxSegments: 64,       // Segments of a full circle at most
+    ///   skipOpenings: true,
+    ///   skipSpaces: true,
+    ///   minFeatureSize: 0.02,  // Drop elements smaller than 2 cm
+    /// });
+    /// ```
+    #[wasm_bindgen(js_name = setGeometryOptions)]
+    pub fn set_geometry_options(&mut self, options: JsValue) {
+        let get = |key: &str| js_sys::Reflect::get(&options, &key.into()).ok();
+        let number = |key: &str| get(key).and_then(|v| v.as_f64());
+        let flag = |key: &str| get(key).and_then(|v| v.as_bool());
+
+        let defaults = GeometryOptions::default();
+        self.geometry_options = GeometryOptions {
+            deflection: number("deflection").unwrap_or(defaults.deflection),
+            max_segments: number("maxSegments")
+                .map(|v| v as usize)
+                .unwrap_or(defaults.max_segments),
+            skip_openings: flag("skipOpenings").unwrap_or(defaults.skip_openings),
+            skip_spaces: flag("skipSpaces").unwrap_or(defaults.skip_spaces),
+            min_feature_size: number("minFeatureSize").unwrap_or(defaults.min_feature_size),
+        };
+    }
+
     /// Parse IFC file with streaming events
     /// Calls the callback function for each parse event
     ///
@@ -333,7 +371,8 @@ impl IfcAPI {
         let mut decoder = EntityDecoder::with_index(&content, entity_index);
 
         // Create geometry router (reuses processor instances)
-        let router = GeometryRouter::with_units(&content, &mut decoder);
+        let mut router = GeometryRouter::with_units(&content, &mut decoder);
+        router.set_options(self.geometry_options);
 
         // Collect all meshes first (better for batch merge)
         let mut meshes: Vec<Mesh> = Vec::with_capacity(2000);
@@ -398,9 +437,11 @@ impl IfcAPI {
 
         // OPTIMIZATION: Collect all FacetedBrep IDs for batch processing
         // Also build void relationship index (host → openings)
+        // and filling index (door/window → opening) for bodiless fillings
         let mut scanner = EntityScanner::new(&content);
         let mut faceted_brep_ids: Vec<u32> = Vec::new();
         let mut void_index: rustc_hash::FxHashMap<u32, Vec<u32>> = rustc_hash::FxHashMap::default();
+        let mut filling_index: rustc_hash::FxHashMap<u32, u32> = rustc_hash::FxHashMap::default();
 
         while let Some((id, type_name, start, end)) = scanner.next_entity() {
             if type_name == "IFCFACETEDBREP" {
@@ -414,11 +455,21 @@ impl IfcAPI {
                         void_index.entry(host_id).or_default().push(opening_id);
                     }
                 }
+            } else if type_name == "IFCRELFILLSELEMENT" {
+                // IfcRelFillsElement: Attr 4 = RelatingOpeningElement, Attr 5 = RelatedBuildingElement
+                if let Ok(entity) = decoder.decode_at(start, end) {
+                    if let (Some(opening_id), Some(filling_id)) =
+                        (entity.get_ref(4), entity.get_ref(5))
+                    {
+                        filling_index.insert(filling_id, opening_id);
+                    }
+                }
             }
         }
 
         // Create geometry router (reuses processor instances)
-        let router = GeometryRouter::with_units(&content, &mut decoder);
+        let mut router = GeometryRouter::with_units(&content, &mut decoder);
+        router.set_options(self.geometry_options);
 
         // Batch preprocess FacetedBrep entities for maximum parallelism
         // This triangulates ALL faces from ALL BREPs in one parallel batch
@@ -433,43 +484,60 @@ impl IfcAPI {
         let estimated_elements = content.len() / 500;
         let mut mesh_collection = MeshCollection::with_capacity(estimated_elements);
 
-        // Process all building elements
-        while let Some((id, type_name, start, end)) = scanner.next_entity() {
-            // Check if this is a building element type
-            if !ifc_lite_core::has_geometry_by_name(type_name) {
-                continue;
+        // Collect building elements, then process them on the rayon pool
+        // (sequential unless the `threads` feature set up a thread pool)
+        let mut element_ids = Vec::new();
+        while let Some((id, type_name, _, _)) = scanner.next_entity() {
+            if ifc_lite_core::has_geometry_by_name(type_name) {
+                element_ids.push(id);
             }
+        }
 
-            // Decode and process the entity
-            if let Ok(entity) = decoder.decode_at(start, end) {
+        let results = ifc_lite_geometry::process_parallel(
+            &element_ids,
+            &mut decoder,
+            &router,
+            |id, decoder, router| {
+                let entity = decoder.decode_by_id(id).ok()?;
                 // Check if entity actually has representation (attribute index 6 for IfcProduct)
                 let has_representation = entity.get(6).map(|a| !a.is_null()).unwrap_or(false);
-                if !has_representation {
-                    continue;
+                let filled_opening = filling_index.get(&id).copied();
+                if !has_representation && filled_opening.is_none() {
+                    return None;
                 }
 
-                if let Ok(mut mesh) =
-                    router.process_element_with_voids(&entity, &mut decoder, &void_index)
-                {
-                    if !mesh.is_empty() {
-                        // Calculate normals if not present
-                        if mesh.normals.is_empty() {
-                            calculate_normals(&mut mesh);
-                        }
-
-                        // Try to get color from style index, otherwise use default
-                        let color = style_index
-                            .get(&id)
-                            .copied()
-                            .unwrap_or_else(|| get_default_color_for_type(&entity.ifc_type));
+                let mut result = if has_representation {
+                    router.process_element_with_voids(&entity, decoder, &void_index)
+                } else {
+                    Ok(ifc_lite_geometry::Mesh::new())
+                };
 
-                        // Create mesh data with express ID, IFC type, and color
-                        let ifc_type_name = entity.ifc_type.name().to_string();
-                        let mesh_data = MeshDataJs::new(id, ifc_type_name, mesh, color);
-                        mesh_collection.add(mesh_data);
+                // Doors/windows without a body: show the filled opening as placeholder
+                if let Some(opening_id) = filled_opening {
+                    if result.as_ref().map(|m| m.is_empty()).unwrap_or(true) {
+                        result = router.process_filling_placeholder(opening_id, decoder);
                     }
                 }
-            }
+
+                let mut mesh = result.ok().filter(|m| !m.is_empty())?;
+                // Calculate normals if not present
+                if mesh.normals.is_empty() {
+                    calculate_normals(&mut mesh);
+                }
+                Some((id, entity.ifc_type, mesh))
+            },
+        );
+
+        for (id, ifc_type, mesh) in results.into_iter().flatten() {
+            // Try to get color from style index, otherwise use default
+            let color = style_index
+                .get(&id)
+                .copied()
+                .unwrap_or_else(|| get_default_color_for_type(&ifc_type));
+
+            // Create mesh data with express ID, IFC type, and color
+            let mesh_data = MeshDataJs::new(id, ifc_type.name().to_string(), mesh, color);
+            mesh_collection.add(mesh_data);
         }
 
         mesh_collection
@@ -521,7 +589,8 @@ impl IfcAPI {
         }
 
         // Create geometry router (reuses processor instances)
-        let router = GeometryRouter::with_units(&content, &mut decoder);
+        let mut router = GeometryRouter::with_units(&content, &mut decoder);
+        router.set_options(self.geometry_options);
 
         // Batch preprocess FacetedBrep entities for maximum parallelism
         if !faceted_brep_ids.is_empty() {
@@ -646,6 +715,7 @@ impl IfcAPI {
         use rustc_hash::{FxHashMap, FxHasher};
         use std::hash::{Hash, Hasher};
 
+        let geometry_options = self.geometry_options;
         let promise = Promise::new(&mut |resolve, _reject| {
             let content = content.clone();
             let options = options.clone();
@@ -685,7 +755,8 @@ impl IfcAPI {
                 }
 
                 // Create geometry router
-                let router = GeometryRouter::with_units(&content, &mut decoder);
+                let mut router = GeometryRouter::with_units(&content, &mut decoder);
+                router.set_options(geometry_options);
 
                 // Batch preprocess FacetedBreps
                 if !faceted_brep_ids.is_empty() {
@@ -1107,6 +1178,7 @@ impl IfcAPI {
         use ifc_lite_core::{EntityDecoder, EntityScanner};
         use ifc_lite_geometry::{calculate_normals, GeometryRouter};
 
+        let geometry_options = self.geometry_options;
         let promise = Promise::new(&mut |resolve, _reject| {
             let content = content.clone();
             let options = options.clone();
@@ -1132,7 +1204,8 @@ impl IfcAPI {
                 let mut decoder = EntityDecoder::new(&content);
 
                 // Create geometry router
-                let router = GeometryRouter::with_units(&content, &mut decoder);
+                let mut router = GeometryRouter::with_units(&content, &mut decoder);
+                router.set_options(geometry_options);
 
                 // Process counters
                 let mut processed = 0;
@@ -1466,7 +1539,8 @@ impl IfcAPI {
             }
         }
 
-        let router = GeometryRouter::with_units(&content, &mut decoder);
+        let mut router = GeometryRouter::with_units(&content, &mut decoder);
+        router.set_options(self.geometry_options);
 
         // Batch preprocess FacetedBrep entities for maximum parallelism
         if !faceted_brep_ids.is_empty() {
@@ -1599,7 +1673,8 @@ impl IfcAPI {
         }
 
         // Create geometry router
-        let router = GeometryRouter::with_units(&content, &mut decoder);
+        let mut router = GeometryRouter::with_units(&content, &mut decoder);
+        router.set_options(self.geometry_options);
 
         // Batch preprocess FacetedBreps
         if !faceted_brep_ids.is_empty() {
@@ -1696,6 +1771,7 @@ impl IfcAPI {
         use ifc_lite_core::{build_entity_index, EntityDecoder, EntityScanner};
         use ifc_lite_geometry::{calculate_normals, GeometryRouter};
 
+        let geometry_options = self.geometry_options;
         let promise = Promise::new(&mut |resolve, _reject| {
             let content = content.clone();
             let options = options.clone();
@@ -1746,7 +1822,8 @@ impl IfcAPI {
                 }
 
                 // Create geometry router
-                let router = GeometryRouter::with_units(&content, &mut decoder);
+                let mut router = GeometryRouter::with_units(&content, &mut decoder);
+                router.set_options(geometry_options);
 
                 // Batch preprocess FacetedBreps
                 if !faceted_brep_ids.is_empty() {
@@ -2014,7 +2091,8 @@ impl IfcAPI {
         }
 
         // Create geometry router
-        let router = GeometryRouter::with_units(&content, &mut decoder);
+        let mut router = GeometryRouter::with_units(&content, &mut decoder);
+        router.set_options(self.geometry_options);
 
         // Batch preprocess FacetedBreps
         if !faceted_brep_ids.is_empty() {
@@ -2363,9 +2441,7 @@ fn build_element_style_index(
             Err(_) => continue,
         };
 
-        // Building elements have Representation attribute at index 6
-        // IfcProduct: GlobalId, OwnerHistory, Name, Description, ObjectType, ObjectPlacement, Representation
-        let repr_id = match element.get_ref(6) {
+        let repr_id = match element.attribute_ref(ifc_lite_core::Attribute::Representation) {
             Some(id) => id,
             None => continue,
         };
@@ -2564,47 +2640,9 @@ fn extract_color_rgb(
     Some([red as f32, green as f32, blue as f32, 1.0])
 }
 
-/// Get default color for IFC type (matches default-materials.ts)
+/// Default color of an IFC type, the same as in the other front-ends
 fn get_default_color_for_type(ifc_type: &ifc_lite_core::IfcType) -> [f32; 4] {
-    use ifc_lite_core::IfcType;
-
-    match ifc_type {
-        // Walls - light gray
-        IfcType::IfcWall | IfcType::IfcWallStandardCase => [0.85, 0.85, 0.85, 1.0],
-
-        // Slabs - darker gray
-        IfcType::IfcSlab => [0.7, 0.7, 0.7, 1.0],
-
-        // Roofs - brown-ish
-        IfcType::IfcRoof => [0.6, 0.5, 0.4, 1.0],
-
-        // Columns/Beams - steel gray
-        IfcType::IfcColumn | IfcType::IfcBeam | IfcType::IfcMember => [0.6, 0.65, 0.7, 1.0],
-
-        // Windows - light blue transparent
-        IfcType::IfcWindow => [0.6, 0.8, 1.0, 0.4],
-
-        // Doors - wood brown
-        IfcType::IfcDoor => [0.6, 0.45, 0.3, 1.0],
-
-        // Stairs
-        IfcType::IfcStair => [0.75, 0.75, 0.75, 1.0],
-
-        // Railings
-        IfcType::IfcRailing => [0.4, 0.4, 0.45, 1.0],
-
-        // Plates/Coverings
-        IfcType::IfcPlate | IfcType::IfcCovering => [0.8, 0.8, 0.8, 1.0],
-
-        // Curtain walls - glass blue
-        IfcType::IfcCurtainWall => [0.5, 0.7, 0.9, 0.5],
-
-        // Furniture - wood
-        IfcType::IfcFurnishingElement => [0.7, 0.55, 0.4, 1.0],
-
-        // Default gray
-        _ => [0.8, 0.8, 0.8, 1.0],
-    }
+    ifc_lite_engine::element_color(ifc_type.as_str(), None)
 }
 
 /// Convert entity counts map to JavaScript object
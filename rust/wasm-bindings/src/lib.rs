@@ -16,6 +16,7 @@
 //! - **Zero-Copy Buffers**: Direct GPU buffer access without data copying
 //! - **Streaming Parse**: Event-based parsing with progress callbacks
 //! - **Small Bundle**: ~60 KB WASM binary, ~20 KB gzipped
+//! - **Threads** (`threads` feature): Parallel geometry on a web worker pool
 //!
 //! ## JavaScript Usage
 //!
@@ -46,6 +47,16 @@
 //! });
 //! ```
 //!
+//! ## Parallel Geometry
+//!
+//! Built with the `threads` feature (and `-C target-feature=+atomics,+bulk-memory`),
+//! the module exports `initThreadPool`. Call it once before parsing:
+//!
+//! ```javascript
+//! await init();
+//! await initThreadPool(navigator.hardwareConcurrency);
+//! ```
+//!
 //! ## Zero-Copy Memory Access
 //!
 //! For optimal performance, mesh data can be accessed directly from WASM memory:
@@ -74,6 +85,8 @@ pub use gpu_geometry::{
     GpuMeshMetadata,
 };
 pub use utils::set_panic_hook as init_panic_hook;
+#[cfg(feature = "threads")]
+pub use wasm_bindgen_rayon::init_thread_pool;
 pub use zero_copy::{
     get_memory, InstanceData, InstancedGeometry, InstancedMeshCollection, MeshCollection,
     MeshDataJs, ZeroCopyMesh,
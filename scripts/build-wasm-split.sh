@@ -650,7 +650,8 @@ fi
 if [[ "$BUILD_BEVY" == "true" ]]; then
     # Check if loader already exists with correct hash reference
     EXISTING_LOADER=$(ls "$DIST_DIR/bevy-loader-"*.js 2>/dev/null | head -1)
-    if [[ -n "$EXISTING_LOADER" ]] && grep -q "${BEVY_LIBRARY}-${JS_HASH}.js" "$EXISTING_LOADER" 2>/dev/null; then
+    if [[ -n "$EXISTING_LOADER" ]] && grep -q "${BEVY_LIBRARY}-${JS_HASH}.js" "$EXISTING_LOADER" 2>/dev/null \
+        && grep -q "preloadBevyViewer" "$EXISTING_LOADER" 2>/dev/null; then
         echo "[$STEP/$TOTAL_STEPS] bevy-loader.js: unchanged, skipping"
         BEVY_LOADER_HASH=$(echo "$EXISTING_LOADER" | sed 's/.*bevy-loader-\([^.]*\)\.js/\1/')
     else
@@ -767,6 +768,27 @@ function clearIfcGeometry() {
 JSEOF
         # Now append the dynamic part with variable substitution
         cat >> "$DIST_DIR/bevy-loader-temp.js" << EOF
+let bevyPreloadPromise = null;
+
+// Fetch the glue JS and compile the wasm while it downloads, without starting
+// the renderer. Called as soon as a model starts loading, so compilation
+// overlaps with parsing.
+function preloadBevyViewer() {
+    if (!bevyPreloadPromise) {
+        console.log("[Bevy] Preloading 3D viewer...");
+        bevyPreloadPromise = Promise.all([
+            import('./bevy/${BEVY_LIBRARY}-${JS_HASH}.js'),
+            WebAssembly.compileStreaming(fetch('./bevy/${BEVY_LIBRARY}-${WASM_HASH}_bg.wasm')),
+        ]).catch((error) => {
+            bevyPreloadPromise = null;
+            throw error;
+        });
+        // Failures surface again from loadBevyViewer
+        bevyPreloadPromise.catch(() => {});
+    }
+    return bevyPreloadPromise;
+}
+
 async function loadBevyViewer() {
     if (bevyLoaded) {
         console.log("[Bevy] Already loaded");
@@ -782,8 +804,8 @@ async function loadBevyViewer() {
 
     bevyLoadPromise = (async () => {
         try {
-            const bevy = await import('./bevy/${BEVY_LIBRARY}-${JS_HASH}.js');
-            await bevy.default();
+            const [bevy, module] = await preloadBevyViewer();
+            await bevy.default({ module_or_path: module });
             bevy.run_on_canvas("#bevy-canvas");
             bevyLoaded = true;
             bevyLoading = false;
@@ -810,6 +832,7 @@ async function loadBevyViewer() {
 function isBevyLoaded() { return bevyLoaded; }
 function isBevyLoading() { return bevyLoading; }
 
+window.preloadBevyViewer = preloadBevyViewer;
 window.loadBevyViewer = loadBevyViewer;
 window.isBevyLoaded = isBevyLoaded;
 window.isBevyLoading = isBevyLoading;